@@ -0,0 +1,122 @@
+//! Chain admin CLI commands
+//!
+//! Operator tools for testnet resets: roll the chain back to a height and
+//! invalidate or reconsider individual blocks. The node only accepts these
+//! when API authentication is enabled, and refuses to roll back below the
+//! last checkpoint. `rotate-identity` replaces the node's P2P identity key.
+
+use crate::commands::{print_error, print_success};
+use crate::config::{Config, OutputFormat};
+use crate::rpc::{ChainAdminResult, IdentityRotationResult, RpcClient};
+use anyhow::Result;
+use clap::Subcommand;
+
+/// Admin subcommands
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// Disconnect and invalidate every block above a height
+    Rollback {
+        /// Height of the new tip
+        #[arg(value_name = "HEIGHT")]
+        height: u64,
+
+        /// Also remove every transaction from the mempool
+        #[arg(long)]
+        wipe_mempool: bool,
+    },
+
+    /// Mark a block and its descendants invalid
+    InvalidateBlock {
+        #[arg(value_name = "HASH")]
+        hash: String,
+    },
+
+    /// Clear an earlier invalidation of a block
+    ReconsiderBlock {
+        #[arg(value_name = "HASH")]
+        hash: String,
+    },
+
+    /// Generate a new P2P identity; the current one stays in use for the
+    /// configured grace period while peers are told about the change
+    RotateIdentity,
+}
+
+pub async fn execute(command: AdminCommand, config: &Config) -> Result<()> {
+    let client = RpcClient::new(config.rpc_url.clone(), config.timeout)?;
+
+    let (action, result) = match command {
+        AdminCommand::Rollback {
+            height,
+            wipe_mempool,
+        } => (
+            "Rollback",
+            client.rollback_chain(height, wipe_mempool).await,
+        ),
+        AdminCommand::InvalidateBlock { hash } => {
+            ("Invalidate block", client.invalidate_block(&hash).await)
+        }
+        AdminCommand::ReconsiderBlock { hash } => {
+            ("Reconsider block", client.reconsider_block(&hash).await)
+        }
+        AdminCommand::RotateIdentity => return rotate_identity(&client, config).await,
+    };
+
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            print_error(&format!("{} failed: {}", action, e));
+            return Err(e);
+        }
+    };
+
+    match &config.output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
+        _ => print_result(action, &result),
+    }
+    Ok(())
+}
+
+fn print_result(action: &str, result: &ChainAdminResult) {
+    print_success(&format!(
+        "{} complete: height {} -> {}",
+        action, result.old_height, result.new_height
+    ));
+    println!("Old tip: {}", result.old_tip);
+    println!("New tip: {}", result.new_tip);
+    if !result.affected_blocks.is_empty() {
+        println!("Affected blocks ({}):", result.affected_blocks.len());
+        for hash in &result.affected_blocks {
+            println!("  {}", hash);
+        }
+    }
+    if result.mempool_cleared {
+        println!("Mempool cleared");
+    }
+}
+
+async fn rotate_identity(client: &RpcClient, config: &Config) -> Result<()> {
+    let result = match client.rotate_identity().await {
+        Ok(result) => result,
+        Err(e) => {
+            print_error(&format!("Identity rotation failed: {}", e));
+            return Err(e);
+        }
+    };
+
+    match &config.output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
+        _ => print_rotation(&result),
+    }
+    Ok(())
+}
+
+fn print_rotation(result: &IdentityRotationResult) {
+    print_success("Identity rotation started");
+    println!("Current peer id: {}", result.old_peer_id);
+    println!("New peer id:     {}", result.new_peer_id);
+    let switch_at = chrono::DateTime::from_timestamp(result.grace_until as i64, 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| result.grace_until.to_string());
+    println!("The new identity is used from the first restart after {}", switch_at);
+}
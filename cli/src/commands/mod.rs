@@ -1,7 +1,9 @@
+pub mod admin;
 pub mod blockchain;
 pub mod config;
 pub mod mining;
 pub mod swap;
+pub mod sync;
 pub mod transaction;
 pub mod wallet;
 
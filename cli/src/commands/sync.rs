@@ -0,0 +1,107 @@
+use crate::commands::{print_error, print_success};
+use crate::config::{Config, OutputFormat};
+use crate::rpc::{RpcClient, SyncProgress};
+use anyhow::Result;
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+
+/// Show sync progress once, or keep a live progress bar until synced.
+pub async fn status(config: &Config, watch: bool, interval_secs: u64) -> Result<()> {
+    let client = RpcClient::new(config.rpc_url.clone(), config.timeout)?;
+
+    if !watch {
+        let progress = match client.get_sync_progress().await {
+            Ok(progress) => progress,
+            Err(e) => {
+                print_error(&format!("Failed to get sync progress: {}", e));
+                return Err(e);
+            }
+        };
+        match &config.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&progress)?),
+            _ => print_progress(&progress),
+        }
+        return Ok(());
+    }
+
+    let pb = ProgressBar::new(100);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:40.cyan/blue}] {pos:>3}% {msg}")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+
+    loop {
+        match client.get_sync_progress().await {
+            Ok(progress) => {
+                pb.set_position(progress.percent.floor() as u64);
+                pb.set_message(progress_message(&progress));
+                if progress.synced {
+                    pb.finish_and_clear();
+                    print_success(&format!("Synced at height {}", progress.current_height));
+                    return Ok(());
+                }
+            }
+            Err(e) => pb.set_message(format!("waiting for node: {}", e).yellow().to_string()),
+        }
+        tokio::time::sleep(Duration::from_secs(interval_secs.max(1))).await;
+    }
+}
+
+fn print_progress(progress: &SyncProgress) {
+    if progress.synced {
+        print_success(&format!("Synced at height {}", progress.current_height));
+        return;
+    }
+    println!(
+        "{} {:.2}%",
+        progress_bar(progress.percent, 40).cyan(),
+        progress.percent
+    );
+    println!("{}", progress_message(progress));
+}
+
+fn progress_message(progress: &SyncProgress) -> String {
+    format!(
+        "{}/{} blocks, {:.1} blk/s, {}/s, ETA {}",
+        progress.current_height,
+        progress.target_height,
+        progress.blocks_per_sec,
+        format_bytes(progress.bytes_per_sec),
+        progress
+            .eta_secs
+            .map(format_eta)
+            .unwrap_or_else(|| "unknown".to_string())
+    )
+}
+
+fn progress_bar(percent: f64, width: usize) -> String {
+    let filled = ((percent.clamp(0.0, 100.0) / 100.0) * width as f64).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(width - filled))
+}
+
+fn format_eta(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h {:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m {:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+fn format_bytes(bytes: f64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
+    let mut size = bytes;
+    let mut unit_index = 0;
+
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit_index])
+}
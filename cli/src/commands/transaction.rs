@@ -99,7 +99,10 @@ pub async fn send(config: &Config, to: String, amount: f64) -> Result<()> {
 pub async fn get(config: &Config, txid: String) -> Result<()> {
     let client = RpcClient::new(config.rpc_url.clone(), config.timeout)?;
 
-    print_info(&format!("Fetching transaction {}...", &txid[..8]));
+    print_info(&format!(
+        "Fetching transaction {}...",
+        txid.get(..8).unwrap_or(&txid)
+    ));
 
     match client.get_transaction(&txid).await {
         Ok(tx) => match &config.output_format {
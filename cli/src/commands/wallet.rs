@@ -1,11 +1,183 @@
 use crate::commands::{print_error, print_info, print_success, print_warning};
 use crate::config::{Config, OutputFormat};
 use crate::rpc::RpcClient;
+use crate::wallet::backend::{self, WalletBackendError};
 use crate::wallet::WalletManager;
 use anyhow::Result;
+use clap::Subcommand;
 use colored::*;
 use dialoguer::{Confirm, Input, Password, Select};
 use prettytable::{Cell, Row, Table};
+use serde::Serialize;
+use supernova_wallet::display::{format_amount, DisplayUnit};
+
+/// Wallet subcommands
+#[derive(Debug, Subcommand)]
+pub enum WalletCommand {
+    /// Create the HD wallet used by getnewaddress, getbalance and
+    /// sendtoaddress
+    Create {
+        /// Store the seed unencrypted instead of asking for a passphrase
+        #[arg(long)]
+        no_passphrase: bool,
+    },
+
+    /// Replace an unconfirmed transaction with one paying a higher fee
+    BumpFee {
+        #[arg(value_name = "TXID")]
+        txid: String,
+
+        /// New fee rate in attonovas per byte
+        #[arg(long)]
+        rate: u64,
+    },
+}
+
+pub async fn execute(command: WalletCommand, config: &Config) -> Result<()> {
+    match command {
+        WalletCommand::Create { no_passphrase } => create_hd_wallet(config, no_passphrase).await,
+        WalletCommand::BumpFee { txid, rate } => bump_fee(config, &txid, rate).await,
+    }
+}
+
+/// Create the HD wallet in the wallet directory and show its recovery
+/// phrase
+pub async fn create_hd_wallet(config: &Config, no_passphrase: bool) -> Result<()> {
+    let dir = backend::wallet_dir(config).map_err(report)?;
+    let passphrase = if no_passphrase {
+        None
+    } else {
+        Some(
+            Password::new()
+                .with_prompt("Wallet passphrase")
+                .with_confirmation("Repeat passphrase", "Passphrases do not match")
+                .interact()?,
+        )
+    };
+    let created = backend::create(&dir, &config.network, passphrase.as_deref()).map_err(report)?;
+
+    let rows = [
+        ("Directory", created.wallet_dir.display().to_string()),
+        ("Network", created.network.clone()),
+        ("Address", created.address.clone()),
+    ];
+    print_wallet_result(config, &created, "Wallet created", &rows)?;
+    if config.output_format != OutputFormat::Json {
+        println!("\n{}", "IMPORTANT: Save your recovery phrase!".bold().red());
+        println!("{}", "=".repeat(50));
+        println!("\n{}\n", created.mnemonic.yellow().bold());
+        println!("{}", "=".repeat(50));
+        print_warning("Write down this phrase and store it securely.");
+        if !created.encrypted {
+            print_warning("The seed is stored unencrypted in the wallet directory.");
+        }
+    }
+    Ok(())
+}
+
+/// Hand out the next receive address of the HD wallet
+pub async fn get_new_address(config: &Config) -> Result<()> {
+    let mut manager = open_hd_wallet(config)?;
+    let address = backend::new_address(&mut manager).map_err(report)?;
+
+    let rows = [
+        ("Address", address.address.clone()),
+        ("Account", address.account.clone()),
+    ];
+    print_wallet_result(config, &address, "New address", &rows)
+}
+
+/// Balance of the HD wallet, from a rescan of the node's chain
+pub async fn get_balance(config: &Config) -> Result<()> {
+    let client = RpcClient::new(config.rpc_url.clone(), config.timeout)?;
+    let mut manager = open_hd_wallet(config)?;
+    let balance = backend::balance(&mut manager, &client)
+        .await
+        .map_err(report)?;
+
+    let rows = [
+        (
+            "Balance",
+            format_amount(balance.balance_units, DisplayUnit::Nova),
+        ),
+        ("Chain height", balance.height.to_string()),
+    ];
+    print_wallet_result(config, &balance, "Wallet balance", &rows)
+}
+
+/// Pay `amount` NOVA from the HD wallet and submit the payment to the node
+pub async fn send_to_address(
+    config: &Config,
+    address: &str,
+    amount: f64,
+    fee_rate: u64,
+) -> Result<()> {
+    let client = RpcClient::new(config.rpc_url.clone(), config.timeout)?;
+    let mut manager = open_hd_wallet(config)?;
+    let sent = backend::send_to_address(&mut manager, &client, address, amount, fee_rate)
+        .await
+        .map_err(report)?;
+
+    let rows = [
+        ("Txid", sent.txid.clone()),
+        ("Recipient", sent.address.clone()),
+        (
+            "Amount",
+            format_amount(sent.amount_units, DisplayUnit::Nova),
+        ),
+        ("Fee", format_amount(sent.fee_units, DisplayUnit::Nova)),
+        ("Fee rate", format!("{} nova units/byte", sent.fee_rate)),
+    ];
+    print_wallet_result(config, &sent, "Transaction submitted", &rows)
+}
+
+/// Open the HD wallet, asking for its passphrase if the seed is encrypted
+fn open_hd_wallet(config: &Config) -> Result<supernova_wallet::WalletManager> {
+    let dir = backend::wallet_dir(config).map_err(report)?;
+    let mut manager = backend::open(&dir).map_err(report)?;
+    if manager.is_locked() {
+        let passphrase = Password::new()
+            .with_prompt("Wallet passphrase")
+            .interact()?;
+        manager.unlock(&passphrase).map_err(|e| report(e.into()))?;
+    }
+    Ok(manager)
+}
+
+/// Print a wallet result as JSON, or as labelled rows in a table or as text
+fn print_wallet_result<T: Serialize>(
+    config: &Config,
+    result: &T,
+    title: &str,
+    rows: &[(&str, String)],
+) -> Result<()> {
+    match &config.output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(result)?),
+        OutputFormat::Table => {
+            println!("\n{}", title.bold().green());
+            let mut table = Table::new();
+            for (label, value) in rows {
+                table.add_row(Row::new(vec![
+                    Cell::new(label).style_spec("bFg"),
+                    Cell::new(value),
+                ]));
+            }
+            table.printstd();
+        }
+        OutputFormat::Text => {
+            print_success(title);
+            for (label, value) in rows {
+                println!("{}: {}", label, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn report(error: WalletBackendError) -> anyhow::Error {
+    print_error(&error.to_string());
+    error.into()
+}
 
 pub async fn create(config: &Config, name: Option<String>) -> Result<()> {
     let wallet_manager = WalletManager::new(Config::wallet_dir()?)?;
@@ -334,3 +506,25 @@ pub async fn export(config: &Config, wallet_name: String) -> Result<()> {
 
     Ok(())
 }
+
+pub async fn bump_fee(config: &Config, txid: &str, rate: u64) -> Result<()> {
+    let client = RpcClient::new(config.rpc_url.clone(), config.timeout)?;
+
+    let result = match client.bump_fee(txid, rate).await {
+        Ok(result) => result,
+        Err(e) => {
+            print_error(&format!("Fee bump failed: {}", e));
+            return Err(e);
+        }
+    };
+
+    match &config.output_format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&result)?),
+        _ => {
+            print_success(&format!("Transaction {} replaced", result.origtxid));
+            println!("Replacement: {}", result.txid.cyan());
+            println!("Fee rate:    {} attonovas/byte", result.feerate);
+        }
+    }
+    Ok(())
+}
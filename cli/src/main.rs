@@ -65,6 +65,23 @@ struct Cli {
 
     #[arg(long)]
     no_banner: bool,
+
+    /// Wallet directory; defaults to the configured wallet path, else
+    /// ~/.supernova-wallet
+    #[arg(long, value_name = "DIR")]
+    wallet_dir: Option<std::path::PathBuf>,
+
+    /// Signed netparams file of a custom network
+    #[arg(long, value_name = "PATH")]
+    network_file: Option<std::path::PathBuf>,
+
+    /// Key the netparams file must be signed by (`ed25519:<hex>`)
+    #[arg(long, value_name = "KEY", requires = "network_file")]
+    netparams_authority: Option<String>,
+
+    /// Load the netparams file without verifying its signature
+    #[arg(long, requires = "network_file")]
+    insecure_netparams: bool,
 }
 
 #[derive(Subcommand)]
@@ -106,11 +123,34 @@ enum Commands {
 
         #[arg(value_name = "AMOUNT")]
         amount: f64,
+
+        /// Fee rate in nova units per byte
+        #[arg(long, default_value_t = wallet::backend::DEFAULT_FEE_RATE)]
+        fee_rate: u64,
     },
 
     /// Atomic swap operations
     #[command(subcommand)]
     Swap(commands::swap::SwapCommand),
+
+    /// Show initial block download progress and ETA
+    SyncStatus {
+        /// Keep refreshing a progress bar until the node is synced
+        #[arg(short, long)]
+        watch: bool,
+
+        /// Refresh interval in seconds when watching
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
+
+    /// Chain admin operations (rollback, invalidate/reconsider block)
+    #[command(subcommand)]
+    Admin(commands::admin::AdminCommand),
+
+    /// Wallet operations
+    #[command(subcommand)]
+    Wallet(commands::wallet::WalletCommand),
 }
 
 #[derive(Subcommand)]
@@ -257,9 +297,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         };
     }
+    if let Some(dir) = cli.wallet_dir {
+        config.wallet_path = Some(dir);
+    }
     if cli.debug {
         config.debug = true;
     }
+    if let Some(path) = &cli.network_file {
+        use supernova_core::netparams::{self, NetParamsTrust};
+
+        let trust = NetParamsTrust::from_options(
+            cli.netparams_authority.as_deref(),
+            cli.insecure_netparams,
+        )?;
+        let params = netparams::activate(netparams::load(path, &trust)?)?;
+        config.network = params.name.clone();
+
+        // Refuse to talk to a node of another network
+        let client = rpc::RpcClient::new(config.rpc_url.clone(), config.timeout)?;
+        match client.get_block_hash(0).await {
+            Ok(hash) if !hash.eq_ignore_ascii_case(&params.genesis.hash) => {
+                return Err(format!(
+                    "Node at {} has genesis {}, but network '{}' has genesis {}",
+                    config.rpc_url, hash, params.name, params.genesis.hash
+                )
+                .into());
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Could not check the node's genesis block: {}", e),
+        }
+    }
 
     // Execute command
     let result = match cli.command {
@@ -322,37 +389,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             })
         }
         Commands::GetTransaction { txid } => {
-            json!({
-                "txid": txid,
-                "hash": txid,
-                "version": 2,
-                "size": 0,
-                "vsize": 0,
-                "weight": 0,
-                "locktime": 0,
-                "vin": [],
-                "vout": [],
-                "hex": ""
-            })
+            commands::transaction::get(&config, txid).await?;
+            return Ok(());
         }
         Commands::GetNewAddress => {
-            json!({
-                "address": "testnet1qnewaddress000000000000000000000000000"
-            })
+            commands::wallet::get_new_address(&config).await?;
+            return Ok(());
         }
         Commands::GetBalance => {
-            json!({
-                "balance": 0.0,
-                "unconfirmed_balance": 0.0,
-                "immature_balance": 0.0
-            })
+            commands::wallet::get_balance(&config).await?;
+            return Ok(());
         }
-        Commands::SendToAddress { address, amount } => {
-            json!({
-                "txid": "0000000000000000000000000000000000000000000000000000000000000000",
-                "address": address,
-                "amount": amount
-            })
+        Commands::SendToAddress {
+            address,
+            amount,
+            fee_rate,
+        } => {
+            commands::wallet::send_to_address(&config, &address, amount, fee_rate).await?;
+            return Ok(());
+        }
+        Commands::SyncStatus { watch, interval } => {
+            commands::sync::status(&config, watch, interval).await?;
+            return Ok(());
+        }
+        Commands::Admin(cmd) => {
+            commands::admin::execute(cmd, &config).await?;
+            return Ok(());
+        }
+        Commands::Wallet(cmd) => {
+            commands::wallet::execute(cmd, &config).await?;
+            return Ok(());
         }
         Commands::Swap(cmd) => {
             commands::swap::execute(commands::swap::SwapCmd { command: cmd }, &config).await?;
@@ -33,6 +33,24 @@ pub struct RpcError {
     data: Option<serde_json::Value>,
 }
 
+impl RpcError {
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RPC error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
 // Common RPC response types
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BlockchainInfo {
@@ -110,6 +128,45 @@ pub struct EnvironmentalMetrics {
     pub carbon_credits_earned: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncProgress {
+    pub current_height: u64,
+    pub target_height: u64,
+    pub percent: f64,
+    pub blocks_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub eta_secs: Option<u64>,
+    pub synced: bool,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChainAdminResult {
+    pub old_height: u64,
+    pub old_tip: String,
+    pub new_height: u64,
+    pub new_tip: String,
+    pub affected_blocks: Vec<String>,
+    pub mempool_cleared: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IdentityRotationResult {
+    pub old_peer_id: String,
+    pub new_peer_id: String,
+    pub issued_at: u64,
+    pub grace_until: u64,
+    pub old_signature: String,
+    pub new_signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FeeBumpResult {
+    pub txid: String,
+    pub origtxid: String,
+    pub feerate: u64,
+}
+
 impl RpcClient {
     pub fn new(url: String, timeout: u64) -> Result<Self> {
         let client = Client::builder()
@@ -120,6 +177,10 @@ impl RpcClient {
         Ok(Self { client, url })
     }
 
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
     pub async fn call<T>(&self, method: &str, params: serde_json::Value) -> Result<T>
     where
         T: for<'de> Deserialize<'de>,
@@ -144,8 +205,10 @@ impl RpcClient {
             .await
             .context("Failed to parse RPC response")?;
 
+        // Kept typed so callers can tell a node's refusal from a transport
+        // failure with `downcast_ref::<RpcError>()`
         if let Some(error) = rpc_response.error {
-            anyhow::bail!("RPC error {}: {}", error.code, error.message);
+            return Err(error.into());
         }
 
         rpc_response.result.context("Empty RPC response")
@@ -156,6 +219,23 @@ impl RpcClient {
         self.call("getblockchaininfo", json!([])).await
     }
 
+    pub async fn get_block_count(&self) -> Result<u64> {
+        self.call("getblockcount", json!([])).await
+    }
+
+    pub async fn get_block_hash(&self, height: u64) -> Result<String> {
+        self.call("getblockhash", json!([height])).await
+    }
+
+    /// Hex of the bincode-serialized block
+    pub async fn get_raw_block(&self, hash: &str) -> Result<String> {
+        self.call("getblock", json!([hash, 0])).await
+    }
+
+    pub async fn get_sync_progress(&self) -> Result<SyncProgress> {
+        self.call("getsyncprogress", json!([])).await
+    }
+
     pub async fn get_node_info(&self) -> Result<NodeInfo> {
         self.call("getinfo", json!([])).await
     }
@@ -190,10 +270,20 @@ impl RpcClient {
         self.call("sendtoaddress", json!([to, amount])).await
     }
 
+    /// Submit a signed transaction (hex of its bincode serialization) to the
+    /// node's mempool; returns its txid
+    pub async fn send_raw_transaction(&self, raw_tx: &str) -> Result<String> {
+        self.call("sendrawtransaction", json!([raw_tx])).await
+    }
+
     pub async fn get_transaction(&self, txid: &str) -> Result<TransactionInfo> {
         self.call("gettransaction", json!([txid])).await
     }
 
+    pub async fn bump_fee(&self, txid: &str, fee_rate: u64) -> Result<FeeBumpResult> {
+        self.call("bumpfee", json!([txid, fee_rate])).await
+    }
+
     // Mining methods
     pub async fn start_mining(&self, threads: u32) -> Result<bool> {
         self.call("setgenerate", json!([true, threads])).await
@@ -203,6 +293,23 @@ impl RpcClient {
         self.call("setgenerate", json!([false])).await
     }
 
+    // Admin methods
+    pub async fn rollback_chain(&self, height: u64, wipe_mempool: bool) -> Result<ChainAdminResult> {
+        self.call("rollbackchain", json!([height, wipe_mempool])).await
+    }
+
+    pub async fn invalidate_block(&self, hash: &str) -> Result<ChainAdminResult> {
+        self.call("invalidateblock", json!([hash])).await
+    }
+
+    pub async fn reconsider_block(&self, hash: &str) -> Result<ChainAdminResult> {
+        self.call("reconsiderblock", json!([hash])).await
+    }
+
+    pub async fn rotate_identity(&self) -> Result<IdentityRotationResult> {
+        self.call("rotateidentity", json!([])).await
+    }
+
     // Utility methods
     pub async fn validate_address(&self, address: &str) -> Result<bool> {
         #[derive(Deserialize)]
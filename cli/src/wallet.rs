@@ -1,3 +1,5 @@
+pub mod backend;
+
 use anyhow::{Context, Result};
 use bip32::{DerivationPath, PublicKey, XPrv};
 use bip39::Mnemonic;
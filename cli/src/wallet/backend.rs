@@ -0,0 +1,552 @@
+//! Local HD wallet behind `getnewaddress`, `getbalance` and `sendtoaddress`
+//!
+//! The wallet directory is the one the `wallet` binary manages, by default
+//! `~/.supernova-wallet`. The wallet keeps no UTXO set on disk, so balances
+//! come from rescanning the node's chain over RPC. Payments are signed
+//! locally and submitted with `sendrawtransaction`.
+
+use crate::config::Config;
+use crate::rpc::{RpcClient, RpcError};
+use bitcoin::Network;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use supernova_core::types::block::Block;
+use supernova_wallet::display::NOVA_UNITS_PER_NOVA;
+use supernova_wallet::seeds::WALLET_FILE;
+use supernova_wallet::{
+    AccountType, ChainQuery, Destination, PolicyDecision, SeedStore, TransactionStatus,
+    WalletError, WalletManager,
+};
+use thiserror::Error;
+use tokio::runtime::Handle;
+
+/// Wallet directory under the home directory when none is configured
+pub const DEFAULT_WALLET_DIR: &str = ".supernova-wallet";
+
+/// Account addresses are handed out from and payments are made from
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+/// Fee rate of `sendtoaddress` when none is given, in nova units per byte:
+/// the node's normal-priority estimate at the default relay minimum
+pub const DEFAULT_FEE_RATE: u64 = 2;
+
+#[derive(Debug, Error)]
+pub enum WalletBackendError {
+    #[error("No wallet in {0}; create one with `supernova-cli wallet create`")]
+    NoWallet(PathBuf),
+    #[error("A wallet already exists in {0}")]
+    WalletExists(PathBuf),
+    #[error("Cannot locate the home directory; pass --wallet-dir")]
+    NoHomeDir,
+    #[error("Unknown network '{0}': expected mainnet, testnet or devnet")]
+    UnknownNetwork(String),
+    #[error("Invalid amount {0}: expected a positive number of NOVA")]
+    InvalidAmount(f64),
+    #[error("Cannot reach the node at {url}: {reason}")]
+    NodeUnreachable { url: String, reason: String },
+    #[error("Node error: {0}")]
+    Node(String),
+    #[error("Transaction {txid} rejected by the node: {reason}")]
+    Rejected { txid: String, reason: String },
+    #[error("Payment held for a second approval as pending spend {0}")]
+    PendingApproval(String),
+    #[error("Failed to encode transaction: {0}")]
+    Encoding(#[from] bincode::Error),
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] WalletError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A freshly created wallet and the phrase that restores it
+#[derive(Debug, Serialize)]
+pub struct CreatedWallet {
+    pub wallet_dir: PathBuf,
+    pub network: String,
+    pub mnemonic: String,
+    pub address: String,
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NewAddress {
+    pub address: String,
+    pub account: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WalletBalance {
+    /// In NOVA
+    pub balance: f64,
+    pub balance_units: u64,
+    /// Height of the node's tip when the chain was scanned
+    pub height: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SentPayment {
+    pub txid: String,
+    pub address: String,
+    /// In NOVA
+    pub amount: f64,
+    pub amount_units: u64,
+    pub fee_units: u64,
+    pub fee_rate: u64,
+}
+
+/// `--wallet-dir` or the configured wallet path, else
+/// [`DEFAULT_WALLET_DIR`] in the home directory
+pub fn wallet_dir(config: &Config) -> Result<PathBuf, WalletBackendError> {
+    match &config.wallet_path {
+        Some(path) => Ok(path.clone()),
+        None => dirs::home_dir()
+            .map(|home| home.join(DEFAULT_WALLET_DIR))
+            .ok_or(WalletBackendError::NoHomeDir),
+    }
+}
+
+/// Wallet network of a CLI network name
+pub fn network(name: &str) -> Result<Network, WalletBackendError> {
+    match name.to_lowercase().as_str() {
+        "mainnet" => Ok(Network::Bitcoin),
+        "testnet" => Ok(Network::Testnet),
+        "devnet" | "regtest" => Ok(Network::Regtest),
+        _ => Err(WalletBackendError::UnknownNetwork(name.to_string())),
+    }
+}
+
+/// Whether the active seed of `dir` has been created
+pub fn wallet_exists(dir: &Path) -> Result<bool, WalletBackendError> {
+    let seed_dir = SeedStore::new(dir)
+        .active_dir()
+        .map_err(WalletError::from)?;
+    Ok(seed_dir.join(WALLET_FILE).exists())
+}
+
+/// Open the active seed of `dir`; a locked wallet must still be unlocked
+/// before it can derive addresses or sign
+pub fn open(dir: &Path) -> Result<WalletManager, WalletBackendError> {
+    if !wallet_exists(dir)? {
+        return Err(WalletBackendError::NoWallet(dir.to_path_buf()));
+    }
+    Ok(WalletManager::load(dir.to_path_buf())?)
+}
+
+/// Create a wallet for the network called `network_name` in `dir`, with a
+/// native segwit [`DEFAULT_ACCOUNT`] and its first receive address. The
+/// seed is encrypted under `passphrase` if one is given.
+pub fn create(
+    dir: &Path,
+    network_name: &str,
+    passphrase: Option<&str>,
+) -> Result<CreatedWallet, WalletBackendError> {
+    let network = network(network_name)?;
+    if wallet_exists(dir)? {
+        return Err(WalletBackendError::WalletExists(dir.to_path_buf()));
+    }
+    std::fs::create_dir_all(dir)?;
+
+    let mut manager = WalletManager::new(dir.to_path_buf(), network)?;
+    manager.create_account(DEFAULT_ACCOUNT.to_string(), AccountType::NativeSegWit)?;
+    let address = manager.get_new_address(DEFAULT_ACCOUNT)?.address;
+    let mnemonic = manager.export_mnemonic()?.to_string();
+    if let Some(passphrase) = passphrase {
+        manager.encrypt(passphrase)?;
+    }
+
+    Ok(CreatedWallet {
+        wallet_dir: dir.to_path_buf(),
+        network: network_name.to_lowercase(),
+        mnemonic,
+        address,
+        encrypted: passphrase.is_some(),
+    })
+}
+
+pub fn new_address(manager: &mut WalletManager) -> Result<NewAddress, WalletBackendError> {
+    let address = manager.get_new_address(DEFAULT_ACCOUNT)?;
+    Ok(NewAddress {
+        address: address.address,
+        account: DEFAULT_ACCOUNT.to_string(),
+    })
+}
+
+/// Rebuild the wallet's outputs from the node's chain; returns the tip
+/// height
+pub async fn sync(
+    manager: &mut WalletManager,
+    client: &RpcClient,
+) -> Result<u64, WalletBackendError> {
+    // Ask once up front so an unreachable node is reported as such rather
+    // than as a failed rescan
+    let height = client
+        .get_block_count()
+        .await
+        .map_err(|e| node_error(client, e))?;
+
+    let chain = NodeChain {
+        client,
+        runtime: Handle::current(),
+    };
+    let gap_limit = manager.gap_limit();
+    tokio::task::block_in_place(|| manager.rescan(&chain, 0, gap_limit))?;
+    Ok(height)
+}
+
+pub async fn balance(
+    manager: &mut WalletManager,
+    client: &RpcClient,
+) -> Result<WalletBalance, WalletBackendError> {
+    let height = sync(manager, client).await?;
+    let balance_units = manager.get_total_balance()?;
+    Ok(WalletBalance {
+        balance: units_to_nova(balance_units),
+        balance_units,
+        height,
+    })
+}
+
+/// Pay `amount` NOVA to `address` from [`DEFAULT_ACCOUNT`] and submit the
+/// signed transaction to the node.
+///
+/// The payment passes the account's spending policy first. A transaction
+/// the node refuses is marked failed in the wallet's history.
+pub async fn send_to_address(
+    manager: &mut WalletManager,
+    client: &RpcClient,
+    address: &str,
+    amount: f64,
+    fee_rate: u64,
+) -> Result<SentPayment, WalletBackendError> {
+    let amount_units = nova_to_units(amount)?;
+    sync(manager, client).await?;
+
+    let destination = Destination::address(address.to_string());
+    if let PolicyDecision::PendingApproval(pending) =
+        manager.authorize_spend(DEFAULT_ACCOUNT, &destination, amount_units)?
+    {
+        return Err(WalletBackendError::PendingApproval(pending.id));
+    }
+    let transaction =
+        manager.create_transaction(DEFAULT_ACCOUNT, address, amount_units, fee_rate)?;
+    let txid = hex::encode(transaction.hash());
+    let fee_units = manager
+        .get_transaction(&txid)
+        .map_or(0, |record| record.fee);
+    let raw_tx = hex::encode(bincode::serialize(&transaction)?);
+
+    match client.send_raw_transaction(&raw_tx).await {
+        Ok(_) => Ok(SentPayment {
+            txid,
+            address: address.to_string(),
+            amount: units_to_nova(amount_units),
+            amount_units,
+            fee_units,
+            fee_rate,
+        }),
+        Err(error) => match error.downcast_ref::<RpcError>() {
+            Some(rejection) => {
+                manager.update_transaction_status(&txid, TransactionStatus::Failed)?;
+                let reason = rejection.message();
+                let reason = reason
+                    .strip_prefix("Transaction rejected: ")
+                    .unwrap_or(reason);
+                Err(WalletBackendError::Rejected {
+                    txid,
+                    reason: reason.to_string(),
+                })
+            }
+            // The node may still have accepted it, so the record stays pending
+            None => Err(node_error(client, error)),
+        },
+    }
+}
+
+/// Nova units in `amount` NOVA
+pub fn nova_to_units(amount: f64) -> Result<u64, WalletBackendError> {
+    let units = (amount * NOVA_UNITS_PER_NOVA as f64).round();
+    if !units.is_finite() || units < 1.0 || units >= u64::MAX as f64 {
+        return Err(WalletBackendError::InvalidAmount(amount));
+    }
+    Ok(units as u64)
+}
+
+pub fn units_to_nova(units: u64) -> f64 {
+    units as f64 / NOVA_UNITS_PER_NOVA as f64
+}
+
+fn node_error(client: &RpcClient, error: anyhow::Error) -> WalletBackendError {
+    if let Some(transport) = error.downcast_ref::<reqwest::Error>() {
+        if transport.is_connect() || transport.is_timeout() {
+            return WalletBackendError::NodeUnreachable {
+                url: client.url().to_string(),
+                reason: error.root_cause().to_string(),
+            };
+        }
+    }
+    WalletBackendError::Node(format!("{:#}", error))
+}
+
+/// The node's best chain for [`WalletManager::rescan`], which is
+/// synchronous; it must run inside `block_in_place`
+struct NodeChain<'a> {
+    client: &'a RpcClient,
+    runtime: Handle,
+}
+
+impl ChainQuery for NodeChain<'_> {
+    fn tip_height(&self) -> Result<u64, String> {
+        self.runtime
+            .block_on(self.client.get_block_count())
+            .map_err(|e| format!("{:#}", e))
+    }
+
+    fn block_at_height(&self, height: u64) -> Result<Option<Block>, String> {
+        let raw_block = self
+            .runtime
+            .block_on(async {
+                let hash = self.client.get_block_hash(height).await?;
+                self.client.get_raw_block(&hash).await
+            })
+            .map_err(|e| format!("{:#}", e))?;
+        let bytes = hex::decode(raw_block).map_err(|e| e.to_string())?;
+        bincode::deserialize(&bytes)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Address;
+    use serde_json::{json, Value};
+    use std::str::FromStr;
+    use std::sync::{Arc, Mutex};
+    use supernova_core::test_utils::{TestBlockBuilder, TestTransactionBuilder};
+    use supernova_core::types::transaction::Transaction;
+    use tempfile::tempdir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    const RECIPIENT: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    /// Answers the node's JSON-RPC methods from a fixed chain
+    #[derive(Default)]
+    struct MockNode {
+        blocks: Vec<Block>,
+        /// Reason every submitted transaction is refused with
+        reject: Option<String>,
+        submitted: Mutex<Vec<Transaction>>,
+    }
+
+    impl MockNode {
+        /// Append a block whose coinbase pays `amount` to `address`
+        fn mine_to(&mut self, address: &str, amount: u64) {
+            let script = Address::from_str(address)
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+                .as_bytes()
+                .to_vec();
+            let height = self.blocks.len() as u64;
+            let coinbase = TestTransactionBuilder::new()
+                .add_coinbase_input(height.to_le_bytes().to_vec())
+                .add_output(amount, script)
+                .build()
+                .unwrap();
+            let block = TestBlockBuilder::new()
+                .prev_block_hash(self.blocks.last().map_or([0u8; 32], Block::hash))
+                .timestamp(1_700_000_000 + height * 600)
+                .target(0x207f_ffff)
+                .add_transaction(coinbase)
+                .build()
+                .unwrap();
+            self.blocks.push(block);
+        }
+
+        fn answer(&self, method: &str, params: &Value) -> Value {
+            match method {
+                "getblockcount" => json!({ "result": self.blocks.len() - 1 }),
+                "getblockhash" => {
+                    let height = params[0].as_u64().unwrap() as usize;
+                    json!({ "result": hex::encode(self.blocks[height].hash()) })
+                }
+                "getblock" => {
+                    let hash = params[0].as_str().unwrap();
+                    let block = self
+                        .blocks
+                        .iter()
+                        .find(|block| hex::encode(block.hash()) == hash)
+                        .unwrap();
+                    json!({ "result": hex::encode(bincode::serialize(block).unwrap()) })
+                }
+                "sendrawtransaction" => {
+                    if let Some(reason) = &self.reject {
+                        let message = format!("Transaction rejected: {}", reason);
+                        return json!({ "error": { "code": -26, "message": message } });
+                    }
+                    let bytes = hex::decode(params[0].as_str().unwrap()).unwrap();
+                    let tx: Transaction = bincode::deserialize(&bytes).unwrap();
+                    let txid = hex::encode(tx.hash());
+                    self.submitted.lock().unwrap().push(tx);
+                    json!({ "result": txid })
+                }
+                _ => json!({ "error": { "code": -32601, "message": "Method not found" } }),
+            }
+        }
+    }
+
+    /// Serve `node` over HTTP, one request per connection; returns its URL
+    async fn serve(node: Arc<MockNode>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let node = node.clone();
+                tokio::spawn(async move {
+                    let request = read_request(&mut socket).await;
+                    let method = request["method"].as_str().unwrap_or_default();
+                    let mut response = node.answer(method, &request["params"]);
+                    response["jsonrpc"] = json!("2.0");
+                    response["id"] = request["id"].clone();
+                    let body = response.to_string();
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    socket.write_all(body.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        url
+    }
+
+    async fn read_request(socket: &mut TcpStream) -> Value {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let read = socket.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed mid-request");
+            buffer.extend_from_slice(&chunk[..read]);
+            let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") else {
+                continue;
+            };
+            let head = String::from_utf8_lossy(&buffer[..end]).to_lowercase();
+            let length: usize = head
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |value| value.trim().parse().unwrap());
+            let body = end + 4;
+            if buffer.len() >= body + length {
+                return serde_json::from_slice(&buffer[body..body + length]).unwrap();
+            }
+        }
+    }
+
+    /// A new testnet wallet and a node whose chain pays its first address
+    async fn funded_wallet(
+        funds: u64,
+        reject: Option<&str>,
+    ) -> (tempfile::TempDir, WalletManager, Arc<MockNode>, RpcClient) {
+        let dir = tempdir().unwrap();
+        let created = create(dir.path(), "testnet", None).unwrap();
+        let mut node = MockNode {
+            reject: reject.map(str::to_string),
+            ..MockNode::default()
+        };
+        node.mine_to(RECIPIENT, 1);
+        node.mine_to(&created.address, funds);
+        let node = Arc::new(node);
+        let client = RpcClient::new(serve(node.clone()).await, 5).unwrap();
+        (dir, open(dir.path()).unwrap(), node, client)
+    }
+
+    #[test]
+    fn opening_a_missing_wallet_suggests_creating_one() {
+        let dir = tempdir().unwrap();
+        let error = open(dir.path()).err().unwrap();
+        assert!(matches!(error, WalletBackendError::NoWallet(_)));
+        assert!(error.to_string().contains("supernova-cli wallet create"));
+
+        create(dir.path(), "testnet", None).unwrap();
+        assert!(open(dir.path()).is_ok());
+        assert!(matches!(
+            create(dir.path(), "testnet", None),
+            Err(WalletBackendError::WalletExists(_))
+        ));
+    }
+
+    #[test]
+    fn amounts_convert_from_nova() {
+        assert_eq!(nova_to_units(1.5).unwrap(), 150_000_000);
+        assert_eq!(nova_to_units(0.00000001).unwrap(), 1);
+        for invalid in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e12] {
+            assert!(nova_to_units(invalid).is_err(), "{} accepted", invalid);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn balance_comes_from_the_nodes_chain() {
+        let (_dir, mut manager, _node, client) = funded_wallet(70_000, None).await;
+
+        let balance = balance(&mut manager, &client).await.unwrap();
+        assert_eq!(balance.balance_units, 70_000);
+        assert_eq!(balance.height, 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn payment_is_signed_and_submitted() {
+        let (_dir, mut manager, node, client) = funded_wallet(70_000, None).await;
+
+        let sent = send_to_address(&mut manager, &client, RECIPIENT, 0.0002, 2)
+            .await
+            .unwrap();
+        assert_eq!(sent.amount_units, 20_000);
+        assert!(sent.fee_units > 0);
+
+        let submitted = node.submitted.lock().unwrap();
+        assert_eq!(submitted.len(), 1);
+        assert_eq!(hex::encode(submitted[0].hash()), sent.txid);
+        assert_eq!(submitted[0].outputs()[0].value(), 20_000);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn rejected_payment_reports_the_nodes_reason() {
+        let reason = "fee rate 2 below the minimum of 5";
+        let (_dir, mut manager, _node, client) = funded_wallet(70_000, Some(reason)).await;
+
+        let error = send_to_address(&mut manager, &client, RECIPIENT, 0.0002, 2)
+            .await
+            .unwrap_err();
+        let WalletBackendError::Rejected { txid, reason: got } = &error else {
+            panic!("expected a rejection, got {}", error);
+        };
+        assert_eq!(got, reason);
+        let record = manager.get_transaction(txid).unwrap();
+        assert!(matches!(record.status, TransactionStatus::Failed));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn unreachable_node_is_reported() {
+        let dir = tempdir().unwrap();
+        create(dir.path(), "testnet", None).unwrap();
+        let mut manager = open(dir.path()).unwrap();
+        // Nothing listens on a port just released
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let client = RpcClient::new(url.clone(), 5).unwrap();
+
+        let error = balance(&mut manager, &client).await.unwrap_err();
+        match error {
+            WalletBackendError::NodeUnreachable { url: reported, .. } => {
+                assert_eq!(reported, url)
+            }
+            other => panic!("expected an unreachable node, got {}", other),
+        }
+    }
+}
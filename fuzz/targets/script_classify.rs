@@ -0,0 +1,28 @@
+//! Fuzz harness: output script classification.
+//!
+//! Entry point: classify arbitrary bytes as a script pubkey under every
+//! classifier version and derive its index key and address. Goal:
+//! panic-free on any input, and every address decodes back to the script.
+
+use afl::fuzz;
+use supernova_core::script::classify::{
+    address_to_script, classify_as_of, index_key, CLASSIFIER_VERSION,
+};
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        for version in 0..=CLASSIFIER_VERSION {
+            let classification = classify_as_of(data, version);
+            let _ = classification.kind().as_str();
+            if let Some(address) = classification.address() {
+                assert_eq!(address_to_script(&address).ok().as_deref(), Some(data));
+            }
+        }
+        let _ = index_key(data);
+
+        // The same bytes as an address string
+        if let Ok(text) = std::str::from_utf8(data) {
+            let _ = address_to_script(text);
+        }
+    });
+}
@@ -1,20 +1,34 @@
 use super::reward::EnvironmentalProfile;
 use super::template::{BlockTemplate, MempoolInterface};
+use super::work::WorkBoard;
 use super::worker::MiningWorker;
 use supernova_core::config::NetworkType;
+use supernova_core::environmental::GreenAttestation;
 use supernova_core::types::block::Block;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
 use std::time::{Duration, Instant};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Notify};
 use tracing::{error, info};
 
+/// How often the template is checked for new transactions
+const TEMPLATE_REFRESH_CHECK: Duration = Duration::from_secs(3);
+
+/// Hashes of one mining thread
+struct ThreadRecord {
+    hashes: u64,
+    started: Instant,
+    last_hash: Instant,
+}
+
 pub struct MiningMetrics {
-    total_hash_rate: AtomicU64,
+    total_hashes: AtomicU64,
     blocks_found: AtomicU64,
     active_workers: AtomicU64,
     start_time: Instant,
-    last_hash_time: std::sync::Mutex<Option<Instant>>,
+    threads: Mutex<BTreeMap<usize, ThreadRecord>>,
 }
 
 impl Default for MiningMetrics {
@@ -26,19 +40,33 @@ impl Default for MiningMetrics {
 impl MiningMetrics {
     pub fn new() -> Self {
         Self {
-            total_hash_rate: AtomicU64::new(0),
+            total_hashes: AtomicU64::new(0),
             blocks_found: AtomicU64::new(0),
             active_workers: AtomicU64::new(0),
             start_time: Instant::now(),
-            last_hash_time: std::sync::Mutex::new(None),
+            threads: Mutex::new(BTreeMap::new()),
         }
     }
 
-    pub fn record_hash(&self, _worker_id: usize, hashes: u64) {
-        let current_rate = self.total_hash_rate.load(Ordering::Relaxed);
-        self.total_hash_rate
-            .store(current_rate + hashes, Ordering::Relaxed);
-        *self.last_hash_time.lock().unwrap() = Some(Instant::now());
+    pub fn record_hash(&self, worker_id: usize, hashes: u64) {
+        self.total_hashes.fetch_add(hashes, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut threads = self.threads.lock().unwrap_or_else(PoisonError::into_inner);
+        let record = threads.entry(worker_id).or_insert(ThreadRecord {
+            hashes: 0,
+            started: now,
+            last_hash: now,
+        });
+        record.hashes += hashes;
+        record.last_hash = now;
+    }
+
+    /// Forget a thread that has exited
+    pub fn thread_stopped(&self, worker_id: usize) {
+        self.threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&worker_id);
     }
 
     pub fn record_block(&self) {
@@ -46,38 +74,84 @@ impl MiningMetrics {
     }
 
     pub fn get_stats(&self) -> MiningStats {
+        let threads = self.threads.lock().unwrap_or_else(PoisonError::into_inner);
+        let thread_stats: Vec<ThreadStats> = threads
+            .iter()
+            .map(|(worker_id, record)| {
+                // Averaged over the thread's life, at least a second
+                let elapsed = record.started.elapsed().as_secs_f64().max(1.0);
+                ThreadStats {
+                    worker_id: *worker_id,
+                    hashes: record.hashes,
+                    hash_rate: (record.hashes as f64 / elapsed) as u64,
+                }
+            })
+            .collect();
         MiningStats {
-            hash_rate: self.total_hash_rate.load(Ordering::Relaxed),
+            hash_rate: thread_stats.iter().map(|thread| thread.hash_rate).sum(),
+            total_hashes: self.total_hashes.load(Ordering::Relaxed),
             blocks_found: self.blocks_found.load(Ordering::Relaxed),
             active_workers: self.active_workers.load(Ordering::Relaxed),
             uptime: self.start_time.elapsed(),
-            last_hash: *self.last_hash_time.lock().unwrap(),
+            last_hash: threads.values().map(|record| record.last_hash).max(),
+            threads: thread_stats,
         }
     }
 }
 
 #[derive(Debug)]
 pub struct MiningStats {
+    /// Hashes per second across threads
     pub hash_rate: u64,
+    pub total_hashes: u64,
     pub blocks_found: u64,
     pub active_workers: u64,
     pub uptime: Duration,
     pub last_hash: Option<Instant>,
+    /// Running threads by worker id
+    pub threads: Vec<ThreadStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadStats {
+    pub worker_id: usize,
+    pub hashes: u64,
+    /// Hashes per second since the thread started
+    pub hash_rate: u64,
+}
+
+/// The block being mined on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tip {
+    version: u32,
+    prev_block_hash: [u8; 32],
+    height: u64,
+}
+
+struct WorkerThread {
+    worker: Arc<MiningWorker>,
+    handle: JoinHandle<()>,
 }
 
 #[derive(Clone)]
 pub struct Miner {
-    workers: Vec<Arc<MiningWorker>>,
+    workers: Arc<Mutex<Vec<WorkerThread>>>,
+    /// Threads to run; changes apply to a running miner at once
+    num_threads: Arc<AtomicUsize>,
+    next_worker_id: Arc<AtomicUsize>,
     current_difficulty_target: Arc<AtomicU32>,
+    mining: Arc<AtomicBool>,
     stop_signal: Arc<AtomicBool>,
+    stopped: Arc<Notify>,
     block_sender: mpsc::Sender<Block>,
-    num_threads: usize,
     mempool: Arc<dyn MempoolInterface + Send + Sync>,
     reward_address: Vec<u8>,
     metrics: Arc<MiningMetrics>,
-    shared_template: Option<Arc<tokio::sync::Mutex<BlockTemplate>>>,
+    board: Arc<WorkBoard>,
+    tip: Arc<Mutex<Option<Tip>>>,
     template_refresh_signal: Arc<AtomicBool>,
     environmental_profile: Option<EnvironmentalProfile>,
+    green_attestation: Option<GreenAttestation>,
     current_height: Arc<AtomicU64>,
     network: NetworkType,
 }
@@ -91,86 +165,71 @@ impl Miner {
         network: NetworkType,
     ) -> (Self, mpsc::Receiver<Block>) {
         let (tx, rx) = mpsc::channel(100);
-        let stop_signal = Arc::new(AtomicBool::new(false));
-        let metrics = Arc::new(MiningMetrics::new());
-        let template_refresh_signal = Arc::new(AtomicBool::new(false));
-        let current_height = Arc::new(AtomicU64::new(0));
-
-        let mut workers = Vec::with_capacity(num_threads);
-        for i in 0..num_threads {
-            workers.push(Arc::new(MiningWorker::new(
-                Arc::clone(&stop_signal),
-                tx.clone(),
-                AtomicU32::new(initial_target),
-                i,
-                Arc::clone(&mempool),
-                Arc::clone(&current_height),
-                None, // environmental_profile will be set later
-                network,
-            )));
-        }
 
         (
             Self {
-                workers,
+                workers: Arc::new(Mutex::new(Vec::new())),
+                num_threads: Arc::new(AtomicUsize::new(num_threads)),
+                next_worker_id: Arc::new(AtomicUsize::new(0)),
                 current_difficulty_target: Arc::new(AtomicU32::new(initial_target)),
-                stop_signal,
+                mining: Arc::new(AtomicBool::new(false)),
+                stop_signal: Arc::new(AtomicBool::new(false)),
+                stopped: Arc::new(Notify::new()),
                 block_sender: tx,
-                num_threads,
                 mempool,
                 reward_address,
-                metrics,
-                shared_template: None,
-                template_refresh_signal,
+                metrics: Arc::new(MiningMetrics::new()),
+                board: Arc::new(WorkBoard::new()),
+                tip: Arc::new(Mutex::new(None)),
+                template_refresh_signal: Arc::new(AtomicBool::new(false)),
                 environmental_profile: None,
-                current_height,
+                green_attestation: None,
+                current_height: Arc::new(AtomicU64::new(0)),
                 network,
             },
             rx,
         )
     }
 
+    /// Mine on top of `prev_block_hash` until [`Miner::stop_mining`].
+    ///
+    /// Calling it again while mining switches the running threads to the
+    /// new tip and returns; they drop the old template at their next check.
+    /// Thread count changes from [`Miner::set_threads`] apply without a
+    /// restart.
     pub async fn start_mining(
         &self,
-        _version: u32,
-        _prev_block_hash: [u8; 32],
-        _current_height: u64,
+        version: u32,
+        prev_block_hash: [u8; 32],
+        current_height: u64,
     ) -> Result<(), String> {
+        let tip = Tip {
+            version,
+            prev_block_hash,
+            height: current_height,
+        };
+        *self.tip.lock().unwrap_or_else(PoisonError::into_inner) = Some(tip);
+        self.current_height.store(current_height, Ordering::Relaxed);
+        let template = self.build_template(tip).await;
+        self.board.publish(template);
+
+        if self.mining.swap(true, Ordering::AcqRel) {
+            info!("Mining switched to height {}", current_height);
+            return Ok(());
+        }
+        self.stop_signal.store(false, Ordering::Release);
         info!(
             "Starting mining with {} workers at height {}",
-            self.num_threads, _current_height
-        );
-        self.metrics
-            .active_workers
-            .store(self.num_threads as u64, Ordering::Relaxed);
-
-        // Update current height for all workers
-        self.current_height
-            .store(_current_height, Ordering::Relaxed);
-
-        let template = BlockTemplate::new(
-            _version,
-            _prev_block_hash,
-            self.current_difficulty_target.load(Ordering::Relaxed),
-            self.reward_address.clone(),
-            self.mempool.as_ref(),
-            _current_height,
-            self.environmental_profile.as_ref(),
-            self.network,
-        )
-        .await;
-        let shared_template = Arc::new(tokio::sync::Mutex::new(template));
-
-        let template_refresh_handle = self.start_template_refresh_task(
-            Arc::clone(&shared_template),
-            _version,
-            _prev_block_hash,
-            _current_height,
+            self.thread_count(),
+            current_height
         );
+        if let Err(e) = self.resize_workers() {
+            self.mining.store(false, Ordering::Release);
+            return Err(e);
+        }
 
-        let mut handles = Vec::new();
+        let template_refresh_handle = self.start_template_refresh_task();
         let metrics = Arc::clone(&self.metrics);
-
         let metrics_handle = tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(10));
             loop {
@@ -180,56 +239,133 @@ impl Miner {
             }
         });
 
-        for worker in &self.workers {
-            let worker = Arc::clone(worker);
-            let reward_address = self.reward_address.clone();
-            let template = Arc::clone(&shared_template);
-
-            handles.push(tokio::spawn(async move {
-                worker
-                    .mine_block_with_template(_version, _prev_block_hash, reward_address, template)
-                    .await
-            }));
+        while !self.stop_signal.load(Ordering::Acquire) {
+            self.stopped.notified().await;
         }
 
-        for handle in handles {
-            if let Err(e) = handle.await {
-                error!("Mining task error: {}", e);
-                self.metrics.active_workers.fetch_sub(1, Ordering::Relaxed);
+        metrics_handle.abort();
+        template_refresh_handle.abort();
+        self.board.clear();
+        let workers =
+            std::mem::take(&mut *self.workers.lock().unwrap_or_else(PoisonError::into_inner));
+        let joined = tokio::task::spawn_blocking(move || {
+            for thread in workers {
+                if thread.handle.join().is_err() {
+                    error!("Mining thread {} panicked", thread.worker.worker_id);
+                }
             }
+        })
+        .await;
+        self.metrics.active_workers.store(0, Ordering::Relaxed);
+        self.mining.store(false, Ordering::Release);
+        joined.map_err(|e| format!("Mining threads not joined: {}", e))
+    }
+
+    /// Number of mining threads
+    pub fn thread_count(&self) -> usize {
+        self.num_threads.load(Ordering::Relaxed)
+    }
+
+    /// Change the number of mining threads, at once if mining
+    pub fn set_threads(&self, threads: usize) -> Result<(), String> {
+        self.num_threads.store(threads, Ordering::Relaxed);
+        if self.mining.load(Ordering::Acquire) && !self.stop_signal.load(Ordering::Acquire) {
+            self.resize_workers()?;
         }
+        Ok(())
+    }
 
-        metrics_handle.abort();
-        template_refresh_handle.abort();
+    /// Start or retire threads to match the configured count
+    fn resize_workers(&self) -> Result<(), String> {
+        let target = self.thread_count();
+        let mut workers = self.workers.lock().unwrap_or_else(PoisonError::into_inner);
+        while workers.len() > target {
+            if let Some(thread) = workers.pop() {
+                // It exits within a batch of hashes and drops its stats
+                thread.worker.retire();
+            }
+        }
+        while workers.len() < target {
+            workers.push(self.spawn_worker()?);
+        }
+        self.metrics
+            .active_workers
+            .store(workers.len() as u64, Ordering::Relaxed);
+        info!("Mining with {} threads", workers.len());
         Ok(())
     }
 
-    fn start_template_refresh_task(
-        &self,
-        shared_template: Arc<tokio::sync::Mutex<BlockTemplate>>,
-        _version: u32,
-        _prev_block_hash: [u8; 32],
-        _current_height: u64,
-    ) -> tokio::task::JoinHandle<()> {
-        let mempool = Arc::clone(&self.mempool);
-        let _reward_address = self.reward_address.clone();
-        let template_refresh_signal = Arc::clone(&self.template_refresh_signal);
+    fn spawn_worker(&self) -> Result<WorkerThread, String> {
+        let worker_id = self.next_worker_id.fetch_add(1, Ordering::Relaxed);
+        let worker = Arc::new(MiningWorker::new(
+            Arc::clone(&self.stop_signal),
+            self.block_sender.clone(),
+            AtomicU32::new(self.current_difficulty_target.load(Ordering::Relaxed)),
+            worker_id,
+            Arc::clone(&self.mempool),
+            Arc::clone(&self.current_height),
+            self.environmental_profile.clone(),
+            self.network,
+        ));
+        let handle = std::thread::Builder::new()
+            .name(format!("miner-{}", worker_id))
+            .spawn({
+                let worker = Arc::clone(&worker);
+                let board = Arc::clone(&self.board);
+                let metrics = Arc::clone(&self.metrics);
+                move || worker.mine_shared(&board, &metrics)
+            })
+            .map_err(|e| format!("Failed to start mining thread: {}", e))?;
+        Ok(WorkerThread { worker, handle })
+    }
+
+    async fn build_template(&self, tip: Tip) -> BlockTemplate {
+        let template = BlockTemplate::new(
+            tip.version,
+            tip.prev_block_hash,
+            self.current_difficulty_target.load(Ordering::Relaxed),
+            self.reward_address.clone(),
+            self.mempool.as_ref(),
+            tip.height,
+            self.environmental_profile.as_ref(),
+            self.network,
+        )
+        .await;
+        match &self.green_attestation {
+            Some(attestation) => template.with_green_attestation(attestation),
+            None => template,
+        }
+    }
+
+    fn current_tip(&self) -> Option<Tip> {
+        *self.tip.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn start_template_refresh_task(&self) -> tokio::task::JoinHandle<()> {
+        let miner = self.clone();
 
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(3));
+            let mut interval = tokio::time::interval(TEMPLATE_REFRESH_CHECK);
 
             loop {
                 interval.tick().await;
 
-                let needs_refresh = {
-                    let template = shared_template.lock().await;
-                    template.needs_refresh() || template_refresh_signal.load(Ordering::Relaxed)
+                let needs_refresh = miner.template_refresh_signal.load(Ordering::Relaxed)
+                    || miner
+                        .board
+                        .current()
+                        .is_some_and(|work| work.template.needs_refresh());
+                let Some(tip) = miner.current_tip().filter(|_| needs_refresh) else {
+                    continue;
                 };
 
-                if needs_refresh {
-                    let mut template = shared_template.lock().await;
-                    template.update_transactions(&*mempool).await;
-                    template_refresh_signal.store(false, Ordering::Relaxed);
+                let template = miner.build_template(tip).await;
+                // A new tip published meanwhile wins over this refresh
+                if miner.current_tip() == Some(tip) {
+                    miner.board.publish(template);
+                    miner
+                        .template_refresh_signal
+                        .store(false, Ordering::Relaxed);
                     info!("Block template refreshed with new transactions");
                 }
             }
@@ -237,7 +373,8 @@ impl Miner {
     }
 
     pub fn stop_mining(&self) {
-        self.stop_signal.store(true, Ordering::Relaxed);
+        self.stop_signal.store(true, Ordering::Release);
+        self.stopped.notify_one();
         info!("Mining stopped");
     }
 
@@ -249,9 +386,16 @@ impl Miner {
     pub fn update_difficulty_target(&self, new_target: u32) {
         self.current_difficulty_target
             .store(new_target, Ordering::Relaxed);
-        for worker in &self.workers {
-            worker.update_target(new_target);
+        for thread in self
+            .workers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            thread.worker.update_target(new_target);
         }
+        // The target is part of the template
+        self.request_template_refresh();
         info!("Updated mining difficulty target to: 0x{:08x}", new_target);
     }
 
@@ -265,77 +409,26 @@ impl Miner {
         Arc::clone(&self.metrics)
     }
 
+    /// Hashrate and hash counts in total and per thread
+    pub fn get_mining_stats(&self) -> MiningStats {
+        self.metrics.get_stats()
+    }
+
     /// Set the environmental profile for mining rewards
     pub fn set_environmental_profile(&mut self, profile: EnvironmentalProfile) {
         self.environmental_profile = Some(profile);
     }
+
+    /// Claim the green bonus of a registered attestation in mined blocks
+    pub fn set_green_attestation(&mut self, attestation: GreenAttestation) {
+        self.green_attestation = Some(attestation);
+    }
 }
 
 impl MiningWorker {
     pub fn update_target(&self, new_target: u32) {
         self.target.store(new_target, Ordering::Relaxed);
     }
-
-    pub async fn mine_block_with_template(
-        &self,
-        _version: u32,
-        _prev_block_hash: [u8; 32],
-        _reward_address: Vec<u8>,
-        shared_template: Arc<tokio::sync::Mutex<BlockTemplate>>,
-    ) -> Result<(), String> {
-        let mut attempts = 0;
-        let update_interval = 100_000;
-        let metrics_interval = 1_000_000;
-        let start_time = Instant::now();
-        let mut current_nonce = self.worker_id as u32 * 1_000_000;
-
-        while !self.stop_signal.load(Ordering::Relaxed) {
-            while self.pause_signal.load(Ordering::Relaxed) {
-                tokio::time::sleep(Duration::from_millis(100)).await;
-            }
-
-            if attempts % metrics_interval == 0 {
-                self.metrics
-                    .update_hash_rate(attempts as u64, start_time.elapsed());
-                tracing::info!(
-                    "Worker {} - Mining stats: {:?}",
-                    self.worker_id,
-                    self.metrics.get_stats()
-                );
-            }
-
-            let mut block = {
-                let template = shared_template.lock().await;
-                let mut block = template.create_block();
-
-                for _ in 0..current_nonce {
-                    block.increment_nonce();
-                }
-                block
-            };
-
-            for _ in 0..update_interval {
-                if self.check_proof_of_work(&block) {
-                    self.metrics.record_block_found();
-                    tracing::info!(
-                        "Worker {} - Found valid block after {} attempts!",
-                        self.worker_id,
-                        attempts
-                    );
-                    self.block_sender
-                        .send(block)
-                        .await
-                        .map_err(|e| e.to_string())?;
-                    return Ok(());
-                }
-                block.increment_nonce();
-                attempts += 1;
-                current_nonce += 1;
-            }
-        }
-
-        Ok(())
-    }
 }
 
 #[cfg(test)]
@@ -365,7 +458,7 @@ mod tests {
             reward_address,
             NetworkType::Regtest,
         );
-        assert_eq!(miner.num_threads, 4);
+        assert_eq!(miner.thread_count(), 4);
         assert_eq!(miner.get_current_target(), 0x1d00ffff);
     }
 
@@ -392,10 +485,10 @@ mod tests {
                 assert!(block.validate());
             }
             _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {
-                miner.stop_mining();
             }
         }
 
+        miner.stop_mining();
         mining_handle.await.unwrap();
     }
 
@@ -439,4 +532,47 @@ mod tests {
         let updated_stats = metrics.get_stats();
         assert_eq!(updated_stats.blocks_found, 1);
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn threads_can_be_resized_while_mining() {
+        let (miner, _rx) = Miner::new(
+            1,
+            0x03000001, // Never met, so the threads keep hashing
+            Arc::new(MockMempool),
+            vec![1, 2, 3, 4],
+            NetworkType::Regtest,
+        );
+        let mining_miner = miner.clone();
+        let mining_handle =
+            tokio::spawn(async move { mining_miner.start_mining(1, [0u8; 32], 0).await });
+
+        let wait_for = |threads: usize| {
+            let miner = miner.clone();
+            async move {
+                for _ in 0..500 {
+                    let stats = miner.get_mining_stats();
+                    if stats.threads.len() == threads
+                        && stats.threads.iter().all(|thread| thread.hashes > 0)
+                    {
+                        return stats;
+                    }
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                }
+                panic!("never mined with {} threads", threads);
+            }
+        };
+
+        wait_for(1).await;
+        miner.set_threads(3).unwrap();
+        let stats = wait_for(3).await;
+        assert_eq!(stats.active_workers, 3);
+        assert!(stats.hash_rate > 0);
+
+        miner.set_threads(1).unwrap();
+        assert_eq!(wait_for(1).await.active_workers, 1);
+
+        miner.stop_mining();
+        mining_handle.await.unwrap().unwrap();
+        assert!(miner.get_mining_stats().threads.is_empty());
+    }
 }
@@ -3,6 +3,7 @@ pub mod environmental_verification;
 pub mod fraud_detection;
 pub mod reward;
 pub mod template;
+pub mod work;
 pub mod worker;
 
 #[cfg(test)]
@@ -20,6 +21,7 @@ pub use reward::{
     calculate_base_reward, calculate_mining_reward, EnvironmentalProfile, MiningReward,
 };
 pub use template::{BlockTemplate, MempoolInterface};
+pub use work::{Work, WorkBoard, WorkUnit};
 pub use worker::MiningWorker;
 
 // Note: with the canonical schedule (50 NOVA initial reward, halving every 420,000 blocks),
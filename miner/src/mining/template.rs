@@ -1,10 +1,12 @@
 use super::reward::EnvironmentalProfile;
 use async_trait::async_trait;
 use supernova_core::config::NetworkType;
+use supernova_core::environmental::GreenAttestation;
 use supernova_core::governance::{
     treasury_script_pubkey, validate_treasury_script, TreasuryError,
     TREASURY_ALLOCATION_PERCENT as GOVERNANCE_TREASURY_PERCENT,
 };
+use supernova_core::mempool::{select_by_package_fee_rate, PackageEntry};
 use supernova_core::types::block::Block;
 use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -114,7 +116,8 @@ impl BlockTemplate {
         }
     }
 
-    // Efficient transaction selection based on fees
+    // Transaction selection by ancestor package fee rate, so a high-fee child
+    // pulls its low-fee parent into the block (CPFP)
     async fn select_transactions(
         mempool: &dyn MempoolInterface,
         available_size: usize,
@@ -124,49 +127,23 @@ impl BlockTemplate {
             .get_prioritized_transactions(available_size * 2)
             .await;
 
-        // Sort by fee per byte (fee density) if not already sorted
         let txids: Vec<Vec<u8>> = transactions.iter().map(|tx| tx.hash().to_vec()).collect();
         let fees = mempool.get_transaction_fees(&txids).await;
 
-        // Create tuples of (transaction, fee, size) for sorting
-        let mut tx_fee_size: Vec<(Transaction, u64, usize)> = transactions
-            .into_iter()
-            .zip(fees.into_iter())
+        let packages: Vec<PackageEntry> = transactions
+            .iter()
+            .zip(fees)
             .map(|(tx, fee)| {
-                let size = bincode::serialize(&tx).unwrap().len();
-                (tx, fee, size)
+                let size = bincode::serialize(tx).unwrap().len();
+                PackageEntry::new(tx, fee, size)
             })
             .collect();
 
-        // Sort by fee per byte (fee density) in descending order
-        tx_fee_size.sort_by(|a, b| {
-            let fee_rate_a = if a.2 > 0 {
-                a.1 as f64 / a.2 as f64
-            } else {
-                0.0
-            };
-            let fee_rate_b = if b.2 > 0 {
-                b.1 as f64 / b.2 as f64
-            } else {
-                0.0
-            };
-            fee_rate_b
-                .partial_cmp(&fee_rate_a)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        // Select transactions that fit in the block
-        let mut selected = Vec::new();
-        let mut total_size = 0;
-
-        for (tx, _, size) in tx_fee_size {
-            if total_size + size <= available_size {
-                total_size += size;
-                selected.push(tx);
-            }
-        }
-
-        selected
+        // Parents come before children, and whole packages fit in the block
+        select_by_package_fee_rate(&packages, available_size)
+            .into_iter()
+            .map(|i| transactions[i].clone())
+            .collect()
     }
 
     pub fn create_block(&self) -> Block {
@@ -181,6 +158,65 @@ impl BlockTemplate {
         )
     }
 
+    /// Block whose coinbase script carries `extranonce`, so every extranonce
+    /// has its own merkle root and nonce space
+    pub fn create_block_with_extranonce(&self, extranonce: u64) -> Block {
+        let inputs = self
+            .coinbase
+            .inputs()
+            .iter()
+            .map(|input| {
+                TransactionInput::new(
+                    input.prev_tx_hash(),
+                    input.prev_output_index(),
+                    extranonce.to_le_bytes().to_vec(),
+                    input.sequence(),
+                )
+            })
+            .collect();
+        let coinbase = Transaction::new(
+            self.coinbase.version(),
+            inputs,
+            self.coinbase.outputs().to_vec(),
+            self.coinbase.lock_time(),
+        );
+        let mut transactions = vec![coinbase];
+        transactions.extend(self.transactions.clone());
+
+        Block::new_with_params(
+            self.version,
+            self.prev_block_hash,
+            transactions,
+            self.target,
+        )
+    }
+
+    /// Claim the green bonus `attestation` grants. The bonus is paid from
+    /// the environmental treasury, so it and its commitment are added after
+    /// the miner and treasury outputs without touching either.
+    pub fn with_green_attestation(mut self, attestation: &GreenAttestation) -> Self {
+        let bonus = attestation.bonus_for(supernova_core::types::block_subsidy(self.block_height));
+        // The commitment must follow the miner and treasury outputs
+        if bonus == 0 || self.coinbase.outputs().len() != 2 {
+            tracing::warn!(
+                "No green bonus claimed at height {}: bonus {}",
+                self.block_height,
+                bonus
+            );
+            return self;
+        }
+
+        let mut outputs = self.coinbase.outputs().to_vec();
+        outputs.extend(attestation.bonus_outputs(bonus));
+        self.coinbase = Transaction::new(
+            self.coinbase.version(),
+            self.coinbase.inputs().to_vec(),
+            outputs,
+            self.coinbase.lock_time(),
+        );
+        self
+    }
+
     // Check if template needs refresh
     pub fn needs_refresh(&self) -> bool {
         self.needs_refresh.load(Ordering::Relaxed)
@@ -502,6 +538,52 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_green_attestation_adds_bonus_after_treasury() {
+        use supernova_core::environmental::GreenBonusClaim;
+        use supernova_core::types::block_subsidy;
+
+        let attestation = GreenAttestation {
+            attestor: "rec-auditor".to_string(),
+            payout_script: vec![5, 6, 7, 8],
+            bonus_bps: 1_000,
+            valid_from: 0,
+            expires_at: u64::MAX,
+            max_total_bonus: u64::MAX,
+            signature: Vec::new(),
+        };
+        let template = BlockTemplate::new(
+            1,
+            [0u8; 32],
+            u32::MAX,
+            vec![1, 2, 3, 4],
+            &MockMempool,
+            1,
+            None,
+            NetworkType::Regtest,
+        )
+        .await;
+        let paid = |outputs: &[TransactionOutput]| -> Vec<(u64, Vec<u8>)> {
+            outputs
+                .iter()
+                .map(|o| (o.amount(), o.pub_key_script.clone()))
+                .collect()
+        };
+        let plain = paid(template.coinbase.outputs());
+
+        let block = template.with_green_attestation(&attestation).create_block();
+        let coinbase = &block.transactions()[0];
+        let outputs = coinbase.outputs();
+        assert_eq!(outputs.len(), 4);
+        assert_eq!(paid(&outputs[..2]), plain);
+
+        let claim = GreenBonusClaim::from_coinbase(coinbase).unwrap().unwrap();
+        assert_eq!(claim.attestation_id, attestation.id());
+        assert_eq!(claim.bonus_index, 2);
+        assert_eq!(claim.amount, block_subsidy(1) / 10);
+        assert_eq!(outputs[2].pub_key_script, attestation.payout_script);
+    }
+
     #[tokio::test]
     async fn test_transaction_selection() {
         let mempool = MockMempool;
@@ -518,6 +600,59 @@ mod tests {
         assert!(fee_ratio_first >= fee_ratio_second);
     }
 
+    /// Mempool holding a fixed set of transactions with known fees
+    struct ChainMempool {
+        transactions: Vec<Transaction>,
+        fees: Vec<u64>,
+    }
+
+    #[async_trait]
+    impl MempoolInterface for ChainMempool {
+        async fn get_transactions(&self, _max_size: usize) -> Vec<Transaction> {
+            self.transactions.clone()
+        }
+
+        async fn get_transaction_fees(&self, txids: &[Vec<u8>]) -> Vec<u64> {
+            txids
+                .iter()
+                .map(|txid| {
+                    self.transactions
+                        .iter()
+                        .position(|tx| tx.hash().as_slice() == txid.as_slice())
+                        .map(|i| self.fees[i])
+                        .unwrap_or(0)
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_child_pays_for_parent_selection() {
+        let spend = |prev: [u8; 32], amount: u64| {
+            Transaction::new(
+                1,
+                vec![TransactionInput::new(prev, 0, vec![], 0xffffffff)],
+                vec![TransactionOutput::new(amount, vec![])],
+                0,
+            )
+        };
+        let parent = spend([1u8; 32], 100_000);
+        let other = spend([2u8; 32], 100_000);
+        let child = spend(parent.hash(), 90_000);
+        let mempool = ChainMempool {
+            transactions: vec![parent.clone(), other.clone(), child.clone()],
+            fees: vec![100, 5_000, 50_000],
+        };
+
+        // Room for two transactions: the low-fee parent is carried in by its
+        // child ahead of the higher-fee unrelated transaction
+        let size = |tx: &Transaction| bincode::serialize(tx).unwrap().len();
+        let available = size(&parent) + size(&child);
+        let selected = BlockTemplate::select_transactions(&mempool, available).await;
+        let hashes: Vec<[u8; 32]> = selected.iter().map(|tx| tx.hash()).collect();
+        assert_eq!(hashes, vec![parent.hash(), child.hash()]);
+    }
+
     #[tokio::test]
     async fn test_template_refresh() {
         let mempool = MockMempool;
@@ -0,0 +1,217 @@
+//! Work shared by the mining threads
+//!
+//! Threads never grind the same header. Each claims a [`WorkUnit`] — a
+//! range of nonces under one extranonce — from the current [`Work`], and the
+//! extranonce goes into the coinbase script so every extranonce has its own
+//! merkle root. Publishing new work bumps the board's generation, which
+//! threads check between batches of hashes to drop a stale template at once.
+
+use super::template::BlockTemplate;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Nonces in a unit; a thread claims its next unit after hashing these
+pub const NONCES_PER_UNIT: u64 = 1 << 20;
+
+const NONCE_SPACE: u64 = 1 << 32;
+
+/// Nonces under one extranonce that a single thread mines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkUnit {
+    pub extranonce: u64,
+    pub nonces: RangeInclusive<u32>,
+}
+
+/// A template being mined, split between threads by extranonce and nonce
+pub struct Work {
+    pub template: BlockTemplate,
+    generation: u64,
+    nonces_per_unit: u64,
+    next_unit: AtomicU64,
+    solved: AtomicBool,
+}
+
+impl Work {
+    fn new(template: BlockTemplate, generation: u64, nonces_per_unit: u64) -> Self {
+        Self {
+            template,
+            generation,
+            nonces_per_unit,
+            next_unit: AtomicU64::new(0),
+            solved: AtomicBool::new(false),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Claim a unit no other thread is given; `None` once a block was found
+    pub fn claim(&self) -> Option<WorkUnit> {
+        if self.is_solved() {
+            return None;
+        }
+        let units_per_extranonce = NONCE_SPACE / self.nonces_per_unit;
+        let unit = self.next_unit.fetch_add(1, Ordering::Relaxed);
+        let first = (unit % units_per_extranonce) * self.nonces_per_unit;
+        let last = first + self.nonces_per_unit - 1;
+        Some(WorkUnit {
+            extranonce: unit / units_per_extranonce,
+            nonces: first as u32..=last as u32,
+        })
+    }
+
+    /// Record that a block was found; true only for the first caller
+    pub fn mark_solved(&self) -> bool {
+        !self.solved.swap(true, Ordering::AcqRel)
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.solved.load(Ordering::Acquire)
+    }
+}
+
+/// The current work and the "new work available" generation
+pub struct WorkBoard {
+    current: RwLock<Option<Arc<Work>>>,
+    generation: AtomicU64,
+    nonces_per_unit: u64,
+}
+
+impl Default for WorkBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorkBoard {
+    pub fn new() -> Self {
+        Self::with_unit_size(NONCES_PER_UNIT)
+    }
+
+    /// A board handing out `nonces_per_unit` nonces per claim, rounded up
+    /// to a power of two no larger than the nonce space
+    pub fn with_unit_size(nonces_per_unit: u64) -> Self {
+        Self {
+            current: RwLock::new(None),
+            generation: AtomicU64::new(0),
+            nonces_per_unit: nonces_per_unit.clamp(1, NONCE_SPACE).next_power_of_two(),
+        }
+    }
+
+    /// Replace the work; threads drop the old template at their next check
+    pub fn publish(&self, template: BlockTemplate) -> Arc<Work> {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let generation = self.generation.load(Ordering::Relaxed) + 1;
+        let work = Arc::new(Work::new(template, generation, self.nonces_per_unit));
+        *current = Some(Arc::clone(&work));
+        self.generation.store(generation, Ordering::Release);
+        work
+    }
+
+    /// Withdraw the work, leaving threads idle
+    pub fn clear(&self) {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        *current = None;
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn current(&self) -> Option<Arc<Work>> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Whether `work` has been replaced or withdrawn
+    pub fn is_stale(&self, work: &Work) -> bool {
+        self.generation.load(Ordering::Acquire) != work.generation
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::mining::MempoolInterface;
+    use async_trait::async_trait;
+    use std::collections::HashSet;
+    use supernova_core::config::NetworkType;
+    use supernova_core::types::transaction::Transaction;
+
+    struct MockMempool;
+
+    #[async_trait]
+    impl MempoolInterface for MockMempool {
+        async fn get_transactions(&self, _max_size: usize) -> Vec<Transaction> {
+            Vec::new()
+        }
+    }
+
+    pub(crate) async fn template() -> BlockTemplate {
+        BlockTemplate::new(
+            1,
+            [0u8; 32],
+            0x207fffff,
+            vec![1, 2, 3, 4],
+            &MockMempool,
+            1,
+            None,
+            NetworkType::Regtest,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn concurrent_claims_are_disjoint_and_roll_the_extranonce() {
+        // Four units per extranonce
+        let board = WorkBoard::with_unit_size(1 << 30);
+        let work = board.publish(template().await);
+
+        let claims: Vec<WorkUnit> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| (0..10).filter_map(|_| work.claim()).collect::<Vec<_>>()))
+                .collect();
+            threads
+                .into_iter()
+                .flat_map(|thread| thread.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(claims.len(), 80);
+        let starts: HashSet<(u64, u32)> = claims
+            .iter()
+            .map(|unit| (unit.extranonce, *unit.nonces.start()))
+            .collect();
+        assert_eq!(starts.len(), 80, "two threads were given the same unit");
+        for unit in &claims {
+            assert_eq!(unit.nonces.start() % (1 << 30), 0);
+            assert_eq!(
+                u64::from(unit.nonces.end() - unit.nonces.start()) + 1,
+                1 << 30
+            );
+        }
+        let extranonces: HashSet<u64> = claims.iter().map(|unit| unit.extranonce).collect();
+        assert_eq!(extranonces, (0..20).collect::<HashSet<u64>>());
+    }
+
+    #[tokio::test]
+    async fn new_work_makes_old_work_stale() {
+        let board = WorkBoard::new();
+        let first = board.publish(template().await);
+        assert!(!board.is_stale(&first));
+
+        let second = board.publish(template().await);
+        assert!(board.is_stale(&first));
+        assert!(!board.is_stale(&second));
+        assert_eq!(second.claim().unwrap().extranonce, 0);
+
+        assert!(second.mark_solved());
+        assert!(!second.mark_solved());
+        assert_eq!(second.claim(), None);
+
+        board.clear();
+        assert!(board.is_stale(&second));
+        assert!(board.current().is_none());
+    }
+}
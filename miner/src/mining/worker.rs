@@ -1,5 +1,7 @@
+use crate::mining::coordinator::MiningMetrics as SharedMetrics;
 use crate::mining::reward::EnvironmentalProfile;
 use crate::mining::template::BlockTemplate;
+use crate::mining::work::{Work, WorkBoard, WorkUnit};
 use crate::mining::MempoolInterface;
 use supernova_core::config::NetworkType;
 use supernova_core::types::block::Block;
@@ -17,6 +19,12 @@ const MEMORY_SIZE: usize = 4 * 1024 * 1024; // 4MB memory requirement
 const MEMORY_ITERATIONS: usize = 64; // Number of memory accesses
 const MIXING_ROUNDS: usize = 16; // Number of mixing rounds
 
+/// Hashes between checks for new work and stop requests
+pub const CHECK_INTERVAL: u64 = 4096;
+
+/// Wait before looking again when there is no work to claim
+const IDLE_WAIT: Duration = Duration::from_millis(20);
+
 pub struct MiningMetrics {
     hash_rate: AtomicU64,
     blocks_mined: AtomicU64,
@@ -86,6 +94,8 @@ pub struct MiningWorker {
     pub(crate) current_height: Arc<AtomicU64>,
     pub(crate) environmental_profile: Option<EnvironmentalProfile>,
     pub(crate) network: NetworkType,
+    /// Set when the thread count shrinks and this worker should exit
+    retired: AtomicBool,
 }
 
 impl MiningWorker {
@@ -110,6 +120,7 @@ impl MiningWorker {
             current_height,
             environmental_profile,
             network,
+            retired: AtomicBool::new(false),
         }
     }
 
@@ -125,6 +136,105 @@ impl MiningWorker {
         Arc::clone(&self.metrics)
     }
 
+    /// Ask the worker to exit after its current batch of hashes
+    pub fn retire(&self) {
+        self.retired.store(true, Ordering::Relaxed);
+    }
+
+    fn is_running(&self) -> bool {
+        !self.stop_signal.load(Ordering::Relaxed) && !self.retired.load(Ordering::Relaxed)
+    }
+
+    /// Mine the work on `board` until stopped or retired, on the calling
+    /// thread. Units are claimed from the current work, so no two workers
+    /// hash the same extranonce and nonce for a template.
+    pub fn mine_shared(&self, board: &WorkBoard, metrics: &SharedMetrics) {
+        self.mine_with(board, metrics, |block| self.check_proof_of_work(block));
+        metrics.thread_stopped(self.worker_id);
+    }
+
+    fn mine_with(
+        &self,
+        board: &WorkBoard,
+        metrics: &SharedMetrics,
+        mut check: impl FnMut(&Block) -> bool,
+    ) {
+        // The block of the last unit, reused while the extranonce is unchanged
+        let mut candidate: Option<(Arc<Work>, u64, Block)> = None;
+
+        while self.is_running() {
+            if self.pause_signal.load(Ordering::Relaxed) {
+                std::thread::sleep(IDLE_WAIT);
+                continue;
+            }
+            let Some((work, unit)) = board
+                .current()
+                .and_then(|work| work.claim().map(|unit| (work, unit)))
+            else {
+                std::thread::sleep(IDLE_WAIT);
+                continue;
+            };
+
+            let reusable = matches!(
+                &candidate,
+                Some((mined, extranonce, _)) if Arc::ptr_eq(mined, &work) && *extranonce == unit.extranonce
+            );
+            if !reusable {
+                let block = work.template.create_block_with_extranonce(unit.extranonce);
+                candidate = Some((Arc::clone(&work), unit.extranonce, block));
+            }
+            let Some((_, extranonce, block)) = candidate.as_mut() else {
+                continue;
+            };
+
+            if self.grind(block, unit, board, &work, metrics, &mut check) && work.mark_solved() {
+                metrics.record_block();
+                self.metrics.record_block_found();
+                tracing::info!(
+                    "Worker {} - Found block with extranonce {} and nonce {}",
+                    self.worker_id,
+                    extranonce,
+                    block.header.nonce
+                );
+                if self.block_sender.blocking_send(block.clone()).is_err() {
+                    tracing::warn!("Worker {} - Block receiver closed", self.worker_id);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Hash the nonces of `unit`; true when `block` now holds a solution.
+    /// Gives up early when the work goes stale or the worker is stopped.
+    fn grind(
+        &self,
+        block: &mut Block,
+        unit: WorkUnit,
+        board: &WorkBoard,
+        work: &Work,
+        metrics: &SharedMetrics,
+        check: &mut impl FnMut(&Block) -> bool,
+    ) -> bool {
+        let mut hashes = 0;
+        for nonce in unit.nonces {
+            block.header.set_nonce(nonce);
+            hashes += 1;
+            if check(&*block) {
+                metrics.record_hash(self.worker_id, hashes);
+                return true;
+            }
+            if hashes == CHECK_INTERVAL {
+                metrics.record_hash(self.worker_id, hashes);
+                hashes = 0;
+                if !self.is_running() || board.is_stale(work) || work.is_solved() {
+                    return false;
+                }
+            }
+        }
+        metrics.record_hash(self.worker_id, hashes);
+        false
+    }
+
     pub async fn mine_block(
         &self,
         version: u32,
@@ -363,4 +473,61 @@ mod tests {
         metrics.record_block_found();
         assert_eq!(metrics.get_stats().blocks_mined, 1);
     }
+
+    #[tokio::test]
+    async fn threads_never_hash_the_same_header() {
+        let (tx, _rx) = mpsc::channel(1);
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let board = WorkBoard::with_unit_size(1 << 12);
+        board.publish(crate::mining::work::tests::template().await);
+        let metrics = SharedMetrics::new();
+
+        let workers: Vec<MiningWorker> = (0..4)
+            .map(|worker_id| {
+                MiningWorker::new(
+                    Arc::clone(&stop_signal),
+                    tx.clone(),
+                    AtomicU32::new(0x207fffff),
+                    worker_id,
+                    Arc::new(MockMempool),
+                    Arc::new(AtomicU64::new(0)),
+                    None,
+                    NetworkType::Regtest,
+                )
+            })
+            .collect();
+
+        let hashed: Vec<(u64, u32)> = std::thread::scope(|scope| {
+            let threads: Vec<_> = workers
+                .iter()
+                .map(|worker| {
+                    let (board, metrics) = (&board, &metrics);
+                    scope.spawn(move || {
+                        let mut hashed = Vec::new();
+                        worker.mine_with(board, metrics, |block| {
+                            let script = block.transactions[0].inputs()[0].signature_script();
+                            let extranonce = u64::from_le_bytes(script.try_into().unwrap());
+                            hashed.push((extranonce, block.header.nonce));
+                            if hashed.len() >= 5 * CHECK_INTERVAL as usize {
+                                worker.retire();
+                            }
+                            false
+                        });
+                        hashed
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .flat_map(|thread| thread.join().unwrap())
+                .collect()
+        });
+
+        let unique: std::collections::HashSet<_> = hashed.iter().collect();
+        assert_eq!(unique.len(), hashed.len(), "a header was hashed twice");
+        let stats = metrics.get_stats();
+        assert_eq!(stats.total_hashes, hashed.len() as u64);
+        assert_eq!(stats.threads.len(), 4);
+        assert!(stats.threads.iter().all(|thread| thread.hashes > 0));
+    }
 }
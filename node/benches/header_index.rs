@@ -0,0 +1,152 @@
+//! Header index versus database walks for the ancestor queries validation
+//! runs on every block.
+//!
+//! - `mtp`: median-time-past over the last 11 ancestors of the tip.
+//! - `fork_point`: last common ancestor of two branches that diverged 500
+//!   blocks below their tips.
+//!
+//! The `db_walk` variants reproduce the previous implementation (one block
+//! read per parent); the `header_index` variants query a warm index the way
+//! `ChainState` does.
+//!
+//! Run:
+//!
+//! ```
+//! cargo bench -p supernova-node --bench header_index
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::convert::Infallible;
+use tempfile::TempDir;
+
+use node::storage::{BlockchainDB, HeaderIndex, IndexedHeader};
+use supernova_core::types::block::Block;
+
+const MAIN_LEN: u64 = 2_000;
+const FORK_LEN: u64 = 500;
+
+struct Fixture {
+    _dir: TempDir,
+    db: BlockchainDB,
+    main_tip: [u8; 32],
+    fork_tip: [u8; 32],
+    index: HeaderIndex,
+}
+
+/// Store a chain of `len` empty blocks above `parent`.
+fn extend(db: &BlockchainDB, parent: Option<&Block>, len: u64, salt: u64) -> Vec<Block> {
+    let mut blocks = Vec::with_capacity(len as usize);
+    let mut prev = parent.cloned();
+    for _ in 0..len {
+        let (prev_hash, height) = prev
+            .as_ref()
+            .map_or(([0u8; 32], 0), |p| (p.hash(), p.height() + 1));
+        let mut block = Block::new_with_params(1, prev_hash, vec![], 0x207f_ffff);
+        block.set_height(height);
+        block
+            .header
+            .set_timestamp(1_700_000_000 + height * 150 + salt);
+        db.store_block(&block.hash(), &bincode::serialize(&block).unwrap())
+            .unwrap();
+        blocks.push(block.clone());
+        prev = Some(block);
+    }
+    blocks
+}
+
+fn fixture() -> Fixture {
+    let dir = TempDir::new().unwrap();
+    let db = BlockchainDB::new(dir.path()).unwrap();
+    let main = extend(&db, None, MAIN_LEN, 0);
+    let fork_base = &main[(MAIN_LEN - FORK_LEN - 1) as usize];
+    let fork = extend(&db, Some(fork_base), FORK_LEN, 7);
+
+    let mut index = HeaderIndex::default();
+    for block in main.iter().chain(fork.iter()) {
+        index.insert(IndexedHeader::from_header(&block.header));
+    }
+    Fixture {
+        _dir: dir,
+        main_tip: main.last().unwrap().hash(),
+        fork_tip: fork.last().unwrap().hash(),
+        db,
+        index,
+    }
+}
+
+fn load(
+    db: &BlockchainDB,
+) -> impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, Infallible> + '_ {
+    move |hash| {
+        Ok(db
+            .get_block(hash)
+            .unwrap()
+            .map(|b| IndexedHeader::from_header(&b.header)))
+    }
+}
+
+fn db_walk_mtp(db: &BlockchainDB, tip: &[u8; 32]) -> u64 {
+    let mut timestamps = Vec::with_capacity(11);
+    let mut cursor = db.get_block(tip).unwrap().unwrap();
+    loop {
+        timestamps.push(cursor.timestamp());
+        if timestamps.len() >= 11 || *cursor.prev_block_hash() == [0u8; 32] {
+            break;
+        }
+        cursor = db.get_block(cursor.prev_block_hash()).unwrap().unwrap();
+    }
+    timestamps.sort_unstable();
+    timestamps[timestamps.len() / 2]
+}
+
+fn db_walk_fork_point(db: &BlockchainDB, a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let mut left = db.get_block(a).unwrap().unwrap();
+    let mut right = db.get_block(b).unwrap().unwrap();
+    while left.hash() != right.hash() {
+        if left.height() >= right.height() {
+            left = db.get_block(left.prev_block_hash()).unwrap().unwrap();
+        } else {
+            right = db.get_block(right.prev_block_hash()).unwrap().unwrap();
+        }
+    }
+    left.hash()
+}
+
+fn bench_mtp(c: &mut Criterion) {
+    let mut fx = fixture();
+    let mut group = c.benchmark_group("mtp");
+    group.bench_function("db_walk", |b| {
+        b.iter(|| db_walk_mtp(&fx.db, black_box(&fx.fork_tip)))
+    });
+    group.bench_function("header_index", |b| {
+        b.iter(|| {
+            fx.index
+                .median_time_past(black_box(&fx.fork_tip), load(&fx.db))
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+fn bench_fork_point(c: &mut Criterion) {
+    let mut fx = fixture();
+    let mut group = c.benchmark_group("fork_point");
+    group.bench_function("db_walk", |b| {
+        b.iter(|| db_walk_fork_point(&fx.db, black_box(&fx.main_tip), black_box(&fx.fork_tip)))
+    });
+    group.bench_function("header_index", |b| {
+        b.iter(|| {
+            fx.index
+                .fork_point(
+                    black_box(&fx.main_tip),
+                    black_box(&fx.fork_tip),
+                    load(&fx.db),
+                )
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_mtp, bench_fork_point);
+criterion_main!(benches);
@@ -0,0 +1,860 @@
+//! Watch-only address subscriptions
+//!
+//! Light wallet backends subscribe to a set of addresses over the WebSocket
+//! endpoint instead of polling the address index:
+//!
+//! ```text
+//! -> {"subscribe_addresses": ["<address>", ...]}
+//! <- {"type": "subscribed", "token": "...", "snapshot": [...]}
+//! <- {"type": "event", "seq": 1, "event": {"kind": "received", ...}}
+//! ```
+//!
+//! The snapshot lists current UTXOs and recent transactions per address when
+//! the node keeps an address index. Afterwards every output paid to, or spent
+//! from, a subscribed address is pushed as it enters the mempool and again
+//! when it confirms; a block disconnected by a reorg produces `unconfirmed`
+//! corrections for the transactions it carried.
+//!
+//! Each subscription is a session identified by a resumption token. Events
+//! are numbered per session and the most recent ones are buffered, so a
+//! client that reconnects within [`RESUME_WINDOW`] can send
+//! `{"resume": {"token": "...", "last_seq": n}}` and receive what it missed.
+//!
+//! Addresses are identified the same way as in the transaction index: the
+//! bech32m address for quantum pubkey-hash outputs, the hex-encoded output
+//! script for anything else (`supernova_core::script::classify::index_key`).
+
+use crate::events::NodeEvent;
+use crate::storage::{BlockchainDB, TransactionIndexer};
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::Transaction;
+use tokio::sync::{broadcast, mpsc};
+
+/// Most addresses a single connection may watch
+pub const MAX_ADDRESSES_PER_CONNECTION: usize = 100;
+
+/// Events buffered per session for resumption
+pub const RESUME_BUFFER_EVENTS: usize = 1_000;
+
+/// How long a disconnected session stays resumable
+pub const RESUME_WINDOW: Duration = Duration::from_secs(120);
+
+/// Recent transactions included per address in a snapshot
+pub const SNAPSHOT_RECENT_TXS: usize = 25;
+
+/// Error codes sent in `error` messages
+pub mod error_codes {
+    pub const INVALID_MESSAGE: i32 = 1;
+    pub const TOO_MANY_ADDRESSES: i32 = 2;
+    pub const UNKNOWN_TOKEN: i32 = 3;
+    pub const EVENTS_LOST: i32 = 4;
+}
+
+/// Message sent by a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientMessage {
+    SubscribeAddresses(Vec<String>),
+    UnsubscribeAddresses(Vec<String>),
+    Resume { token: String, last_seq: u64 },
+}
+
+/// Message pushed to a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Subscription accepted, with the state of the newly added addresses
+    Subscribed {
+        token: String,
+        snapshot: Vec<AddressSnapshot>,
+    },
+    /// Addresses removed from the subscription
+    Unsubscribed { addresses: Vec<String> },
+    /// Session resumed; `replayed` missed events follow
+    Resumed { token: String, replayed: usize },
+    /// Activity on a subscribed address
+    Event { seq: u64, event: AddressEvent },
+    Error { code: i32, message: String },
+}
+
+/// Current state of an address
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressSnapshot {
+    pub address: String,
+    /// Unspent outputs; `None` when the address index is disabled
+    pub utxos: Option<Vec<AddressUtxo>>,
+    /// Most recent transactions first; `None` when the address index is disabled
+    pub recent_transactions: Option<Vec<AddressTxSummary>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressUtxo {
+    pub txid: String,
+    pub vout: u32,
+    pub value: u64,
+    pub height: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressTxSummary {
+    pub txid: String,
+    pub height: Option<u64>,
+}
+
+/// Activity on a subscribed address. `height` is `None` while unconfirmed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AddressEvent {
+    /// The address received an output
+    Received {
+        address: String,
+        txid: String,
+        vout: u32,
+        value: u64,
+        height: Option<u64>,
+    },
+    /// An output of the address was spent
+    Spent {
+        address: String,
+        txid: String,
+        vout: u32,
+        spending_txid: String,
+        height: Option<u64>,
+    },
+    /// A transaction already reported from the mempool was mined
+    Confirmed {
+        address: String,
+        txid: String,
+        height: u64,
+        block_hash: String,
+    },
+    /// Reorg correction: the block confirming the transaction was
+    /// disconnected, so it is unconfirmed again
+    Unconfirmed {
+        address: String,
+        txid: String,
+        height: u64,
+        block_hash: String,
+    },
+}
+
+impl AddressEvent {
+    pub fn address(&self) -> &str {
+        match self {
+            AddressEvent::Received { address, .. }
+            | AddressEvent::Spent { address, .. }
+            | AddressEvent::Confirmed { address, .. }
+            | AddressEvent::Unconfirmed { address, .. } => address,
+        }
+    }
+}
+
+/// Chain data backing snapshots and spend detection
+pub trait AddressDataSource: Send + Sync {
+    /// Current state of `address`, or `None` without an address index
+    fn snapshot(&self, address: &str) -> Option<AddressSnapshot>;
+
+    /// Address and value of an unspent output
+    fn prevout(&self, txid: &[u8; 32], vout: u32) -> Option<(String, u64)>;
+}
+
+/// Data source over the block database and, when enabled, the transaction
+/// index.
+pub struct ChainAddressData {
+    db: Arc<BlockchainDB>,
+    index: Option<Arc<TransactionIndexer>>,
+}
+
+impl ChainAddressData {
+    pub fn new(db: Arc<BlockchainDB>, index: Option<Arc<TransactionIndexer>>) -> Self {
+        Self { db, index }
+    }
+}
+
+impl AddressDataSource for ChainAddressData {
+    fn snapshot(&self, address: &str) -> Option<AddressSnapshot> {
+        let index = self.index.as_ref()?;
+        let mut txids = index.get_transactions_by_address(address, None, 0).ok()?;
+        txids.reverse();
+
+        let mut utxos = Vec::new();
+        let mut recent_transactions = Vec::new();
+        for txid in txids {
+            let height = index.get_transaction(&txid).ok().map(|t| t.location.height);
+            if recent_transactions.len() < SNAPSHOT_RECENT_TXS {
+                recent_transactions.push(AddressTxSummary {
+                    txid: hex::encode(txid),
+                    height,
+                });
+            }
+            let Ok(Some(tx)) = self.db.get_transaction(&txid) else {
+                continue;
+            };
+            for (vout, output) in tx.outputs().iter().enumerate() {
+                let vout = vout as u32;
+                if TransactionIndexer::extract_address_from_output(output).as_deref() == Some(address)
+                    && matches!(self.db.get_utxo(&txid, vout), Ok(Some(_)))
+                {
+                    utxos.push(AddressUtxo {
+                        txid: hex::encode(txid),
+                        vout,
+                        value: output.amount(),
+                        height,
+                    });
+                }
+            }
+        }
+
+        Some(AddressSnapshot {
+            address: address.to_string(),
+            utxos: Some(utxos),
+            recent_transactions: Some(recent_transactions),
+        })
+    }
+
+    fn prevout(&self, txid: &[u8; 32], vout: u32) -> Option<(String, u64)> {
+        let output = self.db.get_utxo(txid, vout).ok()??;
+        let address = TransactionIndexer::extract_address_from_output(&output)?;
+        Some((address, output.amount()))
+    }
+}
+
+/// Transport side of one client connection
+pub struct SubscriberConnection {
+    id: u64,
+    token: Option<String>,
+    sender: mpsc::UnboundedSender<ServerMessage>,
+}
+
+impl SubscriberConnection {
+    /// Resumption token of the connection's session, once it has one
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+struct Session {
+    addresses: HashSet<String>,
+    next_seq: u64,
+    buffer: VecDeque<(u64, AddressEvent)>,
+    /// Attached connection id and sender; `None` while disconnected
+    attached: Option<(u64, mpsc::UnboundedSender<ServerMessage>)>,
+    detached_at: Option<Instant>,
+}
+
+impl Session {
+    fn new() -> Self {
+        Self {
+            addresses: HashSet::new(),
+            next_seq: 1,
+            buffer: VecDeque::new(),
+            attached: None,
+            detached_at: None,
+        }
+    }
+
+    fn push(&mut self, event: AddressEvent) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        if self.buffer.len() >= RESUME_BUFFER_EVENTS {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back((seq, event.clone()));
+
+        let delivered = match &self.attached {
+            Some((_, sender)) => sender.send(ServerMessage::Event { seq, event }).is_ok(),
+            None => true,
+        };
+        if !delivered {
+            self.detach();
+        }
+    }
+
+    fn detach(&mut self) {
+        self.attached = None;
+        self.detached_at = Some(Instant::now());
+    }
+}
+
+#[derive(Default)]
+struct HubState {
+    next_connection: u64,
+    sessions: HashMap<String, Session>,
+    /// Outputs of watched addresses: outpoint -> (address, value)
+    watched_outputs: HashMap<([u8; 32], u32), (String, u64)>,
+    /// Unconfirmed transactions already reported: txid -> addresses touched
+    pending: HashMap<[u8; 32], HashSet<String>>,
+}
+
+impl HubState {
+    fn watched(&self, address: &str) -> bool {
+        self.sessions.values().any(|s| s.addresses.contains(address))
+    }
+
+    fn publish(&mut self, event: AddressEvent) {
+        let address = event.address().to_string();
+        for session in self.sessions.values_mut() {
+            if session.addresses.contains(&address) {
+                session.push(event.clone());
+            }
+        }
+    }
+
+    fn purge_expired(&mut self, now: Instant) {
+        self.sessions.retain(|_, s| match s.detached_at {
+            Some(at) => now.duration_since(at) < RESUME_WINDOW,
+            None => true,
+        });
+        let watched: HashSet<String> = self
+            .sessions
+            .values()
+            .flat_map(|s| s.addresses.iter().cloned())
+            .collect();
+        self.watched_outputs.retain(|_, (address, _)| watched.contains(address));
+        self.pending.retain(|_, addresses| {
+            addresses.retain(|a| watched.contains(a));
+            !addresses.is_empty()
+        });
+    }
+}
+
+/// Routes chain and mempool activity to address subscribers
+pub struct AddressSubscriptionHub {
+    source: Arc<dyn AddressDataSource>,
+    state: Mutex<HubState>,
+}
+
+impl AddressSubscriptionHub {
+    pub fn new(source: Arc<dyn AddressDataSource>) -> Self {
+        Self {
+            source,
+            state: Mutex::new(HubState::default()),
+        }
+    }
+
+    /// Register a new transport connection
+    pub fn connect(&self, sender: mpsc::UnboundedSender<ServerMessage>) -> SubscriberConnection {
+        let mut state = self.state.lock();
+        state.next_connection += 1;
+        SubscriberConnection {
+            id: state.next_connection,
+            token: None,
+            sender,
+        }
+    }
+
+    /// The transport closed. The session stays resumable for [`RESUME_WINDOW`].
+    pub fn disconnect(&self, conn: &SubscriberConnection) {
+        let Some(token) = &conn.token else { return };
+        let mut state = self.state.lock();
+        if let Some(session) = state.sessions.get_mut(token) {
+            if matches!(session.attached, Some((id, _)) if id == conn.id) {
+                session.detach();
+            }
+        }
+    }
+
+    /// Handle one text frame from a client
+    pub fn handle_text(&self, conn: &mut SubscriberConnection, text: &str) {
+        let reply = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::SubscribeAddresses(addresses)) => self.subscribe(conn, addresses),
+            Ok(ClientMessage::UnsubscribeAddresses(addresses)) => self.unsubscribe(conn, addresses),
+            Ok(ClientMessage::Resume { token, last_seq }) => self.resume(conn, token, last_seq),
+            Err(e) => Some(ServerMessage::Error {
+                code: error_codes::INVALID_MESSAGE,
+                message: format!("Invalid message: {}", e),
+            }),
+        };
+        if let Some(reply) = reply {
+            let _ = conn.sender.send(reply);
+        }
+    }
+
+    fn subscribe(&self, conn: &mut SubscriberConnection, addresses: Vec<String>) -> Option<ServerMessage> {
+        let mut state = self.state.lock();
+        state.purge_expired(Instant::now());
+
+        let token = match conn.token.clone().filter(|t| state.sessions.contains_key(t)) {
+            Some(token) => token,
+            None => {
+                let mut bytes = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut bytes);
+                let token = hex::encode(bytes);
+                let mut session = Session::new();
+                session.attached = Some((conn.id, conn.sender.clone()));
+                state.sessions.insert(token.clone(), session);
+                conn.token = Some(token.clone());
+                token
+            }
+        };
+
+        let current = state.sessions.get(&token).map(|s| s.addresses.clone()).unwrap_or_default();
+        let new: Vec<String> = addresses
+            .into_iter()
+            .filter(|a| !current.contains(a))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if current.len() + new.len() > MAX_ADDRESSES_PER_CONNECTION {
+            return Some(ServerMessage::Error {
+                code: error_codes::TOO_MANY_ADDRESSES,
+                message: format!(
+                    "At most {} addresses per connection",
+                    MAX_ADDRESSES_PER_CONNECTION
+                ),
+            });
+        }
+
+        let mut snapshot = Vec::with_capacity(new.len());
+        for address in &new {
+            let state_of = self.source.snapshot(address).unwrap_or_else(|| AddressSnapshot {
+                address: address.clone(),
+                utxos: None,
+                recent_transactions: None,
+            });
+            for utxo in state_of.utxos.iter().flatten() {
+                if let Ok(txid) = hex_to_txid(&utxo.txid) {
+                    state
+                        .watched_outputs
+                        .insert((txid, utxo.vout), (address.clone(), utxo.value));
+                }
+            }
+            snapshot.push(state_of);
+        }
+        if let Some(session) = state.sessions.get_mut(&token) {
+            session.addresses.extend(new);
+        }
+
+        Some(ServerMessage::Subscribed { token, snapshot })
+    }
+
+    fn unsubscribe(&self, conn: &mut SubscriberConnection, addresses: Vec<String>) -> Option<ServerMessage> {
+        let mut state = self.state.lock();
+        if let Some(session) = conn.token.as_ref().and_then(|t| state.sessions.get_mut(t)) {
+            for address in &addresses {
+                session.addresses.remove(address);
+            }
+        }
+        state.purge_expired(Instant::now());
+        Some(ServerMessage::Unsubscribed { addresses })
+    }
+
+    fn resume(&self, conn: &mut SubscriberConnection, token: String, last_seq: u64) -> Option<ServerMessage> {
+        let mut state = self.state.lock();
+        state.purge_expired(Instant::now());
+
+        let Some(session) = state.sessions.get_mut(&token) else {
+            return Some(ServerMessage::Error {
+                code: error_codes::UNKNOWN_TOKEN,
+                message: "Unknown or expired resumption token; subscribe again".to_string(),
+            });
+        };
+        let oldest = session.buffer.front().map(|(seq, _)| *seq).unwrap_or(session.next_seq);
+        if last_seq + 1 < oldest || last_seq >= session.next_seq {
+            state.sessions.remove(&token);
+            return Some(ServerMessage::Error {
+                code: error_codes::EVENTS_LOST,
+                message: "Missed events are no longer buffered; subscribe again".to_string(),
+            });
+        }
+
+        let missed: Vec<(u64, AddressEvent)> = session
+            .buffer
+            .iter()
+            .filter(|(seq, _)| *seq > last_seq)
+            .cloned()
+            .collect();
+        let _ = conn.sender.send(ServerMessage::Resumed {
+            token: token.clone(),
+            replayed: missed.len(),
+        });
+        for (seq, event) in missed {
+            let _ = conn.sender.send(ServerMessage::Event { seq, event });
+        }
+        session.attached = Some((conn.id, conn.sender.clone()));
+        session.detached_at = None;
+        conn.token = Some(token);
+        None
+    }
+
+    /// Route a node event to subscribers
+    pub fn process_event(&self, event: &NodeEvent) {
+        match event {
+            NodeEvent::MempoolTransaction(tx) => self.on_mempool_transaction(tx),
+            NodeEvent::BlockConnected(block) => self.on_block_connected(block),
+            NodeEvent::BlockDisconnected(block) => self.on_block_disconnected(block),
+            _ => {}
+        }
+    }
+
+    /// A transaction was accepted into the mempool
+    pub fn on_mempool_transaction(&self, tx: &Transaction) {
+        let mut state = self.state.lock();
+        let txid = tx.hash();
+        if state.pending.contains_key(&txid) {
+            return;
+        }
+        let touched = self.publish_transaction(&mut state, tx, None);
+        if !touched.is_empty() {
+            state.pending.insert(txid, touched);
+        }
+    }
+
+    /// A block was connected to the active chain
+    pub fn on_block_connected(&self, block: &Block) {
+        let mut state = self.state.lock();
+        let height = block.height();
+        let block_hash = hex::encode(block.hash());
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            match state.pending.remove(&txid) {
+                Some(addresses) => {
+                    for address in addresses {
+                        state.publish(AddressEvent::Confirmed {
+                            address,
+                            txid: hex::encode(txid),
+                            height,
+                            block_hash: block_hash.clone(),
+                        });
+                    }
+                }
+                None => {
+                    self.publish_transaction(&mut state, tx, Some(height));
+                }
+            }
+        }
+    }
+
+    /// A block was disconnected from the active chain (reorg or rollback).
+    /// Its transactions are unconfirmed again; those that return to the
+    /// mempool confirm later with a `confirmed` event.
+    pub fn on_block_disconnected(&self, block: &Block) {
+        let mut state = self.state.lock();
+        let height = block.height();
+        let block_hash = hex::encode(block.hash());
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            let mut touched = HashSet::new();
+            for (vout, output) in tx.outputs().iter().enumerate() {
+                if let Some(address) = TransactionIndexer::extract_address_from_output(output) {
+                    if state.watched(&address) {
+                        state
+                            .watched_outputs
+                            .insert((txid, vout as u32), (address.clone(), output.amount()));
+                        touched.insert(address);
+                    }
+                }
+            }
+            for input in tx.inputs() {
+                let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+                if let Some((address, _)) = state.watched_outputs.get(&outpoint) {
+                    touched.insert(address.clone());
+                }
+            }
+            for address in &touched {
+                state.publish(AddressEvent::Unconfirmed {
+                    address: address.clone(),
+                    txid: hex::encode(txid),
+                    height,
+                    block_hash: block_hash.clone(),
+                });
+            }
+            if !touched.is_empty() {
+                state.pending.insert(txid, touched);
+            }
+        }
+    }
+
+    /// Publish received/spent events for a transaction not seen before and
+    /// return the watched addresses it touches.
+    fn publish_transaction(&self, state: &mut HubState, tx: &Transaction, height: Option<u64>) -> HashSet<String> {
+        let txid = tx.hash();
+        let mut touched = HashSet::new();
+
+        for input in tx.inputs() {
+            let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+            let prevout = match state.watched_outputs.get(&outpoint) {
+                Some(prevout) => Some(prevout.clone()),
+                None => self.source.prevout(&outpoint.0, outpoint.1),
+            };
+            let Some((address, _)) = prevout else { continue };
+            if !state.watched(&address) {
+                continue;
+            }
+            state.publish(AddressEvent::Spent {
+                address: address.clone(),
+                txid: hex::encode(outpoint.0),
+                vout: outpoint.1,
+                spending_txid: hex::encode(txid),
+                height,
+            });
+            touched.insert(address);
+        }
+
+        for (vout, output) in tx.outputs().iter().enumerate() {
+            let Some(address) = TransactionIndexer::extract_address_from_output(output) else {
+                continue;
+            };
+            if !state.watched(&address) {
+                continue;
+            }
+            let vout = vout as u32;
+            state
+                .watched_outputs
+                .insert((txid, vout), (address.clone(), output.amount()));
+            state.publish(AddressEvent::Received {
+                address: address.clone(),
+                txid: hex::encode(txid),
+                vout,
+                value: output.amount(),
+                height,
+            });
+            touched.insert(address);
+        }
+
+        touched
+    }
+
+    /// Feed the hub from the node event bus until the bus closes
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<NodeEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.process_event(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Address subscriptions lagged; {} node events skipped", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn hex_to_txid(txid: &str) -> Result<[u8; 32], hex::FromHexError> {
+    let mut out = [0u8; 32];
+    hex::decode_to_slice(txid, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+
+    const ALICE: &[u8] = &[0xa1; 25];
+    const BOB: &[u8] = &[0xb0; 25];
+
+    struct FakeChain {
+        funding: Transaction,
+    }
+
+    impl AddressDataSource for FakeChain {
+        fn snapshot(&self, address: &str) -> Option<AddressSnapshot> {
+            let txid = hex::encode(self.funding.hash());
+            let utxos = if address == hex::encode(ALICE) {
+                vec![AddressUtxo { txid: txid.clone(), vout: 0, value: 5_000, height: Some(7) }]
+            } else {
+                Vec::new()
+            };
+            let recent_transactions = utxos
+                .iter()
+                .map(|u| AddressTxSummary { txid: u.txid.clone(), height: u.height })
+                .collect();
+            Some(AddressSnapshot {
+                address: address.to_string(),
+                utxos: Some(utxos),
+                recent_transactions: Some(recent_transactions),
+            })
+        }
+
+        fn prevout(&self, _txid: &[u8; 32], _vout: u32) -> Option<(String, u64)> {
+            None
+        }
+    }
+
+    fn funding_tx() -> Transaction {
+        Transaction::new(1, vec![TransactionInput::new([9; 32], 0, vec![], 0)], vec![TransactionOutput::new(5_000, ALICE.to_vec())], 0)
+    }
+
+    fn setup() -> (AddressSubscriptionHub, Transaction) {
+        let funding = funding_tx();
+        let hub = AddressSubscriptionHub::new(Arc::new(FakeChain { funding: funding.clone() }));
+        (hub, funding)
+    }
+
+    fn block_at(height: u64, txs: Vec<Transaction>) -> Block {
+        Block::new(BlockHeader::new_with_height(1, [0; 32], [0; 32], 0, 0, 0, height), txs)
+    }
+
+    fn drain(rx: &mut mpsc::UnboundedReceiver<ServerMessage>) -> Vec<ServerMessage> {
+        let mut out = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            out.push(msg);
+        }
+        out
+    }
+
+    fn subscribe(hub: &AddressSubscriptionHub, addresses: &[&[u8]]) -> (SubscriberConnection, mpsc::UnboundedReceiver<ServerMessage>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut conn = hub.connect(tx);
+        let list: Vec<String> = addresses.iter().map(hex::encode).collect();
+        hub.handle_text(&mut conn, &serde_json::json!({ "subscribe_addresses": list }).to_string());
+        (conn, rx)
+    }
+
+    fn spend_to_bob(funding: &Transaction) -> Transaction {
+        Transaction::new(1, vec![TransactionInput::new(funding.hash(), 0, vec![], 0)], vec![TransactionOutput::new(4_000, BOB.to_vec())], 0)
+    }
+
+    #[test]
+    fn subscribe_returns_snapshot_and_caps_addresses() {
+        let (hub, funding) = setup();
+        let (conn, mut rx) = subscribe(&hub, &[ALICE, BOB]);
+
+        let messages = drain(&mut rx);
+        let ServerMessage::Subscribed { token, snapshot } = &messages[0] else {
+            panic!("expected subscribed, got {:?}", messages);
+        };
+        assert_eq!(Some(token.as_str()), conn.token());
+        assert_eq!(snapshot.len(), 2);
+        let alice = snapshot.iter().find(|s| s.address == hex::encode(ALICE)).unwrap();
+        let utxos = alice.utxos.as_ref().unwrap();
+        assert_eq!(utxos.len(), 1);
+        assert_eq!(utxos[0].txid, hex::encode(funding.hash()));
+        assert_eq!(utxos[0].value, 5_000);
+        let bob = snapshot.iter().find(|s| s.address == hex::encode(BOB)).unwrap();
+        assert_eq!(bob.utxos.as_deref(), Some(&[][..]));
+
+        let too_many: Vec<String> = (0..=MAX_ADDRESSES_PER_CONNECTION).map(|i| format!("{:04x}", i)).collect();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut conn = hub.connect(tx);
+        hub.handle_text(&mut conn, &serde_json::json!({ "subscribe_addresses": too_many }).to_string());
+        assert!(matches!(
+            drain(&mut rx).as_slice(),
+            [ServerMessage::Error { code: error_codes::TOO_MANY_ADDRESSES, .. }]
+        ));
+    }
+
+    #[test]
+    fn pushes_mempool_arrival_then_confirmation() {
+        let (hub, funding) = setup();
+        let (_conn, mut rx) = subscribe(&hub, &[ALICE, BOB]);
+        drain(&mut rx);
+
+        let spend = spend_to_bob(&funding);
+        hub.process_event(&NodeEvent::MempoolTransaction(spend.clone()));
+        let events: Vec<AddressEvent> = drain(&mut rx)
+            .into_iter()
+            .filter_map(|m| match m {
+                ServerMessage::Event { event, .. } => Some(event),
+                _ => None,
+            })
+            .collect();
+        assert!(events.contains(&AddressEvent::Spent {
+            address: hex::encode(ALICE),
+            txid: hex::encode(funding.hash()),
+            vout: 0,
+            spending_txid: hex::encode(spend.hash()),
+            height: None,
+        }));
+        assert!(events.contains(&AddressEvent::Received {
+            address: hex::encode(BOB),
+            txid: hex::encode(spend.hash()),
+            vout: 0,
+            value: 4_000,
+            height: None,
+        }));
+
+        let block = block_at(8, vec![spend.clone()]);
+        hub.process_event(&NodeEvent::BlockConnected(block.clone()));
+        let confirmed: Vec<ServerMessage> = drain(&mut rx);
+        assert_eq!(confirmed.len(), 2);
+        for message in confirmed {
+            let ServerMessage::Event { event: AddressEvent::Confirmed { txid, height, block_hash, .. }, .. } = message else {
+                panic!("expected confirmation");
+            };
+            assert_eq!(txid, hex::encode(spend.hash()));
+            assert_eq!(height, 8);
+            assert_eq!(block_hash, hex::encode(block.hash()));
+        }
+    }
+
+    #[test]
+    fn reorg_emits_unconfirmed_correction() {
+        let (hub, funding) = setup();
+        let (_conn, mut rx) = subscribe(&hub, &[BOB]);
+        drain(&mut rx);
+
+        let spend = spend_to_bob(&funding);
+        let block = block_at(8, vec![spend.clone()]);
+        hub.process_event(&NodeEvent::BlockConnected(block.clone()));
+        assert!(matches!(
+            drain(&mut rx).as_slice(),
+            [ServerMessage::Event { event: AddressEvent::Received { height: Some(8), .. }, .. }]
+        ));
+
+        hub.process_event(&NodeEvent::BlockDisconnected(block.clone()));
+        assert_eq!(
+            drain(&mut rx),
+            vec![ServerMessage::Event {
+                seq: 2,
+                event: AddressEvent::Unconfirmed {
+                    address: hex::encode(BOB),
+                    txid: hex::encode(spend.hash()),
+                    height: 8,
+                    block_hash: hex::encode(block.hash()),
+                },
+            }]
+        );
+
+        // Mined again on the new branch
+        hub.process_event(&NodeEvent::BlockConnected(block_at(9, vec![spend])));
+        assert!(matches!(
+            drain(&mut rx).as_slice(),
+            [ServerMessage::Event { seq: 3, event: AddressEvent::Confirmed { height: 9, .. } }]
+        ));
+    }
+
+    #[test]
+    fn resumes_after_disconnect_with_missed_events() {
+        let (hub, funding) = setup();
+        let (conn, mut rx) = subscribe(&hub, &[BOB]);
+        drain(&mut rx);
+        let token = conn.token().unwrap().to_string();
+
+        let spend = spend_to_bob(&funding);
+        hub.process_event(&NodeEvent::MempoolTransaction(spend.clone()));
+        assert_eq!(drain(&mut rx).len(), 1);
+
+        hub.disconnect(&conn);
+        hub.process_event(&NodeEvent::BlockConnected(block_at(8, vec![spend])));
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut conn = hub.connect(tx);
+        hub.handle_text(&mut conn, &serde_json::json!({ "resume": { "token": token, "last_seq": 1 } }).to_string());
+        let messages = drain(&mut rx);
+        assert_eq!(messages[0], ServerMessage::Resumed { token: token.clone(), replayed: 1 });
+        assert!(matches!(
+            &messages[1],
+            ServerMessage::Event { seq: 2, event: AddressEvent::Confirmed { height: 8, .. } }
+        ));
+
+        // Live delivery continues on the new connection
+        hub.process_event(&NodeEvent::BlockDisconnected(block_at(8, vec![spend_to_bob(&funding)])));
+        assert!(matches!(drain(&mut rx).as_slice(), [ServerMessage::Event { seq: 3, .. }]));
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut conn = hub.connect(tx);
+        hub.handle_text(&mut conn, r#"{"resume": {"token": "nope", "last_seq": 0}}"#);
+        assert!(matches!(
+            drain(&mut rx).as_slice(),
+            [ServerMessage::Error { code: error_codes::UNKNOWN_TOKEN, .. }]
+        ));
+    }
+}
@@ -0,0 +1,264 @@
+//! Historical chart data
+//!
+//! Serves per-block metrics (difficulty, median fee rate, block interval,
+//! block weight, transaction count) over a height range, downsampled to a
+//! fixed number of points by bucket aggregation. Each bucket carries its
+//! min, max and average so short spikes survive downsampling. Heights with
+//! no stored stats are gaps: they never contribute zeros to a bucket, and a
+//! bucket with no data at all has no values.
+
+use crate::storage::{BlockStatsRecord, BlockchainDB, StorageError};
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// Default number of points when the request does not specify one
+pub const DEFAULT_CHART_POINTS: usize = 200;
+
+/// Maximum number of points a single request may ask for
+pub const MAX_CHART_POINTS: usize = 2_000;
+
+#[derive(Debug, Error)]
+pub enum ChartError {
+    #[error("Unknown chart metric '{0}'; expected one of difficulty, median-fee-rate, block-interval, block-weight, tx-count")]
+    UnknownMetric(String),
+
+    #[error("Points must be between 1 and {max}, got {points}")]
+    InvalidPoints { points: usize, max: usize },
+
+    #[error("Range start {from} is after range end {to}")]
+    InvalidRange { from: u64, to: u64 },
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+}
+
+/// Per-block metric that can be charted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartMetric {
+    Difficulty,
+    MedianFeeRate,
+    BlockInterval,
+    BlockWeight,
+    TxCount,
+}
+
+impl FromStr for ChartMetric {
+    type Err = ChartError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "difficulty" => Ok(Self::Difficulty),
+            "median-fee-rate" => Ok(Self::MedianFeeRate),
+            "block-interval" => Ok(Self::BlockInterval),
+            "block-weight" => Ok(Self::BlockWeight),
+            "tx-count" => Ok(Self::TxCount),
+            other => Err(ChartError::UnknownMetric(other.to_string())),
+        }
+    }
+}
+
+impl ChartMetric {
+    /// The metric's value for a block, if it has one.
+    pub fn value(self, record: &BlockStatsRecord) -> Option<f64> {
+        match self {
+            Self::Difficulty => Some(record.difficulty),
+            Self::MedianFeeRate => record.median_fee_rate,
+            Self::BlockInterval => record.interval.map(|v| v as f64),
+            Self::BlockWeight => Some(record.weight as f64),
+            Self::TxCount => Some(record.tx_count as f64),
+        }
+    }
+}
+
+/// One downsampled bucket covering `from_height..=to_height`
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ChartPoint {
+    pub from_height: u64,
+    pub to_height: u64,
+    /// Number of blocks in the bucket that had a value
+    pub samples: u64,
+    /// Smallest value; `None` when the bucket is a gap
+    pub min: Option<f64>,
+    /// Largest value; `None` when the bucket is a gap
+    pub max: Option<f64>,
+    /// Mean value; `None` when the bucket is a gap
+    pub avg: Option<f64>,
+}
+
+/// Downsampled series for one metric
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ChartResponse {
+    pub metric: ChartMetric,
+    pub from: u64,
+    pub to: u64,
+    /// Buckets in height order; fewer than requested only when the range
+    /// holds fewer blocks than points
+    pub points: Vec<ChartPoint>,
+}
+
+/// Check the request parameters before touching storage.
+pub fn validate(from: u64, to: u64, points: usize) -> Result<(), ChartError> {
+    if points == 0 || points > MAX_CHART_POINTS {
+        return Err(ChartError::InvalidPoints {
+            points,
+            max: MAX_CHART_POINTS,
+        });
+    }
+    if from > to {
+        return Err(ChartError::InvalidRange { from, to });
+    }
+    Ok(())
+}
+
+/// Aggregate `(height, value)` samples into buckets of equal height span.
+///
+/// The range `from..=to` is split into exactly `points` buckets, or one per
+/// height when the range is shorter. Samples outside the range are ignored
+/// and heights without a sample leave the bucket's statistics untouched.
+pub fn downsample(samples: &[(u64, f64)], from: u64, to: u64, points: usize) -> Vec<ChartPoint> {
+    let span = to - from + 1;
+    let buckets = (points as u64).min(span);
+    let bounds = |i: u64| from + (i as u128 * span as u128 / buckets as u128) as u64;
+
+    let mut out: Vec<ChartPoint> = (0..buckets)
+        .map(|i| ChartPoint {
+            from_height: bounds(i),
+            to_height: bounds(i + 1) - 1,
+            samples: 0,
+            min: None,
+            max: None,
+            avg: None,
+        })
+        .collect();
+    let mut sums = vec![0.0f64; out.len()];
+
+    for &(height, value) in samples {
+        if height < from || height > to {
+            continue;
+        }
+        let index = ((height - from) as u128 * buckets as u128 / span as u128) as usize;
+        let (Some(point), Some(sum)) = (out.get_mut(index), sums.get_mut(index)) else {
+            continue;
+        };
+        point.samples += 1;
+        point.min = Some(point.min.map_or(value, |m| m.min(value)));
+        point.max = Some(point.max.map_or(value, |m| m.max(value)));
+        *sum += value;
+    }
+
+    for (point, sum) in out.iter_mut().zip(sums) {
+        if point.samples > 0 {
+            point.avg = Some(sum / point.samples as f64);
+        }
+    }
+    out
+}
+
+/// Build a chart from stored per-block stats. `to` defaults to, and is
+/// capped at, the current tip; a range starting above the tip is empty.
+pub fn chart(
+    db: &BlockchainDB,
+    metric: ChartMetric,
+    from: u64,
+    to: Option<u64>,
+    points: usize,
+) -> Result<ChartResponse, ChartError> {
+    validate(from, to.unwrap_or(u64::MAX), points)?;
+    let tip = db.get_height()?;
+    let to = to.unwrap_or(tip).min(tip);
+    if from > to {
+        // Entirely above the tip: nothing to chart yet.
+        return Ok(ChartResponse {
+            metric,
+            from,
+            to,
+            points: Vec::new(),
+        });
+    }
+
+    let samples: Vec<(u64, f64)> = db
+        .get_block_stats_range(from, to)?
+        .iter()
+        .filter_map(|record| metric.value(record).map(|v| (record.height, v)))
+        .collect();
+
+    Ok(ChartResponse {
+        metric,
+        from,
+        to,
+        points: downsample(&samples, from, to, points),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downsampling_preserves_spike() {
+        let spike_height = 6_123;
+        let samples: Vec<(u64, f64)> = (0..10_000u64)
+            .map(|h| (h, if h == spike_height { 500.0 } else { 1.0 + (h % 7) as f64 }))
+            .collect();
+
+        let points = downsample(&samples, 0, 9_999, 200);
+        assert_eq!(points.len(), 200);
+        assert_eq!(points.first().unwrap().from_height, 0);
+        assert_eq!(points.last().unwrap().to_height, 9_999);
+        assert!(points.iter().all(|p| p.samples == 50));
+
+        let spiked: Vec<&ChartPoint> = points.iter().filter(|p| p.max == Some(500.0)).collect();
+        assert_eq!(spiked.len(), 1);
+        assert!((spiked[0].from_height..=spiked[0].to_height).contains(&spike_height));
+        // The average dilutes the spike; max keeps it.
+        assert!(spiked[0].avg.unwrap() < 20.0);
+        assert_eq!(spiked[0].min, Some(1.0));
+    }
+
+    #[test]
+    fn missing_heights_are_gaps_not_zeros() {
+        // Only heights 500.. have stats, as after pruning without stats.
+        let samples: Vec<(u64, f64)> = (500..1_000u64).map(|h| (h, 10.0)).collect();
+        let points = downsample(&samples, 0, 999, 10);
+
+        for point in &points[..5] {
+            assert_eq!(point.samples, 0);
+            assert_eq!((point.min, point.max, point.avg), (None, None, None));
+        }
+        for point in &points[5..] {
+            assert_eq!(point.min, Some(10.0));
+            assert_eq!(point.avg, Some(10.0));
+        }
+
+        // A partially covered bucket averages only what is present.
+        let points = downsample(&[(0, 4.0), (3, 8.0)], 0, 3, 1);
+        assert_eq!(points[0].samples, 2);
+        assert_eq!(points[0].avg, Some(6.0));
+
+        // Short ranges yield one point per height.
+        assert_eq!(downsample(&[], 10, 14, 200).len(), 5);
+    }
+
+    #[test]
+    fn rejects_bad_parameters() {
+        assert!(validate(0, 10, DEFAULT_CHART_POINTS).is_ok());
+        assert!(validate(5, 5, 1).is_ok());
+        assert!(matches!(validate(0, 10, 0), Err(ChartError::InvalidPoints { .. })));
+        assert!(matches!(
+            validate(0, 10, MAX_CHART_POINTS + 1),
+            Err(ChartError::InvalidPoints { points, max: MAX_CHART_POINTS }) if points == MAX_CHART_POINTS + 1
+        ));
+        assert!(matches!(
+            validate(11, 10, 10),
+            Err(ChartError::InvalidRange { from: 11, to: 10 })
+        ));
+        assert!(matches!(
+            "hashrate".parse::<ChartMetric>(),
+            Err(ChartError::UnknownMetric(_))
+        ));
+        assert_eq!("median-fee-rate".parse::<ChartMetric>().unwrap(), ChartMetric::MedianFeeRate);
+    }
+}
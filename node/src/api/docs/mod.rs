@@ -19,6 +19,17 @@ use utoipa::OpenApi;
         crate::api::routes::blockchain::get_block_by_hash,
         crate::api::routes::blockchain::get_transaction,
         crate::api::routes::blockchain::submit_transaction,
+        crate::api::routes::blockchain::get_block_rejections,
+        crate::api::routes::blockchain::get_rejection_trace,
+        crate::api::routes::blockchain::get_deployments,
+        crate::api::routes::blockchain::search_blockchain,
+        crate::api::routes::blockchain::get_chart,
+        crate::api::routes::blockchain::get_supply,
+
+        // Address routes
+        crate::api::routes::address::get_address_utxos,
+        crate::api::routes::address::get_address_balance,
+        crate::api::routes::address::get_address_history,
 
         // Mempool routes
         crate::api::routes::mempool::get_mempool_info,
@@ -27,6 +38,7 @@ use utoipa::OpenApi;
         crate::api::routes::mempool::submit_transaction,
         crate::api::routes::mempool::validate_transaction,
         crate::api::routes::mempool::get_fee_estimates,
+        crate::api::routes::mempool::get_mempool_rejections,
 
         // Network routes
         crate::api::routes::network::get_network_info,
@@ -47,6 +59,7 @@ use utoipa::OpenApi;
         crate::api::routes::mining::stop_mining,
         crate::api::routes::mining::get_mining_config,
         crate::api::routes::mining::update_mining_config,
+        crate::api::routes::mining::get_template_audit,
 
         // Environmental routes
         crate::api::routes::environmental::get_environmental_impact,
@@ -69,6 +82,9 @@ use utoipa::OpenApi;
         crate::api::routes::lightning::get_network_nodes,
         crate::api::routes::lightning::get_node_info,
         crate::api::routes::lightning::find_route,
+        crate::api::routes::lightning::export_graph,
+        crate::api::routes::lightning::export_mission_control,
+        crate::api::routes::lightning::import_mission_control,
 
         // Node routes
         crate::api::routes::node::get_node_info,
@@ -83,6 +99,28 @@ use utoipa::OpenApi;
         crate::api::routes::node::get_backup_info,
         crate::api::routes::node::restart_node,
         crate::api::routes::node::shutdown_node,
+        crate::api::routes::node::admin_rollback,
+        crate::api::routes::node::admin_invalidate_block,
+        crate::api::routes::node::admin_reconsider_block,
+        crate::api::routes::node::get_invalid_blocks,
+        crate::api::routes::node::admin_rotate_identity,
+        crate::api::routes::node::admin_verify_utxo_integrity,
+        crate::api::routes::node::get_storage_stats,
+        crate::api::routes::node::compact_storage,
+        crate::api::routes::node::list_jobs,
+        crate::api::routes::node::start_job,
+        crate::api::routes::node::get_job,
+        crate::api::routes::node::cancel_job,
+        crate::api::routes::node::create_webhook,
+        crate::api::routes::node::list_webhooks,
+        crate::api::routes::node::delete_webhook,
+        crate::api::routes::node::enable_webhook,
+        crate::api::routes::node::get_webhook_deliveries,
+        crate::api::routes::node::list_operator_messages,
+        crate::api::routes::node::send_operator_message,
+        crate::api::routes::node::list_api_key_usage,
+        crate::api::routes::node::get_api_key_usage,
+        crate::api::routes::node::get_my_usage,
         crate::api::routes::node::get_debug_info,
     ),
     components(
@@ -95,6 +133,7 @@ use utoipa::OpenApi;
             types::TransactionInfo,
             types::TransactionInput,
             types::TransactionOutput,
+            types::DataOutput,
             types::BlockchainInfo,
             types::TransactionSubmissionResponse,
 
@@ -105,15 +144,45 @@ use utoipa::OpenApi;
             types::MempoolTransactionSubmissionResponse,
             types::TransactionValidationResult,
             types::TransactionFees,
+            types::DeploymentInfo,
+            types::DeploymentStatistics,
+            types::RejectionTraceInfo,
+            types::TraceStepInfo,
+            crate::metrics::rejections::RejectionStats,
+            crate::metrics::rejections::RejectionRecord,
+            crate::network::sync_progress::SyncProgress,
+            crate::clock::ClockStatus,
+            crate::network::block_serving::BlockServingStats,
+            crate::api::search::SearchResponse,
+            crate::api::search::SearchMatch,
+            crate::api::search::SearchEntity,
+            crate::api::charts::ChartResponse,
+            crate::api::charts::ChartPoint,
+            crate::api::charts::ChartMetric,
+            crate::storage::SupplyAudit,
+            crate::storage::AddressUtxo,
+            crate::storage::AddressUtxos,
+            crate::storage::AddressBalance,
+            crate::storage::AddressTransaction,
+            crate::storage::AddressHistory,
+            crate::storage::UtxoIntegrityReport,
+            crate::storage::StorageStats,
+            crate::storage::TreeStats,
+            crate::storage::CompactionRecord,
+            crate::storage::UtxoStats,
+            crate::storage::ClassTotals,
             crate::api::routes::mempool::SubmitTxRequest,
 
             // Network
             types::NetworkInfo,
+            crate::network::nat::NatStatus,
+            crate::network::nat::MappingState,
             types::NetworkAddress,
             types::NetworkStats,
             types::PeerInfo,
             types::PeerConnectionStatus,
             types::BandwidthUsage,
+            types::PeerBandwidthUsage,
             types::PeerAddRequest,
             types::PeerAddResponse,
             types::NodeAddress,
@@ -128,6 +197,11 @@ use utoipa::OpenApi;
             types::MiningStatus,
             types::MiningConfiguration,
             crate::api::routes::mining::StartMiningRequest,
+            crate::mining::template_audit::TemplateAuditSummary,
+            crate::mining::template_audit::BlockAudit,
+            crate::mining::template_audit::MissingTransaction,
+            crate::mining::template_audit::MissingReason,
+            crate::mining::template_audit::MissingCounts,
 
             // Environmental
             types::EnvironmentalImpact,
@@ -161,6 +235,34 @@ use utoipa::OpenApi;
             types::NodeMetrics,
             types::DebugInfo,
             crate::api::routes::node::BackupRequest,
+            crate::api::routes::node::RollbackRequest,
+            crate::api::routes::node::BlockHashRequest,
+            crate::api::routes::node::ChainAdminResponse,
+            crate::api::routes::node::InvalidBlockEntry,
+            crate::api::routes::node::IdentityRotationResponse,
+            crate::api::routes::node::UtxoIntegrityRequest,
+            crate::api::jobs::JobInfo,
+            crate::api::jobs::JobKind,
+            crate::api::jobs::JobStatus,
+            crate::api::jobs::JobProgress,
+            crate::api::webhooks::WebhookSpec,
+            crate::api::webhooks::WebhookFilter,
+            crate::api::webhooks::WebhookInfo,
+            crate::api::webhooks::WebhookCreated,
+            crate::api::webhooks::WebhookEvent,
+            crate::api::webhooks::DeliveryRecord,
+            crate::api::webhooks::DeliveryStatus,
+            crate::network::operator_messages::OperatorMessage,
+            crate::network::operator_messages::MessageKind,
+            crate::api::routes::node::OperatorMessagesQuery,
+            crate::api::routes::node::SendOperatorMessageRequest,
+            crate::api::routes::node::SendOperatorMessageResponse,
+            crate::api::routes::node::DeliveriesQuery,
+            crate::api::usage::KeyUsage,
+            crate::api::usage::UsageCounts,
+            crate::api::usage::UsagePeriod,
+            crate::api::usage::QuotaStatus,
+            crate::api::routes::node::UsageQuery,
             crate::api::routes::node::LogsQuery,
             crate::api::routes::node::MetricsQuery,
 
@@ -240,6 +342,16 @@ The JSON-RPC API is available at `/rpc` by default.
 }
 ```
 
+Parameters are passed by position (an array) or by name (an object), as in
+Bitcoin Core; omitted or null trailing parameters take their defaults.
+
+## Batch Requests
+
+An array of request objects is answered with an array of responses in the
+same order. An entry that is not a valid request gets its own `-32600` error
+without failing the rest of the batch. Each entry counts against the rate
+limits separately.
+
 ## Response Format
 
 ```json
@@ -309,6 +421,11 @@ Or in case of an error:
 - `-32003`: Transaction error - Error in transaction processing
 - `-32004`: Wallet error - Error in wallet operations
 - `-32005`: Network error - Error in network operations
+- `-32006`: Rate limit exceeded - Too many requests from this client
+- `-32007`: Insufficient scope - The API key may not call this method
+
+`getblocktemplate` and `generate` return `-32001` until the node has caught up
+with its peers.
             "#;
 
             doc.to_string()
@@ -1,5 +1,5 @@
 use crate::api::routes::{
-    blockchain, environmental, faucet, lightning, mempool, mining, network, node,
+    address, blockchain, environmental, faucet, lightning, mempool, mining, network, node,
 };
 use crate::api::types;
 use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
@@ -30,6 +30,17 @@ impl Modify for SecurityAddon {
         blockchain::get_block_by_hash,
         blockchain::get_transaction,
         blockchain::submit_transaction,
+        blockchain::get_block_rejections,
+        blockchain::get_rejection_trace,
+        blockchain::get_deployments,
+        blockchain::search_blockchain,
+        blockchain::get_chart,
+        blockchain::get_supply,
+
+        // Address routes
+        address::get_address_utxos,
+        address::get_address_balance,
+        address::get_address_history,
 
         // Mempool routes
         mempool::get_mempool_info,
@@ -38,6 +49,7 @@ impl Modify for SecurityAddon {
         mempool::submit_transaction,
         mempool::validate_transaction,
         mempool::get_fee_estimates,
+        mempool::get_mempool_rejections,
 
         // Network routes
         network::get_network_info,
@@ -58,6 +70,7 @@ impl Modify for SecurityAddon {
         mining::stop_mining,
         mining::get_mining_config,
         mining::update_mining_config,
+        mining::get_template_audit,
 
         // Environmental routes
         environmental::get_environmental_impact,
@@ -80,6 +93,9 @@ impl Modify for SecurityAddon {
         lightning::get_network_nodes,
         lightning::get_node_info,
         lightning::find_route,
+        lightning::export_graph,
+        lightning::export_mission_control,
+        lightning::import_mission_control,
 
         // Node routes
         node::get_node_info,
@@ -94,6 +110,28 @@ impl Modify for SecurityAddon {
         node::get_backup_info,
         node::restart_node,
         node::shutdown_node,
+        node::admin_rollback,
+        node::admin_invalidate_block,
+        node::admin_reconsider_block,
+        node::get_invalid_blocks,
+        node::admin_rotate_identity,
+        node::admin_verify_utxo_integrity,
+        node::get_storage_stats,
+        node::compact_storage,
+        node::list_jobs,
+        node::start_job,
+        node::get_job,
+        node::cancel_job,
+        node::create_webhook,
+        node::list_webhooks,
+        node::delete_webhook,
+        node::enable_webhook,
+        node::get_webhook_deliveries,
+        node::list_operator_messages,
+        node::send_operator_message,
+        node::list_api_key_usage,
+        node::get_api_key_usage,
+        node::get_my_usage,
         node::get_debug_info,
 
         // Faucet routes
@@ -109,6 +147,7 @@ impl Modify for SecurityAddon {
             types::Transaction,
             types::TransactionInput,
             types::TransactionOutput,
+            types::DataOutput,
             types::BlockHeader,
             types::TransactionSubmissionResponse,
 
@@ -121,12 +160,42 @@ impl Modify for SecurityAddon {
             types::TransactionFees,
             mempool::SubmitTransactionRequest,
             mempool::ValidateTransactionRequest,
+            types::DeploymentInfo,
+            types::DeploymentStatistics,
+            types::RejectionTraceInfo,
+            types::TraceStepInfo,
+            crate::metrics::rejections::RejectionStats,
+            crate::metrics::rejections::RejectionRecord,
+            crate::network::sync_progress::SyncProgress,
+            crate::clock::ClockStatus,
+            crate::network::block_serving::BlockServingStats,
+            crate::api::search::SearchResponse,
+            crate::api::search::SearchMatch,
+            crate::api::search::SearchEntity,
+            crate::api::charts::ChartResponse,
+            crate::api::charts::ChartPoint,
+            crate::api::charts::ChartMetric,
+            crate::storage::SupplyAudit,
+            crate::storage::AddressUtxo,
+            crate::storage::AddressUtxos,
+            crate::storage::AddressBalance,
+            crate::storage::AddressTransaction,
+            crate::storage::AddressHistory,
+            crate::storage::UtxoIntegrityReport,
+            crate::storage::StorageStats,
+            crate::storage::TreeStats,
+            crate::storage::CompactionRecord,
+            crate::storage::UtxoStats,
+            crate::storage::ClassTotals,
 
             // Network types
             types::NetworkInfo,
+            crate::network::nat::NatStatus,
+            crate::network::nat::MappingState,
             types::PeerInfo,
             types::PeerConnectionStatus,
             types::BandwidthUsage,
+            types::PeerBandwidthUsage,
             types::PeerAddRequest,
             types::PeerAddResponse,
             types::NodeAddress,
@@ -141,6 +210,11 @@ impl Modify for SecurityAddon {
             types::MiningStatus,
             types::MiningConfiguration,
             mining::StartMiningRequest,
+            crate::mining::template_audit::TemplateAuditSummary,
+            crate::mining::template_audit::BlockAudit,
+            crate::mining::template_audit::MissingTransaction,
+            crate::mining::template_audit::MissingReason,
+            crate::mining::template_audit::MissingCounts,
 
             // Environmental types
             types::EnvironmentalImpact,
@@ -174,6 +248,34 @@ impl Modify for SecurityAddon {
             types::NodeMetrics,
             types::DebugInfo,
             node::BackupRequest,
+            node::RollbackRequest,
+            node::BlockHashRequest,
+            node::ChainAdminResponse,
+            node::InvalidBlockEntry,
+            node::IdentityRotationResponse,
+            node::UtxoIntegrityRequest,
+            crate::api::jobs::JobInfo,
+            crate::api::jobs::JobKind,
+            crate::api::jobs::JobStatus,
+            crate::api::jobs::JobProgress,
+            crate::api::webhooks::WebhookSpec,
+            crate::api::webhooks::WebhookFilter,
+            crate::api::webhooks::WebhookInfo,
+            crate::api::webhooks::WebhookCreated,
+            crate::api::webhooks::WebhookEvent,
+            crate::api::webhooks::DeliveryRecord,
+            crate::api::webhooks::DeliveryStatus,
+            crate::network::operator_messages::OperatorMessage,
+            crate::network::operator_messages::MessageKind,
+            node::OperatorMessagesQuery,
+            node::SendOperatorMessageRequest,
+            node::SendOperatorMessageResponse,
+            node::DeliveriesQuery,
+            crate::api::usage::KeyUsage,
+            crate::api::usage::UsageCounts,
+            crate::api::usage::UsagePeriod,
+            crate::api::usage::QuotaStatus,
+            node::UsageQuery,
 
             // Faucet types
             faucet::FaucetStatusResponse,
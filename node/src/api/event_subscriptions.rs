@@ -0,0 +1,737 @@
+//! Chain, mempool and peer event subscriptions
+//!
+//! Explorers and bots subscribe to topics over the `/ws` WebSocket endpoint
+//! instead of polling the REST API:
+//!
+//! ```text
+//! -> {"subscribe": {"topic": "new_transaction", "min_fee_rate": 10}}
+//! <- {"type": "subscribed", "subscription": 1, "topic": "new_transaction"}
+//! <- {"type": "event", "subscription": 1, "event": {"topic": "new_transaction", "data": {...}}}
+//! -> {"unsubscribe": 1}
+//! <- {"type": "unsubscribed", "subscription": 1}
+//! ```
+//!
+//! Topics are `new_block` (header summary), `new_transaction` (txid, fee
+//! rate, size), `mempool_removed`, `reorg` (old and new tip, depth) and
+//! `peers` (connections and disconnections). A connection holds at most
+//! [`MAX_SUBSCRIPTIONS_PER_CONNECTION`] subscriptions.
+//!
+//! Each connection has a queue of [`OUTBOUND_QUEUE_MESSAGES`]. A client that
+//! reads too slowly to keep it from filling is handled by the
+//! [`BackpressurePolicy`]: it is either disconnected, or its events are
+//! dropped until it catches up and then told how many it lost with a
+//! `lagged` message.
+
+use crate::events::{MempoolRemovalReason, NodeEvent};
+use crate::mempool::TransactionPool;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::Transaction;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc};
+
+/// Most subscriptions a single connection may hold
+pub const MAX_SUBSCRIPTIONS_PER_CONNECTION: usize = 16;
+
+/// Messages queued per connection before backpressure applies
+pub const OUTBOUND_QUEUE_MESSAGES: usize = 512;
+
+/// Error codes sent in `error` messages
+pub mod error_codes {
+    pub const INVALID_MESSAGE: i32 = 1;
+    pub const TOO_MANY_SUBSCRIPTIONS: i32 = 2;
+    pub const UNKNOWN_SUBSCRIPTION: i32 = 3;
+}
+
+/// What happens to a connection whose queue is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackpressurePolicy {
+    /// Close the connection; the client reconnects and resubscribes
+    #[default]
+    Disconnect,
+    /// Drop the client's events until its queue drains, then send `lagged`
+    DropEvents,
+}
+
+/// Subscription topic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topic {
+    NewBlock,
+    NewTransaction,
+    MempoolRemoved,
+    Reorg,
+    Peers,
+}
+
+/// A subscription request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscribeRequest {
+    pub topic: Topic,
+    /// `new_transaction` only: skip transactions paying less (novas per
+    /// byte)
+    #[serde(default)]
+    pub min_fee_rate: Option<u64>,
+}
+
+/// Message sent by a client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe(SubscribeRequest),
+    Unsubscribe(u64),
+}
+
+/// Message pushed to a client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed {
+        subscription: u64,
+        topic: Topic,
+    },
+    Unsubscribed {
+        subscription: u64,
+    },
+    Event {
+        subscription: u64,
+        event: TopicEvent,
+    },
+    /// Events were dropped because the client read too slowly
+    Lagged {
+        dropped: u64,
+    },
+    Error {
+        code: i32,
+        message: String,
+    },
+}
+
+/// An event on a topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "topic", content = "data", rename_all = "snake_case")]
+pub enum TopicEvent {
+    NewBlock(BlockSummary),
+    NewTransaction {
+        txid: String,
+        /// Novas per byte; `None` when the transaction left the mempool
+        /// before the event was routed
+        fee_rate: Option<u64>,
+        size: usize,
+    },
+    MempoolRemoved {
+        txid: String,
+        reason: MempoolRemovalReason,
+    },
+    /// The active chain switched branches. `new_tip` is the first block
+    /// connected on the new branch; any further ones follow as `new_block`.
+    Reorg {
+        old_tip: TipSummary,
+        new_tip: TipSummary,
+        depth: u64,
+    },
+    Peers(PeerEvent),
+}
+
+impl TopicEvent {
+    pub fn topic(&self) -> Topic {
+        match self {
+            TopicEvent::NewBlock(_) => Topic::NewBlock,
+            TopicEvent::NewTransaction { .. } => Topic::NewTransaction,
+            TopicEvent::MempoolRemoved { .. } => Topic::MempoolRemoved,
+            TopicEvent::Reorg { .. } => Topic::Reorg,
+            TopicEvent::Peers(_) => Topic::Peers,
+        }
+    }
+}
+
+/// Header fields of a connected block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockSummary {
+    pub hash: String,
+    pub height: u64,
+    pub prev_hash: String,
+    pub merkle_root: String,
+    pub timestamp: u64,
+    pub bits: u32,
+    pub nonce: u32,
+    pub tx_count: usize,
+    pub size: usize,
+}
+
+impl BlockSummary {
+    fn of(block: &Block) -> Self {
+        Self {
+            hash: hex::encode(block.hash()),
+            height: block.height(),
+            prev_hash: hex::encode(block.prev_block_hash()),
+            merkle_root: hex::encode(block.merkle_root()),
+            timestamp: block.timestamp(),
+            bits: block.header().bits(),
+            nonce: block.nonce(),
+            tx_count: block.transactions().len(),
+            size: block.size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TipSummary {
+    pub hash: String,
+    pub height: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PeerEvent {
+    Connected {
+        peer_id: String,
+        address: Option<String>,
+        inbound: bool,
+    },
+    Disconnected {
+        peer_id: String,
+    },
+}
+
+/// Fee rates of mempool transactions
+pub trait FeeRateSource: Send + Sync {
+    fn fee_rate(&self, txid: &[u8; 32]) -> Option<u64>;
+}
+
+impl FeeRateSource for TransactionPool {
+    fn fee_rate(&self, txid: &[u8; 32]) -> Option<u64> {
+        self.get_fee_rate(txid)
+    }
+}
+
+/// Transport side of one client connection
+pub struct SubscriberConnection {
+    id: u64,
+}
+
+struct Subscription {
+    topic: Topic,
+    min_fee_rate: Option<u64>,
+}
+
+impl Subscription {
+    fn wants(&self, event: &TopicEvent) -> bool {
+        if event.topic() != self.topic {
+            return false;
+        }
+        match (self.min_fee_rate, event) {
+            (Some(min), TopicEvent::NewTransaction { fee_rate, .. }) => {
+                fee_rate.is_some_and(|rate| rate >= min)
+            }
+            _ => true,
+        }
+    }
+}
+
+struct Connection {
+    sender: mpsc::Sender<ServerMessage>,
+    next_subscription: u64,
+    subscriptions: HashMap<u64, Subscription>,
+    /// Events dropped since the client last kept up
+    dropped: u64,
+}
+
+impl Connection {
+    /// Queue a message; `false` when the connection must be closed
+    fn deliver(&mut self, message: ServerMessage, policy: BackpressurePolicy) -> bool {
+        if self.dropped > 0 {
+            let notice = ServerMessage::Lagged {
+                dropped: self.dropped,
+            };
+            match self.sender.try_send(notice) {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return true;
+                }
+                Err(TrySendError::Closed(_)) => return false,
+            }
+        }
+        match self.sender.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => match policy {
+                BackpressurePolicy::Disconnect => false,
+                BackpressurePolicy::DropEvents => {
+                    self.dropped += 1;
+                    true
+                }
+            },
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+/// Blocks disconnected since the chain last advanced
+struct PendingReorg {
+    old_tip: TipSummary,
+    depth: u64,
+}
+
+#[derive(Default)]
+struct HubState {
+    next_connection: u64,
+    connections: HashMap<u64, Connection>,
+    tip: Option<([u8; 32], u64)>,
+    reorg: Option<PendingReorg>,
+}
+
+/// Routes node events to topic subscribers
+pub struct EventSubscriptionHub {
+    fees: Arc<dyn FeeRateSource>,
+    policy: BackpressurePolicy,
+    state: Mutex<HubState>,
+}
+
+impl EventSubscriptionHub {
+    pub fn new(fees: Arc<dyn FeeRateSource>, policy: BackpressurePolicy) -> Self {
+        Self {
+            fees,
+            policy,
+            state: Mutex::new(HubState::default()),
+        }
+    }
+
+    /// Register a new transport connection. Events are queued on a channel
+    /// of [`OUTBOUND_QUEUE_MESSAGES`]; it closes if the hub drops the
+    /// connection under [`BackpressurePolicy::Disconnect`].
+    pub fn connect(&self) -> (SubscriberConnection, mpsc::Receiver<ServerMessage>) {
+        let (sender, receiver) = mpsc::channel(OUTBOUND_QUEUE_MESSAGES);
+        let mut state = self.state.lock();
+        state.next_connection += 1;
+        let id = state.next_connection;
+        state.connections.insert(
+            id,
+            Connection {
+                sender,
+                next_subscription: 1,
+                subscriptions: HashMap::new(),
+                dropped: 0,
+            },
+        );
+        (SubscriberConnection { id }, receiver)
+    }
+
+    /// The transport closed
+    pub fn disconnect(&self, conn: &SubscriberConnection) {
+        self.state.lock().connections.remove(&conn.id);
+    }
+
+    /// Handle one text frame from a client and return the reply
+    pub fn handle_text(&self, conn: &SubscriberConnection, text: &str) -> ServerMessage {
+        let mut state = self.state.lock();
+        let Some(connection) = state.connections.get_mut(&conn.id) else {
+            return ServerMessage::Error {
+                code: error_codes::INVALID_MESSAGE,
+                message: "Connection closed".to_string(),
+            };
+        };
+        match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::Subscribe(request)) => subscribe(connection, request),
+            Ok(ClientMessage::Unsubscribe(subscription)) => {
+                match connection.subscriptions.remove(&subscription) {
+                    Some(_) => ServerMessage::Unsubscribed { subscription },
+                    None => ServerMessage::Error {
+                        code: error_codes::UNKNOWN_SUBSCRIPTION,
+                        message: format!("No subscription {}", subscription),
+                    },
+                }
+            }
+            Err(e) => ServerMessage::Error {
+                code: error_codes::INVALID_MESSAGE,
+                message: format!("Invalid message: {}", e),
+            },
+        }
+    }
+
+    /// Route a node event to subscribers
+    pub fn process_event(&self, event: &NodeEvent) {
+        let mut state = self.state.lock();
+        match event {
+            NodeEvent::MempoolTransaction(tx) => {
+                let event = self.transaction_event(tx);
+                self.publish(&mut state, event);
+            }
+            NodeEvent::MempoolRemoved { txid, reason } => {
+                let event = TopicEvent::MempoolRemoved {
+                    txid: hex::encode(txid),
+                    reason: *reason,
+                };
+                self.publish(&mut state, event);
+            }
+            NodeEvent::BlockConnected(block) => self.on_block_connected(&mut state, block),
+            NodeEvent::BlockDisconnected(block) => on_block_disconnected(&mut state, block),
+            NodeEvent::PeerConnected {
+                peer_id,
+                address,
+                inbound,
+            } => {
+                let event = TopicEvent::Peers(PeerEvent::Connected {
+                    peer_id: peer_id.clone(),
+                    address: address.clone(),
+                    inbound: *inbound,
+                });
+                self.publish(&mut state, event);
+            }
+            NodeEvent::PeerDisconnected { peer_id } => {
+                let event = TopicEvent::Peers(PeerEvent::Disconnected {
+                    peer_id: peer_id.clone(),
+                });
+                self.publish(&mut state, event);
+            }
+            _ => {}
+        }
+    }
+
+    fn transaction_event(&self, tx: &Transaction) -> TopicEvent {
+        let txid = tx.hash();
+        TopicEvent::NewTransaction {
+            txid: hex::encode(txid),
+            fee_rate: self.fees.fee_rate(&txid),
+            size: tx.calculate_size(),
+        }
+    }
+
+    fn on_block_connected(&self, state: &mut HubState, block: &Block) {
+        let hash = block.hash();
+        let height = block.height();
+        let new_tip = TipSummary {
+            hash: hex::encode(hash),
+            height,
+        };
+
+        // Disconnections announced the switch; otherwise a block that does
+        // not extend the tip replaced the blocks from its height up
+        let reorg = match (state.reorg.take(), state.tip) {
+            (Some(pending), _) => Some((pending.old_tip, pending.depth)),
+            (None, Some((tip_hash, tip_height)))
+                if *block.prev_block_hash() != tip_hash && height <= tip_height =>
+            {
+                let old_tip = TipSummary {
+                    hash: hex::encode(tip_hash),
+                    height: tip_height,
+                };
+                Some((old_tip, tip_height - height + 1))
+            }
+            _ => None,
+        };
+        if let Some((old_tip, depth)) = reorg {
+            let event = TopicEvent::Reorg {
+                old_tip,
+                new_tip,
+                depth,
+            };
+            self.publish(state, event);
+        }
+
+        state.tip = Some((hash, height));
+        self.publish(state, TopicEvent::NewBlock(BlockSummary::of(block)));
+    }
+
+    fn publish(&self, state: &mut HubState, event: TopicEvent) {
+        let policy = self.policy;
+        state.connections.retain(|_, connection| {
+            let matching: Vec<u64> = connection
+                .subscriptions
+                .iter()
+                .filter(|(_, subscription)| subscription.wants(&event))
+                .map(|(id, _)| *id)
+                .collect();
+            matching.into_iter().all(|subscription| {
+                let message = ServerMessage::Event {
+                    subscription,
+                    event: event.clone(),
+                };
+                connection.deliver(message, policy)
+            })
+        });
+    }
+
+    /// Feed the hub from the node event bus until the bus closes
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<NodeEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.process_event(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(
+                        "Event subscriptions lagged; {} node events skipped",
+                        skipped
+                    );
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn subscribe(connection: &mut Connection, request: SubscribeRequest) -> ServerMessage {
+    if request.min_fee_rate.is_some() && request.topic != Topic::NewTransaction {
+        return ServerMessage::Error {
+            code: error_codes::INVALID_MESSAGE,
+            message: "min_fee_rate only applies to new_transaction".to_string(),
+        };
+    }
+    if connection.subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_CONNECTION {
+        return ServerMessage::Error {
+            code: error_codes::TOO_MANY_SUBSCRIPTIONS,
+            message: format!(
+                "At most {} subscriptions per connection",
+                MAX_SUBSCRIPTIONS_PER_CONNECTION
+            ),
+        };
+    }
+    let subscription = connection.next_subscription;
+    connection.next_subscription += 1;
+    connection.subscriptions.insert(
+        subscription,
+        Subscription {
+            topic: request.topic,
+            min_fee_rate: request.min_fee_rate,
+        },
+    );
+    ServerMessage::Subscribed {
+        subscription,
+        topic: request.topic,
+    }
+}
+
+/// A block left the active chain: remember the tip it had before the
+/// first disconnection until the chain advances again
+fn on_block_disconnected(state: &mut HubState, block: &Block) {
+    let pending = state.reorg.get_or_insert_with(|| PendingReorg {
+        old_tip: TipSummary {
+            hash: hex::encode(block.hash()),
+            height: block.height(),
+        },
+        depth: 0,
+    });
+    pending.depth += 1;
+    state.tip = Some((*block.prev_block_hash(), block.height().saturating_sub(1)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+
+    struct FixedFees(u64);
+
+    impl FeeRateSource for FixedFees {
+        fn fee_rate(&self, _txid: &[u8; 32]) -> Option<u64> {
+            Some(self.0)
+        }
+    }
+
+    fn hub(policy: BackpressurePolicy) -> EventSubscriptionHub {
+        EventSubscriptionHub::new(Arc::new(FixedFees(5)), policy)
+    }
+
+    fn block(prev: [u8; 32], height: u64, nonce: u32) -> Block {
+        let header = BlockHeader::new_with_height(1, prev, [0; 32], 0, 0x207f_ffff, nonce, height);
+        Block::new(header, vec![Transaction::new_coinbase()])
+    }
+
+    fn subscribe_to(
+        hub: &EventSubscriptionHub,
+        conn: &SubscriberConnection,
+        request: serde_json::Value,
+    ) -> ServerMessage {
+        hub.handle_text(
+            conn,
+            &serde_json::json!({ "subscribe": request }).to_string(),
+        )
+    }
+
+    fn drain(rx: &mut mpsc::Receiver<ServerMessage>) -> Vec<ServerMessage> {
+        let mut out = Vec::new();
+        while let Ok(message) = rx.try_recv() {
+            out.push(message);
+        }
+        out
+    }
+
+    fn events(rx: &mut mpsc::Receiver<ServerMessage>) -> Vec<TopicEvent> {
+        drain(rx)
+            .into_iter()
+            .filter_map(|message| match message {
+                ServerMessage::Event { event, .. } => Some(event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn subscriptions_are_limited_and_filtered() {
+        let hub = hub(BackpressurePolicy::Disconnect);
+        let (conn, mut rx) = hub.connect();
+
+        let reply = subscribe_to(
+            &hub,
+            &conn,
+            serde_json::json!({ "topic": "new_transaction" }),
+        );
+        assert_eq!(
+            reply,
+            ServerMessage::Subscribed {
+                subscription: 1,
+                topic: Topic::NewTransaction
+            }
+        );
+        let filtered = serde_json::json!({ "topic": "new_transaction", "min_fee_rate": 10 });
+        assert!(matches!(
+            subscribe_to(&hub, &conn, filtered),
+            ServerMessage::Subscribed {
+                subscription: 2,
+                ..
+            }
+        ));
+        let misplaced = serde_json::json!({ "topic": "new_block", "min_fee_rate": 10 });
+        assert!(matches!(
+            subscribe_to(&hub, &conn, misplaced),
+            ServerMessage::Error {
+                code: error_codes::INVALID_MESSAGE,
+                ..
+            }
+        ));
+
+        let tx = Transaction::new(
+            1,
+            vec![TransactionInput::new([9; 32], 0, vec![], 0)],
+            vec![TransactionOutput::new(5_000, vec![0xa1; 25])],
+            0,
+        );
+        hub.process_event(&NodeEvent::MempoolTransaction(tx.clone()));
+        assert_eq!(
+            drain(&mut rx),
+            vec![ServerMessage::Event {
+                subscription: 1,
+                event: TopicEvent::NewTransaction {
+                    txid: hex::encode(tx.hash()),
+                    fee_rate: Some(5),
+                    size: tx.calculate_size(),
+                },
+            }]
+        );
+
+        assert_eq!(
+            hub.handle_text(&conn, r#"{"unsubscribe": 1}"#),
+            ServerMessage::Unsubscribed { subscription: 1 }
+        );
+        assert!(matches!(
+            hub.handle_text(&conn, r#"{"unsubscribe": 1}"#),
+            ServerMessage::Error {
+                code: error_codes::UNKNOWN_SUBSCRIPTION,
+                ..
+            }
+        ));
+        for _ in 1..MAX_SUBSCRIPTIONS_PER_CONNECTION {
+            let reply = subscribe_to(&hub, &conn, serde_json::json!({ "topic": "peers" }));
+            assert!(matches!(reply, ServerMessage::Subscribed { .. }));
+        }
+        assert!(matches!(
+            subscribe_to(&hub, &conn, serde_json::json!({ "topic": "peers" })),
+            ServerMessage::Error {
+                code: error_codes::TOO_MANY_SUBSCRIPTIONS,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reorgs_are_reported_before_the_new_block() {
+        let hub = hub(BackpressurePolicy::Disconnect);
+        let (conn, mut rx) = hub.connect();
+        subscribe_to(&hub, &conn, serde_json::json!({ "topic": "reorg" }));
+        subscribe_to(&hub, &conn, serde_json::json!({ "topic": "new_block" }));
+
+        let a1 = block([0; 32], 1, 1);
+        let a2 = block(a1.hash(), 2, 2);
+        hub.process_event(&NodeEvent::BlockConnected(a1.clone()));
+        hub.process_event(&NodeEvent::BlockConnected(a2.clone()));
+        let connected = events(&mut rx);
+        assert_eq!(connected.len(), 2);
+        assert_eq!(connected[1], TopicEvent::NewBlock(BlockSummary::of(&a2)));
+
+        // Announced by disconnections
+        let b2 = block(a1.hash(), 2, 3);
+        hub.process_event(&NodeEvent::BlockDisconnected(a2.clone()));
+        hub.process_event(&NodeEvent::BlockConnected(b2.clone()));
+        let switched = events(&mut rx);
+        assert_eq!(
+            switched,
+            vec![
+                TopicEvent::Reorg {
+                    old_tip: TipSummary {
+                        hash: hex::encode(a2.hash()),
+                        height: 2
+                    },
+                    new_tip: TipSummary {
+                        hash: hex::encode(b2.hash()),
+                        height: 2
+                    },
+                    depth: 1,
+                },
+                TopicEvent::NewBlock(BlockSummary::of(&b2)),
+            ]
+        );
+
+        // A block replacing the tip without disconnections
+        let c1 = block([0; 32], 1, 4);
+        hub.process_event(&NodeEvent::BlockConnected(c1.clone()));
+        assert!(matches!(
+            events(&mut rx).as_slice(),
+            [TopicEvent::Reorg { depth: 2, .. }, TopicEvent::NewBlock(_)]
+        ));
+    }
+
+    #[test]
+    fn slow_clients_are_disconnected_or_told_what_they_missed() {
+        let peer = |n: usize| NodeEvent::PeerDisconnected {
+            peer_id: format!("peer-{}", n),
+        };
+
+        let strict = hub(BackpressurePolicy::Disconnect);
+        let (conn, mut rx) = strict.connect();
+        subscribe_to(&strict, &conn, serde_json::json!({ "topic": "peers" }));
+        for n in 0..=OUTBOUND_QUEUE_MESSAGES {
+            strict.process_event(&peer(n));
+        }
+        assert_eq!(drain(&mut rx).len(), OUTBOUND_QUEUE_MESSAGES);
+        assert!(matches!(
+            rx.try_recv(),
+            Err(mpsc::error::TryRecvError::Disconnected)
+        ));
+
+        let lenient = hub(BackpressurePolicy::DropEvents);
+        let (conn, mut rx) = lenient.connect();
+        subscribe_to(&lenient, &conn, serde_json::json!({ "topic": "peers" }));
+        for n in 0..OUTBOUND_QUEUE_MESSAGES + 3 {
+            lenient.process_event(&peer(n));
+        }
+        assert_eq!(drain(&mut rx).len(), OUTBOUND_QUEUE_MESSAGES);
+        lenient.process_event(&peer(0));
+        assert_eq!(
+            drain(&mut rx),
+            vec![
+                ServerMessage::Lagged { dropped: 3 },
+                ServerMessage::Event {
+                    subscription: 1,
+                    event: TopicEvent::Peers(PeerEvent::Disconnected {
+                        peer_id: "peer-0".to_string()
+                    }),
+                },
+            ]
+        );
+    }
+}
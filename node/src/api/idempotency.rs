@@ -0,0 +1,337 @@
+//! Idempotent request handling
+//!
+//! Payment services retry transaction submissions after timeouts. When such a
+//! request carries an `Idempotency-Key` header, the first execution's response
+//! is remembered for the retention window and replayed for every retry with
+//! the same key — whatever happened to the transaction since. Requests racing
+//! on one key are serialized, so the operation runs at most once.
+//!
+//! Keys are scoped by the caller's API key, and a key reused for a different
+//! request (other endpoint or body) is refused rather than replayed.
+
+use crate::api::error::ApiError;
+use actix_web::http::header::HeaderMap;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Request header carrying the client's idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Response header set on replayed responses
+pub const IDEMPOTENT_REPLAY_HEADER: &str = "Idempotent-Replayed";
+
+/// Longest accepted idempotency key
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdempotencyError {
+    #[error("Idempotency key must be 1 to {MAX_IDEMPOTENCY_KEY_LEN} visible ASCII characters")]
+    InvalidKey,
+
+    #[error("Idempotency key was already used for a different request")]
+    KeyReused,
+}
+
+/// Response remembered for a key: HTTP status and JSON body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl StoredResponse {
+    /// Capture the outcome of a REST handler
+    pub fn from_result<T: Serialize>(result: Result<T, ApiError>) -> Self {
+        match result {
+            Ok(value) => Self {
+                status: 200,
+                body: serde_json::to_value(value).unwrap_or_default(),
+            },
+            Err(err) => Self {
+                status: err.status,
+                body: serde_json::to_value(&err).unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Render the stored response, marking replays with a header
+    pub fn to_http_response(&self, replayed: bool) -> HttpResponse {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut builder = HttpResponse::build(status);
+        if replayed {
+            builder.insert_header((IDEMPOTENT_REPLAY_HEADER, "true"));
+        }
+        builder.json(&self.body)
+    }
+}
+
+struct Entry {
+    created: Instant,
+    fingerprint: [u8; 32],
+    response: tokio::sync::Mutex<Option<StoredResponse>>,
+}
+
+/// Bounded, expiring store of responses keyed by (scope, idempotency key)
+pub struct IdempotencyStore {
+    retention: Duration,
+    max_keys: usize,
+    entries: Mutex<HashMap<(String, String), Arc<Entry>>>,
+}
+
+impl IdempotencyStore {
+    pub fn new(retention: Duration, max_keys: usize) -> Self {
+        Self {
+            retention,
+            max_keys: max_keys.max(1),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Number of remembered keys, including expired ones not yet purged
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Run `operation` once per (scope, key) within the retention window.
+    ///
+    /// Returns the response and whether it was replayed. A concurrent request
+    /// with the same key waits for the first to finish and replays its
+    /// response; if the first request was dropped mid-flight, the waiter runs
+    /// the operation itself.
+    pub async fn execute<F, Fut>(
+        &self,
+        scope: &str,
+        key: &str,
+        fingerprint: [u8; 32],
+        operation: F,
+    ) -> Result<(StoredResponse, bool), IdempotencyError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = StoredResponse>,
+    {
+        validate_key(key)?;
+        let entry = self.entry(scope, key, fingerprint);
+        if entry.fingerprint != fingerprint {
+            return Err(IdempotencyError::KeyReused);
+        }
+
+        let mut slot = entry.response.lock().await;
+        if let Some(response) = slot.as_ref() {
+            return Ok((response.clone(), true));
+        }
+        let response = operation().await;
+        *slot = Some(response.clone());
+        Ok((response, false))
+    }
+
+    /// Fetch the live entry for a key or create one, purging expired entries
+    /// and evicting the oldest when full.
+    fn entry(&self, scope: &str, key: &str, fingerprint: [u8; 32]) -> Arc<Entry> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock();
+        entries.retain(|_, e| now.duration_since(e.created) < self.retention);
+
+        let id = (scope.to_string(), key.to_string());
+        if let Some(entry) = entries.get(&id) {
+            return Arc::clone(entry);
+        }
+
+        while entries.len() >= self.max_keys {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.created)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        let entry = Arc::new(Entry {
+            created: now,
+            fingerprint,
+            response: tokio::sync::Mutex::new(None),
+        });
+        entries.insert(id, Arc::clone(&entry));
+        entry
+    }
+}
+
+fn validate_key(key: &str) -> Result<(), IdempotencyError> {
+    if key.is_empty()
+        || key.len() > MAX_IDEMPOTENCY_KEY_LEN
+        || !key.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return Err(IdempotencyError::InvalidKey);
+    }
+    Ok(())
+}
+
+/// The request's idempotency key, if it sent one. A header that is not
+/// valid text yields an empty key, which `execute` rejects.
+pub fn idempotency_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .map(|v| v.to_str().unwrap_or_default().trim().to_string())
+}
+
+/// Scope for idempotency keys: a digest of the caller's API key, so keys from
+/// different API clients never collide and raw credentials are never stored.
+/// Unauthenticated callers share one scope.
+pub fn request_scope(req: &HttpRequest) -> String {
+    match req
+        .headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(auth) => {
+            let api_key = auth.strip_prefix("Bearer ").unwrap_or(auth);
+            hex::encode(&Sha256::digest(api_key.as_bytes())[..16])
+        }
+        None => String::new(),
+    }
+}
+
+/// Fingerprint of a request: the operation name and its canonical body
+pub fn request_fingerprint(operation: &str, body: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(operation.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(body);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn submitted(txid: &str) -> StoredResponse {
+        StoredResponse {
+            status: 200,
+            body: serde_json::json!({ "txid": txid, "accepted": true }),
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicate_submit_replays_without_resubmitting() {
+        let store = IdempotencyStore::new(Duration::from_secs(60), 100);
+        let mempool = Mutex::new(Vec::new());
+        let fingerprint = request_fingerprint("submit", b"raw-tx");
+
+        let submit = || async {
+            mempool.lock().push("tx-1".to_string());
+            submitted("tx-1")
+        };
+        let (first, replayed) = store.execute("client", "pay-42", fingerprint, submit).await.unwrap();
+        assert!(!replayed);
+
+        let submit = || async {
+            mempool.lock().push("tx-1".to_string());
+            submitted("tx-1")
+        };
+        let (second, replayed) = store.execute("client", "pay-42", fingerprint, submit).await.unwrap();
+        assert!(replayed);
+        assert_eq!(first, second);
+        assert_eq!(mempool.lock().len(), 1);
+
+        // Rejections are replayed too
+        let rejected = StoredResponse {
+            status: 400,
+            body: serde_json::json!({ "message": "Insufficient transaction fee" }),
+        };
+        let reject = || async { rejected.clone() };
+        store.execute("client", "pay-43", fingerprint, reject).await.unwrap();
+        let (replay, replayed) = store
+            .execute("client", "pay-43", fingerprint, || async { submitted("tx-2") })
+            .await
+            .unwrap();
+        assert!(replayed);
+        assert_eq!(replay.status, 400);
+
+        // Same key, different request: refused
+        let other = request_fingerprint("submit", b"other-tx");
+        assert_eq!(
+            store.execute("client", "pay-42", other, || async { submitted("tx-3") }).await,
+            Err(IdempotencyError::KeyReused)
+        );
+
+        // Keys are scoped per API key
+        let (_, replayed) = store
+            .execute("other-client", "pay-42", other, || async { submitted("tx-3") })
+            .await
+            .unwrap();
+        assert!(!replayed);
+
+        assert_eq!(
+            store.execute("client", "", fingerprint, || async { submitted("x") }).await,
+            Err(IdempotencyError::InvalidKey)
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_duplicates_execute_once() {
+        let store = Arc::new(IdempotencyStore::new(Duration::from_secs(60), 100));
+        let executions = Arc::new(AtomicUsize::new(0));
+        let fingerprint = request_fingerprint("submit", b"raw-tx");
+
+        let mut tasks = Vec::new();
+        for _ in 0..16 {
+            let store = Arc::clone(&store);
+            let executions = Arc::clone(&executions);
+            tasks.push(tokio::spawn(async move {
+                store
+                    .execute("client", "race", fingerprint, || async {
+                        executions.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        submitted("tx-race")
+                    })
+                    .await
+                    .unwrap()
+            }));
+        }
+
+        let mut fresh = 0;
+        for task in tasks {
+            let (response, replayed) = task.await.unwrap();
+            assert_eq!(response, submitted("tx-race"));
+            if !replayed {
+                fresh += 1;
+            }
+        }
+        assert_eq!(fresh, 1);
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_key_reexecutes_and_store_is_bounded() {
+        let store = IdempotencyStore::new(Duration::from_millis(30), 3);
+        let fingerprint = request_fingerprint("submit", b"raw-tx");
+
+        store.execute("c", "k", fingerprint, || async { submitted("tx-1") }).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let (response, replayed) = store
+            .execute("c", "k", fingerprint, || async { submitted("tx-2") })
+            .await
+            .unwrap();
+        assert!(!replayed);
+        assert_eq!(response, submitted("tx-2"));
+
+        for key in ["a", "b", "c", "d"] {
+            store.execute("c", key, fingerprint, || async { submitted(key) }).await.unwrap();
+        }
+        assert_eq!(store.len(), 3);
+    }
+}
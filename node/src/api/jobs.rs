@@ -0,0 +1,993 @@
+//! Long-running admin jobs
+//!
+//! Reindexing, compaction, snapshot export, deep integrity checks and wallet
+//! rescans can take minutes to hours, so they run as jobs instead of inside a
+//! request. A [`JobManager`] owns the registered [`JobHandler`]s, starts each
+//! job on its own worker thread with a cancellation flag, tracks progress,
+//! and persists job state to a JSON file so a restart reports jobs that were
+//! cut short as [`JobStatus::Interrupted`].
+//!
+//! Storage-heavy handlers are exclusive: only one of them may run at a time.
+
+use crate::storage::compaction::COMPACTION_PAUSE;
+use crate::storage::utxo_commitment::COMMITMENT_BUCKETS;
+use crate::storage::{
+    snapshot, BlockchainDB, IntegrityCheckLevel, SnapshotNetwork, SnapshotPhase, SnapshotProgress,
+    StorageError, UtxoIntegrityReport,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// Finished jobs kept in the list (and the state file); older ones are dropped
+pub const MAX_FINISHED_JOBS: usize = 100;
+
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("Unknown job type '{0}'; expected one of reindex, compaction, snapshot-export, debug-check, wallet-rescan, utxo-audit, utxo-integrity")]
+    UnknownKind(String),
+
+    #[error("No handler is registered for {0} jobs on this node")]
+    NoHandler(JobKind),
+
+    #[error("Job {running} ({kind}) is already running; storage-heavy jobs run one at a time")]
+    Busy { running: u64, kind: JobKind },
+
+    #[error("Job {0} not found")]
+    NotFound(u64),
+
+    #[error("Job {0} is not running")]
+    NotRunning(u64),
+
+    #[error("Invalid job parameters: {0}")]
+    InvalidParams(String),
+
+    #[error("Job cancelled")]
+    Cancelled,
+
+    #[error("{0}")]
+    Failed(String),
+
+    #[error("Failed to start job worker: {0}")]
+    Spawn(std::io::Error),
+}
+
+/// Type of job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum JobKind {
+    Reindex,
+    Compaction,
+    SnapshotExport,
+    DebugCheck,
+    WalletRescan,
+    UtxoAudit,
+    UtxoIntegrity,
+}
+
+impl FromStr for JobKind {
+    type Err = JobError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reindex" => Ok(Self::Reindex),
+            "compaction" => Ok(Self::Compaction),
+            "snapshot-export" => Ok(Self::SnapshotExport),
+            "debug-check" => Ok(Self::DebugCheck),
+            "wallet-rescan" => Ok(Self::WalletRescan),
+            "utxo-audit" => Ok(Self::UtxoAudit),
+            "utxo-integrity" => Ok(Self::UtxoIntegrity),
+            other => Err(JobError::UnknownKind(other.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Reindex => "reindex",
+            Self::Compaction => "compaction",
+            Self::SnapshotExport => "snapshot-export",
+            Self::DebugCheck => "debug-check",
+            Self::WalletRescan => "wallet-rescan",
+            Self::UtxoAudit => "utxo-audit",
+            Self::UtxoIntegrity => "utxo-integrity",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Lifecycle state of a job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// The node stopped while the job was running
+    Interrupted,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        self != Self::Running
+    }
+}
+
+/// Progress reported by a running job
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobProgress {
+    /// 0.0 to 100.0
+    pub percent: f64,
+    pub stage: String,
+    pub message: String,
+}
+
+/// Job state as reported by the API and persisted across restarts
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub progress: JobProgress,
+    /// Parameters the job was started with
+    #[schema(value_type = Object)]
+    pub params: Value,
+    /// Handler-specific result of a completed job
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub created_at: u64,
+    pub finished_at: Option<u64>,
+}
+
+/// Handle passed to a running job for progress reporting and cancellation
+pub struct JobContext {
+    id: u64,
+    cancel: Arc<AtomicBool>,
+    state: Arc<Mutex<JobsState>>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Whether cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// `Err(JobError::Cancelled)` once cancellation was requested; call it
+    /// between units of work.
+    pub fn check_cancelled(&self) -> Result<(), JobError> {
+        if self.is_cancelled() {
+            Err(JobError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Report progress. Progress is kept in memory only; the state file is
+    /// written on status changes.
+    pub fn progress(&self, percent: f64, stage: &str, message: impl Into<String>) {
+        let mut state = self.state.lock();
+        if let Some(entry) = state.jobs.get_mut(&self.id) {
+            entry.info.progress = JobProgress {
+                percent: percent.clamp(0.0, 100.0),
+                stage: stage.to_string(),
+                message: message.into(),
+            };
+        }
+    }
+}
+
+/// A type of job the manager can run
+pub trait JobHandler: Send + Sync {
+    fn kind(&self) -> JobKind;
+
+    /// Exclusive jobs never run concurrently with each other
+    fn exclusive(&self) -> bool {
+        true
+    }
+
+    /// Reject bad parameters before a job is created
+    fn validate(&self, _params: &Value) -> Result<(), JobError> {
+        Ok(())
+    }
+
+    /// Run the job to completion on the worker thread. Long jobs should call
+    /// [`JobContext::check_cancelled`] regularly.
+    fn run(&self, params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError>;
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancel: Arc<AtomicBool>,
+    exclusive: bool,
+}
+
+#[derive(Default)]
+struct JobsState {
+    jobs: BTreeMap<u64, JobEntry>,
+    next_id: u64,
+}
+
+/// Registry and runner for long-running jobs
+pub struct JobManager {
+    handlers: Mutex<HashMap<JobKind, Arc<dyn JobHandler>>>,
+    state: Arc<Mutex<JobsState>>,
+    state_path: Option<PathBuf>,
+}
+
+impl JobManager {
+    /// Manager that keeps job state in memory only
+    pub fn in_memory() -> Self {
+        Self {
+            handlers: Mutex::new(HashMap::new()),
+            state: Arc::new(Mutex::new(JobsState {
+                jobs: BTreeMap::new(),
+                next_id: 1,
+            })),
+            state_path: None,
+        }
+    }
+
+    /// Manager persisting job state to `state_path`. Jobs recorded as running
+    /// by a previous process are reported as interrupted. An unreadable state
+    /// file is logged and replaced.
+    pub fn open(state_path: impl Into<PathBuf>) -> Self {
+        let state_path = state_path.into();
+        let mut manager = Self::in_memory();
+
+        match load_jobs(&state_path) {
+            Ok(jobs) => {
+                let mut state = manager.state.lock();
+                for mut info in jobs {
+                    if info.status == JobStatus::Running {
+                        info.status = JobStatus::Interrupted;
+                        info.error = Some("Node stopped while the job was running".to_string());
+                        info.finished_at = Some(now_secs());
+                    }
+                    state.next_id = state.next_id.max(info.id + 1);
+                    state.jobs.insert(
+                        info.id,
+                        JobEntry {
+                            info,
+                            cancel: Arc::new(AtomicBool::new(false)),
+                            exclusive: false,
+                        },
+                    );
+                }
+            }
+            Err(e) => warn!("Ignoring unreadable job state {:?}: {}", state_path, e),
+        }
+
+        manager.state_path = Some(state_path);
+        manager.persist();
+        manager
+    }
+
+    /// Register (or replace) the handler for its job type
+    pub fn register(&self, handler: Arc<dyn JobHandler>) {
+        self.handlers.lock().insert(handler.kind(), handler);
+    }
+
+    /// Register the storage jobs backed by `db`
+    pub fn register_storage_jobs(&self, db: Arc<BlockchainDB>, network: SnapshotNetwork) {
+        self.register(Arc::new(ReindexJob { db: Arc::clone(&db) }));
+        self.register(Arc::new(CompactionJob { db: Arc::clone(&db) }));
+        self.register(Arc::new(DebugCheckJob { db: Arc::clone(&db) }));
+        self.register(Arc::new(UtxoAuditJob { db: Arc::clone(&db) }));
+        self.register(Arc::new(UtxoIntegrityJob { db: Arc::clone(&db) }));
+        self.register(Arc::new(SnapshotExportJob { db, network }));
+    }
+
+    /// Start a job of type `kind`
+    pub fn start(&self, kind: JobKind, params: Value) -> Result<JobInfo, JobError> {
+        let handler = self
+            .handlers
+            .lock()
+            .get(&kind)
+            .cloned()
+            .ok_or(JobError::NoHandler(kind))?;
+        handler.validate(&params)?;
+        let exclusive = handler.exclusive();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let info = {
+            let mut state = self.state.lock();
+            if exclusive {
+                if let Some(running) = state
+                    .jobs
+                    .values()
+                    .find(|e| e.exclusive && e.info.status == JobStatus::Running)
+                {
+                    return Err(JobError::Busy {
+                        running: running.info.id,
+                        kind: running.info.kind,
+                    });
+                }
+            }
+
+            let id = state.next_id;
+            state.next_id += 1;
+            let info = JobInfo {
+                id,
+                kind,
+                status: JobStatus::Running,
+                progress: JobProgress {
+                    percent: 0.0,
+                    stage: "starting".to_string(),
+                    message: String::new(),
+                },
+                params: params.clone(),
+                result: None,
+                error: None,
+                created_at: now_secs(),
+                finished_at: None,
+            };
+            state.jobs.insert(
+                id,
+                JobEntry {
+                    info: info.clone(),
+                    cancel: Arc::clone(&cancel),
+                    exclusive,
+                },
+            );
+            info
+        };
+        self.persist();
+
+        let ctx = JobContext {
+            id: info.id,
+            cancel,
+            state: Arc::clone(&self.state),
+        };
+        let state = Arc::clone(&self.state);
+        let state_path = self.state_path.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("job-{}-{}", info.id, kind))
+            .spawn(move || {
+                info!("Job {} ({}) started", ctx.id, kind);
+                let outcome = handler.run(&params, &ctx);
+                finish(&state, state_path.as_deref(), ctx.id, outcome);
+            });
+
+        if let Err(e) = spawned {
+            finish(
+                &self.state,
+                self.state_path.as_deref(),
+                info.id,
+                Err(JobError::Failed(format!("worker did not start: {}", e))),
+            );
+            return Err(JobError::Spawn(e));
+        }
+        Ok(info)
+    }
+
+    /// Request cancellation of a running job. The job stops at its next
+    /// cancellation check.
+    pub fn cancel(&self, id: u64) -> Result<JobInfo, JobError> {
+        let state = self.state.lock();
+        let entry = state.jobs.get(&id).ok_or(JobError::NotFound(id))?;
+        if entry.info.status != JobStatus::Running {
+            return Err(JobError::NotRunning(id));
+        }
+        entry.cancel.store(true, Ordering::Relaxed);
+        info!("Cancellation requested for job {} ({})", id, entry.info.kind);
+        Ok(entry.info.clone())
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobInfo> {
+        self.state.lock().jobs.get(&id).map(|e| e.info.clone())
+    }
+
+    /// All known jobs, newest first
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.state
+            .lock()
+            .jobs
+            .values()
+            .rev()
+            .map(|e| e.info.clone())
+            .collect()
+    }
+
+    fn persist(&self) {
+        if let Some(path) = &self.state_path {
+            persist_jobs(&self.state.lock(), path);
+        }
+    }
+}
+
+/// Record a job's outcome, prune old finished jobs and persist
+fn finish(
+    state: &Mutex<JobsState>,
+    state_path: Option<&Path>,
+    id: u64,
+    outcome: Result<Option<Value>, JobError>,
+) {
+    let mut guard = state.lock();
+    if let Some(entry) = guard.jobs.get_mut(&id) {
+        let info = &mut entry.info;
+        info.finished_at = Some(now_secs());
+        match outcome {
+            Ok(result) => {
+                info.status = JobStatus::Completed;
+                info.progress.percent = 100.0;
+                info.progress.stage = "done".to_string();
+                info.result = result;
+            }
+            Err(JobError::Cancelled) => {
+                info.status = JobStatus::Cancelled;
+                info.progress.stage = "cancelled".to_string();
+            }
+            Err(e) => {
+                info.status = JobStatus::Failed;
+                info.error = Some(e.to_string());
+            }
+        }
+        info!("Job {} ({}) finished: {:?}", id, info.kind, info.status);
+    }
+
+    let finished: Vec<u64> = guard
+        .jobs
+        .values()
+        .filter(|e| e.info.status.is_finished())
+        .map(|e| e.info.id)
+        .collect();
+    for old in finished.iter().take(finished.len().saturating_sub(MAX_FINISHED_JOBS)) {
+        guard.jobs.remove(old);
+    }
+
+    // Written under the lock so the file never lags a visible status
+    if let Some(path) = state_path {
+        persist_jobs(&guard, path);
+    }
+}
+
+fn load_jobs(path: &Path) -> Result<Vec<JobInfo>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Write the state file via a temporary file and rename. Failures are logged:
+/// job tracking keeps working in memory.
+fn persist_jobs(state: &JobsState, path: &Path) {
+    let jobs: Vec<&JobInfo> = state.jobs.values().map(|e| &e.info).collect();
+    let result = serde_json::to_vec_pretty(&jobs)
+        .map_err(std::io::Error::other)
+        .and_then(|bytes| {
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(&tmp, path)
+        });
+    if let Err(e) = result {
+        warn!("Failed to persist job state to {:?}: {}", path, e);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn storage_error(e: impl std::fmt::Display) -> JobError {
+    JobError::Failed(format!("Storage error: {}", e))
+}
+
+/// Rebuild the derived per-height indexes (height index and block stats)
+/// from the stored best chain.
+struct ReindexJob {
+    db: Arc<BlockchainDB>,
+}
+
+impl JobHandler for ReindexJob {
+    fn kind(&self) -> JobKind {
+        JobKind::Reindex
+    }
+
+    fn run(&self, _params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+        ctx.progress(0.0, "height-index", "Rebuilding height index");
+        let best = self.db.get_best_block_hash().map_err(storage_error)?;
+        let indexed = self.db.backfill_height_index(&best).map_err(storage_error)?;
+
+        let tip = self.db.get_height().map_err(storage_error)?;
+        let mut recorded = 0u64;
+        for height in 0..=tip {
+            ctx.check_cancelled()?;
+            if let Some(block) = self.db.get_block_by_height(height).map_err(storage_error)? {
+                self.db.record_block_stats(&block).map_err(storage_error)?;
+                recorded += 1;
+            }
+            if height % 100 == 0 || height == tip {
+                ctx.progress(
+                    (height + 1) as f64 * 100.0 / (tip + 1) as f64,
+                    "block-stats",
+                    format!("{}/{} blocks", height + 1, tip + 1),
+                );
+            }
+        }
+        self.db.flush().map_err(storage_error)?;
+
+        Ok(Some(serde_json::json!({
+            "height_index_entries": indexed,
+            "block_stats_records": recorded,
+        })))
+    }
+}
+
+/// Rewrite every tree so sled can release the space of dead entries. Pauses
+/// between batches so block processing is not starved.
+struct CompactionJob {
+    db: Arc<BlockchainDB>,
+}
+
+impl JobHandler for CompactionJob {
+    fn kind(&self) -> JobKind {
+        JobKind::Compaction
+    }
+
+    fn run(&self, _params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+        ctx.progress(0.0, "compacting", "Rewriting trees");
+        let compacted = self.db.compact_with(|progress| {
+            if ctx.is_cancelled() {
+                return Err(StorageError::DatabaseError(
+                    "Compaction cancelled".to_string(),
+                ));
+            }
+            ctx.progress(
+                progress.trees_done as f64 * 100.0 / progress.trees_total.max(1) as f64,
+                "compacting",
+                format!(
+                    "{}: {} entries rewritten",
+                    progress.tree, progress.entries_rewritten
+                ),
+            );
+            std::thread::sleep(COMPACTION_PAUSE);
+            Ok(())
+        });
+        ctx.check_cancelled()?;
+        let record = compacted.map_err(storage_error)?;
+        Ok(Some(serde_json::to_value(record).map_err(|e| {
+            JobError::Failed(format!("Failed to encode the compaction record: {}", e))
+        })?))
+    }
+}
+
+/// Integrity check at the level given by `{"level": "quick|standard|comprehensive|deep"}`
+/// (default `deep`). Never repairs.
+struct DebugCheckJob {
+    db: Arc<BlockchainDB>,
+}
+
+fn check_level(params: &Value) -> Result<IntegrityCheckLevel, JobError> {
+    match params.get("level").and_then(Value::as_str).unwrap_or("deep") {
+        "quick" => Ok(IntegrityCheckLevel::Quick),
+        "standard" => Ok(IntegrityCheckLevel::Standard),
+        "comprehensive" => Ok(IntegrityCheckLevel::Comprehensive),
+        "deep" => Ok(IntegrityCheckLevel::Deep),
+        other => Err(JobError::InvalidParams(format!(
+            "unknown check level '{}'; expected quick, standard, comprehensive or deep",
+            other
+        ))),
+    }
+}
+
+impl JobHandler for DebugCheckJob {
+    fn kind(&self) -> JobKind {
+        JobKind::DebugCheck
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), JobError> {
+        check_level(params).map(|_| ())
+    }
+
+    fn run(&self, params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+        let level = check_level(params)?;
+        ctx.progress(0.0, "verifying", format!("{:?} integrity check", level));
+        let result = self
+            .db
+            .verify_integrity(level, false)
+            .map_err(storage_error)?;
+        serde_json::to_value(&result)
+            .map(Some)
+            .map_err(|e| JobError::Failed(e.to_string()))
+    }
+}
+
+/// Recompute the UTXO statistics with a full scan (`gettxoutsetinfo`) and
+/// cross-check them against the running statistics and the supply schedule.
+/// `{"repair": true}` replaces running statistics that disagree with the scan.
+/// A tip that moves during the scan makes the comparison inconclusive, so
+/// nothing is flagged or repaired in that case.
+struct UtxoAuditJob {
+    db: Arc<BlockchainDB>,
+}
+
+impl JobHandler for UtxoAuditJob {
+    fn kind(&self) -> JobKind {
+        JobKind::UtxoAudit
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), JobError> {
+        match params.get("repair") {
+            None | Some(Value::Bool(_)) => Ok(()),
+            Some(_) => Err(JobError::InvalidParams("\"repair\" must be a boolean".to_string())),
+        }
+    }
+
+    fn run(&self, params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+        let repair = params.get("repair").and_then(Value::as_bool).unwrap_or(false);
+
+        ctx.progress(0.0, "scanning", "Scanning the UTXO set");
+        let height = self.db.get_height().map_err(storage_error)?;
+        let running = self.db.utxo_stats().map_err(storage_error)?;
+        let (commitment, scanned) = self.db.scan_utxo_stats().map_err(storage_error)?;
+        let tip_moved = self.db.get_height().map_err(storage_error)? != height;
+        ctx.check_cancelled()?;
+
+        ctx.progress(90.0, "auditing", "Checking supply against the subsidy schedule");
+        let stats_match = running == scanned;
+        let supply = self
+            .db
+            .supply_audit_at(height, scanned.clone())
+            .map_err(storage_error)?;
+        let mut repaired = false;
+        if !tip_moved {
+            metrics::gauge!(
+                "supernova_utxo_verification_failures",
+                if stats_match { 0.0 } else { 1.0 }
+            );
+            supply.publish();
+            if !stats_match {
+                tracing::error!(
+                    target: "audit",
+                    height,
+                    running_count = running.count,
+                    scanned_count = scanned.count,
+                    running_amount = running.total_amount,
+                    scanned_amount = scanned.total_amount,
+                    "Running UTXO statistics disagree with a full scan"
+                );
+                if repair {
+                    self.db.rebuild_utxo_stats().map_err(storage_error)?;
+                    repaired = true;
+                }
+            }
+        }
+
+        Ok(Some(serde_json::json!({
+            "height": height,
+            "utxo_commitment": hex::encode(commitment),
+            "tip_moved": tip_moved,
+            "stats_match": stats_match,
+            "repaired": repaired,
+            "running": running,
+            "scanned": scanned,
+            "supply": supply,
+        })))
+    }
+}
+
+/// Recompute the UTXO set commitment from scratch, one bucket at a time, and
+/// compare each bucket with the running commitment. UTXO writes wait only
+/// for the bucket being scanned, so blocks connected meanwhile can make the
+/// recomputed commitment differ from the running one while every bucket
+/// still matches.
+struct UtxoIntegrityJob {
+    db: Arc<BlockchainDB>,
+}
+
+impl JobHandler for UtxoIntegrityJob {
+    fn kind(&self) -> JobKind {
+        JobKind::UtxoIntegrity
+    }
+
+    fn run(&self, _params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+        let mut checks = Vec::with_capacity(COMMITMENT_BUCKETS);
+        for bucket in 0..=u8::MAX {
+            ctx.check_cancelled()?;
+            checks.push(self.db.check_utxo_bucket(bucket).map_err(storage_error)?);
+            if bucket % 16 == 15 {
+                ctx.progress(
+                    checks.len() as f64 * 100.0 / COMMITMENT_BUCKETS as f64,
+                    "recomputing",
+                    format!("{}/{} buckets", checks.len(), COMMITMENT_BUCKETS),
+                );
+            }
+        }
+
+        let commitment = self.db.utxo_commitment().map_err(storage_error)?;
+        let report = UtxoIntegrityReport::new(commitment, &checks, true);
+        metrics::gauge!(
+            "supernova_utxo_commitment_mismatches",
+            report.mismatched_buckets.len() as f64
+        );
+        if !report.consistent {
+            tracing::error!(
+                target: "audit",
+                buckets = ?report.mismatched_buckets,
+                "UTXO set disagrees with its running commitment"
+            );
+        }
+        serde_json::to_value(&report)
+            .map(Some)
+            .map_err(|e| JobError::Failed(e.to_string()))
+    }
+}
+
+/// Export a snapshot archive to `{"path": "..."}`. The tip is read at the
+/// start; blocks connected while exporting can make the archive fail its
+/// UTXO commitment check on import, so pause sync for a clean export.
+struct SnapshotExportJob {
+    db: Arc<BlockchainDB>,
+    network: SnapshotNetwork,
+}
+
+fn snapshot_path(params: &Value) -> Result<PathBuf, JobError> {
+    params
+        .get("path")
+        .and_then(Value::as_str)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .ok_or_else(|| JobError::InvalidParams("\"path\" is required".to_string()))
+}
+
+impl JobHandler for SnapshotExportJob {
+    fn kind(&self) -> JobKind {
+        JobKind::SnapshotExport
+    }
+
+    fn validate(&self, params: &Value) -> Result<(), JobError> {
+        snapshot_path(params).map(|_| ())
+    }
+
+    fn run(&self, params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+        let path = snapshot_path(params)?;
+        let mut report = |p: &SnapshotProgress| {
+            let percent = match p.total_entries {
+                0 => 0.0,
+                total => p.entries_done as f64 * 100.0 / total as f64,
+            };
+            let stage = match p.phase {
+                SnapshotPhase::Exporting => "exporting",
+                SnapshotPhase::Importing => "importing",
+                SnapshotPhase::Verifying => "verifying",
+            };
+            ctx.progress(
+                percent,
+                stage,
+                format!("{}/{} entries", p.entries_done, p.total_entries),
+            );
+        };
+        let manifest = snapshot::export_snapshot(&self.db, &self.network, &path, &mut report)
+            .map_err(|e| JobError::Failed(e.to_string()))?;
+        serde_json::to_value(&manifest)
+            .map(Some)
+            .map_err(|e| JobError::Failed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::CompactionRecord;
+    use std::time::{Duration, Instant};
+
+    /// Counts to `steps`, sleeping between steps
+    struct SlowJob {
+        steps: u64,
+    }
+
+    impl JobHandler for SlowJob {
+        fn kind(&self) -> JobKind {
+            JobKind::Compaction
+        }
+
+        fn run(&self, _params: &Value, ctx: &JobContext) -> Result<Option<Value>, JobError> {
+            for step in 0..self.steps {
+                ctx.check_cancelled()?;
+                ctx.progress(step as f64 * 100.0 / self.steps as f64, "counting", format!("step {}", step));
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Ok(Some(serde_json::json!({ "steps": self.steps })))
+        }
+    }
+
+    fn wait_for(manager: &JobManager, id: u64, mut done: impl FnMut(&JobInfo) -> bool) -> JobInfo {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let info = manager.get(id).unwrap();
+            if done(&info) {
+                return info;
+            }
+            assert!(Instant::now() < deadline, "timed out waiting for job {}: {:?}", id, info);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn slow_job_progress_cancel_and_exclusion() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = JobManager::open(dir.path().join("jobs.json"));
+        manager.register(Arc::new(SlowJob { steps: 10_000 }));
+
+        assert!(matches!(
+            manager.start(JobKind::Reindex, Value::Null),
+            Err(JobError::NoHandler(JobKind::Reindex))
+        ));
+
+        let job = manager.start(JobKind::Compaction, Value::Null).unwrap();
+        assert_eq!(job.status, JobStatus::Running);
+        let running = wait_for(&manager, job.id, |info| info.progress.percent > 0.0);
+        assert_eq!(running.progress.stage, "counting");
+
+        // A second storage-heavy job is refused while the first runs
+        match manager.start(JobKind::Compaction, Value::Null) {
+            Err(JobError::Busy { running, kind }) => {
+                assert_eq!(running, job.id);
+                assert_eq!(kind, JobKind::Compaction);
+            }
+            other => panic!("expected Busy, got {:?}", other.map(|i| i.id)),
+        }
+
+        manager.cancel(job.id).unwrap();
+        let cancelled = wait_for(&manager, job.id, |info| info.status.is_finished());
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert!(cancelled.finished_at.is_some());
+        assert!(matches!(manager.cancel(job.id), Err(JobError::NotRunning(_))));
+        assert!(matches!(manager.cancel(999), Err(JobError::NotFound(999))));
+
+        // Once the first job stopped, a new one may start and run to completion
+        manager.register(Arc::new(SlowJob { steps: 3 }));
+        let second = manager.start(JobKind::Compaction, Value::Null).unwrap();
+        let done = wait_for(&manager, second.id, |info| info.status.is_finished());
+        assert_eq!(done.status, JobStatus::Completed);
+        assert_eq!(done.progress.percent, 100.0);
+        assert_eq!(done.result, Some(serde_json::json!({ "steps": 3 })));
+
+        let ids: Vec<u64> = manager.list().iter().map(|i| i.id).collect();
+        assert_eq!(ids, vec![second.id, job.id]);
+    }
+
+    #[test]
+    fn restart_reports_interrupted_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+
+        let stuck = JobInfo {
+            id: 7,
+            kind: JobKind::Reindex,
+            status: JobStatus::Running,
+            progress: JobProgress::default(),
+            params: Value::Null,
+            result: None,
+            error: None,
+            created_at: 1,
+            finished_at: None,
+        };
+        std::fs::write(&path, serde_json::to_vec(&vec![stuck]).unwrap()).unwrap();
+
+        let manager = JobManager::open(&path);
+        let info = manager.get(7).unwrap();
+        assert_eq!(info.status, JobStatus::Interrupted);
+        assert!(info.error.is_some());
+
+        // New ids continue after persisted ones, and the file reflects the change
+        manager.register(Arc::new(SlowJob { steps: 1 }));
+        let next = manager.start(JobKind::Compaction, Value::Null).unwrap();
+        assert_eq!(next.id, 8);
+        wait_for(&manager, next.id, |info| info.status.is_finished());
+
+        let reloaded = JobManager::open(&path);
+        assert_eq!(reloaded.get(7).unwrap().status, JobStatus::Interrupted);
+        assert_eq!(reloaded.get(8).unwrap().status, JobStatus::Completed);
+    }
+
+    #[test]
+    fn utxo_audit_flags_and_repairs_drifted_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(dir.path().join("db")).unwrap());
+        let output = bincode::serialize(
+            &supernova_core::types::transaction::TransactionOutput::new(7, vec![]),
+        )
+        .unwrap();
+        db.store_utxo(&[1; 32], 0, &output).unwrap();
+        db.open_tree("utxos").unwrap().insert([2u8; 36], output).unwrap();
+
+        let manager = JobManager::in_memory();
+        manager.register(Arc::new(UtxoAuditJob { db: Arc::clone(&db) }));
+        assert!(matches!(
+            manager.start(JobKind::UtxoAudit, serde_json::json!({"repair": "yes"})),
+            Err(JobError::InvalidParams(_))
+        ));
+        let job = manager
+            .start("utxo-audit".parse().unwrap(), serde_json::json!({"repair": true}))
+            .unwrap();
+        let done = wait_for(&manager, job.id, |info| info.status.is_finished());
+        assert_eq!(done.status, JobStatus::Completed);
+
+        let result = done.result.unwrap();
+        assert_eq!(result["stats_match"], false);
+        assert_eq!(result["repaired"], true);
+        assert_eq!(result["supply"]["discrepancy"], true);
+        assert_eq!(db.utxo_stats().unwrap().count, 2);
+    }
+
+    #[test]
+    fn utxo_integrity_job_recomputes_every_bucket() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(dir.path().join("db")).unwrap());
+        for n in 0..50u8 {
+            db.store_utxo(&[n.wrapping_mul(37); 32], n as u32, &[n]).unwrap();
+        }
+
+        let manager = JobManager::in_memory();
+        manager.register(Arc::new(UtxoIntegrityJob { db: Arc::clone(&db) }));
+        let run = |manager: &JobManager| {
+            let job = manager
+                .start("utxo-integrity".parse().unwrap(), Value::Null)
+                .unwrap();
+            let done = wait_for(manager, job.id, |info| info.status.is_finished());
+            assert_eq!(done.status, JobStatus::Completed);
+            serde_json::from_value::<UtxoIntegrityReport>(done.result.unwrap()).unwrap()
+        };
+
+        let report = run(&manager);
+        assert!(report.consistent);
+        assert_eq!(report.buckets_checked, COMMITMENT_BUCKETS);
+        assert_eq!(report.entries_checked, 50);
+        assert_eq!(
+            report.recomputed_commitment,
+            Some(report.commitment.clone())
+        );
+
+        db.open_tree("utxos")
+            .unwrap()
+            .insert([200u8; 36], vec![1])
+            .unwrap();
+        let report = run(&manager);
+        assert!(!report.consistent);
+        assert_eq!(report.mismatched_buckets, vec![200]);
+        assert_ne!(report.recomputed_commitment, Some(report.commitment));
+    }
+
+    #[test]
+    fn compaction_job_reports_its_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(dir.path().join("db")).unwrap());
+        for n in 0..3_000u32 {
+            db.store_metadata(&n.to_be_bytes(), &[1; 32]).unwrap();
+        }
+
+        let manager = JobManager::in_memory();
+        manager.register(Arc::new(CompactionJob {
+            db: Arc::clone(&db),
+        }));
+        let job = manager
+            .start("compaction".parse().unwrap(), Value::Null)
+            .unwrap();
+        let done = wait_for(&manager, job.id, |info| info.status.is_finished());
+        assert_eq!(done.status, JobStatus::Completed);
+        let record: CompactionRecord = serde_json::from_value(done.result.unwrap()).unwrap();
+        assert!(record.entries_rewritten >= 3_000);
+        assert_eq!(db.last_compaction().unwrap(), Some(record));
+    }
+}
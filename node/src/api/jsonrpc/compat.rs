@@ -0,0 +1,1327 @@
+//! Bitcoin Core compatibility mode for the JSON-RPC server
+//!
+//! Explorers and exchange integrations speak Bitcoin Core RPC verbatim. With
+//! `api.bitcoin_rpc_compat` enabled, the methods both nodes share answer with
+//! Core's parameter conventions, field names and error codes:
+//! `getblockchaininfo`, `getblock`, `getblockhash`, `getrawtransaction`,
+//! `sendrawtransaction`, `estimatesmartfee`, `getmempoolinfo` and
+//! `getpeerinfo`. Every other method keeps its native behaviour.
+//!
+//! Data with no Core field goes in an `extensions` object next to the Core
+//! fields instead of being dropped. Where Core semantics cannot be honoured,
+//! e.g. `getblock` verbosity 3 needs undo data this node does not keep, the
+//! call fails with `RPC_INVALID_PARAMETER` and `data.unsupported = true`
+//! rather than returning something that only looks right.
+//!
+//! Deliberate divergences, documented in `docs/api/BITCOIN_COMPAT.md`:
+//! - amounts are NOVA (10^8 nova units, the same scale as BTC/satoshi) and
+//!   fee rates NOVA/kvB;
+//! - hashes are hex in Supernova's byte order, not reversed;
+//! - raw blocks and transactions are Supernova's bincode encoding, so `hex`
+//!   fields cannot be parsed by Bitcoin decoders;
+//! - quantum output scripts have no Core type and report `nonstandard`, with
+//!   their bech32m address and `extensions.script_kind`;
+//! - quantum signatures appear under `extensions`, not `txinwitness`.
+
+use super::handlers::{directory_size_on_disk, submit_transaction};
+use super::types::JsonRpcError;
+use crate::api::types::{MempoolInfo, PeerInfo, TransactionFees};
+use crate::api_facade::ApiFacade;
+use crate::mempool::MempoolError;
+use crate::storage::BlockchainDB;
+use serde_json::{json, Map, Value};
+use supernova_core::blockchain::calculate_difficulty_from_bits;
+use supernova_core::script::classify::{classify, ScriptKind};
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::Transaction;
+use supernova_core::types::NOVAS_PER_NOVA;
+
+// Bitcoin Core error codes (src/rpc/protocol.h)
+const RPC_MISC_ERROR: i32 = -1;
+const RPC_TYPE_ERROR: i32 = -3;
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+const RPC_INVALID_PARAMETER: i32 = -8;
+const RPC_DESERIALIZATION_ERROR: i32 = -22;
+const RPC_VERIFY_ERROR: i32 = -25;
+const RPC_VERIFY_REJECTED: i32 = -26;
+const RPC_VERIFY_ALREADY_IN_CHAIN: i32 = -27;
+
+/// Blocks `estimatesmartfee` accepts as a confirmation target, as in Core
+const MAX_CONF_TARGET: u64 = 1008;
+
+/// Blocks in the median-time-past window
+const MEDIAN_TIME_SPAN: u64 = 11;
+
+/// Methods answered in Core's format when compatibility mode is on
+pub const COMPAT_METHODS: &[&str] = &[
+    "getblockchaininfo",
+    "getblock",
+    "getblockhash",
+    "getrawtransaction",
+    "sendrawtransaction",
+    "estimatesmartfee",
+    "getmempoolinfo",
+    "getpeerinfo",
+];
+
+/// Whether the node runs with `api.bitcoin_rpc_compat`
+pub fn enabled(node: &ApiFacade) -> bool {
+    node.config()
+        .read()
+        .map(|config| config.api.bitcoin_rpc_compat)
+        .unwrap_or(false)
+}
+
+/// Answer `method` in Core's format, or `None` if it isn't shimmed
+pub(super) async fn dispatch(
+    method: &str,
+    params: &Value,
+    node: &ApiFacade,
+) -> Option<Result<Value, JsonRpcError>> {
+    let result = match method {
+        "getblockchaininfo" => get_blockchain_info(node),
+        "getblock" => get_block(params, node),
+        "getblockhash" => get_block_hash(params, &node.storage()),
+        "getrawtransaction" => get_raw_transaction(params, node),
+        "sendrawtransaction" => send_raw_transaction(params, node),
+        "estimatesmartfee" => estimate_smart_fee(params, node),
+        "getmempoolinfo" => get_mempool_info(node),
+        "getpeerinfo" => get_peer_info(node).await,
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn error(code: i32, message: impl Into<String>) -> JsonRpcError {
+    JsonRpcError {
+        code,
+        message: message.into(),
+        data: None,
+    }
+}
+
+/// A Core feature this node cannot provide faithfully
+fn unsupported(message: impl Into<String>) -> JsonRpcError {
+    JsonRpcError {
+        code: RPC_INVALID_PARAMETER,
+        message: message.into(),
+        data: Some(json!({ "unsupported": true })),
+    }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> JsonRpcError {
+    error(RPC_MISC_ERROR, format!("Storage error: {}", e))
+}
+
+/// Parameter by position, or by name for named-parameter requests
+fn param<'a>(params: &'a Value, index: usize, name: &str) -> Option<&'a Value> {
+    match params {
+        Value::Array(values) => values.get(index),
+        Value::Object(values) => values.get(name),
+        _ => None,
+    }
+    .filter(|value| !value.is_null())
+}
+
+fn parse_hash(value: Option<&Value>, name: &str) -> Result<[u8; 32], JsonRpcError> {
+    let text = value
+        .ok_or_else(|| error(RPC_INVALID_PARAMETER, format!("{} is required", name)))?
+        .as_str()
+        .ok_or_else(|| error(RPC_TYPE_ERROR, format!("{} must be a string", name)))?;
+    let bytes = hex::decode(text)
+        .ok()
+        .filter(|bytes| bytes.len() == 32)
+        .ok_or_else(|| {
+            error(
+                RPC_INVALID_PARAMETER,
+                format!(
+                    "{} must be of length 64 (not {}, for '{}')",
+                    name,
+                    text.len(),
+                    text
+                ),
+            )
+        })?;
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&bytes);
+    Ok(hash)
+}
+
+/// Core's verbosity argument: a boolean or a level up to `max`
+fn parse_verbosity(value: Option<&Value>, default: u64, max: u64) -> Result<u64, JsonRpcError> {
+    let level = match value {
+        None => default,
+        Some(Value::Bool(verbose)) => u64::from(*verbose),
+        Some(Value::Number(n)) => n.as_u64().ok_or_else(|| {
+            error(
+                RPC_INVALID_PARAMETER,
+                "verbosity must be a non-negative integer",
+            )
+        })?,
+        Some(_) => {
+            return Err(error(
+                RPC_TYPE_ERROR,
+                "verbosity must be a boolean or an integer",
+            ))
+        }
+    };
+    if level > max {
+        return Err(error(
+            RPC_INVALID_PARAMETER,
+            format!("Invalid verbosity {} (must be 0 to {})", level, max),
+        ));
+    }
+    Ok(level)
+}
+
+/// Amount in NOVA as a JSON number, as Core reports BTC
+fn amount(units: u64) -> Value {
+    json!(units as f64 / NOVAS_PER_NOVA as f64)
+}
+
+/// Per-byte fee rate in nova units as NOVA per 1000 virtual bytes
+fn fee_rate_per_kvb(rate_per_byte: u64) -> Value {
+    json!(rate_per_byte as f64 * 1000.0 / NOVAS_PER_NOVA as f64)
+}
+
+/// Core's name for a network, where there is one
+fn chain_name(network_id: &str) -> &str {
+    match network_id {
+        "main" | "mainnet" | "supernova-mainnet" => "main",
+        "test" | "testnet" | "supernova-testnet" => "test",
+        "regtest" | "supernova-regtest" => "regtest",
+        other => other,
+    }
+}
+
+/// Core's `scriptPubKey.type`; `None` for kinds Core has no name for
+fn core_script_type(kind: ScriptKind) -> Option<&'static str> {
+    match kind {
+        ScriptKind::P2pkh => Some("pubkeyhash"),
+        ScriptKind::P2sh => Some("scripthash"),
+        ScriptKind::P2wpkh => Some("witness_v0_keyhash"),
+        ScriptKind::P2wsh => Some("witness_v0_scripthash"),
+        ScriptKind::Multisig => Some("multisig"),
+        ScriptKind::NullData => Some("nulldata"),
+        ScriptKind::Nonstandard => Some("nonstandard"),
+        ScriptKind::QuantumPkh => None,
+    }
+}
+
+/// Median timestamp of the main-chain block at `height` and its ten
+/// predecessors
+fn median_time_past(storage: &BlockchainDB, height: u64) -> Result<u64, JsonRpcError> {
+    let mut times = Vec::with_capacity(MEDIAN_TIME_SPAN as usize);
+    for h in (0..=height).rev().take(MEDIAN_TIME_SPAN as usize) {
+        let hash = storage.get_block_hash_by_height(h).map_err(storage_error)?;
+        if let Some(block) = hash
+            .map(|hash| storage.get_block(&hash))
+            .transpose()
+            .map_err(storage_error)?
+            .flatten()
+        {
+            times.push(block.timestamp());
+        }
+    }
+    times.sort_unstable();
+    Ok(times.get(times.len() / 2).copied().unwrap_or(0))
+}
+
+fn tip(storage: &BlockchainDB) -> Result<(u64, Option<Block>), JsonRpcError> {
+    let height = storage.get_height().map_err(storage_error)?;
+    let block = match storage
+        .get_block_hash_by_height(height)
+        .map_err(storage_error)?
+    {
+        Some(hash) => storage.get_block(&hash).map_err(storage_error)?,
+        None => None,
+    };
+    Ok((height, block))
+}
+
+// ---- getblockchaininfo ----
+
+/// Chain state for [`blockchain_info_json`]
+pub(super) struct ChainInfo {
+    pub network_id: String,
+    pub height: u64,
+    pub best_block_hash: [u8; 32],
+    pub bits: u32,
+    pub time: u64,
+    pub median_time: u64,
+    pub total_difficulty: u64,
+    pub verification_progress: f64,
+    pub size_on_disk: u64,
+    /// Lowest block height still stored, when pruned
+    pub prune_height: Option<u64>,
+}
+
+pub(super) fn blockchain_info_json(info: &ChainInfo) -> Value {
+    let mut value = json!({
+        "chain": chain_name(&info.network_id),
+        "blocks": info.height,
+        "headers": info.height,
+        "bestblockhash": hex::encode(info.best_block_hash),
+        "difficulty": calculate_difficulty_from_bits(info.bits),
+        "time": info.time,
+        "mediantime": info.median_time,
+        "verificationprogress": info.verification_progress,
+        "initialblockdownload": info.verification_progress < 0.999,
+        // Supernova's chain work is cumulative difficulty, not expected hashes
+        "chainwork": format!("{:064x}", info.total_difficulty),
+        "size_on_disk": info.size_on_disk,
+        "pruned": info.prune_height.is_some(),
+        "warnings": "",
+        "extensions": {
+            "network_id": info.network_id,
+            "total_difficulty": info.total_difficulty,
+        },
+    });
+    if let Some(prune_height) = info.prune_height {
+        value["pruneheight"] = json!(prune_height);
+    }
+    value
+}
+
+fn get_blockchain_info(node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let storage = node.storage();
+    let (height, block) = tip(&storage)?;
+    let network_id = node
+        .config()
+        .read()
+        .map_err(|e| error(RPC_MISC_ERROR, format!("Failed to read config: {}", e)))?
+        .network
+        .network_id
+        .clone();
+    let total_difficulty = node
+        .chain_state()
+        .read()
+        .map_err(|e| error(RPC_MISC_ERROR, format!("Chain state lock poisoned: {}", e)))?
+        .get_total_difficulty();
+
+    Ok(blockchain_info_json(&ChainInfo {
+        network_id,
+        height,
+        best_block_hash: block.as_ref().map(|b| b.hash()).unwrap_or([0u8; 32]),
+        bits: block.as_ref().map(|b| b.header().bits()).unwrap_or(0),
+        time: block.as_ref().map(|b| b.timestamp()).unwrap_or(0),
+        median_time: median_time_past(&storage, height)?,
+        total_difficulty,
+        verification_progress: node.network().get_sync_progress(),
+        size_on_disk: directory_size_on_disk(storage.path()),
+        prune_height: node.prune_height(),
+    }))
+}
+
+// ---- getblock / getblockhash ----
+
+/// Where a block sits relative to the current chain
+pub(super) struct BlockContext {
+    /// -1 when the block is not on the main chain, as in Core
+    pub confirmations: i64,
+    pub median_time: u64,
+    pub next_block_hash: Option<[u8; 32]>,
+}
+
+/// Containing block of a transaction, for verbose `getrawtransaction`
+pub(super) struct TxBlockContext {
+    pub block_hash: [u8; 32],
+    pub confirmations: i64,
+    pub time: u64,
+    /// Only reported when the caller named the block
+    pub in_active_chain: Option<bool>,
+}
+
+pub(super) fn block_json(
+    block: &Block,
+    verbosity: u64,
+    context: &BlockContext,
+) -> Result<Value, JsonRpcError> {
+    if verbosity == 0 {
+        let raw = bincode::serialize(block)
+            .map_err(|e| error(RPC_MISC_ERROR, format!("Failed to serialize block: {}", e)))?;
+        return Ok(Value::String(hex::encode(raw)));
+    }
+    if verbosity >= 3 {
+        return Err(unsupported(
+            "getblock verbosity 3 is not supported: this node keeps no undo data to report prevouts",
+        ));
+    }
+
+    let size = bincode::serialized_size(block).unwrap_or(0);
+    let header = block.header();
+    let txs: Vec<Value> = block
+        .transactions()
+        .iter()
+        .map(|tx| {
+            if verbosity == 2 {
+                transaction_json(tx, None)
+            } else {
+                Value::String(hex::encode(tx.hash()))
+            }
+        })
+        .collect();
+    let quantum_signed = block
+        .transactions()
+        .iter()
+        .filter(|tx| tx.has_quantum_signatures())
+        .count();
+
+    let mut result = json!({
+        "hash": hex::encode(block.hash()),
+        "confirmations": context.confirmations,
+        "height": block.height(),
+        "version": block.version(),
+        "versionHex": format!("{:08x}", block.version()),
+        "merkleroot": hex::encode(block.merkle_root()),
+        "time": block.timestamp(),
+        "mediantime": context.median_time,
+        "nonce": block.nonce(),
+        "bits": format!("{:08x}", header.bits()),
+        "difficulty": calculate_difficulty_from_bits(header.bits()),
+        "nTx": block.transactions().len(),
+        "strippedsize": size,
+        "size": size,
+        "weight": size * 4,
+        "tx": txs,
+        "extensions": {
+            "quantum_signed_transactions": quantum_signed,
+        },
+    });
+    if block.height() > 0 {
+        result["previousblockhash"] = Value::String(hex::encode(block.prev_block_hash()));
+    }
+    if let Some(next) = context.next_block_hash {
+        result["nextblockhash"] = Value::String(hex::encode(next));
+    }
+    Ok(result)
+}
+
+fn block_context(storage: &BlockchainDB, block: &Block) -> Result<BlockContext, JsonRpcError> {
+    let tip_height = storage.get_height().map_err(storage_error)?;
+    let main_chain_hash = storage
+        .get_block_hash_by_height(block.height())
+        .map_err(storage_error)?;
+    if main_chain_hash != Some(block.hash()) {
+        return Ok(BlockContext {
+            confirmations: -1,
+            median_time: block.timestamp(),
+            next_block_hash: None,
+        });
+    }
+    Ok(BlockContext {
+        confirmations: tip_height.saturating_sub(block.height()) as i64 + 1,
+        median_time: median_time_past(storage, block.height())?,
+        next_block_hash: storage
+            .get_block_hash_by_height(block.height() + 1)
+            .map_err(storage_error)?,
+    })
+}
+
+fn get_block(params: &Value, node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let hash = parse_hash(param(params, 0, "blockhash"), "blockhash")?;
+    let verbosity = parse_verbosity(param(params, 1, "verbosity"), 1, 3)?;
+    let storage = node.storage();
+    let block = storage
+        .get_block(&hash)
+        .map_err(storage_error)?
+        .ok_or_else(|| error(RPC_INVALID_ADDRESS_OR_KEY, "Block not found"))?;
+    let context = block_context(&storage, &block)?;
+    block_json(&block, verbosity, &context)
+}
+
+fn get_block_hash(params: &Value, storage: &BlockchainDB) -> Result<Value, JsonRpcError> {
+    let height = param(params, 0, "height")
+        .ok_or_else(|| error(RPC_INVALID_PARAMETER, "height is required"))?
+        .as_u64()
+        .ok_or_else(|| error(RPC_INVALID_PARAMETER, "Block height out of range"))?;
+    storage
+        .get_block_hash_by_height(height)
+        .map_err(storage_error)?
+        .map(|hash| Value::String(hex::encode(hash)))
+        .ok_or_else(|| error(RPC_INVALID_PARAMETER, "Block height out of range"))
+}
+
+// ---- getrawtransaction / sendrawtransaction ----
+
+pub(super) fn transaction_json(tx: &Transaction, block: Option<&TxBlockContext>) -> Value {
+    let raw = bincode::serialize(tx).unwrap_or_default();
+    let size = raw.len();
+    let coinbase = tx.is_coinbase();
+
+    let vin: Vec<Value> = tx
+        .inputs()
+        .iter()
+        .map(|input| {
+            let mut entry = if coinbase {
+                json!({ "coinbase": hex::encode(input.signature_script()) })
+            } else {
+                json!({
+                    "txid": hex::encode(input.prev_tx_hash()),
+                    "vout": input.prev_output_index(),
+                    "scriptSig": { "hex": hex::encode(input.signature_script()) },
+                })
+            };
+            if input.has_witness() {
+                entry["txinwitness"] = input.witness().iter().map(hex::encode).collect();
+            }
+            entry["sequence"] = json!(input.sequence());
+            entry
+        })
+        .collect();
+
+    let vout: Vec<Value> = tx
+        .outputs()
+        .iter()
+        .enumerate()
+        .map(|(n, output)| {
+            let class = classify(output.script_pubkey());
+            let kind = class.kind();
+            let mut script = json!({
+                "hex": hex::encode(output.script_pubkey()),
+                "type": core_script_type(kind).unwrap_or("nonstandard"),
+            });
+            if let Some(address) = class.address() {
+                script["address"] = Value::String(address);
+            }
+            let mut entry = json!({
+                "value": amount(output.amount()),
+                "n": n,
+                "scriptPubKey": script,
+            });
+            if core_script_type(kind).is_none() {
+                entry["extensions"] = json!({ "script_kind": kind.as_str() });
+            }
+            entry
+        })
+        .collect();
+
+    let mut result = json!({
+        "txid": hex::encode(tx.hash()),
+        "hash": hex::encode(tx.hash()),
+        "version": tx.version(),
+        "size": size,
+        "vsize": size,
+        "weight": size * 4,
+        "locktime": tx.lock_time(),
+        "vin": vin,
+        "vout": vout,
+        "hex": hex::encode(&raw),
+    });
+
+    if let Some(signature) = tx.signature_data() {
+        result["extensions"] = json!({
+            "signature_scheme": signature.scheme,
+            "security_level": signature.security_level,
+            "signature_size": signature.data.len(),
+            "public_key_size": signature.public_key.len(),
+        });
+    }
+
+    if let Some(block) = block {
+        if let Some(in_active_chain) = block.in_active_chain {
+            result["in_active_chain"] = json!(in_active_chain);
+        }
+        result["blockhash"] = Value::String(hex::encode(block.block_hash));
+        result["confirmations"] = json!(block.confirmations);
+        result["time"] = json!(block.time);
+        result["blocktime"] = json!(block.time);
+    }
+    result
+}
+
+fn tx_block_context(
+    storage: &BlockchainDB,
+    block: &Block,
+    named: bool,
+) -> Result<TxBlockContext, JsonRpcError> {
+    let context = block_context(storage, block)?;
+    Ok(TxBlockContext {
+        block_hash: block.hash(),
+        confirmations: context.confirmations,
+        time: block.timestamp(),
+        in_active_chain: named.then_some(context.confirmations >= 0),
+    })
+}
+
+fn get_raw_transaction(params: &Value, node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let txid = parse_hash(param(params, 0, "txid"), "txid")?;
+    let verbosity = parse_verbosity(param(params, 1, "verbose"), 0, 2)?;
+    if verbosity == 2 {
+        return Err(unsupported(
+            "getrawtransaction verbosity 2 is not supported: this node keeps no undo data to report prevouts and fees",
+        ));
+    }
+    let storage = node.storage();
+
+    let (tx, context) = match param(params, 2, "blockhash") {
+        Some(block_hash) => {
+            let block_hash = parse_hash(Some(block_hash), "blockhash")?;
+            let block = storage
+                .get_block(&block_hash)
+                .map_err(storage_error)?
+                .ok_or_else(|| error(RPC_INVALID_ADDRESS_OR_KEY, "Block hash not found"))?;
+            let tx = block
+                .transactions()
+                .iter()
+                .find(|tx| tx.hash() == txid)
+                .cloned()
+                .ok_or_else(|| {
+                    error(
+                        RPC_INVALID_ADDRESS_OR_KEY,
+                        "No such transaction found in the provided block",
+                    )
+                })?;
+            let context = tx_block_context(&storage, &block, true)?;
+            (tx, Some(context))
+        }
+        None => match node.mempool().get_transaction(&txid) {
+            Some(tx) => (tx, None),
+            None => {
+                let confirmed = node
+                    .chain_state()
+                    .read()
+                    .map_err(|_| error(RPC_MISC_ERROR, "Chain state lock poisoned"))?
+                    .get_transaction_by_id(&txid)
+                    .map_err(storage_error)?;
+                let tx = match confirmed {
+                    Some(confirmed) => confirmed.transaction,
+                    None => storage.get_transaction(&txid).map_err(storage_error)?.ok_or_else(|| {
+                        error(
+                            RPC_INVALID_ADDRESS_OR_KEY,
+                            "No such mempool or blockchain transaction. Use gettransaction for wallet transactions.",
+                        )
+                    })?,
+                };
+                let block = match storage
+                    .get_transaction_block(&txid)
+                    .map_err(storage_error)?
+                {
+                    Some(hash) => storage.get_block(&hash).map_err(storage_error)?,
+                    None => None,
+                };
+                let context = block
+                    .map(|block| tx_block_context(&storage, &block, false))
+                    .transpose()?;
+                (tx, context)
+            }
+        },
+    };
+
+    if verbosity == 0 {
+        let raw = bincode::serialize(&tx).map_err(|e| {
+            error(
+                RPC_MISC_ERROR,
+                format!("Failed to serialize transaction: {}", e),
+            )
+        })?;
+        return Ok(Value::String(hex::encode(raw)));
+    }
+    Ok(transaction_json(&tx, context.as_ref()))
+}
+
+fn send_raw_transaction(params: &Value, node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let raw = param(params, 0, "hexstring")
+        .ok_or_else(|| error(RPC_INVALID_PARAMETER, "hexstring is required"))?
+        .as_str()
+        .ok_or_else(|| error(RPC_TYPE_ERROR, "hexstring must be a string"))?;
+    if let Some(max_fee_rate) = param(params, 1, "maxfeerate") {
+        if max_fee_rate.as_f64() != Some(0.0) {
+            return Err(unsupported(
+                "maxfeerate is not supported: pass 0 and check the fee before submitting",
+            ));
+        }
+    }
+    let max_burn = match param(params, 2, "maxburnamount") {
+        Some(value) => value.as_f64().filter(|v| *v >= 0.0).ok_or_else(|| {
+            error(
+                RPC_TYPE_ERROR,
+                "maxburnamount must be a non-negative amount",
+            )
+        })?,
+        None => 0.0,
+    };
+
+    let tx: Transaction = hex::decode(raw)
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .ok_or_else(|| error(RPC_DESERIALIZATION_ERROR, "TX decode failed"))?;
+    let txid = tx.hash();
+
+    let burned: u64 = tx
+        .outputs()
+        .iter()
+        .filter(|output| classify(output.script_pubkey()).kind() == ScriptKind::NullData)
+        .map(|output| output.amount())
+        .sum();
+    if burned as f64 / NOVAS_PER_NOVA as f64 > max_burn {
+        return Err(error(
+            RPC_VERIFY_ERROR,
+            "Unspendable output exceeds maximum configured by user (maxburnamount)",
+        ));
+    }
+
+    if node
+        .storage()
+        .get_transaction_block(&txid)
+        .map_err(storage_error)?
+        .is_some()
+    {
+        return Err(error(
+            RPC_VERIFY_ALREADY_IN_CHAIN,
+            "Transaction already in block chain",
+        ));
+    }
+
+    match submit_transaction(node, &tx) {
+        // Core answers a resubmission of a mempool transaction with its txid
+        Ok(())
+        | Err(MempoolError::TransactionExists(_))
+        | Err(MempoolError::DuplicateTransaction) => Ok(Value::String(hex::encode(txid))),
+        Err(e) => Err(JsonRpcError {
+            code: RPC_VERIFY_REJECTED,
+            message: e.reason_code().to_string(),
+            data: Some(json!({ "reason": e.to_string() })),
+        }),
+    }
+}
+
+// ---- estimatesmartfee ----
+
+/// Core's estimate modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum EstimateMode {
+    Economical,
+    Conservative,
+}
+
+fn parse_estimate_mode(value: Option<&Value>) -> Result<EstimateMode, JsonRpcError> {
+    let mode = match value {
+        None => return Ok(EstimateMode::Conservative),
+        Some(value) => value
+            .as_str()
+            .ok_or_else(|| error(RPC_TYPE_ERROR, "estimate_mode must be a string"))?,
+    };
+    match mode.to_ascii_lowercase().as_str() {
+        "unset" | "conservative" => Ok(EstimateMode::Conservative),
+        "economical" => Ok(EstimateMode::Economical),
+        _ => Err(error(
+            RPC_INVALID_PARAMETER,
+            "Invalid estimate_mode parameter",
+        )),
+    }
+}
+
+pub(super) fn smart_fee_json(
+    estimate: Result<TransactionFees, String>,
+    mode: EstimateMode,
+    conf_target: u64,
+) -> Value {
+    match estimate {
+        Ok(fees) => {
+            let rate = match mode {
+                EstimateMode::Economical => fees.normal_priority,
+                EstimateMode::Conservative => fees.high_priority,
+            };
+            json!({
+                "feerate": fee_rate_per_kvb(rate),
+                "blocks": conf_target,
+                "extensions": { "fee_rate_per_byte": rate },
+            })
+        }
+        Err(e) => json!({
+            "errors": [e],
+            "blocks": conf_target,
+        }),
+    }
+}
+
+fn estimate_smart_fee(params: &Value, node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let conf_target = param(params, 0, "conf_target")
+        .ok_or_else(|| error(RPC_INVALID_PARAMETER, "conf_target is required"))?
+        .as_u64()
+        .filter(|target| (1..=MAX_CONF_TARGET).contains(target))
+        .ok_or_else(|| {
+            error(
+                RPC_INVALID_PARAMETER,
+                format!(
+                    "Invalid conf_target, must be between 1 and {}",
+                    MAX_CONF_TARGET
+                ),
+            )
+        })?;
+    let mode = parse_estimate_mode(param(params, 1, "estimate_mode"))?;
+    let estimate = node
+        .mempool()
+        .estimate_fee(conf_target as u32)
+        .map_err(|e| e.to_string());
+    Ok(smart_fee_json(estimate, mode, conf_target))
+}
+
+// ---- getmempoolinfo ----
+
+/// Core's `maxmempool` is a byte limit; Supernova caps the transaction
+/// count instead, so that limit is reported under `extensions` only.
+pub(super) fn mempool_info_json(info: &MempoolInfo, usage: u64, max_transactions: u64) -> Value {
+    json!({
+        "loaded": true,
+        "size": info.transaction_count,
+        "bytes": info.total_size,
+        "usage": usage,
+        "total_fee": amount(info.total_fee),
+        "mempoolminfee": fee_rate_per_kvb(info.min_fee_rate),
+        "minrelaytxfee": fee_rate_per_kvb(info.min_fee_rate),
+        "extensions": {
+            "max_transactions": max_transactions,
+            "min_fee_rate_per_byte": info.min_fee_rate,
+            "max_fee_rate_per_byte": info.max_fee_rate,
+            "avg_fee_rate_per_byte": info.avg_fee_rate,
+        },
+    })
+}
+
+fn get_mempool_info(node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let mempool = node.mempool();
+    let max_transactions = node
+        .config()
+        .read()
+        .map_err(|e| error(RPC_MISC_ERROR, format!("Failed to read config: {}", e)))?
+        .mempool
+        .max_size as u64;
+    Ok(mempool_info_json(
+        &mempool.get_info(),
+        mempool.get_memory_usage(),
+        max_transactions,
+    ))
+}
+
+// ---- getpeerinfo ----
+
+/// A peer in Core's layout. The network reports times as seconds elapsed;
+/// Core reports UNIX times, so they are taken back from `now`.
+pub(super) fn peer_json(peer: &PeerInfo, now: u64) -> Value {
+    let mut result = json!({
+        "id": peer.id,
+        "addr": peer.address,
+        "lastsend": now.saturating_sub(peer.last_send),
+        "lastrecv": now.saturating_sub(peer.last_recv),
+        "bytessent": peer.bytes_sent,
+        "bytesrecv": peer.bytes_received,
+        "conntime": now.saturating_sub(peer.connected_time),
+        "subver": peer.user_agent,
+        "inbound": peer.direction == "inbound",
+        "startingheight": peer.height,
+    });
+    if let Some(ping_ms) = peer.ping_time {
+        result["pingtime"] = json!(ping_ms / 1000.0);
+    }
+    // Core's version is the numeric protocol version
+    if let Ok(version) = peer.version.parse::<u64>() {
+        result["version"] = json!(version);
+    }
+    let mut extensions = Map::new();
+    extensions.insert("services".to_string(), json!(peer.services));
+    extensions.insert("banned".to_string(), json!(peer.banned));
+    extensions.insert("reputation_score".to_string(), json!(peer.reputation_score));
+    extensions.insert("encryption".to_string(), json!(peer.encryption));
+    if peer.version.parse::<u64>().is_err() {
+        extensions.insert("version".to_string(), json!(peer.version));
+    }
+    result["extensions"] = Value::Object(extensions);
+    result
+}
+
+async fn get_peer_info(node: &ApiFacade) -> Result<Value, JsonRpcError> {
+    let peers = node
+        .network()
+        .get_peers()
+        .await
+        .map_err(|e| error(RPC_MISC_ERROR, format!("Failed to get peers: {}", e)))?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(Value::Array(
+        peers.iter().map(|peer| peer_json(peer, now)).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+
+    const BITS: u32 = 0x207fffff;
+
+    fn p2wpkh_script() -> Vec<u8> {
+        let mut script = vec![0x00, 0x14];
+        script.extend_from_slice(&[0x11; 20]);
+        script
+    }
+
+    fn coinbase() -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(vec![0x01, 0x02])],
+            vec![TransactionOutput::new(5_000_000_000, p2wpkh_script())],
+            0,
+        )
+    }
+
+    fn spend() -> Transaction {
+        Transaction::new(
+            2,
+            vec![TransactionInput::new_with_witness(
+                [0xaa; 32],
+                1,
+                vec![],
+                0xfffffffd,
+                vec![vec![0x30, 0x44], vec![0x02]],
+            )],
+            vec![
+                TransactionOutput::new(150_000_000, p2wpkh_script()),
+                TransactionOutput::new(0, vec![0x6a, 0x02, 0xbe, 0xef]),
+            ],
+            100,
+        )
+    }
+
+    fn fixture_block(height: u64) -> Block {
+        let header = BlockHeader::new_with_height(
+            0x2000_0000,
+            [0x22; 32],
+            [0x33; 32],
+            1_700_000_000,
+            BITS,
+            7,
+            height,
+        );
+        Block::new(header, vec![coinbase(), spend()])
+    }
+
+    fn main_chain_context() -> BlockContext {
+        BlockContext {
+            confirmations: 3,
+            median_time: 1_699_999_000,
+            next_block_hash: Some([0x44; 32]),
+        }
+    }
+
+    #[test]
+    fn blockchain_info_golden() {
+        let info = ChainInfo {
+            network_id: "testnet".to_string(),
+            height: 120,
+            best_block_hash: [0x0b; 32],
+            bits: BITS,
+            time: 1_700_000_600,
+            median_time: 1_700_000_000,
+            total_difficulty: 0x1234,
+            verification_progress: 1.0,
+            size_on_disk: 4096,
+            prune_height: None,
+        };
+        assert_eq!(
+            blockchain_info_json(&info),
+            json!({
+                "chain": "test",
+                "blocks": 120,
+                "headers": 120,
+                "bestblockhash": "0b".repeat(32),
+                "difficulty": calculate_difficulty_from_bits(BITS),
+                "time": 1_700_000_600u64,
+                "mediantime": 1_700_000_000u64,
+                "verificationprogress": 1.0,
+                "initialblockdownload": false,
+                "chainwork": format!("{}1234", "0".repeat(60)),
+                "size_on_disk": 4096,
+                "pruned": false,
+                "warnings": "",
+                "extensions": { "network_id": "testnet", "total_difficulty": 0x1234 },
+            })
+        );
+
+        // Networks without a Core name keep their own
+        let custom = ChainInfo {
+            network_id: "carbonnet".to_string(),
+            verification_progress: 0.5,
+            ..info
+        };
+        let value = blockchain_info_json(&custom);
+        assert_eq!(value["chain"], json!("carbonnet"));
+        assert_eq!(value["initialblockdownload"], json!(true));
+
+        // Pruned nodes report the lowest block they still store
+        let pruned = ChainInfo {
+            prune_height: Some(20),
+            ..custom
+        };
+        let value = blockchain_info_json(&pruned);
+        assert_eq!(value["pruned"], json!(true));
+        assert_eq!(value["pruneheight"], json!(20));
+    }
+
+    #[test]
+    fn getblock_verbosity_matrix() {
+        let block = fixture_block(5);
+        let context = main_chain_context();
+        let size = bincode::serialized_size(&block).unwrap();
+        let txids: Vec<Value> = block
+            .transactions()
+            .iter()
+            .map(|tx| json!(hex::encode(tx.hash())))
+            .collect();
+
+        // 0: the serialized block
+        assert_eq!(
+            block_json(&block, 0, &context).unwrap(),
+            json!(hex::encode(bincode::serialize(&block).unwrap()))
+        );
+
+        // 1: header fields and txids
+        let expected = json!({
+            "hash": hex::encode(block.hash()),
+            "confirmations": 3,
+            "height": 5,
+            "version": 0x2000_0000u32,
+            "versionHex": "20000000",
+            "merkleroot": "33".repeat(32),
+            "time": 1_700_000_000u64,
+            "mediantime": 1_699_999_000u64,
+            "nonce": 7,
+            "bits": "207fffff",
+            "difficulty": calculate_difficulty_from_bits(BITS),
+            "nTx": 2,
+            "strippedsize": size,
+            "size": size,
+            "weight": size * 4,
+            "tx": txids,
+            "previousblockhash": "22".repeat(32),
+            "nextblockhash": "44".repeat(32),
+            "extensions": { "quantum_signed_transactions": 0 },
+        });
+        assert_eq!(block_json(&block, 1, &context).unwrap(), expected);
+
+        // 2: the same with decoded transactions
+        let verbose = block_json(&block, 2, &context).unwrap();
+        let mut expected_verbose = expected.clone();
+        expected_verbose["tx"] = block
+            .transactions()
+            .iter()
+            .map(|tx| transaction_json(tx, None))
+            .collect();
+        assert_eq!(verbose, expected_verbose);
+
+        // 3: prevouts need undo data, refused rather than faked
+        let err = block_json(&block, 3, &context).unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+        assert_eq!(err.data, Some(json!({ "unsupported": true })));
+
+        // Genesis has no previous block; stale blocks have -1 confirmations
+        let stale = BlockContext {
+            confirmations: -1,
+            median_time: 0,
+            next_block_hash: None,
+        };
+        let genesis = block_json(&fixture_block(0), 1, &stale).unwrap();
+        assert!(genesis.get("previousblockhash").is_none());
+        assert!(genesis.get("nextblockhash").is_none());
+        assert_eq!(genesis["confirmations"], json!(-1));
+    }
+
+    #[test]
+    fn chain_position_from_storage() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = BlockchainDB::new(temp_dir.path()).unwrap();
+
+        // Twelve blocks whose timestamps run backwards, so the median differs
+        // from the tip's own time
+        let mut chain = Vec::new();
+        let mut prev = [0u8; 32];
+        for height in 0..12u64 {
+            let header = BlockHeader::new_with_height(
+                1,
+                prev,
+                [0x33; 32],
+                1_700_000_000 - height * 10,
+                BITS,
+                height as u32,
+                height,
+            );
+            let block = Block::new(header, vec![coinbase()]);
+            storage.insert_block(&block).unwrap();
+            storage
+                .store_block_height_index(height, &block.hash())
+                .unwrap();
+            prev = block.hash();
+            chain.push(block);
+        }
+        storage.set_height(11).unwrap();
+
+        assert_eq!(
+            get_block_hash(&json!([4]), &storage).unwrap(),
+            json!(hex::encode(chain[4].hash()))
+        );
+        assert_eq!(
+            get_block_hash(&json!({ "height": 0 }), &storage).unwrap(),
+            json!(hex::encode(chain[0].hash()))
+        );
+        let err = get_block_hash(&json!([12]), &storage).unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+        assert_eq!(err.message, "Block height out of range");
+
+        let context = block_context(&storage, &chain[4]).unwrap();
+        assert_eq!(context.confirmations, 8);
+        assert_eq!(context.next_block_hash, Some(chain[5].hash()));
+        // Heights 0..=4 have times 1_700_000_000 down to 1_699_999_960
+        assert_eq!(context.median_time, 1_699_999_980);
+
+        let tip = block_context(&storage, &chain[11]).unwrap();
+        assert_eq!(tip.confirmations, 1);
+        assert_eq!(tip.next_block_hash, None);
+        // The window is the eleven blocks ending at the tip: heights 1..=11
+        assert_eq!(tip.median_time, 1_699_999_940);
+
+        // A block that lost its height to another is off the main chain
+        let stale = BlockHeader::new_with_height(1, chain[3].hash(), [0x99; 32], 0, BITS, 0, 4);
+        let stale = Block::new(stale, vec![coinbase()]);
+        assert_eq!(block_context(&storage, &stale).unwrap().confirmations, -1);
+    }
+
+    #[test]
+    fn verbosity_argument_forms() {
+        assert_eq!(parse_verbosity(None, 1, 3).unwrap(), 1);
+        assert_eq!(parse_verbosity(Some(&json!(false)), 1, 3).unwrap(), 0);
+        assert_eq!(parse_verbosity(Some(&json!(true)), 1, 3).unwrap(), 1);
+        assert_eq!(parse_verbosity(Some(&json!(2)), 1, 3).unwrap(), 2);
+        assert_eq!(
+            parse_verbosity(Some(&json!(4)), 1, 3).unwrap_err().code,
+            RPC_INVALID_PARAMETER
+        );
+        assert_eq!(
+            parse_verbosity(Some(&json!(-1)), 1, 3).unwrap_err().code,
+            RPC_INVALID_PARAMETER
+        );
+        assert_eq!(
+            parse_verbosity(Some(&json!("1")), 1, 3).unwrap_err().code,
+            RPC_TYPE_ERROR
+        );
+    }
+
+    #[test]
+    fn raw_transaction_golden() {
+        let tx = spend();
+        let raw = bincode::serialize(&tx).unwrap();
+        let context = TxBlockContext {
+            block_hash: [0x55; 32],
+            confirmations: 2,
+            time: 1_700_000_000,
+            in_active_chain: Some(true),
+        };
+        assert_eq!(
+            transaction_json(&tx, Some(&context)),
+            json!({
+                "txid": hex::encode(tx.hash()),
+                "hash": hex::encode(tx.hash()),
+                "version": 2,
+                "size": raw.len(),
+                "vsize": raw.len(),
+                "weight": raw.len() * 4,
+                "locktime": 100,
+                "vin": [{
+                    "txid": "aa".repeat(32),
+                    "vout": 1,
+                    "scriptSig": { "hex": "" },
+                    "txinwitness": ["3044", "02"],
+                    "sequence": 0xfffffffdu32,
+                }],
+                "vout": [
+                    {
+                        "value": 1.5,
+                        "n": 0,
+                        "scriptPubKey": {
+                            "hex": hex::encode(p2wpkh_script()),
+                            "type": "witness_v0_keyhash",
+                        },
+                    },
+                    {
+                        "value": 0.0,
+                        "n": 1,
+                        "scriptPubKey": { "hex": "6a02beef", "type": "nulldata" },
+                    },
+                ],
+                "hex": hex::encode(&raw),
+                "in_active_chain": true,
+                "blockhash": "55".repeat(32),
+                "confirmations": 2,
+                "time": 1_700_000_000u64,
+                "blocktime": 1_700_000_000u64,
+            })
+        );
+
+        // Coinbase inputs use Core's coinbase layout; mempool transactions
+        // carry no block fields
+        let value = transaction_json(&coinbase(), None);
+        assert_eq!(
+            value["vin"],
+            json!([{ "coinbase": "0102", "sequence": 0xffffffffu32 }])
+        );
+        assert_eq!(value["vout"][0]["value"], json!(50.0));
+        assert!(value.get("blockhash").is_none());
+        assert!(value.get("confirmations").is_none());
+    }
+
+    #[test]
+    fn mempool_info_golden() {
+        let info = MempoolInfo {
+            transaction_count: 3,
+            total_size: 900,
+            total_fee: 12_000,
+            min_fee_rate: 1,
+            max_fee_rate: 40,
+            avg_fee_rate: 13,
+        };
+        assert_eq!(
+            mempool_info_json(&info, 20_000, 5_000),
+            json!({
+                "loaded": true,
+                "size": 3,
+                "bytes": 900,
+                "usage": 20_000,
+                "total_fee": 0.00012,
+                "mempoolminfee": 0.00001,
+                "minrelaytxfee": 0.00001,
+                "extensions": {
+                    "max_transactions": 5_000,
+                    "min_fee_rate_per_byte": 1,
+                    "max_fee_rate_per_byte": 40,
+                    "avg_fee_rate_per_byte": 13,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn peer_info_golden() {
+        let peer = PeerInfo {
+            id: 4,
+            address: "12D3KooWPeer".to_string(),
+            direction: "inbound".to_string(),
+            connected_time: 600,
+            last_send: 5,
+            last_recv: 2,
+            bytes_sent: 1_000,
+            bytes_received: 2_000,
+            ping_time: Some(250.0),
+            version: "70015".to_string(),
+            user_agent: "/supernova:1.0.0/".to_string(),
+            height: 321,
+            services: "1".to_string(),
+            banned: false,
+            reputation_score: 1.0,
+            encryption: "encrypted".to_string(),
+        };
+        assert_eq!(
+            peer_json(&peer, 10_000),
+            json!({
+                "id": 4,
+                "addr": "12D3KooWPeer",
+                "lastsend": 9_995,
+                "lastrecv": 9_998,
+                "bytessent": 1_000,
+                "bytesrecv": 2_000,
+                "conntime": 9_400,
+                "pingtime": 0.25,
+                "version": 70015,
+                "subver": "/supernova:1.0.0/",
+                "inbound": true,
+                "startingheight": 321,
+                "extensions": {
+                    "services": "1",
+                    "banned": false,
+                    "reputation_score": 1.0,
+                    "encryption": "encrypted",
+                },
+            })
+        );
+
+        // A version Core can't represent moves to extensions; no ping, no pingtime
+        let other = PeerInfo {
+            direction: "outbound".to_string(),
+            ping_time: None,
+            version: "unknown".to_string(),
+            ..peer
+        };
+        let value = peer_json(&other, 10_000);
+        assert_eq!(value["inbound"], json!(false));
+        assert!(value.get("pingtime").is_none());
+        assert!(value.get("version").is_none());
+        assert_eq!(value["extensions"]["version"], json!("unknown"));
+    }
+
+    #[test]
+    fn estimate_smart_fee_golden() {
+        let fees = || TransactionFees {
+            low_priority: 1,
+            normal_priority: 2,
+            high_priority: 5,
+            target_blocks: 6,
+        };
+        assert_eq!(
+            smart_fee_json(Ok(fees()), EstimateMode::Conservative, 6),
+            json!({
+                "feerate": 0.00005,
+                "blocks": 6,
+                "extensions": { "fee_rate_per_byte": 5 },
+            })
+        );
+        assert_eq!(
+            smart_fee_json(Ok(fees()), EstimateMode::Economical, 6)["feerate"],
+            json!(0.00002)
+        );
+        assert_eq!(
+            smart_fee_json(Err("no data".to_string()), EstimateMode::Economical, 2),
+            json!({ "errors": ["no data"], "blocks": 2 })
+        );
+
+        assert_eq!(
+            parse_estimate_mode(Some(&json!("ECONOMICAL"))).unwrap(),
+            EstimateMode::Economical
+        );
+        assert_eq!(
+            parse_estimate_mode(None).unwrap(),
+            EstimateMode::Conservative
+        );
+        assert_eq!(
+            parse_estimate_mode(Some(&json!("fast"))).unwrap_err().code,
+            RPC_INVALID_PARAMETER
+        );
+    }
+
+    #[test]
+    fn params_by_position_or_name() {
+        let positional = json!(["ab", 2]);
+        let named = json!({ "blockhash": "ab", "verbosity": 2 });
+        assert_eq!(param(&positional, 1, "verbosity"), Some(&json!(2)));
+        assert_eq!(param(&named, 1, "verbosity"), Some(&json!(2)));
+        assert_eq!(param(&json!(["ab", null]), 1, "verbosity"), None);
+        assert_eq!(param(&Value::Null, 0, "blockhash"), None);
+
+        let err = parse_hash(Some(&json!("abcd")), "blockhash").unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+        assert!(err.message.contains("length 64"));
+    }
+
+    #[test]
+    fn script_types_map_to_core_names() {
+        assert_eq!(core_script_type(ScriptKind::P2pkh), Some("pubkeyhash"));
+        assert_eq!(
+            core_script_type(ScriptKind::P2wsh),
+            Some("witness_v0_scripthash")
+        );
+        assert_eq!(core_script_type(ScriptKind::QuantumPkh), None);
+        assert_eq!(chain_name("supernova-mainnet"), "main");
+        assert_eq!(chain_name("regtest"), "regtest");
+    }
+}
@@ -5,16 +5,34 @@
 use std::sync::Arc;
 use actix_web::web;
 use serde_json::{Value, json};
-use crate::api_facade::ApiFacade;
+use crate::api_facade::{ApiFacade, BlockSubmitError, ChainAdminOp};
+use crate::network::SyncProgress;
+use super::compat;
+use super::params::{Params, Verbosity};
 use super::types::{JsonRpcError, ErrorCode};
 use supernova_core::blockchain::{calculate_difficulty_from_bits, calculate_hashrate};
 
+/// Methods that would hand out stale work while the node is catching up
+const SYNC_REQUIRED_METHODS: &[&str] = &["getblocktemplate", "generate"];
+
 /// Dispatch method to appropriate handler
 pub async fn dispatch(
     method: &str,
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
+    if SYNC_REQUIRED_METHODS.contains(&method) {
+        if let Some(progress) = node.sync_progress().filter(|p| !p.synced) {
+            return Err(syncing_error(&progress));
+        }
+    }
+
+    if compat::enabled(&node) {
+        if let Some(result) = compat::dispatch(method, &params, &node).await {
+            return result;
+        }
+    }
+
     match method {
         // General info method
         "getinfo" => get_info(params, node).await,
@@ -40,6 +58,7 @@ pub async fn dispatch(
         "getnetworkinfo" => get_network_info(params, node).await,
         "getpeerinfo" => get_peer_info(params, node).await,
         "getlocalpeerid" => get_local_peer_id(params, node).await,
+        "getsyncprogress" => get_sync_progress(params, node).await,
 
         // Mining methods
         "getmininginfo" => get_mining_info(params, node).await,
@@ -57,12 +76,19 @@ pub async fn dispatch(
         "getbalance" => get_balance(params, node).await,
         "listunspent" => list_unspent(params, node).await,
         "sendtoaddress" => send_to_address(params, node).await,
+        "bumpfee" => bump_fee(params, node).await,
+        "parkrawtransaction" => park_raw_transaction(params, node).await,
+        "listpendingfinal" => list_pending_final(params, node).await,
         
         // Network admin methods
         "addnode" => add_node(params, node).await,
         
         // Test/admin methods
         "addtestutxo" => add_test_utxo(params, node).await,
+        "rollbackchain" => rollback_chain(params, node).await,
+        "invalidateblock" => invalidate_block(params, node).await,
+        "reconsiderblock" => reconsider_block(params, node).await,
+        "rotateidentity" => rotate_identity(node).await,
 
         // Method not found
         _ => Err(JsonRpcError {
@@ -151,13 +177,17 @@ async fn get_info(
     }))
 }
 
-// Extract parameters from value with proper error handling
-fn extract_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, JsonRpcError> {
-    serde_json::from_value(params).map_err(|e| JsonRpcError {
-        code: ErrorCode::InvalidParams as i32,
-        message: format!("Invalid parameters: {}", e),
-        data: None,
-    })
+/// Error for a call refused until the node has caught up
+fn syncing_error(progress: &SyncProgress) -> JsonRpcError {
+    JsonRpcError {
+        code: ErrorCode::NodeSyncing as i32,
+        message: format!("Node is syncing: {}", progress.summary()),
+        data: Some(json!({
+            "current_height": progress.current_height,
+            "target_height": progress.target_height,
+            "percent": progress.percent,
+        })),
+    }
 }
 
 /// Get blockchain information
@@ -210,7 +240,7 @@ async fn get_blockchain_info(
         "difficulty": difficulty,
         "mediantime": median_time,
         "verificationprogress": verification_progress,
-        "pruned": false,
+        "pruned": node.prune_height().is_some(),
         "chainwork": chain_work,
         "size_on_disk": size_on_disk,
     }))
@@ -221,20 +251,14 @@ async fn get_block(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Extract parameters
-    #[derive(serde::Deserialize)]
-    struct Params {
-        blockhash: String,
-        #[serde(default = "default_verbosity")]
-        verbosity: u8,
-    }
-
-    fn default_verbosity() -> u8 { 1 }
-
-    let params: Params = extract_params(params)?;
+    let params = Params::new(params)?;
+    let blockhash: String = params.required(0, "blockhash")?;
+    let verbosity = params
+        .optional::<Verbosity>(1, "verbosity")?
+        .map_or(1, Verbosity::level);
 
     // Parse hash from hex
-    let hash_bytes = hex::decode(&params.blockhash).map_err(|_| JsonRpcError {
+    let hash_bytes = hex::decode(&blockhash).map_err(|_| JsonRpcError {
         code: ErrorCode::InvalidParams as i32,
         message: "Invalid block hash format".to_string(),
         data: None,
@@ -262,13 +286,13 @@ async fn get_block(
         Some(block) => block,
         None => return Err(JsonRpcError {
             code: ErrorCode::BlockchainError as i32,
-            message: format!("Block not found: {}", params.blockhash),
+            message: format!("Block not found: {}", blockhash),
             data: None,
         }),
     };
 
     // Format response based on verbosity
-    match params.verbosity {
+    match verbosity {
         0 => {
             // Return hex-encoded serialized block
             let serialized = bincode::serialize(&block).map_err(|e| JsonRpcError {
@@ -282,13 +306,13 @@ async fn get_block(
         1 | 2 => {
             // Format block as JSON
             let mut txids = Vec::with_capacity(block.transactions().len());
-            let mut txs = Vec::with_capacity(if params.verbosity == 2 { block.transactions().len() } else { 0 });
+            let mut txs = Vec::with_capacity(if verbosity == 2 { block.transactions().len() } else { 0 });
 
             for tx in block.transactions() {
                 let txid = tx.hash();
                 txids.push(hex::encode(txid));
 
-                if params.verbosity == 2 {
+                if verbosity == 2 {
                     // Full transaction details for verbosity 2
                     txs.push(format_transaction(tx));
                 }
@@ -307,7 +331,7 @@ async fn get_block(
                 "height": block.height(),
                 "version": block.version(),
                 "merkleroot": hex::encode(block.merkle_root()),
-                "tx": if params.verbosity == 2 { Value::Array(txs) } else { Value::Array(txids.into_iter().map(Value::String).collect()) },
+                "tx": if verbosity == 2 { Value::Array(txs) } else { Value::Array(txids.into_iter().map(Value::String).collect()) },
                 "time": block.timestamp(),
                 "nonce": block.nonce(),
                 "bits": format!("{:08x}", block.header().bits()),
@@ -336,30 +360,7 @@ async fn get_block_hash(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Extract height parameter
-    let height = match params {
-        Value::Array(arr) if !arr.is_empty() => {
-            match &arr[0] {
-                Value::Number(n) => {
-                    n.as_u64().ok_or_else(|| JsonRpcError {
-                        code: ErrorCode::InvalidParams as i32,
-                        message: "Invalid height parameter (must be a non-negative integer)".to_string(),
-                        data: None,
-                    })?
-                },
-                _ => return Err(JsonRpcError {
-                    code: ErrorCode::InvalidParams as i32,
-                    message: "Invalid height parameter (must be a number)".to_string(),
-                    data: None,
-                }),
-            }
-        },
-        _ => return Err(JsonRpcError {
-            code: ErrorCode::InvalidParams as i32,
-            message: "Missing height parameter".to_string(),
-            data: None,
-        }),
-    };
+    let height: u64 = Params::new(params)?.required(0, "height")?;
 
     // Get block hash
     let storage = node.storage();
@@ -458,33 +459,17 @@ async fn get_difficulty(
     Ok(Value::Number(serde_json::Number::from_f64(difficulty).unwrap_or(serde_json::Number::from(0))))
 }
 
-/// Get transaction information (renamed to avoid conflict)
+/// Get transaction information (renamed to avoid conflict). Falls back to
+/// the transaction index and the mempool for transactions the wallet
+/// doesn't know.
 async fn get_transaction_rpc(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Parse txid parameter
-    let txid_str = match params {
-        Value::Array(ref arr) if !arr.is_empty() => {
-            arr.get(0)
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| JsonRpcError {
-                    code: ErrorCode::InvalidParams as i32,
-                    message: "Missing or invalid txid parameter".to_string(),
-        data: None,
-                })?
-        }
-        _ => {
-            return Err(JsonRpcError {
-                code: ErrorCode::InvalidParams as i32,
-                message: "Missing txid parameter".to_string(),
-                data: None,
-            });
-        }
-    };
-    
+    let txid_str: String = Params::new(params)?.required(0, "txid")?;
+
     // Decode txid
-    let txid_bytes = hex::decode(txid_str)
+    let txid_bytes = hex::decode(&txid_str)
         .map_err(|_| JsonRpcError {
             code: ErrorCode::InvalidParams as i32,
             message: "Invalid txid format".to_string(),
@@ -511,53 +496,46 @@ async fn get_transaction_rpc(
             data: None,
         })?;
     
-    // Get transaction
-    let transaction = wallet.get_transaction(&txid)
+    let wallet_tx = wallet.get_transaction(&txid)
         .map_err(|e| JsonRpcError {
             code: -1,
             message: format!("Failed to get transaction: {}", e),
             data: None,
+        })?;
+    drop(wallet);
+
+    // Confirmed transactions are looked up in the transaction index
+    let confirmed = node
+        .chain_state()
+        .read()
+        .map_err(|_| JsonRpcError {
+            code: -1,
+            message: "Chain state lock poisoned".to_string(),
+            data: None,
         })?
-        .ok_or_else(|| JsonRpcError {
+        .get_transaction_by_id(&txid)
+        .map_err(|e| JsonRpcError {
+            code: -1,
+            message: format!("Failed to get transaction: {}", e),
+            data: None,
+        })?;
+
+    let transaction = match (&confirmed, wallet_tx) {
+        (Some(confirmed), _) => confirmed.transaction.clone(),
+        (None, Some(tx)) => tx,
+        (None, None) => node.mempool().get_transaction(&txid).ok_or_else(|| JsonRpcError {
             code: -5,
             message: format!("Transaction {} not found", txid_str),
             data: None,
-        })?;
-    
-    // Calculate confirmations from blockchain
-    let confirmations = {
-        let db = node.storage();
-        let chain_state = node.chain_state();
-
-        // Get the block containing this transaction
-        match db.get_transaction_block(&txid) {
-            Ok(Some(block_hash)) => {
-                // Get block height
-                match db.get_block_height(&block_hash) {
-                    Ok(Some(tx_block_height)) => {
-                        // Get current chain height
-                        match chain_state.read() {
-                            Ok(state) => {
-                                let current_height = state.get_height();
-                                // Confirmations = current_height - tx_block_height + 1
-                                if current_height >= tx_block_height {
-                                    (current_height - tx_block_height + 1) as i64
-                                } else {
-                                    0i64
-                                }
-                            }
-                            Err(_) => 0i64,
-                        }
-                    }
-                    Ok(None) | Err(_) => 0i64,
-                }
-            }
-            Ok(None) => {
-                // Transaction not in a block yet (in mempool)
-                0i64
-            }
-            Err(_) => 0i64,
-        }
+        })?,
+    };
+    let (confirmations, block_hash, time) = match &confirmed {
+        Some(confirmed) => (
+            confirmed.confirmations as i64,
+            Some(hex::encode(confirmed.block_hash)),
+            Some(confirmed.block_time),
+        ),
+        None => (0i64, None, None),
     };
 
     // Format transaction as JSON
@@ -570,6 +548,8 @@ async fn get_transaction_rpc(
         "vin": transaction.inputs().len(),
         "vout": transaction.outputs().len(),
         "confirmations": confirmations,
+        "block_hash": block_hash,
+        "time": time,
     }))
 }
 
@@ -578,28 +558,10 @@ async fn get_raw_transaction_rpc(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Parse txid parameter
-    let txid_str = match params {
-        Value::Array(ref arr) if !arr.is_empty() => {
-            arr.get(0)
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| JsonRpcError {
-                    code: ErrorCode::InvalidParams as i32,
-                    message: "Missing or invalid txid parameter".to_string(),
-        data: None,
-                })?
-        }
-        _ => {
-            return Err(JsonRpcError {
-                code: ErrorCode::InvalidParams as i32,
-                message: "Missing txid parameter".to_string(),
-                data: None,
-            });
-        }
-    };
-    
+    let txid_str: String = Params::new(params)?.required(0, "txid")?;
+
     // Decode txid
-    let txid_bytes = hex::decode(txid_str)
+    let txid_bytes = hex::decode(&txid_str)
         .map_err(|_| JsonRpcError {
             code: ErrorCode::InvalidParams as i32,
             message: "Invalid txid format".to_string(),
@@ -660,24 +622,7 @@ async fn send_raw_transaction(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Parse raw transaction hex parameter
-    let raw_tx_hex = match params {
-        Value::Array(ref arr) if !arr.is_empty() => arr
-            .get(0)
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| JsonRpcError {
-                code: ErrorCode::InvalidParams as i32,
-                message: "Missing or invalid raw transaction parameter".to_string(),
-                data: None,
-            })?,
-        _ => {
-            return Err(JsonRpcError {
-                code: ErrorCode::InvalidParams as i32,
-                message: "Missing raw transaction parameter".to_string(),
-                data: None,
-            });
-        }
-    };
+    let raw_tx_hex: String = Params::new(params)?.required(0, "hexstring")?;
 
     // Decode hex
     let tx_bytes = hex::decode(raw_tx_hex).map_err(|_| JsonRpcError {
@@ -697,15 +642,16 @@ async fn send_raw_transaction(
 
     let txid = hex::encode(transaction.hash());
 
-    // Submit to mempool via the existing mempool-acceptance validation path
-    match node.mempool().add_transaction(transaction.clone(), 1000) {
-        Ok(()) => {
-            // Broadcast to the P2P network directly (avoids re-adding to the
-            // mempool, which `ApiFacade::broadcast_transaction` would do and
-            // which would short-circuit on `TransactionExists`).
-            node.network().broadcast_transaction(&transaction);
-            Ok(Value::String(txid))
-        }
+    match submit_transaction(&node, &transaction) {
+        Ok(()) => Ok(Value::String(txid)),
+        Err(crate::mempool::MempoolError::NonFinal { min_height, min_time }) => Err(JsonRpcError {
+            code: -26,
+            message: format!(
+                "Transaction rejected: not final until height {} and median time {}",
+                min_height, min_time
+            ),
+            data: Some(json!({ "min_height": min_height, "min_time": min_time })),
+        }),
         Err(e) => Err(JsonRpcError {
             code: ErrorCode::InvalidParams as i32,
             message: format!("Transaction rejected: {}", e),
@@ -714,6 +660,20 @@ async fn send_raw_transaction(
     }
 }
 
+/// Submit a transaction to the mempool via the existing mempool-acceptance
+/// validation path and, once accepted, broadcast it.
+pub(super) fn submit_transaction(
+    node: &ApiFacade,
+    transaction: &supernova_core::types::transaction::Transaction,
+) -> Result<(), crate::mempool::MempoolError> {
+    node.mempool().add_transaction(transaction.clone(), 1000)?;
+    // Broadcast to the P2P network directly (avoids re-adding to the
+    // mempool, which `ApiFacade::broadcast_transaction` would do and
+    // which would short-circuit on `TransactionExists`).
+    node.network().broadcast_transaction(transaction);
+    Ok(())
+}
+
 /// Get mempool information
 async fn get_mempool_info(
     _params: Value,
@@ -746,16 +706,9 @@ async fn get_raw_mempool(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Extract verbose parameter
-    let verbose = match params {
-        Value::Array(arr) if !arr.is_empty() => {
-            match &arr[0] {
-                Value::Bool(b) => *b,
-                _ => false,
-            }
-        },
-        _ => false,
-    };
+    let verbose = Params::new(params)?
+        .optional::<bool>(0, "verbose")?
+        .unwrap_or(false);
 
     let mempool = node.mempool();
 
@@ -864,6 +817,7 @@ async fn get_peer_info(
             "pingtime": peer.ping_time,
             "version": peer.version,
             "direction": peer.direction,
+            "encryption": peer.encryption,
         })
     }).collect();
 
@@ -962,67 +916,64 @@ async fn get_mining_info(
     }))
 }
 
+/// Template request object `getblocktemplate` takes (BIP22)
+#[derive(Debug, Default, serde::Deserialize)]
+struct TemplateRequest {
+    /// Hold the call until the template with this id is out of date
+    #[serde(default)]
+    longpollid: Option<String>,
+}
+
 /// Get block template for mining
 async fn get_block_template(
-    _params: Value,
+    params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Get wallet manager for reward address
-    let wallet_manager = node.wallet_manager();
-    let wallet = wallet_manager.read()
+    let request: TemplateRequest = Params::new(params)?
+        .optional(0, "template_request")?
+        .unwrap_or_default();
+
+    let mining_config = node
+        .config()
+        .read()
+        .map(|c| c.mining.clone())
         .map_err(|_| JsonRpcError {
             code: -1,
-            message: "Wallet lock poisoned".to_string(),
+            message: "Config lock poisoned".to_string(),
+            data: None,
+        })?;
+
+    let templates = node.template_manager();
+    if let Some(longpollid) = &request.longpollid {
+        templates
+            .wait(
+                longpollid,
+                mining_config.longpoll_fee_delta,
+                std::time::Duration::from_secs(mining_config.longpoll_timeout_secs),
+            )
+            .await
+            .map_err(|e| JsonRpcError {
+                code: ErrorCode::InvalidParams as i32,
+                message: e.to_string(),
+                data: None,
+            })?;
+    }
+
+    // A template stamped from a skewed clock would be rejected or mislead
+    node.ensure_clock_sane().map_err(|e| JsonRpcError {
+        code: -1,
+        message: e.to_string(),
         data: None,
     })?;
 
-    // Generate reward address (or use existing)
-    let reward_address = wallet.generate_new_address(Some("mining_reward".to_string()))
-        .map_err(|e| JsonRpcError {
-            code: -1,
-            message: format!("Failed to generate reward address: {}", e),
-            data: None,
-        })?;
-    
-    let reward_addr = wallet::quantum_wallet::Address::from_str(&reward_address)
-        .map_err(|e| JsonRpcError {
-            code: -1,
-            message: format!("Invalid reward address: {}", e),
-            data: None,
-        })?;
-    
-    // Generate treasury address
-    let treasury_address = wallet.generate_new_address(Some("environmental_treasury".to_string()))
-        .map_err(|e| JsonRpcError {
-            code: -1,
-            message: format!("Failed to generate treasury address: {}", e),
-            data: None,
-        })?;
-    
-    let treasury_addr = wallet::quantum_wallet::Address::from_str(&treasury_address)
-        .map_err(|e| JsonRpcError {
-            code: -1,
-            message: format!("Invalid treasury address: {}", e),
-            data: None,
-        })?;
-    
-    drop(wallet); // Release wallet lock
-    
-    // Generate block template
-    use crate::mining::template::BlockTemplate;
-    let template = BlockTemplate::generate(
-        node.chain_state(),
-        node.mempool(),
-        &reward_addr,
-        &treasury_addr,
-    ).map_err(|e| JsonRpcError {
+    let (template, longpollid) = node.block_template().map_err(|e| JsonRpcError {
         code: -1,
-        message: format!("Failed to generate template: {}", e),
+        message: e.to_string(),
         data: None,
     })?;
-    
-    // Format as JSON-RPC response
     let mempool = node.mempool();
+
+    // Format as JSON-RPC response
     let transactions_json: Vec<Value> = template.transactions.iter().skip(1) // Skip coinbase
         .map(|tx| {
         let txid = tx.hash();
@@ -1049,6 +1000,7 @@ async fn get_block_template(
         "bits": format!("{:08x}", template.bits),
         "height": template.height,
         "merkleroot": hex::encode(template.merkle_root),
+        "longpollid": longpollid,
     }))
 }
 
@@ -1114,73 +1066,47 @@ async fn submit_block(
         });
     }
     
-    // Get chain state and process block.
-    // Use spawn_blocking so the std::sync::RwLock write guard is never held
-    // across the .await inside add_block on the async actix worker thread
-    // (mirrors the discipline in node.rs / network/mod.rs).
-    let chain_state = node.chain_state();
-    let block_for_add = block.clone();
-    // The guard is intentionally held across the await inside a dedicated
-    // spawn_blocking thread, so it cannot stall an actix worker's executor.
-    #[allow(clippy::await_holding_lock)]
-    let add_result = tokio::task::spawn_blocking(move || {
-        tokio::runtime::Handle::current().block_on(async move {
-            match chain_state.write() {
-                Ok(mut chain) => chain.add_block(&block_for_add).await,
-                Err(e) => Err(crate::storage::StorageError::DatabaseError(format!(
-                    "Chain state lock poisoned: {}",
-                    e
-                ))),
-            }
-        })
-    })
-    .await;
-    add_result
-        .map_err(|e| JsonRpcError {
-            code: -1,
-            message: format!("Block processing task failed: {}", e),
-            data: None,
-        })?
-        .map_err(|e| JsonRpcError {
+    node.submit_mined_block(&block)
+        .await
+        .map_err(|e| submit_block_error(&block, e))?;
+
+    // Success - return null
+    Ok(Value::Null)
+}
+
+fn submit_block_error(
+    block: &supernova_core::types::block::Block,
+    e: BlockSubmitError,
+) -> JsonRpcError {
+    match e {
+        // Work on a replaced tip is reported distinctly so miners switch jobs
+        BlockSubmitError::Stale { reason, tip } => JsonRpcError {
+            code: -25,
+            message: reason.to_string(),
+            data: Some(json!({
+                "hash": hex::encode(block.hash()),
+                "previousblockhash": hex::encode(block.prev_block_hash()),
+                "tip": hex::encode(tip),
+            })),
+        },
+        BlockSubmitError::Rejected(e) => JsonRpcError {
             code: -25,
             message: format!("Failed to add block: {}", e),
             data: None,
-        })?;
-
-    // Scan block for wallet transactions
-    let wallet_manager = node.wallet_manager();
-    if let Ok(wallet) = wallet_manager.write() {
-        if let Err(e) = wallet.scan_block(&block) {
-            tracing::warn!("Failed to scan block for wallet: {}", e);
-        }
-    }
-    
-    // Store block in database
-    node.storage().insert_block(&block)
-        .map_err(|e| JsonRpcError {
+        },
+        BlockSubmitError::Node(e) => JsonRpcError {
             code: -1,
-            message: format!("Failed to store block: {}", e),
+            message: e.to_string(),
             data: None,
-        })?;
-    
-    // Broadcast block to P2P network
-    let block_hash = block.hash();
-    tracing::info!("Broadcasting block {} to network", hex::encode(&block_hash[..8]));
-    node.network().broadcast_block(&block);
-    
-    tracing::info!("Accepted block {} at height {}", 
-        hex::encode(&block_hash[..8]), block.height());
-    
-    // Success - return null
-        Ok(Value::Null)
+        },
+    }
 }
 
 /// Generate blocks using CPU mining (testnet only)
 #[cfg(feature = "testnet")]
-// The `wallet` read guard is explicitly dropped before the spawn_blocking
-// await, and the chain-state write guard lives entirely on the dedicated
-// spawn_blocking thread; clippy's await_holding_lock ignores explicit drops
-// and cannot see the thread boundary, so both are false positives here.
+// The `wallet` read guard is explicitly dropped before the block is
+// submitted; clippy's await_holding_lock ignores explicit drops, so this is
+// a false positive.
 #[allow(clippy::await_holding_lock)]
 async fn generate_blocks(
     params: Value,
@@ -1227,6 +1153,12 @@ async fn generate_blocks(
         });
     }
 
+    node.ensure_clock_sane().map_err(|e| JsonRpcError {
+        code: -1,
+        message: e.to_string(),
+        data: None,
+    })?;
+
     tracing::info!("Generating {} block(s) using CPU miner", num_blocks);
 
     let mut block_hashes = Vec::new();
@@ -1280,9 +1212,19 @@ async fn generate_blocks(
 
         // Generate block template
         use crate::mining::template::BlockTemplate;
-        let template = BlockTemplate::generate(
+        let mining_config = node
+            .config()
+            .read()
+            .map(|c| c.mining.clone())
+            .map_err(|_| JsonRpcError {
+                code: -1,
+                message: "Config lock poisoned".to_string(),
+                data: None,
+            })?;
+        let template = BlockTemplate::generate_with_payout(
             node.chain_state(),
             node.mempool(),
+            &mining_config,
             &reward_addr,
             &treasury_addr,
         ).map_err(|e| JsonRpcError {
@@ -1320,58 +1262,9 @@ async fn generate_blocks(
             hex::encode(&block_hash[..8])
         );
 
-        // Add block to chain state.
-        // Use spawn_blocking so the std::sync::RwLock write guard is never held
-        // across the .await inside add_block on the async actix worker thread
-        // (mirrors the discipline in node.rs / network/mod.rs).
-        let chain_state = node.chain_state();
-        let block_for_add = mined_block.clone();
-        // The guard is intentionally held across the await inside a dedicated
-        // spawn_blocking thread, so it cannot stall an actix worker's executor.
-        let add_result = tokio::task::spawn_blocking(move || {
-            tokio::runtime::Handle::current().block_on(async move {
-                match chain_state.write() {
-                    Ok(mut chain) => chain.add_block(&block_for_add).await,
-                    Err(e) => Err(crate::storage::StorageError::DatabaseError(format!(
-                        "Chain state lock poisoned: {}",
-                        e
-                    ))),
-                }
-            })
-        })
-        .await;
-        add_result
-            .map_err(|e| JsonRpcError {
-                code: -1,
-                message: format!("Block processing task failed: {}", e),
-                data: None,
-            })?
-            .map_err(|e| JsonRpcError {
-                code: -25,
-                message: format!("Failed to add block to chain: {}", e),
-                data: None,
-            })?;
-
-        // Scan block for wallet transactions
-        let wallet_manager = node.wallet_manager();
-        if let Ok(wallet) = wallet_manager.write() {
-            if let Err(e) = wallet.scan_block(&mined_block) {
-                tracing::warn!("Failed to scan block for wallet: {}", e);
-            }
-        }
-
-        // Store block in database
-        node.storage().insert_block(&mined_block)
-            .map_err(|e| JsonRpcError {
-                code: -1,
-                message: format!("Failed to store block: {}", e),
-                data: None,
-            })?;
-
-        // Broadcast block to network
-        tracing::info!("Broadcasting mined block {} to network", hex::encode(&block_hash[..8]));
-        node.network().broadcast_block(&mined_block);
-        tracing::info!("Block {} broadcast complete", hex::encode(&block_hash[..8]));
+        node.submit_mined_block(&mined_block)
+            .await
+            .map_err(|e| submit_block_error(&mined_block, e))?;
 
         block_hashes.push(hex::encode(block_hash));
     }
@@ -1441,7 +1334,7 @@ async fn get_next_block_hash(
 /// Any entry that cannot be read (permissions, races with compaction) is
 /// skipped rather than failing the RPC, so the returned value is a lower
 /// bound rather than an error.
-fn directory_size_on_disk(path: &std::path::Path) -> u64 {
+pub(super) fn directory_size_on_disk(path: &std::path::Path) -> u64 {
     let metadata = match std::fs::symlink_metadata(path) {
         Ok(m) => m,
         Err(_) => return 0,
@@ -1575,6 +1468,23 @@ fn environmental_info_json(
     }))
 }
 
+/// Get sync progress: heights, percentage, smoothed rates and ETA.
+async fn get_sync_progress(
+    _params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    let progress = node.sync_progress().ok_or_else(|| JsonRpcError {
+        code: ErrorCode::InternalError as i32,
+        message: "Sync progress not yet available".to_string(),
+        data: None,
+    })?;
+    serde_json::to_value(progress).map_err(|e| JsonRpcError {
+        code: ErrorCode::InternalError as i32,
+        message: format!("Failed to serialize sync progress: {}", e),
+        data: None,
+    })
+}
+
 /// Get comprehensive network statistics
 async fn get_network_stats(
     _params: Value,
@@ -1733,15 +1643,10 @@ async fn get_balance(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Parse parameters
-    let (minconf, _include_watchonly) = match params {
-        Value::Array(arr) => {
-            let minconf = arr.get(0).and_then(|v| v.as_u64()).unwrap_or(1);
-            let watchonly = arr.get(1).and_then(|v| v.as_bool()).unwrap_or(false);
-            (minconf, watchonly)
-        }
-        _ => (1, false),
-    };
+    // Bitcoin Core's first parameter is a dummy account name ("*")
+    let params = Params::new(params)?;
+    let minconf = params.optional::<u64>(1, "minconf")?.unwrap_or(1);
+    let _include_watchonly = params.optional::<bool>(2, "include_watchonly")?;
     
     // Get wallet manager
     let wallet_manager = node.wallet_manager();
@@ -1770,21 +1675,10 @@ async fn list_unspent(
     params: Value,
     node: web::Data<Arc<ApiFacade>>,
 ) -> Result<Value, JsonRpcError> {
-    // Parse parameters
-    let (minconf, maxconf, addresses) = match params {
-        Value::Array(arr) => {
-            let minconf = arr.get(0).and_then(|v| v.as_u64()).unwrap_or(1);
-            let maxconf = arr.get(1).and_then(|v| v.as_u64()).unwrap_or(9999999);
-            let addresses: Vec<String> = arr.get(2)
-                .and_then(|v| v.as_array())
-                .map(|a| a.iter()
-                    .filter_map(|v| v.as_str().map(String::from))
-                    .collect())
-                .unwrap_or_default();
-            (minconf, maxconf, addresses)
-        }
-        _ => (1, 9999999, vec![]),
-    };
+    let params = Params::new(params)?;
+    let minconf = params.optional::<u64>(0, "minconf")?.unwrap_or(1);
+    let maxconf = params.optional::<u64>(1, "maxconf")?.unwrap_or(9999999);
+    let addresses: Vec<String> = params.optional(2, "addresses")?.unwrap_or_default();
     
     // Get wallet manager
     let wallet_manager = node.wallet_manager();
@@ -1991,6 +1885,7 @@ async fn send_to_address(
         .map_err(|e| JsonRpcError {
             code: match e {
                 crate::wallet_manager::WalletManagerError::TransactionError(_) => -25,
+                crate::wallet_manager::WalletManagerError::NonFinal { .. } => -26,
                 _ => -1,
             },
             message: format!("Failed to submit transaction: {}", e),
@@ -2008,6 +1903,160 @@ async fn send_to_address(
     Ok(Value::String(hex::encode(txid)))
 }
 
+/// Replace an unconfirmed wallet transaction with a higher-fee version
+async fn bump_fee(
+    params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    // Parse parameters: txid, new fee rate (attonovas per byte)
+    let (txid_str, fee_rate) = match params {
+        Value::Array(ref arr) => {
+            let txid = arr.get(0)
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .ok_or_else(|| JsonRpcError {
+                    code: ErrorCode::InvalidParams as i32,
+                    message: "Missing or invalid txid parameter".to_string(),
+                    data: None,
+                })?;
+            
+            let fee_rate = arr.get(1)
+                .and_then(|v| v.as_u64())
+                .filter(|rate| *rate > 0)
+                .ok_or_else(|| JsonRpcError {
+                    code: ErrorCode::InvalidParams as i32,
+                    message: "Missing or invalid fee rate parameter".to_string(),
+                    data: None,
+                })?;
+            
+            (txid, fee_rate)
+        }
+        _ => {
+            return Err(JsonRpcError {
+                code: ErrorCode::InvalidParams as i32,
+                message: "Invalid parameters for bumpfee".to_string(),
+                data: None,
+            });
+        }
+    };
+    
+    let txid_bytes = hex::decode(&txid_str)
+        .ok()
+        .filter(|bytes| bytes.len() == 32)
+        .ok_or_else(|| JsonRpcError {
+            code: ErrorCode::InvalidParams as i32,
+            message: "Invalid txid format".to_string(),
+            data: None,
+        })?;
+    let mut txid = [0u8; 32];
+    txid.copy_from_slice(&txid_bytes);
+    
+    let wallet_manager = node.wallet_manager();
+    let wallet = wallet_manager.read()
+        .map_err(|_| JsonRpcError {
+            code: -13,
+            message: "Wallet lock poisoned".to_string(),
+            data: None,
+        })?;
+    
+    let replacement = wallet.bump_fee(&txid, fee_rate)
+        .map_err(|e| JsonRpcError {
+            code: match e {
+                crate::wallet_manager::WalletManagerError::WalletLocked => -13,
+                crate::wallet_manager::WalletManagerError::TransactionError(_) => -8,
+                _ => -1,
+            },
+            message: format!("Failed to bump fee: {}", e),
+            data: None,
+        })?;
+    
+    Ok(json!({
+        "txid": hex::encode(replacement),
+        "origtxid": txid_str,
+        "feerate": fee_rate,
+    }))
+}
+
+/// Broadcast a wallet transaction now if it is final, otherwise hold it in
+/// the wallet's pending-final area and broadcast it once the chain reaches
+/// its lock time or relative locks
+async fn park_raw_transaction(
+    params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    let raw_tx_hex = params
+        .as_array()
+        .and_then(|arr| arr.get(0))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JsonRpcError {
+            code: ErrorCode::InvalidParams as i32,
+            message: "Missing or invalid raw transaction parameter".to_string(),
+            data: None,
+        })?;
+    let transaction = hex::decode(raw_tx_hex)
+        .ok()
+        .and_then(|bytes| {
+            bincode::deserialize::<supernova_core::types::transaction::Transaction>(&bytes).ok()
+        })
+        .ok_or_else(|| JsonRpcError {
+            code: ErrorCode::InvalidParams as i32,
+            message: "Invalid raw transaction".to_string(),
+            data: None,
+        })?;
+    let txid = hex::encode(transaction.hash());
+
+    let wallet_manager = node.wallet_manager();
+    let wallet = wallet_manager.read()
+        .map_err(|_| JsonRpcError {
+            code: -13,
+            message: "Wallet lock poisoned".to_string(),
+            data: None,
+        })?;
+    let submission = wallet.park_transaction(transaction)
+        .map_err(|e| JsonRpcError {
+            code: -25,
+            message: format!("Failed to submit transaction: {}", e),
+            data: None,
+        })?;
+
+    Ok(match submission {
+        crate::pending_final::Submission::Accepted => json!({
+            "txid": txid,
+            "status": "broadcast",
+        }),
+        crate::pending_final::Submission::Parked(lock) => json!({
+            "txid": txid,
+            "status": "parked",
+            "min_height": lock.min_height,
+            "min_time": lock.min_time,
+        }),
+    })
+}
+
+/// Wallet transactions waiting for their timelocks, earliest first
+async fn list_pending_final(
+    _params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    let wallet_manager = node.wallet_manager();
+    let wallet = wallet_manager.read()
+        .map_err(|_| JsonRpcError {
+            code: -13,
+            message: "Wallet lock poisoned".to_string(),
+            data: None,
+        })?;
+    let parked: Vec<Value> = wallet.pending_final()
+        .into_iter()
+        .map(|p| json!({
+            "txid": hex::encode(p.transaction.hash()),
+            "min_height": p.lock.min_height,
+            "min_time": p.lock.min_time,
+            "parked_at": p.parked_at.to_rfc3339(),
+        }))
+        .collect();
+    Ok(Value::Array(parked))
+}
+
 /// Add test UTXO (testnet only)
 #[cfg(feature = "testnet")]
 async fn add_test_utxo(
@@ -2132,6 +2181,105 @@ async fn add_node(
     }))
 }
 
+/// Roll the chain back to a height: `[height, wipe_mempool?]`
+async fn rollback_chain(
+    params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    let (height, wipe_mempool) = match params {
+        Value::Array(ref arr) if !arr.is_empty() => (
+            arr[0].as_u64(),
+            arr.get(1).and_then(|v| v.as_bool()).unwrap_or(false),
+        ),
+        Value::Number(ref n) => (n.as_u64(), false),
+        _ => (None, false),
+    };
+    let height = height.ok_or_else(|| JsonRpcError {
+        code: ErrorCode::InvalidParams as i32,
+        message: "Target height required".to_string(),
+        data: None,
+    })?;
+
+    run_chain_admin(node, ChainAdminOp::Rollback { height, wipe_mempool }).await
+}
+
+/// Invalidate a block and roll back past it if it is active: `[hash]`
+async fn invalidate_block(
+    params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    let hash = parse_admin_block_hash(&params)?;
+    run_chain_admin(node, ChainAdminOp::InvalidateBlock(hash)).await
+}
+
+/// Clear an earlier invalidation: `[hash]`
+async fn reconsider_block(
+    params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+) -> Result<Value, JsonRpcError> {
+    let hash = parse_admin_block_hash(&params)?;
+    run_chain_admin(node, ChainAdminOp::ReconsiderBlock(hash)).await
+}
+
+fn parse_admin_block_hash(params: &Value) -> Result<[u8; 32], JsonRpcError> {
+    let hash_str = match params {
+        Value::Array(arr) => arr.first().and_then(|v| v.as_str()),
+        Value::String(s) => Some(s.as_str()),
+        _ => None,
+    }
+    .ok_or_else(|| JsonRpcError {
+        code: ErrorCode::InvalidParams as i32,
+        message: "Block hash required".to_string(),
+        data: None,
+    })?;
+
+    let mut hash = [0u8; 32];
+    hex::decode_to_slice(hash_str.trim(), &mut hash).map_err(|_| JsonRpcError {
+        code: ErrorCode::InvalidParams as i32,
+        message: format!("Invalid block hash: {}", hash_str),
+        data: None,
+    })?;
+    Ok(hash)
+}
+
+async fn run_chain_admin(
+    node: web::Data<Arc<ApiFacade>>,
+    op: ChainAdminOp,
+) -> Result<Value, JsonRpcError> {
+    let change = node.chain_admin(op).await.map_err(|e| JsonRpcError {
+        code: -1,
+        message: e.to_string(),
+        data: None,
+    })?;
+
+    Ok(json!({
+        "old_height": change.old_height,
+        "old_tip": hex::encode(change.old_tip),
+        "new_height": change.new_height,
+        "new_tip": hex::encode(change.new_tip),
+        "affected_blocks": change.affected_blocks.iter().map(hex::encode).collect::<Vec<_>>(),
+        "mempool_cleared": matches!(op, ChainAdminOp::Rollback { wipe_mempool: true, .. }),
+    }))
+}
+
+/// Start rotating the node's P2P identity
+async fn rotate_identity(node: web::Data<Arc<ApiFacade>>) -> Result<Value, JsonRpcError> {
+    let attestation = node.rotate_identity().await.map_err(|e| JsonRpcError {
+        code: -1,
+        message: e.to_string(),
+        data: None,
+    })?;
+
+    Ok(json!({
+        "old_peer_id": attestation.link.old_peer_id,
+        "new_peer_id": attestation.link.new_peer_id,
+        "issued_at": attestation.link.issued_at,
+        "grace_until": attestation.link.grace_until,
+        "old_signature": attestation.old_signature,
+        "new_signature": attestation.new_signature,
+    }))
+}
+
 #[cfg(test)]
 mod send_raw_transaction_tests {
     use super::*;
@@ -2198,9 +2346,47 @@ mod send_raw_transaction_tests {
         assert!(err.message.to_lowercase().contains("hex"));
 
         // Missing params are rejected before any decoding.
-        let result = send_raw_transaction(Value::Null, facade).await;
+        let result = send_raw_transaction(Value::Null, facade.clone()).await;
         let err = result.unwrap_err();
         assert_eq!(err.code, ErrorCode::InvalidParams as i32);
+
+        // Dispatch: unknown methods and mistyped parameters.
+        let err = dispatch("getfoo", json!([]), facade.clone()).await.unwrap_err();
+        assert_eq!(err.code, ErrorCode::MethodNotFound as i32);
+        let err = dispatch("getblockhash", json!(["ten"]), facade.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidParams as i32);
+        let err = dispatch("getblockhash", json!({ "height": -1 }), facade.clone())
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("'height'"), "{}", err.message);
+        let count = dispatch("getblockcount", json!({}), facade).await.unwrap();
+        assert!(count.is_u64());
+    }
+}
+
+#[cfg(test)]
+mod sync_gate_tests {
+    use super::*;
+
+    #[test]
+    fn syncing_error_reports_progress() {
+        let progress = SyncProgress {
+            current_height: 120,
+            target_height: 480,
+            percent: 25.0,
+            blocks_per_sec: 0.0,
+            bytes_per_sec: 0.0,
+            eta_secs: None,
+            synced: false,
+            updated_at: 0,
+        };
+        let err = syncing_error(&progress);
+        assert_eq!(err.code, -32001);
+        let data = err.data.unwrap();
+        assert_eq!(data["current_height"], 120);
+        assert_eq!(data["target_height"], 480);
     }
 }
 
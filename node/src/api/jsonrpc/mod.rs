@@ -1,27 +1,95 @@
 //! JSON-RPC server implementation
 //!
-//! This module implements the JSON-RPC 2.0 API for supernova blockchain.
+//! This module implements the JSON-RPC 2.0 API for supernova blockchain,
+//! served at `/rpc` (and `/` for older clients). A body holds one request
+//! object or a batch array, which is answered with an array in the same
+//! order.
 
+pub mod compat;
 mod handlers;
+mod params;
 mod types;
 
-use actix_web::{web, HttpRequest, HttpResponse, Responder, http::header};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder, http::header};
 use serde_json::Value;
 use std::sync::Arc;
 use crate::api_facade::ApiFacade;
+use crate::api::idempotency::{
+    idempotency_key, request_fingerprint, request_scope, StoredResponse, IDEMPOTENT_REPLAY_HEADER,
+};
+use crate::api::middleware::scopes::{ApiScope, GrantedScopes};
 use crate::api::rate_limiter::{ApiRateLimiter, ApiRateLimitConfig, is_expensive_endpoint};
 use types::{JsonRpcRequest, JsonRpcResponse, ErrorCode};
 
-/// JSON-RPC request handler
+/// Largest request body: room for `submitblock` with a full block in hex
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// A parsed request body
+enum RpcBody {
+    Single(Result<JsonRpcRequest, JsonRpcResponse>),
+    /// Entries that are not request objects carry their error response
+    Batch(Vec<Result<JsonRpcRequest, JsonRpcResponse>>),
+}
+
+/// Parse a request body. Invalid JSON is a parse error for the whole body;
+/// in a batch, an entry that is not a valid request is answered on its own
+/// and the others are still served.
+fn parse_body(body: &[u8]) -> Result<RpcBody, JsonRpcResponse> {
+    let value: Value = serde_json::from_slice(body).map_err(|e| {
+        JsonRpcResponse::error(Value::Null, ErrorCode::ParseError, format!("Parse error: {}", e), None)
+    })?;
+    match value {
+        Value::Array(entries) => {
+            if entries.is_empty() {
+                return Err(JsonRpcResponse::error(
+                    Value::Null,
+                    ErrorCode::InvalidRequest,
+                    "Empty batch".to_string(),
+                    None,
+                ));
+            }
+            if let Some(err) = oversized_batch_response(entries.len()) {
+                return Err(err);
+            }
+            Ok(RpcBody::Batch(entries.into_iter().map(parse_request).collect()))
+        }
+        value => Ok(RpcBody::Single(parse_request(value))),
+    }
+}
+
+fn parse_request(value: Value) -> Result<JsonRpcRequest, JsonRpcResponse> {
+    // Answer under the entry's id when it has a usable one
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    serde_json::from_value(value).map_err(|e| {
+        JsonRpcResponse::error(id, ErrorCode::InvalidRequest, format!("Invalid request: {}", e), None)
+    })
+}
+
+/// JSON-RPC request handler, for single requests and batches
 /// 
 /// Enhanced with rate limiting to prevent API DoS attacks.
 pub async fn handle_jsonrpc(
     http_req: HttpRequest,
-    request: web::Json<JsonRpcRequest>,
+    body: web::Bytes,
     node: web::Data<Arc<ApiFacade>>,
     rate_limiter: web::Data<Arc<ApiRateLimiter>>,
-) -> impl Responder {
-    let req = request.into_inner();
+) -> HttpResponse {
+    match parse_body(&body) {
+        Err(response) | Ok(RpcBody::Single(Err(response))) => HttpResponse::Ok().json(response),
+        Ok(RpcBody::Single(Ok(req))) => handle_single(&http_req, req, node, &rate_limiter).await,
+        Ok(RpcBody::Batch(requests)) => {
+            let responses = handle_batch(&http_req, requests, node, &rate_limiter).await;
+            HttpResponse::Ok().json(responses)
+        }
+    }
+}
+
+async fn handle_single(
+    http_req: &HttpRequest,
+    req: JsonRpcRequest,
+    node: web::Data<Arc<ApiFacade>>,
+    rate_limiter: &ApiRateLimiter,
+) -> HttpResponse {
     let id = req.id.clone();
 
     // SECURITY: Extract client IP address for rate limiting
@@ -47,7 +115,7 @@ pub async fn handle_jsonrpc(
     }
 
     // Validate JSON-RPC version
-    if req.jsonrpc != "2.0" {
+    if !version_accepted(&req.jsonrpc, &node) {
         rate_limiter.complete_request(client_ip);
         return HttpResponse::Ok().json(JsonRpcResponse::error(
             id,
@@ -57,21 +125,115 @@ pub async fn handle_jsonrpc(
         ));
     }
 
-    // Dispatch to appropriate method handler
-    let result = match handlers::dispatch(&req.method, req.params, node).await {
-        Ok(result) => JsonRpcResponse::result(id, result),
-        Err(e) => JsonRpcResponse::error(
-            id,
-            ErrorCode::from(e.code),
-            e.message,
-            e.data,
-        ),
+    if let Some(response) = scope_error(http_req, &req.method, &id) {
+        rate_limiter.complete_request(client_ip);
+        return HttpResponse::Forbidden().json(response);
+    }
+
+    // Transaction-submitting methods honour an Idempotency-Key header so
+    // client retries after a timeout cannot pay twice. Batch requests are
+    // not covered: one header cannot name several submissions.
+    let idempotency = match idempotency_key(http_req.headers()) {
+        Some(key) if IDEMPOTENT_METHODS.contains(&req.method.as_str()) => Some(key),
+        _ => None,
+    };
+
+    let (result, replayed) = match idempotency {
+        Some(key) => {
+            let params = serde_json::to_vec(&req.params).unwrap_or_default();
+            let fingerprint = request_fingerprint(&req.method, &params);
+            let store = node.idempotency();
+            let outcome = store
+                .execute(&request_scope(http_req), &key, fingerprint, || async {
+                    // Stored without the request id; each replay answers
+                    // under the id of the request it replies to.
+                    let response = dispatch_request(&req.method, req.params.clone(), node, Value::Null).await;
+                    StoredResponse {
+                        status: 200,
+                        body: serde_json::to_value(response).unwrap_or_default(),
+                    }
+                })
+                .await;
+            match outcome {
+                Ok((stored, replayed)) => {
+                    let mut response = serde_json::from_value::<JsonRpcResponse>(stored.body)
+                        .unwrap_or_else(|e| {
+                            JsonRpcResponse::error(Value::Null, ErrorCode::InternalError, e.to_string(), None)
+                        });
+                    response.id = id;
+                    (response, replayed)
+                }
+                Err(e) => (
+                    JsonRpcResponse::error(id, ErrorCode::InvalidParams, e.to_string(), None),
+                    false,
+                ),
+            }
+        }
+        None => (dispatch_request(&req.method, req.params, node, id).await, false),
     };
 
     // Mark request as complete (decrements concurrent counter)
     rate_limiter.complete_request(client_ip);
 
-    HttpResponse::Ok().json(result)
+    let mut response = HttpResponse::Ok();
+    if replayed {
+        response.insert_header((IDEMPOTENT_REPLAY_HEADER, "true"));
+    }
+    response.json(result)
+}
+
+/// Whether a request's `jsonrpc` version is served. Bitcoin Core clients
+/// send "1.0" or omit the field; they are accepted in compatibility mode.
+fn version_accepted(version: &str, node: &ApiFacade) -> bool {
+    version == "2.0" || (compat::enabled(node) && matches!(version, "" | "1.0" | "1.1"))
+}
+
+/// Scope an API key needs to call `method`. The endpoint itself only
+/// requires `read`.
+fn method_scope(method: &str) -> ApiScope {
+    match method {
+        "sendrawtransaction" | "getnewaddress" | "getbalance" | "listunspent" | "sendtoaddress"
+        | "bumpfee" | "parkrawtransaction" | "listpendingfinal" => ApiScope::Wallet,
+        "getblocktemplate" | "submitblock" | "generate" | "addnode" | "addtestutxo"
+        | "rollbackchain" | "invalidateblock" | "reconsiderblock" | "rotateidentity" => {
+            ApiScope::Admin
+        }
+        _ => ApiScope::Read,
+    }
+}
+
+/// Error for a call the request's API key may not make. Requests that did
+/// not pass the auth middleware carry no scopes and are refused.
+fn scope_error(http_req: &HttpRequest, method: &str, id: &Value) -> Option<JsonRpcResponse> {
+    let required = method_scope(method);
+    let allowed = http_req
+        .extensions()
+        .get::<GrantedScopes>()
+        .is_some_and(|scopes| scopes.allows(required));
+    (!allowed).then(|| {
+        JsonRpcResponse::error(
+            id.clone(),
+            ErrorCode::InsufficientScope,
+            format!("Method '{}' requires the '{}' scope", method, required),
+            None,
+        )
+    })
+}
+
+/// Methods whose calls are made idempotent by an `Idempotency-Key` header
+const IDEMPOTENT_METHODS: &[&str] = &["sendrawtransaction", "sendtoaddress", "parkrawtransaction"];
+
+/// Dispatch one call to its method handler and wrap the outcome
+async fn dispatch_request(
+    method: &str,
+    params: Value,
+    node: web::Data<Arc<ApiFacade>>,
+    id: Value,
+) -> JsonRpcResponse {
+    match handlers::dispatch(method, params, node).await {
+        Ok(result) => JsonRpcResponse::result(id, result),
+        Err(e) => JsonRpcResponse::from_error(id, e),
+    }
 }
 
 /// Build a rejection response for an over-sized batch, or `None` if the batch
@@ -99,30 +261,14 @@ fn oversized_batch_response(len: usize) -> Option<JsonRpcResponse> {
     }
 }
 
-/// Batch JSON-RPC request handler
-pub async fn handle_jsonrpc_batch(
-    http_req: HttpRequest,
-    requests: web::Json<Vec<JsonRpcRequest>>,
+/// Serve the requests of a batch in order. Every sub-request counts
+/// against the rate limits on its own.
+async fn handle_batch(
+    http_req: &HttpRequest,
+    requests: Vec<Result<JsonRpcRequest, JsonRpcResponse>>,
     node: web::Data<Arc<ApiFacade>>,
-    rate_limiter: web::Data<Arc<ApiRateLimiter>>,
-) -> impl Responder {
-    if requests.is_empty() {
-        return HttpResponse::Ok().json(JsonRpcResponse::error(
-            Value::Null,
-            ErrorCode::InvalidRequest,
-            "Empty batch".to_string(),
-            None,
-        ));
-    }
-
-    // SECURITY: Cap the number of sub-requests per batch. Without this bound a
-    // client can pack many expensive methods into one POST to amplify load,
-    // since each sub-request is dispatched serially. Reject the entire batch
-    // rather than truncating it.
-    if let Some(err) = oversized_batch_response(requests.len()) {
-        return HttpResponse::Ok().json(err);
-    }
-
+    rate_limiter: &ApiRateLimiter,
+) -> Vec<JsonRpcResponse> {
     // SECURITY: Extract client IP for per-sub-request rate limiting. Batching
     // must NOT be a bypass for the DoS protection enforced on the single-call
     // path — each sub-request is dispatched serially and counts against the
@@ -134,7 +280,14 @@ pub async fn handle_jsonrpc_batch(
 
     let mut responses = Vec::with_capacity(requests.len());
 
-    for req in requests.iter() {
+    for req in requests {
+        let req = match req {
+            Ok(req) => req,
+            Err(response) => {
+                responses.push(response);
+                continue;
+            }
+        };
         let id = req.id.clone();
 
         // CRITICAL SECURITY CHECK: Rate limit every sub-request individually so
@@ -154,7 +307,7 @@ pub async fn handle_jsonrpc_batch(
         }
 
         // Validate JSON-RPC version
-        if req.jsonrpc != "2.0" {
+        if !version_accepted(&req.jsonrpc, &node) {
             rate_limiter.complete_request(client_ip);
             responses.push(JsonRpcResponse::error(
                 id,
@@ -165,16 +318,14 @@ pub async fn handle_jsonrpc_batch(
             continue;
         }
 
+        if let Some(response) = scope_error(http_req, &req.method, &id) {
+            rate_limiter.complete_request(client_ip);
+            responses.push(response);
+            continue;
+        }
+
         // Dispatch to appropriate method handler
-        let result = match handlers::dispatch(&req.method, req.params.clone(), node.clone()).await {
-            Ok(result) => JsonRpcResponse::result(id, result),
-            Err(e) => JsonRpcResponse::error(
-                id,
-                ErrorCode::from(e.code),
-                e.message,
-                e.data,
-            ),
-        };
+        let result = dispatch_request(&req.method, req.params, node.clone(), id).await;
 
         // Mark sub-request complete (decrements concurrent counter).
         rate_limiter.complete_request(client_ip);
@@ -182,7 +333,7 @@ pub async fn handle_jsonrpc_batch(
         responses.push(result);
     }
 
-    HttpResponse::Ok().json(responses)
+    responses
 }
 
 /// Serve JSON-RPC documentation
@@ -245,11 +396,17 @@ pub async fn get_docs() -> impl Responder {
         ))
 }
 
-/// Configure JSON-RPC routes
+/// Configure JSON-RPC routes. `/batch` predates batch support on the main
+/// endpoints and serves the same requests.
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.route("/", web::post().to(handle_jsonrpc))
-        .route("/batch", web::post().to(handle_jsonrpc_batch))
-        .route("/docs", web::get().to(get_docs));
+    for path in ["/rpc", "/", "/batch"] {
+        cfg.service(
+            web::resource(path)
+                .app_data(web::PayloadConfig::new(MAX_BODY_BYTES))
+                .route(web::post().to(handle_jsonrpc)),
+        );
+    }
+    cfg.route("/docs", web::get().to(get_docs));
 }
 
 #[cfg(test)]
@@ -288,7 +445,7 @@ mod batch_limit_tests {
 
     /// SECURITY: batched sub-requests must be subject to the same per-IP rate
     /// limit as single POST-`/` calls. This mirrors the check/complete pairing
-    /// `handle_jsonrpc_batch` now applies per sub-request, proving that packing
+    /// `handle_batch` now applies per sub-request, proving that packing
     /// expensive methods into one batch cannot exceed the per-IP token budget.
     #[test]
     fn batch_subrequests_share_the_per_ip_rate_limit() {
@@ -320,4 +477,99 @@ mod batch_limit_tests {
             "further sub-requests from the same IP must be rate limited"
         );
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod parse_tests {
+    use super::*;
+
+    fn error_code(response: &JsonRpcResponse) -> i32 {
+        response.error.as_ref().expect("error response").code
+    }
+
+    #[test]
+    fn invalid_json_is_a_parse_error() {
+        let Err(response) = parse_body(br#"{"jsonrpc": "2.0", "method""#) else {
+            panic!("truncated body must not parse");
+        };
+        assert_eq!(error_code(&response), ErrorCode::ParseError as i32);
+        assert_eq!(response.id, Value::Null);
+    }
+
+    #[test]
+    fn single_request_without_method_is_invalid() {
+        let body = br#"{"jsonrpc": "2.0", "id": 7}"#;
+        let Ok(RpcBody::Single(Err(response))) = parse_body(body) else {
+            panic!("request without a method must be rejected on its own");
+        };
+        assert_eq!(error_code(&response), ErrorCode::InvalidRequest as i32);
+        assert_eq!(response.id, 7);
+    }
+
+    #[test]
+    fn batch_entries_are_parsed_independently() {
+        let body = br#"[
+            {"jsonrpc": "2.0", "method": "getblockcount", "id": 1},
+            42,
+            {"jsonrpc": "2.0", "method": "getblockhash", "params": [0]}
+        ]"#;
+        let Ok(RpcBody::Batch(entries)) = parse_body(body) else {
+            panic!("array body must parse as a batch");
+        };
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().method, "getblockcount");
+        let invalid = entries[1].as_ref().unwrap_err();
+        assert_eq!(error_code(invalid), ErrorCode::InvalidRequest as i32);
+        // A request without an id is still served, answered with a null id
+        assert_eq!(entries[2].as_ref().unwrap().id, Value::Null);
+    }
+
+    #[test]
+    fn empty_and_oversized_batches_are_rejected() {
+        let Err(response) = parse_body(b"[]") else {
+            panic!("empty batch must be rejected");
+        };
+        assert_eq!(error_code(&response), ErrorCode::InvalidRequest as i32);
+
+        let entry = serde_json::json!({"jsonrpc": "2.0", "method": "getblockcount", "id": 1});
+        let batch = vec![entry; ApiRateLimitConfig::MAX_BATCH_SIZE + 1];
+        let body = serde_json::to_vec(&batch).unwrap();
+        assert!(parse_body(&body).is_err());
+    }
+}
+
+#[cfg(test)]
+mod scope_tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn methods_require_their_scope() {
+        let request = |scopes: Option<Vec<ApiScope>>| {
+            let req = TestRequest::default().to_http_request();
+            if let Some(scopes) = scopes {
+                req.extensions_mut().insert(GrantedScopes::new(scopes));
+            }
+            req
+        };
+        let allowed = |req: &HttpRequest, method| scope_error(req, method, &Value::Null).is_none();
+
+        let read = request(Some(vec![ApiScope::Read]));
+        assert!(allowed(&read, "getblockcount"));
+        assert!(allowed(&read, "getrawmempool"));
+        assert!(!allowed(&read, "sendtoaddress"));
+        assert!(!allowed(&read, "rollbackchain"));
+
+        let wallet = request(Some(vec![ApiScope::Wallet]));
+        assert!(allowed(&wallet, "sendrawtransaction"));
+        assert!(!allowed(&wallet, "generate"));
+
+        let admin = request(Some(vec![ApiScope::Admin]));
+        assert!(allowed(&admin, "invalidateblock"));
+
+        // Without scopes from the auth middleware nothing is allowed
+        let response = scope_error(&request(None), "getblockcount", &Value::from(3)).unwrap();
+        assert_eq!(response.error.unwrap().code, ErrorCode::InsufficientScope as i32);
+        assert_eq!(response.id, 3);
+    }
+}
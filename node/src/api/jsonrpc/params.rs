@@ -0,0 +1,142 @@
+//! Method parameters
+//!
+//! Bitcoin Core takes a method's parameters either by position (a JSON
+//! array) or by name (a JSON object), and omitted or null trailing
+//! parameters take their defaults. Generic RPC libraries send either form,
+//! so handlers read parameters through [`Params`] instead of indexing the
+//! array themselves.
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::types::{ErrorCode, JsonRpcError};
+
+/// Parameters of one call
+#[derive(Debug, Clone)]
+pub struct Params(Value);
+
+impl Params {
+    /// Accepts an array, an object, or no parameters at all
+    pub fn new(params: Value) -> Result<Self, JsonRpcError> {
+        match params {
+            Value::Null | Value::Array(_) | Value::Object(_) => Ok(Self(params)),
+            other => Err(invalid_params(format!(
+                "Parameters must be an array or an object, got {}",
+                other
+            ))),
+        }
+    }
+
+    /// Parameter at `index`, or called `name`; `None` when omitted or null
+    pub fn optional<T: DeserializeOwned>(
+        &self,
+        index: usize,
+        name: &str,
+    ) -> Result<Option<T>, JsonRpcError> {
+        let value = match &self.0 {
+            Value::Array(values) => values.get(index),
+            Value::Object(values) => values.get(name),
+            _ => None,
+        };
+        match value {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => T::deserialize(value)
+                .map(Some)
+                .map_err(|e| invalid_params(format!("Invalid parameter '{}': {}", name, e))),
+        }
+    }
+
+    pub fn required<T: DeserializeOwned>(
+        &self,
+        index: usize,
+        name: &str,
+    ) -> Result<T, JsonRpcError> {
+        self.optional(index, name)?
+            .ok_or_else(|| invalid_params(format!("Missing parameter '{}'", name)))
+    }
+}
+
+/// Verbosity of `getblock` and similar methods: a level, or a boolean as
+/// older Bitcoin Core callers send
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum Verbosity {
+    Level(u8),
+    Verbose(bool),
+}
+
+impl Verbosity {
+    pub fn level(self) -> u8 {
+        match self {
+            Verbosity::Level(level) => level,
+            Verbosity::Verbose(verbose) => verbose as u8,
+        }
+    }
+}
+
+fn invalid_params(message: String) -> JsonRpcError {
+    JsonRpcError {
+        code: ErrorCode::InvalidParams as i32,
+        message,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parameters_are_read_by_position_or_by_name() {
+        let positional = Params::new(json!(["00ff", 2])).unwrap();
+        let named = Params::new(json!({ "blockhash": "00ff", "verbosity": 2 })).unwrap();
+        for params in [positional, named] {
+            assert_eq!(params.required::<String>(0, "blockhash").unwrap(), "00ff");
+            assert_eq!(params.optional::<u8>(1, "verbosity").unwrap(), Some(2));
+        }
+    }
+
+    #[test]
+    fn omitted_and_null_parameters_are_absent() {
+        for params in [json!(null), json!([]), json!(["00ff", null]), json!({})] {
+            let params = Params::new(params).unwrap();
+            assert_eq!(params.optional::<u8>(1, "verbosity").unwrap(), None);
+        }
+        let error = Params::new(json!([]))
+            .unwrap()
+            .required::<u64>(0, "height")
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidParams as i32);
+        assert!(error.message.contains("'height'"));
+    }
+
+    #[test]
+    fn mismatched_types_are_invalid_params() {
+        let params = Params::new(json!(["ten", -1])).unwrap();
+        let error = params.required::<u64>(0, "height").unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidParams as i32);
+        assert!(error.message.contains("'height'"), "{}", error.message);
+        assert!(params.optional::<u64>(1, "minconf").is_err());
+
+        let error = Params::new(json!("00ff")).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidParams as i32);
+    }
+
+    #[test]
+    fn verbosity_accepts_levels_and_booleans() {
+        let params = Params::new(json!([2, true, false])).unwrap();
+        let level = |index| {
+            params
+                .required::<Verbosity>(index, "verbosity")
+                .unwrap()
+                .level()
+        };
+        assert_eq!((level(0), level(1), level(2)), (2, 1, 0));
+        assert!(Params::new(json!(["yes"]))
+            .unwrap()
+            .required::<Verbosity>(0, "verbosity")
+            .is_err());
+    }
+}
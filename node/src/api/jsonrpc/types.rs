@@ -8,14 +8,17 @@ use serde_json::Value;
 /// JSON-RPC 2.0 request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
-    /// JSON-RPC version, must be "2.0"
+    /// JSON-RPC version, must be "2.0". Bitcoin Core clients send "1.0" or
+    /// nothing, which compatibility mode accepts.
+    #[serde(default)]
     pub jsonrpc: String,
     /// Method name
     pub method: String,
     /// Method parameters
     #[serde(default)]
     pub params: Value,
-    /// Request ID
+    /// Request ID; null when omitted
+    #[serde(default)]
     pub id: Value,
 }
 
@@ -45,6 +48,18 @@ impl JsonRpcResponse {
         }
     }
 
+    /// Create an error response from a handler's error, keeping its code.
+    /// Handlers return Bitcoin Core codes (e.g. -5, -26) that have no
+    /// [`ErrorCode`] variant.
+    pub fn from_error(id: Value, error: JsonRpcError) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(error),
+            id,
+        }
+    }
+
     /// Create an error response
     pub fn error(id: Value, code: ErrorCode, message: String, data: Option<Value>) -> Self {
         Self {
@@ -138,6 +153,11 @@ pub enum ErrorCode {
     ///
     /// Too many requests from this IP address 
     RateLimitExceeded = -32006,
+
+    /// Insufficient scope (-32007)
+    ///
+    /// The API key may not call this method
+    InsufficientScope = -32007,
 }
 
 impl From<i32> for ErrorCode {
@@ -155,6 +175,7 @@ impl From<i32> for ErrorCode {
             -32004 => ErrorCode::WalletError,
             -32005 => ErrorCode::NetworkError,
             -32006 => ErrorCode::RateLimitExceeded,
+            -32007 => ErrorCode::InsufficientScope,
             _ => ErrorCode::InternalError, // Default for unknown codes
         }
     }
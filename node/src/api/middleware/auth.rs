@@ -1,27 +1,65 @@
 //! API authentication middleware
 //!
-//! This module provides API key authentication for the Supernova API.
+//! This module provides API key authentication for the Supernova API. Keys
+//! are held only as hashes, and each request must be allowed by the scopes
+//! of its key (see [`super::scopes`]).
 //!
 //! SECURITY: Authentication is MANDATORY. Empty API key lists are rejected to prevent bypass.
 
 use actix_web::{
+    body::{BodySize, MessageBody},
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    error::ErrorUnauthorized,
-    http::header,
-    Error,
+    http::{header, StatusCode},
+    Error, HttpMessage, HttpResponse, ResponseError,
 };
 use std::future::{ready, Ready};
 use std::rc::Rc;
 use std::sync::Arc;
-use subtle::ConstantTimeEq;
+use thiserror::Error;
 use tracing::{error, warn};
 
 use super::auth_rate_limiter::{AuthBlockedError, AuthRateLimiter, AuthRateLimiterConfig};
+use super::scopes::{
+    find_credential, legacy_admin_credentials, required_scope, ApiCredential, ApiScope,
+    GrantedScopes,
+};
+use crate::api::types::ApiResponse;
+use crate::api::usage::{ApiKeyId, UsageMeter};
+use crate::logging;
+
+/// Rejection of a request by the auth middleware
+#[derive(Debug, Error)]
+pub enum AuthError {
+    /// Missing, unknown and malformed keys are answered alike so the
+    /// response says nothing about key validity
+    #[error("Missing or invalid API key")]
+    Unauthorized,
+    #[error("API key lacks the '{0}' scope required for this route")]
+    InsufficientScope(ApiScope),
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AuthError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AuthError::InsufficientScope(_) => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = HttpResponse::build(self.status_code());
+        if self.status_code() == StatusCode::UNAUTHORIZED {
+            res.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+        }
+        res.json(ApiResponse::<()>::error(self.to_string()))
+    }
+}
 
 /// API authentication middleware
 pub struct ApiAuth {
-    api_keys: Rc<Vec<String>>,
+    credentials: Rc<Vec<ApiCredential>>,
     rate_limiter: Arc<AuthRateLimiter>,
+    usage: Option<Arc<UsageMeter>>,
     enabled: bool,
 }
 
@@ -45,8 +83,9 @@ impl ApiAuth {
         }
 
         Ok(Self {
-            api_keys: Rc::new(api_keys),
+            credentials: Rc::new(legacy_admin_credentials(&api_keys)),
             rate_limiter: Arc::new(AuthRateLimiter::new(AuthRateLimiterConfig::default())),
+            usage: None,
             enabled: true,
         })
     }
@@ -79,10 +118,21 @@ impl ApiAuth {
     pub fn from_validated_keys_with_rate_limiter(
         api_keys: Vec<String>,
         rate_limiter: Arc<AuthRateLimiter>,
+    ) -> Self {
+        Self::from_credentials_with_rate_limiter(legacy_admin_credentials(&api_keys), rate_limiter)
+    }
+
+    /// Create authentication middleware from hashed, scoped credentials,
+    /// reusing the supplied rate limiter. The caller must have checked that
+    /// at least one credential is configured.
+    pub fn from_credentials_with_rate_limiter(
+        credentials: Vec<ApiCredential>,
+        rate_limiter: Arc<AuthRateLimiter>,
     ) -> Self {
         Self {
-            api_keys: Rc::new(api_keys),
+            credentials: Rc::new(credentials),
             rate_limiter,
+            usage: None,
             enabled: true,
         }
     }
@@ -102,11 +152,21 @@ impl ApiAuth {
     /// whether auth is enabled.
     pub fn disabled_with_rate_limiter(rate_limiter: Arc<AuthRateLimiter>) -> Self {
         Self {
-            api_keys: Rc::new(Vec::new()),
+            credentials: Rc::new(Vec::new()),
             rate_limiter,
+            usage: None,
             enabled: false,
         }
     }
+
+    /// Meter authenticated requests against their API key, enforcing the
+    /// meter's daily quotas. Pass the same `Arc` to every per-worker
+    /// instance. Has no effect when authentication is disabled, as requests
+    /// then carry no key to meter.
+    pub fn with_usage_meter(mut self, usage: Arc<UsageMeter>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
 }
 
 /// Paths served publicly (no API key required). Liveness / readiness probes
@@ -125,31 +185,11 @@ fn is_public_path(path: &str) -> bool {
         .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
 }
 
-/// Constant-time check that `presented` matches one of the configured API
-/// keys.
-///
-/// # Security
-/// Uses `subtle::ConstantTimeEq` and folds every key's comparison with
-/// bitwise-OR so that neither the per-byte match length nor the index of the
-/// matching key leaks through timing / early-exit behaviour. A plain
-/// `Vec<String>::contains` (`String ==`) short-circuits on the first differing
-/// byte, giving an attacker a prefix-length timing oracle for incremental key
-/// recovery; this function does not.
-fn api_key_matches(keys: &[String], presented: &str) -> bool {
-    let presented_bytes = presented.as_bytes();
-    // Start false; OR in each key's constant-time equality without early exit.
-    let mut matched = subtle::Choice::from(0u8);
-    for key in keys {
-        matched |= key.as_bytes().ct_eq(presented_bytes);
-    }
-    matched.into()
-}
-
 impl<S, B> Transform<S, ServiceRequest> for ApiAuth
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
-    B: 'static,
+    B: MessageBody + 'static,
 {
     type Response = ServiceResponse<B>;
     type Error = Error;
@@ -160,8 +200,9 @@ where
     fn new_transform(&self, service: S) -> Self::Future {
         ready(Ok(ApiAuthMiddleware {
             service,
-            api_keys: self.api_keys.clone(),
+            credentials: self.credentials.clone(),
             rate_limiter: self.rate_limiter.clone(),
+            usage: self.usage.clone(),
             enabled: self.enabled,
         }))
     }
@@ -170,8 +211,9 @@ where
 /// API authentication middleware service
 pub struct ApiAuthMiddleware<S> {
     service: S,
-    api_keys: Rc<Vec<String>>,
+    credentials: Rc<Vec<ApiCredential>>,
     rate_limiter: Arc<AuthRateLimiter>,
+    usage: Option<Arc<UsageMeter>>,
     enabled: bool,
 }
 
@@ -179,7 +221,7 @@ impl<S, B> Service<ServiceRequest> for ApiAuthMiddleware<S>
 where
     S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
     S::Future: 'static,
-    B: 'static,
+    B: MessageBody + 'static,
 {
     type Response = ServiceResponse<B>;
     type Error = Error;
@@ -193,6 +235,7 @@ where
         // into this path must also have pinned the server to a trusted
         // interface (the server's default is loopback-only).
         if !self.enabled {
+            req.extensions_mut().insert(GrantedScopes::all());
             let fut = self.service.call(req);
             return Box::pin(async move {
                 let res = fut.await?;
@@ -250,57 +293,86 @@ where
             });
         }
 
-        // Extract API key from Authorization header
-        let auth_header = req.headers().get(header::AUTHORIZATION);
-
-        // Check if API key is valid
-        let is_authorized = match auth_header {
-            Some(auth) => {
-                if let Ok(auth_str) = auth.to_str() {
-                    // Support "Bearer <token>" format for API keys
-                    let api_key = if auth_str.starts_with("Bearer ") {
-                        auth_str.strip_prefix("Bearer ").unwrap_or(auth_str)
-                    } else {
-                        auth_str
-                    };
-
-                    // SECURITY: Authentication is mandatory - no bypass allowed.
-                    // Constant-time comparison across all keys (no timing oracle).
-                    api_key_matches(&self.api_keys, api_key)
-                } else {
-                    false
-                }
-            }
-            None => false,
-        };
+        // Extract API key from Authorization header, supporting the
+        // "Bearer <token>" format
+        let api_key = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|auth| auth.to_str().ok())
+            .map(|auth| auth.strip_prefix("Bearer ").unwrap_or(auth));
 
-        let rate_limiter = self.rate_limiter.clone();
-        let client_ip_clone = client_ip.clone();
+        // SECURITY: Authentication is mandatory - no bypass allowed.
+        // Hashes are compared in constant time (no timing oracle).
+        let credential = api_key.and_then(|key| find_credential(&self.credentials, key));
 
-        if is_authorized {
-            // Record successful authentication
-            rate_limiter.record_successful_auth(&client_ip_clone);
+        let Some(credential) = credential else {
+            // Record failed authentication attempt
+            self.rate_limiter.record_failed_attempt(&client_ip);
+
+            // Log unauthorized access attempt. The URI may carry secrets in
+            // its query, so it goes through the redactor.
+            let message = format!(
+                "Unauthorized API access attempt from {} to {} {}",
+                client_ip,
+                req.method(),
+                req.uri()
+            );
+            warn!("{}", logging::redact(&message));
+
+            return Box::pin(async move { Err(AuthError::Unauthorized.into()) });
+        };
 
+        // Record successful authentication
+        self.rate_limiter.record_successful_auth(&client_ip);
+
+        let scopes = credential.scopes();
+        let required = required_scope(req.method(), req.path());
+        if !scopes.allows(required) {
+            let message = format!(
+                "API key '{}' from {} lacks the '{}' scope for {} {}",
+                credential.name,
+                client_ip,
+                required,
+                req.method(),
+                req.uri()
+            );
+            warn!("{}", logging::redact(&message));
+            return Box::pin(async move { Err(AuthError::InsufficientScope(required).into()) });
+        }
+        req.extensions_mut().insert(scopes);
+
+        let Some(usage) = self.usage.clone() else {
             let fut = self.service.call(req);
-            Box::pin(async move {
+            return Box::pin(async move {
                 let res = fut.await?;
                 Ok(res)
-            })
-        } else {
-            // Record failed authentication attempt
-            rate_limiter.record_failed_attempt(&client_ip);
-
-            // Log unauthorized access attempt
-            warn!("Unauthorized API access attempt from {}", client_ip);
+            });
+        };
 
-            Box::pin(async move { Err(ErrorUnauthorized("Invalid API key")) })
+        let key_id = credential.key_id();
+        if let Err(exceeded) = usage.admit(&key_id, req.path()) {
+            return Box::pin(async move { Err(exceeded.into()) });
         }
+        req.extensions_mut().insert(ApiKeyId(key_id.clone()));
+
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            // Streamed bodies (including compressed ones) have no size
+            // up front and are not counted
+            if let BodySize::Sized(bytes) = res.response().body().size() {
+                usage.record_bytes(&key_id, bytes);
+            }
+            Ok(res)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::middleware::scopes::hash_api_key;
+    use crate::api::usage;
     use actix_web::{
         http::StatusCode,
         test::{call_service, init_service, TestRequest},
@@ -386,6 +458,9 @@ mod tests {
     #[test]
     fn test_api_key_matches_constant_time_helper() {
         let keys = vec!["alpha-key".to_string(), "beta-key".to_string()];
+        let api_key_matches = |keys: &[String], presented: &str| {
+            find_credential(&legacy_admin_credentials(keys), presented).is_some()
+        };
 
         // Exact match against any configured key.
         assert!(api_key_matches(&keys, "alpha-key"));
@@ -401,6 +476,47 @@ mod tests {
         assert!(!api_key_matches(&[], "alpha-key"));
     }
 
+    #[actix_web::test]
+    async fn test_usage_meter_counts_and_enforces_quota() {
+        let key = "metered-key";
+        let policy = usage::QuotaPolicy {
+            default_limit: Some(2),
+            ..Default::default()
+        };
+        let meter = Arc::new(UsageMeter::new(policy, Arc::new(usage::SystemClock)));
+        let app = init_service(
+            App::new()
+                .wrap(
+                    ApiAuth::from_validated_keys(vec![key.to_string()])
+                        .with_usage_meter(meter.clone()),
+                )
+                .route("/api/v1/node/info", web::get().to(test_handler)),
+        )
+        .await;
+
+        let request = || {
+            TestRequest::get()
+                .uri("/api/v1/node/info")
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", key)))
+                .to_request()
+        };
+        for _ in 0..2 {
+            assert_eq!(call_service(&app, request()).await.status(), StatusCode::OK);
+        }
+        let err = app.call(request()).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::TOO_MANY_REQUESTS
+        );
+
+        let report = meter
+            .usage(&usage::key_id(key), usage::UsagePeriod::Day)
+            .unwrap();
+        assert_eq!(report.usage.requests["node"], 2);
+        assert_eq!(report.usage.bytes_served, 2 * "success".len() as u64);
+        assert_eq!(report.usage.quota_rejections, 1);
+    }
+
     #[actix_web::test]
     async fn test_no_auth_bypass() {
         // Ensure authentication cannot be bypassed
@@ -428,4 +544,119 @@ mod tests {
         let resp = call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
     }
+
+    #[actix_web::test]
+    async fn test_scopes_bound_each_route() {
+        let credential = |name: &str, scope| {
+            let hash = hex::encode(hash_api_key(&format!("{}-key", name)));
+            ApiCredential::from_hash(name, &hash, vec![scope]).unwrap()
+        };
+        let credentials = vec![
+            credential("read", ApiScope::Read),
+            credential("wallet", ApiScope::Wallet),
+            credential("admin", ApiScope::Admin),
+        ];
+        let rate_limiter = Arc::new(AuthRateLimiter::new(AuthRateLimiterConfig::default()));
+        let app = init_service(
+            App::new()
+                .wrap(ApiAuth::from_credentials_with_rate_limiter(
+                    credentials,
+                    rate_limiter,
+                ))
+                .route("/api/v1/blockchain/stats", web::get().to(test_handler))
+                .route("/api/v1/mempool/info", web::get().to(test_handler))
+                .route("/api/v1/wallet/balance", web::get().to(test_handler))
+                .route("/api/v1/mempool/submit", web::post().to(test_handler))
+                .route("/api/v1/node/shutdown", web::post().to(test_handler))
+                .route("/api/v1/mining/start", web::post().to(test_handler)),
+        )
+        .await;
+
+        let status = |method: &str, path: &str, key: &str| {
+            let request = match method {
+                "GET" => TestRequest::get(),
+                _ => TestRequest::post(),
+            }
+            .uri(path)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}-key", key)))
+            .to_request();
+            let response = app.call(request);
+            async move {
+                match response.await {
+                    Ok(res) => res.status(),
+                    Err(err) => err.as_response_error().status_code(),
+                }
+            }
+        };
+
+        let cases = [
+            ("GET", "/api/v1/blockchain/stats", [true, true, true]),
+            ("GET", "/api/v1/mempool/info", [true, true, true]),
+            ("GET", "/api/v1/wallet/balance", [false, true, true]),
+            ("POST", "/api/v1/mempool/submit", [false, true, true]),
+            ("POST", "/api/v1/node/shutdown", [false, false, true]),
+            ("POST", "/api/v1/mining/start", [false, false, true]),
+        ];
+        for (method, path, allowed) in cases {
+            for (key, allowed) in ["read", "wallet", "admin"].into_iter().zip(allowed) {
+                let expected = if allowed {
+                    StatusCode::OK
+                } else {
+                    StatusCode::FORBIDDEN
+                };
+                assert_eq!(
+                    status(method, path, key).await,
+                    expected,
+                    "{} {} with {}",
+                    method,
+                    path,
+                    key
+                );
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_unauthenticated_requests_get_structured_401() {
+        let app = init_service(
+            App::new()
+                .wrap(ApiAuth::from_validated_keys(vec!["test-key".to_string()]))
+                .route("/api/v1/node/shutdown", web::post().to(test_handler)),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/api/v1/node/shutdown")
+            .to_request();
+        let err = app.call(req).await.unwrap_err();
+        let response = err.error_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(response.headers().contains_key(header::WWW_AUTHENTICATE));
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "Missing or invalid API key");
+    }
+
+    #[actix_web::test]
+    async fn test_disabled_auth_grants_every_scope() {
+        async fn scopes(req: actix_web::HttpRequest) -> HttpResponse {
+            let admin = req
+                .extensions()
+                .get::<GrantedScopes>()
+                .is_some_and(|scopes| scopes.allows(ApiScope::Admin));
+            HttpResponse::Ok().json(admin)
+        }
+        let app = init_service(
+            App::new()
+                .wrap(ApiAuth::disabled())
+                .route("/api/v1/node/config", web::get().to(scopes)),
+        )
+        .await;
+        let req = TestRequest::get().uri("/api/v1/node/config").to_request();
+        let body = actix_web::test::call_and_read_body(&app, req).await;
+        assert_eq!(&body[..], b"true");
+    }
 }
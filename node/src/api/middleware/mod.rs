@@ -8,10 +8,12 @@ pub mod auth_rate_limiter;
 pub mod distributed_rate_limit;
 pub mod logging;
 pub mod rate_limiting;
+pub mod scopes;
 
 // Re-export middleware components
-pub use auth::ApiAuth;
+pub use auth::{ApiAuth, AuthError};
 pub use auth_rate_limiter::{AuthRateLimiter, AuthRateLimiterConfig};
 pub use distributed_rate_limit::{DistributedRateLimiter, DistributedRateLimitConfig, RateLimitResult};
 pub use logging::ApiLogger;
 pub use rate_limiting::RateLimiter;
+pub use scopes::{ApiCredential, ApiScope, GrantedScopes};
@@ -0,0 +1,294 @@
+//! API permission scopes
+//!
+//! Every API key carries a set of scopes and every route requires one.
+//! `read` covers chain, mempool and network state; `wallet` adds spending and
+//! wallet access; `admin` covers node management and mining control, and
+//! grants everything else too.
+//!
+//! Routes are matched against [`ROUTE_SCOPES`] in order; routes not listed
+//! need `read` for GET and `admin` for anything that changes state, so a new
+//! route is never accidentally writable by a read-only key.
+
+use actix_web::http::Method;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use subtle::ConstantTimeEq;
+
+/// A permission scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiScope {
+    Read,
+    Wallet,
+    Admin,
+}
+
+impl ApiScope {
+    /// Whether holding `self` allows a request that requires `required`
+    pub fn grants(self, required: ApiScope) -> bool {
+        match self {
+            ApiScope::Admin => true,
+            // Wallet clients need chain state to build transactions
+            ApiScope::Wallet => required != ApiScope::Admin,
+            ApiScope::Read => required == ApiScope::Read,
+        }
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiScope::Read => write!(f, "read"),
+            ApiScope::Wallet => write!(f, "wallet"),
+            ApiScope::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Scopes of the key a request was authenticated with, stored in the
+/// request extensions by the auth middleware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrantedScopes(Vec<ApiScope>);
+
+impl GrantedScopes {
+    pub fn new(scopes: Vec<ApiScope>) -> Self {
+        Self(scopes)
+    }
+
+    /// Every scope; granted when authentication is disabled
+    pub fn all() -> Self {
+        Self(vec![ApiScope::Admin])
+    }
+
+    pub fn allows(&self, required: ApiScope) -> bool {
+        self.0.iter().any(|scope| scope.grants(required))
+    }
+}
+
+/// A configured API key, held only as its SHA-256 hash
+#[derive(Debug, Clone)]
+pub struct ApiCredential {
+    /// Operator-chosen name, used in logs
+    pub name: String,
+    hash: [u8; 32],
+    scopes: Vec<ApiScope>,
+}
+
+impl ApiCredential {
+    /// Credential for a key given as the hex SHA-256 of the secret
+    pub fn from_hash(
+        name: impl Into<String>,
+        hash_hex: &str,
+        scopes: Vec<ApiScope>,
+    ) -> Result<Self, String> {
+        let name = name.into();
+        let hash = hex::decode(hash_hex.trim())
+            .ok()
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+            .ok_or_else(|| {
+                format!(
+                    "API token '{}' must have a 64-character hex SHA-256 hash",
+                    name
+                )
+            })?;
+        if scopes.is_empty() {
+            return Err(format!("API token '{}' has no scopes", name));
+        }
+        Ok(Self { name, hash, scopes })
+    }
+
+    /// Credential for a plaintext key
+    pub fn from_plaintext(name: impl Into<String>, key: &str, scopes: Vec<ApiScope>) -> Self {
+        Self {
+            name: name.into(),
+            hash: hash_api_key(key),
+            scopes,
+        }
+    }
+
+    pub fn scopes(&self) -> GrantedScopes {
+        GrantedScopes::new(self.scopes.clone())
+    }
+
+    /// Usage-meter id of this key; matches `usage::key_id` of the secret
+    pub fn key_id(&self) -> String {
+        hex::encode(&self.hash[..8])
+    }
+}
+
+/// Credentials for plaintext keys from `api_keys`, which predate scopes and
+/// keep full access
+pub fn legacy_admin_credentials(api_keys: &[String]) -> Vec<ApiCredential> {
+    api_keys
+        .iter()
+        .enumerate()
+        .map(|(i, key)| {
+            ApiCredential::from_plaintext(format!("api_keys[{}]", i), key, vec![ApiScope::Admin])
+        })
+        .collect()
+}
+
+/// SHA-256 of an API key, as stored in `api_tokens`
+pub fn hash_api_key(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+/// Credential whose hash matches `presented`.
+///
+/// # Security
+/// Every configured hash is compared in constant time and the loop never
+/// exits early, so neither match length nor the matching index leaks
+/// through timing.
+pub fn find_credential<'a>(
+    credentials: &'a [ApiCredential],
+    presented: &str,
+) -> Option<&'a ApiCredential> {
+    let presented = hash_api_key(presented);
+    let mut found = None;
+    for credential in credentials {
+        if bool::from(credential.hash.ct_eq(&presented)) {
+            found = Some(credential);
+        }
+    }
+    found
+}
+
+/// Scope required by requests to a path prefix
+struct RouteScope {
+    prefix: &'static str,
+    /// `None` matches every method
+    method: Option<&'static str>,
+    scope: ApiScope,
+}
+
+const fn route(prefix: &'static str, method: Option<&'static str>, scope: ApiScope) -> RouteScope {
+    RouteScope {
+        prefix,
+        method,
+        scope,
+    }
+}
+
+/// Scopes of routes whose needs differ from the method default. The first
+/// matching entry applies.
+const ROUTE_SCOPES: &[RouteScope] = &[
+    // JSON-RPC checks each method's scope itself (`api::jsonrpc`)
+    route("/rpc", None, ApiScope::Read),
+    route("/batch", None, ApiScope::Read),
+    // Node management: only the node's public state is readable
+    route("/api/v1/node/info", Some("GET"), ApiScope::Read),
+    route("/api/v1/node/status", Some("GET"), ApiScope::Read),
+    route("/api/v1/node/system", Some("GET"), ApiScope::Read),
+    route("/api/v1/node/my-usage", Some("GET"), ApiScope::Read),
+    route("/api/v1/node", None, ApiScope::Admin),
+    // Mining control
+    route("/api/v1/mining/config", None, ApiScope::Admin),
+    route("/api/v1/mining/template", None, ApiScope::Admin),
+    route("/api/v1/mining/template-audit", None, ApiScope::Admin),
+    // Spending
+    route("/api/v1/wallet", None, ApiScope::Wallet),
+    route("/api/v1/mempool/submit", Some("POST"), ApiScope::Wallet),
+    route("/api/v1/blockchain/submit", Some("POST"), ApiScope::Wallet),
+    route("/api/v1/faucet/send", Some("POST"), ApiScope::Wallet),
+    route("/api/v1/lightning/channel", Some("POST"), ApiScope::Wallet),
+    route(
+        "/api/v1/lightning/channel",
+        Some("DELETE"),
+        ApiScope::Wallet,
+    ),
+    route("/api/v1/lightning/pay", Some("POST"), ApiScope::Wallet),
+    route("/api/v1/lightning/invoice", Some("POST"), ApiScope::Wallet),
+    route("/api/v1/lightning/invoices", None, ApiScope::Wallet),
+    route("/api/v1/lightning/payments", None, ApiScope::Wallet),
+    // Transaction validation only reads state
+    route("/api/v1/mempool/validate", Some("POST"), ApiScope::Read),
+];
+
+/// Scope a request to `path` with `method` requires
+pub fn required_scope(method: &Method, path: &str) -> ApiScope {
+    let matches_prefix = |prefix: &str| {
+        path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    };
+    let rule = ROUTE_SCOPES.iter().find(|rule| {
+        let method_matches = rule.method.is_none() || rule.method == Some(method.as_str());
+        matches_prefix(rule.prefix) && method_matches
+    });
+    match rule {
+        Some(rule) => rule.scope,
+        None if path == "/" => ApiScope::Read,
+        None if *method == Method::GET || *method == Method::HEAD => ApiScope::Read,
+        None => ApiScope::Admin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scopes_grant_along_the_hierarchy() {
+        use ApiScope::*;
+        assert!(Admin.grants(Admin) && Admin.grants(Wallet) && Admin.grants(Read));
+        assert!(Wallet.grants(Wallet) && Wallet.grants(Read));
+        assert!(!Wallet.grants(Admin));
+        assert!(Read.grants(Read));
+        assert!(!Read.grants(Wallet) && !Read.grants(Admin));
+    }
+
+    #[test]
+    fn routes_require_their_scope() {
+        let get = |path| required_scope(&Method::GET, path);
+        let post = |path| required_scope(&Method::POST, path);
+
+        assert_eq!(get("/api/v1/blockchain/block/10"), ApiScope::Read);
+        assert_eq!(get("/api/v1/mempool/transactions"), ApiScope::Read);
+        assert_eq!(get("/api/v1/node/info"), ApiScope::Read);
+        assert_eq!(get("/api/v1/node/config"), ApiScope::Admin);
+        assert_eq!(get("/api/v1/node/logs"), ApiScope::Admin);
+        assert_eq!(post("/api/v1/node/shutdown"), ApiScope::Admin);
+        assert_eq!(post("/api/v1/mining/start"), ApiScope::Admin);
+        assert_eq!(get("/api/v1/mining/template"), ApiScope::Admin);
+        assert_eq!(get("/api/v1/mining/info"), ApiScope::Read);
+        assert_eq!(get("/api/v1/wallet/balance"), ApiScope::Wallet);
+        assert_eq!(post("/api/v1/mempool/submit"), ApiScope::Wallet);
+        assert_eq!(post("/rpc"), ApiScope::Read);
+        assert_eq!(post("/"), ApiScope::Read);
+        // Unlisted writes default to admin
+        assert_eq!(post("/api/v1/network/peers"), ApiScope::Admin);
+        // Prefixes match whole path segments only
+        assert_eq!(get("/api/v1/wallet-stats"), ApiScope::Read);
+    }
+
+    #[test]
+    fn credentials_are_matched_by_hash() {
+        let hash = hex::encode(hash_api_key("reader-key"));
+        let reader = ApiCredential::from_hash("reader", &hash, vec![ApiScope::Read]).unwrap();
+        let admin = ApiCredential::from_plaintext("admin", "admin-key", vec![ApiScope::Admin]);
+        let credentials = [reader, admin];
+
+        let found = find_credential(&credentials, "reader-key").unwrap();
+        assert_eq!(found.name, "reader");
+        assert_eq!(found.key_id(), crate::api::usage::key_id("reader-key"));
+        assert!(!found.scopes().allows(ApiScope::Wallet));
+        assert_eq!(
+            find_credential(&credentials, "admin-key").unwrap().name,
+            "admin"
+        );
+        assert!(find_credential(&credentials, "reader").is_none());
+        assert!(find_credential(&credentials, "").is_none());
+        assert!(find_credential(&[], "reader-key").is_none());
+    }
+
+    #[test]
+    fn malformed_token_hashes_are_rejected() {
+        assert!(ApiCredential::from_hash("short", "abcd", vec![ApiScope::Read]).is_err());
+        assert!(ApiCredential::from_hash("hex", &"zz".repeat(32), vec![ApiScope::Read]).is_err());
+        let hash = "ab".repeat(32);
+        assert!(ApiCredential::from_hash("none", &hash, Vec::new()).is_err());
+        assert!(ApiCredential::from_hash("ok", &hash, vec![ApiScope::Admin]).is_ok());
+    }
+}
@@ -4,11 +4,15 @@
 //! providing endpoints for blocks, transactions, wallet operations, network information,
 //! environmental data, and Lightning Network functionality.
 
+pub mod address_subscriptions;
+pub mod charts;
 pub mod docs;
 mod error;
+pub mod event_subscriptions;
 pub mod middleware;
 pub mod rate_limiter;   // API rate limiting
 pub mod routes;
+pub mod search;
 mod server;
 pub mod types;
 // pub mod blockchain_api;  // Missing file
@@ -21,11 +25,15 @@ pub mod lightning_api;
 // pub mod wallet;          // Missing file
 // pub mod node;            // Missing file
 pub mod faucet_wrapper;
+pub mod idempotency;
+pub mod jobs;
 pub mod metrics;
 pub mod jsonrpc;         // JSON-RPC 2.0 API enabled
+pub mod usage;
+pub mod webhooks;
 
 pub use error::{ApiError, Result};
-pub use server::{ApiConfig, ApiServer};
+pub use server::{ApiConfig, ApiServer, ApiTokenConfig};
 pub use types::*;
 pub use rate_limiter::{ApiRateLimiter, ApiRateLimitConfig, ApiRateLimitStats};
 
@@ -0,0 +1,133 @@
+//! Address API routes
+//!
+//! Balance, unspent outputs and history of an address, served from the
+//! address index (`storage.address_index`). `{address}` is a bech32m address
+//! or, for outputs without one, the hex-encoded output script.
+
+use actix_web::web;
+
+use super::NodeData;
+use crate::api::error::{ApiError, ApiResult};
+use crate::storage::address_index::{self, HISTORY_PAGE_SIZE};
+use crate::storage::{AddressBalance, AddressHistory, AddressUtxos};
+
+/// Configure address routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/{address}/utxos", web::get().to(get_address_utxos))
+        .route("/{address}/balance", web::get().to(get_address_balance))
+        .route("/{address}/history", web::get().to(get_address_history));
+}
+
+/// Output script of `address`, once the address index is usable
+fn indexed_script(node: &NodeData, address: &str) -> ApiResult<Vec<u8>> {
+    let storage = node.storage();
+    if !storage.address_index_enabled() {
+        return Err(ApiError::service_unavailable(
+            "Address index is disabled; enable storage.address_index",
+        ));
+    }
+    let building = storage
+        .tx_index_next_height()
+        .map_err(|e| ApiError::internal_error(format!("Failed to read index state: {}", e)))?;
+    if let Some(height) = building {
+        return Err(ApiError::service_unavailable(format!(
+            "Address index is being built (at height {})",
+            height
+        )));
+    }
+    address_index::resolve_address(address)
+        .map_err(|e| ApiError::bad_request(format!("Invalid address: {}", e)))
+}
+
+/// Get the unspent outputs of an address
+///
+/// Returns every confirmed unspent output paying the address.
+#[utoipa::path(
+    get,
+    path = "/api/v1/address/{address}/utxos",
+    params(
+        ("address" = String, Path, description = "Bech32m address or hex-encoded output script")
+    ),
+    responses(
+        (status = 200, description = "Unspent outputs retrieved successfully", body = AddressUtxos),
+        (status = 400, description = "Invalid address", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError),
+        (status = 503, description = "Address index disabled or still being built", body = ApiError)
+    )
+)]
+pub async fn get_address_utxos(
+    path: web::Path<String>,
+    node: NodeData,
+) -> ApiResult<web::Json<AddressUtxos>> {
+    let script = indexed_script(&node, &path)?;
+    address_index::utxos(&node.storage(), &script)
+        .map(web::Json)
+        .map_err(|e| ApiError::internal_error(format!("Failed to read address index: {}", e)))
+}
+
+/// Get the balance of an address
+///
+/// Returns the confirmed balance with lifetime totals received and sent.
+#[utoipa::path(
+    get,
+    path = "/api/v1/address/{address}/balance",
+    params(
+        ("address" = String, Path, description = "Bech32m address or hex-encoded output script")
+    ),
+    responses(
+        (status = 200, description = "Balance retrieved successfully", body = AddressBalance),
+        (status = 400, description = "Invalid address", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError),
+        (status = 503, description = "Address index disabled or still being built", body = ApiError)
+    )
+)]
+pub async fn get_address_balance(
+    path: web::Path<String>,
+    node: NodeData,
+) -> ApiResult<web::Json<AddressBalance>> {
+    let script = indexed_script(&node, &path)?;
+    address_index::balance(&node.storage(), &script)
+        .map(web::Json)
+        .map_err(|e| ApiError::internal_error(format!("Failed to read address index: {}", e)))
+}
+
+/// Query parameters for address history
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct HistoryParams {
+    /// Page number, from 0 (default 0); pages hold 50 transactions
+    pub page: Option<usize>,
+}
+
+/// Get the transaction history of an address
+///
+/// Returns the confirmed transactions paying or spending from the address,
+/// newest first, with the amount each moved in and out.
+#[utoipa::path(
+    get,
+    path = "/api/v1/address/{address}/history",
+    params(
+        ("address" = String, Path, description = "Bech32m address or hex-encoded output script"),
+        HistoryParams
+    ),
+    responses(
+        (status = 200, description = "History retrieved successfully", body = AddressHistory),
+        (status = 400, description = "Invalid address", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError),
+        (status = 503, description = "Address index disabled or still being built", body = ApiError)
+    )
+)]
+pub async fn get_address_history(
+    path: web::Path<String>,
+    params: web::Query<HistoryParams>,
+    node: NodeData,
+) -> ApiResult<web::Json<AddressHistory>> {
+    let script = indexed_script(&node, &path)?;
+    address_index::history(
+        &node.storage(),
+        &script,
+        params.page.unwrap_or(0),
+        HISTORY_PAGE_SIZE,
+    )
+    .map(web::Json)
+    .map_err(|e| ApiError::internal_error(format!("Failed to read address index: {}", e)))
+}
@@ -3,16 +3,27 @@
 //! This module provides API endpoints for accessing blockchain data,
 //! including blocks and transactions.
 
-use actix_web::web;
+use actix_web::{web, HttpRequest, HttpResponse};
 use bincode;
 
 use super::NodeData;
 use crate::api::error::{ApiError, ApiResult};
 use crate::api::types::{
-    BlockInfo, BlockchainInfo, BlockchainStats, SubmitTxRequest, TransactionInfo,
+    BlockInfo, BlockchainInfo, BlockchainStats, DataOutput, DeploymentInfo, DeploymentStatistics,
+    RejectionTraceInfo, SubmitTxRequest, TraceStepInfo, TransactionInfo,
     TransactionSubmissionResponse,
 };
+use crate::api::idempotency::{
+    idempotency_key, request_fingerprint, request_scope, StoredResponse,
+};
+use crate::api::charts::{self, ChartError, ChartMetric, ChartResponse, DEFAULT_CHART_POINTS};
+use crate::api::search::{self, SearchError, SearchResponse};
+use crate::metrics::rejections::RejectionStats;
+use crate::storage::SupplyAudit;
+use super::mempool::RejectionParams;
 use supernova_core::blockchain::{calculate_difficulty_from_bits, calculate_hashrate};
+use supernova_core::script::classify;
+use supernova_core::validation::TraceOutcome;
 
 /// Configure blockchain routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -21,7 +32,13 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .route("/block/hash/{hash}", web::get().to(get_block_by_hash))
         .route("/transaction/{txid}", web::get().to(get_transaction))
         .route("/submit", web::post().to(submit_transaction))
-        .route("/stats", web::get().to(get_blockchain_stats));
+        .route("/stats", web::get().to(get_blockchain_stats))
+        .route("/deployments", web::get().to(get_deployments))
+        .route("/rejections", web::get().to(get_block_rejections))
+        .route("/rejections/{hash}/trace", web::get().to(get_rejection_trace))
+        .route("/search", web::get().to(search_blockchain))
+        .route("/charts/{metric}", web::get().to(get_chart))
+        .route("/supply", web::get().to(get_supply));
 }
 
 /// Get blockchain information
@@ -70,6 +87,7 @@ pub async fn get_blockchain_info(node: NodeData) -> ApiResult<BlockchainInfo> {
         .map(|c| c.network.network_id.clone())
         .unwrap_or_else(|_| "supernova-testnet".to_string());
 
+    let prune_height = node.prune_height();
     let info = BlockchainInfo {
         height,
         best_block_hash: hex::encode(best_block_hash),
@@ -77,6 +95,8 @@ pub async fn get_blockchain_info(node: NodeData) -> ApiResult<BlockchainInfo> {
         total_work,
         network: network_id,
         version: env!("CARGO_PKG_VERSION").to_string(),
+        pruned: prune_height.is_some(),
+        prune_height,
     };
 
     Ok(info)
@@ -241,7 +261,8 @@ pub async fn get_block_by_hash(path: web::Path<String>, node: NodeData) -> ApiRe
 
 /// Get a transaction by ID
 ///
-/// Returns detailed information about a transaction.
+/// Returns detailed information about a transaction. Confirmed transactions
+/// are found through the transaction index (`storage.tx_index`).
 #[utoipa::path(
     get,
     path = "/api/v1/blockchain/transaction/{txid}",
@@ -302,11 +323,16 @@ pub async fn get_transaction(
                 .iter()
                 .enumerate()
                 .map(|(i, output)| {
-                    serde_json::json!({
+                    let class = classify(output.script_pubkey());
+                    let mut json = serde_json::json!({
                         "value": output.value(),
                         "n": i,
-                        "script_pubkey": hex::encode(output.script_pubkey())
-                    })
+                        "script_pubkey": hex::encode(output.script_pubkey()),
+                        "script_type": class.kind().as_str(),
+                        "address": class.address()
+                    });
+                    add_data_payload(&mut json, &class.class);
+                    json
                 })
                 .collect(),
             block_hash: None,
@@ -318,48 +344,47 @@ pub async fn get_transaction(
         return Ok(tx_info);
     }
 
-    // Check blockchain storage
-    let tx = storage
-        .get_transaction(&tx_hash)
-        .map_err(|e| ApiError::internal_error(format!("Failed to get transaction: {}", e)))?
-        .ok_or_else(|| ApiError::not_found("Transaction not found"))?;
+    // Confirmed transactions come from the transaction index; others kept in
+    // storage (e.g. by the wallet) are reported without a block
+    let confirmed = node
+        .chain_state()
+        .read()
+        .map_err(|_| ApiError::internal_error("Chain state lock poisoned"))?
+        .get_transaction_by_id(&tx_hash)
+        .map_err(|e| ApiError::internal_error(format!("Failed to get transaction: {}", e)))?;
+    let (tx, block_info) = match confirmed {
+        Some(confirmed) => (
+            confirmed.transaction,
+            Some((
+                confirmed.block_hash,
+                confirmed.block_height,
+                confirmed.confirmations,
+                confirmed.block_time,
+            )),
+        ),
+        None => (
+            storage
+                .get_transaction(&tx_hash)
+                .map_err(|e| ApiError::internal_error(format!("Failed to get transaction: {}", e)))?
+                .ok_or_else(|| ApiError::not_found("Transaction not found"))?,
+            None,
+        ),
+    };
 
     // Calculate transaction size and weight
     let tx_size = bincode::serialize(&tx).unwrap_or_default().len();
     let vsize = tx_size; // Simplified - in reality would consider witness data
     let weight = tx_size * 4; // Simplified weight calculation
 
-    // Get block information if transaction is in a block
-    let (block_hash, block_height, confirmations, time, block_time) = if let Some(block_hash) =
-        storage.get_transaction_block(&tx_hash).map_err(|e| {
-            ApiError::internal_error(format!("Failed to get transaction block: {}", e))
-        })? {
-        let block_height = storage
-            .get_block_height(&block_hash)
-            .map_err(|e| ApiError::internal_error(format!("Failed to get block height: {}", e)))?
-            .unwrap_or(0);
-
-        let block = storage
-            .get_block(&block_hash)
-            .map_err(|e| ApiError::internal_error(format!("Failed to get block: {}", e)))?
-            .ok_or_else(|| ApiError::not_found("Block not found"))?;
-
-        let confirmations = node
-            .chain_state()
-            .read()
-            .map(|state| state.get_height().saturating_sub(block_height) + 1)
-            .unwrap_or(0);
-        let block_time = block.timestamp();
-
-        (
+    let (block_hash, block_height, confirmations, time, block_time) = match block_info {
+        Some((block_hash, block_height, confirmations, block_time)) => (
             Some(hex::encode(block_hash)),
             Some(block_height),
             confirmations,
             Some(block_time),
             Some(block_time),
-        )
-    } else {
-        (None, None, 0, None, None)
+        ),
+        None => (None, None, 0, None, None),
     };
 
     // Get input and output information
@@ -387,11 +412,14 @@ pub async fn get_transaction(
         .iter()
         .enumerate()
         .map(|(i, output)| {
-            let spent_info = storage
-                .get_transaction_output(&tx_hash, i as u32)
-                .ok()
-                .flatten();
-            let is_spent = spent_info.is_none();
+            let class = classify(output.script_pubkey());
+            // Data outputs never enter the UTXO set but were not spent either
+            let is_spent = !class.class.is_unspendable()
+                && storage
+                    .get_transaction_output(&tx_hash, i as u32)
+                    .ok()
+                    .flatten()
+                    .is_none();
             let spent_by_tx = if is_spent {
                 storage
                     .is_output_spent(&tx_hash, i as u32)
@@ -402,13 +430,17 @@ pub async fn get_transaction(
                 None
             };
 
-            serde_json::json!({
+            let mut json = serde_json::json!({
                 "value": output.value(),
                 "n": i,
                 "script_pubkey": hex::encode(output.script_pubkey()),
+                "script_type": class.kind().as_str(),
+                "address": class.address(),
                 "spent": is_spent,
                 "spent_by": spent_by_tx
-            })
+            });
+            add_data_payload(&mut json, &class.class);
+            json
         })
         .collect();
 
@@ -435,19 +467,48 @@ pub async fn get_transaction(
 /// Submit a transaction to the blockchain
 ///
 /// Submits a new transaction to the mempool for validation and broadcasting.
+///
+/// With an `Idempotency-Key` header, retries with the same key replay the
+/// first attempt's response instead of submitting again.
 #[utoipa::path(
     post,
     path = "/api/v1/blockchain/submit",
     request_body = SubmitTxRequest,
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Client key making retries of this submission safe")
+    ),
     responses(
         (status = 200, description = "Transaction submitted successfully", body = TransactionSubmissionResponse),
         (status = 400, description = "Invalid transaction", body = ApiError),
+        (status = 422, description = "Idempotency key invalid or reused for a different transaction", body = ApiError),
         (status = 500, description = "Internal server error", body = ApiError)
     )
 )]
 pub async fn submit_transaction(
+    http_req: HttpRequest,
     request: web::Json<SubmitTxRequest>,
     node: NodeData,
+) -> ApiResult<HttpResponse> {
+    let Some(key) = idempotency_key(http_req.headers()) else {
+        let response = submit_to_mempool(&request, &node)?;
+        return Ok(HttpResponse::Ok().json(response));
+    };
+
+    let fingerprint = request_fingerprint("blockchain/submit", request.raw_tx.as_bytes());
+    let (stored, replayed) = node
+        .idempotency()
+        .execute(&request_scope(&http_req), &key, fingerprint, || async {
+            StoredResponse::from_result(submit_to_mempool(&request, &node))
+        })
+        .await
+        .map_err(|e| ApiError::unprocessable_entity(e.to_string()))?;
+
+    Ok(stored.to_http_response(replayed))
+}
+
+fn submit_to_mempool(
+    request: &SubmitTxRequest,
+    node: &NodeData,
 ) -> ApiResult<TransactionSubmissionResponse> {
     // Parse the raw transaction
     let tx_data = hex::decode(&request.raw_tx)
@@ -481,6 +542,9 @@ pub async fn submit_transaction(
             crate::mempool::MempoolError::FeeTooLow { .. } => {
                 Err(ApiError::bad_request("Insufficient transaction fee"))
             }
+            crate::mempool::MempoolError::DataCarrier(_) => {
+                Err(ApiError::bad_request(e.to_string()))
+            }
             _ => Err(ApiError::internal_error(format!(
                 "Failed to add transaction to mempool: {}",
                 e
@@ -489,6 +553,13 @@ pub async fn submit_transaction(
     }
 }
 
+/// Add the decoded payload of a data carrier output to its JSON as `data`
+fn add_data_payload(output: &mut serde_json::Value, class: &classify::ScriptClass) {
+    if let Some(payload) = class.data_payload() {
+        output["data"] = serde_json::json!(DataOutput::from_payload(&payload));
+    }
+}
+
 /// Get blockchain statistics
 ///
 /// Returns statistical information about the blockchain.
@@ -546,3 +617,245 @@ pub async fn get_blockchain_stats(node: NodeData) -> ApiResult<BlockchainStats>
 
     Ok(stats)
 }
+
+/// Get soft-fork deployment states
+///
+/// Returns every version-bits deployment with its state for the next block
+/// and, while signaling is open, the current window's signaling statistics.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/deployments",
+    responses(
+        (status = 200, description = "Deployment states retrieved successfully", body = Vec<DeploymentInfo>),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_deployments(node: NodeData) -> ApiResult<web::Json<Vec<DeploymentInfo>>> {
+    let chain_state = node.chain_state();
+    let statuses = chain_state
+        .read()
+        .map_err(|_| ApiError::internal_error("Chain state lock poisoned"))?
+        .deployments()
+        .map_err(|e| ApiError::internal_error(format!("Failed to compute deployments: {}", e)))?;
+
+    let deployments = statuses
+        .into_iter()
+        .map(|status| DeploymentInfo {
+            name: status.deployment.name,
+            bit: status.deployment.bit,
+            state: status.state.to_string(),
+            start_time: status.deployment.start_time,
+            timeout: status.deployment.timeout,
+            threshold_percent: status.deployment.threshold_percent,
+            statistics: status.stats.map(|stats| DeploymentStatistics {
+                period: stats.period,
+                threshold: stats.threshold,
+                elapsed: stats.elapsed,
+                count: stats.count,
+                possible: stats.possible,
+            }),
+        })
+        .collect();
+
+    Ok(web::Json(deployments))
+}
+
+/// Get block validation rejection statistics
+///
+/// Returns block validation failure counts by reason code and by peer, plus
+/// the most recently rejected blocks. Reason codes are stable; see
+/// `RejectionRecord`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/rejections",
+    params(
+        RejectionParams
+    ),
+    responses(
+        (status = 200, description = "Block rejection statistics retrieved successfully", body = RejectionStats),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_block_rejections(
+    params: web::Query<RejectionParams>,
+    node: NodeData,
+) -> ApiResult<web::Json<RejectionStats>> {
+    let limit = params.limit.unwrap_or(50);
+    Ok(web::Json(node.block_rejections().snapshot(limit)))
+}
+
+/// Get the validation trace of a rejected block
+///
+/// Returns every check the node ran against the block, ending at the one
+/// that rejected it. Traces are only recorded with `node.validation_tracing`
+/// enabled, and only for the most recent rejections.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/rejections/{hash}/trace",
+    params(
+        ("hash" = String, Path, description = "Block hash")
+    ),
+    responses(
+        (status = 200, description = "Rejection trace retrieved successfully", body = RejectionTraceInfo),
+        (status = 400, description = "Invalid block hash", body = ApiError),
+        (status = 404, description = "No trace recorded for this block", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_rejection_trace(
+    path: web::Path<String>,
+    node: NodeData,
+) -> ApiResult<web::Json<RejectionTraceInfo>> {
+    let hash = hex::decode(path.into_inner())
+        .map_err(|_| ApiError::bad_request("Invalid block hash format"))?;
+    let block_hash: [u8; 32] = hash
+        .try_into()
+        .map_err(|_| ApiError::bad_request("Invalid block hash length"))?;
+
+    let rejection = node
+        .chain_state()
+        .read()
+        .map_err(|_| ApiError::internal_error("Chain state lock poisoned"))?
+        .rejection_trace(&block_hash)
+        .map_err(|e| ApiError::internal_error(format!("Failed to read rejection trace: {}", e)))?
+        .ok_or_else(|| ApiError::not_found("No validation trace recorded for this block"))?;
+
+    let steps = rejection
+        .trace
+        .steps()
+        .iter()
+        .map(|step| {
+            let (status, reason) = match &step.outcome {
+                TraceOutcome::Passed => ("passed", None),
+                TraceOutcome::Failed { reason } => ("failed", Some(reason.clone())),
+                TraceOutcome::Skipped { reason } => ("skipped", Some(reason.clone())),
+            };
+            TraceStepInfo {
+                check: step.check.clone(),
+                inputs: step.inputs.clone(),
+                status: status.to_string(),
+                reason,
+                duration_us: step.duration_us,
+            }
+        })
+        .collect();
+
+    Ok(web::Json(RejectionTraceInfo {
+        block_hash: hex::encode(rejection.block_hash),
+        height: rejection.height,
+        rejected_at: rejection.rejected_at,
+        steps,
+        omitted_steps: rejection.trace.omitted_steps(),
+    }))
+}
+
+/// Query parameters for explorer search
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct SearchParams {
+    /// Block height, block hash, txid, address, or hex prefix (min 8 chars)
+    pub q: String,
+}
+
+/// Search blocks, transactions and addresses
+///
+/// Classifies the query and returns every matching entity. Partial hashes are
+/// resolved by prefix; ambiguous queries return several candidates.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/search",
+    params(
+        SearchParams
+    ),
+    responses(
+        (status = 200, description = "Search completed", body = SearchResponse),
+        (status = 400, description = "Query too short, malformed, or for another network", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn search_blockchain(
+    params: web::Query<SearchParams>,
+    node: NodeData,
+) -> ApiResult<web::Json<SearchResponse>> {
+    let storage = node.storage();
+    search::search(&storage, &params.q)
+        .map(web::Json)
+        .map_err(|e| match e {
+            SearchError::Storage(e) => ApiError::internal_error(format!("Search failed: {}", e)),
+            other => ApiError::bad_request(other.to_string()),
+        })
+}
+
+/// Query parameters for chart data
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+pub struct ChartParams {
+    /// First height of the range (default 0)
+    pub from: Option<u64>,
+    /// Last height of the range (default and maximum: current tip)
+    pub to: Option<u64>,
+    /// Number of points to return (default 200, max 2000)
+    pub points: Option<usize>,
+}
+
+/// Get historical chart data
+///
+/// Returns a per-block metric over a height range, downsampled into buckets
+/// with min/max/avg each. Metrics: difficulty, median-fee-rate,
+/// block-interval, block-weight, tx-count. Heights without stored stats are
+/// returned as gaps.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/charts/{metric}",
+    params(
+        ("metric" = String, Path, description = "Metric name, e.g. median-fee-rate"),
+        ChartParams
+    ),
+    responses(
+        (status = 200, description = "Chart data retrieved successfully", body = ChartResponse),
+        (status = 400, description = "Unknown metric, invalid range or too many points", body = ApiError),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_chart(
+    path: web::Path<String>,
+    params: web::Query<ChartParams>,
+    node: NodeData,
+) -> ApiResult<web::Json<ChartResponse>> {
+    let to_api_error = |e: ChartError| match e {
+        ChartError::Storage(e) => ApiError::internal_error(format!("Failed to load chart data: {}", e)),
+        other => ApiError::bad_request(other.to_string()),
+    };
+    let metric: ChartMetric = path.into_inner().parse().map_err(to_api_error)?;
+    let storage = node.storage();
+    charts::chart(
+        &storage,
+        metric,
+        params.from.unwrap_or(0),
+        params.to,
+        params.points.unwrap_or(DEFAULT_CHART_POINTS),
+    )
+    .map(web::Json)
+    .map_err(to_api_error)
+}
+
+/// Audit the coin supply
+///
+/// Compares the UTXO set total, from the running UTXO statistics, with the
+/// supply the subsidy schedule allows at the current height. `discrepancy` is
+/// set when the UTXO set holds more; run a `utxo-audit` job to cross-check
+/// the statistics against a full scan.
+#[utoipa::path(
+    get,
+    path = "/api/v1/blockchain/supply",
+    responses(
+        (status = 200, description = "Supply audit retrieved successfully", body = SupplyAudit),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_supply(node: NodeData) -> ApiResult<web::Json<SupplyAudit>> {
+    let audit = node
+        .storage()
+        .supply_audit()
+        .map_err(|e| ApiError::internal_error(format!("Failed to audit supply: {}", e)))?;
+    audit.publish();
+    Ok(web::Json(audit))
+}
@@ -4,6 +4,7 @@ use crate::node::Node;
 use actix_web::{web, HttpResponse};
 use serde::Deserialize;
 use std::sync::Arc;
+use supernova_core::lightning::{ManagerError, MissionControlExport};
 use utoipa::IntoParams;
 
 /// Configure lightning API routes
@@ -19,7 +20,10 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .route("/invoice", web::post().to(create_invoice))
         .route("/nodes", web::get().to(get_network_nodes))
         .route("/node/{node_id}", web::get().to(get_node_info))
-        .route("/routes", web::get().to(find_route));
+        .route("/routes", web::get().to(find_route))
+        .route("/graph", web::get().to(export_graph))
+        .route("/mission-control", web::get().to(export_mission_control))
+        .route("/mission-control", web::post().to(import_mission_control));
 }
 
 /// Placeholder handler for Lightning Network endpoints
@@ -549,3 +553,147 @@ pub async fn find_route(
 
     Ok(HttpResponse::Ok().json(route))
 }
+
+/// Export the known network graph
+///
+/// Returns every known node (with alias, features and environmental data)
+/// and announced channel (with capacity and forwarding policy) for external
+/// pathfinding and analysis tools. The body is streamed.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportGraphParams {
+    /// Output format: `json` (default) or `dot` for Graphviz
+    #[param(default = "json")]
+    format: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/lightning/graph",
+    params(
+        ExportGraphParams
+    ),
+    responses(
+        (status = 200, description = "Network graph as JSON (schema version 1) or Graphviz DOT", body = String),
+        (status = 400, description = "Unknown format", body = ApiError),
+        (status = 503, description = "Lightning Network is not enabled", body = ApiError)
+    )
+)]
+pub async fn export_graph(
+    params: web::Query<ExportGraphParams>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    let dot = match params.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "dot" => true,
+        other => {
+            return Err(ApiError::bad_request(format!(
+                "Unknown graph format: {}",
+                other
+            )))
+        }
+    };
+
+    // Check if Lightning Network is enabled
+    let lightning_manager = node
+        .lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+
+    // Snapshot the graph, then release the lock before streaming it out
+    let export = lightning_manager
+        .read()
+        .map_err(|e| ApiError::internal_error(format!("Lightning manager lock poisoned: {}", e)))?
+        .export_graph()
+        .map_err(|e| ApiError::internal_error(format!("Failed to export graph: {}", e)))?;
+
+    let (content_type, chunks): (_, Box<dyn Iterator<Item = String>>) = if dot {
+        ("text/vnd.graphviz", Box::new(export.into_dot_chunks()))
+    } else {
+        ("application/json", Box::new(export.into_json_chunks()))
+    };
+    let body = futures::stream::iter(
+        chunks.map(|chunk| Ok::<_, actix_web::Error>(web::Bytes::from(chunk))),
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .streaming(body))
+}
+
+/// Export mission control
+///
+/// Returns our local payment success/failure history per channel. With
+/// `anonymize`, node IDs are replaced by hashes salted per export.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportMissionControlParams {
+    /// Hash node IDs (default: false)
+    #[param(default = "false")]
+    anonymize: Option<bool>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/lightning/mission-control",
+    params(
+        ExportMissionControlParams
+    ),
+    responses(
+        (status = 200, description = "Mission control exported successfully", body = Object),
+        (status = 503, description = "Lightning Network is not enabled", body = ApiError)
+    )
+)]
+pub async fn export_mission_control(
+    params: web::Query<ExportMissionControlParams>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node
+        .lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+
+    let manager = lightning_manager
+        .read()
+        .map_err(|e| ApiError::internal_error(format!("Lightning manager lock poisoned: {}", e)))?;
+    let export = manager
+        .export_mission_control(params.anonymize.unwrap_or(false))
+        .map_err(|e| {
+            ApiError::internal_error(format!("Failed to export mission control: {}", e))
+        })?;
+
+    Ok(HttpResponse::Ok().json(export))
+}
+
+/// Import mission control
+///
+/// Seeds payment history from a previous (non-anonymized) export, e.g. after
+/// a reinstall. Channels with local history keep it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/lightning/mission-control",
+    request_body = Object,
+    responses(
+        (status = 200, description = "Mission control imported successfully"),
+        (status = 400, description = "Anonymized, malformed or unsupported export", body = ApiError),
+        (status = 503, description = "Lightning Network is not enabled", body = ApiError)
+    )
+)]
+pub async fn import_mission_control(
+    request: web::Json<MissionControlExport>,
+    node: web::Data<Arc<Node>>,
+) -> ApiResult<HttpResponse> {
+    // Check if Lightning Network is enabled
+    let lightning_manager = node
+        .lightning()
+        .ok_or_else(|| ApiError::service_unavailable("Lightning Network is not enabled"))?;
+
+    let manager = lightning_manager
+        .read()
+        .map_err(|e| ApiError::internal_error(format!("Lightning manager lock poisoned: {}", e)))?;
+    let imported = manager
+        .import_mission_control(&request)
+        .map_err(|e| match e {
+            ManagerError::RouterError(reason) => ApiError::bad_request(reason),
+            e => ApiError::internal_error(format!("Failed to import mission control: {}", e)),
+        })?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "imported": imported })))
+}
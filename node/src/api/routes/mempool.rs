@@ -1,3 +1,4 @@
+use super::NodeData;
 use crate::api::error::ApiError;
 use crate::api::types::MempoolTransactionSubmissionResponse;
 use crate::node::Node;
@@ -17,7 +18,8 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         )
         .route("/submit", web::post().to(submit_transaction))
         .route("/validate", web::post().to(validate_transaction))
-        .route("/fees", web::get().to(get_fee_estimates));
+        .route("/fees", web::get().to(get_fee_estimates))
+        .route("/rejections", web::get().to(get_mempool_rejections));
 }
 
 /// Request for submitting a transaction
@@ -297,3 +299,34 @@ pub async fn get_fee_estimates(
         ),
     }
 }
+
+/// Query parameters for rejection statistics
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RejectionParams {
+    /// Maximum number of recent rejections to return (default: 50)
+    #[param(default = "50")]
+    pub limit: Option<usize>,
+}
+
+/// Get mempool rejection statistics
+///
+/// Returns rejection counts by reason code and by peer, plus the most recent
+/// rejected transactions. Reason codes are stable; see `RejectionRecord`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/mempool/rejections",
+    params(
+        RejectionParams
+    ),
+    responses(
+        (status = 200, description = "Mempool rejection statistics retrieved successfully", body = RejectionStats),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_mempool_rejections(
+    params: web::Query<RejectionParams>,
+    node: NodeData,
+) -> Result<HttpResponse, actix_web::Error> {
+    let limit = params.limit.unwrap_or(50);
+    Ok(HttpResponse::Ok().json(node.mempool().rejections().snapshot(limit)))
+}
@@ -1,12 +1,19 @@
+use super::NodeData;
 use crate::api::error::{ApiError, ApiResult};
 use crate::api::types::{
     MiningConfiguration, MiningInfo, MiningStats, MiningStatus, MiningTemplate, SubmitBlockRequest,
     SubmitBlockResponse,
 };
 use actix_web::{web, HttpResponse};
+use crate::mining::template_audit::TemplateAuditSummary;
+use crate::mining::template_manager::stale_reason;
+#[cfg(feature = "stratum")]
+use crate::mining::stratum::StratumStats;
 use supernova_core::mining::manager::MiningManager;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
+use supernova_core::types::block::Block;
 use utoipa::{IntoParams, ToSchema};
 
 /// Configure mining API routes
@@ -19,7 +26,10 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .route("/start", web::post().to(start_mining))
         .route("/stop", web::post().to(stop_mining))
         .route("/config", web::get().to(get_mining_config))
-        .route("/config", web::put().to(update_mining_config));
+        .route("/config", web::put().to(update_mining_config))
+        .route("/template-audit", web::get().to(get_template_audit));
+    #[cfg(feature = "stratum")]
+    cfg.route("/stats/stratum", web::get().to(get_stratum_stats));
 }
 
 /// Get mining information
@@ -122,6 +132,10 @@ pub struct GetMiningTemplateParams {
 
     /// Maximum number of transactions to include (default: all available)
     max_transactions: Option<u32>,
+
+    /// `longpollid` of the caller's current template; the request is held
+    /// until that template is out of date
+    longpollid: Option<String>,
 }
 
 #[utoipa::path(
@@ -132,14 +146,33 @@ pub struct GetMiningTemplateParams {
     ),
     responses(
         (status = 200, description = "Mining template retrieved successfully", body = MiningTemplate),
-        (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 400, description = "Invalid request parameters or longpollid", body = ApiError),
+        (status = 503, description = "Local clock is too far off to build a template", body = ApiError),
         (status = 500, description = "Internal server error", body = ApiError)
     )
 )]
 pub async fn get_mining_template(
     params: web::Query<GetMiningTemplateParams>,
     mining: web::Data<Arc<MiningManager>>,
+    node: NodeData,
 ) -> ApiResult<MiningTemplate> {
+    let templates = node.template_manager();
+    if let Some(longpollid) = &params.longpollid {
+        let (fee_delta, timeout_secs) = node
+            .config()
+            .read()
+            .map(|c| (c.mining.longpoll_fee_delta, c.mining.longpoll_timeout_secs))
+            .map_err(|_| ApiError::internal_error("Config lock poisoned"))?;
+        templates
+            .wait(longpollid, fee_delta, Duration::from_secs(timeout_secs))
+            .await
+            .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    }
+    // Taken before building, so a tip change mid-build ends the next poll
+    let longpollid = templates.longpoll_id();
+
+    node.ensure_clock_sane()
+        .map_err(|e| ApiError::service_unavailable(e.to_string()))?;
     let capabilities = params.capabilities.as_deref().unwrap_or("standard");
     let max_transactions = params.max_transactions;
 
@@ -176,6 +209,7 @@ pub async fn get_mining_template(
                         green_mining_bonus: ed.green_mining_bonus,
                     }
                 }),
+                longpollid,
             };
             Ok(api_template)
         }
@@ -202,11 +236,28 @@ pub async fn get_mining_template(
 pub async fn submit_block(
     request: web::Json<SubmitBlockRequest>,
     mining: web::Data<Arc<MiningManager>>,
+    node: NodeData,
 ) -> ApiResult<SubmitBlockResponse> {
     // Convert hex string to bytes
     let block_bytes = hex::decode(&request.block_data)
         .map_err(|_| ApiError::bad_request("Invalid block data format"))?;
 
+    // Work on a replaced tip is reported distinctly so miners switch jobs;
+    // undecodable blocks are left for the mining manager to reject
+    if let Ok(block) = bincode::deserialize::<Block>(&block_bytes) {
+        let chain_state = node.chain_state();
+        let chain = chain_state
+            .read()
+            .map_err(|_| ApiError::internal_error("Chain state lock poisoned"))?;
+        if let Some(reason) = stale_reason(&chain, &block) {
+            return Ok(SubmitBlockResponse {
+                accepted: false,
+                block_hash: hex::encode(block.hash()),
+                reject_reason: Some(reason.to_string()),
+            });
+        }
+    }
+
     match mining.submit_block(&block_bytes) {
         Ok(btclib_response) => {
             // Convert btclib response to API response
@@ -321,13 +372,17 @@ pub struct StartMiningRequest {
     responses(
         (status = 200, description = "Mining operation started successfully"),
         (status = 400, description = "Invalid request parameters", body = ApiError),
+        (status = 503, description = "Local clock is too far off to mine", body = ApiError),
         (status = 500, description = "Internal server error", body = ApiError)
     )
 )]
 pub async fn start_mining(
     request: web::Json<StartMiningRequest>,
     mining: web::Data<Arc<MiningManager>>,
+    node: NodeData,
 ) -> ApiResult<HttpResponse> {
+    node.ensure_clock_sane()
+        .map_err(|e| ApiError::service_unavailable(e.to_string()))?;
     let threads = request.threads;
 
     match mining.start_mining(threads) {
@@ -439,3 +494,54 @@ pub async fn update_mining_config(
         ))),
     }
 }
+
+/// Query parameters for the template audit
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TemplateAuditParams {
+    /// Number of most recent audited blocks to summarize (default: 10)
+    #[param(default = "10")]
+    pub last: Option<usize>,
+}
+
+/// Get the template audit
+///
+/// Compares our `getblocktemplate` templates with the blocks other miners
+/// actually found at the same height: the average fee delta, transaction
+/// overlap, and the highest-fee transactions our templates missed, with why.
+#[utoipa::path(
+    get,
+    path = "/api/v1/mining/template-audit",
+    params(
+        TemplateAuditParams
+    ),
+    responses(
+        (status = 200, description = "Template audit retrieved successfully", body = TemplateAuditSummary),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_template_audit(
+    params: web::Query<TemplateAuditParams>,
+    node: NodeData,
+) -> ApiResult<web::Json<TemplateAuditSummary>> {
+    let last = params.last.unwrap_or(10);
+    Ok(web::Json(node.template_audit().summary(last)))
+}
+
+/// Get stratum share statistics
+///
+/// Connected stratum miners, and per worker the estimated hashrate and the
+/// shares accepted and rejected since startup.
+#[cfg(feature = "stratum")]
+#[utoipa::path(
+    get,
+    path = "/api/v1/mining/stats/stratum",
+    responses(
+        (status = 200, description = "Stratum statistics retrieved successfully", body = StratumStats),
+        (status = 500, description = "Internal server error", body = ApiError)
+    )
+)]
+pub async fn get_stratum_stats(node: NodeData) -> ApiResult<web::Json<StratumStats>> {
+    Ok(web::Json(
+        node.stratum_metrics().snapshot(std::time::Instant::now()),
+    ))
+}
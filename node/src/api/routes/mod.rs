@@ -5,6 +5,7 @@
 use actix_web::web;
 use std::sync::Arc;
 
+pub mod address;
 pub mod blockchain;
 pub mod environmental;
 pub mod faucet;
@@ -14,6 +15,7 @@ pub mod mempool;
 pub mod mining;
 pub mod network;
 pub mod node;
+pub mod subscriptions;
 pub mod wallet;
 
 // Type alias for the node data passed to route handlers
@@ -22,12 +24,14 @@ pub type NodeData = web::Data<Arc<crate::api_facade::ApiFacade>>;
 /// Configure all API routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg
-        // JSON-RPC 2.0 API at root (main endpoint)
+        // JSON-RPC 2.0 API at /rpc and root (main endpoint)
         .configure(crate::api::jsonrpc::configure)
         // Health check routes (Kubernetes probes)
         .configure(health::configure)
         // Blockchain routes
         .service(web::scope("/api/v1/blockchain").configure(blockchain::configure))
+        // Address index routes
+        .service(web::scope("/api/v1/address").configure(address::configure))
         // Node routes
         .service(web::scope("/api/v1/node").configure(node::configure))
         // Network routes
@@ -43,7 +47,10 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         // Mining routes
         .service(web::scope("/api/v1/mining").configure(mining::configure))
         // Environmental routes
-        .service(web::scope("/api/v1/environmental").configure(environmental::configure));
+        .service(web::scope("/api/v1/environmental").configure(environmental::configure))
+        // WebSocket subscriptions
+        .service(web::scope("/api/v1/ws").configure(subscriptions::configure))
+        .route("/ws", web::get().to(subscriptions::event_subscriptions));
 
     // Legacy health check endpoint (for backwards compatibility)
     cfg.route("/health", web::get().to(health_check_legacy));
@@ -89,6 +96,13 @@ mod tests {
             // Modules that were always correct (regression guard).
             "/api/v1/blockchain/info",
             "/api/v1/node/info",
+            "/api/v1/mempool/rejections",
+            "/api/v1/blockchain/rejections",
+            "/api/v1/blockchain/search?q=1",
+            "/api/v1/blockchain/charts/difficulty?points=10",
+            "/api/v1/node/jobs",
+            "/api/v1/node/storage",
+            "/api/v1/address/00/balance",
         ];
 
         for path in documented_paths {
@@ -3,13 +3,25 @@
 //! This module implements the HTTP routes for node management, monitoring,
 //! and configuration operations.
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, warn};
 use utoipa::{IntoParams, ToSchema};
 
 use super::NodeData;
+use crate::api::jobs::{JobError, JobInfo, JobKind};
+use crate::api::usage::{ApiKeyId, KeyUsage, UsageMeter, UsagePeriod};
+use crate::api::webhooks::{
+    DeliveryRecord, DeliveryStatus, WebhookCreated, WebhookError, WebhookHub, WebhookInfo,
+    WebhookSpec,
+};
 use crate::api::types::*;
+use crate::api_facade::ChainAdminOp;
+use crate::network::operator_messages::{OperatorMessageError, OperatorMessenger};
+use crate::network::OperatorMessage;
+use crate::node::NodeError;
+use crate::storage::utxo_commitment::DEFAULT_SAMPLE_BUCKETS;
+use crate::storage::{StorageStats, UtxoIntegrityReport};
 
 /// Configure node routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -25,7 +37,29 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         .route("/version", web::get().to(get_version))
         .route("/backup", web::post().to(create_backup))
         .route("/backup", web::get().to(get_backup_info))
-        .route("/debug", web::get().to(get_debug_info));
+        .route("/debug", web::get().to(get_debug_info))
+        .route("/debug/invalid-blocks", web::get().to(get_invalid_blocks))
+        .route("/admin/rollback", web::post().to(admin_rollback))
+        .route("/admin/invalidate-block", web::post().to(admin_invalidate_block))
+        .route("/admin/reconsider-block", web::post().to(admin_reconsider_block))
+        .route("/admin/rotate-identity", web::post().to(admin_rotate_identity))
+        .route("/admin/verify-utxo-integrity", web::post().to(admin_verify_utxo_integrity))
+        .route("/storage", web::get().to(get_storage_stats))
+        .route("/storage/compact", web::post().to(compact_storage))
+        .route("/jobs", web::get().to(list_jobs))
+        .route("/jobs/{kind}", web::post().to(start_job))
+        .route("/jobs/{id}", web::get().to(get_job))
+        .route("/jobs/{id}", web::delete().to(cancel_job))
+        .route("/webhooks", web::post().to(create_webhook))
+        .route("/webhooks", web::get().to(list_webhooks))
+        .route("/webhooks/{id}", web::delete().to(delete_webhook))
+        .route("/webhooks/{id}/enable", web::post().to(enable_webhook))
+        .route("/webhooks/{id}/deliveries", web::get().to(get_webhook_deliveries))
+        .route("/messages", web::get().to(list_operator_messages))
+        .route("/messages", web::post().to(send_operator_message))
+        .route("/api-keys", web::get().to(list_api_key_usage))
+        .route("/api-keys/{id}/usage", web::get().to(get_api_key_usage))
+        .route("/my-usage", web::get().to(get_my_usage));
 }
 
 /// Get node information
@@ -374,3 +408,895 @@ pub async fn get_debug_info(node: NodeData) -> impl Responder {
         }
     }
 }
+
+/// A block the node rejects without running validation again
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InvalidBlockEntry {
+    /// Block hash (hex)
+    pub hash: String,
+    /// Parent hash (hex), if known
+    pub parent_hash: Option<String>,
+    pub height: Option<u64>,
+    /// Why the block failed; `ParentInvalid` for descendants of a failed block
+    pub reason: String,
+    /// RFC 3339 timestamp of the first failure
+    pub invalidated_at: String,
+}
+
+/// List invalid blocks
+///
+/// Blocks that failed validation (or descend from one) and are refused on
+/// sight. Entries survive restarts; clear one with `admin/reconsider-block`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/debug/invalid-blocks",
+    responses(
+        (status = 200, description = "Cached invalid blocks, most recent first", body = [InvalidBlockEntry]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "node"
+)]
+pub async fn get_invalid_blocks(node: NodeData) -> impl Responder {
+    match node.get_invalid_blocks() {
+        Ok(blocks) => {
+            let entries: Vec<InvalidBlockEntry> = blocks
+                .into_iter()
+                .map(|b| InvalidBlockEntry {
+                    hash: hex::encode(b.block_hash),
+                    parent_hash: b.parent_hash.map(hex::encode),
+                    height: b.height,
+                    reason: format!("{:?}", b.reason),
+                    invalidated_at: b.invalidated_at.to_rfc3339(),
+                })
+                .collect();
+            HttpResponse::Ok().json(entries)
+        }
+        Err(e) => {
+            error!("Failed to list invalid blocks: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to list invalid blocks: {}", e),
+            })
+        }
+    }
+}
+
+/// Rollback request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RollbackRequest {
+    /// Height to roll back to; blocks above it are disconnected and invalidated
+    pub height: u64,
+    /// Whether to clear the mempool after the rollback
+    #[serde(default)]
+    pub wipe_mempool: bool,
+}
+
+/// Block hash request for invalidate/reconsider
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BlockHashRequest {
+    /// Block hash (hex)
+    pub hash: String,
+}
+
+/// Result of a chain admin operation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChainAdminResponse {
+    pub old_height: u64,
+    pub old_tip: String,
+    pub new_height: u64,
+    pub new_tip: String,
+    /// Blocks invalidated, or cleared for reconsider
+    pub affected_blocks: Vec<String>,
+    /// Whether the mempool was cleared
+    pub mempool_cleared: bool,
+}
+
+async fn run_chain_admin(node: NodeData, op: ChainAdminOp) -> HttpResponse {
+    match node.chain_admin(op).await {
+        Ok(change) => HttpResponse::Ok().json(ChainAdminResponse {
+            old_height: change.old_height,
+            old_tip: hex::encode(change.old_tip),
+            new_height: change.new_height,
+            new_tip: hex::encode(change.new_tip),
+            affected_blocks: change.affected_blocks.iter().map(hex::encode).collect(),
+            mempool_cleared: matches!(op, ChainAdminOp::Rollback { wipe_mempool: true, .. }),
+        }),
+        Err(NodeError::ConfigError(e)) => HttpResponse::Forbidden().json(ErrorResponse { error: e }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Admin {} failed: {}", op, e),
+        }),
+    }
+}
+
+fn parse_block_hash(hash: &str) -> Result<[u8; 32], HttpResponse> {
+    let mut out = [0u8; 32];
+    hex::decode_to_slice(hash.trim(), &mut out).map_err(|_| {
+        HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Invalid block hash: {}", hash),
+        })
+    })?;
+    Ok(out)
+}
+
+/// Roll the chain back to a height
+///
+/// Disconnects blocks above the target through the reorg path and
+/// invalidates them so they are not re-accepted from peers. Refuses targets
+/// below the last embedded checkpoint. Intended for coordinated testnet resets.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/admin/rollback",
+    request_body = RollbackRequest,
+    responses(
+        (status = 200, description = "Chain rolled back", body = ChainAdminResponse),
+        (status = 400, description = "Rollback refused"),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn admin_rollback(node: NodeData, request: web::Json<RollbackRequest>) -> impl Responder {
+    let op = ChainAdminOp::Rollback {
+        height: request.height,
+        wipe_mempool: request.wipe_mempool,
+    };
+    run_chain_admin(node, op).await
+}
+
+/// Invalidate a block
+///
+/// Marks the block and its descendants invalid. If it is on the active
+/// chain, the chain is rolled back to its parent.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/admin/invalidate-block",
+    request_body = BlockHashRequest,
+    responses(
+        (status = 200, description = "Block invalidated", body = ChainAdminResponse),
+        (status = 400, description = "Unknown block or invalidation refused"),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn admin_invalidate_block(
+    node: NodeData,
+    request: web::Json<BlockHashRequest>,
+) -> impl Responder {
+    match parse_block_hash(&request.hash) {
+        Ok(hash) => run_chain_admin(node, ChainAdminOp::InvalidateBlock(hash)).await,
+        Err(response) => response,
+    }
+}
+
+/// Reconsider a block
+///
+/// Clears an earlier invalidation of the block and its descendants, whether
+/// manual or a cached validation failure, and switches to that chain if it
+/// has more work than the current tip.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/admin/reconsider-block",
+    request_body = BlockHashRequest,
+    responses(
+        (status = 200, description = "Block reconsidered", body = ChainAdminResponse),
+        (status = 400, description = "Block is not invalidated"),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn admin_reconsider_block(
+    node: NodeData,
+    request: web::Json<BlockHashRequest>,
+) -> impl Responder {
+    match parse_block_hash(&request.hash) {
+        Ok(hash) => run_chain_admin(node, ChainAdminOp::ReconsiderBlock(hash)).await,
+        Err(response) => response,
+    }
+}
+
+/// Result of starting an identity rotation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IdentityRotationResponse {
+    /// Identity the node keeps using during the grace period
+    pub old_peer_id: String,
+    /// Identity the node switches to afterwards
+    pub new_peer_id: String,
+    /// Unix seconds
+    pub issued_at: u64,
+    /// Unix seconds; the first restart after this uses the new identity
+    pub grace_until: u64,
+    /// Signature by the old key over the link (hex)
+    pub old_signature: String,
+    /// Signature by the new key over the link (hex)
+    pub new_signature: String,
+}
+
+/// Rotate the node's P2P identity
+///
+/// Generates a new identity keypair and a cross-signed attestation linking
+/// the current peer id to it, and gossips the attestation so peers carry
+/// our addresses and reputation over. The node keeps its current identity
+/// for `network.identity_rotation_grace` and switches on the next restart
+/// after that.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/admin/rotate-identity",
+    responses(
+        (status = 200, description = "Rotation started", body = IdentityRotationResponse),
+        (status = 400, description = "A rotation is already pending or the key could not be read"),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn admin_rotate_identity(node: NodeData) -> impl Responder {
+    match node.rotate_identity().await {
+        Ok(attestation) => HttpResponse::Ok().json(IdentityRotationResponse {
+            old_peer_id: attestation.link.old_peer_id,
+            new_peer_id: attestation.link.new_peer_id,
+            issued_at: attestation.link.issued_at,
+            grace_until: attestation.link.grace_until,
+            old_signature: attestation.old_signature,
+            new_signature: attestation.new_signature,
+        }),
+        Err(NodeError::ConfigError(e)) => HttpResponse::Forbidden().json(ErrorResponse { error: e }),
+        Err(e) => HttpResponse::BadRequest().json(ErrorResponse {
+            error: format!("Admin rotate-identity failed: {}", e),
+        }),
+    }
+}
+
+/// UTXO integrity check request
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UtxoIntegrityRequest {
+    /// Recompute the whole set in a `utxo-integrity` job instead of a sample
+    #[serde(default)]
+    pub full: bool,
+    /// Buckets, out of 256, a fast check recomputes (default 8)
+    pub sample_buckets: Option<usize>,
+}
+
+/// Verify the UTXO set against its commitment
+///
+/// The fast check recomputes a random sample of the 256 commitment buckets
+/// and answers directly. `full` recomputes every bucket in a background
+/// `utxo-integrity` job; poll `/api/v1/node/jobs/{id}` for progress and the
+/// report.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/admin/verify-utxo-integrity",
+    request_body = UtxoIntegrityRequest,
+    responses(
+        (status = 200, description = "Sample checked", body = UtxoIntegrityReport),
+        (status = 202, description = "Full check started", body = JobInfo),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 409, description = "Another storage-heavy job is running")
+    ),
+    tag = "node"
+)]
+pub async fn admin_verify_utxo_integrity(
+    node: NodeData,
+    request: Option<web::Json<UtxoIntegrityRequest>>,
+) -> impl Responder {
+    let request = request.map(web::Json::into_inner).unwrap_or_default();
+    let mode = if request.full { "full" } else { "sample" };
+    let db = match node.utxo_integrity(mode) {
+        Ok(db) => db,
+        Err(NodeError::ConfigError(e)) => {
+            return HttpResponse::Forbidden().json(ErrorResponse { error: e })
+        }
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: e.to_string(),
+            })
+        }
+    };
+
+    if request.full {
+        let started = node
+            .jobs()
+            .start(JobKind::UtxoIntegrity, serde_json::Value::Null);
+        return match started {
+            Ok(job) => {
+                info!("Started job {} ({})", job.id, JobKind::UtxoIntegrity);
+                HttpResponse::Accepted().json(job)
+            }
+            Err(e) => job_error_response(e),
+        };
+    }
+
+    let buckets = request.sample_buckets.unwrap_or(DEFAULT_SAMPLE_BUCKETS);
+    match db.verify_utxo_sample(buckets) {
+        Ok(report) => {
+            tracing::warn!(
+                target: "audit",
+                "Admin sample UTXO integrity check: {} buckets, consistent: {}",
+                report.buckets_checked,
+                report.consistent
+            );
+            HttpResponse::Ok().json(report)
+        }
+        Err(e) => {
+            error!("UTXO integrity check failed: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("UTXO integrity check failed: {}", e),
+            })
+        }
+    }
+}
+
+/// Get storage statistics
+///
+/// Per-tree entry counts and sizes, the size of the database files, the
+/// space a compaction is estimated to release and the last compaction.
+/// Measurements are reused for up to a minute.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/storage",
+    responses(
+        (status = 200, description = "Storage statistics retrieved successfully", body = StorageStats),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "node"
+)]
+pub async fn get_storage_stats(node: NodeData) -> impl Responder {
+    let storage = node.storage();
+    match web::block(move || storage.storage_stats()).await {
+        Ok(Ok(stats)) => HttpResponse::Ok().json(stats),
+        Ok(Err(e)) => {
+            error!("Failed to measure storage: {}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to measure storage: {}", e),
+            })
+        }
+        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: format!("Failed to measure storage: {}", e),
+        }),
+    }
+}
+
+/// Compact the database
+///
+/// Starts a `compaction` job rewriting every tree so the space of deleted
+/// and overwritten entries can be released; poll `/api/v1/node/jobs/{id}`
+/// for progress and the resulting sizes.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/storage/compact",
+    responses(
+        (status = 202, description = "Compaction started", body = JobInfo),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 409, description = "Another storage-heavy job is running")
+    ),
+    tag = "node"
+)]
+pub async fn compact_storage(node: NodeData) -> impl Responder {
+    let jobs = match node.storage_compaction() {
+        Ok(jobs) => jobs,
+        Err(NodeError::ConfigError(e)) => {
+            return HttpResponse::Forbidden().json(ErrorResponse { error: e })
+        }
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: e.to_string(),
+            })
+        }
+    };
+    match jobs.start(JobKind::Compaction, serde_json::Value::Null) {
+        Ok(job) => {
+            info!("Started job {} ({})", job.id, JobKind::Compaction);
+            HttpResponse::Accepted().json(job)
+        }
+        Err(e) => job_error_response(e),
+    }
+}
+
+fn job_error_response(e: JobError) -> HttpResponse {
+    let body = ErrorResponse {
+        error: e.to_string(),
+    };
+    match e {
+        JobError::UnknownKind(_) | JobError::NoHandler(_) | JobError::InvalidParams(_) => {
+            HttpResponse::BadRequest().json(body)
+        }
+        JobError::NotFound(_) => HttpResponse::NotFound().json(body),
+        JobError::Busy { .. } | JobError::NotRunning(_) => HttpResponse::Conflict().json(body),
+        JobError::Cancelled | JobError::Failed(_) | JobError::Spawn(_) => {
+            HttpResponse::InternalServerError().json(body)
+        }
+    }
+}
+
+/// List jobs
+///
+/// Returns running and recently finished jobs, newest first. Jobs that were
+/// running when the node last stopped are reported as `interrupted`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/jobs",
+    responses(
+        (status = 200, description = "Jobs listed", body = Vec<JobInfo>)
+    ),
+    tag = "node"
+)]
+pub async fn list_jobs(node: NodeData) -> impl Responder {
+    HttpResponse::Ok().json(node.jobs().list())
+}
+
+/// Start a job
+///
+/// Starts a long-running job (`reindex`, `compaction`, `snapshot-export`,
+/// `debug-check`, `wallet-rescan`, `utxo-audit` or `utxo-integrity`) with
+/// optional JSON parameters and returns immediately. Storage-heavy jobs run
+/// one at a time.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/jobs/{kind}",
+    params(
+        ("kind" = String, Path, description = "Job type")
+    ),
+    request_body(content = serde_json::Value, description = "Job parameters", content_type = "application/json"),
+    responses(
+        (status = 202, description = "Job started", body = JobInfo),
+        (status = 400, description = "Unknown job type or invalid parameters"),
+        (status = 409, description = "Another storage-heavy job is running")
+    ),
+    tag = "node"
+)]
+pub async fn start_job(
+    node: NodeData,
+    path: web::Path<String>,
+    params: Option<web::Json<serde_json::Value>>,
+) -> impl Responder {
+    let kind = match path.parse::<JobKind>() {
+        Ok(kind) => kind,
+        Err(e) => return job_error_response(e),
+    };
+    let params = params.map(web::Json::into_inner).unwrap_or(serde_json::Value::Null);
+
+    match node.jobs().start(kind, params) {
+        Ok(job) => {
+            info!("Started job {} ({})", job.id, kind);
+            HttpResponse::Accepted().json(job)
+        }
+        Err(e) => {
+            warn!("Refused to start {} job: {}", kind, e);
+            job_error_response(e)
+        }
+    }
+}
+
+/// Get a job
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/jobs/{id}",
+    params(
+        ("id" = u64, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Job state and progress", body = JobInfo),
+        (status = 404, description = "Job not found")
+    ),
+    tag = "node"
+)]
+pub async fn get_job(node: NodeData, path: web::Path<u64>) -> impl Responder {
+    let id = path.into_inner();
+    match node.jobs().get(id) {
+        Some(job) => HttpResponse::Ok().json(job),
+        None => job_error_response(JobError::NotFound(id)),
+    }
+}
+
+/// Cancel a job
+///
+/// Requests cancellation; the job stops at its next checkpoint and is then
+/// reported as `cancelled`.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/node/jobs/{id}",
+    params(
+        ("id" = u64, Path, description = "Job id")
+    ),
+    responses(
+        (status = 202, description = "Cancellation requested", body = JobInfo),
+        (status = 404, description = "Job not found"),
+        (status = 409, description = "Job is not running")
+    ),
+    tag = "node"
+)]
+pub async fn cancel_job(node: NodeData, path: web::Path<u64>) -> impl Responder {
+    match node.jobs().cancel(path.into_inner()) {
+        Ok(job) => HttpResponse::Accepted().json(job),
+        Err(e) => job_error_response(e),
+    }
+}
+
+fn webhook_error_response(e: WebhookError) -> HttpResponse {
+    let body = ErrorResponse {
+        error: e.to_string(),
+    };
+    match e {
+        WebhookError::NotFound(_) => HttpResponse::NotFound().json(body),
+        WebhookError::TooMany(_) => HttpResponse::Conflict().json(body),
+        WebhookError::InvalidUrl(_) | WebhookError::NoFilters | WebhookError::InvalidLimit(_) => {
+            HttpResponse::BadRequest().json(body)
+        }
+    }
+}
+
+fn webhook_hub(node: &NodeData, action: &str) -> Result<std::sync::Arc<WebhookHub>, HttpResponse> {
+    node.webhooks(action).map_err(|e| match e {
+        NodeError::ConfigError(e) => HttpResponse::Forbidden().json(ErrorResponse { error: e }),
+        e => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: e.to_string(),
+        }),
+    })
+}
+
+/// Register a webhook
+///
+/// Subscribes a URL to new blocks, large transactions and reorgs. The
+/// response carries the HMAC secret used to sign deliveries; it is not
+/// shown again.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/webhooks",
+    request_body = WebhookSpec,
+    responses(
+        (status = 201, description = "Webhook registered", body = WebhookCreated),
+        (status = 400, description = "Invalid URL, filters or limits"),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 409, description = "Too many webhooks registered")
+    ),
+    tag = "node"
+)]
+pub async fn create_webhook(node: NodeData, request: web::Json<WebhookSpec>) -> impl Responder {
+    let hub = match webhook_hub(&node, "create") {
+        Ok(hub) => hub,
+        Err(response) => return response,
+    };
+    match hub.create(request.into_inner()) {
+        Ok(created) => {
+            info!("Registered webhook {} -> {}", created.webhook.id, created.webhook.url);
+            HttpResponse::Created().json(created)
+        }
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+/// List webhooks
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/webhooks",
+    responses(
+        (status = 200, description = "Registered webhooks", body = Vec<WebhookInfo>),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn list_webhooks(node: NodeData) -> impl Responder {
+    match webhook_hub(&node, "list") {
+        Ok(hub) => HttpResponse::Ok().json(hub.list()),
+        Err(response) => response,
+    }
+}
+
+/// Delete a webhook
+#[utoipa::path(
+    delete,
+    path = "/api/v1/node/webhooks/{id}",
+    params(
+        ("id" = String, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = "node"
+)]
+pub async fn delete_webhook(node: NodeData, path: web::Path<String>) -> impl Responder {
+    let hub = match webhook_hub(&node, "delete") {
+        Ok(hub) => hub,
+        Err(response) => return response,
+    };
+    match hub.delete(&path) {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+/// Re-enable a webhook
+///
+/// Clears an automatic disable after sustained delivery failures. Events
+/// that occurred while disabled are not replayed.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/webhooks/{id}/enable",
+    params(
+        ("id" = String, Path, description = "Webhook id")
+    ),
+    responses(
+        (status = 200, description = "Webhook enabled", body = WebhookInfo),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = "node"
+)]
+pub async fn enable_webhook(node: NodeData, path: web::Path<String>) -> impl Responder {
+    let hub = match webhook_hub(&node, "enable") {
+        Ok(hub) => hub,
+        Err(response) => return response,
+    };
+    match hub.enable(&path) {
+        Ok(webhook) => HttpResponse::Ok().json(webhook),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+/// Query parameters for webhook deliveries
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct DeliveriesQuery {
+    /// Only deliveries in this state (`pending`, `delivered`, `failed`, `dropped`)
+    pub status: Option<DeliveryStatus>,
+}
+
+/// Get webhook deliveries
+///
+/// Returns the most recent deliveries of a webhook, newest first, with
+/// attempt counts and the last error of each.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/webhooks/{id}/deliveries",
+    params(
+        ("id" = String, Path, description = "Webhook id"),
+        DeliveriesQuery
+    ),
+    responses(
+        (status = 200, description = "Recent deliveries", body = Vec<DeliveryRecord>),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 404, description = "Webhook not found")
+    ),
+    tag = "node"
+)]
+pub async fn get_webhook_deliveries(
+    node: NodeData,
+    path: web::Path<String>,
+    query: web::Query<DeliveriesQuery>,
+) -> impl Responder {
+    let hub = match webhook_hub(&node, "deliveries") {
+        Ok(hub) => hub,
+        Err(response) => return response,
+    };
+    match hub.deliveries(&path, query.status) {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(e) => webhook_error_response(e),
+    }
+}
+
+fn operator_messenger(
+    node: &NodeData,
+    action: &str,
+) -> Result<std::sync::Arc<OperatorMessenger>, HttpResponse> {
+    node.operator_messages(action).map_err(|e| match e {
+        NodeError::ConfigError(e) => HttpResponse::Forbidden().json(ErrorResponse { error: e }),
+        e => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: e.to_string(),
+        }),
+    })
+}
+
+/// Query parameters for operator messages
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct OperatorMessagesQuery {
+    /// Most messages returned, newest first (default: 50)
+    pub limit: Option<usize>,
+}
+
+/// List operator messages
+///
+/// Returns the direct messages, broadcasts and network notices this node
+/// accepted, newest first. Live messages are also published on the node
+/// event bus.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/messages",
+    params(OperatorMessagesQuery),
+    responses(
+        (status = 200, description = "Accepted operator messages", body = Vec<OperatorMessage>),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn list_operator_messages(
+    node: NodeData,
+    query: web::Query<OperatorMessagesQuery>,
+) -> impl Responder {
+    match operator_messenger(&node, "list") {
+        Ok(messenger) => HttpResponse::Ok().json(messenger.messages(query.limit.unwrap_or(50))),
+        Err(response) => response,
+    }
+}
+
+/// Operator message to send
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SendOperatorMessageRequest {
+    pub text: String,
+    /// Peer id to send an encrypted direct message to; without one the
+    /// message goes to every directly connected peer
+    pub recipient: Option<String>,
+    /// Issue a network notice signed with this node's authority key
+    #[serde(default)]
+    pub notice: bool,
+}
+
+/// A sent operator message
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SendOperatorMessageResponse {
+    /// Hex message id
+    pub id: String,
+}
+
+/// Send an operator message
+///
+/// Sends a direct message, a broadcast to connected peers, or a network
+/// notice. Receivers drop messages from senders they have not allow-listed.
+#[utoipa::path(
+    post,
+    path = "/api/v1/node/messages",
+    request_body = SendOperatorMessageRequest,
+    responses(
+        (status = 200, description = "Message sent", body = SendOperatorMessageResponse),
+        (status = 400, description = "Invalid text or recipient"),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 409, description = "This node has no authority key")
+    ),
+    tag = "node"
+)]
+pub async fn send_operator_message(
+    node: NodeData,
+    request: web::Json<SendOperatorMessageRequest>,
+) -> impl Responder {
+    let messenger = match operator_messenger(&node, "send") {
+        Ok(messenger) => messenger,
+        Err(response) => return response,
+    };
+    let request = request.into_inner();
+    let result = match (&request.recipient, request.notice) {
+        (Some(_), true) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: "A notice cannot have a recipient".to_string(),
+            })
+        }
+        (Some(recipient), false) => match recipient.parse() {
+            Ok(peer_id) => messenger.send_direct(peer_id, &request.text).await,
+            Err(_) => {
+                return HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!("Invalid peer id: {}", recipient),
+                })
+            }
+        },
+        (None, true) => messenger.issue_notice(&request.text).await,
+        (None, false) => messenger.broadcast(&request.text).await,
+    };
+    match result {
+        Ok(id) => {
+            info!("Sent operator message {}", id);
+            HttpResponse::Ok().json(SendOperatorMessageResponse { id })
+        }
+        Err(e) => {
+            let body = ErrorResponse {
+                error: e.to_string(),
+            };
+            match e {
+                OperatorMessageError::TooLarge(_)
+                | OperatorMessageError::Malformed(_)
+                | OperatorMessageError::InvalidPublicKey(_) => HttpResponse::BadRequest().json(body),
+                OperatorMessageError::NoAuthorityKey => HttpResponse::Conflict().json(body),
+                OperatorMessageError::Network(_) => HttpResponse::ServiceUnavailable().json(body),
+                _ => HttpResponse::InternalServerError().json(body),
+            }
+        }
+    }
+}
+
+/// Query parameters for API key usage
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams, ToSchema)]
+pub struct UsageQuery {
+    /// Reporting window: `day` (default), `week`, `month` or `all`
+    #[serde(default)]
+    pub period: UsagePeriod,
+}
+
+fn usage_meter(node: &NodeData, action: &str) -> Result<std::sync::Arc<UsageMeter>, HttpResponse> {
+    node.api_key_usage(action).map_err(|e| match e {
+        NodeError::ConfigError(e) => HttpResponse::Forbidden().json(ErrorResponse { error: e }),
+        e => HttpResponse::InternalServerError().json(ErrorResponse {
+            error: e.to_string(),
+        }),
+    })
+}
+
+/// List API key usage
+///
+/// Returns the usage of every API key that has made a request, by key id.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/api-keys",
+    params(UsageQuery),
+    responses(
+        (status = 200, description = "Usage of every key", body = Vec<KeyUsage>),
+        (status = 403, description = "API authentication is disabled")
+    ),
+    tag = "node"
+)]
+pub async fn list_api_key_usage(node: NodeData, query: web::Query<UsageQuery>) -> impl Responder {
+    match usage_meter(&node, "list") {
+        Ok(meter) => HttpResponse::Ok().json(meter.all_usage(query.period)),
+        Err(response) => response,
+    }
+}
+
+/// Get API key usage
+///
+/// Returns requests by route group, bytes served, WebSocket events and
+/// quota standing of one API key over the requested period.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/api-keys/{id}/usage",
+    params(
+        ("id" = String, Path, description = "API key id"),
+        UsageQuery
+    ),
+    responses(
+        (status = 200, description = "Key usage", body = KeyUsage),
+        (status = 403, description = "API authentication is disabled"),
+        (status = 404, description = "No requests recorded for the key")
+    ),
+    tag = "node"
+)]
+pub async fn get_api_key_usage(
+    node: NodeData,
+    path: web::Path<String>,
+    query: web::Query<UsageQuery>,
+) -> impl Responder {
+    let meter = match usage_meter(&node, "get") {
+        Ok(meter) => meter,
+        Err(response) => return response,
+    };
+    match meter.usage(&path, query.period) {
+        Some(usage) => HttpResponse::Ok().json(usage),
+        None => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No usage recorded for API key {}", path),
+        }),
+    }
+}
+
+/// Get usage of the calling API key
+///
+/// Self-service view of the key the request is authenticated with,
+/// including how much of its daily quota remains.
+#[utoipa::path(
+    get,
+    path = "/api/v1/node/my-usage",
+    params(UsageQuery),
+    responses(
+        (status = 200, description = "Usage of the calling key", body = KeyUsage),
+        (status = 404, description = "Request not authenticated with an API key")
+    ),
+    tag = "node"
+)]
+pub async fn get_my_usage(
+    req: HttpRequest,
+    node: NodeData,
+    query: web::Query<UsageQuery>,
+) -> impl Responder {
+    let Some(ApiKeyId(key_id)) = req.extensions().get::<ApiKeyId>().cloned() else {
+        return HttpResponse::NotFound().json(ErrorResponse {
+            error: "Request was not authenticated with an API key".to_string(),
+        });
+    };
+    match node.usage_meter().usage(&key_id, query.period) {
+        Some(usage) => HttpResponse::Ok().json(usage),
+        None => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No usage recorded for API key {}", key_id),
+        }),
+    }
+}
@@ -0,0 +1,297 @@
+//! WebSocket subscription routes
+//!
+//! Upgrades the connection and bridges frames to the address subscription
+//! hub; the message protocol is described in
+//! [`crate::api::address_subscriptions`]. Operator messages accepted by the
+//! node are pushed as they arrive, one JSON `OperatorMessage` per frame.
+//!
+//! `/ws` carries chain, mempool and peer event subscriptions
+//! ([`crate::api::event_subscriptions`]). The server pings every
+//! [`PING_INTERVAL`] and closes connections it has not heard from within
+//! [`CLIENT_TIMEOUT`].
+
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message};
+use futures_util::StreamExt;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+
+use super::NodeData;
+use crate::api::usage::ApiKeyId;
+use crate::events::NodeEvent;
+use crate::node::NodeError;
+
+/// How often `/ws` clients are pinged
+pub const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a `/ws` client may stay silent, pongs included
+pub const CLIENT_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Configure subscription routes
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/addresses", web::get().to(address_subscriptions))
+        .route("/operator-messages", web::get().to(operator_messages));
+}
+
+/// Watch-only address subscriptions over WebSocket
+pub async fn address_subscriptions(
+    req: HttpRequest,
+    body: web::Payload,
+    node: NodeData,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (response, mut session, mut frames) = actix_ws::handle(&req, body)?;
+    let hub = node.address_subscriptions();
+    let usage = node.usage_meter();
+    let key_id = req.extensions().get::<ApiKeyId>().map(|id| id.0.clone());
+    let (sender, mut outgoing) = mpsc::unbounded_channel();
+    let mut conn = hub.connect(sender);
+
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                frame = frames.next() => match frame {
+                    Some(Ok(Message::Text(text))) => hub.handle_text(&mut conn, &text),
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+                message = outgoing.recv() => {
+                    let Some(message) = message else { break };
+                    let Ok(text) = serde_json::to_string(&message) else { continue };
+                    if session.text(text).await.is_err() {
+                        break;
+                    }
+                    if let Some(key_id) = &key_id {
+                        usage.record_ws_event(key_id);
+                    }
+                }
+            }
+        }
+        hub.disconnect(&conn);
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+/// Chain, mempool and peer events over WebSocket
+pub async fn event_subscriptions(
+    req: HttpRequest,
+    body: web::Payload,
+    node: NodeData,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (response, mut session, mut frames) = actix_ws::handle(&req, body)?;
+    let hub = node.event_subscriptions();
+    let usage = node.usage_meter();
+    let key_id = req.extensions().get::<ApiKeyId>().map(|id| id.0.clone());
+    let (conn, mut outgoing) = hub.connect();
+
+    actix_web::rt::spawn(async move {
+        let mut last_heard = Instant::now();
+        let mut keepalive = tokio::time::interval(PING_INTERVAL);
+        let reason: Option<CloseReason> = loop {
+            tokio::select! {
+                frame = frames.next() => {
+                    last_heard = Instant::now();
+                    match frame {
+                        Some(Ok(Message::Text(text))) => {
+                            let reply = hub.handle_text(&conn, &text);
+                            let Ok(text) = serde_json::to_string(&reply) else { continue };
+                            if session.text(text).await.is_err() {
+                                break None;
+                            }
+                        }
+                        Some(Ok(Message::Ping(bytes))) => {
+                            if session.pong(&bytes).await.is_err() {
+                                break None;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break None,
+                        Some(Ok(_)) => {}
+                    }
+                }
+                message = outgoing.recv() => {
+                    // The hub only lets go of a connection that fell behind
+                    let Some(message) = message else {
+                        break Some((CloseCode::Policy, "subscriber too slow").into());
+                    };
+                    let Ok(text) = serde_json::to_string(&message) else { continue };
+                    if session.text(text).await.is_err() {
+                        break None;
+                    }
+                    if let Some(key_id) = &key_id {
+                        usage.record_ws_event(key_id);
+                    }
+                }
+                _ = keepalive.tick() => {
+                    if last_heard.elapsed() > CLIENT_TIMEOUT {
+                        break Some((CloseCode::Away, "keepalive timeout").into());
+                    }
+                    if session.ping(b"").await.is_err() {
+                        break None;
+                    }
+                }
+            }
+        };
+        hub.disconnect(&conn);
+        let _ = session.close(reason).await;
+    });
+
+    Ok(response)
+}
+
+/// Operator messages over WebSocket, as they are accepted
+pub async fn operator_messages(
+    req: HttpRequest,
+    body: web::Payload,
+    node: NodeData,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let Err(e) = node.operator_messages("subscribe") {
+        return Ok(match e {
+            NodeError::ConfigError(e) => HttpResponse::Forbidden().body(e),
+            e => HttpResponse::InternalServerError().body(e.to_string()),
+        });
+    }
+    let (response, mut session, mut frames) = actix_ws::handle(&req, body)?;
+    let mut events = node.events().subscribe();
+
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                frame = frames.next() => match frame {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                },
+                event = events.recv() => match event {
+                    Ok(NodeEvent::OperatorMessage(message)) => {
+                        let Ok(text) = serde_json::to_string(&message) else { continue };
+                        if session.text(text).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+#[cfg(all(test, feature = "testnet"))]
+mod tests {
+    use super::*;
+    use crate::api::middleware::auth::ApiAuth;
+    use crate::api::rate_limiter::ApiRateLimiter;
+    use crate::api_facade::ApiFacade;
+    use crate::config::NodeConfig;
+    use crate::node::Node;
+    use actix_web::{App, HttpServer};
+    use futures_util::{SinkExt, Stream};
+    use serde_json::{json, Value};
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::Arc;
+    use tokio_tungstenite::tungstenite::{Error as WsError, Message as WsMessage};
+
+    /// Serve the API of a fresh node on an ephemeral local port
+    async fn serve_test_node() -> (SocketAddr, tempfile::TempDir) {
+        let data_dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let mut config = NodeConfig::default();
+        config.network.max_peers = config
+            .network
+            .max_peers
+            .max(config.network.max_inbound_connections)
+            .max(config.network.max_outbound_connections);
+        config.storage.db_path = data_dir.path().join("data");
+        // Keep clear of the fixed default P2P port other node tests bind
+        let p2p_port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+        config.network.listen_addr = format!("/ip4/127.0.0.1/tcp/{}", p2p_port);
+
+        let node = Node::new(config).await.expect("Failed to create test node");
+        let facade = web::Data::new(Arc::new(
+            ApiFacade::new(&node).expect("Failed to create ApiFacade"),
+        ));
+        let rate_limiter = web::Data::new(Arc::new(ApiRateLimiter::new()));
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(facade.clone())
+                .app_data(rate_limiter.clone())
+                .wrap(ApiAuth::disabled())
+                .configure(crate::api::routes::configure)
+        })
+        .workers(1)
+        .disable_signals()
+        .bind(("127.0.0.1", 0))
+        .expect("Failed to bind API server");
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        (addr, data_dir)
+    }
+
+    /// Next JSON text frame, skipping keepalive frames
+    async fn next_json<S>(ws: &mut S) -> Value
+    where
+        S: Stream<Item = Result<WsMessage, WsError>> + Unpin,
+    {
+        loop {
+            let frame = tokio::time::timeout(Duration::from_secs(120), ws.next())
+                .await
+                .expect("Timed out waiting for a frame")
+                .expect("WebSocket closed")
+                .expect("WebSocket error");
+            if let WsMessage::Text(text) = frame {
+                return serde_json::from_str(&text).expect("Frame is not JSON");
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn mined_block_reaches_subscriber() {
+        let (addr, _data_dir) = serve_test_node().await;
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", addr))
+            .await
+            .expect("Failed to connect to /ws");
+
+        let subscribe = json!({ "subscribe": { "topic": "new_block" } });
+        ws.send(WsMessage::Text(subscribe.to_string()))
+            .await
+            .unwrap();
+        let subscribed = next_json(&mut ws).await;
+        assert_eq!(subscribed["type"], "subscribed", "{}", subscribed);
+        let subscription = subscribed["subscription"].clone();
+
+        let response: Value = reqwest::Client::new()
+            .post(format!("http://{}/rpc", addr))
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": "generate", "params": [1] }))
+            .send()
+            .await
+            .expect("generate request failed")
+            .json()
+            .await
+            .expect("generate response is not JSON");
+        let hash = response["result"][0]
+            .as_str()
+            .unwrap_or_else(|| panic!("generate failed: {}", response))
+            .to_string();
+
+        let event = next_json(&mut ws).await;
+        assert_eq!(event["type"], "event", "{}", event);
+        assert_eq!(event["subscription"], subscription);
+        assert_eq!(event["event"]["topic"], "new_block");
+        assert_eq!(event["event"]["data"]["hash"], hash.as_str());
+    }
+}
@@ -0,0 +1,307 @@
+//! Explorer search
+//!
+//! Classifies a free-form query (block height, full block hash or txid,
+//! address, or hex prefix) and resolves it against the block and transaction
+//! stores. Hash prefixes use the storage layer's prefix scans, so partial
+//! hashes never walk the whole database.
+
+use crate::storage::{BlockchainDB, StorageError};
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+use supernova_core::script::classify::{self as script_class, address_hrp, address_to_script};
+
+/// Shortest hex prefix accepted for partial-hash lookups
+pub const MIN_PREFIX_LEN: usize = 8;
+
+/// Maximum number of candidates returned for a single query
+pub const MAX_SEARCH_RESULTS: usize = 20;
+
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Search query is empty")]
+    Empty,
+
+    #[error("Hash prefix must be at least {min} hex characters, got {len}")]
+    PrefixTooShort { len: usize, min: usize },
+
+    #[error("Address '{address}' belongs to a different network (prefix '{hrp}'); this node expects '{expected}1...' addresses")]
+    WrongNetwork {
+        address: String,
+        hrp: String,
+        expected: &'static str,
+    },
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Query is not a block height, hash, txid, address or hex prefix")]
+    Unrecognized,
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+}
+
+/// What a query was interpreted as. A query may have several interpretations,
+/// e.g. an 8+ digit number is both a height and a hex prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchQuery {
+    Height(u64),
+    FullHash([u8; 32]),
+    HexPrefix(String),
+    Address(String),
+}
+
+/// Kind of entity a search result refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchEntity {
+    Block,
+    Transaction,
+    Address,
+}
+
+/// A single search candidate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct SearchMatch {
+    /// Entity type
+    pub entity: SearchEntity,
+    /// Block hash, txid or address
+    pub id: String,
+    /// Block height, for block matches
+    pub height: Option<u64>,
+}
+
+/// Search results
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchResponse {
+    /// The query as received
+    pub query: String,
+    /// Matching entities; more than one when the query is ambiguous
+    pub results: Vec<SearchMatch>,
+    /// True when more candidates exist than were returned
+    pub truncated: bool,
+}
+
+/// Interpret a raw query string.
+pub fn classify(query: &str) -> Result<Vec<SearchQuery>, SearchError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(SearchError::Empty);
+    }
+
+    let is_hex = query.chars().all(|c| c.is_ascii_hexdigit());
+    let is_digits = query.chars().all(|c| c.is_ascii_digit());
+    let mut candidates = Vec::new();
+
+    if is_digits {
+        if let Ok(height) = query.parse::<u64>() {
+            candidates.push(SearchQuery::Height(height));
+        }
+    }
+
+    if is_hex {
+        if query.len() == 64 {
+            let mut hash = [0u8; 32];
+            hex::decode_to_slice(query, &mut hash).map_err(|_| SearchError::Unrecognized)?;
+            candidates.push(SearchQuery::FullHash(hash));
+        } else if query.len() < 64 && query.len() >= MIN_PREFIX_LEN {
+            candidates.push(SearchQuery::HexPrefix(query.to_ascii_lowercase()));
+        } else if candidates.is_empty() && query.len() < MIN_PREFIX_LEN {
+            return Err(SearchError::PrefixTooShort {
+                len: query.len(),
+                min: MIN_PREFIX_LEN,
+            });
+        }
+    }
+
+    if !candidates.is_empty() {
+        return Ok(candidates);
+    }
+
+    if let Some(separator) = query.rfind('1') {
+        let hrp = query[..separator].to_ascii_lowercase();
+        if !hrp.is_empty() && hrp.chars().all(|c| c.is_ascii_alphanumeric()) {
+            if hrp != address_hrp() {
+                return Err(SearchError::WrongNetwork {
+                    address: query.to_string(),
+                    hrp,
+                    expected: address_hrp(),
+                });
+            }
+            // Report the address in its canonical form, which is also the
+            // key the address index uses
+            return address_to_script(query)
+                .map(|script| {
+                    let canonical = script_class::classify(&script)
+                        .address()
+                        .unwrap_or_else(|| query.to_ascii_lowercase());
+                    vec![SearchQuery::Address(canonical)]
+                })
+                .map_err(|e| SearchError::InvalidAddress(e.to_string()));
+        }
+    }
+
+    Err(SearchError::Unrecognized)
+}
+
+/// Resolve a raw query against storage.
+pub fn search(db: &BlockchainDB, query: &str) -> Result<SearchResponse, SearchError> {
+    let mut results = Vec::new();
+    let mut truncated = false;
+
+    for candidate in classify(query)? {
+        match candidate {
+            SearchQuery::Height(height) => {
+                if let Some(hash) = db.get_block_hash_by_height(height)? {
+                    results.push(block_match(hash, Some(height)));
+                }
+            }
+            SearchQuery::FullHash(hash) => {
+                if let Some(block) = db.get_block(&hash)? {
+                    results.push(block_match(hash, Some(block.height())));
+                }
+                if db.get_transaction(&hash)?.is_some() {
+                    results.push(SearchMatch {
+                        entity: SearchEntity::Transaction,
+                        id: hex::encode(hash),
+                        height: None,
+                    });
+                }
+            }
+            SearchQuery::HexPrefix(prefix) => {
+                // Ask for one more than we can return so truncation is visible.
+                let remaining = (MAX_SEARCH_RESULTS + 1).saturating_sub(results.len());
+                for hash in db.find_block_hashes_by_prefix(&prefix, remaining)? {
+                    let height = db.get_block(&hash)?.map(|b| b.height());
+                    results.push(block_match(hash, height));
+                }
+                let remaining = (MAX_SEARCH_RESULTS + 1).saturating_sub(results.len());
+                for hash in db.find_transaction_hashes_by_prefix(&prefix, remaining)? {
+                    results.push(SearchMatch {
+                        entity: SearchEntity::Transaction,
+                        id: hex::encode(hash),
+                        height: None,
+                    });
+                }
+            }
+            SearchQuery::Address(address) => results.push(SearchMatch {
+                entity: SearchEntity::Address,
+                id: address,
+                height: None,
+            }),
+        }
+    }
+
+    if results.len() > MAX_SEARCH_RESULTS {
+        results.truncate(MAX_SEARCH_RESULTS);
+        truncated = true;
+    }
+
+    Ok(SearchResponse {
+        query: query.trim().to_string(),
+        results,
+        truncated,
+    })
+}
+
+fn block_match(hash: [u8; 32], height: Option<u64>) -> SearchMatch {
+    SearchMatch {
+        entity: SearchEntity::Block,
+        id: hex::encode(hash),
+        height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::types::transaction::Transaction;
+    use tempfile::tempdir;
+    use wallet::quantum_wallet::Address;
+
+    fn address() -> String {
+        Address::from_public_key(&[7u8; 64]).unwrap().to_string()
+    }
+
+    #[test]
+    fn classifies_each_query_type() {
+        assert_eq!(classify("42").unwrap(), vec![SearchQuery::Height(42)]);
+
+        let full = "ab".repeat(32);
+        assert_eq!(classify(&full).unwrap(), vec![SearchQuery::FullHash([0xab; 32])]);
+
+        assert_eq!(
+            classify("ABCDEF01").unwrap(),
+            vec![SearchQuery::HexPrefix("abcdef01".to_string())]
+        );
+
+        // All-digit queries long enough to be a prefix are ambiguous.
+        assert_eq!(
+            classify("12345678").unwrap(),
+            vec![
+                SearchQuery::Height(12345678),
+                SearchQuery::HexPrefix("12345678".to_string())
+            ]
+        );
+
+        let addr = address();
+        assert_eq!(classify(&addr).unwrap(), vec![SearchQuery::Address(addr.clone())]);
+        // Upper-case input is reported in canonical form
+        assert_eq!(
+            classify(&addr.to_ascii_uppercase()).unwrap(),
+            vec![SearchQuery::Address(addr.clone())]
+        );
+    }
+
+    #[test]
+    fn short_prefix_is_rejected() {
+        assert!(matches!(
+            classify("abcd"),
+            Err(SearchError::PrefixTooShort { len: 4, min: MIN_PREFIX_LEN })
+        ));
+    }
+
+    #[test]
+    fn wrong_network_address_is_explained() {
+        let err = classify("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap_err();
+        assert!(matches!(err, SearchError::WrongNetwork { ref hrp, .. } if hrp == "tb"));
+        assert!(err.to_string().contains("different network"));
+    }
+
+    #[test]
+    fn resolves_heights_hashes_and_capped_prefixes() {
+        let dir = tempdir().unwrap();
+        let db = BlockchainDB::new(dir.path()).unwrap();
+
+        db.store_block_height_index(5, &[0x11; 32]).unwrap();
+        let by_height = search(&db, "5").unwrap();
+        assert_eq!(by_height.results, vec![block_match([0x11; 32], Some(5))]);
+
+        let tx_data = bincode::serialize(&Transaction::new(1, vec![], vec![], 0)).unwrap();
+        let mut txid = [0u8; 32];
+        txid[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        for i in 0..(MAX_SEARCH_RESULTS as u8 + 5) {
+            txid[31] = i;
+            db.store_transaction(&txid, &tx_data).unwrap();
+        }
+
+        let prefixed = search(&db, "deadbeef").unwrap();
+        assert_eq!(prefixed.results.len(), MAX_SEARCH_RESULTS);
+        assert!(prefixed.truncated);
+        assert!(prefixed
+            .results
+            .iter()
+            .all(|m| m.entity == SearchEntity::Transaction && m.id.starts_with("deadbeef")));
+
+        // Odd-length prefixes match on the trailing nibble too.
+        assert_eq!(search(&db, "deadbeef0").unwrap().results.len(), MAX_SEARCH_RESULTS);
+        assert!(search(&db, "deadbeef1").unwrap().results.is_empty());
+
+        txid[31] = 3;
+        let exact = search(&db, &hex::encode(txid)).unwrap();
+        assert_eq!(exact.results.len(), 1);
+        assert_eq!(exact.results[0].entity, SearchEntity::Transaction);
+        assert!(!exact.truncated);
+    }
+}
@@ -6,6 +6,7 @@
 use actix_cors::Cors;
 use actix_web::{dev::Server, middleware, web, App, HttpServer};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -14,9 +15,11 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use super::docs::ApiDoc;
+use super::event_subscriptions::BackpressurePolicy;
 use super::middleware::auth::ApiAuth;
 use super::middleware::auth_rate_limiter::{AuthRateLimiter, AuthRateLimiterConfig};
 use super::middleware::rate_limiting;
+use super::middleware::scopes::{legacy_admin_credentials, ApiCredential, ApiScope};
 use super::routes;
 use crate::api::rate_limiter::ApiRateLimiter;
 use crate::api_facade::ApiFacade;
@@ -65,15 +68,65 @@ pub struct ApiConfig {
     pub rate_limit: Option<u32>,
     /// Enable authentication
     pub enable_auth: bool,
-    /// API keys (only used if enable_auth is true)
+    /// Plaintext API keys with full access (only used if enable_auth is
+    /// true). Prefer `api_tokens`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub api_keys: Option<Vec<String>>,
+    /// Hashed API keys with permission scopes (only used if enable_auth is
+    /// true)
+    #[serde(default)]
+    pub api_tokens: Vec<ApiTokenConfig>,
     /// Detailed logging
     pub detailed_logging: bool,
     /// Maximum JSON payload size in megabytes
     pub max_json_payload_size: usize,
     /// Request timeout in seconds
     pub request_timeout: u64,
+    /// How long idempotency keys are remembered, in seconds
+    #[serde(default = "default_idempotency_retention_secs")]
+    pub idempotency_retention_secs: u64,
+    /// Maximum number of idempotency keys remembered at once
+    #[serde(default = "default_idempotency_max_keys")]
+    pub idempotency_max_keys: usize,
+    /// Requests per day each API key may make; unlimited when unset
+    #[serde(default)]
+    pub daily_request_quota: Option<u64>,
+    /// Daily request quotas of individual keys by key id, overriding
+    /// `daily_request_quota`
+    #[serde(default)]
+    pub api_key_quotas: HashMap<String, u64>,
+    /// UTC hour (0-23) at which daily usage counters and quotas reset
+    #[serde(default)]
+    pub quota_reset_hour_utc: u8,
+    /// Answer the JSON-RPC methods Bitcoin Core shares with Supernova using
+    /// Core's field layouts (see `api::jsonrpc::compat`)
+    #[serde(default)]
+    pub bitcoin_rpc_compat: bool,
+    /// What `/ws` does with a subscriber that cannot keep up:
+    /// `disconnect` or `drop_events`
+    #[serde(default)]
+    pub ws_backpressure: BackpressurePolicy,
+}
+
+/// An API key stored as its SHA-256 hash, so the config file never holds
+/// the secret itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiTokenConfig {
+    /// Name used in logs
+    pub name: String,
+    /// Hex SHA-256 of the key, e.g. from `printf %s "$KEY" | sha256sum`
+    pub sha256: String,
+    /// Scopes granted to the key: `read`, `wallet` and/or `admin`
+    pub scopes: Vec<ApiScope>,
+}
+
+fn default_idempotency_retention_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_idempotency_max_keys() -> usize {
+    10_000
 }
 
 impl Default for ApiConfig {
@@ -100,9 +153,17 @@ impl Default for ApiConfig {
             // ran production with a known credential. The server now
             // refuses to start unless the operator supplies a real key.
             api_keys: None,
+            api_tokens: Vec::new(),
             detailed_logging: true,
             max_json_payload_size: 5, // 5 MB
             request_timeout: 30,      // 30 seconds
+            idempotency_retention_secs: default_idempotency_retention_secs(),
+            idempotency_max_keys: default_idempotency_max_keys(),
+            daily_request_quota: None,
+            api_key_quotas: HashMap::new(),
+            quota_reset_hour_utc: 0,
+            bitcoin_rpc_compat: false,
+            ws_backpressure: BackpressurePolicy::default(),
         }
     }
 }
@@ -115,8 +176,9 @@ fn validate_api_keys(keys: &[String]) -> std::io::Result<()> {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidInput,
             "API authentication is enabled but no API keys are configured. \
-             Set api_keys to one or more operator-generated secrets of at \
-             least 32 characters before starting.",
+             Set api_tokens to the hashes of one or more operator-generated \
+             secrets, or api_keys to secrets of at least 32 characters, \
+             before starting.",
         ));
     }
     for key in keys {
@@ -170,6 +232,29 @@ fn validate_api_keys(keys: &[String]) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Credentials of every configured key. Plaintext `api_keys` are validated
+/// as before; at least one key of either kind is required.
+fn api_credentials(config: &ApiConfig) -> std::io::Result<Vec<ApiCredential>> {
+    let keys = config.api_keys.clone().unwrap_or_default();
+    if !keys.is_empty() || config.api_tokens.is_empty() {
+        validate_api_keys(&keys)?;
+    }
+
+    let mut credentials = legacy_admin_credentials(&keys);
+    for token in &config.api_tokens {
+        if credentials.iter().any(|c| c.name == token.name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("API token name '{}' is used more than once", token.name),
+            ));
+        }
+        let credential = ApiCredential::from_hash(&token.name, &token.sha256, token.scopes.clone())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        credentials.push(credential);
+    }
+    Ok(credentials)
+}
+
 /// Build a CORS middleware from the configured allow-list.
 ///
 /// * empty list — returns the `Cors::default()` layer, which has no allowed
@@ -186,7 +271,13 @@ fn build_cors(allowed_origins: &[String]) -> Cors {
     }
     let mut cors = Cors::default()
         .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
-        .allowed_headers(vec!["Content-Type", "Authorization", "Accept", "X-Requested-With"])
+        .allowed_headers(vec![
+            "Content-Type",
+            "Authorization",
+            "Accept",
+            "X-Requested-With",
+            "Idempotency-Key",
+        ])
         .max_age(3600);
 
     if allowed_origins.iter().any(|origin| origin == "*") {
@@ -237,6 +328,8 @@ impl ApiServer {
     /// (e.g. a missing wallet manager whose fallback initialization fails).
     pub fn new(node: Arc<Node>, config: ApiConfig) -> Result<Self, crate::node::NodeError> {
         let node_facade = Arc::new(ApiFacade::new(&node)?);
+        #[cfg(feature = "stratum")]
+        crate::mining::stratum::spawn(Arc::clone(&node_facade));
         let bind_address = config.bind_address.clone();
         let port = config.port;
 
@@ -271,10 +364,23 @@ impl ApiServer {
         // Validate API key configuration fail-closed. An enable_auth=true
         // config with missing or placeholder keys is a production foot-gun
         // and must refuse to start.
-        let validated_keys: Option<Vec<String>> = if config.enable_auth {
-            let keys = config.api_keys.clone().unwrap_or_default();
-            if let Err(err) = validate_api_keys(&keys) {
-                error!("SECURITY: refusing to start API server: {}", err);
+        let validated_keys: Option<Vec<ApiCredential>> = if config.enable_auth {
+            let keys = match api_credentials(&config) {
+                Ok(keys) => keys,
+                Err(err) => {
+                    error!("SECURITY: refusing to start API server: {}", err);
+                    return Err(err);
+                }
+            };
+            if config.quota_reset_hour_utc > 23 {
+                let err = std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "quota_reset_hour_utc must be between 0 and 23 (found {})",
+                        config.quota_reset_hour_utc
+                    ),
+                );
+                error!("Refusing to start API server: {}", err);
                 return Err(err);
             }
             Some(keys)
@@ -328,11 +434,15 @@ impl ApiServer {
         // same reason as `auth_rate_limiter` above: the factory closure runs
         // once per worker, and a per-worker limiter would multiply every
         // per-IP / per-endpoint / concurrency ceiling by the worker count.
-        // The `handle_jsonrpc` / `handle_jsonrpc_batch` handlers extract this
+        // The `handle_jsonrpc` handler extracts this
         // as `web::Data<Arc<ApiRateLimiter>>`; without registering it here,
-        // actix data extraction fails and every POST to `/` returns 500.
+        // actix data extraction fails and every POST to `/rpc` returns 500.
         let api_rate_limiter = web::Data::new(Arc::new(ApiRateLimiter::new()));
 
+        // Usage counters live on the facade so the usage routes read the
+        // same meter every worker's auth middleware records into.
+        let usage_meter = self.node_facade.usage_meter();
+
         // Set up the HTTP server. The factory closure is invoked per worker;
         // middleware values must be freshly constructed each call because
         // actix-cors' `Cors` and our `ApiAuth` are not `Clone`. The
@@ -341,10 +451,11 @@ impl ApiServer {
         // the App type stays homogeneous and avoids conditional `.boxed()`.
         let server = HttpServer::new(move || {
             let auth = match &validated_keys {
-                Some(keys) => ApiAuth::from_validated_keys_with_rate_limiter(
+                Some(keys) => ApiAuth::from_credentials_with_rate_limiter(
                     keys.clone(),
                     auth_rate_limiter.clone(),
-                ),
+                )
+                .with_usage_meter(usage_meter.clone()),
                 None => ApiAuth::disabled_with_rate_limiter(auth_rate_limiter.clone()),
             };
 
@@ -352,7 +463,8 @@ impl ApiServer {
                 .app_data(node_data.clone())
                 .app_data(metrics_data.clone())
                 .app_data(api_rate_limiter.clone())
-                // Configure JSON extractor limits
+                // Configure JSON extractor limits. The JSON-RPC routes read
+                // raw bytes under their own, larger limit.
                 .app_data(web::JsonConfig::default().limit(4096))
                 .wrap(middleware::Compress::default())
                 .wrap(
@@ -483,6 +595,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_api_tokens_are_read_as_hashed_scoped_credentials() {
+        let hash = hex::encode(crate::api::middleware::scopes::hash_api_key("reader"));
+        let config: ApiConfig = toml::from_str(&format!(
+            r#"
+            bind_address = "127.0.0.1"
+            port = 8080
+            enable_docs = false
+            cors_allowed_origins = []
+            enable_auth = true
+            detailed_logging = false
+            max_json_payload_size = 5
+            request_timeout = 30
+
+            [[api_tokens]]
+            name = "explorer"
+            sha256 = "{}"
+            scopes = ["read"]
+            "#,
+            hash
+        ))
+        .unwrap();
+
+        // Hashed tokens alone satisfy the key requirement
+        let credentials = api_credentials(&config).unwrap();
+        assert_eq!(credentials.len(), 1);
+        assert_eq!(credentials[0].name, "explorer");
+        assert!(!credentials[0].scopes().allows(ApiScope::Wallet));
+
+        let mut duplicate = config.clone();
+        duplicate.api_tokens.push(duplicate.api_tokens[0].clone());
+        assert!(api_credentials(&duplicate).is_err());
+
+        let mut malformed = config.clone();
+        malformed.api_tokens[0].sha256 = "reader".to_string();
+        assert!(api_credentials(&malformed).is_err());
+
+        // Plaintext keys are still validated alongside tokens
+        let mut weak = config;
+        weak.api_keys = Some(vec!["short".to_string()]);
+        assert!(api_credentials(&weak).is_err());
+
+        assert!(api_credentials(&ApiConfig::default()).is_err());
+    }
+
     #[test]
     fn test_validate_api_keys_accepts_real_key() {
         // 64-char non-placeholder key.
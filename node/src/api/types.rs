@@ -7,6 +7,8 @@ use actix_web::{HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use utoipa::ToSchema;
+use crate::clock::ClockStatus;
+use crate::network::sync_progress::SyncProgress;
 
 // Import and re-export environmental types
 pub mod environmental;
@@ -81,6 +83,10 @@ pub struct BlockchainInfo {
     pub network: String,
     /// Software version
     pub version: String,
+    /// Whether old blocks are pruned
+    pub pruned: bool,
+    /// Lowest block height still stored, when pruned
+    pub prune_height: Option<u64>,
 }
 
 /// Block information response
@@ -198,6 +204,70 @@ pub struct BlockchainStats {
     pub chain_size_bytes: u64,
 }
 
+/// Version-bits deployment state and signaling progress
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentInfo {
+    /// Deployment name
+    pub name: String,
+    /// Header version bit it signals on
+    pub bit: u8,
+    /// State for the next block: defined, started, locked_in, active or failed
+    pub state: String,
+    /// Median-time-past at which signaling opens
+    pub start_time: u64,
+    /// Median-time-past after which it fails if not locked in
+    pub timeout: u64,
+    /// Percentage of a window that must signal
+    pub threshold_percent: u8,
+    /// Signaling in the current window, while the deployment is started
+    pub statistics: Option<DeploymentStatistics>,
+}
+
+/// Signaling progress within the current window
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeploymentStatistics {
+    /// Blocks per window
+    pub period: u64,
+    /// Signaling blocks needed to lock in
+    pub threshold: u64,
+    /// Blocks of the window mined so far
+    pub elapsed: u64,
+    /// Signaling blocks so far
+    pub count: u64,
+    /// Whether the threshold can still be met this window
+    pub possible: bool,
+}
+
+/// Validation trace of a rejected block
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RejectionTraceInfo {
+    /// Block hash
+    pub block_hash: String,
+    /// Block height
+    pub height: u64,
+    /// Unix timestamp (seconds) of the rejection
+    pub rejected_at: u64,
+    /// Checks in the order they ran; the last failed one rejected the block
+    pub steps: Vec<TraceStepInfo>,
+    /// Passing checks dropped to keep the trace bounded
+    pub omitted_steps: usize,
+}
+
+/// One check from a validation trace
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TraceStepInfo {
+    /// Dotted check name, e.g. `difficulty` or `tx[2].inputs`
+    pub check: String,
+    /// Summary of the values checked; script and witness bytes are truncated
+    pub inputs: String,
+    /// passed, failed or skipped
+    pub status: String,
+    /// Why the check failed or was skipped
+    pub reason: Option<String>,
+    /// Time spent in the check, in microseconds
+    pub duration_us: u64,
+}
+
 /// Mempool information response
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct MempoolInfo {
@@ -304,8 +374,13 @@ pub struct NetworkInfo {
     pub accepts_incoming: bool,
     /// Local addresses
     pub local_addresses: Vec<NetworkAddress>,
+    /// Addresses advertised to peers: configured external addresses, or
+    /// routable listen addresses plus observed addresses confirmed by peers
+    pub advertised_addresses: Vec<String>,
     /// External IP address (if detected)
     pub external_ip: Option<String>,
+    /// Gateway port mapping (UPnP / NAT-PMP)
+    pub nat: crate::network::nat::NatStatus,
     /// Network stats
     pub network_stats: NetworkStats,
 }
@@ -369,6 +444,8 @@ pub struct PeerInfo {
     pub banned: bool,
     /// Peer reputation score
     pub reputation_score: f64,
+    /// Direct message transport: plaintext, negotiating or encrypted
+    pub encryption: String,
 }
 
 /// Peer connection status
@@ -397,6 +474,29 @@ pub struct BandwidthUsage {
     pub peak_upload_rate: f64,
     /// Peak download rate (bytes/sec)
     pub peak_download_rate: f64,
+    /// Sends delayed by the upload budget
+    pub delayed_sends: u64,
+    /// Relay messages not sent because of the upload budget
+    pub dropped_sends: u64,
+    /// Relay messages discarded because of the download budget
+    pub dropped_receives: u64,
+    /// Traffic with each connected peer
+    pub peers: Vec<PeerBandwidthUsage>,
+}
+
+/// Traffic with one connected peer
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PeerBandwidthUsage {
+    /// Peer ID
+    pub peer_id: String,
+    /// Bytes sent to the peer; broadcasts count once per peer
+    pub bytes_sent: u64,
+    /// Bytes received from the peer
+    pub bytes_received: u64,
+    /// Messages sent to the peer
+    pub messages_sent: u64,
+    /// Messages received from the peer
+    pub messages_received: u64,
 }
 
 /// Peer add request
@@ -507,6 +607,8 @@ pub struct MiningTemplate {
     pub estimated_time_to_mine: f64,
     /// Environmental data
     pub environmental_data: Option<TemplateEnvironmentalData>,
+    /// Pass back as `longpollid` to wait for the next template
+    pub longpollid: String,
 }
 
 /// Template transaction
@@ -1048,6 +1150,12 @@ pub struct NodeStatus {
     pub difficulty: f64,
     /// Network hashrate estimate
     pub network_hashrate: u64,
+    /// Sync progress, rates and ETA
+    pub sync: Option<SyncProgress>,
+    /// Local clock skew against peers and the tip
+    pub clock: ClockStatus,
+    /// Incremental UTXO set commitment (hex)
+    pub utxo_commitment: Option<String>,
 }
 
 /// Version information
@@ -1090,6 +1198,8 @@ pub struct NodeMetrics {
     pub memory_usage: u64,
     /// Disk usage in bytes
     pub disk_usage: u64,
+    /// Blocks and headers served to peers
+    pub block_serving: crate::network::BlockServingStats,
 }
 
 /// Block height parameter for API requests
@@ -1612,6 +1722,27 @@ pub struct TransactionOutput {
     pub spent: Option<bool>,
     /// Transaction ID of spending transaction (if spent)
     pub spent_by_tx: Option<String>,
+    /// Payload of an `OP_RETURN` output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<DataOutput>,
+}
+
+/// Payload of an `OP_RETURN` data carrier output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DataOutput {
+    /// Payload bytes, hex-encoded
+    pub hex: String,
+    /// Payload decoded as UTF-8 with invalid sequences replaced, for display
+    pub text: String,
+}
+
+impl DataOutput {
+    pub fn from_payload(payload: &[u8]) -> Self {
+        Self {
+            hex: hex::encode(payload),
+            text: String::from_utf8_lossy(payload).into_owned(),
+        }
+    }
 }
 
 /// Response for mempool transaction submission
@@ -0,0 +1,714 @@
+//! Per-API-key usage metering
+//!
+//! Every request authenticated with an API key is counted against that key:
+//! requests per route group, response bytes and WebSocket events pushed. The
+//! counters are atomics bumped on the request path; a background task writes
+//! them to disk periodically so a restart loses at most one flush interval.
+//!
+//! Counters are kept per metering day. A day starts at the configured UTC
+//! hour, which is also when daily request quotas reset. A key over its quota
+//! gets `429 Too Many Requests` until the next boundary.
+//!
+//! Keys are reported by id — the first 8 bytes of the key's SHA-256 in hex —
+//! so usage can be shown and configured without handling the secret itself.
+
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// How often counters are written to disk
+pub const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Closed metering days kept per key
+pub const RETAINED_DAYS: u64 = 90;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Route groups requests are counted under: the scope after `/api/v1/`,
+/// `jsonrpc` for the JSON-RPC endpoint and `other` for everything else
+pub const ROUTE_GROUPS: &[&str] = &[
+    "blockchain",
+    "address",
+    "node",
+    "network",
+    "mempool",
+    "faucet",
+    "wallet",
+    "lightning",
+    "mining",
+    "environmental",
+    "ws",
+    "jsonrpc",
+    "other",
+];
+
+/// Index into [`ROUTE_GROUPS`] of the group `path` is counted under
+fn route_group(path: &str) -> usize {
+    let group = match path.strip_prefix("/api/v1/") {
+        Some(rest) => rest.split('/').next().unwrap_or_default(),
+        None if path == "/" || path.is_empty() => "jsonrpc",
+        None => "other",
+    };
+    ROUTE_GROUPS
+        .iter()
+        .position(|g| *g == group)
+        .unwrap_or(ROUTE_GROUPS.len() - 1)
+}
+
+/// Public identifier of an API key
+pub fn key_id(api_key: &str) -> String {
+    hex::encode(&Sha256::digest(api_key.as_bytes())[..8])
+}
+
+/// Id of the API key a request was authenticated with, stored in the
+/// request extensions by the auth middleware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyId(pub String);
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    /// Seconds since the Unix epoch
+    fn now_secs(&self) -> u64;
+}
+
+/// Wall-clock time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+}
+
+/// Daily request quotas
+#[derive(Debug, Clone, Default)]
+pub struct QuotaPolicy {
+    /// Requests per day for keys without an override; unlimited when unset
+    pub default_limit: Option<u64>,
+    /// Per-key limits by key id
+    pub per_key: HashMap<String, u64>,
+    /// UTC hour (0-23) at which metering days start
+    pub reset_hour_utc: u8,
+}
+
+impl QuotaPolicy {
+    /// Requests per day allowed to `key_id`
+    pub fn limit(&self, key_id: &str) -> Option<u64> {
+        self.per_key.get(key_id).copied().or(self.default_limit)
+    }
+
+    fn offset_secs(&self) -> u64 {
+        u64::from(self.reset_hour_utc % 24) * 60 * 60
+    }
+
+    /// Metering day containing `now`
+    fn day(&self, now: u64) -> u64 {
+        now.saturating_sub(self.offset_secs()) / SECS_PER_DAY
+    }
+
+    /// Unix time at which `day` starts
+    fn day_start(&self, day: u64) -> u64 {
+        day * SECS_PER_DAY + self.offset_secs()
+    }
+}
+
+/// Usage over a period
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UsageCounts {
+    /// Requests served by route group
+    pub requests: BTreeMap<String, u64>,
+    /// Response body bytes served
+    pub bytes_served: u64,
+    /// WebSocket events pushed
+    pub ws_events: u64,
+    /// Requests refused because the daily quota was exhausted
+    pub quota_rejections: u64,
+}
+
+impl UsageCounts {
+    /// Requests served across all route groups
+    pub fn total_requests(&self) -> u64 {
+        self.requests.values().sum()
+    }
+
+    fn add(&mut self, other: &UsageCounts) {
+        for (group, count) in &other.requests {
+            *self.requests.entry(group.clone()).or_default() += count;
+        }
+        self.bytes_served += other.bytes_served;
+        self.ws_events += other.ws_events;
+        self.quota_rejections += other.quota_rejections;
+    }
+}
+
+/// Reporting window for usage queries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UsagePeriod {
+    /// The current metering day
+    #[default]
+    Day,
+    /// The current day and the six before it
+    Week,
+    /// The current day and the 29 before it
+    Month,
+    /// Everything retained
+    All,
+}
+
+impl UsagePeriod {
+    fn days(self) -> u64 {
+        match self {
+            UsagePeriod::Day => 1,
+            UsagePeriod::Week => 7,
+            UsagePeriod::Month => 30,
+            UsagePeriod::All => RETAINED_DAYS + 1,
+        }
+    }
+}
+
+/// Where a key stands against its daily quota
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QuotaStatus {
+    /// Requests allowed per day
+    pub limit: u64,
+    /// Requests served so far today
+    pub used: u64,
+    /// Unix time of the next reset
+    pub resets_at: u64,
+}
+
+/// Usage report for one API key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct KeyUsage {
+    /// API key id
+    pub key_id: String,
+    /// Reporting window
+    pub period: UsagePeriod,
+    /// Unix time the window starts
+    pub since: u64,
+    /// Requests served in the window across all route groups
+    pub total_requests: u64,
+    /// Counters for the window
+    pub usage: UsageCounts,
+    /// Daily quota, if one applies to the key
+    pub quota: Option<QuotaStatus>,
+}
+
+/// A request refused because its key's daily quota is exhausted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    pub limit: u64,
+    pub resets_at: u64,
+    /// Seconds until `resets_at`
+    pub retry_after: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "API key quota of {} requests per day exceeded",
+            self.limit
+        )
+    }
+}
+
+impl ResponseError for QuotaExceeded {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::TOO_MANY_REQUESTS
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::TooManyRequests()
+            .insert_header(("Retry-After", self.retry_after.to_string()))
+            .json(json!({
+                "success": false,
+                "error": "quota_exceeded",
+                "message": self.to_string(),
+                "limit": self.limit,
+                "resets_at": self.resets_at,
+            }))
+    }
+}
+
+/// Live counters of the current metering day
+struct Counters {
+    requests: [AtomicU64; ROUTE_GROUPS.len()],
+    bytes_served: AtomicU64,
+    ws_events: AtomicU64,
+    quota_rejections: AtomicU64,
+    /// Requests admitted against the quota; equals the sum of `requests`
+    /// but is claimed before the request runs so concurrent requests cannot
+    /// overshoot the limit
+    admitted: AtomicU64,
+}
+
+impl Counters {
+    fn new() -> Self {
+        Self {
+            requests: std::array::from_fn(|_| AtomicU64::new(0)),
+            bytes_served: AtomicU64::new(0),
+            ws_events: AtomicU64::new(0),
+            quota_rejections: AtomicU64::new(0),
+            admitted: AtomicU64::new(0),
+        }
+    }
+
+    fn snapshot(&self) -> UsageCounts {
+        UsageCounts {
+            requests: ROUTE_GROUPS
+                .iter()
+                .zip(&self.requests)
+                .map(|(group, count)| (group.to_string(), count.load(Ordering::Relaxed)))
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+            bytes_served: self.bytes_served.load(Ordering::Relaxed),
+            ws_events: self.ws_events.load(Ordering::Relaxed),
+            quota_rejections: self.quota_rejections.load(Ordering::Relaxed),
+        }
+    }
+
+    fn set(&self, counts: &UsageCounts) {
+        for (group, count) in ROUTE_GROUPS.iter().zip(&self.requests) {
+            count.store(
+                counts.requests.get(*group).copied().unwrap_or_default(),
+                Ordering::Relaxed,
+            );
+        }
+        self.bytes_served
+            .store(counts.bytes_served, Ordering::Relaxed);
+        self.ws_events.store(counts.ws_events, Ordering::Relaxed);
+        self.quota_rejections
+            .store(counts.quota_rejections, Ordering::Relaxed);
+        self.admitted
+            .store(counts.total_requests(), Ordering::Relaxed);
+    }
+}
+
+struct KeyMeter {
+    /// Metering day `today` counts
+    day: AtomicU64,
+    today: Counters,
+    /// Closed days
+    history: Mutex<BTreeMap<u64, UsageCounts>>,
+}
+
+impl KeyMeter {
+    fn new(day: u64) -> Self {
+        Self {
+            day: AtomicU64::new(day),
+            today: Counters::new(),
+            history: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Close the live day if `day` is later. Requests racing the rollover
+    /// may land on either side of it.
+    fn roll_to(&self, day: u64) {
+        if self.day.load(Ordering::Acquire) >= day {
+            return;
+        }
+        let mut history = self.history.lock();
+        let current = self.day.load(Ordering::Acquire);
+        if current >= day {
+            return;
+        }
+        let closed = self.today.snapshot();
+        if closed != UsageCounts::default() {
+            history.entry(current).or_default().add(&closed);
+        }
+        self.today.set(&UsageCounts::default());
+        self.day.store(day, Ordering::Release);
+        let oldest = day.saturating_sub(RETAINED_DAYS);
+        history.retain(|d, _| *d >= oldest);
+    }
+
+    /// Usage of the days from `first` through the live day
+    fn usage_since(&self, first: u64) -> UsageCounts {
+        let history = self.history.lock();
+        let mut usage = UsageCounts::default();
+        for (_, counts) in history.range(first..) {
+            usage.add(counts);
+        }
+        usage.add(&self.today.snapshot());
+        usage
+    }
+
+    fn record(&self) -> KeyRecord {
+        let history = self.history.lock();
+        KeyRecord {
+            day: self.day.load(Ordering::Acquire),
+            today: self.today.snapshot(),
+            history: history.clone(),
+        }
+    }
+}
+
+/// On-disk form of one key's counters
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeyRecord {
+    day: u64,
+    today: UsageCounts,
+    history: BTreeMap<u64, UsageCounts>,
+}
+
+/// Usage counters of every API key seen
+pub struct UsageMeter {
+    keys: Mutex<HashMap<String, Arc<KeyMeter>>>,
+    policy: QuotaPolicy,
+    clock: Arc<dyn Clock>,
+    state_path: Option<PathBuf>,
+    dirty: AtomicBool,
+}
+
+impl UsageMeter {
+    /// Meter kept in memory only
+    pub fn new(policy: QuotaPolicy, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            keys: Mutex::new(HashMap::new()),
+            policy,
+            clock,
+            state_path: None,
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Meter flushed to `state_path`. An unreadable state file is logged and
+    /// counting starts over.
+    pub fn open(
+        state_path: impl Into<PathBuf>,
+        policy: QuotaPolicy,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let state_path = state_path.into();
+        let records = load_usage(&state_path).unwrap_or_else(|e| {
+            warn!(
+                "Ignoring unreadable API usage state {:?}: {}",
+                state_path, e
+            );
+            HashMap::new()
+        });
+        let keys = records
+            .into_iter()
+            .map(|(id, record)| {
+                let meter = KeyMeter::new(record.day);
+                meter.today.set(&record.today);
+                *meter.history.lock() = record.history;
+                (id, Arc::new(meter))
+            })
+            .collect();
+        Self {
+            keys: Mutex::new(keys),
+            policy,
+            clock,
+            state_path: Some(state_path),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Counters of `key_id`, rolled to the current day
+    fn meter(&self, key_id: &str) -> Arc<KeyMeter> {
+        let day = self.policy.day(self.clock.now_secs());
+        let meter = {
+            let mut keys = self.keys.lock();
+            match keys.get(key_id) {
+                Some(meter) => Arc::clone(meter),
+                None => {
+                    let meter = Arc::new(KeyMeter::new(day));
+                    keys.insert(key_id.to_string(), Arc::clone(&meter));
+                    meter
+                }
+            }
+        };
+        meter.roll_to(day);
+        meter
+    }
+
+    /// Count a request to `path` against `key_id`, or refuse it when the
+    /// key's daily quota is exhausted
+    pub fn admit(&self, key_id: &str, path: &str) -> Result<(), QuotaExceeded> {
+        let meter = self.meter(key_id);
+        self.dirty.store(true, Ordering::Relaxed);
+        if let Some(limit) = self.policy.limit(key_id) {
+            if meter.today.admitted.fetch_add(1, Ordering::AcqRel) >= limit {
+                meter.today.admitted.fetch_sub(1, Ordering::AcqRel);
+                meter.today.quota_rejections.fetch_add(1, Ordering::Relaxed);
+                let now = self.clock.now_secs();
+                let resets_at = self.policy.day_start(self.policy.day(now) + 1);
+                return Err(QuotaExceeded {
+                    limit,
+                    resets_at,
+                    retry_after: resets_at.saturating_sub(now),
+                });
+            }
+        } else {
+            meter.today.admitted.fetch_add(1, Ordering::Relaxed);
+        }
+        meter.today.requests[route_group(path)].fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Count response body bytes served to `key_id`
+    pub fn record_bytes(&self, key_id: &str, bytes: u64) {
+        self.meter(key_id)
+            .today
+            .bytes_served
+            .fetch_add(bytes, Ordering::Relaxed);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Count a WebSocket event pushed to `key_id`
+    pub fn record_ws_event(&self, key_id: &str) {
+        self.meter(key_id)
+            .today
+            .ws_events
+            .fetch_add(1, Ordering::Relaxed);
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Usage of `key_id` over `period`, or `None` for a key never seen
+    pub fn usage(&self, key_id: &str, period: UsagePeriod) -> Option<KeyUsage> {
+        if !self.keys.lock().contains_key(key_id) {
+            return None;
+        }
+        Some(self.report(key_id, period))
+    }
+
+    /// Usage of every key seen over `period`, ordered by key id
+    pub fn all_usage(&self, period: UsagePeriod) -> Vec<KeyUsage> {
+        let mut ids: Vec<String> = self.keys.lock().keys().cloned().collect();
+        ids.sort();
+        ids.iter().map(|id| self.report(id, period)).collect()
+    }
+
+    fn report(&self, key_id: &str, period: UsagePeriod) -> KeyUsage {
+        let meter = self.meter(key_id);
+        let today = self.policy.day(self.clock.now_secs());
+        let first = today.saturating_sub(period.days() - 1);
+        let usage = meter.usage_since(first);
+        let quota = self.policy.limit(key_id).map(|limit| QuotaStatus {
+            limit,
+            used: meter.today.admitted.load(Ordering::Relaxed),
+            resets_at: self.policy.day_start(today + 1),
+        });
+        KeyUsage {
+            key_id: key_id.to_string(),
+            period,
+            since: self.policy.day_start(first),
+            total_requests: usage.total_requests(),
+            usage,
+            quota,
+        }
+    }
+
+    /// Write the counters to the state file if anything changed since the
+    /// last flush
+    pub fn flush(&self) {
+        let Some(path) = &self.state_path else { return };
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        let records: BTreeMap<String, KeyRecord> = self
+            .keys
+            .lock()
+            .iter()
+            .map(|(id, meter)| (id.clone(), meter.record()))
+            .collect();
+        if let Err(e) = persist_usage(&records, path) {
+            self.dirty.store(true, Ordering::Relaxed);
+            warn!("Failed to persist API usage to {:?}: {}", path, e);
+        }
+    }
+
+    /// Flush every [`USAGE_FLUSH_INTERVAL`] until the meter is dropped
+    pub async fn run(self: Arc<Self>) {
+        let meter = Arc::downgrade(&self);
+        drop(self);
+        let mut interval = tokio::time::interval(USAGE_FLUSH_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let Some(meter) = meter.upgrade() else { break };
+            tokio::task::spawn_blocking(move || meter.flush());
+        }
+    }
+}
+
+fn load_usage(path: &Path) -> Result<HashMap<String, KeyRecord>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Write the state file via a temporary file and rename
+fn persist_usage(records: &BTreeMap<String, KeyRecord>, path: &Path) -> std::io::Result<()> {
+    let bytes = serde_json::to_vec(records).map_err(std::io::Error::other)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Clock the tests move by hand
+    struct MockClock(AtomicU64);
+
+    impl MockClock {
+        fn at(secs: u64) -> Arc<Self> {
+            Arc::new(Self(AtomicU64::new(secs)))
+        }
+
+        fn set(&self, secs: u64) {
+            self.0.store(secs, Ordering::Relaxed);
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now_secs(&self) -> u64 {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    /// 2026-01-01T00:00:00Z
+    const DAY_START: u64 = 1_767_225_600;
+
+    #[test]
+    fn test_counts_requests_by_route_group() {
+        let meter = UsageMeter::new(QuotaPolicy::default(), MockClock::at(DAY_START));
+        let key = key_id("first-api-key");
+        for path in [
+            "/api/v1/blockchain/block/1",
+            "/api/v1/blockchain/info",
+            "/api/v1/mempool/info",
+            "/",
+            "/api/v1/ws/addresses",
+            "/metrics",
+        ] {
+            meter.admit(&key, path).unwrap();
+        }
+        meter.record_bytes(&key, 700);
+        meter.record_bytes(&key, 300);
+        meter.record_ws_event(&key);
+        meter
+            .admit(&key_id("second-api-key"), "/api/v1/node/info")
+            .unwrap();
+
+        let usage = meter.usage(&key, UsagePeriod::Day).unwrap();
+        assert_eq!(usage.total_requests, 6);
+        assert_eq!(usage.usage.requests["blockchain"], 2);
+        assert_eq!(usage.usage.requests["mempool"], 1);
+        assert_eq!(usage.usage.requests["jsonrpc"], 1);
+        assert_eq!(usage.usage.requests["ws"], 1);
+        assert_eq!(usage.usage.requests["other"], 1);
+        assert!(!usage.usage.requests.contains_key("node"));
+        assert_eq!(usage.usage.bytes_served, 1_000);
+        assert_eq!(usage.usage.ws_events, 1);
+        assert_eq!(usage.quota, None);
+
+        assert_eq!(meter.all_usage(UsagePeriod::Day).len(), 2);
+        assert!(meter
+            .usage(&key_id("never-used"), UsagePeriod::Day)
+            .is_none());
+    }
+
+    #[test]
+    fn test_periods_cover_closed_days() {
+        let clock = MockClock::at(DAY_START);
+        let meter = UsageMeter::new(QuotaPolicy::default(), clock.clone());
+        let key = key_id("api-key");
+        meter.admit(&key, "/api/v1/node/info").unwrap();
+        clock.set(DAY_START + 3 * SECS_PER_DAY);
+        meter.admit(&key, "/api/v1/node/info").unwrap();
+        meter.admit(&key, "/api/v1/node/info").unwrap();
+
+        assert_eq!(
+            meter.usage(&key, UsagePeriod::Day).unwrap().total_requests,
+            2
+        );
+        let week = meter.usage(&key, UsagePeriod::Week).unwrap();
+        assert_eq!(week.total_requests, 3);
+        assert_eq!(week.since, DAY_START - 3 * SECS_PER_DAY);
+    }
+
+    #[test]
+    fn test_counters_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_usage.json");
+        let clock = MockClock::at(DAY_START);
+        let key = key_id("api-key");
+
+        let meter = UsageMeter::open(&path, QuotaPolicy::default(), clock.clone());
+        meter.admit(&key, "/api/v1/wallet/balance").unwrap();
+        meter.record_bytes(&key, 42);
+        clock.set(DAY_START + SECS_PER_DAY);
+        meter.admit(&key, "/api/v1/wallet/balance").unwrap();
+        meter.flush();
+        drop(meter);
+
+        let meter = UsageMeter::open(&path, QuotaPolicy::default(), clock);
+        let today = meter.usage(&key, UsagePeriod::Day).unwrap();
+        assert_eq!(today.usage.requests["wallet"], 1);
+        let all = meter.usage(&key, UsagePeriod::All).unwrap();
+        assert_eq!(all.total_requests, 2);
+        assert_eq!(all.usage.bytes_served, 42);
+    }
+
+    #[test]
+    fn test_quota_exhausts_and_resets_at_boundary() {
+        let key = key_id("api-key");
+        let policy = QuotaPolicy {
+            default_limit: Some(100),
+            per_key: HashMap::from([(key.clone(), 2)]),
+            reset_hour_utc: 6,
+        };
+        let boundary = DAY_START + 6 * 60 * 60;
+        let clock = MockClock::at(boundary - 10);
+        let meter = UsageMeter::new(policy, clock.clone());
+
+        meter.admit(&key, "/").unwrap();
+        meter.admit(&key, "/").unwrap();
+        let refused = meter.admit(&key, "/").unwrap_err();
+        assert_eq!(refused.limit, 2);
+        assert_eq!(refused.resets_at, boundary);
+        assert_eq!(refused.retry_after, 10);
+
+        let usage = meter.usage(&key, UsagePeriod::Day).unwrap();
+        assert_eq!(usage.total_requests, 2);
+        assert_eq!(usage.usage.quota_rejections, 1);
+        assert_eq!(
+            usage.quota,
+            Some(QuotaStatus {
+                limit: 2,
+                used: 2,
+                resets_at: boundary
+            })
+        );
+
+        // Other keys fall back to the default limit
+        meter.admit(&key_id("other-key"), "/").unwrap();
+
+        clock.set(boundary);
+        meter.admit(&key, "/").unwrap();
+        let usage = meter.usage(&key, UsagePeriod::Day).unwrap();
+        assert_eq!(usage.quota.unwrap().used, 1);
+        assert_eq!(usage.since, boundary);
+    }
+}
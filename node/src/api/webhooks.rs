@@ -0,0 +1,1335 @@
+//! Block explorer webhooks
+//!
+//! Operators register HTTP endpoints that receive chain notifications
+//! without holding a WebSocket open. Each subscription carries a list of
+//! filters:
+//!
+//! - `new_block`: every block connected to the active chain
+//! - `large_transaction`: a confirmed transaction moving at least
+//!   `min_value` (in novas, 10^-8 NOVA) to or from a watched address set; an
+//!   empty set matches any non-coinbase transaction whose outputs total at
+//!   least `min_value`
+//! - `reorg`: a block disconnected from the active chain
+//!
+//! Deliveries are `POST`ed with the canonical JSON payload as the body:
+//!
+//! ```text
+//! X-Supernova-Webhook: <id>
+//! X-Supernova-Sequence: 42
+//! X-Supernova-Signature: sha256=<hex HMAC-SHA256(secret, body)>
+//!
+//! {"created_at":...,"event":{"kind":"new_block",...},"seq":42,"webhook_id":"..."}
+//! ```
+//!
+//! The secret is returned once, when the subscription is created. Sequence
+//! numbers increase by one per matched event and are kept across retries,
+//! so a receiver can deduplicate redelivered events and detect gaps. Events
+//! dropped by the subscription's rate limit or payload cap still consume a
+//! number and are listed in the delivery log.
+//!
+//! Deliveries for one subscription are made in order. A delivery is retried
+//! with exponential backoff until it gets a 2xx response or runs out of
+//! attempts; after [`RetryPolicy::disable_after`] consecutive failed
+//! deliveries the subscription is disabled until re-enabled by an operator.
+
+use crate::events::NodeEvent;
+use crate::storage::{BlockchainDB, TransactionIndexer};
+use hmac::{Hmac, Mac};
+use parking_lot::Mutex;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::Transaction;
+use supernova_core::util::canonical_json::to_canonical_vec;
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use tracing::warn;
+use utoipa::ToSchema;
+
+/// Header carrying the subscription id
+pub const WEBHOOK_ID_HEADER: &str = "X-Supernova-Webhook";
+
+/// Header carrying the delivery sequence number
+pub const SEQUENCE_HEADER: &str = "X-Supernova-Sequence";
+
+/// Header carrying `sha256=<hex>` over the request body
+pub const SIGNATURE_HEADER: &str = "X-Supernova-Signature";
+
+/// Most subscriptions a node keeps
+pub const MAX_WEBHOOKS: usize = 64;
+
+/// Deliveries per minute when the request does not set a limit
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 60;
+
+/// Payload cap when the request does not set one
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 64 * 1024;
+
+/// Largest payload cap a subscription may ask for
+pub const MAX_PAYLOAD_BYTES_LIMIT: usize = 1024 * 1024;
+
+/// Delivery records kept per subscription
+pub const DELIVERY_LOG_LEN: usize = 200;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("Invalid webhook URL: {0}")]
+    InvalidUrl(String),
+    #[error("At least one event filter is required")]
+    NoFilters,
+    #[error("Invalid webhook limit: {0}")]
+    InvalidLimit(String),
+    #[error("At most {0} webhooks may be registered")]
+    TooMany(usize),
+    #[error("Unknown webhook: {0}")]
+    NotFound(String),
+}
+
+/// Which events a subscription receives
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WebhookFilter {
+    /// Every block connected to the active chain
+    NewBlock,
+    /// Confirmed transactions moving at least `min_value` novas to or from
+    /// `addresses` (any transaction when empty)
+    LargeTransaction {
+        min_value: u64,
+        #[serde(default)]
+        addresses: Vec<String>,
+    },
+    /// Blocks disconnected from the active chain
+    Reorg,
+}
+
+/// Event delivered to a webhook
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    NewBlock {
+        height: u64,
+        hash: String,
+        prev_hash: String,
+        timestamp: u64,
+        tx_count: usize,
+    },
+    LargeTransaction {
+        txid: String,
+        height: u64,
+        block_hash: String,
+        /// Sum of all outputs
+        total_output: u64,
+        /// Paid to watched addresses
+        received: u64,
+        /// Spent from watched addresses
+        sent: u64,
+        /// Watched addresses the transaction touches
+        addresses: Vec<String>,
+    },
+    Reorg {
+        /// Height and hash of the disconnected block
+        height: u64,
+        hash: String,
+    },
+}
+
+impl WebhookEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NewBlock { .. } => "new_block",
+            Self::LargeTransaction { .. } => "large_transaction",
+            Self::Reorg { .. } => "reorg",
+        }
+    }
+}
+
+/// Signed body of one delivery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub webhook_id: String,
+    pub seq: u64,
+    /// Unix seconds when the event was queued
+    pub created_at: u64,
+    pub event: WebhookEvent,
+}
+
+/// Subscription parameters supplied by the operator
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSpec {
+    /// `http` or `https` endpoint receiving deliveries
+    pub url: String,
+    pub filters: Vec<WebhookFilter>,
+    /// Deliveries per minute; events beyond the limit are dropped
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    /// Events whose payload exceeds this many bytes are dropped
+    #[serde(default)]
+    pub max_payload_bytes: Option<usize>,
+}
+
+/// Public view of a subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookInfo {
+    pub id: String,
+    pub url: String,
+    pub filters: Vec<WebhookFilter>,
+    pub rate_limit_per_minute: u32,
+    pub max_payload_bytes: usize,
+    pub enabled: bool,
+    /// Why the subscription was disabled, if it was
+    pub disabled_reason: Option<String>,
+    /// Sequence number of the next event
+    pub next_seq: u64,
+    /// Failed deliveries since the last successful one
+    pub consecutive_failures: u32,
+    /// Unix seconds
+    pub created_at: u64,
+}
+
+/// A newly created subscription and its signing secret
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookCreated {
+    pub webhook: WebhookInfo,
+    /// HMAC key for verifying deliveries; not retrievable later
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Queued or being retried
+    Pending,
+    Delivered,
+    /// Ran out of attempts
+    Failed,
+    /// Not sent: rate limited, over the payload cap, or the subscription was
+    /// disabled first
+    Dropped,
+}
+
+/// Outcome of one event for a subscription
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DeliveryRecord {
+    pub seq: u64,
+    pub event: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the last attempt
+    pub last_status_code: Option<u16>,
+    /// Transport error or drop reason
+    pub last_error: Option<String>,
+    /// Unix seconds
+    pub created_at: u64,
+    /// Unix seconds of the final outcome
+    pub completed_at: Option<u64>,
+}
+
+/// Delivery retry and auto-disable policy
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts per delivery, including the first
+    pub max_attempts: u32,
+    /// Wait before the second attempt; doubles after each failure
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Per-request timeout
+    pub timeout: Duration,
+    /// Consecutive failed deliveries before the subscription is disabled
+    pub disable_after: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            initial_backoff: Duration::from_secs(2),
+            max_backoff: Duration::from_secs(300),
+            timeout: Duration::from_secs(10),
+            disable_after: 5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn backoff(&self, failed_attempts: u32) -> Duration {
+        let factor = 1u32 << failed_attempts.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Lookup of outputs that may already be spent, for attributing inputs to
+/// watched addresses once their block is connected
+pub trait OutputSource: Send + Sync {
+    /// Address and value of output `vout` of `txid`
+    fn output(&self, txid: &[u8; 32], vout: u32) -> Option<(String, u64)>;
+}
+
+impl OutputSource for BlockchainDB {
+    fn output(&self, txid: &[u8; 32], vout: u32) -> Option<(String, u64)> {
+        let tx = self.get_transaction(txid).ok()??;
+        let output = tx.outputs().get(vout as usize)?;
+        let address = TransactionIndexer::extract_address_from_output(output)?;
+        Some((address, output.amount()))
+    }
+}
+
+/// `sha256=<hex>` signature of a delivery body
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+        .unwrap_or_else(|_| unreachable!("HMAC accepts keys of any length"));
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Check a [`SIGNATURE_HEADER`] value against a delivery body in constant
+/// time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(digest) = signature
+        .strip_prefix("sha256=")
+        .and_then(|h| hex::decode(h).ok())
+    else {
+        return false;
+    };
+    let Ok(mut mac) = HmacSha256::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+    mac.verify_slice(&digest).is_ok()
+}
+
+/// Persisted part of a subscription
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WebhookRecord {
+    id: String,
+    url: String,
+    secret: String,
+    filters: Vec<WebhookFilter>,
+    rate_limit_per_minute: u32,
+    max_payload_bytes: usize,
+    enabled: bool,
+    disabled_reason: Option<String>,
+    next_seq: u64,
+    created_at: u64,
+}
+
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            capacity: f64::from(per_minute),
+            tokens: f64::from(per_minute),
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn try_take(&mut self, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct QueuedDelivery {
+    seq: u64,
+    body: Vec<u8>,
+}
+
+struct Subscription {
+    record: WebhookRecord,
+    /// Watched addresses of `large_transaction` filters, as a set
+    watched: Vec<(u64, HashSet<String>)>,
+    consecutive_failures: u32,
+    bucket: TokenBucket,
+    deliveries: VecDeque<DeliveryRecord>,
+    queue: Option<mpsc::UnboundedSender<QueuedDelivery>>,
+}
+
+impl Subscription {
+    fn new(record: WebhookRecord) -> Self {
+        let watched = record
+            .filters
+            .iter()
+            .filter_map(|f| match f {
+                WebhookFilter::LargeTransaction {
+                    min_value,
+                    addresses,
+                } => Some((*min_value, addresses.iter().cloned().collect())),
+                _ => None,
+            })
+            .collect();
+        Self {
+            bucket: TokenBucket::new(record.rate_limit_per_minute),
+            record,
+            watched,
+            consecutive_failures: 0,
+            deliveries: VecDeque::new(),
+            queue: None,
+        }
+    }
+
+    fn info(&self) -> WebhookInfo {
+        let r = &self.record;
+        WebhookInfo {
+            id: r.id.clone(),
+            url: r.url.clone(),
+            filters: r.filters.clone(),
+            rate_limit_per_minute: r.rate_limit_per_minute,
+            max_payload_bytes: r.max_payload_bytes,
+            enabled: r.enabled,
+            disabled_reason: r.disabled_reason.clone(),
+            next_seq: r.next_seq,
+            consecutive_failures: self.consecutive_failures,
+            created_at: r.created_at,
+        }
+    }
+
+    fn wants(&self, kind: &str) -> bool {
+        self.record.filters.iter().any(|f| match f {
+            WebhookFilter::NewBlock => kind == "new_block",
+            WebhookFilter::Reorg => kind == "reorg",
+            WebhookFilter::LargeTransaction { .. } => false,
+        })
+    }
+
+    fn log(&mut self, record: DeliveryRecord) {
+        if self.deliveries.len() >= DELIVERY_LOG_LEN {
+            self.deliveries.pop_front();
+        }
+        self.deliveries.push_back(record);
+    }
+
+    fn delivery_mut(&mut self, seq: u64) -> Option<&mut DeliveryRecord> {
+        self.deliveries.iter_mut().rev().find(|d| d.seq == seq)
+    }
+}
+
+/// Dispatches node events to registered webhooks
+pub struct WebhookHub {
+    outputs: Arc<dyn OutputSource>,
+    client: reqwest::Client,
+    policy: RetryPolicy,
+    subscriptions: Mutex<HashMap<String, Subscription>>,
+    state_path: Option<PathBuf>,
+    this: Weak<WebhookHub>,
+}
+
+impl WebhookHub {
+    /// Hub keeping subscriptions in memory only
+    pub fn in_memory(outputs: Arc<dyn OutputSource>, policy: RetryPolicy) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            outputs,
+            client: reqwest::Client::new(),
+            policy,
+            subscriptions: Mutex::new(HashMap::new()),
+            state_path: None,
+            this: this.clone(),
+        })
+    }
+
+    /// Hub persisting subscriptions to `state_path`. An unreadable state
+    /// file is logged and replaced.
+    pub fn open(
+        state_path: impl Into<PathBuf>,
+        outputs: Arc<dyn OutputSource>,
+        policy: RetryPolicy,
+    ) -> Arc<Self> {
+        let state_path = state_path.into();
+        let records = load_webhooks(&state_path).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable webhook state {:?}: {}", state_path, e);
+            Vec::new()
+        });
+        Arc::new_cyclic(|this| Self {
+            outputs,
+            client: reqwest::Client::new(),
+            policy,
+            subscriptions: Mutex::new(
+                records
+                    .into_iter()
+                    .map(|r| (r.id.clone(), Subscription::new(r)))
+                    .collect(),
+            ),
+            state_path: Some(state_path),
+            this: this.clone(),
+        })
+    }
+
+    /// Register a subscription
+    pub fn create(&self, spec: WebhookSpec) -> Result<WebhookCreated, WebhookError> {
+        let url = reqwest::Url::parse(&spec.url)
+            .map_err(|e| WebhookError::InvalidUrl(format!("{}: {}", spec.url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(WebhookError::InvalidUrl(format!(
+                "{}: scheme must be http or https",
+                spec.url
+            )));
+        }
+        if spec.filters.is_empty() {
+            return Err(WebhookError::NoFilters);
+        }
+        let rate_limit_per_minute = spec
+            .rate_limit_per_minute
+            .unwrap_or(DEFAULT_RATE_LIMIT_PER_MINUTE);
+        if rate_limit_per_minute == 0 {
+            return Err(WebhookError::InvalidLimit(
+                "rate_limit_per_minute must be positive".to_string(),
+            ));
+        }
+        let max_payload_bytes = spec.max_payload_bytes.unwrap_or(DEFAULT_MAX_PAYLOAD_BYTES);
+        if max_payload_bytes == 0 || max_payload_bytes > MAX_PAYLOAD_BYTES_LIMIT {
+            return Err(WebhookError::InvalidLimit(format!(
+                "max_payload_bytes must be between 1 and {}",
+                MAX_PAYLOAD_BYTES_LIMIT
+            )));
+        }
+
+        let mut subscriptions = self.subscriptions.lock();
+        if subscriptions.len() >= MAX_WEBHOOKS {
+            return Err(WebhookError::TooMany(MAX_WEBHOOKS));
+        }
+        let record = WebhookRecord {
+            id: random_hex(8),
+            url: url.to_string(),
+            secret: random_hex(32),
+            filters: spec.filters,
+            rate_limit_per_minute,
+            max_payload_bytes,
+            enabled: true,
+            disabled_reason: None,
+            next_seq: 1,
+            created_at: now_secs(),
+        };
+        let secret = record.secret.clone();
+        let subscription = Subscription::new(record);
+        let webhook = subscription.info();
+        subscriptions.insert(webhook.id.clone(), subscription);
+        self.persist(&subscriptions);
+        Ok(WebhookCreated { webhook, secret })
+    }
+
+    pub fn list(&self) -> Vec<WebhookInfo> {
+        let mut list: Vec<WebhookInfo> = self
+            .subscriptions
+            .lock()
+            .values()
+            .map(Subscription::info)
+            .collect();
+        list.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        list
+    }
+
+    pub fn get(&self, id: &str) -> Result<WebhookInfo, WebhookError> {
+        self.subscriptions
+            .lock()
+            .get(id)
+            .map(Subscription::info)
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))
+    }
+
+    /// Remove a subscription. A delivery in flight finishes its current
+    /// attempt; nothing further is sent.
+    pub fn delete(&self, id: &str) -> Result<(), WebhookError> {
+        let mut subscriptions = self.subscriptions.lock();
+        subscriptions
+            .remove(id)
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        self.persist(&subscriptions);
+        Ok(())
+    }
+
+    /// Re-enable a disabled subscription and reset its failure count
+    pub fn enable(&self, id: &str) -> Result<WebhookInfo, WebhookError> {
+        let mut subscriptions = self.subscriptions.lock();
+        let subscription = subscriptions
+            .get_mut(id)
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        subscription.record.enabled = true;
+        subscription.record.disabled_reason = None;
+        subscription.consecutive_failures = 0;
+        let info = subscription.info();
+        self.persist(&subscriptions);
+        Ok(info)
+    }
+
+    /// Delivery log of a subscription, newest first, optionally restricted
+    /// to one status
+    pub fn deliveries(
+        &self,
+        id: &str,
+        status: Option<DeliveryStatus>,
+    ) -> Result<Vec<DeliveryRecord>, WebhookError> {
+        let subscriptions = self.subscriptions.lock();
+        let subscription = subscriptions
+            .get(id)
+            .ok_or_else(|| WebhookError::NotFound(id.to_string()))?;
+        Ok(subscription
+            .deliveries
+            .iter()
+            .rev()
+            .filter(|d| status.map_or(true, |s| d.status == s))
+            .cloned()
+            .collect())
+    }
+
+    /// Route a node event to subscriptions
+    pub fn process_event(&self, event: &NodeEvent) {
+        match event {
+            NodeEvent::BlockConnected(block) => self.on_block_connected(block),
+            NodeEvent::BlockDisconnected(block) => self.publish_to_all(&WebhookEvent::Reorg {
+                height: block.height(),
+                hash: hex::encode(block.hash()),
+            }),
+            _ => {}
+        }
+    }
+
+    fn on_block_connected(&self, block: &Block) {
+        self.publish_to_all(&WebhookEvent::NewBlock {
+            height: block.height(),
+            hash: hex::encode(block.hash()),
+            prev_hash: hex::encode(block.prev_block_hash()),
+            timestamp: block.timestamp(),
+            tx_count: block.transactions().len(),
+        });
+
+        let mut subscriptions = self.subscriptions.lock();
+        if !subscriptions
+            .values()
+            .any(|s| s.record.enabled && !s.watched.is_empty())
+        {
+            return;
+        }
+        let height = block.height();
+        let block_hash = hex::encode(block.hash());
+        let mut advanced = false;
+        for tx in block.transactions().iter().filter(|tx| !tx.is_coinbase()) {
+            let flows = TransactionFlows::of(tx, self.outputs.as_ref());
+            for subscription in subscriptions.values_mut() {
+                if !subscription.record.enabled {
+                    continue;
+                }
+                let Some(event) =
+                    flows.match_filters(&subscription.watched, tx, height, &block_hash)
+                else {
+                    continue;
+                };
+                self.enqueue(subscription, event);
+                advanced = true;
+            }
+        }
+        if advanced {
+            self.persist(&subscriptions);
+        }
+    }
+
+    fn publish_to_all(&self, event: &WebhookEvent) {
+        let mut subscriptions = self.subscriptions.lock();
+        let mut advanced = false;
+        for subscription in subscriptions.values_mut() {
+            if subscription.record.enabled && subscription.wants(event.kind()) {
+                self.enqueue(subscription, event.clone());
+                advanced = true;
+            }
+        }
+        if advanced {
+            self.persist(&subscriptions);
+        }
+    }
+
+    /// Number the event and queue it, or record why it was dropped
+    fn enqueue(&self, subscription: &mut Subscription, event: WebhookEvent) {
+        let seq = subscription.record.next_seq;
+        subscription.record.next_seq += 1;
+        let created_at = now_secs();
+        let mut record = DeliveryRecord {
+            seq,
+            event: event.kind().to_string(),
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_status_code: None,
+            last_error: None,
+            created_at,
+            completed_at: None,
+        };
+
+        let payload = WebhookPayload {
+            webhook_id: subscription.record.id.clone(),
+            seq,
+            created_at,
+            event,
+        };
+        let dropped = match to_canonical_vec(&payload) {
+            Err(e) => Err(format!("payload encoding failed: {}", e)),
+            Ok(body) if body.len() > subscription.record.max_payload_bytes => Err(format!(
+                "payload of {} bytes exceeds the {} byte cap",
+                body.len(),
+                subscription.record.max_payload_bytes
+            )),
+            Ok(_) if !subscription.bucket.try_take(Instant::now()) => Err(format!(
+                "rate limit of {} per minute exceeded",
+                subscription.record.rate_limit_per_minute
+            )),
+            Ok(body) => match self.sender(subscription) {
+                Some(queue) => queue
+                    .send(QueuedDelivery { seq, body })
+                    .map_err(|_| "delivery worker stopped".to_string()),
+                None => Err("no async runtime to deliver on".to_string()),
+            },
+        };
+        if let Err(reason) = dropped {
+            record.status = DeliveryStatus::Dropped;
+            record.last_error = Some(reason);
+            record.completed_at = Some(created_at);
+        }
+        subscription.log(record);
+    }
+
+    /// Queue of the subscription's delivery worker, started on first use
+    fn sender(
+        &self,
+        subscription: &mut Subscription,
+    ) -> Option<mpsc::UnboundedSender<QueuedDelivery>> {
+        if let Some(queue) = subscription.queue.as_ref().filter(|q| !q.is_closed()) {
+            return Some(queue.clone());
+        }
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        let (queue, rx) = mpsc::unbounded_channel();
+        runtime.spawn(deliver_loop(
+            self.this.clone(),
+            subscription.record.id.clone(),
+            rx,
+        ));
+        subscription.queue = Some(queue.clone());
+        Some(queue)
+    }
+
+    /// Send one queued event, retrying with backoff
+    async fn deliver(&self, id: &str, delivery: QueuedDelivery) {
+        let mut attempt = 0;
+        loop {
+            let (url, secret) = {
+                let mut subscriptions = self.subscriptions.lock();
+                let Some(subscription) = subscriptions.get_mut(id) else {
+                    return;
+                };
+                if !subscription.record.enabled {
+                    let reason = subscription.record.disabled_reason.clone();
+                    if let Some(record) = subscription.delivery_mut(delivery.seq) {
+                        record.status = DeliveryStatus::Dropped;
+                        record.last_error =
+                            reason.or_else(|| Some("subscription disabled".to_string()));
+                        record.completed_at = Some(now_secs());
+                    }
+                    return;
+                }
+                (
+                    subscription.record.url.clone(),
+                    subscription.record.secret.clone(),
+                )
+            };
+
+            attempt += 1;
+            let result = self
+                .client
+                .post(&url)
+                .timeout(self.policy.timeout)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(WEBHOOK_ID_HEADER, id)
+                .header(SEQUENCE_HEADER, delivery.seq.to_string())
+                .header(SIGNATURE_HEADER, sign_payload(&secret, &delivery.body))
+                .body(delivery.body.clone())
+                .send()
+                .await;
+            let (status_code, error) = match result {
+                Ok(response) if response.status().is_success() => {
+                    (Some(response.status().as_u16()), None)
+                }
+                Ok(response) => (
+                    Some(response.status().as_u16()),
+                    Some(format!("HTTP {}", response.status())),
+                ),
+                Err(e) => (e.status().map(|s| s.as_u16()), Some(e.to_string())),
+            };
+            let delivered = error.is_none();
+            let exhausted = !delivered && attempt >= self.policy.max_attempts;
+
+            {
+                let mut subscriptions = self.subscriptions.lock();
+                let Some(subscription) = subscriptions.get_mut(id) else {
+                    return;
+                };
+                if let Some(record) = subscription.delivery_mut(delivery.seq) {
+                    record.attempts = attempt;
+                    record.last_status_code = status_code;
+                    record.last_error = error.clone();
+                    if delivered || exhausted {
+                        record.status = if delivered {
+                            DeliveryStatus::Delivered
+                        } else {
+                            DeliveryStatus::Failed
+                        };
+                        record.completed_at = Some(now_secs());
+                    }
+                }
+                if delivered {
+                    subscription.consecutive_failures = 0;
+                    return;
+                }
+                if exhausted {
+                    subscription.consecutive_failures += 1;
+                    if subscription.consecutive_failures >= self.policy.disable_after {
+                        let reason = format!(
+                            "disabled after {} consecutive failed deliveries",
+                            subscription.consecutive_failures
+                        );
+                        warn!("Webhook {} {}", id, reason);
+                        subscription.record.enabled = false;
+                        subscription.record.disabled_reason = Some(reason);
+                        self.persist(&subscriptions);
+                    }
+                    return;
+                }
+            }
+
+            tokio::time::sleep(self.policy.backoff(attempt)).await;
+        }
+    }
+
+    /// Feed the hub from the node event bus until the bus closes
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<NodeEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.process_event(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Webhooks lagged; {} node events skipped", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn persist(&self, subscriptions: &HashMap<String, Subscription>) {
+        if let Some(path) = &self.state_path {
+            persist_webhooks(subscriptions, path);
+        }
+    }
+}
+
+async fn deliver_loop(
+    hub: Weak<WebhookHub>,
+    id: String,
+    mut rx: mpsc::UnboundedReceiver<QueuedDelivery>,
+) {
+    while let Some(delivery) = rx.recv().await {
+        let Some(hub) = hub.upgrade() else { break };
+        hub.deliver(&id, delivery).await;
+    }
+}
+
+/// Value a transaction moves to and from each address
+struct TransactionFlows {
+    total_output: u64,
+    received: HashMap<String, u64>,
+    sent: HashMap<String, u64>,
+}
+
+impl TransactionFlows {
+    fn of(tx: &Transaction, outputs: &dyn OutputSource) -> Self {
+        let mut received: HashMap<String, u64> = HashMap::new();
+        for output in tx.outputs() {
+            if let Some(address) = TransactionIndexer::extract_address_from_output(output) {
+                let entry = received.entry(address).or_default();
+                *entry = entry.saturating_add(output.amount());
+            }
+        }
+        let mut sent: HashMap<String, u64> = HashMap::new();
+        for input in tx.inputs() {
+            if let Some((address, value)) =
+                outputs.output(&input.prev_tx_hash(), input.prev_output_index())
+            {
+                let entry = sent.entry(address).or_default();
+                *entry = entry.saturating_add(value);
+            }
+        }
+        Self {
+            total_output: tx
+                .outputs()
+                .iter()
+                .fold(0u64, |acc, o| acc.saturating_add(o.amount())),
+            received,
+            sent,
+        }
+    }
+
+    /// Event for the first `large_transaction` filter the transaction meets
+    fn match_filters(
+        &self,
+        filters: &[(u64, HashSet<String>)],
+        tx: &Transaction,
+        height: u64,
+        block_hash: &str,
+    ) -> Option<WebhookEvent> {
+        filters.iter().find_map(|(min_value, watched)| {
+            let sum = |flows: &HashMap<String, u64>| {
+                flows
+                    .iter()
+                    .filter(|(address, _)| watched.contains(*address))
+                    .fold(0u64, |acc, (_, v)| acc.saturating_add(*v))
+            };
+            let (received, sent, addresses) = if watched.is_empty() {
+                (self.total_output, 0, Vec::new())
+            } else {
+                let mut addresses: Vec<String> = self
+                    .received
+                    .keys()
+                    .chain(self.sent.keys())
+                    .filter(|a| watched.contains(*a))
+                    .cloned()
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .collect();
+                addresses.sort();
+                (sum(&self.received), sum(&self.sent), addresses)
+            };
+            (received.max(sent) >= *min_value).then(|| WebhookEvent::LargeTransaction {
+                txid: hex::encode(tx.hash()),
+                height,
+                block_hash: block_hash.to_string(),
+                total_output: self.total_output,
+                received,
+                sent,
+                addresses,
+            })
+        })
+    }
+}
+
+fn load_webhooks(path: &Path) -> Result<Vec<WebhookRecord>, String> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Write the state file via a temporary file and rename. The file holds
+/// signing secrets, so it is created owner-only on Unix.
+fn persist_webhooks(subscriptions: &HashMap<String, Subscription>, path: &Path) {
+    let records: Vec<&WebhookRecord> = subscriptions.values().map(|s| &s.record).collect();
+    let result = serde_json::to_vec_pretty(&records)
+        .map_err(std::io::Error::other)
+        .and_then(|bytes| {
+            let tmp = path.with_extension("json.tmp");
+            write_private(&tmp, &bytes)?;
+            std::fs::rename(&tmp, path)
+        });
+    if let Err(e) = result {
+        warn!("Failed to persist webhook state to {:?}: {}", path, e);
+    }
+}
+
+fn write_private(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(bytes)
+}
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+
+    const ALICE: &[u8] = &[0xa1; 25];
+    const BOB: &[u8] = &[0xb0; 25];
+
+    /// Previous outputs by outpoint
+    #[derive(Default)]
+    struct FakeOutputs(HashMap<([u8; 32], u32), (String, u64)>);
+
+    impl OutputSource for FakeOutputs {
+        fn output(&self, txid: &[u8; 32], vout: u32) -> Option<(String, u64)> {
+            self.0.get(&(*txid, vout)).cloned()
+        }
+    }
+
+    struct Received {
+        headers: HeaderMap,
+        body: Vec<u8>,
+    }
+
+    /// Local receiver answering 500 to the first `fail_first` requests
+    #[derive(Clone)]
+    struct Receiver {
+        requests: Arc<Mutex<Vec<Received>>>,
+        fail_first: Arc<AtomicUsize>,
+    }
+
+    async fn receive(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: axum::body::Bytes,
+    ) -> StatusCode {
+        receiver.requests.lock().push(Received {
+            headers,
+            body: body.to_vec(),
+        });
+        let failing = receiver
+            .fail_first
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if failing {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    async fn start_receiver(fail_first: usize) -> (String, Receiver) {
+        let receiver = Receiver {
+            requests: Arc::new(Mutex::new(Vec::new())),
+            fail_first: Arc::new(AtomicUsize::new(fail_first)),
+        };
+        let app = Router::new()
+            .route("/hook", post(receive))
+            .with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, receiver)
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            timeout: Duration::from_secs(5),
+            disable_after: 2,
+        }
+    }
+
+    fn block_at(height: u64, txs: Vec<Transaction>) -> Block {
+        Block::new(
+            BlockHeader::new_with_height(1, [height as u8; 32], [0; 32], 0, 0, 0, height),
+            txs,
+        )
+    }
+
+    fn spend(prev: [u8; 32], to: &[u8], value: u64) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new(prev, 0, vec![], 0)],
+            vec![TransactionOutput::new(value, to.to_vec())],
+            0,
+        )
+    }
+
+    async fn wait_for(
+        hub: &WebhookHub,
+        id: &str,
+        done: impl Fn(&[DeliveryRecord]) -> bool,
+    ) -> Vec<DeliveryRecord> {
+        for _ in 0..500 {
+            let deliveries = hub.deliveries(id, None).unwrap();
+            if done(&deliveries) {
+                return deliveries;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "deliveries did not settle: {:?}",
+            hub.deliveries(id, None).unwrap()
+        );
+    }
+
+    fn spec(url: &str, filters: Vec<WebhookFilter>) -> WebhookSpec {
+        WebhookSpec {
+            url: url.to_string(),
+            filters,
+            rate_limit_per_minute: None,
+            max_payload_bytes: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn deliveries_are_signed_and_keep_their_sequence_across_retries() {
+        let (url, receiver) = start_receiver(2).await;
+        let hub = WebhookHub::in_memory(Arc::new(FakeOutputs::default()), fast_policy());
+        let created = hub
+            .create(spec(
+                &url,
+                vec![WebhookFilter::NewBlock, WebhookFilter::Reorg],
+            ))
+            .unwrap();
+        let id = created.webhook.id.clone();
+
+        let first = block_at(1, vec![]);
+        hub.process_event(&NodeEvent::BlockConnected(first.clone()));
+        hub.process_event(&NodeEvent::BlockDisconnected(first));
+        hub.process_event(&NodeEvent::BlockConnected(block_at(2, vec![])));
+        let deliveries = wait_for(&hub, &id, |d| {
+            d.len() == 3 && d.iter().all(|r| r.status == DeliveryStatus::Delivered)
+        })
+        .await;
+        assert_eq!(
+            deliveries.iter().map(|d| d.seq).collect::<Vec<_>>(),
+            vec![3, 2, 1]
+        );
+        assert_eq!(deliveries[2].attempts, 3);
+
+        let requests = receiver.requests.lock();
+        assert_eq!(requests.len(), 5);
+        let mut seqs = Vec::new();
+        for request in requests.iter() {
+            let signature = request.headers[SIGNATURE_HEADER].to_str().unwrap();
+            assert!(verify_signature(&created.secret, &request.body, signature));
+            assert!(!verify_signature("wrong secret", &request.body, signature));
+            let payload: WebhookPayload = serde_json::from_slice(&request.body).unwrap();
+            assert_eq!(payload.webhook_id, id);
+            assert_eq!(
+                request.headers[SEQUENCE_HEADER].to_str().unwrap(),
+                payload.seq.to_string()
+            );
+            assert_eq!(request.body, to_canonical_vec(&payload).unwrap());
+            seqs.push(payload.seq);
+        }
+        // Two failed attempts and the retry that succeeded carry seq 1
+        assert_eq!(seqs, vec![1, 1, 1, 2, 3]);
+
+        let kinds: Vec<String> = requests[2..]
+            .iter()
+            .map(|r| {
+                serde_json::from_slice::<WebhookPayload>(&r.body)
+                    .unwrap()
+                    .event
+                    .kind()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(kinds, vec!["new_block", "reorg", "new_block"]);
+    }
+
+    #[tokio::test]
+    async fn large_transaction_filter_matches_watched_flows_above_threshold() {
+        let (url, receiver) = start_receiver(0).await;
+        let funding = [7u8; 32];
+        let mut outputs = FakeOutputs::default();
+        outputs.0.insert((funding, 0), (hex::encode(ALICE), 9_000));
+        let hub = WebhookHub::in_memory(Arc::new(outputs), fast_policy());
+        let filter = WebhookFilter::LargeTransaction {
+            min_value: 5_000,
+            addresses: vec![hex::encode(ALICE)],
+        };
+        let id = hub.create(spec(&url, vec![filter])).unwrap().webhook.id;
+
+        let small_to_alice = spend([1; 32], ALICE, 4_999);
+        let large_to_bob = spend([2; 32], BOB, 50_000);
+        let large_to_alice = spend([3; 32], ALICE, 5_000);
+        let alice_spends = spend(funding, BOB, 8_000);
+        let block = block_at(
+            10,
+            vec![
+                small_to_alice,
+                large_to_bob,
+                large_to_alice.clone(),
+                alice_spends.clone(),
+            ],
+        );
+        hub.process_event(&NodeEvent::BlockConnected(block));
+        wait_for(&hub, &id, |d| {
+            d.len() == 2 && d.iter().all(|r| r.status == DeliveryStatus::Delivered)
+        })
+        .await;
+
+        let events: Vec<WebhookEvent> = receiver
+            .requests
+            .lock()
+            .iter()
+            .map(|r| {
+                serde_json::from_slice::<WebhookPayload>(&r.body)
+                    .unwrap()
+                    .event
+            })
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            &events[0],
+            WebhookEvent::LargeTransaction { txid, received: 5_000, sent: 0, .. }
+                if *txid == hex::encode(large_to_alice.hash())
+        ));
+        assert!(matches!(
+            &events[1],
+            WebhookEvent::LargeTransaction { txid, received: 0, sent: 9_000, addresses, .. }
+                if *txid == hex::encode(alice_spends.hash()) && *addresses == vec![hex::encode(ALICE)]
+        ));
+    }
+
+    #[tokio::test]
+    async fn persistent_failures_disable_the_subscription() {
+        let (url, receiver) = start_receiver(usize::MAX).await;
+        let hub = WebhookHub::in_memory(Arc::new(FakeOutputs::default()), fast_policy());
+        let id = hub
+            .create(spec(&url, vec![WebhookFilter::NewBlock]))
+            .unwrap()
+            .webhook
+            .id;
+
+        for height in 1..=3 {
+            hub.process_event(&NodeEvent::BlockConnected(block_at(height, vec![])));
+        }
+        let deliveries = wait_for(&hub, &id, |d| {
+            d.iter().all(|r| r.status != DeliveryStatus::Pending)
+        })
+        .await;
+
+        let info = hub.get(&id).unwrap();
+        assert!(!info.enabled);
+        assert!(info.disabled_reason.is_some());
+        let failed = hub.deliveries(&id, Some(DeliveryStatus::Failed)).unwrap();
+        assert_eq!(failed.iter().map(|d| d.seq).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(failed
+            .iter()
+            .all(|d| d.attempts == 3 && d.last_status_code == Some(500)));
+        // The third event was queued before the subscription gave up
+        assert_eq!(deliveries[0].status, DeliveryStatus::Dropped);
+        assert_eq!(receiver.requests.lock().len(), 6);
+
+        // Disabled subscriptions receive nothing new
+        hub.process_event(&NodeEvent::BlockConnected(block_at(4, vec![])));
+        assert_eq!(hub.deliveries(&id, None).unwrap().len(), 3);
+
+        let info = hub.enable(&id).unwrap();
+        assert!(info.enabled);
+        assert_eq!(info.consecutive_failures, 0);
+        assert_eq!(info.next_seq, 4);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_and_payload_cap_drop_events() {
+        let (url, receiver) = start_receiver(0).await;
+        let hub = WebhookHub::in_memory(Arc::new(FakeOutputs::default()), fast_policy());
+        let mut limited = spec(&url, vec![WebhookFilter::NewBlock]);
+        limited.rate_limit_per_minute = Some(1);
+        let limited = hub.create(limited).unwrap().webhook.id;
+        let mut capped = spec(&url, vec![WebhookFilter::NewBlock]);
+        capped.max_payload_bytes = Some(64);
+        let capped = hub.create(capped).unwrap().webhook.id;
+
+        hub.process_event(&NodeEvent::BlockConnected(block_at(1, vec![])));
+        hub.process_event(&NodeEvent::BlockConnected(block_at(2, vec![])));
+        let deliveries = wait_for(&hub, &limited, |d| {
+            d.iter().all(|r| r.status != DeliveryStatus::Pending)
+        })
+        .await;
+        assert_eq!(
+            deliveries
+                .iter()
+                .map(|d| (d.seq, d.status))
+                .collect::<Vec<_>>(),
+            vec![(2, DeliveryStatus::Dropped), (1, DeliveryStatus::Delivered)]
+        );
+        let dropped = hub
+            .deliveries(&capped, Some(DeliveryStatus::Dropped))
+            .unwrap();
+        assert_eq!(dropped.len(), 2);
+        assert_eq!(receiver.requests.lock().len(), 1);
+
+        assert!(matches!(
+            hub.create(spec("ftp://example.com", vec![WebhookFilter::NewBlock])),
+            Err(WebhookError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            hub.create(spec(&url, vec![])),
+            Err(WebhookError::NoFilters)
+        ));
+    }
+
+    #[test]
+    fn subscriptions_survive_restart_without_their_delivery_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("webhooks.json");
+        let hub = WebhookHub::open(
+            &path,
+            Arc::new(FakeOutputs::default()),
+            RetryPolicy::default(),
+        );
+        let created = hub
+            .create(spec("https://example.com/hook", vec![WebhookFilter::Reorg]))
+            .unwrap();
+        // No runtime: the event is numbered and recorded as dropped
+        hub.process_event(&NodeEvent::BlockDisconnected(block_at(5, vec![])));
+        drop(hub);
+
+        let hub = WebhookHub::open(
+            &path,
+            Arc::new(FakeOutputs::default()),
+            RetryPolicy::default(),
+        );
+        let info = hub.get(&created.webhook.id).unwrap();
+        assert_eq!(
+            info,
+            WebhookInfo {
+                next_seq: 2,
+                ..created.webhook
+            }
+        );
+        assert!(hub.deliveries(&info.id, None).unwrap().is_empty());
+        hub.delete(&info.id).unwrap();
+        assert!(WebhookHub::open(
+            &path,
+            Arc::new(FakeOutputs::default()),
+            RetryPolicy::default()
+        )
+        .list()
+        .is_empty());
+    }
+}
@@ -3,18 +3,72 @@
 //! This module provides a thread-safe wrapper around the Node that can be safely
 //! shared across threads in the API server.
 
+use crate::api::address_subscriptions::{AddressSubscriptionHub, ChainAddressData};
+use crate::api::event_subscriptions::EventSubscriptionHub;
+use crate::api::idempotency::IdempotencyStore;
+use crate::api::jobs::JobManager;
+use crate::api::usage::{QuotaPolicy, SystemClock, UsageMeter};
+use crate::api::webhooks::{RetryPolicy, WebhookHub};
 use crate::api::types::*;
+use crate::blockchain::invalidation::InvalidBlock;
+use crate::clock::{tip_timestamp, ClockMonitor, ClockStatus};
 use crate::environmental::EnvironmentalMonitor;
+use crate::events::{EventBus, NodeEvent};
 use crate::mempool::TransactionPool;
-use crate::network::NetworkProxy;
+use crate::mining::{BlockTemplate, TemplateAuditor, TemplateManager};
+#[cfg(feature = "stratum")]
+use crate::mining::stratum::StratumMetrics;
+use crate::metrics::rejections::RejectionTracker;
+use crate::network::identity_rotation::{self, IdentityAttestation};
+use crate::network::peer_identity::{KeyProtection, DEFAULT_IDENTITY_DIR};
+use crate::network::{
+    BlockServer, NetworkProxy, OperatorMessenger, SyncProgress, SyncProgressTracker,
+};
 use crate::node::{Node, NodeError};
-use crate::storage::{BlockchainDB, ChainState};
+use crate::storage::{BlockchainDB, ChainState, StorageError, TipChange};
 use crate::wallet_manager::WalletManager;
+use supernova_core::types::block::Block;
 use supernova_core::types::transaction::Transaction;
 use std::sync::Arc;
 use std::sync::RwLock as StdRwLock;
 use sysinfo::System;
 
+/// Operator chain-management operation
+#[derive(Debug, Clone, Copy)]
+pub enum ChainAdminOp {
+    /// Disconnect blocks down to a height and invalidate them
+    Rollback { height: u64, wipe_mempool: bool },
+    /// Invalidate a block (and roll back if it is on the active chain)
+    InvalidateBlock([u8; 32]),
+    /// Clear an invalidation and reactivate the chain if it has more work
+    ReconsiderBlock([u8; 32]),
+}
+
+impl std::fmt::Display for ChainAdminOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainAdminOp::Rollback { height, wipe_mempool } => {
+                write!(f, "rollback to height {} (wipe_mempool={})", height, wipe_mempool)
+            }
+            ChainAdminOp::InvalidateBlock(hash) => write!(f, "invalidate-block {}", hex::encode(hash)),
+            ChainAdminOp::ReconsiderBlock(hash) => write!(f, "reconsider-block {}", hex::encode(hash)),
+        }
+    }
+}
+
+/// Why a mined block was not connected
+#[derive(Debug, thiserror::Error)]
+pub enum BlockSubmitError {
+    /// Already known, or not built on the current tip; `reason` is the
+    /// BIP22 reject reason
+    #[error("{reason}")]
+    Stale { reason: &'static str, tip: [u8; 32] },
+    #[error("Failed to add block: {0}")]
+    Rejected(StorageError),
+    #[error(transparent)]
+    Node(#[from] NodeError),
+}
+
 /// Thread-safe API facade that wraps the Node
 pub struct ApiFacade {
     /// Configuration
@@ -25,6 +79,14 @@ pub struct ApiFacade {
     chain_state: Arc<StdRwLock<ChainState>>,
     /// Transaction mempool
     mempool: Arc<TransactionPool>,
+    /// Block validation rejection statistics
+    block_rejections: Arc<RejectionTracker>,
+    /// Sync progress tracker
+    sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+    /// Block and header serving to peers
+    block_server: Arc<BlockServer>,
+    /// Node event bus
+    events: EventBus,
     /// Network proxy (thread-safe)
     network: Arc<NetworkProxy>,
     /// Peer ID
@@ -37,6 +99,29 @@ pub struct ApiFacade {
     wallet_manager: Arc<StdRwLock<WalletManager>>,
     /// Environmental monitor providing real energy/carbon telemetry
     environmental: Arc<EnvironmentalMonitor>,
+    /// Long-running admin jobs
+    jobs: Arc<JobManager>,
+    /// Remembered responses for idempotent submissions
+    idempotency: Arc<IdempotencyStore>,
+    /// Watch-only address subscriptions fed from the event bus
+    address_subscriptions: Arc<AddressSubscriptionHub>,
+    /// Chain, mempool and peer event subscriptions fed from the event bus
+    event_subscriptions: Arc<EventSubscriptionHub>,
+    /// Operator-registered webhooks fed from the event bus
+    webhooks: Arc<WebhookHub>,
+    /// Per-API-key usage counters and quotas
+    usage: Arc<UsageMeter>,
+    /// Our block templates compared with blocks mined by others
+    template_audit: Arc<TemplateAuditor>,
+    /// Cached block template and `getblocktemplate` long polls
+    template_manager: Arc<TemplateManager>,
+    /// Share counters of stratum miners
+    #[cfg(feature = "stratum")]
+    stratum_metrics: Arc<StratumMetrics>,
+    /// Messages between node operators
+    operator_messages: Arc<OperatorMessenger>,
+    /// Local clock skew monitor
+    clock: Arc<ClockMonitor>,
 }
 
 // Ensure ApiFacade is Send + Sync. If this fails to compile, a newly added
@@ -88,17 +173,134 @@ impl ApiFacade {
             }
         };
 
+        let jobs = {
+            let cfg = node.config();
+            let cfg_guard = cfg.read().map_err(|_| {
+                NodeError::General("config lock poisoned".to_string())
+            })?;
+            let jobs = JobManager::open(cfg_guard.storage.db_path.join("jobs.json"));
+            jobs.register_storage_jobs(
+                node.db(),
+                crate::storage::SnapshotNetwork {
+                    chain_id: cfg_guard.node.chain_id.clone(),
+                    network_id: cfg_guard.network.network_id.clone(),
+                },
+            );
+            Arc::new(jobs)
+        };
+
+        let idempotency = {
+            let cfg = node.config();
+            let cfg_guard = cfg.read().map_err(|_| {
+                NodeError::General("config lock poisoned".to_string())
+            })?;
+            Arc::new(IdempotencyStore::new(
+                std::time::Duration::from_secs(cfg_guard.api.idempotency_retention_secs),
+                cfg_guard.api.idempotency_max_keys,
+            ))
+        };
+
+        // The node keeps no address index, so subscription snapshots carry
+        // no UTXO lists; live events work regardless.
+        let address_subscriptions = Arc::new(AddressSubscriptionHub::new(Arc::new(
+            ChainAddressData::new(node.db(), None),
+        )));
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(Arc::clone(&address_subscriptions).run(node.events().subscribe()));
+        }
+
+        let event_subscriptions = {
+            let cfg = node.config();
+            let cfg_guard = cfg.read().map_err(|_| {
+                NodeError::General("config lock poisoned".to_string())
+            })?;
+            Arc::new(EventSubscriptionHub::new(
+                node.mempool(),
+                cfg_guard.api.ws_backpressure,
+            ))
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(Arc::clone(&event_subscriptions).run(node.events().subscribe()));
+        }
+
+        let webhooks = {
+            let cfg = node.config();
+            let cfg_guard = cfg.read().map_err(|_| {
+                NodeError::General("config lock poisoned".to_string())
+            })?;
+            WebhookHub::open(
+                cfg_guard.storage.db_path.join("webhooks.json"),
+                node.db(),
+                RetryPolicy::default(),
+            )
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(Arc::clone(&webhooks).run(node.events().subscribe()));
+        }
+
+        let usage = {
+            let cfg = node.config();
+            let cfg_guard = cfg.read().map_err(|_| {
+                NodeError::General("config lock poisoned".to_string())
+            })?;
+            Arc::new(UsageMeter::open(
+                cfg_guard.storage.db_path.join("api_usage.json"),
+                QuotaPolicy {
+                    default_limit: cfg_guard.api.daily_request_quota,
+                    per_key: cfg_guard.api.api_key_quotas.clone(),
+                    reset_hour_utc: cfg_guard.api.quota_reset_hour_utc,
+                },
+                Arc::new(SystemClock),
+            ))
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(Arc::clone(&usage).run());
+        }
+
+        let template_audit = Arc::new(TemplateAuditor::new(node.mempool().rejections()));
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(Arc::clone(&template_audit).run(node.events().subscribe()));
+        }
+
+        let template_manager = {
+            let chain_state = node.chain_state();
+            let tip = chain_state
+                .read()
+                .map_err(|_| NodeError::General("chain state lock poisoned".to_string()))?
+                .get_best_block_hash();
+            Arc::new(TemplateManager::new(tip, node.mempool()))
+        };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(Arc::clone(&template_manager).run(node.events().subscribe()));
+        }
+
         Ok(Self {
             config: node.config(),
             db: node.db(),
             chain_state: node.chain_state(),
             mempool: node.mempool(),
+            block_rejections: node.block_rejections(),
+            sync_progress: node.sync_progress(),
+            block_server: node.block_server(),
+            events: node.events(),
             network: node.network_proxy(),
             peer_id: node.peer_id,
             start_time: node.start_time,
             lightning_manager: node.lightning(),
             wallet_manager,
             environmental: Arc::new(EnvironmentalMonitor::new()),
+            jobs,
+            idempotency,
+            address_subscriptions,
+            event_subscriptions,
+            webhooks,
+            usage,
+            template_audit,
+            template_manager,
+            #[cfg(feature = "stratum")]
+            stratum_metrics: Arc::new(StratumMetrics::new()),
+            operator_messages: node.operator_messages(),
+            clock: node.clock(),
         })
     }
 
@@ -107,16 +309,301 @@ impl ApiFacade {
         Arc::clone(&self.db)
     }
 
+    /// Get the long-running job manager
+    pub fn jobs(&self) -> Arc<JobManager> {
+        Arc::clone(&self.jobs)
+    }
+
+    /// Get the idempotency key store
+    pub fn idempotency(&self) -> Arc<IdempotencyStore> {
+        Arc::clone(&self.idempotency)
+    }
+
+    /// Get the watch-only address subscription hub
+    pub fn address_subscriptions(&self) -> Arc<AddressSubscriptionHub> {
+        Arc::clone(&self.address_subscriptions)
+    }
+
+    /// Get the chain, mempool and peer event subscription hub
+    pub fn event_subscriptions(&self) -> Arc<EventSubscriptionHub> {
+        Arc::clone(&self.event_subscriptions)
+    }
+
+    /// Get the webhook hub for an admin operation.
+    ///
+    /// Webhooks send chain data to arbitrary URLs, so managing them is gated
+    /// on API authentication and audited like `chain_admin`.
+    pub fn webhooks(&self, action: &str) -> Result<Arc<WebhookHub>, NodeError> {
+        let auth_enabled = self.config.read().map(|c| c.api.enable_auth).unwrap_or(false);
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin webhook {}: API authentication is disabled", action);
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: webhook {}", action);
+        Ok(Arc::clone(&self.webhooks))
+    }
+
+    /// Get storage for an admin UTXO integrity check.
+    ///
+    /// Integrity checks scan the UTXO set, so they are gated on API
+    /// authentication and audited like `chain_admin`.
+    pub fn utxo_integrity(&self, mode: &str) -> Result<Arc<BlockchainDB>, NodeError> {
+        let auth_enabled = self.config.read().map(|c| c.api.enable_auth).unwrap_or(false);
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin {} UTXO integrity check: API authentication is disabled", mode);
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: {} UTXO integrity check", mode);
+        Ok(Arc::clone(&self.db))
+    }
+
+    /// Get the job manager for an admin compaction.
+    ///
+    /// Compaction rewrites every tree, so it is gated on API authentication
+    /// and audited like `chain_admin`.
+    pub fn storage_compaction(&self) -> Result<Arc<JobManager>, NodeError> {
+        let auth_enabled = self.config.read().map(|c| c.api.enable_auth).unwrap_or(false);
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin compaction: API authentication is disabled");
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: storage compaction");
+        Ok(Arc::clone(&self.jobs))
+    }
+
+    /// Get the API usage meter
+    pub fn usage_meter(&self) -> Arc<UsageMeter> {
+        Arc::clone(&self.usage)
+    }
+
+    /// Get the API usage meter for an admin query of other keys' usage.
+    ///
+    /// Gated on API authentication like the other admin operations; callers
+    /// reading their own key's usage use [`ApiFacade::usage_meter`].
+    pub fn api_key_usage(&self, action: &str) -> Result<Arc<UsageMeter>, NodeError> {
+        let auth_enabled = self.config.read().map(|c| c.api.enable_auth).unwrap_or(false);
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin API key usage {}: API authentication is disabled", action);
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: API key usage {}", action);
+        Ok(Arc::clone(&self.usage))
+    }
+
+    /// Get the operator messenger for an admin operation.
+    ///
+    /// Operator messages are private to the node's operator, so reading and
+    /// sending them is gated on API authentication and audited like
+    /// `chain_admin`.
+    pub fn operator_messages(&self, action: &str) -> Result<Arc<OperatorMessenger>, NodeError> {
+        let auth_enabled = self.config.read().map(|c| c.api.enable_auth).unwrap_or(false);
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin operator message {}: API authentication is disabled", action);
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: operator message {}", action);
+        Ok(Arc::clone(&self.operator_messages))
+    }
+
+    /// Get the template auditor
+    pub fn template_audit(&self) -> Arc<TemplateAuditor> {
+        Arc::clone(&self.template_audit)
+    }
+
+    /// Get the block template manager
+    pub fn template_manager(&self) -> Arc<TemplateManager> {
+        Arc::clone(&self.template_manager)
+    }
+
+    /// Get the stratum share counters
+    #[cfg(feature = "stratum")]
+    pub fn stratum_metrics(&self) -> Arc<StratumMetrics> {
+        Arc::clone(&self.stratum_metrics)
+    }
+
+    /// Current block template and its `longpollid`, rebuilt when the tip or
+    /// mempool has changed since the cached one
+    pub fn block_template(&self) -> Result<(Arc<BlockTemplate>, String), NodeError> {
+        self.template_manager
+            .template(|| self.build_block_template())
+    }
+
+    /// Build a fresh template paying the node wallet, and record it for the
+    /// template audit
+    fn build_block_template(&self) -> Result<BlockTemplate, NodeError> {
+        let mining_config = self
+            .config
+            .read()
+            .map(|c| c.mining.clone())
+            .map_err(|_| NodeError::General("Config lock poisoned".to_string()))?;
+
+        let (reward_addr, treasury_addr) = {
+            let wallet = self
+                .wallet_manager
+                .read()
+                .map_err(|_| NodeError::General("Wallet lock poisoned".to_string()))?;
+            let address = |label: &str| {
+                let address = wallet
+                    .generate_new_address(Some(label.to_string()))
+                    .map_err(|e| {
+                        NodeError::General(format!("Failed to generate {} address: {}", label, e))
+                    })?;
+                wallet::quantum_wallet::Address::from_str(&address)
+                    .map_err(|e| NodeError::General(format!("Invalid {} address: {}", label, e)))
+            };
+            (address("mining_reward")?, address("environmental_treasury")?)
+        };
+
+        let template = BlockTemplate::generate_with_payout(
+            Arc::clone(&self.chain_state),
+            Arc::clone(&self.mempool),
+            &mining_config,
+            &reward_addr,
+            &treasury_addr,
+        )
+        .map_err(|e| NodeError::General(format!("Failed to generate template: {}", e)))?;
+
+        // Snapshot the template so blocks mined by others can be audited against it
+        let selected: Vec<([u8; 32], u64)> = template
+            .transactions
+            .iter()
+            .skip(1)
+            .map(|tx| {
+                let txid = tx.hash();
+                (txid, self.mempool.get_transaction_fee(&txid).unwrap_or(0))
+            })
+            .collect();
+        let candidates = self
+            .mempool
+            .get_transactions_with_fee_rates()
+            .into_iter()
+            .map(|(tx, fee_rate, size)| (tx.hash(), fee_rate.saturating_mul(size as u64)));
+        self.template_audit
+            .record_template(template.height, selected, candidates);
+
+        Ok(template)
+    }
+
+    /// Connect a block mined on one of our templates and announce it. Every
+    /// mined block goes through here, whether from `generate`, `submitblock`
+    /// or a stratum worker; callers check proof of work first.
+    pub async fn submit_mined_block(&self, block: &Block) -> Result<(), BlockSubmitError> {
+        {
+            let chain = self
+                .chain_state
+                .read()
+                .map_err(|e| StorageError::LockPoisoned(e.to_string()))
+                .map_err(NodeError::from)?;
+            if let Some(reason) = crate::mining::template_manager::stale_reason(&chain, block) {
+                return Err(BlockSubmitError::Stale {
+                    reason,
+                    tip: chain.get_best_block_hash(),
+                });
+            }
+        }
+
+        // Our own block is not audited against our templates
+        self.template_audit.record_own_block(block.hash());
+
+        // The chain state write lock is taken on a blocking thread because
+        // add_block is async (same pattern as `chain_admin`)
+        let chain_state = Arc::clone(&self.chain_state);
+        let block_for_add = block.clone();
+        // Clippy's await_holding_lock lint cannot see through spawn_blocking.
+        #[allow(clippy::await_holding_lock)]
+        let added = tokio::task::spawn_blocking(move || {
+            tokio::runtime::Handle::current().block_on(async move {
+                match chain_state.write() {
+                    Ok(mut chain) => chain.add_block(&block_for_add).await,
+                    Err(e) => Err(StorageError::LockPoisoned(e.to_string())),
+                }
+            })
+        })
+        .await
+        .map_err(|e| NodeError::General(format!("Block processing task failed: {}", e)))?;
+        added.map_err(BlockSubmitError::Rejected)?;
+
+        if let Ok(wallet) = self.wallet_manager.write() {
+            if let Err(e) = wallet.scan_block(block) {
+                tracing::warn!("Failed to scan block for wallet: {}", e);
+            }
+        }
+        self.db.insert_block(block).map_err(NodeError::from)?;
+
+        // Confirmed transactions leave the mempool; subscribers and long
+        // polls see the new tip
+        Node::remove_confirmed(&self.mempool, &self.events, block);
+        let _ = self.events.send(NodeEvent::BlockConnected(block.clone()));
+
+        let block_hash = block.hash();
+        tracing::info!(
+            "Accepted mined block {} at height {}",
+            hex::encode(&block_hash[..8]),
+            block.height()
+        );
+        self.network.broadcast_block(block);
+        Ok(())
+    }
+
+    /// Local clock skew against peers and the tip
+    pub fn clock_status(&self) -> ClockStatus {
+        self.clock.status(tip_timestamp(&self.chain_state))
+    }
+
+    /// Refuse to build blocks or sign while the local clock is critically off
+    pub fn ensure_clock_sane(&self) -> Result<(), NodeError> {
+        self.clock
+            .ensure_sane(tip_timestamp(&self.chain_state))
+            .map_err(|e| NodeError::General(e.to_string()))
+    }
+
     /// Get chain state
     pub fn chain_state(&self) -> Arc<StdRwLock<ChainState>> {
         Arc::clone(&self.chain_state)
     }
 
+    /// Lowest block height still stored when the node runs pruned, or
+    /// `None` when it keeps every block
+    pub fn prune_height(&self) -> Option<u64> {
+        let prune_mode = self
+            .chain_state
+            .read()
+            .map(|chain| chain.prune_target().is_some())
+            .unwrap_or(false);
+        let height = self.db.pruned_height().unwrap_or(0);
+        (prune_mode || height > 0).then_some(height)
+    }
+
     /// Get mempool
     pub fn mempool(&self) -> Arc<TransactionPool> {
         Arc::clone(&self.mempool)
     }
 
+    /// Get block validation rejection statistics
+    pub fn block_rejections(&self) -> Arc<RejectionTracker> {
+        Arc::clone(&self.block_rejections)
+    }
+
+    /// Latest sync progress snapshot, if the node has sampled one yet
+    pub fn sync_progress(&self) -> Option<SyncProgress> {
+        self.sync_progress.lock().latest().cloned()
+    }
+
+    /// Get the node event bus
+    pub fn events(&self) -> EventBus {
+        self.events.clone()
+    }
+
     /// Get config
     pub fn config(&self) -> Arc<StdRwLock<crate::config::NodeConfig>> {
         Arc::clone(&self.config)
@@ -211,6 +698,9 @@ impl ApiFacade {
             hashrate: if is_mining { hashrate / 1_000_000 } else { 0 }, // Convert to MH/s
             difficulty,
             network_hashrate: network_hashrate / 1_000_000, // Convert to MH/s
+            sync: self.sync_progress(),
+            clock: self.clock_status(),
+            utxo_commitment: self.db.utxo_commitment().ok().map(hex::encode),
         }
     }
 
@@ -306,16 +796,17 @@ impl ApiFacade {
             cpu_usage,
             memory_usage,
             disk_usage,
+            block_serving: self.block_server.stats(),
         })
     }
 
-    /// Get config
+    /// Get config, including the effective relay policy
     pub fn get_config(&self) -> Result<serde_json::Value, NodeError> {
         let config = self
             .config
             .read()
             .map_err(|e| NodeError::ConfigError(format!("Config lock poisoned: {}", e)))?;
-        serde_json::to_value(&*config).map_err(|e| NodeError::ConfigError(e.to_string()))
+        config_to_json(&config)
     }
 
     /// Update config
@@ -336,7 +827,7 @@ impl ApiFacade {
             .map_err(|e| NodeError::ConfigError(format!("Config lock poisoned: {}", e)))?;
         *config = updated_config;
 
-        serde_json::to_value(&*config).map_err(|e| NodeError::ConfigError(e.to_string()))
+        config_to_json(&config)
     }
 
     /// Create backup
@@ -422,6 +913,143 @@ impl ApiFacade {
         crate::shutdown::request_admin_shutdown(false).map_err(NodeError::General)
     }
 
+    /// Start rotating the node's P2P identity.
+    ///
+    /// Generates the next keypair and the old→new attestation, then
+    /// announces it right away; the node keeps answering under the current
+    /// identity until `network.identity_rotation_grace` has passed and
+    /// switches on the next restart after that. Gated and audited like
+    /// `chain_admin`.
+    pub async fn rotate_identity(&self) -> Result<IdentityAttestation, NodeError> {
+        let (auth_enabled, grace) = self
+            .config
+            .read()
+            .map(|c| (c.api.enable_auth, c.network.identity_rotation_grace))
+            .map_err(|_| NodeError::General("Config lock poisoned".to_string()))?;
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin rotate-identity: API authentication is disabled");
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: rotate-identity (grace {}s)", grace.as_secs());
+        if let Err(e) = self.ensure_clock_sane() {
+            tracing::warn!(target: "audit", "Refused admin rotate-identity: {}", e);
+            return Err(e);
+        }
+
+        // Key derivation for an encrypted identity is deliberately slow
+        let result = tokio::task::spawn_blocking(move || {
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            identity_rotation::rotate_identity(
+                std::path::Path::new(DEFAULT_IDENTITY_DIR),
+                &KeyProtection::from_env(),
+                grace,
+                now,
+            )
+        })
+        .await
+        .map_err(|e| NodeError::General(format!("Task join error during identity rotation: {}", e)))?;
+
+        let attestation = match result {
+            Ok(attestation) => attestation,
+            Err(e) => {
+                tracing::warn!(target: "audit", "Admin rotate-identity failed: {}", e);
+                return Err(NodeError::General(format!("Identity rotation failed: {}", e)));
+            }
+        };
+        tracing::warn!(
+            target: "audit",
+            "Admin rotate-identity completed: {} -> {}, grace until {}",
+            attestation.link.old_peer_id,
+            attestation.link.new_peer_id,
+            attestation.link.grace_until
+        );
+
+        // Later announcements come from the node's periodic announcer
+        match attestation.to_message() {
+            Ok(message) => {
+                if let Err(e) = self.network.broadcast_message(message).await {
+                    tracing::warn!("Failed to announce identity rotation: {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to encode identity rotation: {}", e),
+        }
+        Ok(attestation)
+    }
+
+    /// Run an operator chain-management operation.
+    ///
+    /// Only allowed when API authentication is enabled. Every request and
+    /// its outcome is logged to the `audit` target. The chain state write
+    /// lock is taken on a blocking thread because the reorg path is async
+    /// (same pattern as block submission in `node.rs`).
+    pub async fn chain_admin(&self, op: ChainAdminOp) -> Result<TipChange, NodeError> {
+        let auth_enabled = self.config.read().map(|c| c.api.enable_auth).unwrap_or(false);
+        if !auth_enabled {
+            tracing::warn!(target: "audit", "Refused admin {}: API authentication is disabled", op);
+            return Err(NodeError::ConfigError(
+                "Admin operations require API authentication to be enabled".to_string(),
+            ));
+        }
+        tracing::warn!(target: "audit", "Admin request: {}", op);
+
+        let result = self.run_chain_admin(op).await;
+        match &result {
+            Ok(change) => tracing::warn!(
+                target: "audit",
+                "Admin {} completed: height {} -> {}, tip {} -> {}, {} blocks affected",
+                op,
+                change.old_height,
+                change.new_height,
+                hex::encode(change.old_tip),
+                hex::encode(change.new_tip),
+                change.affected_blocks.len()
+            ),
+            Err(e) => tracing::warn!(target: "audit", "Admin {} failed: {}", op, e),
+        }
+        result
+    }
+
+    async fn run_chain_admin(&self, op: ChainAdminOp) -> Result<TipChange, NodeError> {
+        let chain_state = Arc::clone(&self.chain_state);
+        // Clippy's await_holding_lock lint cannot see through spawn_blocking.
+        #[allow(clippy::await_holding_lock)]
+        let result = tokio::task::spawn_blocking(move || {
+            tokio::runtime::Handle::current().block_on(async move {
+                let mut chain = chain_state
+                    .write()
+                    .map_err(|e| StorageError::LockPoisoned(e.to_string()))?;
+                match op {
+                    ChainAdminOp::Rollback { height, .. } => chain.rollback_to(height).await,
+                    ChainAdminOp::InvalidateBlock(hash) => chain.invalidate_block(&hash).await,
+                    ChainAdminOp::ReconsiderBlock(hash) => chain.reconsider_block(&hash).await,
+                }
+            })
+        })
+        .await
+        .map_err(|e| NodeError::General(format!("Task join error during chain admin: {}", e)))?;
+        let change = result?;
+
+        // Tell subscribers which blocks left the active chain, tip first
+        if !matches!(op, ChainAdminOp::ReconsiderBlock(_)) {
+            let mut disconnected: Vec<Block> = change
+                .affected_blocks
+                .iter()
+                .filter_map(|hash| self.db.get_block(hash).ok().flatten())
+                .collect();
+            disconnected.sort_by_key(|block| std::cmp::Reverse(block.height()));
+            for block in disconnected {
+                let _ = self.events.send(NodeEvent::BlockDisconnected(block));
+            }
+        }
+
+        if let ChainAdminOp::Rollback { wipe_mempool: true, .. } = op {
+            self.mempool.clear_all()?;
+        }
+        Ok(change)
+    }
+
     /// Get debug info
     pub fn get_debug_info(&self) -> Result<DebugInfo, NodeError> {
         // Get node info
@@ -520,6 +1148,15 @@ impl ApiFacade {
         })
     }
 
+    /// Blocks the node refuses without revalidating, most recent first
+    pub fn get_invalid_blocks(&self) -> Result<Vec<InvalidBlock>, NodeError> {
+        let state = self
+            .chain_state
+            .read()
+            .map_err(|_| NodeError::General("chain state lock poisoned".to_string()))?;
+        Ok(state.invalid_blocks())
+    }
+
     /// Broadcast transaction (stub - needs network access)
     pub fn broadcast_transaction(&self, tx: &Transaction) {
         // Add to mempool
@@ -612,6 +1249,19 @@ fn enumerate_backups(backup_dir: &std::path::Path) -> Result<Vec<BackupInfo>, No
     Ok(backups)
 }
 
+/// Serialize the node config, adding the relay settings actually in effect
+/// so operators can see what their role and overrides resolve to.
+fn config_to_json(config: &crate::config::NodeConfig) -> Result<serde_json::Value, NodeError> {
+    let mut value =
+        serde_json::to_value(config).map_err(|e| NodeError::ConfigError(e.to_string()))?;
+    let policy = serde_json::to_value(config.relay_policy())
+        .map_err(|e| NodeError::ConfigError(e.to_string()))?;
+    if let Some(object) = value.as_object_mut() {
+        object.insert("effective_relay_policy".to_string(), policy);
+    }
+    Ok(value)
+}
+
 #[cfg(test)]
 mod backup_enumeration_tests {
     use super::enumerate_backups;
@@ -654,3 +1304,4 @@ mod backup_enumeration_tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 }
+
@@ -270,6 +270,17 @@ pub fn can_reorganize_below(fork_height: u64) -> Result<(), CheckpointError> {
     manager.can_reorganize_below(fork_height)
 }
 
+/// Lowest height an operator rollback may target: the last embedded
+/// checkpoint, or 0 when checkpoint enforcement is disabled.
+pub fn min_rollback_height() -> Result<u64, CheckpointError> {
+    let manager = get_checkpoint_manager();
+    let manager = manager.read().map_err(|_| CheckpointError::LockPoisoned)?;
+    if manager.enforcement == CheckpointEnforcement::Disabled {
+        return Ok(0);
+    }
+    Ok(manager.max_checkpoint_height())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
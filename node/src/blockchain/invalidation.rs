@@ -49,6 +49,9 @@ pub enum InvalidationReason {
     ForkTooDeep,
     /// Parent block invalid
     ParentInvalid,
+    /// Invalidated by an operator (rollback or invalidate-block); only
+    /// cleared by an explicit reconsider
+    Manual,
     /// Unknown reason
     Unknown(String),
 }
@@ -208,6 +211,129 @@ impl InvalidBlockTracker {
         Ok(())
     }
 
+    /// Mark a block as invalid on operator request. Unlike consensus
+    /// failures this is permanent on the first call.
+    pub fn mark_manually_invalid(
+        &self,
+        block_hash: [u8; 32],
+        parent_hash: Option<[u8; 32]>,
+        height: Option<u64>,
+    ) -> Result<(), InvalidationError> {
+        self.restore(InvalidBlock {
+            block_hash,
+            reason: InvalidationReason::Manual,
+            invalidated_at: Utc::now(),
+            attempt_count: 0,
+            permanent: true,
+            parent_hash,
+            height,
+        })
+    }
+
+    /// Mark a block as invalid after a deterministic validation failure.
+    /// Permanent on the first call: the same block can never pass, so it is
+    /// rejected without revalidation from then on. Returns the record to
+    /// persist.
+    pub fn mark_permanently_invalid(
+        &self,
+        block_hash: [u8; 32],
+        reason: InvalidationReason,
+        parent_hash: Option<[u8; 32]>,
+        height: Option<u64>,
+    ) -> Result<InvalidBlock, InvalidationError> {
+        let record = InvalidBlock {
+            block_hash,
+            reason: reason.clone(),
+            invalidated_at: Utc::now(),
+            attempt_count: 1,
+            permanent: true,
+            parent_hash,
+            height,
+        };
+        self.restore(record.clone())?;
+        if self.config.mark_descendants {
+            self.mark_descendants_invalid(block_hash, &reason)?;
+        }
+        Ok(record)
+    }
+
+    /// Track a previously persisted record as-is
+    pub fn restore(&self, record: InvalidBlock) -> Result<(), InvalidationError> {
+        let mut invalid_blocks = self
+            .invalid_blocks
+            .write()
+            .map_err(|_| InvalidationError::LockPoisoned)?;
+        let mut permanent_invalid = self
+            .permanent_invalid
+            .write()
+            .map_err(|_| InvalidationError::LockPoisoned)?;
+
+        let block_hash = record.block_hash;
+        if let Some(parent) = record.parent_hash {
+            let mut parent_map = self
+                .parent_to_children
+                .write()
+                .map_err(|_| InvalidationError::LockPoisoned)?;
+            let children = parent_map.entry(parent).or_insert_with(Vec::new);
+            if !children.contains(&block_hash) {
+                children.push(block_hash);
+            }
+        }
+        if record.permanent {
+            permanent_invalid.insert(block_hash);
+        }
+        invalid_blocks.insert(block_hash, record);
+
+        Ok(())
+    }
+
+    /// All tracked records, most recent first
+    pub fn list(&self) -> Vec<InvalidBlock> {
+        let invalid_blocks = self
+            .invalid_blocks
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut records: Vec<InvalidBlock> = invalid_blocks.values().cloned().collect();
+        records.sort_by(|a, b| {
+            b.invalidated_at
+                .cmp(&a.invalidated_at)
+                .then_with(|| a.block_hash.cmp(&b.block_hash))
+        });
+        records
+    }
+
+    /// Clear a block and every tracked descendant, returning the hashes that
+    /// were cleared (the block itself first).
+    pub fn reconsider(&self, block_hash: &[u8; 32]) -> Result<Vec<[u8; 32]>, InvalidationError> {
+        let parent_to_children = self
+            .parent_to_children
+            .read()
+            .map_err(|_| InvalidationError::LockPoisoned)?;
+        let mut invalid_blocks = self
+            .invalid_blocks
+            .write()
+            .map_err(|_| InvalidationError::LockPoisoned)?;
+        let mut permanent_invalid = self
+            .permanent_invalid
+            .write()
+            .map_err(|_| InvalidationError::LockPoisoned)?;
+
+        let mut cleared = Vec::new();
+        let mut pending = vec![*block_hash];
+        while let Some(hash) = pending.pop() {
+            if invalid_blocks.remove(&hash).is_none() {
+                continue;
+            }
+            permanent_invalid.remove(&hash);
+            cleared.push(hash);
+            if let Some(children) = parent_to_children.get(&hash) {
+                pending.extend(children.iter().copied());
+            }
+        }
+
+        Ok(cleared)
+    }
+
     /// Check if a block is invalid
     pub fn is_invalid(&self, block_hash: &[u8; 32]) -> bool {
         // Lock poisoning means another thread panicked mid-update; the
@@ -564,6 +690,40 @@ mod tests {
         assert!(tracker.is_permanently_invalid(&block_hash));
     }
 
+    #[test]
+    fn test_permanent_invalidation_and_reconsider() {
+        let tracker = InvalidBlockTracker::new(InvalidBlockTrackerConfig::default());
+        let parent_hash = [1u8; 32];
+        let child_hash = [2u8; 32];
+
+        tracker
+            .restore(InvalidBlock {
+                block_hash: child_hash,
+                reason: InvalidationReason::ParentInvalid,
+                invalidated_at: Utc::now(),
+                attempt_count: 0,
+                permanent: true,
+                parent_hash: Some(parent_hash),
+                height: Some(101),
+            })
+            .unwrap();
+        let record = tracker
+            .mark_permanently_invalid(
+                parent_hash,
+                InvalidationReason::InvalidMerkleRoot,
+                None,
+                Some(100),
+            )
+            .unwrap();
+        assert!(record.permanent);
+        assert!(tracker.is_permanently_invalid(&parent_hash)); // First attempt
+        assert_eq!(tracker.list().len(), 2);
+
+        let cleared = tracker.reconsider(&parent_hash).unwrap();
+        assert_eq!(cleared, vec![parent_hash, child_hash]);
+        assert!(tracker.list().is_empty());
+    }
+
     #[test]
     fn test_peer_notification() {
         let tracker = InvalidBlockTracker::new(InvalidBlockTrackerConfig::default());
@@ -0,0 +1,251 @@
+//! Local clock monitoring
+//!
+//! Peers stamp their status announcements with their wall-clock time. The
+//! median offset to those stamps, together with the tip timestamp, tells us
+//! whether our own clock has drifted. A small skew is logged; beyond
+//! `clock.refuse_skew_secs` the node refuses to build block templates or sign
+//! attestations, since either would carry a bad timestamp, until the operator
+//! fixes the clock or sets `clock.allow_skew`.
+
+use crate::config::ClockConfig;
+use crate::storage::ChainState;
+use libp2p::PeerId;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use supernova_core::util::clock::{
+    check_clock, median_offset, ClockCheck, SkewSeverity, SkewThresholds,
+};
+use thiserror::Error;
+use tracing::{error, warn};
+
+/// Peer samples needed before the network offset is trusted
+pub const MIN_PEER_SAMPLES: usize = 3;
+
+/// Peers whose offsets are remembered
+const MAX_PEER_SAMPLES: usize = 200;
+
+#[derive(Debug, Error)]
+pub enum ClockError {
+    #[error(
+        "Local clock is off by {skew_secs}s (limit {limit_secs}s); fix the system clock or set clock.allow_skew"
+    )]
+    Skewed { skew_secs: i64, limit_secs: u64 },
+}
+
+/// Clock state as reported in node status
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ClockStatus {
+    /// Node wall-clock time, Unix seconds
+    pub node_time: u64,
+    /// Median peer time minus node time, once enough peers have reported
+    pub network_offset_secs: Option<i64>,
+    /// Timestamp of the tip, if the chain has one
+    pub tip_timestamp: Option<u64>,
+    /// How far the tip timestamp is ahead of node time
+    pub tip_lag_secs: i64,
+    /// `ok`, `warning` or `critical`
+    #[schema(value_type = String)]
+    pub severity: SkewSeverity,
+    /// Peers that have reported their time
+    pub peer_samples: usize,
+    /// Whether a critical skew is being ignored (`clock.allow_skew`)
+    pub override_active: bool,
+}
+
+/// Tracks peer-reported time and classifies the local clock
+pub struct ClockMonitor {
+    thresholds: SkewThresholds,
+    allow_skew: AtomicBool,
+    check_interval: Duration,
+    offsets: RwLock<HashMap<PeerId, i64>>,
+}
+
+impl ClockMonitor {
+    pub fn new(config: &ClockConfig) -> Self {
+        Self {
+            thresholds: SkewThresholds {
+                warn_secs: config.warn_skew_secs,
+                critical_secs: config.refuse_skew_secs,
+            },
+            allow_skew: AtomicBool::new(config.allow_skew),
+            check_interval: Duration::from_secs(config.check_interval_secs),
+            offsets: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record the time a peer reported in its status announcement
+    pub fn record_peer_time(&self, peer: PeerId, peer_time: u64) {
+        let offset = peer_time as i64 - now() as i64;
+        let mut offsets = self.offsets.write();
+        if offsets.len() >= MAX_PEER_SAMPLES && !offsets.contains_key(&peer) {
+            return;
+        }
+        offsets.insert(peer, offset);
+    }
+
+    pub fn forget_peer(&self, peer: &PeerId) {
+        self.offsets.write().remove(peer);
+    }
+
+    /// Median peer offset, `None` until `MIN_PEER_SAMPLES` peers reported
+    pub fn network_offset(&self) -> Option<i64> {
+        let offsets: Vec<i64> = self.offsets.read().values().copied().collect();
+        if offsets.len() < MIN_PEER_SAMPLES {
+            return None;
+        }
+        median_offset(&offsets)
+    }
+
+    /// Check the local clock against peers and the given tip timestamp
+    pub fn check(&self, tip_timestamp: Option<u64>) -> ClockCheck {
+        check_clock(
+            now(),
+            self.network_offset(),
+            tip_timestamp,
+            &self.thresholds,
+        )
+    }
+
+    pub fn status(&self, tip_timestamp: Option<u64>) -> ClockStatus {
+        let check = self.check(tip_timestamp);
+        ClockStatus {
+            node_time: now(),
+            network_offset_secs: check.offset_secs,
+            tip_timestamp,
+            tip_lag_secs: check.tip_lag_secs,
+            severity: check.severity,
+            peer_samples: self.offsets.read().len(),
+            override_active: check.severity == SkewSeverity::Critical && self.allow_skew(),
+        }
+    }
+
+    /// Refuse timestamp-bearing work while the clock is critically off
+    pub fn ensure_sane(&self, tip_timestamp: Option<u64>) -> Result<(), ClockError> {
+        let check = self.check(tip_timestamp);
+        if check.severity < SkewSeverity::Critical {
+            return Ok(());
+        }
+        if self.allow_skew() {
+            warn!(
+                "Local clock is off by {}s; continuing because clock.allow_skew is set",
+                check.skew_secs()
+            );
+            return Ok(());
+        }
+        Err(ClockError::Skewed {
+            skew_secs: check.skew_secs(),
+            limit_secs: self.thresholds.critical_secs,
+        })
+    }
+
+    pub fn allow_skew(&self) -> bool {
+        self.allow_skew.load(Ordering::Relaxed)
+    }
+
+    pub fn set_allow_skew(&self, allow: bool) {
+        self.allow_skew.store(allow, Ordering::Relaxed);
+    }
+
+    /// Check at startup and then every `clock.check_interval_secs`, logging
+    /// any skew
+    pub async fn run(self: Arc<Self>, chain_state: Arc<std::sync::RwLock<ChainState>>) {
+        let mut interval = tokio::time::interval(self.check_interval);
+        loop {
+            interval.tick().await;
+            let check = self.check(tip_timestamp(&chain_state));
+            match check.severity {
+                SkewSeverity::Ok => {}
+                SkewSeverity::Warning => warn!(
+                    "Local clock looks off by {}s (network offset {:?}, tip lag {}s); check NTP",
+                    check.skew_secs(),
+                    check.offset_secs,
+                    check.tip_lag_secs
+                ),
+                SkewSeverity::Critical => error!(
+                    "Local clock is off by {}s (network offset {:?}, tip lag {}s); {}",
+                    check.skew_secs(),
+                    check.offset_secs,
+                    check.tip_lag_secs,
+                    if self.allow_skew() {
+                        "clock.allow_skew is set, still mining and signing"
+                    } else {
+                        "refusing to mine or sign attestations until it is fixed"
+                    }
+                ),
+            }
+        }
+    }
+}
+
+/// Timestamp of the current tip, if the chain state is readable
+pub fn tip_timestamp(chain_state: &std::sync::RwLock<ChainState>) -> Option<u64> {
+    let chain = chain_state.read().ok()?;
+    chain
+        .get_block(&chain.get_best_block_hash())
+        .map(|block| block.header().timestamp())
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor_with_offset(offset: i64) -> ClockMonitor {
+        let monitor = ClockMonitor::new(&ClockConfig::default());
+        for _ in 0..MIN_PEER_SAMPLES {
+            monitor.record_peer_time(PeerId::random(), (now() as i64 + offset) as u64);
+        }
+        monitor
+    }
+
+    #[test]
+    fn test_peer_offsets_set_severity() {
+        assert_eq!(
+            monitor_with_offset(0).check(None).severity,
+            SkewSeverity::Ok
+        );
+        assert_eq!(
+            monitor_with_offset(-300).check(None).severity,
+            SkewSeverity::Warning
+        );
+        assert_eq!(
+            monitor_with_offset(7_200).check(None).severity,
+            SkewSeverity::Critical
+        );
+
+        // Too few peers to judge
+        let monitor = ClockMonitor::new(&ClockConfig::default());
+        monitor.record_peer_time(PeerId::random(), now() + 7_200);
+        assert_eq!(monitor.network_offset(), None);
+        assert_eq!(monitor.check(None).severity, SkewSeverity::Ok);
+    }
+
+    #[test]
+    fn test_mining_refused_until_overridden() {
+        let monitor = monitor_with_offset(3_600);
+        assert!(matches!(
+            monitor.ensure_sane(None),
+            Err(ClockError::Skewed {
+                limit_secs: 600,
+                ..
+            })
+        ));
+        assert!(!monitor.status(None).override_active);
+
+        monitor.set_allow_skew(true);
+        assert!(monitor.ensure_sane(None).is_ok());
+        assert!(monitor.status(None).override_active);
+
+        // A tip far in our future is refused the same way
+        let monitor = ClockMonitor::new(&ClockConfig::default());
+        assert!(monitor.ensure_sane(Some(now() + 3_600)).is_err());
+        assert!(monitor.ensure_sane(Some(now() - 3_600)).is_ok());
+    }
+}
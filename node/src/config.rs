@@ -1,10 +1,20 @@
+pub mod layered;
+
 use crate::api::ApiConfig;
-use config::{Config, ConfigError, Environment, File};
+use crate::network::relay_policy::{NodeRole, RelayPolicy, TxAnnouncement};
+use crate::storage::persistence::MAX_REORG_DEPTH;
+use crate::storage::state_snapshot::TrustedSnapshot;
+use config::ConfigError;
+use libp2p::Multiaddr;
+use layered::{diff_configs, LayeredConfigLoader, ResolvedConfig};
 use notify::{self, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
+use supernova_core::validation::BlockValidationConfig;
 use thiserror::Error;
 use tracing::{error, info, warn};
 
@@ -36,6 +46,14 @@ pub struct NodeConfig {
     pub checkpoint: CheckpointConfig,
     pub api: ApiConfig,
     pub testnet: TestnetConfig,
+    #[serde(default)]
+    pub mining: MiningConfig,
+    #[serde(default)]
+    pub relay: RelayConfig,
+    #[serde(default)]
+    pub clock: ClockConfig,
+    #[serde(default)]
+    pub sync: SyncConfig,
 
     /// Filesystem path this configuration was actually loaded from.
     ///
@@ -46,15 +64,47 @@ pub struct NodeConfig {
     /// on-disk TOML shape is unchanged; it is populated at load time only.
     #[serde(skip)]
     pub source_path: Option<PathBuf>,
+
+    /// `--set section.key=value` overrides supplied on the command line.
+    ///
+    /// Kept alongside `source_path` so `reload()` re-applies the same CLI
+    /// layer on top of the edited file instead of quietly dropping it.
+    #[serde(skip)]
+    pub cli_overrides: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NetworkConfig {
     pub listen_addr: String,
+    /// Addresses to bind, e.g. IPv4 and IPv6 wildcards on a dual-stack host.
+    /// When non-empty this replaces `listen_addr`.
+    #[serde(default)]
+    pub listen_addrs: Vec<Multiaddr>,
+    /// Public addresses to advertise to peers. When set, these are the only
+    /// advertised addresses and observed addresses are ignored.
+    #[serde(default)]
+    pub external_addrs: Vec<Multiaddr>,
+    /// Distinct peers that must report the same observed address for us
+    /// before it is advertised
+    #[serde(default = "default_observed_addr_confirmations")]
+    pub observed_addr_confirmations: usize,
     pub max_peers: usize,
+    /// Block and transaction hashes remembered per peer to avoid relaying
+    /// them back (see `network::inventory`)
+    #[serde(default = "default_known_inventory_size")]
+    pub known_inventory_size: usize,
+    /// Encrypt direct messages with peers that support it (see
+    /// `network::encryption`)
+    #[serde(default = "default_encrypted_transport")]
+    pub encrypted_transport: bool,
     pub bootstrap_nodes: Vec<String>,
     #[serde(with = "duration_serde")]
     pub peer_ping_interval: Duration,
+    /// Consecutive unanswered keepalive pings before a peer is dropped
+    pub max_missed_pongs: u32,
+    /// Ping round-trip ceiling; peers persistently above it are dropped
+    #[serde(with = "duration_serde")]
+    pub max_ping_latency: Duration,
     pub max_outbound_connections: usize,
     pub max_inbound_connections: usize,
     pub ban_threshold: u32,
@@ -63,9 +113,20 @@ pub struct NetworkConfig {
 
     // Added network configuration options
     pub key_path: Option<PathBuf>,
+    /// How long a rotated-out identity stays in service while peers learn
+    /// the new one (see `network::identity_rotation`)
+    #[serde(default = "default_identity_rotation_grace", with = "duration_serde")]
+    pub identity_rotation_grace: Duration,
     pub network_id: String,
     pub enable_mdns: bool,
+    /// Map the listen port on the gateway (see `network::nat`)
     pub enable_upnp: bool,
+    /// Lease requested for the port mapping; renewed at half its length
+    #[serde(default = "default_upnp_lease", with = "duration_serde")]
+    pub upnp_lease: Duration,
+    /// Gateway to ask over NAT-PMP when UPnP finds none
+    #[serde(default)]
+    pub nat_pmp_gateway: Option<Ipv4Addr>,
     pub enable_peer_exchange: bool,
     pub enable_nat_traversal: bool,
     #[serde(with = "duration_serde")]
@@ -78,6 +139,26 @@ pub struct NetworkConfig {
     pub min_outbound_connections: usize,
     pub peer_diversity: PeerDiversityConfig,
     pub pubsub_config: PubSubConfig,
+    /// Limits on serving blocks below the recency threshold to peers
+    #[serde(default)]
+    pub block_serving: BlockServingConfig,
+    /// DNS seeds queried for peer addresses (see `network::dns_seed`)
+    #[serde(default)]
+    pub dns_seeds: DnsSeedsConfig,
+    /// SOCKS5 proxy for outbound lookups. When set, DNS seed queries are
+    /// tunnelled through it rather than sent to the system resolver.
+    #[serde(default)]
+    pub socks_proxy: Option<SocketAddr>,
+    /// Mempool warm-up from outbound peers after a restart (see
+    /// `network::mempool_sync`)
+    #[serde(default)]
+    pub mempool_sync: MempoolSyncConfig,
+    /// Messages between node operators (see `network::operator_messages`)
+    #[serde(default)]
+    pub operator_messages: OperatorMessagesConfig,
+    /// Global upload and download caps (see `network::bandwidth`)
+    #[serde(default)]
+    pub bandwidth: BandwidthConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -87,6 +168,35 @@ pub struct StorageConfig {
     pub cache_size: usize,
     pub max_open_files: i32,
     pub block_cache_size: usize,
+    /// Blocks below the tip whose undo data is kept for fast disconnects
+    #[serde(default = "default_undo_depth")]
+    pub undo_depth: u64,
+    /// Prune mode: raw block data kept, in GB. 0 keeps every block. The
+    /// last `undo_depth` blocks are always kept.
+    #[serde(default)]
+    pub prune_target_gb: f64,
+    /// Index every best-chain transaction by txid. Turning it on for an
+    /// existing chain indexes the stored blocks in the background.
+    #[serde(default)]
+    pub tx_index: bool,
+    /// Index outputs and spends by script for the address endpoints.
+    /// Requires `tx_index`, whose rebuild also builds this index.
+    #[serde(default)]
+    pub address_index: bool,
+    /// Hours between background compactions; 0 turns the schedule off
+    #[serde(default = "default_compaction_interval_hours")]
+    pub compaction_interval_hours: u64,
+    /// Compact early once this share of the database files is estimated
+    /// reclaimable, from 0 to 1; 0 turns the trigger off
+    #[serde(default = "default_compaction_threshold")]
+    pub compaction_threshold: f64,
+}
+
+impl StorageConfig {
+    /// Prune target in bytes, if prune mode is on
+    pub fn prune_target_bytes(&self) -> Option<u64> {
+        (self.prune_target_gb > 0.0).then(|| (self.prune_target_gb * 1e9) as u64)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -102,6 +212,64 @@ pub struct MempoolConfig {
     pub max_orphan_transactions: usize,
     pub enable_rbf: bool,
     pub min_rbf_fee_increase: f64,
+    /// Largest `OP_RETURN` payload relayed, in bytes; 0 relays no data
+    /// outputs with a payload. Blocks are not limited.
+    #[serde(default = "default_max_data_carrier_bytes")]
+    pub max_data_carrier_bytes: usize,
+    /// Save the mempool to `mempool.dat` in the storage directory on
+    /// shutdown and reload it on startup
+    #[serde(default = "default_persist_mempool")]
+    pub persist: bool,
+}
+
+/// Transaction relay profile, e.g. `role = "wallet"`. The role selects a
+/// preset (see `RelayPolicy::for_role`) and any field set here overrides it.
+/// Without a role the `[mempool]` section applies unchanged.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct RelayConfig {
+    #[serde(default)]
+    pub role: Option<NodeRole>,
+    #[serde(default)]
+    pub max_mempool_size: Option<usize>,
+    #[serde(default)]
+    pub min_relay_fee: Option<f64>,
+    #[serde(default)]
+    pub enable_rbf: Option<bool>,
+    #[serde(default)]
+    pub package_relay: Option<bool>,
+    #[serde(default)]
+    pub max_orphan_transactions: Option<usize>,
+    #[serde(default)]
+    pub tx_announcement: Option<TxAnnouncement>,
+}
+
+impl RelayConfig {
+    /// Resolve the profile and overrides against the `[mempool]` section.
+    pub fn effective(&self, mempool: &MempoolConfig) -> RelayPolicy {
+        let base = match self.role {
+            Some(role) => RelayPolicy::for_role(role),
+            None => RelayPolicy {
+                role: None,
+                max_mempool_size: mempool.max_size,
+                min_relay_fee: mempool.min_fee_rate,
+                enable_rbf: mempool.enable_rbf,
+                package_relay: true,
+                max_orphan_transactions: mempool.max_orphan_transactions,
+                tx_announcement: TxAnnouncement::All,
+            },
+        };
+        RelayPolicy {
+            role: base.role,
+            max_mempool_size: self.max_mempool_size.unwrap_or(base.max_mempool_size),
+            min_relay_fee: self.min_relay_fee.unwrap_or(base.min_relay_fee),
+            enable_rbf: self.enable_rbf.unwrap_or(base.enable_rbf),
+            package_relay: self.package_relay.unwrap_or(base.package_relay),
+            max_orphan_transactions: self
+                .max_orphan_transactions
+                .unwrap_or(base.max_orphan_transactions),
+            tx_announcement: self.tx_announcement.unwrap_or(base.tx_announcement),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -126,6 +294,19 @@ pub struct GeneralConfig {
     pub enable_lightning: bool,
     pub enable_quantum_security: bool,
     pub enable_mining: bool,
+    /// Record a trace of every check run against a block that fails
+    /// validation, served at `GET /blockchain/rejections/{hash}/trace`
+    #[serde(default)]
+    pub validation_tracing: bool,
+    /// Memory for remembering signatures that verified, shared by the
+    /// mempool and block validation; 0 disables the cache
+    #[serde(default = "default_signature_cache_bytes")]
+    pub signature_cache_bytes: usize,
+    /// Accept a hybrid signature when only one of its classical and ML-DSA
+    /// halves verifies. A consensus rule for the migration period to hybrid
+    /// keys: every node on the network must agree on it.
+    #[serde(default)]
+    pub hybrid_signature_migration: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -143,6 +324,17 @@ pub struct CheckpointConfig {
     pub checkpoint_type: String,
     pub data_dir: PathBuf,
     pub max_checkpoints: usize,
+    /// Take a UTXO state snapshot every N blocks and serve it to
+    /// bootstrapping peers; 0 disables snapshots
+    #[serde(default)]
+    pub snapshot_interval_blocks: u64,
+    /// State snapshots kept, newest first
+    #[serde(default = "default_max_snapshots")]
+    pub max_snapshots: usize,
+}
+
+fn default_max_snapshots() -> usize {
+    2
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -160,6 +352,313 @@ pub struct TestnetConfig {
     pub simulated_packet_loss: f64,
 }
 
+/// Local clock sanity limits. The clock is compared against peer-reported
+/// time and the latest block timestamp at startup and periodically.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClockConfig {
+    /// Skew, in seconds, beyond which a warning is logged
+    #[serde(default = "default_clock_warn_skew_secs")]
+    pub warn_skew_secs: u64,
+    /// Skew, in seconds, beyond which the node refuses to build blocks or
+    /// sign attestations
+    #[serde(default = "default_clock_refuse_skew_secs")]
+    pub refuse_skew_secs: u64,
+    /// Keep mining and signing despite a large skew
+    #[serde(default)]
+    pub allow_skew: bool,
+    /// Seconds between periodic checks
+    #[serde(default = "default_clock_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+fn default_clock_warn_skew_secs() -> u64 {
+    supernova_core::util::clock::DEFAULT_WARN_SKEW_SECS
+}
+
+fn default_clock_refuse_skew_secs() -> u64 {
+    supernova_core::util::clock::DEFAULT_CRITICAL_SKEW_SECS
+}
+
+fn default_clock_check_interval_secs() -> u64 {
+    300
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            warn_skew_secs: default_clock_warn_skew_secs(),
+            refuse_skew_secs: default_clock_refuse_skew_secs(),
+            allow_skew: false,
+            check_interval_secs: default_clock_check_interval_secs(),
+        }
+    }
+}
+
+impl ClockConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        if self.warn_skew_secs == 0 || self.refuse_skew_secs < self.warn_skew_secs {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "clock.refuse_skew_secs must be >= clock.warn_skew_secs > 0".to_string(),
+            ));
+        }
+        if self.check_interval_secs == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "clock.check_interval_secs must be > 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// How a node with an empty chain reaches the tip
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncMode {
+    /// Download and validate every block from genesis
+    #[default]
+    Full,
+    /// Restore the trusted state snapshot from peers, then sync the blocks
+    /// above it
+    Snapshot,
+}
+
+impl SyncMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncMode::Full => "full",
+            SyncMode::Snapshot => "snapshot",
+        }
+    }
+}
+
+impl std::fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SyncMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(SyncMode::Full),
+            "snapshot" => Ok(SyncMode::Snapshot),
+            _ => Err(format!(
+                "unknown sync mode {s:?}, expected full or snapshot"
+            )),
+        }
+    }
+}
+
+/// Initial sync settings. The snapshot values come from a source the
+/// operator trusts, such as the release notes.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SyncConfig {
+    #[serde(default)]
+    pub mode: SyncMode,
+    /// Height of the trusted state snapshot
+    #[serde(default)]
+    pub snapshot_height: u64,
+    /// Hex block hash at `snapshot_height`
+    #[serde(default)]
+    pub snapshot_hash: String,
+    /// Hex UTXO set commitment at `snapshot_height`
+    #[serde(default)]
+    pub snapshot_utxo_commitment: String,
+    /// Hex assumevalid block hash; it and its ancestors skip signature
+    /// checks. Unset uses the built-in checkpoint, "0" verifies every block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assume_valid: Option<String>,
+}
+
+impl SyncConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        if self.mode == SyncMode::Snapshot && self.trusted_snapshot().is_none() {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "sync.mode = \"snapshot\" needs sync.snapshot_height > 0 and 32-byte hex \
+                 sync.snapshot_hash and sync.snapshot_utxo_commitment"
+                    .to_string(),
+            ));
+        }
+        if let Some(hash) = &self.assume_valid {
+            if hash != "0" && hex::decode(hash).map_or(true, |bytes| bytes.len() != 32) {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "sync.assume_valid must be a 32-byte hex block hash or \"0\", got {hash:?}"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Block whose ancestors skip signature verification, if any
+    pub fn assume_valid(&self) -> Option<[u8; 32]> {
+        match self.assume_valid.as_deref() {
+            None => BlockValidationConfig::default().assume_valid,
+            Some("0") => None,
+            Some(hash) => hex::decode(hash).ok()?.try_into().ok(),
+        }
+    }
+
+    /// The snapshot to bootstrap from, in snapshot mode
+    pub fn trusted_snapshot(&self) -> Option<TrustedSnapshot> {
+        let hash32 =
+            |hex_str: &str| -> Option<[u8; 32]> { hex::decode(hex_str).ok()?.try_into().ok() };
+        if self.mode != SyncMode::Snapshot || self.snapshot_height == 0 {
+            return None;
+        }
+        Some(TrustedSnapshot {
+            height: self.snapshot_height,
+            tip_hash: hash32(&self.snapshot_hash)?,
+            utxo_commitment: hash32(&self.snapshot_utxo_commitment)?,
+        })
+    }
+}
+
+/// Coinbase payout settings. Read on every template, so edits take effect on
+/// the next template without a restart. The node never needs the payout keys.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MiningConfig {
+    /// Addresses the miner's share rotates through, one per block height.
+    /// Empty uses the node wallet's mining address.
+    #[serde(default)]
+    pub payout_rotation: Vec<String>,
+    /// Splits of the miner's share. Percentages must total 100; fixed amounts
+    /// are paid first and, without percentage splits, the rest goes to the
+    /// rotation address.
+    #[serde(default)]
+    pub payout_splits: Vec<PayoutSplit>,
+    /// Version-bits deployments (by name) templates signal for while their
+    /// signaling window is open. Empty signals for none.
+    #[serde(default)]
+    pub signal_deployments: Vec<String>,
+    /// New mempool fees, in novas, that end a `getblocktemplate` long poll
+    /// early. 0 only ends long polls on a new tip.
+    #[serde(default = "default_longpoll_fee_delta")]
+    pub longpoll_fee_delta: u64,
+    /// Seconds a long poll is held before the current template is returned
+    #[serde(default = "default_longpoll_timeout_secs")]
+    pub longpoll_timeout_secs: u64,
+    /// Stratum v1 server for external miners
+    #[serde(default)]
+    pub stratum: StratumConfig,
+}
+
+fn default_longpoll_fee_delta() -> u64 {
+    100_000
+}
+
+fn default_longpoll_timeout_secs() -> u64 {
+    60
+}
+
+impl Default for MiningConfig {
+    fn default() -> Self {
+        Self {
+            payout_rotation: Vec::new(),
+            payout_splits: Vec::new(),
+            signal_deployments: Vec::new(),
+            longpoll_fee_delta: default_longpoll_fee_delta(),
+            longpoll_timeout_secs: default_longpoll_timeout_secs(),
+            stratum: StratumConfig::default(),
+        }
+    }
+}
+
+/// Stratum v1 server settings (`[mining.stratum]`). The server runs
+/// alongside the API server and needs the `stratum` feature.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct StratumConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    /// Share difficulty given to new connections
+    pub initial_difficulty: f64,
+    pub min_difficulty: f64,
+    pub max_difficulty: f64,
+    /// Seconds between shares vardiff aims for
+    pub target_share_secs: u64,
+    /// Seconds of shares vardiff looks at before retargeting
+    pub retarget_secs: u64,
+    pub max_connections: usize,
+}
+
+impl Default for StratumConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 3333,
+            initial_difficulty: 1.0,
+            min_difficulty: 0.001,
+            max_difficulty: 1e12,
+            target_share_secs: 10,
+            retarget_secs: 90,
+            max_connections: 1024,
+        }
+    }
+}
+
+impl StratumConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        let positive = |value: f64| value.is_finite() && value > 0.0;
+        if !positive(self.min_difficulty)
+            || !positive(self.max_difficulty)
+            || self.min_difficulty > self.initial_difficulty
+            || self.initial_difficulty > self.max_difficulty
+        {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "mining.stratum difficulties must satisfy 0 < min_difficulty <= \
+                 initial_difficulty <= max_difficulty"
+                    .to_string(),
+            ));
+        }
+        if self.target_share_secs == 0 || self.retarget_secs < self.target_share_secs {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "mining.stratum.retarget_secs must be at least target_share_secs, which must \
+                 be greater than 0"
+                    .to_string(),
+            ));
+        }
+        if self.enabled && self.max_connections == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "mining.stratum.max_connections must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One coinbase payee, e.g. `{ address = "nova1...", percent = 70.0 }` or
+/// `{ address = "nova1...", fixed = 100000000 }`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PayoutSplit {
+    pub address: String,
+    #[serde(flatten)]
+    pub share: PayoutShare,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayoutShare {
+    /// Percentage of the miner's share remaining after fixed splits, with up
+    /// to two decimal places
+    Percent(f64),
+    /// Fixed amount in nova units
+    Fixed(u64),
+}
+
+impl PayoutShare {
+    /// Percentage expressed in basis points, if this is a percentage split.
+    pub fn basis_points(&self) -> Option<u64> {
+        match self {
+            PayoutShare::Percent(percent) => Some((percent * 100.0).round() as u64),
+            PayoutShare::Fixed(_) => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PeerDiversityConfig {
     pub enabled: bool,
@@ -172,6 +671,139 @@ pub struct PeerDiversityConfig {
     pub max_peers_per_region: usize,
     pub max_inbound_ratio: f64,
     pub max_connection_attempts_per_min: usize,
+    /// Outbound peers allowed in one IPv4 /16 (IPv6 /32)
+    #[serde(default = "default_max_outbound_per_prefix")]
+    pub max_outbound_per_prefix: usize,
+    /// Outbound peers allowed in one ASN; only enforced with `asn_database`
+    #[serde(default = "default_max_outbound_per_asn")]
+    pub max_outbound_per_asn: usize,
+    /// MaxMind ASN database (e.g. GeoLite2-ASN.mmdb) for ASN bucketing
+    #[serde(default)]
+    pub asn_database: Option<PathBuf>,
+    /// Pause between disconnecting two outbound peers that exceed a cap
+    #[serde(default = "default_excess_rotation_interval", with = "duration_serde")]
+    pub excess_rotation_interval: Duration,
+}
+
+/// Budget for serving historical blocks to syncing peers (see
+/// `network::block_serving`). Recent blocks and headers are not budgeted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BlockServingConfig {
+    /// Historical block bytes served per second across all peers
+    pub historical_bytes_per_sec: u64,
+    /// Historical requests served at the same time
+    pub max_concurrent_requests: usize,
+    /// Historical requests queued per peer before it is told we are busy
+    pub max_queued_per_peer: usize,
+    /// Blocks within this many of the tip are recent and always served
+    pub recent_depth: u64,
+    /// Minimum back-off suggested to peers in a busy response
+    #[serde(with = "duration_serde")]
+    pub busy_retry_after: Duration,
+}
+
+/// Global bandwidth budgets. Unset limits are unlimited. When a budget is
+/// tight, block and header traffic is sent first and transaction relay is
+/// dropped; once a daily budget is spent only block and header traffic
+/// continues, so the node keeps following the chain.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BandwidthConfig {
+    /// Bytes sent per second across all peers
+    pub max_upload_per_sec: Option<u64>,
+    /// Bytes received per second across all peers
+    pub max_download_per_sec: Option<u64>,
+    /// Bytes sent per rolling day
+    pub max_upload_per_day: Option<u64>,
+    /// Bytes received per rolling day
+    pub max_download_per_day: Option<u64>,
+}
+
+/// Mempool warm-up after startup. Once initial block download is done, a
+/// few outbound peers are asked for their mempool txids and the unknown
+/// transactions are fetched. Disabling it also stops answering peers'
+/// summary requests, for operators who do not want their mempool probed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MempoolSyncConfig {
+    pub enabled: bool,
+    /// Outbound peers asked for a mempool summary
+    pub peers: usize,
+    /// Lowest fee rate (novas/byte) requested from peers
+    pub min_fee_rate: u64,
+    /// Most txids requested in one summary
+    pub max_entries: usize,
+    /// Transaction bytes fetched across the whole warm-up
+    pub max_bytes: u64,
+    /// Delay between summary requests to successive peers
+    #[serde(with = "duration_serde")]
+    pub stagger: Duration,
+    /// How long after IBD the warm-up may run
+    #[serde(with = "duration_serde")]
+    pub window: Duration,
+}
+
+/// Operator-to-operator messages. Nothing is accepted until senders are
+/// allow-listed or authority keys configured.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct OperatorMessagesConfig {
+    /// Peer ids whose direct and broadcast messages are accepted
+    pub allowed_senders: Vec<String>,
+    /// Hex ed25519 keys whose network notices are accepted and relayed
+    pub authority_keys: Vec<String>,
+    /// Hex ed25519 seed this node signs notices with, if it is an authority
+    pub authority_key_file: Option<PathBuf>,
+    /// Messages accepted from one sender per minute
+    pub max_per_sender_per_minute: u32,
+    /// Leading zero bits a notice id must have
+    pub notice_pow_bits: u8,
+}
+
+/// DNS seeds and how often they are queried. Seeds are resolved at startup
+/// and again whenever outbound connections fall below
+/// `min_outbound_connections`, no more often than `min_reresolve_interval`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DnsSeedsConfig {
+    pub seeds: Vec<DnsSeedConfig>,
+    /// Deadline for resolving all seeds once
+    #[serde(with = "duration_serde")]
+    pub timeout: Duration,
+    #[serde(with = "duration_serde")]
+    pub min_reresolve_interval: Duration,
+    /// Nameserver queried over TCP through `socks_proxy`
+    pub proxy_nameserver: SocketAddr,
+}
+
+/// A DNS seed hostname
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DnsSeedConfig {
+    pub host: String,
+    /// Hex ed25519 key of the seed operator. When set, the seed's TXT peer
+    /// lists must carry a valid signature from it.
+    #[serde(default)]
+    pub operator_key: Option<String>,
+    /// P2P port assumed for plain A/AAAA answers
+    #[serde(default = "default_dns_seed_port")]
+    pub port: u16,
+}
+
+fn default_max_data_carrier_bytes() -> usize {
+    supernova_core::script::classify::DEFAULT_MAX_DATA_CARRIER_BYTES
+}
+
+fn default_persist_mempool() -> bool {
+    true
+}
+
+fn default_signature_cache_bytes() -> usize {
+    supernova_core::validation::sig_cache::DEFAULT_SIG_CACHE_BYTES
+}
+
+fn default_dns_seed_port() -> u16 {
+    8333
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -188,6 +820,50 @@ pub struct PubSubConfig {
     pub explicit_relays: usize,
 }
 
+fn default_observed_addr_confirmations() -> usize {
+    3
+}
+
+fn default_undo_depth() -> u64 {
+    crate::storage::undo::DEFAULT_UNDO_DEPTH
+}
+
+fn default_compaction_interval_hours() -> u64 {
+    24
+}
+
+fn default_compaction_threshold() -> f64 {
+    0.5
+}
+
+fn default_known_inventory_size() -> usize {
+    crate::network::inventory::DEFAULT_KNOWN_INVENTORY_SIZE
+}
+
+fn default_encrypted_transport() -> bool {
+    true
+}
+
+fn default_upnp_lease() -> Duration {
+    Duration::from_secs(60 * 60)
+}
+
+fn default_identity_rotation_grace() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+fn default_max_outbound_per_prefix() -> usize {
+    2
+}
+
+fn default_max_outbound_per_asn() -> usize {
+    2
+}
+
+fn default_excess_rotation_interval() -> Duration {
+    Duration::from_secs(600)
+}
+
 fn parse_libp2p_listen_port(listen_addr: &str) -> Result<u16, NodeConfigValidationError> {
     // Expected pattern contains "/tcp/<port>"
     let port_str = listen_addr
@@ -216,14 +892,63 @@ fn parse_libp2p_listen_port(listen_addr: &str) -> Result<u16, NodeConfigValidati
 }
 
 impl NetworkConfig {
+    /// Addresses the P2P layer binds: `listen_addrs`, or `listen_addr` alone
+    /// when none are configured.
+    pub fn effective_listen_addrs(&self) -> Result<Vec<Multiaddr>, NodeConfigValidationError> {
+        if !self.listen_addrs.is_empty() {
+            return Ok(self.listen_addrs.clone());
+        }
+        let addr = self.listen_addr.parse().map_err(|e| {
+            NodeConfigValidationError::InvalidValue(format!(
+                "network.listen_addr '{}' is not a multiaddr: {e}",
+                self.listen_addr
+            ))
+        })?;
+        Ok(vec![addr])
+    }
+
+    /// TCP ports of every listen address
+    pub fn listen_ports(&self) -> Result<Vec<u16>, NodeConfigValidationError> {
+        if self.listen_addrs.is_empty() {
+            return Ok(vec![parse_libp2p_listen_port(&self.listen_addr)?]);
+        }
+        self.listen_addrs
+            .iter()
+            .map(|addr| parse_libp2p_listen_port(&addr.to_string()))
+            .collect()
+    }
+
     pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
-        let _listen_port = parse_libp2p_listen_port(&self.listen_addr)?;
+        let _listen_ports = self.listen_ports()?;
+
+        if self.observed_addr_confirmations == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.observed_addr_confirmations must be > 0".to_string(),
+            ));
+        }
+
+        if self.identity_rotation_grace.is_zero() {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.identity_rotation_grace must be > 0".to_string(),
+            ));
+        }
+
+        if self.enable_upnp && self.upnp_lease < Duration::from_secs(120) {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.upnp_lease must be at least 120 seconds".to_string(),
+            ));
+        }
 
         if self.max_peers < 8 {
             return Err(NodeConfigValidationError::InvalidValue(
                 "network.max_peers must be >= 8".to_string(),
             ));
         }
+        if self.known_inventory_size == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.known_inventory_size must be > 0".to_string(),
+            ));
+        }
         if self.max_inbound_connections > self.max_peers {
             return Err(NodeConfigValidationError::InvalidValue(
                 "network.max_inbound_connections cannot exceed network.max_peers".to_string(),
@@ -260,6 +985,16 @@ impl NetworkConfig {
                 "network.peer_ping_interval must be >= 1 second".to_string(),
             ));
         }
+        if self.max_missed_pongs == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.max_missed_pongs must be > 0".to_string(),
+            ));
+        }
+        if self.max_ping_latency.as_secs() < 1 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.max_ping_latency must be >= 1 second".to_string(),
+            ));
+        }
         if self.connection_timeout.as_secs() < 1 {
             return Err(NodeConfigValidationError::InvalidValue(
                 "network.connection_timeout must be >= 1 second".to_string(),
@@ -293,6 +1028,139 @@ impl NetworkConfig {
 
         self.peer_diversity.validate()?;
         self.pubsub_config.validate()?;
+        self.block_serving.validate()?;
+        self.dns_seeds.validate()?;
+        self.mempool_sync.validate()?;
+        self.operator_messages.validate()?;
+        self.bandwidth.validate()?;
+        Ok(())
+    }
+}
+
+impl DnsSeedsConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        if self.timeout.as_secs() < 1 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.dns_seeds.timeout must be >= 1 second".to_string(),
+            ));
+        }
+        for seed in &self.seeds {
+            if seed.host.trim().is_empty() {
+                return Err(NodeConfigValidationError::InvalidValue(
+                    "network.dns_seeds.seeds entries need a host".to_string(),
+                ));
+            }
+            if let Some(key) = &seed.operator_key {
+                let valid = hex::decode(key)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok())
+                    .is_some();
+                if !valid {
+                    return Err(NodeConfigValidationError::InvalidValue(format!(
+                        "network.dns_seeds operator_key for {} is not a hex ed25519 public key",
+                        seed.host
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MempoolSyncConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.peers == 0 || self.max_entries == 0 || self.max_bytes == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.mempool_sync limits must be > 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl OperatorMessagesConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        for peer in &self.allowed_senders {
+            if libp2p::PeerId::from_str(peer).is_err() {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "network.operator_messages.allowed_senders entry {} is not a peer id",
+                    peer
+                )));
+            }
+        }
+        for key in &self.authority_keys {
+            let valid = hex::decode(key)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok())
+                .is_some();
+            if !valid {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "network.operator_messages authority key {} is not a hex ed25519 public key",
+                    key
+                )));
+            }
+        }
+        if self.max_per_sender_per_minute == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.operator_messages.max_per_sender_per_minute must be > 0".to_string(),
+            ));
+        }
+        if self.notice_pow_bits > 32 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.operator_messages.notice_pow_bits must be <= 32".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl BandwidthConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        // A per-second cap below the largest message would stall block relay
+        let floor = crate::network::message::MessageSizeLimits::MAX_MESSAGE_SIZE as u64 / 64;
+        for (name, limit) in [
+            ("max_upload_per_sec", self.max_upload_per_sec),
+            ("max_download_per_sec", self.max_download_per_sec),
+        ] {
+            if matches!(limit, Some(limit) if limit < floor) {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "network.bandwidth.{} must be >= {} bytes",
+                    name, floor
+                )));
+            }
+        }
+        for (name, limit) in [
+            ("max_upload_per_day", self.max_upload_per_day),
+            ("max_download_per_day", self.max_download_per_day),
+        ] {
+            if limit == Some(0) {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "network.bandwidth.{} must be > 0",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BlockServingConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        if self.historical_bytes_per_sec == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.block_serving.historical_bytes_per_sec must be > 0".to_string(),
+            ));
+        }
+        if self.max_concurrent_requests == 0 || self.max_queued_per_peer == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.block_serving request limits must be > 0".to_string(),
+            ));
+        }
         Ok(())
     }
 }
@@ -331,6 +1199,25 @@ impl PeerDiversityConfig {
                 "network.peer_diversity.max_connection_attempts_per_min must be > 0".to_string(),
             ));
         }
+        if self.max_outbound_per_prefix == 0 || self.max_outbound_per_asn == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.peer_diversity max_outbound_per_* values must be > 0".to_string(),
+            ));
+        }
+        if self.excess_rotation_interval.as_secs() < 10 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "network.peer_diversity.excess_rotation_interval must be >= 10 seconds"
+                    .to_string(),
+            ));
+        }
+        if let Some(path) = &self.asn_database {
+            if !path.exists() {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "network.peer_diversity.asn_database {} does not exist",
+                    path.display()
+                )));
+            }
+        }
         Ok(())
     }
 }
@@ -378,6 +1265,32 @@ impl StorageConfig {
                 "storage.block_cache_size must be >= 8MB".to_string(),
             ));
         }
+        if self.undo_depth < MAX_REORG_DEPTH {
+            return Err(NodeConfigValidationError::InvalidValue(format!(
+                "storage.undo_depth must be >= {} (the deepest reorg accepted)",
+                MAX_REORG_DEPTH
+            )));
+        }
+        if !self.prune_target_gb.is_finite() || self.prune_target_gb < 0.0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "storage.prune_target_gb must be >= 0".to_string(),
+            ));
+        }
+        if self.tx_index && self.prune_target_bytes().is_some() {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "storage.tx_index cannot be used with storage.prune_target_gb".to_string(),
+            ));
+        }
+        if self.address_index && !self.tx_index {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "storage.address_index requires storage.tx_index".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.compaction_threshold) {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "storage.compaction_threshold must be between 0 and 1".to_string(),
+            ));
+        }
         fs::create_dir_all(&self.db_path).map_err(|e| {
             NodeConfigValidationError::InvalidPath(format!(
                 "Cannot create storage.db_path {:?}: {e}",
@@ -452,6 +1365,91 @@ impl MempoolConfig {
     }
 }
 
+impl MiningConfig {
+    pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        use crate::mining::coinbase::{COINBASE_DUST_LIMIT, INITIAL_BLOCK_REWARD};
+        use wallet::quantum_wallet::Address;
+
+        if self.longpoll_timeout_secs == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "mining.longpoll_timeout_secs must be greater than 0".to_string(),
+            ));
+        }
+        self.stratum.validate()?;
+
+        let deployments = supernova_core::consensus::VersionBitsParams::default();
+        for name in &self.signal_deployments {
+            if deployments.deployment(name).is_none() {
+                return Err(NodeConfigValidationError::InvalidValue(format!(
+                    "mining.signal_deployments: unknown deployment '{name}'"
+                )));
+            }
+        }
+
+        for address in &self.payout_rotation {
+            Address::from_str(address).map_err(|e| {
+                NodeConfigValidationError::InvalidValue(format!(
+                    "mining.payout_rotation address '{address}' is invalid: {e}"
+                ))
+            })?;
+        }
+
+        let mut total_bps = 0u64;
+        let mut has_percent = false;
+        for split in &self.payout_splits {
+            Address::from_str(&split.address).map_err(|e| {
+                NodeConfigValidationError::InvalidValue(format!(
+                    "mining.payout_splits address '{}' is invalid: {e}",
+                    split.address
+                ))
+            })?;
+            match split.share {
+                PayoutShare::Fixed(amount) => {
+                    if amount < COINBASE_DUST_LIMIT {
+                        return Err(NodeConfigValidationError::InvalidValue(format!(
+                            "mining.payout_splits fixed amount {amount} for '{}' is below \
+                             the dust limit of {COINBASE_DUST_LIMIT}",
+                            split.address
+                        )));
+                    }
+                }
+                PayoutShare::Percent(percent) => {
+                    if !percent.is_finite() || percent <= 0.0 || percent > 100.0 {
+                        return Err(NodeConfigValidationError::InvalidValue(format!(
+                            "mining.payout_splits percent for '{}' must be in (0, 100]",
+                            split.address
+                        )));
+                    }
+                    let bps = split.share.basis_points().unwrap_or(0);
+                    if ((bps as f64) - percent * 100.0).abs() > 1e-6 {
+                        return Err(NodeConfigValidationError::InvalidValue(format!(
+                            "mining.payout_splits percent {percent} for '{}' has more than \
+                             two decimal places",
+                            split.address
+                        )));
+                    }
+                    if INITIAL_BLOCK_REWARD / 10_000 * bps < COINBASE_DUST_LIMIT {
+                        return Err(NodeConfigValidationError::InvalidValue(format!(
+                            "mining.payout_splits percent {percent} for '{}' yields a \
+                             dust-sized output",
+                            split.address
+                        )));
+                    }
+                    has_percent = true;
+                    total_bps += bps;
+                }
+            }
+        }
+        if has_percent && total_bps != 10_000 {
+            return Err(NodeConfigValidationError::InvalidValue(format!(
+                "mining.payout_splits percentages must total 100, got {}",
+                total_bps as f64 / 100.0
+            )));
+        }
+        Ok(())
+    }
+}
+
 impl GeneralConfig {
     pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
         if self.chain_id.trim().is_empty() {
@@ -475,6 +1473,11 @@ impl GeneralConfig {
 
 impl CheckpointConfig {
     pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
+        if self.snapshot_interval_blocks > 0 && self.max_snapshots == 0 {
+            return Err(NodeConfigValidationError::InvalidValue(
+                "checkpoint.max_snapshots must be > 0 when snapshots are enabled".to_string(),
+            ));
+        }
         if self.checkpoints_enabled {
             if self.checkpoint_interval.as_secs() < 60 {
                 return Err(NodeConfigValidationError::InvalidValue(
@@ -521,9 +1524,16 @@ impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             listen_addr: "/ip4/0.0.0.0/tcp/8000".to_string(),
+            listen_addrs: Vec::new(),
+            external_addrs: Vec::new(),
+            observed_addr_confirmations: default_observed_addr_confirmations(),
             max_peers: 50,
+            known_inventory_size: default_known_inventory_size(),
+            encrypted_transport: default_encrypted_transport(),
             bootstrap_nodes: vec![],
             peer_ping_interval: Duration::from_secs(20), // Faster pings for 2.5-min blocks
+            max_missed_pongs: 3,
+            max_ping_latency: Duration::from_secs(10),
             max_outbound_connections: 32,                // More connections for faster propagation
             max_inbound_connections: 128,
             ban_threshold: 100,
@@ -531,9 +1541,12 @@ impl Default for NetworkConfig {
 
             // New defaults
             key_path: None,
+            identity_rotation_grace: default_identity_rotation_grace(),
             network_id: "supernova-mainnet".to_string(),
             enable_mdns: true,
             enable_upnp: true,
+            upnp_lease: default_upnp_lease(),
+            nat_pmp_gateway: None,
             enable_peer_exchange: true,
             enable_nat_traversal: true,
             connection_timeout: Duration::from_secs(20), // Faster connection timeout
@@ -543,6 +1556,23 @@ impl Default for NetworkConfig {
             min_outbound_connections: 8,
             peer_diversity: PeerDiversityConfig::default(),
             pubsub_config: PubSubConfig::default(),
+            block_serving: BlockServingConfig::default(),
+            dns_seeds: DnsSeedsConfig::default(),
+            socks_proxy: None,
+            mempool_sync: MempoolSyncConfig::default(),
+            operator_messages: OperatorMessagesConfig::default(),
+            bandwidth: BandwidthConfig::default(),
+        }
+    }
+}
+
+impl Default for DnsSeedsConfig {
+    fn default() -> Self {
+        Self {
+            seeds: Vec::new(),
+            timeout: Duration::from_secs(10),
+            min_reresolve_interval: Duration::from_secs(10 * 60),
+            proxy_nameserver: SocketAddr::from(([1, 1, 1, 1], 53)),
         }
     }
 }
@@ -555,6 +1585,12 @@ impl Default for StorageConfig {
             cache_size: 512 * 1024 * 1024,
             max_open_files: 1000,
             block_cache_size: 32 * 1024 * 1024,
+            undo_depth: default_undo_depth(),
+            prune_target_gb: 0.0,
+            tx_index: false,
+            address_index: false,
+            compaction_interval_hours: default_compaction_interval_hours(),
+            compaction_threshold: default_compaction_threshold(),
         }
     }
 }
@@ -570,6 +1606,8 @@ impl Default for MempoolConfig {
             max_orphan_transactions: 100,
             enable_rbf: true,
             min_rbf_fee_increase: 10.0,
+            max_data_carrier_bytes: default_max_data_carrier_bytes(),
+            persist: default_persist_mempool(),
         }
     }
 }
@@ -599,6 +1637,9 @@ impl Default for GeneralConfig {
             enable_lightning: true,
             enable_quantum_security: true,
             enable_mining: true,
+            validation_tracing: false,
+            signature_cache_bytes: default_signature_cache_bytes(),
+            hybrid_signature_migration: false,
         }
     }
 }
@@ -611,6 +1652,8 @@ impl Default for CheckpointConfig {
             checkpoint_type: "Full".to_string(),
             data_dir: PathBuf::from("./checkpoints"),
             max_checkpoints: 7, // Keep a week of checkpoints
+            snapshot_interval_blocks: 0,
+            max_snapshots: default_max_snapshots(),
         }
     }
 }
@@ -645,6 +1688,48 @@ impl Default for PeerDiversityConfig {
             max_peers_per_region: 10,
             max_inbound_ratio: 3.0,
             max_connection_attempts_per_min: 5,
+            max_outbound_per_prefix: default_max_outbound_per_prefix(),
+            max_outbound_per_asn: default_max_outbound_per_asn(),
+            asn_database: None,
+            excess_rotation_interval: default_excess_rotation_interval(),
+        }
+    }
+}
+
+impl Default for MempoolSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            peers: 3,
+            min_fee_rate: 1,
+            max_entries: 5_000,
+            max_bytes: 32 * 1024 * 1024,
+            stagger: Duration::from_secs(5),
+            window: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl Default for OperatorMessagesConfig {
+    fn default() -> Self {
+        Self {
+            allowed_senders: Vec::new(),
+            authority_keys: Vec::new(),
+            authority_key_file: None,
+            max_per_sender_per_minute: 5,
+            notice_pow_bits: 20,
+        }
+    }
+}
+
+impl Default for BlockServingConfig {
+    fn default() -> Self {
+        Self {
+            historical_bytes_per_sec: 4 * 1024 * 1024,
+            max_concurrent_requests: 4,
+            max_queued_per_peer: 8,
+            recent_depth: 576, // one day of 2.5-minute blocks
+            busy_retry_after: Duration::from_secs(30),
         }
     }
 }
@@ -682,14 +1767,54 @@ impl NodeConfig {
     /// silently loaded `./config.toml` instead. The explicit-path branch
     /// here closes that foot-gun.
     pub fn load(explicit_path: Option<&str>) -> Result<Self, ConfigError> {
-        let mut config = Config::builder();
-        config = config.add_source(Config::try_from(&Self::default())?);
+        Self::load_with_overrides(explicit_path, &[])
+    }
+
+    /// Load configuration with `--set section.key=value` overrides applied as
+    /// the final layer. See [`layered`] for the full precedence order.
+    pub fn load_with_overrides(
+        explicit_path: Option<&str>,
+        cli_overrides: &[String],
+    ) -> Result<Self, ConfigError> {
+        let resolved = Self::resolve(explicit_path, cli_overrides)?;
+        let mut config = resolved.config;
+
+        if config.source_path.is_none() && explicit_path.is_none() {
+            warn!("No configuration file found, using defaults");
+            let default_path = PathBuf::from("config.toml");
+            if let Err(e) = Self::create_default_config(&default_path) {
+                warn!("Failed to create default config file: {}", e);
+            }
+        }
+
+        config.cli_overrides = cli_overrides.to_vec();
+        Self::ensure_directories(&config)?;
+        if let Err(e) = config.validate() {
+            return Err(ConfigError::Message(format!("Configuration validation error: {e}")));
+        }
+
+        // Log loaded configuration for debugging
+        info!("Configuration loaded:");
+        info!("  Network listen_addr: {}", config.network.listen_addr);
+        info!("  Bootstrap nodes: {} configured", config.network.bootstrap_nodes.len());
+        for (i, node) in config.network.bootstrap_nodes.iter().enumerate() {
+            info!("    [{}] {}", i, node);
+        }
+
+        Ok(config)
+    }
 
+    /// Resolve the configuration layers (defaults → file → environment → CLI)
+    /// without validating or touching the filesystem beyond reading the
+    /// config file. Backs `--dump-config`, which must describe the effective
+    /// configuration even when it would fail validation.
+    pub fn resolve(
+        explicit_path: Option<&str>,
+        cli_overrides: &[String],
+    ) -> Result<ResolvedConfig, ConfigError> {
         // Remember the file we actually loaded from so `reload()` /
         // `watch_config()` can operate on it instead of re-searching.
-        let resolved_source: Option<PathBuf>;
-
-        if let Some(path) = explicit_path {
+        let resolved_source: Option<PathBuf> = if let Some(path) = explicit_path {
             let pb = PathBuf::from(path);
             if !pb.exists() {
                 return Err(ConfigError::Message(format!(
@@ -700,83 +1825,39 @@ impl NodeConfig {
                         .unwrap_or_else(|_| "<unknown>".into())
                 )));
             }
-            info!("Loading configuration from: {:?}", pb);
-            // The `config` crate's `File::with_name` strips the extension
-            // and probes for any supported format. Trim the trailing `.toml`
-            // so an explicit path like `foo.toml` resolves to `foo` (matching
-            // the historical search behavior below).
-            let stem = path.trim_end_matches(".toml");
-            config = config.add_source(File::with_name(stem));
-            resolved_source = Some(pb);
+            Some(pb)
         } else {
             // Try multiple config file locations
-            let config_paths = vec![
+            let config_paths = [
                 PathBuf::from("config.toml"),           // Root directory
                 PathBuf::from("config/node.toml"),      // Legacy location
                 PathBuf::from(".supernova/node.toml"),  // User directory
             ];
+            config_paths.into_iter().find(|p| p.exists())
+        };
 
-            let mut config_loaded = None;
-            for config_path in &config_paths {
-                if config_path.exists() {
-                    info!("Loading configuration from: {:?}", config_path);
-                    if let Some(config_str) = config_path.to_str() {
-                        config = config.add_source(File::with_name(config_str.trim_end_matches(".toml")));
-                        config_loaded = Some(config_path.clone());
-                        break;
-                    }
-                }
-            }
+        let mut loader =
+            LayeredConfigLoader::new().map_err(|e| ConfigError::Message(e.to_string()))?;
+        if let Some(path) = &resolved_source {
+            info!("Loading configuration from: {:?}", path);
+            loader = loader
+                .with_file(path)
+                .map_err(|e| ConfigError::Message(e.to_string()))?;
+        }
 
-            if config_loaded.is_none() {
-                warn!("No configuration file found, using defaults");
-                let default_path = PathBuf::from("config.toml");
-                if let Err(e) = Self::create_default_config(&default_path) {
-                    warn!("Failed to create default config file: {}", e);
-                }
-            }
-            resolved_source = config_loaded;
-        }
-
-        // Environment overrides.
-        //
-        // Every top-level `NodeConfig` field is a nested struct, and the leaf
-        // field names themselves contain underscores (`max_peers`,
-        // `listen_addr`, `db_path`, `bootstrap_nodes`, ...). A single `_`
-        // separator therefore cannot both strip the `SUPERNOVA` prefix AND
-        // address a nested leaf: `SUPERNOVA_NETWORK_MAX_PEERS` would split into
-        // the non-existent path `network.max.peers` and be silently discarded.
-        //
-        // Use a distinct nested separator so the two roles never collide:
-        //   * `prefix_separator("_")` strips the `SUPERNOVA_` prefix.
-        //   * `separator("__")` delimits nested path segments.
-        // Thus `SUPERNOVA_NETWORK__MAX_PEERS` maps to `network.max_peers` and
-        // `SUPERNOVA_STORAGE__DB_PATH` maps to `storage.db_path`.
-        config = config.add_source(
-            Environment::with_prefix("SUPERNOVA")
-                .prefix_separator("_")
-                .separator("__")
-                .try_parsing(true),
-        );
+        // Environment overrides use `SUPERNOVA__SECTION__KEY`. Leaf names
+        // contain underscores (`max_peers`), so a distinct `__` separator is
+        // what lets `SUPERNOVA__NETWORK__MAX_PEERS` address `network.max_peers`.
+        let mut resolved = loader
+            .with_env(std::env::vars())
+            .and_then(|l| l.with_cli_overrides(cli_overrides))
+            .and_then(|l| l.resolve())
+            .map_err(|e| ConfigError::Message(e.to_string()))?;
 
-        let mut config: NodeConfig = config.build()?.try_deserialize()?;
         // `source_path` is `#[serde(skip)]`, so it is always `None` after
         // deserialization; record the file we resolved above.
-        config.source_path = resolved_source;
-        Self::ensure_directories(&config)?;
-        if let Err(e) = config.validate() {
-            return Err(ConfigError::Message(format!("Configuration validation error: {e}")));
-        }
-
-        // Log loaded configuration for debugging
-        info!("Configuration loaded:");
-        info!("  Network listen_addr: {}", config.network.listen_addr);
-        info!("  Bootstrap nodes: {} configured", config.network.bootstrap_nodes.len());
-        for (i, node) in config.network.bootstrap_nodes.iter().enumerate() {
-            info!("    [{}] {}", i, node);
-        }
-
-        Ok(config)
+        resolved.config.source_path = resolved_source;
+        Ok(resolved)
     }
 
     pub async fn reload(&mut self) -> Result<(), ConfigError> {
@@ -798,7 +1879,7 @@ impl NodeConfig {
                 "Cannot reload configuration: source path {source:?} is not valid UTF-8"
             ))
         })?;
-        match Self::load(Some(source_str)) {
+        match Self::load_with_overrides(Some(source_str), &self.cli_overrides) {
             Ok(new_config) => {
                 if let Err(e) = new_config.validate() {
                     error!("Invalid configuration: {}", e);
@@ -807,8 +1888,15 @@ impl NodeConfig {
                         e
                     )));
                 }
+                let changes = diff_configs(self, &new_config);
+                for change in &changes {
+                    info!("Configuration changed: {}", change);
+                }
                 *self = new_config;
-                info!("Configuration reloaded successfully");
+                info!(
+                    "Configuration reloaded successfully ({} keys changed)",
+                    changes.len()
+                );
                 Ok(())
             }
             Err(e) => {
@@ -902,23 +1990,43 @@ impl NodeConfig {
         Ok(())
     }
 
+    /// Effective relay settings: the `[relay]` profile with overrides applied.
+    pub fn relay_policy(&self) -> RelayPolicy {
+        self.relay.effective(&self.mempool)
+    }
+
+    /// Mempool settings with the relay policy applied, as used at startup.
+    pub fn effective_mempool(&self) -> MempoolConfig {
+        let policy = self.relay_policy();
+        MempoolConfig {
+            max_size: policy.max_mempool_size,
+            min_fee_rate: policy.min_relay_fee,
+            enable_rbf: policy.enable_rbf,
+            max_orphan_transactions: policy.max_orphan_transactions,
+            ..self.mempool.clone()
+        }
+    }
+
     pub fn validate(&self) -> Result<(), NodeConfigValidationError> {
         // Validate per-module config first
         self.network.validate()?;
         self.storage.validate()?;
-        self.mempool.validate()?;
+        self.effective_mempool().validate()?;
         self.backup.validate()?;
         self.node.validate()?;
         self.checkpoint.validate()?;
+        self.mining.validate()?;
+        self.clock.validate()?;
+        self.sync.validate()?;
 
         // Cross-field validation
-        let p2p_port = parse_libp2p_listen_port(&self.network.listen_addr)?;
+        let p2p_ports = self.network.listen_ports()?;
         if self.api.port == 0 {
             return Err(NodeConfigValidationError::InvalidPort(
                 "api.port cannot be 0".to_string(),
             ));
         }
-        if p2p_port == self.api.port {
+        if let Some(p2p_port) = p2p_ports.iter().find(|p| **p == self.api.port) {
             return Err(NodeConfigValidationError::PortConflict(format!(
                 "network.listen_addr TCP port ({p2p_port}) must differ from api.port ({})",
                 self.api.port
@@ -936,10 +2044,10 @@ impl NodeConfig {
                     self.api.port
                 )));
             }
-            if metrics_port == p2p_port {
+            if p2p_ports.contains(&metrics_port) {
                 return Err(NodeConfigValidationError::PortConflict(format!(
                     "node.metrics_port ({metrics_port}) must differ from \
-                     network.listen_addr TCP port ({p2p_port})"
+                     network.listen_addr TCP port ({metrics_port})"
                 )));
             }
         }
@@ -1265,3 +2373,105 @@ mod port_conflict_tests {
             .expect("disabled metrics must not trigger a port conflict");
     }
 }
+
+#[cfg(test)]
+mod mining_payout_tests {
+    use super::*;
+    use wallet::quantum_wallet::Address;
+
+    fn address(seed: u8) -> String {
+        Address::from_public_key(&[seed; 64]).unwrap().to_string()
+    }
+
+    fn split(seed: u8, share: PayoutShare) -> PayoutSplit {
+        PayoutSplit {
+            address: address(seed),
+            share,
+        }
+    }
+
+    #[test]
+    fn percent_splits_must_total_100() {
+        let mut config = MiningConfig {
+            payout_rotation: vec![address(1)],
+            payout_splits: vec![
+                split(2, PayoutShare::Percent(70.0)),
+                split(3, PayoutShare::Percent(30.0)),
+            ],
+            signal_deployments: Vec::new(),
+            ..MiningConfig::default()
+        };
+        config.validate().expect("70/30 split must validate");
+
+        config.payout_splits[1].share = PayoutShare::Percent(20.0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn dust_sized_split_is_rejected() {
+        let fixed = MiningConfig {
+            payout_rotation: Vec::new(),
+            payout_splits: vec![split(2, PayoutShare::Fixed(100))],
+            signal_deployments: Vec::new(),
+            ..MiningConfig::default()
+        };
+        let err = fixed.validate().unwrap_err().to_string();
+        assert!(err.contains("dust"), "{err}");
+    }
+
+    #[test]
+    fn splits_deserialize_from_toml() {
+        let toml = format!(
+            "payout_rotation = [\"{}\"]\n\
+             payout_splits = [{{ address = \"{}\", percent = 70.0 }}, \
+             {{ address = \"{}\", fixed = 100000 }}]\n",
+            address(1),
+            address(2),
+            address(3)
+        );
+        let config: MiningConfig = toml::from_str(&toml).unwrap();
+        assert_eq!(config.payout_splits[0].share, PayoutShare::Percent(70.0));
+        assert_eq!(config.payout_splits[1].share, PayoutShare::Fixed(100_000));
+    }
+
+    #[test]
+    fn snapshot_sync_needs_a_trusted_snapshot() {
+        let mut config: SyncConfig = toml::from_str("mode = \"snapshot\"").unwrap();
+        assert!(config.validate().is_err());
+
+        config.snapshot_height = 5000;
+        config.snapshot_hash = hex::encode([1u8; 32]);
+        config.snapshot_utxo_commitment = hex::encode([2u8; 16]);
+        assert!(config.validate().is_err());
+
+        config.snapshot_utxo_commitment = hex::encode([2u8; 32]);
+        config.validate().unwrap();
+        let trusted = config.trusted_snapshot().unwrap();
+        assert_eq!(trusted.tip_hash, [1u8; 32]);
+        assert_eq!(trusted.utxo_commitment, [2u8; 32]);
+
+        // Full sync ignores the snapshot settings
+        config.mode = "full".parse().unwrap();
+        assert!(config.trusted_snapshot().is_none());
+    }
+
+    #[test]
+    fn assume_valid_defaults_to_the_checkpoint_and_can_be_disabled() {
+        let mut config = SyncConfig::default();
+        assert_eq!(
+            config.assume_valid(),
+            BlockValidationConfig::default().assume_valid
+        );
+
+        config.assume_valid = Some(hex::encode([7u8; 32]));
+        config.validate().unwrap();
+        assert_eq!(config.assume_valid(), Some([7u8; 32]));
+
+        config.assume_valid = Some("0".to_string());
+        config.validate().unwrap();
+        assert_eq!(config.assume_valid(), None);
+
+        config.assume_valid = Some(hex::encode([7u8; 16]));
+        assert!(config.validate().is_err());
+    }
+}
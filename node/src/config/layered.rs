@@ -0,0 +1,827 @@
+//! Layered configuration resolution with per-field provenance.
+//!
+//! The effective `NodeConfig` is built from four layers, each overriding the
+//! one before it:
+//!
+//! 1. compiled-in defaults (`NodeConfig::default()`)
+//! 2. the config file (`config.toml` or the `--config` path)
+//! 3. environment variables using the `SUPERNOVA__SECTION__KEY` scheme
+//!    (`SUPERNOVA__NETWORK__MAX_PEERS=64`, nested sections add another
+//!    `__` segment: `SUPERNOVA__NETWORK__PEER_DIVERSITY__ENABLED=false`).
+//!    The legacy single-underscore prefix (`SUPERNOVA_NETWORK__MAX_PEERS`)
+//!    is still accepted.
+//! 4. `--set section.key=value` command-line overrides
+//!
+//! Every leaf remembers which layer supplied it, so `--dump-config` can show
+//! the operator exactly why a value is what it is, and hot reload can report
+//! precisely which keys changed. Keys that do not exist in the schema are
+//! rejected instead of being silently ignored — a typo such as `max_peer`
+//! used to leave the default in place with no indication anything was wrong.
+
+use super::NodeConfig;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Prefix for environment overrides (`SUPERNOVA__SECTION__KEY`).
+pub const ENV_PREFIX: &str = "SUPERNOVA__";
+
+/// Legacy prefix form (`SUPERNOVA_SECTION__KEY`) kept for existing deployments.
+const LEGACY_ENV_PREFIX: &str = "SUPERNOVA_";
+
+/// Separator between nested path segments in environment variable names.
+const ENV_SEPARATOR: &str = "__";
+
+/// Placeholder printed in place of secret values.
+const MASKED: &str = "********";
+
+/// Leaf names whose values must never be printed or logged.
+const SECRET_KEYS: &[&str] = &["api_keys", "password", "secret", "token", "private_key"];
+
+/// Where an effective configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Compiled-in default.
+    Default,
+    /// The configuration file at this path.
+    File(PathBuf),
+    /// The named environment variable.
+    Env(String),
+    /// A `--set` command-line override.
+    Cli(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Env(var) => write!(f, "env {}", var),
+            ConfigSource::Cli(arg) => write!(f, "cli --set {}", arg),
+        }
+    }
+}
+
+/// Errors raised while resolving the configuration layers.
+#[derive(Debug, Error)]
+pub enum ConfigLayerError {
+    #[error("Unknown configuration key `{path}` from {source_desc}{}", suggestion_suffix(.suggestion))]
+    UnknownKey {
+        path: String,
+        source_desc: String,
+        suggestion: Option<String>,
+    },
+
+    #[error("Environment variable {var}={value:?} cannot be used for `{path}`: expected {expected}")]
+    EnvCoercion {
+        var: String,
+        path: String,
+        value: String,
+        expected: &'static str,
+    },
+
+    #[error("Command-line override --set {arg} is invalid: expected {expected}")]
+    CliCoercion { arg: String, expected: &'static str },
+
+    #[error("Malformed command-line override `{0}`: expected section.key=value")]
+    MalformedOverride(String),
+
+    #[error("Failed to read configuration file {path}: {reason}")]
+    Read { path: PathBuf, reason: String },
+
+    #[error("Failed to parse configuration file {path}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+
+    #[error("Effective configuration is invalid: {0}")]
+    Deserialize(String),
+}
+
+fn suggestion_suffix(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(s) => format!(" (did you mean `{}`?)", s),
+        None => String::new(),
+    }
+}
+
+/// A single key whose effective value differs between two configurations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let render = |v: &Option<Value>| match v {
+            Some(v) => render_value(&self.path, v),
+            None => "<unset>".to_string(),
+        };
+        write!(f, "{}: {} -> {}", self.path, render(&self.old), render(&self.new))
+    }
+}
+
+/// The merged configuration together with the provenance of every leaf.
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    pub config: NodeConfig,
+    values: BTreeMap<String, Value>,
+    provenance: BTreeMap<String, ConfigSource>,
+}
+
+impl ResolvedConfig {
+    /// Layer that supplied the value at a dotted path (e.g. `network.max_peers`).
+    pub fn source_of(&self, path: &str) -> Option<&ConfigSource> {
+        self.provenance.get(path)
+    }
+
+    /// Keys whose effective value differs from `newer`.
+    pub fn diff(&self, newer: &ResolvedConfig) -> Vec<ConfigChange> {
+        diff_flattened(&self.values, &newer.values)
+    }
+
+    /// Render the effective configuration as TOML, each value annotated with
+    /// the layer it came from and secrets masked.
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# Effective Supernova node configuration\n");
+        out.push_str("# Each value is annotated with the layer that supplied it.\n");
+
+        // Group leaves by their parent table so nested sections such as
+        // `network.peer_diversity` get their own `[table]` header.
+        let mut tables: BTreeMap<&str, Vec<(&str, &Value)>> = BTreeMap::new();
+        for (path, value) in &self.values {
+            let (table, key) = path.rsplit_once('.').unwrap_or(("", path.as_str()));
+            tables.entry(table).or_default().push((key, value));
+        }
+
+        for (table, entries) in tables {
+            if !table.is_empty() {
+                out.push_str(&format!("\n[{}]\n", table));
+            }
+            for (key, value) in entries {
+                let path = if table.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", table, key)
+                };
+                let source = self
+                    .provenance
+                    .get(&path)
+                    .cloned()
+                    .unwrap_or(ConfigSource::Default);
+                if value.is_null() {
+                    out.push_str(&format!("# {} = <unset>  # {}\n", key, source));
+                } else {
+                    out.push_str(&format!(
+                        "{} = {}  # {}\n",
+                        key,
+                        render_value(&path, value),
+                        source
+                    ));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Builder that applies the configuration layers in order.
+pub struct LayeredConfigLoader {
+    schema: Value,
+    merged: Value,
+    provenance: BTreeMap<String, ConfigSource>,
+}
+
+impl LayeredConfigLoader {
+    /// Start from the compiled-in defaults.
+    pub fn new() -> Result<Self, ConfigLayerError> {
+        let merged = serde_json::to_value(NodeConfig::default())
+            .map_err(|e| ConfigLayerError::Deserialize(e.to_string()))?;
+        let schema = schema_template()?;
+
+        let mut provenance = BTreeMap::new();
+        let mut leaves = BTreeMap::new();
+        flatten("", &schema, &mut leaves);
+        for path in leaves.into_keys() {
+            provenance.insert(path, ConfigSource::Default);
+        }
+
+        Ok(Self {
+            schema,
+            merged,
+            provenance,
+        })
+    }
+
+    /// Apply a TOML configuration file.
+    pub fn with_file(self, path: &Path) -> Result<Self, ConfigLayerError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| ConfigLayerError::Read {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        self.with_file_contents(path, &contents)
+    }
+
+    /// Apply TOML text as if it had been read from `path`.
+    pub fn with_file_contents(mut self, path: &Path, contents: &str) -> Result<Self, ConfigLayerError> {
+        let parsed: toml::Value = toml::from_str(contents).map_err(|e| ConfigLayerError::Parse {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        let layer =
+            serde_json::to_value(parsed).map_err(|e| ConfigLayerError::Parse {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+
+        let source_desc = format!("file {}", path.display());
+        check_known_keys("", &layer, &self.schema, &source_desc)?;
+
+        let mut touched = BTreeMap::new();
+        flatten("", &layer, &mut touched);
+        merge(&mut self.merged, layer);
+        for path_key in touched.into_keys() {
+            self.provenance
+                .insert(path_key, ConfigSource::File(path.to_path_buf()));
+        }
+        Ok(self)
+    }
+
+    /// Apply environment overrides. Variables that do not use the
+    /// `SUPERNOVA__SECTION__KEY` shape are ignored.
+    pub fn with_env<I>(mut self, vars: I) -> Result<Self, ConfigLayerError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        // Sort so the error reported for several bad variables is stable.
+        let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+        vars.sort();
+
+        for (var, raw) in vars {
+            let Some(path) = env_var_path(&var) else {
+                continue;
+            };
+            let source_desc = format!("environment variable {}", var);
+            let expected = schema_leaf(&self.schema, &path, &source_desc)?;
+            let value = coerce(&raw, expected).map_err(|expected| ConfigLayerError::EnvCoercion {
+                var: var.clone(),
+                path: path.clone(),
+                value: raw.clone(),
+                expected,
+            })?;
+            set_path(&mut self.merged, &path, value);
+            self.provenance.insert(path, ConfigSource::Env(var));
+        }
+        Ok(self)
+    }
+
+    /// Apply `--set section.key=value` overrides.
+    pub fn with_cli_overrides(mut self, overrides: &[String]) -> Result<Self, ConfigLayerError> {
+        for arg in overrides {
+            let (path, raw) = arg
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .filter(|(k, _)| !k.is_empty())
+                .ok_or_else(|| ConfigLayerError::MalformedOverride(arg.clone()))?;
+            let source_desc = format!("command-line override --set {}", arg);
+            let expected = schema_leaf(&self.schema, path, &source_desc)?;
+            let value = coerce(raw, expected).map_err(|expected| ConfigLayerError::CliCoercion {
+                arg: arg.clone(),
+                expected,
+            })?;
+            set_path(&mut self.merged, path, value);
+            self.provenance
+                .insert(path.to_string(), ConfigSource::Cli(arg.clone()));
+        }
+        Ok(self)
+    }
+
+    /// Deserialize the merged tree into a typed `NodeConfig`.
+    pub fn resolve(self) -> Result<ResolvedConfig, ConfigLayerError> {
+        let config: NodeConfig = serde_json::from_value(self.merged.clone())
+            .map_err(|e| ConfigLayerError::Deserialize(e.to_string()))?;
+
+        // Flatten the schema (so unset optional keys still appear in dumps)
+        // and overlay the effective values.
+        let mut values = BTreeMap::new();
+        flatten("", &self.schema, &mut values);
+        for value in values.values_mut() {
+            *value = Value::Null;
+        }
+        flatten("", &self.merged, &mut values);
+
+        Ok(ResolvedConfig {
+            config,
+            values,
+            provenance: self.provenance,
+        })
+    }
+}
+
+/// Keys whose effective value differs between two typed configurations.
+/// Backs the hot-reload change report.
+pub fn diff_configs(old: &NodeConfig, new: &NodeConfig) -> Vec<ConfigChange> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    let mut old_values = BTreeMap::new();
+    let mut new_values = BTreeMap::new();
+    flatten("", &old, &mut old_values);
+    flatten("", &new, &mut new_values);
+    diff_flattened(&old_values, &new_values)
+}
+
+/// Whether the value at this dotted path must be masked in output.
+pub fn is_secret_path(path: &str) -> bool {
+    let leaf = path.rsplit('.').next().unwrap_or(path);
+    SECRET_KEYS.iter().any(|secret| leaf.contains(secret))
+}
+
+/// Default config with every optional field populated, so that keys which
+/// serialize to nothing by default (`api.api_keys`) are still known to the
+/// unknown-key check and have a type for environment coercion. New
+/// `skip_serializing_if` fields must be populated here as well.
+fn schema_template() -> Result<Value, ConfigLayerError> {
+    let mut template = NodeConfig::default();
+    template.api.api_keys = Some(Vec::new());
+    serde_json::to_value(template).map_err(|e| ConfigLayerError::Deserialize(e.to_string()))
+}
+
+fn env_var_path(var: &str) -> Option<String> {
+    let rest = var
+        .strip_prefix(ENV_PREFIX)
+        .or_else(|| var.strip_prefix(LEGACY_ENV_PREFIX))?;
+    if !rest.contains(ENV_SEPARATOR) {
+        return None;
+    }
+    Some(
+        rest.split(ENV_SEPARATOR)
+            .map(|segment| segment.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .join("."),
+    )
+}
+
+/// Look up the schema value for a leaf path, rejecting unknown keys.
+fn schema_leaf<'a>(
+    schema: &'a Value,
+    path: &str,
+    source_desc: &str,
+) -> Result<&'a Value, ConfigLayerError> {
+    let mut current = schema;
+    let mut walked = Vec::new();
+    for segment in path.split('.') {
+        let table = current.as_object().ok_or_else(|| ConfigLayerError::UnknownKey {
+            path: path.to_string(),
+            source_desc: source_desc.to_string(),
+            suggestion: None,
+        })?;
+        current = match table.get(segment) {
+            Some(v) => v,
+            None => {
+                return Err(unknown_key(table, &walked, segment, source_desc));
+            }
+        };
+        walked.push(segment);
+    }
+    if current.is_object() {
+        // Whole sections cannot be replaced from a single string.
+        return Err(ConfigLayerError::UnknownKey {
+            path: path.to_string(),
+            source_desc: source_desc.to_string(),
+            suggestion: current
+                .as_object()
+                .and_then(|t| t.keys().next())
+                .map(|k| format!("{}.{}", path, k)),
+        });
+    }
+    Ok(current)
+}
+
+/// Recursively reject keys in `layer` that are not present in `schema`.
+fn check_known_keys(
+    prefix: &str,
+    layer: &Value,
+    schema: &Value,
+    source_desc: &str,
+) -> Result<(), ConfigLayerError> {
+    let (Some(layer_table), Some(schema_table)) = (layer.as_object(), schema.as_object()) else {
+        return Ok(());
+    };
+    for (key, value) in layer_table {
+        match schema_table.get(key) {
+            Some(schema_value) => {
+                let nested = join_path(prefix, key);
+                check_known_keys(&nested, value, schema_value, source_desc)?;
+            }
+            None => {
+                let walked: Vec<&str> = if prefix.is_empty() {
+                    Vec::new()
+                } else {
+                    prefix.split('.').collect()
+                };
+                return Err(unknown_key(schema_table, &walked, key, source_desc));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn unknown_key(
+    siblings: &Map<String, Value>,
+    walked: &[&str],
+    key: &str,
+    source_desc: &str,
+) -> ConfigLayerError {
+    let prefix = walked.join(".");
+    ConfigLayerError::UnknownKey {
+        path: join_path(&prefix, key),
+        source_desc: source_desc.to_string(),
+        suggestion: closest_key(key, siblings.keys()).map(|k| join_path(&prefix, k)),
+    }
+}
+
+/// Nearest sibling key by edit distance, if it is plausibly a typo.
+fn closest_key<'a>(key: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+    let max_distance = (key.len() / 3).max(2);
+    candidates
+        .map(|c| (edit_distance(key, c), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c.as_str())
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b_chars.len() + 1];
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        prev = current;
+    }
+    prev[b_chars.len()]
+}
+
+/// Convert a raw string to the JSON type of the schema slot it targets.
+fn coerce(raw: &str, expected: &Value) -> Result<Value, &'static str> {
+    match expected {
+        Value::Bool(_) => match raw.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(Value::Bool(false)),
+            _ => Err("a boolean (true/false)"),
+        },
+        Value::Number(n) if n.is_f64() => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or("a number"),
+        Value::Number(n) if n.is_i64() && n.as_i64().is_some_and(|v| v < 0) => raw
+            .parse::<i64>()
+            .map(|v| Value::Number(v.into()))
+            .map_err(|_| "an integer"),
+        Value::Number(_) => raw
+            .parse::<u64>()
+            .map(|v| Value::Number(v.into()))
+            .or_else(|_| raw.parse::<i64>().map(|v| Value::Number(v.into())))
+            .map_err(|_| "an integer"),
+        Value::Array(items) => {
+            if raw.trim().is_empty() {
+                return Ok(Value::Array(Vec::new()));
+            }
+            let element = items.first().cloned().unwrap_or(Value::String(String::new()));
+            raw.split(',')
+                .map(|part| coerce(part.trim(), &element))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array)
+                .map_err(|_| "a comma-separated list")
+        }
+        Value::Object(_) => Err("a scalar value, not a section"),
+        Value::String(_) | Value::Null => Ok(Value::String(raw.to_string())),
+    }
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base_table), Value::Object(layer_table)) => {
+            for (key, value) in layer_table {
+                match base_table.get_mut(&key) {
+                    Some(existing) if existing.is_object() && value.is_object() => {
+                        merge(existing, value)
+                    }
+                    _ => {
+                        base_table.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+fn set_path(root: &mut Value, path: &str, value: Value) {
+    let mut current = root;
+    let segments: Vec<&str> = path.split('.').collect();
+    for (i, segment) in segments.iter().enumerate() {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
+        }
+        let Value::Object(table) = current else {
+            return;
+        };
+        if i + 1 == segments.len() {
+            table.insert((*segment).to_string(), value);
+            return;
+        }
+        current = table
+            .entry((*segment).to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+}
+
+/// Flatten a JSON tree into dotted leaf paths. Arrays are leaves.
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(table) => {
+            for (key, nested) in table {
+                flatten(&join_path(prefix, key), nested, out);
+            }
+        }
+        leaf => {
+            out.insert(prefix.to_string(), leaf.clone());
+        }
+    }
+}
+
+fn diff_flattened(
+    old: &BTreeMap<String, Value>,
+    new: &BTreeMap<String, Value>,
+) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    for (path, old_value) in old {
+        match new.get(path) {
+            Some(new_value) if new_value == old_value => {}
+            new_value => changes.push(ConfigChange {
+                path: path.clone(),
+                old: Some(old_value.clone()),
+                new: new_value.cloned(),
+            }),
+        }
+    }
+    for (path, new_value) in new {
+        if !old.contains_key(path) {
+            changes.push(ConfigChange {
+                path: path.clone(),
+                old: None,
+                new: Some(new_value.clone()),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn join_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", prefix, key)
+    }
+}
+
+fn render_value(path: &str, value: &Value) -> String {
+    if is_secret_path(path) {
+        return match value {
+            Value::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|_| format!("\"{}\"", MASKED))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Null => "<unset>".to_string(),
+            _ => format!("\"{}\"", MASKED),
+        };
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(contents: &str) -> LayeredConfigLoader {
+        LayeredConfigLoader::new()
+            .unwrap()
+            .with_file_contents(Path::new("test.toml"), contents)
+            .unwrap()
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn each_layer_overrides_the_previous() {
+        let resolved = file("[network]\nmax_peers = 60\nban_threshold = 50\n[storage]\ncache_size = 1\n")
+            .with_env(env(&[
+                ("SUPERNOVA__NETWORK__MAX_PEERS", "70"),
+                ("SUPERNOVA__STORAGE__CACHE_SIZE", "2"),
+            ]))
+            .unwrap()
+            .with_cli_overrides(&["network.max_peers=80".to_string()])
+            .unwrap()
+            .resolve()
+            .unwrap();
+
+        assert_eq!(resolved.config.network.max_peers, 80);
+        assert_eq!(resolved.config.storage.cache_size, 2);
+        assert_eq!(resolved.config.network.ban_threshold, 50);
+        assert_eq!(
+            resolved.config.mempool.max_size,
+            NodeConfig::default().mempool.max_size
+        );
+    }
+
+    #[test]
+    fn provenance_annotations_name_each_layer() {
+        let resolved = file("[network]\nban_threshold = 50\n")
+            .with_env(env(&[("SUPERNOVA__STORAGE__CACHE_SIZE", "2")]))
+            .unwrap()
+            .with_cli_overrides(&["network.max_peers=80".to_string()])
+            .unwrap()
+            .resolve()
+            .unwrap();
+
+        assert_eq!(resolved.source_of("mempool.max_size"), Some(&ConfigSource::Default));
+        assert_eq!(
+            resolved.source_of("network.ban_threshold"),
+            Some(&ConfigSource::File(PathBuf::from("test.toml")))
+        );
+        assert_eq!(
+            resolved.source_of("storage.cache_size"),
+            Some(&ConfigSource::Env("SUPERNOVA__STORAGE__CACHE_SIZE".to_string()))
+        );
+        assert_eq!(
+            resolved.source_of("network.max_peers"),
+            Some(&ConfigSource::Cli("network.max_peers=80".to_string()))
+        );
+
+        let dump = resolved.dump();
+        assert!(dump.contains("[network]"));
+        assert!(dump.contains("max_peers = 80  # cli --set network.max_peers=80"));
+        assert!(dump.contains("ban_threshold = 50  # file test.toml"));
+        assert!(dump.contains("cache_size = 2  # env SUPERNOVA__STORAGE__CACHE_SIZE"));
+        assert!(dump.contains("[network.peer_diversity]"));
+    }
+
+    #[test]
+    fn legacy_env_prefix_still_applies() {
+        let resolved = LayeredConfigLoader::new()
+            .unwrap()
+            .with_env(env(&[
+                ("SUPERNOVA_NETWORK__MAX_PEERS", "99"),
+                ("SUPERNOVA_LOG", "ignored: not a config path"),
+            ]))
+            .unwrap()
+            .resolve()
+            .unwrap();
+        assert_eq!(resolved.config.network.max_peers, 99);
+    }
+
+    #[test]
+    fn unknown_file_key_is_rejected_with_suggestion() {
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_file_contents(Path::new("test.toml"), "[network]\nmax_peer = 10\n")
+            .err()
+            .expect("unknown key must be rejected");
+        match &err {
+            ConfigLayerError::UnknownKey { path, suggestion, .. } => {
+                assert_eq!(path, "network.max_peer");
+                assert_eq!(suggestion.as_deref(), Some("network.max_peers"));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert!(err.to_string().contains("did you mean `network.max_peers`"));
+
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_file_contents(Path::new("test.toml"), "[netwrok]\nmax_peers = 10\n")
+            .err()
+            .expect("unknown section must be rejected");
+        assert!(err.to_string().contains("did you mean `network`"));
+    }
+
+    #[test]
+    fn unknown_env_and_cli_keys_are_rejected() {
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_env(env(&[("SUPERNOVA__MEMPOOL__MAX_SIZ", "10")]))
+            .err()
+            .expect("unknown env key must be rejected");
+        assert!(err.to_string().contains("SUPERNOVA__MEMPOOL__MAX_SIZ"));
+        assert!(err.to_string().contains("mempool.max_size"));
+
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_cli_overrides(&["storage.nope=1".to_string()])
+            .err()
+            .expect("unknown cli key must be rejected");
+        assert!(matches!(err, ConfigLayerError::UnknownKey { .. }));
+    }
+
+    #[test]
+    fn env_coercion_error_names_the_variable() {
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_env(env(&[("SUPERNOVA__NETWORK__MAX_PEERS", "lots")]))
+            .err()
+            .expect("non-integer must be rejected");
+        match &err {
+            ConfigLayerError::EnvCoercion { var, expected, .. } => {
+                assert_eq!(var, "SUPERNOVA__NETWORK__MAX_PEERS");
+                assert_eq!(*expected, "an integer");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert!(err.to_string().contains("SUPERNOVA__NETWORK__MAX_PEERS"));
+
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_env(env(&[("SUPERNOVA__NODE__METRICS_ENABLED", "maybe")]))
+            .err()
+            .expect("non-boolean must be rejected");
+        assert!(err.to_string().contains("a boolean"));
+    }
+
+    #[test]
+    fn env_lists_and_optional_keys_coerce() {
+        let resolved = LayeredConfigLoader::new()
+            .unwrap()
+            .with_env(env(&[
+                ("SUPERNOVA__NETWORK__BOOTSTRAP_NODES", "/ip4/1.2.3.4/tcp/1, /ip4/5.6.7.8/tcp/2"),
+                ("SUPERNOVA__API__API_KEYS", "k1,k2"),
+                ("SUPERNOVA__NETWORK__KEY_PATH", "/etc/supernova/key"),
+            ]))
+            .unwrap()
+            .resolve()
+            .unwrap();
+        assert_eq!(resolved.config.network.bootstrap_nodes.len(), 2);
+        assert_eq!(
+            resolved.config.api.api_keys,
+            Some(vec!["k1".to_string(), "k2".to_string()])
+        );
+        assert_eq!(
+            resolved.config.network.key_path,
+            Some(PathBuf::from("/etc/supernova/key"))
+        );
+    }
+
+    #[test]
+    fn dump_masks_secrets() {
+        let resolved = LayeredConfigLoader::new()
+            .unwrap()
+            .with_cli_overrides(&["api.api_keys=super-secret-key".to_string()])
+            .unwrap()
+            .resolve()
+            .unwrap();
+        let dump = resolved.dump();
+        assert!(!dump.contains("super-secret-key"));
+        assert!(dump.contains("api_keys = [\"********\"]"));
+    }
+
+    #[test]
+    fn malformed_cli_override_is_rejected() {
+        let err = LayeredConfigLoader::new()
+            .unwrap()
+            .with_cli_overrides(&["network.max_peers".to_string()])
+            .err()
+            .expect("missing '=' must be rejected");
+        assert!(matches!(err, ConfigLayerError::MalformedOverride(_)));
+    }
+
+    #[test]
+    fn diff_reports_changed_keys_only() {
+        let old = NodeConfig::default();
+        let mut new = old.clone();
+        new.network.max_peers = 77;
+        new.api.api_keys = Some(vec!["secret".to_string()]);
+
+        let changes = diff_configs(&old, &new);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["api.api_keys", "network.max_peers"]);
+        let rendered: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+        assert!(rendered.iter().all(|r| !r.contains("\"secret\"")));
+    }
+}
@@ -1,3 +1,6 @@
+// Keep lock handling panic-free here even if the crate-wide policy is relaxed
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Mutex, RwLock};
@@ -60,16 +63,19 @@ pub enum EnvironmentalError {
     InvalidSetting(String),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Lock poisoned: {0}")]
+    LockPoisoned(&'static str),
 }
 
 /// Main structure for monitoring and tracking environmental impact
 pub struct EnvironmentalMonitor {
     /// Current settings for environmental monitoring
     settings: RwLock<EnvironmentalSettingsInternal>,
-    /// System information for resource monitoring
-    system: Mutex<System>,
-    /// Historical energy usage data
-    energy_history: RwLock<Vec<EnergyUsageHistory>>,
+    /// System information for resource monitoring. Only a cache of OS
+    /// readings, so it uses a lock that can't be poisoned.
+    system: parking_lot::Mutex<System>,
+    /// Historical energy usage data, recorded a whole reading at a time
+    energy_history: parking_lot::RwLock<Vec<EnergyUsageHistory>>,
     /// Emissions factors by region (g CO2e/kWh)
     emission_factors: HashMap<String, f64>,
     /// Energy mix data by region
@@ -125,8 +131,8 @@ impl EnvironmentalMonitor {
 
         Self {
             settings: RwLock::new(settings),
-            system: Mutex::new(system),
-            energy_history: RwLock::new(Vec::new()),
+            system: parking_lot::Mutex::new(system),
+            energy_history: parking_lot::RwLock::new(Vec::new()),
             emission_factors,
             energy_mix,
             node_location: "global".to_string(),
@@ -207,10 +213,7 @@ impl EnvironmentalMonitor {
         }
 
         // Calculate energy usage based on system resources
-        let mut system = self
-            .system
-            .lock()
-            .map_err(|_| EnvironmentalError::InvalidSetting("System lock poisoned".to_string()))?;
+        let mut system = self.system.lock();
         system.refresh_cpu();
 
         // Get global CPU usage - in sysinfo 0.29, we need to calculate it from all CPUs
@@ -240,7 +243,8 @@ impl EnvironmentalMonitor {
         let _non_renewable_consumption = total_energy_kwh * (1.0 - renewable_percentage);
 
         // Store this reading in history (internal tracking)
-        if let Ok(mut energy_history) = self.energy_history.write() {
+        {
+            let mut energy_history = self.energy_history.write();
             // Add current reading to history
             let history_entry = EnergyUsageHistory {
                 timestamp: SystemTime::now()
@@ -257,9 +261,7 @@ impl EnvironmentalMonitor {
             let retention_seconds = self
                 .settings
                 .read()
-                .map_err(|_| {
-                    EnvironmentalError::InvalidSetting("Settings lock poisoned".to_string())
-                })?
+                .map_err(|_| EnvironmentalError::LockPoisoned("settings"))?
                 .data_retention_days
                 * 86400;
             let current_timestamp = SystemTime::now()
@@ -282,21 +284,17 @@ impl EnvironmentalMonitor {
             efficiency: total_energy_kwh / cpu_usage.max(0.01), // Avoid division by zero
             history: if include_history {
                 // Read energy history for the response
-                if let Ok(energy_history) = self.energy_history.read() {
-                    Some(
-                        energy_history
-                            .clone()
-                            .into_iter()
-                            .map(|h| crate::api::types::environmental::EnergyUsageHistory {
-                                timestamp: h.timestamp,
-                                usage: h.usage,
-                                power: h.power,
-                            })
-                            .collect(),
-                    )
-                } else {
-                    None
-                }
+                Some(
+                    self.energy_history
+                        .read()
+                        .iter()
+                        .map(|h| crate::api::types::environmental::EnergyUsageHistory {
+                            timestamp: h.timestamp,
+                            usage: h.usage,
+                            power: h.power,
+                        })
+                        .collect(),
+                )
             } else {
                 None
             },
@@ -336,9 +334,7 @@ impl EnvironmentalMonitor {
             && self
                 .settings
                 .read()
-                .map_err(|_| {
-                    EnvironmentalError::InvalidSetting("Settings lock poisoned".to_string())
-                })?
+                .map_err(|_| EnvironmentalError::LockPoisoned("settings"))?
                 .carbon_offset_enabled
         {
             Some(vec![crate::api::types::CarbonOffset {
@@ -398,10 +394,7 @@ impl EnvironmentalMonitor {
             ));
         }
 
-        let mut system = self
-            .system
-            .lock()
-            .map_err(|_| EnvironmentalError::InvalidSetting("System lock poisoned".to_string()))?;
+        let mut system = self.system.lock();
         system.refresh_all();
 
         // Calculate CPU usage - in sysinfo 0.29, we need to calculate it from all CPUs
@@ -422,7 +415,7 @@ impl EnvironmentalMonitor {
         };
 
         // Release the system lock before calling calculate_network_usage, which
-        // re-acquires self.system. std::sync::Mutex is non-reentrant, so holding
+        // re-acquires self.system. parking_lot::Mutex is non-reentrant, so holding
         // the guard across that call would self-deadlock the calling thread.
         drop(system);
 
@@ -463,9 +456,10 @@ impl EnvironmentalMonitor {
 
     /// Get current environmental settings (converted to API type)
     pub fn get_settings(&self) -> Result<EnvironmentalSettings, EnvironmentalError> {
-        let internal_settings = self.settings.read().map_err(|_| {
-            EnvironmentalError::InvalidSetting("Settings lock poisoned".to_string())
-        })?;
+        let internal_settings = self
+            .settings
+            .read()
+            .map_err(|_| EnvironmentalError::LockPoisoned("settings"))?;
         Ok(EnvironmentalSettings {
             monitoring_enabled: internal_settings.monitoring_enabled,
             emission_tracking_enabled: internal_settings.emission_tracking_enabled,
@@ -484,9 +478,10 @@ impl EnvironmentalMonitor {
         &self,
         new_settings: EnvironmentalSettings,
     ) -> Result<EnvironmentalSettings, EnvironmentalError> {
-        let mut internal_settings = self.settings.write().map_err(|_| {
-            EnvironmentalError::InvalidSetting("Settings lock poisoned".to_string())
-        })?;
+        let mut internal_settings = self
+            .settings
+            .write()
+            .map_err(|_| EnvironmentalError::LockPoisoned("settings"))?;
 
         // Update only the fields that exist in the API type
         internal_settings.monitoring_enabled = new_settings.monitoring_enabled;
@@ -677,18 +672,12 @@ impl EnvironmentalMonitor {
     }
 
     /// Calculate network usage as percentage of bandwidth
-    fn calculate_network_usage(&self, period: u64) -> f64 {
+    fn calculate_network_usage(&self, _period: u64) -> f64 {
         // In a real implementation, this would track actual network I/O
         // For now, we'll estimate based on node activity
 
         // Get system network stats if available
-        let system = match self.system.lock() {
-            Ok(s) => s,
-            Err(_) => {
-                tracing::warn!("Failed to acquire system lock: using estimated network usage");
-                return 0.01 * period as f64; // Return conservative estimate
-            }
-        };
+        let _system = self.system.lock();
 
         // Estimate network usage based on period and typical node activity
         // Assume average of 1 MB/s for an active node
@@ -827,4 +816,42 @@ mod tests {
             Err(_) => panic!("get_resource_utilization deadlocked (no result within timeout)"),
         }
     }
+
+    #[test]
+    fn poisoned_locks_fail_or_recover_instead_of_panicking() {
+        use std::sync::Arc;
+
+        let monitor = Arc::new(EnvironmentalMonitor::new());
+
+        // A panic holding the system cache doesn't affect later readings
+        let held = Arc::clone(&monitor);
+        assert!(std::thread::spawn(move || {
+            let _system = held.system.lock();
+            panic!("panic while holding the system lock");
+        })
+        .join()
+        .is_err());
+        assert!(monitor.get_resource_utilization(300).is_ok());
+        assert!(monitor.get_energy_usage(3600, true).is_ok());
+
+        // Settings can't be trusted after a panic mid-update, so callers
+        // that need them get an error
+        let held = Arc::clone(&monitor);
+        assert!(std::thread::spawn(move || {
+            let _settings = held.settings.write().unwrap();
+            panic!("panic while holding the settings lock");
+        })
+        .join()
+        .is_err());
+        assert!(matches!(
+            monitor.get_settings(),
+            Err(EnvironmentalError::LockPoisoned("settings"))
+        ));
+        assert!(matches!(
+            monitor.get_energy_usage(3600, false),
+            Err(EnvironmentalError::LockPoisoned("settings"))
+        ));
+        // Readings that don't need the settings are unaffected
+        assert!(monitor.get_resource_utilization(300).is_ok());
+    }
 }
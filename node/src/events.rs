@@ -0,0 +1,62 @@
+//! Node event bus
+//!
+//! In-process broadcast of node-level events (sync progress, mempool
+//! arrivals and removals, chain tip changes, peer connections and operator
+//! messages) to any number of subscribers, such as the API layer. A subscriber that falls
+//! behind skips events (`RecvError::Lagged`) instead of slowing the node.
+
+use crate::network::operator_messages::OperatorMessage;
+use crate::network::sync_progress::SyncProgress;
+use serde::{Deserialize, Serialize};
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::Transaction;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before it starts lagging
+pub const EVENT_BUS_CAPACITY: usize = 256;
+
+/// An event published on the node event bus.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum NodeEvent {
+    /// Periodic initial block download progress
+    SyncProgress(SyncProgress),
+    /// A transaction was accepted into the mempool
+    MempoolTransaction(Transaction),
+    /// A transaction left the mempool other than by expiry or eviction
+    MempoolRemoved {
+        txid: [u8; 32],
+        reason: MempoolRemovalReason,
+    },
+    /// A block was connected to the active chain
+    BlockConnected(Block),
+    /// A block was disconnected from the active chain (reorg or rollback)
+    BlockDisconnected(Block),
+    /// An operator message or network notice was accepted
+    OperatorMessage(OperatorMessage),
+    /// A peer connection was established
+    PeerConnected {
+        peer_id: String,
+        address: Option<String>,
+        inbound: bool,
+    },
+    /// A peer connection closed
+    PeerDisconnected { peer_id: String },
+}
+
+/// Why a transaction left the mempool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolRemovalReason {
+    /// Included in a connected block
+    Confirmed,
+    /// No longer final after a reorg moved the tip back
+    NonFinal,
+}
+
+/// Sending half of the event bus; call `subscribe()` for a receiver.
+pub type EventBus = broadcast::Sender<NodeEvent>;
+
+pub fn new_event_bus() -> EventBus {
+    broadcast::channel(EVENT_BUS_CAPACITY).0
+}
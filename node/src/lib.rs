@@ -23,8 +23,10 @@
 
 pub mod api;
 pub mod blockchain;
+pub mod clock;
 pub mod config;
 pub mod environmental;
+pub mod events;
 pub mod logging;
 pub mod mempool;
 pub mod miner;
@@ -44,6 +46,7 @@ pub mod secrets; // Secrets management (Vault, AWS SM, env)
 pub mod telemetry; // Distributed tracing (OpenTelemetry)
 pub mod validation; // High-performance block validation (P1-004)
 pub mod wallet_manager; // Quantum wallet integration
+pub mod pending_final; // Timelocked wallet transactions awaiting finality
 pub mod testnet;
 
 // Re-exports for convenience
@@ -45,6 +45,9 @@ pub struct LogRedactor {
     password_pattern: Regex,
     /// API key pattern
     api_key_pattern: Regex,
+    /// Lightning preimage / payment secret pattern (32 bytes as hex or a
+    /// debug-printed byte array)
+    lightning_secret_pattern: Regex,
     /// Current redaction level
     redaction_level: RedactionLevel,
 }
@@ -67,6 +70,8 @@ impl LogRedactor {
                 .expect("password_pattern is a valid regex literal"),
             api_key_pattern: Regex::new(r"(?i)(?:api[_\s]?key|apikey|token)[\s:=]+([a-zA-Z0-9_\-]{20,})")
                 .expect("api_key_pattern is a valid regex literal"),
+            lightning_secret_pattern: Regex::new(r#"(?i)(?:preimage|payment[_\s]?secret)["']?[\s:=]+["']?([0-9a-f]{64}|\[(?:\s*(?:0x)?[0-9a-f]{1,3}\s*,){31}\s*(?:0x)?[0-9a-f]{1,3}\s*\])"#)
+                .expect("lightning_secret_pattern is a valid regex literal"),
             redaction_level: RedactionLevel::Full,
         }
     }
@@ -183,6 +188,15 @@ impl LogRedactor {
             }
         }).to_string();
 
+        // Redact lightning preimages and payment secrets. These are proof of
+        // payment, so they are never partially shown.
+        redacted = self.lightning_secret_pattern.replace_all(&redacted, |caps: &regex::Captures| {
+            let full_match = caps.get(0).map(|m| m.as_str()).unwrap_or("");
+            let secret = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+            let prefix = full_match.find(secret).map(|pos| &full_match[..pos]).unwrap_or(full_match);
+            format!("{}[REDACTED]", prefix)
+        }).to_string();
+
         // Additional pattern: Hex strings that look like private keys (standalone)
         redacted = Self::redact_standalone_hex_keys(&redacted, self.redaction_level);
 
@@ -332,6 +346,16 @@ pub fn add_log_entry(level: &str, component: &str, message: String) {
     });
 }
 
+/// Redact sensitive data from a message before it is logged anywhere
+/// else than the log buffer, which redacts on its own
+pub fn redact(message: &str) -> String {
+    match LOG_REDACTOR.lock() {
+        Ok(redactor) => redactor.redact(message),
+        // Never fall back to the unredacted message
+        Err(_) => "[REDACTED]".to_string(),
+    }
+}
+
 /// Set the redaction level for log messages
 pub fn set_redaction_level(level: RedactionLevel) {
     if let Ok(mut redactor) = LOG_REDACTOR.lock() {
@@ -624,4 +648,20 @@ mod tests {
             "Standalone 64-char hex should be redacted"
         );
     }
+
+    #[test]
+    fn test_lightning_preimage_redaction() {
+        let redactor = LogRedactor::new();
+        let preimage = "9F86D081884C7D659A2FEAA0C55AD015A3BF4F1B2B0B822CD15D6C15B0F00A08";
+
+        let message = format!("HTLC 7 settled on channel 3, preimage={} amount=5000", preimage);
+        let redacted = redactor.redact(&message);
+        assert!(!redacted.to_lowercase().contains(&preimage.to_lowercase()));
+        assert!(redacted.contains("preimage=[REDACTED] amount=5000"));
+
+        let bytes: Vec<String> = (0u8..32).map(|b| b.to_string()).collect();
+        let message = format!("invoice payment_secret: [{}]", bytes.join(", "));
+        let redacted = redactor.redact(&message);
+        assert_eq!(redacted, "invoice payment_secret: [REDACTED]");
+    }
 }
@@ -11,9 +11,9 @@
 #![warn(clippy::indexing_slicing)]
 
 use clap::{Parser, Subcommand};
-use node::config::NodeConfig;
+use node::config::{NodeConfig, SyncMode};
 use node::Node;
-use node::shutdown::{ShutdownCoordinator, ShutdownConfig, ShutdownSignal, register_signal_handlers};
+use node::shutdown::{ShutdownCoordinator, ShutdownConfig, ShutdownSignal, ShutdownStage, register_signal_handlers};
 use std::sync::Arc;
 use tokio::signal;
 use tracing::{error, info, warn};
@@ -38,11 +38,53 @@ struct Args {
     #[arg(short, long)]
     debug: bool,
 
+    /// Override a configuration value (`section.key=value`). Applied after
+    /// the config file and `SUPERNOVA__SECTION__KEY` environment variables.
+    /// May be repeated.
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
+    /// How to reach the chain tip: `full` or `snapshot` (restore the
+    /// trusted state snapshot in `[sync]` first). Shorthand for
+    /// `--set sync.mode=<MODE>`.
+    #[arg(long, value_name = "MODE")]
+    sync_mode: Option<SyncMode>,
+
+    /// Print the effective configuration, annotated with the layer each value
+    /// came from (default, file, env, cli) and with secrets masked, then exit.
+    #[arg(long)]
+    dump_config: bool,
+
+    /// Run a custom network defined by this signed netparams file (JSON, or
+    /// TOML with a `.toml` extension) instead of the configured network
+    #[arg(long, value_name = "PATH")]
+    network_file: Option<std::path::PathBuf>,
+
+    /// Key the netparams file must be signed by (`ed25519:<hex>` or
+    /// `secp256k1:<hex>`)
+    #[arg(long, value_name = "KEY", requires = "network_file")]
+    netparams_authority: Option<String>,
+
+    /// Load the netparams file without verifying its signature
+    #[arg(long, requires = "network_file")]
+    insecure_netparams: bool,
+
     /// Subcommand to run
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+impl Args {
+    /// `--set` overrides plus those implied by other flags
+    fn overrides(&self) -> Vec<String> {
+        let mut overrides = self.set.clone();
+        if let Some(mode) = self.sync_mode {
+            overrides.push(format!("sync.mode={}", mode));
+        }
+        overrides
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Generate a secure API key for authentication
@@ -51,6 +93,89 @@ enum Commands {
         #[arg(short, long, default_value = "32")]
         bytes: usize,
     },
+    /// Export or import a chain state snapshot (the node must be stopped)
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommand,
+    },
+    /// Create a custom network: mine its genesis block and write a signed
+    /// netparams file
+    GenesisTool(GenesisToolArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct GenesisToolArgs {
+    /// Network name; becomes the chain id and network id
+    #[arg(long)]
+    name: String,
+    /// P2P magic, 4 bytes hex (random when omitted)
+    #[arg(long)]
+    magic: Option<String>,
+    /// Address prefix
+    #[arg(long, default_value = "tnova")]
+    hrp: String,
+    /// Default P2P port
+    #[arg(long, default_value_t = 18_333)]
+    port: u16,
+    /// Genesis timestamp (now when omitted)
+    #[arg(long)]
+    timestamp: Option<u64>,
+    /// Genesis coinbase message
+    #[arg(long)]
+    message: Option<String>,
+    /// Genesis output as `<script hex>:<amount>`; may be repeated. Defaults
+    /// to one unspendable output of the initial subsidy.
+    #[arg(long = "premine", value_name = "SCRIPT:AMOUNT")]
+    premine: Vec<String>,
+    /// Compact difficulty of the genesis block, also the easiest allowed
+    #[arg(long, default_value = "207fffff", value_parser = parse_compact_bits)]
+    bits: u32,
+    /// Target seconds per block
+    #[arg(long, default_value_t = 150)]
+    block_time: u64,
+    /// Blocks per difficulty retarget
+    #[arg(long, default_value_t = 2016)]
+    retarget_interval: u64,
+    /// Allow a block at the genesis difficulty after four block times without
+    /// one (test networks only)
+    #[arg(long)]
+    allow_min_difficulty_blocks: bool,
+    /// First block subsidy in base units
+    #[arg(long, default_value_t = supernova_core::netparams::DEFAULT_INITIAL_REWARD)]
+    initial_reward: u64,
+    /// Blocks between subsidy halvings
+    #[arg(long, default_value_t = supernova_core::netparams::DEFAULT_HALVING_INTERVAL)]
+    halving_interval: u64,
+    /// Version-bits deployment as `name:bit:threshold_percent[:start[:timeout]]`;
+    /// may be repeated
+    #[arg(long = "deployment", value_name = "SPEC")]
+    deployments: Vec<String>,
+    /// Height from which blocks commit to witness-separated txids
+    #[arg(long, default_value_t = 0)]
+    witness_txid_height: u64,
+    /// Authority signing key, an ed25519 seed in hex; created when missing
+    #[arg(long)]
+    signing_key: std::path::PathBuf,
+    /// Netparams file to write
+    #[arg(short, long)]
+    output: std::path::PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotCommand {
+    /// Write the chain state in `storage.db_path` to a compressed archive
+    Export {
+        /// Archive path to write
+        path: std::path::PathBuf,
+    },
+    /// Restore a snapshot archive into `storage.db_path`
+    Import {
+        /// Archive path to read
+        path: std::path::PathBuf,
+        /// Replace an existing, non-empty datadir
+        #[arg(long)]
+        force: bool,
+    },
 }
 
 /// Generate a cryptographically secure API key
@@ -87,11 +212,267 @@ fn generate_api_key(bytes: usize) {
     println!("IMPORTANT: Keep this key secure and never commit it to version control.");
 }
 
+fn parse_compact_bits(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("invalid compact bits '{}': {}", value, e))
+}
+
+/// Load and activate the netparams file named on the command line, if any
+fn activate_network_file(
+    args: &Args,
+) -> Result<Option<&'static supernova_core::netparams::NetParams>, Box<dyn std::error::Error>> {
+    use supernova_core::netparams::{self, NetParamsTrust};
+
+    let Some(path) = &args.network_file else {
+        return Ok(None);
+    };
+    let trust =
+        NetParamsTrust::from_options(args.netparams_authority.as_deref(), args.insecure_netparams)?;
+    if args.insecure_netparams && args.netparams_authority.is_none() {
+        eprintln!("WARNING: loading {} without verifying its signature", path.display());
+    }
+    let params = netparams::load(path, &trust)?;
+    Ok(Some(netparams::activate(params)?))
+}
+
+/// Point the configuration at a custom network
+fn apply_netparams(config: &mut NodeConfig, params: &supernova_core::netparams::NetParams) {
+    config.node.chain_id = params.name.clone();
+    config.node.network_name = params.name.clone();
+    config.network.network_id = params.name.clone();
+}
+
+/// Parse `name:bit:threshold_percent[:start[:timeout]]`
+fn parse_deployment(
+    spec: &str,
+) -> Result<supernova_core::netparams::DeploymentSchedule, String> {
+    let invalid = || format!("invalid deployment '{}'", spec);
+    let parts: Vec<&str> = spec.split(':').collect();
+    let number = |i: usize| -> Result<Option<u64>, String> {
+        parts
+            .get(i)
+            .map(|p| p.parse::<u64>().map_err(|_| invalid()))
+            .transpose()
+    };
+    let (Some(name), Some(bit), Some(threshold)) = (parts.first(), parts.get(1), parts.get(2))
+    else {
+        return Err(invalid());
+    };
+    if parts.len() > 5 {
+        return Err(invalid());
+    }
+    Ok(supernova_core::netparams::DeploymentSchedule {
+        name: name.to_string(),
+        bit: bit.parse().map_err(|_| invalid())?,
+        start_time: number(3)?,
+        timeout: number(4)?,
+        threshold_percent: threshold.parse().map_err(|_| invalid())?,
+        min_activation_height: 0,
+    })
+}
+
+/// Read the authority's ed25519 seed, generating and saving one if the file
+/// doesn't exist
+fn load_or_create_signing_key(
+    path: &std::path::Path,
+) -> Result<ed25519_dalek::SigningKey, Box<dyn std::error::Error>> {
+    use rand::RngCore;
+
+    if path.exists() {
+        let seed: [u8; 32] = hex::decode(std::fs::read_to_string(path)?.trim())?
+            .try_into()
+            .map_err(|_| format!("{:?} must hold a 32-byte hex seed", path))?;
+        return Ok(ed25519_dalek::SigningKey::from_bytes(&seed));
+    }
+    let mut seed = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut seed);
+    std::fs::write(path, hex::encode(seed))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    println!("Created authority key {}", path.display());
+    Ok(ed25519_dalek::SigningKey::from_bytes(&seed))
+}
+
+/// Run `genesis-tool`: build the network parameters, mine the genesis block
+/// and write the signed netparams file
+fn run_genesis_tool(args: &GenesisToolArgs) -> Result<(), Box<dyn std::error::Error>> {
+    use rand::RngCore;
+    use supernova_core::crypto::canonical_signing::CanonicalSigningKey;
+    use supernova_core::netparams::{
+        self, DifficultyParams, GenesisParams, NetParams, PremineOutput, SubsidySchedule,
+    };
+
+    let magic = match &args.magic {
+        Some(magic) => magic.to_ascii_lowercase(),
+        None => {
+            let mut bytes = [0u8; 4];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            hex::encode(bytes)
+        }
+    };
+    let outputs = if args.premine.is_empty() {
+        vec![PremineOutput {
+            amount: args.initial_reward,
+            script_pubkey: String::new(),
+        }]
+    } else {
+        args.premine
+            .iter()
+            .map(|spec| {
+                let (script, amount) = spec
+                    .rsplit_once(':')
+                    .ok_or_else(|| format!("invalid premine '{}'", spec))?;
+                Ok(PremineOutput {
+                    amount: amount
+                        .parse()
+                        .map_err(|_| format!("invalid premine amount in '{}'", spec))?,
+                    script_pubkey: script.to_ascii_lowercase(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?
+    };
+    let deployments = args
+        .deployments
+        .iter()
+        .map(|spec| parse_deployment(spec))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut params = NetParams {
+        name: args.name.clone(),
+        magic,
+        address_hrp: args.hrp.clone(),
+        default_port: args.port,
+        version_bits_window: args.retarget_interval,
+        genesis: GenesisParams {
+            timestamp: args
+                .timestamp
+                .unwrap_or_else(|| chrono::Utc::now().timestamp() as u64),
+            bits: args.bits,
+            nonce: 0,
+            message: args
+                .message
+                .clone()
+                .unwrap_or_else(|| format!("Genesis block for Supernova {}", args.name)),
+            hash: String::new(),
+            outputs,
+        },
+        subsidy: SubsidySchedule {
+            initial_reward: args.initial_reward,
+            halving_interval: args.halving_interval,
+        },
+        difficulty: DifficultyParams {
+            target_block_time: args.block_time,
+            retarget_interval: args.retarget_interval,
+            pow_limit_bits: args.bits,
+            allow_min_difficulty_blocks: args.allow_min_difficulty_blocks,
+        },
+        deployments,
+        witness_txid_height: Some(args.witness_txid_height),
+    };
+
+    println!("Mining genesis block for '{}'...", params.name);
+    params.mine_genesis()?;
+    params.validate()?;
+
+    let key = CanonicalSigningKey::Ed25519(load_or_create_signing_key(&args.signing_key)?);
+    let authority = netparams::format_authority(&key.public_key())
+        .ok_or("authority key cannot be formatted")?;
+    let genesis_hash = params.genesis.hash.clone();
+    netparams::save(&args.output, &params.sign(&key)?)?;
+
+    println!("Wrote {}", args.output.display());
+    println!("  Genesis:   {}", genesis_hash);
+    println!("  Authority: {}", authority);
+    println!();
+    println!("Start nodes of this network with:");
+    println!();
+    println!(
+        "  supernova-node --network-file {} --netparams-authority {}",
+        args.output.display(),
+        authority
+    );
+    Ok(())
+}
+
+/// Run `snapshot export|import` against the configured datadir
+fn run_snapshot_command(
+    action: &SnapshotCommand,
+    config_path: Option<&str>,
+    overrides: &[String],
+    netparams: Option<&supernova_core::netparams::NetParams>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use node::storage::{snapshot, BlockchainDB, SnapshotNetwork, SnapshotProgress};
+
+    let mut config = NodeConfig::load_with_overrides(config_path, overrides)?;
+    if let Some(params) = netparams {
+        apply_netparams(&mut config, params);
+    }
+    let network = SnapshotNetwork {
+        chain_id: config.node.chain_id.clone(),
+        network_id: config.network.network_id.clone(),
+    };
+    let mut report = |p: &SnapshotProgress| {
+        eprintln!(
+            "  {:?}: {}/{} entries",
+            p.phase, p.entries_done, p.total_entries
+        );
+    };
+
+    match action {
+        SnapshotCommand::Export { path } => {
+            let db = BlockchainDB::new(&config.storage.db_path).map_err(|e| {
+                format!(
+                    "Cannot open {:?} ({}). Stop the node before exporting a snapshot.",
+                    config.storage.db_path, e
+                )
+            })?;
+            let manifest = snapshot::export_snapshot(&db, &network, path, &mut report)?;
+            println!(
+                "Exported height {} (tip {}, {} UTXOs, commitment {}) to {}",
+                manifest.height,
+                manifest.tip_hash,
+                manifest.utxo_count,
+                manifest.utxo_commitment,
+                path.display()
+            );
+        }
+        SnapshotCommand::Import { path, force } => {
+            let manifest = snapshot::import_snapshot(
+                path,
+                &config.storage.db_path,
+                &network,
+                *force,
+                &mut report,
+            )?;
+            println!(
+                "Imported height {} (tip {}) into {}; UTXO commitment verified",
+                manifest.height,
+                manifest.tip_hash,
+                config.storage.db_path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Parse command-line arguments
     let args = Args::parse();
 
+    let netparams = match activate_network_file(&args) {
+        Ok(params) => params,
+        Err(e) => {
+            eprintln!("Failed to load network file: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let overrides = args.overrides();
+
     // Handle subcommands that don't require full node startup
     if let Some(command) = &args.command {
         match command {
@@ -99,6 +480,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 generate_api_key(*bytes);
                 return Ok(());
             }
+            Commands::GenesisTool(tool_args) => {
+                if let Err(e) = run_genesis_tool(tool_args) {
+                    eprintln!("genesis-tool failed: {}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+            Commands::Snapshot { action } => {
+                if let Err(e) =
+                    run_snapshot_command(action, args.config.as_deref(), &overrides, netparams)
+                {
+                    eprintln!("Snapshot failed: {}", e);
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+        }
+    }
+
+    if args.dump_config {
+        match NodeConfig::resolve(args.config.as_deref(), &overrides) {
+            Ok(resolved) => {
+                print!("{}", resolved.dump());
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("Failed to resolve configuration: {}", e);
+                std::process::exit(1);
+            }
         }
     }
 
@@ -122,10 +532,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Load configuration. `args.config` is what the operator passed via
     // `-c`/`--config`; `None` triggers the legacy multi-path search.
-    let config = NodeConfig::load(args.config.as_deref()).unwrap_or_else(|e| {
-        eprintln!("Failed to load configuration: {}", e);
-        std::process::exit(1);
-    });
+    let mut config = NodeConfig::load_with_overrides(args.config.as_deref(), &overrides)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to load configuration: {}", e);
+            std::process::exit(1);
+        });
+    if let Some(params) = netparams {
+        apply_netparams(&mut config, params);
+    }
 
     // Validate early, fail fast (also runs during load, but keep explicit here for clarity)
     if let Err(e) = config.validate() {
@@ -237,6 +651,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         persist_state: true,
         status_file_path: std::path::PathBuf::from("./data/shutdown_status.json"),
         force_after_timeout: true,
+        // Flushing state and closing storage get longer than the 5s
+        // default; the four stages together fit max_shutdown_time
+        phase_timeouts: [
+            (ShutdownStage::FlushState, std::time::Duration::from_secs(10)),
+            (ShutdownStage::CloseStorage, std::time::Duration::from_secs(10)),
+        ]
+        .into_iter()
+        .collect(),
     };
     let shutdown_coordinator = Arc::new(ShutdownCoordinator::new(
         Arc::clone(&node),
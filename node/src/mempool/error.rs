@@ -36,6 +36,9 @@ pub enum MempoolError {
     #[error("Transaction expired")]
     TransactionExpired,
 
+    #[error("Transaction is not final: valid from height {min_height} and median time {min_time}")]
+    NonFinal { min_height: u64, min_time: u64 },
+
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
 
@@ -86,6 +89,44 @@ pub enum MempoolError {
 
     #[error("Fee overflow: {0}")]
     FeeOverflow(String),
+
+    #[error("Data carrier output rejected: {0}")]
+    DataCarrier(String),
+}
+
+impl MempoolError {
+    /// Stable reason code used for rejection statistics and metric labels.
+    /// See `crate::metrics::rejections::MEMPOOL_REASON_CODES`.
+    pub fn reason_code(&self) -> &'static str {
+        match self {
+            MempoolError::ValidationFailed(_) => "validation-failed",
+            MempoolError::TransactionExists(_) | MempoolError::DuplicateTransaction => "duplicate",
+            MempoolError::TransactionNotFound(_) => "not-found",
+            MempoolError::MempoolFull { .. } => "mempool-full",
+            MempoolError::FeeTooLow { .. } => "fee-too-low",
+            MempoolError::FeeTooHigh { .. } => "fee-too-high",
+            MempoolError::DoubleSpend(_) => "double-spend",
+            MempoolError::TransactionExpired => "expired",
+            MempoolError::NonFinal { .. } => "non-final",
+            MempoolError::InvalidTransaction(_) => "invalid-transaction",
+            MempoolError::TransactionTooLarge { .. } => "too-large",
+            MempoolError::StorageError(_)
+            | MempoolError::SerializationError(_)
+            | MempoolError::LockError(_)
+            | MempoolError::InternalError(_) => "internal-error",
+            MempoolError::RateLimitExceeded { .. } | MempoolError::RelayRateLimitExceeded { .. } => {
+                "rate-limited"
+            }
+            MempoolError::MemoryLimitExceeded { .. } => "memory-limit",
+            MempoolError::AncestorChainTooLong { .. }
+            | MempoolError::DescendantChainTooLong { .. }
+            | MempoolError::AncestorSizeTooLarge { .. }
+            | MempoolError::DescendantSizeTooLarge { .. } => "too-long-mempool-chain",
+            MempoolError::RbfTooManyEvictions { .. } => "rbf-too-many-evictions",
+            MempoolError::FeeOverflow(_) => "fee-overflow",
+            MempoolError::DataCarrier(_) => "data-carrier",
+        }
+    }
 }
 
 impl From<bincode::Error> for MempoolError {
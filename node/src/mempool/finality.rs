@@ -0,0 +1,40 @@
+//! Chain view for mempool timelock checks
+//!
+//! The pool only admits transactions that could be mined in the next block.
+//! It asks a [`FinalityView`] for the chain position and for where the
+//! outputs a transaction spends were confirmed; inputs spending another
+//! mempool transaction count as confirming in the next block.
+
+use crate::storage::ChainState;
+use std::sync::RwLock;
+use supernova_core::types::timelock::{FinalityContext, InputAge};
+
+/// Chain position and confirmation lookups used to evaluate timelocks
+pub trait FinalityView: Send + Sync {
+    /// Height of the next block and median time past of the current tip
+    fn context(&self) -> FinalityContext;
+
+    /// Where `txid` was confirmed on the active chain
+    fn input_age(&self, txid: &[u8; 32]) -> InputAge;
+}
+
+impl FinalityView for RwLock<ChainState> {
+    fn context(&self) -> FinalityContext {
+        match self.read() {
+            Ok(chain) => chain.finality_context(),
+            // Without a readable tip nothing is held back; block validation
+            // still applies
+            Err(_) => FinalityContext {
+                next_height: u64::MAX,
+                median_time_past: u64::MAX,
+            },
+        }
+    }
+
+    fn input_age(&self, txid: &[u8; 32]) -> InputAge {
+        match self.read() {
+            Ok(chain) => chain.input_age(txid),
+            Err(_) => InputAge::Unknown,
+        }
+    }
+}
@@ -2,6 +2,7 @@ pub mod atomic_pool;
 pub mod chain_limits;
 pub mod error;
 pub mod fee_estimator;
+pub mod finality;
 pub mod manager;
 pub mod mev_protection;
 pub mod pool;
@@ -19,9 +20,10 @@ pub use atomic_pool::AtomicTransactionPool;
 pub use chain_limits::{ChainLimitsConfig, ChainLimitsTracker, ChainStats};
 pub use error::{MempoolError, MempoolResult};
 pub use fee_estimator::{FeeEstimator, FeeEstimatorConfig, FeeDistribution, FeePriority};
+pub use finality::FinalityView;
 pub use manager::{MempoolManager, MempoolStats};
 pub use mev_protection::{MEVProtection, MEVProtectionConfig, MEVProtectionStats};
-pub use pool::{MempoolConfig, TransactionPool};
+pub use pool::{MempoolConfig, MempoolLoadStats, TransactionPool};
 pub use prioritization::{PrioritizationConfig, PrioritizedTransaction, TransactionPrioritizer};
 pub use priority::TransactionPriority;
 pub use priority_queue::{PriorityQueueConfig, PriorityQueueEntry, PriorityQueueMetrics, TransactionPriorityQueue};
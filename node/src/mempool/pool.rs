@@ -3,13 +3,21 @@ use crate::api::types::{
 };
 use crate::config;
 use crate::mempool::error::MempoolError;
+use crate::mempool::finality::FinalityView;
 use crate::mempool::rate_limiter::MempoolRateLimiter;
+use crate::metrics::rejections::{RejectionDomain, RejectionTracker};
+use supernova_core::script::classify::DEFAULT_MAX_DATA_CARRIER_BYTES;
+use supernova_core::types::timelock::{InputAge, TimeLock};
 use supernova_core::types::transaction::Transaction;
+use supernova_core::validation::{SignatureCache, CONSENSUS_VERIFY_FLAGS};
 use dashmap::DashMap;
 use hex;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tracing::debug;
 
 /// Configuration for the transaction memory pool
@@ -28,6 +36,8 @@ pub struct MempoolConfig {
     pub enable_rbf: bool,
     /// Minimum fee increase required for RBF (as a percentage)
     pub min_rbf_fee_increase: f64,
+    /// Largest `OP_RETURN` payload relayed, in bytes
+    pub max_data_carrier_bytes: usize,
 }
 
 impl From<config::MempoolConfig> for MempoolConfig {
@@ -39,6 +49,7 @@ impl From<config::MempoolConfig> for MempoolConfig {
             max_fee_rate: config.max_fee_rate as u64, // SECURITY (P1-002): Wire max_fee_rate
             enable_rbf: config.enable_rbf,
             min_rbf_fee_increase: config.min_rbf_fee_increase,
+            max_data_carrier_bytes: config.max_data_carrier_bytes,
         }
     }
 }
@@ -52,6 +63,7 @@ impl Default for MempoolConfig {
             max_fee_rate: 100000,       // SECURITY (P1-002): 100K novas/byte max prevents fee sniping
             enable_rbf: true,           // Enable RBF by default
             min_rbf_fee_increase: 10.0, // 10% minimum fee increase
+            max_data_carrier_bytes: DEFAULT_MAX_DATA_CARRIER_BYTES,
         }
     }
 }
@@ -82,6 +94,16 @@ pub struct TransactionPool {
     config: MempoolConfig,
     /// DoS protection rate limiter (SECURITY FIX P1-003)
     rate_limiter: Arc<MempoolRateLimiter>,
+    /// Admission rejections by reason code, with recent history
+    rejections: Arc<RejectionTracker>,
+    /// Chain view for timelock checks; without one every transaction is
+    /// treated as final
+    finality: RwLock<Option<Arc<dyn FinalityView>>>,
+    /// Signatures known to verify, shared with block validation so a
+    /// confirmed pool transaction is not verified again
+    signature_cache: Arc<SignatureCache>,
+    /// Signature rule flags, matching block validation's
+    verify_flags: u32,
 }
 
 impl TransactionPool {
@@ -93,9 +115,46 @@ impl TransactionPool {
             modification_lock: Mutex::new(()),
             config,
             rate_limiter: Arc::new(MempoolRateLimiter::new()),
+            rejections: Arc::new(RejectionTracker::new(RejectionDomain::Mempool)),
+            finality: RwLock::new(None),
+            signature_cache: Arc::new(SignatureCache::default()),
+            verify_flags: CONSENSUS_VERIFY_FLAGS,
         }
     }
 
+    /// Record verified signatures in `cache` instead of a private one
+    pub fn with_signature_cache(mut self, cache: Arc<SignatureCache>) -> Self {
+        self.signature_cache = cache;
+        self
+    }
+
+    /// Verify signatures under `flags` instead of [`CONSENSUS_VERIFY_FLAGS`]
+    pub fn with_verify_flags(mut self, flags: u32) -> Self {
+        self.verify_flags = flags;
+        self
+    }
+
+    /// Check timelocks against `view` from now on
+    pub fn set_finality_view(&self, view: Arc<dyn FinalityView>) {
+        *self.finality.write() = Some(view);
+    }
+
+    /// The lock `transaction` still waits for, or `None` if it could be mined
+    /// in the next block. Inputs spending pool transactions count as
+    /// confirming in the next block.
+    pub fn unmet_time_lock(&self, transaction: &Transaction) -> Option<TimeLock> {
+        let view = self.finality.read().clone()?;
+        let ctx = view.context();
+        let lock = TimeLock::of(transaction, &ctx, |txid| {
+            if self.transactions.contains_key(txid) {
+                InputAge::Unconfirmed
+            } else {
+                view.input_age(txid)
+            }
+        });
+        (!lock.is_satisfied(&ctx)).then_some(lock)
+    }
+
     /// Extract the `(prev_tx_hash, prev_output_index)` references a transaction
     /// spends. Used to maintain the `spent_outputs` double-spend index.
     fn input_refs(transaction: &Transaction) -> Vec<([u8; 32], u32)> {
@@ -129,11 +188,34 @@ impl TransactionPool {
     /// # Returns
     /// * `Ok(())` - Transaction added successfully
     /// * `Err(MempoolError)` - Transaction rejected due to validation or DoS protection
+    ///
+    /// Every rejection is recorded in [`Self::rejections`] under the error's
+    /// reason code, attributed to `peer_id`.
     pub fn add_transaction_from_peer(
         &self,
         transaction: Transaction,
         fee_rate: u64,
         peer_id: Option<&str>,
+    ) -> Result<(), MempoolError> {
+        let tx_hash = transaction.txid();
+        let result = self.admit_transaction(transaction, fee_rate, peer_id);
+        if let Err(e) = &result {
+            self.rejections
+                .record(e.reason_code(), &tx_hash, peer_id, e.to_string());
+        }
+        result
+    }
+
+    /// Rejection statistics for mempool admission
+    pub fn rejections(&self) -> Arc<RejectionTracker> {
+        Arc::clone(&self.rejections)
+    }
+
+    fn admit_transaction(
+        &self,
+        transaction: Transaction,
+        fee_rate: u64,
+        peer_id: Option<&str>,
     ) -> Result<(), MempoolError> {
         // SECURITY (R3-53): Serialize the entire admission critical section
         // (conflict check -> eviction -> insert) so it is atomic. Without this,
@@ -144,7 +226,7 @@ impl TransactionPool {
         // it changes no consensus rule, block validity, or wire/disk format.
         let _guard = self.modification_lock.lock();
 
-        let tx_hash = transaction.hash();
+        let tx_hash = transaction.txid();
 
         // Check if transaction already exists
         if self.transactions.contains_key(&tx_hash) {
@@ -176,6 +258,17 @@ impl TransactionPool {
             });
         }
 
+        self.check_data_carriers(&transaction)?;
+
+        // Timelocked transactions wait outside the pool until the chain reaches
+        // them; the error tells the sender when to try again
+        if let Some(lock) = self.unmet_time_lock(&transaction) {
+            return Err(MempoolError::NonFinal {
+                min_height: lock.min_height,
+                min_time: lock.min_time,
+            });
+        }
+
         // SECURITY (R3-12): Verify the transaction's cryptographic (post-quantum)
         // signature BEFORE accepting it into the pool or re-gossiping it.
         //
@@ -192,8 +285,9 @@ impl TransactionPool {
         // is relay/mempool policy only and changes no consensus rule, block
         // validity, or wire/disk format. Ordered after the cheap rate-limit and
         // fee checks so an attacker is throttled before we spend CPU on crypto.
-        transaction
-            .verify_signature_only()
+        // Successes go into the signature cache shared with block validation.
+        self.signature_cache
+            .verify_signature(&transaction, self.verify_flags)
             .map_err(|e| MempoolError::InvalidTransaction(e.to_string()))?;
 
         // SECURITY (R3-53): Atomic double-spend rejection. Under `modification_lock`,
@@ -242,6 +336,38 @@ impl TransactionPool {
         Ok(())
     }
     
+    /// Relay policy for `OP_RETURN` outputs: at most one per transaction,
+    /// carrying no value and at most `max_data_carrier_bytes` of payload.
+    /// Blocks may contain any data carriers; this only limits relay.
+    fn check_data_carriers(&self, transaction: &Transaction) -> Result<(), MempoolError> {
+        let mut carriers = 0;
+        for output in transaction.outputs() {
+            let Some(payload) = output.data_payload() else {
+                continue;
+            };
+            carriers += 1;
+            if carriers > 1 {
+                return Err(MempoolError::DataCarrier(
+                    "more than one data output".to_string(),
+                ));
+            }
+            if output.amount() != 0 {
+                return Err(MempoolError::DataCarrier(format!(
+                    "data output carries {} novas",
+                    output.amount()
+                )));
+            }
+            if payload.len() > self.config.max_data_carrier_bytes {
+                return Err(MempoolError::DataCarrier(format!(
+                    "{} bytes of data exceeds the limit of {}",
+                    payload.len(),
+                    self.config.max_data_carrier_bytes
+                )));
+            }
+        }
+        Ok(())
+    }
+
     /// Try to evict a lower-fee transaction to make room
     /// 
     /// SECURITY: Implements fee-based eviction policy to prevent low-fee spam
@@ -317,6 +443,44 @@ impl TransactionPool {
         }
     }
 
+    /// Remove transactions that are no longer final, e.g. after a reorg
+    /// orphaned the block confirming their parent, along with anything in the
+    /// pool spending them. Returns the removed transactions.
+    pub fn remove_non_final(&self) -> Vec<Transaction> {
+        let candidates: Vec<Transaction> = self
+            .transactions
+            .iter()
+            .map(|entry| entry.transaction.clone())
+            .collect();
+        let mut evicted: HashSet<[u8; 32]> = candidates
+            .iter()
+            .filter(|tx| self.unmet_time_lock(tx).is_some())
+            .map(|tx| tx.hash())
+            .collect();
+        if evicted.is_empty() {
+            return Vec::new();
+        }
+        loop {
+            let before = evicted.len();
+            for tx in &candidates {
+                if tx.inputs().iter().any(|i| evicted.contains(&i.prev_tx_hash())) {
+                    evicted.insert(tx.hash());
+                }
+            }
+            if evicted.len() == before {
+                break;
+            }
+        }
+
+        let removed: Vec<Transaction> = candidates
+            .into_iter()
+            .filter(|tx| evicted.contains(&tx.hash()))
+            .filter_map(|tx| self.remove_transaction(&tx.hash()))
+            .collect();
+        debug!("Removed {} non-final transactions from the mempool", removed.len());
+        removed
+    }
+
     /// Get a transaction by its hash
     pub fn get_transaction(&self, tx_hash: &[u8; 32]) -> Option<Transaction> {
         self.transactions
@@ -335,6 +499,11 @@ impl TransactionPool {
             .map(|entry| entry.fee_rate.saturating_mul(entry.size as u64))
     }
 
+    /// Fee rate (novas per byte) the pool admitted a transaction at
+    pub fn get_fee_rate(&self, tx_hash: &[u8; 32]) -> Option<u64> {
+        self.transactions.get(tx_hash).map(|entry| entry.fee_rate)
+    }
+
     /// Clear expired transactions from the pool
     pub fn clear_expired(&self) -> usize {
         // SECURITY (R3-53): Serialize with admission so the spent-output index stays
@@ -440,6 +609,14 @@ impl TransactionPool {
             return Err(MempoolError::TransactionExists(hex::encode(tx_hash)));
         }
 
+        // A replacement must be minable now, like any other admission
+        if let Some(lock) = self.unmet_time_lock(&new_transaction) {
+            return Err(MempoolError::NonFinal {
+                min_height: lock.min_height,
+                min_time: lock.min_time,
+            });
+        }
+
         // Find transactions in the mempool that have inputs overlapping with the new transaction
         let conflicting_txs: Vec<([u8; 32], MempoolEntry)> =
             self.find_conflicting_transactions(&new_transaction);
@@ -839,6 +1016,17 @@ impl TransactionPool {
             .collect()
     }
 
+    /// Every pooled transaction with its fee rate and serialized size
+    pub fn get_transactions_with_fee_rates(&self) -> Vec<(Transaction, u64, usize)> {
+        self.transactions
+            .iter()
+            .map(|entry| {
+                let entry = entry.value();
+                (entry.transaction.clone(), entry.fee_rate, entry.size)
+            })
+            .collect()
+    }
+
     /// Get all transactions for a given block
     pub fn get_transactions_for_block(
         &self,
@@ -882,12 +1070,151 @@ impl TransactionPool {
 
         histogram
     }
+
+    /// Write every pooled transaction to `path`, oldest first, so
+    /// [`Self::load_from_disk`] can restore the pool after a restart.
+    /// Returns the number of transactions saved.
+    pub fn save_to_disk(&self, path: &Path) -> Result<usize, MempoolError> {
+        let mut entries: Vec<SavedEntry> = self
+            .transactions
+            .iter()
+            .map(|entry| {
+                Ok(SavedEntry {
+                    transaction: bincode::serialize(&entry.transaction)
+                        .map_err(|e| MempoolError::SerializationError(e.to_string()))?,
+                    fee_rate: entry.fee_rate,
+                    time_added: entry
+                        .timestamp
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or(Duration::ZERO)
+                        .as_secs(),
+                })
+            })
+            .collect::<Result<_, MempoolError>>()?;
+        // Parents were admitted before their children
+        entries.sort_by_key(|entry| entry.time_added);
+
+        let mut bytes = MEMPOOL_FILE_MAGIC.to_vec();
+        bytes.extend_from_slice(&MEMPOOL_FILE_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut bytes, &entries)
+            .map_err(|e| MempoolError::SerializationError(e.to_string()))?;
+
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, &bytes)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| MempoolError::StorageError(format!("{}: {}", path.display(), e)))?;
+        Ok(entries.len())
+    }
+
+    /// Re-admit the transactions saved by [`Self::save_to_disk`]. Each must
+    /// still pass `validator` (e.g. spend unspent outputs) and the normal
+    /// admission checks; those that do not, or have outlived `max_age`, are
+    /// dropped. A missing file loads nothing.
+    pub fn load_from_disk<F>(
+        &self,
+        path: &Path,
+        validator: F,
+    ) -> Result<MempoolLoadStats, MempoolError>
+    where
+        F: Fn(&Transaction) -> bool,
+    {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(MempoolLoadStats::default())
+            }
+            Err(e) => {
+                return Err(MempoolError::StorageError(format!("{}: {}", path.display(), e)))
+            }
+        };
+        let header = MEMPOOL_FILE_MAGIC.len() + 4;
+        if bytes.len() < header || !bytes.starts_with(MEMPOOL_FILE_MAGIC) {
+            return Err(MempoolError::SerializationError(
+                "not a saved mempool file".to_string(),
+            ));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&bytes[MEMPOOL_FILE_MAGIC.len()..header]);
+        let version = u32::from_le_bytes(version);
+        if version != MEMPOOL_FILE_VERSION {
+            return Err(MempoolError::SerializationError(format!(
+                "unsupported mempool file version {}",
+                version
+            )));
+        }
+        let entries: Vec<SavedEntry> = bincode::deserialize(&bytes[header..])
+            .map_err(|e| MempoolError::SerializationError(e.to_string()))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+        let mut stats = MempoolLoadStats::default();
+        for saved in entries {
+            if now.saturating_sub(saved.time_added) > self.config.max_age {
+                stats.expired += 1;
+                continue;
+            }
+            let transaction: Transaction = match bincode::deserialize(&saved.transaction) {
+                Ok(tx) => tx,
+                Err(_) => {
+                    stats.invalid += 1;
+                    continue;
+                }
+            };
+            let tx_hash = transaction.hash();
+            if !validator(&transaction) {
+                stats.invalid += 1;
+                continue;
+            }
+            if let Err(e) = self.add_transaction(transaction, saved.fee_rate) {
+                debug!("Dropped saved transaction {}: {}", hex::encode(tx_hash), e);
+                stats.invalid += 1;
+                continue;
+            }
+            // Keep the original admission time so expiry is unchanged
+            if let Some(mut entry) = self.transactions.get_mut(&tx_hash) {
+                entry.timestamp = UNIX_EPOCH + Duration::from_secs(saved.time_added);
+            }
+            stats.loaded += 1;
+        }
+        Ok(stats)
+    }
+}
+
+/// File name of the saved mempool inside the storage directory
+pub const MEMPOOL_FILE: &str = "mempool.dat";
+/// Leading bytes of a saved mempool file
+const MEMPOOL_FILE_MAGIC: &[u8; 4] = b"SNMP";
+/// Saved mempool format version
+const MEMPOOL_FILE_VERSION: u32 = 1;
+
+/// A transaction as written by [`TransactionPool::save_to_disk`]
+#[derive(Serialize, Deserialize)]
+struct SavedEntry {
+    /// bincode-encoded transaction
+    transaction: Vec<u8>,
+    fee_rate: u64,
+    /// Admission time, seconds since the epoch
+    time_added: u64,
+}
+
+/// Outcome of [`TransactionPool::load_from_disk`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MempoolLoadStats {
+    /// Transactions back in the pool
+    pub loaded: usize,
+    /// Dropped as no longer valid
+    pub invalid: usize,
+    /// Dropped for outliving the pool's maximum age
+    pub expired: usize,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use supernova_core::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use supernova_core::types::timelock::FinalityContext;
     use supernova_core::types::transaction::{
         SignatureSchemeType, TransactionInput, TransactionOutput,
     };
@@ -908,7 +1235,10 @@ mod tests {
     /// uses a fresh keypair; the tests here only depend on the signature being
     /// cryptographically valid, not on which key signed it.
     fn create_test_transaction(prev_hash: [u8; 32], value: u64) -> Transaction {
-        let mut tx = create_unsigned_transaction(prev_hash, value);
+        sign_test_transaction(create_unsigned_transaction(prev_hash, value))
+    }
+
+    fn sign_test_transaction(mut tx: Transaction) -> Transaction {
         let params = QuantumParameters {
             scheme: QuantumScheme::Dilithium,
             security_level: 2, // maps to SecurityLevel::Low (Dilithium2)
@@ -1221,4 +1551,228 @@ mod tests {
         assert!(pool.add_transaction(tx, 2000).is_ok());
         assert!(pool.get_transaction(&tx_hash).is_some());
     }
+
+    #[test]
+    fn test_rejections_recorded_by_reason_and_peer() {
+        let pool = TransactionPool::new(MempoolConfig::default());
+        let rejections = pool.rejections();
+
+        let tx1 = create_test_transaction([5u8; 32], 50_000_000);
+        let conflicting = create_test_transaction([5u8; 32], 40_000_000);
+        assert!(pool.add_transaction_from_peer(tx1.clone(), 2000, Some("peer-a")).is_ok());
+
+        // duplicate
+        assert!(pool.add_transaction_from_peer(tx1, 2000, Some("peer-a")).is_err());
+        // double-spend
+        let conflicting_hash = conflicting.hash();
+        assert!(pool
+            .add_transaction_from_peer(conflicting, 2000, Some("peer-b"))
+            .is_err());
+        // fee-too-low
+        let cheap = create_test_transaction([6u8; 32], 50_000_000);
+        assert!(pool.add_transaction_from_peer(cheap, 1, Some("peer-b")).is_err());
+
+        assert_eq!(rejections.count("duplicate"), 1);
+        assert_eq!(rejections.count("double-spend"), 1);
+        assert_eq!(rejections.count("fee-too-low"), 1);
+        assert_eq!(rejections.peer_count("peer-a"), 1);
+        assert_eq!(rejections.peer_count("peer-b"), 2);
+
+        let stats = rejections.snapshot(10);
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.recent[1].reason, "double-spend");
+        assert_eq!(stats.recent[1].object_hash, hex::encode(conflicting_hash));
+        assert_eq!(stats.recent[1].peer.as_deref(), Some("peer-b"));
+    }
+
+    /// Chain view at a fixed position with one confirmed parent
+    struct FixedView {
+        ctx: Mutex<FinalityContext>,
+        parent: [u8; 32],
+        parent_age: Mutex<InputAge>,
+    }
+
+    impl FinalityView for FixedView {
+        fn context(&self) -> FinalityContext {
+            *self.ctx.lock()
+        }
+
+        fn input_age(&self, txid: &[u8; 32]) -> InputAge {
+            if *txid == self.parent {
+                *self.parent_age.lock()
+            } else {
+                InputAge::Unknown
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_final_transactions_rejected_with_earliest_height() {
+        let pool = TransactionPool::new(MempoolConfig::default());
+        let parent = [8u8; 32];
+        let view = Arc::new(FixedView {
+            ctx: Mutex::new(FinalityContext {
+                next_height: 100,
+                median_time_past: 1_700_000_000,
+            }),
+            parent,
+            parent_age: Mutex::new(InputAge::Confirmed {
+                height: 95,
+                median_time_past: 1_699_999_000,
+            }),
+        });
+        pool.set_finality_view(view.clone());
+
+        // Absolute height lock: minable from height 121
+        let locked = sign_test_transaction(Transaction::new(
+            1,
+            vec![TransactionInput::new([9u8; 32], 0, vec![], 0)],
+            vec![TransactionOutput::new(50_000_000, vec![])],
+            120,
+        ));
+        match pool.add_transaction(locked, 2000) {
+            Err(MempoolError::NonFinal { min_height, min_time }) => {
+                assert_eq!(min_height, 121);
+                assert_eq!(min_time, 0);
+            }
+            other => panic!("expected NonFinal, got {:?}", other),
+        }
+        assert_eq!(pool.rejections().count("non-final"), 1);
+
+        // Relative lock of 10 blocks on a parent confirmed at 95
+        let csv = sign_test_transaction(Transaction::new(
+            2,
+            vec![TransactionInput::new(parent, 0, vec![], 10)],
+            vec![TransactionOutput::new(50_000_000, vec![])],
+            0,
+        ));
+        assert!(matches!(
+            pool.add_transaction(csv.clone(), 2000),
+            Err(MempoolError::NonFinal { min_height: 105, .. })
+        ));
+        view.ctx.lock().next_height = 105;
+        assert!(pool.add_transaction(csv.clone(), 2000).is_ok());
+
+        // A reorg orphans the parent: the spend is evicted
+        *view.parent_age.lock() = InputAge::Unconfirmed;
+        let evicted = pool.remove_non_final();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].hash(), csv.hash());
+        assert!(pool.get_transaction(&csv.hash()).is_none());
+    }
+
+    fn data_transaction(prev_hash: [u8; 32], data_outputs: Vec<TransactionOutput>) -> Transaction {
+        let mut outputs = vec![TransactionOutput::new(50_000_000, vec![])];
+        outputs.extend(data_outputs);
+        sign_test_transaction(Transaction::new(
+            1,
+            vec![TransactionInput::new(prev_hash, 0, vec![], 0xffffffff)],
+            outputs,
+            0,
+        ))
+    }
+
+    #[test]
+    fn test_data_carrier_size_boundary() {
+        let pool = TransactionPool::new(MempoolConfig {
+            max_data_carrier_bytes: 40,
+            ..MempoolConfig::default()
+        });
+
+        let at_limit = data_transaction([1u8; 32], vec![TransactionOutput::data_carrier(&[7; 40])]);
+        assert!(pool.add_transaction(at_limit, 2000).is_ok());
+
+        let over = data_transaction([2u8; 32], vec![TransactionOutput::data_carrier(&[7; 41])]);
+        assert!(matches!(
+            pool.add_transaction(over, 2000),
+            Err(MempoolError::DataCarrier(_))
+        ));
+        assert_eq!(pool.rejections().count("data-carrier"), 1);
+    }
+
+    #[test]
+    fn test_data_carrier_count_and_value() {
+        let pool = TransactionPool::new(MempoolConfig::default());
+
+        let two = data_transaction(
+            [1u8; 32],
+            vec![
+                TransactionOutput::data_carrier(b"first"),
+                TransactionOutput::data_carrier(b"second"),
+            ],
+        );
+        assert!(matches!(
+            pool.add_transaction(two, 2000),
+            Err(MempoolError::DataCarrier(_))
+        ));
+
+        let burning = data_transaction(
+            [2u8; 32],
+            vec![TransactionOutput::new(
+                1_000,
+                supernova_core::script::ScriptBuilder::data_carrier(b"anchor"),
+            )],
+        );
+        assert!(matches!(
+            pool.add_transaction(burning, 2000),
+            Err(MempoolError::DataCarrier(_))
+        ));
+
+        let one = data_transaction([3u8; 32], vec![TransactionOutput::data_carrier(b"anchor")]);
+        assert!(pool.add_transaction(one, 2000).is_ok());
+        assert_eq!(pool.rejections().count("data-carrier"), 2);
+    }
+
+    #[test]
+    fn test_mempool_survives_restart() {
+        let pool = TransactionPool::new(MempoolConfig::default());
+        let keypair = QuantumKeyPair::generate(QuantumParameters {
+            scheme: QuantumScheme::Dilithium,
+            security_level: 2,
+        })
+        .expect("keypair generation");
+
+        let mut hashes = Vec::new();
+        for i in 0..1000u32 {
+            let mut prev_hash = [0u8; 32];
+            prev_hash[..4].copy_from_slice(&i.to_le_bytes());
+            let mut tx = create_unsigned_transaction(prev_hash, 50_000_000);
+            tx.sign(
+                &keypair.secret_key,
+                &keypair.public_key,
+                SignatureSchemeType::Dilithium,
+                2,
+            )
+            .expect("transaction signing");
+            hashes.push(tx.hash());
+            pool.add_transaction(tx, 2000).unwrap();
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mempool.dat");
+        assert_eq!(pool.save_to_disk(&path).unwrap(), 1000);
+
+        // Every tenth transaction's input was spent while the node was down
+        let spent = |tx: &Transaction| {
+            let prev = tx.inputs()[0].prev_tx_hash();
+            u32::from_le_bytes([prev[0], prev[1], prev[2], prev[3]]) % 10 == 0
+        };
+        let restarted = TransactionPool::new(MempoolConfig::default());
+        let stats = restarted.load_from_disk(&path, |tx| !spent(tx)).unwrap();
+        assert_eq!(stats.loaded, 900);
+        assert_eq!(stats.invalid, 100);
+        assert_eq!(stats.expired, 0);
+        assert_eq!(restarted.size(), 900);
+        for hash in &hashes {
+            let tx = pool.get_transaction(hash).unwrap();
+            assert_eq!(restarted.get_transaction(hash).is_some(), !spent(&tx));
+        }
+
+        // A missing file is an empty mempool, an unknown format an error
+        let empty = TransactionPool::new(MempoolConfig::default());
+        let missing = empty.load_from_disk(&dir.path().join("none.dat"), |_| true).unwrap();
+        assert_eq!(missing, MempoolLoadStats::default());
+        std::fs::write(&path, b"SNMP\x09\x00\x00\x00").unwrap();
+        assert!(empty.load_from_disk(&path, |_| true).is_err());
+    }
 }
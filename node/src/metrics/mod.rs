@@ -142,6 +142,7 @@ pub mod error_metrics;    // Error metrics for P1-005
 pub mod performance;
 pub mod privacy;          // Metrics privacy filtering
 pub mod registry;
+pub mod rejections;
 pub mod types;
 
 pub use collector::MetricsCollector;
@@ -158,6 +159,7 @@ pub use error_metrics::{
 };
 pub use performance::{MetricType, PerformanceMonitor};
 pub use privacy::{MetricsPrivacyFilter, MetricsPrivacyLevel, MetricsPrivacyConfig};
+pub use rejections::{RejectionDomain, RejectionRecord, RejectionStats, RejectionTracker};
 pub use registry::MetricsRegistry;
 pub use types::{MetricValue, SystemMetrics};
 
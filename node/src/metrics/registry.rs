@@ -1,5 +1,6 @@
-use metrics::{counter, gauge, histogram};
+use metrics::{absolute_counter, counter, gauge, histogram};
 use metrics_exporter_prometheus::PrometheusHandle;
+use supernova_core::validation::SignatureCacheStats;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -181,6 +182,13 @@ impl ConsensusMetrics {
     pub fn new() -> Self {
         Self
     }
+
+    pub fn update_signature_cache(&self, stats: &SignatureCacheStats) {
+        absolute_counter!("consensus_signature_cache_hits", stats.hits);
+        absolute_counter!("consensus_signature_cache_misses", stats.misses);
+        gauge!("consensus_signature_cache_entries", stats.entries as f64);
+        gauge!("consensus_signature_cache_bytes", stats.bytes as f64);
+    }
 }
 
 impl Default for MempoolMetrics {
@@ -0,0 +1,283 @@
+//! Rejection statistics for mempool admission and block validation.
+//!
+//! Every rejected transaction or block is recorded under a stable, kebab-case
+//! reason code. Codes are part of the public API (REST and Prometheus labels)
+//! and must not be renamed; new codes may be added.
+//!
+//! Each domain keeps a counter per reason, a counter per peer, and a bounded
+//! ring buffer of the most recent rejections for operator debugging.
+
+use metrics::counter;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Number of recent rejections retained per domain.
+pub const DEFAULT_REJECTION_HISTORY: usize = 256;
+
+/// Mempool rejection reason codes and their meaning.
+pub const MEMPOOL_REASON_CODES: &[(&str, &str)] = &[
+    ("duplicate", "Transaction is already in the mempool"),
+    ("fee-too-low", "Fee rate below the minimum relay fee"),
+    ("fee-too-high", "Fee rate above the configured maximum"),
+    ("fee-overflow", "Fee computation overflowed"),
+    ("double-spend", "Spends an output already spent by a mempool transaction"),
+    ("invalid-transaction", "Malformed transaction or invalid signature"),
+    ("unauthorized", "Not authorized against the current UTXO set"),
+    ("validation-failed", "Failed mempool policy validation"),
+    ("too-large", "Transaction exceeds the maximum size"),
+    ("data-carrier", "OP_RETURN output over the relay limit, carrying value, or not the only one"),
+    ("expired", "Transaction expired"),
+    ("non-final", "Lock time or relative lock not yet reached"),
+    ("mempool-full", "Mempool is full and the fee is too low to evict"),
+    ("memory-limit", "Mempool memory limit reached"),
+    ("rate-limited", "Peer exceeded its relay rate limit"),
+    ("too-long-mempool-chain", "Ancestor or descendant limits exceeded"),
+    ("rbf-too-many-evictions", "Replacement would evict too many transactions"),
+    ("not-found", "Referenced transaction not found"),
+    ("internal-error", "Local storage, serialization or lock failure"),
+];
+
+/// Block validation failure reason codes and their meaning.
+pub const BLOCK_REASON_CODES: &[(&str, &str)] = &[
+    ("bad-block-structure", "Failed stateless block checks (merkle root, structure)"),
+    ("invalid-block", "Rejected by chain validation"),
+    ("known-invalid", "Failed validation before, or descends from a block that did"),
+    ("invalid-transaction", "Contains an invalid transaction"),
+    ("invalid-reorg", "Would cause an invalid chain reorganization"),
+    ("checkpoint-mismatch", "Conflicts with a checkpoint"),
+    ("pending-block-invalid", "Orphan block failed validation once connected"),
+    ("pending-block-expired", "Orphan block expired before it could connect"),
+    ("utxo-locked", "Spends a locked UTXO"),
+    ("internal-error", "Local storage or lock failure"),
+];
+
+/// Which object type a tracker records rejections for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionDomain {
+    Mempool,
+    Block,
+}
+
+impl RejectionDomain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectionDomain::Mempool => "mempool",
+            RejectionDomain::Block => "block",
+        }
+    }
+}
+
+/// A single recorded rejection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RejectionRecord {
+    /// Unix timestamp (seconds) of the rejection
+    pub timestamp: u64,
+    /// Stable reason code, see `MEMPOOL_REASON_CODES` / `BLOCK_REASON_CODES`.
+    /// Mempool: duplicate, fee-too-low, fee-too-high, fee-overflow,
+    /// double-spend, invalid-transaction, unauthorized, validation-failed,
+    /// too-large, data-carrier, expired, non-final, mempool-full, memory-limit,
+    /// rate-limited, too-long-mempool-chain, rbf-too-many-evictions, not-found,
+    /// internal-error. Block: bad-block-structure, invalid-block,
+    /// known-invalid, invalid-transaction, invalid-reorg, checkpoint-mismatch,
+    /// pending-block-invalid, pending-block-expired, utxo-locked,
+    /// internal-error.
+    #[schema(example = "fee-too-low")]
+    pub reason: String,
+    /// Transaction ID or block hash (hex)
+    pub object_hash: String,
+    /// Peer that relayed the object, if it came from the network
+    pub peer: Option<String>,
+    /// Human-readable error message
+    pub detail: String,
+}
+
+/// Rejection statistics for one domain.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RejectionStats {
+    /// "mempool" or "block"
+    pub domain: String,
+    /// Total rejections since startup
+    pub total: u64,
+    /// Rejections per reason code since startup
+    pub by_reason: BTreeMap<String, u64>,
+    /// Rejections per relaying peer since startup
+    pub by_peer: BTreeMap<String, u64>,
+    /// Most recent rejections, newest first
+    pub recent: Vec<RejectionRecord>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    total: u64,
+    by_reason: BTreeMap<&'static str, u64>,
+    by_peer: BTreeMap<String, u64>,
+    recent: VecDeque<RejectionRecord>,
+}
+
+/// Counts rejections by reason and peer, and keeps the last N.
+#[derive(Debug)]
+pub struct RejectionTracker {
+    domain: RejectionDomain,
+    capacity: usize,
+    state: Mutex<TrackerState>,
+}
+
+impl RejectionTracker {
+    pub fn new(domain: RejectionDomain) -> Self {
+        Self::with_capacity(domain, DEFAULT_REJECTION_HISTORY)
+    }
+
+    pub fn with_capacity(domain: RejectionDomain, capacity: usize) -> Self {
+        Self {
+            domain,
+            capacity,
+            state: Mutex::new(TrackerState::default()),
+        }
+    }
+
+    pub fn domain(&self) -> RejectionDomain {
+        self.domain
+    }
+
+    /// Record a rejection and bump the matching Prometheus counter.
+    pub fn record(
+        &self,
+        reason: &'static str,
+        object_hash: &[u8; 32],
+        peer: Option<&str>,
+        detail: impl Into<String>,
+    ) {
+        match self.domain {
+            RejectionDomain::Mempool => {
+                counter!("supernova_mempool_rejections_total", 1, "reason" => reason)
+            }
+            RejectionDomain::Block => {
+                counter!("supernova_block_rejections_total", 1, "reason" => reason)
+            }
+        }
+
+        let record = RejectionRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            reason: reason.to_string(),
+            object_hash: hex::encode(object_hash),
+            peer: peer.map(str::to_string),
+            detail: detail.into(),
+        };
+
+        let mut state = self.state.lock();
+        state.total += 1;
+        *state.by_reason.entry(reason).or_insert(0) += 1;
+        if let Some(peer) = peer {
+            *state.by_peer.entry(peer.to_string()).or_insert(0) += 1;
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        while state.recent.len() >= self.capacity {
+            state.recent.pop_front();
+        }
+        state.recent.push_back(record);
+    }
+
+    /// Number of rejections recorded for `reason`.
+    pub fn count(&self, reason: &str) -> u64 {
+        self.state.lock().by_reason.get(reason).copied().unwrap_or(0)
+    }
+
+    /// Number of rejections attributed to `peer`.
+    pub fn peer_count(&self, peer: &str) -> u64 {
+        self.state.lock().by_peer.get(peer).copied().unwrap_or(0)
+    }
+
+    /// Reason code of the most recent retained rejection of `object_hash`
+    pub fn latest_reason(&self, object_hash: &[u8; 32]) -> Option<String> {
+        let object_hash = hex::encode(object_hash);
+        self.state
+            .lock()
+            .recent
+            .iter()
+            .rev()
+            .find(|record| record.object_hash == object_hash)
+            .map(|record| record.reason.clone())
+    }
+
+    /// Counters plus up to `limit` most recent records, newest first.
+    pub fn snapshot(&self, limit: usize) -> RejectionStats {
+        let state = self.state.lock();
+        RejectionStats {
+            domain: self.domain.as_str().to_string(),
+            total: state.total,
+            by_reason: state
+                .by_reason
+                .iter()
+                .map(|(reason, count)| (reason.to_string(), *count))
+                .collect(),
+            by_peer: state.by_peer.clone(),
+            recent: state.recent.iter().rev().take(limit).cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_by_reason_and_peer() {
+        let tracker = RejectionTracker::new(RejectionDomain::Mempool);
+        tracker.record("fee-too-low", &[1u8; 32], Some("peer-a"), "low");
+        tracker.record("fee-too-low", &[2u8; 32], Some("peer-b"), "low");
+        tracker.record("double-spend", &[3u8; 32], Some("peer-a"), "conflict");
+        tracker.record("duplicate", &[4u8; 32], None, "dup");
+
+        assert_eq!(tracker.count("fee-too-low"), 2);
+        assert_eq!(tracker.count("double-spend"), 1);
+        assert_eq!(tracker.count("duplicate"), 1);
+        assert_eq!(tracker.peer_count("peer-a"), 2);
+        assert_eq!(tracker.peer_count("peer-b"), 1);
+
+        let stats = tracker.snapshot(10);
+        assert_eq!(stats.total, 4);
+        assert_eq!(stats.domain, "mempool");
+        assert_eq!(stats.recent[0].reason, "duplicate");
+        assert_eq!(stats.recent[0].peer, None);
+        assert_eq!(stats.recent[3].object_hash, hex::encode([1u8; 32]));
+    }
+
+    #[test]
+    fn ring_buffer_evicts_oldest_but_counters_keep_totals() {
+        let tracker = RejectionTracker::with_capacity(RejectionDomain::Block, 3);
+        for i in 0..5u8 {
+            tracker.record("invalid-block", &[i; 32], Some("peer"), "bad");
+        }
+
+        let stats = tracker.snapshot(usize::MAX);
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.by_reason.get("invalid-block"), Some(&5));
+        let hashes: Vec<_> = stats.recent.iter().map(|r| r.object_hash.clone()).collect();
+        assert_eq!(
+            hashes,
+            vec![hex::encode([4u8; 32]), hex::encode([3u8; 32]), hex::encode([2u8; 32])]
+        );
+        assert_eq!(tracker.snapshot(1).recent.len(), 1);
+    }
+
+    #[test]
+    fn reason_codes_are_unique_kebab_case() {
+        for codes in [MEMPOOL_REASON_CODES, BLOCK_REASON_CODES] {
+            let mut seen = std::collections::HashSet::new();
+            for (code, _) in codes {
+                assert!(seen.insert(*code), "duplicate code {}", code);
+                assert!(code
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c == '-'));
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 // Coinbase Transaction Construction for Supernova Mining
 // Handles block rewards, environmental treasury allocation, and quantum signatures
 
+use crate::config::{MiningConfig, PayoutShare};
 use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
 use thiserror::Error;
 use wallet::quantum_wallet::Address;
@@ -18,16 +19,133 @@ pub enum CoinbaseError {
     
     #[error("Treasury allocation error: {0}")]
     TreasuryError(String),
+
+    #[error("Payout error: {0}")]
+    PayoutError(String),
 }
 
 /// Block reward schedule for Supernova
 /// Similar to Bitcoin's halving schedule
-const INITIAL_BLOCK_REWARD: u64 = 50_00000000; // 50 NOVA (in attonovas)
-const HALVING_INTERVAL: u64 = 420_000; // Blocks between halvings (50 NOVA start -> 42M total)
+pub const INITIAL_BLOCK_REWARD: u64 = 50_00000000; // 50 NOVA (in attonovas)
 
 /// Environmental treasury allocation percentage
 const TREASURY_PERCENTAGE: f64 = 0.025; // 2.5% of block reward to treasury
 
+/// Smallest coinbase payout output; matches the relay dust threshold
+pub const COINBASE_DUST_LIMIT: u64 = 546;
+
+/// Who receives the miner's share of the coinbase.
+///
+/// Fixed splits are paid first. The rest is divided among percentage splits,
+/// with the rounding remainder going to the first of them; without percentage
+/// splits it goes to `primary`. Outputs that would be dust are folded into the
+/// remainder recipient.
+#[derive(Debug, Clone)]
+pub struct CoinbasePayout {
+    pub primary: Address,
+    pub splits: Vec<(Address, PayoutShare)>,
+}
+
+impl CoinbasePayout {
+    /// Pay the whole miner share to one address
+    pub fn single(address: Address) -> Self {
+        Self {
+            primary: address,
+            splits: Vec::new(),
+        }
+    }
+
+    /// Resolve the payout for `block_height` from the mining configuration.
+    /// The rotation advances by one address per block; `fallback` is used when
+    /// no rotation is configured.
+    pub fn from_config(
+        config: &MiningConfig,
+        block_height: u64,
+        fallback: &Address,
+    ) -> Result<Self, CoinbaseError> {
+        let parse = |address: &str| {
+            Address::from_str(address)
+                .map_err(|e| CoinbaseError::AddressError(format!("{}: {}", address, e)))
+        };
+
+        let primary = if config.payout_rotation.is_empty() {
+            fallback.clone()
+        } else {
+            let index = (block_height % config.payout_rotation.len() as u64) as usize;
+            parse(&config.payout_rotation[index])?
+        };
+
+        let splits = config
+            .payout_splits
+            .iter()
+            .map(|split| Ok((parse(&split.address)?, split.share)))
+            .collect::<Result<Vec<_>, CoinbaseError>>()?;
+
+        Ok(Self { primary, splits })
+    }
+
+    /// Divide `amount` into `(address, value)` outputs.
+    pub fn allocate(&self, amount: u64) -> Result<Vec<(Address, u64)>, CoinbaseError> {
+        let fixed_total = self
+            .splits
+            .iter()
+            .filter_map(|(_, share)| match share {
+                PayoutShare::Fixed(value) => Some(*value),
+                PayoutShare::Percent(_) => None,
+            })
+            .try_fold(0u64, |acc, value| acc.checked_add(value))
+            .ok_or_else(|| CoinbaseError::PayoutError("fixed splits overflow".to_string()))?;
+        let rest = amount.checked_sub(fixed_total).ok_or_else(|| {
+            CoinbaseError::PayoutError(format!(
+                "fixed splits total {} exceeds miner reward {}",
+                fixed_total, amount
+            ))
+        })?;
+
+        let mut outputs: Vec<(Address, u64)> = Vec::with_capacity(self.splits.len() + 1);
+        let mut remainder_index = None;
+        let mut distributed = 0u64;
+        for (address, share) in &self.splits {
+            let value = match share {
+                PayoutShare::Fixed(value) => *value,
+                PayoutShare::Percent(_) => {
+                    let bps = share.basis_points().unwrap_or(0);
+                    let value = (rest as u128 * bps as u128 / 10_000) as u64;
+                    distributed += value;
+                    remainder_index.get_or_insert(outputs.len());
+                    value
+                }
+            };
+            outputs.push((address.clone(), value));
+        }
+
+        let remainder = match remainder_index {
+            Some(index) => {
+                let leftover = rest.saturating_sub(distributed);
+                outputs[index].1 += leftover;
+                index
+            }
+            None => {
+                outputs.push((self.primary.clone(), rest));
+                outputs.len() - 1
+            }
+        };
+
+        // Fold dust into the remainder recipient rather than creating
+        // unspendable outputs (e.g. small percentages after a halving).
+        let mut folded = 0u64;
+        for (index, (_, value)) in outputs.iter_mut().enumerate() {
+            if index != remainder && *value < COINBASE_DUST_LIMIT {
+                folded += *value;
+                *value = 0;
+            }
+        }
+        outputs[remainder].1 += folded;
+        outputs.retain(|(_, value)| *value > 0);
+        Ok(outputs)
+    }
+}
+
 /// Build coinbase transaction for a new block
 ///
 /// # Arguments
@@ -43,6 +161,21 @@ pub fn build_coinbase_transaction(
     reward_address: &Address,
     total_fees: u64,
     treasury_address: &Address,
+) -> Result<Transaction, CoinbaseError> {
+    build_coinbase_transaction_with_payout(
+        block_height,
+        &CoinbasePayout::single(reward_address.clone()),
+        total_fees,
+        treasury_address,
+    )
+}
+
+/// Build coinbase transaction paying the miner's share according to `payout`
+pub fn build_coinbase_transaction_with_payout(
+    block_height: u64,
+    payout: &CoinbasePayout,
+    total_fees: u64,
+    treasury_address: &Address,
 ) -> Result<Transaction, CoinbaseError> {
     // Calculate block reward based on halving schedule
     let base_reward = calculate_block_reward(block_height);
@@ -62,13 +195,12 @@ pub fn build_coinbase_transaction(
     // Create outputs
     let mut outputs = Vec::new();
     
-    // Output 1: Miner reward
-    outputs.push(TransactionOutput::new(
-        miner_amount,
-        reward_address.pubkey_hash().to_vec(),
-    ));
+    // Miner reward outputs
+    for (address, value) in payout.allocate(miner_amount)? {
+        outputs.push(TransactionOutput::new(value, address.pubkey_hash().to_vec()));
+    }
     
-    // Output 2: Environmental treasury
+    // Environmental treasury
     if treasury_amount > 0 {
         outputs.push(TransactionOutput::new(
             treasury_amount,
@@ -87,17 +219,10 @@ pub fn build_coinbase_transaction(
     Ok(transaction)
 }
 
-/// Calculate block reward based on height (with halving)
+/// Calculate block reward based on height (with halving). Delegates to the
+/// consensus schedule so a custom network's subsidy is honored.
 fn calculate_block_reward(block_height: u64) -> u64 {
-    let halvings = block_height / HALVING_INTERVAL;
-    
-    // Maximum 64 halvings (after which reward is 0)
-    if halvings >= 64 {
-        return 0;
-    }
-    
-    // Right shift is equivalent to dividing by 2^halvings
-    INITIAL_BLOCK_REWARD >> halvings
+    supernova_core::types::block_subsidy(block_height)
 }
 
 /// Create coinbase script with block height
@@ -242,5 +367,75 @@ mod tests {
         // Should validate correctly
         assert!(validate_coinbase(&coinbase, 100, 0).is_ok());
     }
-}
 
+    fn address(seed: u8) -> Address {
+        Address::from_public_key(&[seed; 64]).unwrap()
+    }
+
+    #[test]
+    fn test_percentage_split_assigns_remainder_to_first_split() {
+        let payout = CoinbasePayout {
+            primary: address(1),
+            splits: vec![
+                (address(2), PayoutShare::Percent(70.0)),
+                (address(3), PayoutShare::Percent(30.0)),
+            ],
+        };
+
+        let outputs = payout.allocate(100_000_001).unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0], (address(2), 70_000_001));
+        assert_eq!(outputs[1], (address(3), 30_000_000));
+
+        // Same split through the full coinbase: 70/30 of the miner share,
+        // followed by the treasury output, nothing lost to rounding.
+        let coinbase =
+            build_coinbase_transaction_with_payout(100, &payout, 1_00000000, &address(9)).unwrap();
+        let total = 50_00000000u64 + 1_00000000;
+        let treasury = (total as f64 * TREASURY_PERCENTAGE) as u64;
+        let miner = total - treasury;
+        let values: Vec<u64> = coinbase.outputs().iter().map(|o| o.value()).collect();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[1], miner * 30 / 100);
+        assert_eq!(values[0], miner - miner * 30 / 100);
+        assert_eq!(values[0] + values[1], miner);
+        assert_eq!(values[2], treasury);
+        assert_eq!(coinbase.outputs()[0].script_pubkey(), &address(2).pubkey_hash()[..]);
+    }
+
+    #[test]
+    fn test_fixed_split_remainder_goes_to_primary() {
+        let payout = CoinbasePayout {
+            primary: address(1),
+            splits: vec![(address(2), PayoutShare::Fixed(10_000))],
+        };
+        let outputs = payout.allocate(50_000).unwrap();
+        assert_eq!(outputs, vec![(address(2), 10_000), (address(1), 40_000)]);
+        assert!(payout.allocate(5_000).is_err());
+    }
+
+    #[test]
+    fn test_rotation_advances_per_block() {
+        let rotation: Vec<String> = (1..=3).map(|i| address(i).to_string()).collect();
+        let config = MiningConfig {
+            payout_rotation: rotation,
+            payout_splits: Vec::new(),
+            signal_deployments: Vec::new(),
+            ..MiningConfig::default()
+        };
+        let fallback = address(99);
+        let primaries: Vec<Address> = (10..16)
+            .map(|height| CoinbasePayout::from_config(&config, height, &fallback).unwrap().primary)
+            .collect();
+        assert_eq!(
+            primaries,
+            vec![address(2), address(3), address(1), address(2), address(3), address(1)]
+        );
+
+        let empty = MiningConfig::default();
+        assert_eq!(
+            CoinbasePayout::from_config(&empty, 10, &fallback).unwrap().primary,
+            fallback
+        );
+    }
+}
@@ -3,6 +3,11 @@
 pub mod merkle;
 pub mod coinbase;
 pub mod template;
+pub mod template_audit;
+pub mod template_manager;
+
+#[cfg(feature = "stratum")]
+pub mod stratum;
 
 #[cfg(feature = "testnet")]
 pub mod test_miner;
@@ -10,6 +15,8 @@ pub mod test_miner;
 pub use merkle::{calculate_merkle_root, build_merkle_tree, generate_merkle_proof, verify_merkle_proof};
 pub use coinbase::build_coinbase_transaction;
 pub use template::BlockTemplate;
+pub use template_audit::TemplateAuditor;
+pub use template_manager::TemplateManager;
 
 #[cfg(feature = "testnet")]
 pub use test_miner::mine_block_simple;
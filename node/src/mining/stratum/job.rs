@@ -0,0 +1,284 @@
+//! Stratum jobs built from block templates
+//!
+//! The coinbase is serialized with room for the extranonces at the end of
+//! its input script and split around them into `coinb1` and `coinb2`. A
+//! miner rebuilds the coinbase as `coinb1 || extranonce1 || extranonce2 ||
+//! coinb2`, hashes it to its txid and folds the merkle branch into the root.
+//! Hashing follows consensus, not Bitcoin:
+//!
+//! - txid: SHA-256 of the serialized transaction
+//! - merkle leaf: SHA-256 of the txid; parent: SHA-256 of `left || right`
+//! - header: SHA-256d of version (u32 LE), previous hash, merkle root,
+//!   timestamp (u64 LE), bits (u32 LE) and nonce (u32 LE)
+
+use super::StratumError;
+use crate::mining::BlockTemplate;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use supernova_core::consensus::witness;
+use supernova_core::types::block::{Block, BlockHeader};
+use supernova_core::types::transaction::{Transaction, TransactionInput};
+use supernova_core::util::merkle::MerkleTree;
+
+/// Bytes of extranonce1, assigned per connection
+pub const EXTRANONCE1_SIZE: usize = 4;
+
+/// Bytes of extranonce2, rolled by the miner
+pub const EXTRANONCE2_SIZE: usize = 4;
+
+const EXTRANONCE_SIZE: usize = EXTRANONCE1_SIZE + EXTRANONCE2_SIZE;
+
+/// Target of a difficulty-1 share, `0xffff * 2^208` as in Bitcoin, so a
+/// share of difficulty `d` takes about `d * 2^32` hashes
+fn diff1_target() -> f64 {
+    65535.0 * 2f64.powi(208)
+}
+
+/// Difficulty a header hash satisfies
+pub fn hash_difficulty(hash: &[u8; 32]) -> f64 {
+    // Hashes compare as little-endian 256-bit integers, as in `meets_target`
+    let value = hash
+        .iter()
+        .rev()
+        .fold(0f64, |value, byte| value * 256.0 + f64::from(*byte));
+    if value == 0.0 {
+        f64::INFINITY
+    } else {
+        diff1_target() / value
+    }
+}
+
+/// Work from one template, shared by every connection
+#[derive(Debug)]
+pub struct Job {
+    pub template: Arc<BlockTemplate>,
+    /// `longpollid` of the template, to wait for the next one
+    pub longpollid: String,
+    coinb1: Vec<u8>,
+    coinb2: Vec<u8>,
+    merkle_branch: Vec<[u8; 32]>,
+}
+
+/// A miner's solution to a job
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Solution {
+    pub extranonce2: Vec<u8>,
+    pub ntime: u64,
+    pub nonce: u32,
+}
+
+impl Job {
+    pub fn new(template: Arc<BlockTemplate>, longpollid: String) -> Result<Self, StratumError> {
+        let coinbase = template
+            .transactions
+            .first()
+            .ok_or_else(|| StratumError::Template("template has no coinbase".to_string()))?;
+        let (coinb1, coinb2) = split_coinbase(coinbase)?;
+
+        // Siblings of the coinbase on its way up the tree. It is always the
+        // leftmost node, so a sibling exists at every level below the root;
+        // its own txid does not matter here.
+        let mut txids = witness::merkle_leaves(&template.transactions, template.height);
+        txids[0] = [0; 32];
+        let merkle_branch = MerkleTree::new(&txids)
+            .create_proof(0)
+            .map_err(|e| StratumError::Template(e.to_string()))?
+            .lemma;
+
+        Ok(Self {
+            template,
+            longpollid,
+            coinb1,
+            coinb2,
+            merkle_branch,
+        })
+    }
+
+    /// Parameters of `mining.notify`
+    pub fn notify_params(&self, job_id: &str, clean_jobs: bool) -> Value {
+        json!([
+            job_id,
+            hex::encode(self.template.previous_block_hash),
+            hex::encode(&self.coinb1),
+            hex::encode(&self.coinb2),
+            self.merkle_branch
+                .iter()
+                .map(hex::encode)
+                .collect::<Vec<_>>(),
+            format!("{:08x}", self.template.version),
+            format!("{:08x}", self.template.bits),
+            format!("{:08x}", self.template.timestamp),
+            clean_jobs,
+        ])
+    }
+
+    /// Header a solution produces
+    pub fn header(&self, extranonce1: &[u8], solution: &Solution) -> BlockHeader {
+        let coinbase = self.coinbase_bytes(extranonce1, &solution.extranonce2);
+        let txid: [u8; 32] = Sha256::digest(&coinbase).into();
+        let merkle_root =
+            self.merkle_branch
+                .iter()
+                .fold(MerkleTree::hash_leaf(txid), |node, sibling| {
+                    Sha256::new()
+                        .chain_update(node)
+                        .chain_update(sibling)
+                        .finalize()
+                        .into()
+                });
+        BlockHeader::new_with_height(
+            self.template.version,
+            self.template.previous_block_hash,
+            merkle_root,
+            solution.ntime,
+            self.template.bits,
+            solution.nonce,
+            self.template.height,
+        )
+    }
+
+    /// The block a solution completes
+    pub fn assemble(&self, extranonce1: &[u8], solution: &Solution) -> Result<Block, StratumError> {
+        let coinbase: Transaction =
+            bincode::deserialize(&self.coinbase_bytes(extranonce1, &solution.extranonce2))
+                .map_err(|e| StratumError::Template(format!("coinbase does not decode: {}", e)))?;
+        let mut transactions = Vec::with_capacity(self.template.transactions.len());
+        transactions.push(coinbase);
+        transactions.extend(self.template.transactions[1..].iter().cloned());
+        Ok(Block::new(self.header(extranonce1, solution), transactions))
+    }
+
+    fn coinbase_bytes(&self, extranonce1: &[u8], extranonce2: &[u8]) -> Vec<u8> {
+        [&self.coinb1[..], extranonce1, extranonce2, &self.coinb2[..]].concat()
+    }
+}
+
+/// Serialized coinbase before and after the extranonces, which are appended
+/// to its input script
+fn split_coinbase(coinbase: &Transaction) -> Result<(Vec<u8>, Vec<u8>), StratumError> {
+    let with_extranonce = |fill: u8| -> Result<Vec<u8>, StratumError> {
+        let inputs = coinbase
+            .inputs()
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                let mut script = input.signature_script().to_vec();
+                if i == 0 {
+                    script.extend_from_slice(&[fill; EXTRANONCE_SIZE]);
+                }
+                let mut rebuilt = TransactionInput::new(
+                    input.prev_tx_hash(),
+                    input.prev_output_index(),
+                    script,
+                    input.sequence(),
+                );
+                rebuilt.set_witness(input.witness().to_vec());
+                rebuilt
+            })
+            .collect();
+        let tx = Transaction::new(
+            coinbase.version(),
+            inputs,
+            coinbase.outputs().to_vec(),
+            coinbase.lock_time(),
+        );
+        let bytes = bincode::serialize(&tx)
+            .map_err(|e| StratumError::Template(format!("coinbase does not encode: {}", e)))?;
+        // Miners compute the txid from these bytes
+        if <[u8; 32]>::from(Sha256::digest(&bytes)) != tx.hash() {
+            return Err(StratumError::Template(
+                "coinbase txid is not the hash of its encoding".to_string(),
+            ));
+        }
+        Ok(bytes)
+    };
+
+    let zeros = with_extranonce(0x00)?;
+    let ones = with_extranonce(0xff)?;
+    let start = zeros
+        .iter()
+        .zip(&ones)
+        .position(|(a, b)| a != b)
+        .ok_or_else(|| StratumError::Template("coinbase has no input".to_string()))?;
+    if zeros.len() != ones.len()
+        || zeros[start + EXTRANONCE_SIZE..] != ones[start + EXTRANONCE_SIZE..]
+    {
+        return Err(StratumError::Template(
+            "extranonce is not contiguous in the coinbase".to_string(),
+        ));
+    }
+    Ok((
+        zeros[..start].to_vec(),
+        zeros[start + EXTRANONCE_SIZE..].to_vec(),
+    ))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use supernova_core::types::transaction::TransactionOutput;
+
+    /// A template at `bits` with a coinbase and `extra` spends
+    pub fn template(bits: u32, extra: u8) -> BlockTemplate {
+        let coinbase = Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(vec![1, 2, 3])],
+            vec![TransactionOutput::new(5_000_000_000, vec![0xcb; 32])],
+            0,
+        );
+        let mut transactions = vec![coinbase];
+        transactions.extend((1..=extra).map(|seed| {
+            Transaction::new(
+                1,
+                vec![TransactionInput::new([seed; 32], 0, vec![], 0)],
+                vec![TransactionOutput::new(1_000, vec![seed; 25])],
+                0,
+            )
+        }));
+        BlockTemplate {
+            version: 1,
+            previous_block_hash: [7; 32],
+            merkle_root: [0; 32],
+            timestamp: 1_760_000_000,
+            bits,
+            height: 42,
+            transactions,
+            total_fees: 0,
+            coinbase_value: 5_000_000_000,
+        }
+    }
+
+    #[test]
+    fn solutions_assemble_into_consistent_blocks() {
+        for extra in [0, 1, 2, 4, 5] {
+            let job = Job::new(Arc::new(template(0x207f_ffff, extra)), String::new()).unwrap();
+            let solution = Solution {
+                extranonce2: vec![9, 8, 7, 6],
+                ntime: 1_760_000_100,
+                nonce: 12345,
+            };
+            let block = job.assemble(&[1, 2, 3, 4], &solution).unwrap();
+
+            assert!(block.verify_merkle_root(), "{} extra transactions", extra);
+            assert_eq!(block.header, job.header(&[1, 2, 3, 4], &solution));
+            assert_eq!(block.transactions.len(), usize::from(extra) + 1);
+            let script = block.transactions[0].inputs()[0].signature_script();
+            assert_eq!(script, [1, 2, 3, 1, 2, 3, 4, 9, 8, 7, 6]);
+        }
+    }
+
+    #[test]
+    fn hash_difficulty_matches_the_diff1_target() {
+        // 0xffff * 2^208 little-endian: bytes 26 and 27 are 0xff
+        let mut diff1 = [0u8; 32];
+        diff1[26] = 0xff;
+        diff1[27] = 0xff;
+        assert!((hash_difficulty(&diff1) - 1.0).abs() < 1e-9);
+
+        let mut easier = diff1;
+        easier[31] = 1;
+        assert!(hash_difficulty(&easier) < 1.0);
+        assert!(hash_difficulty(&[0; 32]).is_infinite());
+    }
+}
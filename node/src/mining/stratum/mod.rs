@@ -0,0 +1,439 @@
+//! Stratum v1 server for external miners
+//!
+//! Miners connect over TCP and exchange newline-delimited JSON-RPC:
+//! `mining.subscribe`, `mining.authorize` and `mining.submit` from the
+//! miner, `mining.set_difficulty` and `mining.notify` from the server. Jobs
+//! come from the node's template manager and are replaced whenever it hands
+//! out a new template; a new previous block sets `clean_jobs`. Shares are
+//! checked against the connection's share difficulty, which [`vardiff`]
+//! adjusts to the miner's share rate. A share that also meets the block
+//! target is assembled into a block and submitted like any block we mine.
+//!
+//! Worker names only label shares in the statistics; no password is
+//! checked, and block rewards go to the node wallet.
+
+pub mod job;
+mod session;
+pub mod stats;
+pub mod vardiff;
+
+pub use job::Job;
+pub use stats::{ShareOutcome, StratumMetrics, StratumStats, WorkerStats};
+
+use crate::api_facade::ApiFacade;
+use crate::config::StratumConfig;
+use crate::mining::BlockTemplate;
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use supernova_core::types::block::Block;
+use thiserror::Error;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+
+/// Wait before asking again for a template that failed to build
+const TEMPLATE_RETRY: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug)]
+pub enum StratumError {
+    #[error("Cannot build a job from the template: {0}")]
+    Template(String),
+    #[error("Line longer than {0} bytes")]
+    LineTooLong(usize),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// What the server needs from the node
+#[async_trait]
+pub trait StratumBackend: Send + Sync {
+    /// Current template and its `longpollid`
+    fn template(&self) -> Result<(Arc<BlockTemplate>, String), String>;
+
+    /// Return once the template named by `longpollid` may be out of date
+    async fn wait_for_update(&self, longpollid: &str);
+
+    /// Connect and announce a solved block
+    async fn submit_block(&self, block: Block) -> Result<(), String>;
+}
+
+#[async_trait]
+impl StratumBackend for ApiFacade {
+    fn template(&self) -> Result<(Arc<BlockTemplate>, String), String> {
+        self.block_template().map_err(|e| e.to_string())
+    }
+
+    async fn wait_for_update(&self, longpollid: &str) {
+        let (fee_delta, timeout_secs) = self
+            .config()
+            .read()
+            .map(|c| (c.mining.longpoll_fee_delta, c.mining.longpoll_timeout_secs))
+            .unwrap_or((0, 60));
+        let templates = self.template_manager();
+        if let Err(e) = templates
+            .wait(longpollid, fee_delta, Duration::from_secs(timeout_secs))
+            .await
+        {
+            tracing::warn!("Stratum could not wait for a new template: {}", e);
+            tokio::time::sleep(TEMPLATE_RETRY).await;
+        }
+    }
+
+    async fn submit_block(&self, block: Block) -> Result<(), String> {
+        self.submit_mined_block(&block)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Start the stratum server for `facade` when `[mining.stratum]` enables it
+pub fn spawn(facade: Arc<ApiFacade>) {
+    let config = match facade.config().read() {
+        Ok(config) => config.mining.stratum.clone(),
+        Err(_) => return,
+    };
+    if !config.enabled {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        tracing::error!("Stratum server needs a Tokio runtime; not started");
+        return;
+    };
+    let metrics = facade.stratum_metrics();
+    let server = Arc::new(StratumServer::new(config, facade, metrics));
+    runtime.spawn(async move {
+        if let Err(e) = server.run().await {
+            tracing::error!("Stratum server stopped: {}", e);
+        }
+    });
+}
+
+/// Hands jobs to connected miners and checks their shares
+pub struct StratumServer {
+    config: StratumConfig,
+    backend: Arc<dyn StratumBackend>,
+    metrics: Arc<StratumMetrics>,
+    /// Newest job; `None` until the first template is built
+    job: watch::Sender<Option<Arc<Job>>>,
+    next_extranonce1: AtomicU32,
+}
+
+impl StratumServer {
+    pub fn new(
+        config: StratumConfig,
+        backend: Arc<dyn StratumBackend>,
+        metrics: Arc<StratumMetrics>,
+    ) -> Self {
+        let (job, _) = watch::channel(None);
+        Self {
+            config,
+            backend,
+            metrics,
+            job,
+            next_extranonce1: AtomicU32::new(rand::random()),
+        }
+    }
+
+    /// Listen on the configured address and serve miners
+    pub async fn run(self: Arc<Self>) -> Result<(), StratumError> {
+        let address = format!("{}:{}", self.config.bind_address, self.config.port);
+        let listener = TcpListener::bind(&address).await?;
+        tracing::info!("Stratum server listening on {}", address);
+        self.serve(listener).await
+    }
+
+    /// Serve miners connecting to `listener`
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<(), StratumError> {
+        let jobs = tokio::spawn(Arc::clone(&self).refresh_jobs());
+        let result = loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => break Err(e.into()),
+            };
+            if self.metrics.connections() >= self.config.max_connections {
+                tracing::warn!("Stratum connection from {} refused: server full", peer);
+                continue;
+            }
+            tokio::spawn(session::run(Arc::clone(&self), stream, peer));
+        };
+        jobs.abort();
+        result
+    }
+
+    pub fn metrics(&self) -> Arc<StratumMetrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    fn extranonce1(&self) -> [u8; job::EXTRANONCE1_SIZE] {
+        self.next_extranonce1
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes()
+    }
+
+    /// Publish a job for every new template
+    async fn refresh_jobs(self: Arc<Self>) {
+        loop {
+            let (template, longpollid) = match self.backend.template() {
+                Ok(current) => current,
+                Err(e) => {
+                    tracing::warn!("Stratum has no template: {}", e);
+                    tokio::time::sleep(TEMPLATE_RETRY).await;
+                    continue;
+                }
+            };
+            let unchanged = self
+                .job
+                .borrow()
+                .as_ref()
+                .is_some_and(|job| Arc::ptr_eq(&job.template, &template));
+            if !unchanged {
+                match Job::new(template, longpollid.clone()) {
+                    Ok(job) => {
+                        self.job.send_replace(Some(Arc::new(job)));
+                    }
+                    Err(e) => tracing::warn!("Stratum job not published: {}", e),
+                }
+            }
+            self.backend.wait_for_update(&longpollid).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::job::{hash_difficulty, tests::template};
+    use super::*;
+    use parking_lot::Mutex;
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+    use std::net::SocketAddr;
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::util::merkle::MerkleTree;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+    use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::net::TcpStream;
+
+    /// Serves one fixed template and keeps what is submitted
+    struct MockBackend {
+        template: Arc<BlockTemplate>,
+        blocks: Mutex<Vec<Block>>,
+    }
+
+    #[async_trait]
+    impl StratumBackend for MockBackend {
+        fn template(&self) -> Result<(Arc<BlockTemplate>, String), String> {
+            Ok((Arc::clone(&self.template), "mock".to_string()))
+        }
+
+        async fn wait_for_update(&self, _longpollid: &str) {
+            std::future::pending::<()>().await
+        }
+
+        async fn submit_block(&self, block: Block) -> Result<(), String> {
+            self.blocks.lock().push(block);
+            Ok(())
+        }
+    }
+
+    async fn start(bits: u32) -> (SocketAddr, Arc<MockBackend>, Arc<StratumMetrics>) {
+        let backend = Arc::new(MockBackend {
+            template: Arc::new(template(bits, 3)),
+            blocks: Mutex::new(Vec::new()),
+        });
+        let config = StratumConfig {
+            enabled: true,
+            initial_difficulty: 1e-6,
+            min_difficulty: 1e-6,
+            ..StratumConfig::default()
+        };
+        let metrics = Arc::new(StratumMetrics::new());
+        let server = Arc::new(StratumServer::new(
+            config,
+            Arc::clone(&backend) as Arc<dyn StratumBackend>,
+            Arc::clone(&metrics),
+        ));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(server.serve(listener));
+        (address, backend, metrics)
+    }
+
+    /// A miner speaking the protocol from the wire format alone
+    struct MockMiner {
+        lines: Lines<BufReader<OwnedReadHalf>>,
+        writer: OwnedWriteHalf,
+        next_id: u64,
+    }
+
+    impl MockMiner {
+        async fn connect(address: SocketAddr) -> Self {
+            let (read, writer) = TcpStream::connect(address).await.unwrap().into_split();
+            Self {
+                lines: BufReader::new(read).lines(),
+                writer,
+                next_id: 1,
+            }
+        }
+
+        async fn receive(&mut self) -> Value {
+            let line = tokio::time::timeout(Duration::from_secs(5), self.lines.next_line())
+                .await
+                .expect("no message from the server")
+                .unwrap()
+                .expect("server closed the connection");
+            serde_json::from_str(&line).unwrap()
+        }
+
+        /// Send a request and return its response, skipping notifications
+        async fn call(&mut self, method: &str, params: Value) -> Value {
+            let id = self.next_id;
+            self.next_id += 1;
+            let request = json!({ "id": id, "method": method, "params": params });
+            self.writer
+                .write_all(format!("{}\n", request).as_bytes())
+                .await
+                .unwrap();
+            loop {
+                let message = self.receive().await;
+                if message["id"] == json!(id) {
+                    return message;
+                }
+            }
+        }
+
+        async fn notification(&mut self, method: &str) -> Value {
+            loop {
+                let message = self.receive().await;
+                if message["method"] == json!(method) {
+                    return message["params"].clone();
+                }
+            }
+        }
+    }
+
+    /// Header hash of a solution, computed from `mining.notify` parameters
+    fn solve(notify: &Value, extranonce1: &str, extranonce2: &str, nonce: u32) -> [u8; 32] {
+        let field = |i: usize| hex::decode(notify[i].as_str().unwrap()).unwrap();
+        let number = |i: usize| u64::from_str_radix(notify[i].as_str().unwrap(), 16).unwrap();
+        let coinbase = [
+            field(2),
+            hex::decode(extranonce1).unwrap(),
+            hex::decode(extranonce2).unwrap(),
+            field(3),
+        ]
+        .concat();
+        let txid: [u8; 32] = Sha256::digest(&coinbase).into();
+        let merkle_root = notify[4]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|sibling| hex::decode(sibling.as_str().unwrap()).unwrap())
+            .fold(MerkleTree::hash_leaf(txid), |node, sibling| {
+                Sha256::new()
+                    .chain_update(node)
+                    .chain_update(sibling)
+                    .finalize()
+                    .into()
+            });
+        let mut header = Vec::new();
+        header.extend_from_slice(&(number(5) as u32).to_le_bytes());
+        header.extend_from_slice(&field(1));
+        header.extend_from_slice(&merkle_root);
+        header.extend_from_slice(&number(7).to_le_bytes());
+        header.extend_from_slice(&(number(6) as u32).to_le_bytes());
+        header.extend_from_slice(&nonce.to_le_bytes());
+        Sha256::digest(Sha256::digest(&header)).into()
+    }
+
+    /// Subscribe and authorize, returning extranonce1, the share
+    /// difficulty and the first job
+    async fn handshake(miner: &mut MockMiner) -> (String, f64, Value) {
+        let subscribed = miner.call("mining.subscribe", json!(["mock/1.0"])).await;
+        let extranonce1 = subscribed["result"][1].as_str().unwrap().to_string();
+        assert_eq!(subscribed["result"][2], json!(job::EXTRANONCE2_SIZE));
+        let difficulty = miner.notification("mining.set_difficulty").await[0]
+            .as_f64()
+            .unwrap();
+        let notify = miner.notification("mining.notify").await;
+        let authorized = miner.call("mining.authorize", json!(["rig1", "x"])).await;
+        assert_eq!(authorized["result"], json!(true));
+        (extranonce1, difficulty, notify)
+    }
+
+    #[tokio::test]
+    async fn mock_miner_share_is_accepted_and_counted() {
+        // Block difficulty 1 is out of reach; shares at 1e-6 take a few
+        // thousand hashes
+        let (address, backend, metrics) = start(0x1d00_ffff).await;
+        let mut miner = MockMiner::connect(address).await;
+        let (extranonce1, difficulty, notify) = handshake(&mut miner).await;
+        assert_eq!(difficulty, 1e-6);
+        assert_eq!(notify[8], json!(true));
+
+        let extranonce2 = "00000001";
+        let nonce = (0u32..)
+            .find(|nonce| {
+                hash_difficulty(&solve(&notify, &extranonce1, extranonce2, *nonce)) >= difficulty
+            })
+            .unwrap();
+        let submit = json!([
+            "rig1",
+            notify[0],
+            extranonce2,
+            notify[7],
+            format!("{:08x}", nonce)
+        ]);
+        let accepted = miner.call("mining.submit", submit.clone()).await;
+        assert_eq!(accepted["result"], json!(true), "{}", accepted);
+
+        let duplicate = miner.call("mining.submit", submit).await;
+        assert_eq!(duplicate["error"][0], json!(22));
+        let unknown = miner
+            .call(
+                "mining.submit",
+                json!(["rig9", notify[0], extranonce2, notify[7], "00000000"]),
+            )
+            .await;
+        assert_eq!(unknown["error"][0], json!(24));
+
+        let stats = metrics.snapshot(std::time::Instant::now());
+        assert_eq!(stats.connections, 1);
+        assert_eq!(stats.workers[0].worker, "rig1");
+        assert_eq!((stats.accepted_shares, stats.rejected_shares), (1, 1));
+        assert!(stats.workers[0].hashrate > 0.0);
+        assert!(backend.blocks.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn share_meeting_the_block_target_submits_a_block() {
+        let (address, backend, metrics) = start(0x207f_ffff).await;
+        let mut miner = MockMiner::connect(address).await;
+        let (extranonce1, _, notify) = handshake(&mut miner).await;
+
+        let target = BlockHeader::new(0, [0; 32], [0; 32], 0, 0x207f_ffff, 0).target();
+        let nonce = (0u32..)
+            .find(|nonce| {
+                let hash = solve(&notify, &extranonce1, "0000abcd", *nonce);
+                // Little-endian comparison, as consensus does
+                hash.iter().rev().lt(target.iter().rev())
+            })
+            .unwrap();
+        let submit = json!([
+            "rig1",
+            notify[0],
+            "0000abcd",
+            notify[7],
+            format!("{:08x}", nonce)
+        ]);
+        let accepted = miner.call("mining.submit", submit).await;
+        assert_eq!(accepted["result"], json!(true), "{}", accepted);
+
+        let blocks = backend.blocks.lock();
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].verify_merkle_root());
+        assert!(blocks[0].header.meets_target());
+        assert_eq!(blocks[0].transactions.len(), 4);
+        assert_eq!(metrics.snapshot(std::time::Instant::now()).blocks_found, 1);
+    }
+}
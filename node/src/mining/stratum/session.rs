@@ -0,0 +1,367 @@
+//! One miner's connection
+
+use super::job::{hash_difficulty, Job, Solution, EXTRANONCE1_SIZE, EXTRANONCE2_SIZE};
+use super::stats::ShareOutcome;
+use super::vardiff::VarDiff;
+use super::{StratumError, StratumServer};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+
+/// Longest request line accepted
+const MAX_LINE: usize = 16 * 1024;
+
+/// Jobs a share may still be submitted for
+const RECENT_JOBS: usize = 8;
+
+const MAX_WORKERS: usize = 16;
+const MAX_WORKER_NAME: usize = 128;
+
+/// How far ahead of our clock a share's ntime may be
+const MAX_FUTURE_NTIME: u64 = 2 * 60 * 60;
+
+/// How often the share rate is checked against the target
+const VARDIFF_TICK: Duration = Duration::from_secs(5);
+
+// Error codes of `mining.submit`, as used by common pool software
+const OTHER: i64 = 20;
+const JOB_NOT_FOUND: i64 = 21;
+const DUPLICATE_SHARE: i64 = 22;
+const LOW_DIFFICULTY: i64 = 23;
+const UNAUTHORIZED: i64 = 24;
+const NOT_SUBSCRIBED: i64 = 25;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A job as sent on this connection
+struct SessionJob {
+    id: String,
+    job: Arc<Job>,
+    difficulty: f64,
+    solutions: HashSet<Solution>,
+}
+
+/// A rejected request: error code and message
+type Rejection = (i64, String);
+
+struct Session {
+    server: Arc<StratumServer>,
+    writer: OwnedWriteHalf,
+    jobs_rx: watch::Receiver<Option<Arc<Job>>>,
+    extranonce1: [u8; EXTRANONCE1_SIZE],
+    subscribed: bool,
+    workers: HashSet<String>,
+    difficulty: f64,
+    vardiff: VarDiff,
+    /// Newest last
+    jobs: VecDeque<SessionJob>,
+    next_job_id: u64,
+}
+
+pub(super) async fn run(server: Arc<StratumServer>, stream: TcpStream, peer: SocketAddr) {
+    let metrics = server.metrics();
+    metrics.connection_opened();
+    tracing::debug!("Stratum miner {} connected", peer);
+    match serve(server, stream).await {
+        Ok(()) => tracing::debug!("Stratum miner {} disconnected", peer),
+        Err(e) => tracing::debug!("Stratum miner {} dropped: {}", peer, e),
+    }
+    metrics.connection_closed();
+}
+
+async fn serve(server: Arc<StratumServer>, stream: TcpStream) -> Result<(), StratumError> {
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut session = Session {
+        writer,
+        jobs_rx: server.job.subscribe(),
+        extranonce1: server.extranonce1(),
+        subscribed: false,
+        workers: HashSet::new(),
+        difficulty: server.config.initial_difficulty,
+        vardiff: VarDiff::new(&server.config, Instant::now()),
+        jobs: VecDeque::new(),
+        next_job_id: 0,
+        server,
+    };
+    let mut line = Vec::new();
+    let mut vardiff = tokio::time::interval(VARDIFF_TICK);
+    vardiff.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            complete = read_line(&mut reader, &mut line) => {
+                if !complete? {
+                    return Ok(());
+                }
+                session.handle_line(&line).await?;
+                line.clear();
+            }
+            changed = session.jobs_rx.changed(), if session.subscribed => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let job = session.jobs_rx.borrow_and_update().clone();
+                if let Some(job) = job {
+                    session.send_job(job).await?;
+                }
+            }
+            _ = vardiff.tick(), if session.subscribed => session.retarget().await?,
+        }
+    }
+}
+
+/// Read up to a newline into `line`, keeping partial input when cancelled.
+/// Returns false at end of input.
+async fn read_line(
+    reader: &mut BufReader<OwnedReadHalf>,
+    line: &mut Vec<u8>,
+) -> Result<bool, StratumError> {
+    loop {
+        let limit = (MAX_LINE + 1).saturating_sub(line.len()) as u64;
+        let read = (&mut *reader).take(limit).read_until(b'\n', line).await?;
+        if line.ends_with(b"\n") {
+            return Ok(true);
+        }
+        if line.len() > MAX_LINE {
+            return Err(StratumError::LineTooLong(MAX_LINE));
+        }
+        if read == 0 {
+            return Ok(false);
+        }
+    }
+}
+
+impl Session {
+    async fn handle_line(&mut self, line: &[u8]) -> Result<(), StratumError> {
+        if line.trim_ascii().is_empty() {
+            return Ok(());
+        }
+        let request: Request = match serde_json::from_slice(line) {
+            Ok(request) => request,
+            Err(e) => {
+                let error = (OTHER, format!("Malformed request: {}", e));
+                return self.respond(Value::Null, Err(error)).await;
+            }
+        };
+        let result = match request.method.as_str() {
+            "mining.subscribe" => {
+                self.subscribed = true;
+                let extranonce1 = hex::encode(self.extranonce1);
+                let result = json!([
+                    [
+                        ["mining.set_difficulty", extranonce1],
+                        ["mining.notify", extranonce1]
+                    ],
+                    extranonce1,
+                    EXTRANONCE2_SIZE,
+                ]);
+                self.respond(request.id, Ok(result)).await?;
+                self.notify("mining.set_difficulty", json!([self.difficulty]))
+                    .await?;
+                let job = self.jobs_rx.borrow_and_update().clone();
+                if let Some(job) = job {
+                    self.send_job(job).await?;
+                }
+                return Ok(());
+            }
+            "mining.authorize" => self.authorize(&request.params),
+            "mining.submit" => self.submit(&request.params).await,
+            method => Err((OTHER, format!("Unknown method {}", method))),
+        };
+        self.respond(request.id, result).await
+    }
+
+    fn authorize(&mut self, params: &Value) -> Result<Value, Rejection> {
+        let worker = params
+            .get(0)
+            .and_then(Value::as_str)
+            .filter(|name| !name.is_empty() && name.len() <= MAX_WORKER_NAME)
+            .ok_or((OTHER, "Invalid worker name".to_string()))?;
+        if !self.workers.contains(worker) {
+            if self.workers.len() >= MAX_WORKERS {
+                return Err((OTHER, "Too many workers".to_string()));
+            }
+            self.workers.insert(worker.to_string());
+        }
+        Ok(json!(true))
+    }
+
+    async fn submit(&mut self, params: &Value) -> Result<Value, Rejection> {
+        if !self.subscribed {
+            return Err((NOT_SUBSCRIBED, "Not subscribed".to_string()));
+        }
+        let field = |i: usize| params.get(i).and_then(Value::as_str);
+        let worker = field(0)
+            .filter(|worker| self.workers.contains(*worker))
+            .ok_or((UNAUTHORIZED, "Unauthorized worker".to_string()))?
+            .to_string();
+        let now = Instant::now();
+        let record = |outcome, difficulty| {
+            self.server
+                .metrics
+                .record_share(&worker, difficulty, outcome, now)
+        };
+
+        let Some((job_id, solution)) = parse_submit(params) else {
+            record(ShareOutcome::Rejected, self.difficulty);
+            return Err((OTHER, "Malformed share".to_string()));
+        };
+        let Some(index) = self.jobs.iter().position(|job| job.id == job_id) else {
+            record(ShareOutcome::Stale, self.difficulty);
+            return Err((JOB_NOT_FOUND, "Job not found".to_string()));
+        };
+        let job = &self.jobs[index];
+        let difficulty = job.difficulty;
+        let unix_now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if solution.ntime < job.job.template.timestamp
+            || solution.ntime > unix_now.saturating_add(MAX_FUTURE_NTIME)
+        {
+            record(ShareOutcome::Rejected, difficulty);
+            return Err((OTHER, "ntime out of range".to_string()));
+        }
+        // A resent job shares its work with the ids it was sent under before
+        let duplicate = self
+            .jobs
+            .iter()
+            .filter(|sent| Arc::ptr_eq(&sent.job, &job.job))
+            .any(|sent| sent.solutions.contains(&solution));
+        if duplicate {
+            record(ShareOutcome::Rejected, difficulty);
+            return Err((DUPLICATE_SHARE, "Duplicate share".to_string()));
+        }
+
+        let header = job.job.header(&self.extranonce1, &solution);
+        let mut outcome = None;
+        if header.meets_target() {
+            match job.job.assemble(&self.extranonce1, &solution) {
+                Ok(block) => {
+                    let hash = hex::encode(block.hash());
+                    match self.server.backend.submit_block(block).await {
+                        Ok(()) => {
+                            tracing::info!("Stratum worker {} found block {}", worker, hash);
+                            outcome = Some(ShareOutcome::Block);
+                        }
+                        Err(e) => tracing::warn!("Block {} from {} rejected: {}", hash, worker, e),
+                    }
+                }
+                Err(e) => tracing::warn!("Cannot assemble block from share: {}", e),
+            }
+        }
+        let outcome = match outcome {
+            Some(outcome) => outcome,
+            None if hash_difficulty(&header.hash()) >= difficulty => ShareOutcome::Accepted,
+            None => {
+                record(ShareOutcome::Rejected, difficulty);
+                return Err((LOW_DIFFICULTY, "Low difficulty share".to_string()));
+            }
+        };
+        self.jobs[index].solutions.insert(solution);
+        self.vardiff.record_share();
+        record(outcome, difficulty);
+        Ok(json!(true))
+    }
+
+    /// Send `job` under a new id, replacing older work if it builds on a
+    /// different block
+    async fn send_job(&mut self, job: Arc<Job>) -> Result<(), StratumError> {
+        let clean = self.jobs.back().is_none_or(|last| {
+            last.job.template.previous_block_hash != job.template.previous_block_hash
+        });
+        if clean {
+            self.jobs.clear();
+        }
+        let id = format!("{:x}", self.next_job_id);
+        self.next_job_id += 1;
+        let params = job.notify_params(&id, clean);
+        self.jobs.push_back(SessionJob {
+            id,
+            job,
+            difficulty: self.difficulty,
+            solutions: HashSet::new(),
+        });
+        while self.jobs.len() > RECENT_JOBS {
+            self.jobs.pop_front();
+        }
+        self.notify("mining.notify", params).await
+    }
+
+    /// Apply a vardiff change; the current job is resent so the new
+    /// difficulty takes effect now
+    async fn retarget(&mut self) -> Result<(), StratumError> {
+        let Some(difficulty) = self.vardiff.retarget(self.difficulty, Instant::now()) else {
+            return Ok(());
+        };
+        self.difficulty = difficulty;
+        self.notify("mining.set_difficulty", json!([difficulty]))
+            .await?;
+        match self.jobs.back() {
+            Some(last) => {
+                let job = Arc::clone(&last.job);
+                self.send_job(job).await
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn respond(
+        &mut self,
+        id: Value,
+        result: Result<Value, Rejection>,
+    ) -> Result<(), StratumError> {
+        let message = match result {
+            Ok(result) => json!({ "id": id, "result": result, "error": null }),
+            Err((code, message)) => {
+                json!({ "id": id, "result": null, "error": [code, message, null] })
+            }
+        };
+        self.send(message).await
+    }
+
+    async fn notify(&mut self, method: &str, params: Value) -> Result<(), StratumError> {
+        self.send(json!({ "id": null, "method": method, "params": params }))
+            .await
+    }
+
+    async fn send(&mut self, message: Value) -> Result<(), StratumError> {
+        let mut line = message.to_string();
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+/// Job id and solution of `mining.submit` parameters:
+/// `[worker, job_id, extranonce2, ntime, nonce]`
+fn parse_submit(params: &Value) -> Option<(String, Solution)> {
+    let field = |i: usize| params.get(i).and_then(Value::as_str);
+    let extranonce2 = hex::decode(field(2)?).ok()?;
+    if extranonce2.len() != EXTRANONCE2_SIZE {
+        return None;
+    }
+    Some((
+        field(1)?.to_string(),
+        Solution {
+            extranonce2,
+            ntime: u64::from_str_radix(field(3)?, 16).ok()?,
+            nonce: u32::from_str_radix(field(4)?, 16).ok()?,
+        },
+    ))
+}
@@ -0,0 +1,230 @@
+//! Per-worker share accounting
+//!
+//! Hashrate is estimated from the difficulty of accepted shares over the
+//! last [`HASHRATE_WINDOW`]: a share of difficulty `d` stands for about
+//! `d * 2^32` hashes.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Span accepted shares are averaged over
+pub const HASHRATE_WINDOW: Duration = Duration::from_secs(600);
+
+/// Workers tracked; the least recently active is forgotten beyond this
+pub const MAX_WORKERS: usize = 4096;
+
+const HASHES_PER_DIFF1: f64 = 4_294_967_296.0;
+
+/// What became of a submitted share
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareOutcome {
+    Accepted,
+    /// Accepted, and it solved a block the node connected
+    Block,
+    /// For a job that has been replaced
+    Stale,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WorkerStats {
+    pub worker: String,
+    /// Hashes per second, from accepted shares
+    pub hashrate: f64,
+    pub accepted_shares: u64,
+    /// Rejected shares, stale ones included
+    pub rejected_shares: u64,
+    pub stale_shares: u64,
+    pub blocks_found: u64,
+    /// Unix time of the last accepted share
+    pub last_share: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StratumStats {
+    pub connections: usize,
+    /// Hashes per second across workers
+    pub hashrate: f64,
+    pub accepted_shares: u64,
+    pub rejected_shares: u64,
+    pub blocks_found: u64,
+    /// Workers by name
+    pub workers: Vec<WorkerStats>,
+}
+
+struct WorkerRecord {
+    first_seen: Instant,
+    last_active: Instant,
+    /// Accepted shares in the hashrate window and their difficulty
+    recent: VecDeque<(Instant, f64)>,
+    accepted: u64,
+    rejected: u64,
+    stale: u64,
+    blocks: u64,
+    last_share: Option<u64>,
+}
+
+impl WorkerRecord {
+    fn new(now: Instant) -> Self {
+        Self {
+            first_seen: now,
+            last_active: now,
+            recent: VecDeque::new(),
+            accepted: 0,
+            rejected: 0,
+            stale: 0,
+            blocks: 0,
+            last_share: None,
+        }
+    }
+
+    fn hashrate(&self, now: Instant) -> f64 {
+        let work: f64 = self
+            .recent
+            .iter()
+            .filter(|(at, _)| now.saturating_duration_since(*at) <= HASHRATE_WINDOW)
+            .map(|(_, difficulty)| difficulty * HASHES_PER_DIFF1)
+            .sum();
+        // A new worker is averaged over the time it has been mining
+        let span = now
+            .saturating_duration_since(self.first_seen)
+            .clamp(Duration::from_secs(1), HASHRATE_WINDOW);
+        work / span.as_secs_f64()
+    }
+}
+
+/// Share counters of every worker and the open connections
+#[derive(Default)]
+pub struct StratumMetrics {
+    workers: Mutex<HashMap<String, WorkerRecord>>,
+    connections: AtomicUsize,
+}
+
+impl StratumMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connection_opened(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_closed(&self) {
+        self.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn record_share(&self, worker: &str, difficulty: f64, outcome: ShareOutcome, now: Instant) {
+        let mut workers = self.workers.lock();
+        if !workers.contains_key(worker) && workers.len() >= MAX_WORKERS {
+            let idle = workers
+                .iter()
+                .min_by_key(|(_, record)| record.last_active)
+                .map(|(name, _)| name.clone());
+            if let Some(idle) = idle {
+                workers.remove(&idle);
+            }
+        }
+        let record = workers
+            .entry(worker.to_string())
+            .or_insert_with(|| WorkerRecord::new(now));
+        record.last_active = now;
+        match outcome {
+            ShareOutcome::Accepted | ShareOutcome::Block => {
+                record.accepted += 1;
+                record.recent.push_back((now, difficulty));
+                record.last_share = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .ok()
+                    .map(|d| d.as_secs());
+                if outcome == ShareOutcome::Block {
+                    record.blocks += 1;
+                }
+            }
+            ShareOutcome::Stale => {
+                record.rejected += 1;
+                record.stale += 1;
+            }
+            ShareOutcome::Rejected => record.rejected += 1,
+        }
+        while record
+            .recent
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > HASHRATE_WINDOW)
+        {
+            record.recent.pop_front();
+        }
+    }
+
+    pub fn snapshot(&self, now: Instant) -> StratumStats {
+        let workers = self.workers.lock();
+        let mut stats: Vec<WorkerStats> = workers
+            .iter()
+            .map(|(name, record)| WorkerStats {
+                worker: name.clone(),
+                hashrate: record.hashrate(now),
+                accepted_shares: record.accepted,
+                rejected_shares: record.rejected,
+                stale_shares: record.stale,
+                blocks_found: record.blocks,
+                last_share: record.last_share,
+            })
+            .collect();
+        stats.sort_by(|a, b| a.worker.cmp(&b.worker));
+        StratumStats {
+            connections: self.connections(),
+            hashrate: stats.iter().map(|w| w.hashrate).sum(),
+            accepted_shares: stats.iter().map(|w| w.accepted_shares).sum(),
+            rejected_shares: stats.iter().map(|w| w.rejected_shares).sum(),
+            blocks_found: stats.iter().map(|w| w.blocks_found).sum(),
+            workers: stats,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_are_counted_per_worker() {
+        let metrics = StratumMetrics::new();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        metrics.record_share("rig1", 2.0, ShareOutcome::Accepted, at(0));
+        metrics.record_share("rig1", 2.0, ShareOutcome::Block, at(50));
+        metrics.record_share("rig1", 2.0, ShareOutcome::Stale, at(60));
+        metrics.record_share("rig2", 1.0, ShareOutcome::Rejected, at(60));
+
+        let stats = metrics.snapshot(at(100));
+        assert_eq!(stats.workers.len(), 2);
+        let rig1 = &stats.workers[0];
+        assert_eq!(rig1.worker, "rig1");
+        assert_eq!(
+            (
+                rig1.accepted_shares,
+                rig1.rejected_shares,
+                rig1.stale_shares,
+                rig1.blocks_found
+            ),
+            (2, 1, 1, 1)
+        );
+        // 4 difficulty-1 shares' worth of work over 100 seconds
+        assert!((rig1.hashrate - 4.0 * HASHES_PER_DIFF1 / 100.0).abs() < 1.0);
+        assert_eq!(stats.workers[1].hashrate, 0.0);
+        assert_eq!((stats.accepted_shares, stats.rejected_shares), (2, 2));
+
+        // Shares age out of the window
+        let later = metrics.snapshot(at(700));
+        assert_eq!(later.workers[0].hashrate, 0.0);
+        assert_eq!(later.workers[0].accepted_shares, 2);
+    }
+}
@@ -0,0 +1,120 @@
+//! Variable share difficulty
+//!
+//! Each connection's share difficulty is retargeted so it submits a share
+//! about every `target_share_secs`: once `retarget_secs` have passed, the
+//! observed share interval sets the new difficulty. Changes are capped at a
+//! factor of [`MAX_STEP`] per retarget and ignored when within [`DEADBAND`]
+//! of the current difficulty.
+
+use crate::config::StratumConfig;
+use std::time::{Duration, Instant};
+
+/// Largest factor a single retarget moves the difficulty by
+pub const MAX_STEP: f64 = 4.0;
+
+/// Relative changes smaller than this are not worth a new difficulty
+pub const DEADBAND: f64 = 0.2;
+
+#[derive(Debug)]
+pub struct VarDiff {
+    min: f64,
+    max: f64,
+    target_share: Duration,
+    retarget_after: Duration,
+    window_start: Instant,
+    shares: u32,
+}
+
+impl VarDiff {
+    pub fn new(config: &StratumConfig, now: Instant) -> Self {
+        Self {
+            min: config.min_difficulty,
+            max: config.max_difficulty,
+            target_share: Duration::from_secs(config.target_share_secs),
+            retarget_after: Duration::from_secs(config.retarget_secs),
+            window_start: now,
+            shares: 0,
+        }
+    }
+
+    pub fn record_share(&mut self) {
+        self.shares = self.shares.saturating_add(1);
+    }
+
+    /// New difficulty once the window is over and the share rate is off
+    /// target. A window without shares counts as one share, so a miner
+    /// that cannot reach its difficulty is eased down.
+    pub fn retarget(&mut self, current: f64, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < self.retarget_after {
+            return None;
+        }
+        let observed = elapsed.as_secs_f64() / f64::from(self.shares.max(1));
+        self.window_start = now;
+        self.shares = 0;
+
+        let factor = (self.target_share.as_secs_f64() / observed).clamp(1.0 / MAX_STEP, MAX_STEP);
+        let next = (current * factor).clamp(self.min, self.max);
+        ((next / current - 1.0).abs() > DEADBAND).then_some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vardiff(now: Instant) -> VarDiff {
+        let config = StratumConfig {
+            min_difficulty: 0.5,
+            max_difficulty: 64.0,
+            target_share_secs: 10,
+            retarget_secs: 60,
+            ..StratumConfig::default()
+        };
+        VarDiff::new(&config, now)
+    }
+
+    #[test]
+    fn difficulty_follows_the_share_rate() {
+        let start = Instant::now();
+        let mut vardiff = vardiff(start);
+
+        // 12 shares in 60s is twice the target rate
+        (0..12).for_each(|_| vardiff.record_share());
+        assert_eq!(vardiff.retarget(4.0, start + Duration::from_secs(30)), None);
+        assert_eq!(
+            vardiff.retarget(4.0, start + Duration::from_secs(60)),
+            Some(8.0)
+        );
+
+        // On target: unchanged
+        (0..6).for_each(|_| vardiff.record_share());
+        assert_eq!(
+            vardiff.retarget(8.0, start + Duration::from_secs(120)),
+            None
+        );
+    }
+
+    #[test]
+    fn steps_are_capped_and_clamped() {
+        let start = Instant::now();
+        let mut vardiff = vardiff(start);
+
+        (0..600).for_each(|_| vardiff.record_share());
+        assert_eq!(
+            vardiff.retarget(4.0, start + Duration::from_secs(60)),
+            Some(16.0)
+        );
+        (0..600).for_each(|_| vardiff.record_share());
+        assert_eq!(
+            vardiff.retarget(32.0, start + Duration::from_secs(120)),
+            Some(64.0)
+        );
+
+        // No shares at all eases the difficulty down, not below the minimum
+        assert_eq!(
+            vardiff.retarget(1.0, start + Duration::from_secs(600)),
+            Some(0.5)
+        );
+    }
+}
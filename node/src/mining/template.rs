@@ -9,8 +9,9 @@ use thiserror::Error;
 
 use crate::mempool::TransactionPool;
 use crate::storage::ChainState;
-use super::coinbase::build_coinbase_transaction;
-use supernova_core::util::merkle::MerkleTree;
+use super::coinbase::{build_coinbase_transaction_with_payout, CoinbasePayout};
+use crate::config::MiningConfig;
+use supernova_core::consensus::witness;
 use wallet::quantum_wallet::Address;
 
 #[derive(Error, Debug)]
@@ -70,6 +71,31 @@ impl BlockTemplate {
         reward_address: &Address,
         treasury_address: &Address,
     ) -> Result<Self, TemplateError> {
+        Self::generate_with_payout(
+            chain_state,
+            mempool,
+            &MiningConfig::default(),
+            reward_address,
+            treasury_address,
+        )
+    }
+
+    /// Generate new block template paying the coinbase according to the
+    /// configured splits and rotation. `reward_address` is used when no
+    /// rotation is configured.
+    pub fn generate_with_payout(
+        chain_state: Arc<std::sync::RwLock<ChainState>>,
+        mempool: Arc<TransactionPool>,
+        mining_config: &MiningConfig,
+        reward_address: &Address,
+        treasury_address: &Address,
+    ) -> Result<Self, TemplateError> {
+        // Get current timestamp
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| TemplateError::ChainStateError(e.to_string()))?
+            .as_secs();
+
         // Get current chain state
         let chain = chain_state.read()
             .map_err(|_| TemplateError::ChainStateError("Lock poisoned".to_string()))?;
@@ -81,8 +107,13 @@ impl BlockTemplate {
         // `required_bits` rule `validate_block` enforces, so a block mined from
         // this template is accepted rather than rejected for wrong difficulty.
         // This replaces a hardcoded easy `0x207fffff`, which the testnet floor
-        // (0x1e0fffff) would reject.
-        let difficulty_bits = chain.get_difficulty_target();
+        // (0x1e0fffff) would reject. After a testnet stall the template's
+        // timestamp makes it eligible for the minimum difficulty.
+        let difficulty_bits = chain.difficulty_target_at(timestamp);
+
+        // Signal for the configured deployments while they are open
+        let version = chain.block_version(&mining_config.signal_deployments)
+            .map_err(|e| TemplateError::ChainStateError(e.to_string()))?;
 
         drop(chain); // Release lock
         
@@ -96,42 +127,37 @@ impl BlockTemplate {
         let total_fees: u64 = 0; // Placeholder - proper fee calculation needed
         
         // Build coinbase transaction
-        let coinbase = build_coinbase_transaction(
+        let payout = CoinbasePayout::from_config(mining_config, height, reward_address)
+            .map_err(|e| TemplateError::AddressError(e.to_string()))?;
+        let coinbase = build_coinbase_transaction_with_payout(
             height,
-            reward_address,
+            &payout,
             total_fees,
             treasury_address,
         ).map_err(|e| TemplateError::CoinbaseError(e.to_string()))?;
         
-        // Assemble all transactions (coinbase first)
+        // Assemble all transactions (coinbase first). Blocks carrying witness
+        // data commit to their wtxids in the coinbase.
         let mut all_transactions = vec![coinbase.clone()];
         all_transactions.extend(selected_txs);
+        witness::commit(&mut all_transactions, height);
         
         // Calculate merkle root using the EXACT algorithm consensus validation
-        // uses (supernova_core MerkleTree: SHA-256, re-hashed leaves, promote-odd),
-        // which is also what `to_block` -> `Block::new_with_params` recomputes into
-        // the block header. The mining-local SHA3-512 merkle (super::merkle) produced
-        // a different root for every block, so any block an external miner built from
-        // this template's advertised `merkle_root` was rejected by
-        // `Block::verify_merkle_root` in submit_block. Matching the consensus tree
-        // here keeps getblocktemplate honest and unblocks external mining.
-        let tx_hashes: Vec<[u8; 32]> = all_transactions.iter()
-            .map(|tx| tx.hash())
-            .collect();
-
-        let merkle_root = MerkleTree::new(&tx_hashes).root_hash();
-        
-        // Get current timestamp
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|e| TemplateError::ChainStateError(e.to_string()))?
-            .as_secs();
+        // uses (supernova_core MerkleTree: SHA-256, re-hashed leaves, promote-odd,
+        // over the leaves the height's txid rules select), which is also what
+        // `to_block` recomputes into the block header. The mining-local SHA3-512
+        // merkle (super::merkle) produced a different root for every block, so any
+        // block an external miner built from this template's advertised
+        // `merkle_root` was rejected by `Block::verify_merkle_root` in
+        // submit_block. Matching the consensus tree here keeps getblocktemplate
+        // honest and unblocks external mining.
+        let merkle_root = witness::merkle_root(&all_transactions, height);
         
         // Calculate coinbase value
         let coinbase_value = coinbase.outputs().iter().map(|o| o.value()).sum();
         
         Ok(Self {
-            version: 1,
+            version,
             previous_block_hash: prev_hash,
             merkle_root,
             timestamp,
@@ -146,15 +172,17 @@ impl BlockTemplate {
     /// Build actual Block from template (after nonce is found)
     pub fn to_block(&self, nonce: u32) -> Block {
         let mut block = Block::new_with_params(
-            1, // version
+            self.version,
             self.previous_block_hash,
             self.transactions.clone(),
             self.bits,
         );
         
-        // Set height and nonce via header
+        // Set height and nonce via header; the merkle root depends on the
+        // height through the txid rules it selects
         block.header.set_height(self.height);
         block.header.set_nonce(nonce);
+        block.header.merkle_root = block.calculate_merkle_root();
         block
     }
 }
@@ -180,17 +208,15 @@ mod tests {
     /// Regression test for R5-30: the merkle root advertised by the mining
     /// template MUST equal the consensus merkle root that `to_block` /
     /// `Block::validate` recompute, otherwise every externally mined block is
-    /// rejected by `Block::verify_merkle_root`. The template uses the
-    /// supernova_core `MerkleTree`; the block header is filled by
-    /// `Block::new_with_params`, which uses the same tree.
+    /// rejected by `Block::verify_merkle_root`. The template and the block
+    /// header both take the root from `consensus::witness::merkle_root`.
     #[test]
     fn template_merkle_root_matches_consensus() {
         for count in [1usize, 2, 3, 5, 8] {
             let txs = sample_txs(count);
 
             // Root computed the way BlockTemplate::generate now computes it.
-            let tx_hashes: Vec<[u8; 32]> = txs.iter().map(|tx| tx.hash()).collect();
-            let template_root = MerkleTree::new(&tx_hashes).root_hash();
+            let template_root = witness::merkle_root(&txs, 0);
 
             // Root the actual block will carry / consensus will enforce.
             let block = Block::new_with_params(1, [0u8; 32], txs.clone(), 0x1e0fffff);
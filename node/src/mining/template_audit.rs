@@ -0,0 +1,497 @@
+//! Template audit: how our block templates compare with blocks mined by others
+//!
+//! Every template handed out by `getblocktemplate` is snapshotted with the fee
+//! of each transaction it selected and of the pool transactions it left out.
+//! When a block we did not mine connects, it is compared with our newest
+//! template for the same height: the fees it claimed against ours, how many
+//! of its transactions we also selected, and why we lacked the rest:
+//!
+//! - `prioritisation`: we had the transaction but ranked it out
+//! - `policy`: our mempool rejected it (the rejection code is reported)
+//! - `not-in-mempool`: we never saw it
+//!
+//! A block's fees are what its coinbase claims above the subsidy. Fees of
+//! transactions we never pooled are not known one by one, so they are
+//! reported together as [`BlockAudit::unattributed_fees`].
+//!
+//! Retention is bounded: the last [`TEMPLATE_HISTORY`] templates, the last
+//! [`AUDIT_HISTORY`] audits, and the [`MAX_MISSING_PER_AUDIT`] highest-fee
+//! missing transactions of each.
+
+use crate::events::NodeEvent;
+use crate::metrics::RejectionTracker;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use supernova_core::types::block::Block;
+use supernova_core::types::block_subsidy;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+/// Templates kept for comparison
+pub const TEMPLATE_HISTORY: usize = 16;
+
+/// Audits of external blocks kept for the summary
+pub const AUDIT_HISTORY: usize = 288;
+
+/// Missing transactions kept per audit, highest fee first
+pub const MAX_MISSING_PER_AUDIT: usize = 20;
+
+/// Missing transactions listed in a summary
+pub const TOP_MISSING: usize = 10;
+
+/// Blocks we submitted, remembered so they are not audited
+const OWN_BLOCK_HISTORY: usize = 64;
+
+/// Why a transaction in an external block was not in our template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum MissingReason {
+    /// Never seen by our mempool
+    NotInMempool,
+    /// Rejected by our mempool policy
+    Policy,
+    /// In our mempool but not selected
+    Prioritisation,
+}
+
+/// A transaction an external block carried that our template did not
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MissingTransaction {
+    pub txid: String,
+    pub reason: MissingReason,
+    /// Fee, known only for transactions we had pooled
+    pub fee: Option<u64>,
+    /// Mempool rejection code, for `policy`
+    pub rejection: Option<String>,
+}
+
+/// Number of missing transactions per reason
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct MissingCounts {
+    pub not_in_mempool: usize,
+    pub policy: usize,
+    pub prioritisation: usize,
+}
+
+impl MissingCounts {
+    fn add(&mut self, reason: MissingReason) {
+        match reason {
+            MissingReason::NotInMempool => self.not_in_mempool += 1,
+            MissingReason::Policy => self.policy += 1,
+            MissingReason::Prioritisation => self.prioritisation += 1,
+        }
+    }
+}
+
+/// Comparison of one external block with our template for its height
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BlockAudit {
+    pub height: u64,
+    pub block_hash: String,
+    /// Seconds from our template to the block connecting
+    pub template_age_secs: u64,
+    /// Fees the block's coinbase claims above the subsidy
+    pub block_fees: u64,
+    /// Fees of the transactions our template selected
+    pub template_fees: u64,
+    /// `block_fees - template_fees`; positive when our template earned less
+    pub fee_delta: i64,
+    /// Non-coinbase transactions in the block
+    pub block_transactions: usize,
+    /// Non-coinbase transactions in our template
+    pub template_transactions: usize,
+    /// Transactions in both
+    pub shared_transactions: usize,
+    /// Share of the block's transactions our template also selected (1.0
+    /// for a block with none)
+    pub overlap: f64,
+    /// Fees of block transactions we had but did not select
+    pub prioritisation_fees: u64,
+    /// Block fees not accounted for by transactions we had pooled
+    pub unattributed_fees: u64,
+    pub missing_counts: MissingCounts,
+    /// Highest-fee missing transactions, unknown fees last
+    pub missing: Vec<MissingTransaction>,
+}
+
+/// Summary of the most recent audits
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TemplateAuditSummary {
+    pub blocks_audited: usize,
+    /// Mean of `fee_delta` over the audits
+    pub average_fee_delta: f64,
+    /// Mean of `overlap` over the audits
+    pub average_overlap: f64,
+    pub unattributed_fees: u64,
+    pub missing_counts: MissingCounts,
+    /// Highest-fee missing transactions across the audits
+    pub top_missing: Vec<MissingTransaction>,
+    /// The audits, newest first
+    pub audits: Vec<BlockAudit>,
+}
+
+/// A template as handed out, with the fees needed to compare it
+struct TemplateSnapshot {
+    height: u64,
+    created_at: u64,
+    /// Selected transactions and their fees
+    selected: HashMap<[u8; 32], u64>,
+    /// Pool transactions left out and their fees
+    candidates: HashMap<[u8; 32], u64>,
+}
+
+#[derive(Default)]
+struct AuditState {
+    templates: VecDeque<TemplateSnapshot>,
+    audits: VecDeque<BlockAudit>,
+    own_blocks: VecDeque<[u8; 32]>,
+}
+
+/// Records templates and audits external blocks against them
+pub struct TemplateAuditor {
+    rejections: Arc<RejectionTracker>,
+    state: Mutex<AuditState>,
+}
+
+impl TemplateAuditor {
+    /// Auditor attributing policy misses with the mempool's `rejections`
+    pub fn new(rejections: Arc<RejectionTracker>) -> Self {
+        Self {
+            rejections,
+            state: Mutex::new(AuditState::default()),
+        }
+    }
+
+    /// Snapshot a template for `height`: the transactions it `selected` and
+    /// the pool `candidates` it left out, each with its fee
+    pub fn record_template(
+        &self,
+        height: u64,
+        selected: impl IntoIterator<Item = ([u8; 32], u64)>,
+        candidates: impl IntoIterator<Item = ([u8; 32], u64)>,
+    ) {
+        let selected: HashMap<_, _> = selected.into_iter().collect();
+        let candidates = candidates
+            .into_iter()
+            .filter(|(txid, _)| !selected.contains_key(txid))
+            .collect();
+        let mut state = self.state.lock();
+        if state.templates.len() >= TEMPLATE_HISTORY {
+            state.templates.pop_front();
+        }
+        state.templates.push_back(TemplateSnapshot {
+            height,
+            created_at: now_secs(),
+            selected,
+            candidates,
+        });
+    }
+
+    /// Remember a block we are submitting so it isn't audited when it connects
+    pub fn record_own_block(&self, block_hash: [u8; 32]) {
+        let mut state = self.state.lock();
+        if state.own_blocks.len() >= OWN_BLOCK_HISTORY {
+            state.own_blocks.pop_front();
+        }
+        state.own_blocks.push_back(block_hash);
+    }
+
+    /// Compare a connected block with our newest template for its height and
+    /// keep the result. Returns `None` for our own blocks and for heights we
+    /// made no template for.
+    pub fn audit_block(&self, block: &Block) -> Option<BlockAudit> {
+        let block_hash = block.hash();
+        let height = block.height();
+        let mut state = self.state.lock();
+        if state.own_blocks.contains(&block_hash) {
+            return None;
+        }
+        let template = state.templates.iter().rev().find(|t| t.height == height)?;
+
+        let block_fees = block
+            .transactions()
+            .iter()
+            .find(|tx| tx.is_coinbase())
+            .map(|coinbase| {
+                let claimed: u64 = coinbase.outputs().iter().map(|o| o.value()).sum();
+                claimed.saturating_sub(block_subsidy(height))
+            })
+            .unwrap_or(0);
+        let template_fees: u64 = template.selected.values().sum();
+
+        let mut block_transactions = 0;
+        let mut shared_transactions = 0;
+        let mut known_fees = 0u64;
+        let mut prioritisation_fees = 0u64;
+        let mut missing_counts = MissingCounts::default();
+        let mut missing = Vec::new();
+        for tx in block.transactions().iter().filter(|tx| !tx.is_coinbase()) {
+            block_transactions += 1;
+            let txid = tx.hash();
+            if let Some(fee) = template.selected.get(&txid) {
+                shared_transactions += 1;
+                known_fees = known_fees.saturating_add(*fee);
+                continue;
+            }
+            let (reason, fee, rejection) = match template.candidates.get(&txid) {
+                Some(fee) => (MissingReason::Prioritisation, Some(*fee), None),
+                None => match self.rejections.latest_reason(&txid) {
+                    Some(code) => (MissingReason::Policy, None, Some(code)),
+                    None => (MissingReason::NotInMempool, None, None),
+                },
+            };
+            if let Some(fee) = fee {
+                known_fees = known_fees.saturating_add(fee);
+                prioritisation_fees = prioritisation_fees.saturating_add(fee);
+            }
+            missing_counts.add(reason);
+            missing.push(MissingTransaction {
+                txid: hex::encode(txid),
+                reason,
+                fee,
+                rejection,
+            });
+        }
+        sort_by_fee(&mut missing);
+        missing.truncate(MAX_MISSING_PER_AUDIT);
+
+        let audit = BlockAudit {
+            height,
+            block_hash: hex::encode(block_hash),
+            template_age_secs: now_secs().saturating_sub(template.created_at),
+            block_fees,
+            template_fees,
+            fee_delta: block_fees as i64 - template_fees as i64,
+            block_transactions,
+            template_transactions: template.selected.len(),
+            shared_transactions,
+            overlap: if block_transactions == 0 {
+                1.0
+            } else {
+                shared_transactions as f64 / block_transactions as f64
+            },
+            prioritisation_fees,
+            unattributed_fees: block_fees.saturating_sub(known_fees),
+            missing_counts,
+            missing,
+        };
+        if state.audits.len() >= AUDIT_HISTORY {
+            state.audits.pop_front();
+        }
+        state.audits.push_back(audit.clone());
+        Some(audit)
+    }
+
+    /// Summary of the `last` most recent audits
+    pub fn summary(&self, last: usize) -> TemplateAuditSummary {
+        let state = self.state.lock();
+        let audits: Vec<BlockAudit> = state.audits.iter().rev().take(last).cloned().collect();
+        drop(state);
+
+        let count = audits.len();
+        let mean = |total: f64| if count == 0 { 0.0 } else { total / count as f64 };
+        let mut missing_counts = MissingCounts::default();
+        let mut seen = HashSet::new();
+        let mut top_missing = Vec::new();
+        for audit in &audits {
+            missing_counts.not_in_mempool += audit.missing_counts.not_in_mempool;
+            missing_counts.policy += audit.missing_counts.policy;
+            missing_counts.prioritisation += audit.missing_counts.prioritisation;
+            for tx in &audit.missing {
+                if seen.insert(tx.txid.clone()) {
+                    top_missing.push(tx.clone());
+                }
+            }
+        }
+        sort_by_fee(&mut top_missing);
+        top_missing.truncate(TOP_MISSING);
+
+        TemplateAuditSummary {
+            blocks_audited: count,
+            average_fee_delta: mean(audits.iter().map(|a| a.fee_delta as f64).sum()),
+            average_overlap: mean(audits.iter().map(|a| a.overlap).sum()),
+            unattributed_fees: audits.iter().map(|a| a.unattributed_fees).sum(),
+            missing_counts,
+            top_missing,
+            audits,
+        }
+    }
+
+    /// Route a node event to the auditor
+    pub fn process_event(&self, event: &NodeEvent) {
+        if let NodeEvent::BlockConnected(block) = event {
+            if let Some(audit) = self.audit_block(block) {
+                tracing::debug!(
+                    "Template audit at height {}: fee delta {}, overlap {:.2}",
+                    audit.height,
+                    audit.fee_delta,
+                    audit.overlap
+                );
+            }
+        }
+    }
+
+    /// Consume node events until the bus closes
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<NodeEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.process_event(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Template audit lagged; {} node events skipped", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+/// Highest fee first, unknown fees last
+fn sort_by_fee(transactions: &mut [MissingTransaction]) {
+    transactions.sort_by(|a, b| b.fee.cmp(&a.fee));
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::RejectionDomain;
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+
+    const HEIGHT: u64 = 10;
+
+    fn tx(seed: u8) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new([seed; 32], 0, vec![], 0)],
+            vec![TransactionOutput::new(1_000, vec![seed; 25])],
+            0,
+        )
+    }
+
+    /// A block at `HEIGHT` whose coinbase claims the subsidy plus `fees`
+    fn block(fees: u64, txs: Vec<Transaction>) -> Block {
+        let coinbase = Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(vec![1, 2, 3])],
+            vec![TransactionOutput::new(block_subsidy(HEIGHT) + fees, vec![0xcb; 25])],
+            0,
+        );
+        let mut transactions = vec![coinbase];
+        transactions.extend(txs);
+        Block::new(
+            BlockHeader::new_with_height(1, [0; 32], [0; 32], 0, 0, 0, HEIGHT),
+            transactions,
+        )
+    }
+
+    fn auditor() -> (TemplateAuditor, Arc<RejectionTracker>) {
+        let rejections = Arc::new(RejectionTracker::new(RejectionDomain::Mempool));
+        (TemplateAuditor::new(Arc::clone(&rejections)), rejections)
+    }
+
+    #[test]
+    fn block_covered_by_our_template_has_no_delta() {
+        let (auditor, _) = auditor();
+        let (a, b) = (tx(1), tx(2));
+        auditor.record_template(HEIGHT, [(a.hash(), 3_000), (b.hash(), 2_000)], []);
+
+        let audit = auditor.audit_block(&block(5_000, vec![a, b])).unwrap();
+        assert_eq!(audit.block_fees, 5_000);
+        assert_eq!(audit.template_fees, 5_000);
+        assert_eq!(audit.fee_delta, 0);
+        assert_eq!(audit.shared_transactions, 2);
+        assert_eq!(audit.overlap, 1.0);
+        assert_eq!(audit.unattributed_fees, 0);
+        assert!(audit.missing.is_empty());
+    }
+
+    #[test]
+    fn missing_transactions_are_attributed() {
+        let (auditor, rejections) = auditor();
+        let (ours, ranked_out, rejected, unseen) = (tx(1), tx(2), tx(3), tx(4));
+        rejections.record("fee-too-low", &rejected.hash(), None, "below minimum");
+        auditor.record_template(HEIGHT, [(ours.hash(), 3_000)], [(ranked_out.hash(), 1_000)]);
+
+        // The block also carries a 50k fee transaction we never saw, plus
+        // 500 for the one we rejected
+        let external = block(
+            3_000 + 1_000 + 500 + 50_000,
+            vec![ours, ranked_out, rejected, unseen.clone()],
+        );
+        let audit = auditor.audit_block(&external).unwrap();
+
+        assert_eq!(audit.fee_delta, 51_500);
+        assert_eq!(audit.prioritisation_fees, 1_000);
+        assert_eq!(audit.unattributed_fees, 50_500);
+        assert_eq!(audit.shared_transactions, 1);
+        assert_eq!(audit.overlap, 0.25);
+        assert_eq!(
+            audit.missing_counts,
+            MissingCounts {
+                not_in_mempool: 1,
+                policy: 1,
+                prioritisation: 1,
+            }
+        );
+        assert_eq!(audit.missing[0].reason, MissingReason::Prioritisation);
+        assert_eq!(audit.missing[0].fee, Some(1_000));
+        let policy = audit
+            .missing
+            .iter()
+            .find(|m| m.reason == MissingReason::Policy)
+            .unwrap();
+        assert_eq!(policy.rejection.as_deref(), Some("fee-too-low"));
+        assert!(audit.missing.iter().any(|m| {
+            m.reason == MissingReason::NotInMempool && m.txid == hex::encode(unseen.hash())
+        }));
+
+        let summary = auditor.summary(10);
+        assert_eq!(summary.blocks_audited, 1);
+        assert_eq!(summary.average_fee_delta, 51_500.0);
+        assert_eq!(summary.unattributed_fees, 50_500);
+        assert_eq!(summary.top_missing.len(), 3);
+    }
+
+    #[test]
+    fn own_blocks_and_untemplated_heights_are_skipped() {
+        let (auditor, _) = auditor();
+        let mined = block(0, vec![]);
+        assert!(auditor.audit_block(&mined).is_none());
+
+        auditor.record_template(HEIGHT, [], []);
+        auditor.record_own_block(mined.hash());
+        assert!(auditor.audit_block(&mined).is_none());
+        assert_eq!(auditor.summary(10).blocks_audited, 0);
+    }
+
+    #[test]
+    fn retention_is_bounded() {
+        let (auditor, _) = auditor();
+        for _ in 0..TEMPLATE_HISTORY + 5 {
+            auditor.record_template(HEIGHT, [], []);
+        }
+        let txs: Vec<Transaction> = (0..MAX_MISSING_PER_AUDIT as u8 + 5).map(tx).collect();
+        for _ in 0..AUDIT_HISTORY + 5 {
+            let audit = auditor.audit_block(&block(0, txs.clone())).unwrap();
+            assert_eq!(audit.missing.len(), MAX_MISSING_PER_AUDIT);
+            assert_eq!(audit.missing_counts.not_in_mempool, txs.len());
+        }
+
+        let state = auditor.state.lock();
+        assert_eq!(state.templates.len(), TEMPLATE_HISTORY);
+        assert_eq!(state.audits.len(), AUDIT_HISTORY);
+        drop(state);
+        assert_eq!(auditor.summary(usize::MAX).blocks_audited, AUDIT_HISTORY);
+        assert_eq!(auditor.summary(3).audits.len(), 3);
+    }
+}
@@ -0,0 +1,341 @@
+//! Template manager: the current block template and long polling
+//!
+//! Templates are cached until the tip moves or the mempool changes, so
+//! miners polling at the same time share one build. Every template carries a
+//! `longpollid` naming the tip it builds on and the mempool fees seen when it
+//! was built. A miner that sends the id back is held until the tip changes
+//! or enough new fees have arrived to be worth switching work to, as BIP22
+//! long polling describes.
+
+use crate::events::NodeEvent;
+use crate::mempool::TransactionPool;
+use crate::storage::ChainState;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use supernova_core::types::block::Block;
+use thiserror::Error;
+use tokio::sync::{broadcast, watch};
+
+use super::template::BlockTemplate;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TemplateManagerError {
+    #[error("Malformed longpollid: {0}")]
+    InvalidLongPollId(String),
+}
+
+/// Fees of mempool transactions
+pub trait MempoolFees: Send + Sync {
+    fn fee(&self, txid: &[u8; 32]) -> Option<u64>;
+}
+
+impl MempoolFees for TransactionPool {
+    fn fee(&self, txid: &[u8; 32]) -> Option<u64> {
+        self.get_transaction_fee(txid)
+    }
+}
+
+/// What a template was built against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChainView {
+    tip: [u8; 32],
+    /// Total fees of transactions admitted since startup
+    mempool_fees: u64,
+    /// Bumped on every mempool admission or removal
+    mempool_version: u64,
+}
+
+impl ChainView {
+    fn longpoll_id(&self) -> String {
+        format!("{}-{}", hex::encode(self.tip), self.mempool_fees)
+    }
+}
+
+/// Caches the current template and wakes long polls
+pub struct TemplateManager {
+    fees: Arc<dyn MempoolFees>,
+    view: watch::Sender<ChainView>,
+    cached: Mutex<Option<(ChainView, Arc<BlockTemplate>)>>,
+}
+
+impl TemplateManager {
+    pub fn new(tip: [u8; 32], fees: Arc<dyn MempoolFees>) -> Self {
+        let (view, _) = watch::channel(ChainView {
+            tip,
+            mempool_fees: 0,
+            mempool_version: 0,
+        });
+        Self {
+            fees,
+            view,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// `longpollid` of a template built now
+    pub fn longpoll_id(&self) -> String {
+        self.view.borrow().longpoll_id()
+    }
+
+    /// The current template and its `longpollid`, built with `build` when
+    /// the cached one is out of date
+    pub fn template<E>(
+        &self,
+        build: impl FnOnce() -> Result<BlockTemplate, E>,
+    ) -> Result<(Arc<BlockTemplate>, String), E> {
+        // Held while building so concurrent requests share one build
+        let mut cached = self.cached.lock();
+        let view = *self.view.borrow();
+        if let Some((built_at, template)) = cached.as_ref() {
+            if *built_at == view {
+                return Ok((Arc::clone(template), view.longpoll_id()));
+            }
+        }
+        let template = Arc::new(build()?);
+        *cached = Some((view, Arc::clone(&template)));
+        Ok((template, view.longpoll_id()))
+    }
+
+    /// Wait until the template named by `longpollid` is out of date: the tip
+    /// moved, or at least `fee_delta` in new fees arrived (never, when
+    /// zero). Returns after `timeout` regardless.
+    pub async fn wait(
+        &self,
+        longpollid: &str,
+        fee_delta: u64,
+        timeout: Duration,
+    ) -> Result<(), TemplateManagerError> {
+        let (tip, fees) = parse_longpoll_id(longpollid)?;
+        let stale = |view: &ChainView| {
+            view.tip != tip
+                || (fee_delta > 0 && view.mempool_fees.saturating_sub(fees) >= fee_delta)
+        };
+        let mut view = self.view.subscribe();
+        let changed = async {
+            loop {
+                if stale(&view.borrow_and_update()) {
+                    return;
+                }
+                if view.changed().await.is_err() {
+                    return;
+                }
+            }
+        };
+        let _ = tokio::time::timeout(timeout, changed).await;
+        Ok(())
+    }
+
+    /// Track the tip and mempool from a node event
+    pub fn process_event(&self, event: &NodeEvent) {
+        match event {
+            NodeEvent::BlockConnected(block) => {
+                let tip = block.hash();
+                self.view.send_modify(|view| view.tip = tip);
+            }
+            NodeEvent::BlockDisconnected(block) => {
+                let tip = *block.prev_block_hash();
+                self.view.send_modify(|view| view.tip = tip);
+            }
+            NodeEvent::MempoolTransaction(tx) => {
+                let fee = self.fees.fee(&tx.hash()).unwrap_or(0);
+                self.view.send_modify(|view| {
+                    view.mempool_fees = view.mempool_fees.saturating_add(fee);
+                    view.mempool_version += 1;
+                });
+            }
+            NodeEvent::MempoolRemoved { .. } => {
+                self.view.send_modify(|view| view.mempool_version += 1);
+            }
+            _ => {}
+        }
+    }
+
+    /// Consume node events until the bus closes
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<NodeEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.process_event(&event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Template manager lagged; {} node events skipped", skipped);
+                    // The missed events may have moved the tip; rebuild
+                    *self.cached.lock() = None;
+                    self.view.send_modify(|view| view.mempool_version += 1);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn parse_longpoll_id(longpollid: &str) -> Result<([u8; 32], u64), TemplateManagerError> {
+    let invalid = || TemplateManagerError::InvalidLongPollId(longpollid.to_string());
+    let (tip_hex, fees) = longpollid.split_once('-').ok_or_else(invalid)?;
+    let mut tip = [0u8; 32];
+    hex::decode_to_slice(tip_hex, &mut tip).map_err(|_| invalid())?;
+    let fees = fees.parse().map_err(|_| invalid())?;
+    Ok((tip, fees))
+}
+
+/// Why a submitted block cannot extend the active chain, as a BIP22 reject
+/// reason: `duplicate` when it is already known, `stale-prevblk` when it
+/// builds on a block that is no longer the tip
+pub fn stale_reason(chain: &ChainState, block: &Block) -> Option<&'static str> {
+    if chain.get_block(&block.hash()).is_some() {
+        Some("duplicate")
+    } else if *block.prev_block_hash() != chain.get_best_block_hash() {
+        Some("stale-prevblk")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use supernova_core::types::block::BlockHeader;
+    use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+
+    struct FakeFees(HashMap<[u8; 32], u64>);
+
+    impl MempoolFees for FakeFees {
+        fn fee(&self, txid: &[u8; 32]) -> Option<u64> {
+            self.0.get(txid).copied()
+        }
+    }
+
+    fn spend(seed: u8) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new([seed; 32], 0, vec![], 0)],
+            vec![TransactionOutput::new(1_000, vec![seed; 25])],
+            0,
+        )
+    }
+
+    fn block_on(prev: [u8; 32], height: u64) -> Block {
+        let header = BlockHeader::new_with_height(1, prev, [0; 32], 0, 0x207f_ffff, 0, height);
+        Block::new(header, vec![Transaction::new_coinbase()])
+    }
+
+    fn template_on(tip: [u8; 32]) -> BlockTemplate {
+        BlockTemplate {
+            version: 1,
+            previous_block_hash: tip,
+            merkle_root: [0; 32],
+            timestamp: 0,
+            bits: 0x207f_ffff,
+            height: 1,
+            transactions: Vec::new(),
+            total_fees: 0,
+            coinbase_value: 0,
+        }
+    }
+
+    fn manager(tip: [u8; 32], fees: &[(&Transaction, u64)]) -> Arc<TemplateManager> {
+        let fees = fees.iter().map(|(tx, fee)| (tx.hash(), *fee)).collect();
+        Arc::new(TemplateManager::new(tip, Arc::new(FakeFees(fees))))
+    }
+
+    #[test]
+    fn templates_are_cached_until_the_tip_or_mempool_changes() {
+        let tx = spend(1);
+        let manager = manager([1; 32], &[(&tx, 500)]);
+        let mut builds = 0;
+        let mut build = || -> Result<BlockTemplate, ()> {
+            builds += 1;
+            Ok(template_on([1; 32]))
+        };
+
+        let (first, id) = manager.template(&mut build).unwrap();
+        let (second, same_id) = manager.template(&mut build).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(id, same_id);
+        assert_eq!(id, format!("{}-0", hex::encode([1u8; 32])));
+
+        manager.process_event(&NodeEvent::MempoolTransaction(tx));
+        let (_, id) = manager.template(&mut build).unwrap();
+        assert_eq!(id, format!("{}-500", hex::encode([1u8; 32])));
+
+        manager.process_event(&NodeEvent::BlockConnected(block_on([1; 32], 1)));
+        manager.template(&mut build).unwrap();
+        assert_eq!(builds, 3);
+    }
+
+    #[tokio::test]
+    async fn long_poll_returns_when_the_tip_changes() {
+        let manager = manager([1; 32], &[]);
+        let id = manager.longpoll_id();
+
+        let waiter = tokio::spawn({
+            let manager = Arc::clone(&manager);
+            async move { manager.wait(&id, 0, Duration::from_secs(30)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            !waiter.is_finished(),
+            "long poll returned before the tip moved"
+        );
+
+        let block = block_on([1; 32], 1);
+        manager.process_event(&NodeEvent::BlockConnected(block.clone()));
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("long poll still held after the tip moved")
+            .unwrap()
+            .unwrap();
+        assert!(manager
+            .longpoll_id()
+            .starts_with(&hex::encode(block.hash())));
+    }
+
+    #[tokio::test]
+    async fn long_poll_returns_once_new_fees_pass_the_threshold() {
+        let (small, large) = (spend(1), spend(2));
+        let manager = manager([1; 32], &[(&small, 100), (&large, 900)]);
+        let id = manager.longpoll_id();
+
+        let waiter = tokio::spawn({
+            let manager = Arc::clone(&manager);
+            async move { manager.wait(&id, 1_000, Duration::from_secs(30)).await }
+        });
+        manager.process_event(&NodeEvent::MempoolTransaction(small));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiter.is_finished(), "100 novas must not end the poll");
+
+        manager.process_event(&NodeEvent::MempoolTransaction(large));
+        tokio::time::timeout(Duration::from_secs(5), waiter)
+            .await
+            .expect("long poll still held past the fee threshold")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn long_poll_times_out_and_rejects_malformed_ids() {
+        let manager = manager([1; 32], &[]);
+        let id = manager.longpoll_id();
+        manager
+            .wait(&id, 0, Duration::from_millis(20))
+            .await
+            .unwrap();
+
+        // An id from an older tip is already out of date
+        let old = format!("{}-0", hex::encode([9u8; 32]));
+        tokio::time::timeout(
+            Duration::from_secs(5),
+            manager.wait(&old, 0, Duration::from_secs(30)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        for malformed in ["", "abcd-0", &format!("{}-x", hex::encode([1u8; 32]))] {
+            assert!(matches!(
+                manager.wait(malformed, 0, Duration::from_secs(30)).await,
+                Err(TemplateManagerError::InvalidLongPollId(_))
+            ));
+        }
+    }
+}
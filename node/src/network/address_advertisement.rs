@@ -0,0 +1,355 @@
+//! Advertised address selection
+//!
+//! Decides which of our addresses are handed to peers through identify and
+//! Kademlia. Operators behind NAT can pin public addresses with
+//! `external_addrs`; those always win. Otherwise we advertise routable listen
+//! addresses plus addresses peers report observing us at, but only once
+//! `observed_addr_confirmations` distinct peers agree, so a single peer (or a
+//! handful of sybils below the threshold) cannot make us advertise an address
+//! of its choosing.
+
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use crate::config::NetworkConfig;
+
+/// Observed addresses tracked at once; the least supported are evicted first
+pub const MAX_OBSERVED_ADDRS: usize = 64;
+
+/// Observations not refreshed within this window are forgotten
+pub const OBSERVATION_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct Observation {
+    reporters: HashSet<PeerId>,
+    last_seen: Instant,
+}
+
+#[derive(Debug)]
+pub struct AddressAdvertiser {
+    explicit: Vec<Multiaddr>,
+    listen: Vec<Multiaddr>,
+    /// Public address forwarded to us by the gateway (see `network::nat`)
+    mapped: Option<Multiaddr>,
+    confirmations: usize,
+    observed: HashMap<Multiaddr, Observation>,
+}
+
+impl AddressAdvertiser {
+    pub fn new(explicit: Vec<Multiaddr>, confirmations: usize) -> Self {
+        Self {
+            explicit,
+            listen: Vec::new(),
+            mapped: None,
+            confirmations: confirmations.max(1),
+            observed: HashMap::new(),
+        }
+    }
+
+    pub fn from_network_config(config: &NetworkConfig) -> Self {
+        Self::new(config.external_addrs.clone(), config.observed_addr_confirmations)
+    }
+
+    /// Explicitly configured external addresses
+    pub fn explicit(&self) -> &[Multiaddr] {
+        &self.explicit
+    }
+
+    /// Addresses the swarm is actually bound to
+    pub fn listen_addrs(&self) -> &[Multiaddr] {
+        &self.listen
+    }
+
+    pub fn add_listen_addr(&mut self, addr: Multiaddr) {
+        if !self.listen.contains(&addr) {
+            self.listen.push(addr);
+        }
+    }
+
+    pub fn remove_listen_addr(&mut self, addr: &Multiaddr) {
+        self.listen.retain(|a| a != addr);
+    }
+
+    /// Record the public address of a gateway port mapping, or its loss
+    pub fn set_mapped(&mut self, addr: Option<Multiaddr>) {
+        self.mapped = addr;
+    }
+
+    /// Record that `peer` sees us at `observed`. Returns the normalized
+    /// address if this report is the one that confirms it.
+    pub fn record_observed(&mut self, peer: PeerId, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.record_observed_at(peer, observed, Instant::now())
+    }
+
+    fn record_observed_at(
+        &mut self,
+        peer: PeerId,
+        observed: &Multiaddr,
+        now: Instant,
+    ) -> Option<Multiaddr> {
+        let addr = self.normalize(observed)?;
+        self.observed
+            .retain(|_, o| now.saturating_duration_since(o.last_seen) < OBSERVATION_TTL);
+
+        if !self.observed.contains_key(&addr) && self.observed.len() >= MAX_OBSERVED_ADDRS {
+            let weakest = self
+                .observed
+                .iter()
+                .min_by_key(|(_, o)| (o.reporters.len(), o.last_seen))
+                .map(|(a, _)| a.clone());
+            if let Some(weakest) = weakest {
+                self.observed.remove(&weakest);
+            }
+        }
+
+        let entry = self.observed.entry(addr.clone()).or_insert_with(|| Observation {
+            reporters: HashSet::new(),
+            last_seen: now,
+        });
+        entry.last_seen = now;
+        let newly_reported = entry.reporters.insert(peer);
+        (newly_reported && entry.reporters.len() == self.confirmations).then_some(addr)
+    }
+
+    /// Observed addresses reported by enough distinct peers
+    pub fn confirmed_observed(&self) -> Vec<Multiaddr> {
+        let mut confirmed: Vec<Multiaddr> = self
+            .observed
+            .iter()
+            .filter(|(_, o)| o.reporters.len() >= self.confirmations)
+            .map(|(a, _)| a.clone())
+            .collect();
+        confirmed.sort_by_key(|a| a.to_string());
+        confirmed
+    }
+
+    /// The addresses to advertise: explicit addresses if configured,
+    /// otherwise the gateway mapping, routable listen addresses and confirmed
+    /// observations.
+    pub fn advertised(&self) -> Vec<Multiaddr> {
+        if !self.explicit.is_empty() {
+            return self.explicit.clone();
+        }
+        let mut advertised: Vec<Multiaddr> = self.mapped.iter().cloned().collect();
+        for addr in self
+            .listen
+            .iter()
+            .filter(|a| ip_of(a).is_some_and(is_routable))
+        {
+            if !advertised.contains(addr) {
+                advertised.push(addr.clone());
+            }
+        }
+        for addr in self.confirmed_observed() {
+            if !advertised.contains(&addr) {
+                advertised.push(addr);
+            }
+        }
+        advertised
+    }
+
+    /// Reduce an observed address to `/ip*/<ip>/tcp/<port>`, replacing the
+    /// observed port with our listen port for that address family: outbound
+    /// connections are seen from an ephemeral port nobody can dial back.
+    /// Non-routable addresses are dropped.
+    fn normalize(&self, observed: &Multiaddr) -> Option<Multiaddr> {
+        let ip = ip_of(observed).filter(|ip| is_routable(*ip))?;
+        let observed_port = observed.iter().find_map(|p| match p {
+            Protocol::Tcp(port) => Some(port),
+            _ => None,
+        })?;
+        let port = self
+            .listen
+            .iter()
+            .filter(|a| ip_of(a).is_some_and(|l| l.is_ipv4() == ip.is_ipv4()))
+            .find_map(|a| {
+                a.iter().find_map(|p| match p {
+                    Protocol::Tcp(port) => Some(port),
+                    _ => None,
+                })
+            })
+            .unwrap_or(observed_port);
+
+        let mut addr = Multiaddr::empty();
+        addr.push(match ip {
+            IpAddr::V4(v4) => Protocol::Ip4(v4),
+            IpAddr::V6(v6) => Protocol::Ip6(v6),
+        });
+        addr.push(Protocol::Tcp(port));
+        Some(addr)
+    }
+}
+
+fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|p| match p {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+/// Whether an address is worth handing to remote peers
+pub(crate) fn is_routable(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            !(v4.is_unspecified()
+                || v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_multicast())
+        }
+        IpAddr::V6(v6) => {
+            let segments = v6.segments();
+            !(v6.is_unspecified()
+                || v6.is_loopback()
+                || v6.is_multicast()
+                // fc00::/7 unique local, fe80::/10 link local
+                || (segments[0] & 0xfe00) == 0xfc00
+                || (segments[0] & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> Multiaddr {
+        s.parse().unwrap()
+    }
+
+    fn advertiser(confirmations: usize) -> AddressAdvertiser {
+        let mut a = AddressAdvertiser::new(Vec::new(), confirmations);
+        a.add_listen_addr(addr("/ip4/0.0.0.0/tcp/8000"));
+        a.add_listen_addr(addr("/ip6/::/tcp/8001"));
+        a
+    }
+
+    #[test]
+    fn observed_address_needs_distinct_confirmations() {
+        let mut a = advertiser(3);
+        let seen = addr("/ip4/198.51.100.20/tcp/51234");
+        let (p1, p2, p3) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        assert_eq!(a.record_observed(p1, &seen), None);
+        // The same peer repeating itself does not count twice
+        assert_eq!(a.record_observed(p1, &seen), None);
+        assert_eq!(a.record_observed(p2, &seen), None);
+        assert!(a.advertised().is_empty());
+
+        // Normalized to our listen port, not the ephemeral one observed
+        let confirmed = a.record_observed(p3, &seen);
+        assert_eq!(confirmed, Some(addr("/ip4/198.51.100.20/tcp/8000")));
+        assert_eq!(a.advertised(), vec![addr("/ip4/198.51.100.20/tcp/8000")]);
+
+        // Already confirmed: further reports are not "new" confirmations
+        assert_eq!(a.record_observed(PeerId::random(), &seen), None);
+
+        // IPv6 observations take the IPv6 listen port
+        let seen6 = addr("/ip6/2001:db8:1::5/tcp/40000");
+        for _ in 0..3 {
+            a.record_observed(PeerId::random(), &seen6);
+        }
+        assert!(a.confirmed_observed().contains(&addr("/ip6/2001:db8:1::5/tcp/8001")));
+
+        // Private and loopback observations are never tracked
+        for _ in 0..5 {
+            assert_eq!(a.record_observed(PeerId::random(), &addr("/ip4/10.0.0.7/tcp/8000")), None);
+            assert_eq!(a.record_observed(PeerId::random(), &addr("/ip4/127.0.0.1/tcp/8000")), None);
+        }
+        assert_eq!(a.confirmed_observed().len(), 2);
+    }
+
+    #[test]
+    fn stale_and_excess_observations_are_dropped() {
+        let mut a = advertiser(2);
+        let start = Instant::now();
+        let seen = addr("/ip4/198.51.100.20/tcp/8000");
+        a.record_observed_at(PeerId::random(), &seen, start);
+        // The first report expired before the second arrived
+        let later = start + OBSERVATION_TTL + Duration::from_secs(1);
+        assert_eq!(a.record_observed_at(PeerId::random(), &seen, later), None);
+        assert!(a.confirmed_observed().is_empty());
+
+        // A flood of single-report addresses cannot grow the table unbounded
+        for i in 0..(MAX_OBSERVED_ADDRS as u32 * 2) {
+            let ip = std::net::Ipv4Addr::from(0xc633_6400 + i + 256); // 198.51.101.0+
+            a.record_observed_at(PeerId::random(), &addr(&format!("/ip4/{ip}/tcp/1")), later);
+        }
+        assert!(a.observed.len() <= MAX_OBSERVED_ADDRS);
+    }
+
+    #[test]
+    fn explicit_addresses_override_advertisement() {
+        let mut a = advertiser(1);
+        a.add_listen_addr(addr("/ip4/203.0.113.9/tcp/8000"));
+        a.record_observed(PeerId::random(), &addr("/ip4/198.51.100.20/tcp/9999"));
+        assert_eq!(
+            a.advertised(),
+            vec![addr("/ip4/203.0.113.9/tcp/8000"), addr("/ip4/198.51.100.20/tcp/8000")]
+        );
+        // Wildcard listen addresses are never advertised
+        assert!(!a.advertised().contains(&addr("/ip4/0.0.0.0/tcp/8000")));
+
+        let explicit = vec![addr("/dns4/node.example.org/tcp/8000"), addr("/ip4/192.0.2.44/tcp/8000")];
+        let mut pinned = AddressAdvertiser::new(explicit.clone(), 1);
+        pinned.add_listen_addr(addr("/ip4/203.0.113.9/tcp/8000"));
+        pinned.record_observed(PeerId::random(), &addr("/ip4/198.51.100.20/tcp/9999"));
+        assert_eq!(pinned.advertised(), explicit);
+    }
+
+    #[test]
+    fn gateway_mapping_is_advertised_until_lost() {
+        let mut a = advertiser(2);
+        let mapped = addr("/ip4/203.0.113.7/tcp/8000");
+        a.set_mapped(Some(mapped.clone()));
+        assert_eq!(a.advertised(), vec![mapped.clone()]);
+
+        // Peers confirming the same address do not list it twice
+        a.record_observed(PeerId::random(), &addr("/ip4/203.0.113.7/tcp/50000"));
+        a.record_observed(PeerId::random(), &addr("/ip4/203.0.113.7/tcp/50001"));
+        assert_eq!(a.advertised(), vec![mapped]);
+
+        let mut a = advertiser(2);
+        a.set_mapped(Some(addr("/ip4/203.0.113.7/tcp/8000")));
+        a.set_mapped(None);
+        assert!(a.advertised().is_empty());
+    }
+
+    #[test]
+    fn config_parses_multiple_addresses() {
+        let mut table: toml::Value = toml::Value::try_from(NetworkConfig::default()).unwrap();
+        let network = table.as_table_mut().unwrap();
+        network.insert(
+            "listen_addrs".into(),
+            toml::Value::try_from(vec!["/ip4/0.0.0.0/tcp/8000", "/ip6/::/tcp/8000"]).unwrap(),
+        );
+        network.insert(
+            "external_addrs".into(),
+            toml::Value::try_from(vec!["/ip4/203.0.113.7/tcp/8000"]).unwrap(),
+        );
+        network.remove("observed_addr_confirmations");
+        let config: NetworkConfig = table.try_into().unwrap();
+
+        assert_eq!(
+            config.effective_listen_addrs().unwrap(),
+            vec![addr("/ip4/0.0.0.0/tcp/8000"), addr("/ip6/::/tcp/8000")]
+        );
+        assert_eq!(config.listen_ports().unwrap(), vec![8000, 8000]);
+        assert_eq!(config.observed_addr_confirmations, 3);
+        let a = AddressAdvertiser::from_network_config(&config);
+        assert_eq!(a.explicit(), &[addr("/ip4/203.0.113.7/tcp/8000")]);
+
+        // Without listen_addrs the legacy single address applies
+        let legacy = NetworkConfig::default();
+        assert_eq!(legacy.effective_listen_addrs().unwrap(), vec![addr("/ip4/0.0.0.0/tcp/8000")]);
+
+        let mut bad = NetworkConfig::default();
+        bad.listen_addrs = vec![addr("/ip4/0.0.0.0/udp/8000")];
+        assert!(bad.validate().is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Global bandwidth budgets.
+//!
+//! Upload and download are each capped per second by a token bucket holding
+//! one second of bytes, and per rolling day by a byte count. Traffic is split
+//! into two classes: block and header traffic (plus small control messages)
+//! is never dropped and waits for tokens when the bucket is empty, while
+//! transaction relay is dropped whenever sending it would dip into the
+//! reserve kept for blocks. Once a daily budget is spent only block and
+//! header traffic continues, so the node keeps following the chain. Time is
+//! passed in so the policy can be tested without sleeping.
+
+use crate::config::BandwidthConfig;
+use crate::network::protocol::Message;
+use std::time::{Duration, Instant};
+
+/// Share of the per-second bucket kept for block and header traffic
+const RELAY_RESERVE: f64 = 0.25;
+
+/// Length of the daily budget window
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Which traffic gives way when a budget is tight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficClass {
+    /// Blocks, headers and control messages; delayed but never dropped
+    Priority,
+    /// Transaction and address relay; dropped under pressure
+    Relay,
+}
+
+impl TrafficClass {
+    pub fn of(message: &Message) -> Self {
+        match message {
+            Message::Transaction { .. }
+            | Message::BroadcastTransaction(_)
+            | Message::TransactionAnnouncement { .. }
+            | Message::GetData(_)
+            | Message::GetMempool { .. }
+            | Message::Mempool { .. }
+            | Message::GetMempoolSummary { .. }
+            | Message::MempoolSummary { .. }
+            | Message::Addr(_)
+            | Message::Environmental(_)
+            | Message::Extension(..) => TrafficClass::Relay,
+            _ => TrafficClass::Priority,
+        }
+    }
+}
+
+/// Outcome of asking the upload budget to send a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendDecision {
+    /// Send now; the bytes have been charged
+    Send,
+    /// Ask again after this long
+    Wait(Duration),
+    /// Do not send
+    Drop,
+}
+
+/// Bytes and messages exchanged with one peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerTraffic {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+}
+
+/// Throttling counters since startup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthLimitStats {
+    /// Sends that had to wait for upload tokens
+    pub delayed_sends: u64,
+    /// Relay messages not sent
+    pub dropped_sends: u64,
+    /// Relay messages received but discarded
+    pub dropped_receives: u64,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    /// Tokens; negative after a message larger than the bucket
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u64, now: Instant) -> Self {
+        Self {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+    }
+}
+
+#[derive(Debug)]
+struct DailyBudget {
+    limit: u64,
+    used: u64,
+    window_start: Instant,
+}
+
+impl DailyBudget {
+    fn exhausted(&mut self, bytes: u64, now: Instant) -> bool {
+        if now.saturating_duration_since(self.window_start) >= DAY {
+            self.used = 0;
+            self.window_start = now;
+        }
+        self.used.saturating_add(bytes) > self.limit
+    }
+}
+
+/// Budget for one direction
+#[derive(Debug, Default)]
+struct DirectionBudget {
+    per_sec: Option<TokenBucket>,
+    per_day: Option<DailyBudget>,
+}
+
+impl DirectionBudget {
+    fn new(per_sec: Option<u64>, per_day: Option<u64>, now: Instant) -> Self {
+        Self {
+            per_sec: per_sec.map(|rate| TokenBucket::new(rate, now)),
+            per_day: per_day.map(|limit| DailyBudget {
+                limit,
+                used: 0,
+                window_start: now,
+            }),
+        }
+    }
+
+    fn day_exhausted(&mut self, bytes: u64, now: Instant) -> bool {
+        self.per_day
+            .as_mut()
+            .is_some_and(|day| day.exhausted(bytes, now))
+    }
+
+    /// Whether relay traffic of `bytes` fits without touching the reserve
+    fn relay_fits(&mut self, bytes: u64, now: Instant) -> bool {
+        match self.per_sec.as_mut() {
+            Some(bucket) => {
+                bucket.refill(now);
+                bucket.tokens - bytes as f64 >= bucket.rate * RELAY_RESERVE
+            }
+            None => true,
+        }
+    }
+
+    fn charge(&mut self, bytes: u64, now: Instant) {
+        if let Some(bucket) = self.per_sec.as_mut() {
+            bucket.refill(now);
+            bucket.tokens -= bytes as f64;
+        }
+        if let Some(day) = self.per_day.as_mut() {
+            day.used = day.used.saturating_add(bytes);
+        }
+    }
+}
+
+/// Upload and download budgets shared by all peers
+#[derive(Debug, Default)]
+pub struct BandwidthLimiter {
+    upload: DirectionBudget,
+    download: DirectionBudget,
+    stats: BandwidthLimitStats,
+}
+
+impl BandwidthLimiter {
+    pub fn new(config: &BandwidthConfig, now: Instant) -> Self {
+        Self {
+            upload: DirectionBudget::new(config.max_upload_per_sec, config.max_upload_per_day, now),
+            download: DirectionBudget::new(
+                config.max_download_per_sec,
+                config.max_download_per_day,
+                now,
+            ),
+            stats: BandwidthLimitStats::default(),
+        }
+    }
+
+    /// Decide whether `bytes` of `class` traffic may be sent now. Priority
+    /// traffic larger than the bucket goes out once the bucket is full and
+    /// leaves it in debt, so the long-run rate still holds.
+    pub fn admit_send(&mut self, class: TrafficClass, bytes: u64, now: Instant) -> SendDecision {
+        if class == TrafficClass::Relay {
+            if self.upload.day_exhausted(bytes, now) || !self.upload.relay_fits(bytes, now) {
+                self.stats.dropped_sends += 1;
+                return SendDecision::Drop;
+            }
+            self.upload.charge(bytes, now);
+            return SendDecision::Send;
+        }
+
+        if let Some(bucket) = self.upload.per_sec.as_mut() {
+            bucket.refill(now);
+            let needed = (bytes as f64).min(bucket.rate);
+            if bucket.tokens < needed {
+                self.stats.delayed_sends += 1;
+                let wait = (needed - bucket.tokens) / bucket.rate;
+                return SendDecision::Wait(Duration::from_secs_f64(wait));
+            }
+        }
+        self.upload.charge(bytes, now);
+        SendDecision::Send
+    }
+
+    /// Charge `bytes` that arrived from a peer. Returns false if the message
+    /// is relay traffic over the download budget and should be discarded.
+    pub fn admit_received(&mut self, class: TrafficClass, bytes: u64, now: Instant) -> bool {
+        let keep = class == TrafficClass::Priority
+            || (!self.download.day_exhausted(bytes, now) && self.download.relay_fits(bytes, now));
+        self.download.charge(bytes, now);
+        if !keep {
+            self.stats.dropped_receives += 1;
+        }
+        keep
+    }
+
+    pub fn stats(&self) -> BandwidthLimitStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn upload_cap(per_sec: Option<u64>, per_day: Option<u64>) -> BandwidthConfig {
+        BandwidthConfig {
+            max_upload_per_sec: per_sec,
+            max_upload_per_day: per_day,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn one_megabyte_cap_bounds_send_rate_across_peers() {
+        let start = Instant::now();
+        let mut limiter = BandwidthLimiter::new(&upload_cap(Some(MB), None), start);
+
+        // Eight peers each try to push a 256 KB block and a 400-byte
+        // transaction every 10 ms for ten seconds, far above the cap.
+        const BLOCK: u64 = 256 * 1024;
+        const TX: u64 = 400;
+        let mut sent = 0u64;
+        let mut per_second = [0u64; 10];
+        let mut next_try = [start; 8];
+        for tick in 0..1000u64 {
+            let now = start + Duration::from_millis(tick * 10);
+            for next in next_try.iter_mut() {
+                if now >= *next {
+                    match limiter.admit_send(TrafficClass::Priority, BLOCK, now) {
+                        SendDecision::Send => {
+                            sent += BLOCK;
+                            per_second[(tick / 100) as usize] += BLOCK;
+                        }
+                        SendDecision::Wait(wait) => *next = now + wait,
+                        SendDecision::Drop => panic!("block traffic is never dropped"),
+                    }
+                }
+                if limiter.admit_send(TrafficClass::Relay, TX, now) == SendDecision::Send {
+                    sent += TX;
+                    per_second[(tick / 100) as usize] += TX;
+                }
+            }
+        }
+
+        // Ten seconds at 1 MB/s plus the initial one-second burst
+        assert!(sent <= 11 * MB, "sent {} bytes", sent);
+        assert!(sent >= 9 * MB, "cap underused: {} bytes", sent);
+        for (second, bytes) in per_second.iter().enumerate().skip(1) {
+            assert!(
+                *bytes <= MB + BLOCK,
+                "second {} sent {} bytes",
+                second,
+                bytes
+            );
+        }
+        assert!(limiter.stats().delayed_sends > 0);
+    }
+
+    #[test]
+    fn relay_gives_way_to_blocks_when_tight() {
+        let start = Instant::now();
+        let mut limiter = BandwidthLimiter::new(&upload_cap(Some(MB), None), start);
+
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Priority, 800 * 1024, start),
+            SendDecision::Send
+        );
+        // Less than the reserve is left: relay is dropped, blocks still go
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Relay, 1_000, start),
+            SendDecision::Drop
+        );
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Priority, 100 * 1024, start),
+            SendDecision::Send
+        );
+        assert!(matches!(
+            limiter.admit_send(TrafficClass::Priority, 200 * 1024, start),
+            SendDecision::Wait(_)
+        ));
+        let later = start + Duration::from_secs(1);
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Relay, 1_000, later),
+            SendDecision::Send
+        );
+        assert_eq!(limiter.stats().dropped_sends, 1);
+    }
+
+    #[test]
+    fn daily_budget_stops_relay_until_the_window_rolls() {
+        let start = Instant::now();
+        let mut limiter = BandwidthLimiter::new(&upload_cap(None, Some(10_000)), start);
+
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Relay, 9_000, start),
+            SendDecision::Send
+        );
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Relay, 2_000, start),
+            SendDecision::Drop
+        );
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Priority, 2_000, start),
+            SendDecision::Send
+        );
+        assert_eq!(
+            limiter.admit_send(TrafficClass::Relay, 2_000, start + DAY),
+            SendDecision::Send
+        );
+    }
+
+    #[test]
+    fn download_budget_discards_relay_only() {
+        let start = Instant::now();
+        let config = BandwidthConfig {
+            max_download_per_sec: Some(MB),
+            ..Default::default()
+        };
+        let mut limiter = BandwidthLimiter::new(&config, start);
+
+        assert!(limiter.admit_received(TrafficClass::Priority, 2 * MB, start));
+        assert!(!limiter.admit_received(TrafficClass::Relay, 500, start));
+        assert!(limiter.admit_received(TrafficClass::Priority, 500, start));
+        assert_eq!(limiter.stats().dropped_receives, 1);
+
+        let unlimited = BandwidthLimiter::default().admit_send(TrafficClass::Relay, MB, start);
+        assert_eq!(unlimited, SendDecision::Send);
+    }
+}
@@ -0,0 +1,683 @@
+//! Throttled serving of blocks and headers to syncing peers.
+//!
+//! A fresh node that several other fresh nodes pick as a sync source can
+//! spend all of its bandwidth and disk time shipping old blocks, starving its
+//! own sync. Requests are classified by how deep they reach: headers and
+//! blocks within `recent_depth` of the tip are always served, while older
+//! (historical) blocks draw from a byte budget, a concurrency limit and
+//! per-peer queues drained round-robin. While we are still in initial block
+//! download historical requests are refused outright. Every refusal is a
+//! `Message::Busy` carrying a retry hint, so the requester moves on to
+//! another peer instead of waiting on a timeout. Pruned nodes answer
+//! requests for blocks they no longer store with `Message::NotFound`.
+
+use crate::config::BlockServingConfig;
+use crate::network::p2p::NetworkCommand;
+use crate::network::protocol::Message;
+use crate::network::sync_progress::SyncProgressTracker;
+use crate::storage::{BlockchainDB, ChainState};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+/// Most headers returned for a single `GetHeaders`
+pub const MAX_HEADERS_PER_RESPONSE: u64 = 2000;
+
+/// Most blocks returned for a single block request
+pub const MAX_BLOCKS_PER_RESPONSE: u64 = 16;
+
+/// How often queued historical requests are drained
+const DRAIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How deep a request reaches into the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServeClass {
+    /// Header ranges, cheap enough to always serve
+    Headers,
+    /// Blocks within `recent_depth` of the tip
+    Recent,
+    /// Blocks older than `recent_depth`; budgeted
+    Historical,
+}
+
+impl ServeClass {
+    /// Classify a block request by the lowest height it touches.
+    pub fn for_blocks(lowest_height: u64, tip_height: u64, recent_depth: u64) -> Self {
+        if lowest_height.saturating_add(recent_depth) < tip_height {
+            ServeClass::Historical
+        } else {
+            ServeClass::Recent
+        }
+    }
+}
+
+/// Why a request was declined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyReason {
+    /// We are syncing ourselves and only serve recent blocks and headers
+    InitialBlockDownload,
+    /// The historical byte budget is spent
+    BudgetExhausted,
+    /// This peer already has the maximum number of requests queued
+    QueueFull,
+}
+
+impl fmt::Display for BusyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BusyReason::InitialBlockDownload => write!(f, "initial block download"),
+            BusyReason::BudgetExhausted => write!(f, "historical serving budget exhausted"),
+            BusyReason::QueueFull => write!(f, "too many queued requests"),
+        }
+    }
+}
+
+/// Outcome of submitting a request to the throttle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+    /// Serve now; not subject to the historical budget
+    Serve,
+    /// Queued; handed out by `BlockServeThrottle::next`
+    Queued,
+    /// Declined; the peer should retry elsewhere or after the hint
+    Busy {
+        reason: BusyReason,
+        retry_after_secs: u64,
+    },
+}
+
+/// Counters for block serving, reported with the node metrics.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BlockServingStats {
+    /// Bytes of historical blocks served
+    pub historical_bytes_served: u64,
+    /// Bytes of recent blocks served
+    pub recent_bytes_served: u64,
+    /// Headers served
+    pub headers_served: u64,
+    /// Requests answered with a busy response
+    pub throttle_events: u64,
+    /// Of those, requests refused because we were in initial block download
+    pub ibd_refusals: u64,
+    /// Historical requests waiting to be served
+    pub queued_requests: usize,
+}
+
+/// Byte budget, concurrency limit and per-peer fair queues for historical
+/// block requests. Time is passed in so the policy can be tested without
+/// sleeping.
+#[derive(Debug)]
+pub struct BlockServeThrottle {
+    config: BlockServingConfig,
+    /// Remaining budget in bytes; negative after a large response
+    tokens: f64,
+    last_refill: Instant,
+    in_flight: usize,
+    queues: HashMap<PeerId, VecDeque<Message>>,
+    /// Peers with queued requests, in serving order
+    rotation: VecDeque<PeerId>,
+    stats: BlockServingStats,
+}
+
+impl BlockServeThrottle {
+    pub fn new(config: BlockServingConfig, now: Instant) -> Self {
+        Self {
+            tokens: config.historical_bytes_per_sec as f64,
+            config,
+            last_refill: now,
+            in_flight: 0,
+            queues: HashMap::new(),
+            rotation: VecDeque::new(),
+            stats: BlockServingStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> &BlockServingConfig {
+        &self.config
+    }
+
+    /// Decide what to do with a request from `peer`. Historical requests
+    /// that are admitted are queued with `request` as the payload.
+    pub fn admit(
+        &mut self,
+        peer: PeerId,
+        class: ServeClass,
+        in_ibd: bool,
+        request: Message,
+        now: Instant,
+    ) -> Admission {
+        if class != ServeClass::Historical {
+            return Admission::Serve;
+        }
+        if in_ibd {
+            self.stats.ibd_refusals += 1;
+            return self.busy(BusyReason::InitialBlockDownload, 0);
+        }
+
+        self.refill(now);
+        if self.tokens <= 0.0 {
+            let wait = (-self.tokens / self.config.historical_bytes_per_sec as f64).ceil() as u64;
+            return self.busy(BusyReason::BudgetExhausted, wait);
+        }
+
+        let queue = self.queues.entry(peer).or_default();
+        if queue.len() >= self.config.max_queued_per_peer {
+            return self.busy(BusyReason::QueueFull, 0);
+        }
+        queue.push_back(request);
+        if queue.len() == 1 {
+            self.rotation.push_back(peer);
+        }
+        Admission::Queued
+    }
+
+    /// Hand out the next queued request, taking one request per peer in
+    /// turn, while a concurrency slot and budget are available. The caller
+    /// must report the response size with `complete`.
+    pub fn next(&mut self, now: Instant) -> Option<(PeerId, Message)> {
+        self.refill(now);
+        if self.in_flight >= self.config.max_concurrent_requests || self.tokens <= 0.0 {
+            return None;
+        }
+        while let Some(peer) = self.rotation.pop_front() {
+            let Some(queue) = self.queues.get_mut(&peer) else {
+                continue;
+            };
+            let Some(request) = queue.pop_front() else {
+                self.queues.remove(&peer);
+                continue;
+            };
+            if queue.is_empty() {
+                self.queues.remove(&peer);
+            } else {
+                self.rotation.push_back(peer);
+            }
+            self.in_flight += 1;
+            return Some((peer, request));
+        }
+        None
+    }
+
+    /// Release the slot taken by `next` and charge `bytes` to the budget.
+    pub fn complete(&mut self, bytes: u64, now: Instant) {
+        self.refill(now);
+        self.in_flight = self.in_flight.saturating_sub(1);
+        self.tokens -= bytes as f64;
+        self.stats.historical_bytes_served += bytes;
+    }
+
+    /// Record a response that bypassed the budget.
+    pub fn record_unbudgeted(&mut self, class: ServeClass, count: u64, bytes: u64) {
+        match class {
+            ServeClass::Headers => self.stats.headers_served += count,
+            ServeClass::Recent | ServeClass::Historical => self.stats.recent_bytes_served += bytes,
+        }
+    }
+
+    /// Drop everything queued for a disconnected peer.
+    pub fn forget_peer(&mut self, peer: &PeerId) {
+        self.queues.remove(peer);
+        self.rotation.retain(|p| p != peer);
+    }
+
+    pub fn stats(&self) -> BlockServingStats {
+        BlockServingStats {
+            queued_requests: self.queues.values().map(VecDeque::len).sum(),
+            ..self.stats.clone()
+        }
+    }
+
+    fn busy(&mut self, reason: BusyReason, wait_secs: u64) -> Admission {
+        self.stats.throttle_events += 1;
+        Admission::Busy {
+            reason,
+            retry_after_secs: wait_secs.max(self.config.busy_retry_after.as_secs()),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        let rate = self.config.historical_bytes_per_sec as f64;
+        self.tokens = (self.tokens + elapsed * rate).min(rate);
+        self.last_refill = now;
+    }
+}
+
+/// Answers `GetHeaders`, `GetBlocksByHeight` and `GetBlocksByHash` from
+/// storage, routing historical requests through a [`BlockServeThrottle`].
+pub struct BlockServer {
+    throttle: parking_lot::Mutex<BlockServeThrottle>,
+    db: Arc<BlockchainDB>,
+    chain_state: Arc<RwLock<ChainState>>,
+    sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+    command_tx: mpsc::Sender<NetworkCommand>,
+}
+
+impl BlockServer {
+    pub fn new(
+        config: BlockServingConfig,
+        db: Arc<BlockchainDB>,
+        chain_state: Arc<RwLock<ChainState>>,
+        sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+        command_tx: mpsc::Sender<NetworkCommand>,
+    ) -> Self {
+        Self {
+            throttle: parking_lot::Mutex::new(BlockServeThrottle::new(config, Instant::now())),
+            db,
+            chain_state,
+            sync_progress,
+            command_tx,
+        }
+    }
+
+    pub fn stats(&self) -> BlockServingStats {
+        self.throttle.lock().stats()
+    }
+
+    pub fn forget_peer(&self, peer: &PeerId) {
+        self.throttle.lock().forget_peer(peer);
+    }
+
+    /// Handle an inbound request. Returns false for messages that are not
+    /// block or header requests.
+    pub async fn handle_request(&self, peer_id: PeerId, message: Message) -> bool {
+        let pruned = pruned_blocks(&self.db, &message);
+        if !pruned.is_empty() {
+            debug!("Block request from {} reaches pruned history", peer_id);
+            self.send(
+                peer_id,
+                Message::NotFound {
+                    block_hashes: pruned,
+                },
+            )
+            .await;
+            return true;
+        }
+
+        let class = match &message {
+            Message::GetHeaders { .. } => ServeClass::Headers,
+            Message::GetBlocksByHeight { start_height, .. } => self.classify(Some(*start_height)),
+            Message::GetBlocksByHash { block_hashes } => {
+                let lowest = block_hashes
+                    .iter()
+                    .filter_map(|hash| self.db.get_block_height(hash).ok().flatten())
+                    .min();
+                self.classify(lowest)
+            }
+            _ => return false,
+        };
+
+        let in_ibd = self.in_ibd();
+        let admission =
+            self.throttle
+                .lock()
+                .admit(peer_id, class, in_ibd, message.clone(), Instant::now());
+        match admission {
+            Admission::Serve => {
+                if let Some((response, count, bytes)) = self.respond(&message) {
+                    self.throttle.lock().record_unbudgeted(class, count, bytes);
+                    self.send(peer_id, response).await;
+                }
+            }
+            Admission::Queued => {}
+            Admission::Busy {
+                reason,
+                retry_after_secs,
+            } => {
+                debug!("Declining block request from {}: {}", peer_id, reason);
+                self.send(
+                    peer_id,
+                    Message::Busy {
+                        retry_after_secs,
+                        reason: reason.to_string(),
+                    },
+                )
+                .await;
+            }
+        }
+        true
+    }
+
+    /// Drain queued historical requests as budget allows.
+    pub async fn run(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(DRAIN_INTERVAL);
+        loop {
+            ticker.tick().await;
+            loop {
+                let next = self.throttle.lock().next(Instant::now());
+                let Some((peer_id, request)) = next else {
+                    break;
+                };
+                let response = self.respond(&request);
+                let bytes = response.as_ref().map(|(_, _, bytes)| *bytes).unwrap_or(0);
+                self.throttle.lock().complete(bytes, Instant::now());
+                if let Some((response, _, _)) = response {
+                    self.send(peer_id, response).await;
+                }
+            }
+        }
+    }
+
+    fn classify(&self, lowest_height: Option<u64>) -> ServeClass {
+        let tip = match self.chain_state.read() {
+            Ok(chain) => chain.get_height(),
+            Err(_) => return ServeClass::Historical,
+        };
+        let recent_depth = self.throttle.lock().config().recent_depth;
+        match lowest_height {
+            Some(height) => ServeClass::for_blocks(height, tip, recent_depth),
+            // Nothing we know of; answering costs nothing
+            None => ServeClass::Recent,
+        }
+    }
+
+    fn in_ibd(&self) -> bool {
+        self.sync_progress
+            .lock()
+            .latest()
+            .map(|progress| !progress.synced)
+            .unwrap_or(false)
+    }
+
+    /// Build the response to a request from storage, with the number of
+    /// items and encoded bytes it carries. Returns None when there is
+    /// nothing to send.
+    fn respond(&self, request: &Message) -> Option<(Message, u64, u64)> {
+        match request {
+            Message::GetHeaders {
+                start_height,
+                end_height,
+            } => {
+                let end =
+                    (*end_height).min(start_height.saturating_add(MAX_HEADERS_PER_RESPONSE - 1));
+                let headers: Vec<Vec<u8>> = (*start_height..=end)
+                    .map_while(|height| self.db.get_header_by_height(height).ok().flatten())
+                    .filter_map(|header| bincode::serialize(&header).ok())
+                    .collect();
+                if headers.is_empty() {
+                    return None;
+                }
+                let total_difficulty = self
+                    .chain_state
+                    .read()
+                    .map(|chain| chain.get_total_difficulty())
+                    .unwrap_or(0);
+                let count = headers.len() as u64;
+                let bytes = headers.iter().map(|h| h.len() as u64).sum();
+                Some((
+                    Message::Headers {
+                        headers,
+                        total_difficulty,
+                    },
+                    count,
+                    bytes,
+                ))
+            }
+            Message::GetBlocksByHeight {
+                start_height,
+                end_height,
+            } => {
+                let end =
+                    (*end_height).min(start_height.saturating_add(MAX_BLOCKS_PER_RESPONSE - 1));
+                let blocks = (*start_height..=end)
+                    .map_while(|height| self.db.get_block_by_height(height).ok().flatten())
+                    .filter_map(|block| bincode::serialize(&block).ok())
+                    .collect();
+                Self::blocks_response(blocks)
+            }
+            Message::GetBlocksByHash { block_hashes } => {
+                let blocks = block_hashes
+                    .iter()
+                    .take(MAX_BLOCKS_PER_RESPONSE as usize)
+                    .filter_map(|hash| self.db.get_block(hash).ok().flatten())
+                    .filter_map(|block| bincode::serialize(&block).ok())
+                    .collect();
+                Self::blocks_response(blocks)
+            }
+            _ => None,
+        }
+    }
+
+    fn blocks_response(blocks: Vec<Vec<u8>>) -> Option<(Message, u64, u64)> {
+        if blocks.is_empty() {
+            return None;
+        }
+        let count = blocks.len() as u64;
+        let bytes = blocks.iter().map(|b| b.len() as u64).sum();
+        Some((Message::Blocks { blocks }, count, bytes))
+    }
+
+    async fn send(&self, peer_id: PeerId, message: Message) {
+        if let Err(e) = self
+            .command_tx
+            .send(NetworkCommand::SendToPeer { peer_id, message })
+            .await
+        {
+            warn!(
+                "Failed to queue block serving response for {}: {}",
+                peer_id, e
+            );
+        }
+    }
+}
+
+/// Hashes of the requested blocks that have been pruned. A block request
+/// reaching into pruned history is answered with these alone.
+fn pruned_blocks(db: &BlockchainDB, request: &Message) -> Vec<[u8; 32]> {
+    let pruned_height = match db.pruned_height() {
+        Ok(height) => height,
+        Err(_) => return Vec::new(),
+    };
+    let is_pruned = |height: u64| height > 0 && height < pruned_height;
+    match request {
+        Message::GetBlocksByHeight {
+            start_height,
+            end_height,
+        } => {
+            let end = (*end_height).min(start_height.saturating_add(MAX_BLOCKS_PER_RESPONSE - 1));
+            (*start_height..=end)
+                .filter(|height| is_pruned(*height))
+                .filter_map(|height| db.get_block_hash_by_height(height).ok().flatten())
+                .collect()
+        }
+        Message::GetBlocksByHash { block_hashes } => block_hashes
+            .iter()
+            .take(MAX_BLOCKS_PER_RESPONSE as usize)
+            .filter(|hash| !db.has_block(hash).unwrap_or(true))
+            .filter(|hash| {
+                db.get_block_header(hash)
+                    .ok()
+                    .flatten()
+                    .is_some_and(|header| is_pruned(header.height()))
+            })
+            .copied()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> BlockServingConfig {
+        BlockServingConfig {
+            historical_bytes_per_sec: 1000,
+            max_concurrent_requests: 1,
+            max_queued_per_peer: 4,
+            recent_depth: 10,
+            busy_retry_after: Duration::from_secs(1),
+        }
+    }
+
+    fn request(start_height: u64) -> Message {
+        Message::GetBlocksByHeight {
+            start_height,
+            end_height: start_height,
+        }
+    }
+
+    #[test]
+    fn ibd_refuses_deep_history_but_serves_headers() {
+        let now = Instant::now();
+        let mut throttle = BlockServeThrottle::new(config(), now);
+        let peer = PeerId::random();
+        let tip = 1000;
+
+        let deep = ServeClass::for_blocks(5, tip, 10);
+        assert_eq!(deep, ServeClass::Historical);
+        assert!(matches!(
+            throttle.admit(peer, deep, true, request(5), now),
+            Admission::Busy {
+                reason: BusyReason::InitialBlockDownload,
+                ..
+            }
+        ));
+
+        let headers = Message::GetHeaders {
+            start_height: 0,
+            end_height: 100,
+        };
+        assert_eq!(
+            throttle.admit(peer, ServeClass::Headers, true, headers, now),
+            Admission::Serve
+        );
+        let recent = ServeClass::for_blocks(995, tip, 10);
+        assert_eq!(
+            throttle.admit(peer, recent, true, request(995), now),
+            Admission::Serve
+        );
+
+        let stats = throttle.stats();
+        assert_eq!(stats.ibd_refusals, 1);
+        assert_eq!(stats.throttle_events, 1);
+        assert_eq!(stats.queued_requests, 0);
+    }
+
+    #[test]
+    fn exhausted_budget_answers_busy_until_refilled() {
+        let now = Instant::now();
+        let mut throttle = BlockServeThrottle::new(config(), now);
+        let peer = PeerId::random();
+
+        assert_eq!(
+            throttle.admit(peer, ServeClass::Historical, false, request(1), now),
+            Admission::Queued
+        );
+        assert!(throttle.next(now).is_some());
+        throttle.complete(5000, now);
+
+        match throttle.admit(peer, ServeClass::Historical, false, request(2), now) {
+            Admission::Busy {
+                reason,
+                retry_after_secs,
+            } => {
+                assert_eq!(reason, BusyReason::BudgetExhausted);
+                assert_eq!(retry_after_secs, 4);
+            }
+            other => panic!("expected busy, got {:?}", other),
+        }
+        assert_eq!(throttle.stats().historical_bytes_served, 5000);
+        assert_eq!(throttle.stats().throttle_events, 1);
+
+        let later = now + Duration::from_secs(5);
+        assert_eq!(
+            throttle.admit(peer, ServeClass::Historical, false, request(2), later),
+            Admission::Queued
+        );
+    }
+
+    #[test]
+    fn peers_are_served_in_turn() {
+        let now = Instant::now();
+        let mut throttle = BlockServeThrottle::new(
+            BlockServingConfig {
+                historical_bytes_per_sec: 1_000_000,
+                ..config()
+            },
+            now,
+        );
+        let greedy = PeerId::random();
+        let modest = PeerId::random();
+
+        for height in 0..4 {
+            assert_eq!(
+                throttle.admit(greedy, ServeClass::Historical, false, request(height), now),
+                Admission::Queued
+            );
+        }
+        // The greedy peer cannot queue beyond its share
+        assert!(matches!(
+            throttle.admit(greedy, ServeClass::Historical, false, request(4), now),
+            Admission::Busy {
+                reason: BusyReason::QueueFull,
+                ..
+            }
+        ));
+        assert_eq!(
+            throttle.admit(modest, ServeClass::Historical, false, request(0), now),
+            Admission::Queued
+        );
+
+        let mut order = Vec::new();
+        while let Some((peer, _)) = throttle.next(now) {
+            // One request in flight at a time
+            assert!(throttle.next(now).is_none());
+            throttle.complete(100, now);
+            order.push(peer);
+        }
+        assert_eq!(order, vec![greedy, modest, greedy, greedy, greedy]);
+    }
+
+    #[test]
+    fn pruned_history_is_reported_not_served() {
+        use supernova_core::types::block::Block;
+        use supernova_core::types::transaction::Transaction;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        let mut hashes = Vec::new();
+        let mut prev = [0u8; 32];
+        for height in 0..6 {
+            let mut block =
+                Block::new_with_params(1, prev, vec![Transaction::new_coinbase()], 0x207f_ffff);
+            block.set_height(height);
+            prev = block.hash();
+            db.store_block(&prev, &bincode::serialize(&block).unwrap())
+                .unwrap();
+            db.store_block_height_index(height, &prev).unwrap();
+            hashes.push(prev);
+        }
+        assert!(pruned_blocks(&db, &request(1)).is_empty());
+
+        assert_eq!(db.prune_blocks(4).unwrap(), 3);
+        assert_eq!(
+            pruned_blocks(
+                &db,
+                &Message::GetBlocksByHeight {
+                    start_height: 0,
+                    end_height: 5
+                }
+            ),
+            hashes[1..4].to_vec()
+        );
+        let by_hash = Message::GetBlocksByHash {
+            block_hashes: vec![hashes[0], hashes[2], hashes[5]],
+        };
+        assert_eq!(pruned_blocks(&db, &by_hash), vec![hashes[2]]);
+        // Blocks above the prune height are served as usual
+        assert!(pruned_blocks(&db, &request(4)).is_empty());
+        assert_eq!(
+            db.get_header_by_height(2).unwrap().map(|h| h.hash()),
+            Some(hashes[2])
+        );
+    }
+}
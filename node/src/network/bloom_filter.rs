@@ -3,8 +3,27 @@
 //! This module implements optimized bloom filters for efficient transaction
 //! and block filtering, enabling lightweight SPV clients and reduced bandwidth.
 
+use crate::network::protocol::Message;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use supernova_core::consensus::witness;
+use supernova_core::types::block::{Block, BlockHeader};
+use supernova_core::types::transaction::Transaction;
+use supernova_core::util::merkle::{MerkleMultiProof, MerkleTree};
+
+/// Largest filter a peer may load, in bytes (BIP37)
+pub const MAX_FILTER_BYTES: usize = 36_000;
+
+/// Most hash functions a peer's filter may use (BIP37)
+pub const MAX_HASH_FUNCS: u32 = 50;
+
+/// Largest element a peer may add to its filter, in bytes (BIP37)
+pub const MAX_FILTER_ELEMENT_BYTES: usize = 520;
+
+/// Highest false-positive rate a peer's filter may reach. A filter that
+/// matches nearly everything costs us as much as full relay while looking
+/// like a light client.
+pub const MAX_FALSE_POSITIVE_RATE: f64 = 0.05;
 
 /// Simple hash function for bloom filters (using FNV-1a as fallback)
 /// In production, this should use Murmur3, but we'll use a simple hash for now
@@ -27,6 +46,14 @@ pub enum BloomFilterError {
     InvalidSize,
     InvalidHashCount,
     SerializationError(String),
+    /// Filter larger than `MAX_FILTER_BYTES`
+    TooLarge(usize),
+    /// More hash functions than `MAX_HASH_FUNCS`
+    TooManyHashFunctions(u32),
+    /// Element larger than `MAX_FILTER_ELEMENT_BYTES`
+    ElementTooLarge(usize),
+    /// Filter would match more than `MAX_FALSE_POSITIVE_RATE` of elements
+    FalsePositiveRateTooHigh,
 }
 
 impl std::fmt::Display for BloomFilterError {
@@ -42,6 +69,22 @@ impl std::fmt::Display for BloomFilterError {
             BloomFilterError::SerializationError(msg) => {
                 write!(f, "Serialization error: {}", msg)
             }
+            BloomFilterError::TooLarge(bytes) => {
+                write!(f, "Filter of {} bytes exceeds {}", bytes, MAX_FILTER_BYTES)
+            }
+            BloomFilterError::TooManyHashFunctions(count) => {
+                write!(f, "{} hash functions exceeds {}", count, MAX_HASH_FUNCS)
+            }
+            BloomFilterError::ElementTooLarge(bytes) => {
+                write!(
+                    f,
+                    "Element of {} bytes exceeds {}",
+                    bytes, MAX_FILTER_ELEMENT_BYTES
+                )
+            }
+            BloomFilterError::FalsePositiveRateTooHigh => {
+                write!(f, "False positive rate exceeds {}", MAX_FALSE_POSITIVE_RATE)
+            }
         }
     }
 }
@@ -268,6 +311,183 @@ impl SupernovaBloomFilter {
         self.bits.fill(0);
         self.element_count = 0;
     }
+
+    /// Build a peer's filter from a `FilterLoad` message, enforcing the
+    /// relay limits
+    pub fn from_filter_load(
+        filter: &[u8],
+        bit_count: usize,
+        hash_count: u32,
+        tweak: u32,
+    ) -> Result<Self, BloomFilterError> {
+        let byte_count = bit_count.div_ceil(8);
+        if byte_count > MAX_FILTER_BYTES {
+            return Err(BloomFilterError::TooLarge(byte_count));
+        }
+        if hash_count > MAX_HASH_FUNCS {
+            return Err(BloomFilterError::TooManyHashFunctions(hash_count));
+        }
+        if filter.len() > byte_count {
+            return Err(BloomFilterError::InvalidSize);
+        }
+        let mut loaded = Self::with_size(bit_count, hash_count, tweak)?;
+        loaded.bits = Self::decompress(filter, bit_count);
+        loaded.check_false_positive_rate()?;
+        Ok(loaded)
+    }
+
+    /// Add an element from a `FilterAdd` message, enforcing the relay limits
+    pub fn add_from_peer(&mut self, element: &[u8]) -> Result<(), BloomFilterError> {
+        if element.len() > MAX_FILTER_ELEMENT_BYTES {
+            return Err(BloomFilterError::ElementTooLarge(element.len()));
+        }
+        self.add(element);
+        self.check_false_positive_rate()
+    }
+
+    /// False-positive rate implied by the bits actually set, which unlike
+    /// `stats` cannot be understated by a peer
+    pub fn fill_false_positive_rate(&self) -> f64 {
+        let set: u32 = self.bits.iter().map(|byte| byte.count_ones()).sum();
+        (set as f64 / self.bit_count as f64).powi(self.hash_count as i32)
+    }
+
+    fn check_false_positive_rate(&self) -> Result<(), BloomFilterError> {
+        if self.fill_false_positive_rate() > MAX_FALSE_POSITIVE_RATE {
+            return Err(BloomFilterError::FalsePositiveRateTooHigh);
+        }
+        Ok(())
+    }
+
+    /// Whether `tx` is relevant to the filter: its txid, an output script,
+    /// a spent outpoint or an input script matches. Outpoints of matching
+    /// outputs are added so that transactions spending them match too.
+    pub fn matches_transaction(&mut self, tx: &Transaction) -> bool {
+        let txid = tx.hash();
+        let mut matched = self.contains(txid);
+        for (index, output) in tx.outputs().iter().enumerate() {
+            let script = output.script_pubkey();
+            if !script.is_empty() && self.contains(script) {
+                matched = true;
+                self.add(outpoint(&txid, index as u32));
+            }
+        }
+        if matched {
+            return true;
+        }
+        tx.inputs().iter().any(|input| {
+            self.contains(outpoint(&input.prev_tx_hash(), input.prev_output_index()))
+                || (!input.signature_script().is_empty() && self.contains(input.signature_script()))
+        })
+    }
+}
+
+/// Outpoint as added to filters: txid followed by the little-endian index
+pub fn outpoint(txid: &[u8; 32], index: u32) -> [u8; 36] {
+    let mut bytes = [0u8; 36];
+    bytes[..32].copy_from_slice(txid);
+    bytes[32..].copy_from_slice(&index.to_le_bytes());
+    bytes
+}
+
+/// A block as sent to a filtering peer: the header, the transactions the
+/// peer's filter matched, and a proof tying them to the merkle root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MerkleBlock {
+    pub header: BlockHeader,
+    /// Transactions in the full block
+    pub total_transactions: u32,
+    /// Positions of `transactions` in the block
+    pub indices: Vec<u32>,
+    pub transactions: Vec<Transaction>,
+    /// Absent when nothing matched
+    pub proof: Option<MerkleMultiProof>,
+}
+
+impl MerkleBlock {
+    /// Select the transactions of `block` that match `filter`
+    pub fn build(block: &Block, filter: &mut SupernovaBloomFilter) -> Self {
+        let mut indices = Vec::new();
+        let mut transactions = Vec::new();
+        for (index, tx) in block.transactions().iter().enumerate() {
+            if filter.matches_transaction(tx) {
+                indices.push(index as u32);
+                transactions.push(tx.clone());
+            }
+        }
+        let proof = if indices.is_empty() {
+            None
+        } else {
+            let hashes = witness::merkle_leaves(block.transactions(), block.height());
+            let positions: Vec<usize> = indices.iter().map(|&i| i as usize).collect();
+            MerkleTree::new(&hashes).prove_subset(&positions).ok()
+        };
+        Self {
+            header: block.header().clone(),
+            total_transactions: block.transactions().len() as u32,
+            indices,
+            transactions,
+            proof,
+        }
+    }
+
+    /// Check the matched transactions against the header's merkle root
+    pub fn verify(&self) -> bool {
+        if self.indices.len() != self.transactions.len() {
+            return false;
+        }
+        let Some(proof) = &self.proof else {
+            return self.transactions.is_empty();
+        };
+        if proof.leaf_count != self.total_transactions as usize {
+            return false;
+        }
+        let leaves: Vec<(usize, [u8; 32])> = self
+            .indices
+            .iter()
+            .zip(&self.transactions)
+            .map(|(&index, tx)| {
+                let leaf = witness::merkle_leaf(tx, self.header.height());
+                (index as usize, MerkleTree::hash_leaf(leaf))
+            })
+            .collect();
+        proof
+            .verify(self.header.merkle_root(), &leaves)
+            .unwrap_or(false)
+    }
+}
+
+/// Rewrite a message bound for a peer that loaded `filter`: blocks become
+/// `MerkleBlock`s and transactions the filter does not match are dropped.
+/// Other messages pass through unchanged.
+pub fn filter_message(message: Message, filter: &mut SupernovaBloomFilter) -> Vec<Message> {
+    let merkle_block = |data: &[u8], filter: &mut SupernovaBloomFilter| {
+        bincode::deserialize::<Block>(data)
+            .ok()
+            .map(|block| Message::MerkleBlock(MerkleBlock::build(&block, filter)))
+    };
+    match message {
+        Message::Block(block) => vec![Message::MerkleBlock(MerkleBlock::build(&block, filter))],
+        Message::NewBlock { block_data, .. } => {
+            merkle_block(&block_data, filter).into_iter().collect()
+        }
+        Message::BlockResponse {
+            block: Some(block_data),
+        } => merkle_block(&block_data, filter).into_iter().collect(),
+        Message::Blocks { blocks } => blocks
+            .iter()
+            .filter_map(|data| merkle_block(data, filter))
+            .collect(),
+        Message::Transaction { transaction } => {
+            match bincode::deserialize::<Transaction>(&transaction) {
+                Ok(tx) if filter.matches_transaction(&tx) => {
+                    vec![Message::Transaction { transaction }]
+                }
+                _ => Vec::new(),
+            }
+        }
+        other => vec![other],
+    }
 }
 
 /// Bloom filter statistics
@@ -461,6 +681,16 @@ pub enum FilterMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+
+    fn paying_to(seed: u8, script: Vec<u8>) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new([seed; 32], 0, vec![], 0xffffffff)],
+            vec![TransactionOutput::new(1_000, script)],
+            0,
+        )
+    }
 
     #[test]
     fn test_bloom_filter_false_positive_rate() {
@@ -562,5 +792,93 @@ mod tests {
         mempool_filter.clear();
         assert!(!mempool_filter.contains_transaction(&tx1));
     }
-}
 
+    #[test]
+    fn filtered_block_relays_only_the_matching_transaction_with_proof() {
+        let watched = vec![0xab; 25];
+        let txs: Vec<Transaction> = (0u8..6)
+            .map(|i| {
+                let script = if i == 3 { watched.clone() } else { vec![i; 25] };
+                paying_to(i, script)
+            })
+            .collect();
+        let block = Block::new_with_params(1, [0u8; 32], txs.clone(), 0x207fffff);
+
+        let mut filter = SupernovaBloomFilter::new(10, 0.0001, 7).unwrap();
+        filter.add(&watched);
+        let mut filter = SupernovaBloomFilter::from_filter_load(
+            &filter.compress(),
+            filter.bit_count(),
+            filter.hash_count(),
+            7,
+        )
+        .unwrap();
+
+        let announcement = Message::NewBlock {
+            block_data: bincode::serialize(&block).unwrap(),
+            height: 1,
+            total_difficulty: 1,
+        };
+        let relayed = filter_message(announcement, &mut filter);
+        assert_eq!(relayed.len(), 1);
+        let Message::MerkleBlock(merkle_block) = &relayed[0] else {
+            panic!("expected a merkle block, got {:?}", relayed[0]);
+        };
+        assert_eq!(merkle_block.total_transactions, 6);
+        assert_eq!(merkle_block.indices, vec![3]);
+        assert_eq!(merkle_block.transactions[0].hash(), txs[3].hash());
+        assert!(merkle_block.verify());
+
+        // A proof does not cover a transaction swapped in
+        let mut forged = merkle_block.clone();
+        forged.transactions[0] = txs[2].clone();
+        assert!(!forged.verify());
+
+        // Unrelated transactions are withheld; spends of the matched output
+        // are relayed
+        let unrelated = Message::Transaction {
+            transaction: bincode::serialize(&txs[1]).unwrap(),
+        };
+        assert!(filter_message(unrelated, &mut filter).is_empty());
+        let spend = Transaction::new(
+            1,
+            vec![TransactionInput::new(txs[3].hash(), 0, vec![], 0xffffffff)],
+            vec![TransactionOutput::new(900, vec![0x11; 25])],
+            0,
+        );
+        let spend = Message::Transaction {
+            transaction: bincode::serialize(&spend).unwrap(),
+        };
+        assert_eq!(filter_message(spend, &mut filter).len(), 1);
+    }
+
+    #[test]
+    fn abusive_filters_are_rejected() {
+        assert_eq!(
+            SupernovaBloomFilter::from_filter_load(&[], (MAX_FILTER_BYTES + 1) * 8, 10, 0)
+                .unwrap_err(),
+            BloomFilterError::TooLarge(MAX_FILTER_BYTES + 1)
+        );
+        assert_eq!(
+            SupernovaBloomFilter::from_filter_load(&[0u8; 16], 128, MAX_HASH_FUNCS + 1, 0)
+                .unwrap_err(),
+            BloomFilterError::TooManyHashFunctions(MAX_HASH_FUNCS + 1)
+        );
+        // A saturated filter would match every transaction
+        assert_eq!(
+            SupernovaBloomFilter::from_filter_load(&[0xff; 16], 128, 5, 0).unwrap_err(),
+            BloomFilterError::FalsePositiveRateTooHigh
+        );
+
+        let mut filter = SupernovaBloomFilter::from_filter_load(&[], 128, 5, 0).unwrap();
+        assert_eq!(
+            filter.add_from_peer(&[0u8; MAX_FILTER_ELEMENT_BYTES + 1]),
+            Err(BloomFilterError::ElementTooLarge(
+                MAX_FILTER_ELEMENT_BYTES + 1
+            ))
+        );
+        // Filling a small filter element by element trips the same limit
+        let filled = (0u32..200).try_for_each(|i| filter.add_from_peer(&i.to_le_bytes()));
+        assert_eq!(filled, Err(BloomFilterError::FalsePositiveRateTooHigh));
+    }
+}
@@ -1,11 +1,12 @@
 use crate::network::peer::{PeerManager, PeerState};
-use crate::network::peer_diversity::PeerDiversityManager;
+use crate::network::peer_diversity::{OutboundDiversityPolicy, PeerDiversityManager};
 use libp2p::{
     core::{ConnectedPoint, Multiaddr},
     swarm::DialError,
     PeerId,
 };
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
@@ -148,6 +149,10 @@ pub struct ConnectionManager {
     max_feeler_connections: usize,
     /// Next connection ID
     next_connection_id: u64,
+    /// Caps on outbound peers sharing a prefix or ASN
+    outbound_diversity: OutboundDiversityPolicy,
+    /// Last time a peer over an outbound diversity cap was rotated out
+    last_diversity_rotation: Instant,
 }
 
 impl ConnectionManager {
@@ -178,6 +183,8 @@ impl ConnectionManager {
             feeler_addresses: HashMap::new(),
             max_feeler_connections: 2,
             next_connection_id: 0,
+            outbound_diversity: OutboundDiversityPolicy::default(),
+            last_diversity_rotation: Instant::now(),
         }
     }
 
@@ -209,6 +216,8 @@ impl ConnectionManager {
             feeler_addresses: HashMap::new(),
             max_feeler_connections: 2,
             next_connection_id: 0,
+            outbound_diversity: OutboundDiversityPolicy::default(),
+            last_diversity_rotation: Instant::now(),
         }
     }
 
@@ -357,6 +366,59 @@ impl ConnectionManager {
         }
     }
 
+    /// Replace the outbound diversity policy. Peers already over a new cap
+    /// are rotated out one at a time by `next_diversity_rotation`.
+    pub fn set_outbound_diversity(&mut self, policy: OutboundDiversityPolicy) {
+        self.outbound_diversity = policy;
+    }
+
+    /// Outbound peers with an IP address, longest connected first
+    fn outbound_peers(&self) -> Vec<(PeerId, IpAddr)> {
+        let mut peers: Vec<(Instant, PeerId, IpAddr)> = self
+            .peer_endpoints
+            .iter()
+            .filter_map(|(peer_id, endpoint)| match endpoint {
+                ConnectedPoint::Dialer { address, .. } => {
+                    let ip = PeerDiversityManager::extract_ip_from_multiaddr(address)?;
+                    let established = self
+                        .connection_metadata
+                        .iter()
+                        .filter(|((peer, _), _)| peer == peer_id)
+                        .map(|(_, metadata)| metadata.established_at)
+                        .min()?;
+                    Some((established, *peer_id, ip))
+                }
+                ConnectedPoint::Listener { .. } => None,
+            })
+            .collect();
+        peers.sort_by_key(|(established, _, _)| *established);
+        peers.into_iter().map(|(_, peer, ip)| (peer, ip)).collect()
+    }
+
+    /// An outbound peer to disconnect because it exceeds a diversity cap, at
+    /// most one per rotation interval so a tightened policy does not drop
+    /// many peers at once. Persistent peers are never chosen.
+    pub fn next_diversity_rotation(&mut self) -> Option<PeerId> {
+        let now = Instant::now();
+        if now.duration_since(self.last_diversity_rotation)
+            < self.outbound_diversity.rotation_interval()
+        {
+            return None;
+        }
+        let outbound: Vec<(PeerId, IpAddr)> = self
+            .outbound_peers()
+            .into_iter()
+            .filter(|(peer_id, _)| !self.persistent_peers.contains(peer_id))
+            .collect();
+        let peer_id = self.outbound_diversity.excess_peers(&outbound, 1).pop()?;
+        self.last_diversity_rotation = now;
+        debug!(
+            "Rotating out peer {} to restore outbound diversity",
+            peer_id
+        );
+        Some(peer_id)
+    }
+
     /// Start a connection attempt (track dial start time)
     pub fn start_dial(&mut self, peer_id: PeerId) {
         self.pending_dials.insert(peer_id, Instant::now());
@@ -634,29 +696,50 @@ impl ConnectionManager {
             }
         }
 
-        // Then process regular connection queue
-        while self.has_outbound_slots() && !self.connection_queue.is_empty() {
-            if let Some((peer_id, addr)) = self.connection_queue.pop_front() {
-                // Skip if already connected or dialing
-                if self.is_connected(&peer_id) || self.pending_dials.contains_key(&peer_id) {
-                    continue;
-                }
-
-                // Skip if banned
-                if self.peer_manager.is_peer_banned(&peer_id) {
-                    continue;
-                }
-
-                debug!("Dialing queued peer {}", peer_id);
-                dial_peer(peer_id, addr);
-                self.start_dial(peer_id);
-
-                // Break if no more slots
-                if !self.has_outbound_slots() {
-                    break;
-                }
+        // Then process regular connection queue, least represented prefixes
+        // and ASNs first. Peers that would exceed a diversity cap stay queued
+        // until the distribution changes.
+        let queued: Vec<(PeerId, Multiaddr)> = std::mem::take(&mut self.connection_queue)
+            .into_iter()
+            .filter(|(peer_id, _)| {
+                !self.is_connected(peer_id)
+                    && !self.pending_dials.contains_key(peer_id)
+                    && !self.peer_manager.is_peer_banned(peer_id)
+            })
+            .collect();
+        let free_slots = self
+            .max_outbound_connections
+            .saturating_sub(self.outbound_count + self.pending_dials.len());
+        let outbound: Vec<IpAddr> = self
+            .outbound_peers()
+            .into_iter()
+            .map(|(_, ip)| ip)
+            .collect();
+        let candidates = queued
+            .iter()
+            .enumerate()
+            .map(|(index, (_, addr))| {
+                (index, PeerDiversityManager::extract_ip_from_multiaddr(addr))
+            })
+            .collect();
+        let selected: HashSet<usize> = self
+            .outbound_diversity
+            .select_candidates(&outbound, candidates, free_slots)
+            .into_iter()
+            .collect();
+        let mut dials = Vec::new();
+        for (index, entry) in queued.into_iter().enumerate() {
+            if selected.contains(&index) {
+                dials.push(entry);
+            } else {
+                self.connection_queue.push_back(entry);
             }
         }
+        for (peer_id, addr) in dials {
+            debug!("Dialing queued peer {}", peer_id);
+            dial_peer(peer_id, addr);
+            self.start_dial(peer_id);
+        }
 
         // Finally, try feeler connections if enabled
         if include_feelers && self.has_outbound_slots() {
@@ -798,7 +881,7 @@ impl ConnectionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::network::peer_diversity::ConnectionStrategy;
+    use crate::network::peer_diversity::{ConnectionStrategy, NetPrefix};
     use std::sync::Arc;
 
     // Helper to create a test manager
@@ -925,4 +1008,84 @@ mod tests {
         assert!(manager.has_outbound_slots());
         assert!(manager.has_inbound_slots());
     }
+
+    fn tcp_addr(ip: &str) -> Multiaddr {
+        format!("/ip4/{}/tcp/8000", ip).parse().unwrap()
+    }
+
+    fn dialer(ip: &str) -> ConnectedPoint {
+        ConnectedPoint::Dialer {
+            address: tcp_addr(ip),
+            role_override: libp2p::core::Endpoint::Dialer,
+        }
+    }
+
+    #[test]
+    fn test_queue_prefers_underrepresented_prefixes() {
+        let mut manager = create_test_manager();
+
+        // Skewed address book: ten peers in 10.1/16, one each elsewhere
+        for i in 0..10 {
+            manager.queue_connection(PeerId::random(), tcp_addr(&format!("10.1.{}.1", i)));
+        }
+        manager.queue_connection(PeerId::random(), tcp_addr("172.16.0.1"));
+        manager.queue_connection(PeerId::random(), tcp_addr("8.8.8.8"));
+
+        let mut dialed = Vec::new();
+        manager.process_connection_queue(|_, addr| dialed.push(addr));
+
+        let prefixes: HashSet<NetPrefix> = dialed
+            .iter()
+            .filter_map(PeerDiversityManager::extract_ip_from_multiaddr)
+            .map(NetPrefix::of)
+            .collect();
+        assert_eq!(dialed.len(), 3);
+        assert_eq!(prefixes.len(), 3);
+        assert_eq!(manager.connection_queue.len(), 9);
+
+        // 10.1/16 already holds the cap of two outbound peers
+        let mut manager = create_test_manager();
+        manager.handle_connection_established(&PeerId::random(), 1, dialer("10.1.0.1"));
+        manager.handle_connection_established(&PeerId::random(), 2, dialer("10.1.0.2"));
+        manager.queue_connection(PeerId::random(), tcp_addr("10.1.9.9"));
+        let mut dialed = 0;
+        manager.process_connection_queue(|_, _| dialed += 1);
+        assert_eq!(dialed, 0);
+        assert_eq!(manager.connection_queue.len(), 1);
+    }
+
+    #[test]
+    fn test_excess_outbound_peers_rotate_one_at_a_time() {
+        let mut manager = ConnectionManager::new(
+            Arc::new(PeerManager::new()),
+            Arc::new(PeerDiversityManager::new()),
+            5,
+            8,
+        );
+        let mut peers = Vec::new();
+        for i in 0..4u64 {
+            let peer_id = PeerId::random();
+            manager.handle_connection_established(&peer_id, i, dialer(&format!("10.1.0.{}", i)));
+            peers.push(peer_id);
+        }
+        manager.handle_connection_established(&PeerId::random(), 9, dialer("172.16.0.1"));
+
+        // Tightened policy: two of the four 10.1/16 peers must go
+        manager.set_outbound_diversity(
+            OutboundDiversityPolicy::new(2, 2).with_rotation_interval(Duration::ZERO),
+        );
+        for _ in 0..2 {
+            let peer_id = manager.next_diversity_rotation().unwrap();
+            let index = peers.iter().position(|p| *p == peer_id).unwrap();
+            manager.handle_connection_closed(&peer_id, index as u64);
+        }
+        assert_eq!(manager.next_diversity_rotation(), None);
+        assert_eq!(manager.outbound_count, 3);
+
+        // Within the rotation interval nothing more is rotated out
+        manager.set_outbound_diversity(
+            OutboundDiversityPolicy::new(1, 1).with_rotation_interval(Duration::from_secs(3600)),
+        );
+        assert_eq!(manager.next_diversity_rotation(), None);
+    }
 }
@@ -0,0 +1,288 @@
+//! Peer address exchange
+//!
+//! [`AddressBook`] runs the [`AddrMan`] against the live network: outbound
+//! peers are asked for addresses (`GetAddr`) once connected, their `Addr`
+//! answers are recorded, and free outbound slots are filled from the book.
+//! Only inbound peers are answered, and only once per connection, so a peer
+//! we dialed cannot map our book by asking repeatedly. The book is saved
+//! periodically and on shutdown.
+
+use super::addrman::{AddrMan, AddrManError, MAX_ADDR_PER_MESSAGE};
+use crate::network::address_advertisement::AddressAdvertiser;
+use crate::network::p2p::{extract_ip_from_multiaddr, NetworkCommand};
+use crate::network::peer::PeerInfo;
+use crate::network::peer_diversity::OutboundDiversityPolicy;
+use crate::network::protocol::{Message, PeerAddr};
+use libp2p::{Multiaddr, PeerId};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// How often free outbound slots are filled from the book
+const FILL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the book is saved while running
+const SAVE_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug)]
+struct ConnectedPeer {
+    addr: Option<Multiaddr>,
+    inbound: bool,
+    /// Whether we already answered its `GetAddr`
+    answered: bool,
+}
+
+/// Feeds the address manager from peer exchange and dials from it
+pub struct AddressBook {
+    addrman: parking_lot::Mutex<AddrMan>,
+    path: Option<PathBuf>,
+    target_outbound: usize,
+    diversity: OutboundDiversityPolicy,
+    peers: parking_lot::Mutex<HashMap<PeerId, ConnectedPeer>>,
+    /// Our own addresses, announced ahead of the book
+    advertiser: Option<Arc<std::sync::Mutex<AddressAdvertiser>>>,
+    command_tx: mpsc::Sender<NetworkCommand>,
+}
+
+impl AddressBook {
+    /// Load the book from `path`, if given; an unreadable file starts an
+    /// empty one
+    pub fn new(
+        path: Option<PathBuf>,
+        target_outbound: usize,
+        diversity: OutboundDiversityPolicy,
+        command_tx: mpsc::Sender<NetworkCommand>,
+    ) -> Self {
+        let addrman = match &path {
+            Some(path) => match AddrMan::load(path, now()) {
+                Ok(addrman) => {
+                    info!(
+                        "Loaded {} peer addresses ({} tried) from {}",
+                        addrman.len(),
+                        addrman.tried_count(),
+                        path.display()
+                    );
+                    addrman
+                }
+                Err(e) => {
+                    warn!("Starting with an empty address book: {}", e);
+                    AddrMan::new()
+                }
+            },
+            None => AddrMan::new(),
+        };
+        Self {
+            addrman: parking_lot::Mutex::new(addrman),
+            path,
+            target_outbound,
+            diversity,
+            peers: parking_lot::Mutex::new(HashMap::new()),
+            advertiser: None,
+            command_tx,
+        }
+    }
+
+    /// Include the addresses `advertiser` selects in our `Addr` answers
+    pub fn with_advertiser(mut self, advertiser: Arc<std::sync::Mutex<AddressAdvertiser>>) -> Self {
+        self.advertiser = Some(advertiser);
+        self
+    }
+
+    /// Known addresses, and how many of them we have connected to
+    pub fn counts(&self) -> (usize, usize) {
+        let addrman = self.addrman.lock();
+        (addrman.len(), addrman.tried_count())
+    }
+
+    /// Record a new connection; outbound peers go to the tried table and are
+    /// asked for addresses
+    pub async fn peer_connected(&self, peer: &PeerInfo) {
+        let addr = peer.addresses.first().cloned();
+        self.peers.lock().insert(
+            peer.peer_id,
+            ConnectedPeer {
+                addr: addr.clone(),
+                inbound: peer.is_inbound,
+                answered: false,
+            },
+        );
+        if peer.is_inbound {
+            return;
+        }
+        if let Some(addr) = &addr {
+            self.addrman.lock().mark_good(addr, now());
+        }
+        self.send(peer.peer_id, Message::GetAddr).await;
+    }
+
+    pub fn peer_disconnected(&self, peer_id: &PeerId) {
+        self.peers.lock().remove(peer_id);
+    }
+
+    /// Handle `GetAddr` and `Addr`. Returns false for any other message.
+    pub async fn handle_message(&self, peer_id: PeerId, message: &Message) -> bool {
+        match message {
+            Message::GetAddr => {
+                let answer = match self.peers.lock().get_mut(&peer_id) {
+                    Some(peer) if peer.inbound && !peer.answered => {
+                        peer.answered = true;
+                        true
+                    }
+                    _ => false,
+                };
+                if !answer {
+                    debug!("Ignoring address request from {}", peer_id);
+                    return true;
+                }
+                let now = now();
+                let mut addrs: Vec<PeerAddr> = self
+                    .advertiser
+                    .as_ref()
+                    .and_then(|advertiser| advertiser.lock().ok().map(|a| a.advertised()))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|addr| PeerAddr {
+                        addr: addr.to_string(),
+                        last_seen: now,
+                    })
+                    .collect();
+                let remaining = MAX_ADDR_PER_MESSAGE.saturating_sub(addrs.len());
+                addrs.extend(
+                    self.addrman
+                        .lock()
+                        .get_addr(remaining, now)
+                        .into_iter()
+                        .map(|info| PeerAddr {
+                            addr: info.addr.to_string(),
+                            last_seen: info.last_seen,
+                        }),
+                );
+                self.send(peer_id, Message::Addr(addrs)).await;
+            }
+            Message::Addr(addrs) => {
+                let source = self
+                    .peers
+                    .lock()
+                    .get(&peer_id)
+                    .and_then(|peer| peer.addr.clone());
+                let Some(source) = source else {
+                    debug!("Ignoring addresses from unknown peer {}", peer_id);
+                    return true;
+                };
+                if addrs.len() > MAX_ADDR_PER_MESSAGE {
+                    debug!("Ignoring {} addresses from {}", addrs.len(), peer_id);
+                    return true;
+                }
+                let now = now();
+                let mut addrman = self.addrman.lock();
+                let added = addrs
+                    .iter()
+                    .filter_map(|entry| entry.addr.parse::<Multiaddr>().ok().map(|a| (a, entry)))
+                    .filter(|(addr, entry)| addrman.add(addr, &source, entry.last_seen, now))
+                    .count();
+                debug!(
+                    "{} of {} addresses from {} were new",
+                    added,
+                    addrs.len(),
+                    peer_id
+                );
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Save the book, if it has a path. Returns the number of addresses
+    /// written.
+    pub fn save(&self) -> Result<usize, AddrManError> {
+        match &self.path {
+            Some(path) => self.addrman.lock().save(path),
+            None => Ok(0),
+        }
+    }
+
+    /// Addresses to dial so that outbound connections reach the target,
+    /// spread across prefixes by the diversity policy
+    fn candidates(&self) -> Vec<Multiaddr> {
+        let peers = self.peers.lock();
+        let outbound: Vec<&ConnectedPeer> = peers.values().filter(|peer| !peer.inbound).collect();
+        let free = self.target_outbound.saturating_sub(outbound.len());
+        if free == 0 {
+            return Vec::new();
+        }
+        let outbound_ips: Vec<IpAddr> = outbound
+            .iter()
+            .filter_map(|peer| peer.addr.as_ref().and_then(extract_ip_from_multiaddr))
+            .collect();
+        let connected: HashSet<Multiaddr> = peers
+            .values()
+            .filter_map(|peer| peer.addr.clone())
+            .collect();
+        drop(peers);
+
+        let candidates = self
+            .addrman
+            .lock()
+            .select_candidates(free * 2, &connected, now())
+            .into_iter()
+            .map(|addr| {
+                let ip = extract_ip_from_multiaddr(&addr);
+                (addr, ip)
+            })
+            .collect();
+        self.diversity
+            .select_candidates(&outbound_ips, candidates, free)
+    }
+
+    /// Fill free outbound slots every `FILL_INTERVAL` and save the book
+    /// every `SAVE_INTERVAL`
+    pub async fn run(self: Arc<Self>) {
+        let mut fill = tokio::time::interval(FILL_INTERVAL);
+        let mut save = tokio::time::interval(SAVE_INTERVAL);
+        // The first tick of each fires immediately
+        save.tick().await;
+        loop {
+            tokio::select! {
+                _ = fill.tick() => {
+                    for addr in self.candidates() {
+                        self.addrman.lock().mark_attempt(&addr, now());
+                        debug!("Dialing {} from the address book", addr);
+                        if let Err(e) = self
+                            .command_tx
+                            .send(NetworkCommand::ConnectToPeer(addr.to_string()))
+                            .await
+                        {
+                            warn!("Failed to queue dial to {}: {}", addr, e);
+                        }
+                    }
+                }
+                _ = save.tick() => {
+                    let book = Arc::clone(&self);
+                    match tokio::task::spawn_blocking(move || book.save()).await {
+                        Ok(Ok(saved)) => debug!("Saved {} peer addresses", saved),
+                        Ok(Err(e)) => warn!("Failed to save the address book: {}", e),
+                        Err(e) => warn!("Address book save task failed: {}", e),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send(&self, peer_id: PeerId, message: Message) {
+        if let Err(e) = self
+            .command_tx
+            .send(NetworkCommand::SendToPeer { peer_id, message })
+            .await
+        {
+            warn!("Failed to queue address message for {}: {}", peer_id, e);
+        }
+    }
+}
+
+fn now() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
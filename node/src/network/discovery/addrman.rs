@@ -0,0 +1,674 @@
+//! Address manager
+//!
+//! Remembers peer addresses learned from peer exchange so a restarted node
+//! can find the network without its bootstrap nodes. Addresses start in the
+//! "new" table and move to the "tried" table once we have connected to them.
+//! Both tables are split into buckets picked by a keyed hash of the address
+//! group (its /16 or /32 prefix, see [`NetPrefix`]) and, for new addresses,
+//! of the group of the peer that relayed it. Whatever one peer sends, its
+//! addresses land in a handful of buckets, so it cannot flush the table.
+//!
+//! Each address holds exactly one slot. When two addresses hash to the same
+//! slot the one already there stays, unless it has gone stale or keeps
+//! failing ([`AddrInfo::is_terrible`]).
+
+use crate::network::peer_diversity::NetPrefix;
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use thiserror::Error;
+
+/// Buckets in the new table
+pub const NEW_BUCKET_COUNT: usize = 256;
+/// Buckets in the tried table
+pub const TRIED_BUCKET_COUNT: usize = 64;
+/// Slots per bucket
+pub const BUCKET_SIZE: usize = 64;
+/// New buckets the addresses relayed by one source group can land in
+pub const NEW_BUCKETS_PER_SOURCE_GROUP: u64 = 16;
+/// Tried buckets the addresses of one group can land in
+pub const TRIED_BUCKETS_PER_GROUP: u64 = 8;
+/// Addresses returned for a `GetAddr`, as a percentage of the table
+pub const GETADDR_PERCENT: usize = 23;
+/// Most addresses sent in, or accepted from, one `Addr` message
+pub const MAX_ADDR_PER_MESSAGE: usize = 1000;
+/// File the address book is kept in, under the data directory
+pub const PEERS_FILE: &str = "peers.json";
+
+const PEERS_FILE_VERSION: u32 = 1;
+/// Addresses not seen for this long are terrible
+const HORIZON_SECS: u64 = 30 * 24 * 60 * 60;
+/// Relayed timestamps are aged by this much; only a connection proves liveness
+const RELAY_PENALTY_SECS: u64 = 2 * 60 * 60;
+/// Attempts without ever connecting before an address is terrible
+const MAX_RETRIES: u32 = 3;
+/// Attempts since the last success, at least `MIN_FAIL_SECS` ago, before an
+/// address is terrible
+const MAX_FAILURES: u32 = 10;
+const MIN_FAIL_SECS: u64 = 7 * 24 * 60 * 60;
+/// Addresses tried this recently are much less likely to be selected again
+const RECENT_TRY_SECS: u64 = 10 * 60;
+
+#[derive(Debug, Error)]
+pub enum AddrManError {
+    #[error("I/O error on {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Malformed peers file: {0}")]
+    Malformed(String),
+    #[error("Unsupported peers file version {0}")]
+    UnsupportedVersion(u32),
+}
+
+/// What we know about one address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddrInfo {
+    #[serde(with = "multiaddr_string")]
+    pub addr: Multiaddr,
+    /// Group of the peer that told us about the address
+    pub source_group: String,
+    /// Last time the address was reported or connected to, Unix seconds
+    pub last_seen: u64,
+    /// Last connection attempt, 0 if never tried
+    pub last_try: u64,
+    /// Last successful connection, 0 if never connected
+    pub last_success: u64,
+    /// Attempts since the last success
+    pub attempts: u32,
+    /// Whether the address is in the tried table
+    pub tried: bool,
+}
+
+impl AddrInfo {
+    /// Whether the address is not worth its slot: stale, claiming to be
+    /// from the future, or failing to connect
+    pub fn is_terrible(&self, now: u64) -> bool {
+        // Just tried; give the attempt a chance to finish
+        if self.last_try > 0 && now.saturating_sub(self.last_try) < 60 {
+            return false;
+        }
+        if self.last_seen > now + 10 * 60 {
+            return true;
+        }
+        if self.last_seen == 0 || now.saturating_sub(self.last_seen) > HORIZON_SECS {
+            return true;
+        }
+        if self.last_success == 0 && self.attempts >= MAX_RETRIES {
+            return true;
+        }
+        now.saturating_sub(self.last_success) > MIN_FAIL_SECS && self.attempts >= MAX_FAILURES
+    }
+
+    /// Relative chance of being selected
+    fn chance(&self, now: u64) -> f64 {
+        let mut chance = 1.0;
+        if now.saturating_sub(self.last_try) < RECENT_TRY_SECS {
+            chance *= 0.01;
+        }
+        chance * 0.66f64.powi(self.attempts.min(8) as i32)
+    }
+}
+
+/// Address book with new and tried tables
+pub struct AddrMan {
+    /// Secret bucketing key, so others cannot predict where addresses land
+    key: [u8; 32],
+    entries: HashMap<Multiaddr, AddrInfo>,
+    new_table: Vec<Option<Multiaddr>>,
+    tried_table: Vec<Option<Multiaddr>>,
+}
+
+impl Default for AddrMan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AddrMan {
+    pub fn new() -> Self {
+        Self::with_key(rand::thread_rng().gen())
+    }
+
+    pub fn with_key(key: [u8; 32]) -> Self {
+        Self {
+            key,
+            entries: HashMap::new(),
+            new_table: vec![None; NEW_BUCKET_COUNT * BUCKET_SIZE],
+            tried_table: vec![None; TRIED_BUCKET_COUNT * BUCKET_SIZE],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn tried_count(&self) -> usize {
+        self.entries.values().filter(|info| info.tried).count()
+    }
+
+    pub fn new_count(&self) -> usize {
+        self.len() - self.tried_count()
+    }
+
+    pub fn get(&self, addr: &Multiaddr) -> Option<&AddrInfo> {
+        self.entries.get(&normalize(addr))
+    }
+
+    /// Record an address reported by the peer at `source`. Returns whether
+    /// it is new to the book; an address whose slot is held by a healthy
+    /// entry is dropped.
+    pub fn add(&mut self, addr: &Multiaddr, source: &Multiaddr, last_seen: u64, now: u64) -> bool {
+        let addr = normalize(addr);
+        if addr.is_empty() {
+            return false;
+        }
+        let relayed = normalize(source) != addr;
+        let mut last_seen = last_seen.min(now);
+        if relayed {
+            last_seen = last_seen.saturating_sub(RELAY_PENALTY_SECS);
+        }
+
+        if let Some(info) = self.entries.get_mut(&addr) {
+            info.last_seen = info.last_seen.max(last_seen);
+            return false;
+        }
+
+        let info = AddrInfo {
+            source_group: group(source),
+            addr: addr.clone(),
+            last_seen,
+            last_try: 0,
+            last_success: 0,
+            attempts: 0,
+            tried: false,
+        };
+        self.place_new(info, now)
+    }
+
+    /// Record a connection attempt
+    pub fn mark_attempt(&mut self, addr: &Multiaddr, now: u64) {
+        if let Some(info) = self.entries.get_mut(&normalize(addr)) {
+            info.last_try = now;
+            info.attempts = info.attempts.saturating_add(1);
+        }
+    }
+
+    /// Record a successful outbound connection and move the address to the
+    /// tried table. An address already in the tried slot is moved back to
+    /// the new table.
+    pub fn mark_good(&mut self, addr: &Multiaddr, now: u64) {
+        let addr = normalize(addr);
+        if !self.entries.contains_key(&addr) && !self.add(&addr, &addr, now, now) {
+            return;
+        }
+        let Some(info) = self.entries.get_mut(&addr) else {
+            return;
+        };
+        info.last_seen = now;
+        info.last_try = now;
+        info.last_success = now;
+        info.attempts = 0;
+        if info.tried {
+            return;
+        }
+
+        let source_group = info.source_group.clone();
+        let new_slot = self.new_slot(&addr, &source_group);
+        if self.new_table[new_slot].as_ref() == Some(&addr) {
+            self.new_table[new_slot] = None;
+        }
+
+        let tried_slot = self.tried_slot(&addr);
+        if let Some(evicted) = self.tried_table[tried_slot].take() {
+            if let Some(mut evicted_info) = self.entries.remove(&evicted) {
+                evicted_info.tried = false;
+                self.place_new(evicted_info, now);
+            }
+        }
+        self.tried_table[tried_slot] = Some(addr.clone());
+        if let Some(info) = self.entries.get_mut(&addr) {
+            info.tried = true;
+        }
+    }
+
+    /// Pick an address to connect to, half the time from each table, less
+    /// often ones that were tried recently or keep failing
+    pub fn select<R: Rng>(
+        &self,
+        exclude: &HashSet<Multiaddr>,
+        now: u64,
+        rng: &mut R,
+    ) -> Option<Multiaddr> {
+        let candidates = |tried: bool| {
+            self.entries
+                .values()
+                .filter(|info| info.tried == tried && !exclude.contains(&info.addr))
+                .collect::<Vec<_>>()
+        };
+        let (tried, new) = (candidates(true), candidates(false));
+        let pool = match (tried.is_empty(), new.is_empty()) {
+            (true, true) => return None,
+            (false, true) => tried,
+            (true, false) => new,
+            (false, false) => {
+                if rng.gen_bool(0.5) {
+                    tried
+                } else {
+                    new
+                }
+            }
+        };
+
+        let mut factor = 1.0;
+        loop {
+            let info = pool.choose(rng)?;
+            if rng.gen::<f64>() < info.chance(now) * factor {
+                return Some(info.addr.clone());
+            }
+            factor *= 1.2;
+        }
+    }
+
+    /// Up to `count` distinct addresses to connect to, skipping `exclude`
+    pub fn select_candidates(
+        &self,
+        count: usize,
+        exclude: &HashSet<Multiaddr>,
+        now: u64,
+    ) -> Vec<Multiaddr> {
+        let mut rng = rand::thread_rng();
+        let mut exclude: HashSet<Multiaddr> = exclude.iter().map(normalize).collect();
+        let mut selected = Vec::new();
+        while selected.len() < count {
+            let Some(addr) = self.select(&exclude, now, &mut rng) else {
+                break;
+            };
+            exclude.insert(addr.clone());
+            selected.push(addr);
+        }
+        selected
+    }
+
+    /// Random sample of healthy addresses to answer a `GetAddr`: at most
+    /// `max`, and at most `GETADDR_PERCENT` of the book
+    pub fn get_addr(&self, max: usize, now: u64) -> Vec<AddrInfo> {
+        let mut healthy: Vec<&AddrInfo> = self
+            .entries
+            .values()
+            .filter(|info| !info.is_terrible(now))
+            .collect();
+        let count = (self.entries.len() * GETADDR_PERCENT)
+            .div_ceil(100)
+            .min(max);
+        healthy.shuffle(&mut rand::thread_rng());
+        healthy.into_iter().take(count).cloned().collect()
+    }
+
+    /// Write the book to `path` as JSON, replacing it atomically
+    pub fn save(&self, path: &Path) -> Result<usize, AddrManError> {
+        let mut entries: Vec<&AddrInfo> = self.entries.values().collect();
+        entries.sort_by(|a, b| a.addr.to_string().cmp(&b.addr.to_string()));
+        let file = PeersFileRef {
+            version: PEERS_FILE_VERSION,
+            key: hex::encode(self.key),
+            entries,
+        };
+        let bytes =
+            serde_json::to_vec_pretty(&file).map_err(|e| AddrManError::Malformed(e.to_string()))?;
+        let io_error = |source| AddrManError::Io {
+            path: path.display().to_string(),
+            source,
+        };
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, &bytes).map_err(io_error)?;
+        std::fs::rename(&tmp, path).map_err(io_error)?;
+        Ok(file.entries.len())
+    }
+
+    /// Read a book written by [`Self::save`]. A missing file is an empty
+    /// book. Entries are placed again with the saved key, so they keep their
+    /// buckets.
+    pub fn load(path: &Path, now: u64) -> Result<Self, AddrManError> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(source) => {
+                return Err(AddrManError::Io {
+                    path: path.display().to_string(),
+                    source,
+                })
+            }
+        };
+        let file: PeersFile =
+            serde_json::from_slice(&bytes).map_err(|e| AddrManError::Malformed(e.to_string()))?;
+        if file.version != PEERS_FILE_VERSION {
+            return Err(AddrManError::UnsupportedVersion(file.version));
+        }
+        let key: [u8; 32] = hex::decode(&file.key)
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| AddrManError::Malformed("bucketing key".to_string()))?;
+
+        let mut addrman = Self::with_key(key);
+        let (tried, new): (Vec<AddrInfo>, Vec<AddrInfo>) =
+            file.entries.into_iter().partition(|info| info.tried);
+        for mut info in tried {
+            info.addr = normalize(&info.addr);
+            let slot = addrman.tried_slot(&info.addr);
+            if addrman.tried_table[slot].is_none() && !addrman.entries.contains_key(&info.addr) {
+                addrman.tried_table[slot] = Some(info.addr.clone());
+                addrman.entries.insert(info.addr.clone(), info);
+            } else {
+                info.tried = false;
+                addrman.place_new(info, now);
+            }
+        }
+        for mut info in new {
+            info.addr = normalize(&info.addr);
+            if !addrman.entries.contains_key(&info.addr) {
+                addrman.place_new(info, now);
+            }
+        }
+        Ok(addrman)
+    }
+
+    /// Put `info` in its new-table slot, evicting a terrible occupant
+    fn place_new(&mut self, info: AddrInfo, now: u64) -> bool {
+        let slot = self.new_slot(&info.addr, &info.source_group);
+        if let Some(occupant) = &self.new_table[slot] {
+            let terrible = match self.entries.get(occupant) {
+                Some(existing) => existing.is_terrible(now),
+                None => true,
+            };
+            if !terrible {
+                return false;
+            }
+            let occupant = occupant.clone();
+            self.entries.remove(&occupant);
+        }
+        self.new_table[slot] = Some(info.addr.clone());
+        self.entries.insert(info.addr.clone(), info);
+        true
+    }
+
+    fn hash(&self, parts: &[&[u8]]) -> u64 {
+        let mut hasher = Sha256::new();
+        hasher.update(self.key);
+        for part in parts {
+            hasher.update((part.len() as u32).to_le_bytes());
+            hasher.update(part);
+        }
+        let digest = hasher.finalize();
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&digest[..8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn new_slot(&self, addr: &Multiaddr, source_group: &str) -> usize {
+        let addr_group = group(addr);
+        let spread = self.hash(&[addr_group.as_bytes(), source_group.as_bytes()])
+            % NEW_BUCKETS_PER_SOURCE_GROUP;
+        let bucket =
+            self.hash(&[source_group.as_bytes(), &spread.to_le_bytes()]) % NEW_BUCKET_COUNT as u64;
+        self.slot(false, bucket, addr)
+    }
+
+    fn tried_slot(&self, addr: &Multiaddr) -> usize {
+        let spread = self.hash(&[&addr.to_vec()]) % TRIED_BUCKETS_PER_GROUP;
+        let bucket =
+            self.hash(&[group(addr).as_bytes(), &spread.to_le_bytes()]) % TRIED_BUCKET_COUNT as u64;
+        self.slot(true, bucket, addr)
+    }
+
+    fn slot(&self, tried: bool, bucket: u64, addr: &Multiaddr) -> usize {
+        let position = self.hash(&[&[tried as u8], &bucket.to_le_bytes(), &addr.to_vec()])
+            % BUCKET_SIZE as u64;
+        bucket as usize * BUCKET_SIZE + position as usize
+    }
+}
+
+/// Bucketing group of an address: its network prefix, or the host name of a
+/// DNS address
+pub fn group(addr: &Multiaddr) -> String {
+    addr.iter()
+        .find_map(|protocol| match protocol {
+            Protocol::Ip4(ip) => Some(NetPrefix::of(ip.into()).to_string()),
+            Protocol::Ip6(ip) => Some(NetPrefix::of(ip.into()).to_string()),
+            Protocol::Dns(host) | Protocol::Dns4(host) | Protocol::Dns6(host) => {
+                Some(host.to_string())
+            }
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// The address without its `/p2p/<peer id>` part, so the same host is one entry
+fn normalize(addr: &Multiaddr) -> Multiaddr {
+    addr.iter()
+        .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+        .collect()
+}
+
+#[derive(Serialize)]
+struct PeersFileRef<'a> {
+    version: u32,
+    key: String,
+    entries: Vec<&'a AddrInfo>,
+}
+
+#[derive(Deserialize)]
+struct PeersFile {
+    version: u32,
+    key: String,
+    entries: Vec<AddrInfo>,
+}
+
+mod multiaddr_string {
+    use libp2p::Multiaddr;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(addr: &Multiaddr, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&addr.to_string())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Multiaddr, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn addr(ip: &str) -> Multiaddr {
+        format!("/ip4/{}/tcp/8000", ip).parse().unwrap()
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(PEERS_FILE);
+        let source = addr("1.2.3.4");
+
+        let mut addrman = AddrMan::with_key([3; 32]);
+        let relayed = addr("10.200.0.1");
+        assert!(addrman.add(&relayed, &source, NOW - 60, NOW));
+        let with_peer_id: Multiaddr =
+            format!("{}/p2p/{}", addr("10.201.0.1"), libp2p::PeerId::random())
+                .parse()
+                .unwrap();
+        addrman.mark_good(&with_peer_id, NOW);
+        let added: Vec<Multiaddr> = (0..50u8)
+            .map(|i| addr(&format!("10.{}.0.1", i)))
+            .filter(|a| addrman.add(a, &source, NOW - 60, NOW))
+            .collect();
+        addrman.mark_attempt(&added[0], NOW);
+        addrman.mark_good(&added[1], NOW);
+
+        assert_eq!(addrman.save(&path).unwrap(), addrman.len());
+        let loaded = AddrMan::load(&path, NOW).unwrap();
+        assert_eq!(loaded.len(), addrman.len());
+        assert_eq!(loaded.tried_count(), 2);
+        assert_eq!(loaded.entries, addrman.entries);
+        assert_eq!(loaded.new_table, addrman.new_table);
+        assert_eq!(loaded.tried_table, addrman.tried_table);
+        assert_eq!(loaded.get(&added[0]).unwrap().attempts, 1);
+        assert!(loaded.get(&with_peer_id).unwrap().tried);
+
+        // Relayed timestamps were aged
+        assert_eq!(
+            loaded.get(&relayed).unwrap().last_seen,
+            NOW - 60 - RELAY_PENALTY_SECS
+        );
+
+        // A missing file is an empty book; a foreign one is refused
+        assert!(AddrMan::load(&dir.path().join("absent.json"), NOW)
+            .unwrap()
+            .is_empty());
+        std::fs::write(&path, b"{\"version\":9,\"key\":\"\",\"entries\":[]}").unwrap();
+        assert!(matches!(
+            AddrMan::load(&path, NOW),
+            Err(AddrManError::UnsupportedVersion(9))
+        ));
+    }
+
+    /// Two addresses from one source that land in the same new-table slot
+    fn colliding_pair(addrman: &AddrMan, source: &Multiaddr) -> (Multiaddr, Multiaddr) {
+        let source_group = group(source);
+        let mut seen: HashMap<usize, Multiaddr> = HashMap::new();
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let candidate = addr(&format!("{}.{}.1.1", a, b));
+                let slot = addrman.new_slot(&candidate, &source_group);
+                if let Some(first) = seen.insert(slot, candidate.clone()) {
+                    return (first, candidate);
+                }
+            }
+        }
+        panic!("no collision found");
+    }
+
+    #[test]
+    fn test_new_bucket_collision_keeps_healthy_entry() {
+        let mut addrman = AddrMan::with_key([7; 32]);
+        let source = addr("1.2.3.4");
+        let (first, second) = colliding_pair(&addrman, &source);
+
+        assert!(addrman.add(&first, &source, NOW, NOW));
+        assert!(!addrman.add(&second, &source, NOW, NOW));
+        assert!(addrman.get(&first).is_some());
+        assert!(addrman.get(&second).is_none());
+
+        // Once the occupant keeps failing it gives up its slot
+        for _ in 0..MAX_RETRIES {
+            addrman.mark_attempt(&first, NOW - 3_600);
+        }
+        assert!(addrman.get(&first).unwrap().is_terrible(NOW));
+        assert!(addrman.add(&second, &source, NOW, NOW));
+        assert!(addrman.get(&first).is_none());
+        assert_eq!(addrman.len(), 1);
+    }
+
+    #[test]
+    fn test_tried_collision_moves_occupant_back_to_new() {
+        let mut addrman = AddrMan::with_key([9; 32]);
+        let mut seen: HashMap<usize, Multiaddr> = HashMap::new();
+        let (first, second) = (0..=255u8)
+            .flat_map(|a| (0..=255u8).map(move |b| addr(&format!("{}.{}.2.2", a, b))))
+            .find_map(|candidate| {
+                let slot = addrman.tried_slot(&candidate);
+                seen.insert(slot, candidate.clone())
+                    .map(|first| (first, candidate))
+            })
+            .unwrap();
+
+        addrman.mark_good(&first, NOW);
+        addrman.mark_good(&second, NOW);
+        assert!(addrman.get(&second).unwrap().tried);
+        // The displaced address keeps its history, back in the new table
+        let displaced = addrman.get(&first).unwrap();
+        assert!(!displaced.tried);
+        assert_eq!(displaced.last_success, NOW);
+        assert_eq!(addrman.tried_count(), 1);
+        assert_eq!(addrman.new_count(), 1);
+    }
+
+    #[test]
+    fn test_one_source_cannot_flood_the_new_table() {
+        let mut addrman = AddrMan::new();
+        let attacker = addr("6.6.6.6");
+        for a in 0..=255u8 {
+            for b in 0..40u8 {
+                addrman.add(&addr(&format!("{}.{}.0.1", a, b)), &attacker, NOW, NOW);
+            }
+        }
+        let buckets: HashSet<usize> = addrman
+            .new_table
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_some())
+            .map(|(index, _)| index / BUCKET_SIZE)
+            .collect();
+        assert!(buckets.len() as u64 <= NEW_BUCKETS_PER_SOURCE_GROUP);
+        assert!(addrman.len() <= NEW_BUCKETS_PER_SOURCE_GROUP as usize * BUCKET_SIZE);
+
+        // Honest sources elsewhere still get their addresses in
+        let admitted = (0..100u8)
+            .filter(|i| {
+                addrman.add(
+                    &addr(&format!("8.{}.4.4", i)),
+                    &addr(&format!("9.{}.9.9", i)),
+                    NOW,
+                    NOW,
+                )
+            })
+            .count();
+        assert!(admitted > 50);
+    }
+
+    #[test]
+    fn test_selection_and_getaddr() {
+        let mut addrman = AddrMan::new();
+        let source = addr("1.2.3.4");
+        for i in 0..100u8 {
+            addrman.add(&addr(&format!("10.{}.0.1", i)), &source, NOW, NOW);
+        }
+        addrman.mark_good(&addr("10.0.0.1"), NOW);
+
+        let exclude: HashSet<Multiaddr> = [addr("10.1.0.1")].into_iter().collect();
+        let selected = addrman.select_candidates(10, &exclude, NOW);
+        assert_eq!(selected.len(), 10);
+        assert!(!selected.contains(&addr("10.1.0.1")));
+        assert_eq!(selected.iter().collect::<HashSet<_>>().len(), 10);
+
+        let sample = addrman.get_addr(MAX_ADDR_PER_MESSAGE, NOW);
+        assert_eq!(
+            sample.len(),
+            (addrman.len() * GETADDR_PERCENT).div_ceil(100)
+        );
+        assert!(addrman.get_addr(5, NOW).len() <= 5);
+        assert!(AddrMan::new().get_addr(10, NOW).is_empty());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod address_book;
+pub mod addrman;
+
 use libp2p::{
     core::Multiaddr,
     identity::Keypair,
@@ -0,0 +1,854 @@
+//! Peer discovery through DNS seeds
+//!
+//! A seed is a hostname whose A/AAAA records point at reachable nodes. Plain
+//! address records carry no peer id and no authentication, so seeds may also
+//! publish signed peer lists as TXT records:
+//!
+//! ```text
+//! v=snseed1 exp=<unix seconds> peer=<peer_id>@<ip:port> [peer=...] sig=<hex>
+//! ```
+//!
+//! A record may be split across several TXT strings; they are joined before
+//! parsing. `sig` is an ed25519 signature by the seed operator over
+//! [`seed_list_message`], which binds the list to the seed hostname and its
+//! expiry so it can be neither moved to another seed nor replayed forever.
+//! When a seed is configured with an operator key, only lists that verify
+//! under it are used. Lists past their expiry are always rejected. If a seed
+//! publishes no usable list the node falls back to its A/AAAA records.
+//!
+//! Seeds are resolved at startup and again whenever outbound connections run
+//! short, at most once per `min_reresolve_interval`. With a SOCKS5 proxy
+//! configured, queries travel over TCP through the proxy so they don't leak
+//! to the local resolver.
+
+use crate::config::{DnsSeedConfig, NetworkConfig};
+use async_trait::async_trait;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+use trust_dns_resolver::proto::op::{Message, MessageType, OpCode, Query};
+use trust_dns_resolver::proto::rr::{Name, RData, RecordType};
+use trust_dns_resolver::TokioAsyncResolver;
+
+/// Version tag opening every signed seed list
+pub const SEED_LIST_VERSION: &str = "v=snseed1";
+
+/// Domain separator for seed list signatures
+const SIGNING_DOMAIN: &[u8] = b"supernova/dns-seed/v1";
+
+/// Peers taken from a single seed, so one seed can't flood the dial queue
+const MAX_PEERS_PER_SEED: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum DnsSeedError {
+    #[error("DNS lookup failed: {0}")]
+    Lookup(String),
+    #[error("SOCKS5 proxy error: {0}")]
+    Proxy(String),
+    #[error("Seed list is malformed: {0}")]
+    Malformed(String),
+    #[error("Seed list expired at {expires}")]
+    Expired { expires: u64 },
+    #[error("Seed list is not signed")]
+    MissingSignature,
+    #[error("Seed list signature does not verify under the operator key")]
+    BadSignature,
+    #[error("Invalid operator key for seed {0}")]
+    InvalidOperatorKey(String),
+}
+
+/// An address learned from a DNS seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeedPeer {
+    pub addr: Multiaddr,
+    /// Known only for peers from a signed list
+    pub peer_id: Option<PeerId>,
+    /// Hostname of the seed that returned the address
+    pub seed: String,
+}
+
+/// The bytes a seed operator signs for a list
+pub fn seed_list_message(host: &str, expires: u64, entries: &[String]) -> Vec<u8> {
+    let mut message = SIGNING_DOMAIN.to_vec();
+    message.push(b'\n');
+    message.extend_from_slice(host.to_ascii_lowercase().as_bytes());
+    message.push(b'\n');
+    message.extend_from_slice(expires.to_string().as_bytes());
+    for entry in entries {
+        message.push(b'\n');
+        message.extend_from_slice(entry.as_bytes());
+    }
+    message
+}
+
+/// Render a signed TXT seed list, for seed operators
+pub fn sign_seed_list(host: &str, expires: u64, entries: &[String], key: &SigningKey) -> String {
+    let signature = key.sign(&seed_list_message(host, expires, entries));
+    let mut record = format!("{} exp={}", SEED_LIST_VERSION, expires);
+    for entry in entries {
+        record.push_str(" peer=");
+        record.push_str(entry);
+    }
+    record.push_str(" sig=");
+    record.push_str(&hex::encode(signature.to_bytes()));
+    record
+}
+
+/// Parse a TXT seed list published by `host`, checking its expiry against
+/// `now` and, when `operator_key` is given, its signature
+pub fn parse_seed_list(
+    host: &str,
+    record: &str,
+    operator_key: Option<&VerifyingKey>,
+    now: u64,
+) -> Result<Vec<SeedPeer>, DnsSeedError> {
+    let mut fields = record.split_whitespace();
+    if fields.next() != Some(SEED_LIST_VERSION) {
+        return Err(DnsSeedError::Malformed(format!(
+            "does not start with {}",
+            SEED_LIST_VERSION
+        )));
+    }
+
+    let mut expires = None;
+    let mut entries = Vec::new();
+    let mut signature = None;
+    for field in fields {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| DnsSeedError::Malformed(format!("field '{}' has no value", field)))?;
+        match key {
+            "exp" => {
+                expires = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| DnsSeedError::Malformed(format!("bad expiry '{}'", value)))?,
+                )
+            }
+            "peer" => entries.push(value.to_string()),
+            "sig" => signature = Some(value),
+            // Unknown fields are left for future list versions
+            _ => {}
+        }
+    }
+    let expires = expires.ok_or_else(|| DnsSeedError::Malformed("missing exp".to_string()))?;
+
+    if let Some(key) = operator_key {
+        let signature = signature.ok_or(DnsSeedError::MissingSignature)?;
+        let signature = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or(DnsSeedError::BadSignature)?;
+        key.verify(&seed_list_message(host, expires, &entries), &signature)
+            .map_err(|_| DnsSeedError::BadSignature)?;
+    }
+    if expires <= now {
+        return Err(DnsSeedError::Expired { expires });
+    }
+
+    entries
+        .iter()
+        .map(|entry| {
+            let (peer_id, addr) = parse_entry(entry)?;
+            Ok(SeedPeer {
+                addr: socket_multiaddr(addr).with(Protocol::P2p(peer_id)),
+                peer_id: Some(peer_id),
+                seed: host.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A `peer_id@ip:port` list entry
+fn parse_entry(entry: &str) -> Result<(PeerId, SocketAddr), DnsSeedError> {
+    let (peer_id, addr) = entry
+        .split_once('@')
+        .ok_or_else(|| DnsSeedError::Malformed(format!("entry '{}' is not peer_id@addr", entry)))?;
+    let peer_id = peer_id
+        .parse::<PeerId>()
+        .map_err(|_| DnsSeedError::Malformed(format!("bad peer id in '{}'", entry)))?;
+    let addr = addr
+        .parse::<SocketAddr>()
+        .map_err(|_| DnsSeedError::Malformed(format!("bad address in '{}'", entry)))?;
+    Ok((peer_id, addr))
+}
+
+fn socket_multiaddr(addr: SocketAddr) -> Multiaddr {
+    Multiaddr::empty()
+        .with(Protocol::from(addr.ip()))
+        .with(Protocol::Tcp(addr.port()))
+}
+
+/// DNS lookups a seeder needs, so tests and proxies can stand in for the
+/// system resolver
+#[async_trait]
+pub trait SeedResolver: Send + Sync {
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, DnsSeedError>;
+
+    /// TXT records, each with its strings joined
+    async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, DnsSeedError>;
+}
+
+/// Resolves through the operating system's DNS configuration
+pub struct SystemResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl SystemResolver {
+    pub fn new() -> Result<Self, DnsSeedError> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| DnsSeedError::Lookup(e.to_string()))?;
+        Ok(Self { resolver })
+    }
+}
+
+#[async_trait]
+impl SeedResolver for SystemResolver {
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, DnsSeedError> {
+        let lookup = self
+            .resolver
+            .lookup_ip(host)
+            .await
+            .map_err(|e| DnsSeedError::Lookup(e.to_string()))?;
+        Ok(lookup.iter().collect())
+    }
+
+    async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, DnsSeedError> {
+        let lookup = self
+            .resolver
+            .txt_lookup(host)
+            .await
+            .map_err(|e| DnsSeedError::Lookup(e.to_string()))?;
+        Ok(lookup.iter().map(|txt| join_txt(txt.txt_data())).collect())
+    }
+}
+
+fn join_txt(strings: &[Box<[u8]>]) -> String {
+    strings
+        .iter()
+        .map(|s| String::from_utf8_lossy(s))
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// Sends DNS queries over TCP to `nameserver` through a SOCKS5 proxy
+pub struct Socks5Resolver {
+    proxy: SocketAddr,
+    nameserver: SocketAddr,
+}
+
+impl Socks5Resolver {
+    pub fn new(proxy: SocketAddr, nameserver: SocketAddr) -> Self {
+        Self { proxy, nameserver }
+    }
+
+    async fn query(&self, host: &str, record_type: RecordType) -> Result<Vec<RData>, DnsSeedError> {
+        let name = Name::from_ascii(host).map_err(|e| DnsSeedError::Lookup(e.to_string()))?;
+        let id = rand::random::<u16>();
+        let mut request = Message::new();
+        request
+            .set_id(id)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true)
+            .add_query(Query::query(name, record_type));
+        let request = request
+            .to_vec()
+            .map_err(|e| DnsSeedError::Lookup(e.to_string()))?;
+        let request_len = u16::try_from(request.len())
+            .map_err(|_| DnsSeedError::Lookup("query too large".to_string()))?;
+
+        let mut stream = socks5_connect(self.proxy, self.nameserver).await?;
+        let io = |e: std::io::Error| DnsSeedError::Lookup(e.to_string());
+        // DNS over TCP prefixes each message with its length
+        stream
+            .write_all(&request_len.to_be_bytes())
+            .await
+            .map_err(io)?;
+        stream.write_all(&request).await.map_err(io)?;
+        let mut len = [0u8; 2];
+        stream.read_exact(&mut len).await.map_err(io)?;
+        let mut response = vec![0u8; u16::from_be_bytes(len) as usize];
+        stream.read_exact(&mut response).await.map_err(io)?;
+
+        let response =
+            Message::from_vec(&response).map_err(|e| DnsSeedError::Lookup(e.to_string()))?;
+        if response.id() != id {
+            return Err(DnsSeedError::Lookup("response id mismatch".to_string()));
+        }
+        Ok(response
+            .answers()
+            .iter()
+            .filter_map(|record| record.data().cloned())
+            .collect())
+    }
+}
+
+#[async_trait]
+impl SeedResolver for Socks5Resolver {
+    async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, DnsSeedError> {
+        let mut ips = Vec::new();
+        for record_type in [RecordType::A, RecordType::AAAA] {
+            for data in self.query(host, record_type).await? {
+                match data {
+                    RData::A(ip) => ips.push(IpAddr::V4(ip)),
+                    RData::AAAA(ip) => ips.push(IpAddr::V6(ip)),
+                    _ => {}
+                }
+            }
+        }
+        Ok(ips)
+    }
+
+    async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, DnsSeedError> {
+        Ok(self
+            .query(host, RecordType::TXT)
+            .await?
+            .into_iter()
+            .filter_map(|data| match data {
+                RData::TXT(txt) => Some(join_txt(txt.txt_data())),
+                _ => None,
+            })
+            .collect())
+    }
+}
+
+/// Open a TCP stream to `target` through a SOCKS5 proxy without
+/// authentication (RFC 1928)
+async fn socks5_connect(proxy: SocketAddr, target: SocketAddr) -> Result<TcpStream, DnsSeedError> {
+    let io = |e: std::io::Error| DnsSeedError::Proxy(e.to_string());
+    let mut stream = TcpStream::connect(proxy).await.map_err(io)?;
+
+    stream.write_all(&[5, 1, 0]).await.map_err(io)?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).await.map_err(io)?;
+    if choice != [5, 0] {
+        return Err(DnsSeedError::Proxy(
+            "proxy requires an authentication method we don't support".to_string(),
+        ));
+    }
+
+    let mut request = vec![5, 1, 0];
+    match target.ip() {
+        IpAddr::V4(ip) => {
+            request.push(1);
+            request.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            request.push(4);
+            request.extend_from_slice(&ip.octets());
+        }
+    }
+    request.extend_from_slice(&target.port().to_be_bytes());
+    stream.write_all(&request).await.map_err(io)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).await.map_err(io)?;
+    if reply[1] != 0 {
+        return Err(DnsSeedError::Proxy(format!(
+            "connect to {} refused with code {}",
+            target, reply[1]
+        )));
+    }
+    // Skip the bound address the proxy reports
+    let bound_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await.map_err(io)?;
+            len[0] as usize
+        }
+        other => {
+            return Err(DnsSeedError::Proxy(format!(
+                "unknown address type {}",
+                other
+            )));
+        }
+    };
+    let mut bound = vec![0u8; bound_len + 2];
+    stream.read_exact(&mut bound).await.map_err(io)?;
+    Ok(stream)
+}
+
+/// A configured seed
+#[derive(Debug, Clone)]
+pub struct Seed {
+    pub host: String,
+    pub port: u16,
+    pub operator_key: Option<VerifyingKey>,
+}
+
+impl TryFrom<&DnsSeedConfig> for Seed {
+    type Error = DnsSeedError;
+
+    fn try_from(config: &DnsSeedConfig) -> Result<Self, Self::Error> {
+        let operator_key = config
+            .operator_key
+            .as_ref()
+            .map(|key| {
+                hex::decode(key)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+                    .ok_or_else(|| DnsSeedError::InvalidOperatorKey(config.host.clone()))
+            })
+            .transpose()?;
+        Ok(Self {
+            host: config.host.clone(),
+            port: config.port,
+            operator_key,
+        })
+    }
+}
+
+/// Resolves the configured seeds, rate-limiting repeat resolutions
+pub struct DnsSeeder {
+    seeds: Vec<Seed>,
+    resolver: Arc<dyn SeedResolver>,
+    timeout: Duration,
+    min_reresolve_interval: Duration,
+    /// Outbound connections below which the node counts as starved
+    min_outbound: usize,
+    last_resolution: Option<Instant>,
+}
+
+impl DnsSeeder {
+    pub fn new(
+        seeds: Vec<Seed>,
+        resolver: Arc<dyn SeedResolver>,
+        timeout: Duration,
+        min_reresolve_interval: Duration,
+        min_outbound: usize,
+    ) -> Self {
+        Self {
+            seeds,
+            resolver,
+            timeout,
+            min_reresolve_interval,
+            min_outbound,
+            last_resolution: None,
+        }
+    }
+
+    /// Build the seeder `config` describes, or `None` when no seeds are
+    /// configured. Lookups go through `config.socks_proxy` when it is set.
+    pub fn from_config(config: &NetworkConfig) -> Result<Option<Self>, DnsSeedError> {
+        if config.dns_seeds.seeds.is_empty() {
+            return Ok(None);
+        }
+        let seeds = config
+            .dns_seeds
+            .seeds
+            .iter()
+            .map(Seed::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let resolver: Arc<dyn SeedResolver> = match config.socks_proxy {
+            Some(proxy) => Arc::new(Socks5Resolver::new(
+                proxy,
+                config.dns_seeds.proxy_nameserver,
+            )),
+            None => Arc::new(SystemResolver::new()?),
+        };
+        Ok(Some(Self::new(
+            seeds,
+            resolver,
+            config.dns_seeds.timeout,
+            config.dns_seeds.min_reresolve_interval,
+            config.min_outbound_connections,
+        )))
+    }
+
+    /// Whether seeds should be resolved: always the first time, afterwards
+    /// only while starved and once the re-resolve interval has passed
+    pub fn due(&self, outbound: usize, now: Instant) -> bool {
+        match self.last_resolution {
+            None => true,
+            Some(last) => {
+                outbound < self.min_outbound
+                    && now.duration_since(last) >= self.min_reresolve_interval
+            }
+        }
+    }
+
+    /// Resolve the seeds if [`due`](Self::due)
+    pub async fn resolve_if_due(&mut self, outbound: usize) -> Option<Vec<SeedPeer>> {
+        if !self.due(outbound, Instant::now()) {
+            return None;
+        }
+        Some(self.resolve().await)
+    }
+
+    /// Resolve every seed concurrently, each bounded by the timeout, and
+    /// return the distinct addresses found
+    pub async fn resolve(&mut self) -> Vec<SeedPeer> {
+        self.last_resolution = Some(Instant::now());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let resolver = &*self.resolver;
+        let timeout = self.timeout;
+        let lookups = self.seeds.iter().map(|seed| async move {
+            match tokio::time::timeout(timeout, resolve_seed(resolver, seed, now)).await {
+                Ok(peers) => peers,
+                Err(_) => {
+                    warn!("DNS seed {} timed out", seed.host);
+                    Vec::new()
+                }
+            }
+        });
+        let results = futures::future::join_all(lookups).await;
+
+        let mut seen = HashSet::new();
+        let peers: Vec<SeedPeer> = results
+            .into_iter()
+            .flatten()
+            .filter(|peer| seen.insert(peer.addr.clone()))
+            .collect();
+        info!(
+            "Resolved {} peer addresses from {} DNS seeds",
+            peers.len(),
+            self.seeds.len()
+        );
+        peers
+    }
+}
+
+/// Peers from one seed: its valid signed lists, or failing those its
+/// address records
+async fn resolve_seed(resolver: &dyn SeedResolver, seed: &Seed, now: u64) -> Vec<SeedPeer> {
+    let mut peers = Vec::new();
+    match resolver.lookup_txt(&seed.host).await {
+        Ok(records) => {
+            for record in records.iter().filter(|r| r.starts_with(SEED_LIST_VERSION)) {
+                match parse_seed_list(&seed.host, record, seed.operator_key.as_ref(), now) {
+                    Ok(list) => peers.extend(list),
+                    Err(e) => warn!("Ignoring seed list from {}: {}", seed.host, e),
+                }
+            }
+        }
+        Err(e) => debug!("No TXT seed list from {}: {}", seed.host, e),
+    }
+
+    if peers.is_empty() {
+        match resolver.lookup_ip(&seed.host).await {
+            Ok(ips) => peers.extend(ips.into_iter().map(|ip| SeedPeer {
+                addr: socket_multiaddr(SocketAddr::new(ip, seed.port)),
+                peer_id: None,
+                seed: seed.host.clone(),
+            })),
+            Err(e) => warn!("DNS seed {} failed: {}", seed.host, e),
+        }
+    }
+    peers.truncate(MAX_PEERS_PER_SEED);
+    peers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const HOST: &str = "seed.example.org";
+    const NOW: u64 = 1_700_000_000;
+
+    #[derive(Default)]
+    struct MockResolver {
+        txt: HashMap<String, Vec<String>>,
+        ips: HashMap<String, Vec<IpAddr>>,
+        lookups: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl SeedResolver for MockResolver {
+        async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, DnsSeedError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.ips
+                .get(host)
+                .cloned()
+                .ok_or_else(|| DnsSeedError::Lookup("NXDOMAIN".to_string()))
+        }
+
+        async fn lookup_txt(&self, host: &str) -> Result<Vec<String>, DnsSeedError> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            self.txt
+                .get(host)
+                .cloned()
+                .ok_or_else(|| DnsSeedError::Lookup("NXDOMAIN".to_string()))
+        }
+    }
+
+    fn operator() -> SigningKey {
+        SigningKey::from_bytes(&[5u8; 32])
+    }
+
+    fn entries() -> (Vec<String>, Vec<PeerId>) {
+        let ids = vec![PeerId::random(), PeerId::random()];
+        let entries = vec![
+            format!("{}@203.0.113.5:8333", ids[0]),
+            format!("{}@[2001:db8::7]:9333", ids[1]),
+        ];
+        (entries, ids)
+    }
+
+    fn seed(operator_key: Option<VerifyingKey>) -> Seed {
+        Seed {
+            host: HOST.to_string(),
+            port: 8333,
+            operator_key,
+        }
+    }
+
+    #[test]
+    fn parses_and_verifies_signed_list() {
+        let (entries, ids) = entries();
+        let record = sign_seed_list(HOST, NOW + 3600, &entries, &operator());
+        let key = operator().verifying_key();
+
+        let peers = parse_seed_list(HOST, &record, Some(&key), NOW).unwrap();
+        assert_eq!(
+            peers,
+            vec![
+                SeedPeer {
+                    addr: format!("/ip4/203.0.113.5/tcp/8333/p2p/{}", ids[0])
+                        .parse()
+                        .unwrap(),
+                    peer_id: Some(ids[0]),
+                    seed: HOST.to_string(),
+                },
+                SeedPeer {
+                    addr: format!("/ip6/2001:db8::7/tcp/9333/p2p/{}", ids[1])
+                        .parse()
+                        .unwrap(),
+                    peer_id: Some(ids[1]),
+                    seed: HOST.to_string(),
+                },
+            ]
+        );
+
+        // Without an operator key the list is taken as published
+        assert_eq!(parse_seed_list(HOST, &record, None, NOW).unwrap(), peers);
+    }
+
+    #[test]
+    fn rejects_tampered_misplaced_or_unsigned_lists() {
+        let (entries, _) = entries();
+        let key = operator().verifying_key();
+        let record = sign_seed_list(HOST, NOW + 3600, &entries, &operator());
+
+        // An injected peer breaks the signature
+        let injected = record.replacen(
+            " sig=",
+            &format!(" peer={}@198.51.100.1:8333 sig=", PeerId::random()),
+            1,
+        );
+        assert!(matches!(
+            parse_seed_list(HOST, &injected, Some(&key), NOW),
+            Err(DnsSeedError::BadSignature)
+        ));
+
+        // A list signed for another seed doesn't verify here
+        let elsewhere = sign_seed_list("other.example.org", NOW + 3600, &entries, &operator());
+        assert!(matches!(
+            parse_seed_list(HOST, &elsewhere, Some(&key), NOW),
+            Err(DnsSeedError::BadSignature)
+        ));
+
+        // Nor does one signed by someone else
+        let forged = sign_seed_list(
+            HOST,
+            NOW + 3600,
+            &entries,
+            &SigningKey::from_bytes(&[6u8; 32]),
+        );
+        assert!(matches!(
+            parse_seed_list(HOST, &forged, Some(&key), NOW),
+            Err(DnsSeedError::BadSignature)
+        ));
+
+        let unsigned = record.split(" sig=").next().unwrap().to_string();
+        assert!(matches!(
+            parse_seed_list(HOST, &unsigned, Some(&key), NOW),
+            Err(DnsSeedError::MissingSignature)
+        ));
+
+        for malformed in [
+            "v=snseed2 exp=1".to_string(),
+            format!("{} peer=x@1.2.3.4:1", SEED_LIST_VERSION),
+            format!("{} exp=soon", SEED_LIST_VERSION),
+            format!("{} exp={} peer=1.2.3.4:1", SEED_LIST_VERSION, NOW + 1),
+        ] {
+            assert!(matches!(
+                parse_seed_list(HOST, &malformed, None, NOW),
+                Err(DnsSeedError::Malformed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_expired_list() {
+        let (entries, _) = entries();
+        let key = operator().verifying_key();
+        let record = sign_seed_list(HOST, NOW, &entries, &operator());
+        assert!(matches!(
+            parse_seed_list(HOST, &record, Some(&key), NOW),
+            Err(DnsSeedError::Expired { expires: NOW })
+        ));
+        assert!(matches!(
+            parse_seed_list(HOST, &record, None, NOW + 1),
+            Err(DnsSeedError::Expired { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn uses_valid_lists_and_skips_bad_ones() {
+        let (entries, ids) = entries();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut resolver = MockResolver::default();
+        resolver.txt.insert(
+            HOST.to_string(),
+            vec![
+                "google-site-verification=abc".to_string(),
+                sign_seed_list(HOST, now - 1, &entries[..1], &operator()),
+                sign_seed_list(HOST, now + 3600, &entries[1..], &operator()),
+            ],
+        );
+        resolver
+            .ips
+            .insert(HOST.to_string(), vec!["192.0.2.1".parse().unwrap()]);
+
+        let peers = resolve_seed(&resolver, &seed(Some(operator().verifying_key())), now).await;
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].peer_id, Some(ids[1]));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_address_records() {
+        let (entries, _) = entries();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut resolver = MockResolver::default();
+        // Only an expired list is published
+        resolver.txt.insert(
+            HOST.to_string(),
+            vec![sign_seed_list(HOST, now - 1, &entries, &operator())],
+        );
+        resolver.ips.insert(
+            HOST.to_string(),
+            vec!["192.0.2.1".parse().unwrap(), "2001:db8::1".parse().unwrap()],
+        );
+
+        let peers = resolve_seed(&resolver, &seed(None), now).await;
+        let addrs: Vec<String> = peers.iter().map(|p| p.addr.to_string()).collect();
+        assert_eq!(
+            addrs,
+            vec!["/ip4/192.0.2.1/tcp/8333", "/ip6/2001:db8::1/tcp/8333"]
+        );
+        assert!(peers.iter().all(|p| p.peer_id.is_none() && p.seed == HOST));
+
+        // A seed with no TXT records at all behaves the same
+        resolver.txt.clear();
+        assert_eq!(resolve_seed(&resolver, &seed(None), now).await, peers);
+    }
+
+    #[tokio::test]
+    async fn re_resolves_only_when_starved_and_rate_limited() {
+        let mut resolver = MockResolver::default();
+        resolver
+            .ips
+            .insert(HOST.to_string(), vec!["192.0.2.1".parse().unwrap()]);
+        let resolver = Arc::new(resolver);
+        let interval = Duration::from_secs(600);
+        let mut seeder = DnsSeeder::new(
+            vec![seed(None)],
+            resolver.clone(),
+            Duration::from_secs(5),
+            interval,
+            8,
+        );
+
+        // Startup resolves regardless of connection count
+        let peers = seeder.resolve_if_due(20).await.unwrap();
+        assert_eq!(peers.len(), 1);
+        let lookups = resolver.lookups.load(Ordering::SeqCst);
+
+        // Starved again right away: rate-limited
+        assert!(seeder.resolve_if_due(0).await.is_none());
+        assert_eq!(resolver.lookups.load(Ordering::SeqCst), lookups);
+
+        let later = Instant::now() + interval;
+        assert!(seeder.due(3, later));
+        assert!(!seeder.due(8, later), "enough outbound peers: no need");
+
+        // Once the interval has passed, starvation triggers a new lookup
+        seeder.last_resolution = Some(Instant::now() - interval);
+        assert!(seeder.resolve_if_due(3).await.is_some());
+        assert!(resolver.lookups.load(Ordering::SeqCst) > lookups);
+    }
+
+    #[tokio::test]
+    async fn slow_seed_times_out_without_losing_others() {
+        struct SlowResolver;
+
+        #[async_trait]
+        impl SeedResolver for SlowResolver {
+            async fn lookup_ip(&self, host: &str) -> Result<Vec<IpAddr>, DnsSeedError> {
+                if host == "slow.example.org" {
+                    tokio::time::sleep(Duration::from_secs(3600)).await;
+                }
+                Ok(vec!["192.0.2.9".parse().unwrap()])
+            }
+
+            async fn lookup_txt(&self, _host: &str) -> Result<Vec<String>, DnsSeedError> {
+                Ok(Vec::new())
+            }
+        }
+
+        let slow = Seed {
+            host: "slow.example.org".to_string(),
+            ..seed(None)
+        };
+        let mut seeder = DnsSeeder::new(
+            vec![slow, seed(None)],
+            Arc::new(SlowResolver),
+            Duration::from_millis(50),
+            Duration::from_secs(600),
+            8,
+        );
+        let peers = seeder.resolve().await;
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].seed, HOST);
+    }
+
+    #[test]
+    fn seed_config_requires_a_valid_operator_key() {
+        let mut config = DnsSeedConfig {
+            host: HOST.to_string(),
+            operator_key: Some(hex::encode(operator().verifying_key().to_bytes())),
+            port: 8333,
+        };
+        assert!(Seed::try_from(&config).unwrap().operator_key.is_some());
+        config.operator_key = Some("abcd".to_string());
+        assert!(matches!(
+            Seed::try_from(&config),
+            Err(DnsSeedError::InvalidOperatorKey(_))
+        ));
+    }
+}
@@ -0,0 +1,744 @@
+//! Encrypted peer transport.
+//!
+//! Messages addressed to one peer are published on gossipsub's `messages`
+//! topic, so every node relaying them can read them. Peers that both
+//! advertise `NODE_ENCRYPTED` in the version handshake agree a session key
+//! with ML-KEM-768 and from then on exchange every direct message as a
+//! ChaCha20-Poly1305 [`EncryptedFrame`]:
+//!
+//! 1. The peer with the lower peer id sends a [`KemOffer`] carrying an
+//!    ephemeral ML-KEM public key.
+//! 2. The other encapsulates a shared secret to it and answers with a
+//!    [`KemAccept`] carrying the ciphertext.
+//!
+//! Each side signs its half of the transcript with its libp2p identity key,
+//! binding the session to the peer ids. The transcript covers the services
+//! both sides advertised, so a `Version` altered in transit fails the
+//! signature check. Once both sides advertised encryption, a plaintext
+//! direct message from the peer is a downgrade attempt. Blocks and
+//! transactions gossiped to the whole network stay plaintext.
+
+use crate::network::protocol::Message;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use libp2p::identity::{Keypair, PublicKey};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use supernova_core::crypto::kem::{decapsulate, encapsulate, KemError, KemKeyPair};
+use thiserror::Error;
+
+/// Domain separator for transcripts and session keys
+const TRANSCRIPT_DOMAIN: &[u8] = b"supernova/encrypted-transport/v1";
+
+/// Direct messages held back while a session is negotiated; later ones are
+/// dropped
+pub const MAX_PENDING_MESSAGES: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("peer advertised encryption but sent a plaintext message")]
+    Downgrade,
+    #[error("unexpected {0}")]
+    Unexpected(&'static str),
+    #[error("handshake identity key is malformed: {0}")]
+    InvalidIdentityKey(String),
+    #[error("handshake identity key does not belong to the peer")]
+    IdentityMismatch,
+    #[error("handshake transcript signature does not verify")]
+    BadSignature,
+    #[error("signing the handshake failed: {0}")]
+    Signing(String),
+    #[error("key encapsulation failed: {0}")]
+    Kem(#[from] KemError),
+    #[error("frame {0} could not be sealed")]
+    Sealing(u64),
+    #[error("frame {0} failed authentication")]
+    Decryption(u64),
+    #[error("frame {sequence} replayed; next expected is {expected}")]
+    Replay { sequence: u64, expected: u64 },
+    #[error("message could not be encoded or decoded: {0}")]
+    Codec(#[from] bincode::Error),
+}
+
+/// How direct messages to and from a peer travel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptionStatus {
+    #[default]
+    Plaintext,
+    /// Waiting for the version or key exchange; direct messages are held back
+    Negotiating,
+    Encrypted,
+}
+
+impl fmt::Display for EncryptionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EncryptionStatus::Plaintext => "plaintext",
+            EncryptionStatus::Negotiating => "negotiating",
+            EncryptionStatus::Encrypted => "encrypted",
+        })
+    }
+}
+
+/// Opens the key exchange with an ephemeral ML-KEM-768 public key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KemOffer {
+    pub kem_public_key: Vec<u8>,
+    /// Protobuf-encoded libp2p public key of the sender
+    pub identity_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// Completes the key exchange with a secret encapsulated to the offer's key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KemAccept {
+    pub ciphertext: Vec<u8>,
+    /// Protobuf-encoded libp2p public key of the sender
+    pub identity_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// A direct message sealed under the session key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedFrame {
+    /// Increases with every frame in one direction; the AEAD nonce
+    pub sequence: u64,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Both ends of a session and the services each advertised in its
+/// `Version`, as seen locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandshakeContext {
+    pub local: PeerId,
+    pub remote: PeerId,
+    pub local_services: u64,
+    pub remote_services: u64,
+}
+
+impl HandshakeContext {
+    /// The peer with the lower id sends the offer
+    pub fn initiates(&self) -> bool {
+        self.local.to_bytes() < self.remote.to_bytes()
+    }
+
+    /// Transcript prefix for `stage`, the same on both sides
+    fn header(&self, stage: &[u8]) -> Vec<u8> {
+        let local = (self.local, self.local_services);
+        let remote = (self.remote, self.remote_services);
+        let (initiator, responder) = if self.initiates() {
+            (local, remote)
+        } else {
+            (remote, local)
+        };
+        let mut header = TRANSCRIPT_DOMAIN.to_vec();
+        header.extend_from_slice(stage);
+        for (peer, services) in [initiator, responder] {
+            let id = peer.to_bytes();
+            header.extend_from_slice(&(id.len() as u32).to_le_bytes());
+            header.extend_from_slice(&id);
+            header.extend_from_slice(&services.to_le_bytes());
+        }
+        header
+    }
+
+    fn offer_transcript(&self, kem_public_key: &[u8]) -> Vec<u8> {
+        let mut transcript = self.header(b"/offer");
+        transcript.extend_from_slice(kem_public_key);
+        transcript
+    }
+
+    fn accept_transcript(&self, kem_public_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+        let mut transcript = self.header(b"/accept");
+        transcript.extend_from_slice(&Sha256::digest(kem_public_key));
+        transcript.extend_from_slice(ciphertext);
+        transcript
+    }
+}
+
+/// Directional keys and frame counters of an established session
+#[derive(Clone)]
+pub struct SecureSession {
+    send: ChaCha20Poly1305,
+    receive: ChaCha20Poly1305,
+    next_send: u64,
+    next_receive: u64,
+}
+
+impl fmt::Debug for SecureSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureSession")
+            .field("next_send", &self.next_send)
+            .field("next_receive", &self.next_receive)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecureSession {
+    fn new(shared_secret: &[u8], transcript: &[u8], initiator: bool) -> Self {
+        let key = |direction: &[u8]| {
+            let digest = Sha256::new()
+                .chain_update(TRANSCRIPT_DOMAIN)
+                .chain_update(direction)
+                .chain_update(shared_secret)
+                .chain_update(transcript)
+                .finalize();
+            ChaCha20Poly1305::new(Key::from_slice(&digest))
+        };
+        let (outgoing, incoming) = (key(b"/initiator"), key(b"/responder"));
+        let (send, receive) = if initiator {
+            (outgoing, incoming)
+        } else {
+            (incoming, outgoing)
+        };
+        Self {
+            send,
+            receive,
+            next_send: 0,
+            next_receive: 0,
+        }
+    }
+
+    fn nonce(sequence: u64) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&sequence.to_le_bytes());
+        Nonce::clone_from_slice(&nonce)
+    }
+
+    pub fn seal(&mut self, message: &Message) -> Result<EncryptedFrame, EncryptionError> {
+        let plaintext = bincode::serialize(message)?;
+        let sequence = self.next_send;
+        let ciphertext = self
+            .send
+            .encrypt(&Self::nonce(sequence), plaintext.as_slice())
+            .map_err(|_| EncryptionError::Sealing(sequence))?;
+        self.next_send += 1;
+        Ok(EncryptedFrame {
+            sequence,
+            ciphertext,
+        })
+    }
+
+    /// Open `frame`. Frames may be lost on the way but never replayed.
+    pub fn open(&mut self, frame: &EncryptedFrame) -> Result<Message, EncryptionError> {
+        if frame.sequence < self.next_receive {
+            return Err(EncryptionError::Replay {
+                sequence: frame.sequence,
+                expected: self.next_receive,
+            });
+        }
+        let plaintext = self
+            .receive
+            .decrypt(&Self::nonce(frame.sequence), frame.ciphertext.as_slice())
+            .map_err(|_| EncryptionError::Decryption(frame.sequence))?;
+        self.next_receive = frame.sequence.saturating_add(1);
+        Ok(bincode::deserialize(&plaintext)?)
+    }
+}
+
+/// Encryption state of one peer connection
+#[derive(Debug, Clone, Default)]
+pub struct PeerEncryption {
+    state: SessionState,
+    /// Direct messages held back until negotiation settles
+    pending: Vec<Message>,
+}
+
+#[derive(Debug, Clone, Default)]
+enum SessionState {
+    /// Neither side requires encryption
+    #[default]
+    Plaintext,
+    /// We offer encryption and wait for the peer's `Version`
+    AwaitingVersion,
+    /// Our offer is out
+    Offered {
+        context: HandshakeContext,
+        kem: KemKeyPair,
+        transcript: Vec<u8>,
+    },
+    /// Waiting for the peer's offer
+    AwaitingOffer(HandshakeContext),
+    Established(SecureSession),
+}
+
+impl PeerEncryption {
+    /// State for a new connection; `offered` when we advertise encryption
+    pub fn new(offered: bool) -> Self {
+        Self {
+            state: if offered {
+                SessionState::AwaitingVersion
+            } else {
+                SessionState::Plaintext
+            },
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn status(&self) -> EncryptionStatus {
+        match self.state {
+            SessionState::Plaintext => EncryptionStatus::Plaintext,
+            SessionState::Established(_) => EncryptionStatus::Encrypted,
+            _ => EncryptionStatus::Negotiating,
+        }
+    }
+
+    /// Whether both sides advertised encryption, so plaintext is refused
+    fn required(&self) -> bool {
+        matches!(
+            self.state,
+            SessionState::Offered { .. }
+                | SessionState::AwaitingOffer(_)
+                | SessionState::Established(_)
+        )
+    }
+
+    /// Fall back to plaintext after a `Version` without encryption.
+    /// Returns the messages held back so far.
+    pub fn decline(&mut self) -> Result<Vec<Message>, EncryptionError> {
+        if self.required() {
+            return Err(EncryptionError::Downgrade);
+        }
+        self.state = SessionState::Plaintext;
+        Ok(std::mem::take(&mut self.pending))
+    }
+
+    /// Begin the key exchange once both `Version`s advertised encryption.
+    /// Returns the offer to send when this side initiates.
+    pub fn start(
+        &mut self,
+        identity: &Keypair,
+        context: HandshakeContext,
+    ) -> Result<Option<KemOffer>, EncryptionError> {
+        if self.required() {
+            return Err(EncryptionError::Unexpected("second version handshake"));
+        }
+        if !context.initiates() {
+            self.state = SessionState::AwaitingOffer(context);
+            return Ok(None);
+        }
+        let kem = KemKeyPair::generate()?;
+        let transcript = context.offer_transcript(&kem.public_key);
+        let offer = KemOffer {
+            kem_public_key: kem.public_key.clone(),
+            identity_key: identity.public().encode_protobuf(),
+            signature: sign(identity, &transcript)?,
+        };
+        self.state = SessionState::Offered {
+            context,
+            kem,
+            transcript,
+        };
+        Ok(Some(offer))
+    }
+
+    /// Answer the peer's offer. The session is up once the accept is sent;
+    /// the held back messages follow it.
+    pub fn accept(
+        &mut self,
+        identity: &Keypair,
+        offer: &KemOffer,
+    ) -> Result<(KemAccept, Vec<Message>), EncryptionError> {
+        let context = match &self.state {
+            SessionState::AwaitingOffer(context) => *context,
+            _ => return Err(EncryptionError::Unexpected("key offer")),
+        };
+        let offer_transcript = context.offer_transcript(&offer.kem_public_key);
+        verify_signed(
+            &context.remote,
+            &offer.identity_key,
+            &offer_transcript,
+            &offer.signature,
+        )?;
+        let (ciphertext, mut shared_secret) = encapsulate(&offer.kem_public_key)?;
+        let transcript = context.accept_transcript(&offer.kem_public_key, &ciphertext);
+        let accept = KemAccept {
+            ciphertext,
+            identity_key: identity.public().encode_protobuf(),
+            signature: sign(identity, &transcript)?,
+        };
+        let session = SecureSession::new(
+            &shared_secret,
+            &[offer_transcript, transcript].concat(),
+            false,
+        );
+        shared_secret.fill(0);
+        self.state = SessionState::Established(session);
+        Ok((accept, std::mem::take(&mut self.pending)))
+    }
+
+    /// Finish our offer with the peer's accept. Returns the held back
+    /// messages.
+    pub fn complete(&mut self, accept: &KemAccept) -> Result<Vec<Message>, EncryptionError> {
+        let SessionState::Offered {
+            context,
+            kem,
+            transcript: offer_transcript,
+        } = &self.state
+        else {
+            return Err(EncryptionError::Unexpected("key accept"));
+        };
+        let transcript = context.accept_transcript(&kem.public_key, &accept.ciphertext);
+        verify_signed(
+            &context.remote,
+            &accept.identity_key,
+            &transcript,
+            &accept.signature,
+        )?;
+        let mut shared_secret = decapsulate(&kem.secret_key, &accept.ciphertext)?;
+        let session = SecureSession::new(
+            &shared_secret,
+            &[offer_transcript.as_slice(), &transcript].concat(),
+            true,
+        );
+        shared_secret.fill(0);
+        self.state = SessionState::Established(session);
+        Ok(std::mem::take(&mut self.pending))
+    }
+
+    /// Prepare a direct message for the peer: sealed once the session is
+    /// up, held back while negotiating, unchanged otherwise. `None` when
+    /// nothing is to be sent now.
+    pub fn outbound(&mut self, message: Message) -> Result<Option<Message>, EncryptionError> {
+        if is_handshake(&message) || matches!(message, Message::Encrypted(_)) {
+            return Ok(Some(message));
+        }
+        match &mut self.state {
+            SessionState::Plaintext => Ok(Some(message)),
+            SessionState::Established(session) => {
+                Ok(Some(Message::Encrypted(session.seal(&message)?)))
+            }
+            _ => {
+                if self.pending.len() < MAX_PENDING_MESSAGES {
+                    self.pending.push(message);
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Check a direct message from the peer, opening it if sealed
+    pub fn inbound(&mut self, message: Message) -> Result<Message, EncryptionError> {
+        let required = self.required();
+        match (&mut self.state, message) {
+            (SessionState::Established(session), Message::Encrypted(frame)) => {
+                let opened = session.open(&frame)?;
+                if is_handshake(&opened) || matches!(opened, Message::Encrypted(_)) {
+                    return Err(EncryptionError::Unexpected(
+                        "handshake inside an encrypted frame",
+                    ));
+                }
+                Ok(opened)
+            }
+            (_, Message::Encrypted(_)) => Err(EncryptionError::Unexpected(
+                "encrypted frame before the session is up",
+            )),
+            (_, message) if is_handshake(&message) || !required => Ok(message),
+            _ => Err(EncryptionError::Downgrade),
+        }
+    }
+}
+
+/// Messages exchanged in plaintext to set up the session
+fn is_handshake(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::Version(_) | Message::Verack | Message::KemOffer(_) | Message::KemAccept(_)
+    )
+}
+
+fn sign(identity: &Keypair, transcript: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    identity
+        .sign(transcript)
+        .map_err(|e| EncryptionError::Signing(e.to_string()))
+}
+
+/// Check that `identity_key` is `peer`'s and signed `transcript`
+fn verify_signed(
+    peer: &PeerId,
+    identity_key: &[u8],
+    transcript: &[u8],
+    signature: &[u8],
+) -> Result<(), EncryptionError> {
+    let key = PublicKey::try_decode_protobuf(identity_key)
+        .map_err(|e| EncryptionError::InvalidIdentityKey(e.to_string()))?;
+    if key.to_peer_id() != *peer {
+        return Err(EncryptionError::IdentityMismatch);
+    }
+    if !key.verify(transcript, signature) {
+        return Err(EncryptionError::BadSignature);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::handshake::{NODE_BLOOM, NODE_ENCRYPTED, NODE_NETWORK};
+
+    const SERVICES: u64 = NODE_NETWORK | NODE_BLOOM | NODE_ENCRYPTED;
+
+    struct Node {
+        identity: Keypair,
+        encryption: PeerEncryption,
+    }
+
+    impl Node {
+        fn new() -> Self {
+            Self {
+                identity: Keypair::generate_ed25519(),
+                encryption: PeerEncryption::new(true),
+            }
+        }
+
+        fn peer_id(&self) -> PeerId {
+            self.identity.public().to_peer_id()
+        }
+
+        fn context(&self, remote: &Node) -> HandshakeContext {
+            HandshakeContext {
+                local: self.peer_id(),
+                remote: remote.peer_id(),
+                local_services: SERVICES,
+                remote_services: SERVICES,
+            }
+        }
+    }
+
+    /// Two nodes, initiator first
+    fn pair() -> (Node, Node) {
+        let (a, b) = (Node::new(), Node::new());
+        if a.context(&b).initiates() {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    /// Run the key exchange, returning the messages each side held back
+    fn establish(initiator: &mut Node, responder: &mut Node) -> (Vec<Message>, Vec<Message>) {
+        let context = responder.context(initiator);
+        assert_eq!(
+            responder
+                .encryption
+                .start(&responder.identity, context)
+                .unwrap(),
+            None
+        );
+        let context = initiator.context(responder);
+        let offer = initiator
+            .encryption
+            .start(&initiator.identity, context)
+            .unwrap()
+            .expect("the initiator offers");
+        let (accept, responder_pending) = responder
+            .encryption
+            .accept(&responder.identity, &offer)
+            .unwrap();
+        let initiator_pending = initiator.encryption.complete(&accept).unwrap();
+        (initiator_pending, responder_pending)
+    }
+
+    fn not_found(tag: u8) -> Message {
+        Message::NotFound {
+            block_hashes: vec![[tag; 32]],
+        }
+    }
+
+    fn tag_of(message: &Message) -> u8 {
+        match message {
+            Message::NotFound { block_hashes } => block_hashes[0][0],
+            other => panic!("expected NotFound, got {:?}", other),
+        }
+    }
+
+    fn seal(node: &mut Node, message: Message) -> EncryptedFrame {
+        match node.encryption.outbound(message).unwrap() {
+            Some(Message::Encrypted(frame)) => frame,
+            other => panic!("expected an encrypted frame, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn peers_agree_a_session_and_exchange_sealed_messages() {
+        let (mut initiator, mut responder) = pair();
+        assert_eq!(initiator.encryption.status(), EncryptionStatus::Negotiating);
+
+        // Direct messages wait for the key exchange
+        assert!(initiator
+            .encryption
+            .outbound(not_found(1))
+            .unwrap()
+            .is_none());
+        assert!(responder
+            .encryption
+            .outbound(not_found(2))
+            .unwrap()
+            .is_none());
+        let (initiator_pending, responder_pending) = establish(&mut initiator, &mut responder);
+        assert_eq!(
+            initiator_pending.iter().map(tag_of).collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(
+            responder_pending.iter().map(tag_of).collect::<Vec<_>>(),
+            [2]
+        );
+        for node in [&initiator, &responder] {
+            assert_eq!(node.encryption.status(), EncryptionStatus::Encrypted);
+        }
+
+        // Each direction has its own key
+        let reply = seal(&mut responder, not_found(9));
+        assert!(matches!(
+            responder
+                .encryption
+                .inbound(Message::Encrypted(reply.clone())),
+            Err(EncryptionError::Decryption(0))
+        ));
+        let opened = initiator
+            .encryption
+            .inbound(Message::Encrypted(reply))
+            .unwrap();
+        assert_eq!(tag_of(&opened), 9);
+
+        for tag in 3..6 {
+            let frame = seal(&mut initiator, not_found(tag));
+            assert!(!frame.ciphertext.windows(32).any(|w| w == [tag; 32]));
+            let opened = responder
+                .encryption
+                .inbound(Message::Encrypted(frame))
+                .unwrap();
+            assert_eq!(tag_of(&opened), tag);
+        }
+    }
+
+    #[test]
+    fn tampered_and_replayed_frames_are_rejected() {
+        let (mut initiator, mut responder) = pair();
+        establish(&mut initiator, &mut responder);
+
+        let frame = seal(&mut initiator, not_found(1));
+        let mut tampered = frame.clone();
+        tampered.ciphertext[0] ^= 0x01;
+        assert!(matches!(
+            responder.encryption.inbound(Message::Encrypted(tampered)),
+            Err(EncryptionError::Decryption(0))
+        ));
+        let mut renumbered = frame.clone();
+        renumbered.sequence = 1;
+        assert!(matches!(
+            responder.encryption.inbound(Message::Encrypted(renumbered)),
+            Err(EncryptionError::Decryption(1))
+        ));
+
+        // A lost frame is skipped over, a replayed one is refused
+        let later = seal(&mut initiator, not_found(2));
+        assert!(responder
+            .encryption
+            .inbound(Message::Encrypted(later))
+            .is_ok());
+        assert!(matches!(
+            responder.encryption.inbound(Message::Encrypted(frame)),
+            Err(EncryptionError::Replay {
+                sequence: 0,
+                expected: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn downgrades_and_forged_handshakes_are_detected() {
+        // Plaintext from a peer that advertised encryption, before and after
+        // the key exchange
+        let (mut initiator, mut responder) = pair();
+        let context = responder.context(&initiator);
+        responder
+            .encryption
+            .start(&responder.identity, context)
+            .unwrap();
+        assert!(matches!(
+            responder.encryption.inbound(not_found(1)),
+            Err(EncryptionError::Downgrade)
+        ));
+        assert!(matches!(
+            responder.encryption.decline(),
+            Err(EncryptionError::Downgrade)
+        ));
+        let (mut initiator_b, mut responder_b) = pair();
+        establish(&mut initiator_b, &mut responder_b);
+        assert!(matches!(
+            initiator_b.encryption.inbound(not_found(1)),
+            Err(EncryptionError::Downgrade)
+        ));
+
+        // The responder saw the initiator's Version with a service bit
+        // stripped, so the offer's transcript signature no longer matches
+        let mut stripped = PeerEncryption::new(true);
+        let mut context = responder.context(&initiator);
+        context.remote_services &= !NODE_BLOOM;
+        stripped.start(&responder.identity, context).unwrap();
+        let context = initiator.context(&responder);
+        let offer = initiator
+            .encryption
+            .start(&initiator.identity, context)
+            .unwrap()
+            .expect("the initiator offers");
+        assert!(matches!(
+            stripped.accept(&responder.identity, &offer),
+            Err(EncryptionError::BadSignature)
+        ));
+
+        // An offer signed by a key other than the peer's
+        let impostor = Keypair::generate_ed25519();
+        let transcript = responder
+            .context(&initiator)
+            .offer_transcript(&offer.kem_public_key);
+        let forged = KemOffer {
+            identity_key: impostor.public().encode_protobuf(),
+            signature: impostor.sign(&transcript).unwrap(),
+            ..offer
+        };
+        assert!(matches!(
+            responder.encryption.accept(&responder.identity, &forged),
+            Err(EncryptionError::IdentityMismatch)
+        ));
+    }
+
+    #[test]
+    fn peers_without_encryption_stay_plaintext() {
+        let mut plain = PeerEncryption::new(false);
+        assert_eq!(plain.status(), EncryptionStatus::Plaintext);
+        assert!(matches!(
+            plain.outbound(not_found(1)),
+            Ok(Some(Message::NotFound { .. }))
+        ));
+        assert!(plain.inbound(not_found(1)).is_ok());
+
+        // Messages held back for the peer's Version go out once it declines
+        let mut offered = PeerEncryption::new(true);
+        assert!(offered.outbound(not_found(2)).unwrap().is_none());
+        assert!(offered.inbound(not_found(3)).is_ok());
+        assert_eq!(
+            offered
+                .decline()
+                .unwrap()
+                .iter()
+                .map(tag_of)
+                .collect::<Vec<_>>(),
+            [2]
+        );
+        assert_eq!(offered.status(), EncryptionStatus::Plaintext);
+        assert!(matches!(
+            offered.inbound(Message::KemOffer(KemOffer {
+                kem_public_key: vec![],
+                identity_key: vec![],
+                signature: vec![],
+            })),
+            Ok(Message::KemOffer(_))
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+//! Wire framing for protocol messages.
+//!
+//! Every message is sent as a frame of a 4-byte little-endian payload
+//! length, the first 4 bytes of the payload's double SHA-256, and the
+//! bincode payload. Frames whose length or checksum do not match are
+//! corrupted in transit and the connection is reset rather than handing
+//! garbage to the deserializer.
+
+use crate::network::message::MessageSizeLimits;
+use crate::network::protocol::Message;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Bytes before the payload
+pub const FRAME_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum FrameError {
+    #[error("frame of {0} bytes is shorter than its header")]
+    Truncated(usize),
+    #[error("frame declares {declared} payload bytes but carries {actual}")]
+    LengthMismatch { declared: usize, actual: usize },
+    #[error("payload of {0} bytes exceeds the message size limit")]
+    TooLarge(usize),
+    #[error("payload checksum mismatch")]
+    ChecksumMismatch,
+    #[error("payload could not be encoded or decoded: {0}")]
+    Codec(#[from] bincode::Error),
+}
+
+impl FrameError {
+    /// Whether the frame was damaged, as opposed to well-formed but
+    /// carrying a message we cannot decode
+    pub fn is_corruption(&self) -> bool {
+        !matches!(self, FrameError::Codec(_))
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let digest = Sha256::digest(Sha256::digest(payload));
+    [digest[0], digest[1], digest[2], digest[3]]
+}
+
+/// Serialize `value` into a frame
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, FrameError> {
+    let payload = bincode::serialize(value)?;
+    if payload.len() > MessageSizeLimits::MAX_MESSAGE_SIZE {
+        return Err(FrameError::TooLarge(payload.len()));
+    }
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&checksum(&payload));
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Check a frame and deserialize its payload
+pub fn decode<T: DeserializeOwned>(frame: &[u8]) -> Result<T, FrameError> {
+    if frame.len() < FRAME_HEADER_LEN {
+        return Err(FrameError::Truncated(frame.len()));
+    }
+    let (header, payload) = frame.split_at(FRAME_HEADER_LEN);
+    let declared = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    if declared > MessageSizeLimits::MAX_MESSAGE_SIZE {
+        return Err(FrameError::TooLarge(declared));
+    }
+    if declared != payload.len() {
+        return Err(FrameError::LengthMismatch {
+            declared,
+            actual: payload.len(),
+        });
+    }
+    if header[4..] != checksum(payload) {
+        return Err(FrameError::ChecksumMismatch);
+    }
+    Ok(bincode::deserialize(payload)?)
+}
+
+pub fn encode_message(message: &Message) -> Result<Vec<u8>, FrameError> {
+    encode(message)
+}
+
+pub fn decode_message(frame: &[u8]) -> Result<Message, FrameError> {
+    decode(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let frame = encode_message(&Message::Ping(42)).unwrap();
+        assert!(matches!(decode_message(&frame), Ok(Message::Ping(42))));
+
+        let direct = encode(&("peer".to_string(), Message::GetAddr)).unwrap();
+        let (recipient, message): (String, Message) = decode(&direct).unwrap();
+        assert_eq!(recipient, "peer");
+        assert!(matches!(message, Message::GetAddr));
+    }
+
+    #[test]
+    fn damaged_frames_are_detected() {
+        let frame = encode_message(&Message::Ping(42)).unwrap();
+
+        let mut flipped = frame.clone();
+        *flipped.last_mut().unwrap() ^= 0x01;
+        assert!(matches!(
+            decode_message(&flipped),
+            Err(FrameError::ChecksumMismatch)
+        ));
+
+        let cut = &frame[..frame.len() - 1];
+        assert!(matches!(
+            decode_message(cut),
+            Err(FrameError::LengthMismatch { .. })
+        ));
+        assert!(matches!(
+            decode_message(&frame[..3]),
+            Err(FrameError::Truncated(3))
+        ));
+
+        // Unframed bincode, as sent before framing, is rejected too
+        let raw = bincode::serialize(&Message::Ping(42)).unwrap();
+        let err = decode_message(&raw).unwrap_err();
+        assert!(err.is_corruption(), "{}", err);
+    }
+}
@@ -0,0 +1,251 @@
+//! Version handshake.
+//!
+//! Each side sends `Version` as soon as a connection is up and answers the
+//! peer's `Version` with `Verack` once it accepts it. Peers on another
+//! genesis block or older than `MIN_PROTOCOL_VERSION` are disconnected.
+//! Otherwise both sides speak the lower of the two versions and use only the
+//! services both advertise that the negotiated version supports.
+
+use crate::network::protocol::VersionMessage;
+use libp2p::identity::Keypair;
+use thiserror::Error;
+
+/// Protocol version this node speaks
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Oldest protocol version still accepted from peers
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Serves the full chain
+pub const NODE_NETWORK: u64 = 0x01;
+/// Serves bloom-filtered blocks and transactions to light clients
+pub const NODE_BLOOM: u64 = 0x04;
+/// Serves at least the blocks within the reorg window; set alone by pruned
+/// nodes
+pub const NODE_NETWORK_LIMITED: u64 = 0x0400;
+/// Relays compact blocks
+pub const NODE_COMPACT_BLOCKS: u64 = 0x0800;
+/// Relays and validates quantum-resistant signatures
+pub const NODE_QUANTUM_SIGS: u64 = 0x1000;
+/// Encrypts direct messages, see [`crate::network::encryption`]
+pub const NODE_ENCRYPTED: u64 = 0x8000;
+
+/// Services introduced by each protocol version
+const SERVICES_BY_VERSION: &[(u32, u64)] = &[
+    (
+        1,
+        NODE_NETWORK | NODE_BLOOM | NODE_NETWORK_LIMITED | NODE_COMPACT_BLOCKS,
+    ),
+    (2, NODE_QUANTUM_SIGS),
+    (3, NODE_ENCRYPTED),
+];
+
+/// Services usable when speaking `version`
+pub fn services_for_version(version: u32) -> u64 {
+    SERVICES_BY_VERSION
+        .iter()
+        .filter(|(introduced, _)| *introduced <= version)
+        .fold(0, |services, (_, added)| services | added)
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum HandshakeError {
+    #[error("peer is on genesis {0}")]
+    GenesisMismatch(String),
+    #[error("peer protocol version {0} is no longer supported")]
+    VersionTooOld(u32),
+}
+
+/// What two peers agreed to speak
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegotiatedVersion {
+    pub version: u32,
+    pub services: u64,
+}
+
+/// Our side of the handshake
+#[derive(Debug, Clone)]
+pub struct LocalVersion {
+    pub genesis_hash: [u8; 32],
+    pub version: u32,
+    pub services: u64,
+    pub user_agent: String,
+    pub best_height: u64,
+    /// Our libp2p identity; signs the encrypted transport handshake
+    pub identity: Option<Keypair>,
+}
+
+impl LocalVersion {
+    pub fn new(genesis_hash: [u8; 32]) -> Self {
+        Self {
+            genesis_hash,
+            version: PROTOCOL_VERSION,
+            services: services_for_version(PROTOCOL_VERSION) & !NODE_ENCRYPTED,
+            user_agent: format!("/supernova:{}/", env!("CARGO_PKG_VERSION")),
+            best_height: 0,
+            identity: None,
+        }
+    }
+
+    pub fn with_identity(mut self, identity: Keypair) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Offer the encrypted transport; needs an identity to sign with
+    pub fn set_encryption(&mut self, enabled: bool) {
+        if enabled && self.identity.is_some() {
+            self.services |= NODE_ENCRYPTED & services_for_version(self.version);
+        } else {
+            self.services &= !NODE_ENCRYPTED;
+        }
+    }
+
+    /// Whether we offer the encrypted transport
+    pub fn offers_encryption(&self) -> bool {
+        self.services & NODE_ENCRYPTED != 0
+    }
+
+    /// Pruned nodes stop advertising the full chain
+    pub fn set_pruned(&mut self, pruned: bool) {
+        if pruned {
+            self.services &= !NODE_NETWORK;
+        } else {
+            self.services |= NODE_NETWORK & services_for_version(self.version);
+        }
+    }
+
+    /// The `Version` message sent to a new peer
+    pub fn message(&self, addr_recv: String, addr_from: String) -> VersionMessage {
+        VersionMessage {
+            version: self.version,
+            services: self.services,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            addr_recv,
+            addr_from,
+            nonce: rand::random(),
+            user_agent: self.user_agent.clone(),
+            start_height: self.best_height,
+            genesis_hash: self.genesis_hash,
+        }
+    }
+
+    /// Accept or reject a peer's `Version`
+    pub fn negotiate(&self, remote: &VersionMessage) -> Result<NegotiatedVersion, HandshakeError> {
+        if remote.genesis_hash != self.genesis_hash {
+            return Err(HandshakeError::GenesisMismatch(hex::encode(
+                remote.genesis_hash,
+            )));
+        }
+        if remote.version < MIN_PROTOCOL_VERSION {
+            return Err(HandshakeError::VersionTooOld(remote.version));
+        }
+        let version = self.version.min(remote.version);
+        Ok(NegotiatedVersion {
+            version,
+            services: self.services & remote.services & services_for_version(version),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: [u8; 32] = [3u8; 32];
+
+    fn remote(version: u32, services: u64, genesis_hash: [u8; 32]) -> VersionMessage {
+        let mut message = LocalVersion::new(genesis_hash).message(String::new(), String::new());
+        message.version = version;
+        message.services = services;
+        message
+    }
+
+    #[test]
+    fn downgrades_to_the_older_peer() {
+        let local = LocalVersion::new(GENESIS);
+        let all = NODE_NETWORK | NODE_BLOOM | NODE_COMPACT_BLOCKS | NODE_QUANTUM_SIGS;
+
+        let current = local.negotiate(&remote(PROTOCOL_VERSION, all, GENESIS));
+        assert_eq!(
+            current,
+            Ok(NegotiatedVersion {
+                version: PROTOCOL_VERSION,
+                services: all
+            })
+        );
+
+        // A version 1 peer advertising quantum signatures cannot use them
+        let old = local.negotiate(&remote(1, all, GENESIS)).unwrap();
+        assert_eq!(old.version, 1);
+        assert_eq!(old.services & NODE_QUANTUM_SIGS, 0);
+        assert_ne!(old.services & NODE_COMPACT_BLOCKS, 0);
+
+        // Only services both sides offer are kept
+        let light = local
+            .negotiate(&remote(PROTOCOL_VERSION, NODE_NETWORK, GENESIS))
+            .unwrap();
+        assert_eq!(light.services, NODE_NETWORK);
+
+        // A newer peer is spoken to at our version
+        let newer = local.negotiate(&remote(PROTOCOL_VERSION + 5, all, GENESIS));
+        assert_eq!(newer.unwrap().version, PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn rejects_wrong_genesis_and_ancient_versions() {
+        let local = LocalVersion::new(GENESIS);
+        assert!(matches!(
+            local.negotiate(&remote(PROTOCOL_VERSION, NODE_NETWORK, [4u8; 32])),
+            Err(HandshakeError::GenesisMismatch(_))
+        ));
+        assert_eq!(
+            local.negotiate(&remote(0, NODE_NETWORK, GENESIS)),
+            Err(HandshakeError::VersionTooOld(0))
+        );
+    }
+
+    #[test]
+    fn pruned_nodes_advertise_only_recent_blocks() {
+        let mut local = LocalVersion::new(GENESIS);
+        local.set_pruned(true);
+        let sent = local.message(String::new(), String::new());
+        assert_eq!(sent.services & NODE_NETWORK, 0);
+        assert_ne!(sent.services & NODE_NETWORK_LIMITED, 0);
+
+        let full = NODE_NETWORK | NODE_NETWORK_LIMITED;
+        let negotiated = local
+            .negotiate(&remote(PROTOCOL_VERSION, full, GENESIS))
+            .unwrap();
+        assert_eq!(negotiated.services, NODE_NETWORK_LIMITED);
+
+        local.set_pruned(false);
+        assert_ne!(local.services & NODE_NETWORK, 0);
+    }
+
+    #[test]
+    fn encryption_is_advertised_only_with_an_identity() {
+        let mut local = LocalVersion::new(GENESIS);
+        local.set_encryption(true);
+        assert!(!local.offers_encryption());
+
+        let mut local = local.with_identity(Keypair::generate_ed25519());
+        local.set_encryption(true);
+        let both = local
+            .negotiate(&remote(PROTOCOL_VERSION, NODE_ENCRYPTED, GENESIS))
+            .unwrap();
+        assert_eq!(both.services, NODE_ENCRYPTED);
+
+        // Version 2 peers predate the encrypted transport
+        let old = local
+            .negotiate(&remote(2, NODE_ENCRYPTED, GENESIS))
+            .unwrap();
+        assert_eq!(old.services, 0);
+
+        local.set_encryption(false);
+        assert_eq!(local.services & NODE_ENCRYPTED, 0);
+    }
+}
@@ -0,0 +1,499 @@
+//! Node identity key rotation
+//!
+//! [`rotate_identity`] generates a new libp2p keypair next to the active one
+//! and a cross-attestation in which both keys sign the link old → new. For
+//! the configured grace period the node keeps running under the old
+//! identity and gossips the attestation, so peers can move what they know
+//! about us (addresses, reputation, trust) to the new peer id before it
+//! appears. The first start after the grace period switches to the new key;
+//! the old one is kept as `peer_id.previous.key`.
+//!
+//! Peers verify an attestation with [`IdentityAttestation::verify`] and keep
+//! the links in an [`IdentityLinkRegistry`].
+
+use crate::network::peer_identity::{self, IdentityError, KeyProtection, KEY_FILE};
+use crate::network::peer_manager::PeerManager;
+use crate::network::protocol::Message;
+use libp2p::identity::{Keypair, PublicKey};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use supernova_core::util::canonical_json::{to_canonical_vec, CanonicalJsonError};
+use thiserror::Error;
+
+/// `Message::Extension` tag carrying a serialized [`IdentityAttestation`]
+pub const IDENTITY_ROTATION_EXTENSION: &str = "identity-rotation/1";
+
+/// How often a node re-announces its pending rotation during the grace
+/// period, so peers that connect late still learn the link
+pub const IDENTITY_ROTATION_ANNOUNCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Domain separator for attestation signatures
+const ATTESTATION_DOMAIN: &[u8] = b"supernova/identity-rotation/v1";
+
+/// Key the node switches to when the grace period ends
+const NEXT_KEY_FILE: &str = "peer_id.next.key";
+/// Key the node ran under before the last completed rotation
+const PREVIOUS_KEY_FILE: &str = "peer_id.previous.key";
+/// Attestation of the rotation in progress
+const ROTATION_FILE: &str = "identity_rotation.json";
+
+/// How long links are remembered after their grace period ends
+const LINK_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Upper bound on remembered links
+const MAX_LINKS: usize = 4096;
+/// Longest chain of rotations followed when resolving a peer id
+const MAX_LINK_CHAIN: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum IdentityRotationError {
+    #[error("Identity key error: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Canonical encoding error: {0}")]
+    Canonical(#[from] CanonicalJsonError),
+    #[error("Signing failed: {0}")]
+    Signing(String),
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("Peer id {0} does not match its public key")]
+    PeerIdMismatch(String),
+    #[error("Invalid {0} key signature")]
+    BadSignature(&'static str),
+    #[error("Old and new identities are the same")]
+    SameIdentity,
+    #[error("Grace period ends before the attestation was issued")]
+    InvalidGrace,
+    #[error("A rotation to {0} is already pending")]
+    AlreadyPending(String),
+    #[error("Peer record migration failed: {0}")]
+    Migration(String),
+}
+
+/// The statement both keys sign
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityLink {
+    pub old_peer_id: String,
+    pub new_peer_id: String,
+    /// Protobuf-encoded public keys, hex
+    pub old_public_key: String,
+    pub new_public_key: String,
+    /// Unix seconds
+    pub issued_at: u64,
+    /// Unix seconds after which the old identity is retired
+    pub grace_until: u64,
+}
+
+/// Cross-signed link from a retiring identity to its replacement. The old
+/// key's signature shows the owner of the old id vouches for the new one;
+/// the new key's signature shows the new id agreed to be linked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityAttestation {
+    pub link: IdentityLink,
+    /// Hex signatures over the canonical JSON of `link`
+    pub old_signature: String,
+    pub new_signature: String,
+}
+
+impl IdentityAttestation {
+    /// Link `old` to `new`, retiring `old` `grace` after `issued_at`
+    pub fn create(
+        old: &Keypair,
+        new: &Keypair,
+        issued_at: u64,
+        grace: Duration,
+    ) -> Result<Self, IdentityRotationError> {
+        let link = IdentityLink {
+            old_peer_id: PeerId::from(old.public()).to_string(),
+            new_peer_id: PeerId::from(new.public()).to_string(),
+            old_public_key: hex::encode(old.public().encode_protobuf()),
+            new_public_key: hex::encode(new.public().encode_protobuf()),
+            issued_at,
+            grace_until: issued_at.saturating_add(grace.as_secs()),
+        };
+        if link.old_peer_id == link.new_peer_id {
+            return Err(IdentityRotationError::SameIdentity);
+        }
+        let message = signing_message(&link)?;
+        let sign = |keypair: &Keypair| {
+            keypair
+                .sign(&message)
+                .map(hex::encode)
+                .map_err(|e| IdentityRotationError::Signing(e.to_string()))
+        };
+        Ok(Self {
+            old_signature: sign(old)?,
+            new_signature: sign(new)?,
+            link,
+        })
+    }
+
+    /// Check both signatures and that the peer ids match their keys.
+    /// Returns the `(old, new)` peer ids.
+    pub fn verify(&self) -> Result<(PeerId, PeerId), IdentityRotationError> {
+        let link = &self.link;
+        if link.grace_until < link.issued_at {
+            return Err(IdentityRotationError::InvalidGrace);
+        }
+        let old = decode_peer(&link.old_peer_id, &link.old_public_key)?;
+        let new = decode_peer(&link.new_peer_id, &link.new_public_key)?;
+        if old.0 == new.0 {
+            return Err(IdentityRotationError::SameIdentity);
+        }
+
+        let message = signing_message(link)?;
+        let signed = |key: &PublicKey, signature: &str| {
+            hex::decode(signature).is_ok_and(|sig| key.verify(&message, &sig))
+        };
+        if !signed(&old.1, &self.old_signature) {
+            return Err(IdentityRotationError::BadSignature("old"));
+        }
+        if !signed(&new.1, &self.new_signature) {
+            return Err(IdentityRotationError::BadSignature("new"));
+        }
+        Ok((old.0, new.0))
+    }
+
+    /// Whether the old identity is still in service at `now`
+    pub fn in_grace(&self, now: u64) -> bool {
+        now < self.link.grace_until
+    }
+
+    /// Peer ids that speak for this node at `now`: both during the grace
+    /// period, only the new one afterwards
+    pub fn active_peer_ids(&self, now: u64) -> Vec<String> {
+        if self.in_grace(now) {
+            vec![self.link.old_peer_id.clone(), self.link.new_peer_id.clone()]
+        } else {
+            vec![self.link.new_peer_id.clone()]
+        }
+    }
+
+    /// Gossip message announcing this rotation
+    pub fn to_message(&self) -> Result<Message, IdentityRotationError> {
+        let payload = serde_json::to_vec(self)
+            .map_err(|e| IdentityRotationError::Serialization(e.to_string()))?;
+        Ok(Message::Extension(IDENTITY_ROTATION_EXTENSION.to_string(), payload))
+    }
+
+    /// Decode a rotation announcement; `None` for any other message
+    pub fn from_message(message: &Message) -> Option<Result<Self, IdentityRotationError>> {
+        match message {
+            Message::Extension(tag, payload) if tag == IDENTITY_ROTATION_EXTENSION => Some(
+                serde_json::from_slice(payload)
+                    .map_err(|e| IdentityRotationError::Serialization(e.to_string())),
+            ),
+            _ => None,
+        }
+    }
+}
+
+fn signing_message(link: &IdentityLink) -> Result<Vec<u8>, IdentityRotationError> {
+    let mut message = ATTESTATION_DOMAIN.to_vec();
+    message.extend(to_canonical_vec(link)?);
+    Ok(message)
+}
+
+fn decode_peer(peer_id: &str, public_key: &str) -> Result<(PeerId, PublicKey), IdentityRotationError> {
+    let bytes = hex::decode(public_key)
+        .map_err(|e| IdentityRotationError::InvalidPublicKey(e.to_string()))?;
+    let key = PublicKey::try_decode_protobuf(&bytes)
+        .map_err(|e| IdentityRotationError::InvalidPublicKey(e.to_string()))?;
+    let derived = PeerId::from(key.clone());
+    if derived.to_string() != peer_id {
+        return Err(IdentityRotationError::PeerIdMismatch(peer_id.to_string()));
+    }
+    Ok((derived, key))
+}
+
+/// Start rotating the identity in `data_dir`: write the new key and the
+/// attestation. The node keeps using the current key until `grace` has
+/// passed.
+pub fn rotate_identity(
+    data_dir: &Path,
+    protection: &KeyProtection,
+    grace: Duration,
+    now: u64,
+) -> Result<IdentityAttestation, IdentityRotationError> {
+    if let Some(pending) = pending_rotation(data_dir)? {
+        return Err(IdentityRotationError::AlreadyPending(pending.link.new_peer_id));
+    }
+    let current = peer_identity::load_keypair(&data_dir.join(KEY_FILE), protection)?;
+    let next = Keypair::generate_ed25519();
+    let attestation = IdentityAttestation::create(&current, &next, now, grace)?;
+
+    peer_identity::save_keypair(&next, &data_dir.join(NEXT_KEY_FILE), protection)?;
+    let json = serde_json::to_vec_pretty(&attestation)
+        .map_err(|e| IdentityRotationError::Serialization(e.to_string()))?;
+    std::fs::write(data_dir.join(ROTATION_FILE), json)?;
+    Ok(attestation)
+}
+
+/// Attestation of the rotation in progress in `data_dir`, if any
+pub fn pending_rotation(data_dir: &Path) -> Result<Option<IdentityAttestation>, IdentityRotationError> {
+    let path = data_dir.join(ROTATION_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path)?;
+    serde_json::from_slice(&bytes)
+        .map(Some)
+        .map_err(|e| IdentityRotationError::Serialization(e.to_string()))
+}
+
+/// Switch to the new key if a rotation's grace period has ended. Returns
+/// the completed rotation.
+pub fn complete_due_rotation(
+    data_dir: &Path,
+    now: u64,
+) -> Result<Option<IdentityAttestation>, IdentityRotationError> {
+    let Some(attestation) = pending_rotation(data_dir)? else {
+        return Ok(None);
+    };
+    if attestation.in_grace(now) {
+        return Ok(None);
+    }
+    std::fs::rename(data_dir.join(KEY_FILE), data_dir.join(PREVIOUS_KEY_FILE))?;
+    std::fs::rename(data_dir.join(NEXT_KEY_FILE), data_dir.join(KEY_FILE))?;
+    std::fs::remove_file(data_dir.join(ROTATION_FILE))?;
+    Ok(Some(attestation))
+}
+
+/// Identity links learned from peers' attestations
+#[derive(Debug, Default)]
+pub struct IdentityLinkRegistry {
+    /// Old peer id → (new peer id, end of grace period)
+    links: HashMap<PeerId, (PeerId, u64)>,
+}
+
+impl IdentityLinkRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verify and remember `attestation`. Returns the `(old, new)` pair when
+    /// the link is new, `None` if it was already known or there is no room.
+    pub fn record(
+        &mut self,
+        attestation: &IdentityAttestation,
+        now: u64,
+    ) -> Result<Option<(PeerId, PeerId)>, IdentityRotationError> {
+        let (old, new) = attestation.verify()?;
+        if self.links.get(&old).is_some_and(|(known, _)| *known == new) {
+            return Ok(None);
+        }
+
+        let retention = LINK_RETENTION.as_secs();
+        self.links
+            .retain(|_, (_, grace_until)| grace_until.saturating_add(retention) > now);
+        if self.links.len() >= MAX_LINKS {
+            return Ok(None);
+        }
+        self.links.insert(old, (new, attestation.link.grace_until));
+        Ok(Some((old, new)))
+    }
+
+    /// Latest known identity of `peer`
+    pub fn resolve(&self, peer: &PeerId) -> PeerId {
+        let mut current = *peer;
+        for _ in 0..MAX_LINK_CHAIN {
+            match self.links.get(&current) {
+                Some((next, _)) => current = *next,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// Whether `a` and `b` are known to be the same node
+    pub fn same_node(&self, a: &PeerId, b: &PeerId) -> bool {
+        self.resolve(a) == self.resolve(b)
+    }
+
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+}
+
+/// Handle a rotation announced by a peer: verify it, remember the link and
+/// move the peer's records to its new identity. Returns whether the link
+/// was new.
+pub async fn apply_attestation(
+    registry: &Mutex<IdentityLinkRegistry>,
+    peer_manager: &PeerManager,
+    attestation: &IdentityAttestation,
+    now: u64,
+) -> Result<bool, IdentityRotationError> {
+    let recorded = match registry.lock() {
+        Ok(mut links) => links.record(attestation, now)?,
+        Err(_) => {
+            return Err(IdentityRotationError::Migration(
+                "link registry lock poisoned".to_string(),
+            ))
+        }
+    };
+    let Some((old, new)) = recorded else {
+        return Ok(false);
+    };
+    peer_manager
+        .migrate_peer_identity(&old, new)
+        .await
+        .map_err(|e| IdentityRotationError::Migration(e.to_string()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::peer::{PeerInfo, PeerMetadata, PeerState};
+    use crate::network::peer_identity::load_or_generate_keypair_at;
+    use crate::network::peer_manager::{ConnectionLimits, PeerManager};
+    use crate::storage::MemoryStorage;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tempfile::TempDir;
+
+    const DAY: u64 = 24 * 60 * 60;
+    const NOW: u64 = 1_700_000_000;
+
+    fn protection() -> KeyProtection {
+        KeyProtection::Passphrase("rotation test".to_string())
+    }
+
+    fn peer_info(peer_id: PeerId, reputation: i32) -> PeerInfo {
+        PeerInfo {
+            peer_id,
+            state: PeerState::Connected,
+            addresses: vec!["/ip4/203.0.113.9/tcp/8000".parse().unwrap()],
+            first_seen: Instant::now(),
+            last_seen: Instant::now(),
+            last_sent: None,
+            is_inbound: false,
+            protocol_version: None,
+            user_agent: None,
+            height: None,
+            best_hash: None,
+            total_difficulty: None,
+            network_info: None,
+            reputation,
+            failed_attempts: 0,
+            ping_ms: None,
+            verified: true,
+            services: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            metadata: PeerMetadata::default(),
+        }
+    }
+
+    #[test]
+    fn rotation_produces_verifiable_attestation() {
+        let dir = TempDir::new().unwrap();
+        let old = load_or_generate_keypair_at(dir.path(), &protection(), NOW).unwrap();
+
+        let attestation =
+            rotate_identity(dir.path(), &protection(), Duration::from_secs(DAY), NOW).unwrap();
+        let (old_id, new_id) = attestation.verify().unwrap();
+        assert_eq!(old_id, PeerId::from(old.public()));
+        assert_ne!(old_id, new_id);
+        assert_eq!(attestation.link.grace_until, NOW + DAY);
+        assert_eq!(pending_rotation(dir.path()).unwrap(), Some(attestation.clone()));
+        assert!(matches!(
+            rotate_identity(dir.path(), &protection(), Duration::from_secs(DAY), NOW),
+            Err(IdentityRotationError::AlreadyPending(_))
+        ));
+
+        // Survives the gossip encoding
+        let message = attestation.to_message().unwrap();
+        let decoded = IdentityAttestation::from_message(&message).unwrap().unwrap();
+        assert_eq!(decoded, attestation);
+        assert!(IdentityAttestation::from_message(&Message::GetAddr).is_none());
+
+        // Tampering with the link breaks the signatures
+        let mut forged = attestation.clone();
+        forged.link.grace_until += 1;
+        assert!(matches!(forged.verify(), Err(IdentityRotationError::BadSignature("old"))));
+
+        // Someone else cannot claim the link to the new key
+        let mallory = Keypair::generate_ed25519();
+        let mut hijacked = attestation.clone();
+        hijacked.link.old_peer_id = PeerId::from(mallory.public()).to_string();
+        assert!(matches!(hijacked.verify(), Err(IdentityRotationError::PeerIdMismatch(_))));
+    }
+
+    #[test]
+    fn grace_period_keeps_old_identity_then_switches() {
+        let dir = TempDir::new().unwrap();
+        let old = load_or_generate_keypair_at(dir.path(), &protection(), NOW).unwrap();
+        let attestation =
+            rotate_identity(dir.path(), &protection(), Duration::from_secs(DAY), NOW).unwrap();
+        let old_id = PeerId::from(old.public()).to_string();
+        let new_id = attestation.link.new_peer_id.clone();
+
+        // During the grace period the node still runs as the old identity
+        // while both ids speak for it
+        let during = load_or_generate_keypair_at(dir.path(), &protection(), NOW + DAY / 2).unwrap();
+        assert_eq!(during.public(), old.public());
+        assert_eq!(attestation.active_peer_ids(NOW + DAY / 2), vec![old_id.clone(), new_id.clone()]);
+        assert!(pending_rotation(dir.path()).unwrap().is_some());
+
+        // Afterwards it switches, keeping the retired key
+        let after = load_or_generate_keypair_at(dir.path(), &protection(), NOW + DAY).unwrap();
+        assert_eq!(PeerId::from(after.public()).to_string(), new_id);
+        assert_eq!(attestation.active_peer_ids(NOW + DAY), vec![new_id]);
+        assert!(pending_rotation(dir.path()).unwrap().is_none());
+        let previous =
+            peer_identity::load_keypair(&dir.path().join(PREVIOUS_KEY_FILE), &protection()).unwrap();
+        assert_eq!(previous.public(), old.public());
+    }
+
+    #[tokio::test]
+    async fn peer_records_link_and_migrates_reputation() {
+        let old = Keypair::generate_ed25519();
+        let new = Keypair::generate_ed25519();
+        let attestation =
+            IdentityAttestation::create(&old, &new, NOW, Duration::from_secs(DAY)).unwrap();
+        let old_id = PeerId::from(old.public());
+        let new_id = PeerId::from(new.public());
+
+        // A peer that knows the rotating node under its old id
+        let manager = PeerManager::new(Arc::new(MemoryStorage::new()), ConnectionLimits::default());
+        manager.add_peer(old_id, peer_info(old_id, 40)).await.unwrap();
+        manager.update_peer_score(&old_id, 25.0).await.unwrap();
+        manager.add_trusted_peer(old_id).await.unwrap();
+
+        let registry = Mutex::new(IdentityLinkRegistry::new());
+        assert!(apply_attestation(&registry, &manager, &attestation, NOW).await.unwrap());
+        assert!(!apply_attestation(&registry, &manager, &attestation, NOW).await.unwrap());
+        let links = registry.lock().unwrap();
+        assert!(links.same_node(&old_id, &new_id));
+        assert_eq!(links.resolve(&old_id), new_id);
+        drop(links);
+
+        assert!(manager.get_peer_info(&old_id).await.is_none());
+        let migrated = manager.get_peer_info(&new_id).await.unwrap();
+        assert_eq!(migrated.peer_id, new_id);
+        assert_eq!(migrated.reputation, 40);
+        assert_eq!(migrated.addresses.len(), 1);
+        assert!(manager.is_trusted(&new_id).await);
+        assert!(!manager.is_trusted(&old_id).await);
+        assert_eq!(manager.get_best_peers(1).await, vec![new_id]);
+
+        // A forged attestation is not recorded
+        let mut forged = attestation.clone();
+        forged.new_signature = forged.old_signature.clone();
+        let mut fresh = IdentityLinkRegistry::new();
+        assert!(fresh.record(&forged, NOW).is_err());
+        assert!(fresh.is_empty());
+    }
+}
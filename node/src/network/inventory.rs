@@ -0,0 +1,161 @@
+//! Per-peer known inventory.
+//!
+//! Every connected peer carries a rolling set of the block and transaction
+//! hashes it is known to have, because it sent or announced them to us or we
+//! sent them to it. Relays of items already in the set are skipped, which
+//! stops transactions echoing back to the peer that just announced them. The
+//! set lives in the peer's metadata, so it starts empty on every connection.
+
+use crate::network::protocol::Message;
+use crate::network::request_manager::ObjectId;
+use std::collections::HashSet;
+use std::mem;
+use supernova_core::{Block, Transaction};
+
+/// Entries remembered per peer unless configured otherwise
+pub const DEFAULT_KNOWN_INVENTORY_SIZE: usize = 50_000;
+
+/// The most recent items a peer knows, bounded to `capacity` entries. Items
+/// are kept in two generations of half the capacity each; when the newer one
+/// fills up, the older one is forgotten.
+#[derive(Debug, Clone)]
+pub struct KnownInventory {
+    generation_size: usize,
+    current: HashSet<ObjectId>,
+    previous: HashSet<ObjectId>,
+}
+
+impl Default for KnownInventory {
+    fn default() -> Self {
+        Self::new(DEFAULT_KNOWN_INVENTORY_SIZE)
+    }
+}
+
+impl KnownInventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            generation_size: (capacity / 2).max(1),
+            current: HashSet::new(),
+            previous: HashSet::new(),
+        }
+    }
+
+    pub fn contains(&self, id: &ObjectId) -> bool {
+        self.current.contains(id) || self.previous.contains(id)
+    }
+
+    /// Remember `id`. Returns false if it was already known.
+    pub fn insert(&mut self, id: ObjectId) -> bool {
+        if self.contains(&id) {
+            return false;
+        }
+        if self.current.len() >= self.generation_size {
+            self.previous = mem::take(&mut self.current);
+        }
+        self.current.insert(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.current.len() + self.previous.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The block or transaction an announcement or relayed message carries
+pub fn inventory_of(message: &Message) -> Option<ObjectId> {
+    match message {
+        Message::Transaction { transaction } | Message::BroadcastTransaction(transaction) => {
+            bincode::deserialize::<Transaction>(transaction)
+                .ok()
+                .map(|tx| ObjectId::transaction(tx.hash()))
+        }
+        Message::TransactionAnnouncement { tx_hash, .. } => Some(ObjectId::transaction(*tx_hash)),
+        Message::Block(block) => Some(ObjectId::block(block.hash())),
+        Message::NewBlock { block_data, .. } => bincode::deserialize::<Block>(block_data)
+            .ok()
+            .map(|block| ObjectId::block(block.hash())),
+        Message::CompactBlock(compact) => Some(ObjectId::block(compact.header.hash())),
+        Message::MerkleBlock(merkle) => Some(ObjectId::block(merkle.header.hash())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn tx(n: u32) -> ObjectId {
+        let mut hash = [0u8; 32];
+        hash[..4].copy_from_slice(&n.to_le_bytes());
+        ObjectId::transaction(hash)
+    }
+
+    #[test]
+    fn known_inventory_is_bounded() {
+        let mut known = KnownInventory::new(100);
+        for n in 0..1_000 {
+            assert!(known.insert(tx(n)));
+            assert!(known.len() <= 100);
+        }
+        assert!(!known.insert(tx(999)));
+        assert!(known.contains(&tx(950)));
+        assert!(!known.contains(&tx(0)));
+    }
+
+    /// Flood `items` through a ring of `nodes` where each node also links to
+    /// the node two hops away, and count the messages sent. Naive relay
+    /// forwards every new item to all neighbours; with known inventory a
+    /// node skips neighbours that sent or were sent the item.
+    fn simulate(nodes: usize, items: u32, track_inventory: bool) -> (u64, u64) {
+        let neighbours =
+            |node: usize| [1, 2, nodes - 1, nodes - 2].map(|offset| (node + offset) % nodes);
+        let mut seen = vec![HashSet::new(); nodes];
+        // known[node][i] is what node knows about its i-th neighbour
+        let mut known = vec![vec![KnownInventory::new(64); 4]; nodes];
+        let (mut sent, mut suppressed) = (0u64, 0u64);
+        let mut queue = VecDeque::new();
+
+        for item in 0..items {
+            queue.push_back((None, item as usize % nodes, tx(item)));
+        }
+        while let Some((from, node, item)) = queue.pop_front() {
+            if let Some(from) = from {
+                let slot = neighbours(node).iter().position(|&n| n == from).unwrap();
+                known[node][slot].insert(item);
+            }
+            if !seen[node].insert(item) {
+                continue;
+            }
+            for (slot, peer) in neighbours(node).into_iter().enumerate() {
+                if track_inventory && !known[node][slot].insert(item) {
+                    suppressed += 1;
+                    continue;
+                }
+                sent += 1;
+                queue.push_back((Some(node), peer, item));
+            }
+        }
+
+        assert!(seen
+            .iter()
+            .all(|items_seen| items_seen.len() == items as usize));
+        (sent, suppressed)
+    }
+
+    #[test]
+    fn known_inventory_cuts_relay_volume() {
+        let (naive, none_suppressed) = simulate(10, 20, false);
+        let (tracked, suppressed) = simulate(10, 20, true);
+
+        // Naive relay sends each item over every link in both directions
+        assert_eq!(naive, 20 * 10 * 4);
+        assert_eq!(none_suppressed, 0);
+        assert_eq!(tracked + suppressed, naive);
+        // At least the echo back to the sender is saved at every hop
+        assert!(tracked <= naive - 20 * 9, "{} vs naive {}", tracked, naive);
+    }
+}
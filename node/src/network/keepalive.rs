@@ -0,0 +1,501 @@
+//! Application-level keepalive and dead peer detection.
+//!
+//! Transport keepalives do not catch every failure mode: a peer behind a NAT
+//! whose mapping expired, or a process that hung without closing its socket,
+//! keeps its slot in our peer set indefinitely. The keepalive manager pings
+//! connections that have been idle for `ping_interval`, measures round-trip
+//! latency from matching pongs, and tells the caller to disconnect peers that
+//! miss `max_missed_pongs` consecutive pongs or whose latency stays above
+//! `max_latency` for `max_latency_strikes` consecutive pongs.
+//!
+//! The manager is a pure state machine driven by explicit timestamps so it
+//! can be exercised deterministically in tests; the P2P layer calls
+//! [`KeepaliveManager::poll`] on its maintenance tick and feeds inbound
+//! activity and pongs back in.
+
+use libp2p::PeerId;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Keepalive configuration
+#[derive(Debug, Clone)]
+pub struct KeepaliveConfig {
+    /// Idle time after which a ping is sent, and the interval between
+    /// subsequent pings while the peer stays silent
+    pub ping_interval: Duration,
+    /// Consecutive unanswered pings before the peer is considered dead
+    pub max_missed_pongs: u32,
+    /// Round-trip latency above which a pong counts as a latency strike
+    pub max_latency: Duration,
+    /// Consecutive latency strikes before the peer is disconnected
+    pub max_latency_strikes: u32,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval: Duration::from_secs(20),
+            max_missed_pongs: 3,
+            max_latency: Duration::from_secs(10),
+            max_latency_strikes: 5,
+        }
+    }
+}
+
+impl KeepaliveConfig {
+    /// Build the keepalive configuration from the node's network section.
+    pub fn from_network_config(config: &crate::config::NetworkConfig) -> Self {
+        Self {
+            ping_interval: config.peer_ping_interval,
+            max_missed_pongs: config.max_missed_pongs,
+            max_latency: config.max_ping_latency,
+            ..Self::default()
+        }
+    }
+}
+
+/// Why the keepalive manager wants a peer disconnected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeadPeerReason {
+    /// The peer failed to answer this many consecutive pings
+    MissedPongs(u32),
+}
+
+/// Action requested by [`KeepaliveManager::poll`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeepaliveAction {
+    /// Send `Ping(nonce)` to the peer
+    SendPing { peer_id: PeerId, nonce: u64 },
+    /// Disconnect the peer
+    Disconnect {
+        peer_id: PeerId,
+        reason: DeadPeerReason,
+    },
+}
+
+/// Result of processing a pong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PongOutcome {
+    /// The pong answered our outstanding ping; round-trip time measured
+    Latency(Duration),
+    /// The pong answered our ping but latency has been too high for too long
+    Disconnect(Duration),
+    /// Unknown peer, no outstanding ping, or nonce mismatch
+    Unsolicited,
+}
+
+#[derive(Debug, Clone)]
+struct PeerKeepalive {
+    /// Last time anything was received from the peer
+    last_activity: Instant,
+    /// Outstanding ping nonce and when it was sent
+    outstanding: Option<(u64, Instant)>,
+    /// Consecutive pings that went unanswered
+    missed_pongs: u32,
+    /// Consecutive pongs above the latency ceiling
+    latency_strikes: u32,
+    /// Most recent round-trip time
+    last_latency: Option<Duration>,
+}
+
+/// Tracks ping state for every connected peer
+pub struct KeepaliveManager {
+    config: KeepaliveConfig,
+    peers: HashMap<PeerId, PeerKeepalive>,
+}
+
+impl KeepaliveManager {
+    /// Create a new keepalive manager
+    pub fn new(config: KeepaliveConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Access the configuration
+    pub fn config(&self) -> &KeepaliveConfig {
+        &self.config
+    }
+
+    /// Start tracking a newly connected peer
+    pub fn register_peer(&mut self, peer_id: PeerId, now: Instant) {
+        self.peers.insert(
+            peer_id,
+            PeerKeepalive {
+                last_activity: now,
+                outstanding: None,
+                missed_pongs: 0,
+                latency_strikes: 0,
+                last_latency: None,
+            },
+        );
+    }
+
+    /// Stop tracking a disconnected peer
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    /// Record that a message arrived from the peer. Any traffic proves the
+    /// connection is alive, so the missed-pong count is reset; latency is only
+    /// measured from pongs. Timestamps no newer than the last recorded
+    /// activity are ignored, so callers may replay a peer's `last_seen`.
+    pub fn record_activity(&mut self, peer_id: &PeerId, at: Instant) {
+        if let Some(state) = self.peers.get_mut(peer_id) {
+            if at > state.last_activity {
+                state.last_activity = at;
+                state.missed_pongs = 0;
+            }
+        }
+    }
+
+    /// Reconcile tracked peers with the current connection set: register new
+    /// peers, record their latest activity, and forget disconnected ones.
+    pub fn sync_connected(&mut self, connected: &[(PeerId, Instant)]) {
+        self.peers
+            .retain(|peer_id, _| connected.iter().any(|(id, _)| id == peer_id));
+        for (peer_id, last_seen) in connected {
+            if self.peers.contains_key(peer_id) {
+                self.record_activity(peer_id, *last_seen);
+            } else {
+                self.register_peer(*peer_id, *last_seen);
+            }
+        }
+    }
+
+    /// Most recently measured round-trip time for a peer
+    pub fn latency(&self, peer_id: &PeerId) -> Option<Duration> {
+        self.peers.get(peer_id).and_then(|s| s.last_latency)
+    }
+
+    /// Number of peers being tracked
+    pub fn peer_count(&self) -> usize {
+        self.peers.len()
+    }
+
+    /// Determine which peers to ping and which to drop.
+    ///
+    /// A peer is pinged once it has been idle for `ping_interval`. If the
+    /// previous ping is still unanswered when the next one falls due, it is
+    /// counted as missed; reaching `max_missed_pongs` yields a disconnect
+    /// exactly `max_missed_pongs` intervals after the first unanswered ping.
+    pub fn poll(&mut self, now: Instant, mut next_nonce: impl FnMut() -> u64) -> Vec<KeepaliveAction> {
+        let mut actions = Vec::new();
+        let interval = self.config.ping_interval;
+
+        for (peer_id, state) in self.peers.iter_mut() {
+            let due = match state.outstanding {
+                Some((_, sent_at)) => now.saturating_duration_since(sent_at) >= interval,
+                None => now.saturating_duration_since(state.last_activity) >= interval,
+            };
+            if !due {
+                continue;
+            }
+
+            if state.outstanding.take().is_some() {
+                state.missed_pongs += 1;
+                if state.missed_pongs >= self.config.max_missed_pongs {
+                    actions.push(KeepaliveAction::Disconnect {
+                        peer_id: *peer_id,
+                        reason: DeadPeerReason::MissedPongs(state.missed_pongs),
+                    });
+                    continue;
+                }
+            }
+
+            let nonce = next_nonce();
+            state.outstanding = Some((nonce, now));
+            actions.push(KeepaliveAction::SendPing {
+                peer_id: *peer_id,
+                nonce,
+            });
+        }
+
+        // Disconnected peers no longer need tracking.
+        for action in &actions {
+            if let KeepaliveAction::Disconnect { peer_id, .. } = action {
+                self.peers.remove(peer_id);
+            }
+        }
+
+        actions
+    }
+
+    /// Process a pong from a peer
+    pub fn handle_pong(&mut self, peer_id: &PeerId, nonce: u64, now: Instant) -> PongOutcome {
+        let Some(state) = self.peers.get_mut(peer_id) else {
+            return PongOutcome::Unsolicited;
+        };
+        state.last_activity = now;
+
+        match state.outstanding {
+            Some((expected, sent_at)) if expected == nonce => {
+                state.outstanding = None;
+                state.missed_pongs = 0;
+                let latency = now.saturating_duration_since(sent_at);
+                state.last_latency = Some(latency);
+
+                if latency > self.config.max_latency {
+                    state.latency_strikes += 1;
+                    if state.latency_strikes >= self.config.max_latency_strikes {
+                        self.peers.remove(peer_id);
+                        return PongOutcome::Disconnect(latency);
+                    }
+                } else {
+                    state.latency_strikes = 0;
+                }
+                PongOutcome::Latency(latency)
+            }
+            _ => PongOutcome::Unsolicited,
+        }
+    }
+}
+
+/// Reputation delta applied for a measured round-trip time. Fast peers are
+/// rewarded slightly; slow peers are penalized in proportion to how far over
+/// the ceiling they are.
+pub fn latency_score_delta(latency: Duration, max_latency: Duration) -> f64 {
+    if latency <= max_latency / 4 {
+        0.5
+    } else if latency <= max_latency {
+        0.0
+    } else {
+        -((latency.as_secs_f64() / max_latency.as_secs_f64().max(0.001)).min(10.0))
+    }
+}
+
+/// Outbound send queue with a priority lane for control traffic.
+///
+/// Pongs and pings must go out promptly even when the connection is busy
+/// relaying blocks, or a peer under load looks dead to everyone pinging it.
+/// Control messages always drain before bulk messages.
+#[derive(Debug)]
+pub struct PrioritizedSendQueue<T> {
+    control: VecDeque<T>,
+    bulk: VecDeque<T>,
+    max_bulk: usize,
+}
+
+impl<T> PrioritizedSendQueue<T> {
+    /// Create a queue holding at most `max_bulk` bulk messages
+    pub fn new(max_bulk: usize) -> Self {
+        Self {
+            control: VecDeque::new(),
+            bulk: VecDeque::new(),
+            max_bulk,
+        }
+    }
+
+    /// Queue a control message (ping/pong). Never rejected.
+    pub fn push_control(&mut self, message: T) {
+        self.control.push_back(message);
+    }
+
+    /// Queue a bulk message. Returns the message back if the queue is full.
+    pub fn push_bulk(&mut self, message: T) -> Result<(), T> {
+        if self.bulk.len() >= self.max_bulk {
+            return Err(message);
+        }
+        self.bulk.push_back(message);
+        Ok(())
+    }
+
+    /// Next message to send, control traffic first
+    pub fn pop(&mut self) -> Option<T> {
+        self.control.pop_front().or_else(|| self.bulk.pop_front())
+    }
+
+    /// Total queued messages
+    pub fn len(&self) -> usize {
+        self.control.len() + self.bulk.len()
+    }
+
+    /// Whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.control.is_empty() && self.bulk.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> KeepaliveConfig {
+        KeepaliveConfig {
+            ping_interval: Duration::from_secs(10),
+            max_missed_pongs: 3,
+            max_latency: Duration::from_millis(500),
+            max_latency_strikes: 2,
+        }
+    }
+
+    fn counter() -> impl FnMut() -> u64 {
+        let mut n = 0;
+        move || {
+            n += 1;
+            n
+        }
+    }
+
+    #[test]
+    fn test_no_ping_while_active() {
+        let mut manager = KeepaliveManager::new(config());
+        let peer = PeerId::random();
+        let start = Instant::now();
+        manager.register_peer(peer, start);
+
+        manager.record_activity(&peer, start + Duration::from_secs(8));
+        assert!(manager
+            .poll(start + Duration::from_secs(12), counter())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_missed_pongs_disconnect_after_exactly_n_intervals() {
+        let mut manager = KeepaliveManager::new(config());
+        let peer = PeerId::random();
+        let start = Instant::now();
+        manager.register_peer(peer, start);
+        let mut nonces = counter();
+        let interval = Duration::from_secs(10);
+
+        // First ping once idle for one interval.
+        let first_ping = start + interval;
+        let actions = manager.poll(first_ping, &mut nonces);
+        assert!(matches!(actions[..], [KeepaliveAction::SendPing { .. }]));
+
+        // Intervals 1 and 2 after the first ping: missed, re-ping.
+        for i in 1..3 {
+            let actions = manager.poll(first_ping + interval * i, &mut nonces);
+            assert!(
+                matches!(actions[..], [KeepaliveAction::SendPing { .. }]),
+                "interval {i}: {actions:?}"
+            );
+        }
+
+        // Just before the third interval elapses nothing happens.
+        assert!(manager
+            .poll(first_ping + interval * 3 - Duration::from_millis(1), &mut nonces)
+            .is_empty());
+
+        // Exactly N intervals after the first unanswered ping: disconnect.
+        let actions = manager.poll(first_ping + interval * 3, &mut nonces);
+        assert_eq!(
+            actions,
+            vec![KeepaliveAction::Disconnect {
+                peer_id: peer,
+                reason: DeadPeerReason::MissedPongs(3),
+            }]
+        );
+        assert_eq!(manager.peer_count(), 0);
+    }
+
+    #[test]
+    fn test_latency_measurement_with_simulated_delay() {
+        let mut manager = KeepaliveManager::new(config());
+        let peer = PeerId::random();
+        let start = Instant::now();
+        manager.register_peer(peer, start);
+
+        let sent = start + Duration::from_secs(10);
+        let actions = manager.poll(sent, counter());
+        let nonce = match actions[..] {
+            [KeepaliveAction::SendPing { nonce, .. }] => nonce,
+            _ => panic!("expected a ping: {actions:?}"),
+        };
+
+        // Wrong nonce is ignored and does not clear the outstanding ping.
+        assert_eq!(
+            manager.handle_pong(&peer, nonce + 100, sent + Duration::from_millis(50)),
+            PongOutcome::Unsolicited
+        );
+
+        let outcome = manager.handle_pong(&peer, nonce, sent + Duration::from_millis(137));
+        assert_eq!(outcome, PongOutcome::Latency(Duration::from_millis(137)));
+        assert_eq!(manager.latency(&peer), Some(Duration::from_millis(137)));
+    }
+
+    #[test]
+    fn test_persistent_high_latency_disconnects() {
+        let mut manager = KeepaliveManager::new(config());
+        let peer = PeerId::random();
+        let start = Instant::now();
+        manager.register_peer(peer, start);
+        let mut nonces = counter();
+        let mut now = start;
+
+        for strike in 1..=2 {
+            now += Duration::from_secs(10);
+            let nonce = match manager.poll(now, &mut nonces)[..] {
+                [KeepaliveAction::SendPing { nonce, .. }] => nonce,
+                ref other => panic!("expected a ping: {other:?}"),
+            };
+            now += Duration::from_millis(900);
+            let outcome = manager.handle_pong(&peer, nonce, now);
+            if strike < 2 {
+                assert_eq!(outcome, PongOutcome::Latency(Duration::from_millis(900)));
+            } else {
+                assert_eq!(outcome, PongOutcome::Disconnect(Duration::from_millis(900)));
+            }
+        }
+        assert_eq!(manager.peer_count(), 0);
+    }
+
+    #[test]
+    fn test_activity_resets_missed_pongs() {
+        let mut manager = KeepaliveManager::new(config());
+        let peer = PeerId::random();
+        let start = Instant::now();
+        manager.register_peer(peer, start);
+        let mut nonces = counter();
+        let interval = Duration::from_secs(10);
+
+        manager.poll(start + interval, &mut nonces);
+        manager.poll(start + interval * 2, &mut nonces);
+        // Traffic arrives: the peer is alive even though it ignored pings.
+        manager.record_activity(&peer, start + interval * 2 + Duration::from_secs(1));
+        let actions = manager.poll(start + interval * 3, &mut nonces);
+        assert!(matches!(actions[..], [KeepaliveAction::SendPing { .. }]));
+        let actions = manager.poll(start + interval * 4, &mut nonces);
+        assert!(matches!(actions[..], [KeepaliveAction::SendPing { .. }]));
+    }
+
+    #[test]
+    fn test_sync_connected_tracks_peer_set() {
+        let mut manager = KeepaliveManager::new(config());
+        let (a, b) = (PeerId::random(), PeerId::random());
+        let start = Instant::now();
+        manager.sync_connected(&[(a, start), (b, start)]);
+        assert_eq!(manager.peer_count(), 2);
+
+        manager.sync_connected(&[(a, start)]);
+        assert_eq!(manager.peer_count(), 1);
+        assert!(manager.latency(&b).is_none());
+    }
+
+    #[test]
+    fn test_ping_prioritized_over_backed_up_queue() {
+        let mut queue: PrioritizedSendQueue<&str> = PrioritizedSendQueue::new(3);
+        for msg in ["block-1", "block-2", "block-3"] {
+            queue.push_bulk(msg).unwrap();
+        }
+        // Bulk lane is full, control traffic is still accepted.
+        assert_eq!(queue.push_bulk("block-4"), Err("block-4"));
+        queue.push_control("pong");
+        queue.push_control("ping");
+
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.pop(), Some("pong"));
+        assert_eq!(queue.pop(), Some("ping"));
+        assert_eq!(queue.pop(), Some("block-1"));
+    }
+
+    #[test]
+    fn test_latency_score_delta() {
+        let ceiling = Duration::from_millis(400);
+        assert!(latency_score_delta(Duration::from_millis(50), ceiling) > 0.0);
+        assert_eq!(latency_score_delta(Duration::from_millis(300), ceiling), 0.0);
+        assert!(latency_score_delta(Duration::from_millis(800), ceiling) < 0.0);
+    }
+}
@@ -0,0 +1,988 @@
+//! Mempool warm-up after a restart.
+//!
+//! A restarted node comes back with an empty mempool and only refills it as
+//! new transactions are relayed, which skews fee estimates and block
+//! templates for the first hour or so. Once initial block download is done,
+//! [`MempoolWarmup`] asks a few outbound peers, one at a time, for a summary
+//! of their mempool (`GetMempoolSummary`): txids above a fee-rate floor with
+//! each one's size and in-mempool parents. Unknown transactions are fetched
+//! through the request manager, parents before children, and each one goes
+//! through normal mempool admission. A child that arrives before its parent
+//! is held until the parent is admitted, and dropped if the parent is not.
+//!
+//! Only outbound peers are asked, so connecting to us is not enough to feed
+//! us a summary; unsolicited summaries are ignored, declared sizes are
+//! charged against a byte budget for the whole warm-up, and nothing new is
+//! requested once the warm-up window closes.
+
+use crate::config::MempoolSyncConfig;
+use crate::mempool::TransactionPool;
+use crate::network::message::MessageSizeLimits;
+use crate::network::p2p::NetworkCommand;
+use crate::network::peer::PeerInfo;
+use crate::network::protocol::Message;
+use crate::network::request_manager::{ObjectId, RequestManager};
+use crate::network::sync_progress::SyncProgressTracker;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use supernova_core::types::transaction::Transaction;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+/// Most entries in one `MempoolSummary`
+pub const MAX_SUMMARY_ENTRIES: usize = 5_000;
+
+/// Most parents listed for one summary entry
+pub const MAX_SUMMARY_PARENTS: usize = 25;
+
+/// How often the warm-up checks whether to ask the next peer
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// One transaction in a peer's mempool summary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolSummaryEntry {
+    pub txid: [u8; 32],
+    /// Fee rate in novas per byte
+    pub fee_rate: u64,
+    /// Serialized size in bytes
+    pub size: u32,
+    /// Unconfirmed transactions this one spends, all listed earlier in the
+    /// same summary
+    pub parents: Vec<[u8; 32]>,
+}
+
+/// Summarize a mempool for a peer: transactions paying at least
+/// `min_fee_rate`, highest fee rate first, each preceded by any in-mempool
+/// ancestors (whatever their fee rate) so the list is always parents first.
+/// Packages that would not fit in `max_entries` are left out whole.
+pub fn build_summary(
+    pool: impl IntoIterator<Item = (Transaction, u64, usize)>,
+    min_fee_rate: u64,
+    max_entries: usize,
+) -> Vec<MempoolSummaryEntry> {
+    let max_entries = max_entries.min(MAX_SUMMARY_ENTRIES);
+    let pool: HashMap<[u8; 32], (Transaction, u64, usize)> = pool
+        .into_iter()
+        .map(|(tx, fee_rate, size)| (tx.hash(), (tx, fee_rate, size)))
+        .collect();
+
+    let parents_of = |tx: &Transaction| -> Vec<[u8; 32]> {
+        let mut parents: Vec<[u8; 32]> = Vec::new();
+        for input in tx.inputs() {
+            let parent = input.prev_tx_hash();
+            if pool.contains_key(&parent) && !parents.contains(&parent) {
+                parents.push(parent);
+            }
+        }
+        parents
+    };
+
+    let mut by_fee: Vec<(&[u8; 32], u64)> = pool
+        .iter()
+        .filter(|(_, (_, fee_rate, _))| *fee_rate >= min_fee_rate)
+        .map(|(txid, (_, fee_rate, _))| (txid, *fee_rate))
+        .collect();
+    by_fee.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let mut included: HashSet<[u8; 32]> = HashSet::new();
+    let mut summary = Vec::new();
+    for (txid, _) in by_fee {
+        if included.contains(txid) {
+            continue;
+        }
+        // Collect the package in post-order: ancestors before descendants
+        let mut package: Vec<[u8; 32]> = Vec::new();
+        let mut seen: HashSet<[u8; 32]> = HashSet::new();
+        let mut stack = vec![(*txid, false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if expanded {
+                package.push(current);
+                continue;
+            }
+            if included.contains(&current) || !seen.insert(current) {
+                continue;
+            }
+            stack.push((current, true));
+            for parent in parents_of(&pool[&current].0) {
+                stack.push((parent, false));
+            }
+        }
+        if summary.len() + package.len() > max_entries {
+            continue;
+        }
+        for member in package {
+            let (tx, fee_rate, size) = &pool[&member];
+            let mut parents = parents_of(tx);
+            parents.truncate(MAX_SUMMARY_PARENTS);
+            summary.push(MempoolSummaryEntry {
+                txid: member,
+                fee_rate: *fee_rate,
+                size: (*size).min(u32::MAX as usize) as u32,
+                parents,
+            });
+            included.insert(member);
+        }
+    }
+    summary
+}
+
+/// Counters for the warm-up, logged when the window closes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MempoolSyncStats {
+    /// Summary requests sent to outbound peers
+    pub summaries_requested: u64,
+    /// Summaries received in answer to our requests
+    pub summaries_received: u64,
+    /// Transactions handed to the request manager
+    pub transactions_requested: u64,
+    /// Transactions admitted to the mempool
+    pub transactions_accepted: u64,
+    /// Transactions rejected by mempool policy, or whose parent was
+    pub transactions_rejected: u64,
+    /// Children that arrived before their parent and were admitted later
+    pub orphans_resolved: u64,
+    /// Transaction bytes fetched or reserved for fetching
+    pub bytes_fetched: u64,
+    /// Whether the byte budget stopped further requests
+    pub byte_cap_reached: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WantState {
+    /// Listed in a summary, not requested yet
+    Pending,
+    /// Handed to the request manager
+    Requested,
+    /// Arrived before a parent; waiting in the orphan pool
+    Held,
+    /// Handed back to the caller for admission
+    Validating,
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug)]
+struct Wanted {
+    fee_rate: u64,
+    size: u64,
+    parents: Vec<[u8; 32]>,
+    announcers: Vec<PeerId>,
+    state: WantState,
+    held: Option<Transaction>,
+}
+
+/// Warm-up state machine: which peers to ask, which transactions to fetch
+/// and in what order. Time is passed in so it can be driven without sleeping.
+#[derive(Debug)]
+pub struct MempoolWarmup {
+    config: MempoolSyncConfig,
+    /// Set when the node first reports itself synced
+    window_end: Option<Instant>,
+    /// Connected outbound peers, in connection order
+    outbound: Vec<PeerId>,
+    /// Peers asked for a summary, and whether they answered
+    asked: HashMap<PeerId, bool>,
+    next_request_at: Option<Instant>,
+    wanted: HashMap<[u8; 32], Wanted>,
+    /// Wanted txids in the order summaries listed them
+    order: Vec<[u8; 32]>,
+    children: HashMap<[u8; 32], Vec<[u8; 32]>>,
+    stats: MempoolSyncStats,
+}
+
+impl MempoolWarmup {
+    pub fn new(config: MempoolSyncConfig) -> Self {
+        Self {
+            config,
+            window_end: None,
+            outbound: Vec::new(),
+            asked: HashMap::new(),
+            next_request_at: None,
+            wanted: HashMap::new(),
+            order: Vec::new(),
+            children: HashMap::new(),
+            stats: MempoolSyncStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> &MempoolSyncConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> &MempoolSyncStats {
+        &self.stats
+    }
+
+    /// Whether the warm-up started and its window has closed
+    pub fn finished(&self, now: Instant) -> bool {
+        self.window_end.is_some_and(|end| now >= end)
+    }
+
+    pub fn peer_connected(&mut self, peer_id: PeerId, inbound: bool) {
+        if !inbound && !self.outbound.contains(&peer_id) {
+            self.outbound.push(peer_id);
+        }
+    }
+
+    /// Forget a disconnected peer. If it never answered, another outbound
+    /// peer is asked in its place.
+    pub fn peer_disconnected(&mut self, peer_id: &PeerId) {
+        self.outbound.retain(|p| p != peer_id);
+        if self.asked.get(peer_id) == Some(&false) {
+            self.asked.remove(peer_id);
+        }
+        for wanted in self.wanted.values_mut() {
+            wanted.announcers.retain(|p| p != peer_id);
+        }
+    }
+
+    /// The next summary request to send, if one is due. Nothing is asked
+    /// until `synced`; the first synced poll opens the warm-up window.
+    pub fn poll(&mut self, now: Instant, synced: bool) -> Option<(PeerId, Message)> {
+        if !self.config.enabled {
+            return None;
+        }
+        let window_end = match self.window_end {
+            Some(end) => end,
+            None if synced => *self.window_end.insert(now + self.config.window),
+            None => return None,
+        };
+        if now >= window_end || self.asked.len() >= self.config.peers {
+            return None;
+        }
+        if self.next_request_at.is_some_and(|at| now < at) {
+            return None;
+        }
+        let peer_id = *self
+            .outbound
+            .iter()
+            .find(|peer| !self.asked.contains_key(peer))?;
+        self.asked.insert(peer_id, false);
+        self.next_request_at = Some(now + self.config.stagger);
+        self.stats.summaries_requested += 1;
+        let max_entries = self.config.max_entries.min(MAX_SUMMARY_ENTRIES) as u32;
+        Some((
+            peer_id,
+            Message::GetMempoolSummary {
+                min_fee_rate: self.config.min_fee_rate,
+                max_entries,
+            },
+        ))
+    }
+
+    /// Record a peer's summary. Returns false if it was not asked for, or
+    /// came after the window closed, in which case it is ignored. `known`
+    /// reports transactions we already have.
+    pub fn on_summary(
+        &mut self,
+        peer_id: PeerId,
+        entries: &[MempoolSummaryEntry],
+        now: Instant,
+        known: impl Fn(&[u8; 32]) -> bool,
+    ) -> bool {
+        if self.asked.get(&peer_id) != Some(&false) || self.finished(now) {
+            return false;
+        }
+        self.asked.insert(peer_id, true);
+        self.stats.summaries_received += 1;
+
+        let max_entries = self.config.max_entries.min(MAX_SUMMARY_ENTRIES);
+        for entry in entries.iter().take(max_entries) {
+            if entry.fee_rate < self.config.min_fee_rate
+                || entry.size == 0
+                || entry.size as usize > MessageSizeLimits::MAX_TRANSACTION_SIZE
+                || known(&entry.txid)
+            {
+                continue;
+            }
+            if let Some(wanted) = self.wanted.get_mut(&entry.txid) {
+                if !wanted.announcers.contains(&peer_id) {
+                    wanted.announcers.push(peer_id);
+                }
+                continue;
+            }
+            let mut parents = entry.parents.clone();
+            parents.truncate(MAX_SUMMARY_PARENTS);
+            parents.retain(|parent| parent != &entry.txid);
+            for parent in &parents {
+                self.children.entry(*parent).or_default().push(entry.txid);
+            }
+            self.wanted.insert(
+                entry.txid,
+                Wanted {
+                    fee_rate: entry.fee_rate,
+                    size: entry.size as u64,
+                    parents,
+                    announcers: vec![peer_id],
+                    state: WantState::Pending,
+                    held: None,
+                },
+            );
+            self.order.push(entry.txid);
+        }
+        true
+    }
+
+    /// Transactions to fetch now, with every peer that listed them. A
+    /// transaction is released only once all of its listed parents have
+    /// been admitted, and only while the byte budget lasts.
+    pub fn take_requests(&mut self, now: Instant) -> Vec<(PeerId, [u8; 32])> {
+        let mut requests = Vec::new();
+        if self.finished(now) || self.stats.byte_cap_reached {
+            return requests;
+        }
+        for index in 0..self.order.len() {
+            let txid = self.order[index];
+            let Some(wanted) = self.wanted.get(&txid) else {
+                continue;
+            };
+            if wanted.state != WantState::Pending || wanted.announcers.is_empty() {
+                continue;
+            }
+            match self.parents_state(&wanted.parents) {
+                ParentsState::Unsettled => continue,
+                ParentsState::Rejected => {
+                    self.reject(&txid);
+                    continue;
+                }
+                ParentsState::Accepted => {}
+            }
+            if self.stats.bytes_fetched + wanted.size > self.config.max_bytes {
+                self.stats.byte_cap_reached = true;
+                break;
+            }
+            self.stats.bytes_fetched += wanted.size;
+            self.stats.transactions_requested += 1;
+            requests.extend(wanted.announcers.iter().map(|peer| (*peer, txid)));
+            if let Some(wanted) = self.wanted.get_mut(&txid) {
+                wanted.state = WantState::Requested;
+            }
+        }
+        // Only pending transactions can be released later
+        let wanted = &self.wanted;
+        self.order
+            .retain(|txid| wanted.get(txid).map(|w| w.state) == Some(WantState::Pending));
+        requests
+    }
+
+    /// Take charge of an arriving transaction. `Err` hands back transactions
+    /// the warm-up is not waiting for. `Ok(Some)` is ready for admission with
+    /// the fee rate its summary declared; `Ok(None)` means it was held until
+    /// a parent is admitted, or dropped because a parent was rejected.
+    #[allow(clippy::result_large_err)]
+    pub fn claim(
+        &mut self,
+        transaction: Transaction,
+        size: u64,
+    ) -> Result<Option<(Transaction, u64)>, Transaction> {
+        let txid = transaction.hash();
+        let Some(wanted) = self.wanted.get_mut(&txid) else {
+            return Err(transaction);
+        };
+        match wanted.state {
+            // Fetched by us: charge anything beyond the declared size
+            WantState::Requested => {
+                self.stats.bytes_fetched += size.saturating_sub(wanted.size);
+            }
+            // Relayed to us before we asked for it
+            WantState::Pending => {}
+            _ => return Err(transaction),
+        }
+        let parents = wanted.parents.clone();
+        match self.parents_state(&parents) {
+            ParentsState::Accepted => {
+                let wanted = self.wanted.get_mut(&txid).expect("checked above");
+                wanted.state = WantState::Validating;
+                Ok(Some((transaction, wanted.fee_rate)))
+            }
+            ParentsState::Unsettled => {
+                let wanted = self.wanted.get_mut(&txid).expect("checked above");
+                wanted.state = WantState::Held;
+                wanted.held = Some(transaction);
+                Ok(None)
+            }
+            ParentsState::Rejected => {
+                self.reject(&txid);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Record the admission outcome of a claimed transaction. Returns held
+    /// children that are now ready for admission.
+    pub fn resolve(&mut self, txid: &[u8; 32], accepted: bool) -> Vec<(Transaction, u64)> {
+        match self.wanted.get_mut(txid) {
+            Some(wanted) if wanted.state == WantState::Validating => {}
+            _ => return Vec::new(),
+        }
+        if !accepted {
+            self.reject(txid);
+            return Vec::new();
+        }
+        if let Some(wanted) = self.wanted.get_mut(txid) {
+            wanted.state = WantState::Accepted;
+        }
+        self.stats.transactions_accepted += 1;
+
+        let mut ready = Vec::new();
+        for child in self.children.get(txid).cloned().unwrap_or_default() {
+            let Some(wanted) = self.wanted.get(&child) else {
+                continue;
+            };
+            if wanted.state != WantState::Held
+                || self.parents_state(&wanted.parents) != ParentsState::Accepted
+            {
+                continue;
+            }
+            let wanted = self.wanted.get_mut(&child).expect("checked above");
+            if let Some(transaction) = wanted.held.take() {
+                wanted.state = WantState::Validating;
+                self.stats.orphans_resolved += 1;
+                ready.push((transaction, wanted.fee_rate));
+            }
+        }
+        ready
+    }
+
+    /// Mark a transaction and everything depending on it as rejected
+    fn reject(&mut self, txid: &[u8; 32]) {
+        let mut stack = vec![*txid];
+        while let Some(current) = stack.pop() {
+            let Some(wanted) = self.wanted.get_mut(&current) else {
+                continue;
+            };
+            if wanted.state == WantState::Rejected || wanted.state == WantState::Accepted {
+                continue;
+            }
+            wanted.state = WantState::Rejected;
+            wanted.held = None;
+            self.stats.transactions_rejected += 1;
+            if let Some(children) = self.children.get(&current) {
+                stack.extend(children.iter().copied());
+            }
+        }
+    }
+
+    fn parents_state(&self, parents: &[[u8; 32]]) -> ParentsState {
+        let mut state = ParentsState::Accepted;
+        for parent in parents {
+            match self.wanted.get(parent).map(|wanted| wanted.state) {
+                // Not from a summary: already ours, or left to normal relay
+                None | Some(WantState::Accepted) => {}
+                Some(WantState::Rejected) => return ParentsState::Rejected,
+                Some(_) => state = ParentsState::Unsettled,
+            }
+        }
+        state
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParentsState {
+    Accepted,
+    Unsettled,
+    Rejected,
+}
+
+/// Runs the warm-up against the live network and answers peers' summary
+/// and transaction requests from our mempool.
+pub struct MempoolSync {
+    warmup: parking_lot::Mutex<MempoolWarmup>,
+    mempool: Arc<TransactionPool>,
+    request_manager: Arc<Mutex<RequestManager>>,
+    sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+    command_tx: mpsc::Sender<NetworkCommand>,
+}
+
+impl MempoolSync {
+    pub fn new(
+        config: MempoolSyncConfig,
+        mempool: Arc<TransactionPool>,
+        request_manager: Arc<Mutex<RequestManager>>,
+        sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+        command_tx: mpsc::Sender<NetworkCommand>,
+    ) -> Self {
+        Self {
+            warmup: parking_lot::Mutex::new(MempoolWarmup::new(config)),
+            mempool,
+            request_manager,
+            sync_progress,
+            command_tx,
+        }
+    }
+
+    pub fn stats(&self) -> MempoolSyncStats {
+        self.warmup.lock().stats().clone()
+    }
+
+    pub fn peer_connected(&self, peer: &PeerInfo) {
+        self.warmup
+            .lock()
+            .peer_connected(peer.peer_id, peer.is_inbound);
+    }
+
+    pub fn peer_disconnected(&self, peer_id: &PeerId) {
+        self.warmup.lock().peer_disconnected(peer_id);
+    }
+
+    /// Handle a summary request, a summary, or a request for mempool
+    /// transactions. Returns false for any other message.
+    pub async fn handle_message(&self, peer_id: PeerId, message: &Message) -> bool {
+        match message {
+            Message::GetMempoolSummary {
+                min_fee_rate,
+                max_entries,
+            } => {
+                if !self.warmup.lock().config().enabled {
+                    debug!("Ignoring mempool summary request from {}", peer_id);
+                    return true;
+                }
+                let entries = build_summary(
+                    self.mempool.get_transactions_with_fee_rates(),
+                    *min_fee_rate,
+                    *max_entries as usize,
+                );
+                self.send(peer_id, Message::MempoolSummary { entries })
+                    .await;
+            }
+            Message::MempoolSummary { entries } => {
+                let accepted =
+                    self.warmup
+                        .lock()
+                        .on_summary(peer_id, entries, Instant::now(), |txid| {
+                            self.mempool.get_transaction(txid).is_some()
+                        });
+                if accepted {
+                    debug!(
+                        "Mempool summary of {} txids from {}",
+                        entries.len(),
+                        peer_id
+                    );
+                    self.request_released();
+                } else {
+                    debug!("Ignoring unsolicited mempool summary from {}", peer_id);
+                }
+            }
+            Message::GetData(hashes) => {
+                for hash in hashes {
+                    let Some(tx) = self.mempool.get_transaction(hash) else {
+                        continue;
+                    };
+                    match bincode::serialize(&tx) {
+                        Ok(transaction) => {
+                            self.send(peer_id, Message::Transaction { transaction })
+                                .await
+                        }
+                        Err(e) => warn!("Failed to encode transaction for {}: {}", peer_id, e),
+                    }
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// See [`MempoolWarmup::claim`].
+    #[allow(clippy::result_large_err)]
+    pub fn claim(
+        &self,
+        transaction: Transaction,
+    ) -> Result<Option<(Transaction, u64)>, Transaction> {
+        let size = bincode::serialized_size(&transaction).unwrap_or(0);
+        self.warmup.lock().claim(transaction, size)
+    }
+
+    /// See [`MempoolWarmup::resolve`]. Transactions whose parents are now
+    /// in the mempool are handed to the request manager.
+    pub fn resolve(&self, txid: &[u8; 32], accepted: bool) -> Vec<(Transaction, u64)> {
+        let ready = self.warmup.lock().resolve(txid, accepted);
+        self.request_released();
+        ready
+    }
+
+    /// Ask the next outbound peer for its summary every `stagger` until
+    /// enough peers were asked or the window closes.
+    pub async fn run(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let synced = self
+                .sync_progress
+                .lock()
+                .latest()
+                .map(|progress| progress.synced)
+                .unwrap_or(false);
+            let now = Instant::now();
+            let request = {
+                let mut warmup = self.warmup.lock();
+                if !warmup.config().enabled {
+                    return;
+                }
+                if warmup.finished(now) {
+                    info!("Mempool warm-up finished: {:?}", warmup.stats());
+                    return;
+                }
+                warmup.poll(now, synced)
+            };
+            if let Some((peer_id, message)) = request {
+                debug!("Requesting mempool summary from {}", peer_id);
+                self.send(peer_id, message).await;
+            }
+        }
+    }
+
+    fn request_released(&self) {
+        let now = Instant::now();
+        let requests = self.warmup.lock().take_requests(now);
+        if requests.is_empty() {
+            return;
+        }
+        if let Ok(mut manager) = self.request_manager.lock() {
+            for (peer_id, txid) in requests {
+                manager.announce(peer_id, ObjectId::transaction(txid), now);
+            }
+        }
+    }
+
+    async fn send(&self, peer_id: PeerId, message: Message) {
+        if let Err(e) = self
+            .command_tx
+            .send(NetworkCommand::SendToPeer { peer_id, message })
+            .await
+        {
+            warn!(
+                "Failed to queue mempool sync message for {}: {}",
+                peer_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+
+    /// Transactions in one simulated mempool: `(tx, fee rate, size)`
+    type Pool = Vec<(Transaction, u64, usize)>;
+
+    fn config() -> MempoolSyncConfig {
+        MempoolSyncConfig {
+            enabled: true,
+            peers: 3,
+            min_fee_rate: 1,
+            max_entries: MAX_SUMMARY_ENTRIES,
+            max_bytes: 64 * 1024 * 1024,
+            stagger: Duration::from_secs(2),
+            window: Duration::from_secs(600),
+        }
+    }
+
+    fn tx(seed: u64, parent: Option<[u8; 32]>) -> Transaction {
+        let prev = parent.unwrap_or_else(|| {
+            let mut hash = [0u8; 32];
+            hash[..8].copy_from_slice(&seed.to_le_bytes());
+            hash[31] = 0xee;
+            hash
+        });
+        Transaction::new(
+            1,
+            vec![TransactionInput::new(prev, 0, vec![], 0xffffffff)],
+            vec![TransactionOutput::new(1_000 + seed, vec![seed as u8; 32])],
+            0,
+        )
+    }
+
+    fn entry(tx: &Transaction, fee_rate: u64) -> (Transaction, u64, usize) {
+        let size = bincode::serialized_size(tx).unwrap() as usize;
+        (tx.clone(), fee_rate, size)
+    }
+
+    /// A shared network mempool of `count` transactions in chains of four,
+    /// with each peer missing about 5% of the chain roots (and so their
+    /// descendants, since a mempool never holds a child without its parent).
+    fn network(count: u64, peers: usize) -> (Pool, Vec<Pool>) {
+        let mut all: Pool = Vec::new();
+        for seed in 0..count {
+            let parent = (seed % 4 != 0).then(|| all.last().unwrap().0.hash());
+            let tx = tx(seed, parent);
+            all.push(entry(&tx, 1 + seed % 50));
+        }
+        let views = (0..peers)
+            .map(|peer| {
+                all.iter()
+                    .enumerate()
+                    .filter(|(i, _)| (i / 4 + peer * 7) % 20 != 0)
+                    .map(|(_, e)| e.clone())
+                    .collect()
+            })
+            .collect();
+        (all, views)
+    }
+
+    /// A restarted node warming up from `peers`, with mempool admission
+    /// reduced to "all parents are present". Transactions are delivered in
+    /// reverse request order, so children routinely arrive first.
+    struct Restarted {
+        warmup: MempoolWarmup,
+        mempool: HashMap<[u8; 32], Transaction>,
+        peers: Vec<(PeerId, Pool)>,
+        delivered_bytes: u64,
+    }
+
+    impl Restarted {
+        fn new(config: MempoolSyncConfig, views: Vec<Pool>) -> Self {
+            let mut warmup = MempoolWarmup::new(config);
+            let peers: Vec<(PeerId, Pool)> =
+                views.into_iter().map(|v| (PeerId::random(), v)).collect();
+            for (peer_id, _) in &peers {
+                warmup.peer_connected(*peer_id, false);
+            }
+            Self {
+                warmup,
+                mempool: HashMap::new(),
+                peers,
+                delivered_bytes: 0,
+            }
+        }
+
+        fn admit(&mut self, tx: Transaction, spends: &HashSet<[u8; 32]>) -> bool {
+            let ok = tx.inputs().iter().all(|input| {
+                let prev = input.prev_tx_hash();
+                !spends.contains(&prev) || self.mempool.contains_key(&prev)
+            });
+            if ok {
+                self.mempool.insert(tx.hash(), tx);
+            }
+            ok
+        }
+
+        fn deliver(&mut self, tx: Transaction, spends: &HashSet<[u8; 32]>) {
+            self.delivered_bytes += bincode::serialized_size(&tx).unwrap();
+            let mut ready: Vec<(Transaction, u64)> = match self.warmup.claim(tx.clone(), 0) {
+                Ok(ready) => ready.into_iter().collect(),
+                Err(tx) => {
+                    self.admit(tx, spends);
+                    return;
+                }
+            };
+            while let Some((tx, _)) = ready.pop() {
+                let txid = tx.hash();
+                let accepted = self.admit(tx, spends);
+                ready.extend(self.warmup.resolve(&txid, accepted));
+            }
+        }
+
+        /// Run the warm-up for `secs` simulated seconds
+        fn run(&mut self, start: Instant, secs: u64, spends: &HashSet<[u8; 32]>) {
+            for second in 0..secs {
+                let now = start + Duration::from_secs(second);
+                if let Some((peer_id, message)) = self.warmup.poll(now, true) {
+                    let Message::GetMempoolSummary {
+                        min_fee_rate,
+                        max_entries,
+                    } = message
+                    else {
+                        panic!("unexpected request {:?}", message);
+                    };
+                    let view = &self.peers.iter().find(|(p, _)| *p == peer_id).unwrap().1;
+                    let summary = build_summary(view.clone(), min_fee_rate, max_entries as usize);
+                    let mempool = &self.mempool;
+                    assert!(self.warmup.on_summary(peer_id, &summary, now, |txid| {
+                        mempool.contains_key(txid)
+                    }));
+                }
+                // Each wave of requests is answered by the first announcer,
+                // last request first
+                let mut requests = self.warmup.take_requests(now);
+                let mut seen = HashSet::new();
+                requests.retain(|(_, txid)| seen.insert(*txid));
+                for (peer_id, txid) in requests.into_iter().rev() {
+                    let view = &self.peers.iter().find(|(p, _)| *p == peer_id).unwrap().1;
+                    let tx = view
+                        .iter()
+                        .find(|(t, _, _)| t.hash() == txid)
+                        .unwrap()
+                        .0
+                        .clone();
+                    self.deliver(tx, spends);
+                }
+            }
+        }
+    }
+
+    fn overlap(local: &HashMap<[u8; 32], Transaction>, peer: &Pool) -> f64 {
+        let present = peer
+            .iter()
+            .filter(|(tx, _, _)| local.contains_key(&tx.hash()))
+            .count();
+        present as f64 / peer.len() as f64
+    }
+
+    fn spends(all: &Pool) -> HashSet<[u8; 32]> {
+        all.iter().map(|(tx, _, _)| tx.hash()).collect()
+    }
+
+    #[test]
+    fn summary_lists_parents_first() {
+        let parent = tx(1, None);
+        let child = tx(2, Some(parent.hash()));
+        // The child pays more, but its low-fee parent must precede it
+        let pool = vec![
+            entry(&child, 40),
+            entry(&parent, 1),
+            entry(&tx(3, None), 20),
+        ];
+        let summary = build_summary(pool.clone(), 10, 10);
+
+        let order: Vec<_> = summary.iter().map(|e| e.txid).collect();
+        assert_eq!(order, [parent.hash(), child.hash(), tx(3, None).hash()]);
+        assert_eq!(summary[1].parents, [parent.hash()]);
+
+        // A package that does not fit is left out whole
+        let capped = build_summary(pool, 10, 1);
+        assert_eq!(capped.len(), 1);
+        assert_eq!(capped[0].txid, tx(3, None).hash());
+    }
+
+    #[test]
+    fn restarted_node_reaches_peer_overlap_within_window() {
+        let (all, views) = network(600, 4);
+        let spends = spends(&all);
+        let mut node = Restarted::new(config(), views.clone());
+        let start = Instant::now();
+
+        node.run(start, 30, &spends);
+
+        // Three of the four outbound peers were asked, staggered
+        assert_eq!(node.warmup.stats().summaries_requested, 3);
+        for view in &views {
+            assert!(
+                overlap(&node.mempool, view) >= 0.9,
+                "overlap {}",
+                overlap(&node.mempool, view)
+            );
+        }
+        assert_eq!(node.warmup.stats().transactions_rejected, 0);
+    }
+
+    #[test]
+    fn requests_are_staggered_and_wait_for_ibd() {
+        let (_, views) = network(10, 3);
+        let mut node = Restarted::new(config(), views);
+        let start = Instant::now();
+
+        assert!(node.warmup.poll(start, false).is_none());
+        assert!(node.warmup.poll(start, true).is_some());
+        assert!(node
+            .warmup
+            .poll(start + Duration::from_secs(1), true)
+            .is_none());
+        assert!(node
+            .warmup
+            .poll(start + Duration::from_secs(2), true)
+            .is_some());
+        // Nothing is asked once the window has closed
+        assert!(node
+            .warmup
+            .poll(start + Duration::from_secs(600), true)
+            .is_none());
+    }
+
+    #[test]
+    fn only_outbound_peers_are_asked_and_unsolicited_summaries_ignored() {
+        let mut warmup = MempoolWarmup::new(config());
+        let inbound = PeerId::random();
+        warmup.peer_connected(inbound, true);
+        let start = Instant::now();
+        assert!(warmup.poll(start, true).is_none());
+
+        let summary = build_summary(vec![entry(&tx(1, None), 5)], 1, 10);
+        assert!(!warmup.on_summary(inbound, &summary, start, |_| false));
+        assert!(warmup.take_requests(start).is_empty());
+    }
+
+    #[test]
+    fn byte_cap_is_respected() {
+        let (all, views) = network(600, 3);
+        let spends = spends(&all);
+        let cap = 20_000;
+        let mut node = Restarted::new(
+            MempoolSyncConfig {
+                max_bytes: cap,
+                ..config()
+            },
+            views,
+        );
+
+        node.run(Instant::now(), 30, &spends);
+
+        let stats = node.warmup.stats();
+        assert!(stats.byte_cap_reached);
+        assert!(stats.bytes_fetched <= cap);
+        assert!(node.delivered_bytes <= cap);
+        assert!(!node.mempool.is_empty());
+    }
+
+    #[test]
+    fn children_arriving_first_resolve_through_orphan_pool() {
+        let parent = tx(1, None);
+        let child = tx(2, Some(parent.hash()));
+        let grandchild = tx(3, Some(child.hash()));
+        let pool = vec![entry(&parent, 5), entry(&child, 5), entry(&grandchild, 5)];
+        let summary = build_summary(pool, 1, 10);
+
+        let mut warmup = MempoolWarmup::new(config());
+        let peer = PeerId::random();
+        warmup.peer_connected(peer, false);
+        let now = Instant::now();
+        warmup.poll(now, true).unwrap();
+        assert!(warmup.on_summary(peer, &summary, now, |_| false));
+
+        // Only the parent is requested until it is admitted
+        assert_eq!(warmup.take_requests(now), [(peer, parent.hash())]);
+
+        // The descendants are relayed to us first and wait
+        assert!(matches!(warmup.claim(grandchild.clone(), 0), Ok(None)));
+        assert!(matches!(warmup.claim(child.clone(), 0), Ok(None)));
+        match warmup.claim(parent.clone(), 0) {
+            Ok(Some((ready, _))) => assert_eq!(ready.hash(), parent.hash()),
+            _ => panic!("parent should be ready for admission"),
+        }
+
+        let released = warmup.resolve(&parent.hash(), true);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].0.hash(), child.hash());
+        let released = warmup.resolve(&child.hash(), true);
+        assert_eq!(released[0].0.hash(), grandchild.hash());
+        assert!(warmup.resolve(&grandchild.hash(), true).is_empty());
+        assert_eq!(warmup.stats().orphans_resolved, 2);
+        assert_eq!(warmup.stats().transactions_accepted, 3);
+        assert!(warmup.take_requests(now).is_empty());
+    }
+
+    #[test]
+    fn rejected_parent_drops_held_children() {
+        let parent = tx(1, None);
+        let child = tx(2, Some(parent.hash()));
+        let summary = build_summary(vec![entry(&parent, 5), entry(&child, 5)], 1, 10);
+
+        let mut warmup = MempoolWarmup::new(config());
+        let peer = PeerId::random();
+        warmup.peer_connected(peer, false);
+        let now = Instant::now();
+        warmup.poll(now, true).unwrap();
+        warmup.on_summary(peer, &summary, now, |_| false);
+        warmup.take_requests(now);
+
+        assert!(matches!(warmup.claim(child.clone(), 0), Ok(None)));
+        assert!(matches!(warmup.claim(parent.clone(), 0), Ok(Some(_))));
+        assert!(warmup.resolve(&parent.hash(), false).is_empty());
+        assert_eq!(warmup.stats().transactions_rejected, 2);
+        // Neither is handed out again
+        assert!(warmup.claim(child, 0).is_err());
+    }
+}
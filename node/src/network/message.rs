@@ -1,4 +1,7 @@
+use crate::network::bloom_filter::{MAX_FILTER_BYTES, MAX_FILTER_ELEMENT_BYTES, MAX_HASH_FUNCS};
+use crate::network::discovery::addrman::MAX_ADDR_PER_MESSAGE;
 use crate::network::protocol::{Message as ProtocolMessage, PublishError};
+use crate::network::mempool_sync::{MAX_SUMMARY_ENTRIES, MAX_SUMMARY_PARENTS};
 use blake3;
 use libp2p::{gossipsub, PeerId};
 use std::{
@@ -356,6 +359,50 @@ impl MessageHandler {
                     return Err(format!("Too many block hashes: {} (max: 500)", block_hashes.len()));
                 }
             }
+            ProtocolMessage::FilterLoad {
+                filter,
+                hash_count,
+                ..
+            } => {
+                if filter.len() > MAX_FILTER_BYTES {
+                    return Err(format!(
+                        "Filter too large: {} bytes (max: {})",
+                        filter.len(),
+                        MAX_FILTER_BYTES
+                    ));
+                }
+                if *hash_count > MAX_HASH_FUNCS {
+                    return Err(format!(
+                        "Too many filter hash functions: {} (max: {})",
+                        hash_count, MAX_HASH_FUNCS
+                    ));
+                }
+            }
+            ProtocolMessage::FilterAdd { element } => {
+                if element.len() > MAX_FILTER_ELEMENT_BYTES {
+                    return Err(format!(
+                        "Filter element too large: {} bytes (max: {})",
+                        element.len(),
+                        MAX_FILTER_ELEMENT_BYTES
+                    ));
+                }
+            }
+            ProtocolMessage::MerkleBlock(merkle_block) => {
+                if merkle_block.indices.len() != merkle_block.transactions.len()
+                    || merkle_block.indices.len() > merkle_block.total_transactions as usize
+                {
+                    return Err("MerkleBlock transaction count mismatch".to_string());
+                }
+            }
+            ProtocolMessage::Addr(addrs) => {
+                if addrs.len() > MAX_ADDR_PER_MESSAGE {
+                    return Err(format!(
+                        "Too many Addr entries: {} (max: {})",
+                        addrs.len(),
+                        MAX_ADDR_PER_MESSAGE
+                    ));
+                }
+            }
             ProtocolMessage::GetData(hashes) => {
                 if hashes.is_empty() {
                     return Err("GetData has no hashes".to_string());
@@ -366,6 +413,29 @@ impl MessageHandler {
                 // Note: hashes are Vec<[u8; 32]>, so each hash is guaranteed to be 32 bytes
                 // Fixed-size arrays ensure correct length, no need to validate individually
             }
+            ProtocolMessage::NotFound { block_hashes } => {
+                if block_hashes.len() > 500 {
+                    return Err(format!("Too many NotFound hashes: {} (max: 500)", block_hashes.len()));
+                }
+            }
+            ProtocolMessage::MempoolSummary { entries } => {
+                if entries.len() > MAX_SUMMARY_ENTRIES {
+                    return Err(format!("Too many mempool summary entries: {} (max: {})", entries.len(), MAX_SUMMARY_ENTRIES));
+                }
+                if entries.iter().any(|entry| entry.parents.len() > MAX_SUMMARY_PARENTS) {
+                    return Err(format!("Mempool summary entry lists more than {} parents", MAX_SUMMARY_PARENTS));
+                }
+            }
+            ProtocolMessage::SnapshotChunk { data, .. } => {
+                if data.len() > MessageSizeLimits::MAX_MESSAGE_SIZE {
+                    return Err(format!("Snapshot chunk too large: {} bytes", data.len()));
+                }
+            }
+            ProtocolMessage::Encrypted(frame) => {
+                if frame.ciphertext.len() > MessageSizeLimits::MAX_MESSAGE_SIZE {
+                    return Err(format!("Encrypted frame too large: {} bytes", frame.ciphertext.len()));
+                }
+            }
             ProtocolMessage::GetHeaders {
                 start_height,
                 end_height,
@@ -385,6 +455,14 @@ impl MessageHandler {
                 // Note: best_hash is [u8; 32], a fixed-size array guaranteed to be 32 bytes
                 // No need to validate length, deserialization ensures correctness
             }
+            ProtocolMessage::Version(version) => {
+                if version.user_agent.len() > 256 {
+                    return Err(format!(
+                        "Version user agent too long: {} bytes (max: 256)",
+                        version.user_agent.len()
+                    ));
+                }
+            }
             ProtocolMessage::Extension(name, payload) => {
                 // Validate extension message name is not empty
                 if name.is_empty() {
@@ -401,7 +479,6 @@ impl MessageHandler {
             }
             ProtocolMessage::Ping(_) | ProtocolMessage::Pong(_) | ProtocolMessage::GetStatus 
             | ProtocolMessage::Verack | ProtocolMessage::GetAddr
-            | ProtocolMessage::Version(_) | ProtocolMessage::Addr(_)
             | ProtocolMessage::Environmental(_) | ProtocolMessage::Lightning(_)
             | ProtocolMessage::NewBlock { .. } | ProtocolMessage::GetBlocksByHeight { .. }
             | ProtocolMessage::BroadcastTransaction(_) | ProtocolMessage::TransactionAnnouncement { .. }
@@ -410,9 +487,12 @@ impl MessageHandler {
             | ProtocolMessage::CompactBlock(_)
             | ProtocolMessage::GetCompactBlockTxs { .. }
             | ProtocolMessage::CompactBlockTxs(_)
-            | ProtocolMessage::FilterLoad { .. }
-            | ProtocolMessage::FilterAdd { .. }
-            | ProtocolMessage::FilterClear => {
+            | ProtocolMessage::FilterClear
+            | ProtocolMessage::Busy { .. }
+            | ProtocolMessage::GetMempoolSummary { .. }
+            | ProtocolMessage::SnapshotRequest { .. }
+            | ProtocolMessage::KemOffer(_)
+            | ProtocolMessage::KemAccept(_) => {
                 // Simple messages or messages with validation handled elsewhere
                 // No additional validation needed at this layer
             }
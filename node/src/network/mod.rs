@@ -1,22 +1,39 @@
+pub mod address_advertisement;
 pub mod advanced;
+pub mod bandwidth;
 pub mod behaviour;
 pub mod block_propagation;
+pub mod block_serving;
 pub mod bloom_filter;
 pub mod compact_block;
 pub mod connection;
 pub mod peer_identity;
 pub mod discovery;
+pub mod dns_seed;
 pub mod eclipse_prevention;
+pub mod encryption;
+pub mod framing;
+pub mod handshake;
+pub mod identity_rotation;
 pub mod identity_verification;
+pub mod inventory;
+pub mod keepalive;
+pub mod mempool_sync;
 pub mod message;
+pub mod nat;
 pub mod network_proxy;
+pub mod operator_messages;
 pub mod p2p;
 pub mod peer;
 pub mod peer_diversity;
 pub mod peer_manager;
 pub mod protocol;
 pub mod rate_limiter;
+pub mod relay_policy;
+pub mod request_manager;
+pub mod snapshot_sync;
 pub mod sync;
+pub mod sync_progress;
 
 #[cfg(test)]
 pub mod eclipse_prevention_tests;
@@ -32,16 +49,23 @@ use tracing::{debug, error, info, warn};
 
 // Re-export network types for external use
 pub use behaviour::SupernovaBehaviour;
+pub use block_serving::{BlockServer, BlockServingStats};
 pub use connection::ConnectionState;
+pub use discovery::address_book::AddressBook;
 pub use discovery::DiscoveryEvent;
 pub use message::NetworkMessage;
+pub use mempool_sync::{MempoolSync, MempoolSyncStats};
 pub use network_proxy::NetworkProxy;
+pub use operator_messages::{OperatorMessage, OperatorMessenger};
 pub use p2p::{
     NetworkCommand, NetworkEvent, NetworkHealth, NetworkStats as P2PNetworkStats, P2PNetwork,
 };
 pub use peer::{PeerInfo, PeerMetadata, PeerState};
 pub use protocol::{Message as ProtocolMessage, ProtocolError};
 pub use rate_limiter::{NetworkRateLimiter, RateLimitConfig, RateLimitError};
+pub use relay_policy::{NodeRole, RelayPolicy, TxAnnouncement, TxOrigin};
+pub use snapshot_sync::SnapshotSync;
+pub use sync_progress::{SyncProgress, SyncProgressTracker};
 
 /// Maximum number of peers to connect to
 pub const MAX_PEERS: usize = 50;
@@ -71,7 +95,7 @@ pub async fn initialize_network(
     Box<dyn std::error::Error>,
 > {
     // Load or generate persistent node identity
-    let data_dir = std::path::PathBuf::from("./data");
+    let data_dir = std::path::PathBuf::from(peer_identity::DEFAULT_IDENTITY_DIR);
     let keypair = peer_identity::load_or_generate_keypair(&data_dir)
         .map_err(|e| format!("Failed to load peer identity: {}", e))?;
 
@@ -85,6 +109,9 @@ pub async fn initialize_network(
             Some(config.pubsub_config.validation_mode.clone()), // Gossipsub validation mode
         ).await?;
 
+    network.configure_keepalive(keepalive::KeepaliveConfig::from_network_config(config));
+    network.configure_addresses(config)?;
+
     // Add bootstrap nodes if configured
     if !config.bootstrap_nodes.is_empty() {
         info!("Loading {} bootstrap nodes from config: {:?}", 
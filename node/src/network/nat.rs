@@ -0,0 +1,540 @@
+//! NAT traversal for the P2P listener
+//!
+//! Nodes behind a home router only accept inbound connections if the router
+//! forwards the listen port. With `network.enable_upnp`, [`PortMapping`] asks
+//! the gateway for a mapping over UPnP IGD, falling back to NAT-PMP when
+//! `network.nat_pmp_gateway` is set, advertises the resulting public address,
+//! renews the lease at half its length and removes the mapping on shutdown.
+//! When no gateway cooperates the node simply stays listen-only.
+//!
+//! Gateways are reached through the [`PortMapper`] trait so the mapping
+//! logic can be exercised without a router.
+
+use crate::config::NetworkConfig;
+use crate::network::address_advertisement::is_routable;
+use async_trait::async_trait;
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::UdpSocket;
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+
+/// Description attached to UPnP mappings
+const MAPPING_DESCRIPTION: &str = "supernova p2p";
+
+/// How long to search for a UPnP gateway
+const GATEWAY_SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// NAT-PMP server port on the gateway (RFC 6886)
+const NAT_PMP_PORT: u16 = 5351;
+
+/// NAT-PMP requests are retried with doubling timeouts from this value
+const NAT_PMP_INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+const NAT_PMP_ATTEMPTS: u32 = 4;
+
+/// Retry a failed mapping this often while listen-only
+const RETRY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Error)]
+pub enum NatError {
+    #[error("No gateway found: {0}")]
+    GatewayNotFound(String),
+    #[error("Gateway refused the mapping: {0}")]
+    MappingRefused(String),
+    #[error("Gateway reports non-routable external address {0}; is there a second NAT?")]
+    NonRoutable(IpAddr),
+    #[error("Malformed gateway response: {0}")]
+    Protocol(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// A gateway that can forward a TCP port to this host
+#[async_trait]
+pub trait PortMapper: Send + Sync {
+    /// Mechanism name for logs and status, e.g. `upnp`
+    fn name(&self) -> &'static str;
+
+    /// Forward TCP `port` on the gateway to `port` on this host for `lease`.
+    /// Returns the public address peers can dial, whose port may differ.
+    async fn map_port(&self, port: u16, lease: Duration) -> Result<SocketAddr, NatError>;
+
+    /// Remove the mapping of local `port` to public `external_port`
+    async fn unmap_port(&self, port: u16, external_port: u16) -> Result<(), NatError>;
+}
+
+/// UPnP Internet Gateway Device mapper
+#[derive(Default)]
+pub struct UpnpMapper;
+
+impl UpnpMapper {
+    async fn gateway(
+        &self,
+    ) -> Result<igd_next::aio::Gateway<igd_next::aio::tokio::Tokio>, NatError> {
+        let options = igd_next::SearchOptions {
+            timeout: Some(GATEWAY_SEARCH_TIMEOUT),
+            ..Default::default()
+        };
+        igd_next::aio::tokio::search_gateway(options)
+            .await
+            .map_err(|e| NatError::GatewayNotFound(e.to_string()))
+    }
+}
+
+#[async_trait]
+impl PortMapper for UpnpMapper {
+    fn name(&self) -> &'static str {
+        "upnp"
+    }
+
+    async fn map_port(&self, port: u16, lease: Duration) -> Result<SocketAddr, NatError> {
+        let gateway = self.gateway().await?;
+        let external_ip = gateway
+            .get_external_ip()
+            .await
+            .map_err(|e| NatError::Protocol(e.to_string()))?;
+        if !is_routable(external_ip) {
+            return Err(NatError::NonRoutable(external_ip));
+        }
+        let local_ip = local_ip_towards(gateway.addr.ip()).await?;
+        gateway
+            .add_port(
+                igd_next::PortMappingProtocol::TCP,
+                port,
+                SocketAddr::new(local_ip, port),
+                lease.as_secs().min(u32::MAX as u64) as u32,
+                MAPPING_DESCRIPTION,
+            )
+            .await
+            .map_err(|e| NatError::MappingRefused(e.to_string()))?;
+        Ok(SocketAddr::new(external_ip, port))
+    }
+
+    async fn unmap_port(&self, _port: u16, external_port: u16) -> Result<(), NatError> {
+        self.gateway()
+            .await?
+            .remove_port(igd_next::PortMappingProtocol::TCP, external_port)
+            .await
+            .map_err(|e| NatError::MappingRefused(e.to_string()))
+    }
+}
+
+/// NAT-PMP (RFC 6886) mapper for a known gateway
+pub struct NatPmpMapper {
+    gateway: SocketAddr,
+}
+
+impl NatPmpMapper {
+    pub fn new(gateway: Ipv4Addr) -> Self {
+        Self {
+            gateway: SocketAddr::new(IpAddr::V4(gateway), NAT_PMP_PORT),
+        }
+    }
+
+    /// Send `request` and wait for a response to `opcode`, retrying with
+    /// doubling timeouts
+    async fn request(&self, request: &[u8], opcode: u8) -> Result<Vec<u8>, NatError> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).await?;
+        socket.connect(self.gateway).await?;
+        let mut timeout = NAT_PMP_INITIAL_TIMEOUT;
+        let mut buf = [0u8; 16];
+        for _ in 0..NAT_PMP_ATTEMPTS {
+            socket.send(request).await?;
+            if let Ok(received) = tokio::time::timeout(timeout, socket.recv(&mut buf)).await {
+                let len = received?;
+                return parse_nat_pmp_response(&buf[..len], opcode);
+            }
+            timeout *= 2;
+        }
+        Err(NatError::GatewayNotFound(format!(
+            "no NAT-PMP response from {}",
+            self.gateway
+        )))
+    }
+
+    async fn mapping_request(
+        &self,
+        internal_port: u16,
+        external_port: u16,
+        lifetime: u32,
+    ) -> Result<Vec<u8>, NatError> {
+        let mut request = vec![0, 2, 0, 0];
+        request.extend_from_slice(&internal_port.to_be_bytes());
+        request.extend_from_slice(&external_port.to_be_bytes());
+        request.extend_from_slice(&lifetime.to_be_bytes());
+        self.request(&request, 2).await
+    }
+}
+
+#[async_trait]
+impl PortMapper for NatPmpMapper {
+    fn name(&self) -> &'static str {
+        "natpmp"
+    }
+
+    async fn map_port(&self, port: u16, lease: Duration) -> Result<SocketAddr, NatError> {
+        let response = self.request(&[0, 0], 0).await?;
+        if response.len() < 12 {
+            return Err(NatError::Protocol("short address response".to_string()));
+        }
+        let external_ip = IpAddr::V4(Ipv4Addr::new(
+            response[8],
+            response[9],
+            response[10],
+            response[11],
+        ));
+        if !is_routable(external_ip) {
+            return Err(NatError::NonRoutable(external_ip));
+        }
+        let lifetime = lease.as_secs().min(u32::MAX as u64) as u32;
+        let response = self.mapping_request(port, port, lifetime).await?;
+        if response.len() < 16 {
+            return Err(NatError::Protocol("short mapping response".to_string()));
+        }
+        let external_port = u16::from_be_bytes([response[10], response[11]]);
+        Ok(SocketAddr::new(external_ip, external_port))
+    }
+
+    async fn unmap_port(&self, port: u16, _external_port: u16) -> Result<(), NatError> {
+        // Deletion is keyed by internal port, with external port and
+        // lifetime zero
+        self.mapping_request(port, 0, 0).await.map(|_| ())
+    }
+}
+
+/// Check a NAT-PMP response header: version 0, the request opcode + 128,
+/// result code 0
+fn parse_nat_pmp_response(response: &[u8], opcode: u8) -> Result<Vec<u8>, NatError> {
+    if response.len() < 4 || response[0] != 0 || response[1] != opcode + 128 {
+        return Err(NatError::Protocol(format!(
+            "unexpected NAT-PMP response {:02x?}",
+            &response[..response.len().min(4)]
+        )));
+    }
+    match u16::from_be_bytes([response[2], response[3]]) {
+        0 => Ok(response.to_vec()),
+        code => Err(NatError::MappingRefused(format!(
+            "NAT-PMP result code {}",
+            code
+        ))),
+    }
+}
+
+/// Our address on the interface facing `gateway`
+async fn local_ip_towards(gateway: IpAddr) -> Result<IpAddr, NatError> {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).await?;
+    socket.connect((gateway, 1)).await?;
+    Ok(socket.local_addr()?.ip())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MappingState {
+    /// `network.enable_upnp` is off
+    #[default]
+    Disabled,
+    /// Not attempted yet
+    Pending,
+    Mapped,
+    /// No gateway cooperated; listening only
+    Failed,
+}
+
+/// Port mapping state as reported in network health
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct NatStatus {
+    pub state: MappingState,
+    /// Mechanism that holds the mapping, `upnp` or `natpmp`
+    pub protocol: Option<String>,
+    /// Public address advertised to peers
+    pub external_addr: Option<String>,
+    /// Last time the lease was granted or renewed, Unix seconds
+    pub renewed_at: Option<u64>,
+    /// Requested lease length in seconds
+    pub lease_secs: u64,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+}
+
+struct ActiveMapping {
+    mapper: Arc<dyn PortMapper>,
+    external: SocketAddr,
+}
+
+/// Keeps the listen port mapped on the gateway
+pub struct PortMapping {
+    mappers: Vec<Arc<dyn PortMapper>>,
+    port: u16,
+    lease: Duration,
+    active: tokio::sync::Mutex<Option<ActiveMapping>>,
+    status: parking_lot::Mutex<NatStatus>,
+}
+
+impl PortMapping {
+    /// Try `mappers` in order to forward `port`
+    pub fn new(mappers: Vec<Arc<dyn PortMapper>>, port: u16, lease: Duration) -> Self {
+        Self {
+            mappers,
+            port,
+            lease,
+            active: tokio::sync::Mutex::new(None),
+            status: parking_lot::Mutex::new(NatStatus {
+                state: MappingState::Pending,
+                lease_secs: lease.as_secs(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// UPnP, then NAT-PMP if a gateway is configured. `None` when
+    /// `enable_upnp` is off or we do not listen on TCP.
+    pub fn from_config(config: &NetworkConfig) -> Option<Self> {
+        if !config.enable_upnp {
+            return None;
+        }
+        let port = config.listen_ports().ok()?.into_iter().next()?;
+        let mut mappers: Vec<Arc<dyn PortMapper>> = vec![Arc::new(UpnpMapper)];
+        if let Some(gateway) = config.nat_pmp_gateway {
+            mappers.push(Arc::new(NatPmpMapper::new(gateway)));
+        }
+        Some(Self::new(mappers, port, config.upnp_lease))
+    }
+
+    pub fn status(&self) -> NatStatus {
+        self.status.lock().clone()
+    }
+
+    /// Renew the current mapping, or create one. Returns the public address
+    /// to advertise, `None` if no gateway cooperated.
+    pub async fn refresh(&self) -> Option<Multiaddr> {
+        let mut active = self.active.lock().await;
+        if let Some(current) = active.as_ref() {
+            match current.mapper.map_port(self.port, self.lease).await {
+                Ok(external) => {
+                    debug!(
+                        "Renewed {} port mapping {}",
+                        current.mapper.name(),
+                        external
+                    );
+                    let mapper = Arc::clone(&current.mapper);
+                    *active = Some(ActiveMapping { mapper, external });
+                    return Some(self.mapped(active.as_ref()?));
+                }
+                Err(e) => warn!(
+                    "Failed to renew {} port mapping: {}",
+                    current.mapper.name(),
+                    e
+                ),
+            }
+            *active = None;
+        }
+
+        let mut last_error = None;
+        for mapper in &self.mappers {
+            match mapper.map_port(self.port, self.lease).await {
+                Ok(external) => {
+                    info!(
+                        "Mapped listen port {} to {} via {}",
+                        self.port,
+                        external,
+                        mapper.name()
+                    );
+                    *active = Some(ActiveMapping {
+                        mapper: Arc::clone(mapper),
+                        external,
+                    });
+                    return Some(self.mapped(active.as_ref()?));
+                }
+                Err(e) => {
+                    debug!("{} port mapping failed: {}", mapper.name(), e);
+                    last_error = Some(format!("{}: {}", mapper.name(), e));
+                }
+            }
+        }
+        warn!(
+            "Could not map listen port {} on the gateway ({}); accepting inbound connections only if forwarded manually",
+            self.port,
+            last_error.as_deref().unwrap_or("no mapper configured")
+        );
+        let mut status = self.status.lock();
+        status.state = MappingState::Failed;
+        status.protocol = None;
+        status.external_addr = None;
+        status.last_error = last_error;
+        None
+    }
+
+    /// Remove the mapping from the gateway, if we hold one
+    pub async fn release(&self) {
+        let Some(current) = self.active.lock().await.take() else {
+            return;
+        };
+        match current
+            .mapper
+            .unmap_port(self.port, current.external.port())
+            .await
+        {
+            Ok(()) => info!(
+                "Removed {} port mapping {}",
+                current.mapper.name(),
+                current.external
+            ),
+            Err(e) => warn!(
+                "Failed to remove {} port mapping: {}",
+                current.mapper.name(),
+                e
+            ),
+        }
+        let mut status = self.status.lock();
+        status.state = MappingState::Pending;
+        status.external_addr = None;
+    }
+
+    /// How long to wait before the next refresh
+    pub fn next_refresh(&self) -> Duration {
+        match self.status.lock().state {
+            MappingState::Mapped => self.lease / 2,
+            _ => RETRY_INTERVAL,
+        }
+    }
+
+    fn mapped(&self, mapping: &ActiveMapping) -> Multiaddr {
+        let addr = socket_multiaddr(mapping.external);
+        let mut status = self.status.lock();
+        status.state = MappingState::Mapped;
+        status.protocol = Some(mapping.mapper.name().to_string());
+        status.external_addr = Some(addr.to_string());
+        status.renewed_at = Some(chrono::Utc::now().timestamp().max(0) as u64);
+        status.last_error = None;
+        addr
+    }
+}
+
+fn socket_multiaddr(addr: SocketAddr) -> Multiaddr {
+    let mut multiaddr = Multiaddr::empty();
+    multiaddr.push(match addr.ip() {
+        IpAddr::V4(v4) => Protocol::Ip4(v4),
+        IpAddr::V6(v6) => Protocol::Ip6(v6),
+    });
+    multiaddr.push(Protocol::Tcp(addr.port()));
+    multiaddr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Gateway stand-in: fails the first `failures` map calls, then maps to
+    /// 203.0.113.7
+    #[derive(Default)]
+    struct MockGateway {
+        failures: usize,
+        maps: AtomicUsize,
+        unmaps: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PortMapper for MockGateway {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        async fn map_port(&self, port: u16, _lease: Duration) -> Result<SocketAddr, NatError> {
+            if self.maps.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(NatError::GatewayNotFound("mock".to_string()));
+            }
+            Ok(SocketAddr::from(([203, 0, 113, 7], port)))
+        }
+
+        async fn unmap_port(&self, _port: u16, _external_port: u16) -> Result<(), NatError> {
+            self.unmaps.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mapping_lifecycle() {
+        let gateway = Arc::new(MockGateway::default());
+        let mapping = PortMapping::new(
+            vec![gateway.clone() as Arc<dyn PortMapper>],
+            8000,
+            Duration::from_secs(3600),
+        );
+        assert_eq!(mapping.status().state, MappingState::Pending);
+
+        let addr = mapping.refresh().await.unwrap();
+        assert_eq!(addr.to_string(), "/ip4/203.0.113.7/tcp/8000");
+        let status = mapping.status();
+        assert_eq!(status.state, MappingState::Mapped);
+        assert_eq!(status.protocol.as_deref(), Some("mock"));
+        assert_eq!(mapping.next_refresh(), Duration::from_secs(1800));
+
+        // Renewal goes to the same gateway
+        assert_eq!(mapping.refresh().await, Some(addr));
+        assert_eq!(gateway.maps.load(Ordering::SeqCst), 2);
+
+        mapping.release().await;
+        assert_eq!(gateway.unmaps.load(Ordering::SeqCst), 1);
+        assert_eq!(mapping.status().external_addr, None);
+        // Nothing left to remove
+        mapping.release().await;
+        assert_eq!(gateway.unmaps.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failure_falls_back_then_degrades_to_listen_only() {
+        let upnp = Arc::new(MockGateway {
+            failures: 1,
+            ..Default::default()
+        });
+        let natpmp = Arc::new(MockGateway::default());
+        let mapping = PortMapping::new(
+            vec![
+                upnp as Arc<dyn PortMapper>,
+                natpmp.clone() as Arc<dyn PortMapper>,
+            ],
+            8000,
+            Duration::from_secs(3600),
+        );
+        assert!(mapping.refresh().await.is_some());
+        assert_eq!(natpmp.maps.load(Ordering::SeqCst), 1);
+
+        let broken = Arc::new(MockGateway {
+            failures: usize::MAX,
+            ..Default::default()
+        });
+        let mapping = PortMapping::new(
+            vec![broken as Arc<dyn PortMapper>],
+            8000,
+            Duration::from_secs(3600),
+        );
+        assert_eq!(mapping.refresh().await, None);
+        let status = mapping.status();
+        assert_eq!(status.state, MappingState::Failed);
+        assert!(status.last_error.unwrap().contains("mock"));
+        assert_eq!(mapping.next_refresh(), RETRY_INTERVAL);
+        mapping.release().await;
+    }
+
+    #[test]
+    fn test_nat_pmp_response_parsing() {
+        let mut response = vec![0, 130, 0, 0];
+        response.extend_from_slice(&[0; 12]);
+        assert!(parse_nat_pmp_response(&response, 2).is_ok());
+        assert!(matches!(
+            parse_nat_pmp_response(&response, 0),
+            Err(NatError::Protocol(_))
+        ));
+        response[3] = 2; // Not authorized
+        assert!(matches!(
+            parse_nat_pmp_response(&response, 2),
+            Err(NatError::MappingRefused(_))
+        ));
+    }
+}
@@ -522,6 +522,14 @@ impl NetworkProxy {
 
         Ok(())
     }
+
+    /// Broadcast an arbitrary protocol message to all peers
+    pub async fn broadcast_message(&self, message: super::protocol::Message) -> Result<(), String> {
+        self.command_tx
+            .send(NetworkCommand::Broadcast(message))
+            .await
+            .map_err(|e| format!("Failed to send broadcast command: {}", e))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,976 @@
+//! Operator-to-operator messaging
+//!
+//! Testnet coordination (planned resets, fork alerts) used to happen out of
+//! band. Operators can instead send short messages over the P2P layer, as a
+//! `Message::Extension` tagged [`OPERATOR_MESSAGE_EXTENSION`]:
+//!
+//! - direct: addressed to one peer id and encrypted to that peer's identity
+//!   key (X25519 agreement with the ed25519 key, ChaCha20-Poly1305)
+//! - broadcast: readable by every directly connected peer
+//! - notice: a network notice signed by an authority key
+//!
+//! Direct and broadcast messages are signed by the sender's node identity
+//! key and accepted only from senders on `allowed_senders`, which is empty
+//! by default, so every message is dropped until the operator opts in. They
+//! never travel more than one hop: a node accepts one only from the peer
+//! that wrote it and never forwards it. Notices are accepted from any peer
+//! when signed by one of the configured `authority_keys` and carrying a
+//! proof of work, and each receiver relays them while hops are left (at
+//! most [`MAX_NOTICE_HOPS`]).
+//!
+//! Every sender is held to `max_per_sender_per_minute`, stale or
+//! future-dated messages are dropped, and a message id already seen is
+//! ignored, so a relayed notice is delivered once.
+
+use crate::config::OperatorMessagesConfig;
+use crate::events::{EventBus, NodeEvent};
+use crate::network::p2p::NetworkCommand;
+use crate::network::protocol::Message;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use libp2p::identity::{Keypair, PublicKey};
+use libp2p::PeerId;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::str::FromStr;
+use supernova_core::util::canonical_json::{to_canonical_vec, CanonicalJsonError};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+/// `Message::Extension` tag carrying a serialized [`OperatorEnvelope`]
+pub const OPERATOR_MESSAGE_EXTENSION: &str = "operator-message/1";
+
+/// Longest message text, in bytes
+pub const MAX_TEXT_BYTES: usize = 1024;
+
+/// Most relays of a notice after the first hop
+pub const MAX_NOTICE_HOPS: u8 = 3;
+
+/// Oldest message accepted, in seconds
+pub const MAX_MESSAGE_AGE: u64 = 60 * 60;
+
+/// How far in the future a message may be dated, in seconds
+pub const MAX_CLOCK_SKEW: u64 = 5 * 60;
+
+/// Received messages kept for the API
+pub const INBOX_CAPACITY: usize = 256;
+
+/// Message ids remembered to drop repeats
+const SEEN_CAPACITY: usize = 4096;
+
+/// Rate limit window, in seconds
+const RATE_WINDOW: u64 = 60;
+
+/// Domain separator for message ids and signatures
+const SIGNATURE_DOMAIN: &[u8] = b"supernova/operator-message/v1";
+
+/// Domain separator for the key sealing a direct message
+const SEAL_DOMAIN: &[u8] = b"supernova/operator-message/seal/v1";
+
+/// Multihash code of a peer id that inlines its public key
+const IDENTITY_MULTIHASH: u64 = 0x00;
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum OperatorMessageError {
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Canonical encoding error: {0}")]
+    Canonical(#[from] CanonicalJsonError),
+    #[error("Message text of {0} bytes is too long")]
+    TooLarge(usize),
+    #[error("Message is too old")]
+    Stale,
+    #[error("Message is dated in the future")]
+    FromFuture,
+    #[error("Message already seen")]
+    Duplicate,
+    #[error("Malformed message: {0}")]
+    Malformed(&'static str),
+    #[error("Sender {0} is not allowed")]
+    UnauthorizedSender(String),
+    #[error("Message from {sender} relayed by {via}")]
+    NotAuthor { sender: String, via: String },
+    #[error("Message is addressed to {0}")]
+    NotForUs(String),
+    #[error("Notice signed by unknown authority {0}")]
+    UnknownAuthority(String),
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("Invalid signature")]
+    BadSignature,
+    #[error("Notice lacks the required proof of work")]
+    InsufficientWork,
+    #[error("Notice claims {0} hops left, more than allowed")]
+    TooManyHops(u8),
+    #[error("Sender {0} exceeded its message rate")]
+    RateLimited(String),
+    #[error("Encryption failed")]
+    Encryption,
+    #[error("Decryption failed")]
+    Decryption,
+    #[error("Signing failed: {0}")]
+    Signing(String),
+    #[error("This node has no authority key")]
+    NoAuthorityKey,
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Network unavailable: {0}")]
+    Network(String),
+}
+
+/// How a message is addressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// To one peer, encrypted
+    Direct,
+    /// To every directly connected peer
+    Broadcast,
+    /// Authority-signed network notice, relayed
+    Notice,
+}
+
+/// Message text, in the clear or sealed to the recipient
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Payload {
+    Plain(String),
+    /// Hex ephemeral X25519 key, nonce and ChaCha20-Poly1305 ciphertext
+    Sealed {
+        ephemeral_key: String,
+        nonce: String,
+        ciphertext: String,
+    },
+}
+
+/// The signed part of a message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageContent {
+    pub kind: MessageKind,
+    /// Peer id of the node that wrote the message
+    pub sender: String,
+    /// Addressee of a direct message
+    pub recipient: Option<String>,
+    /// Hex ed25519 key signing a notice; other messages are signed by the
+    /// sender's identity key
+    pub authority: Option<String>,
+    /// Unix seconds
+    pub sent_at: u64,
+    pub payload: Payload,
+    /// Random, and varied until a notice's id meets the proof of work
+    pub nonce: u64,
+}
+
+/// A message on the wire
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperatorEnvelope {
+    pub content: MessageContent,
+    /// Hex signature over the canonical JSON of `content`
+    pub signature: String,
+    /// Relays left for a notice. Not signed: relays only decrement it and
+    /// receivers reject more than [`MAX_NOTICE_HOPS`].
+    pub hops_left: u8,
+}
+
+impl OperatorEnvelope {
+    /// Id of the message: hash of its signed content
+    pub fn id(&self) -> Result<[u8; 32], OperatorMessageError> {
+        Ok(Sha256::digest(signing_message(&self.content)?).into())
+    }
+
+    pub fn to_message(&self) -> Result<Message, OperatorMessageError> {
+        let payload = serde_json::to_vec(self)
+            .map_err(|e| OperatorMessageError::Serialization(e.to_string()))?;
+        Ok(Message::Extension(OPERATOR_MESSAGE_EXTENSION.to_string(), payload))
+    }
+
+    /// Decode an operator message; `None` for any other message
+    pub fn from_message(message: &Message) -> Option<Result<Self, OperatorMessageError>> {
+        match message {
+            Message::Extension(tag, payload) if tag == OPERATOR_MESSAGE_EXTENSION => Some(
+                serde_json::from_slice(payload)
+                    .map_err(|e| OperatorMessageError::Serialization(e.to_string())),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// A message this node accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OperatorMessage {
+    /// Hex message id
+    pub id: String,
+    pub kind: MessageKind,
+    /// Peer id of the node that wrote it
+    pub sender: String,
+    /// Authority key that signed a notice
+    pub authority: Option<String>,
+    /// Unix seconds, as claimed by the sender
+    pub sent_at: u64,
+    /// Unix seconds
+    pub received_at: u64,
+    /// Peer it arrived from
+    pub via: String,
+    pub text: String,
+}
+
+/// A message accepted by [`OperatorInbox::receive`]
+#[derive(Debug, Clone)]
+pub struct Accepted {
+    pub message: OperatorMessage,
+    /// The notice to pass on, one hop fewer
+    pub relay: Option<OperatorEnvelope>,
+}
+
+/// Verifies, decrypts and rate limits incoming messages and composes
+/// outgoing ones.
+pub struct OperatorInbox {
+    config: OperatorMessagesConfig,
+    local: Keypair,
+    local_peer_id: PeerId,
+    allowed: HashSet<PeerId>,
+    seen: HashSet<[u8; 32]>,
+    seen_order: VecDeque<[u8; 32]>,
+    /// Per sender: start of the current window and messages in it
+    rates: HashMap<String, (u64, u32)>,
+    messages: VecDeque<OperatorMessage>,
+}
+
+impl OperatorInbox {
+    /// Inbox for the node with identity `local`. Unparseable allow-list
+    /// entries are ignored; config validation reports them.
+    pub fn new(config: OperatorMessagesConfig, local: Keypair) -> Self {
+        let allowed = config
+            .allowed_senders
+            .iter()
+            .filter_map(|peer| PeerId::from_str(peer).ok())
+            .collect();
+        Self {
+            local_peer_id: PeerId::from(local.public()),
+            config,
+            local,
+            allowed,
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+            rates: HashMap::new(),
+            messages: VecDeque::new(),
+        }
+    }
+
+    /// Accepted messages, newest first
+    pub fn messages(&self, limit: usize) -> Vec<OperatorMessage> {
+        self.messages.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Check a message that arrived from `via` at `now` and keep it
+    pub fn receive(
+        &mut self,
+        via: PeerId,
+        envelope: &OperatorEnvelope,
+        now: u64,
+    ) -> Result<Accepted, OperatorMessageError> {
+        let content = &envelope.content;
+        let ciphertext_len = match &content.payload {
+            Payload::Plain(text) => text.len(),
+            Payload::Sealed { ciphertext, .. } => ciphertext.len() / 2,
+        };
+        if ciphertext_len > MAX_TEXT_BYTES + 16 {
+            return Err(OperatorMessageError::TooLarge(ciphertext_len));
+        }
+        if content.sent_at.saturating_add(MAX_MESSAGE_AGE) < now {
+            return Err(OperatorMessageError::Stale);
+        }
+        if content.sent_at > now.saturating_add(MAX_CLOCK_SKEW) {
+            return Err(OperatorMessageError::FromFuture);
+        }
+        let id = envelope.id()?;
+        if self.seen.contains(&id) {
+            return Err(OperatorMessageError::Duplicate);
+        }
+
+        let (rate_key, text) = match content.kind {
+            MessageKind::Direct | MessageKind::Broadcast => {
+                if content.authority.is_some() {
+                    return Err(OperatorMessageError::Malformed("authority on a peer message"));
+                }
+                let sender = PeerId::from_str(&content.sender)
+                    .map_err(|e| OperatorMessageError::InvalidPublicKey(e.to_string()))?;
+                if sender != via {
+                    return Err(OperatorMessageError::NotAuthor {
+                        sender: content.sender.clone(),
+                        via: via.to_string(),
+                    });
+                }
+                if !self.allowed.contains(&sender) {
+                    return Err(OperatorMessageError::UnauthorizedSender(content.sender.clone()));
+                }
+                let signature = hex::decode(&envelope.signature)
+                    .map_err(|_| OperatorMessageError::BadSignature)?;
+                if !identity_key(&sender)?.verify(&signing_message(content)?, &signature) {
+                    return Err(OperatorMessageError::BadSignature);
+                }
+                let text = match (content.kind, &content.payload) {
+                    (MessageKind::Direct, Payload::Sealed { .. }) => {
+                        let recipient = content.recipient.as_deref().unwrap_or_default();
+                        if recipient != self.local_peer_id.to_string() {
+                            return Err(OperatorMessageError::NotForUs(recipient.to_string()));
+                        }
+                        self.open(&content.payload)?
+                    }
+                    (MessageKind::Broadcast, Payload::Plain(text)) if content.recipient.is_none() => {
+                        text.clone()
+                    }
+                    _ => return Err(OperatorMessageError::Malformed("payload does not match kind")),
+                };
+                (content.sender.clone(), text)
+            }
+            MessageKind::Notice => {
+                let authority = content
+                    .authority
+                    .as_deref()
+                    .ok_or(OperatorMessageError::Malformed("notice without authority"))?;
+                if !self.config.authority_keys.iter().any(|key| key == authority) {
+                    return Err(OperatorMessageError::UnknownAuthority(authority.to_string()));
+                }
+                let key = authority_key(authority)?;
+                let signature = hex::decode(&envelope.signature)
+                    .ok()
+                    .and_then(|bytes| Signature::from_slice(&bytes).ok())
+                    .ok_or(OperatorMessageError::BadSignature)?;
+                key.verify(&signing_message(content)?, &signature)
+                    .map_err(|_| OperatorMessageError::BadSignature)?;
+                if leading_zero_bits(&id) < self.config.notice_pow_bits as u32 {
+                    return Err(OperatorMessageError::InsufficientWork);
+                }
+                if envelope.hops_left > MAX_NOTICE_HOPS {
+                    return Err(OperatorMessageError::TooManyHops(envelope.hops_left));
+                }
+                let Payload::Plain(text) = &content.payload else {
+                    return Err(OperatorMessageError::Malformed("sealed notice"));
+                };
+                (authority.to_string(), text.clone())
+            }
+        };
+        if text.len() > MAX_TEXT_BYTES {
+            return Err(OperatorMessageError::TooLarge(text.len()));
+        }
+
+        self.take_rate(&rate_key, now)?;
+        self.remember(id);
+
+        let message = OperatorMessage {
+            id: hex::encode(id),
+            kind: content.kind,
+            sender: content.sender.clone(),
+            authority: content.authority.clone(),
+            sent_at: content.sent_at,
+            received_at: now,
+            via: via.to_string(),
+            text,
+        };
+        if self.messages.len() >= INBOX_CAPACITY {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message.clone());
+
+        let relay = (content.kind == MessageKind::Notice && envelope.hops_left > 0).then(|| {
+            OperatorEnvelope {
+                hops_left: envelope.hops_left - 1,
+                ..envelope.clone()
+            }
+        });
+        Ok(Accepted { message, relay })
+    }
+
+    /// A message for `recipient` only, encrypted to its identity key
+    pub fn compose_direct(
+        &mut self,
+        recipient: &PeerId,
+        text: &str,
+        now: u64,
+    ) -> Result<OperatorEnvelope, OperatorMessageError> {
+        check_text(text)?;
+        let payload = seal(recipient, text)?;
+        self.compose_peer_message(MessageKind::Direct, Some(recipient.to_string()), payload, now)
+    }
+
+    /// A message for every directly connected peer
+    pub fn compose_broadcast(
+        &mut self,
+        text: &str,
+        now: u64,
+    ) -> Result<OperatorEnvelope, OperatorMessageError> {
+        check_text(text)?;
+        self.compose_peer_message(MessageKind::Broadcast, None, Payload::Plain(text.to_string()), now)
+    }
+
+    /// A network notice signed by `authority`, with the configured proof of
+    /// work. Searching for the work takes a while at high difficulty.
+    pub fn compose_notice(
+        &mut self,
+        authority: &SigningKey,
+        text: &str,
+        now: u64,
+    ) -> Result<OperatorEnvelope, OperatorMessageError> {
+        let envelope = notice_envelope(
+            &self.local_peer_id,
+            authority,
+            text,
+            self.config.notice_pow_bits,
+            now,
+        )?;
+        self.remember(envelope.id()?);
+        Ok(envelope)
+    }
+
+    fn compose_peer_message(
+        &mut self,
+        kind: MessageKind,
+        recipient: Option<String>,
+        payload: Payload,
+        now: u64,
+    ) -> Result<OperatorEnvelope, OperatorMessageError> {
+        let content = MessageContent {
+            kind,
+            sender: self.local_peer_id.to_string(),
+            recipient,
+            authority: None,
+            sent_at: now,
+            payload,
+            nonce: rand::rngs::OsRng.next_u64(),
+        };
+        let message = signing_message(&content)?;
+        let signature = self
+            .local
+            .sign(&message)
+            .map_err(|e| OperatorMessageError::Signing(e.to_string()))?;
+        self.remember(Sha256::digest(&message).into());
+        Ok(OperatorEnvelope {
+            content,
+            signature: hex::encode(signature),
+            hops_left: 0,
+        })
+    }
+
+    /// Decrypt a payload sealed to our identity key
+    fn open(&self, payload: &Payload) -> Result<String, OperatorMessageError> {
+        let Payload::Sealed {
+            ephemeral_key,
+            nonce,
+            ciphertext,
+        } = payload
+        else {
+            return Err(OperatorMessageError::Malformed("payload is not sealed"));
+        };
+        let ephemeral: [u8; 32] = hex::decode(ephemeral_key)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(OperatorMessageError::Decryption)?;
+        let nonce = hex::decode(nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == NONCE_LEN)
+            .ok_or(OperatorMessageError::Decryption)?;
+        let ciphertext = hex::decode(ciphertext).map_err(|_| OperatorMessageError::Decryption)?;
+
+        let secret = x25519_secret(&self.local)?;
+        let recipient = X25519PublicKey::from(&secret);
+        let shared = secret.diffie_hellman(&X25519PublicKey::from(ephemeral));
+        let key = seal_key(shared.as_bytes(), &ephemeral, recipient.as_bytes());
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| OperatorMessageError::Decryption)?;
+        String::from_utf8(plaintext).map_err(|_| OperatorMessageError::Decryption)
+    }
+
+    fn take_rate(&mut self, sender: &str, now: u64) -> Result<(), OperatorMessageError> {
+        let limit = self.config.max_per_sender_per_minute;
+        let (window_start, count) = self.rates.entry(sender.to_string()).or_insert((now, 0));
+        if now.saturating_sub(*window_start) >= RATE_WINDOW {
+            *window_start = now;
+            *count = 0;
+        }
+        if *count >= limit {
+            return Err(OperatorMessageError::RateLimited(sender.to_string()));
+        }
+        *count += 1;
+        Ok(())
+    }
+
+    fn remember(&mut self, id: [u8; 32]) {
+        if !self.seen.insert(id) {
+            return;
+        }
+        self.seen_order.push_back(id);
+        if self.seen_order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.seen_order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// Sends operator messages through the network and delivers incoming ones
+/// to the inbox and the event bus.
+pub struct OperatorMessenger {
+    inbox: parking_lot::Mutex<OperatorInbox>,
+    /// Key this node signs notices with, if it is an authority
+    authority: Option<SigningKey>,
+    command_tx: mpsc::Sender<NetworkCommand>,
+    events: EventBus,
+}
+
+impl OperatorMessenger {
+    pub fn new(
+        config: OperatorMessagesConfig,
+        local: Keypair,
+        authority: Option<SigningKey>,
+        command_tx: mpsc::Sender<NetworkCommand>,
+        events: EventBus,
+    ) -> Self {
+        Self {
+            inbox: parking_lot::Mutex::new(OperatorInbox::new(config, local)),
+            authority,
+            command_tx,
+            events,
+        }
+    }
+
+    /// Accepted messages, newest first
+    pub fn messages(&self, limit: usize) -> Vec<OperatorMessage> {
+        self.inbox.lock().messages(limit)
+    }
+
+    /// Handle an operator message from `peer_id`. Returns false for any
+    /// other message.
+    pub async fn handle_message(&self, peer_id: PeerId, message: &Message) -> bool {
+        let envelope = match OperatorEnvelope::from_message(message) {
+            None => return false,
+            Some(Ok(envelope)) => envelope,
+            Some(Err(e)) => {
+                debug!("Dropping undecodable operator message from {}: {}", peer_id, e);
+                return true;
+            }
+        };
+        let accepted = self.inbox.lock().receive(peer_id, &envelope, now_secs());
+        match accepted {
+            Ok(Accepted { message, relay }) => {
+                info!(
+                    "Operator {:?} message {} from {}: {}",
+                    message.kind, message.id, message.sender, message.text
+                );
+                let _ = self.events.send(NodeEvent::OperatorMessage(message));
+                if let Some(relay) = relay {
+                    if let Err(e) = self.broadcast_envelope(&relay).await {
+                        warn!("Failed to relay operator notice: {}", e);
+                    }
+                }
+            }
+            Err(OperatorMessageError::Duplicate) => {}
+            Err(e) => debug!("Dropping operator message from {}: {}", peer_id, e),
+        }
+        true
+    }
+
+    /// Send an encrypted message to `recipient`. Returns the message id.
+    pub async fn send_direct(
+        &self,
+        recipient: PeerId,
+        text: &str,
+    ) -> Result<String, OperatorMessageError> {
+        let envelope = self.inbox.lock().compose_direct(&recipient, text, now_secs())?;
+        self.command_tx
+            .send(NetworkCommand::SendToPeer {
+                peer_id: recipient,
+                message: envelope.to_message()?,
+            })
+            .await
+            .map_err(|e| OperatorMessageError::Network(e.to_string()))?;
+        Ok(hex::encode(envelope.id()?))
+    }
+
+    /// Send a message to every directly connected peer. Returns the
+    /// message id.
+    pub async fn broadcast(&self, text: &str) -> Result<String, OperatorMessageError> {
+        let envelope = self.inbox.lock().compose_broadcast(text, now_secs())?;
+        self.broadcast_envelope(&envelope).await?;
+        Ok(hex::encode(envelope.id()?))
+    }
+
+    /// Issue a network notice signed with this node's authority key.
+    /// Returns the message id.
+    pub async fn issue_notice(&self, text: &str) -> Result<String, OperatorMessageError> {
+        let authority = self
+            .authority
+            .clone()
+            .ok_or(OperatorMessageError::NoAuthorityKey)?;
+        let (sender, pow_bits) = {
+            let inbox = self.inbox.lock();
+            (inbox.local_peer_id, inbox.config.notice_pow_bits)
+        };
+        // The proof of work runs off the async workers
+        let text = text.to_string();
+        let envelope = tokio::task::spawn_blocking(move || {
+            notice_envelope(&sender, &authority, &text, pow_bits, now_secs())
+        })
+        .await
+        .map_err(|e| OperatorMessageError::Signing(e.to_string()))??;
+        let id = envelope.id()?;
+        self.inbox.lock().remember(id);
+        self.broadcast_envelope(&envelope).await?;
+        Ok(hex::encode(id))
+    }
+
+    async fn broadcast_envelope(&self, envelope: &OperatorEnvelope) -> Result<(), OperatorMessageError> {
+        self.command_tx
+            .send(NetworkCommand::Broadcast(envelope.to_message()?))
+            .await
+            .map_err(|e| OperatorMessageError::Network(e.to_string()))
+    }
+}
+
+/// Read an authority's hex ed25519 seed, as written by `genesis-tool`
+pub fn load_authority_key(path: &Path) -> Result<SigningKey, OperatorMessageError> {
+    let seed: [u8; 32] = hex::decode(std::fs::read_to_string(path)?.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(OperatorMessageError::InvalidPublicKey(format!(
+            "{} must hold a 32-byte hex seed",
+            path.display()
+        )))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// Sign a notice from `sender` after finding `pow_bits` of work
+fn notice_envelope(
+    sender: &PeerId,
+    authority: &SigningKey,
+    text: &str,
+    pow_bits: u8,
+    now: u64,
+) -> Result<OperatorEnvelope, OperatorMessageError> {
+    check_text(text)?;
+    let mut content = MessageContent {
+        kind: MessageKind::Notice,
+        sender: sender.to_string(),
+        recipient: None,
+        authority: Some(hex::encode(authority.verifying_key().to_bytes())),
+        sent_at: now,
+        payload: Payload::Plain(text.to_string()),
+        nonce: rand::rngs::OsRng.next_u64(),
+    };
+    loop {
+        let message = signing_message(&content)?;
+        let id: [u8; 32] = Sha256::digest(&message).into();
+        if leading_zero_bits(&id) >= pow_bits as u32 {
+            return Ok(OperatorEnvelope {
+                content,
+                signature: hex::encode(authority.sign(&message).to_bytes()),
+                hops_left: MAX_NOTICE_HOPS,
+            });
+        }
+        content.nonce = content.nonce.wrapping_add(1);
+    }
+}
+
+fn check_text(text: &str) -> Result<(), OperatorMessageError> {
+    if text.is_empty() {
+        return Err(OperatorMessageError::Malformed("empty text"));
+    }
+    if text.len() > MAX_TEXT_BYTES {
+        return Err(OperatorMessageError::TooLarge(text.len()));
+    }
+    Ok(())
+}
+
+fn signing_message(content: &MessageContent) -> Result<Vec<u8>, OperatorMessageError> {
+    let mut message = SIGNATURE_DOMAIN.to_vec();
+    message.extend(to_canonical_vec(content)?);
+    Ok(message)
+}
+
+/// Public key inlined in an ed25519 peer id
+fn identity_key(peer_id: &PeerId) -> Result<PublicKey, OperatorMessageError> {
+    let multihash = peer_id.as_ref();
+    if multihash.code() != IDENTITY_MULTIHASH {
+        return Err(OperatorMessageError::InvalidPublicKey(format!(
+            "{} does not inline its key",
+            peer_id
+        )));
+    }
+    PublicKey::try_decode_protobuf(multihash.digest())
+        .map_err(|e| OperatorMessageError::InvalidPublicKey(e.to_string()))
+}
+
+fn authority_key(hex_key: &str) -> Result<VerifyingKey, OperatorMessageError> {
+    hex::decode(hex_key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| OperatorMessageError::InvalidPublicKey(hex_key.to_string()))
+}
+
+/// X25519 form of a peer's ed25519 identity key
+fn x25519_public(peer_id: &PeerId) -> Result<X25519PublicKey, OperatorMessageError> {
+    let key = identity_key(peer_id)?
+        .try_into_ed25519()
+        .map_err(|e| OperatorMessageError::InvalidPublicKey(e.to_string()))?;
+    let key = VerifyingKey::from_bytes(&key.to_bytes())
+        .map_err(|e| OperatorMessageError::InvalidPublicKey(e.to_string()))?;
+    Ok(X25519PublicKey::from(key.to_montgomery().to_bytes()))
+}
+
+/// X25519 form of our ed25519 identity key
+fn x25519_secret(local: &Keypair) -> Result<StaticSecret, OperatorMessageError> {
+    let keypair = local
+        .clone()
+        .try_into_ed25519()
+        .map_err(|e| OperatorMessageError::InvalidPublicKey(e.to_string()))?;
+    let seed: [u8; 32] = keypair
+        .secret()
+        .as_ref()
+        .try_into()
+        .map_err(|_| OperatorMessageError::Decryption)?;
+    Ok(StaticSecret::from(SigningKey::from_bytes(&seed).to_scalar_bytes()))
+}
+
+fn seal_key(shared: &[u8; 32], ephemeral: &[u8; 32], recipient: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(SEAL_DOMAIN);
+    hasher.update(shared);
+    hasher.update(ephemeral);
+    hasher.update(recipient);
+    hasher.finalize().into()
+}
+
+/// Encrypt `text` to `recipient`'s identity key
+fn seal(recipient: &PeerId, text: &str) -> Result<Payload, OperatorMessageError> {
+    let recipient = x25519_public(recipient)?;
+    let ephemeral = StaticSecret::random_from_rng(rand::rngs::OsRng);
+    let ephemeral_public = X25519PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&recipient);
+    let key = seal_key(shared.as_bytes(), ephemeral_public.as_bytes(), recipient.as_bytes());
+
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), text.as_bytes())
+        .map_err(|_| OperatorMessageError::Encryption)?;
+    Ok(Payload::Sealed {
+        ephemeral_key: hex::encode(ephemeral_public.as_bytes()),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+fn leading_zero_bits(hash: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+fn now_secs() -> u64 {
+    chrono::Utc::now().timestamp().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn config(allowed: &[&Keypair], authorities: &[&SigningKey]) -> OperatorMessagesConfig {
+        OperatorMessagesConfig {
+            allowed_senders: allowed
+                .iter()
+                .map(|key| PeerId::from(key.public()).to_string())
+                .collect(),
+            authority_keys: authorities
+                .iter()
+                .map(|key| hex::encode(key.verifying_key().to_bytes()))
+                .collect(),
+            authority_key_file: None,
+            max_per_sender_per_minute: 3,
+            notice_pow_bits: 8,
+        }
+    }
+
+    fn peer(key: &Keypair) -> PeerId {
+        PeerId::from(key.public())
+    }
+
+    #[test]
+    fn direct_message_is_delivered_and_decrypted() {
+        let (alice, bob, carol) = (
+            Keypair::generate_ed25519(),
+            Keypair::generate_ed25519(),
+            Keypair::generate_ed25519(),
+        );
+        let mut alice_inbox = OperatorInbox::new(config(&[], &[]), alice.clone());
+        let envelope = alice_inbox
+            .compose_direct(&peer(&bob), "reset at height 5000", NOW)
+            .unwrap();
+        assert!(!serde_json::to_string(&envelope).unwrap().contains("reset at height"));
+
+        let mut bob_inbox = OperatorInbox::new(config(&[&alice], &[]), bob);
+        let accepted = bob_inbox.receive(peer(&alice), &envelope, NOW + 1).unwrap();
+        assert_eq!(accepted.message.text, "reset at height 5000");
+        assert_eq!(accepted.message.kind, MessageKind::Direct);
+        assert!(accepted.relay.is_none());
+        assert_eq!(bob_inbox.messages(10), vec![accepted.message]);
+        assert!(matches!(
+            bob_inbox.receive(peer(&alice), &envelope, NOW + 1),
+            Err(OperatorMessageError::Duplicate)
+        ));
+
+        // Someone else it was not addressed to cannot read it
+        let mut carol_inbox = OperatorInbox::new(config(&[&alice], &[]), carol);
+        assert!(matches!(
+            carol_inbox.receive(peer(&alice), &envelope, NOW + 1),
+            Err(OperatorMessageError::NotForUs(_))
+        ));
+    }
+
+    #[test]
+    fn unauthorized_and_forged_messages_are_dropped() {
+        let (alice, bob, mallory) = (
+            Keypair::generate_ed25519(),
+            Keypair::generate_ed25519(),
+            Keypair::generate_ed25519(),
+        );
+        let envelope = OperatorInbox::new(config(&[], &[]), mallory.clone())
+            .compose_broadcast("fork now", NOW)
+            .unwrap();
+
+        // The default configuration drops everything
+        let mut inbox = OperatorInbox::new(OperatorMessagesConfig::default(), bob.clone());
+        assert!(matches!(
+            inbox.receive(peer(&mallory), &envelope, NOW),
+            Err(OperatorMessageError::UnauthorizedSender(_))
+        ));
+
+        // Claiming to be an allowed sender fails the signature check
+        let mut inbox = OperatorInbox::new(config(&[&alice], &[]), bob);
+        let mut forged = envelope.clone();
+        forged.content.sender = peer(&alice).to_string();
+        assert!(matches!(
+            inbox.receive(peer(&alice), &forged, NOW),
+            Err(OperatorMessageError::BadSignature)
+        ));
+        assert!(inbox.messages(10).is_empty());
+    }
+
+    #[test]
+    fn peer_messages_travel_one_hop_and_notices_are_relayed() {
+        let (alice, bob, relay) = (
+            Keypair::generate_ed25519(),
+            Keypair::generate_ed25519(),
+            Keypair::generate_ed25519(),
+        );
+        let authority = SigningKey::from_bytes(&[7u8; 32]);
+
+        // A broadcast reaching us through another peer is not accepted
+        let broadcast = OperatorInbox::new(config(&[], &[]), alice.clone())
+            .compose_broadcast("hello", NOW)
+            .unwrap();
+        let mut inbox = OperatorInbox::new(config(&[&alice], &[&authority]), bob.clone());
+        assert!(matches!(
+            inbox.receive(peer(&relay), &broadcast, NOW),
+            Err(OperatorMessageError::NotAuthor { .. })
+        ));
+        assert!(inbox.receive(peer(&alice), &broadcast, NOW).unwrap().relay.is_none());
+
+        // An authority notice is accepted from any peer and passed on with
+        // one hop fewer, until none are left
+        let notice = OperatorInbox::new(config(&[], &[]), alice.clone())
+            .compose_notice(&authority, "testnet reset on Monday", NOW)
+            .unwrap();
+        assert!(leading_zero_bits(&notice.id().unwrap()) >= 8);
+        let accepted = inbox.receive(peer(&relay), &notice, NOW).unwrap();
+        assert_eq!(accepted.message.kind, MessageKind::Notice);
+        let relayed = accepted.relay.unwrap();
+        assert_eq!(relayed.hops_left, MAX_NOTICE_HOPS - 1);
+
+        let mut last_hop = OperatorInbox::new(config(&[], &[&authority]), relay.clone());
+        let spent = OperatorEnvelope {
+            hops_left: 0,
+            ..relayed.clone()
+        };
+        assert!(last_hop.receive(peer(&bob), &spent, NOW).unwrap().relay.is_none());
+
+        // Notices need a known authority, the work, and a sane hop count
+        let mut stranger = OperatorInbox::new(config(&[], &[]), relay.clone());
+        assert!(matches!(
+            stranger.receive(peer(&bob), &relayed, NOW),
+            Err(OperatorMessageError::UnknownAuthority(_))
+        ));
+        let mut inflated = relayed.clone();
+        inflated.hops_left = MAX_NOTICE_HOPS + 1;
+        let mut fresh = OperatorInbox::new(config(&[], &[&authority]), relay.clone());
+        assert!(matches!(
+            fresh.receive(peer(&bob), &inflated, NOW),
+            Err(OperatorMessageError::TooManyHops(_))
+        ));
+        let mut demanding = config(&[], &[&authority]);
+        demanding.notice_pow_bits = 32;
+        let mut demanding = OperatorInbox::new(demanding, relay);
+        assert!(matches!(
+            demanding.receive(peer(&bob), &relayed, NOW),
+            Err(OperatorMessageError::InsufficientWork)
+        ));
+    }
+
+    #[test]
+    fn senders_are_rate_limited() {
+        let (alice, bob) = (Keypair::generate_ed25519(), Keypair::generate_ed25519());
+        let mut outbox = OperatorInbox::new(config(&[], &[]), alice.clone());
+        let mut inbox = OperatorInbox::new(config(&[&alice], &[]), bob);
+
+        for i in 0..3 {
+            let envelope = outbox.compose_broadcast(&format!("message {}", i), NOW).unwrap();
+            inbox.receive(peer(&alice), &envelope, NOW).unwrap();
+        }
+        let envelope = outbox.compose_broadcast("one too many", NOW).unwrap();
+        assert!(matches!(
+            inbox.receive(peer(&alice), &envelope, NOW + 10),
+            Err(OperatorMessageError::RateLimited(_))
+        ));
+        // A rejected message may be retried in the next window
+        inbox.receive(peer(&alice), &envelope, NOW + RATE_WINDOW).unwrap();
+        assert_eq!(inbox.messages(10).len(), 4);
+    }
+
+    #[test]
+    fn stale_and_oversized_messages_are_dropped() {
+        let (alice, bob) = (Keypair::generate_ed25519(), Keypair::generate_ed25519());
+        let mut outbox = OperatorInbox::new(config(&[], &[]), alice.clone());
+        let mut inbox = OperatorInbox::new(config(&[&alice], &[]), bob);
+        let envelope = outbox.compose_broadcast("hello", NOW).unwrap();
+        assert!(matches!(
+            inbox.receive(peer(&alice), &envelope, NOW + MAX_MESSAGE_AGE + 1),
+            Err(OperatorMessageError::Stale)
+        ));
+        assert!(matches!(
+            inbox.receive(peer(&alice), &envelope, NOW - MAX_CLOCK_SKEW - 1),
+            Err(OperatorMessageError::FromFuture)
+        ));
+        assert!(matches!(
+            outbox.compose_broadcast(&"x".repeat(MAX_TEXT_BYTES + 1), NOW),
+            Err(OperatorMessageError::TooLarge(_))
+        ));
+    }
+}
@@ -2,18 +2,40 @@
 
 use crate::{
     api::types::{BandwidthUsage, ConnectionCount, NetworkInfo, PeerAddResponse},
+    config::{BandwidthConfig, NetworkConfig},
     network::{
+        address_advertisement::AddressAdvertiser,
+        bandwidth::{
+            BandwidthLimitStats, BandwidthLimiter, PeerTraffic, SendDecision, TrafficClass,
+        },
         behaviour::{SupernovaBehaviour, SupernovaBehaviourEvent},
+        bloom_filter::{filter_message, BloomFilterError, SupernovaBloomFilter},
         discovery::PeerDiscovery,
+        dns_seed::DnsSeeder,
         eclipse_prevention::EclipseRiskLevel,
+        encryption::{EncryptionError, HandshakeContext, PeerEncryption},
+        framing,
+        handshake::{
+            LocalVersion, NODE_BLOOM, NODE_COMPACT_BLOCKS, NODE_ENCRYPTED, NODE_NETWORK,
+            NODE_NETWORK_LIMITED, NODE_QUANTUM_SIGS,
+        },
+        identity_rotation::IdentityLinkRegistry,
         identity_verification::IdentityVerificationSystem,
+        inventory::{inventory_of, KnownInventory, DEFAULT_KNOWN_INVENTORY_SIZE},
+        keepalive::{
+            latency_score_delta, KeepaliveAction, KeepaliveConfig, KeepaliveManager, PongOutcome,
+        },
+        nat::{NatStatus, PortMapping},
         peer::{self, PeerInfo, PeerState},
+        peer_diversity::{BucketDistribution, OutboundDiversityPolicy},
         peer_manager::{ConnectionLimits, PeerManager},
         protocol::Message,
+        relay_policy::TxAnnouncement,
         rate_limiter::{
             MessageType, NetworkRateLimiter as RateLimiter, RateLimitConfig, RateLimitError,
             RateLimitMetrics,
         },
+        request_manager::{batch_assignments, ObjectId, ObjectKind, RequestManager},
     },
 };
 use supernova_core::{Block, BlockHeader, Transaction};
@@ -48,13 +70,40 @@ const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
 const RECONNECT_INTERVAL: Duration = Duration::from_secs(60);
 const DISCOVERY_INTERVAL: Duration = Duration::from_secs(300);
 const STATUS_BROADCAST_INTERVAL: Duration = Duration::from_secs(180);
+/// How often outbound connections are checked for DNS seed re-resolution
+const SEED_STARVATION_CHECK_INTERVAL: Duration = Duration::from_secs(60);
 
 /// Challenge difficulty for Sybil protection (number of leading zero bits)
 const DEFAULT_CHALLENGE_DIFFICULTY: u8 = 16;
+/// Peers that must report an observed address before we advertise it,
+/// unless overridden by `configure_addresses`
+const OBSERVED_ADDR_CONFIRMATIONS: usize = 3;
 
 /// Challenge timeout in seconds
 const CHALLENGE_TIMEOUT_SECS: u64 = 30;
 
+/// Gossipsub topic carrying messages addressed to one peer, framed with
+/// the recipient's peer id
+const DIRECT_TOPIC: &str = "messages";
+
+/// Identify protocol version of the built-in networks
+const BASE_PROTOCOL_VERSION: &str = "/supernova/1.0.0";
+
+/// Identify protocol version advertised by a node. Custom networks append
+/// their magic and genesis hash so nodes of different networks recognize
+/// each other on the first exchange.
+fn identify_protocol_version(identity: Option<([u8; 4], [u8; 32])>) -> String {
+    match identity {
+        Some((magic, genesis)) => format!(
+            "{}/{}/{}",
+            BASE_PROTOCOL_VERSION,
+            hex::encode(magic),
+            hex::encode(genesis)
+        ),
+        None => BASE_PROTOCOL_VERSION.to_string(),
+    }
+}
+
 /// Identity verification challenge
 #[derive(Debug, Clone)]
 pub struct IdentityChallenge {
@@ -266,6 +315,9 @@ enum SwarmCommand {
     Publish(TopicHash, Vec<u8>),
     /// Forcibly disconnect a peer (e.g. when the peer cap is exceeded)
     Disconnect(PeerId),
+    /// Start or stop announcing an address through identify
+    AddExternalAddress(Multiaddr),
+    RemoveExternalAddress(Multiaddr),
     Stop,
 }
 
@@ -275,6 +327,7 @@ enum SwarmEventWrapper {
     ConnectionEstablished {
         peer_id: PeerId,
         endpoint: String,
+        inbound: bool,
     },
     ConnectionClosed {
         peer_id: PeerId,
@@ -303,6 +356,7 @@ impl SwarmEventWrapper {
             } => Ok(SwarmEventWrapper::ConnectionEstablished {
                 peer_id,
                 endpoint: endpoint.get_remote_address().to_string(),
+                inbound: !endpoint.is_dialer(),
             }),
             SwarmEvent::ConnectionClosed { peer_id, .. } => {
                 Ok(SwarmEventWrapper::ConnectionClosed { peer_id })
@@ -367,6 +421,12 @@ pub struct P2PNetwork {
     discovery: Arc<RwLock<Option<PeerDiscovery>>>,
     /// Configured listen address (e.g., "0.0.0.0:8333")
     listen_address: String,
+    /// Addresses to bind; when empty `listen_address` is used
+    listen_addrs: Vec<Multiaddr>,
+    /// Selection of the addresses we advertise to peers
+    address_advertiser: Arc<Mutex<AddressAdvertiser>>,
+    /// Gateway port mapping, when `enable_upnp` is set
+    port_mapping: Option<Arc<PortMapping>>,
     /// Network task handle
     network_task: Arc<RwLock<Option<JoinHandle<()>>>>,
     /// Bandwidth tracker
@@ -391,6 +451,24 @@ pub struct P2PNetwork {
     identity_system: Arc<IdentityVerificationSystem>,
     /// Storage backend
     storage: Arc<dyn crate::storage::Storage>,
+    /// Application-level ping scheduling and dead peer detection
+    keepalive: Arc<Mutex<KeepaliveManager>>,
+    /// Cross-peer coordination of block and transaction requests
+    request_manager: Arc<Mutex<RequestManager>>,
+    /// Which transactions are announced; fixed once the network starts
+    tx_announcement: Arc<Mutex<TxAnnouncement>>,
+    /// Rotated peer identities learned from verified attestations
+    identity_links: Arc<Mutex<IdentityLinkRegistry>>,
+    /// Magic and genesis hash of a custom network; fixed once the network starts
+    network_identity: Arc<Mutex<Option<([u8; 4], [u8; 32])>>>,
+    /// DNS seeds, handed to a background task when the network starts
+    dns_seeder: Arc<Mutex<Option<DnsSeeder>>>,
+    /// Caps on outbound peers per prefix and ASN; fixed once the network starts
+    outbound_diversity: Arc<Mutex<OutboundDiversityPolicy>>,
+    /// Known-inventory entries kept per peer; fixed once the network starts
+    known_inventory_size: Arc<Mutex<usize>>,
+    /// Our side of the version handshake
+    local_version: Arc<Mutex<LocalVersion>>,
 }
 
 /// Network statistics for monitoring
@@ -423,6 +501,24 @@ pub struct NetworkStats {
     // Bandwidth
     pub bytes_sent: u64,
     pub bytes_received: u64,
+    /// Traffic with each connected peer; broadcasts count once per peer
+    pub peer_bandwidth: HashMap<PeerId, PeerTraffic>,
+    /// Relays skipped because the peer already had the block or transaction
+    pub relay_suppressed: u64,
+}
+
+impl NetworkStats {
+    pub fn record_peer_sent(&mut self, peer_id: PeerId, bytes: u64) {
+        let traffic = self.peer_bandwidth.entry(peer_id).or_default();
+        traffic.bytes_sent += bytes;
+        traffic.messages_sent += 1;
+    }
+
+    pub fn record_peer_received(&mut self, peer_id: PeerId, bytes: u64) {
+        let traffic = self.peer_bandwidth.entry(peer_id).or_default();
+        traffic.bytes_received += bytes;
+        traffic.messages_received += 1;
+    }
 }
 
 /// Bandwidth tracking for network monitoring
@@ -433,6 +529,8 @@ pub struct BandwidthTracker {
     pub messages_sent: u64,
     pub messages_received: u64,
     pub start_time: Option<Instant>,
+    /// Global upload and download budgets; unlimited by default
+    pub limiter: BandwidthLimiter,
 }
 
 impl BandwidthTracker {
@@ -466,13 +564,21 @@ impl BandwidthTracker {
             (0.0, 0.0)
         }
     }
+
+    pub fn admit_send(&mut self, class: TrafficClass, bytes: u64) -> SendDecision {
+        self.limiter.admit_send(class, bytes, Instant::now())
+    }
+
+    pub fn admit_received(&mut self, class: TrafficClass, bytes: u64) -> bool {
+        self.limiter.admit_received(class, bytes, Instant::now())
+    }
 }
 
 impl P2PNetwork {
     /// Create a new P2P network instance
     pub async fn new(
         keypair: Option<identity::Keypair>,
-        _genesis_hash: [u8; 32],
+        genesis_hash: [u8; 32],
         network_id: &str,
         listen_addr: Option<String>,
         gossipsub_validation_mode: Option<String>,
@@ -488,6 +594,7 @@ impl P2PNetwork {
         let id_keys = keypair.unwrap_or_else(identity::Keypair::generate_ed25519);
         let local_peer_id = PeerId::from(id_keys.public());
         info!("Local peer id: {}", local_peer_id);
+        let local_version = LocalVersion::new(genesis_hash).with_identity(id_keys.clone());
 
         // Create communication channels
         let (command_sender, command_receiver) = mpsc::channel(128);
@@ -536,6 +643,12 @@ impl P2PNetwork {
                 connected_peers: Arc::new(RwLock::new(HashMap::new())),
                 discovery: Arc::new(RwLock::new(None)),
                 listen_address: listen_addr.unwrap_or_else(|| "0.0.0.0:8333".to_string()),
+                listen_addrs: Vec::new(),
+                address_advertiser: Arc::new(Mutex::new(AddressAdvertiser::new(
+                    Vec::new(),
+                    OBSERVED_ADDR_CONFIRMATIONS,
+                ))),
+                port_mapping: None,
                 network_task: Arc::new(RwLock::new(None)),
                 bandwidth_tracker: Arc::new(Mutex::new(BandwidthTracker::new())),
                 rate_limiter: Arc::new(RateLimiter::new(RateLimitConfig::default())),
@@ -554,6 +667,17 @@ impl P2PNetwork {
                     true,
                 )),
                 storage,
+                keepalive: Arc::new(Mutex::new(KeepaliveManager::new(
+                    KeepaliveConfig::default(),
+                ))),
+                request_manager: Arc::new(Mutex::new(RequestManager::default())),
+                tx_announcement: Arc::new(Mutex::new(TxAnnouncement::All)),
+                identity_links: Arc::new(Mutex::new(IdentityLinkRegistry::new())),
+                network_identity: Arc::new(Mutex::new(None)),
+                dns_seeder: Arc::new(Mutex::new(None)),
+                outbound_diversity: Arc::new(Mutex::new(OutboundDiversityPolicy::default())),
+                known_inventory_size: Arc::new(Mutex::new(DEFAULT_KNOWN_INVENTORY_SIZE)),
+                local_version: Arc::new(Mutex::new(local_version)),
             },
             command_sender,
             event_receiver,
@@ -707,7 +831,8 @@ impl P2PNetwork {
             let transport = build_transport(id_keys.clone())?;
 
             // Create individual behaviours with configured validation mode
-            let gossipsub_config = gossipsub::ConfigBuilder::default()
+            let mut gossipsub_builder = gossipsub::ConfigBuilder::default();
+            gossipsub_builder
                 .validation_mode(self.gossipsub_validation_mode.clone())
                 // Mesh parameters optimized for blockchain network scalability
                 // Constraint: mesh_outbound_min <= mesh_n_low <= mesh_n <= mesh_n_high
@@ -721,7 +846,13 @@ impl P2PNetwork {
                     let mut hasher = std::collections::hash_map::DefaultHasher::new();
                     msg.data.hash(&mut hasher);
                     gossipsub::MessageId::from(hasher.finish().to_string())
-                })
+                });
+            // Nodes that keep third-party transactions to themselves must
+            // vet each message before gossipsub forwards it.
+            if self.tx_announcement() == TxAnnouncement::OwnOnly {
+                gossipsub_builder.validate_messages();
+            }
+            let gossipsub_config = gossipsub_builder
                 .build()
                 .map_err(|e| format!("Failed to build gossipsub config: {}", e))?;
 
@@ -737,12 +868,13 @@ impl P2PNetwork {
             let mdns = Mdns::new(mdns::Config::default(), self.local_peer_id)?;
 
             // Configure Identify protocol with our version info
+            let protocol_version = self.protocol_version();
             info!("Configuring Identify protocol:");
-            info!("  ├─ Protocol Version: /supernova/1.0.0");
+            info!("  ├─ Protocol Version: {}", protocol_version);
             info!("  └─ Agent Version: supernova/1.0.0");
 
             let identify = Identify::new(
-                identify::Config::new("/supernova/1.0.0".to_string(), id_keys.public())
+                identify::Config::new(protocol_version, id_keys.public())
                     .with_agent_version("supernova/1.0.0".to_string()),
             );
 
@@ -758,7 +890,14 @@ impl P2PNetwork {
 
             // Subscribe to gossipsub topics
             info!("Subscribing to gossipsub topics...");
-            let topics = vec!["blocks", "transactions", "status", "headers", "mempool"];
+            let topics = vec![
+                "blocks",
+                "transactions",
+                "status",
+                "headers",
+                "mempool",
+                DIRECT_TOPIC,
+            ];
             for topic_name in &topics {
                 let topic = gossipsub::IdentTopic::new(*topic_name);
                 if let Err(e) = swarm.behaviour_mut().gossipsub.subscribe(&topic) {
@@ -772,20 +911,35 @@ impl P2PNetwork {
             *self.swarm.write().await = Some(swarm);
         }
 
-        // Start listening on configured address (NOT random port)
-        // Extract port from config (format: "0.0.0.0:8333" or "/ip4/0.0.0.0/tcp/8333")
-        let listen_port = self.extract_port_from_config()
-            .unwrap_or(8333); // Default to 8333 if parsing fails
-        
-        let listen_addr: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", listen_port)
-            .parse()
-            .map_err(|e| format!("Failed to parse listen address: {}", e))?;
-        
-        info!("Binding P2P network to {}", listen_addr);
-        
+        // Start listening on configured addresses (NOT random ports)
+        let listen_addrs = if self.listen_addrs.is_empty() {
+            // Extract port from config (format: "0.0.0.0:8333" or "/ip4/0.0.0.0/tcp/8333")
+            let listen_port = self.extract_port_from_config()
+                .unwrap_or(8333); // Default to 8333 if parsing fails
+
+            let listen_addr: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", listen_port)
+                .parse()
+                .map_err(|e| format!("Failed to parse listen address: {}", e))?;
+            vec![listen_addr]
+        } else {
+            self.listen_addrs.clone()
+        };
+
         if let Some(swarm) = self.swarm.write().await.as_mut() {
-            swarm.listen_on(listen_addr.clone())
-                .map_err(|e| format!("Failed to listen on {}: {}", listen_addr, e))?;
+            for listen_addr in &listen_addrs {
+                info!("Binding P2P network to {}", listen_addr);
+                swarm.listen_on(listen_addr.clone())
+                    .map_err(|e| format!("Failed to listen on {}: {}", listen_addr, e))?;
+            }
+            let explicit = self
+                .address_advertiser
+                .lock()
+                .map(|a| a.explicit().to_vec())
+                .unwrap_or_default();
+            for addr in explicit {
+                info!("Advertising configured external address {}", addr);
+                swarm.add_external_address(addr);
+            }
         } else {
             return Err("Swarm not initialized".into());
         }
@@ -800,10 +954,173 @@ impl P2PNetwork {
         
         // Dial bootstrap peers
         self.dial_bootstrap_peers().await?;
+        self.spawn_dns_seeding();
+        self.spawn_diversity_rotation();
+        self.spawn_port_mapping();
         
         Ok(())
     }
+
+    /// Map the listen port on the gateway and keep the lease fresh,
+    /// advertising the public address while the mapping holds. Without a
+    /// cooperating gateway the node keeps listening and retries later.
+    fn spawn_port_mapping(&self) {
+        let Some(mapping) = self.port_mapping.clone() else {
+            return;
+        };
+        if self
+            .address_advertiser
+            .lock()
+            .map(|a| !a.explicit().is_empty())
+            .unwrap_or(false)
+        {
+            info!("External addresses are configured; not mapping the listen port");
+            return;
+        }
+        let address_advertiser = Arc::clone(&self.address_advertiser);
+        let swarm_cmd_tx = Arc::clone(&self.swarm_cmd_tx);
+        let running = Arc::clone(&self.running);
+
+        tokio::spawn(async move {
+            let mut advertised: Option<Multiaddr> = None;
+            loop {
+                if !*running.read().await {
+                    break;
+                }
+                let mapped = mapping.refresh().await;
+                if mapped != advertised {
+                    if let Ok(mut advertiser) = address_advertiser.lock() {
+                        advertiser.set_mapped(mapped.clone());
+                    }
+                    if let Some(tx) = swarm_cmd_tx.read().await.clone() {
+                        if let Some(old) = advertised.take() {
+                            let _ = tx.send(SwarmCommand::RemoveExternalAddress(old)).await;
+                        }
+                        if let Some(new) = &mapped {
+                            info!("Advertising mapped address {}", new);
+                            let _ = tx.send(SwarmCommand::AddExternalAddress(new.clone())).await;
+                        }
+                    }
+                    advertised = mapped;
+                }
+                tokio::time::sleep(mapping.next_refresh()).await;
+            }
+        });
+    }
+
+    /// Resolve DNS seeds now and again whenever outbound connections run
+    /// short, dialling the addresses they return. Runs in the background so
+    /// slow or unreachable seeds never hold up startup.
+    fn spawn_dns_seeding(&self) {
+        let Some(mut seeder) = self.dns_seeder.lock().ok().and_then(|mut s| s.take()) else {
+            return;
+        };
+        let connected_peers = Arc::clone(&self.connected_peers);
+        let swarm_cmd_tx = Arc::clone(&self.swarm_cmd_tx);
+        let running = Arc::clone(&self.running);
+        let policy = self.outbound_diversity();
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SEED_STARVATION_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if !*running.read().await {
+                    break;
+                }
+                let outbound = connected_peers
+                    .read()
+                    .await
+                    .values()
+                    .filter(|peer| !peer.is_inbound)
+                    .count();
+                let Some(peers) = seeder.resolve_if_due(outbound).await else {
+                    continue;
+                };
+                let Some(tx) = swarm_cmd_tx.read().await.clone() else {
+                    continue;
+                };
+                // Seeds can return many addresses from one provider; dial the
+                // least represented prefixes first and skip those over a cap
+                let outbound_ips: Vec<IpAddr> =
+                    Self::outbound_addrs(&connected_peers.read().await)
+                        .into_iter()
+                        .map(|(_, ip)| ip)
+                        .collect();
+                let candidates = peers
+                    .into_iter()
+                    .map(|peer| {
+                        let ip = Self::multiaddr_to_ip(&peer.addr);
+                        (peer, ip)
+                    })
+                    .collect::<Vec<_>>();
+                let limit = candidates.len();
+                let peers = policy.select_candidates(&outbound_ips, candidates, limit);
+                for peer in peers {
+                    debug!("Dialing {} from DNS seed {}", peer.addr, peer.seed);
+                    if tx.send(SwarmCommand::Dial(peer.addr)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
     
+    /// Disconnect outbound peers that exceed a diversity cap, e.g. after the
+    /// caps were lowered, one per rotation interval so the node never drops
+    /// many peers at once. The newest connections go first.
+    fn spawn_diversity_rotation(&self) {
+        let policy = self.outbound_diversity();
+        let connected_peers = Arc::clone(&self.connected_peers);
+        let trusted_peers = Arc::clone(&self.trusted_peers);
+        let swarm_cmd_tx = Arc::clone(&self.swarm_cmd_tx);
+        let running = Arc::clone(&self.running);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(policy.rotation_interval());
+            // The first tick fires immediately; give peers time to connect
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if !*running.read().await {
+                    break;
+                }
+                let trusted = trusted_peers.read().await.clone();
+                let outbound: Vec<(PeerId, IpAddr)> =
+                    Self::outbound_addrs(&connected_peers.read().await)
+                        .into_iter()
+                        .filter(|(peer_id, _)| !trusted.contains(peer_id))
+                        .collect();
+                let Some(peer_id) = policy.excess_peers(&outbound, 1).pop() else {
+                    continue;
+                };
+                let Some(tx) = swarm_cmd_tx.read().await.clone() else {
+                    continue;
+                };
+                info!(
+                    "Disconnecting outbound peer {} to restore network diversity",
+                    peer_id
+                );
+                if tx.send(SwarmCommand::Disconnect(peer_id)).await.is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Outbound peers with an IP address, longest connected first
+    fn outbound_addrs(peers: &HashMap<PeerId, PeerInfo>) -> Vec<(PeerId, IpAddr)> {
+        let mut outbound: Vec<&PeerInfo> =
+            peers.values().filter(|peer| !peer.is_inbound).collect();
+        outbound.sort_by_key(|peer| peer.first_seen);
+        outbound
+            .into_iter()
+            .filter_map(|peer| {
+                let ip = peer.addresses.iter().find_map(Self::multiaddr_to_ip)?;
+                Some((peer.peer_id, ip))
+            })
+            .collect()
+    }
+
     /// Dial all configured bootstrap peers
     async fn dial_bootstrap_peers(&self) -> Result<(), Box<dyn Error>> {
         if self.bootstrap_nodes.is_empty() {
@@ -846,6 +1163,19 @@ impl P2PNetwork {
         let rate_limiter = Arc::clone(&self.rate_limiter);
         let banned_peers = Arc::clone(&self.banned_peers);
         let running = Arc::clone(&self.running);
+        let keepalive = Arc::clone(&self.keepalive);
+        let request_manager = Arc::clone(&self.request_manager);
+        let outbound_diversity = self.outbound_diversity();
+        let known_inventory_size = self.known_inventory_size();
+        let local_version = Arc::clone(&self.local_version);
+        let swarm_handle = Arc::clone(&self.swarm);
+        let address_advertiser = Arc::clone(&self.address_advertiser);
+        let tx_announcement = self.tx_announcement();
+        let protocol_version = self.protocol_version();
+        let ping_interval = keepalive
+            .lock()
+            .map(|k| k.config().ping_interval)
+            .unwrap_or_else(|_| KeepaliveConfig::default().ping_interval);
 
         // Take ownership of the swarm
         let mut swarm = self
@@ -898,6 +1228,12 @@ impl P2PNetwork {
                                         debug!("Disconnect requested for already-disconnected peer: {}", peer_id);
                                     }
                                 }
+                                SwarmCommand::AddExternalAddress(addr) => {
+                                    swarm.add_external_address(addr);
+                                }
+                                SwarmCommand::RemoveExternalAddress(addr) => {
+                                    swarm.remove_external_address(&addr);
+                                }
                                 SwarmCommand::Stop => {
                                     break;
                                 }
@@ -927,6 +1263,7 @@ impl P2PNetwork {
                                         let wrapped = SwarmEventWrapper::ConnectionEstablished {
                                             peer_id,
                                             endpoint: endpoint.get_remote_address().to_string(),
+                                            inbound: !endpoint.is_dialer(),
                                         };
                                         let _ = swarm_event_tx.send(wrapped).await;
                                     }
@@ -1014,7 +1351,19 @@ impl P2PNetwork {
                                         match behaviour_event {
                                             SupernovaBehaviourEvent::Gossipsub(gossipsub_event) => {
                                                 match gossipsub_event {
-                                                    gossipsub::Event::Message { propagation_source, message, .. } => {
+                                                    gossipsub::Event::Message { propagation_source, message_id, message } => {
+                                                        if tx_announcement == TxAnnouncement::OwnOnly {
+                                                            let acceptance = if tx_announcement.forwards_topic(message.topic.as_str()) {
+                                                                gossipsub::MessageAcceptance::Accept
+                                                            } else {
+                                                                gossipsub::MessageAcceptance::Ignore
+                                                            };
+                                                            let _ = swarm.behaviour_mut().gossipsub.report_message_validation_result(
+                                                                &message_id,
+                                                                &propagation_source,
+                                                                acceptance,
+                                                            );
+                                                        }
                                                         let wrapped = SwarmEventWrapper::Message {
                                                             peer_id: propagation_source,
                                                             topic: message.topic.to_string(),
@@ -1049,6 +1398,25 @@ impl P2PNetwork {
                                                 match identify_event {
                                                     identify::Event::Received { peer_id, info } => {
                                                         info!("✓ IDENTIFY RECEIVED from peer: {}", peer_id);
+                                                        // Peers of another network (different magic or
+                                                        // genesis) can't share blocks with us
+                                                        if info.protocol_version != protocol_version {
+                                                            warn!(
+                                                                "Disconnecting peer {}: network {} does not match ours ({})",
+                                                                peer_id, info.protocol_version, protocol_version
+                                                            );
+                                                            let _ = swarm.disconnect_peer_id(peer_id);
+                                                            continue;
+                                                        }
+                                                        let confirmed = address_advertiser
+                                                            .lock()
+                                                            .ok()
+                                                            .filter(|a| a.explicit().is_empty())
+                                                            .and_then(|mut a| a.record_observed(peer_id, &info.observed_addr));
+                                                        if let Some(addr) = confirmed {
+                                                            info!("  ├─ Observed address confirmed by peers, advertising {}", addr);
+                                                            swarm.add_external_address(addr);
+                                                        }
                                                         info!("  ├─ Protocol Version: {}", info.protocol_version);
                                                         info!("  ├─ Agent Version: {}", info.agent_version);
                                                         info!("  ├─ Supported Protocols ({}):", info.protocols.len());
@@ -1126,6 +1494,18 @@ impl P2PNetwork {
                                             }
                                         }
                                     }
+                                    SwarmEvent::NewListenAddr { address, .. } => {
+                                        info!("Listening on {}", address);
+                                        if let Ok(mut advertiser) = address_advertiser.lock() {
+                                            advertiser.add_listen_addr(address);
+                                        }
+                                    }
+                                    SwarmEvent::ExpiredListenAddr { address, .. } => {
+                                        info!("No longer listening on {}", address);
+                                        if let Ok(mut advertiser) = address_advertiser.lock() {
+                                            advertiser.remove_listen_addr(&address);
+                                        }
+                                    }
                                     _ => {}
                                 }
                             }
@@ -1174,6 +1554,14 @@ impl P2PNetwork {
             
             let mut rate_limit_cleanup_interval = tokio::time::interval(Duration::from_secs(300));
             let mut ban_cleanup_interval = tokio::time::interval(Duration::from_secs(60));
+            // Check more often than the ping interval so missed pongs are
+            // detected close to the configured deadline.
+            let mut keepalive_interval = tokio::time::interval(
+                (ping_interval / 4).max(Duration::from_secs(1)),
+            );
+            // Drain the request manager: newly announced objects, failovers
+            // after timeouts, and capacity freed by deliveries.
+            let mut request_interval = tokio::time::interval(Duration::from_millis(250));
 
             info!("Network event loop STARTED - ready to process commands");
 
@@ -1239,6 +1627,10 @@ impl P2PNetwork {
                             &swarm_cmd_tx,
                             max_peers,
                             &rate_limiter,
+                            &request_manager,
+                            &outbound_diversity,
+                            known_inventory_size,
+                            &local_version,
                         ).await;
 
                         // CRITICAL: Check for pending commands before processing more swarm events
@@ -1286,6 +1678,10 @@ impl P2PNetwork {
                                         &swarm_cmd_tx,
                                         max_peers,
                                         &rate_limiter,
+                                        &request_manager,
+                                        &outbound_diversity,
+                                        known_inventory_size,
+                                        &local_version,
                                     ).await;
                                     batch_count += 1;
                                 }
@@ -1322,6 +1718,40 @@ impl P2PNetwork {
                         let now = Instant::now();
                         banned_peers.write().await.retain(|_, ban_time| *ban_time > now);
                     }
+
+                    _ = keepalive_interval.tick() => {
+                        let dead_peers = Self::run_keepalive_round(
+                            &keepalive,
+                            &connected_peers,
+                            &swarm_handle,
+                            &stats,
+                            &bandwidth_tracker,
+                        ).await;
+                        for peer_id in dead_peers {
+                            let _ = swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+                            if connected_peers.write().await.remove(&peer_id).is_some() {
+                                let mut stats = stats.write().await;
+                                stats.peers_connected = stats.peers_connected.saturating_sub(1);
+                                drop(stats);
+                                let _ = event_sender
+                                    .send(NetworkEvent::PeerDisconnected(peer_id))
+                                    .await;
+                            }
+                            if let Ok(mut requests) = request_manager.lock() {
+                                requests.remove_peer(&peer_id);
+                            }
+                        }
+                    }
+
+                    _ = request_interval.tick() => {
+                        Self::dispatch_object_requests(
+                            &request_manager,
+                            &swarm_cmd_tx,
+                            &stats,
+                            &connected_peers,
+                            &bandwidth_tracker,
+                        ).await;
+                    }
                 }
             }
 
@@ -1346,6 +1776,7 @@ impl P2PNetwork {
             message: &Message,
             swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
             stats: &Arc<RwLock<NetworkStats>>,
+            connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
             bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
         ) {
             let topic = match message {
@@ -1357,17 +1788,52 @@ impl P2PNetwork {
                 Message::Transaction { .. } => TopicHash::from_raw("transactions"),
                 Message::Headers { .. } => TopicHash::from_raw("headers"),
                 Message::Status { .. } | Message::GetStatus => TopicHash::from_raw("status"),
-                Message::GetMempool { .. } | Message::Mempool { .. } => {
-                    TopicHash::from_raw("mempool")
-                }
+                Message::GetMempool { .. }
+                | Message::Mempool { .. }
+                | Message::GetMempoolSummary { .. }
+                | Message::MempoolSummary { .. } => TopicHash::from_raw("mempool"),
                 _ => TopicHash::from_raw("general"),
             };
 
             info!("📨 broadcast_message called for topic: {:?}", topic);
 
-            if let Ok(data) = bincode::serialize(&message) {
+            // Gossip cannot skip single peers, but nothing is published once
+            // every peer already has the block or transaction
+            let inventory = inventory_of(message);
+            if let Some(id) = inventory.as_ref() {
+                let peers = connected_peers.read().await;
+                if !peers.is_empty()
+                    && peers
+                        .values()
+                        .all(|peer_info| peer_info.metadata.known_inventory.contains(id))
+                {
+                    let skipped = peers.len() as u64;
+                    drop(peers);
+                    debug!("All peers already have {:?}, not broadcasting", id);
+                    stats.write().await.relay_suppressed += skipped;
+                    return;
+                }
+            }
+
+            if let Ok(data) = framing::encode_message(message) {
                 let data_len = data.len();
                 info!("📦 Serialized message: {} bytes, sending to swarm", data_len);
+
+                // Gossip reaches every connected peer, so the budget is
+                // charged once per peer
+                let recipients: Vec<PeerId> =
+                    connected_peers.read().await.keys().copied().collect();
+                let charged = data_len as u64 * recipients.len().max(1) as u64;
+                if !P2PNetwork::acquire_upload(
+                    bandwidth_tracker,
+                    TrafficClass::of(message),
+                    charged,
+                )
+                .await
+                {
+                    debug!("Upload budget exhausted, dropping broadcast on {:?}", topic);
+                    return;
+                }
                 
                 match swarm_cmd_tx.send(SwarmCommand::Publish(topic.clone(), data)).await {
                     Ok(_) => {
@@ -1378,10 +1844,22 @@ impl P2PNetwork {
                     }
                 }
 
+                if let Some(id) = inventory {
+                    let mut peers = connected_peers.write().await;
+                    for peer_id in &recipients {
+                        if let Some(peer_info) = peers.get_mut(peer_id) {
+                            peer_info.metadata.known_inventory.insert(id);
+                        }
+                    }
+                }
+
                 // Update stats
                 let mut stats_guard = stats.write().await;
                 stats_guard.messages_sent += 1;
                 stats_guard.bytes_sent += data_len as u64;
+                for peer_id in recipients {
+                    stats_guard.record_peer_sent(peer_id, data_len as u64);
+                }
 
                 // Track bandwidth
                 if let Ok(mut tracker) = bandwidth_tracker.lock() {
@@ -1391,6 +1869,117 @@ impl P2PNetwork {
                 error!("✗ Failed to serialize message for broadcast");
             }
         }
+
+        // Helper to send a message to one peer
+        async fn send_to_peer(
+            peer_id: PeerId,
+            message: Message,
+            swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
+            stats: &Arc<RwLock<NetworkStats>>,
+            connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+            bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+        ) {
+            // Light clients get only what their bloom filter matches
+            let messages = match connected_peers
+                .write()
+                .await
+                .get_mut(&peer_id)
+                .and_then(|peer_info| peer_info.metadata.bloom_filter.as_mut())
+            {
+                Some(filter) => filter_message(message, filter),
+                None => vec![message],
+            };
+
+            for message in messages {
+                // Never relay a block or transaction the peer already has
+                let inventory = inventory_of(&message);
+                if let Some(id) = inventory.as_ref() {
+                    let known = connected_peers
+                        .read()
+                        .await
+                        .get(&peer_id)
+                        .is_some_and(|peer_info| peer_info.metadata.known_inventory.contains(id));
+                    if known {
+                        trace!("Peer {} already has {:?}, not relaying", peer_id, id);
+                        stats.write().await.relay_suppressed += 1;
+                        continue;
+                    }
+                }
+
+                // For direct messages, we'd need to implement a custom protocol
+                // For now, we'll use gossipsub for all messages
+                let class = TrafficClass::of(&message);
+                let Some(message) =
+                    P2PNetwork::seal_direct(peer_id, message, connected_peers).await
+                else {
+                    continue;
+                };
+                let topic = TopicHash::from_raw(DIRECT_TOPIC);
+                let data = match framing::encode(&(peer_id.to_string(), message)) {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!("Failed to frame message for {}: {}", peer_id, e);
+                        continue;
+                    }
+                };
+                let data_len = data.len() as u64;
+                if !P2PNetwork::acquire_upload(bandwidth_tracker, class, data_len).await {
+                    debug!("Upload budget exhausted, dropping message to {}", peer_id);
+                    continue;
+                }
+                if swarm_cmd_tx
+                    .send(SwarmCommand::Publish(topic, data))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+                let mut stats_guard = stats.write().await;
+                stats_guard.messages_sent += 1;
+                stats_guard.bytes_sent += data_len;
+                stats_guard.record_peer_sent(peer_id, data_len);
+                drop(stats_guard);
+                if let Some(peer_info) = connected_peers.write().await.get_mut(&peer_id) {
+                    peer_info.bytes_sent += data_len;
+                    if let Some(id) = inventory {
+                        peer_info.metadata.known_inventory.insert(id);
+                    }
+                }
+                if let Ok(mut tracker) = bandwidth_tracker.lock() {
+                    tracker.record_sent(data_len);
+                }
+            }
+        }
+
+        // Helper to relay an announcement to light clients, which do not
+        // follow the gossip topics; `send_to_peer` applies their filters
+        async fn relay_to_filtering_peers(
+            message: &Message,
+            swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
+            stats: &Arc<RwLock<NetworkStats>>,
+            connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+            bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+        ) {
+            let filtering: Vec<PeerId> = connected_peers
+                .read()
+                .await
+                .iter()
+                .filter(|(_, peer_info)| peer_info.metadata.bloom_filter.is_some())
+                .map(|(peer_id, _)| *peer_id)
+                .collect();
+            for peer_id in filtering {
+                send_to_peer(
+                    peer_id,
+                    message.clone(),
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
+            }
+        }
+
         match cmd {
             NetworkCommand::ConnectToPeer(addr_str) => {
                 // Parse bootstrap peer (supports peer_id@ip:port format)
@@ -1418,15 +2007,26 @@ impl P2PNetwork {
             }
 
             NetworkCommand::Broadcast(message) => {
-                broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                broadcast_message(
+                    &message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
             }
 
             NetworkCommand::SendToPeer { peer_id, message } => {
-                // For direct messages, we'd need to implement a custom protocol
-                // For now, we'll use gossipsub for all messages
-                let topic = TopicHash::from_raw("messages");
-                let data = bincode::serialize(&(peer_id.to_string(), message)).unwrap_or_default();
-                let _ = swarm_cmd_tx.send(SwarmCommand::Publish(topic, data)).await;
+                send_to_peer(
+                    peer_id,
+                    message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
             }
 
             NetworkCommand::AnnounceBlock {
@@ -1444,8 +2044,23 @@ impl P2PNetwork {
                 };
 
                 info!("📤 Broadcasting NewBlock message to gossipsub network");
-                broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                broadcast_message(
+                    &message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
                 info!("✓ Broadcast command completed");
+                relay_to_filtering_peers(
+                    &message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
 
                 let mut stats_guard = stats.write().await;
                 stats_guard.blocks_announced += 1;
@@ -1459,7 +2074,22 @@ impl P2PNetwork {
                     transaction: bincode::serialize(&transaction).unwrap_or_default(),
                 };
 
-                broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                broadcast_message(
+                    &message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
+                relay_to_filtering_peers(
+                    &message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
 
                 let mut stats_guard = stats.write().await;
                 stats_guard.transactions_announced += 1;
@@ -1476,13 +2106,24 @@ impl P2PNetwork {
                 };
 
                 if let Some(peer_id) = preferred_peer {
-                    // Handle SendToPeer directly without recursion
-                    let topic = TopicHash::from_raw("messages");
-                    let data =
-                        bincode::serialize(&(peer_id.to_string(), message)).unwrap_or_default();
-                    let _ = swarm_cmd_tx.send(SwarmCommand::Publish(topic, data)).await;
+                    send_to_peer(
+                        peer_id,
+                        message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
                 } else {
-                    broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                    broadcast_message(
+                        &message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
                 }
             }
 
@@ -1493,13 +2134,24 @@ impl P2PNetwork {
                 let message = Message::GetBlocksByHash { block_hashes };
 
                 if let Some(peer_id) = preferred_peer {
-                    // Handle SendToPeer directly without recursion
-                    let topic = TopicHash::from_raw("messages");
-                    let data =
-                        bincode::serialize(&(peer_id.to_string(), message)).unwrap_or_default();
-                    let _ = swarm_cmd_tx.send(SwarmCommand::Publish(topic, data)).await;
+                    send_to_peer(
+                        peer_id,
+                        message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
                 } else {
-                    broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                    broadcast_message(
+                        &message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
                 }
             }
 
@@ -1514,13 +2166,24 @@ impl P2PNetwork {
                 };
 
                 if let Some(peer_id) = preferred_peer {
-                    // Handle SendToPeer directly without recursion
-                    let topic = TopicHash::from_raw("messages");
-                    let data =
-                        bincode::serialize(&(peer_id.to_string(), message)).unwrap_or_default();
-                    let _ = swarm_cmd_tx.send(SwarmCommand::Publish(topic, data)).await;
+                    send_to_peer(
+                        peer_id,
+                        message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
                 } else {
-                    broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                    broadcast_message(
+                        &message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
                 }
             }
 
@@ -1541,7 +2204,14 @@ impl P2PNetwork {
                         .as_secs(),
                 };
 
-                broadcast_message(&message, swarm_cmd_tx, stats, bandwidth_tracker).await;
+                broadcast_message(
+                    &message,
+                    swarm_cmd_tx,
+                    stats,
+                    connected_peers,
+                    bandwidth_tracker,
+                )
+                .await;
             }
 
             NetworkCommand::BanPeer {
@@ -1613,13 +2283,17 @@ impl P2PNetwork {
             | Message::GetCompactBlockTxs { .. }
             | Message::Blocks { .. }
             | Message::BlockResponse { .. }
-            | Message::Headers { .. } => MessageType::BlockRequest,
+            | Message::Headers { .. }
+            | Message::SnapshotRequest { .. }
+            | Message::SnapshotChunk { .. } => MessageType::BlockRequest,
             Message::GetAddr
             | Message::Addr(_)
             | Message::GetStatus
             | Message::Status { .. }
             | Message::Version(_)
-            | Message::Verack => MessageType::PeerDiscovery,
+            | Message::Verack
+            | Message::KemOffer(_)
+            | Message::KemAccept(_) => MessageType::PeerDiscovery,
             _ => MessageType::General,
         }
     }
@@ -1634,9 +2308,17 @@ impl P2PNetwork {
         swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
         max_peers: usize,
         rate_limiter: &Arc<RateLimiter>,
+        request_manager: &Arc<Mutex<RequestManager>>,
+        outbound_diversity: &OutboundDiversityPolicy,
+        known_inventory_size: usize,
+        local_version: &Arc<Mutex<LocalVersion>>,
     ) {
         match event {
-            SwarmEventWrapper::ConnectionEstablished { peer_id, endpoint } => {
+            SwarmEventWrapper::ConnectionEstablished {
+                peer_id,
+                endpoint,
+                inbound,
+            } => {
                 // DoS bound: enforce the configured peer cap before allocating and
                 // inserting into the unbounded connected_peers map. Without this an
                 // attacker could open unlimited inbound connections and exhaust
@@ -1665,6 +2347,35 @@ impl P2PNetwork {
                 let remote_addrs: Vec<Multiaddr> =
                     endpoint.parse::<Multiaddr>().ok().into_iter().collect();
 
+                // Eclipse resistance: cap outbound peers sharing a prefix or ASN
+                if !inbound {
+                    if let Some(ip) = remote_addrs.iter().find_map(Self::multiaddr_to_ip) {
+                        let outbound: Vec<IpAddr> =
+                            Self::outbound_addrs(&connected_peers.read().await)
+                                .into_iter()
+                                .filter(|(id, _)| *id != peer_id)
+                                .map(|(_, ip)| ip)
+                                .collect();
+                        if !outbound_diversity.admits(&outbound, ip) {
+                            warn!(
+                                "Outbound peer {} at {} would exceed the diversity caps; disconnecting",
+                                peer_id, ip
+                            );
+                            let _ = swarm_cmd_tx
+                                .send(SwarmCommand::Disconnect(peer_id))
+                                .await;
+                            return;
+                        }
+                    }
+                }
+
+                let (version, offers_encryption) = match local_version.lock() {
+                    Ok(local) => (
+                        Some(local.message(endpoint.clone(), String::new())),
+                        local.offers_encryption(),
+                    ),
+                    Err(_) => (None, false),
+                };
                 let peer_info = PeerInfo {
                     peer_id,
                     state: PeerState::Connected,
@@ -1672,7 +2383,7 @@ impl P2PNetwork {
                     first_seen: Instant::now(),
                     last_seen: Instant::now(),
                     last_sent: None,
-                    is_inbound: true, // We'll assume inbound for now
+                    is_inbound: inbound,
                     protocol_version: None,
                     user_agent: None,
                     height: None,
@@ -1686,7 +2397,11 @@ impl P2PNetwork {
                     services: 0,
                     bytes_sent: 0,
                     bytes_received: 0,
-                    metadata: peer::PeerMetadata::default(),
+                    metadata: peer::PeerMetadata {
+                        known_inventory: KnownInventory::new(known_inventory_size),
+                        encryption: PeerEncryption::new(offers_encryption),
+                        ..Default::default()
+                    },
                 };
 
                 connected_peers
@@ -1698,13 +2413,31 @@ impl P2PNetwork {
                 let _ = event_sender
                     .send(NetworkEvent::PeerConnected(peer_info))
                     .await;
+
+                // Open the version handshake
+                if let Some(version) = version {
+                    Self::send_direct(
+                        peer_id,
+                        Message::Version(version),
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
+                }
                 info!("Peer connected: {} at {}", peer_id, endpoint);
             }
             SwarmEventWrapper::ConnectionClosed { peer_id } => {
                 connected_peers.write().await.remove(&peer_id);
+                // Requests outstanding to this peer fail over to other announcers.
+                if let Ok(mut requests) = request_manager.lock() {
+                    requests.remove_peer(&peer_id);
+                }
                 {
                     let mut s = stats.write().await;
                     s.peers_connected = s.peers_connected.saturating_sub(1);
+                    s.peer_bandwidth.remove(&peer_id);
                 }
 
                 let _ = event_sender
@@ -1717,7 +2450,7 @@ impl P2PNetwork {
             }
             SwarmEventWrapper::Message {
                 peer_id,
-                topic: _,
+                topic,
                 data,
             } => {
                 // Check size BEFORE deserialization
@@ -1740,8 +2473,12 @@ impl P2PNetwork {
                 }
                 
                 // Update stats (only for valid-sized messages)
-                stats.write().await.messages_received += 1;
-                stats.write().await.bytes_received += data.len() as u64;
+                {
+                    let mut stats_guard = stats.write().await;
+                    stats_guard.messages_received += 1;
+                    stats_guard.bytes_received += data.len() as u64;
+                    stats_guard.record_peer_received(peer_id, data.len() as u64);
+                }
 
                 // Track bandwidth
                 if let Ok(mut tracker) = bandwidth_tracker.lock() {
@@ -1751,13 +2488,62 @@ impl P2PNetwork {
                 // Update peer info and capture the peer's IP for rate limiting.
                 let mut peer_ip: Option<IpAddr> = None;
                 if let Some(peer_info) = connected_peers.write().await.get_mut(&peer_id) {
+                    peer_info.bytes_received += data.len() as u64;
                     peer_info.metadata.transactions_received += 1;
                     peer_info.last_seen = Instant::now();
                     peer_ip = peer_info.addresses.iter().find_map(Self::multiaddr_to_ip);
                 }
 
-                // NOW SAFE: Deserialize after size validation
-                if let Ok(message) = bincode::deserialize::<Message>(&data) {
+                // NOW SAFE: Deserialize after size validation. Direct
+                // messages are framed with their recipient.
+                let direct = topic == DIRECT_TOPIC;
+                let decoded = if direct {
+                    framing::decode::<(String, Message)>(&data)
+                        .map(|(recipient, message)| (Some(recipient), message))
+                } else {
+                    framing::decode_message(&data).map(|message| (None, message))
+                };
+                if let Err(e) = &decoded {
+                    // A damaged frame means the stream can no longer be trusted
+                    if e.is_corruption() {
+                        warn!("Resetting connection to {}: {}", peer_id, e);
+                        stats.write().await.invalid_messages += 1;
+                        let _ = swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+                        return;
+                    }
+                    debug!("Undecodable message from {}: {}", peer_id, e);
+                }
+                if let Ok((recipient, message)) = decoded {
+                    // Direct messages for other peers are only relayed
+                    let local_peer_id = local_version.lock().ok().and_then(|local| {
+                        local
+                            .identity
+                            .as_ref()
+                            .map(|identity| identity.public().to_peer_id().to_string())
+                    });
+                    if let (Some(recipient), Some(local_peer_id)) = (&recipient, local_peer_id) {
+                        if *recipient != local_peer_id {
+                            trace!("Direct message from {} is for {}", peer_id, recipient);
+                            return;
+                        }
+                    }
+
+                    // Relay traffic over the download budget is discarded
+                    // before any work is done on it
+                    let admitted = bandwidth_tracker
+                        .lock()
+                        .map(|mut tracker| {
+                            tracker.admit_received(TrafficClass::of(&message), data.len() as u64)
+                        })
+                        .unwrap_or(true);
+                    if !admitted {
+                        debug!(
+                            "Download budget exhausted, dropping relay message from {}",
+                            peer_id
+                        );
+                        return;
+                    }
+
                     // SECURITY FIX [R5-88]: Per-peer, per-message-type rate limiting.
                     // The 4MB size cap above bounds a single message, but without a
                     // per-peer throttle one connected peer could flood
@@ -1783,12 +2569,41 @@ impl P2PNetwork {
                             return;
                         }
                     }
+
+                    // A peer that agreed to encrypt must seal its direct messages
+                    let message = if direct {
+                        let opened = match connected_peers.write().await.get_mut(&peer_id) {
+                            Some(peer_info) => peer_info.metadata.encryption.inbound(message),
+                            None => Ok(message),
+                        };
+                        match opened {
+                            Ok(message) => message,
+                            Err(e) => {
+                                warn!("Disconnecting {}: encrypted transport: {}", peer_id, e);
+                                stats.write().await.invalid_messages += 1;
+                                let _ = swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+                                return;
+                            }
+                        }
+                    } else {
+                        message
+                    };
+
+                    // Whatever a peer relays to us, it already has
+                    if let Some(id) = inventory_of(&message) {
+                        if let Some(peer_info) = connected_peers.write().await.get_mut(&peer_id) {
+                            peer_info.metadata.known_inventory.insert(id);
+                        }
+                    }
                     match message {
                         Message::Transaction { transaction } => {
                             // Deserialize transaction bytes
                             match bincode::deserialize::<Transaction>(&transaction) {
                                 Ok(tx) => {
                                     trace!("Dispatching transaction from peer {}", peer_id);
+                                    if let Ok(mut requests) = request_manager.lock() {
+                                        requests.received(&ObjectId::transaction(tx.hash()));
+                                    }
                                     let _ = event_sender.send(NetworkEvent::NewTransaction {
                                         transaction: tx,
                                         fee_rate: 1000, // Default fee rate
@@ -1802,6 +2617,9 @@ impl P2PNetwork {
                         }
                         Message::Block(block) => {
                             trace!("Dispatching block from peer {}", peer_id);
+                            if let Ok(mut requests) = request_manager.lock() {
+                                requests.received(&ObjectId::block(block.hash()));
+                            }
                             let _ = event_sender.send(NetworkEvent::NewBlock {
                                 block: block.clone(),
                                 height: block.height(),
@@ -1814,6 +2632,9 @@ impl P2PNetwork {
                             match bincode::deserialize::<Block>(&block_data) {
                                 Ok(block) => {
                                     trace!("Dispatching new block announcement from peer {}", peer_id);
+                                    if let Ok(mut requests) = request_manager.lock() {
+                                        requests.received(&ObjectId::block(block.hash()));
+                                    }
                                     let _ = event_sender.send(NetworkEvent::NewBlock {
                                         block,
                                         height,
@@ -1868,8 +2689,14 @@ impl P2PNetwork {
                                     let request = Message::GetBlocksByHash {
                                         block_hashes: vec![block_hash],
                                     };
-                                    if let Ok(data) = bincode::serialize(&request) {
+                                    if let Ok(data) = framing::encode_message(&request) {
                                         let data_len = data.len();
+                                        Self::acquire_upload(
+                                            bandwidth_tracker,
+                                            TrafficClass::Priority,
+                                            data_len as u64,
+                                        )
+                                        .await;
                                         if swarm_cmd_tx
                                             .send(SwarmCommand::Publish(
                                                 TopicHash::from_raw("blocks"),
@@ -1881,6 +2708,7 @@ impl P2PNetwork {
                                             let mut stats_guard = stats.write().await;
                                             stats_guard.messages_sent += 1;
                                             stats_guard.bytes_sent += data_len as u64;
+                                            stats_guard.record_peer_sent(peer_id, data_len as u64);
                                             drop(stats_guard);
                                             if let Ok(mut tracker) = bandwidth_tracker.lock() {
                                                 tracker.record_sent(data_len as u64);
@@ -1895,6 +2723,26 @@ impl P2PNetwork {
                                 }
                             }
                         }
+                        Message::TransactionAnnouncement { tx_hash, fee_rate } => {
+                            // Register the announcer; the request manager decides
+                            // whether (and from whom) to fetch the transaction so
+                            // the same tx is never requested from several peers.
+                            let newly_tracked = match request_manager.lock() {
+                                Ok(mut requests) => requests.announce(
+                                    peer_id,
+                                    ObjectId::transaction(tx_hash),
+                                    Instant::now(),
+                                ),
+                                Err(_) => false,
+                            };
+                            trace!(
+                                "Transaction announcement {} (fee rate {}) from peer {} (new: {})",
+                                hex::encode(tx_hash),
+                                fee_rate,
+                                peer_id,
+                                newly_tracked
+                            );
+                        }
                         Message::GetCompactBlockTxs { short_ids } => {
                             trace!(
                                 "Received request for {} missing transactions from peer {}",
@@ -1912,14 +2760,183 @@ impl P2PNetwork {
                                 })
                                 .await;
                         }
-                        Message::CompactBlockTxs(transactions) => {
-                            trace!(
-                                "Received {} missing transactions from peer {}",
-                                transactions.len(),
-                                peer_id
-                            );
-                            let _ = event_sender
-                                .send(NetworkEvent::MessageReceived {
+                        Message::FilterLoad {
+                            filter,
+                            bit_count,
+                            hash_count,
+                            tweak,
+                        } => {
+                            match SupernovaBloomFilter::from_filter_load(
+                                &filter, bit_count, hash_count, tweak,
+                            ) {
+                                Ok(loaded) => {
+                                    if let Some(peer_info) =
+                                        connected_peers.write().await.get_mut(&peer_id)
+                                    {
+                                        peer_info.metadata.bloom_filter = Some(loaded);
+                                    }
+                                    debug!("Peer {} loaded a bloom filter", peer_id);
+                                }
+                                Err(e) => {
+                                    warn!(
+                                        "Disconnecting {}: unacceptable bloom filter: {}",
+                                        peer_id, e
+                                    );
+                                    let _ =
+                                        swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+                                }
+                            }
+                        }
+                        Message::FilterAdd { element } => {
+                            let added = match connected_peers
+                                .write()
+                                .await
+                                .get_mut(&peer_id)
+                                .and_then(|peer_info| peer_info.metadata.bloom_filter.as_mut())
+                            {
+                                Some(filter) => filter.add_from_peer(&element),
+                                // Adding without a loaded filter is a protocol violation
+                                None => Err(BloomFilterError::InvalidSize),
+                            };
+                            if let Err(e) = added {
+                                warn!(
+                                    "Disconnecting {}: rejected bloom filter element: {}",
+                                    peer_id, e
+                                );
+                                let _ = swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+                            }
+                        }
+                        Message::Version(version) => {
+                            let (negotiated, identity, local_services) = match local_version.lock()
+                            {
+                                Ok(local) => (
+                                    local.negotiate(&version),
+                                    local.identity.clone(),
+                                    local.services,
+                                ),
+                                Err(_) => return,
+                            };
+                            match negotiated {
+                                Ok(negotiated) => {
+                                    let encryption =
+                                        match connected_peers.write().await.get_mut(&peer_id) {
+                                            Some(peer_info) => {
+                                                peer_info.protocol_version =
+                                                    Some(negotiated.version);
+                                                peer_info.services = negotiated.services;
+                                                peer_info.user_agent =
+                                                    Some(version.user_agent.clone());
+                                                peer_info.height = Some(version.start_height);
+                                                Self::settle_encryption(
+                                                    peer_info,
+                                                    identity.as_ref(),
+                                                    local_services,
+                                                    version.services,
+                                                )
+                                            }
+                                            None => Ok(Vec::new()),
+                                        };
+                                    debug!(
+                                        "Peer {} speaks protocol {} with services {:#x}",
+                                        peer_id, negotiated.version, negotiated.services
+                                    );
+                                    Self::send_direct(
+                                        peer_id,
+                                        Message::Verack,
+                                        swarm_cmd_tx,
+                                        stats,
+                                        connected_peers,
+                                        bandwidth_tracker,
+                                    )
+                                    .await;
+                                    Self::advance_encryption(
+                                        peer_id,
+                                        encryption,
+                                        swarm_cmd_tx,
+                                        stats,
+                                        connected_peers,
+                                        bandwidth_tracker,
+                                    )
+                                    .await;
+                                }
+                                Err(e) => {
+                                    warn!("Disconnecting {}: {}", peer_id, e);
+                                    let _ =
+                                        swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+                                }
+                            }
+                        }
+                        Message::Verack => {
+                            if let Some(peer_info) = connected_peers.write().await.get_mut(&peer_id)
+                            {
+                                peer_info.verified = true;
+                            }
+                        }
+                        Message::KemOffer(offer) => {
+                            let identity = local_version
+                                .lock()
+                                .ok()
+                                .and_then(|local| local.identity.clone());
+                            let accepted = match connected_peers.write().await.get_mut(&peer_id) {
+                                Some(peer_info) => match &identity {
+                                    Some(identity) => {
+                                        peer_info.metadata.encryption.accept(identity, &offer).map(
+                                            |(accept, held)| {
+                                                let mut outgoing = vec![Message::KemAccept(accept)];
+                                                outgoing.extend(held);
+                                                outgoing
+                                            },
+                                        )
+                                    }
+                                    None => Err(EncryptionError::Unexpected("key offer")),
+                                },
+                                None => return,
+                            };
+                            if accepted.is_ok() {
+                                info!("Encrypted transport established with {}", peer_id);
+                            }
+                            Self::advance_encryption(
+                                peer_id,
+                                accepted,
+                                swarm_cmd_tx,
+                                stats,
+                                connected_peers,
+                                bandwidth_tracker,
+                            )
+                            .await;
+                        }
+                        Message::KemAccept(accept) => {
+                            let completed = match connected_peers.write().await.get_mut(&peer_id) {
+                                Some(peer_info) => peer_info.metadata.encryption.complete(&accept),
+                                None => return,
+                            };
+                            if completed.is_ok() {
+                                info!("Encrypted transport established with {}", peer_id);
+                            }
+                            Self::advance_encryption(
+                                peer_id,
+                                completed,
+                                swarm_cmd_tx,
+                                stats,
+                                connected_peers,
+                                bandwidth_tracker,
+                            )
+                            .await;
+                        }
+                        Message::FilterClear => {
+                            if let Some(peer_info) = connected_peers.write().await.get_mut(&peer_id)
+                            {
+                                peer_info.metadata.bloom_filter = None;
+                            }
+                        }
+                        Message::CompactBlockTxs(transactions) => {
+                            trace!(
+                                "Received {} missing transactions from peer {}",
+                                transactions.len(),
+                                peer_id
+                            );
+                            let _ = event_sender
+                                .send(NetworkEvent::MessageReceived {
                                     peer_id,
                                     message: Message::CompactBlockTxs(transactions),
                                 })
@@ -1982,24 +2999,33 @@ impl P2PNetwork {
                         services: "1".to_string(),
                         banned: false, // Would check banned_peers if needed
                         reputation_score: 1.0, // Default good reputation
+                        encryption: info.encryption_status().to_string(),
                     })
                     .collect();
                 
                 let _ = tx.send(Ok(peers));
             }
             ProxyRequest::GetBandwidthUsage(_period, tx) => {
-                let (upload_rate, download_rate) = bandwidth_tracker
+                let (upload_rate, download_rate, limits) = bandwidth_tracker
                     .lock()
-                    .map(|tracker| tracker.get_rates(60))
-                    .unwrap_or((0.0, 0.0));
-                
+                    .map(|tracker| {
+                        let (up, down) = tracker.get_rates(60);
+                        (up, down, tracker.limiter.stats())
+                    })
+                    .unwrap_or_default();
+                let stats_guard = stats.read().await;
+
                 let usage = crate::api::types::BandwidthUsage {
-                    total_sent: stats.read().await.bytes_sent,
-                    total_received: stats.read().await.bytes_received,
+                    total_sent: stats_guard.bytes_sent,
+                    total_received: stats_guard.bytes_received,
                     upload_rate,
                     download_rate,
                     peak_upload_rate: upload_rate, // Simplified: use current as peak
                     peak_download_rate: download_rate,
+                    delayed_sends: limits.delayed_sends,
+                    dropped_sends: limits.dropped_sends,
+                    dropped_receives: limits.dropped_receives,
+                    peers: peer_bandwidth_usage(&stats_guard.peer_bandwidth),
                 };
                 
                 let _ = tx.send(Ok(usage));
@@ -2043,6 +3069,11 @@ impl P2PNetwork {
             task.abort();
         }
 
+        // Give the forwarded port back to the gateway
+        if let Some(mapping) = &self.port_mapping {
+            mapping.release().await;
+        }
+
         // Clear the swarm
         *self.swarm.write().await = None;
 
@@ -2118,29 +3149,22 @@ impl P2PNetwork {
         let connected_peers = self.connected_peers.read().await;
         let peer_count = connected_peers.len();
 
-        // Determine if we're listening
-        let swarm_guard = self.swarm.read().await;
-        let listening = if let Some(swarm) = swarm_guard.as_ref() {
-            !swarm.listeners().collect::<Vec<_>>().is_empty()
-        } else {
-            false
-        };
-
-        // Lock swarm to collect local addresses
-        let swarm_guard = self.swarm.read().await;
-        let local_addresses = if let Some(swarm) = swarm_guard.as_ref() {
-            swarm
-                .listeners()
-                .map(|addr| crate::api::types::NetworkAddress {
-                    address: addr.to_string(),
-                    port: 0, // Port will be extracted from the address string in practice
-                    score: 0,
-                })
-                .collect()
-        } else {
-            vec![]
-        };
-        drop(swarm_guard);
+        // Bound and advertised addresses, as tracked from swarm events
+        let (bound, advertised) = self
+            .address_advertiser
+            .lock()
+            .map(|a| (a.listen_addrs().to_vec(), a.advertised()))
+            .map_err(|e| Box::<dyn Error>::from(format!("Address advertiser lock poisoned: {}", e)))?;
+        let listening = !bound.is_empty();
+        let local_addresses = bound
+            .iter()
+            .map(|addr| crate::api::types::NetworkAddress {
+                address: addr.to_string(),
+                port: tcp_port(addr).unwrap_or(0),
+                score: 0,
+            })
+            .collect();
+        let advertised_addresses = advertised.iter().map(|addr| addr.to_string()).collect();
 
         // Try to detect external IP from connected peers
         let external_ip = self.detect_external_ip(&connected_peers).await;
@@ -2174,7 +3198,9 @@ impl P2PNetwork {
             is_listening: listening,
             accepts_incoming: listening,
             local_addresses,
+            advertised_addresses,
             external_ip,
+            nat: self.nat_status(),
             network_stats: crate::api::types::NetworkStats {
                 total_bytes_sent: bytes_sent,
                 total_bytes_received: bytes_received,
@@ -2264,6 +3290,7 @@ impl P2PNetwork {
                 services: self.format_service_flags(peer_info.services),
                 banned: matches!(peer_info.state, PeerState::Banned),
                 reputation_score: peer_info.reputation as f64,
+                encryption: peer_info.encryption_status().to_string(),
             };
             api_peers.push(api_peer);
         }
@@ -2319,6 +3346,7 @@ impl P2PNetwork {
                 services: self.format_service_flags(peer_info.services),
                 banned: matches!(peer_info.state, PeerState::Banned),
                 reputation_score: peer_info.reputation as f64,
+                encryption: peer_info.encryption_status().to_string(),
             };
 
             Ok(Some(api_peer))
@@ -2332,24 +3360,27 @@ impl P2PNetwork {
         let mut flags = Vec::new();
 
         // Common Bitcoin-style service flags
-        if services & 0x01 != 0 {
+        if services & NODE_NETWORK != 0 {
             flags.push("NETWORK");
         }
         if services & 0x02 != 0 {
             flags.push("GETUTXO");
         }
-        if services & 0x04 != 0 {
+        if services & NODE_BLOOM != 0 {
             flags.push("BLOOM");
         }
         if services & 0x08 != 0 {
             flags.push("WITNESS");
         }
-        if services & 0x400 != 0 {
+        if services & NODE_NETWORK_LIMITED != 0 {
             flags.push("NETWORK_LIMITED");
         }
+        if services & NODE_COMPACT_BLOCKS != 0 {
+            flags.push("COMPACT_BLOCKS");
+        }
 
         // Supernova-specific flags
-        if services & 0x1000 != 0 {
+        if services & NODE_QUANTUM_SIGS != 0 {
             flags.push("QUANTUM");
         }
         if services & 0x2000 != 0 {
@@ -2358,6 +3389,9 @@ impl P2PNetwork {
         if services & 0x4000 != 0 {
             flags.push("ENVIRONMENTAL");
         }
+        if services & NODE_ENCRYPTED != 0 {
+            flags.push("ENCRYPTED");
+        }
 
         flags.join(",")
     }
@@ -2442,14 +3476,22 @@ impl P2PNetwork {
             Box::<dyn Error>::from(format!("Bandwidth tracker lock poisoned: {}", e))
         })?;
         let (upload_rate, download_rate) = bandwidth.get_rates(period);
+        let limits: BandwidthLimitStats = bandwidth.limiter.stats();
+        let (total_sent, total_received) = (bandwidth.bytes_sent, bandwidth.bytes_received);
+        drop(bandwidth);
+        let peers = peer_bandwidth_usage(&self.stats.read().await.peer_bandwidth);
 
         Ok(BandwidthUsage {
-            total_sent: bandwidth.bytes_sent,
-            total_received: bandwidth.bytes_received,
+            total_sent,
+            total_received,
             upload_rate,
             download_rate,
             peak_upload_rate: upload_rate, // For now, use current rate as peak
             peak_download_rate: download_rate, // For now, use current rate as peak
+            delayed_sends: limits.delayed_sends,
+            dropped_sends: limits.dropped_sends,
+            dropped_receives: limits.dropped_receives,
+            peers,
         })
     }
 
@@ -2505,26 +3547,322 @@ impl P2PNetwork {
         }
     }
 
-    /// Send ping to all connected peers
-    pub async fn ping_peers(&self) {
-        let peer_ids: Vec<PeerId> = self.connected_peers.read().await.keys().cloned().collect();
+    /// Replace the keepalive configuration (ping interval, missed-pong and
+    /// latency limits), typically from `NetworkConfig`.
+    pub fn configure_keepalive(&self, config: KeepaliveConfig) {
+        if let Ok(mut keepalive) = self.keepalive.lock() {
+            *keepalive = KeepaliveManager::new(config);
+        }
+    }
 
-        for peer_id in peer_ids {
-            let ping_message = Message::Ping(rand::random::<u64>());
+    /// Apply listen and advertisement settings from `NetworkConfig`. Must be
+    /// called before `start`.
+    pub fn configure_addresses(&mut self, config: &NetworkConfig) -> Result<(), Box<dyn Error>> {
+        self.listen_addrs = config.effective_listen_addrs()?;
+        if let Ok(mut advertiser) = self.address_advertiser.lock() {
+            *advertiser = AddressAdvertiser::from_network_config(config);
+        }
+        self.port_mapping = PortMapping::from_config(config).map(Arc::new);
+        Ok(())
+    }
 
-            // Send ping message
-            Self::send_to_peer_static(
+    /// State of the gateway port mapping
+    pub fn nat_status(&self) -> NatStatus {
+        self.port_mapping
+            .as_ref()
+            .map(|mapping| mapping.status())
+            .unwrap_or_default()
+    }
+
+    /// Shared handle to the advertised-address selection, so peer exchange
+    /// can announce the same addresses as identify
+    pub fn address_advertiser(&self) -> Arc<Mutex<AddressAdvertiser>> {
+        Arc::clone(&self.address_advertiser)
+    }
+
+    /// Addresses currently advertised to peers
+    pub fn advertised_addresses(&self) -> Vec<Multiaddr> {
+        self.address_advertiser
+            .lock()
+            .map(|a| a.advertised())
+            .unwrap_or_default()
+    }
+
+    /// Shared handle to the request manager, so the sync scheduler can route
+    /// its block requests through the same in-flight accounting.
+    pub fn request_manager(&self) -> Arc<Mutex<RequestManager>> {
+        Arc::clone(&self.request_manager)
+    }
+
+    /// Peer manager, shared with the node so identity rotations announced
+    /// by peers can be applied to its records.
+    pub fn peer_manager(&self) -> Arc<PeerManager> {
+        Arc::clone(&self.peer_manager)
+    }
+
+    /// Identity links learned from peers' rotation attestations
+    pub fn identity_links(&self) -> Arc<Mutex<IdentityLinkRegistry>> {
+        Arc::clone(&self.identity_links)
+    }
+
+    /// Wait until the upload budget admits `bytes` of `class` traffic.
+    /// Returns false if the message should be dropped instead.
+    async fn acquire_upload(
+        bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+        class: TrafficClass,
+        bytes: u64,
+    ) -> bool {
+        loop {
+            let decision = match bandwidth_tracker.lock() {
+                Ok(mut tracker) => tracker.admit_send(class, bytes),
+                Err(_) => return true,
+            };
+            match decision {
+                SendDecision::Send => return true,
+                SendDecision::Drop => return false,
+                SendDecision::Wait(wait) => tokio::time::sleep(wait).await,
+            }
+        }
+    }
+
+    /// Send the requests the request manager is ready to issue, batched into
+    /// one message per peer and object kind: `GetBlocksByHash` for blocks and
+    /// `GetData` for transactions.
+    async fn dispatch_object_requests(
+        request_manager: &Arc<Mutex<RequestManager>>,
+        swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+        bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+    ) {
+        let assignments = match request_manager.lock() {
+            Ok(mut requests) => requests.poll(Instant::now()),
+            Err(_) => return,
+        };
+        if assignments.is_empty() {
+            return;
+        }
+
+        for ((peer_id, kind), hashes) in batch_assignments(&assignments) {
+            let message = match kind {
+                ObjectKind::Block => Message::GetBlocksByHash {
+                    block_hashes: hashes,
+                },
+                ObjectKind::Transaction => Message::GetData(hashes),
+            };
+            Self::send_direct(
                 peer_id,
-                ping_message,
-                &self.swarm,
-                &self.stats,
-                &self.bandwidth_tracker,
+                message,
+                swarm_cmd_tx,
+                stats,
+                connected_peers,
+                bandwidth_tracker,
             )
             .await;
         }
     }
 
+    /// Seal a direct message under the peer's encrypted session, or hold it
+    /// back while one is negotiated. `None` when nothing is to be sent now.
+    async fn seal_direct(
+        peer_id: PeerId,
+        message: Message,
+        connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+    ) -> Option<Message> {
+        let mut peers = connected_peers.write().await;
+        let Some(peer_info) = peers.get_mut(&peer_id) else {
+            return Some(message);
+        };
+        match peer_info.metadata.encryption.outbound(message) {
+            Ok(Some(message)) => Some(message),
+            Ok(None) => {
+                trace!("Holding message for {} until encryption settles", peer_id);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to encrypt message for {}: {}", peer_id, e);
+                None
+            }
+        }
+    }
+
+    /// Start the key exchange with a peer whose `Version` was accepted if
+    /// both sides advertised encryption, or release the messages held back
+    /// for one. Returns what to send the peer next.
+    fn settle_encryption(
+        peer_info: &mut PeerInfo,
+        identity: Option<&identity::Keypair>,
+        local_services: u64,
+        remote_services: u64,
+    ) -> Result<Vec<Message>, EncryptionError> {
+        let negotiated = peer_info.supports(NODE_ENCRYPTED);
+        let encryption = &mut peer_info.metadata.encryption;
+        match identity {
+            Some(identity) if negotiated => {
+                let context = HandshakeContext {
+                    local: identity.public().to_peer_id(),
+                    remote: peer_info.peer_id,
+                    local_services,
+                    remote_services,
+                };
+                let offer = encryption.start(identity, context)?;
+                Ok(offer.map(Message::KemOffer).into_iter().collect())
+            }
+            _ => encryption.decline(),
+        }
+    }
+
+    /// Send the next key exchange messages to a peer, or disconnect it if
+    /// the exchange failed
+    async fn advance_encryption(
+        peer_id: PeerId,
+        step: Result<Vec<Message>, EncryptionError>,
+        swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+        bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+    ) {
+        match step {
+            Ok(messages) => {
+                for message in messages {
+                    Self::send_direct(
+                        peer_id,
+                        message,
+                        swarm_cmd_tx,
+                        stats,
+                        connected_peers,
+                        bandwidth_tracker,
+                    )
+                    .await;
+                }
+            }
+            Err(e) => {
+                warn!("Disconnecting {}: encrypted transport: {}", peer_id, e);
+                stats.write().await.invalid_messages += 1;
+                let _ = swarm_cmd_tx.send(SwarmCommand::Disconnect(peer_id)).await;
+            }
+        }
+    }
+
+    /// Publish a message addressed to one peer, within the upload budget
+    async fn send_direct(
+        peer_id: PeerId,
+        message: Message,
+        swarm_cmd_tx: &mpsc::Sender<SwarmCommand>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+        bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+    ) {
+        let class = TrafficClass::of(&message);
+        let Some(message) = Self::seal_direct(peer_id, message, connected_peers).await else {
+            return;
+        };
+        let topic = TopicHash::from_raw(DIRECT_TOPIC);
+        let data = match framing::encode(&(peer_id.to_string(), message)) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to frame message for {}: {}", peer_id, e);
+                return;
+            }
+        };
+        let data_len = data.len() as u64;
+        if !Self::acquire_upload(bandwidth_tracker, class, data_len).await {
+            return;
+        }
+        if swarm_cmd_tx
+            .send(SwarmCommand::Publish(topic, data))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        let mut stats_guard = stats.write().await;
+        stats_guard.messages_sent += 1;
+        stats_guard.bytes_sent += data_len;
+        stats_guard.record_peer_sent(peer_id, data_len);
+        drop(stats_guard);
+        if let Ok(mut tracker) = bandwidth_tracker.lock() {
+            tracker.record_sent(data_len);
+        }
+    }
+
+    /// Run one keepalive round: ping peers that have been idle for the ping
+    /// interval and disconnect peers that stopped answering.
+    ///
+    /// The network event loop runs this on its own timer; it is exposed for
+    /// callers that drive maintenance manually (tests, simulations).
+    pub async fn ping_peers(&self) {
+        let dead_peers = Self::run_keepalive_round(
+            &self.keepalive,
+            &self.connected_peers,
+            &self.swarm,
+            &self.stats,
+            &self.bandwidth_tracker,
+        )
+        .await;
+
+        for peer_id in dead_peers {
+            self.update_peer_behavior(&peer_id, -5.0).await;
+            if let Err(e) = self.disconnect_from_peer(&peer_id).await {
+                debug!("Failed to disconnect dead peer {}: {}", peer_id, e);
+            }
+        }
+    }
+
+    /// Ping idle peers and return the peers the keepalive manager declared
+    /// dead. Pings are only sent to idle connections, so busy peers are not
+    /// charged extra traffic.
+    async fn run_keepalive_round(
+        keepalive: &Arc<Mutex<KeepaliveManager>>,
+        connected_peers: &Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+        swarm: &Arc<RwLock<Option<Swarm<SupernovaBehaviour>>>>,
+        stats: &Arc<RwLock<NetworkStats>>,
+        bandwidth_tracker: &Arc<Mutex<BandwidthTracker>>,
+    ) -> Vec<PeerId> {
+        let connected: Vec<(PeerId, Instant)> = connected_peers
+            .read()
+            .await
+            .iter()
+            .map(|(peer_id, info)| (*peer_id, info.last_seen))
+            .collect();
+
+        let actions = match keepalive.lock() {
+            Ok(mut keepalive) => {
+                keepalive.sync_connected(&connected);
+                keepalive.poll(Instant::now(), rand::random::<u64>)
+            }
+            Err(_) => {
+                warn!("Keepalive state lock poisoned, skipping ping round");
+                return Vec::new();
+            }
+        };
+
+        let mut dead_peers = Vec::new();
+        for action in actions {
+            match action {
+                KeepaliveAction::SendPing { peer_id, nonce } => {
+                    Self::send_to_peer_static(
+                        peer_id,
+                        Message::Ping(nonce),
+                        swarm,
+                        stats,
+                        bandwidth_tracker,
+                    )
+                    .await;
+                }
+                KeepaliveAction::Disconnect { peer_id, reason } => {
+                    warn!("Disconnecting unresponsive peer {}: {:?}", peer_id, reason);
+                    dead_peers.push(peer_id);
+                }
+            }
+        }
+        dead_peers
+    }
+
     /// Handle incoming ping
+    ///
+    /// The pong is sent immediately rather than queued behind relay traffic:
+    /// a node that answers pings late under load looks dead to its peers.
     pub async fn handle_ping(&self, peer_id: &PeerId, nonce: u64) {
         let pong_message = Message::Pong(nonce);
 
@@ -2541,14 +3879,41 @@ impl P2PNetwork {
 
     /// Handle incoming pong
     pub async fn handle_pong(&self, peer_id: &PeerId, nonce: u64) {
-        // Update peer latency
+        let (outcome, max_latency) = match self.keepalive.lock() {
+            Ok(mut keepalive) => (
+                keepalive.handle_pong(peer_id, nonce, Instant::now()),
+                keepalive.config().max_latency,
+            ),
+            Err(_) => {
+                warn!("Keepalive state lock poisoned, ignoring pong from {}", peer_id);
+                return;
+            }
+        };
+
+        let latency = match outcome {
+            PongOutcome::Latency(latency) | PongOutcome::Disconnect(latency) => latency,
+            PongOutcome::Unsolicited => {
+                debug!("Ignoring unsolicited pong from {} with nonce {}", peer_id, nonce);
+                return;
+            }
+        };
+
         if let Some(peer_info) = self.connected_peers.write().await.get_mut(peer_id) {
-            // In a real implementation, we'd calculate latency based on ping timestamp
-            peer_info.ping_ms = Some(50); // Placeholder
+            peer_info.ping_ms = Some(latency.as_millis() as u64);
             peer_info.last_seen = Instant::now();
         }
+        self.update_peer_behavior(peer_id, latency_score_delta(latency, max_latency))
+            .await;
 
-        debug!("Received pong from {} with nonce {}", peer_id, nonce);
+        if let PongOutcome::Disconnect(latency) = outcome {
+            warn!(
+                "Disconnecting peer {}: latency {:?} persistently above {:?}",
+                peer_id, latency, max_latency
+            );
+            if let Err(e) = self.disconnect_from_peer(peer_id).await {
+                debug!("Failed to disconnect slow peer {}: {}", peer_id, e);
+            }
+        }
     }
 
     /// Static method to broadcast a message
@@ -2697,6 +4062,13 @@ impl P2PNetwork {
             },
             average_latency_ms: stats.avg_latency_ms,
             network_diversity: self.calculate_network_diversity(&connected_peers).await,
+            outbound_buckets: self.outbound_diversity().distribution(
+                &Self::outbound_addrs(&connected_peers)
+                    .into_iter()
+                    .map(|(_, ip)| ip)
+                    .collect::<Vec<_>>(),
+            ),
+            nat: self.nat_status(),
         }
     }
 
@@ -2846,6 +4218,7 @@ impl P2PNetwork {
 
         let keypair = identity::Keypair::generate_ed25519();
         let local_peer_id = PeerId::from(keypair.public());
+        let local_version = LocalVersion::new([0u8; 32]).with_identity(keypair.clone());
         
         Self {
             local_peer_id,
@@ -2860,6 +4233,12 @@ impl P2PNetwork {
             connected_peers: Arc::new(RwLock::new(HashMap::new())),
             discovery: Arc::new(RwLock::new(None)),
             listen_address: "0.0.0.0:8333".to_string(),
+            listen_addrs: Vec::new(),
+            address_advertiser: Arc::new(Mutex::new(AddressAdvertiser::new(
+                Vec::new(),
+                OBSERVED_ADDR_CONFIRMATIONS,
+            ))),
+            port_mapping: None,
             network_task: Arc::new(RwLock::new(None)),
             bandwidth_tracker: Arc::new(Mutex::new(BandwidthTracker::new())),
             rate_limiter: Arc::new(RateLimiter::new(RateLimitConfig::default())),
@@ -2878,6 +4257,15 @@ impl P2PNetwork {
                 true,
             )),
             storage,
+            keepalive: Arc::new(Mutex::new(KeepaliveManager::new(KeepaliveConfig::default()))),
+            request_manager: Arc::new(Mutex::new(RequestManager::default())),
+            tx_announcement: Arc::new(Mutex::new(TxAnnouncement::All)),
+            identity_links: Arc::new(Mutex::new(IdentityLinkRegistry::new())),
+            network_identity: Arc::new(Mutex::new(None)),
+            dns_seeder: Arc::new(Mutex::new(None)),
+            outbound_diversity: Arc::new(Mutex::new(OutboundDiversityPolicy::default())),
+            known_inventory_size: Arc::new(Mutex::new(DEFAULT_KNOWN_INVENTORY_SIZE)),
+            local_version: Arc::new(Mutex::new(local_version)),
         }
     }
 
@@ -2912,6 +4300,104 @@ impl P2PNetwork {
         }
     }
 
+    /// Set which transactions this node announces. Must be called before
+    /// `start`, which fixes the gossipsub configuration.
+    pub fn set_tx_announcement(&self, announcement: TxAnnouncement) {
+        if let Ok(mut current) = self.tx_announcement.lock() {
+            *current = announcement;
+        }
+    }
+
+    /// Join a custom network identified by its P2P magic and genesis hash.
+    /// Peers advertising another network are disconnected. Must be called
+    /// before `start`.
+    pub fn set_network_identity(&self, magic: [u8; 4], genesis_hash: [u8; 32]) {
+        if let Ok(mut identity) = self.network_identity.lock() {
+            *identity = Some((magic, genesis_hash));
+        }
+    }
+
+    /// Resolve peers from DNS seeds once started. Must be called before
+    /// `start`.
+    pub fn set_dns_seeder(&self, seeder: DnsSeeder) {
+        if let Ok(mut current) = self.dns_seeder.lock() {
+            *current = Some(seeder);
+        }
+    }
+
+    /// Cap outbound peers per network prefix and ASN. Must be called before
+    /// `start`.
+    pub fn set_outbound_diversity(&self, policy: OutboundDiversityPolicy) {
+        if let Ok(mut current) = self.outbound_diversity.lock() {
+            *current = policy;
+        }
+    }
+
+    /// Chain height advertised in the version handshake
+    pub fn set_best_height(&self, height: u64) {
+        if let Ok(mut local) = self.local_version.lock() {
+            local.best_height = height;
+        }
+    }
+
+    /// Advertise a pruned node in the version handshake
+    pub fn set_pruned(&self, pruned: bool) {
+        if let Ok(mut local) = self.local_version.lock() {
+            local.set_pruned(pruned);
+        }
+    }
+
+    /// Offer to encrypt direct messages with peers that support it
+    pub fn set_encrypted_transport(&self, enabled: bool) {
+        if let Ok(mut local) = self.local_version.lock() {
+            local.set_encryption(enabled);
+        }
+    }
+
+    /// Entries remembered per peer to avoid relaying inventory back. Must be
+    /// called before `start`.
+    pub fn set_known_inventory_size(&self, size: usize) {
+        if let Ok(mut current) = self.known_inventory_size.lock() {
+            *current = size;
+        }
+    }
+
+    /// Known-inventory entries kept per peer
+    pub fn known_inventory_size(&self) -> usize {
+        self.known_inventory_size
+            .lock()
+            .map(|size| *size)
+            .unwrap_or(DEFAULT_KNOWN_INVENTORY_SIZE)
+    }
+
+    /// Enforce global upload and download budgets from now on
+    pub fn set_bandwidth_limits(&self, config: &BandwidthConfig) {
+        if let Ok(mut tracker) = self.bandwidth_tracker.lock() {
+            tracker.limiter = BandwidthLimiter::new(config, Instant::now());
+        }
+    }
+
+    /// Outbound diversity policy in force
+    pub fn outbound_diversity(&self) -> OutboundDiversityPolicy {
+        self.outbound_diversity
+            .lock()
+            .map(|policy| policy.clone())
+            .unwrap_or_default()
+    }
+
+    /// Identify protocol version this node advertises and requires of peers
+    pub fn protocol_version(&self) -> String {
+        identify_protocol_version(self.network_identity.lock().ok().and_then(|id| *id))
+    }
+
+    /// Transaction announcement behavior
+    pub fn tx_announcement(&self) -> TxAnnouncement {
+        self.tx_announcement
+            .lock()
+            .map(|announcement| *announcement)
+            .unwrap_or(TxAnnouncement::All)
+    }
+
     /// Broadcast a transaction to all peers
     pub fn broadcast_transaction(&self, tx: &Transaction) {
         let tx_bytes = bincode::serialize(tx).unwrap_or_default();
@@ -2965,6 +4451,10 @@ pub struct NetworkHealth {
     pub message_success_rate: f64,
     pub average_latency_ms: f64,
     pub network_diversity: f64,
+    /// Outbound peers per prefix and ASN bucket
+    pub outbound_buckets: BucketDistribution,
+    /// Gateway port mapping and the public address it gives us
+    pub nat: NatStatus,
 }
 
 /// Build the libp2p transport stack
@@ -3003,8 +4493,26 @@ fn count_leading_zero_bits(hash: &[u8]) -> u8 {
     count
 }
 
+/// Per-peer counters for the bandwidth API, heaviest uploads first
+fn peer_bandwidth_usage(
+    peers: &HashMap<PeerId, PeerTraffic>,
+) -> Vec<crate::api::types::PeerBandwidthUsage> {
+    let mut usage: Vec<_> = peers
+        .iter()
+        .map(|(peer_id, traffic)| crate::api::types::PeerBandwidthUsage {
+            peer_id: peer_id.to_string(),
+            bytes_sent: traffic.bytes_sent,
+            bytes_received: traffic.bytes_received,
+            messages_sent: traffic.messages_sent,
+            messages_received: traffic.messages_received,
+        })
+        .collect();
+    usage.sort_by(|a, b| b.bytes_sent.cmp(&a.bytes_sent));
+    usage
+}
+
 /// Extract IP address from multiaddr
-fn extract_ip_from_multiaddr(addr: &Multiaddr) -> Option<IpAddr> {
+pub(crate) fn extract_ip_from_multiaddr(addr: &Multiaddr) -> Option<IpAddr> {
     use libp2p::multiaddr::Protocol;
 
     for proto in addr.iter() {
@@ -3017,6 +4525,16 @@ fn extract_ip_from_multiaddr(addr: &Multiaddr) -> Option<IpAddr> {
     None
 }
 
+/// Extract TCP port from multiaddr
+fn tcp_port(addr: &Multiaddr) -> Option<u16> {
+    use libp2p::multiaddr::Protocol;
+
+    addr.iter().find_map(|proto| match proto {
+        Protocol::Tcp(port) => Some(port),
+        _ => None,
+    })
+}
+
 /// Extract socket address from multiaddr
 fn extract_socket_addr_from_multiaddr(addr: &Multiaddr) -> Option<std::net::SocketAddr> {
     use libp2p::multiaddr::Protocol;
@@ -3069,6 +4587,7 @@ fn solve_pow_challenge(challenge: &[u8], difficulty: u8) -> Vec<u8> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::encryption::EncryptionStatus;
 
     // A basic test for network creation
     #[tokio::test]
@@ -3081,6 +4600,34 @@ mod tests {
         assert_eq!(stats.peers_connected, 0);
     }
 
+    #[tokio::test]
+    async fn test_custom_network_refuses_default_peers() {
+        let magic = [0x0b, 0xad, 0xca, 0xfe];
+        let genesis = [7u8; 32];
+        let (first, _, _) = P2PNetwork::new(None, genesis, "teamnet", None, None)
+            .await
+            .unwrap();
+        let (second, _, _) = P2PNetwork::new(None, genesis, "teamnet", None, None)
+            .await
+            .unwrap();
+        let (default, _, _) = P2PNetwork::new(None, [0u8; 32], "testnet", None, None)
+            .await
+            .unwrap();
+        first.set_network_identity(magic, genesis);
+        second.set_network_identity(magic, genesis);
+
+        // Peers are accepted only when the advertised versions are equal
+        assert_eq!(first.protocol_version(), second.protocol_version());
+        assert_ne!(first.protocol_version(), default.protocol_version());
+        assert_eq!(default.protocol_version(), BASE_PROTOCOL_VERSION);
+
+        let (other_genesis, _, _) = P2PNetwork::new(None, [8u8; 32], "teamnet", None, None)
+            .await
+            .unwrap();
+        other_genesis.set_network_identity(magic, [8u8; 32]);
+        assert_ne!(first.protocol_version(), other_genesis.protocol_version());
+    }
+
     #[test]
     fn test_leading_zero_bits() {
         // All zeros should have 8 leading zero bits
@@ -3165,6 +4712,31 @@ mod tests {
         assert!(recv_rate > 0.0);
     }
 
+    #[test]
+    fn per_peer_traffic_feeds_bandwidth_usage() {
+        let (light, heavy) = (PeerId::random(), PeerId::random());
+        let mut stats = NetworkStats::default();
+        stats.record_peer_sent(light, 100);
+        stats.record_peer_sent(heavy, 5_000);
+        stats.record_peer_sent(heavy, 5_000);
+        stats.record_peer_received(light, 300);
+
+        let usage = peer_bandwidth_usage(&stats.peer_bandwidth);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].peer_id, heavy.to_string());
+        assert_eq!(usage[0].bytes_sent, 10_000);
+        assert_eq!(usage[0].messages_sent, 2);
+        assert_eq!(usage[1].bytes_received, 300);
+        assert_eq!(usage[1].messages_received, 1);
+    }
+
+    /// Next swarm command other than a publish, such as the handshake's
+    /// `Version`
+    fn next_control_command(rx: &mut mpsc::Receiver<SwarmCommand>) -> Option<SwarmCommand> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .find(|command| !matches!(command, SwarmCommand::Publish(..)))
+    }
+
     /// Build a minimal PeerInfo for connection-cap tests.
     fn dummy_peer_info(peer_id: PeerId) -> PeerInfo {
         PeerInfo {
@@ -3220,6 +4792,7 @@ mod tests {
         let event = SwarmEventWrapper::ConnectionEstablished {
             peer_id: new_peer,
             endpoint: "/ip4/127.0.0.1/tcp/1".to_string(),
+            inbound: true,
         };
 
         P2PNetwork::handle_wrapped_swarm_event(
@@ -3231,6 +4804,10 @@ mod tests {
             &swarm_cmd_tx,
             max_peers,
             &rate_limiter,
+            &Arc::new(Mutex::new(RequestManager::default())),
+            &OutboundDiversityPolicy::default(),
+            DEFAULT_KNOWN_INVENTORY_SIZE,
+            &Arc::new(Mutex::new(LocalVersion::new([0u8; 32]))),
         )
         .await;
 
@@ -3263,6 +4840,7 @@ mod tests {
         let event = SwarmEventWrapper::ConnectionEstablished {
             peer_id: new_peer,
             endpoint: "/ip4/127.0.0.1/tcp/1".to_string(),
+            inbound: true,
         };
 
         P2PNetwork::handle_wrapped_swarm_event(
@@ -3274,17 +4852,97 @@ mod tests {
             &swarm_cmd_tx,
             max_peers,
             &rate_limiter,
+            &Arc::new(Mutex::new(RequestManager::default())),
+            &OutboundDiversityPolicy::default(),
+            DEFAULT_KNOWN_INVENTORY_SIZE,
+            &Arc::new(Mutex::new(LocalVersion::new([0u8; 32]))),
         )
         .await;
 
         assert!(connected_peers.read().await.contains_key(&new_peer));
         assert_eq!(connected_peers.read().await.len(), 1);
         assert!(
-            swarm_cmd_rx.try_recv().is_err(),
+            next_control_command(&mut swarm_cmd_rx).is_none(),
             "no Disconnect command should be issued under the cap"
         );
     }
 
+    /// An outbound connection into a /16 that already holds the cap is
+    /// closed; inbound connections and other prefixes are unaffected.
+    #[tokio::test]
+    async fn test_outbound_prefix_cap_rejects_connection() {
+        let connected_peers: Arc<RwLock<HashMap<PeerId, PeerInfo>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+        for i in 1..=2 {
+            let id = PeerId::random();
+            let mut info = dummy_peer_info(id);
+            info.is_inbound = false;
+            info.addresses = vec![format!("/ip4/10.1.0.{}/tcp/1", i).parse().unwrap()];
+            connected_peers.write().await.insert(id, info);
+        }
+
+        let stats = Arc::new(RwLock::new(NetworkStats::default()));
+        let bandwidth_tracker = Arc::new(Mutex::new(BandwidthTracker::new()));
+        let (event_tx, _event_rx) = mpsc::channel::<NetworkEvent>(16);
+        let (swarm_cmd_tx, mut swarm_cmd_rx) = mpsc::channel::<SwarmCommand>(16);
+        let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+        let request_manager = Arc::new(Mutex::new(RequestManager::default()));
+        let policy = OutboundDiversityPolicy::new(2, 2);
+
+        let connect = |endpoint: &str, inbound: bool| {
+            let peer_id = PeerId::random();
+            (
+                peer_id,
+                SwarmEventWrapper::ConnectionEstablished {
+                    peer_id,
+                    endpoint: endpoint.to_string(),
+                    inbound,
+                },
+            )
+        };
+
+        let (rejected, event) = connect("/ip4/10.1.200.7/tcp/1", false);
+        let (inbound, inbound_event) = connect("/ip4/10.1.200.8/tcp/1", true);
+        let (other, other_event) = connect("/ip4/10.2.0.1/tcp/1", false);
+        for event in [event, inbound_event, other_event] {
+            P2PNetwork::handle_wrapped_swarm_event(
+                event,
+                &event_tx,
+                &stats,
+                &connected_peers,
+                &bandwidth_tracker,
+                &swarm_cmd_tx,
+                16,
+                &rate_limiter,
+                &request_manager,
+                &policy,
+                DEFAULT_KNOWN_INVENTORY_SIZE,
+                &Arc::new(Mutex::new(LocalVersion::new([0u8; 32]))),
+            )
+            .await;
+        }
+
+        let peers = connected_peers.read().await;
+        assert!(!peers.contains_key(&rejected));
+        assert!(peers.contains_key(&inbound));
+        assert!(peers.contains_key(&other));
+        match swarm_cmd_rx.try_recv() {
+            Ok(SwarmCommand::Disconnect(id)) => assert_eq!(id, rejected),
+            other => panic!("expected Disconnect command, got {:?}", other),
+        }
+        assert!(next_control_command(&mut swarm_cmd_rx).is_none());
+
+        let distribution = policy.distribution(
+            &P2PNetwork::outbound_addrs(&peers)
+                .into_iter()
+                .map(|(_, ip)| ip)
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(distribution.prefixes.get("10.1.0.0/16"), Some(&2));
+        assert_eq!(distribution.prefixes.get("10.2.0.0/16"), Some(&1));
+        assert_eq!(distribution.excess, 0);
+    }
+
     /// Inbound gossipsub messages from a single peer must be throttled by the
     /// per-message-type rate limiter (finding R5-88). With a block-request
     /// limit of 2/min, the third `GetData` from the same peer IP must be
@@ -3317,8 +4975,8 @@ mod tests {
         let rate_limiter = Arc::new(RateLimiter::new(config));
 
         // GetData maps to the BlockRequest bucket.
-        let data = bincode::serialize(&Message::GetData(vec![[0u8; 32]]))
-            .expect("serialize GetData");
+        let data =
+            framing::encode_message(&Message::GetData(vec![[0u8; 32]])).expect("serialize GetData");
 
         for _ in 0..3 {
             let event = SwarmEventWrapper::Message {
@@ -3335,6 +4993,10 @@ mod tests {
                 &swarm_cmd_tx,
                 max_peers,
                 &rate_limiter,
+                &Arc::new(Mutex::new(RequestManager::default())),
+                &OutboundDiversityPolicy::default(),
+                DEFAULT_KNOWN_INVENTORY_SIZE,
+                &Arc::new(Mutex::new(LocalVersion::new([0u8; 32]))),
             )
             .await;
         }
@@ -3388,5 +5050,403 @@ mod tests {
         assert_eq!(network.stats.read().await.peers_connected, 0);
         assert!(!network.connected_peers.read().await.contains_key(&peer_id));
     }
-}
 
+    /// A transaction a peer announced to us is not relayed back to it; the
+    /// known set starts empty when the peer reconnects.
+    #[tokio::test]
+    async fn test_known_inventory_suppresses_echo_until_reconnect() {
+        let peer_id = PeerId::random();
+        let connected_peers: Arc<RwLock<HashMap<PeerId, PeerInfo>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+        connected_peers
+            .write()
+            .await
+            .insert(peer_id, dummy_peer_info(peer_id));
+        let stats = Arc::new(RwLock::new(NetworkStats::default()));
+        let bandwidth_tracker = Arc::new(Mutex::new(BandwidthTracker::new()));
+        let (event_tx, _event_rx) = mpsc::channel::<NetworkEvent>(16);
+        let (swarm_cmd_tx, mut swarm_cmd_rx) = mpsc::channel::<SwarmCommand>(16);
+        let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+        let request_manager = Arc::new(Mutex::new(RequestManager::default()));
+
+        let announcement = Message::TransactionAnnouncement {
+            tx_hash: [7u8; 32],
+            fee_rate: 10,
+        };
+        let rounds = [
+            vec![SwarmEventWrapper::Message {
+                peer_id,
+                topic: "transactions".to_string(),
+                data: framing::encode_message(&announcement).expect("serialize announcement"),
+            }],
+            vec![
+                SwarmEventWrapper::ConnectionClosed { peer_id },
+                SwarmEventWrapper::ConnectionEstablished {
+                    peer_id,
+                    endpoint: "/ip4/127.0.0.1/tcp/1".to_string(),
+                    inbound: true,
+                },
+            ],
+        ];
+
+        let mut published = Vec::new();
+        for events in rounds {
+            for event in events {
+                P2PNetwork::handle_wrapped_swarm_event(
+                    event,
+                    &event_tx,
+                    &stats,
+                    &connected_peers,
+                    &bandwidth_tracker,
+                    &swarm_cmd_tx,
+                    8,
+                    &rate_limiter,
+                    &request_manager,
+                    &OutboundDiversityPolicy::default(),
+                    DEFAULT_KNOWN_INVENTORY_SIZE,
+                    &Arc::new(Mutex::new(LocalVersion::new([0u8; 32]))),
+                )
+                .await;
+            }
+            // Echo the announcement back to the peer
+            P2PNetwork::handle_command_with_channels(
+                NetworkCommand::SendToPeer {
+                    peer_id,
+                    message: announcement.clone(),
+                },
+                &swarm_cmd_tx,
+                &event_tx,
+                &stats,
+                &connected_peers,
+                &bandwidth_tracker,
+            )
+            .await;
+            let mut count = 0;
+            while let Ok(command) = swarm_cmd_rx.try_recv() {
+                if let SwarmCommand::Publish(_, data) = command {
+                    let (_, message): (String, Message) =
+                        framing::decode(&data).expect("direct message frame");
+                    if matches!(message, Message::TransactionAnnouncement { .. }) {
+                        count += 1;
+                    }
+                }
+            }
+            published.push(count);
+        }
+
+        // Suppressed while connected, sent once the peer has reconnected
+        assert_eq!(published, vec![0, 1]);
+        assert_eq!(stats.read().await.relay_suppressed, 1);
+    }
+
+    /// A peer's `Version` is answered with `Verack` and its negotiated
+    /// version recorded; a peer on another genesis, or one sending a
+    /// corrupted frame, is disconnected.
+    #[tokio::test]
+    async fn test_version_handshake_and_frame_checks() {
+        let (matching, foreign, corrupt) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let connected_peers: Arc<RwLock<HashMap<PeerId, PeerInfo>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+        for peer_id in [matching, foreign, corrupt] {
+            connected_peers
+                .write()
+                .await
+                .insert(peer_id, dummy_peer_info(peer_id));
+        }
+        let stats = Arc::new(RwLock::new(NetworkStats::default()));
+        let bandwidth_tracker = Arc::new(Mutex::new(BandwidthTracker::new()));
+        let (event_tx, _event_rx) = mpsc::channel::<NetworkEvent>(16);
+        let (swarm_cmd_tx, mut swarm_cmd_rx) = mpsc::channel::<SwarmCommand>(16);
+        let rate_limiter = Arc::new(RateLimiter::new(RateLimitConfig::default()));
+        let request_manager = Arc::new(Mutex::new(RequestManager::default()));
+        let local_version = Arc::new(Mutex::new(LocalVersion::new([1u8; 32])));
+
+        let version_from = |genesis_hash: [u8; 32]| {
+            let mut version = LocalVersion::new(genesis_hash).message(String::new(), String::new());
+            version.version = 1;
+            version.start_height = 42;
+            framing::encode_message(&Message::Version(version)).unwrap()
+        };
+        let mut damaged = framing::encode_message(&Message::Verack).unwrap();
+        *damaged.last_mut().unwrap() ^= 0xff;
+
+        for (peer_id, data) in [
+            (matching, version_from([1u8; 32])),
+            (foreign, version_from([2u8; 32])),
+            (corrupt, damaged),
+        ] {
+            P2PNetwork::handle_wrapped_swarm_event(
+                SwarmEventWrapper::Message {
+                    peer_id,
+                    topic: "status".to_string(),
+                    data,
+                },
+                &event_tx,
+                &stats,
+                &connected_peers,
+                &bandwidth_tracker,
+                &swarm_cmd_tx,
+                8,
+                &rate_limiter,
+                &request_manager,
+                &OutboundDiversityPolicy::default(),
+                DEFAULT_KNOWN_INVENTORY_SIZE,
+                &local_version,
+            )
+            .await;
+        }
+
+        match swarm_cmd_rx.try_recv() {
+            Ok(SwarmCommand::Publish(_, data)) => {
+                let (recipient, message): (String, Message) = framing::decode(&data).unwrap();
+                assert_eq!(recipient, matching.to_string());
+                assert!(matches!(message, Message::Verack));
+            }
+            other => panic!("expected Verack, got {:?}", other),
+        }
+        let mut disconnected = Vec::new();
+        while let Ok(command) = swarm_cmd_rx.try_recv() {
+            match command {
+                SwarmCommand::Disconnect(peer_id) => disconnected.push(peer_id),
+                other => panic!("unexpected command {:?}", other),
+            }
+        }
+        assert_eq!(disconnected, vec![foreign, corrupt]);
+
+        let peers = connected_peers.read().await;
+        let peer = &peers[&matching];
+        assert_eq!(peer.protocol_version, Some(1));
+        assert_eq!(peer.height, Some(42));
+        assert!(peer.supports(NODE_COMPACT_BLOCKS));
+        assert!(!peer.supports(NODE_QUANTUM_SIGS));
+        assert_eq!(peers[&foreign].protocol_version, None);
+    }
+
+    /// One side of an in-process connection, driven through the event
+    /// handler with the frames the other side publishes
+    struct TestNode {
+        peer_id: PeerId,
+        connected_peers: Arc<RwLock<HashMap<PeerId, PeerInfo>>>,
+        stats: Arc<RwLock<NetworkStats>>,
+        bandwidth_tracker: Arc<Mutex<BandwidthTracker>>,
+        rate_limiter: Arc<RateLimiter>,
+        request_manager: Arc<Mutex<RequestManager>>,
+        local_version: Arc<Mutex<LocalVersion>>,
+        event_tx: mpsc::Sender<NetworkEvent>,
+        event_rx: mpsc::Receiver<NetworkEvent>,
+        swarm_cmd_tx: mpsc::Sender<SwarmCommand>,
+        swarm_cmd_rx: mpsc::Receiver<SwarmCommand>,
+    }
+
+    impl TestNode {
+        fn new(encrypted: bool) -> Self {
+            let identity = identity::Keypair::generate_ed25519();
+            let mut local_version = LocalVersion::new([1u8; 32]).with_identity(identity.clone());
+            local_version.set_encryption(encrypted);
+            let (event_tx, event_rx) = mpsc::channel(64);
+            let (swarm_cmd_tx, swarm_cmd_rx) = mpsc::channel(64);
+            Self {
+                peer_id: identity.public().to_peer_id(),
+                connected_peers: Arc::new(RwLock::new(HashMap::new())),
+                stats: Arc::new(RwLock::new(NetworkStats::default())),
+                bandwidth_tracker: Arc::new(Mutex::new(BandwidthTracker::new())),
+                rate_limiter: Arc::new(RateLimiter::new(RateLimitConfig::default())),
+                request_manager: Arc::new(Mutex::new(RequestManager::default())),
+                local_version: Arc::new(Mutex::new(local_version)),
+                event_tx,
+                event_rx,
+                swarm_cmd_tx,
+                swarm_cmd_rx,
+            }
+        }
+
+        async fn handle(&self, event: SwarmEventWrapper) {
+            P2PNetwork::handle_wrapped_swarm_event(
+                event,
+                &self.event_tx,
+                &self.stats,
+                &self.connected_peers,
+                &self.bandwidth_tracker,
+                &self.swarm_cmd_tx,
+                8,
+                &self.rate_limiter,
+                &self.request_manager,
+                &OutboundDiversityPolicy::default(),
+                DEFAULT_KNOWN_INVENTORY_SIZE,
+                &self.local_version,
+            )
+            .await;
+        }
+
+        async fn connect(&self, peer: &TestNode, inbound: bool) {
+            self.handle(SwarmEventWrapper::ConnectionEstablished {
+                peer_id: peer.peer_id,
+                endpoint: String::new(),
+                inbound,
+            })
+            .await;
+        }
+
+        async fn send(&self, peer: &TestNode, message: Message) {
+            P2PNetwork::handle_command_with_channels(
+                NetworkCommand::SendToPeer {
+                    peer_id: peer.peer_id,
+                    message,
+                },
+                &self.swarm_cmd_tx,
+                &self.event_tx,
+                &self.stats,
+                &self.connected_peers,
+                &self.bandwidth_tracker,
+            )
+            .await;
+        }
+
+        async fn receive(&self, from: &TestNode, data: Vec<u8>) {
+            self.handle(SwarmEventWrapper::Message {
+                peer_id: from.peer_id,
+                topic: DIRECT_TOPIC.to_string(),
+                data,
+            })
+            .await;
+        }
+
+        /// Frames published since the last call; panics on a disconnect
+        fn published(&mut self) -> Vec<Vec<u8>> {
+            std::iter::from_fn(|| self.swarm_cmd_rx.try_recv().ok())
+                .map(|command| match command {
+                    SwarmCommand::Publish(_, data) => data,
+                    other => panic!("unexpected command {:?}", other),
+                })
+                .collect()
+        }
+
+        fn received(&mut self) -> Vec<Message> {
+            std::iter::from_fn(|| self.event_rx.try_recv().ok())
+                .filter_map(|event| match event {
+                    NetworkEvent::MessageReceived { message, .. } => Some(message),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        async fn encryption_with(&self, peer: &TestNode) -> EncryptionStatus {
+            self.connected_peers.read().await[&peer.peer_id].encryption_status()
+        }
+    }
+
+    fn not_found(tag: u8) -> Message {
+        Message::NotFound {
+            block_hashes: vec![[tag; 32]],
+        }
+    }
+
+    /// Relay frames both ways until both sides are quiet, returning the
+    /// messages that crossed the wire
+    async fn exchange(a: &mut TestNode, b: &mut TestNode) -> Vec<Message> {
+        let mut wire = Vec::new();
+        loop {
+            let (to_b, to_a) = (a.published(), b.published());
+            if to_b.is_empty() && to_a.is_empty() {
+                return wire;
+            }
+            for (to, from, frames) in [(&*b, &*a, to_b), (&*a, &*b, to_a)] {
+                for data in frames {
+                    let (_, message): (String, Message) = framing::decode(&data).unwrap();
+                    wire.push(message);
+                    to.receive(from, data).await;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_transport_between_two_nodes() {
+        let (mut a, mut b) = (TestNode::new(true), TestNode::new(true));
+        a.connect(&b, false).await;
+        b.connect(&a, true).await;
+        // Held back until the session is up
+        a.send(&b, not_found(7)).await;
+
+        let wire = exchange(&mut a, &mut b).await;
+        assert!(wire.iter().any(|m| matches!(m, Message::KemOffer(_))));
+        assert!(wire.iter().any(|m| matches!(m, Message::KemAccept(_))));
+        assert!(!wire.iter().any(|m| matches!(m, Message::NotFound { .. })));
+        assert_eq!(a.encryption_with(&b).await, EncryptionStatus::Encrypted);
+        assert_eq!(b.encryption_with(&a).await, EncryptionStatus::Encrypted);
+        assert!(b.connected_peers.read().await[&a.peer_id].supports(NODE_ENCRYPTED));
+        assert!(matches!(
+            b.received().as_slice(),
+            [Message::NotFound { block_hashes }] if block_hashes[0] == [7; 32]
+        ));
+
+        b.send(&a, not_found(8)).await;
+        let wire = exchange(&mut a, &mut b).await;
+        assert!(matches!(wire.as_slice(), [Message::Encrypted(_)]));
+        assert_eq!(a.received().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_transport_rejects_tampering_and_downgrades() {
+        let (mut a, mut b) = (TestNode::new(true), TestNode::new(true));
+        a.connect(&b, false).await;
+        b.connect(&a, true).await;
+        exchange(&mut a, &mut b).await;
+
+        // A frame altered in transit
+        a.send(&b, not_found(7)).await;
+        let data = a.published().pop().unwrap();
+        let (recipient, message): (String, Message) = framing::decode(&data).unwrap();
+        let Message::Encrypted(mut frame) = message else {
+            panic!("expected an encrypted frame");
+        };
+        frame.ciphertext[0] ^= 0x01;
+        let tampered = framing::encode(&(recipient.clone(), Message::Encrypted(frame))).unwrap();
+        b.receive(&a, tampered).await;
+        assert!(matches!(
+            next_control_command(&mut b.swarm_cmd_rx),
+            Some(SwarmCommand::Disconnect(peer_id)) if peer_id == a.peer_id
+        ));
+
+        // Plaintext from a peer that agreed to encrypt
+        let plaintext = framing::encode(&(recipient, not_found(7))).unwrap();
+        b.receive(&a, plaintext).await;
+        assert!(matches!(
+            next_control_command(&mut b.swarm_cmd_rx),
+            Some(SwarmCommand::Disconnect(peer_id)) if peer_id == a.peer_id
+        ));
+        assert!(b.received().is_empty());
+        assert_eq!(b.stats.read().await.invalid_messages, 2);
+
+        // A Version stripped of the encryption bit after the session is up
+        let mut version = LocalVersion::new([1u8; 32]).message(String::new(), String::new());
+        version.services &= !NODE_ENCRYPTED;
+        let stripped =
+            framing::encode(&(b.peer_id.to_string(), Message::Version(version))).unwrap();
+        b.receive(&a, stripped).await;
+        assert!(matches!(
+            next_control_command(&mut b.swarm_cmd_rx),
+            Some(SwarmCommand::Disconnect(peer_id)) if peer_id == a.peer_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_encryption_falls_back_for_peers_without_it() {
+        let (mut a, mut b) = (TestNode::new(true), TestNode::new(false));
+        a.connect(&b, false).await;
+        b.connect(&a, true).await;
+        a.send(&b, not_found(7)).await;
+
+        let wire = exchange(&mut a, &mut b).await;
+        assert!(!wire
+            .iter()
+            .any(|m| matches!(m, Message::KemOffer(_) | Message::Encrypted(_))));
+        assert_eq!(a.encryption_with(&b).await, EncryptionStatus::Plaintext);
+        assert_eq!(b.received().len(), 1);
+
+        // Frames addressed to another peer are left alone
+        let elsewhere = framing::encode(&(PeerId::random().to_string(), not_found(9))).unwrap();
+        a.receive(&b, elsewhere).await;
+        assert!(a.received().is_empty());
+    }
+}
@@ -1,3 +1,6 @@
+use crate::network::bloom_filter::SupernovaBloomFilter;
+use crate::network::encryption::{EncryptionStatus, PeerEncryption};
+use crate::network::inventory::KnownInventory;
 use crate::network::peer_diversity::IpSubnet;
 use dashmap::DashMap;
 use libp2p::{multiaddr::Multiaddr, PeerId};
@@ -61,7 +64,7 @@ pub struct PeerInfo {
     pub ping_ms: Option<u64>,
     /// Whether the peer has been verified (basic handshake complete)
     pub verified: bool,
-    /// Service flags (bitfield indicating supported services)
+    /// Services negotiated in the version handshake (`handshake::NODE_*`)
     pub services: u64,
     /// Total bytes sent to this peer
     pub bytes_sent: u64,
@@ -71,6 +74,18 @@ pub struct PeerInfo {
     pub metadata: PeerMetadata,
 }
 
+impl PeerInfo {
+    /// Whether the handshake negotiated `service` with this peer
+    pub fn supports(&self, service: u64) -> bool {
+        self.services & service == service
+    }
+
+    /// Whether direct messages with this peer are encrypted
+    pub fn encryption_status(&self) -> EncryptionStatus {
+        self.metadata.encryption.status()
+    }
+}
+
 /// Network information about a peer
 #[derive(Debug, Clone)]
 pub struct PeerNetworkInfo {
@@ -99,6 +114,13 @@ pub struct PeerMetadata {
     pub failed_requests: u64,
     /// Custom attributes for this peer
     pub attributes: HashMap<String, String>,
+    /// Bloom filter loaded by a light client; blocks and transactions sent
+    /// to it are filtered (see `bloom_filter::filter_message`)
+    pub bloom_filter: Option<SupernovaBloomFilter>,
+    /// Blocks and transactions this peer has; not relayed to it again
+    pub known_inventory: KnownInventory,
+    /// Encrypted transport session for direct messages
+    pub encryption: PeerEncryption,
 }
 
 /// Reasons for banning a peer
@@ -791,7 +791,7 @@ impl PeerDiversityManager {
     }
 
     /// Extract IP address from a multiaddr
-    fn extract_ip_from_multiaddr(addr: &libp2p::Multiaddr) -> Option<IpAddr> {
+    pub(crate) fn extract_ip_from_multiaddr(addr: &libp2p::Multiaddr) -> Option<IpAddr> {
         for protocol in addr.iter() {
             match protocol {
                 Protocol::Ip4(ip) => return Some(IpAddr::V4(ip)),
@@ -839,6 +839,326 @@ pub enum SuspiciousBehavior {
     AggressiveAdvertising,
 }
 
+// ============================================================================
+// Outbound Connection Diversity
+// ============================================================================
+
+/// Outbound diversity bucket: the /16 of an IPv4 address or the /32 of an
+/// IPv6 one. IPv4-mapped IPv6 addresses fall in their IPv4 bucket.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NetPrefix(IpAddr);
+
+impl NetPrefix {
+    pub fn of(ip: IpAddr) -> Self {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        match ip {
+            IpAddr::V4(v4) => {
+                let [a, b, _, _] = v4.octets();
+                Self(IpAddr::V4(Ipv4Addr::new(a, b, 0, 0)))
+            }
+            IpAddr::V6(v6) => {
+                let segments = v6.segments();
+                Self(IpAddr::V6(Ipv6Addr::new(
+                    segments[0],
+                    segments[1],
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0,
+                )))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for NetPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            IpAddr::V4(v4) => write!(f, "{}/16", v4),
+            IpAddr::V6(v6) => write!(f, "{}/32", v6),
+        }
+    }
+}
+
+/// Source of Autonomous System Numbers for peer addresses
+pub trait AsnLookup: Send + Sync {
+    fn asn(&self, ip: IpAddr) -> Option<u32>;
+}
+
+/// ASN lookup backed by a MaxMind GeoLite2-ASN (or compatible) database
+pub struct MaxMindAsnLookup {
+    reader: maxminddb::Reader<Vec<u8>>,
+}
+
+impl MaxMindAsnLookup {
+    pub fn open(path: &std::path::Path) -> Result<Self, maxminddb::MaxMindDBError> {
+        Ok(Self {
+            reader: maxminddb::Reader::open_readfile(path)?,
+        })
+    }
+}
+
+impl AsnLookup for MaxMindAsnLookup {
+    fn asn(&self, ip: IpAddr) -> Option<u32> {
+        self.reader
+            .lookup::<maxminddb::geoip2::Asn>(ip)
+            .ok()?
+            .autonomous_system_number
+    }
+}
+
+/// Outbound peers per bucket, as reported in network health
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BucketDistribution {
+    /// Whether the caps are enforced (`network.peer_diversity.enabled`)
+    pub enforced: bool,
+    pub max_per_prefix: usize,
+    /// Only set when an ASN database is configured
+    pub max_per_asn: Option<usize>,
+    /// Outbound peers per /16 (IPv4) or /32 (IPv6) prefix
+    pub prefixes: std::collections::BTreeMap<String, usize>,
+    /// Outbound peers per ASN
+    pub asns: std::collections::BTreeMap<u32, usize>,
+    /// Outbound peers the ASN database has no entry for
+    pub unknown_asn: usize,
+    /// Outbound peers beyond a cap, waiting to be rotated out
+    pub excess: usize,
+}
+
+#[derive(Default)]
+struct BucketCounts {
+    prefixes: HashMap<NetPrefix, usize>,
+    asns: HashMap<u32, usize>,
+}
+
+impl BucketCounts {
+    fn in_prefix(&self, ip: IpAddr) -> usize {
+        self.prefixes.get(&NetPrefix::of(ip)).copied().unwrap_or(0)
+    }
+
+    fn in_asn(&self, asn: Option<u32>) -> usize {
+        asn.and_then(|asn| self.asns.get(&asn).copied())
+            .unwrap_or(0)
+    }
+}
+
+/// Caps on how many outbound peers may share a network prefix or ASN, so an
+/// attacker holding one /16 or one hosting provider cannot fill all of our
+/// outbound slots. Inbound connections are not counted.
+#[derive(Clone)]
+pub struct OutboundDiversityPolicy {
+    enforced: bool,
+    max_per_prefix: usize,
+    max_per_asn: usize,
+    asn_lookup: Option<Arc<dyn AsnLookup>>,
+    rotation_interval: Duration,
+}
+
+impl std::fmt::Debug for OutboundDiversityPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutboundDiversityPolicy")
+            .field("enforced", &self.enforced)
+            .field("max_per_prefix", &self.max_per_prefix)
+            .field("max_per_asn", &self.max_per_asn)
+            .field("asn_lookup", &self.asn_lookup.is_some())
+            .field("rotation_interval", &self.rotation_interval)
+            .finish()
+    }
+}
+
+impl Default for OutboundDiversityPolicy {
+    fn default() -> Self {
+        Self::new(
+            EclipseDefenseConfig::MAX_PEERS_PER_SUBNET,
+            EclipseDefenseConfig::MAX_PEERS_PER_ASN,
+        )
+    }
+}
+
+impl OutboundDiversityPolicy {
+    pub fn new(max_per_prefix: usize, max_per_asn: usize) -> Self {
+        Self {
+            enforced: true,
+            max_per_prefix: max_per_prefix.max(1),
+            max_per_asn: max_per_asn.max(1),
+            asn_lookup: None,
+            rotation_interval: Duration::from_secs(600),
+        }
+    }
+
+    /// A policy that admits everything but still reports the distribution
+    pub fn unenforced() -> Self {
+        Self {
+            enforced: false,
+            ..Self::default()
+        }
+    }
+
+    /// Build the policy from `network.peer_diversity`. An unreadable ASN
+    /// database is logged and the ASN cap left off.
+    pub fn from_config(config: &crate::config::PeerDiversityConfig) -> Self {
+        let mut policy = Self::new(config.max_outbound_per_prefix, config.max_outbound_per_asn)
+            .with_rotation_interval(config.excess_rotation_interval);
+        policy.enforced = config.enabled;
+        if let Some(path) = &config.asn_database {
+            match MaxMindAsnLookup::open(path) {
+                Ok(lookup) => policy = policy.with_asn_lookup(Arc::new(lookup)),
+                Err(e) => warn!(
+                    "Failed to open ASN database {}: {}; outbound peers are capped per prefix only",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        policy
+    }
+
+    pub fn with_asn_lookup(mut self, lookup: Arc<dyn AsnLookup>) -> Self {
+        self.asn_lookup = Some(lookup);
+        self
+    }
+
+    pub fn with_rotation_interval(mut self, interval: Duration) -> Self {
+        self.rotation_interval = interval;
+        self
+    }
+
+    /// Pause between rotating out two peers that exceed a cap
+    pub fn rotation_interval(&self) -> Duration {
+        self.rotation_interval
+    }
+
+    fn asn(&self, ip: IpAddr) -> Option<u32> {
+        self.asn_lookup.as_ref()?.asn(ip)
+    }
+
+    fn counts(&self, outbound: &[IpAddr]) -> BucketCounts {
+        let mut counts = BucketCounts::default();
+        for &ip in outbound {
+            self.count(&mut counts, ip);
+        }
+        counts
+    }
+
+    fn count(&self, counts: &mut BucketCounts, ip: IpAddr) {
+        *counts.prefixes.entry(NetPrefix::of(ip)).or_insert(0) += 1;
+        if let Some(asn) = self.asn(ip) {
+            *counts.asns.entry(asn).or_insert(0) += 1;
+        }
+    }
+
+    fn fits(&self, counts: &BucketCounts, ip: IpAddr) -> bool {
+        if !self.enforced {
+            return true;
+        }
+        if counts.in_prefix(ip) >= self.max_per_prefix {
+            return false;
+        }
+        match self.asn(ip) {
+            Some(asn) => counts.in_asn(Some(asn)) < self.max_per_asn,
+            None => true,
+        }
+    }
+
+    /// Whether one more outbound peer at `ip` stays within the caps, given
+    /// the addresses of the current outbound peers
+    pub fn admits(&self, outbound: &[IpAddr], ip: IpAddr) -> bool {
+        self.fits(&self.counts(outbound), ip)
+    }
+
+    /// Pick up to `limit` candidates to dial, least represented buckets
+    /// first. Candidates that would exceed a cap are left out; ones without
+    /// an IP address (e.g. DNS multiaddrs) cannot be bucketed and are taken
+    /// as they come. Ties keep the input order.
+    pub fn select_candidates<T>(
+        &self,
+        outbound: &[IpAddr],
+        candidates: Vec<(T, Option<IpAddr>)>,
+        limit: usize,
+    ) -> Vec<T> {
+        let mut counts = self.counts(outbound);
+        let mut remaining: Vec<Option<(T, Option<IpAddr>)>> =
+            candidates.into_iter().map(Some).collect();
+        let mut selected = Vec::new();
+
+        while selected.len() < limit {
+            let mut best: Option<(usize, (usize, usize))> = None;
+            for (index, slot) in remaining.iter().enumerate() {
+                let Some((_, ip)) = slot else { continue };
+                let rank = match ip {
+                    Some(ip) if !self.fits(&counts, *ip) => continue,
+                    Some(ip) => (counts.in_prefix(*ip), counts.in_asn(self.asn(*ip))),
+                    None => (0, 0),
+                };
+                match best {
+                    Some((_, best_rank)) if best_rank <= rank => {}
+                    _ => best = Some((index, rank)),
+                }
+            }
+            let Some((index, _)) = best else { break };
+            let Some((candidate, ip)) = remaining[index].take() else {
+                break;
+            };
+            if let Some(ip) = ip {
+                self.count(&mut counts, ip);
+            }
+            selected.push(candidate);
+        }
+
+        selected
+    }
+
+    /// Outbound peers beyond a cap, at most `max` of them. Peers are
+    /// considered in the given order, so passing the oldest connections first
+    /// keeps them and rotates out the newest.
+    pub fn excess_peers<T: Clone>(&self, outbound: &[(T, IpAddr)], max: usize) -> Vec<T> {
+        if !self.enforced {
+            return Vec::new();
+        }
+        let mut counts = BucketCounts::default();
+        let mut excess = Vec::new();
+        for (peer, ip) in outbound {
+            if self.fits(&counts, *ip) {
+                self.count(&mut counts, *ip);
+            } else if excess.len() < max {
+                excess.push(peer.clone());
+            }
+        }
+        excess
+    }
+
+    /// Current bucket distribution of the outbound peers at `outbound`
+    pub fn distribution(&self, outbound: &[IpAddr]) -> BucketDistribution {
+        let counts = self.counts(outbound);
+        let mut unknown_asn = 0;
+        if self.asn_lookup.is_some() {
+            unknown_asn = outbound
+                .iter()
+                .filter(|ip| self.asn(**ip).is_none())
+                .count();
+        }
+        let indexed: Vec<(usize, IpAddr)> = outbound.iter().copied().enumerate().collect();
+        BucketDistribution {
+            enforced: self.enforced,
+            max_per_prefix: self.max_per_prefix,
+            max_per_asn: self.asn_lookup.as_ref().map(|_| self.max_per_asn),
+            prefixes: counts
+                .prefixes
+                .iter()
+                .map(|(prefix, count)| (prefix.to_string(), *count))
+                .collect(),
+            asns: counts.asns.into_iter().collect(),
+            unknown_asn,
+            excess: self.excess_peers(&indexed, usize::MAX).len(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -896,4 +1216,134 @@ mod tests {
             None
         );
     }
+
+    fn v4(a: u8, b: u8, c: u8, d: u8) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+    }
+
+    /// 20 addresses in 10.1.0.0/16, 4 in 10.2.0.0/16, 2 in 172.16.0.0/16 and
+    /// one each in three more prefixes
+    fn skewed_address_book() -> Vec<(usize, Option<IpAddr>)> {
+        let mut book = Vec::new();
+        for i in 0..20 {
+            book.push(v4(10, 1, i, 1));
+        }
+        for i in 0..4 {
+            book.push(v4(10, 2, 0, i));
+        }
+        book.push(v4(172, 16, 1, 1));
+        book.push(v4(172, 16, 2, 1));
+        book.push(v4(192, 168, 0, 1));
+        book.push(v4(8, 8, 8, 8));
+        book.push(IpAddr::V6("2001:db8::1".parse().unwrap()));
+        book.into_iter().map(Some).enumerate().collect()
+    }
+
+    struct FixedAsns(HashMap<IpAddr, u32>);
+
+    impl AsnLookup for FixedAsns {
+        fn asn(&self, ip: IpAddr) -> Option<u32> {
+            self.0.get(&ip).copied()
+        }
+    }
+
+    #[test]
+    fn test_net_prefix_buckets() {
+        assert_eq!(
+            NetPrefix::of(v4(10, 1, 2, 3)),
+            NetPrefix::of(v4(10, 1, 200, 9))
+        );
+        assert_ne!(
+            NetPrefix::of(v4(10, 1, 2, 3)),
+            NetPrefix::of(v4(10, 2, 2, 3))
+        );
+        assert_eq!(NetPrefix::of(v4(10, 1, 2, 3)).to_string(), "10.1.0.0/16");
+
+        let mapped: IpAddr = "::ffff:10.1.9.9".parse().unwrap();
+        assert_eq!(NetPrefix::of(mapped), NetPrefix::of(v4(10, 1, 0, 0)));
+        let v6: IpAddr = "2001:db8:aa::1".parse().unwrap();
+        assert_eq!(NetPrefix::of(v6).to_string(), "2001:db8::/32");
+    }
+
+    #[test]
+    fn test_selection_prefers_underrepresented_prefixes() {
+        let policy = OutboundDiversityPolicy::new(2, 2);
+        let book = skewed_address_book();
+
+        let selected: Vec<IpAddr> = policy
+            .select_candidates(&[], book.clone(), 8)
+            .into_iter()
+            .map(|i| book[i].1.unwrap())
+            .collect();
+        assert_eq!(selected.len(), 8);
+        let distribution = policy.distribution(&selected);
+        assert_eq!(distribution.prefixes.len(), 6);
+        assert!(distribution.prefixes.values().all(|&count| count <= 2));
+        assert_eq!(distribution.excess, 0);
+
+        // Every prefix gets one peer before any gets a second
+        let first_six: HashSet<NetPrefix> =
+            selected[..6].iter().map(|ip| NetPrefix::of(*ip)).collect();
+        assert_eq!(first_six.len(), 6);
+
+        // With 10.1/16 already at the cap none of its 20 addresses is picked
+        let outbound = [v4(10, 1, 50, 1), v4(10, 1, 51, 1)];
+        let selected = policy.select_candidates(&outbound, book.clone(), usize::MAX);
+        let saturated = NetPrefix::of(v4(10, 1, 0, 0));
+        assert!(selected
+            .iter()
+            .all(|&i| NetPrefix::of(book[i].1.unwrap()) != saturated));
+        assert_eq!(selected.len(), 2 + 2 + 1 + 1 + 1);
+        assert!(!policy.admits(&outbound, v4(10, 1, 99, 99)));
+        assert!(policy.admits(&outbound, v4(10, 3, 0, 1)));
+    }
+
+    #[test]
+    fn test_asn_cap_spans_prefixes() {
+        // One provider announcing three /16s
+        let asns: HashMap<IpAddr, u32> = [
+            (v4(10, 1, 0, 1), 64500),
+            (v4(10, 2, 0, 1), 64500),
+            (v4(10, 3, 0, 1), 64500),
+            (v4(172, 16, 0, 1), 64501),
+        ]
+        .into_iter()
+        .collect();
+        let policy = OutboundDiversityPolicy::new(2, 2).with_asn_lookup(Arc::new(FixedAsns(asns)));
+
+        let outbound = [v4(10, 1, 0, 1), v4(10, 2, 0, 1)];
+        assert!(!policy.admits(&outbound, v4(10, 3, 0, 1)));
+        assert!(policy.admits(&outbound, v4(172, 16, 0, 1)));
+        assert!(policy.admits(&outbound, v4(192, 168, 0, 1)));
+
+        let distribution = policy.distribution(&[v4(10, 1, 0, 1), v4(192, 168, 0, 1)]);
+        assert_eq!(distribution.max_per_asn, Some(2));
+        assert_eq!(distribution.asns.get(&64500), Some(&1));
+        assert_eq!(distribution.unknown_asn, 1);
+    }
+
+    #[test]
+    fn test_excess_peers_rotate_gradually() {
+        // Connected under a looser policy: five peers in 10.1/16, oldest first
+        let outbound: Vec<(usize, IpAddr)> = (0..5)
+            .map(|i| (i, v4(10, 1, i as u8, 1)))
+            .chain([(5, v4(172, 16, 0, 1)), (6, v4(8, 8, 8, 8))])
+            .collect();
+        let ips: Vec<IpAddr> = outbound.iter().map(|(_, ip)| *ip).collect();
+
+        let policy = OutboundDiversityPolicy::new(2, 2);
+        assert_eq!(policy.distribution(&ips).excess, 3);
+
+        // One at a time, newest first, the two oldest are kept
+        assert_eq!(policy.excess_peers(&outbound, 1), vec![2]);
+        assert_eq!(policy.excess_peers(&outbound, usize::MAX), vec![2, 3, 4]);
+
+        let remaining: Vec<(usize, IpAddr)> =
+            outbound.iter().copied().filter(|(i, _)| *i != 2).collect();
+        assert_eq!(policy.excess_peers(&remaining, 1), vec![3]);
+
+        let unenforced = OutboundDiversityPolicy::unenforced();
+        assert!(unenforced.excess_peers(&outbound, usize::MAX).is_empty());
+        assert!(unenforced.admits(&ips, v4(10, 1, 9, 9)));
+    }
 }
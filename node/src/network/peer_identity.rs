@@ -1,11 +1,38 @@
 // Persistent Peer Identity Management
-// Ensures nodes maintain stable peer IDs across restarts
+// Ensures nodes maintain stable peer IDs across restarts, optionally
+// encrypting the key at rest
 
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
 use libp2p::identity::Keypair;
+use rand::RngCore;
+use std::fmt;
 use std::path::Path;
 use thiserror::Error;
 use tracing::{info, warn};
 
+/// Directory holding the node identity key files
+pub const DEFAULT_IDENTITY_DIR: &str = "./data";
+
+/// File name of the active identity key inside the identity directory
+pub const KEY_FILE: &str = "peer_id.key";
+
+/// Environment variable supplying the passphrase that encrypts the identity
+/// key at rest. Unset or empty leaves the key unencrypted (or, with the
+/// `os-keyring` feature, protected by a secret kept in the OS keyring).
+pub const IDENTITY_PASSPHRASE_ENV: &str = "SUPERNOVA_IDENTITY_PASSPHRASE";
+
+/// Prefix of an encrypted key file: magic, 16-byte salt, 12-byte nonce,
+/// then the ChaCha20-Poly1305 ciphertext of the protobuf-encoded keypair
+const ENCRYPTED_MAGIC: &[u8; 8] = b"SNIDKEY1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[cfg(feature = "os-keyring")]
+const KEYRING_SERVICE: &str = "supernova-node";
+#[cfg(feature = "os-keyring")]
+const KEYRING_USER: &str = "identity-key";
+
 #[derive(Error, Debug)]
 pub enum IdentityError {
     #[error("IO error: {0}")]
@@ -16,12 +43,89 @@ pub enum IdentityError {
     
     #[error("Failed to encode keypair: {0}")]
     EncodeError(String),
+
+    #[error("Identity key is encrypted; set {IDENTITY_PASSPHRASE_ENV} to unlock it")]
+    PassphraseRequired,
+
+    #[error("Failed to decrypt identity key (wrong passphrase or corrupt file)")]
+    DecryptionFailed,
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+
+    #[error("OS keyring error: {0}")]
+    Keyring(String),
+}
+
+/// How the identity key is protected on disk
+#[derive(Clone)]
+pub enum KeyProtection {
+    /// Plain protobuf encoding, as written by earlier releases
+    Plaintext,
+    /// Encrypted under a key derived from this passphrase with Argon2id
+    Passphrase(String),
+    /// Encrypted under a random secret stored in the OS keyring
+    #[cfg(feature = "os-keyring")]
+    OsKeyring,
+}
+
+impl fmt::Debug for KeyProtection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyProtection::Plaintext => f.write_str("Plaintext"),
+            KeyProtection::Passphrase(_) => f.write_str("Passphrase(<redacted>)"),
+            #[cfg(feature = "os-keyring")]
+            KeyProtection::OsKeyring => f.write_str("OsKeyring"),
+        }
+    }
+}
+
+impl KeyProtection {
+    /// Protection selected by the environment: a passphrase from
+    /// `SUPERNOVA_IDENTITY_PASSPHRASE` when set, otherwise the OS keyring
+    /// when built with `os-keyring`, otherwise none
+    pub fn from_env() -> Self {
+        match std::env::var(IDENTITY_PASSPHRASE_ENV) {
+            Ok(passphrase) if !passphrase.is_empty() => KeyProtection::Passphrase(passphrase),
+            #[cfg(feature = "os-keyring")]
+            _ => KeyProtection::OsKeyring,
+            #[cfg(not(feature = "os-keyring"))]
+            _ => KeyProtection::Plaintext,
+        }
+    }
+
+    /// Secret the key file is encrypted under, if any
+    fn secret(&self) -> Result<Option<String>, IdentityError> {
+        match self {
+            KeyProtection::Plaintext => Ok(None),
+            KeyProtection::Passphrase(passphrase) => Ok(Some(passphrase.clone())),
+            #[cfg(feature = "os-keyring")]
+            KeyProtection::OsKeyring => {
+                let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+                    .map_err(|e| IdentityError::Keyring(e.to_string()))?;
+                match entry.get_password() {
+                    Ok(secret) => Ok(Some(secret)),
+                    Err(keyring::Error::NoEntry) => {
+                        let mut bytes = [0u8; 32];
+                        rand::rngs::OsRng.fill_bytes(&mut bytes);
+                        let secret = hex::encode(bytes);
+                        entry
+                            .set_password(&secret)
+                            .map_err(|e| IdentityError::Keyring(e.to_string()))?;
+                        Ok(Some(secret))
+                    }
+                    Err(e) => Err(IdentityError::Keyring(e.to_string())),
+                }
+            }
+        }
+    }
 }
 
 /// Load or generate persistent peer keypair
 ///
 /// This function ensures the node maintains a stable peer ID across restarts
 /// by saving the keypair to disk on first run and loading it on subsequent runs.
+/// The key is protected as selected by [`KeyProtection::from_env`].
 ///
 /// # Arguments
 /// * `data_dir` - Directory to store the peer_id.key file
@@ -34,17 +138,52 @@ pub enum IdentityError {
 /// - Uses libp2p's protobuf encoding for keypair serialization
 /// - Validates keypair can be decoded before returning
 pub fn load_or_generate_keypair(data_dir: &Path) -> Result<Keypair, IdentityError> {
-    let keypair_path = data_dir.join("peer_id.key");
+    load_or_generate_keypair_with(data_dir, &KeyProtection::from_env())
+}
+
+/// [`load_or_generate_keypair`] with explicit key protection. A plaintext
+/// key file is re-encrypted in place when protection is configured, and a
+/// pending identity rotation whose grace period is over is completed first.
+pub fn load_or_generate_keypair_with(
+    data_dir: &Path,
+    protection: &KeyProtection,
+) -> Result<Keypair, IdentityError> {
+    load_or_generate_keypair_at(data_dir, protection, chrono::Utc::now().timestamp().max(0) as u64)
+}
+
+pub(crate) fn load_or_generate_keypair_at(
+    data_dir: &Path,
+    protection: &KeyProtection,
+    now: u64,
+) -> Result<Keypair, IdentityError> {
+    match crate::network::identity_rotation::complete_due_rotation(data_dir, now) {
+        Ok(Some(attestation)) => info!(
+            "Identity rotation grace period over; switched from {} to {}",
+            attestation.link.old_peer_id, attestation.link.new_peer_id
+        ),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to complete pending identity rotation: {}", e),
+    }
+
+    let keypair_path = data_dir.join(KEY_FILE);
     
     // Try to load existing keypair
     if keypair_path.exists() {
-        match load_keypair_from_file(&keypair_path) {
-            Ok(keypair) => {
+        match read_keypair_file(&keypair_path, protection) {
+            Ok((keypair, encrypted)) => {
                 let peer_id = libp2p::PeerId::from(keypair.public());
                 info!("✓ Loaded persistent peer ID from {:?}", keypair_path);
                 info!("  Peer ID: {}", peer_id);
+                if !encrypted && protection.secret()?.is_some() {
+                    save_keypair(&keypair, &keypair_path, protection)?;
+                    info!("  Identity key is now encrypted at rest");
+                }
                 return Ok(keypair);
             }
+            // Never replace an encrypted identity we merely failed to unlock
+            Err(e @ (IdentityError::PassphraseRequired
+            | IdentityError::DecryptionFailed
+            | IdentityError::Keyring(_))) => return Err(e),
             Err(e) => {
                 warn!("Failed to load keypair from {:?}: {}", keypair_path, e);
                 warn!("Generating new keypair...");
@@ -57,7 +196,7 @@ pub fn load_or_generate_keypair(data_dir: &Path) -> Result<Keypair, IdentityErro
     let peer_id = libp2p::PeerId::from(keypair.public());
     
     // Save to disk
-    if let Err(e) = save_keypair_to_file(&keypair, &keypair_path) {
+    if let Err(e) = save_keypair(&keypair, &keypair_path, protection) {
         warn!("Failed to save keypair to {:?}: {}", keypair_path, e);
         warn!("Peer ID will not persist across restarts!");
     } else {
@@ -68,18 +207,40 @@ pub fn load_or_generate_keypair(data_dir: &Path) -> Result<Keypair, IdentityErro
     Ok(keypair)
 }
 
-/// Load keypair from file
-fn load_keypair_from_file(path: &Path) -> Result<Keypair, IdentityError> {
+/// Load a keypair file written by [`save_keypair`]
+pub fn load_keypair(path: &Path, protection: &KeyProtection) -> Result<Keypair, IdentityError> {
+    read_keypair_file(path, protection).map(|(keypair, _)| keypair)
+}
+
+/// Load keypair from file, reporting whether it was encrypted
+fn read_keypair_file(path: &Path, protection: &KeyProtection) -> Result<(Keypair, bool), IdentityError> {
     let bytes = std::fs::read(path)?;
-    
-    Keypair::from_protobuf_encoding(&bytes)
-        .map_err(|e| IdentityError::DecodeError(e.to_string()))
+
+    let (plaintext, encrypted) = match bytes.strip_prefix(ENCRYPTED_MAGIC.as_slice()) {
+        Some(sealed) => {
+            let secret = protection.secret()?.ok_or(IdentityError::PassphraseRequired)?;
+            (decrypt_key_bytes(sealed, &secret)?, true)
+        }
+        None => (bytes, false),
+    };
+
+    let keypair = Keypair::from_protobuf_encoding(&plaintext)
+        .map_err(|e| IdentityError::DecodeError(e.to_string()))?;
+    Ok((keypair, encrypted))
 }
 
-/// Save keypair to file with secure permissions
-fn save_keypair_to_file(keypair: &Keypair, path: &Path) -> Result<(), IdentityError> {
-    let bytes = keypair.to_protobuf_encoding()
+/// Save keypair to file with secure permissions, encrypted as `protection`
+/// requires
+pub fn save_keypair(
+    keypair: &Keypair,
+    path: &Path,
+    protection: &KeyProtection,
+) -> Result<(), IdentityError> {
+    let mut bytes = keypair.to_protobuf_encoding()
         .map_err(|e| IdentityError::EncodeError(e.to_string()))?;
+    if let Some(secret) = protection.secret()? {
+        bytes = encrypt_key_bytes(&bytes, &secret)?;
+    }
     
     // Create parent directory if needed
     if let Some(parent) = path.parent() {
@@ -101,6 +262,45 @@ fn save_keypair_to_file(keypair: &Keypair, path: &Path) -> Result<(), IdentityEr
     Ok(())
 }
 
+fn derive_file_key(secret: &str, salt: &[u8]) -> Result<[u8; 32], IdentityError> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(secret.as_bytes(), salt, &mut key)
+        .map_err(|e| IdentityError::EncryptionError(e.to_string()))?;
+    Ok(key)
+}
+
+fn encrypt_key_bytes(plaintext: &[u8], secret: &str) -> Result<Vec<u8>, IdentityError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_file_key(secret, &salt)?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| IdentityError::EncryptionError(e.to_string()))?;
+
+    let mut out = Vec::with_capacity(ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(ENCRYPTED_MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt_key_bytes(sealed: &[u8], secret: &str) -> Result<Vec<u8>, IdentityError> {
+    if sealed.len() < SALT_LEN + NONCE_LEN {
+        return Err(IdentityError::DecryptionFailed);
+    }
+    let (salt, rest) = sealed.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let key = derive_file_key(secret, salt)?;
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| IdentityError::DecryptionFailed)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,5 +352,50 @@ mod tests {
         // Should be 0600 (owner read/write only)
         assert_eq!(mode, 0o600);
     }
+
+    #[test]
+    fn test_encrypted_keypair_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+        let protection = KeyProtection::Passphrase("correct horse battery".to_string());
+
+        let keypair1 = load_or_generate_keypair_with(data_dir, &protection).unwrap();
+        let on_disk = std::fs::read(data_dir.join(KEY_FILE)).unwrap();
+        assert!(on_disk.starts_with(ENCRYPTED_MAGIC));
+        let secret = keypair1.to_protobuf_encoding().unwrap();
+        assert!(!on_disk.windows(secret.len()).any(|w| w == secret.as_slice()));
+
+        let keypair2 = load_or_generate_keypair_with(data_dir, &protection).unwrap();
+        assert_eq!(keypair1.public(), keypair2.public());
+
+        // A wrong or missing passphrase fails without replacing the key
+        let wrong = KeyProtection::Passphrase("wrong".to_string());
+        assert!(matches!(
+            load_or_generate_keypair_with(data_dir, &wrong),
+            Err(IdentityError::DecryptionFailed)
+        ));
+        assert!(matches!(
+            load_or_generate_keypair_with(data_dir, &KeyProtection::Plaintext),
+            Err(IdentityError::PassphraseRequired)
+        ));
+        assert_eq!(std::fs::read(data_dir.join(KEY_FILE)).unwrap(), on_disk);
+    }
+
+    #[test]
+    fn test_plaintext_key_is_encrypted_on_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path();
+
+        let keypair1 = load_or_generate_keypair_with(data_dir, &KeyProtection::Plaintext).unwrap();
+        let protection = KeyProtection::Passphrase("migrate me".to_string());
+        let keypair2 = load_or_generate_keypair_with(data_dir, &protection).unwrap();
+
+        assert_eq!(keypair1.public(), keypair2.public());
+        assert!(std::fs::read(data_dir.join(KEY_FILE)).unwrap().starts_with(ENCRYPTED_MAGIC));
+        assert_eq!(
+            load_keypair(&data_dir.join(KEY_FILE), &protection).unwrap().public(),
+            keypair1.public()
+        );
+    }
 }
 
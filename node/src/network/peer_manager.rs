@@ -153,6 +153,58 @@ impl PeerManager {
         Ok(())
     }
 
+    /// Move everything known about `old` to `new` after the peer proved
+    /// the two identities belong to the same node: addresses, reputation,
+    /// score and trust. Returns whether there was anything to migrate.
+    pub async fn migrate_peer_identity(
+        &self,
+        old: &PeerId,
+        new: PeerId,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let mut peers = self.peers.write().await;
+        let Some(mut info) = peers.remove(old) else {
+            return Ok(false);
+        };
+
+        info.peer_id = new;
+        if let Some(existing) = peers.remove(&new) {
+            for addr in existing.addresses {
+                if !info.addresses.contains(&addr) {
+                    info.addresses.push(addr);
+                }
+            }
+            info.reputation = info.reputation.saturating_add(existing.reputation);
+            info.last_seen = info.last_seen.max(existing.last_seen);
+        }
+        peers.insert(new, info.clone());
+
+        let was_trusted = {
+            let mut trusted = self.trusted_peers.write().await;
+            let was_trusted = trusted.remove(old);
+            if was_trusted {
+                trusted.insert(new);
+            }
+            was_trusted
+        };
+
+        {
+            let mut scores = self.peer_scores.write().await;
+            let carried = scores.remove(old).unwrap_or(0.0);
+            let score = scores.entry(new).or_insert(0.0);
+            *score = (*score + carried).clamp(-100.0, 100.0);
+        }
+
+        self.storage.remove_peer_info(old).await?;
+        self.storage.save_peer_info(&new, &info).await?;
+        if was_trusted {
+            self.storage.remove_trusted_peer(old).await?;
+            self.storage.save_trusted_peer(&new).await?;
+        }
+
+        info!("Migrated peer {} to rotated identity {}", old, new);
+        Ok(true)
+    }
+
     /// Get all connected peers
     pub async fn get_connected_peers(&self) -> Vec<PeerInfo> {
         self.peers.read().await.values().cloned().collect()
@@ -1,4 +1,8 @@
+use crate::network::bloom_filter::MerkleBlock;
 use crate::network::compact_block::CompactBlock;
+use crate::network::encryption::{EncryptedFrame, KemAccept, KemOffer};
+use crate::network::framing::{self, FrameError};
+use crate::network::mempool_sync::MempoolSummaryEntry;
 use bincode;
 use libp2p::{
     gossipsub::{self, ConfigBuilder, IdentTopic, MessageAuthenticity, MessageId, ValidationMode},
@@ -34,6 +38,8 @@ pub struct VersionMessage {
     pub nonce: u64,
     pub user_agent: String,
     pub start_height: u64,
+    /// Genesis block of the chain the sender follows
+    pub genesis_hash: [u8; 32],
 }
 
 /// Get headers message
@@ -79,6 +85,15 @@ pub struct LightningMessage {
     pub payload: Vec<u8>,
 }
 
+/// Peer address shared through `Addr`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerAddr {
+    /// Multiaddr string
+    pub addr: String,
+    /// When the address was last known to be reachable, Unix seconds
+    pub last_seen: u64,
+}
+
 // Import types from btclib
 use supernova_core::types::{block::Block, transaction::Transaction};
 
@@ -129,7 +144,7 @@ pub enum Message {
     /// Pong response to ping
     Pong(u64),
     /// Address announcement
-    Addr(Vec<(String, String)>),
+    Addr(Vec<PeerAddr>),
     /// Get addresses request
     GetAddr,
     /// Environmental data
@@ -200,6 +215,32 @@ pub enum Message {
     FilterAdd { element: Vec<u8> },
     /// Bloom filter clear
     FilterClear,
+    /// Block filtered by the recipient's bloom filter
+    MerkleBlock(MerkleBlock),
+    /// Block or header request declined; ask another peer or retry later
+    Busy { retry_after_secs: u64, reason: String },
+    /// Requested blocks this peer has pruned; ask a peer serving the full chain
+    NotFound { block_hashes: Vec<[u8; 32]> },
+    /// Ask for the txids in a peer's mempool paying at least `min_fee_rate`
+    GetMempoolSummary { min_fee_rate: u64, max_entries: u32 },
+    /// Mempool txids, highest fee rate first, with each one's in-mempool parents
+    MempoolSummary { entries: Vec<MempoolSummaryEntry> },
+    /// Ask for the manifest (`chunk: None`) or one chunk of the state
+    /// snapshot at `height`
+    SnapshotRequest { height: u64, chunk: Option<u32> },
+    /// Manifest or chunk of a state snapshot; empty `data` means the peer
+    /// has no such snapshot
+    SnapshotChunk {
+        height: u64,
+        chunk: Option<u32>,
+        data: Vec<u8>,
+    },
+    /// Opens the encrypted transport handshake with an ML-KEM public key
+    KemOffer(KemOffer),
+    /// Answers a `KemOffer` with the encapsulated session secret
+    KemAccept(KemAccept),
+    /// Direct message sealed under the session key
+    Encrypted(EncryptedFrame),
 }
 
 /// Checkpoint information for validation
@@ -432,7 +473,7 @@ impl Protocol {
         topic: &str,
         message: Message,
     ) -> Result<MessageId, PublishError> {
-        let encoded = bincode::serialize(&message)?;
+        let encoded = framing::encode_message(&message)?;
         let topic = IdentTopic::new(topic);
         match self.gossipsub.publish(topic, encoded) {
             Ok(id) => Ok(id),
@@ -588,6 +629,9 @@ pub enum PublishError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] bincode::Error),
 
+    #[error("Framing error: {0}")]
+    Framing(#[from] FrameError),
+
     #[error("Gossipsub error: {0}")]
     Gossipsub(String),
 }
@@ -638,7 +682,11 @@ fn message_to_topic(message: &Message) -> &'static str {
         | Message::BlockResponse { .. }
         | Message::CompactBlock(_)
         | Message::GetCompactBlockTxs { .. }
-        | Message::CompactBlockTxs(_) => BLOCKS_TOPIC,
+        | Message::CompactBlockTxs(_)
+        | Message::MerkleBlock(_)
+        | Message::NotFound { .. }
+        | Message::SnapshotRequest { .. }
+        | Message::SnapshotChunk { .. } => BLOCKS_TOPIC,
 
         Message::Transaction { .. }
         | Message::BroadcastTransaction(_)
@@ -648,7 +696,10 @@ fn message_to_topic(message: &Message) -> &'static str {
 
         Message::Status { .. } | Message::GetStatus => STATUS_TOPIC,
 
-        Message::GetMempool { .. } | Message::Mempool { .. } => MEMPOOL_TOPIC,
+        Message::GetMempool { .. }
+        | Message::Mempool { .. }
+        | Message::GetMempoolSummary { .. }
+        | Message::MempoolSummary { .. } => MEMPOOL_TOPIC,
 
         Message::GetData(_) => MEMPOOL_TOPIC,
 
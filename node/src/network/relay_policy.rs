@@ -0,0 +1,187 @@
+//! Transaction relay policy profiles.
+//!
+//! A node's role selects defaults for mempool sizing, the relay fee floor,
+//! RBF, package relay, orphan pool size and how transactions are announced.
+//! Miners run a large, permissive mempool; public relays keep strict limits;
+//! wallet-only nodes keep a small pool and never forward third-party
+//! transactions, though they still broadcast their own.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Gossip topics that carry transactions relayed on behalf of other nodes
+const TRANSACTION_TOPICS: &[&str] = &["transactions", "mempool"];
+
+/// Deployment role selecting a relay profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    Miner,
+    Relay,
+    Wallet,
+}
+
+/// Which transactions a node announces to its peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TxAnnouncement {
+    /// Announce own transactions and forward those received from peers
+    All,
+    /// Announce own transactions only
+    OwnOnly,
+}
+
+/// Where a transaction entered this node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxOrigin {
+    /// Submitted locally (wallet, RPC or API)
+    Local,
+    /// Received from a peer
+    Peer,
+}
+
+impl TxAnnouncement {
+    /// Whether a transaction of the given origin should be announced.
+    pub fn should_announce(self, origin: TxOrigin) -> bool {
+        match (self, origin) {
+            (_, TxOrigin::Local) => true,
+            (TxAnnouncement::All, TxOrigin::Peer) => true,
+            (TxAnnouncement::OwnOnly, TxOrigin::Peer) => false,
+        }
+    }
+
+    /// Whether gossip received on `topic` is forwarded to other peers. The
+    /// message is delivered locally either way.
+    pub fn forwards_topic(self, topic: &str) -> bool {
+        !TRANSACTION_TOPICS.contains(&topic) || self.should_announce(TxOrigin::Peer)
+    }
+}
+
+/// Effective relay settings after applying a role profile and overrides.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RelayPolicy {
+    /// Configured role; `None` when the `[mempool]` section is used as-is
+    pub role: Option<NodeRole>,
+    /// Maximum number of mempool transactions
+    pub max_mempool_size: usize,
+    /// Minimum fee rate for relay and mempool acceptance (novas/byte)
+    pub min_relay_fee: f64,
+    /// Whether replacement transactions are accepted
+    pub enable_rbf: bool,
+    /// Whether child-pays-for-parent packages are evaluated together
+    pub package_relay: bool,
+    /// Maximum number of orphan transactions held
+    pub max_orphan_transactions: usize,
+    /// Transaction announcement behavior
+    pub tx_announcement: TxAnnouncement,
+}
+
+impl RelayPolicy {
+    /// Documented defaults for a role.
+    ///
+    /// | role   | mempool | min fee | RBF | package | orphans | announce |
+    /// |--------|---------|---------|-----|---------|---------|----------|
+    /// | miner  | 50000   | 1.0     | yes | yes     | 1000    | all      |
+    /// | relay  | 5000    | 2.0     | yes | no      | 100     | all      |
+    /// | wallet | 300     | 1.0     | no  | no      | 10      | own only |
+    pub fn for_role(role: NodeRole) -> Self {
+        match role {
+            NodeRole::Miner => Self {
+                role: Some(role),
+                max_mempool_size: 50_000,
+                min_relay_fee: 1.0,
+                enable_rbf: true,
+                package_relay: true,
+                max_orphan_transactions: 1_000,
+                tx_announcement: TxAnnouncement::All,
+            },
+            NodeRole::Relay => Self {
+                role: Some(role),
+                max_mempool_size: 5_000,
+                min_relay_fee: 2.0,
+                enable_rbf: true,
+                package_relay: false,
+                max_orphan_transactions: 100,
+                tx_announcement: TxAnnouncement::All,
+            },
+            NodeRole::Wallet => Self {
+                role: Some(role),
+                max_mempool_size: 300,
+                min_relay_fee: 1.0,
+                enable_rbf: false,
+                package_relay: false,
+                max_orphan_transactions: 10,
+                tx_announcement: TxAnnouncement::OwnOnly,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MempoolConfig, NodeConfig, RelayConfig};
+
+    #[test]
+    fn profiles_produce_documented_settings() {
+        let miner = RelayPolicy::for_role(NodeRole::Miner);
+        assert_eq!(miner.max_mempool_size, 50_000);
+        assert!(miner.package_relay && miner.enable_rbf);
+        assert_eq!(miner.tx_announcement, TxAnnouncement::All);
+
+        let relay = RelayPolicy::for_role(NodeRole::Relay);
+        assert_eq!(relay.max_mempool_size, 5_000);
+        assert_eq!(relay.min_relay_fee, 2.0);
+        assert!(!relay.package_relay);
+
+        let wallet = RelayPolicy::for_role(NodeRole::Wallet);
+        assert_eq!(wallet.max_mempool_size, 300);
+        assert_eq!(wallet.max_orphan_transactions, 10);
+        assert!(!wallet.enable_rbf);
+        assert_eq!(wallet.tx_announcement, TxAnnouncement::OwnOnly);
+
+        // Without a role the [mempool] section is used unchanged.
+        let legacy = RelayConfig::default().effective(&MempoolConfig::default());
+        assert_eq!(legacy.role, None);
+        assert_eq!(legacy.max_mempool_size, MempoolConfig::default().max_size);
+        assert_eq!(legacy.tx_announcement, TxAnnouncement::All);
+    }
+
+    #[test]
+    fn overrides_win_over_profile() {
+        let mut config = NodeConfig::default();
+        config.relay = toml::from_str(
+            "role = \"wallet\"\nmax_mempool_size = 1000\ntx_announcement = \"all\"\n",
+        )
+        .unwrap();
+
+        let policy = config.relay_policy();
+        assert_eq!(policy.role, Some(NodeRole::Wallet));
+        assert_eq!(policy.max_mempool_size, 1_000);
+        assert_eq!(policy.tx_announcement, TxAnnouncement::All);
+        assert_eq!(policy.max_orphan_transactions, 10);
+
+        let mempool = config.effective_mempool();
+        assert_eq!(mempool.max_size, 1_000);
+        assert!(!mempool.enable_rbf);
+    }
+
+    #[test]
+    fn wallet_role_keeps_foreign_transactions_local() {
+        let wallet = RelayPolicy::for_role(NodeRole::Wallet).tx_announcement;
+
+        // A foreign transaction is still delivered and accepted locally, but
+        // its gossip is not forwarded and it is never re-announced.
+        assert!(!wallet.should_announce(TxOrigin::Peer));
+        assert!(!wallet.forwards_topic("transactions"));
+        assert!(!wallet.forwards_topic("mempool"));
+
+        // Own transactions and non-transaction gossip still flow.
+        assert!(wallet.should_announce(TxOrigin::Local));
+        assert!(wallet.forwards_topic("blocks"));
+
+        let relay = RelayPolicy::for_role(NodeRole::Relay).tx_announcement;
+        assert!(relay.should_announce(TxOrigin::Peer));
+        assert!(relay.forwards_topic("transactions"));
+    }
+}
@@ -0,0 +1,508 @@
+//! Coordinated object requests across peers.
+//!
+//! Every block or transaction we want is tracked here exactly once, no matter
+//! how many peers announced it. Each object is assigned to a single announcer
+//! at a time; the remaining announcers form a fallback queue that is tried in
+//! announcement order when the current request times out, fails, or the peer
+//! disconnects. Per-peer and global in-flight caps bound how much getdata
+//! fan-out an inv flood can cause, and scheduling favours blocks over
+//! transactions and, within a kind, objects announced by more peers.
+
+use libp2p::PeerId;
+use metrics::counter;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Kind of object being requested. Declaration order is scheduling priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ObjectKind {
+    Block,
+    Transaction,
+}
+
+/// A requestable object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectId {
+    pub kind: ObjectKind,
+    pub hash: [u8; 32],
+}
+
+impl ObjectId {
+    pub fn block(hash: [u8; 32]) -> Self {
+        Self {
+            kind: ObjectKind::Block,
+            hash,
+        }
+    }
+
+    pub fn transaction(hash: [u8; 32]) -> Self {
+        Self {
+            kind: ObjectKind::Transaction,
+            hash,
+        }
+    }
+}
+
+/// Request manager limits.
+#[derive(Debug, Clone)]
+pub struct RequestManagerConfig {
+    /// How long a peer has to deliver an object before we fail over.
+    pub request_timeout: Duration,
+    /// Maximum outstanding requests to a single peer.
+    pub max_in_flight_per_peer: usize,
+    /// Maximum outstanding requests across all peers.
+    pub max_in_flight_global: usize,
+    /// Maximum objects a single peer may have queued with us at once.
+    pub max_announcements_per_peer: usize,
+    /// Maximum distinct objects tracked at once.
+    pub max_tracked_objects: usize,
+}
+
+impl Default for RequestManagerConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(30),
+            max_in_flight_per_peer: 64,
+            max_in_flight_global: 512,
+            max_announcements_per_peer: 5_000,
+            max_tracked_objects: 50_000,
+        }
+    }
+}
+
+/// Counters exposed for diagnostics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestStats {
+    /// Requests handed out for sending.
+    pub requests_issued: u64,
+    /// Announcements for objects already tracked, which did not cause a request.
+    pub duplicates_suppressed: u64,
+    /// Requests that timed out.
+    pub timeouts: u64,
+    /// Requests reassigned to another announcer after a timeout or failure.
+    pub failovers: u64,
+    /// Announcements dropped because a per-peer or global cap was hit.
+    pub announcements_dropped: u64,
+    /// Objects given up on because no announcer delivered them.
+    pub exhausted: u64,
+}
+
+/// A request to send: ask `peer_id` for `object`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestAssignment {
+    pub peer_id: PeerId,
+    pub object: ObjectId,
+}
+
+#[derive(Debug)]
+struct ObjectState {
+    /// Announcers not yet tried, in announcement order.
+    candidates: VecDeque<PeerId>,
+    /// Every peer that announced this object (tried or not).
+    announcers: HashSet<PeerId>,
+    in_flight: Option<(PeerId, Instant)>,
+    first_seen: Instant,
+}
+
+/// Global registry of in-flight object requests.
+#[derive(Debug)]
+pub struct RequestManager {
+    config: RequestManagerConfig,
+    objects: HashMap<ObjectId, ObjectState>,
+    in_flight_per_peer: HashMap<PeerId, usize>,
+    queued_per_peer: HashMap<PeerId, usize>,
+    in_flight_total: usize,
+    stats: RequestStats,
+}
+
+impl Default for RequestManager {
+    fn default() -> Self {
+        Self::new(RequestManagerConfig::default())
+    }
+}
+
+impl RequestManager {
+    pub fn new(config: RequestManagerConfig) -> Self {
+        Self {
+            config,
+            objects: HashMap::new(),
+            in_flight_per_peer: HashMap::new(),
+            queued_per_peer: HashMap::new(),
+            in_flight_total: 0,
+            stats: RequestStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> &RequestManagerConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> &RequestStats {
+        &self.stats
+    }
+
+    /// Objects currently requested and not yet delivered.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight_total
+    }
+
+    /// Distinct objects tracked (queued or in flight).
+    pub fn tracked(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_tracked(&self, object: &ObjectId) -> bool {
+        self.objects.contains_key(object)
+    }
+
+    /// Record that `peer_id` announced `object`. Returns `true` if this is the
+    /// first announcement we are tracking for the object; further announcers
+    /// only join its fallback queue.
+    pub fn announce(&mut self, peer_id: PeerId, object: ObjectId, now: Instant) -> bool {
+        let queued = self.queued_per_peer.get(&peer_id).copied().unwrap_or(0);
+        if queued >= self.config.max_announcements_per_peer {
+            self.drop_announcement();
+            return false;
+        }
+
+        if let Some(state) = self.objects.get_mut(&object) {
+            self.stats.duplicates_suppressed += 1;
+            counter!("network_getdata_duplicates_suppressed", 1);
+            if state.announcers.insert(peer_id) {
+                state.candidates.push_back(peer_id);
+                *self.queued_per_peer.entry(peer_id).or_insert(0) += 1;
+            }
+            return false;
+        }
+
+        if self.objects.len() >= self.config.max_tracked_objects {
+            self.drop_announcement();
+            return false;
+        }
+
+        let mut announcers = HashSet::new();
+        announcers.insert(peer_id);
+        self.objects.insert(
+            object,
+            ObjectState {
+                candidates: VecDeque::from([peer_id]),
+                announcers,
+                in_flight: None,
+                first_seen: now,
+            },
+        );
+        *self.queued_per_peer.entry(peer_id).or_insert(0) += 1;
+        true
+    }
+
+    /// Expire timed-out requests and hand out new ones within the caps.
+    pub fn poll(&mut self, now: Instant) -> Vec<RequestAssignment> {
+        self.expire(now);
+
+        if self.in_flight_total >= self.config.max_in_flight_global {
+            return Vec::new();
+        }
+
+        let mut ready: Vec<(ObjectId, Reverse<usize>, Instant)> = self
+            .objects
+            .iter()
+            .filter(|(_, s)| s.in_flight.is_none() && !s.candidates.is_empty())
+            .map(|(id, s)| (*id, Reverse(s.announcers.len()), s.first_seen))
+            .collect();
+        ready.sort_by(|a, b| {
+            a.0.kind
+                .cmp(&b.0.kind)
+                .then(a.1.cmp(&b.1))
+                .then(a.2.cmp(&b.2))
+                .then(a.0.hash.cmp(&b.0.hash))
+        });
+
+        let mut assignments = Vec::new();
+        for (object, _, _) in ready {
+            if self.in_flight_total >= self.config.max_in_flight_global {
+                break;
+            }
+            let per_peer_cap = self.config.max_in_flight_per_peer;
+            let Some(state) = self.objects.get_mut(&object) else {
+                continue;
+            };
+            let in_flight_per_peer = &self.in_flight_per_peer;
+            let Some(position) = state.candidates.iter().position(|p| {
+                in_flight_per_peer.get(p).copied().unwrap_or(0) < per_peer_cap
+            }) else {
+                continue;
+            };
+            let Some(peer_id) = state.candidates.remove(position) else {
+                continue;
+            };
+            state.in_flight = Some((peer_id, now));
+            self.release_queued(&peer_id);
+            *self.in_flight_per_peer.entry(peer_id).or_insert(0) += 1;
+            self.in_flight_total += 1;
+            self.stats.requests_issued += 1;
+            assignments.push(RequestAssignment { peer_id, object });
+        }
+        assignments
+    }
+
+    /// An object arrived. Returns the peer it was requested from, or `None`
+    /// if it was unsolicited or delivered by someone else.
+    pub fn received(&mut self, object: &ObjectId) -> Option<PeerId> {
+        let state = self.objects.remove(object)?;
+        for peer in &state.candidates {
+            self.release_queued(peer);
+        }
+        let (peer_id, _) = state.in_flight?;
+        self.release_in_flight(&peer_id);
+        Some(peer_id)
+    }
+
+    /// The peer could not deliver the object (e.g. notfound). The next
+    /// announcer is tried on the following poll.
+    pub fn request_failed(&mut self, peer_id: &PeerId, object: &ObjectId) {
+        let Some(state) = self.objects.get_mut(object) else {
+            return;
+        };
+        if !matches!(state.in_flight, Some((p, _)) if p == *peer_id) {
+            return;
+        }
+        state.in_flight = None;
+        self.release_in_flight(peer_id);
+        self.stats.failovers += 1;
+        counter!("network_getdata_failovers", 1);
+        self.drop_if_exhausted(object);
+    }
+
+    /// Forget a disconnected peer: its queued announcements are removed and
+    /// its in-flight requests fail over to other announcers.
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        let mut touched = Vec::new();
+        for (object, state) in self.objects.iter_mut() {
+            state.candidates.retain(|p| p != peer_id);
+            state.announcers.remove(peer_id);
+            if matches!(state.in_flight, Some((p, _)) if p == *peer_id) {
+                state.in_flight = None;
+                self.stats.failovers += 1;
+            }
+            touched.push(*object);
+        }
+        if let Some(count) = self.in_flight_per_peer.remove(peer_id) {
+            self.in_flight_total = self.in_flight_total.saturating_sub(count);
+        }
+        self.queued_per_peer.remove(peer_id);
+        for object in touched {
+            self.drop_if_exhausted(&object);
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        let timeout = self.config.request_timeout;
+        let expired: Vec<(ObjectId, PeerId)> = self
+            .objects
+            .iter()
+            .filter_map(|(id, s)| match s.in_flight {
+                Some((peer, sent)) if now.duration_since(sent) >= timeout => Some((*id, peer)),
+                _ => None,
+            })
+            .collect();
+        for (object, peer_id) in expired {
+            self.stats.timeouts += 1;
+            counter!("network_getdata_timeouts", 1);
+            self.request_failed(&peer_id, &object);
+        }
+    }
+
+    fn drop_if_exhausted(&mut self, object: &ObjectId) {
+        let exhausted = self
+            .objects
+            .get(object)
+            .map(|s| s.in_flight.is_none() && s.candidates.is_empty())
+            .unwrap_or(false);
+        if exhausted {
+            self.objects.remove(object);
+            self.stats.exhausted += 1;
+        }
+    }
+
+    fn drop_announcement(&mut self) {
+        self.stats.announcements_dropped += 1;
+        counter!("network_getdata_announcements_dropped", 1);
+    }
+
+    fn release_queued(&mut self, peer_id: &PeerId) {
+        if let Some(count) = self.queued_per_peer.get_mut(peer_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.queued_per_peer.remove(peer_id);
+            }
+        }
+    }
+
+    fn release_in_flight(&mut self, peer_id: &PeerId) {
+        if let Some(count) = self.in_flight_per_peer.get_mut(peer_id) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                self.in_flight_per_peer.remove(peer_id);
+            }
+        }
+        self.in_flight_total = self.in_flight_total.saturating_sub(1);
+    }
+}
+
+/// Group assignments into one batch per peer and object kind, so a peer gets
+/// a single request message per kind.
+pub fn batch_assignments(
+    assignments: &[RequestAssignment],
+) -> HashMap<(PeerId, ObjectKind), Vec<[u8; 32]>> {
+    let mut batches: HashMap<(PeerId, ObjectKind), Vec<[u8; 32]>> = HashMap::new();
+    for assignment in assignments {
+        batches
+            .entry((assignment.peer_id, assignment.object.kind))
+            .or_default()
+            .push(assignment.object.hash);
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(n: u32) -> [u8; 32] {
+        let mut h = [0u8; 32];
+        h[..4].copy_from_slice(&n.to_le_bytes());
+        h
+    }
+
+    #[test]
+    fn tx_announced_by_five_peers_is_requested_once_then_fails_over() {
+        let mut manager = RequestManager::default();
+        let peers: Vec<PeerId> = (0..5).map(|_| PeerId::random()).collect();
+        let tx = ObjectId::transaction(hash(1));
+        let t0 = Instant::now();
+
+        for peer in &peers {
+            manager.announce(*peer, tx, t0);
+        }
+        assert_eq!(manager.tracked(), 1);
+        assert_eq!(manager.stats().duplicates_suppressed, 4);
+
+        let first = manager.poll(t0);
+        assert_eq!(
+            first,
+            vec![RequestAssignment {
+                peer_id: peers[0],
+                object: tx
+            }]
+        );
+        // Still waiting on the first peer: nothing new is sent.
+        assert!(manager.poll(t0 + Duration::from_secs(1)).is_empty());
+
+        let timeout = manager.config().request_timeout;
+        let second = manager.poll(t0 + timeout);
+        assert_eq!(
+            second,
+            vec![RequestAssignment {
+                peer_id: peers[1],
+                object: tx
+            }]
+        );
+        assert_eq!(manager.stats().timeouts, 1);
+        assert_eq!(manager.stats().requests_issued, 2);
+
+        assert_eq!(manager.received(&tx), Some(peers[1]));
+        assert_eq!(manager.in_flight(), 0);
+        assert_eq!(manager.tracked(), 0);
+    }
+
+    #[test]
+    fn global_cap_throttles_inv_flood() {
+        let config = RequestManagerConfig::default();
+        let global = config.max_in_flight_global;
+        let per_peer_queue = config.max_announcements_per_peer;
+        let mut manager = RequestManager::new(config);
+        let attacker = PeerId::random();
+        let honest: Vec<PeerId> = (0..20).map(|_| PeerId::random()).collect();
+        let t0 = Instant::now();
+
+        // One peer floods 10k invs; only its per-peer queue allowance is kept.
+        for n in 0..10_000 {
+            manager.announce(attacker, ObjectId::transaction(hash(n)), t0);
+        }
+        assert_eq!(manager.tracked(), per_peer_queue);
+        assert_eq!(
+            manager.stats().announcements_dropped,
+            (10_000 - per_peer_queue) as u64
+        );
+
+        // Many peers flooding distinct invs are still bounded by the global cap.
+        for (i, peer) in honest.iter().enumerate() {
+            for n in 0..500 {
+                let id = 100_000 + (i as u32) * 1_000 + n;
+                manager.announce(*peer, ObjectId::transaction(hash(id)), t0);
+            }
+        }
+        let issued = manager.poll(t0);
+        assert_eq!(issued.len(), global);
+        assert_eq!(manager.in_flight(), global);
+        assert!(manager.poll(t0).is_empty());
+
+        let per_peer = batch_assignments(&issued);
+        assert!(per_peer
+            .values()
+            .all(|hashes| hashes.len() <= manager.config().max_in_flight_per_peer));
+    }
+
+    #[test]
+    fn blocks_and_widely_announced_objects_go_first() {
+        let mut manager = RequestManager::new(RequestManagerConfig {
+            max_in_flight_global: 2,
+            ..Default::default()
+        });
+        let a = PeerId::random();
+        let b = PeerId::random();
+        let t0 = Instant::now();
+
+        let lone_tx = ObjectId::transaction(hash(1));
+        let popular_tx = ObjectId::transaction(hash(2));
+        let block = ObjectId::block(hash(3));
+        manager.announce(a, lone_tx, t0);
+        manager.announce(a, popular_tx, t0);
+        manager.announce(b, popular_tx, t0);
+        manager.announce(b, block, t0);
+
+        let issued: Vec<ObjectId> = manager.poll(t0).into_iter().map(|a| a.object).collect();
+        assert_eq!(issued, vec![block, popular_tx]);
+    }
+
+    #[test]
+    fn disconnect_fails_over_and_exhausted_objects_are_dropped() {
+        let mut manager = RequestManager::default();
+        let a = PeerId::random();
+        let b = PeerId::random();
+        let t0 = Instant::now();
+        let shared = ObjectId::block(hash(1));
+        let only_a = ObjectId::transaction(hash(2));
+
+        manager.announce(a, shared, t0);
+        manager.announce(b, shared, t0);
+        manager.announce(a, only_a, t0);
+        assert_eq!(manager.poll(t0).len(), 2);
+
+        manager.remove_peer(&a);
+        assert!(!manager.is_tracked(&only_a));
+        assert_eq!(manager.in_flight(), 0);
+        assert_eq!(
+            manager.poll(t0),
+            vec![RequestAssignment {
+                peer_id: b,
+                object: shared
+            }]
+        );
+
+        manager.request_failed(&b, &shared);
+        assert!(!manager.is_tracked(&shared));
+        assert_eq!(manager.stats().exhausted, 2);
+    }
+}
@@ -0,0 +1,736 @@
+//! Bootstrapping from a state snapshot, and serving snapshots to peers.
+//!
+//! In snapshot sync mode a node with a chain below the trusted snapshot
+//! height asks an outbound peer for the snapshot manifest
+//! (`SnapshotRequest { chunk: None }`), checks it against the height, tip
+//! hash and UTXO commitment the operator trusts, then fetches the chunks one
+//! at a time. Every chunk is checked against its manifest hash before it is
+//! applied; a peer sending a bad chunk, no snapshot or no answer within
+//! [`REQUEST_TIMEOUT`] is not asked again and the chunk is requested from
+//! another peer. Since every honest node writes identical chunks, repeated
+//! mismatches from different peers point at the manifest instead, and the
+//! download restarts with a manifest from someone else. Once the restored
+//! state reproduces the trusted commitment it becomes the chain, and the
+//! blocks above it are requested normally.
+//!
+//! Nodes with a snapshot interval take a snapshot every that many blocks and
+//! answer other nodes' requests from them.
+
+use crate::network::message::MessageSizeLimits;
+use crate::network::p2p::NetworkCommand;
+use crate::network::peer::PeerInfo;
+use crate::network::protocol::Message;
+use crate::storage::{
+    BlockchainDB, ChainState, SnapshotRestore, StateSnapshotError, StateSnapshotManifest,
+    StateSnapshotStore, TrustedSnapshot,
+};
+use libp2p::PeerId;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+/// How long a peer has to answer a snapshot request
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Consecutive chunk mismatches after which the manifest is blamed
+const MAX_CHUNK_FAILURES: u32 = 3;
+
+/// Blocks asked for once the snapshot is restored, as many as peers serve
+/// in one response
+const FOLLOW_UP_BLOCKS: u64 = 16;
+
+/// How often the bootstrap checks whether to send the next request
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A part of a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotPart {
+    Manifest,
+    Chunk(u32),
+}
+
+impl SnapshotPart {
+    /// The `chunk` field of requests for this part
+    fn chunk(self) -> Option<u32> {
+        match self {
+            SnapshotPart::Manifest => None,
+            SnapshotPart::Chunk(index) => Some(index),
+        }
+    }
+}
+
+/// Bootstrap state machine: which peer to ask for which part of the trusted
+/// snapshot. Time is passed in so it can be driven without sleeping.
+#[derive(Debug)]
+pub struct SnapshotBootstrap {
+    trusted: TrustedSnapshot,
+    /// Connected outbound peers, in connection order
+    outbound: Vec<PeerId>,
+    /// Peers that timed out, lack the snapshot or sent bad data
+    failed: HashSet<PeerId>,
+    /// Part to fetch next; `None` once the snapshot is restored
+    wanted: Option<SnapshotPart>,
+    /// Outstanding request: peer, part and when it was sent
+    pending: Option<(PeerId, SnapshotPart, Instant)>,
+    /// Peer the manifest being followed came from
+    manifest_peer: Option<PeerId>,
+    chunk_failures: u32,
+}
+
+impl SnapshotBootstrap {
+    pub fn new(trusted: TrustedSnapshot) -> Self {
+        Self {
+            trusted,
+            outbound: Vec::new(),
+            failed: HashSet::new(),
+            wanted: Some(SnapshotPart::Manifest),
+            pending: None,
+            manifest_peer: None,
+            chunk_failures: 0,
+        }
+    }
+
+    pub fn trusted(&self) -> &TrustedSnapshot {
+        &self.trusted
+    }
+
+    pub fn done(&self) -> bool {
+        self.wanted.is_none()
+    }
+
+    pub fn peer_connected(&mut self, peer_id: PeerId, inbound: bool) {
+        if !inbound && !self.outbound.contains(&peer_id) {
+            self.outbound.push(peer_id);
+        }
+    }
+
+    /// Forget a disconnected peer; a request it never answered goes to
+    /// another peer on the next poll
+    pub fn peer_disconnected(&mut self, peer_id: &PeerId) {
+        self.outbound.retain(|p| p != peer_id);
+        if self.pending.is_some_and(|(p, _, _)| p == *peer_id) {
+            self.pending = None;
+        }
+    }
+
+    /// The next request to send, if one is due. A peer that let the last
+    /// request time out is not asked again.
+    pub fn poll(&mut self, now: Instant) -> Option<(PeerId, Message)> {
+        let wanted = self.wanted?;
+        if let Some((peer_id, _, sent_at)) = self.pending {
+            if now < sent_at + REQUEST_TIMEOUT {
+                return None;
+            }
+            debug!("Snapshot request to {} timed out", peer_id);
+            self.failed.insert(peer_id);
+            self.pending = None;
+        }
+        let peer_id = *self
+            .outbound
+            .iter()
+            .find(|peer| !self.failed.contains(peer))?;
+        self.pending = Some((peer_id, wanted, now));
+        Some((
+            peer_id,
+            Message::SnapshotRequest {
+                height: self.trusted.height,
+                chunk: wanted.chunk(),
+            },
+        ))
+    }
+
+    /// Match a response against the outstanding request. Returns false,
+    /// and the response should be ignored, if it was not asked for.
+    pub fn on_response(&mut self, peer_id: PeerId, height: u64, chunk: Option<u32>) -> bool {
+        let expected = self.pending.is_some_and(|(p, part, _)| {
+            p == peer_id && height == self.trusted.height && part.chunk() == chunk
+        });
+        if expected {
+            self.pending = None;
+        }
+        expected
+    }
+
+    /// Follow the manifest received from `peer_id`, starting at chunk 0
+    pub fn manifest_accepted(&mut self, peer_id: PeerId) {
+        self.manifest_peer = Some(peer_id);
+        self.chunk_failures = 0;
+        self.wanted = Some(SnapshotPart::Chunk(0));
+    }
+
+    /// Move on to `next`, or finish with `None`
+    pub fn chunk_applied(&mut self, next: Option<u32>) {
+        self.chunk_failures = 0;
+        self.wanted = next.map(SnapshotPart::Chunk);
+    }
+
+    /// Stop asking `peer_id`, which sent no snapshot or a bad manifest
+    pub fn reject_peer(&mut self, peer_id: PeerId) {
+        self.failed.insert(peer_id);
+    }
+
+    /// Record a chunk from `peer_id` that did not match the manifest. The
+    /// sender is dropped; after [`MAX_CHUNK_FAILURES`] in a row the manifest
+    /// is blamed instead and the download restarts. Returns whether it did.
+    pub fn chunk_rejected(&mut self, peer_id: PeerId) -> bool {
+        self.failed.insert(peer_id);
+        self.chunk_failures += 1;
+        if self.chunk_failures < MAX_CHUNK_FAILURES {
+            return false;
+        }
+        self.restart();
+        true
+    }
+
+    /// Start over with a manifest from another peer. Peers dropped while
+    /// following the old manifest may have been honest, so they are asked
+    /// again.
+    pub fn restart(&mut self) {
+        self.failed.clear();
+        if let Some(peer_id) = self.manifest_peer.take() {
+            self.failed.insert(peer_id);
+        }
+        self.chunk_failures = 0;
+        self.pending = None;
+        self.wanted = Some(SnapshotPart::Manifest);
+    }
+}
+
+/// Restores the trusted snapshot from the live network, takes periodic
+/// snapshots and serves them to peers.
+pub struct SnapshotSync {
+    /// Present while the node still has to bootstrap
+    bootstrap: Option<parking_lot::Mutex<SnapshotBootstrap>>,
+    restore: parking_lot::Mutex<Option<SnapshotRestore>>,
+    /// Snapshots served to peers; `None` serves none
+    store: Option<StateSnapshotStore>,
+    /// Take a snapshot every this many blocks; 0 takes none
+    snapshot_interval: u64,
+    db: Arc<BlockchainDB>,
+    chain_state: Arc<RwLock<ChainState>>,
+    command_tx: mpsc::Sender<NetworkCommand>,
+}
+
+impl SnapshotSync {
+    /// Bootstrap from `trusted` unless the chain already reaches its height
+    pub fn new(
+        trusted: Option<TrustedSnapshot>,
+        store: Option<StateSnapshotStore>,
+        snapshot_interval: u64,
+        db: Arc<BlockchainDB>,
+        chain_state: Arc<RwLock<ChainState>>,
+        command_tx: mpsc::Sender<NetworkCommand>,
+    ) -> Self {
+        let height = db.get_height().unwrap_or(0);
+        let bootstrap = trusted
+            .filter(|trusted| height < trusted.height)
+            .map(|trusted| parking_lot::Mutex::new(SnapshotBootstrap::new(trusted)));
+        Self {
+            bootstrap,
+            restore: parking_lot::Mutex::new(None),
+            store,
+            snapshot_interval,
+            db,
+            chain_state,
+            command_tx,
+        }
+    }
+
+    /// Whether the node is still restoring the trusted snapshot. Blocks
+    /// cannot be connected until it is done.
+    pub fn bootstrapping(&self) -> bool {
+        self.bootstrap
+            .as_ref()
+            .is_some_and(|bootstrap| !bootstrap.lock().done())
+    }
+
+    pub fn peer_connected(&self, peer: &PeerInfo) {
+        if let Some(bootstrap) = &self.bootstrap {
+            bootstrap
+                .lock()
+                .peer_connected(peer.peer_id, peer.is_inbound);
+        }
+    }
+
+    pub fn peer_disconnected(&self, peer_id: &PeerId) {
+        if let Some(bootstrap) = &self.bootstrap {
+            bootstrap.lock().peer_disconnected(peer_id);
+        }
+    }
+
+    /// Handle a snapshot request or response. Returns false for any other
+    /// message.
+    pub async fn handle_message(&self, peer_id: PeerId, message: &Message) -> bool {
+        match message {
+            Message::SnapshotRequest { height, chunk } => {
+                let data = self.serve(*height, *chunk);
+                self.send(
+                    peer_id,
+                    Message::SnapshotChunk {
+                        height: *height,
+                        chunk: *chunk,
+                        data,
+                    },
+                )
+                .await;
+            }
+            Message::SnapshotChunk {
+                height,
+                chunk,
+                data,
+            } => self.on_chunk(peer_id, *height, *chunk, data).await,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Send the next bootstrap request and take the snapshot that is due,
+    /// every second until shutdown.
+    pub async fn run(self: Arc<Self>) {
+        let mut ticker = tokio::time::interval(TICK_INTERVAL);
+        let mut last_snapshot = None;
+        loop {
+            ticker.tick().await;
+            self.poll(Instant::now()).await;
+            if self.bootstrapping() {
+                continue;
+            }
+            let Some(store) = self.store.clone() else {
+                continue;
+            };
+            let tip = self.db.get_height().unwrap_or(0);
+            let Some(height) = StateSnapshotStore::due_height(tip, self.snapshot_interval)
+                .filter(|height| Some(*height) != last_snapshot)
+            else {
+                continue;
+            };
+            // Each height is tried once, even if it fails
+            last_snapshot = Some(height);
+            let db = Arc::clone(&self.db);
+            match tokio::task::spawn_blocking(move || store.create(&db, height)).await {
+                Ok(Ok(manifest)) => info!(
+                    "State snapshot at height {} has {} chunks",
+                    height,
+                    manifest.chunk_hashes.len()
+                ),
+                Ok(Err(e)) => warn!(
+                    "Failed to create state snapshot at height {}: {}",
+                    height, e
+                ),
+                Err(e) => error!("State snapshot task failed: {}", e),
+            }
+        }
+    }
+
+    async fn poll(&self, now: Instant) {
+        let Some(bootstrap) = &self.bootstrap else {
+            return;
+        };
+        let request = bootstrap.lock().poll(now);
+        if let Some((peer_id, message)) = request {
+            debug!(
+                "Requesting state snapshot data from {}: {:?}",
+                peer_id, message
+            );
+            self.send(peer_id, message).await;
+        }
+    }
+
+    /// The manifest or chunk to answer a request with; empty if we have no
+    /// such snapshot
+    fn serve(&self, height: u64, chunk: Option<u32>) -> Vec<u8> {
+        let Some(store) = &self.store else {
+            return Vec::new();
+        };
+        let data = match chunk {
+            None => store
+                .manifest(height)
+                .and_then(|manifest| bincode::serialize(&manifest).map_err(Into::into)),
+            Some(index) => store.chunk(height, index),
+        };
+        match data {
+            Ok(data) if data.len() <= MessageSizeLimits::MAX_MESSAGE_SIZE => data,
+            Ok(data) => {
+                warn!(
+                    "Snapshot chunk {:?} at {} is too large to send: {} bytes",
+                    chunk,
+                    height,
+                    data.len()
+                );
+                Vec::new()
+            }
+            Err(StateSnapshotError::NotFound(_)) => Vec::new(),
+            Err(e) => {
+                warn!("Failed to read state snapshot at height {}: {}", height, e);
+                Vec::new()
+            }
+        }
+    }
+
+    async fn on_chunk(&self, peer_id: PeerId, height: u64, chunk: Option<u32>, data: &[u8]) {
+        let Some(bootstrap) = &self.bootstrap else {
+            debug!("Ignoring unsolicited snapshot data from {}", peer_id);
+            return;
+        };
+        if !bootstrap.lock().on_response(peer_id, height, chunk) {
+            debug!("Ignoring unsolicited snapshot data from {}", peer_id);
+            return;
+        }
+        if data.is_empty() {
+            debug!("{} has no state snapshot at height {}", peer_id, height);
+            bootstrap.lock().reject_peer(peer_id);
+            return;
+        }
+        let Some(index) = chunk else {
+            match self.begin_restore(data) {
+                Ok(chunks) => {
+                    info!(
+                        "Restoring state snapshot at height {} ({} chunks) from {}",
+                        height, chunks, peer_id
+                    );
+                    bootstrap.lock().manifest_accepted(peer_id);
+                }
+                Err(e) => {
+                    warn!("Rejected snapshot manifest from {}: {}", peer_id, e);
+                    bootstrap.lock().reject_peer(peer_id);
+                }
+            }
+            return;
+        };
+
+        let applied = match self.restore.lock().as_mut() {
+            Some(restore) => restore
+                .apply_chunk(index, data)
+                .map(|()| restore.next_chunk()),
+            None => return,
+        };
+        match applied {
+            Ok(Some(next)) => bootstrap.lock().chunk_applied(Some(next)),
+            Ok(None) => self.finish_restore(peer_id).await,
+            Err(e) => {
+                warn!("Rejected snapshot chunk {} from {}: {}", index, peer_id, e);
+                if bootstrap.lock().chunk_rejected(peer_id) {
+                    warn!("Snapshot chunks keep failing; fetching the manifest again");
+                    self.restore.lock().take();
+                }
+            }
+        }
+    }
+
+    /// Start restoring the snapshot in `data`. Returns its chunk count.
+    fn begin_restore(&self, data: &[u8]) -> Result<usize, StateSnapshotError> {
+        let Some(bootstrap) = &self.bootstrap else {
+            return Ok(0);
+        };
+        let trusted = *bootstrap.lock().trusted();
+        let manifest: StateSnapshotManifest = bincode::deserialize(data)?;
+        let chunks = manifest.chunk_hashes.len();
+        *self.restore.lock() = Some(SnapshotRestore::begin(
+            Arc::clone(&self.db),
+            manifest,
+            &trusted,
+        )?);
+        Ok(chunks)
+    }
+
+    /// Make the fully applied snapshot the chain, or start over if it does
+    /// not verify
+    async fn finish_restore(&self, peer_id: PeerId) {
+        let Some(bootstrap) = &self.bootstrap else {
+            return;
+        };
+        let Some(restore) = self.restore.lock().take() else {
+            return;
+        };
+        let finished = tokio::task::spawn_blocking(move || restore.finish()).await;
+        let manifest = match finished {
+            Ok(Ok(manifest)) => manifest,
+            Ok(Err(e)) => {
+                warn!("Restored state snapshot failed verification: {}", e);
+                bootstrap.lock().restart();
+                return;
+            }
+            Err(e) => {
+                error!("State snapshot restore task failed: {}", e);
+                bootstrap.lock().restart();
+                return;
+            }
+        };
+        match self.chain_state.write() {
+            Ok(mut chain) => {
+                if let Err(e) = chain.reload_tip() {
+                    error!("Failed to load the restored chain tip: {}", e);
+                }
+            }
+            Err(_) => error!("Chain state lock poisoned after snapshot restore"),
+        }
+        bootstrap.lock().chunk_applied(None);
+        info!(
+            "Restored state snapshot at height {} ({} UTXOs); syncing the rest of the chain",
+            manifest.height, manifest.utxo_count
+        );
+        self.send(
+            peer_id,
+            Message::GetBlocksByHeight {
+                start_height: manifest.height + 1,
+                end_height: manifest.height + FOLLOW_UP_BLOCKS,
+            },
+        )
+        .await;
+    }
+
+    async fn send(&self, peer_id: PeerId, message: Message) {
+        if let Err(e) = self
+            .command_tx
+            .send(NetworkCommand::SendToPeer { peer_id, message })
+            .await
+        {
+            warn!(
+                "Failed to queue snapshot sync message for {}: {}",
+                peer_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::StorageError;
+    use supernova_core::consensus::difficulty_retarget::RetargetParams;
+    use supernova_core::types::block::Block;
+    use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+    use tempfile::tempdir;
+
+    const BITS: u32 = 0x207f_ffff;
+
+    /// Regtest difficulty without a retarget in reach, so blocks mined in a
+    /// burst keep the easy target
+    const PARAMS: RetargetParams = RetargetParams {
+        target_block_time: 30,
+        interval: 100_000,
+        pow_limit_bits: BITS,
+        allow_min_difficulty_blocks: false,
+    };
+
+    fn trusted() -> TrustedSnapshot {
+        TrustedSnapshot {
+            height: 5000,
+            tip_hash: [1u8; 32],
+            utxo_commitment: [2u8; 32],
+        }
+    }
+
+    fn mined_block(prev: [u8; 32], height: u64) -> Block {
+        let coinbase = Transaction::new(
+            1,
+            vec![TransactionInput::new(
+                [0u8; 32],
+                0xffff_ffff,
+                height.to_le_bytes().to_vec(),
+                0xffff_ffff,
+            )],
+            vec![TransactionOutput::new(5_000_000_000, vec![])],
+            0,
+        );
+        let mut block = Block::new_with_params(1, prev, vec![coinbase], BITS);
+        block.set_height(height);
+        while !block.verify_proof_of_work() {
+            block.increment_nonce();
+        }
+        block
+    }
+
+    /// Store `blocks[0..2]` as the tip directly; the genesis checkpoint
+    /// keeps custom blocks at heights 0 and 1 off the accept path
+    fn seed(db: &BlockchainDB, blocks: &[Block]) -> Result<(), StorageError> {
+        for block in &blocks[..2] {
+            db.store_block(&block.hash(), &bincode::serialize(block)?)?;
+            db.store_block_height_index(block.height(), &block.hash())?;
+        }
+        db.set_metadata(b"height", &1u64.to_be_bytes())?;
+        db.set_metadata(b"best_hash", &blocks[1].hash())?;
+        Ok(())
+    }
+
+    #[test]
+    fn bootstrap_retries_elsewhere_and_blames_the_manifest() {
+        let start = Instant::now();
+        let peers: Vec<PeerId> = (0..5).map(|_| PeerId::random()).collect();
+        let mut bootstrap = SnapshotBootstrap::new(trusted());
+        bootstrap.peer_connected(PeerId::random(), true);
+        assert!(
+            bootstrap.poll(start).is_none(),
+            "inbound peers are never asked"
+        );
+        for peer in &peers {
+            bootstrap.peer_connected(*peer, false);
+        }
+
+        let (peer, request) = bootstrap.poll(start).unwrap();
+        assert_eq!(peer, peers[0]);
+        assert!(matches!(
+            request,
+            Message::SnapshotRequest {
+                height: 5000,
+                chunk: None
+            }
+        ));
+        assert!(bootstrap.poll(start).is_none(), "one request at a time");
+        assert!(!bootstrap.on_response(peers[1], 5000, None));
+        assert!(!bootstrap.on_response(peers[0], 5000, Some(0)));
+
+        // A silent peer is replaced after the timeout
+        let later = start + REQUEST_TIMEOUT;
+        let (peer, _) = bootstrap.poll(later).unwrap();
+        assert_eq!(peer, peers[1]);
+        assert!(bootstrap.on_response(peers[1], 5000, None));
+        bootstrap.manifest_accepted(peers[1]);
+
+        // Bad chunks move the request to the next peer, until enough
+        // peers disagree with the manifest
+        for (i, peer) in peers[1..4].iter().enumerate() {
+            let (asked, request) = bootstrap.poll(later).unwrap();
+            assert_eq!(asked, *peer);
+            assert!(matches!(
+                request,
+                Message::SnapshotRequest { chunk: Some(0), .. }
+            ));
+            assert!(bootstrap.on_response(asked, 5000, Some(0)));
+            assert_eq!(bootstrap.chunk_rejected(asked), i == 2);
+        }
+
+        // The restart forgives everyone but the manifest's sender
+        let (peer, request) = bootstrap.poll(later).unwrap();
+        assert_eq!(peer, peers[0]);
+        assert!(matches!(
+            request,
+            Message::SnapshotRequest { chunk: None, .. }
+        ));
+        bootstrap.peer_disconnected(&peers[0]);
+        let (peer, _) = bootstrap.poll(later).unwrap();
+        assert_eq!(peer, peers[2]);
+        assert!(bootstrap.on_response(peer, 5000, None));
+        bootstrap.manifest_accepted(peer);
+        bootstrap.chunk_applied(None);
+        assert!(bootstrap.done());
+        assert!(bootstrap.poll(later).is_none());
+    }
+
+    #[tokio::test]
+    async fn second_node_bootstraps_from_a_snapshot_at_5000() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let mut blocks = Vec::new();
+        let mut prev = [0u8; 32];
+        for height in 0..=5005 {
+            let block = mined_block(prev, height);
+            prev = block.hash();
+            blocks.push(block);
+        }
+
+        // The source node validates the whole chain up to 5000
+        let source_db = Arc::new(BlockchainDB::new(dir.path().join("source"))?);
+        seed(&source_db, &blocks)?;
+        let mut source_chain = ChainState::with_params(Arc::clone(&source_db), PARAMS)?;
+        for block in &blocks[2..=5000] {
+            assert!(source_chain.process_block(block.clone()).await?);
+        }
+        let store = StateSnapshotStore::new(dir.path().join("snapshots"), 2);
+        let manifest = store.create(&source_db, 5000).unwrap();
+        assert_eq!(manifest.tip_hash, blocks[5000].hash());
+        let (source_tx, mut source_rx) = mpsc::channel(16);
+        let source = SnapshotSync::new(
+            None,
+            Some(store),
+            0,
+            Arc::clone(&source_db),
+            Arc::new(RwLock::new(source_chain.clone())),
+            source_tx,
+        );
+
+        // The new node trusts the published snapshot and has two peers: one
+        // corrupts every chunk it sends
+        let target_db = Arc::new(BlockchainDB::new(dir.path().join("target"))?);
+        let target_state = Arc::new(RwLock::new(ChainState::with_params(
+            Arc::clone(&target_db),
+            PARAMS,
+        )?));
+        let (target_tx, mut target_rx) = mpsc::channel(16);
+        let target = SnapshotSync::new(
+            Some(manifest.trusted()),
+            None,
+            0,
+            Arc::clone(&target_db),
+            Arc::clone(&target_state),
+            target_tx,
+        );
+        let (bad, good) = (PeerId::random(), PeerId::random());
+        if let Some(bootstrap) = &target.bootstrap {
+            bootstrap.lock().peer_connected(bad, false);
+            bootstrap.lock().peer_connected(good, false);
+        }
+        assert!(target.bootstrapping());
+
+        let target_id = PeerId::random();
+        let mut corrupted = 0;
+        let mut follow_up = None;
+        for _ in 0..100 {
+            if !target.bootstrapping() {
+                break;
+            }
+            target.poll(Instant::now()).await;
+            while let Ok(command) = target_rx.try_recv() {
+                let NetworkCommand::SendToPeer { peer_id, message } = command else {
+                    panic!("unexpected network command");
+                };
+                if let Message::GetBlocksByHeight { start_height, .. } = message {
+                    follow_up = Some((peer_id, start_height));
+                    continue;
+                }
+                assert!(source.handle_message(target_id, &message).await);
+                let Some(NetworkCommand::SendToPeer {
+                    message: mut reply, ..
+                }) = source_rx.try_recv().ok()
+                else {
+                    panic!("source did not answer {:?}", message);
+                };
+                if let Message::SnapshotChunk {
+                    chunk: Some(_),
+                    data,
+                    ..
+                } = &mut reply
+                {
+                    if peer_id == bad {
+                        data[data.len() / 2] ^= 0x01;
+                        corrupted += 1;
+                    }
+                }
+                assert!(target.handle_message(peer_id, &reply).await);
+            }
+        }
+        assert!(!target.bootstrapping());
+        assert_eq!(corrupted, 1, "the corrupting peer is asked once");
+        assert_eq!(follow_up, Some((good, 5001)));
+        assert_eq!(target_db.get_height()?, 5000);
+        assert_eq!(target_db.utxo_commitment()?, source_db.utxo_commitment()?);
+        let mut target_chain = target_state.read().unwrap().clone();
+        assert_eq!(target_chain.get_best_block_hash(), blocks[5000].hash());
+
+        // Both nodes accept the next blocks and agree on the result
+        for block in &blocks[5001..] {
+            assert!(source_chain.process_block(block.clone()).await?);
+            assert!(target_chain.process_block(block.clone()).await?);
+        }
+        assert_eq!(target_chain.get_height(), 5005);
+        assert_eq!(
+            target_chain.get_best_block_hash(),
+            source_chain.get_best_block_hash()
+        );
+        assert_eq!(target_db.utxo_commitment()?, source_db.utxo_commitment()?);
+        assert_eq!(target_db.utxo_stats()?, source_db.utxo_stats()?);
+        assert_eq!(target_db.pruned_height()?, 5000);
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use crate::network::protocol::Message;
+use crate::network::request_manager::{ObjectId, RequestManager};
 use crate::network::NetworkCommand;
 use crate::storage::persistence::{ForkInfo, ReorganizationEvent};
 use crate::storage::{BlockchainDB, ChainState, StorageError};
@@ -11,7 +12,7 @@ use std::clone::Clone;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::fmt::Debug;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::mpsc;
 use tracing::{debug, info, trace, warn};
@@ -186,6 +187,8 @@ struct PeerData {
     // Status flags
     is_on_probation: bool,
     is_preferred: bool,
+    /// Set when the peer answered a request with `Message::Busy`
+    busy_until: Option<Instant>,
 }
 
 impl PeerData {
@@ -212,6 +215,7 @@ impl PeerData {
             last_scoring_adjustment: now,
             is_on_probation: false,
             is_preferred: false,
+            busy_until: None,
         }
     }
 
@@ -293,6 +297,10 @@ impl PeerData {
     fn is_reliable(&self) -> bool {
         self.consecutive_successes >= 3 && self.invalid_data < 3 && !self.is_on_probation
     }
+
+    fn is_busy(&self) -> bool {
+        self.busy_until.is_some_and(|until| Instant::now() < until)
+    }
 }
 
 /// Current state of the sync process
@@ -330,6 +338,10 @@ pub struct ChainSync {
     last_status_update: Instant,
     peer_data: DashMap<PeerId, PeerData>,
     metrics: Arc<dyn SyncMetrics>,
+    /// Shared network request manager. When set, block downloads are
+    /// registered with it instead of being sent directly, so a block is never
+    /// in flight from more than one peer and failover is handled centrally.
+    request_manager: Option<Arc<Mutex<RequestManager>>>,
 }
 
 impl Clone for ChainSync {
@@ -346,6 +358,7 @@ impl Clone for ChainSync {
             last_status_update: self.last_status_update,
             peer_data: self.peer_data.clone(),
             metrics: Arc::clone(&self.metrics),
+            request_manager: self.request_manager.clone(),
         }
     }
 }
@@ -369,6 +382,7 @@ impl ChainSync {
             db,
             sync_start_time: None,
             last_status_update: Instant::now(),
+            request_manager: None,
         }
     }
 
@@ -378,6 +392,12 @@ impl ChainSync {
         self
     }
 
+    /// Route block downloads through the network's request manager
+    pub fn with_request_manager(mut self, request_manager: Arc<Mutex<RequestManager>>) -> Self {
+        self.request_manager = Some(request_manager);
+        self
+    }
+
     /// Load checkpoints from database or config
     pub async fn load_checkpoints(&mut self) -> Result<(), StorageError> {
         info!("Loading chain checkpoints");
@@ -435,6 +455,16 @@ impl ChainSync {
         }
     }
 
+    /// Record that a peer declined a request as busy. It is skipped when
+    /// choosing peers for headers and blocks until `retry_after` passes;
+    /// being busy is not misbehavior, so its score is left alone.
+    pub fn handle_busy(&self, peer_id: &PeerId, retry_after: Duration) {
+        if let Some(mut peer) = self.peer_data.get_mut(peer_id) {
+            peer.busy_until = Some(Instant::now() + retry_after);
+            debug!("Peer {} is busy for {:?}", peer_id, retry_after);
+        }
+    }
+
     /// Handle a new block received from the network
     pub async fn handle_new_block(
         &mut self,
@@ -931,9 +961,31 @@ impl ChainSync {
     /// Process headers received from the network
     async fn process_headers(
         &mut self,
-        headers: Vec<BlockHeader>,
+        mut headers: Vec<BlockHeader>,
         total_difficulty: u64,
     ) -> Result<(), String> {
+        // Never download a known-invalid block or anything building on one.
+        // Checking every header (each extends the previous) marks the whole
+        // invalid branch, so later announcements of it are refused too.
+        let mut first_invalid = None;
+        for (i, header) in headers.iter().enumerate() {
+            match self.chain_state.known_invalid(header) {
+                Ok(Some(_)) => {
+                    first_invalid.get_or_insert(i);
+                }
+                Ok(None) => {}
+                Err(e) => return Err(format!("Invalid-block cache lookup failed: {}", e)),
+            }
+        }
+        if let Some(i) = first_invalid {
+            warn!(
+                "Dropping {} headers from known-invalid block {} onwards",
+                headers.len() - i,
+                hex::encode(&headers[i].hash()[..8])
+            );
+            headers.truncate(i);
+        }
+
         if headers.is_empty() {
             return Ok(());
         }
@@ -1039,6 +1091,29 @@ impl ChainSync {
         // Find best peers for requests
         let peers = self.get_peers_for_block_requests(blocks_to_request.len());
 
+        // With a request manager, register every candidate peer as an
+        // announcer of each block (rotated so the first choice is spread
+        // across peers) and let the network layer issue one request per block
+        // with failover to the next peer.
+        if let (Some(request_manager), false) = (&self.request_manager, peers.is_empty()) {
+            if let Ok(mut requests) = request_manager.lock() {
+                let now = Instant::now();
+                for (i, block_hash) in blocks_to_request.iter().enumerate() {
+                    for offset in 0..peers.len() {
+                        let peer = peers[(i + offset) % peers.len()];
+                        requests.announce(peer, ObjectId::block(*block_hash), now);
+                    }
+                }
+            }
+            if let SyncState::SyncingBlocks {
+                last_request_time, ..
+            } = &mut self.sync_state
+            {
+                *last_request_time = Instant::now();
+            }
+            return Ok(());
+        }
+
         // Send block requests
         for (i, block_hash) in blocks_to_request.iter().enumerate() {
             let peer = if i < peers.len() {
@@ -1385,7 +1460,10 @@ impl ChainSync {
         // Check preferred peers first
         for peer_id in &preferred_peers {
             if let Some(peer_data) = self.peer_data.get(peer_id) {
-                if peer_data.reported_height >= height && peer_data.is_reliable() {
+                if peer_data.reported_height >= height
+                    && peer_data.is_reliable()
+                    && !peer_data.is_busy()
+                {
                     return Some(*peer_id);
                 }
             }
@@ -1399,8 +1477,8 @@ impl ChainSync {
             let peer_id = entry.key();
             let peer_data = entry.value();
 
-            // Skip peers that are on probation
-            if peer_data.is_on_probation {
+            // Skip peers that are on probation or asked us to back off
+            if peer_data.is_on_probation || peer_data.is_busy() {
                 continue;
             }
 
@@ -1423,7 +1501,11 @@ impl ChainSync {
             let mut additional_candidates: Vec<(PeerId, i32, bool)> = self
                 .peer_data
                 .iter()
-                .filter(|entry| !peers.contains(entry.key()) && !entry.value().is_on_probation)
+                .filter(|entry| {
+                    !peers.contains(entry.key())
+                        && !entry.value().is_on_probation
+                        && !entry.value().is_busy()
+                })
                 .map(|entry| {
                     let peer_data = entry.value();
                     // Calculate an effective score that rewards reliable peers
@@ -1530,7 +1612,7 @@ impl ChainSync {
         let mut preferred_peers: Vec<PeerId> = self
             .peer_data
             .iter()
-            .filter(|entry| entry.value().is_preferred)
+            .filter(|entry| entry.value().is_preferred && !entry.value().is_busy())
             .map(|entry| *entry.key())
             .collect();
 
@@ -1539,7 +1621,7 @@ impl ChainSync {
             let mut additional_peers: Vec<(PeerId, i32)> = self
                 .peer_data
                 .iter()
-                .filter(|entry| !entry.value().is_preferred)
+                .filter(|entry| !entry.value().is_preferred && !entry.value().is_busy())
                 .map(|entry| (*entry.key(), entry.value().score))
                 .collect();
 
@@ -1744,6 +1826,8 @@ impl ChainSync {
             active_peers,
             checkpoints: self.checkpoints.len(),
             sync_duration: self.sync_start_time.map(|t| t.elapsed().as_secs()),
+            fully_verified_blocks: self.chain_state.fully_verified_blocks(),
+            assumed_valid_blocks: self.chain_state.assumed_valid_blocks(),
         }
     }
 
@@ -1931,6 +2015,10 @@ pub struct SyncStats {
     pub active_peers: usize,
     pub checkpoints: usize,
     pub sync_duration: Option<u64>,
+    /// Blocks accepted with their signatures verified
+    pub fully_verified_blocks: u64,
+    /// Blocks accepted below the assumevalid block without signature checks
+    pub assumed_valid_blocks: u64,
 }
 
 /// Extension methods for BlockHeader providing uniform interface for sync operations
@@ -2145,4 +2233,30 @@ mod tests {
         let best_peer = sync.find_best_peer_for_height(250);
         assert_eq!(best_peer, None);
     }
+
+    #[tokio::test]
+    async fn test_busy_peer_is_skipped() {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let chain_state = ChainState::new(Arc::clone(&db)).unwrap();
+
+        let (tx, _) = mpsc::channel(32);
+        let sync = ChainSync::new(chain_state, Arc::clone(&db), tx);
+
+        let busy = PeerId::random();
+        let other = PeerId::random();
+        sync.register_peer(busy);
+        sync.register_peer(other);
+        sync.update_peer_height(&busy, 200, 2000);
+        sync.update_peer_height(&other, 100, 1000);
+
+        assert_eq!(sync.find_best_peer_for_height(150), Some(busy));
+
+        sync.handle_busy(&busy, Duration::from_secs(30));
+        assert_eq!(sync.find_best_peer_for_height(150), None);
+        assert_eq!(sync.get_peers_for_block_requests(2), vec![other]);
+
+        sync.handle_busy(&busy, Duration::ZERO);
+        assert_eq!(sync.find_best_peer_for_height(150), Some(busy));
+    }
 }
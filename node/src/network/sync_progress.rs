@@ -0,0 +1,347 @@
+//! Sync progress tracking and ETA estimation.
+//!
+//! The tracker is fed periodic samples of (local height, bytes received) and
+//! the best height advertised by peers. It derives block and byte rates over a
+//! sliding window, smooths them with an EWMA so a single slow batch does not
+//! make the ETA jump, and reports percentage complete (never decreasing within
+//! a sync) and an ETA to the best known height.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Minimum interval between published progress events
+pub const SYNC_PROGRESS_PUBLISH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Width of the sliding window rates are measured over
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Weight of the newest rate sample in the EWMA
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Samples required before an ETA is reported
+const MIN_ETA_SAMPLES: u32 = 3;
+
+/// Time observed before an ETA is reported
+const MIN_ETA_ELAPSED: Duration = Duration::from_secs(10);
+
+/// Rates below this are treated as zero
+const MIN_RATE: f64 = 1e-6;
+
+/// A point-in-time view of sync progress.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SyncProgress {
+    /// Local chain height
+    pub current_height: u64,
+    /// Best height advertised by peers
+    pub target_height: u64,
+    /// Percentage complete, 0-100
+    pub percent: f64,
+    /// Smoothed block processing rate
+    pub blocks_per_sec: f64,
+    /// Smoothed download rate
+    pub bytes_per_sec: f64,
+    /// Estimated seconds until caught up; `None` while unknown
+    pub eta_secs: Option<u64>,
+    /// True when caught up with the best known height
+    pub synced: bool,
+    /// Unix timestamp of this snapshot
+    pub updated_at: u64,
+}
+
+impl SyncProgress {
+    /// One-line human-readable summary, used for logs and the CLI.
+    pub fn summary(&self) -> String {
+        if self.synced {
+            return format!("Synced at height {}", self.current_height);
+        }
+        format!(
+            "Syncing {}/{} ({:.2}%), {:.1} blk/s, {}/s, ETA {}",
+            self.current_height,
+            self.target_height,
+            self.percent,
+            self.blocks_per_sec,
+            format_bytes(self.bytes_per_sec),
+            self.eta_secs
+                .map(format_eta)
+                .unwrap_or_else(|| "unknown".to_string())
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    height: u64,
+    bytes: u64,
+}
+
+/// Accumulates samples and produces [`SyncProgress`] snapshots.
+#[derive(Debug)]
+pub struct SyncProgressTracker {
+    window: VecDeque<Sample>,
+    target_height: u64,
+    first_sample: Option<Instant>,
+    samples: u32,
+    blocks_rate: Option<f64>,
+    bytes_rate: Option<f64>,
+    last_percent: f64,
+    last_published: Option<Instant>,
+    latest: Option<SyncProgress>,
+}
+
+impl Default for SyncProgressTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncProgressTracker {
+    pub fn new() -> Self {
+        Self {
+            window: VecDeque::new(),
+            target_height: 0,
+            first_sample: None,
+            samples: 0,
+            blocks_rate: None,
+            bytes_rate: None,
+            last_percent: 0.0,
+            last_published: None,
+            latest: None,
+        }
+    }
+
+    /// Raise the target to a height advertised by a peer. Never lowers it.
+    pub fn note_peer_height(&mut self, height: u64) {
+        self.target_height = self.target_height.max(height);
+    }
+
+    pub fn target_height(&self) -> u64 {
+        self.target_height
+    }
+
+    /// Record the local height and cumulative bytes received at `now`.
+    pub fn observe(&mut self, now: Instant, height: u64, bytes_received: u64) -> SyncProgress {
+        self.target_height = self.target_height.max(height);
+        self.first_sample.get_or_insert(now);
+        self.samples = self.samples.saturating_add(1);
+
+        self.window.push_back(Sample {
+            at: now,
+            height,
+            bytes: bytes_received,
+        });
+        // Keep one sample at or beyond the window edge as the baseline.
+        while self.window.len() > 2
+            && self
+                .window
+                .get(1)
+                .map_or(false, |s| now.duration_since(s.at) >= RATE_WINDOW)
+        {
+            self.window.pop_front();
+        }
+
+        if let (Some(first), Some(last)) = (self.window.front(), self.window.back()) {
+            let elapsed = last.at.duration_since(first.at).as_secs_f64();
+            if elapsed > 0.0 {
+                let blocks = last.height.saturating_sub(first.height) as f64 / elapsed;
+                let bytes = last.bytes.saturating_sub(first.bytes) as f64 / elapsed;
+                self.blocks_rate = Some(ewma(self.blocks_rate, blocks));
+                self.bytes_rate = Some(ewma(self.bytes_rate, bytes));
+            }
+        }
+
+        let progress = self.build(now, height);
+        self.latest = Some(progress.clone());
+        progress
+    }
+
+    /// Most recent snapshot, if any samples have been observed.
+    pub fn latest(&self) -> Option<&SyncProgress> {
+        self.latest.as_ref()
+    }
+
+    /// Whether a progress event should be published at `now`. Returns true
+    /// at most once per [`SYNC_PROGRESS_PUBLISH_INTERVAL`].
+    pub fn should_publish(&mut self, now: Instant) -> bool {
+        match self.last_published {
+            Some(last) if now.duration_since(last) < SYNC_PROGRESS_PUBLISH_INTERVAL => false,
+            _ => {
+                self.last_published = Some(now);
+                true
+            }
+        }
+    }
+
+    fn build(&mut self, now: Instant, height: u64) -> SyncProgress {
+        let target = self.target_height;
+        let synced = height >= target;
+        let raw_percent = if synced {
+            100.0
+        } else {
+            height as f64 / target as f64 * 100.0
+        };
+        // Monotonic within a sync. Once caught up, a new, higher target
+        // starts a fresh sync and the percentage may drop below 100 again.
+        self.last_percent = if self.last_percent >= 100.0 {
+            raw_percent
+        } else {
+            self.last_percent.max(raw_percent)
+        };
+
+        let blocks_per_sec = self.blocks_rate.unwrap_or(0.0);
+        let warmed_up = self.samples >= MIN_ETA_SAMPLES
+            && self
+                .first_sample
+                .map_or(false, |first| now.duration_since(first) >= MIN_ETA_ELAPSED);
+        let eta_secs = if synced {
+            Some(0)
+        } else if warmed_up && blocks_per_sec > MIN_RATE {
+            let remaining = target.saturating_sub(height) as f64;
+            Some((remaining / blocks_per_sec).min(u64::MAX as f64).ceil() as u64)
+        } else {
+            None
+        };
+
+        SyncProgress {
+            current_height: height,
+            target_height: target,
+            percent: self.last_percent,
+            blocks_per_sec,
+            bytes_per_sec: self.bytes_rate.unwrap_or(0.0),
+            eta_secs,
+            synced,
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+fn ewma(previous: Option<f64>, sample: f64) -> f64 {
+    match previous {
+        Some(prev) => EWMA_ALPHA * sample + (1.0 - EWMA_ALPHA) * prev,
+        None => sample,
+    }
+}
+
+/// Format seconds as e.g. "2h 05m", "4m 10s" or "12s".
+pub fn format_eta(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h {:02}m", h, m)
+    } else if m > 0 {
+        format!("{}m {:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+fn format_bytes(bytes_per_sec: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes_per_sec;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed one sample per `step` seconds with the given heights.
+    fn run(tracker: &mut SyncProgressTracker, start: Instant, step: u64, heights: &[u64]) -> Vec<SyncProgress> {
+        heights
+            .iter()
+            .enumerate()
+            .map(|(i, &h)| {
+                let at = start + Duration::from_secs(i as u64 * step);
+                tracker.observe(at, h, h * 1_000)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steady_progress_is_monotonic_with_sensible_eta() {
+        let mut tracker = SyncProgressTracker::new();
+        tracker.note_peer_height(10_000);
+        let start = Instant::now();
+        let heights: Vec<u64> = (0..30).map(|i| i * 50).collect();
+        let reports = run(&mut tracker, start, 5, &heights);
+
+        // Unknown until warmed up.
+        assert_eq!(reports[0].eta_secs, None);
+        assert_eq!(reports[1].eta_secs, None);
+
+        for pair in reports.windows(2) {
+            assert!(pair[1].percent >= pair[0].percent);
+        }
+
+        // 10 blocks/s with 8550 blocks left: about 855s.
+        let last = reports.last().unwrap();
+        assert!((last.blocks_per_sec - 10.0).abs() < 0.5, "{}", last.blocks_per_sec);
+        assert!((last.bytes_per_sec - 10_000.0).abs() < 500.0);
+        let eta = last.eta_secs.unwrap();
+        assert!((800..=900).contains(&eta), "eta {}", eta);
+        assert!(last.summary().contains("ETA 14m"));
+    }
+
+    #[test]
+    fn stall_slows_rate_and_grows_eta() {
+        let mut tracker = SyncProgressTracker::new();
+        tracker.note_peer_height(10_000);
+        let start = Instant::now();
+        let mut heights: Vec<u64> = (0..20).map(|i| i * 50).collect();
+        heights.extend(std::iter::repeat(950).take(40));
+        let reports = run(&mut tracker, start, 5, &heights);
+
+        let before_stall = &reports[19];
+        let stalled: Vec<_> = reports[20..].iter().collect();
+        for pair in stalled.windows(2) {
+            assert!(pair[1].percent >= pair[0].percent);
+            assert!(pair[1].blocks_per_sec <= pair[0].blocks_per_sec);
+            match (pair[0].eta_secs, pair[1].eta_secs) {
+                (Some(a), Some(b)) => assert!(b >= a),
+                (None, Some(_)) => panic!("ETA became known during a stall"),
+                _ => {}
+            }
+        }
+        let last = stalled.last().unwrap();
+        assert!(last.blocks_per_sec < 0.01, "{}", last.blocks_per_sec);
+        assert!(last.eta_secs.map_or(true, |eta| eta > before_stall.eta_secs.unwrap() * 10));
+        assert!(last.summary().contains("Syncing 950/10000"));
+    }
+
+    #[test]
+    fn percent_never_drops_when_target_rises_and_completes_at_100() {
+        let mut tracker = SyncProgressTracker::new();
+        tracker.note_peer_height(1_000);
+        let start = Instant::now();
+        assert_eq!(tracker.observe(start, 500, 0).percent, 50.0);
+
+        let mid = tracker.observe(start + Duration::from_secs(5), 750, 0);
+        tracker.note_peer_height(2_000);
+        let after_raise = tracker.observe(start + Duration::from_secs(10), 800, 0);
+        assert!(after_raise.percent >= mid.percent);
+
+        let done = tracker.observe(start + Duration::from_secs(15), 2_000, 0);
+        assert!(done.synced);
+        assert_eq!(done.percent, 100.0);
+        assert_eq!(done.eta_secs, Some(0));
+        assert_eq!(done.summary(), "Synced at height 2000");
+    }
+
+    #[test]
+    fn publishing_is_rate_limited() {
+        let mut tracker = SyncProgressTracker::new();
+        let start = Instant::now();
+        assert!(tracker.should_publish(start));
+        assert!(!tracker.should_publish(start + Duration::from_secs(1)));
+        assert!(tracker.should_publish(start + SYNC_PROGRESS_PUBLISH_INTERVAL));
+    }
+}
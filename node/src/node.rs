@@ -3,19 +3,30 @@ use crate::adapters::{
 };
 use crate::api::types::{LoadAverage, LogEntry, NodeInfo, NodeMetrics, SystemInfo, VersionInfo};
 use crate::api::ApiConfig;
+use crate::clock::ClockMonitor;
 use crate::config::NodeConfig;
+use crate::events::{new_event_bus, EventBus, MempoolRemovalReason, NodeEvent};
 use crate::mempool::TransactionPool;
 use crate::metrics::performance::PerformanceMonitor;
-use crate::network::{NetworkCommand, NetworkProxy, P2PNetwork};
+use crate::metrics::rejections::{RejectionDomain, RejectionTracker};
+use crate::network::identity_rotation::{
+    self, apply_attestation, IdentityAttestation, IdentityLinkRegistry,
+    IDENTITY_ROTATION_ANNOUNCE_INTERVAL,
+};
+use crate::network::{
+    AddressBook, BlockServer, MempoolSync, NetworkCommand, NetworkProxy, OperatorMessenger, P2PNetwork,
+    SnapshotSync, SyncProgress, SyncProgressTracker,
+};
 use crate::storage::{
-    BlockchainDB, ChainState, DatabaseShutdownHandler, StorageError, WriteAheadLog,
+    BlockchainDB, ChainState, DatabaseShutdownHandler, RecoveryManager, StateSnapshotStore,
+    StorageError, StorageMaintenance, TxIndexBuilder, WriteAheadLog,
 };
 use crate::testnet::NodeTestnetManager;
 use crate::testnet::TestnetNodeConfig;
 use supernova_core::crypto::quantum::QuantumScheme;
 use supernova_core::lightning::manager::{LightningEvent, LightningManager};
 use supernova_core::lightning::wallet::LightningWallet;
-use supernova_core::lightning::LightningConfig;
+use supernova_core::lightning::{FundingConfig, LightningConfig};
 use supernova_core::types::block::Block;
 use supernova_core::types::transaction::Transaction;
 use hex;
@@ -30,6 +41,9 @@ use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 use uuid;
 
+/// Least time between supply audits while the tip is moving
+const SUPPLY_AUDIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
 /// Node status information for internal use
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NodeStatusInfo {
@@ -122,6 +136,24 @@ pub struct Node {
     chain_state: Arc<RwLock<ChainState>>,
     /// Transaction mempool
     mempool: Arc<TransactionPool>,
+    /// Block validation failures by reason code, with recent history
+    block_rejections: Arc<RejectionTracker>,
+    /// Initial block download progress and ETA
+    sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+    /// Answers block and header requests from peers
+    block_server: Arc<BlockServer>,
+    /// Refills the mempool from outbound peers after startup
+    mempool_sync: Arc<MempoolSync>,
+    /// Restores the trusted state snapshot and serves our snapshots
+    snapshot_sync: Arc<SnapshotSync>,
+    /// Peer addresses learned from peer exchange
+    address_book: Arc<AddressBook>,
+    /// Messages between node operators
+    operator_messages: Arc<OperatorMessenger>,
+    /// Local clock skew against peers and the tip
+    clock: Arc<ClockMonitor>,
+    /// Node event bus
+    events: EventBus,
     /// P2P network
     network: Arc<P2PNetwork>,
     /// Thread-safe network proxy for API access
@@ -154,9 +186,55 @@ impl Node {
 
         // Initialize database
         let db = Arc::new(BlockchainDB::new(&config.storage.db_path)?);
-
-        // Initialize chain state
-        let chain_state = Arc::new(RwLock::new(ChainState::new(Arc::clone(&db))?));
+        db.set_tx_index(config.storage.tx_index)?;
+        db.set_address_index(config.storage.address_index)?;
+        // Resolve whatever a crash during a commit left behind before the
+        // chain state loads the tip
+        RecoveryManager::check_consistency(&db)?;
+
+        // Initialize chain state, under a custom network's rules when a
+        // netparams file is active
+        let netparams = supernova_core::netparams::active();
+        let mut state = match netparams {
+            Some(params) => {
+                info!("Using custom network parameters '{}'", params.name);
+                let mut state = ChainState::with_params(Arc::clone(&db), params.retarget_params())?;
+                state.set_version_bits_params(params.version_bits_params());
+                state
+            }
+            None => ChainState::new(Arc::clone(&db))?,
+        };
+        if config.node.validation_tracing {
+            info!("Validation tracing enabled: rejected blocks keep a trace of every check");
+        }
+        state.set_validation_tracing(config.node.validation_tracing);
+        if let Some(hash) = config.sync.assume_valid() {
+            info!(
+                "Assuming valid signatures at and below block {}",
+                hex::encode(hash)
+            );
+        }
+        state.set_assume_valid(config.sync.assume_valid());
+        let signature_cache = Arc::new(supernova_core::validation::SignatureCache::new(
+            config.node.signature_cache_bytes,
+        ));
+        state.set_signature_cache(Arc::clone(&signature_cache));
+        let verify_flags = if config.node.hybrid_signature_migration {
+            warn!("Hybrid signature migration enabled: either half of a hybrid signature suffices");
+            supernova_core::validation::VERIFY_HYBRID_EITHER
+        } else {
+            supernova_core::validation::CONSENSUS_VERIFY_FLAGS
+        };
+        state.set_verify_flags(verify_flags);
+        state.set_undo_depth(config.storage.undo_depth);
+        if let Some(target) = config.storage.prune_target_bytes() {
+            info!(
+                "Prune mode enabled: keeping about {} bytes of raw blocks",
+                target
+            );
+        }
+        state.set_prune_target(config.storage.prune_target_bytes());
+        let chain_state = Arc::new(RwLock::new(state));
 
         // Initialize genesis block if needed
         if chain_state
@@ -168,8 +246,13 @@ impl Node {
             tracing::info!("Creating genesis block for chain: {}", config.node.chain_id);
             
             // Create genesis block
-            let genesis_block = crate::blockchain::create_genesis_block(&config.node.chain_id)
-                .map_err(|e| NodeError::General(format!("Genesis creation failed: {}", e)))?;
+            let genesis_block = match netparams {
+                Some(params) => params
+                    .genesis_block()
+                    .map_err(|e| NodeError::General(format!("Genesis creation failed: {}", e)))?,
+                None => crate::blockchain::create_genesis_block(&config.node.chain_id)
+                    .map_err(|e| NodeError::General(format!("Genesis creation failed: {}", e)))?,
+            };
             
             chain_state
                 .write()
@@ -179,14 +262,54 @@ impl Node {
                 
             tracing::info!("Genesis block initialized successfully");
         }
-        
-        // Initialize mempool
-        let mempool_config = crate::mempool::MempoolConfig::from(config.mempool.clone());
-        let mempool = Arc::new(TransactionPool::new(mempool_config));
+
+        // Index the blocks stored before the transaction index, or the
+        // address index, was enabled
+        if db.tx_index_enabled() && db.tx_index_next_height()?.is_some() {
+            info!("Building the transaction index in the background");
+            tokio::spawn(Arc::new(TxIndexBuilder::new(Arc::clone(&db))).run());
+        }
+
+        // Initialize mempool with the relay profile applied
+        let relay_policy = config.relay_policy();
+        info!(
+            "Relay policy: role={:?}, mempool={}, min fee={}, rbf={}, announce={:?}",
+            relay_policy.role,
+            relay_policy.max_mempool_size,
+            relay_policy.min_relay_fee,
+            relay_policy.enable_rbf,
+            relay_policy.tx_announcement
+        );
+        let mempool_config = crate::mempool::MempoolConfig::from(config.effective_mempool());
+        let mempool = Arc::new(
+            TransactionPool::new(mempool_config)
+                .with_signature_cache(signature_cache)
+                .with_verify_flags(verify_flags),
+        );
+        mempool.set_finality_view(Arc::clone(&chain_state) as Arc<dyn crate::mempool::FinalityView>);
+
+        // Restore the transactions saved at the last shutdown, dropping any
+        // whose inputs were spent or that no longer verify
+        if config.mempool.persist {
+            let path = config.storage.db_path.join(crate::mempool::pool::MEMPOOL_FILE);
+            let still_valid = |tx: &Transaction| {
+                chain_state
+                    .read()
+                    .map(|chain| chain.verify_transaction_authorization(tx).is_ok())
+                    .unwrap_or(false)
+            };
+            match mempool.load_from_disk(&path, still_valid) {
+                Ok(stats) => info!(
+                    "Restored {} mempool transactions ({} no longer valid, {} expired)",
+                    stats.loaded, stats.invalid, stats.expired
+                ),
+                Err(e) => warn!("Failed to restore mempool from {:?}: {}", path, e),
+            }
+        }
 
         // Initialize network with persistent peer ID
         // Use explicit ./data directory for peer identity storage
-        let data_dir = PathBuf::from("./data");
+        let data_dir = PathBuf::from(crate::network::peer_identity::DEFAULT_IDENTITY_DIR);
         
         // Ensure data directory exists
         if let Err(e) = std::fs::create_dir_all(&data_dir) {
@@ -207,6 +330,22 @@ impl Node {
             .read()
             .map_err(|_| NodeError::General("Chain state lock poisoned".to_string()))?
             .get_genesis_hash();
+        if let Some(params) = netparams {
+            // A datadir created for another network must not be reused
+            let expected = params
+                .genesis_hash()
+                .map_err(|e| NodeError::ConfigError(e.to_string()))?;
+            if genesis_hash != expected {
+                return Err(NodeError::ConfigError(format!(
+                    "Datadir {:?} holds genesis {}, but network '{}' has genesis {}",
+                    config.storage.db_path,
+                    hex::encode(genesis_hash),
+                    params.name,
+                    params.genesis.hash
+                )));
+            }
+        }
+        let identity = keypair.clone();
         let (mut network, command_tx, event_rx) =
             P2PNetwork::new(
                 Some(keypair),
@@ -216,6 +355,17 @@ impl Node {
                 Some(config.network.pubsub_config.validation_mode.clone()), // Gossipsub validation mode
             ).await?;
         
+        network.set_tx_announcement(relay_policy.tx_announcement);
+        if let Some(params) = netparams {
+            let magic = params
+                .magic_bytes()
+                .map_err(|e| NodeError::ConfigError(e.to_string()))?;
+            network.set_network_identity(magic, genesis_hash);
+        }
+        network
+            .configure_addresses(&config.network)
+            .map_err(|e| NodeError::General(format!("Invalid listen addresses: {}", e)))?;
+
         // Add bootstrap nodes from config
         info!("Checking bootstrap_nodes config: {} entries", config.network.bootstrap_nodes.len());
         
@@ -256,6 +406,46 @@ impl Node {
             info!("No bootstrap nodes in config");
         }
         
+        match crate::network::dns_seed::DnsSeeder::from_config(&config.network) {
+            Ok(Some(seeder)) => network.set_dns_seeder(seeder),
+            Ok(None) => {}
+            Err(e) => warn!("DNS seeding disabled: {}", e),
+        }
+        network.set_outbound_diversity(
+            crate::network::peer_diversity::OutboundDiversityPolicy::from_config(
+                &config.network.peer_diversity,
+            ),
+        );
+        network.set_bandwidth_limits(&config.network.bandwidth);
+        network.set_known_inventory_size(config.network.known_inventory_size);
+        network.set_encrypted_transport(config.network.encrypted_transport);
+        // A node bootstrapping from a snapshot will have no blocks below it
+        let snapshot_sync = Arc::new(SnapshotSync::new(
+            config.sync.trusted_snapshot(),
+            Some(StateSnapshotStore::new(
+                config.checkpoint.data_dir.join("snapshots"),
+                config.checkpoint.max_snapshots,
+            )),
+            config.checkpoint.snapshot_interval_blocks,
+            Arc::clone(&db),
+            Arc::clone(&chain_state),
+            command_tx.clone(),
+        ));
+        if snapshot_sync.bootstrapping() {
+            info!("Bootstrapping from the trusted state snapshot");
+        }
+        network.set_pruned(
+            config.storage.prune_target_bytes().is_some()
+                || db.pruned_height()? > 0
+                || snapshot_sync.bootstrapping(),
+        );
+        network.set_best_height(
+            chain_state
+                .read()
+                .map_err(|_| NodeError::General("Chain state lock poisoned".to_string()))?
+                .get_height(),
+        );
+
         // Create thread-safe network proxy for API access BEFORE wrapping in Arc
         let (network_proxy, proxy_request_rx, _cached_stats) = NetworkProxy::new(
             network.local_peer_id(),
@@ -274,12 +464,106 @@ impl Node {
         let network = Arc::new(network);
 
         // Spawn network event processing task
+        let block_rejections = Arc::new(RejectionTracker::new(RejectionDomain::Block));
         let mempool_clone = Arc::clone(&mempool);
         let chain_state_clone = Arc::clone(&chain_state);
+        let block_rejections_clone = Arc::clone(&block_rejections);
+        let sync_progress = Arc::new(parking_lot::Mutex::new(SyncProgressTracker::new()));
+        let sync_progress_clone = Arc::clone(&sync_progress);
+        let block_server = Arc::new(BlockServer::new(
+            config.network.block_serving.clone(),
+            Arc::clone(&db),
+            Arc::clone(&chain_state),
+            Arc::clone(&sync_progress),
+            command_tx.clone(),
+        ));
+        let block_server_clone = Arc::clone(&block_server);
+        let mempool_sync = Arc::new(MempoolSync::new(
+            config.network.mempool_sync.clone(),
+            Arc::clone(&mempool),
+            network.request_manager(),
+            Arc::clone(&sync_progress),
+            command_tx.clone(),
+        ));
+        let mempool_sync_clone = Arc::clone(&mempool_sync);
+        let snapshot_sync_clone = Arc::clone(&snapshot_sync);
+        let address_book = Arc::new(
+            AddressBook::new(
+                Some(data_dir.join(crate::network::discovery::addrman::PEERS_FILE)),
+                config.network.max_outbound_connections,
+                network.outbound_diversity(),
+                command_tx.clone(),
+            )
+            .with_advertiser(network.address_advertiser()),
+        );
+        let address_book_clone = Arc::clone(&address_book);
+        let events = new_event_bus();
+        let events_clone = events.clone();
+        let authority_key = match &config.network.operator_messages.authority_key_file {
+            Some(path) => Some(
+                crate::network::operator_messages::load_authority_key(path).map_err(|e| {
+                    NodeError::ConfigError(format!("Operator authority key: {}", e))
+                })?,
+            ),
+            None => None,
+        };
+        let operator_messages = Arc::new(OperatorMessenger::new(
+            config.network.operator_messages.clone(),
+            identity,
+            authority_key,
+            command_tx.clone(),
+            events.clone(),
+        ));
+        let operator_messages_clone = Arc::clone(&operator_messages);
+        let clock = Arc::new(ClockMonitor::new(&config.clock));
+        let clock_clone = Arc::clone(&clock);
+        let peer_manager = network.peer_manager();
+        let identity_links = network.identity_links();
         tokio::spawn(async move {
-            Self::process_network_events(event_rx, mempool_clone, chain_state_clone).await;
+            Self::process_network_events(
+                event_rx,
+                mempool_clone,
+                chain_state_clone,
+                block_rejections_clone,
+                sync_progress_clone,
+                events_clone,
+                peer_manager,
+                identity_links,
+                block_server_clone,
+                mempool_sync_clone,
+                snapshot_sync_clone,
+                address_book_clone,
+                operator_messages_clone,
+                clock_clone,
+            )
+            .await;
         });
 
+        // Drain queued historical block requests within the serving budget
+        tokio::spawn(Arc::clone(&block_server).run());
+
+        // Warm the mempool up from outbound peers once IBD is done
+        tokio::spawn(Arc::clone(&mempool_sync).run());
+
+        // Restore the trusted state snapshot, then keep taking our own
+        tokio::spawn(Arc::clone(&snapshot_sync).run());
+
+        // Fill free outbound slots from the address book
+        tokio::spawn(Arc::clone(&address_book).run());
+
+        // Keep announcing a pending identity rotation until its grace
+        // period ends
+        tokio::spawn(Self::announce_identity_rotation(data_dir.clone(), command_tx.clone()));
+
+        // Sample sync progress every second; publish and log at most once
+        // per SYNC_PROGRESS_PUBLISH_INTERVAL.
+        tokio::spawn(Self::report_sync_progress(
+            Arc::clone(&sync_progress),
+            Arc::clone(&chain_state),
+            Arc::clone(&network_proxy),
+            events.clone(),
+        ));
+
         // Initialize testnet manager if enabled
         let testnet_manager = if config.testnet.enabled {
             // Convert TestnetConfig to TestnetNodeConfig
@@ -324,6 +608,7 @@ impl Node {
                     None
                 },
                 quantum_security_level: 1,
+                funding: FundingConfig::default(),
             };
 
             // Create Lightning wallet
@@ -358,10 +643,26 @@ impl Node {
             let manager_for_task = Arc::clone(&manager_clone);
 
             // Spawn event processing task in the background
+            let mempool_for_task = Arc::clone(&mempool);
+            let events_for_task = events.clone();
+            let command_tx_for_task = command_tx.clone();
             tokio::spawn(async move {
-                Self::process_lightning_events(manager_for_task, event_receiver).await;
+                Self::process_lightning_events(
+                    manager_for_task,
+                    event_receiver,
+                    mempool_for_task,
+                    events_for_task,
+                    command_tx_for_task,
+                )
+                .await;
             });
 
+            // Follow the chain so funded channels activate at depth
+            tokio::spawn(Self::follow_chain_for_lightning(
+                Arc::clone(&manager_clone),
+                events.subscribe(),
+            ));
+
             Some(manager_clone)
         } else {
             None
@@ -429,11 +730,57 @@ impl Node {
             }
         };
 
+        // Check the local clock now and then periodically
+        tokio::spawn(Arc::clone(&clock).run(Arc::clone(&chain_state)));
+
+        // Keep the mempool and the wallet's parked transactions in step with
+        // timelock finality as the tip moves
+        tokio::spawn(Self::follow_chain_for_timelocks(
+            Arc::clone(&mempool),
+            wallet_manager.as_ref().map(Arc::clone),
+            events.clone(),
+            events.subscribe(),
+        ));
+
+        // Audit the coin supply against the subsidy schedule as the tip moves
+        tokio::spawn(Self::audit_supply(Arc::clone(&db), events.subscribe()));
+
+        // Compact the database on schedule or once fragmented, never while
+        // syncing
+        let sync_state = Arc::clone(&sync_progress);
+        let maintenance = StorageMaintenance::new(
+            Arc::clone(&db),
+            config.storage.compaction_interval_hours,
+            config.storage.compaction_threshold,
+            move || {
+                sync_state
+                    .lock()
+                    .latest()
+                    .is_some_and(|progress| !progress.synced)
+            },
+        );
+        tokio::spawn(Arc::new(maintenance).run());
+
+        // Advertise the current tip in version handshakes
+        tokio::spawn(Self::follow_chain_for_handshake(
+            Arc::clone(&network),
+            events.subscribe(),
+        ));
+
         Ok(Self {
             config: Arc::new(RwLock::new(config)),
             db,
             chain_state: Arc::clone(&chain_state),
             mempool,
+            block_rejections,
+            sync_progress,
+            block_server,
+            mempool_sync,
+            snapshot_sync,
+            address_book,
+            operator_messages,
+            clock,
+            events,
             network,
             network_proxy,
             network_command_tx: command_tx,
@@ -473,17 +820,18 @@ impl Node {
     pub async fn stop(&self) -> Result<(), NodeError> {
         tracing::info!("Stopping Supernova node...");
 
+        // Stop producing work (faucet, test mining) before network intake,
+        // mirroring the ShutdownCoordinator stage order
+        if let Some(testnet) = &self.testnet_manager {
+            testnet.stop().map_err(NodeError::TestnetError)?;
+        }
+
         // Stop network
         self.network
             .stop()
             .await
             .map_err(|e| NodeError::NetworkError(e.to_string()))?;
 
-        // Stop testnet manager if enabled
-        if let Some(testnet) = &self.testnet_manager {
-            testnet.stop().map_err(NodeError::TestnetError)?;
-        }
-
         tracing::info!("Node stopped successfully");
         Ok(())
     }
@@ -507,6 +855,51 @@ impl Node {
     pub fn mempool(&self) -> Arc<TransactionPool> {
         Arc::clone(&self.mempool)
     }
+
+    /// Block validation rejection statistics
+    pub fn block_rejections(&self) -> Arc<RejectionTracker> {
+        Arc::clone(&self.block_rejections)
+    }
+
+    /// Sync progress tracker fed by the network event loop
+    pub fn sync_progress(&self) -> Arc<parking_lot::Mutex<SyncProgressTracker>> {
+        Arc::clone(&self.sync_progress)
+    }
+
+    /// Serves blocks and headers to peers within the configured budget
+    pub fn block_server(&self) -> Arc<BlockServer> {
+        Arc::clone(&self.block_server)
+    }
+
+    /// Mempool warm-up from outbound peers
+    pub fn mempool_sync(&self) -> Arc<MempoolSync> {
+        Arc::clone(&self.mempool_sync)
+    }
+
+    /// State snapshot bootstrap and serving
+    pub fn snapshot_sync(&self) -> Arc<SnapshotSync> {
+        Arc::clone(&self.snapshot_sync)
+    }
+
+    /// Peer addresses learned from peer exchange
+    pub fn address_book(&self) -> Arc<AddressBook> {
+        Arc::clone(&self.address_book)
+    }
+
+    /// Messages between node operators
+    pub fn operator_messages(&self) -> Arc<OperatorMessenger> {
+        Arc::clone(&self.operator_messages)
+    }
+
+    /// Local clock monitor
+    pub fn clock(&self) -> Arc<ClockMonitor> {
+        Arc::clone(&self.clock)
+    }
+
+    /// Node event bus
+    pub fn events(&self) -> EventBus {
+        self.events.clone()
+    }
     
     /// Set wallet manager (called by ApiFacade after Node creation)
     pub fn set_wallet_manager(&mut self, wallet_manager: Arc<RwLock<crate::wallet_manager::WalletManager>>) {
@@ -549,8 +942,10 @@ impl Node {
             tracing::warn!("Failed to add transaction to mempool: {}", e);
             return;
         }
+        let _ = self.events.send(NodeEvent::MempoolTransaction(tx.clone()));
 
-        // Broadcast to network
+        // Broadcast to network. Own transactions are announced under every
+        // relay policy; only third-party forwarding is role-dependent.
         self.network.broadcast_transaction(tx);
         tracing::info!("Broadcasting transaction: {:?}", tx.hash());
     }
@@ -560,73 +955,108 @@ impl Node {
         mut event_rx: mpsc::Receiver<crate::network::NetworkEvent>,
         mempool: Arc<TransactionPool>,
         chain_state: Arc<RwLock<ChainState>>,
+        block_rejections: Arc<RejectionTracker>,
+        sync_progress: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+        events: EventBus,
+        peer_manager: Arc<crate::network::peer_manager::PeerManager>,
+        identity_links: Arc<std::sync::Mutex<IdentityLinkRegistry>>,
+        block_server: Arc<BlockServer>,
+        mempool_sync: Arc<MempoolSync>,
+        snapshot_sync: Arc<SnapshotSync>,
+        address_book: Arc<AddressBook>,
+        operator_messages: Arc<OperatorMessenger>,
+        clock: Arc<ClockMonitor>,
     ) {
         tracing::info!("Network event processing task started");
         
         while let Some(event) = event_rx.recv().await {
             match event {
                 crate::network::NetworkEvent::NewTransaction { transaction, fee_rate, from_peer } => {
-                    let tx_hash = transaction.hash();
-                    tracing::debug!("Processing received transaction {} from peer {:?}", 
-                        hex::encode(&tx_hash[..8]), from_peer);
-                    
-                    // Check if already in mempool
-                    if mempool.get_transaction(&tx_hash).is_some() {
-                        tracing::trace!("Transaction already in mempool, ignoring");
-                        continue;
-                    }
-                    
-                    // Verify the transaction is authorized against the current
-                    // UTXO set before relaying it into the mempool (audit
-                    // Critical #1, fail-closed). Reject on lock poisoning.
-                    match chain_state.read() {
-                        Ok(chain) => {
-                            if let Err(e) = chain.verify_transaction_authorization(&transaction) {
-                                tracing::warn!(
-                                    "Rejecting unauthorized transaction {} from peer {:?}: {}",
-                                    hex::encode(&tx_hash[..8]),
-                                    from_peer,
-                                    e
-                                );
-                                continue;
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "Cannot verify transaction {} (chain lock poisoned): {}",
-                                hex::encode(&tx_hash[..8]),
-                                e
+                    // Transactions fetched by the mempool warm-up are admitted
+                    // parents first; the rest take the normal path.
+                    let mut ready = match mempool_sync.claim(transaction) {
+                        Ok(ready) => ready.into_iter().collect::<Vec<_>>(),
+                        Err(transaction) => {
+                            Self::admit_peer_transaction(
+                                &mempool,
+                                &chain_state,
+                                &events,
+                                transaction,
+                                fee_rate,
+                                from_peer.as_ref(),
                             );
                             continue;
                         }
-                    }
-
-                    // Add to mempool
-                    match mempool.add_transaction(transaction, fee_rate) {
-                        Ok(_) => {
-                            tracing::info!("Added received transaction {} to mempool", hex::encode(&tx_hash[..8]));
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to add received transaction to mempool: {}", e);
-                        }
+                    };
+                    while let Some((transaction, fee_rate)) = ready.pop() {
+                        let tx_hash = transaction.hash();
+                        let accepted = Self::admit_peer_transaction(
+                            &mempool,
+                            &chain_state,
+                            &events,
+                            transaction,
+                            fee_rate,
+                            from_peer.as_ref(),
+                        );
+                        ready.extend(mempool_sync.resolve(&tx_hash, accepted));
                     }
                 }
-                crate::network::NetworkEvent::NewBlock { block, from_peer, .. } => {
+                crate::network::NetworkEvent::PeerConnected(peer_info) => {
+                    mempool_sync.peer_connected(&peer_info);
+                    snapshot_sync.peer_connected(&peer_info);
+                    address_book.peer_connected(&peer_info).await;
+                    let _ = events.send(NodeEvent::PeerConnected {
+                        peer_id: peer_info.peer_id.to_string(),
+                        address: peer_info.addresses.first().map(|a| a.to_string()),
+                        inbound: peer_info.is_inbound,
+                    });
+                }
+                crate::network::NetworkEvent::PeerStatus { height, .. } => {
+                    sync_progress.lock().note_peer_height(height);
+                }
+                crate::network::NetworkEvent::NewBlock { block, height, from_peer, .. } => {
+                    sync_progress.lock().note_peer_height(height);
+                    // Blocks cannot connect below the snapshot being restored
+                    if snapshot_sync.bootstrapping() {
+                        tracing::trace!("Ignoring block while restoring the state snapshot");
+                        continue;
+                    }
                     let block_hash = block.hash();
+                    let peer = from_peer.as_ref().map(|p| p.to_string());
                     tracing::info!("Processing received block at height {} (hash: {}) from peer {:?}",
                         block.height(), hex::encode(&block_hash[..8]), from_peer);
                     
-                    // Check if already have this block
+                    // Check if already have this block, or know it to be invalid
                     if let Ok(chain) = chain_state.read() {
                         if chain.get_block(&block_hash).is_some() {
                             tracing::trace!("Block already in chain, ignoring");
                             continue;
                         }
+                        match chain.known_invalid(block.header()) {
+                            Ok(Some(record)) => {
+                                tracing::debug!("Ignoring known-invalid block {}", hex::encode(&block_hash[..8]));
+                                block_rejections.record(
+                                    "known-invalid",
+                                    &block_hash,
+                                    peer.as_deref(),
+                                    format!("{:?}", record.reason),
+                                );
+                                continue;
+                            }
+                            Ok(None) => {}
+                            Err(e) => tracing::warn!("Invalid-block cache lookup failed: {}", e),
+                        }
                     }
                     
                     // Validate block
                     if !block.validate() {
                         tracing::warn!("Received invalid block from peer: failed validation");
+                        block_rejections.record(
+                            "bad-block-structure",
+                            &block_hash,
+                            peer.as_deref(),
+                            "failed stateless block validation",
+                        );
                         continue;
                     }
                     
@@ -649,15 +1079,102 @@ impl Node {
                         Ok(Ok(_)) => {
                             tracing::info!("Successfully added received block {} at height {} to chain",
                                 hex::encode(&block_hash_clone[..8]), block_height);
+                            Self::remove_confirmed(&mempool, &events, &block);
+                            let _ = events.send(NodeEvent::BlockConnected(block));
                         }
                         Ok(Err(e)) => {
                             tracing::warn!("Failed to add received block to chain: {}", e);
+                            block_rejections.record(
+                                e.block_rejection_reason(),
+                                &block_hash_clone,
+                                peer.as_deref(),
+                                e.to_string(),
+                            );
                         }
                         Err(e) => {
                             tracing::error!("Task join error processing block: {}", e);
                         }
                     }
                 }
+                crate::network::NetworkEvent::PeerDisconnected(peer_id) => {
+                    block_server.forget_peer(&peer_id);
+                    clock.forget_peer(&peer_id);
+                    mempool_sync.peer_disconnected(&peer_id);
+                    snapshot_sync.peer_disconnected(&peer_id);
+                    address_book.peer_disconnected(&peer_id);
+                    let _ = events.send(NodeEvent::PeerDisconnected {
+                        peer_id: peer_id.to_string(),
+                    });
+                }
+                crate::network::NetworkEvent::MessageReceived {
+                    peer_id,
+                    message: crate::network::ProtocolMessage::Busy { retry_after_secs, reason },
+                } => {
+                    tracing::debug!(
+                        "Peer {} is busy ({}), retry after {}s",
+                        peer_id,
+                        reason,
+                        retry_after_secs
+                    );
+                }
+                crate::network::NetworkEvent::MessageReceived {
+                    peer_id,
+                    message: crate::network::ProtocolMessage::NotFound { block_hashes },
+                } => {
+                    tracing::debug!(
+                        "Peer {} has pruned {} requested blocks",
+                        peer_id,
+                        block_hashes.len()
+                    );
+                }
+                crate::network::NetworkEvent::MessageReceived {
+                    peer_id,
+                    message: crate::network::ProtocolMessage::Status { head_timestamp, .. },
+                } => {
+                    clock.record_peer_time(peer_id, head_timestamp);
+                }
+                crate::network::NetworkEvent::MessageReceived { peer_id, message } => {
+                    if snapshot_sync.handle_message(peer_id, &message).await {
+                        continue;
+                    }
+                    if mempool_sync.handle_message(peer_id, &message).await {
+                        continue;
+                    }
+                    if address_book.handle_message(peer_id, &message).await {
+                        continue;
+                    }
+                    if operator_messages.handle_message(peer_id, &message).await {
+                        continue;
+                    }
+                    let Some(decoded) = IdentityAttestation::from_message(&message) else {
+                        block_server.handle_request(peer_id, message).await;
+                        continue;
+                    };
+                    let now = chrono::Utc::now().timestamp().max(0) as u64;
+                    let applied = match decoded {
+                        Ok(attestation) => {
+                            apply_attestation(&identity_links, &peer_manager, &attestation, now)
+                                .await
+                                .map(|new_link| (attestation, new_link))
+                        }
+                        Err(e) => Err(e),
+                    };
+                    match applied {
+                        Ok((attestation, true)) => tracing::info!(
+                            "Peer {} rotated identity {} -> {} (grace until {})",
+                            peer_id,
+                            attestation.link.old_peer_id,
+                            attestation.link.new_peer_id,
+                            attestation.link.grace_until
+                        ),
+                        Ok((_, false)) => {}
+                        Err(e) => tracing::warn!(
+                            "Rejected identity rotation relayed by {}: {}",
+                            peer_id,
+                            e
+                        ),
+                    }
+                }
                 _ => {
                     // Other events handled elsewhere or not needed
                 }
@@ -667,12 +1184,162 @@ impl Node {
         tracing::info!("Network event processing task stopped");
     }
 
+    /// Verify a transaction received from a peer and add it to the mempool.
+    /// Returns whether it is now in the mempool.
+    fn admit_peer_transaction(
+        mempool: &TransactionPool,
+        chain_state: &RwLock<ChainState>,
+        events: &EventBus,
+        transaction: Transaction,
+        fee_rate: u64,
+        from_peer: Option<&PeerId>,
+    ) -> bool {
+        let tx_hash = transaction.hash();
+        let peer = from_peer.map(|p| p.to_string());
+        tracing::debug!("Processing received transaction {} from peer {:?}",
+            hex::encode(&tx_hash[..8]), from_peer);
+
+        // Check if already in mempool
+        if mempool.get_transaction(&tx_hash).is_some() {
+            tracing::trace!("Transaction already in mempool, ignoring");
+            return true;
+        }
+
+        // Verify the transaction is authorized against the current
+        // UTXO set before relaying it into the mempool (audit
+        // Critical #1, fail-closed). Reject on lock poisoning.
+        match chain_state.read() {
+            Ok(chain) => {
+                if let Err(e) = chain.verify_transaction_authorization(&transaction) {
+                    tracing::warn!(
+                        "Rejecting unauthorized transaction {} from peer {:?}: {}",
+                        hex::encode(&tx_hash[..8]),
+                        from_peer,
+                        e
+                    );
+                    mempool.rejections().record(
+                        "unauthorized",
+                        &tx_hash,
+                        peer.as_deref(),
+                        e.to_string(),
+                    );
+                    return false;
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Cannot verify transaction {} (chain lock poisoned): {}",
+                    hex::encode(&tx_hash[..8]),
+                    e
+                );
+                return false;
+            }
+        }
+
+        // Add to mempool
+        match mempool.add_transaction_from_peer(transaction.clone(), fee_rate, peer.as_deref()) {
+            Ok(_) => {
+                tracing::info!("Added received transaction {} to mempool", hex::encode(&tx_hash[..8]));
+                let _ = events.send(NodeEvent::MempoolTransaction(transaction));
+                true
+            }
+            Err(e) => {
+                tracing::warn!("Failed to add received transaction to mempool: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Gossip the attestation of a pending identity rotation every
+    /// `IDENTITY_ROTATION_ANNOUNCE_INTERVAL` while the old identity is in
+    /// its grace period. Rotations started later through the admin API are
+    /// picked up on the next tick.
+    async fn announce_identity_rotation(
+        data_dir: PathBuf,
+        command_tx: mpsc::Sender<NetworkCommand>,
+    ) {
+        let mut ticker = tokio::time::interval(IDENTITY_ROTATION_ANNOUNCE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let attestation = match identity_rotation::pending_rotation(&data_dir) {
+                Ok(Some(attestation)) => attestation,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("Failed to read pending identity rotation: {}", e);
+                    continue;
+                }
+            };
+            let now = chrono::Utc::now().timestamp().max(0) as u64;
+            if !attestation.in_grace(now) {
+                info!(
+                    "Identity rotation to {} takes effect on the next restart",
+                    attestation.link.new_peer_id
+                );
+                continue;
+            }
+            match attestation.to_message() {
+                Ok(message) => {
+                    if command_tx.send(NetworkCommand::Broadcast(message)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!("Failed to encode identity rotation: {}", e),
+            }
+        }
+    }
+
+    /// Periodically sample the chain height and bytes received into the
+    /// sync progress tracker. While behind, each published snapshot is
+    /// logged and sent on the event bus; catching up is logged once.
+    async fn report_sync_progress(
+        tracker: Arc<parking_lot::Mutex<SyncProgressTracker>>,
+        chain_state: Arc<RwLock<ChainState>>,
+        network: Arc<NetworkProxy>,
+        events: EventBus,
+    ) {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut was_synced = true;
+        loop {
+            ticker.tick().await;
+            let height = match chain_state.read() {
+                Ok(chain) => chain.get_height(),
+                Err(_) => continue,
+            };
+            let bytes_received = network.get_stats_sync().bytes_received;
+
+            let now = Instant::now();
+            let (progress, publish): (SyncProgress, bool) = {
+                let mut tracker = tracker.lock();
+                let progress = tracker.observe(now, height, bytes_received);
+                let publish = if progress.synced {
+                    !was_synced
+                } else {
+                    tracker.should_publish(now)
+                };
+                (progress, publish)
+            };
+
+            if publish {
+                info!("{}", progress.summary());
+                // No subscribers is not an error.
+                let _ = events.send(NodeEvent::SyncProgress(progress.clone()));
+            }
+            was_synced = progress.synced;
+        }
+    }
+
     /// Process a new block
     pub async fn process_block(&self, block: Block) -> Result<(), NodeError> {
         tracing::info!("Processing block at height: {}", block.header.height);
 
         // Validate block
         if !block.validate() {
+            self.block_rejections.record(
+                "bad-block-structure",
+                &block.hash(),
+                None,
+                "failed stateless block validation",
+            );
             return Err(NodeError::General("Block validation failed".to_string()));
         }
 
@@ -700,7 +1367,15 @@ impl Node {
         .await;
         add_result
             .map_err(|e| NodeError::General(format!("Task join error adding block: {}", e)))?
-            .map_err(NodeError::StorageError)?;
+            .map_err(|e| {
+                self.block_rejections.record(
+                    e.block_rejection_reason(),
+                    &block.hash(),
+                    None,
+                    e.to_string(),
+                );
+                NodeError::StorageError(e)
+            })?;
 
         // Scan block for wallet transactions (NEW: Blockchain Integration)
         if let Some(wallet_manager) = &self.wallet_manager {
@@ -715,9 +1390,7 @@ impl Node {
         }
 
         // Remove transactions from mempool
-        for tx in block.transactions() {
-            self.mempool.remove_transaction(&tx.hash());
-        }
+        Self::remove_confirmed(&self.mempool, &self.events, &block);
 
         // Store full block in database
         self.db
@@ -726,6 +1399,7 @@ impl Node {
 
         // Broadcast to network if this is a new block we mined
         self.network.broadcast_block(&block);
+        let _ = self.events.send(NodeEvent::BlockConnected(block));
 
         Ok(())
     }
@@ -906,6 +1580,7 @@ impl Node {
             cpu_usage,
             memory_usage,
             disk_usage,
+            block_serving: self.block_server.stats(),
         })
     }
 
@@ -1092,15 +1767,41 @@ impl Node {
     async fn process_lightning_events(
         manager: Arc<RwLock<LightningManager>>,
         mut event_receiver: mpsc::UnboundedReceiver<LightningEvent>,
+        mempool: Arc<TransactionPool>,
+        events: EventBus,
+        command_tx: mpsc::Sender<NetworkCommand>,
     ) {
         while let Some(event) = event_receiver.recv().await {
             match event {
                 LightningEvent::ChannelOpened(channel_id) => {
                     info!("Lightning channel opened: {}", channel_id.to_hex());
                 }
+                LightningEvent::ChannelActive(channel_id) => {
+                    info!("Lightning channel active: {}", channel_id.to_hex());
+                }
+                LightningEvent::ChannelFundingFailed(channel_id, reason) => {
+                    warn!(
+                        "Lightning channel {} funding failed: {}",
+                        channel_id.to_hex(),
+                        reason
+                    );
+                }
                 LightningEvent::ChannelClosed(channel_id) => {
                     info!("Lightning channel closed: {}", channel_id.to_hex());
                 }
+                LightningEvent::BroadcastTransaction(tx) => {
+                    if let Err(e) = mempool.add_transaction(tx.clone(), 1) {
+                        warn!("Failed to add Lightning transaction to mempool: {}", e);
+                        continue;
+                    }
+                    let _ = events.send(NodeEvent::MempoolTransaction(tx.clone()));
+                    let message = crate::network::protocol::Message::Transaction {
+                        transaction: bincode::serialize(&tx).unwrap_or_default(),
+                    };
+                    if command_tx.send(NetworkCommand::Broadcast(message)).await.is_err() {
+                        warn!("Network stopped; Lightning transaction not relayed");
+                    }
+                }
                 LightningEvent::PaymentReceived(payment_hash, amount_mnova) => {
                     info!(
                         "Lightning payment received: {} ({} mnova)",
@@ -1127,4 +1828,144 @@ impl Node {
             }
         }
     }
+
+    /// Feed chain tip changes to the Lightning manager's funding tracker
+    async fn follow_chain_for_lightning(
+        manager: Arc<RwLock<LightningManager>>,
+        mut events: tokio::sync::broadcast::Receiver<NodeEvent>,
+    ) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Lightning chain follower skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            let Ok(lightning) = manager.read() else {
+                error!("Lightning manager lock poisoned; stopping chain follower");
+                return;
+            };
+            let result = match &event {
+                NodeEvent::BlockConnected(block) => lightning.block_connected(block),
+                NodeEvent::BlockDisconnected(block) => lightning.block_disconnected(block),
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("Lightning funding tracking failed: {}", e);
+            }
+        }
+    }
+
+    /// Drop a connected block's transactions from the mempool, announcing
+    /// each one that was there
+    pub(crate) fn remove_confirmed(mempool: &TransactionPool, events: &EventBus, block: &Block) {
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            if mempool.remove_transaction(&txid).is_some() {
+                let _ = events.send(NodeEvent::MempoolRemoved {
+                    txid,
+                    reason: MempoolRemovalReason::Confirmed,
+                });
+            }
+        }
+    }
+
+    /// Evict transactions a reorg made non-final, and let the wallet release
+    /// parked transactions that became final or re-park its own evicted ones
+    async fn follow_chain_for_timelocks(
+        mempool: Arc<TransactionPool>,
+        wallet_manager: Option<Arc<RwLock<crate::wallet_manager::WalletManager>>>,
+        bus: EventBus,
+        mut events: tokio::sync::broadcast::Receiver<NodeEvent>,
+    ) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Timelock chain follower skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            match &event {
+                NodeEvent::BlockConnected(_) => {
+                    if let Some(wallet) = wallet_manager.as_ref().and_then(|w| w.read().ok()) {
+                        wallet.release_pending_final();
+                    }
+                }
+                NodeEvent::BlockDisconnected(block) => {
+                    let evicted = mempool.remove_non_final();
+                    for tx in &evicted {
+                        let _ = bus.send(NodeEvent::MempoolRemoved {
+                            txid: tx.hash(),
+                            reason: MempoolRemovalReason::NonFinal,
+                        });
+                    }
+                    if let Some(wallet) = wallet_manager.as_ref().and_then(|w| w.read().ok()) {
+                        wallet.block_disconnected(block, &evicted);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Keep the height sent in version handshakes at the tip
+    async fn follow_chain_for_handshake(
+        network: Arc<P2PNetwork>,
+        mut events: tokio::sync::broadcast::Receiver<NodeEvent>,
+    ) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            match events.recv().await {
+                Ok(NodeEvent::BlockConnected(block)) => network.set_best_height(block.height()),
+                Ok(NodeEvent::BlockDisconnected(block)) => {
+                    network.set_best_height(block.height().saturating_sub(1))
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+
+    /// Re-run the supply audit after the tip moves, at most once per
+    /// `SUPPLY_AUDIT_INTERVAL` so initial sync is not slowed down
+    async fn audit_supply(
+        db: Arc<BlockchainDB>,
+        mut events: tokio::sync::broadcast::Receiver<NodeEvent>,
+    ) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut interval = tokio::time::interval(SUPPLY_AUDIT_INTERVAL);
+        let mut tip_moved = true;
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(NodeEvent::BlockConnected(_))
+                    | Ok(NodeEvent::BlockDisconnected(_))
+                    | Err(RecvError::Lagged(_)) => tip_moved = true,
+                    Ok(_) => {}
+                    Err(RecvError::Closed) => return,
+                },
+                _ = interval.tick() => {
+                    if !std::mem::take(&mut tip_moved) {
+                        continue;
+                    }
+                    let db = Arc::clone(&db);
+                    match tokio::task::spawn_blocking(move || db.supply_audit()).await {
+                        Ok(Ok(audit)) => audit.publish(),
+                        Ok(Err(e)) => warn!("Supply audit failed: {}", e),
+                        Err(e) => warn!("Supply audit task failed: {}", e),
+                    }
+                }
+            }
+        }
+    }
 }
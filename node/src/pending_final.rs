@@ -0,0 +1,433 @@
+//! Pending-final holding area for wallet transactions
+//!
+//! The mempool refuses transactions whose lock time or relative locks aren't
+//! met yet. The wallet can park such a transaction here instead; every
+//! connected block runs [`PendingFinal::release`], which submits whatever the
+//! mempool now accepts. After a reorg that makes a released transaction
+//! non-final again, submitting it once more through [`PendingFinal::submit`]
+//! parks it until the chain catches up.
+//!
+//! Parked transactions are kept in a JSON file next to the wallet so they
+//! survive restarts.
+
+use crate::mempool::{MempoolError, TransactionPool};
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use supernova_core::types::timelock::TimeLock;
+use supernova_core::types::transaction::Transaction;
+use thiserror::Error;
+use tracing::{info, warn};
+
+#[derive(Debug, Error)]
+pub enum PendingFinalError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// A transaction waiting for its locks to be met
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParkedTransaction {
+    pub transaction: Transaction,
+    /// Fee rate to submit with, in attonovas per byte
+    pub fee_rate: u64,
+    /// What the transaction was waiting for when last checked
+    pub lock: TimeLock,
+    pub parked_at: DateTime<Utc>,
+}
+
+/// Outcome of [`PendingFinal::submit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Submission {
+    /// The mempool took the transaction; it still needs relaying
+    Accepted,
+    /// Not final yet; parked until the chain reaches `TimeLock`
+    Parked(TimeLock),
+}
+
+/// Wallet transactions parked until they are final
+pub struct PendingFinal {
+    path: Option<PathBuf>,
+    parked: RwLock<HashMap<[u8; 32], ParkedTransaction>>,
+}
+
+impl PendingFinal {
+    /// Holding area that isn't persisted
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            parked: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Open the holding area stored at `path`, starting empty if the file
+    /// doesn't exist yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PendingFinalError> {
+        let path = path.as_ref().to_path_buf();
+        let parked: Vec<ParkedTransaction> = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: Some(path),
+            parked: RwLock::new(
+                parked
+                    .into_iter()
+                    .map(|p| (p.transaction.hash(), p))
+                    .collect(),
+            ),
+        })
+    }
+
+    /// Submit `transaction` to the mempool, parking it instead if it isn't
+    /// final yet. Other rejections are returned unchanged.
+    pub fn submit(
+        &self,
+        transaction: Transaction,
+        fee_rate: u64,
+        mempool: &TransactionPool,
+    ) -> Result<Submission, MempoolError> {
+        match mempool.add_transaction(transaction.clone(), fee_rate) {
+            Ok(()) => Ok(Submission::Accepted),
+            Err(MempoolError::NonFinal {
+                min_height,
+                min_time,
+            }) => {
+                let lock = TimeLock {
+                    min_height,
+                    min_time,
+                };
+                info!(
+                    "Parked transaction {} until height {} / median time {}",
+                    hex::encode(&transaction.hash()[..8]),
+                    min_height,
+                    min_time
+                );
+                let mut parked = self.parked.write();
+                parked.insert(
+                    transaction.hash(),
+                    ParkedTransaction {
+                        transaction,
+                        fee_rate,
+                        lock,
+                        parked_at: Utc::now(),
+                    },
+                );
+                self.persist(&parked);
+                Ok(Submission::Parked(lock))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Submit every parked transaction the mempool now accepts and return
+    /// them for relaying. Transactions still not final stay parked with their
+    /// lock refreshed; any other rejection (an input spent elsewhere, say)
+    /// drops the transaction.
+    pub fn release(&self, mempool: &TransactionPool) -> Vec<Transaction> {
+        let mut parked = self.parked.write();
+        if parked.is_empty() {
+            return Vec::new();
+        }
+        let mut released = Vec::new();
+        let mut changed = false;
+
+        // A parked transaction may spend another one, so retry until a pass
+        // admits nothing new
+        loop {
+            let mut progress = false;
+            let mut waiting: Vec<[u8; 32]> = parked.keys().copied().collect();
+            waiting.sort_by_key(|txid| {
+                let lock = parked[txid].lock;
+                (lock.min_height, lock.min_time)
+            });
+            for txid in waiting {
+                let Some(entry) = parked.get_mut(&txid) else {
+                    continue;
+                };
+                // Checked first so waiting transactions don't show up in the
+                // mempool's rejection statistics every block
+                if let Some(lock) = mempool.unmet_time_lock(&entry.transaction) {
+                    if entry.lock != lock {
+                        entry.lock = lock;
+                        changed = true;
+                    }
+                    continue;
+                }
+                match mempool.add_transaction(entry.transaction.clone(), entry.fee_rate) {
+                    Ok(()) => {
+                        if let Some(entry) = parked.remove(&txid) {
+                            info!("Released parked transaction {}", hex::encode(&txid[..8]));
+                            released.push(entry.transaction);
+                        }
+                        progress = true;
+                        changed = true;
+                    }
+                    Err(MempoolError::NonFinal { .. }) => {}
+                    Err(MempoolError::TransactionExists(_)) => {
+                        parked.remove(&txid);
+                        changed = true;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Dropping parked transaction {}: {}",
+                            hex::encode(&txid[..8]),
+                            e
+                        );
+                        parked.remove(&txid);
+                        changed = true;
+                    }
+                }
+            }
+            if !progress || parked.is_empty() {
+                break;
+            }
+        }
+
+        if changed {
+            self.persist(&parked);
+        }
+        released
+    }
+
+    /// Parked transactions, earliest unlock first
+    pub fn list(&self) -> Vec<ParkedTransaction> {
+        let mut parked: Vec<ParkedTransaction> = self.parked.read().values().cloned().collect();
+        parked.sort_by_key(|p| (p.lock.min_height, p.lock.min_time));
+        parked
+    }
+
+    pub fn get(&self, txid: &[u8; 32]) -> Option<ParkedTransaction> {
+        self.parked.read().get(txid).cloned()
+    }
+
+    /// Stop waiting for a transaction
+    pub fn remove(&self, txid: &[u8; 32]) -> Option<ParkedTransaction> {
+        let mut parked = self.parked.write();
+        let removed = parked.remove(txid);
+        if removed.is_some() {
+            self.persist(&parked);
+        }
+        removed
+    }
+
+    pub fn len(&self) -> usize {
+        self.parked.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parked.read().is_empty()
+    }
+
+    /// Write the holding area via a temporary file and rename
+    fn persist(&self, parked: &HashMap<[u8; 32], ParkedTransaction>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let records: Vec<&ParkedTransaction> = parked.values().collect();
+        let result = serde_json::to_vec_pretty(&records)
+            .map_err(std::io::Error::other)
+            .and_then(|bytes| {
+                let tmp = path.with_extension("json.tmp");
+                std::fs::write(&tmp, bytes)?;
+                std::fs::rename(&tmp, path)
+            });
+        if let Err(e) = result {
+            warn!("Failed to persist parked transactions to {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mempool::{FinalityView, MempoolConfig};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+    use supernova_core::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use supernova_core::types::timelock::{FinalityContext, InputAge};
+    use supernova_core::types::transaction::{
+        SignatureSchemeType, TransactionInput, TransactionOutput,
+    };
+    use tempfile::TempDir;
+
+    const PARENT: [u8; 32] = [0x42; 32];
+
+    /// Chain whose tip and parent confirmation the test moves by hand
+    struct TestChain {
+        next_height: Mutex<u64>,
+        parent_age: Mutex<InputAge>,
+    }
+
+    impl TestChain {
+        fn at(next_height: u64) -> Arc<Self> {
+            Arc::new(Self {
+                next_height: Mutex::new(next_height),
+                parent_age: Mutex::new(InputAge::Unknown),
+            })
+        }
+
+        fn advance_to(&self, next_height: u64) {
+            *self.next_height.lock() = next_height;
+        }
+    }
+
+    impl FinalityView for TestChain {
+        fn context(&self) -> FinalityContext {
+            FinalityContext {
+                next_height: *self.next_height.lock(),
+                median_time_past: 1_700_000_000,
+            }
+        }
+
+        fn input_age(&self, txid: &[u8; 32]) -> InputAge {
+            if *txid == PARENT {
+                *self.parent_age.lock()
+            } else {
+                InputAge::Unknown
+            }
+        }
+    }
+
+    fn pool(chain: &Arc<TestChain>) -> TransactionPool {
+        let pool = TransactionPool::new(MempoolConfig::default());
+        pool.set_finality_view(chain.clone());
+        pool
+    }
+
+    fn signed(version: u32, prev: [u8; 32], sequence: u32, lock_time: u32) -> Transaction {
+        let mut tx = Transaction::new(
+            version,
+            vec![TransactionInput::new(prev, 0, vec![], sequence)],
+            vec![TransactionOutput::new(50_000_000, vec![])],
+            lock_time,
+        );
+        let params = QuantumParameters {
+            scheme: QuantumScheme::Dilithium,
+            security_level: 2,
+        };
+        let keypair = QuantumKeyPair::generate(params).unwrap();
+        tx.sign(
+            &keypair.secret_key,
+            &keypair.public_key,
+            SignatureSchemeType::Dilithium,
+            2,
+        )
+        .unwrap();
+        tx
+    }
+
+    #[test]
+    fn height_locked_transaction_is_released_at_its_height() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("wallet.pending-final.json");
+        let chain = TestChain::at(100);
+        let mempool = pool(&chain);
+        let pending = PendingFinal::open(&path).unwrap();
+
+        // Minable once the next block is above height 104
+        let tx = signed(1, [1u8; 32], 0, 104);
+        let submission = pending.submit(tx.clone(), 2000, &mempool).unwrap();
+        assert_eq!(
+            submission,
+            Submission::Parked(TimeLock {
+                min_height: 105,
+                min_time: 0
+            })
+        );
+        assert!(mempool.get_transaction(&tx.hash()).is_none());
+
+        // Survives a restart
+        drop(pending);
+        let pending = PendingFinal::open(&path).unwrap();
+        assert_eq!(pending.len(), 1);
+
+        for height in 101..105 {
+            chain.advance_to(height);
+            assert!(pending.release(&mempool).is_empty(), "released at {height}");
+        }
+        chain.advance_to(105);
+        let released = pending.release(&mempool);
+        assert_eq!(released.len(), 1);
+        assert_eq!(released[0].hash(), tx.hash());
+        assert!(mempool.get_transaction(&tx.hash()).is_some());
+        assert!(pending.is_empty());
+        assert!(PendingFinal::open(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn csv_spend_waits_for_parent_to_age() {
+        let chain = TestChain::at(100);
+        *chain.parent_age.lock() = InputAge::Confirmed {
+            height: 98,
+            median_time_past: 1_699_999_000,
+        };
+        let mempool = pool(&chain);
+        let pending = PendingFinal::in_memory();
+
+        // Spendable 10 blocks after the parent confirmed
+        let spend = signed(2, PARENT, 10, 0);
+        assert_eq!(
+            pending.submit(spend.clone(), 2000, &mempool).unwrap(),
+            Submission::Parked(TimeLock {
+                min_height: 108,
+                min_time: 0
+            })
+        );
+
+        chain.advance_to(107);
+        assert!(pending.release(&mempool).is_empty());
+        chain.advance_to(108);
+        assert_eq!(pending.release(&mempool).len(), 1);
+        assert!(mempool.get_transaction(&spend.hash()).is_some());
+    }
+
+    #[test]
+    fn reorg_orphaning_the_parent_reparks_the_spend() {
+        let chain = TestChain::at(110);
+        *chain.parent_age.lock() = InputAge::Confirmed {
+            height: 98,
+            median_time_past: 1_699_999_000,
+        };
+        let mempool = pool(&chain);
+        let pending = PendingFinal::in_memory();
+
+        let spend = signed(2, PARENT, 10, 0);
+        assert_eq!(
+            pending.submit(spend.clone(), 2000, &mempool).unwrap(),
+            Submission::Accepted
+        );
+
+        // A reorg drops the parent's block; it is unconfirmed again
+        chain.advance_to(105);
+        *chain.parent_age.lock() = InputAge::Unconfirmed;
+        let evicted = mempool.remove_non_final();
+        assert_eq!(evicted.len(), 1);
+        let resubmitted = pending.submit(evicted[0].clone(), 2000, &mempool).unwrap();
+        assert_eq!(
+            resubmitted,
+            Submission::Parked(TimeLock {
+                min_height: 115,
+                min_time: 0
+            })
+        );
+
+        // The parent confirms again at 106, so the spend waits for 116
+        *chain.parent_age.lock() = InputAge::Confirmed {
+            height: 106,
+            median_time_past: 1_699_999_500,
+        };
+        chain.advance_to(115);
+        assert!(pending.release(&mempool).is_empty());
+        assert_eq!(pending.get(&spend.hash()).unwrap().lock.min_height, 116);
+        chain.advance_to(116);
+        assert_eq!(pending.release(&mempool).len(), 1);
+        assert!(mempool.get_transaction(&spend.hash()).is_some());
+    }
+}
@@ -4,7 +4,10 @@
 //! clean node termination with proper state persistence and component coordination.
 
 use crate::node::Node;
+use futures::future::{join_all, FutureExt, LocalBoxFuture};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -41,6 +44,75 @@ pub enum ShutdownPhase {
     Complete,
 }
 
+/// Ordered stage a component registers its shutdown hook for.
+///
+/// Stages run strictly in order: no hook of a later stage starts until every
+/// hook of the previous stage has completed, failed or been cut off by the
+/// stage timeout. Hooks within one stage run concurrently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownStage {
+    /// Stop producing new work: miner, faucet, test/auto mining
+    StopProduction = 1,
+    /// Stop network intake
+    StopNetwork = 2,
+    /// Flush in-memory state: mempool, Lightning, wallet
+    FlushState = 3,
+    /// Close storage
+    CloseStorage = 4,
+}
+
+impl ShutdownStage {
+    /// All stages in execution order
+    pub const ALL: [ShutdownStage; 4] = [
+        ShutdownStage::StopProduction,
+        ShutdownStage::StopNetwork,
+        ShutdownStage::FlushState,
+        ShutdownStage::CloseStorage,
+    ];
+
+    /// 1-based stage number
+    pub fn number(self) -> u8 {
+        self as u8
+    }
+}
+
+/// How a component's shutdown hook finished
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ComponentOutcome {
+    /// Hook reported success
+    Completed,
+    /// Hook reported an error
+    Failed { error: String },
+    /// Hook was still running when its stage timed out and was cancelled
+    TimedOut { timeout_ms: u64 },
+}
+
+/// Outcome of one component's shutdown hook, recorded in the status file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentReport {
+    /// Registered component name
+    pub component: String,
+    /// Stage the component was registered for
+    pub stage: ShutdownStage,
+    /// How the hook finished
+    pub outcome: ComponentOutcome,
+    /// Time spent in the hook
+    pub duration_ms: u64,
+}
+
+impl ComponentReport {
+    /// `component: reason` for failed or timed-out hooks
+    pub fn failure(&self) -> Option<String> {
+        match &self.outcome {
+            ComponentOutcome::Completed => None,
+            ComponentOutcome::Failed { error } => Some(format!("{}: {}", self.component, error)),
+            ComponentOutcome::TimedOut { .. } => Some(format!("{}: timeout", self.component)),
+        }
+    }
+}
+
 /// Shutdown status information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShutdownStatus {
@@ -58,6 +130,28 @@ pub struct ShutdownStatus {
     pub success: bool,
     /// Error message if shutdown failed
     pub error: Option<String>,
+    /// Stage currently executing, if any
+    #[serde(default)]
+    pub stage: Option<ShutdownStage>,
+    /// Per-component outcomes, in stage order
+    #[serde(default)]
+    pub component_reports: Vec<ComponentReport>,
+}
+
+impl ShutdownStatus {
+    fn new() -> Self {
+        Self {
+            phase: ShutdownPhase::Preparing,
+            signal: String::new(),
+            started_at: 0,
+            completed_components: Vec::new(),
+            pending_components: Vec::new(),
+            success: false,
+            error: None,
+            stage: None,
+            component_reports: Vec::new(),
+        }
+    }
 }
 
 /// Configuration for graceful shutdown
@@ -73,6 +167,18 @@ pub struct ShutdownConfig {
     pub status_file_path: PathBuf,
     /// Whether to force shutdown after timeout
     pub force_after_timeout: bool,
+    /// Per-stage time limits; stages not listed use `component_timeout`
+    pub phase_timeouts: HashMap<ShutdownStage, Duration>,
+}
+
+impl ShutdownConfig {
+    /// Time limit for all hooks of `stage` together
+    pub fn stage_timeout(&self, stage: ShutdownStage) -> Duration {
+        self.phase_timeouts
+            .get(&stage)
+            .copied()
+            .unwrap_or(self.component_timeout)
+    }
 }
 
 impl Default for ShutdownConfig {
@@ -83,17 +189,123 @@ impl Default for ShutdownConfig {
             persist_state: true,
             status_file_path: PathBuf::from("./data/shutdown_status.json"),
             force_after_timeout: true,
+            phase_timeouts: HashMap::new(),
         }
     }
 }
 
-/// Component shutdown result
-#[derive(Debug)]
-struct ComponentShutdownResult {
-    component: String,
-    success: bool,
-    duration: Duration,
-    error: Option<String>,
+type ShutdownHook = Box<dyn FnOnce() -> LocalBoxFuture<'static, Result<(), String>> + Send>;
+
+struct RegisteredComponent {
+    name: String,
+    stage: ShutdownStage,
+    hook: ShutdownHook,
+}
+
+/// Shutdown hooks registered by components, grouped by stage
+#[derive(Default)]
+pub struct ShutdownRegistry {
+    components: parking_lot::Mutex<Vec<RegisteredComponent>>,
+}
+
+impl ShutdownRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `hook` to run during `stage`. The hook must report
+    /// completion or a failure; if it is still running when the stage times
+    /// out it is cancelled and recorded as timed out.
+    pub fn register<F, Fut>(&self, name: impl Into<String>, stage: ShutdownStage, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        self.components.lock().push(RegisteredComponent {
+            name: name.into(),
+            stage,
+            hook: Box::new(move || hook().boxed_local()),
+        });
+    }
+
+    /// Names and stages of hooks that have not run yet, in stage order
+    pub fn registered(&self) -> Vec<(String, ShutdownStage)> {
+        let mut registered: Vec<_> = self
+            .components
+            .lock()
+            .iter()
+            .map(|c| (c.name.clone(), c.stage))
+            .collect();
+        registered.sort_by_key(|(_, stage)| *stage);
+        registered
+    }
+
+    /// Run every hook registered for `stage` concurrently, bounded by
+    /// `stage_timeout`, and record their outcomes in `status`. Hooks are
+    /// consumed, so each runs at most once.
+    pub async fn run_stage(
+        &self,
+        stage: ShutdownStage,
+        stage_timeout: Duration,
+        status: &RwLock<ShutdownStatus>,
+    ) -> Vec<ComponentReport> {
+        let components: Vec<RegisteredComponent> = {
+            let mut registered = self.components.lock();
+            let (due, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut *registered)
+                .into_iter()
+                .partition(|c| c.stage == stage);
+            *registered = rest;
+            due
+        };
+        status.write().await.stage = Some(stage);
+
+        let deadline = tokio::time::Instant::now() + stage_timeout;
+        let runs = components.into_iter().map(|component| async move {
+            let start_time = Instant::now();
+            info!("Shutting down component: {}", component.name);
+            let outcome = match tokio::time::timeout_at(deadline, (component.hook)()).await {
+                Ok(Ok(())) => ComponentOutcome::Completed,
+                Ok(Err(error)) => ComponentOutcome::Failed { error },
+                Err(_) => ComponentOutcome::TimedOut {
+                    timeout_ms: stage_timeout.as_millis() as u64,
+                },
+            };
+            ComponentReport {
+                component: component.name,
+                stage,
+                outcome,
+                duration_ms: start_time.elapsed().as_millis() as u64,
+            }
+        });
+        let reports = join_all(runs).await;
+
+        let mut status = status.write().await;
+        for report in &reports {
+            status.pending_components.retain(|c| c != &report.component);
+            match &report.outcome {
+                ComponentOutcome::Completed => {
+                    info!(
+                        "Component '{}' shut down successfully in {}ms",
+                        report.component, report.duration_ms
+                    );
+                    status.completed_components.push(report.component.clone());
+                }
+                ComponentOutcome::Failed { error } => {
+                    error!("Component '{}' shutdown failed: {}", report.component, error);
+                }
+                ComponentOutcome::TimedOut { timeout_ms } => {
+                    warn!(
+                        "Component '{}' overran the stage {} timeout of {}ms and was cancelled",
+                        report.component,
+                        stage.number(),
+                        timeout_ms
+                    );
+                }
+            }
+        }
+        status.component_reports.extend(reports.iter().cloned());
+        reports
+    }
 }
 
 /// Graceful shutdown coordinator
@@ -106,27 +318,39 @@ pub struct ShutdownCoordinator {
     status: Arc<RwLock<ShutdownStatus>>,
     /// Shutdown signal receiver
     shutdown_requested: Arc<RwLock<bool>>,
+    /// Staged shutdown hooks
+    registry: ShutdownRegistry,
 }
 
 impl ShutdownCoordinator {
-    /// Create a new shutdown coordinator
+    /// Create a new shutdown coordinator with the node's own components
+    /// registered
     pub fn new(node: Arc<Node>, config: ShutdownConfig) -> Self {
-        let status = ShutdownStatus {
-            phase: ShutdownPhase::Preparing,
-            signal: String::new(),
-            started_at: 0,
-            completed_components: Vec::new(),
-            pending_components: Vec::new(),
-            success: false,
-            error: None,
-        };
-
-        Self {
+        let coordinator = Self {
             node,
             config,
-            status: Arc::new(RwLock::new(status)),
+            status: Arc::new(RwLock::new(ShutdownStatus::new())),
             shutdown_requested: Arc::new(RwLock::new(false)),
-        }
+            registry: ShutdownRegistry::new(),
+        };
+        coordinator.register_node_components();
+        coordinator
+    }
+
+    /// Register a component's shutdown hook for `stage`. Components living
+    /// outside the node (e.g. an embedded miner) use this to be stopped
+    /// before the storage they write to is closed.
+    pub fn register_component<F, Fut>(&self, name: impl Into<String>, stage: ShutdownStage, hook: F)
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + 'static,
+    {
+        self.registry.register(name, stage, hook);
+    }
+
+    /// Components still to be shut down, in stage order
+    pub fn registered_components(&self) -> Vec<(String, ShutdownStage)> {
+        self.registry.registered()
     }
 
     /// Check if shutdown has been requested
@@ -154,14 +378,13 @@ impl ShutdownCoordinator {
             status.signal = format!("{:?}", signal);
             status.started_at = chrono::Utc::now().timestamp();
             status.completed_components.clear();
-            status.pending_components = vec![
-                "network".to_string(),
-                "mempool".to_string(),
-                "lightning".to_string(),
-                "wallet".to_string(),
-                "database".to_string(),
-                "metrics".to_string(),
-            ];
+            status.component_reports.clear();
+            status.pending_components = self
+                .registry
+                .registered()
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
         }
 
         info!("Starting graceful shutdown (signal: {:?})", signal);
@@ -173,7 +396,7 @@ impl ShutdownCoordinator {
 
         // Perform shutdown with timeout
         let shutdown_result = timeout(self.config.max_shutdown_time, async {
-            self.shutdown_internal(signal).await
+            self.shutdown_internal().await
         })
         .await;
 
@@ -200,9 +423,13 @@ impl ShutdownCoordinator {
                 error!("Shutdown failed: {}", e);
                 {
                     let mut status = self.status.write().await;
+                    status.phase = ShutdownPhase::Complete;
                     status.success = false;
                     status.error = Some(e.clone());
                 }
+                if let Err(e) = self.save_status().await {
+                    warn!("Failed to save final shutdown status: {}", e);
+                }
                 Err(e)
             }
             Err(_) => {
@@ -217,235 +444,204 @@ impl ShutdownCoordinator {
                     self.force_shutdown().await;
                 }
 
-                {
+                // The stage in progress was cancelled with the outer
+                // timeout; name it and whoever had not finished
+                let message = {
                     let mut status = self.status.write().await;
+                    let message = match status.stage {
+                        Some(stage) => format!(
+                            "Shutdown timeout in stage {} ({:?}); unfinished: {}",
+                            stage.number(),
+                            stage,
+                            status.pending_components.join(", ")
+                        ),
+                        None => "Shutdown timeout".to_string(),
+                    };
                     status.success = false;
-                    status.error = Some("Shutdown timeout".to_string());
+                    status.error = Some(message.clone());
+                    message
+                };
+                if let Err(e) = self.save_status().await {
+                    warn!("Failed to save final shutdown status: {}", e);
                 }
 
-                Err("Shutdown timeout".to_string())
+                Err(message)
             }
         }
     }
 
-    /// Internal shutdown implementation
-    async fn shutdown_internal(&self, signal: ShutdownSignal) -> Result<(), String> {
-        // Phase 1: Stop accepting new connections
-        {
-            let mut status = self.status.write().await;
-            status.phase = ShutdownPhase::Stopping;
+    /// Register the node's own components with their shutdown stages
+    fn register_node_components(&self) {
+        // Stage 1: testnet faucet and test mining stop handing out coins and
+        // producing blocks.
+        if let Some(testnet) = self.node.testnet_manager() {
+            self.register_component("testnet", ShutdownStage::StopProduction, move || async move {
+                testnet.stop()
+            });
         }
-        info!("Phase 1: Stopping new connections");
 
-        // Stop accepting new P2P connections
-        self.shutdown_component("network_accept", || async {
-            // Network will stop accepting new connections automatically
-            // when stop() is called
-            Ok(())
-        })
-        .await?;
+        // Stage 2: stop accepting blocks, transactions and connections from
+        // peers before any state is flushed.
+        let network = self.node.network();
+        self.register_component("network", ShutdownStage::StopNetwork, move || async move {
+            network.stop().await.map_err(|e| e.to_string())
+        });
 
-        // Phase 2: Finish processing current transactions
-        info!("Phase 2: Finishing transaction processing");
-        self.shutdown_component("transaction_processing", || async {
+        // Stage 3: in-memory state.
+        self.register_component("transaction_processing", ShutdownStage::FlushState, || async {
             // Give mempool time to process pending transactions
             tokio::time::sleep(Duration::from_millis(500)).await;
             Ok(())
-        })
-        .await?;
-
-        // Phase 3: Mempool.
-        //
-        // NOTE: the mempool is deliberately NOT persisted to disk on shutdown.
-        // Unconfirmed transactions are volatile network state and are rebuilt
-        // from peers (mempool gossip) after restart, matching Bitcoin Core's
-        // behaviour. This phase is therefore an intentional no-op; it exists
-        // only to keep the shutdown sequence's phase ordering explicit. Do not
-        // log it as "flushing" — nothing is written.
-        info!("Phase 3: Mempool (in-memory, not persisted — rebuilt from peers on restart)");
-        self.shutdown_component("mempool", || async {
-            // Intentional no-op: mempool is volatile, rebuilt from peers.
+        });
+
+        // The mempool is saved so pending transactions survive a restart;
+        // `Node::new` reloads and re-validates them.
+        let mempool = self.node.mempool();
+        let mempool_path = self.node.config().read().ok().and_then(|config| {
+            config
+                .mempool
+                .persist
+                .then(|| config.storage.db_path.join(crate::mempool::pool::MEMPOOL_FILE))
+        });
+        self.register_component("mempool", ShutdownStage::FlushState, move || async move {
+            let Some(path) = mempool_path else {
+                return Ok(());
+            };
+            let saved = tokio::task::spawn_blocking(move || mempool.save_to_disk(&path))
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+                .map_err(|e| format!("Failed to save mempool: {}", e))?;
+            info!("Saved {} mempool transactions", saved);
             Ok(())
-        })
-        .await?;
+        });
+
+        let address_book = self.node.address_book();
+        self.register_component("address_book", ShutdownStage::FlushState, move || async move {
+            let saved = tokio::task::spawn_blocking(move || address_book.save())
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+                .map_err(|e| format!("Failed to save address book: {}", e))?;
+            info!("Saved {} peer addresses", saved);
+            Ok(())
+        });
 
-        // Phase 4: Lightning channels.
-        //
         // NOTE: graceful cooperative channel close is NOT performed here — it
         // depends on a LightningManager close/checkpoint API that is not yet
         // wired (see round-1 hard-stop catalogue). Channel state that matters
         // for recovery lives in the Lightning channel database and is persisted
         // independently; abandoning the connection without a cooperative close
-        // is safe (the counterparty / watchtower path handles it). This phase
+        // is safe (the counterparty / watchtower path handles it). This hook
         // currently only confirms the manager lock is reachable, so it is
         // logged as a skip rather than an asserted graceful close.
-        info!("Phase 4: Lightning channels (no graceful close — recovery via channel db)");
         let lightning_manager_opt = self.node.lightning();
-        self.shutdown_component("lightning", move || {
-            async move {
-                if let Some(lightning_manager) = lightning_manager_opt {
-                    // Use blocking task for std::sync::RwLock.
-                    tokio::task::spawn_blocking(move || {
-                        let _manager = lightning_manager.read()
-                            .map_err(|_| "Lightning manager lock poisoned".to_string())?;
-                        // Intentional no-op: no cooperative-close API wired yet;
-                        // channel recovery state is persisted in the channel db.
-                        Ok::<(), String>(())
-                    }).await.map_err(|e| format!("Task join error: {}", e))??;
-                }
-                Ok(())
+        self.register_component("lightning", ShutdownStage::FlushState, move || async move {
+            info!("Lightning channels: no graceful close — recovery via channel db");
+            if let Some(lightning_manager) = lightning_manager_opt {
+                // Use blocking task for std::sync::RwLock.
+                tokio::task::spawn_blocking(move || {
+                    let _manager = lightning_manager.read()
+                        .map_err(|_| "Lightning manager lock poisoned".to_string())?;
+                    // Intentional no-op: no cooperative-close API wired yet;
+                    // channel recovery state is persisted in the channel db.
+                    Ok::<(), String>(())
+                }).await.map_err(|e| format!("Task join error: {}", e))??;
             }
-        })
-        .await?;
-
-        // Phase 5: UTXO set.
-        //
-        // NOTE: the UTXO set is NOT saved by this phase. It is owned by
-        // ChainState and is durably flushed as part of the database flush in
-        // Phase 6, which is the authoritative durability guarantee for
-        // on-disk chain state. This phase is an intentional no-op kept for
-        // explicit phase ordering; the real work happens in Phase 6.
-        info!("Phase 5: UTXO set (no-op — durably flushed with database in Phase 6)");
-        self.shutdown_component("utxo_set", || async {
-            // Intentional no-op: UTXO set is flushed with the database (Phase 6).
             Ok(())
-        })
-        .await?;
+        });
 
-        // Phase 6: Flush all database writes
-        {
-            let mut status = self.status.write().await;
-            status.phase = ShutdownPhase::Flushing;
+        if let Some(wallet_manager) = self.node.get_wallet_manager() {
+            self.register_component("wallet", ShutdownStage::FlushState, move || async move {
+                tokio::task::spawn_blocking(move || {
+                    wallet_manager
+                        .read()
+                        .map_err(|_| "Wallet manager lock poisoned".to_string())?
+                        .flush()
+                        .map_err(|e| e.to_string())
+                })
+                .await
+                .map_err(|e| format!("Task join error: {}", e))?
+            });
         }
-        info!("Phase 6: Flushing database");
+
+        // NOTE: the UTXO set is NOT saved by this hook. It is owned by
+        // ChainState and is durably flushed as part of the database close in
+        // stage 4, which is the authoritative durability guarantee for on-disk
+        // chain state. This hook is an intentional no-op kept for explicit
+        // ordering; the real work happens when storage closes.
+        self.register_component("utxo_set", ShutdownStage::FlushState, || async {
+            // Intentional no-op: UTXO set is flushed with the database (stage 4).
+            Ok(())
+        });
+
+        // Stage 4: storage, once nothing can write to it any more.
         let db = self.node.db();
         let db_shutdown_handler = self.node.db_shutdown_handler.clone();
-        self.shutdown_component("database", move || {
-            let db_clone = Arc::clone(&db);
-            let handler_clone = db_shutdown_handler.clone();
-            async move {
-                if let Some(handler) = handler_clone {
-                    handler
-                        .shutdown()
-                        .await
-                        .map_err(|e| format!("Database shutdown failed: {}", e))?;
-                } else {
-                    // Fallback: just flush the database
-                    db_clone.flush().map_err(|e| {
-                        format!("Database flush failed: {}", e)
-                    })?;
-                }
-                Ok(())
-            }
-        })
-        .await?;
-
-        // Phase 7: Close network connections
-        info!("Phase 7: Closing network connections");
-        // Call stop directly - handle timeout manually to avoid Send issues
-        let start_time = Instant::now();
-        let stop_future = self.node.stop();
-        let timeout_future = tokio::time::sleep(self.config.component_timeout);
-        
-        tokio::select! {
-            result = stop_future => {
-                match result {
-                    Ok(()) => {
-                        info!("Network stopped successfully in {:?}", start_time.elapsed());
-                        {
-                            let mut status = self.status.write().await;
-                            if let Some(pos) = status.pending_components.iter().position(|x| x == "network") {
-                                status.pending_components.remove(pos);
-                            }
-                            status.completed_components.push("network".to_string());
-                        }
-                    }
-                    Err(e) => {
-                        error!("Network stop failed: {}", e);
-                        return Err(format!("network: {}", e));
-                    }
-                }
-            }
-            _ = timeout_future => {
-                warn!("Network stop timeout after {:?}", start_time.elapsed());
-                return Err("network: timeout".to_string());
+        self.register_component("database", ShutdownStage::CloseStorage, move || async move {
+            if let Some(handler) = db_shutdown_handler {
+                handler
+                    .shutdown()
+                    .await
+                    .map_err(|e| format!("Database shutdown failed: {}", e))?;
+            } else {
+                // Fallback: just flush the database
+                db.flush().map_err(|e| {
+                    format!("Database flush failed: {}", e)
+                })?;
             }
-        }
+            Ok(())
+        });
 
-        // Phase 8: Metrics.
-        //
         // NOTE: runtime metrics are held in-memory and are exported live over
         // the metrics endpoint; they are NOT snapshotted to disk on shutdown.
-        // This phase is an intentional no-op — do not log it as "saving".
-        info!("Phase 8: Metrics (in-memory, not persisted)");
-        self.shutdown_component("metrics", || async {
+        // This hook is an intentional no-op — do not log it as "saving".
+        self.register_component("metrics", ShutdownStage::CloseStorage, || async {
             // Intentional no-op: metrics are in-memory / scraped live.
             Ok(())
-        })
-        .await?;
-
-        Ok(())
+        });
     }
 
-    /// Shutdown a component with timeout
-    async fn shutdown_component<F, Fut>(
-        &self,
-        component_name: &str,
-        shutdown_fn: F,
-    ) -> Result<(), String>
-    where
-        F: FnOnce() -> Fut + Send,
-        Fut: std::future::Future<Output = Result<(), String>> + Send,
-    {
-        let start_time = Instant::now();
-        info!("Shutting down component: {}", component_name);
+    /// Internal shutdown implementation: run each stage to completion before
+    /// starting the next, even when a component of an earlier stage failed
+    /// or overran, so storage is still closed cleanly.
+    async fn shutdown_internal(&self) -> Result<(), String> {
+        let mut failures = Vec::new();
 
-        // Update pending components
-        {
-            let mut status = self.status.write().await;
-            if let Some(pos) = status
-                .pending_components
-                .iter()
-                .position(|x| x == component_name)
+        for stage in ShutdownStage::ALL {
             {
-                status.pending_components.remove(pos);
+                let mut status = self.status.write().await;
+                status.phase = match stage {
+                    ShutdownStage::StopProduction | ShutdownStage::StopNetwork => {
+                        ShutdownPhase::Stopping
+                    }
+                    ShutdownStage::FlushState | ShutdownStage::CloseStorage => {
+                        ShutdownPhase::Flushing
+                    }
+                };
             }
-        }
 
-        // Execute shutdown with timeout
-        let result = timeout(self.config.component_timeout, shutdown_fn()).await;
-
-        let duration = start_time.elapsed();
-        match result {
-            Ok(Ok(())) => {
-                info!("Component '{}' shut down successfully in {:?}", component_name, duration);
-                {
-                    let mut status = self.status.write().await;
-                    status.completed_components.push(component_name.to_string());
-                }
-                Ok(())
-            }
-            Ok(Err(e)) => {
-                error!("Component '{}' shutdown failed: {}", component_name, e);
-                {
-                    let mut status = self.status.write().await;
-                    status.completed_components.push(component_name.to_string());
-                    // Note: We still mark as completed to continue shutdown sequence
-                }
-                Err(format!("{}: {}", component_name, e))
-            }
-            Err(_) => {
-                warn!(
-                    "Component '{}' shutdown timeout after {:?}",
-                    component_name, duration
-                );
-                {
-                    let mut status = self.status.write().await;
-                    status.completed_components.push(component_name.to_string());
-                }
-                Err(format!("{}: timeout", component_name))
+            let stage_timeout = self.config.stage_timeout(stage);
+            info!(
+                "Shutdown stage {} ({:?}), timeout {:?}",
+                stage.number(),
+                stage,
+                stage_timeout
+            );
+            let reports = self
+                .registry
+                .run_stage(stage, stage_timeout, &self.status)
+                .await;
+            failures.extend(reports.iter().filter_map(ComponentReport::failure));
+
+            if let Err(e) = self.save_status().await {
+                warn!("Failed to save shutdown status: {}", e);
             }
         }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures.join("; "))
+        }
     }
 
     /// Force shutdown (emergency)
@@ -759,11 +955,11 @@ mod tests {
         assert!(!status.completed_components.is_empty());
     }
 
-    /// The mempool, lightning, and utxo_set phases are intentional no-ops:
-    /// none of them persists state (mempool is rebuilt from peers, the UTXO
-    /// set is flushed with the database in Phase 6, Lightning recovery state
-    /// lives in the channel db). Their log lines are worded as skips rather
-    /// than asserted flushes/closes. This test locks in that these phases
+    /// The lightning and utxo_set phases are intentional no-ops: neither
+    /// persists state (the UTXO set is flushed with the database in stage 4,
+    /// Lightning recovery state lives in the channel db), and the mempool
+    /// phase only writes `mempool.dat`. Their log lines are worded as skips
+    /// rather than asserted flushes/closes. This test locks in that these phases
     /// still run and are tracked to completion — they precede the database
     /// phase, so they complete regardless of whether later phases succeed in
     /// the test environment — so that a future change which removes or
@@ -827,5 +1023,112 @@ mod tests {
         let status = coordinator.get_status().await;
         assert!(!status.success);
     }
+
+    async fn run_all_stages(
+        registry: &ShutdownRegistry,
+        stage_timeout: Duration,
+        status: &RwLock<ShutdownStatus>,
+    ) -> Vec<ComponentReport> {
+        let mut reports = Vec::new();
+        for stage in ShutdownStage::ALL {
+            reports.extend(registry.run_stage(stage, stage_timeout, status).await);
+        }
+        reports
+    }
+
+    #[tokio::test]
+    async fn test_storage_stage_waits_for_slow_flush() {
+        let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let registry = ShutdownRegistry::new();
+
+        // Registered out of order on purpose
+        let log = Arc::clone(&events);
+        registry.register("storage", ShutdownStage::CloseStorage, move || async move {
+            log.lock().push("storage closed");
+            Ok(())
+        });
+        let log = Arc::clone(&events);
+        registry.register("lightning", ShutdownStage::FlushState, move || async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            log.lock().push("lightning flushed");
+            Ok(())
+        });
+        let log = Arc::clone(&events);
+        registry.register("miner", ShutdownStage::StopProduction, move || async move {
+            log.lock().push("miner stopped");
+            Ok(())
+        });
+
+        let status = RwLock::new(ShutdownStatus::new());
+        let reports = run_all_stages(&registry, Duration::from_secs(5), &status).await;
+
+        assert_eq!(
+            *events.lock(),
+            vec!["miner stopped", "lightning flushed", "storage closed"]
+        );
+        assert!(reports.iter().all(|r| r.outcome == ComponentOutcome::Completed));
+        let slow = reports.iter().find(|r| r.component == "lightning").unwrap();
+        assert!(slow.duration_ms >= 200);
+        assert!(registry.registered().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stage_timeout_records_overrunning_component() {
+        let registry = ShutdownRegistry::new();
+        let storage_closed = Arc::new(std::sync::atomic::AtomicBool::new(false));
+
+        registry.register("wallet", ShutdownStage::FlushState, || async { Ok(()) });
+        registry.register("stuck_flush", ShutdownStage::FlushState, || async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(())
+        });
+        registry.register("mempool", ShutdownStage::FlushState, || async {
+            Err("disk full".to_string())
+        });
+        let closed = Arc::clone(&storage_closed);
+        registry.register("storage", ShutdownStage::CloseStorage, move || async move {
+            closed.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        });
+
+        let status = RwLock::new(ShutdownStatus::new());
+        let reports = run_all_stages(&registry, Duration::from_millis(50), &status).await;
+
+        // The overrunning hook was cancelled and storage still closed after it
+        assert!(storage_closed.load(std::sync::atomic::Ordering::SeqCst));
+        let failures: Vec<String> = reports.iter().filter_map(ComponentReport::failure).collect();
+        assert_eq!(failures, vec!["stuck_flush: timeout", "mempool: disk full"]);
+
+        // The status file names the component that overran
+        let path = std::env::temp_dir().join(format!(
+            "supernova_shutdown_status_{}.json",
+            std::process::id()
+        ));
+        let status = status.read().await.clone();
+        std::fs::write(&path, serde_json::to_string_pretty(&status).unwrap()).unwrap();
+        let loaded = ShutdownCoordinator::load_status(&path).await.unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let overran = loaded
+            .component_reports
+            .iter()
+            .find(|r| matches!(r.outcome, ComponentOutcome::TimedOut { .. }))
+            .unwrap();
+        assert_eq!(overran.component, "stuck_flush");
+        assert_eq!(overran.stage, ShutdownStage::FlushState);
+        assert_eq!(overran.outcome, ComponentOutcome::TimedOut { timeout_ms: 50 });
+        assert_eq!(loaded.completed_components, vec!["wallet", "storage"]);
+        assert_eq!(loaded.stage, Some(ShutdownStage::CloseStorage));
+    }
+
+    #[test]
+    fn test_status_file_without_stage_reports_still_loads() {
+        let legacy = r#"{"phase":"Complete","signal":"User","started_at":1,
+            "completed_components":["network"],"pending_components":[],
+            "success":true,"error":null}"#;
+        let status: ShutdownStatus = serde_json::from_str(legacy).unwrap();
+        assert!(status.component_reports.is_empty());
+        assert_eq!(status.stage, None);
+    }
 }
 
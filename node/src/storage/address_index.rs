@@ -0,0 +1,700 @@
+//! Address index (`storage.address_index`)
+//!
+//! Records, per output script, the outputs paying it and the inputs
+//! spending them, so balances, unspent outputs and history can be served for
+//! any address. Scripts are keyed by their SHA-256 hash, which covers the
+//! classical templates and bare quantum commitments alike. A block's entries
+//! travel as one [`AddressIndexBatch`] in the change-set that connects or
+//! disconnects it.
+//!
+//! The index is built by the transaction index's
+//! [`TxIndexBuilder`](super::TxIndexBuilder) pass, and a batch only lands
+//! for heights that pass has already covered, so blocks enter the index in
+//! chain order even while it is being built.
+//!
+//! Keys of the `address_index` tree, all prefixed by the script hash:
+//! * history: `'h' || height || offset || kind || index`, value
+//!   `txid || amount`
+//! * unspent output: `'u' || txid || vout`, value `amount`
+//!
+//! Integers are big-endian, so a script's history iterates in chain order.
+
+use super::database::{BlockchainDB, StorageError};
+use super::reorg::ReorgChangeSet;
+use super::undo::BlockUndo;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use supernova_core::script::classify::{address_to_script, index_key, AddressError};
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::TransactionOutput;
+use utoipa::ToSchema;
+
+/// Metadata key set while the address index is on
+pub(crate) const ADDRESS_INDEX_KEY: &[u8] = b"address_index";
+
+/// Transactions per page of address history
+pub const HISTORY_PAGE_SIZE: usize = 50;
+
+const HISTORY_TAG: u8 = b'h';
+const UTXO_TAG: u8 = b'u';
+const FUNDING: u8 = 0;
+const SPENDING: u8 = 1;
+
+/// Spent outputs by outpoint
+pub type Prevouts = HashMap<([u8; 32], u32), TransactionOutput>;
+
+/// The address index writes of one block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressIndexBatch {
+    pub height: u64,
+    /// Keys with the value to store, or `None` to remove them
+    pub writes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+/// Index key of an output script
+pub fn script_hash(script: &[u8]) -> [u8; 32] {
+    Sha256::digest(script).into()
+}
+
+/// Output script an `{addr}` path segment stands for: a bech32m address,
+/// or the hex-encoded script for outputs without one
+pub fn resolve_address(address: &str) -> Result<Vec<u8>, AddressError> {
+    address_to_script(address).or_else(|e| match hex::decode(address) {
+        Ok(script) if !script.is_empty() => Ok(script),
+        _ => Err(e),
+    })
+}
+
+/// Script hash of an output worth indexing; empty and data carrier outputs
+/// never belong to an address
+fn indexed(output: &TransactionOutput) -> Option<[u8; 32]> {
+    if output.script_pubkey().is_empty() || output.is_unspendable() {
+        return None;
+    }
+    Some(script_hash(output.script_pubkey()))
+}
+
+fn history_key(script_hash: &[u8; 32], height: u64, offset: u32, kind: u8, index: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(50);
+    key.extend_from_slice(script_hash);
+    key.push(HISTORY_TAG);
+    key.extend_from_slice(&height.to_be_bytes());
+    key.extend_from_slice(&offset.to_be_bytes());
+    key.push(kind);
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+fn history_value(txid: &[u8; 32], amount: u64) -> Vec<u8> {
+    let mut value = txid.to_vec();
+    value.extend_from_slice(&amount.to_be_bytes());
+    value
+}
+
+fn utxo_key(script_hash: &[u8; 32], txid: &[u8; 32], vout: u32) -> Vec<u8> {
+    let mut key = Vec::with_capacity(69);
+    key.extend_from_slice(script_hash);
+    key.push(UTXO_TAG);
+    key.extend_from_slice(txid);
+    key.extend_from_slice(&vout.to_be_bytes());
+    key
+}
+
+fn prefix(script: &[u8], tag: u8) -> Vec<u8> {
+    let mut prefix = script_hash(script).to_vec();
+    prefix.push(tag);
+    prefix
+}
+
+/// Outputs the block spent, as recorded in its undo data
+pub fn prevouts_from_undo(undo: &BlockUndo) -> Prevouts {
+    undo.spent
+        .iter()
+        .map(|spent| ((spent.tx_hash, spent.index), spent.output.clone()))
+        .collect()
+}
+
+/// Outputs `block` spends from earlier blocks, found through the transaction
+/// index. Outpoints the index cannot place are left out.
+pub fn indexed_prevouts(db: &BlockchainDB, block: &Block) -> Result<Prevouts, StorageError> {
+    let mut prevouts = Prevouts::new();
+    let mut parents: HashMap<[u8; 32], Option<Block>> = HashMap::new();
+    for tx in block.transactions().iter().filter(|tx| !tx.is_coinbase()) {
+        for input in tx.inputs() {
+            let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+            let Some(location) = db.get_tx_location(&outpoint.0)? else {
+                continue;
+            };
+            let parent = match parents.entry(location.block_hash) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(db.get_block(&location.block_hash)?),
+            };
+            let output = parent
+                .as_ref()
+                .and_then(|parent| parent.transactions().get(location.offset as usize))
+                .and_then(|parent_tx| parent_tx.outputs().get(outpoint.1 as usize));
+            if let Some(output) = output {
+                prevouts.insert(outpoint, output.clone());
+            }
+        }
+    }
+    Ok(prevouts)
+}
+
+/// Outputs created in `block`, for spends within the block itself
+fn created_outputs(block: &Block) -> HashMap<([u8; 32], u32), &TransactionOutput> {
+    let mut created = HashMap::new();
+    for tx in block.transactions() {
+        let txid = tx.hash();
+        for (vout, output) in tx.outputs().iter().enumerate() {
+            created.insert((txid, vout as u32), output);
+        }
+    }
+    created
+}
+
+/// Stage the address index entries of a block being connected. `prevouts`
+/// holds the outputs it spends from earlier blocks.
+pub fn plan_index_block(block: &Block, prevouts: &Prevouts, changes: &mut ReorgChangeSet) {
+    let height = block.height();
+    let created = created_outputs(block);
+    let mut batch = AddressIndexBatch {
+        height,
+        writes: Vec::new(),
+    };
+    for (offset, tx) in block.transactions().iter().enumerate() {
+        let offset = offset as u32;
+        let txid = tx.hash();
+        if !tx.is_coinbase() {
+            for (index, input) in tx.inputs().iter().enumerate() {
+                let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+                let Some(prevout) = created.get(&outpoint).copied().or(prevouts.get(&outpoint))
+                else {
+                    continue;
+                };
+                let Some(hash) = indexed(prevout) else {
+                    continue;
+                };
+                batch.writes.push((
+                    history_key(&hash, height, offset, SPENDING, index as u32),
+                    Some(history_value(&txid, prevout.amount())),
+                ));
+                batch
+                    .writes
+                    .push((utxo_key(&hash, &outpoint.0, outpoint.1), None));
+            }
+        }
+        for (vout, output) in tx.outputs().iter().enumerate() {
+            let Some(hash) = indexed(output) else {
+                continue;
+            };
+            batch.writes.push((
+                history_key(&hash, height, offset, FUNDING, vout as u32),
+                Some(history_value(&txid, output.amount())),
+            ));
+            batch.writes.push((
+                utxo_key(&hash, &txid, vout as u32),
+                Some(output.amount().to_be_bytes().to_vec()),
+            ));
+        }
+    }
+    changes.address_index(batch);
+}
+
+/// Stage the removal of a disconnected block's address index entries,
+/// restoring the unspent outputs it had spent
+pub fn plan_unindex_block(block: &Block, prevouts: &Prevouts, changes: &mut ReorgChangeSet) {
+    let height = block.height();
+    let created = created_outputs(block);
+    let mut batch = AddressIndexBatch {
+        height,
+        writes: Vec::new(),
+    };
+    // Newest first, so an output created and spent in the block nets out
+    for (offset, tx) in block.transactions().iter().enumerate().rev() {
+        let offset = offset as u32;
+        let txid = tx.hash();
+        for (vout, output) in tx.outputs().iter().enumerate() {
+            if let Some(hash) = indexed(output) {
+                batch.writes.push((
+                    history_key(&hash, height, offset, FUNDING, vout as u32),
+                    None,
+                ));
+                batch
+                    .writes
+                    .push((utxo_key(&hash, &txid, vout as u32), None));
+            }
+        }
+        if tx.is_coinbase() {
+            continue;
+        }
+        for (index, input) in tx.inputs().iter().enumerate() {
+            let outpoint = (input.prev_tx_hash(), input.prev_output_index());
+            let Some(prevout) = created.get(&outpoint).copied().or(prevouts.get(&outpoint)) else {
+                continue;
+            };
+            if let Some(hash) = indexed(prevout) {
+                batch.writes.push((
+                    history_key(&hash, height, offset, SPENDING, index as u32),
+                    None,
+                ));
+                batch.writes.push((
+                    utxo_key(&hash, &outpoint.0, outpoint.1),
+                    Some(prevout.amount().to_be_bytes().to_vec()),
+                ));
+            }
+        }
+    }
+    changes.address_index(batch);
+}
+
+fn corrupt(key: &[u8]) -> StorageError {
+    StorageError::DatabaseError(format!("Corrupt address index entry {}", hex::encode(key)))
+}
+
+fn read_u64(bytes: &[u8]) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.try_into().ok()?))
+}
+
+/// One history entry: where it sits in the chain, its transaction, and the
+/// amount it paid to (funding) or took from (spending) the script
+struct HistoryEntry {
+    height: u64,
+    offset: u32,
+    spending: bool,
+    txid: [u8; 32],
+    amount: u64,
+}
+
+fn decode_history(key: &[u8], value: &[u8]) -> Result<HistoryEntry, StorageError> {
+    if key.len() != 50 || value.len() != 40 {
+        return Err(corrupt(key));
+    }
+    let mut txid = [0u8; 32];
+    txid.copy_from_slice(&value[..32]);
+    Ok(HistoryEntry {
+        height: read_u64(&key[33..41]).ok_or_else(|| corrupt(key))?,
+        offset: u32::from_be_bytes(key[41..45].try_into().map_err(|_| corrupt(key))?),
+        spending: key[45] == SPENDING,
+        txid,
+        amount: read_u64(&value[32..]).ok_or_else(|| corrupt(key))?,
+    })
+}
+
+/// An unspent output paying an address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AddressUtxo {
+    pub txid: String,
+    pub vout: u32,
+    /// Amount in novas
+    pub value: u64,
+    /// Height of the block holding the output
+    pub height: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AddressUtxos {
+    pub address: String,
+    pub utxos: Vec<AddressUtxo>,
+}
+
+/// Confirmed balance and totals of an address, in novas
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AddressBalance {
+    pub address: String,
+    /// Sum of the unspent outputs
+    pub balance: u64,
+    pub utxo_count: u64,
+    pub total_received: u64,
+    pub total_sent: u64,
+    /// Transactions paying or spending from the address
+    pub tx_count: u64,
+}
+
+/// A transaction touching an address, with its effect on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AddressTransaction {
+    pub txid: String,
+    pub height: u64,
+    /// Paid to the address, in novas
+    pub received: u64,
+    /// Spent from the address, in novas
+    pub sent: u64,
+}
+
+/// One page of an address's transactions, newest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct AddressHistory {
+    pub address: String,
+    pub page: usize,
+    pub page_size: usize,
+    /// Transactions across all pages
+    pub total: usize,
+    pub transactions: Vec<AddressTransaction>,
+}
+
+fn display_address(script: &[u8]) -> String {
+    index_key(script).unwrap_or_default()
+}
+
+/// Unspent outputs paying `script`
+pub fn utxos(db: &BlockchainDB, script: &[u8]) -> Result<AddressUtxos, StorageError> {
+    let mut heights: HashMap<[u8; 32], Option<u64>> = HashMap::new();
+    let mut utxos = Vec::new();
+    for item in db.scan_address_index(&prefix(script, UTXO_TAG)) {
+        let (key, value) = item?;
+        if key.len() != 69 {
+            return Err(corrupt(&key));
+        }
+        let mut txid = [0u8; 32];
+        txid.copy_from_slice(&key[33..65]);
+        let height = match heights.get(&txid) {
+            Some(height) => *height,
+            None => {
+                let height = match db.get_tx_location(&txid)? {
+                    Some(location) => db
+                        .get_stored_header(&location.block_hash)?
+                        .map(|header| header.height()),
+                    None => None,
+                };
+                heights.insert(txid, height);
+                height
+            }
+        };
+        utxos.push(AddressUtxo {
+            txid: hex::encode(txid),
+            vout: u32::from_be_bytes(key[65..].try_into().map_err(|_| corrupt(&key))?),
+            value: read_u64(&value).ok_or_else(|| corrupt(&key))?,
+            height,
+        });
+    }
+    Ok(AddressUtxos {
+        address: display_address(script),
+        utxos,
+    })
+}
+
+/// Confirmed balance and lifetime totals of `script`
+pub fn balance(db: &BlockchainDB, script: &[u8]) -> Result<AddressBalance, StorageError> {
+    let mut balance = AddressBalance {
+        address: display_address(script),
+        balance: 0,
+        utxo_count: 0,
+        total_received: 0,
+        total_sent: 0,
+        tx_count: 0,
+    };
+    for item in db.scan_address_index(&prefix(script, UTXO_TAG)) {
+        let (key, value) = item?;
+        balance.balance += read_u64(&value).ok_or_else(|| corrupt(&key))?;
+        balance.utxo_count += 1;
+    }
+    let mut last = None;
+    for item in db.scan_address_index(&prefix(script, HISTORY_TAG)) {
+        let (key, value) = item?;
+        let entry = decode_history(&key, &value)?;
+        if entry.spending {
+            balance.total_sent += entry.amount;
+        } else {
+            balance.total_received += entry.amount;
+        }
+        if last != Some((entry.height, entry.offset)) {
+            last = Some((entry.height, entry.offset));
+            balance.tx_count += 1;
+        }
+    }
+    Ok(balance)
+}
+
+/// Page `page` (from 0) of the transactions touching `script`, newest first
+pub fn history(
+    db: &BlockchainDB,
+    script: &[u8],
+    page: usize,
+    page_size: usize,
+) -> Result<AddressHistory, StorageError> {
+    let first = page.saturating_mul(page_size);
+    let mut transactions: Vec<AddressTransaction> = Vec::new();
+    let mut total = 0;
+    let mut last = None;
+    for item in db.scan_address_index(&prefix(script, HISTORY_TAG)).rev() {
+        let (key, value) = item?;
+        let entry = decode_history(&key, &value)?;
+        if last != Some((entry.height, entry.offset)) {
+            last = Some((entry.height, entry.offset));
+            total += 1;
+            if total > first && transactions.len() < page_size {
+                transactions.push(AddressTransaction {
+                    txid: hex::encode(entry.txid),
+                    height: entry.height,
+                    received: 0,
+                    sent: 0,
+                });
+            } else {
+                continue;
+            }
+        } else if total <= first || total > first + page_size {
+            continue;
+        }
+        if let Some(transaction) = transactions.last_mut() {
+            if entry.spending {
+                transaction.sent += entry.amount;
+            } else {
+                transaction.received += entry.amount;
+            }
+        }
+    }
+    Ok(AddressHistory {
+        address: display_address(script),
+        page,
+        page_size,
+        total,
+        transactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::txindex::{self, TxIndexBuilder};
+    use crate::storage::undo;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use supernova_core::types::transaction::{Transaction, TransactionInput};
+    use tempfile::tempdir;
+
+    const QUANTUM: [u8; 32] = [7u8; 32];
+
+    fn classic() -> Vec<u8> {
+        let mut script = vec![0x76, 0xa9, 0x14];
+        script.extend_from_slice(&[9u8; 20]);
+        script.extend_from_slice(&[0x88, 0xac]);
+        script
+    }
+
+    fn coinbase(height: u64, outputs: Vec<TransactionOutput>) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(
+                height.to_le_bytes().to_vec(),
+            )],
+            outputs,
+            0,
+        )
+    }
+
+    fn spend(prev: &Transaction, vout: u32, outputs: Vec<TransactionOutput>) -> Transaction {
+        Transaction::new(
+            1,
+            vec![TransactionInput::new(
+                prev.hash(),
+                vout,
+                Vec::new(),
+                u32::MAX,
+            )],
+            outputs,
+            0,
+        )
+    }
+
+    fn block(prev: Option<&Block>, height: u64, transactions: Vec<Transaction>) -> Block {
+        let prev = prev.map(|block| block.hash()).unwrap_or([0u8; 32]);
+        let mut block = Block::new_with_params(1, prev, transactions, 0x207f_ffff);
+        block.set_height(height);
+        block
+    }
+
+    fn connect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let mut changes = ReorgChangeSet::new();
+        changes.put_block(block.hash(), bincode::serialize(block)?);
+        let undo = undo::plan_connect(db, block, &mut changes)?;
+        txindex::plan_index_block(block, &mut changes);
+        plan_index_block(block, &prevouts_from_undo(&undo), &mut changes);
+        changes.put_height_index(block.height(), block.hash());
+        changes.put_meta(b"height".to_vec(), block.height().to_be_bytes().to_vec());
+        changes.put_meta(b"best_hash".to_vec(), block.hash().to_vec());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    fn disconnect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let block_undo = db.get_undo(&block.hash())?.expect("undo record");
+        let mut changes = ReorgChangeSet::new();
+        undo::plan_disconnect(block.hash(), &block_undo, &mut changes)?;
+        txindex::plan_unindex_block(block, &mut changes);
+        plan_unindex_block(block, &prevouts_from_undo(&block_undo), &mut changes);
+        changes.del_height_index(block.height());
+        changes.put_meta(
+            b"height".to_vec(),
+            (block.height() - 1).to_be_bytes().to_vec(),
+        );
+        changes.put_meta(b"best_hash".to_vec(), block.prev_block_hash().to_vec());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    fn build(db: &Arc<BlockchainDB>) -> Result<(), StorageError> {
+        let builder = TxIndexBuilder::new(Arc::clone(db));
+        while builder.step()? {}
+        Ok(())
+    }
+
+    fn indexed_db(dir: &std::path::Path) -> Result<Arc<BlockchainDB>, StorageError> {
+        let db = Arc::new(BlockchainDB::new(dir)?);
+        db.set_tx_index(true)?;
+        db.set_address_index(true)?;
+        Ok(db)
+    }
+
+    #[test]
+    fn tracks_classical_and_quantum_scripts_across_reorgs() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let db = indexed_db(dir.path())?;
+        let classic = classic();
+
+        let cb0 = coinbase(0, vec![TransactionOutput::new(50, QUANTUM.to_vec())]);
+        let b0 = block(None, 0, vec![cb0.clone()]);
+        connect(&db, &b0)?;
+        // Genesis predates the rebuild, which then covers the chain
+        build(&db)?;
+
+        let cb1 = coinbase(1, vec![TransactionOutput::new(50, classic.clone())]);
+        let a = spend(
+            &cb0,
+            0,
+            vec![
+                TransactionOutput::new(30, classic.clone()),
+                TransactionOutput::new(20, QUANTUM.to_vec()),
+            ],
+        );
+        // Spends an output of the same block
+        let b = spend(
+            &a,
+            1,
+            vec![
+                TransactionOutput::new(15, classic.clone()),
+                TransactionOutput::data_carrier(b"note"),
+            ],
+        );
+        let b1 = block(Some(&b0), 1, vec![cb1.clone(), a.clone(), b.clone()]);
+        connect(&db, &b1)?;
+
+        let cb2 = coinbase(2, vec![TransactionOutput::new(50, QUANTUM.to_vec())]);
+        let c = spend(&cb1, 0, vec![TransactionOutput::new(50, QUANTUM.to_vec())]);
+        let b2 = block(Some(&b1), 2, vec![cb2.clone(), c.clone()]);
+        connect(&db, &b2)?;
+
+        let quantum = balance(&db, &QUANTUM)?;
+        assert!(quantum
+            .address
+            .starts_with(supernova_core::script::classify::address_hrp()));
+        assert_eq!(resolve_address(&quantum.address).unwrap(), QUANTUM.to_vec());
+        assert_eq!(
+            (
+                quantum.balance,
+                quantum.utxo_count,
+                quantum.total_received,
+                quantum.total_sent
+            ),
+            (100, 2, 170, 70)
+        );
+        assert_eq!(quantum.tx_count, 5);
+
+        let classical = balance(&db, &classic)?;
+        assert_eq!(resolve_address(&hex::encode(&classic)).unwrap(), classic);
+        assert_eq!(
+            (
+                classical.balance,
+                classical.total_received,
+                classical.total_sent
+            ),
+            (45, 95, 50)
+        );
+        let unspent = utxos(&db, &classic)?.utxos;
+        assert_eq!(unspent.len(), 2);
+        assert!(unspent.iter().all(|utxo| utxo.height == Some(1)));
+
+        // Newest first, paged
+        let page = history(&db, &QUANTUM, 0, 2)?;
+        assert_eq!(page.total, 5);
+        let txids: Vec<_> = page.transactions.iter().map(|tx| tx.txid.clone()).collect();
+        assert_eq!(txids, vec![hex::encode(c.hash()), hex::encode(cb2.hash())]);
+        let last = history(&db, &QUANTUM, 2, 2)?;
+        assert_eq!(last.transactions.len(), 1);
+        assert_eq!(last.transactions[0].txid, hex::encode(cb0.hash()));
+        let middle = history(&db, &QUANTUM, 1, 2)?.transactions;
+        assert_eq!((middle[0].received, middle[0].sent), (0, 20));
+        assert_eq!((middle[1].received, middle[1].sent), (20, 50));
+
+        // Disconnecting block 2 gives the coinbase of block 1 back
+        disconnect(&db, &b2)?;
+        assert_eq!(balance(&db, &QUANTUM)?.balance, 0);
+        assert_eq!(balance(&db, &QUANTUM)?.tx_count, 3);
+        assert_eq!(balance(&db, &classic)?.balance, 95);
+        connect(&db, &b2)?;
+
+        // A rebuild from scratch gives the same index
+        db.set_address_index(false)?;
+        db.set_address_index(true)?;
+        assert_eq!(balance(&db, &QUANTUM)?.tx_count, 0);
+        build(&db)?;
+        assert_eq!(balance(&db, &QUANTUM)?, quantum);
+        assert_eq!(balance(&db, &classic)?, classical);
+        Ok(())
+    }
+
+    #[test]
+    fn indexes_a_large_block_in_bounded_time() -> Result<(), StorageError> {
+        const TXS: usize = 2_000;
+        let dir = tempdir().unwrap();
+        let db = indexed_db(dir.path())?;
+
+        let funding = coinbase(
+            0,
+            (0..TXS - 1)
+                .map(|i| {
+                    let script = if i % 2 == 0 {
+                        QUANTUM.to_vec()
+                    } else {
+                        classic()
+                    };
+                    TransactionOutput::new(1_000, script)
+                })
+                .collect(),
+        );
+        let b0 = block(None, 0, vec![funding.clone()]);
+        connect(&db, &b0)?;
+        build(&db)?;
+
+        let mut transactions = vec![coinbase(1, vec![TransactionOutput::new(50, classic())])];
+        for vout in 0..TXS as u32 - 1 {
+            let mut payee = QUANTUM;
+            payee[..4].copy_from_slice(&vout.to_be_bytes());
+            transactions.push(spend(
+                &funding,
+                vout,
+                vec![
+                    TransactionOutput::new(600, payee.to_vec()),
+                    TransactionOutput::new(400, classic()),
+                ],
+            ));
+        }
+        let b1 = block(Some(&b0), 1, transactions);
+
+        let started = Instant::now();
+        connect(&db, &b1)?;
+        // Generous enough for unoptimised test builds
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "connecting a {}-transaction block took {:?}",
+            TXS,
+            started.elapsed()
+        );
+
+        assert_eq!(balance(&db, &QUANTUM)?.balance, 0);
+        let change = balance(&db, &classic())?;
+        assert_eq!(change.balance, 50 + 400 * (TXS as u64 - 1));
+        assert_eq!(change.tx_count, TXS as u64 + 1);
+        Ok(())
+    }
+}
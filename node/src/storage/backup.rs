@@ -1,4 +1,6 @@
 use super::database::{BlockchainDB, StorageError};
+use super::journal::TipIntent;
+use super::reorg::ReorgChangeSet;
 use crate::metrics::BackupMetrics;
 use supernova_core::storage::chain_state::ChainState;
 use futures::future::join_all;
@@ -84,6 +86,23 @@ pub struct RecoveryCheckpoint {
     pub timestamp: u64,
 }
 
+/// Outcome of [`RecoveryManager::check_consistency`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsistencyReport {
+    /// Tip change whose commit was interrupted, if the journal held one
+    pub interrupted: Option<TipIntent>,
+    /// Divergences found and repaired
+    pub repaired: Vec<String>,
+    /// Divergences found but left in place
+    pub unresolved: Vec<String>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.repaired.is_empty() && self.unresolved.is_empty()
+    }
+}
+
 pub struct RecoveryManager {
     db: Arc<BlockchainDB>,
     backup_dir: PathBuf,
@@ -328,7 +347,123 @@ impl RecoveryManager {
         }
     }
 
+    /// Check that the tip metadata, the height index and the stored blocks
+    /// agree, repairing what a torn write left behind. Runs on startup,
+    /// before the chain state is loaded.
+    ///
+    /// A tip change left in the journal is resolved first: tip commits are
+    /// atomic, so the tip sits at either end of the change. A tip naming a
+    /// missing block, or one the height index disagrees with, is moved back
+    /// to the highest indexed block that is stored, and index entries above
+    /// the tip are dropped.
+    pub fn check_consistency(db: &BlockchainDB) -> Result<ConsistencyReport, StorageError> {
+        let mut report = ConsistencyReport::default();
+        let height = db.get_height()?;
+        let tip = db.get_tip_hash()?;
+
+        if let Some(journal) = db.tip_journal() {
+            match journal.pending() {
+                Ok(None) => {}
+                Ok(Some(intent)) => {
+                    let at = |h: u64, hash: [u8; 32]| height == h && tip == Some(hash);
+                    if at(intent.to_height, intent.to_hash) {
+                        info!(
+                            "Tip change to height {} was committed before shutdown",
+                            intent.to_height
+                        );
+                    } else if at(intent.from_height, intent.from_hash) {
+                        warn!(
+                            "Tip change to height {} was interrupted, staying at height {}",
+                            intent.to_height, height
+                        );
+                    } else {
+                        report.unresolved.push(format!(
+                            "tip at height {} matches neither end of the interrupted change from height {} to {}",
+                            height, intent.from_height, intent.to_height
+                        ));
+                    }
+                    report.interrupted = Some(intent);
+                }
+                Err(e) => report
+                    .repaired
+                    .push(format!("discarded an unreadable tip journal: {}", e)),
+            }
+        }
+
+        // An empty database, or one from before the height index, is left
+        // to genesis initialization and the height index backfill
+        if let (Some(tip), Some(top)) = (tip, db.max_indexed_height()?) {
+            let tip_ok = db.get_block_hash_by_height(height)? == Some(tip)
+                && db
+                    .get_stored_header(&tip)?
+                    .is_some_and(|header| header.height() == height);
+            let mut changes = ReorgChangeSet::new();
+            let mut repaired_tip = tip_ok.then_some((height, tip));
+            let mut floor = height;
+            if !tip_ok {
+                floor = 0;
+                for h in (0..=top).rev() {
+                    let Some(hash) = db.get_block_hash_by_height(h)? else {
+                        continue;
+                    };
+                    let stored = db
+                        .get_stored_header(&hash)?
+                        .is_some_and(|header| header.height() == h);
+                    if stored {
+                        repaired_tip = Some((h, hash));
+                        floor = h;
+                        break;
+                    }
+                }
+            }
+            for h in floor + 1..=top {
+                changes.del_height_index(h);
+            }
+
+            match repaired_tip {
+                Some((new_height, new_tip)) if !tip_ok || top > height => {
+                    if !tip_ok {
+                        report.repaired.push(format!(
+                            "moved the tip from height {} ({}) to the last stored best-chain block at height {} ({})",
+                            height,
+                            hex::encode(tip),
+                            new_height,
+                            hex::encode(new_tip)
+                        ));
+                    }
+                    if top > new_height {
+                        report.repaired.push(format!(
+                            "dropped height index entries {} to {} above the tip",
+                            new_height + 1,
+                            top
+                        ));
+                    }
+                    db.apply_tip_change(changes, new_height, new_tip)?;
+                }
+                Some(_) => {}
+                None => report.unresolved.push(format!(
+                    "tip {} at height {} is not stored and no indexed block is",
+                    hex::encode(tip),
+                    height
+                )),
+            }
+        }
+
+        for repair in &report.repaired {
+            warn!("Repaired database: {}", repair);
+        }
+        for problem in &report.unresolved {
+            error!("Database inconsistency: {}", problem);
+        }
+        if let Some(journal) = db.tip_journal() {
+            journal.complete()?;
+        }
+        Ok(report)
+    }
+
     pub async fn verify_and_recover(&mut self) -> Result<(), StorageError> {
+        Self::check_consistency(&self.db)?;
+
         info!("Starting database integrity verification");
 
         let verification = self.metrics.record_verification_start();
@@ -889,8 +1024,12 @@ async fn verify_blockchain_range(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::reorg::ReorgOp;
+    use crate::storage::undo;
     use supernova_core::storage::chain_state::ChainStateConfig;
     use supernova_core::storage::utxo_set::UtxoSet;
+    use supernova_core::types::block::Block;
+    use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
     use tempfile::tempdir;
 
     #[tokio::test]
@@ -1081,4 +1220,139 @@ mod tests {
 
         Ok(())
     }
+
+    /// Coinbase-only blocks linked from genesis
+    fn chain(length: u64) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for height in 0..=length {
+            let coinbase = Transaction::new(
+                1,
+                vec![TransactionInput::new_coinbase(
+                    height.to_le_bytes().to_vec(),
+                )],
+                vec![TransactionOutput::new(50, vec![1])],
+                0,
+            );
+            let prev = blocks.last().map(|block| block.hash()).unwrap_or([0u8; 32]);
+            let mut block = Block::new_with_params(1, prev, vec![coinbase], 0x207f_ffff);
+            block.set_height(height);
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn connect_changes(db: &BlockchainDB, block: &Block) -> Result<ReorgChangeSet, StorageError> {
+        let mut changes = ReorgChangeSet::new();
+        changes.put_block(block.hash(), bincode::serialize(block)?);
+        undo::plan_connect(db, block, &mut changes)?;
+        changes.put_height_index(block.height(), block.hash());
+        Ok(changes)
+    }
+
+    fn connect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        db.apply_tip_change(connect_changes(db, block)?, block.height(), block.hash())
+    }
+
+    fn coinbase_utxo(db: &BlockchainDB, block: &Block) -> Result<bool, StorageError> {
+        Ok(db.get_utxo(&block.transactions()[0].hash(), 0)?.is_some())
+    }
+
+    #[test]
+    fn crash_during_commit_leaves_the_previous_tip() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let blocks = chain(3);
+        {
+            let db = BlockchainDB::new(dir.path())?;
+            for block in &blocks[..=2] {
+                connect(&db, block)?;
+            }
+            assert_eq!(db.tip_journal().unwrap().pending()?, None);
+
+            // The node dies with the block, its outputs and the tip staged
+            let mut changes = connect_changes(&db, &blocks[3])?;
+            changes.ops.push(ReorgOp::PanicForTest);
+            let crashed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                db.apply_tip_change(changes, 3, blocks[3].hash())
+            }));
+            assert!(crashed.is_err());
+        }
+
+        let db = BlockchainDB::new(dir.path())?;
+        let report = RecoveryManager::check_consistency(&db)?;
+        assert!(report.is_consistent());
+        let interrupted = report.interrupted.unwrap();
+        assert_eq!((interrupted.from_height, interrupted.to_height), (2, 3));
+        assert_eq!(db.get_height()?, 2);
+        assert_eq!(db.get_tip_hash()?, Some(blocks[2].hash()));
+        assert!(db.get_block(&blocks[3].hash())?.is_none());
+        assert!(coinbase_utxo(&db, &blocks[2])?);
+        assert!(!coinbase_utxo(&db, &blocks[3])?);
+        assert_eq!(db.tip_journal().unwrap().pending()?, None);
+
+        // The chain carries on from there
+        connect(&db, &blocks[3])?;
+        assert_eq!(db.get_height()?, 3);
+        assert!(coinbase_utxo(&db, &blocks[3])?);
+        Ok(())
+    }
+
+    #[test]
+    fn crash_after_commit_keeps_the_new_tip() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let blocks = chain(2);
+        {
+            let db = BlockchainDB::new(dir.path())?;
+            for block in &blocks {
+                connect(&db, block)?;
+            }
+            // The commit landed but the node died before clearing the record
+            db.tip_journal().unwrap().begin(TipIntent {
+                from_height: 1,
+                from_hash: blocks[1].hash(),
+                to_height: 2,
+                to_hash: blocks[2].hash(),
+            })?;
+        }
+
+        let db = BlockchainDB::new(dir.path())?;
+        let report = RecoveryManager::check_consistency(&db)?;
+        assert!(report.is_consistent());
+        assert!(report.interrupted.is_some());
+        assert_eq!(db.get_height()?, 2);
+        assert_eq!(db.get_tip_hash()?, Some(blocks[2].hash()));
+        assert!(coinbase_utxo(&db, &blocks[2])?);
+        assert_eq!(db.tip_journal().unwrap().pending()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn torn_tip_write_is_repaired() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let blocks = chain(3);
+        {
+            let db = BlockchainDB::new(dir.path())?;
+            for block in &blocks[..=2] {
+                connect(&db, block)?;
+            }
+            // Separate writes cut short: the tip and its index entry point at
+            // a block that never reached the disk
+            db.store_block_height_index(3, &blocks[3].hash())?;
+            db.set_metadata(b"height", &3u64.to_be_bytes())?;
+            db.set_metadata(b"best_hash", &blocks[3].hash())?;
+            db.flush()?;
+        }
+
+        let db = BlockchainDB::new(dir.path())?;
+        let report = RecoveryManager::check_consistency(&db)?;
+        assert_eq!(report.repaired.len(), 2);
+        assert!(report.unresolved.is_empty());
+        assert_eq!(db.get_height()?, 2);
+        assert_eq!(db.get_tip_hash()?, Some(blocks[2].hash()));
+        assert_eq!(db.max_indexed_height()?, Some(2));
+        assert!(coinbase_utxo(&db, &blocks[2])?);
+
+        // Nothing is left to repair on the next start
+        assert!(RecoveryManager::check_consistency(&db)?.is_consistent());
+        Ok(())
+    }
 }
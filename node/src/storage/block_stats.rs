@@ -0,0 +1,172 @@
+//! Per-block statistics
+//!
+//! A compact record is written for every block connected to the best chain
+//! and kept in its own tree keyed by height. Chart and fee endpoints read
+//! ranges of these records instead of deserializing full blocks. Heights
+//! without a record (e.g. blocks connected before this tree existed, or
+//! pruned without stats) are simply absent and surface as gaps.
+
+use super::database::{BlockchainDB, StorageError};
+use serde::{Deserialize, Serialize};
+use supernova_core::blockchain::calculate_difficulty_from_bits;
+use supernova_core::types::block::Block;
+
+/// Tree holding [`BlockStatsRecord`]s keyed by big-endian height
+const BLOCK_STATS_TREE: &str = "block_stats";
+
+/// Statistics for a single block on the best chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockStatsRecord {
+    pub height: u64,
+    pub hash: [u8; 32],
+    pub timestamp: u64,
+    pub difficulty: f64,
+    /// Median fee rate (novas/byte) of non-coinbase transactions whose
+    /// inputs could be resolved; `None` for blocks without any
+    pub median_fee_rate: Option<f64>,
+    /// Seconds since the parent block; `None` when the parent is unknown
+    pub interval: Option<u64>,
+    /// Serialized block size in bytes
+    pub weight: u64,
+    pub tx_count: u64,
+}
+
+impl BlockStatsRecord {
+    /// Build the record for `block`, resolving input values from the UTXO
+    /// set or, once spent, from the transaction store.
+    pub fn from_block(db: &BlockchainDB, block: &Block) -> Result<Self, StorageError> {
+        let header = block.header();
+
+        let interval = db
+            .get_block(header.prev_block_hash())?
+            .map(|parent| header.timestamp().saturating_sub(parent.header().timestamp()));
+
+        let mut fee_rates = Vec::new();
+        for tx in block.transactions().iter().filter(|tx| !tx.is_coinbase()) {
+            let mut input_total = 0u64;
+            let mut resolved = true;
+            for input in tx.inputs() {
+                match resolve_input_value(db, &input.prev_tx_hash(), input.prev_output_index())? {
+                    Some(value) => input_total = input_total.saturating_add(value),
+                    None => {
+                        resolved = false;
+                        break;
+                    }
+                }
+            }
+            let size = tx.calculate_size();
+            if let (true, Some(output_total)) = (resolved && size > 0, tx.total_output()) {
+                let fee = input_total.saturating_sub(output_total);
+                fee_rates.push(fee as f64 / size as f64);
+            }
+        }
+
+        Ok(Self {
+            height: block.height(),
+            hash: block.hash(),
+            timestamp: header.timestamp(),
+            difficulty: calculate_difficulty_from_bits(header.bits()),
+            median_fee_rate: median(&mut fee_rates),
+            interval,
+            weight: block.size() as u64,
+            tx_count: block.transactions().len() as u64,
+        })
+    }
+}
+
+fn resolve_input_value(
+    db: &BlockchainDB,
+    tx_hash: &[u8; 32],
+    index: u32,
+) -> Result<Option<u64>, StorageError> {
+    if let Some(output) = db.get_utxo(tx_hash, index)? {
+        return Ok(Some(output.amount()));
+    }
+    Ok(db
+        .get_transaction(tx_hash)?
+        .and_then(|tx| tx.outputs().get(index as usize).map(|o| o.amount())))
+}
+
+fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    Some(if values.len() % 2 == 0 {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+impl BlockchainDB {
+    /// Compute and store the stats record for a block connected at its height,
+    /// replacing any record left by a block reorganized away.
+    pub fn record_block_stats(&self, block: &Block) -> Result<(), StorageError> {
+        let record = BlockStatsRecord::from_block(self, block)?;
+        self.store_block_stats(&record)
+    }
+
+    pub fn store_block_stats(&self, record: &BlockStatsRecord) -> Result<(), StorageError> {
+        let tree = self.open_tree(BLOCK_STATS_TREE)?;
+        tree.insert(record.height.to_be_bytes(), bincode::serialize(record)?)?;
+        Ok(())
+    }
+
+    /// Stats records for heights in `from..=to`, in height order. Heights
+    /// without a record are skipped.
+    pub fn get_block_stats_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<BlockStatsRecord>, StorageError> {
+        let tree = self.open_tree(BLOCK_STATS_TREE)?;
+        let mut records = Vec::new();
+        for entry in tree.range(from.to_be_bytes()..=to.to_be_bytes()) {
+            let (_, value) = entry?;
+            records.push(bincode::deserialize(&value)?);
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn record(height: u64) -> BlockStatsRecord {
+        BlockStatsRecord {
+            height,
+            hash: [height as u8; 32],
+            timestamp: 1_000 + height * 150,
+            difficulty: 1.0,
+            median_fee_rate: Some(2.5),
+            interval: Some(150),
+            weight: 1_000,
+            tx_count: 3,
+        }
+    }
+
+    #[test]
+    fn range_skips_missing_heights() {
+        let dir = tempdir().unwrap();
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        for height in [1, 2, 5, 300] {
+            db.store_block_stats(&record(height)).unwrap();
+        }
+
+        let heights: Vec<u64> = db
+            .get_block_stats_range(2, 299)
+            .unwrap()
+            .iter()
+            .map(|r| r.height)
+            .collect();
+        assert_eq!(heights, vec![2, 5]);
+
+        assert_eq!(median(&mut [3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&mut [4.0, 1.0]), Some(2.5));
+        assert_eq!(median(&mut []), None);
+    }
+}
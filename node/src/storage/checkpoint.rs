@@ -6,6 +6,7 @@
 use crate::metrics::BackupMetrics;
 use crate::storage::database::{BlockchainDB, StorageError};
 use crate::storage::persistence::ChainState;
+use crate::storage::state_snapshot::StateSnapshotStore;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
@@ -43,6 +44,7 @@ const DEFAULT_CHECKPOINT_INTERVAL_BLOCKS: u64 = 1000;
 const DEFAULT_CHECKPOINT_INTERVAL_TIME: Duration = Duration::from_secs(3600); // 1 hour
 const DEFAULT_MAX_CHECKPOINTS: usize = 5;
 const DEFAULT_INTEGRITY_CHECK_INTERVAL: Duration = Duration::from_secs(86400); // 1 day
+const DEFAULT_MAX_SNAPSHOTS: usize = 2;
 
 /// Types of checkpoints the system can create
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -95,6 +97,10 @@ pub struct CheckpointConfig {
     pub verify_after_creation: bool,
     /// Whether to enable automatic recovery on startup
     pub auto_recovery_on_startup: bool,
+    /// Take a UTXO state snapshot every N blocks; 0 disables snapshots
+    pub snapshot_interval_blocks: u64,
+    /// Maximum number of state snapshots to keep
+    pub max_snapshots: usize,
 }
 
 impl Default for CheckpointConfig {
@@ -107,6 +113,8 @@ impl Default for CheckpointConfig {
             integrity_check_interval: DEFAULT_INTEGRITY_CHECK_INTERVAL,
             verify_after_creation: true,
             auto_recovery_on_startup: true,
+            snapshot_interval_blocks: 0,
+            max_snapshots: DEFAULT_MAX_SNAPSHOTS,
         }
     }
 }
@@ -122,7 +130,7 @@ pub struct CheckpointInfo {
     pub timestamp: u64,
     /// Type of the checkpoint
     pub checkpoint_type: CheckpointType,
-    /// Hash of UTXO set at checkpoint
+    /// UTXO set commitment at checkpoint (`BlockchainDB::utxo_commitment`)
     pub utxo_hash: [u8; 32],
     /// Metadata about the checkpoint
     pub metadata: HashMap<String, String>,
@@ -187,6 +195,15 @@ impl CheckpointManager {
         }
     }
 
+    /// State snapshots served to bootstrapping peers, kept under the
+    /// checkpoint directory
+    pub fn snapshot_store(&self) -> StateSnapshotStore {
+        StateSnapshotStore::new(
+            self.config.checkpoint_dir.join("snapshots"),
+            self.config.max_snapshots,
+        )
+    }
+
     /// Start the checkpoint manager background task
     pub async fn start(&mut self) -> Result<(), StorageError> {
         info!(
@@ -210,17 +227,19 @@ impl CheckpointManager {
         let db = Arc::clone(&self.db);
         let chain_state = Arc::clone(&self.chain_state);
         let metrics = self.metrics.clone();
+        let snapshots = self.snapshot_store();
 
         // Spawn background task
         tokio::spawn(async move {
             let mut interval = time::interval(Duration::from_secs(10)); // Check every 10 seconds
             let mut integrity_check_timer = Instant::now();
+            let mut last_snapshot_height = None;
 
             loop {
                 tokio::select! {
                     _ = interval.tick() => {
                         // Check if it's time for a new checkpoint
-                        let _current_height = {
+                        let current_height = {
                             let state = match chain_state.lock() {
                                 Ok(s) => s,
                                 Err(_) => {
@@ -231,6 +250,25 @@ impl CheckpointManager {
                             state.get_height()
                         };
 
+                        // Snapshot each interval height once, even if it fails
+                        let due = StateSnapshotStore::due_height(
+                            current_height,
+                            config.snapshot_interval_blocks,
+                        );
+                        if let Some(height) = due.filter(|h| Some(*h) != last_snapshot_height) {
+                            last_snapshot_height = Some(height);
+                            let (snapshots, db) = (snapshots.clone(), Arc::clone(&db));
+                            match tokio::task::spawn_blocking(move || snapshots.create(&db, height)).await {
+                                Ok(Ok(manifest)) => info!(
+                                    "State snapshot at height {} has {} chunks",
+                                    height,
+                                    manifest.chunk_hashes.len()
+                                ),
+                                Ok(Err(e)) => error!("Failed to create state snapshot at height {}: {}", height, e),
+                                Err(e) => error!("State snapshot task failed: {}", e),
+                            }
+                        }
+
                         let now = Instant::now();
                         let time_since_last = now.duration_since(integrity_check_timer);
 
@@ -578,14 +616,12 @@ impl CheckpointManager {
 
         // Flush database to ensure all writes are committed
         db.flush()?;
+        let utxo_hash = db.utxo_commitment()?;
 
         // Copy database files to checkpoint directory
         let db_path = db.path();
         Self::copy_directory(db_path, &data_dir).await?;
 
-        // Calculate UTXO hash (this could be optimized/cached if needed)
-        let utxo_hash = Self::calculate_utxo_hash(db, chain_state).await?;
-
         // Calculate checkpoint data hash
         let data_hash = Self::calculate_checkpoint_hash(&checkpoint_dir).await?;
 
@@ -641,40 +677,6 @@ impl CheckpointManager {
         Ok(checkpoint_info)
     }
 
-    /// Calculate UTXO hash for integrity checking
-    async fn calculate_utxo_hash(
-        db: &Arc<BlockchainDB>,
-        chain_state: &Arc<Mutex<ChainState>>,
-    ) -> Result<[u8; 32], StorageError> {
-        let mut hasher = Sha256::new();
-
-        // This is a simplified implementation - in a real system you might use
-        // a more efficient method like a Merkle tree of the UTXO set
-
-        // Get current height and hash
-        let (height, hash) = {
-            let state = chain_state.lock().map_err(|_| {
-                // ENHANCED ERROR CONTEXT: Chain state lock failure during state checkpoint
-                StorageError::DatabaseError(
-                    "Failed to acquire mutex lock on chain_state during state checkpoint creation. \
-                     Lock may be poisoned. Cannot retrieve current height and best block hash. \
-                     State checkpoint creation aborted. This is a simplified implementation."
-                        .to_string()
-                )
-            })?;
-            (state.get_height(), state.get_best_block_hash())
-        };
-
-        // Add height and hash to hasher
-        hasher.update(height.to_le_bytes());
-        hasher.update(hash);
-
-        // Add a sample of UTXOs
-        // Note: In a real implementation, you would hash the entire UTXO set
-
-        Ok(hasher.finalize().into())
-    }
-
     /// Calculate a hash of checkpoint data for integrity checks
     async fn calculate_checkpoint_hash(path: &Path) -> Result<[u8; 32], StorageError> {
         let mut hasher = Sha256::new();
@@ -0,0 +1,381 @@
+//! Database compaction and storage statistics
+//!
+//! sled reuses the space of overwritten and deleted entries only once the
+//! segments holding them are mostly dead, so the files of a long-running
+//! node keep growing. Compaction rewrites every live entry, which empties
+//! those segments for sled to reuse or release. It works in batches with a
+//! pacing hook between them, so block processing keeps writing throughout.
+//! [`StorageMaintenance`] runs it on a schedule, or early once enough of the
+//! files is estimated reclaimable, and holds off while the node syncs.
+
+use super::database::{BlockchainDB, StorageError};
+use serde::{Deserialize, Serialize};
+use sled::{Db, IVec};
+use std::ops::Bound;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+/// Metadata key of the last [`CompactionRecord`]
+pub(crate) const LAST_COMPACTION_KEY: &[u8] = b"last_compaction";
+
+/// How long measured statistics are served before the trees are scanned again
+pub(crate) const STATS_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Entries rewritten between pacing calls
+pub const COMPACTION_BATCH: usize = 1_000;
+
+/// Pause between batches, leaving the database to block processing
+pub const COMPACTION_PAUSE: Duration = Duration::from_millis(10);
+
+/// How often the background task checks whether compaction is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Shortest gap between compactions triggered by fragmentation
+const MIN_COMPACTION_GAP: Duration = Duration::from_secs(60 * 60);
+
+/// How often a compaction paused for a sync checks whether it can resume
+const SYNC_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Size of one tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TreeStats {
+    pub name: String,
+    pub entries: u64,
+    /// Key and value bytes
+    pub bytes: u64,
+}
+
+/// A finished compaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CompactionRecord {
+    /// Unix time
+    pub started_at: u64,
+    /// Unix time
+    pub finished_at: u64,
+    pub entries_rewritten: u64,
+    /// Key and value bytes over all trees
+    pub live_bytes: u64,
+    pub disk_bytes_before: u64,
+    pub disk_bytes_after: u64,
+}
+
+/// Disk usage of the database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StorageStats {
+    pub trees: Vec<TreeStats>,
+    /// Key and value bytes over all trees
+    pub live_bytes: u64,
+    /// Size of the database files
+    pub disk_bytes: u64,
+    /// Disk bytes a compaction is estimated to release
+    pub reclaimable_bytes: u64,
+    /// `reclaimable_bytes` as a share of `disk_bytes`
+    pub fragmentation: f64,
+    pub last_compaction: Option<CompactionRecord>,
+    /// Whether a compaction is running
+    pub compacting: bool,
+}
+
+/// Where a running compaction is, passed to its pacing hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactionProgress {
+    /// Tree being rewritten
+    pub tree: String,
+    pub trees_done: usize,
+    pub trees_total: usize,
+    pub entries_rewritten: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn tree_label(name: &IVec) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+/// Measure every tree of `db`. The reclaimable estimate allows the live
+/// bytes the disk overhead measured right after the last compaction, when
+/// nothing was left to reclaim.
+pub(crate) fn measure(
+    db: &Db,
+    last_compaction: Option<CompactionRecord>,
+    compacting: bool,
+) -> Result<StorageStats, StorageError> {
+    let mut trees = Vec::new();
+    for name in db.tree_names() {
+        let mut stats = TreeStats {
+            name: tree_label(&name),
+            entries: 0,
+            bytes: 0,
+        };
+        for entry in db.open_tree(&name)?.iter() {
+            let (key, value) = entry?;
+            stats.entries += 1;
+            stats.bytes += (key.len() + value.len()) as u64;
+        }
+        trees.push(stats);
+    }
+
+    let live_bytes = trees.iter().map(|tree| tree.bytes).sum::<u64>();
+    let disk_bytes = db.size_on_disk()?;
+    let overhead = last_compaction
+        .as_ref()
+        .filter(|record| record.live_bytes > 0)
+        .map(|record| record.disk_bytes_after as f64 / record.live_bytes as f64)
+        .unwrap_or(1.0)
+        .max(1.0);
+    let reclaimable_bytes = disk_bytes.saturating_sub((live_bytes as f64 * overhead) as u64);
+    Ok(StorageStats {
+        trees,
+        live_bytes,
+        disk_bytes,
+        reclaimable_bytes,
+        fragmentation: if disk_bytes == 0 {
+            0.0
+        } else {
+            reclaimable_bytes as f64 / disk_bytes as f64
+        },
+        last_compaction,
+        compacting,
+    })
+}
+
+/// Rewrite every entry of `db` in batches of [`COMPACTION_BATCH`], calling
+/// `pace` after each. An entry is swapped only if it is unchanged since it
+/// was read, so concurrent writers always win. An error from `pace` stops
+/// the compaction.
+pub(crate) fn compact(
+    db: &Db,
+    mut pace: impl FnMut(&CompactionProgress) -> Result<(), StorageError>,
+) -> Result<CompactionRecord, StorageError> {
+    let started_at = now_secs();
+    let disk_bytes_before = db.size_on_disk()?;
+    let names = db.tree_names();
+    let mut progress = CompactionProgress {
+        tree: String::new(),
+        trees_done: 0,
+        trees_total: names.len(),
+        entries_rewritten: 0,
+    };
+    let mut live_bytes = 0u64;
+
+    for name in &names {
+        let tree = db.open_tree(name)?;
+        progress.tree = tree_label(name);
+        let mut after: Option<IVec> = None;
+        loop {
+            // No iterator is held across `pace`, which may wait a long time
+            let lower = after.clone().map_or(Bound::Unbounded, Bound::Excluded);
+            let batch = tree
+                .range::<IVec, _>((lower, Bound::Unbounded))
+                .take(COMPACTION_BATCH)
+                .collect::<Result<Vec<_>, _>>()?;
+            let Some((last, _)) = batch.last() else {
+                break;
+            };
+            after = Some(last.clone());
+            for (key, value) in batch {
+                live_bytes += (key.len() + value.len()) as u64;
+                // An entry changed since it was read has just been rewritten
+                if tree
+                    .compare_and_swap(&key, Some(&value), Some(value.clone()))?
+                    .is_ok()
+                {
+                    progress.entries_rewritten += 1;
+                }
+            }
+            pace(&progress)?;
+        }
+        progress.trees_done += 1;
+    }
+    db.flush()?;
+
+    Ok(CompactionRecord {
+        started_at,
+        finished_at: now_secs(),
+        entries_rewritten: progress.entries_rewritten,
+        live_bytes,
+        disk_bytes_before,
+        disk_bytes_after: db.size_on_disk()?,
+    })
+}
+
+/// Background compaction on a schedule, or once fragmentation crosses a
+/// threshold. Held off, and paused between batches, while `syncing` holds.
+pub struct StorageMaintenance {
+    db: Arc<BlockchainDB>,
+    interval: Option<Duration>,
+    threshold: Option<f64>,
+    syncing: Box<dyn Fn() -> bool + Send + Sync>,
+    started: Instant,
+}
+
+impl StorageMaintenance {
+    /// `interval_hours` and `threshold` of 0 turn the schedule and the
+    /// fragmentation trigger off
+    pub fn new(
+        db: Arc<BlockchainDB>,
+        interval_hours: u64,
+        threshold: f64,
+        syncing: impl Fn() -> bool + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            db,
+            interval: (interval_hours > 0).then(|| Duration::from_secs(interval_hours * 3600)),
+            threshold: (threshold > 0.0).then_some(threshold),
+            syncing: Box::new(syncing),
+            started: Instant::now(),
+        }
+    }
+
+    /// Check every few minutes and compact when due
+    pub async fn run(self: Arc<Self>) {
+        if self.interval.is_none() && self.threshold.is_none() {
+            return;
+        }
+        let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if (self.syncing)() {
+                continue;
+            }
+            let maintenance = Arc::clone(&self);
+            match tokio::task::spawn_blocking(move || maintenance.compact_if_due()).await {
+                Ok(Ok(Some(record))) => info!(
+                    "Compaction rewrote {} entries: {} -> {} bytes on disk",
+                    record.entries_rewritten, record.disk_bytes_before, record.disk_bytes_after
+                ),
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => warn!("Background compaction failed: {}", e),
+                Err(e) => error!("Background compaction task failed: {}", e),
+            }
+        }
+    }
+
+    /// Why a compaction is due, if it is. Without a previous compaction the
+    /// schedule counts from startup.
+    pub fn due(&self) -> Result<Option<&'static str>, StorageError> {
+        let since = match self.db.last_compaction()? {
+            Some(record) => Duration::from_secs(now_secs().saturating_sub(record.finished_at)),
+            None => self.started.elapsed(),
+        };
+        if self.interval.is_some_and(|interval| since >= interval) {
+            return Ok(Some("schedule"));
+        }
+        if let Some(threshold) = self.threshold {
+            if since >= MIN_COMPACTION_GAP && self.db.storage_stats()?.fragmentation >= threshold {
+                return Ok(Some("fragmentation"));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Compact if due, pausing between batches and for as long as the node
+    /// syncs
+    pub fn compact_if_due(&self) -> Result<Option<CompactionRecord>, StorageError> {
+        let Some(reason) = self.due()? else {
+            return Ok(None);
+        };
+        info!("Starting background compaction ({})", reason);
+        self.db
+            .compact_with(|_| {
+                std::thread::sleep(COMPACTION_PAUSE);
+                while (self.syncing)() {
+                    std::thread::sleep(SYNC_POLL_INTERVAL);
+                }
+                Ok(())
+            })
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::reorg::ReorgChangeSet;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tempfile::tempdir;
+
+    #[test]
+    fn compaction_keeps_up_with_concurrent_block_writes() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(dir.path())?);
+        for i in 0u32..20_000 {
+            db.store_metadata(&i.to_be_bytes(), &[0u8; 64])?;
+        }
+
+        // Writers commit block-sized change-sets while the compaction runs,
+        // each also overwriting entries of its own range that the compaction
+        // is about to rewrite
+        let done = Arc::new(AtomicBool::new(false));
+        let writers: Vec<_> = (0u8..2)
+            .map(|writer| {
+                let db = Arc::clone(&db);
+                let done = Arc::clone(&done);
+                std::thread::spawn(move || -> Result<(u32, Duration), StorageError> {
+                    let (mut writes, mut slowest) = (0u32, Duration::ZERO);
+                    while writes < 5_000 && (writes < 10 || !done.load(Ordering::SeqCst)) {
+                        let mut changes = ReorgChangeSet::new();
+                        changes.put_block(block_hash(writer, writes), vec![writer; 512]);
+                        changes.put_meta(
+                            overwritten_key(writer, writes).to_be_bytes().to_vec(),
+                            vec![0xff; 8],
+                        );
+                        let started = Instant::now();
+                        db.apply_reorg_atomically(&changes)?;
+                        slowest = slowest.max(started.elapsed());
+                        writes += 1;
+                    }
+                    Ok((writes, slowest))
+                })
+            })
+            .collect();
+
+        let mut batches = 0;
+        let record = db.compact_with(|_| {
+            batches += 1;
+            std::thread::yield_now();
+            Ok(())
+        })?;
+        done.store(true, Ordering::SeqCst);
+
+        let blocks = db.open_tree("blocks")?;
+        for (writer, handle) in (0u8..).zip(writers) {
+            let (writes, slowest) = handle.join().unwrap()?;
+            assert!(
+                slowest < Duration::from_secs(5),
+                "a write waited {:?}",
+                slowest
+            );
+            for n in 0..writes {
+                assert!(blocks.contains_key(block_hash(writer, n))?);
+                // The compaction never put back a value a writer replaced
+                let key = overwritten_key(writer, n).to_be_bytes();
+                assert_eq!(db.get_metadata(&key)?.as_deref(), Some(&[0xff; 8][..]));
+            }
+        }
+        assert!(batches >= 20);
+        assert!(record.entries_rewritten > 0);
+        assert!(!db.is_compacting());
+        assert_eq!(db.last_compaction()?, Some(record));
+        assert!(db.storage_stats()?.last_compaction.is_some());
+        Ok(())
+    }
+
+    fn block_hash(writer: u8, n: u32) -> [u8; 32] {
+        let mut hash = [writer; 32];
+        hash[..4].copy_from_slice(&n.to_be_bytes());
+        hash
+    }
+
+    fn overwritten_key(writer: u8, n: u32) -> u32 {
+        10_000 + u32::from(writer) * 5_000 + n
+    }
+}
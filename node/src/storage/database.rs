@@ -7,23 +7,40 @@ use std::collections::HashMap;
 use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::sync::RwLock;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
+use super::address_index::{AddressIndexBatch, ADDRESS_INDEX_KEY};
+use super::compaction::{
+    self, CompactionProgress, CompactionRecord, StorageStats, LAST_COMPACTION_KEY, STATS_MAX_AGE,
+};
+use super::journal::{TipIntent, TipJournal};
+use super::reorg::{ReorgChangeSet, ReorgOp};
+use super::txindex::{TxLocation, TX_INDEX_PROGRESS_KEY, TX_INDEX_SYNCED};
+use super::undo::{BlockUndo, UndoFormatError};
+use super::utxo_commitment::{bucket_key, BucketCheck, LtHash, UtxoAccumulator};
+use super::utxo_stats::{UtxoStats, UTXO_STATS_KEY};
+
 const BLOCKS_TREE: &str = "blocks";
 const TXNS_TREE: &str = "transactions";
 const UTXO_TREE: &str = "utxos";
 const METADATA_TREE: &str = "metadata";
 const BLOCK_HEIGHT_INDEX_TREE: &str = "block_height_index";
 const TX_INDEX_TREE: &str = "tx_index";
+const ADDRESS_INDEX_TREE: &str = "address_index";
 const HEADERS_TREE: &str = "headers";
 const PENDING_BLOCKS_TREE: &str = "pending_blocks";
 const PENDING_BLOCKS_META_TREE: &str = "pending_blocks_meta";
 const PENDING_BLOCKS_INDEX_TREE: &str = "pending_blocks_index";
 const SPENT_OUTPUTS_TREE: &str = "spent_outputs";
+const UNDO_TREE: &str = "block_undo";
 const HEIGHT_KEY: &[u8] = b"height";
+const BEST_HASH_KEY: &[u8] = b"best_hash";
+/// Tip journal file, kept in the database directory
+const TIP_JOURNAL_FILE: &str = "tip.journal";
 
 /// Metadata about a pending block
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -347,11 +364,15 @@ pub struct BlockchainDB {
     metadata: sled::Tree,
     block_height_index: sled::Tree,
     tx_index: sled::Tree,
+    /// Address index entries, keyed by output script hash
+    address_index: sled::Tree,
     headers: sled::Tree,
     pending_blocks: sled::Tree,
     pending_blocks_meta: sled::Tree,
     pending_blocks_index: sled::Tree,
     spent_outputs: sled::Tree,
+    /// Undo records of connected blocks, keyed by block hash
+    block_undo: sled::Tree,
     /// Expiry time for pending blocks
     pending_block_expiry: Duration,
     /// Maximum number of pending blocks
@@ -370,6 +391,22 @@ pub struct BlockchainDB {
     header_cache: Option<Arc<DatabaseCache<BlockHeader>>>,
     /// UTXO cache
     utxo_cache: Option<Arc<DatabaseCache<Vec<u8>>>>,
+    /// Running UTXO set totals, mirrored to the metadata tree. Held across
+    /// UTXO writes so the totals and the tree change together.
+    utxo_stats: Mutex<UtxoStats>,
+    /// Running UTXO set commitment, one metadata entry per bucket. Only
+    /// locked while `utxo_stats` is held.
+    utxo_commitment: Mutex<UtxoAccumulator>,
+    /// Whether connected blocks are added to the transaction index
+    tx_index_enabled: AtomicBool,
+    /// Whether connected blocks are added to the address index
+    address_index_enabled: AtomicBool,
+    /// Journal of the tip change being committed; `None` in memory
+    tip_journal: Option<TipJournal>,
+    /// Whether a compaction is running
+    compacting: AtomicBool,
+    /// Last measured storage statistics and when they were measured
+    storage_stats: Mutex<Option<(Instant, StorageStats)>>,
 }
 
 impl BlockchainDB {
@@ -433,11 +470,16 @@ impl BlockchainDB {
             metadata: db.open_tree(METADATA_TREE)?,
             block_height_index: db.open_tree(BLOCK_HEIGHT_INDEX_TREE)?,
             tx_index: db.open_tree(TX_INDEX_TREE)?,
+            address_index: db.open_tree(ADDRESS_INDEX_TREE)?,
             headers: db.open_tree(HEADERS_TREE)?,
             pending_blocks: db.open_tree(PENDING_BLOCKS_TREE)?,
             pending_blocks_meta: db.open_tree(PENDING_BLOCKS_META_TREE)?,
             pending_blocks_index: db.open_tree(PENDING_BLOCKS_INDEX_TREE)?,
             spent_outputs: db.open_tree(SPENT_OUTPUTS_TREE)?,
+            block_undo: db.open_tree(UNDO_TREE)?,
+            tip_journal: Some(TipJournal::new(path_buf.join(TIP_JOURNAL_FILE))),
+            compacting: AtomicBool::new(false),
+            storage_stats: Mutex::new(None),
             db_path: path_buf,
             db: Arc::new(db),
             pending_block_expiry: db_config.pending_block_expiry,
@@ -449,8 +491,14 @@ impl BlockchainDB {
             tx_cache,
             header_cache,
             utxo_cache,
+            utxo_stats: Mutex::new(UtxoStats::default()),
+            utxo_commitment: Mutex::new(UtxoAccumulator::default()),
+            tx_index_enabled: AtomicBool::new(false),
+            address_index_enabled: AtomicBool::new(false),
         };
 
+        blockchain_db.load_utxo_stats()?;
+
         // Initialize bloom filters with existing data if enabled
         if blockchain_db.config.use_bloom_filters {
             blockchain_db.init_bloom_filters()?;
@@ -918,47 +966,267 @@ impl BlockchainDB {
         output: &[u8],
     ) -> Result<(), StorageError> {
         let key = create_utxo_key(tx_hash, index);
-        self.utxos.insert(key, output)?;
+        let mut stats = self.lock_utxo_stats()?;
+        let old = self.utxos.insert(key.as_slice(), output)?;
+        stats.update(old.as_deref(), Some(output));
+        self.update_utxo_commitment(&key, old.as_deref(), Some(output))?;
+        self.persist_utxo_stats(&stats)
+    }
+
+    /// SHA-256 over every UTXO entry in key order, and the entry count.
+    /// Two databases with the same UTXO set produce the same digest.
+    pub fn utxo_set_digest(&self) -> Result<([u8; 32], u64), StorageError> {
+        let (digest, stats) = self.scan_utxo_stats()?;
+        Ok((digest, stats.count))
+    }
+
+    /// Recompute the UTXO statistics with a full scan, together with the
+    /// digest from [`Self::utxo_set_digest`]. Unlike [`Self::utxo_stats`] this
+    /// reads every entry.
+    pub fn scan_utxo_stats(&self) -> Result<([u8; 32], UtxoStats), StorageError> {
+        self.scan_utxos(|_, _| {})
+    }
+
+    /// Full scan behind [`Self::scan_utxo_stats`], handing every entry to
+    /// `visit` as well
+    fn scan_utxos(
+        &self,
+        mut visit: impl FnMut(&[u8], &[u8]),
+    ) -> Result<([u8; 32], UtxoStats), StorageError> {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        let mut stats = UtxoStats::default();
+        for entry in self.utxos.iter() {
+            let (key, value) = entry?;
+            hasher.update((key.len() as u32).to_le_bytes());
+            hasher.update(&key);
+            hasher.update((value.len() as u32).to_le_bytes());
+            hasher.update(&value);
+            stats.add(&value);
+            visit(&key, &value);
+        }
+        Ok((hasher.finalize().into(), stats))
+    }
+
+    /// Running UTXO statistics, maintained on every UTXO write
+    pub fn utxo_stats(&self) -> Result<UtxoStats, StorageError> {
+        Ok(self.lock_utxo_stats()?.clone())
+    }
+
+    /// Replace the running statistics and commitment with a fresh scan
+    pub fn rebuild_utxo_stats(&self) -> Result<UtxoStats, StorageError> {
+        let mut stats = self.lock_utxo_stats()?;
+        let mut commitment = self.lock_utxo_commitment()?;
+        let mut scanned_commitment = UtxoAccumulator::default();
+        let (_, scanned) = self.scan_utxos(|key, value| scanned_commitment.insert(key, value))?;
+        *stats = scanned;
+        *commitment = scanned_commitment;
+        self.persist_utxo_stats(&stats)?;
+        self.persist_utxo_commitment(&commitment)?;
+        Ok(stats.clone())
+    }
+
+    /// Run `f` with the tip height and an iterator over the UTXO set at that
+    /// tip. UTXO writes, which commit the new tip with them, wait until `f`
+    /// returns.
+    pub fn read_utxo_set<T>(
+        &self,
+        f: impl FnOnce(u64, sled::Iter) -> T,
+    ) -> Result<T, StorageError> {
+        let _stats = self.lock_utxo_stats()?;
+        Ok(f(self.get_height()?, self.utxos.iter()))
+    }
+
+    /// Replace the UTXO set with the entries of `staged`, such as a set
+    /// restored from a state snapshot, and recompute the statistics and
+    /// commitment
+    pub fn replace_utxo_set(&self, staged: &sled::Tree) -> Result<UtxoStats, StorageError> {
+        {
+            let _stats = self.lock_utxo_stats()?;
+            self.utxos.clear()?;
+            let mut batch = sled::Batch::default();
+            let mut batched = 0;
+            for entry in staged.iter() {
+                let (key, value) = entry?;
+                batch.insert(key, value);
+                batched += 1;
+                if batched == 10_000 {
+                    self.utxos.apply_batch(std::mem::take(&mut batch))?;
+                    batched = 0;
+                }
+            }
+            self.utxos.apply_batch(batch)?;
+        }
+        self.rebuild_utxo_stats()
+    }
+
+    fn load_utxo_stats(&self) -> Result<(), StorageError> {
+        let stored = self
+            .metadata
+            .get(UTXO_STATS_KEY)?
+            .and_then(|bytes| bincode::deserialize::<UtxoStats>(&bytes).ok());
+        let mut commitment = Some(UtxoAccumulator::default());
+        for bucket in 0..=u8::MAX {
+            let hash = self
+                .metadata
+                .get(bucket_key(bucket))?
+                .and_then(|bytes| LtHash::from_bytes(&bytes));
+            match (hash, commitment.as_mut()) {
+                (Some(hash), Some(commitment)) => commitment.set_bucket(bucket, hash),
+                _ => commitment = None,
+            }
+        }
+        match stored.zip(commitment) {
+            Some((stats, commitment)) => {
+                *self.lock_utxo_stats()? = stats;
+                *self.lock_utxo_commitment()? = commitment;
+            }
+            None => {
+                let stats = self.rebuild_utxo_stats()?;
+                tracing::info!("Computed UTXO statistics: {} entries", stats.count);
+            }
+        }
+        Ok(())
+    }
+
+    fn lock_utxo_stats(&self) -> Result<std::sync::MutexGuard<'_, UtxoStats>, StorageError> {
+        self.utxo_stats
+            .lock()
+            .map_err(|e| StorageError::LockPoisoned(format!("UTXO stats lock poisoned: {}", e)))
+    }
+
+    fn persist_utxo_stats(&self, stats: &UtxoStats) -> Result<(), StorageError> {
+        self.metadata
+            .insert(UTXO_STATS_KEY, bincode::serialize(stats)?)?;
         Ok(())
     }
 
+    fn lock_utxo_commitment(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, UtxoAccumulator>, StorageError> {
+        self.utxo_commitment.lock().map_err(|e| {
+            StorageError::LockPoisoned(format!("UTXO commitment lock poisoned: {}", e))
+        })
+    }
+
+    fn persist_utxo_commitment(&self, commitment: &UtxoAccumulator) -> Result<(), StorageError> {
+        let mut batch = sled::Batch::default();
+        for bucket in 0..=u8::MAX {
+            batch.insert(bucket_key(bucket), commitment.bucket(bucket).to_bytes());
+        }
+        self.metadata.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Fold a UTXO write into the commitment and persist the changed bucket.
+    /// Callers hold the stats lock.
+    fn update_utxo_commitment(
+        &self,
+        key: &[u8],
+        old: Option<&[u8]>,
+        new: Option<&[u8]>,
+    ) -> Result<(), StorageError> {
+        let mut commitment = self.lock_utxo_commitment()?;
+        let bucket = commitment.update(key, old, new);
+        self.metadata
+            .insert(bucket_key(bucket), commitment.bucket(bucket).to_bytes())?;
+        Ok(())
+    }
+
+    /// Commitment to the current UTXO set, maintained on every UTXO write.
+    /// See [`crate::storage::utxo_commitment`].
+    pub fn utxo_commitment(&self) -> Result<[u8; 32], StorageError> {
+        let _stats = self.lock_utxo_stats()?;
+        let commitment = self.lock_utxo_commitment()?;
+        Ok(commitment.commitment())
+    }
+
+    /// Recompute one commitment bucket from its key range and compare it
+    /// with the running bucket. UTXO writes wait for the scan, so both
+    /// describe the same set.
+    pub fn check_utxo_bucket(&self, bucket: u8) -> Result<BucketCheck, StorageError> {
+        let _stats = self.lock_utxo_stats()?;
+        let mut recomputed = LtHash::default();
+        let mut entries = 0;
+        for entry in self.utxos.scan_prefix([bucket]) {
+            let (key, value) = entry?;
+            recomputed.insert(&key, &value);
+            entries += 1;
+        }
+        let matches = *self.lock_utxo_commitment()?.bucket(bucket) == recomputed;
+        Ok(BucketCheck {
+            bucket,
+            entries,
+            recomputed,
+            matches,
+        })
+    }
+
     /// Remove a spent UTXO
     pub fn remove_utxo(&self, tx_hash: &[u8; 32], index: u32) -> Result<(), StorageError> {
         let key = create_utxo_key(tx_hash, index);
-        self.utxos.remove(key)?;
+        let mut stats = self.lock_utxo_stats()?;
+        if let Some(old) = self.utxos.remove(key.as_slice())? {
+            stats.update(Some(&old), None);
+            self.update_utxo_commitment(&key, Some(&old), None)?;
+            self.persist_utxo_stats(&stats)?;
+        }
         Ok(())
     }
 
     /// Apply a whole reorganization change-set ATOMICALLY (#5).
     ///
     /// Every op is committed inside a SINGLE sled multi-tree transaction over
-    /// the `blocks`, `utxos`, `metadata`, and `block_height_index` trees: either
-    /// all of them land or none do. This replaces the no-op
-    /// begin/commit/rollback primitives for the reorg path, so a crash or
+    /// the `blocks`, `utxos`, `metadata`, `block_height_index`, `block_undo`,
+    /// `tx_index` and `address_index` trees: either all of them land or none
+    /// do. This replaces the no-op begin/commit/rollback primitives for the
+    /// reorg path, so a crash or
     /// mid-reorg error can never leave a half-updated UTXO set or a dangling
     /// height index. The change-set holds only owned bytes, so the closure is
     /// pure and safe for sled to retry on contention. Durability is forced with
-    /// a flush once the transaction commits.
-    pub fn apply_reorg_atomically(
-        &self,
-        changes: &crate::storage::reorg::ReorgChangeSet,
-    ) -> Result<(), StorageError> {
-        use crate::storage::reorg::ReorgOp;
+    /// a flush once the transaction commits. The UTXO statistics are updated in
+    /// the same transaction, and staged blocks join the block bloom filter
+    /// once it commits.
+    pub fn apply_reorg_atomically(&self, changes: &ReorgChangeSet) -> Result<(), StorageError> {
         use sled::transaction::{ConflictableTransactionError, TransactionError};
         use sled::Transactional;
 
-        let outcome = (&self.blocks, &self.utxos, &self.metadata, &self.block_height_index)
-            .transaction(|(blocks, utxos, metadata, height_idx)| {
+        let mut stats = self.lock_utxo_stats()?;
+        let mut commitment = self.lock_utxo_commitment()?;
+        let outcome = (
+            &self.blocks,
+            &self.utxos,
+            &self.metadata,
+            &self.block_height_index,
+            &self.block_undo,
+            &self.tx_index,
+            &self.address_index,
+        )
+            .transaction(|trees| {
+                let (blocks, utxos, metadata, height_idx, undo, tx_idx, addr_idx) = trees;
+                // Start from the committed totals on every (re)try
+                let mut updated = stats.clone();
+                let mut updated_commitment = commitment.clone();
+                let mut touched = std::collections::BTreeSet::new();
                 for op in &changes.ops {
                     match op {
                         ReorgOp::PutBlock(hash, bytes) => {
                             blocks.insert(&hash[..], bytes.as_slice())?;
                         }
                         ReorgOp::PutUtxo(key, value) => {
-                            utxos.insert(key.as_slice(), value.as_slice())?;
+                            let old = utxos.insert(key.as_slice(), value.as_slice())?;
+                            updated.update(old.as_deref(), Some(value.as_slice()));
+                            touched.insert(updated_commitment.update(
+                                key,
+                                old.as_deref(),
+                                Some(value.as_slice()),
+                            ));
                         }
                         ReorgOp::DelUtxo(key) => {
-                            utxos.remove(key.as_slice())?;
+                            let old = utxos.remove(key.as_slice())?;
+                            updated.update(old.as_deref(), None);
+                            touched.insert(updated_commitment.update(key, old.as_deref(), None));
                         }
                         ReorgOp::PutMeta(key, value) => {
                             metadata.insert(key.as_slice(), value.as_slice())?;
@@ -969,20 +1237,67 @@ impl BlockchainDB {
                         ReorgOp::DelHeightIndex(be_height) => {
                             height_idx.remove(&be_height[..])?;
                         }
+                        ReorgOp::PutUndo(hash, bytes) => {
+                            undo.insert(&hash[..], bytes.as_slice())?;
+                        }
+                        ReorgOp::DelUndo(hash) => {
+                            undo.remove(&hash[..])?;
+                        }
+                        ReorgOp::PutTxIndex(txid, location) => {
+                            tx_idx.insert(&txid[..], &location[..])?;
+                        }
+                        ReorgOp::DelTxIndex(txid) => {
+                            tx_idx.remove(&txid[..])?;
+                        }
+                        ReorgOp::AddressIndex(batch) => {
+                            // Heights the rebuild has yet to reach are left to it
+                            let covered =
+                                read_be_u64(metadata.get(TX_INDEX_PROGRESS_KEY)?).unwrap_or(0);
+                            if batch.height < covered {
+                                apply_address_batch(addr_idx, batch)?;
+                            }
+                        }
                         #[cfg(test)]
                         ReorgOp::AbortForTest => {
                             return sled::transaction::abort(StorageError::DatabaseError(
                                 "reorg transaction aborted for test".to_string(),
                             ));
                         }
+                        #[cfg(test)]
+                        ReorgOp::PanicForTest => panic!("simulated crash during commit"),
                     }
                 }
-                Ok::<(), ConflictableTransactionError<StorageError>>(())
+                let encoded = bincode::serialize(&updated).map_err(|e| {
+                    ConflictableTransactionError::Abort(StorageError::Serialization(e))
+                })?;
+                metadata.insert(UTXO_STATS_KEY, encoded)?;
+                for bucket in touched {
+                    metadata.insert(
+                        bucket_key(bucket),
+                        updated_commitment.bucket(bucket).to_bytes(),
+                    )?;
+                }
+                Ok::<_, ConflictableTransactionError<StorageError>>((updated, updated_commitment))
             });
 
         match outcome {
-            Ok(()) => {
+            Ok((updated, updated_commitment)) => {
+                *stats = updated;
+                *commitment = updated_commitment;
                 self.db.flush()?;
+                if self.config.use_bloom_filters {
+                    let mut block_filter = self.block_filter.write().map_err(|e| {
+                        StorageError::LockPoisoned(format!(
+                            "Block filter write lock poisoned: {}",
+                            e
+                        ))
+                    })?;
+                    for op in &changes.ops {
+                        if let ReorgOp::PutBlock(hash, _) = op {
+                            block_filter.insert(hash);
+                        }
+                    }
+                }
                 Ok(())
             }
             // An application-level abort carries our own StorageError unchanged.
@@ -992,6 +1307,91 @@ impl BlockchainDB {
         }
     }
 
+    /// Commit a change-set that moves the tip to `to_height`/`to_hash`,
+    /// staging the tip metadata with it. The move is journaled first, so a
+    /// commit cut short by a crash is picked up by
+    /// [`RecoveryManager::check_consistency`](super::backup::RecoveryManager::check_consistency)
+    /// on the next start.
+    pub fn apply_tip_change(
+        &self,
+        mut changes: ReorgChangeSet,
+        to_height: u64,
+        to_hash: [u8; 32],
+    ) -> Result<(), StorageError> {
+        changes.put_meta(HEIGHT_KEY.to_vec(), to_height.to_be_bytes().to_vec());
+        changes.put_meta(BEST_HASH_KEY.to_vec(), to_hash.to_vec());
+        let Some(journal) = &self.tip_journal else {
+            return self.apply_reorg_atomically(&changes);
+        };
+        journal.begin(TipIntent {
+            from_height: self.get_height()?,
+            from_hash: self.get_tip_hash()?.unwrap_or([0u8; 32]),
+            to_height,
+            to_hash,
+        })?;
+        let applied = self.apply_reorg_atomically(&changes);
+        // A record left behind only costs a check at the next start
+        if let Err(e) = journal.complete() {
+            tracing::warn!("Failed to clear the tip journal: {}", e);
+        }
+        applied
+    }
+
+    /// Journal of tip changes, for on-disk databases
+    pub fn tip_journal(&self) -> Option<&TipJournal> {
+        self.tip_journal.as_ref()
+    }
+
+    /// Tip hash recorded in the metadata, if any
+    pub fn get_tip_hash(&self) -> Result<Option<[u8; 32]>, StorageError> {
+        Ok(self
+            .get_metadata(BEST_HASH_KEY)?
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes.as_ref()).ok()))
+    }
+
+    /// Highest height in the height->hash index
+    pub fn max_indexed_height(&self) -> Result<Option<u64>, StorageError> {
+        Ok(self
+            .block_height_index
+            .last()?
+            .and_then(|(key, _)| <[u8; 8]>::try_from(key.as_ref()).ok())
+            .map(u64::from_be_bytes))
+    }
+
+    /// Undo record written when the block was connected, if still kept. A
+    /// record that fails its checksum or cannot be decoded is reported as
+    /// [`StorageError::CorruptUndo`].
+    pub fn get_undo(&self, block_hash: &[u8; 32]) -> Result<Option<BlockUndo>, StorageError> {
+        match self.block_undo.get(block_hash)? {
+            Some(bytes) => {
+                BlockUndo::decode(&bytes)
+                    .map(Some)
+                    .map_err(|source| StorageError::CorruptUndo {
+                        block: hex::encode(block_hash),
+                        source,
+                    })
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Drop the undo records of best-chain blocks below `height`, walking
+    /// down from `height - 1` until a block without one. Returns the number
+    /// of records removed.
+    pub fn prune_undo(&self, height: u64) -> Result<usize, StorageError> {
+        let mut pruned = 0;
+        for below in (0..height).rev() {
+            let Some(hash) = self.get_block_hash_by_height(below)? else {
+                break;
+            };
+            if self.block_undo.remove(hash)?.is_none() {
+                break;
+            }
+            pruned += 1;
+        }
+        Ok(pruned)
+    }
+
     /// Store chain metadata
     pub fn store_metadata(&self, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
         self.metadata.insert(key, value)?;
@@ -1069,19 +1469,99 @@ impl BlockchainDB {
         }
     }
 
-    /// Implement block pruning
-    pub fn prune_blocks(&self, height: u64) -> Result<(), StorageError> {
-        let mut pruned_count = 0;
-        for i in 0..height {
-            if let Some(hash) = self.block_height_index.get(i.to_be_bytes())? {
-                self.blocks.remove(hash.as_ref())?;
-                self.block_height_index.remove(i.to_be_bytes())?;
-                pruned_count += 1;
+    /// Header of a best-chain block by height, from the block itself or,
+    /// once it has been pruned, from the headers tree
+    pub fn get_header_by_height(&self, height: u64) -> Result<Option<BlockHeader>, StorageError> {
+        match self.get_block_hash_by_height(height)? {
+            Some(hash) => self.get_stored_header(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Header of a stored block, falling back to the headers tree for
+    /// blocks that have been pruned
+    pub fn get_stored_header(&self, hash: &[u8; 32]) -> Result<Option<BlockHeader>, StorageError> {
+        match self.get_block(hash)? {
+            Some(block) => Ok(Some(block.header().clone())),
+            None => self.get_block_header(hash),
+        }
+    }
+
+    /// Lowest height above genesis whose raw block is still stored, or 0 if
+    /// nothing has been pruned. Genesis is never pruned.
+    pub fn pruned_height(&self) -> Result<u64, StorageError> {
+        Ok(self
+            .get_metadata(b"pruned_height")?
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0))
+    }
+
+    /// Height below which best-chain blocks can be pruned so that the raw
+    /// blocks kept from `tip` down fit in `target_bytes`. The last `keep`
+    /// blocks are always kept. Sizes come from the block stats records,
+    /// falling back to the stored block.
+    pub fn prune_height_for_target(
+        &self,
+        tip: u64,
+        keep: u64,
+        target_bytes: u64,
+    ) -> Result<u64, StorageError> {
+        let floor = self.pruned_height()?.max(1);
+        let highest = tip.saturating_add(1).saturating_sub(keep);
+        if highest <= floor {
+            return Ok(floor);
+        }
+        let sizes: HashMap<u64, u64> = self
+            .get_block_stats_range(floor, tip)?
+            .into_iter()
+            .map(|record| (record.height, record.weight))
+            .collect();
+        let mut kept = 0u64;
+        for height in (floor..=tip).rev() {
+            let size = match sizes.get(&height) {
+                Some(size) => *size,
+                None => match self.get_block_hash_by_height(height)? {
+                    Some(hash) => self.blocks.get(hash)?.map_or(0, |bytes| bytes.len() as u64),
+                    None => 0,
+                },
+            };
+            kept = kept.saturating_add(size);
+            if kept > target_bytes {
+                return Ok((height + 1).min(highest));
             }
         }
+        Ok(floor)
+    }
+
+    /// Delete the raw blocks and undo records of best-chain heights from the
+    /// current prune height up to `height`, keeping their headers and height
+    /// index entries. Returns the number of blocks removed.
+    pub fn prune_blocks(&self, height: u64) -> Result<usize, StorageError> {
+        let start = self.pruned_height()?.max(1);
+        if height <= start {
+            return Ok(0);
+        }
+        let mut pruned = 0;
+        for below in start..height {
+            let Some(hash) = self.get_block_hash_by_height(below)? else {
+                continue;
+            };
+            let Some(block) = self.get_block(&hash)? else {
+                continue;
+            };
+            // The header outlives the block for chain work and serving
+            self.store_block_header(&hash, &bincode::serialize(block.header())?)?;
+            self.blocks.remove(hash)?;
+            self.block_undo.remove(hash)?;
+            if let Some(block_cache) = &self.block_cache {
+                block_cache.remove(&hash);
+            }
+            pruned += 1;
+        }
         self.store_metadata(b"pruned_height", &height.to_be_bytes())?;
-        tracing::info!("Pruned {} blocks up to height {}", pruned_count, height);
-        Ok(())
+        tracing::info!("Pruned {} blocks below height {}", pruned, height);
+        Ok(pruned)
     }
 
     /// Clear the database
@@ -1092,18 +1572,27 @@ impl BlockchainDB {
         self.metadata.clear()?;
         self.block_height_index.clear()?;
         self.tx_index.clear()?;
+        self.address_index.clear()?;
         self.headers.clear()?;
         self.pending_blocks.clear()?;
         self.pending_blocks_meta.clear()?;
         self.pending_blocks_index.clear()?;
         self.spent_outputs.clear()?;
+        self.block_undo.clear()?;
+        *self.lock_utxo_stats()? = UtxoStats::default();
+        *self.lock_utxo_commitment()? = UtxoAccumulator::default();
         Ok(())
     }
 
     /// Clear only the UTXO set
     pub fn clear_utxos(&self) -> Result<(), StorageError> {
+        let mut stats = self.lock_utxo_stats()?;
+        let mut commitment = self.lock_utxo_commitment()?;
         self.utxos.clear()?;
-        Ok(())
+        *stats = UtxoStats::default();
+        *commitment = UtxoAccumulator::default();
+        self.persist_utxo_commitment(&commitment)?;
+        self.persist_utxo_stats(&stats)
     }
 
     /// Begin a transaction
@@ -1129,24 +1618,64 @@ impl BlockchainDB {
         Ok(())
     }
 
-    /// Compact the database to reclaim space
+    /// Compact the database to reclaim space, without pausing between batches
     pub fn compact(&self) -> Result<(), StorageError> {
-        // sled 0.34 doesn't have compact_range, so we just flush the database
-        self.db.flush()?;
+        self.compact_with(|_| Ok(())).map(|_| ())
+    }
 
-        // For each tree, use the regular sled tree flush
-        self.blocks.flush()?;
-        self.transactions.flush()?;
-        self.utxos.flush()?;
-        self.metadata.flush()?;
-        self.block_height_index.flush()?;
-        self.tx_index.flush()?;
-        self.headers.flush()?;
-        self.pending_blocks.flush()?;
-        self.pending_blocks_meta.flush()?;
-        self.pending_blocks_index.flush()?;
+    /// Compact the database, calling `pace` between batches; see
+    /// [`compaction`](super::compaction). One compaction runs at a time.
+    pub fn compact_with(
+        &self,
+        pace: impl FnMut(&CompactionProgress) -> Result<(), StorageError>,
+    ) -> Result<CompactionRecord, StorageError> {
+        if self.compacting.swap(true, Ordering::SeqCst) {
+            return Err(StorageError::DatabaseError(
+                "A compaction is already running".to_string(),
+            ));
+        }
+        let outcome = compaction::compact(&self.db, pace).and_then(|record| {
+            self.metadata
+                .insert(LAST_COMPACTION_KEY, bincode::serialize(&record)?)?;
+            Ok(record)
+        });
+        self.compacting.store(false, Ordering::SeqCst);
+        if let Ok(mut cached) = self.storage_stats.lock() {
+            *cached = None;
+        }
+        outcome
+    }
 
-        Ok(())
+    /// Whether a compaction is running
+    pub fn is_compacting(&self) -> bool {
+        self.compacting.load(Ordering::SeqCst)
+    }
+
+    /// The last finished compaction
+    pub fn last_compaction(&self) -> Result<Option<CompactionRecord>, StorageError> {
+        match self.metadata.get(LAST_COMPACTION_KEY)? {
+            Some(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Per-tree sizes and disk usage. Measuring scans every tree, so a
+    /// measurement is reused for up to a minute.
+    pub fn storage_stats(&self) -> Result<StorageStats, StorageError> {
+        let mut cached = self.storage_stats.lock().map_err(|e| {
+            StorageError::LockPoisoned(format!("Storage stats lock poisoned: {}", e))
+        })?;
+        if let Some((measured, stats)) = cached.as_ref() {
+            if measured.elapsed() < STATS_MAX_AGE {
+                return Ok(StorageStats {
+                    compacting: self.is_compacting(),
+                    ..stats.clone()
+                });
+            }
+        }
+        let stats = compaction::measure(&self.db, self.last_compaction()?, self.is_compacting())?;
+        *cached = Some((Instant::now(), stats.clone()));
+        Ok(stats)
     }
 
     /// Flush all pending writes to disk
@@ -1398,6 +1927,7 @@ impl BlockchainDB {
         }
 
         // Execute operations for each tree
+        let mut touched_utxos = false;
         for (tree_name, ops) in ops_by_tree {
             let tree = self.db.open_tree(&tree_name)?;
 
@@ -1421,6 +1951,12 @@ impl BlockchainDB {
 
             // Apply the batch atomically
             tree.apply_batch(sled_batch)?;
+            touched_utxos |= tree_name == UTXO_TREE;
+        }
+
+        // Batches carry no old values, so recount rather than adjust
+        if touched_utxos {
+            self.rebuild_utxo_stats()?;
         }
 
         Ok(())
@@ -1671,12 +2207,16 @@ impl BlockchainDB {
         for item in self.tx_index.iter() {
             let (key, value) = item?;
 
-            // Check if the transaction exists
-            if self.transactions.get(&key)?.is_none() {
+            // Check if the containing block exists
+            let block_stored = match TxLocation::decode(&value) {
+                Some(location) => self.blocks.contains_key(location.block_hash)?,
+                None => false,
+            };
+            if !block_stored {
                 result.issues.push(IntegrityIssue {
                     issue_type: IntegrityIssueType::BrokenReference,
                     description: format!(
-                        "Transaction index references non-existent transaction: {}",
+                        "Transaction index references non-existent block: {}",
                         hex::encode(&key[0..4])
                     ),
                     key: Some(key.to_vec()),
@@ -2422,11 +2962,13 @@ impl BlockchainDB {
         let metadata = db.open_tree(METADATA_TREE)?;
         let block_height_index = db.open_tree(BLOCK_HEIGHT_INDEX_TREE)?;
         let tx_index = db.open_tree(TX_INDEX_TREE)?;
+        let address_index = db.open_tree(ADDRESS_INDEX_TREE)?;
         let headers = db.open_tree(HEADERS_TREE)?;
         let pending_blocks = db.open_tree(PENDING_BLOCKS_TREE)?;
         let pending_blocks_meta = db.open_tree(PENDING_BLOCKS_META_TREE)?;
         let pending_blocks_index = db.open_tree(PENDING_BLOCKS_INDEX_TREE)?;
         let spent_outputs = db.open_tree(SPENT_OUTPUTS_TREE)?;
+        let block_undo = db.open_tree(UNDO_TREE)?;
 
         let block_filter = Arc::new(RwLock::new(BloomFilter::new(
             db_config.bloom_filter_capacity,
@@ -2473,11 +3015,13 @@ impl BlockchainDB {
             metadata,
             block_height_index,
             tx_index,
+            address_index,
             headers,
             pending_blocks,
             pending_blocks_meta,
             pending_blocks_index,
             spent_outputs,
+            block_undo,
             pending_block_expiry: db_config.pending_block_expiry,
             max_pending_blocks: db_config.max_pending_blocks,
             block_filter,
@@ -2487,6 +3031,13 @@ impl BlockchainDB {
             tx_cache,
             header_cache,
             utxo_cache,
+            utxo_stats: Mutex::new(UtxoStats::default()),
+            utxo_commitment: Mutex::new(UtxoAccumulator::default()),
+            tx_index_enabled: AtomicBool::new(false),
+            address_index_enabled: AtomicBool::new(false),
+            tip_journal: None,
+            compacting: AtomicBool::new(false),
+            storage_stats: Mutex::new(None),
         })
     }
 
@@ -2536,23 +3087,191 @@ impl BlockchainDB {
         Ok(None)
     }
 
+    /// Block hashes whose hex encoding starts with `hex_prefix`, in key
+    /// order, at most `limit`. Block keys are raw hashes, so this is a sled
+    /// prefix scan rather than a full-tree walk.
+    pub fn find_block_hashes_by_prefix(
+        &self,
+        hex_prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<[u8; 32]>, StorageError> {
+        scan_hash_prefix(&self.blocks, hex_prefix, limit)
+    }
+
+    /// Transaction IDs whose hex encoding starts with `hex_prefix`, in key
+    /// order, at most `limit`.
+    pub fn find_transaction_hashes_by_prefix(
+        &self,
+        hex_prefix: &str,
+        limit: usize,
+    ) -> Result<Vec<[u8; 32]>, StorageError> {
+        scan_hash_prefix(&self.transactions, hex_prefix, limit)
+    }
+
     /// Get the block that contains a specific transaction
     pub fn get_transaction_block(
         &self,
         tx_hash: &[u8; 32],
     ) -> Result<Option<[u8; 32]>, StorageError> {
-        // Check if we have an index for this transaction
-        if let Some(block_hash_data) = self.tx_index.get(tx_hash)? {
-            if block_hash_data.len() == 32 {
-                let mut block_hash = [0u8; 32];
-                block_hash.copy_from_slice(&block_hash_data);
-                Ok(Some(block_hash))
-            } else {
-                Err(StorageError::InvalidBlock)
-            }
-        } else {
-            Ok(None)
+        Ok(self
+            .get_tx_location(tx_hash)?
+            .map(|location| location.block_hash))
+    }
+
+    /// Turn the transaction index on or off. Turning it off drops the index,
+    /// so turning it back on later rebuilds it from scratch.
+    pub fn set_tx_index(&self, enabled: bool) -> Result<(), StorageError> {
+        self.tx_index_enabled.store(enabled, Ordering::SeqCst);
+        if !enabled
+            && (!self.tx_index.is_empty() || self.metadata.contains_key(TX_INDEX_PROGRESS_KEY)?)
+        {
+            tracing::info!("Transaction index disabled, dropping it");
+            self.tx_index.clear()?;
+            self.metadata.remove(TX_INDEX_PROGRESS_KEY)?;
         }
+        Ok(())
+    }
+
+    /// Whether connected blocks are added to the transaction index
+    pub fn tx_index_enabled(&self) -> bool {
+        self.tx_index_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Where the transaction index places `txid`. Entries may point at
+    /// blocks that have since left the best chain.
+    pub fn get_tx_location(&self, txid: &[u8; 32]) -> Result<Option<TxLocation>, StorageError> {
+        if !self.tx_index_enabled() {
+            return Ok(None);
+        }
+        match self.tx_index.get(txid)? {
+            Some(bytes) => TxLocation::decode(&bytes)
+                .map(Some)
+                .ok_or(StorageError::InvalidBlock),
+            None => Ok(None),
+        }
+    }
+
+    /// Next best-chain height the transaction index rebuild has to cover,
+    /// or `None` once every stored block is indexed
+    pub fn tx_index_next_height(&self) -> Result<Option<u64>, StorageError> {
+        let next = self
+            .metadata
+            .get(TX_INDEX_PROGRESS_KEY)?
+            .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok())
+            .map(u64::from_be_bytes)
+            .unwrap_or(0);
+        Ok((next != TX_INDEX_SYNCED).then_some(next))
+    }
+
+    /// Apply the index entries the rebuild planned for the block `block_hash`
+    /// at `height` and advance its progress past it, atomically. Writes
+    /// nothing and returns `false` if the block has left the best chain or
+    /// the progress moved meanwhile.
+    pub fn apply_index_changes(
+        &self,
+        changes: &ReorgChangeSet,
+        height: u64,
+        block_hash: &[u8; 32],
+    ) -> Result<bool, StorageError> {
+        use sled::transaction::{ConflictableTransactionError, TransactionError};
+        use sled::Transactional;
+
+        (
+            &self.tx_index,
+            &self.address_index,
+            &self.metadata,
+            &self.block_height_index,
+        )
+            .transaction(|(tx_idx, address_idx, metadata, height_idx)| {
+                let progress = read_be_u64(metadata.get(TX_INDEX_PROGRESS_KEY)?).unwrap_or(0);
+                let best = height_idx.get(height.to_be_bytes())?;
+                if progress != height || best.as_deref() != Some(&block_hash[..]) {
+                    return Ok(false);
+                }
+                for op in &changes.ops {
+                    match op {
+                        ReorgOp::PutTxIndex(txid, location) => {
+                            tx_idx.insert(&txid[..], &location[..])?;
+                        }
+                        ReorgOp::DelTxIndex(txid) => {
+                            tx_idx.remove(&txid[..])?;
+                        }
+                        ReorgOp::AddressIndex(batch) => apply_address_batch(address_idx, batch)?,
+                        _ => {
+                            return sled::transaction::abort(StorageError::DatabaseError(
+                                "index rebuild staged a write outside the indexes".to_string(),
+                            ))
+                        }
+                    }
+                }
+                metadata.insert(TX_INDEX_PROGRESS_KEY, &(height + 1).to_be_bytes()[..])?;
+                Ok::<_, ConflictableTransactionError<StorageError>>(true)
+            })
+            .map_err(|e| match e {
+                TransactionError::Abort(inner) => inner,
+                TransactionError::Storage(e) => StorageError::Database(e),
+            })
+    }
+
+    /// Record the transaction index rebuild's progress without indexing
+    pub fn set_tx_index_next_height(&self, next_height: u64) -> Result<(), StorageError> {
+        self.metadata
+            .insert(TX_INDEX_PROGRESS_KEY, &next_height.to_be_bytes()[..])?;
+        Ok(())
+    }
+
+    /// Mark the transaction index as covering every stored block once the
+    /// rebuild reached `next_height` above the tip. Returns `false` if a
+    /// block was connected at that height meanwhile.
+    pub fn mark_tx_index_synced(&self, next_height: u64) -> Result<bool, StorageError> {
+        use sled::transaction::{ConflictableTransactionError, TransactionError};
+
+        let marked = self
+            .metadata
+            .transaction(|metadata| {
+                let progress = read_be_u64(metadata.get(TX_INDEX_PROGRESS_KEY)?).unwrap_or(0);
+                let tip = read_be_u64(metadata.get(HEIGHT_KEY)?).unwrap_or(0);
+                if progress != next_height || tip >= next_height {
+                    return Ok(false);
+                }
+                metadata.insert(TX_INDEX_PROGRESS_KEY, &TX_INDEX_SYNCED.to_be_bytes()[..])?;
+                Ok::<_, ConflictableTransactionError<StorageError>>(true)
+            })
+            .map_err(|e| match e {
+                TransactionError::Abort(inner) => inner,
+                TransactionError::Storage(e) => StorageError::Database(e),
+            })?;
+        self.metadata.flush()?;
+        Ok(marked)
+    }
+
+    /// Turn the address index on or off. Turning it on for the first time
+    /// restarts the transaction index rebuild, which builds both; turning
+    /// it off drops it.
+    pub fn set_address_index(&self, enabled: bool) -> Result<(), StorageError> {
+        self.address_index_enabled.store(enabled, Ordering::SeqCst);
+        let built = self.metadata.contains_key(ADDRESS_INDEX_KEY)?;
+        if enabled && !built {
+            tracing::info!("Address index enabled, rebuilding it with the transaction index");
+            self.address_index.clear()?;
+            self.metadata.remove(TX_INDEX_PROGRESS_KEY)?;
+            self.metadata.insert(ADDRESS_INDEX_KEY, &[1u8][..])?;
+        } else if !enabled && (built || !self.address_index.is_empty()) {
+            tracing::info!("Address index disabled, dropping it");
+            self.address_index.clear()?;
+            self.metadata.remove(ADDRESS_INDEX_KEY)?;
+        }
+        Ok(())
+    }
+
+    /// Whether connected blocks are added to the address index
+    pub fn address_index_enabled(&self) -> bool {
+        self.address_index_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Address index entries starting with `prefix`, in key order
+    pub fn scan_address_index(&self, prefix: &[u8]) -> sled::Iter {
+        self.address_index.scan_prefix(prefix)
     }
 
     /// Get a transaction output
@@ -2801,6 +3520,59 @@ pub(crate) fn create_utxo_key(tx_hash: &[u8; 32], index: u32) -> Vec<u8> {
     key
 }
 
+/// Scan a tree keyed by 32-byte hashes for keys whose hex encoding starts
+/// with `hex_prefix`. Whole bytes of the prefix drive the sled prefix scan; an
+/// odd trailing nibble is matched on the encoded key.
+fn scan_hash_prefix(
+    tree: &sled::Tree,
+    hex_prefix: &str,
+    limit: usize,
+) -> Result<Vec<[u8; 32]>, StorageError> {
+    let hex_prefix = hex_prefix.to_ascii_lowercase();
+    let byte_prefix = hex::decode(&hex_prefix[..hex_prefix.len() / 2 * 2])
+        .map_err(|e| StorageError::KeyNotFound(format!("invalid hex prefix: {}", e)))?;
+
+    let mut hashes = Vec::new();
+    for entry in tree.scan_prefix(&byte_prefix) {
+        if hashes.len() >= limit {
+            break;
+        }
+        let (key, _) = entry?;
+        if key.len() != 32 || !hex::encode(&key).starts_with(&hex_prefix) {
+            continue;
+        }
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&key);
+        hashes.push(hash);
+    }
+    Ok(hashes)
+}
+
+/// Big-endian `u64` stored under a key, if well-formed
+fn read_be_u64(bytes: Option<sled::IVec>) -> Option<u64> {
+    bytes
+        .and_then(|bytes| <[u8; 8]>::try_from(bytes.as_ref()).ok())
+        .map(u64::from_be_bytes)
+}
+
+/// Apply one block's address index writes inside a transaction
+fn apply_address_batch(
+    tree: &sled::transaction::TransactionalTree,
+    batch: &AddressIndexBatch,
+) -> Result<(), sled::transaction::UnabortableTransactionError> {
+    for (key, value) in &batch.writes {
+        match value {
+            Some(value) => {
+                tree.insert(key.as_slice(), value.as_slice())?;
+            }
+            None => {
+                tree.remove(key.as_slice())?;
+            }
+        }
+    }
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 pub enum StorageError {
     #[error("Database error: {0}")]
@@ -2835,6 +3607,28 @@ pub enum StorageError {
     UtxoLocked(String),
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
+    #[error("Corrupt undo data for block {block}: {source}")]
+    CorruptUndo {
+        block: String,
+        source: UndoFormatError,
+    },
+}
+
+impl StorageError {
+    /// Stable reason code used when this error rejects a block.
+    /// See `crate::metrics::rejections::BLOCK_REASON_CODES`.
+    pub fn block_rejection_reason(&self) -> &'static str {
+        match self {
+            StorageError::InvalidBlock => "invalid-block",
+            StorageError::InvalidTransaction(_) => "invalid-transaction",
+            StorageError::InvalidChainReorganization => "invalid-reorg",
+            StorageError::CheckpointError(_) => "checkpoint-mismatch",
+            StorageError::PendingBlockInvalid => "pending-block-invalid",
+            StorageError::PendingBlockExpired => "pending-block-expired",
+            StorageError::UtxoLocked(_) => "utxo-locked",
+            _ => "internal-error",
+        }
+    }
 }
 
 // Add these implementations after the enum definition
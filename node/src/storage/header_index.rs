@@ -0,0 +1,518 @@
+//! In-memory header tree for fast ancestor queries.
+//!
+//! Median-time-past, retarget periods, deployment windows and fork-point
+//! searches all walk back through parents. Fetching each parent from the
+//! database costs a full block read, so [`HeaderIndex`] keeps the most recent
+//! headers, competing branches included, in memory with parent pointers, a
+//! height index and Bitcoin-style skip pointers, giving O(log n)
+//! `ancestor_at_height` queries. Headers deeper than the retained window are
+//! loaded from storage on demand through a caller-supplied loader, so a miss
+//! is never an error, only slower.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use supernova_core::types::block::BlockHeader;
+
+/// Headers retained below the best tip
+pub const DEFAULT_HEADER_INDEX_DEPTH: u64 = 4096;
+
+/// Blocks whose timestamps make up median-time-past
+pub const MEDIAN_TIME_SPAN: usize = 11;
+
+/// The parts of a block header needed for chain navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexedHeader {
+    pub hash: [u8; 32],
+    pub prev_hash: [u8; 32],
+    pub height: u64,
+    pub timestamp: u64,
+    pub version: u32,
+}
+
+impl IndexedHeader {
+    pub fn from_header(header: &BlockHeader) -> Self {
+        Self {
+            hash: header.hash(),
+            prev_hash: *header.prev_block_hash(),
+            height: header.height(),
+            timestamp: header.timestamp(),
+            version: header.version(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IndexNode {
+    header: IndexedHeader,
+    /// Ancestor at `skip_height(height)`, when it was indexed at insert time
+    skip: Option<[u8; 32]>,
+}
+
+enum Walk {
+    Found([u8; 32]),
+    /// The walk needs this header, which is not indexed
+    Missing([u8; 32]),
+    /// The target height is above the starting header
+    Above,
+}
+
+/// Header tree of the blocks within `depth` of the best tip, including
+/// competing branches.
+#[derive(Debug, Clone)]
+pub struct HeaderIndex {
+    nodes: HashMap<[u8; 32], IndexNode>,
+    by_height: BTreeMap<u64, Vec<[u8; 32]>>,
+    /// Headers with no indexed child
+    tips: HashSet<[u8; 32]>,
+    best: Option<[u8; 32]>,
+    depth: u64,
+}
+
+/// Height of the skip target for a header at `height`, chosen so walks to
+/// any ancestor take O(log n) steps (Bitcoin's `GetSkipHeight`).
+fn skip_height(height: u64) -> u64 {
+    fn invert_lowest_one(n: u64) -> u64 {
+        n & n.wrapping_sub(1)
+    }
+    if height < 2 {
+        return 0;
+    }
+    if height & 1 == 1 {
+        invert_lowest_one(invert_lowest_one(height - 1)) + 1
+    } else {
+        invert_lowest_one(height)
+    }
+}
+
+impl HeaderIndex {
+    pub fn new(depth: u64) -> Self {
+        Self {
+            nodes: HashMap::new(),
+            by_height: BTreeMap::new(),
+            tips: HashSet::new(),
+            best: None,
+            depth,
+        }
+    }
+
+    /// Load the `depth` headers ending at `best` from storage.
+    pub fn rebuild<E>(
+        best: [u8; 32],
+        depth: u64,
+        mut load: impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, E>,
+    ) -> Result<Self, E> {
+        let mut index = Self::new(depth);
+        let mut chain = Vec::new();
+        let mut cursor = best;
+        while (chain.len() as u64) <= depth {
+            let Some(header) = load(&cursor)? else {
+                break;
+            };
+            chain.push(header);
+            if header.height == 0 {
+                break;
+            }
+            cursor = header.prev_hash;
+        }
+        // Oldest first, so every header's skip target is already indexed
+        for header in chain.into_iter().rev() {
+            index.insert(header);
+        }
+        if index.contains(&best) {
+            index.set_best(&best);
+        }
+        Ok(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, hash: &[u8; 32]) -> bool {
+        self.nodes.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &[u8; 32]) -> Option<IndexedHeader> {
+        self.nodes.get(hash).map(|node| node.header)
+    }
+
+    pub fn best(&self) -> Option<[u8; 32]> {
+        self.best
+    }
+
+    /// Headers with no indexed child: the best tip and competing forks.
+    pub fn tips(&self) -> Vec<[u8; 32]> {
+        self.tips.iter().copied().collect()
+    }
+
+    /// Add a header. Returns false if it was already indexed.
+    pub fn insert(&mut self, header: IndexedHeader) -> bool {
+        if self.nodes.contains_key(&header.hash) {
+            return false;
+        }
+        let skip = if header.height >= 2 {
+            match self.walk(header.prev_hash, skip_height(header.height)) {
+                Walk::Found(hash) => Some(hash),
+                Walk::Missing(_) | Walk::Above => None,
+            }
+        } else {
+            None
+        };
+
+        let has_child = self
+            .by_height
+            .get(&(header.height + 1))
+            .is_some_and(|hashes| {
+                hashes.iter().any(|h| {
+                    self.nodes
+                        .get(h)
+                        .is_some_and(|n| n.header.prev_hash == header.hash)
+                })
+            });
+        self.tips.remove(&header.prev_hash);
+        if !has_child {
+            self.tips.insert(header.hash);
+        }
+        self.by_height
+            .entry(header.height)
+            .or_default()
+            .push(header.hash);
+        self.nodes.insert(header.hash, IndexNode { header, skip });
+        true
+    }
+
+    /// Record the new best tip after a connect, disconnect or reorg and drop
+    /// headers, including stale fork tips, that fell out of the retained
+    /// window.
+    pub fn set_best(&mut self, hash: &[u8; 32]) {
+        self.best = Some(*hash);
+        let Some(height) = self.get(hash).map(|h| h.height) else {
+            return;
+        };
+        let floor = height.saturating_sub(self.depth);
+        let retained = self.by_height.split_off(&floor);
+        let expired = std::mem::replace(&mut self.by_height, retained);
+        for hash in expired.into_values().flatten() {
+            self.nodes.remove(&hash);
+            self.tips.remove(&hash);
+        }
+    }
+
+    /// The header for `hash`, loading it from storage if needed.
+    pub fn header<E>(
+        &mut self,
+        hash: &[u8; 32],
+        mut load: impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, E>,
+    ) -> Result<Option<IndexedHeader>, E> {
+        if let Some(header) = self.get(hash) {
+            return Ok(Some(header));
+        }
+        let Some(header) = load(hash)? else {
+            return Ok(None);
+        };
+        self.insert(header);
+        Ok(Some(header))
+    }
+
+    /// The ancestor of `hash` at `height` (the header itself at its own
+    /// height). `None` if `height` is above it or the chain is incomplete.
+    pub fn ancestor_at_height<E>(
+        &mut self,
+        hash: &[u8; 32],
+        height: u64,
+        mut load: impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, E>,
+    ) -> Result<Option<IndexedHeader>, E> {
+        let mut cursor = *hash;
+        loop {
+            match self.walk(cursor, height) {
+                Walk::Found(found) => return Ok(self.get(&found)),
+                Walk::Above => return Ok(None),
+                Walk::Missing(missing) => {
+                    let Some(header) = load(&missing)? else {
+                        return Ok(None);
+                    };
+                    self.insert(header);
+                    cursor = missing;
+                }
+            }
+        }
+    }
+
+    /// Median timestamp of the `MEDIAN_TIME_SPAN` headers ending at `hash`,
+    /// or of as many as are available near genesis or a gap in storage.
+    pub fn median_time_past<E>(
+        &mut self,
+        hash: &[u8; 32],
+        mut load: impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, E>,
+    ) -> Result<Option<u64>, E> {
+        let mut timestamps = Vec::with_capacity(MEDIAN_TIME_SPAN);
+        let mut cursor = *hash;
+        while timestamps.len() < MEDIAN_TIME_SPAN {
+            let Some(header) = self.header(&cursor, &mut load)? else {
+                break;
+            };
+            timestamps.push(header.timestamp);
+            if header.height == 0 || header.prev_hash == [0u8; 32] {
+                break;
+            }
+            cursor = header.prev_hash;
+        }
+        if timestamps.is_empty() {
+            return Ok(None);
+        }
+        timestamps.sort_unstable();
+        Ok(Some(timestamps[timestamps.len() / 2]))
+    }
+
+    /// Last common ancestor of `a` and `b`. Searches down from the lower of
+    /// the two heights in doubling steps, then bisects, so only about twice
+    /// the fork depth is ever visited.
+    pub fn fork_point<E>(
+        &mut self,
+        a: &[u8; 32],
+        b: &[u8; 32],
+        mut load: impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, E>,
+    ) -> Result<Option<IndexedHeader>, E> {
+        let (Some(header_a), Some(header_b)) =
+            (self.header(a, &mut load)?, self.header(b, &mut load)?)
+        else {
+            return Ok(None);
+        };
+        let top = header_a.height.min(header_b.height);
+
+        let mut shared = |index: &mut Self, height: u64| -> Result<Option<IndexedHeader>, E> {
+            let left = index.ancestor_at_height(a, height, &mut load)?;
+            let right = index.ancestor_at_height(b, height, &mut load)?;
+            Ok(match (left, right) {
+                (Some(l), Some(r)) if l.hash == r.hash => Some(l),
+                _ => None,
+            })
+        };
+
+        if let Some(header) = shared(self, top)? {
+            return Ok(Some(header));
+        }
+        // Invariant: the branches differ at `differ`, agree at `agree`
+        let mut differ = top;
+        let mut step = 1;
+        let mut agree = loop {
+            if differ == 0 {
+                return Ok(None);
+            }
+            let candidate = differ.saturating_sub(step);
+            if let Some(header) = shared(self, candidate)? {
+                break header;
+            }
+            differ = candidate;
+            step *= 2;
+        };
+        while differ - agree.height > 1 {
+            let mid = agree.height + (differ - agree.height) / 2;
+            match shared(self, mid)? {
+                Some(header) => agree = header,
+                None => differ = mid,
+            }
+        }
+        Ok(Some(agree))
+    }
+
+    /// Walk from `from` to its ancestor at `height` using only indexed
+    /// headers, taking a skip pointer whenever it does not overshoot.
+    fn walk(&self, from: [u8; 32], height: u64) -> Walk {
+        let mut cursor = from;
+        loop {
+            let Some(node) = self.nodes.get(&cursor) else {
+                return Walk::Missing(cursor);
+            };
+            let current = node.header.height;
+            if current == height {
+                return Walk::Found(cursor);
+            }
+            if current < height {
+                return Walk::Above;
+            }
+            let skip = skip_height(current);
+            let skip_prev = skip_height(current - 1);
+            let use_skip =
+                skip == height || (skip > height && !(skip_prev + 2 < skip && skip_prev >= height));
+            cursor = match node.skip {
+                Some(target) if use_skip && self.nodes.contains_key(&target) => target,
+                _ => node.header.prev_hash,
+            };
+        }
+    }
+}
+
+impl Default for HeaderIndex {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEADER_INDEX_DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn hash_for(branch: u8, height: u64) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash[0] = branch;
+        hash[1..9].copy_from_slice(&height.to_be_bytes());
+        hash
+    }
+
+    /// A chain of `len` headers on `branch` starting above `parent`.
+    fn chain(branch: u8, parent: Option<IndexedHeader>, len: u64) -> Vec<IndexedHeader> {
+        let mut out = Vec::new();
+        let mut prev = parent;
+        for _ in 0..len {
+            let height = prev.map_or(0, |p| p.height + 1);
+            let header = IndexedHeader {
+                hash: hash_for(branch, height),
+                prev_hash: prev.map_or([0u8; 32], |p| p.hash),
+                height,
+                timestamp: 1_000 + height * 150 + u64::from(branch),
+                version: 1,
+            };
+            out.push(header);
+            prev = Some(header);
+        }
+        out
+    }
+
+    fn store(headers: &[IndexedHeader]) -> HashMap<[u8; 32], IndexedHeader> {
+        headers.iter().map(|h| (h.hash, *h)).collect()
+    }
+
+    fn loader(
+        store: &HashMap<[u8; 32], IndexedHeader>,
+    ) -> impl FnMut(&[u8; 32]) -> Result<Option<IndexedHeader>, Infallible> + '_ {
+        move |hash| Ok(store.get(hash).copied())
+    }
+
+    fn slow_ancestor(
+        store: &HashMap<[u8; 32], IndexedHeader>,
+        hash: [u8; 32],
+        height: u64,
+    ) -> [u8; 32] {
+        let mut header = store[&hash];
+        while header.height > height {
+            header = store[&header.prev_hash];
+        }
+        header.hash
+    }
+
+    #[test]
+    fn ancestors_match_a_linear_walk() {
+        let main = chain(1, None, 1_000);
+        let db = store(&main);
+        let mut index = HeaderIndex::new(10_000);
+        for header in &main {
+            index.insert(*header);
+        }
+        let tip = main[999].hash;
+        for height in [0, 1, 2, 7, 255, 256, 511, 998, 999] {
+            let found = index
+                .ancestor_at_height(&tip, height, loader(&db))
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.hash, slow_ancestor(&db, tip, height));
+        }
+        assert_eq!(
+            index.ancestor_at_height(&tip, 1_000, loader(&db)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn median_time_past_uses_eleven_headers() {
+        let main = chain(1, None, 20);
+        let db = store(&main);
+        let mut index = HeaderIndex::new(100);
+        for header in &main {
+            index.insert(*header);
+        }
+        // Timestamps rise monotonically, so the median is the 6th newest
+        let mtp = index.median_time_past(&main[19].hash, loader(&db)).unwrap();
+        assert_eq!(mtp, Some(main[14].timestamp));
+        // Near genesis the median is over what exists
+        let mtp = index.median_time_past(&main[2].hash, loader(&db)).unwrap();
+        assert_eq!(mtp, Some(main[1].timestamp));
+    }
+
+    #[test]
+    fn fork_point_and_tips_survive_reorg() {
+        let main = chain(1, None, 800);
+        let fork = chain(2, Some(main[299]), 600);
+        let mut all = main.clone();
+        all.extend(fork.iter().copied());
+        let db = store(&all);
+
+        let mut index = HeaderIndex::new(10_000);
+        for header in &main {
+            index.insert(*header);
+        }
+        index.set_best(&main[799].hash);
+        for header in &fork {
+            index.insert(*header);
+        }
+
+        let tips = index.tips();
+        assert_eq!(tips.len(), 2);
+        assert!(tips.contains(&main[799].hash) && tips.contains(&fork[599].hash));
+
+        let point = index
+            .fork_point(&main[799].hash, &fork[599].hash, loader(&db))
+            .unwrap()
+            .unwrap();
+        assert_eq!(point.hash, main[299].hash);
+
+        // Reorg onto the fork: the old tip stays queryable as a competing tip
+        index.set_best(&fork[599].hash);
+        let old = index
+            .ancestor_at_height(&main[799].hash, 400, loader(&db))
+            .unwrap()
+            .unwrap();
+        assert_eq!(old.hash, main[400].hash);
+        let new = index
+            .ancestor_at_height(&fork[599].hash, 400, loader(&db))
+            .unwrap()
+            .unwrap();
+        assert_eq!(new.hash, fork[100].hash);
+    }
+
+    #[test]
+    fn pruned_and_rebuilt_indexes_backfill_from_storage() {
+        let main = chain(1, None, 500);
+        let db = store(&main);
+        let tip = main[499].hash;
+
+        let mut index = HeaderIndex::new(50);
+        for header in &main {
+            index.insert(*header);
+        }
+        index.set_best(&tip);
+        assert!(index.len() <= 51);
+        assert!(!index.contains(&main[10].hash));
+
+        let deep = index
+            .ancestor_at_height(&tip, 10, loader(&db))
+            .unwrap()
+            .unwrap();
+        assert_eq!(deep.hash, main[10].hash);
+
+        let mut rebuilt = HeaderIndex::rebuild(tip, 50, loader(&db)).unwrap();
+        assert_eq!(rebuilt.best(), Some(tip));
+        assert_eq!(rebuilt.len(), 51);
+        for height in [0, 10, 449, 450, 499] {
+            let found = rebuilt
+                .ancestor_at_height(&tip, height, loader(&db))
+                .unwrap()
+                .unwrap();
+            assert_eq!(found.hash, main[height as usize].hash);
+        }
+    }
+}
@@ -54,6 +54,17 @@ pub enum JournalEntry {
         hash: [u8; 32],
         timestamp: u64,
     },
+    /// Tip move about to be committed
+    TipChange(TipIntent),
+}
+
+/// The tip a commit moves from and to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TipIntent {
+    pub from_height: u64,
+    pub from_hash: [u8; 32],
+    pub to_height: u64,
+    pub to_hash: [u8; 32],
 }
 
 /// WAL entry with metadata
@@ -488,6 +499,67 @@ impl WriteAheadLog {
     }
 }
 
+/// Single-record journal of the tip change being committed
+///
+/// The record is synced to disk before the commit and removed once it
+/// lands, so a record found at startup marks a commit interrupted by a
+/// crash.
+pub struct TipJournal {
+    path: PathBuf,
+}
+
+impl TipJournal {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Durably record `intent`, replacing any earlier record
+    pub fn begin(&self, intent: TipIntent) -> Result<(), WalError> {
+        let data = bincode::serialize(&WalEntry::new(0, JournalEntry::TipChange(intent)))?;
+        let staging = self.path.with_extension("tmp");
+        {
+            let mut file = File::create(&staging)?;
+            file.write_all(&(data.len() as u32).to_le_bytes())?;
+            file.write_all(&data)?;
+            file.sync_all()?;
+        }
+        std::fs::rename(&staging, &self.path)?;
+        Ok(())
+    }
+
+    /// Drop the record once its commit landed or was abandoned
+    pub fn complete(&self) -> Result<(), WalError> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The record left behind by an interrupted commit
+    pub fn pending(&self) -> Result<Option<TipIntent>, WalError> {
+        let bytes = match std::fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let size = bytes
+            .get(..4)
+            .and_then(|prefix| prefix.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(WalError::FileCorrupted)?;
+        if bytes.len() != 4 + size as usize {
+            return Err(WalError::FileCorrupted);
+        }
+        let entry: WalEntry = bincode::deserialize(&bytes[4..])?;
+        match entry.entry {
+            JournalEntry::TipChange(intent) if entry.verify_checksum() => Ok(Some(intent)),
+            _ => Err(WalError::FileCorrupted),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -619,4 +691,31 @@ mod tests {
         let result = WriteAheadLog::new(wal_path).await;
         assert!(matches!(result, Err(WalError::CorruptedEntry { .. })));
     }
+
+    #[test]
+    fn test_tip_journal_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = TipJournal::new(temp_dir.path().join("tip.journal"));
+        assert_eq!(journal.pending().unwrap(), None);
+
+        let intent = TipIntent {
+            from_height: 7,
+            from_hash: [7u8; 32],
+            to_height: 8,
+            to_hash: [8u8; 32],
+        };
+        journal.begin(intent).unwrap();
+        assert_eq!(journal.pending().unwrap(), Some(intent));
+        journal.complete().unwrap();
+        assert_eq!(journal.pending().unwrap(), None);
+        journal.complete().unwrap();
+
+        // A torn record is reported, not mistaken for a clean journal
+        journal.begin(intent).unwrap();
+        let path = temp_dir.path().join("tip.journal");
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(journal.pending(), Err(WalError::FileCorrupted)));
+    }
 }
@@ -2,22 +2,32 @@
 //
 // This module handles persistence of blockchain data and related functionality
 
+pub mod address_index;
 pub mod atomic_utxo_set;
 pub mod backup;
+pub mod block_stats;
 pub mod checkpoint;
 pub mod checksum;
+pub mod compaction;
 pub mod corruption;
 pub mod database;
 pub mod database_shutdown;
+pub mod header_index;
 pub mod integrity;
 pub mod journal;
 pub mod memory;
 pub mod persistence;
 pub mod reorg;
+pub mod snapshot;
+pub mod state_snapshot;
 pub mod traits;
 pub mod transaction_index;
+pub mod txindex;
+pub mod undo;
 pub mod utxo_cache;
+pub mod utxo_commitment;
 pub mod utxo_set;
+pub mod utxo_stats;
 
 #[cfg(test)]
 pub mod database_shutdown_tests;
@@ -25,16 +35,22 @@ pub mod database_shutdown_tests;
 #[cfg(test)]
 mod utxo_attack_tests;
 
+pub use address_index::{
+    AddressBalance, AddressHistory, AddressTransaction, AddressUtxo, AddressUtxos,
+};
 pub use atomic_utxo_set::{AtomicUtxoSet, OutPoint, UnspentOutput, UtxoLockManager, UtxoTransaction};
 pub use backup::{
-    BackupError, BackupManager, BackupMode, BackupOperation, BackupState, RecoveryManager,
+    BackupError, BackupManager, BackupMode, BackupOperation, BackupState, ConsistencyReport,
+    RecoveryManager,
 };
+pub use block_stats::BlockStatsRecord;
 pub use checkpoint::{CheckpointConfig, CheckpointError, CheckpointManager, CheckpointType};
 pub use checksum::{
     calculate_block_checksum, calculate_crc32, calculate_sha256, calculate_utxo_checksum,
     verify_block_checksum, verify_crc32, verify_sha256, verify_utxo_checksum, ChecksumError,
     ChecksummedData, StreamingChecksum,
 };
+pub use compaction::{CompactionRecord, StorageMaintenance, StorageStats, TreeStats};
 pub use corruption::{
     CorruptionError, CorruptionHandler, CorruptionInfo, CorruptionType, IntegrityChecker,
     RepairPlan,
@@ -43,15 +59,28 @@ pub use database::{
     BlockchainDB, BlockchainDBConfig, IntegrityCheckLevel, IntegrityCheckResult, StorageError,
 };
 pub use database_shutdown::{DatabaseShutdownHandler, DatabaseStartupHandler, ShutdownConfig};
-pub use journal::{JournalEntry, WalError, WriteAheadLog};
+pub use header_index::{HeaderIndex, IndexedHeader};
+pub use journal::{JournalEntry, TipIntent, TipJournal, WalError, WriteAheadLog};
 pub use memory::MemoryStorage;
-pub use persistence::ChainState;
+pub use persistence::{ChainState, DeploymentStatus, TipChange};
+pub use snapshot::{
+    export_snapshot, import_snapshot, read_manifest, SnapshotError, SnapshotManifest,
+    SnapshotNetwork, SnapshotPhase, SnapshotProgress,
+};
+pub use state_snapshot::{
+    SnapshotRestore, StateSnapshotError, StateSnapshotManifest, StateSnapshotStore,
+    TrustedSnapshot,
+};
 pub use traits::Storage;
 pub use transaction_index::{
     BlockLocation, IndexStatistics, IndexedTransaction, TransactionIndexConfig, TransactionIndexer,
     TransactionIndexError,
 };
+pub use txindex::{ConfirmedTransaction, TxIndexBuilder, TxLocation};
+pub use undo::{BlockUndo, SpentOutput, UndoFormatError};
 pub use utxo_cache::{
     CacheEntry, CacheEntryState, CacheStatistics, PruningConfig, UtxoCache, UtxoCacheConfig,
     UtxoSnapshot, load_from_snapshot,
 };
+pub use utxo_commitment::{UtxoAccumulator, UtxoIntegrityReport};
+pub use utxo_stats::{ClassTotals, SupplyAudit, UtxoStats};
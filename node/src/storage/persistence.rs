@@ -1,21 +1,37 @@
+use super::address_index::{self, Prevouts};
 use super::database::{create_utxo_key, BlockchainDB, StorageError};
+use super::header_index::{HeaderIndex, IndexedHeader, DEFAULT_HEADER_INDEX_DEPTH};
 use super::reorg::ReorgChangeSet;
+use super::txindex::{self, ConfirmedTransaction};
+use super::undo::{self, DEFAULT_UNDO_DEPTH};
 use supernova_core::consensus::chainwork::{self, Work};
 use supernova_core::consensus::difficulty_retarget::{self, RetargetParams};
-use supernova_core::types::block::Block;
+use supernova_core::consensus::version_bits::{
+    self, ChainEntry, Deployment, SignalingStats, ThresholdState, VersionBitsCache,
+    VersionBitsChain, VersionBitsParams,
+};
+use supernova_core::types::block::{Block, BlockHeader};
 use supernova_core::types::block_subsidy;
+use supernova_core::types::timelock::{FinalityContext, InputAge};
 use supernova_core::types::transaction::{Transaction, TransactionOutput};
-use crate::blockchain::checkpoint::{validate_checkpoint, can_reorganize_below};
-use crate::blockchain::invalidation::{InvalidBlockTracker, InvalidBlockTrackerConfig, InvalidationReason};
+use supernova_core::validation::trace::redact_bytes;
+use supernova_core::validation::{SignatureCache, Tracer, ValidationTrace, CONSENSUS_VERIFY_FLAGS};
+use crate::blockchain::checkpoint::{can_reorganize_below, min_rollback_height, validate_checkpoint};
+use crate::blockchain::invalidation::{
+    InvalidBlock, InvalidBlockTracker, InvalidBlockTrackerConfig, InvalidationReason,
+};
+use crate::metrics::registry::ConsensusMetrics;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::sync::Arc;
 use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{debug, info, warn};
 
-const MAX_REORG_DEPTH: u64 = 100;
+pub(crate) const MAX_REORG_DEPTH: u64 = 100;
 const MAX_FORK_DISTANCE: u64 = 6;
 const FORK_CHOICE_WINDOW: u64 = 10;
 const STALE_TIP_THRESHOLD: Duration = Duration::from_secs(3600);
@@ -43,9 +59,45 @@ pub struct ChainState {
     last_block_time: SystemTime,
     rejected_reorgs: u64,
     invalid_block_tracker: Arc<InvalidBlockTracker>,
+    /// Blocks that went through full validation, i.e. were not rejected
+    /// from the invalid-block cache
+    validation_runs: Arc<AtomicU64>,
+    /// Blocks accepted after signature verification
+    fully_verified_blocks: Arc<AtomicU64>,
+    /// Blocks accepted without signature verification under assumevalid
+    assumed_valid_blocks: Arc<AtomicU64>,
+    /// Assumevalid block: it and its ancestors skip signature verification
+    assume_valid: Option<[u8; 32]>,
+    /// Signatures known to verify, usually shared with the mempool
+    signature_cache: Arc<SignatureCache>,
+    /// Signature rule flags, see [`supernova_core::validation::sig_cache`]
+    verify_flags: u32,
     /// Per-network consensus parameters (difficulty floor, retarget interval,
     /// block time) — the validator's source of truth for required difficulty.
     retarget_params: RetargetParams,
+    /// Version-bits deployment schedule for this network
+    version_bits: VersionBitsParams,
+    /// Deployment states per signaling window, keyed by block hash so clones
+    /// and competing branches can share it
+    version_bits_cache: Arc<parking_lot::Mutex<VersionBitsCache>>,
+    /// Recent headers and competing tips for ancestor, MTP and fork-point
+    /// queries without a block read per step
+    header_index: Arc<parking_lot::Mutex<HeaderIndex>>,
+    /// Record a validation trace for each rejected block
+    validation_tracing: bool,
+    /// Blocks below the tip whose undo data is kept
+    undo_depth: u64,
+    /// Raw block bytes kept in prune mode; `None` keeps every block
+    prune_target: Option<u64>,
+}
+
+/// A deployment with its state for the next block and, while signaling is
+/// open, the current window's progress
+#[derive(Debug, Clone)]
+pub struct DeploymentStatus {
+    pub deployment: Deployment,
+    pub state: ThresholdState,
+    pub stats: Option<SignalingStats>,
 }
 
 #[derive(Debug)]
@@ -94,6 +146,43 @@ pub enum ForkChoiceReason {
     ManualSelection,
 }
 
+/// Tree persisting operator invalidations: block hash -> (parent hash, height)
+const MANUAL_INVALIDATIONS_TREE: &str = "manual_invalidations";
+
+/// Tree persisting validation failures and descendant markers:
+/// block hash -> `InvalidBlock`
+const INVALID_BLOCKS_TREE: &str = "invalid_blocks";
+
+/// Tree persisting traces of the most recent validation failures:
+/// block hash -> JSON `RejectionTrace`
+const REJECTION_TRACES_TREE: &str = "rejection_traces";
+
+/// Rejection traces kept in `REJECTION_TRACES_TREE`; older ones are evicted
+const MAX_REJECTION_TRACES: usize = 64;
+
+/// The checks run against a block that failed validation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectionTrace {
+    pub block_hash: [u8; 32],
+    pub height: u64,
+    /// Unix timestamp (seconds) of the rejection
+    pub rejected_at: u64,
+    /// Insertion order, for evicting the oldest trace
+    pub sequence: u64,
+    pub trace: ValidationTrace,
+}
+
+/// Result of an operator rollback, invalidation or reconsideration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TipChange {
+    pub old_height: u64,
+    pub old_tip: [u8; 32],
+    pub new_height: u64,
+    pub new_tip: [u8; 32],
+    /// Blocks invalidated (rollback, invalidate) or cleared (reconsider)
+    pub affected_blocks: Vec<[u8; 32]>,
+}
+
 impl ChainState {
     /// Open chain state with the default (launch-network = testnet) consensus
     /// parameters. Defaulting to the HARD difficulty floor is the consensus-safe
@@ -171,6 +260,42 @@ impl ChainState {
             hex::encode(&best_block_hash[..8])
         );
 
+        // Operator invalidations and validation failures survive restarts, so
+        // a re-announced invalid block is never downloaded or validated again.
+        let invalid_block_tracker = Arc::new(InvalidBlockTracker::new(InvalidBlockTrackerConfig::default()));
+        for entry in db.open_tree(INVALID_BLOCKS_TREE)?.iter() {
+            let (_, value) = entry?;
+            let record: InvalidBlock = bincode::deserialize(&value)?;
+            invalid_block_tracker
+                .restore(record)
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+        for entry in db.open_tree(MANUAL_INVALIDATIONS_TREE)?.iter() {
+            let (key, value) = entry?;
+            let Ok(hash) = <[u8; 32]>::try_from(key.as_ref()) else {
+                continue;
+            };
+            let (parent, height): ([u8; 32], u64) = bincode::deserialize(&value)?;
+            invalid_block_tracker
+                .mark_manually_invalid(hash, Some(parent), Some(height))
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Rebuilt from storage on every start so it can never disagree with
+        // the persisted best chain. Like the backfill above, a read failure is
+        // non-fatal: an empty index fills itself from storage on demand.
+        let header_index = if best_block_hash == [0u8; 32] {
+            HeaderIndex::new(DEFAULT_HEADER_INDEX_DEPTH)
+        } else {
+            HeaderIndex::rebuild(best_block_hash, DEFAULT_HEADER_INDEX_DEPTH, |hash| {
+                stored_header(&db, hash)
+            })
+            .unwrap_or_else(|e| {
+                tracing::warn!("Header index rebuild failed (non-fatal): {:?}", e);
+                HeaderIndex::new(DEFAULT_HEADER_INDEX_DEPTH)
+            })
+        };
+
         Ok(Self {
             db,
             current_height,
@@ -182,8 +307,20 @@ impl ChainState {
             active_forks: HashMap::new(),
             last_block_time: SystemTime::now(),
             rejected_reorgs: 0,
-            invalid_block_tracker: Arc::new(InvalidBlockTracker::new(InvalidBlockTrackerConfig::default())),
+            invalid_block_tracker,
+            validation_runs: Arc::new(AtomicU64::new(0)),
+            fully_verified_blocks: Arc::new(AtomicU64::new(0)),
+            assumed_valid_blocks: Arc::new(AtomicU64::new(0)),
+            assume_valid: None,
+            signature_cache: Arc::new(SignatureCache::default()),
+            verify_flags: CONSENSUS_VERIFY_FLAGS,
             retarget_params,
+            version_bits: VersionBitsParams::default(),
+            version_bits_cache: Arc::new(parking_lot::Mutex::new(VersionBitsCache::new())),
+            header_index: Arc::new(parking_lot::Mutex::new(header_index)),
+            validation_tracing: false,
+            undo_depth: DEFAULT_UNDO_DEPTH,
+            prune_target: None,
         })
     }
 
@@ -195,6 +332,41 @@ impl ChainState {
         self.best_block_hash
     }
 
+    /// Look a best-chain transaction up in the transaction index. `None`
+    /// when the index is off, not built that far yet, or the transaction
+    /// is not in the best chain.
+    pub fn get_transaction_by_id(
+        &self,
+        txid: &[u8; 32],
+    ) -> Result<Option<ConfirmedTransaction>, StorageError> {
+        let Some(location) = self.db.get_tx_location(txid)? else {
+            return Ok(None);
+        };
+        let Some(block) = self.db.get_block(&location.block_hash)? else {
+            return Ok(None);
+        };
+        // The rebuild can race a reorg and index a block that just left
+        if self.db.get_block_hash_by_height(block.height())? != Some(location.block_hash) {
+            return Ok(None);
+        }
+        let Some(transaction) = block
+            .transactions()
+            .get(location.offset as usize)
+            .filter(|tx| tx.hash() == *txid)
+            .cloned()
+        else {
+            return Ok(None);
+        };
+        Ok(Some(ConfirmedTransaction {
+            transaction,
+            block_hash: location.block_hash,
+            block_height: block.height(),
+            offset: location.offset,
+            block_time: block.timestamp(),
+            confirmations: self.current_height.saturating_sub(block.height()) + 1,
+        }))
+    }
+
     /// The per-network consensus parameters this chain enforces (difficulty
     /// floor, retarget interval, block time). The miner and the difficulty gate
     /// both read these so they agree on the required difficulty.
@@ -202,11 +374,313 @@ impl ChainState {
         self.retarget_params
     }
 
+    /// The version-bits deployment schedule this chain tracks
+    pub fn version_bits_params(&self) -> &VersionBitsParams {
+        &self.version_bits
+    }
+
+    /// Replace the deployment schedule (e.g. regtest in tests). Cached states
+    /// were computed under the old schedule, so they are dropped.
+    pub fn set_version_bits_params(&mut self, params: VersionBitsParams) {
+        self.version_bits = params;
+        self.version_bits_cache.lock().clear();
+    }
+
+    /// Record a validation trace for every block that fails full
+    /// validation, readable through `rejection_trace`
+    pub fn set_validation_tracing(&mut self, enabled: bool) {
+        self.validation_tracing = enabled;
+    }
+
+    pub fn validation_tracing(&self) -> bool {
+        self.validation_tracing
+    }
+
+    /// Skip signature verification for `hash` and its ancestors; UTXO,
+    /// value, merkle and PoW checks still run. `None` verifies every block.
+    pub fn set_assume_valid(&mut self, hash: Option<[u8; 32]>) {
+        self.assume_valid = hash;
+    }
+
+    pub fn assume_valid(&self) -> Option<[u8; 32]> {
+        self.assume_valid
+    }
+
+    /// Answer signature checks from `cache`, typically the one the mempool
+    /// fills as it admits transactions
+    pub fn set_signature_cache(&mut self, cache: Arc<SignatureCache>) {
+        self.signature_cache = cache;
+    }
+
+    pub fn signature_cache(&self) -> &Arc<SignatureCache> {
+        &self.signature_cache
+    }
+
+    /// Verify signatures under `flags` instead of [`CONSENSUS_VERIFY_FLAGS`]
+    pub fn set_verify_flags(&mut self, flags: u32) {
+        self.verify_flags = flags;
+    }
+
+    /// Keep undo data for the last `depth` blocks below the tip
+    pub fn set_undo_depth(&mut self, depth: u64) {
+        self.undo_depth = depth;
+    }
+
+    /// Drop undo data that has fallen more than `undo_depth` blocks below
+    /// the tip. Undo data only speeds up disconnects, so failures only warn.
+    fn prune_undo(&self) {
+        let below = self.current_height.saturating_sub(self.undo_depth);
+        if let Err(e) = self.db.prune_undo(below) {
+            warn!("Failed to prune undo data below height {}: {}", below, e);
+        }
+    }
+
+    /// Keep at most `bytes` of raw blocks, pruning older ones. `None`
+    /// keeps every block.
+    pub fn set_prune_target(&mut self, bytes: Option<u64>) {
+        self.prune_target = bytes;
+    }
+
+    pub fn prune_target(&self) -> Option<u64> {
+        self.prune_target
+    }
+
+    /// Re-read the tip from the database after it was replaced underneath
+    /// this chain state, as a state snapshot restore does
+    pub fn reload_tip(&mut self) -> Result<(), StorageError> {
+        let reloaded = Self::with_params(Arc::clone(&self.db), self.retarget_params)?;
+        self.current_height = reloaded.current_height;
+        self.best_block_hash = reloaded.best_block_hash;
+        *self.header_index.lock() = std::mem::replace(
+            &mut *reloaded.header_index.lock(),
+            HeaderIndex::new(DEFAULT_HEADER_INDEX_DEPTH),
+        );
+        self.chain_work.clear();
+        self.fork_points.clear();
+        self.active_forks.clear();
+        Ok(())
+    }
+
+    /// In prune mode, drop the raw blocks and undo data beyond the prune
+    /// target. Blocks within the reorg window are always kept, so nothing is
+    /// pruned until the chain is deeper than it. Failures only warn.
+    fn prune_blocks(&self) {
+        let Some(target) = self.prune_target else {
+            return;
+        };
+        let keep = self.undo_depth.max(MAX_REORG_DEPTH);
+        if let Err(e) = self
+            .db
+            .prune_height_for_target(self.current_height, keep, target)
+            .and_then(|height| self.db.prune_blocks(height))
+        {
+            warn!("Failed to prune blocks: {}", e);
+        }
+    }
+
+    /// Validation trace recorded when `hash` was rejected, if it is among
+    /// the last `MAX_REJECTION_TRACES` rejections traced
+    pub fn rejection_trace(&self, hash: &[u8; 32]) -> Result<Option<RejectionTrace>, StorageError> {
+        match self.db.open_tree(REJECTION_TRACES_TREE)?.get(hash)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Persist the trace of a rejected block, evicting the oldest traces
+    /// beyond `MAX_REJECTION_TRACES`
+    fn store_rejection_trace(
+        &self,
+        block: &Block,
+        trace: ValidationTrace,
+    ) -> Result<(), StorageError> {
+        let tree = self.db.open_tree(REJECTION_TRACES_TREE)?;
+        let mut stored = Vec::new();
+        for entry in tree.iter() {
+            let (key, value) = entry?;
+            let record: RejectionTrace = serde_json::from_slice(&value)?;
+            stored.push((record.sequence, key));
+        }
+        stored.sort_by_key(|(sequence, _)| *sequence);
+
+        let block_hash = block.hash();
+        let record = RejectionTrace {
+            block_hash,
+            height: block.height(),
+            rejected_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            sequence: stored.last().map_or(0, |(sequence, _)| sequence + 1),
+            trace,
+        };
+        tree.insert(block_hash, serde_json::to_vec(&record)?)?;
+
+        stored.retain(|(_, key)| key.as_ref() != block_hash.as_slice());
+        let excess = (stored.len() + 1).saturating_sub(MAX_REJECTION_TRACES);
+        for (_, key) in stored.into_iter().take(excess) {
+            tree.remove(key)?;
+        }
+        Ok(())
+    }
+
+    /// State of deployment `name` for `block`, computed along the block's own
+    /// ancestry. Validation rules gate on this rather than on fixed heights.
+    pub fn deployment_state_for_block(
+        &self,
+        name: &str,
+        block: &Block,
+    ) -> Result<ThresholdState, StorageError> {
+        let prev = *block.prev_block_hash();
+        let parent = (prev != [0u8; 32]).then_some(prev);
+        self.version_bits_cache
+            .lock()
+            .state_by_name(&self.version_bits, name, self, parent.as_ref())
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))
+    }
+
+    /// Whether deployment `name` is active for `block`
+    pub fn is_deployment_active(&self, name: &str, block: &Block) -> Result<bool, StorageError> {
+        Ok(self.deployment_state_for_block(name, block)? == ThresholdState::Active)
+    }
+
+    /// Every deployment's state for the block after the current tip
+    pub fn deployments(&self) -> Result<Vec<DeploymentStatus>, StorageError> {
+        let parent = self.tip_parent();
+        let mut cache = self.version_bits_cache.lock();
+        self.version_bits
+            .deployments
+            .iter()
+            .map(|deployment| {
+                let state = cache
+                    .state(&self.version_bits, deployment, self, parent.as_ref())
+                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+                let stats = if state == ThresholdState::Started {
+                    let stats = version_bits::signaling_stats(
+                        &self.version_bits,
+                        deployment,
+                        self,
+                        parent.as_ref(),
+                    )
+                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+                    Some(stats)
+                } else {
+                    None
+                };
+                Ok(DeploymentStatus {
+                    deployment: deployment.clone(),
+                    state,
+                    stats,
+                })
+            })
+            .collect()
+    }
+
+    /// Header version for the block after the current tip, signaling for the
+    /// deployments in `signal` that are open
+    pub fn block_version(&self, signal: &[String]) -> Result<u32, StorageError> {
+        let parent = self.tip_parent();
+        version_bits::compute_block_version(
+            &mut self.version_bits_cache.lock(),
+            &self.version_bits,
+            self,
+            parent.as_ref(),
+            signal,
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))
+    }
+
+    /// The tip as the parent of the next block; `None` before genesis
+    fn tip_parent(&self) -> Option<[u8; 32]> {
+        (self.best_block_hash != [0u8; 32]).then_some(self.best_block_hash)
+    }
+
     /// Get the invalid block tracker
     pub fn invalid_block_tracker(&self) -> Arc<InvalidBlockTracker> {
         self.invalid_block_tracker.clone()
     }
 
+    /// Known-invalid blocks and descendant markers, most recent first
+    pub fn invalid_blocks(&self) -> Vec<InvalidBlock> {
+        self.invalid_block_tracker.list()
+    }
+
+    /// Number of blocks that went through full validation since start
+    pub fn validation_runs(&self) -> u64 {
+        self.validation_runs.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Blocks that passed validation with their signatures verified
+    pub fn fully_verified_blocks(&self) -> u64 {
+        self.fully_verified_blocks.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Blocks that passed validation with signatures assumed valid
+    pub fn assumed_valid_blocks(&self) -> u64 {
+        self.assumed_valid_blocks.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Whether `block` is the assumevalid block or one of its ancestors.
+    /// Before the assumevalid header is known every block is verified.
+    fn is_assumed_valid(&self, block: &Block) -> Result<bool, StorageError> {
+        let Some(assume_valid) = self.assume_valid else {
+            return Ok(false);
+        };
+        let block_hash = block.hash();
+        if block_hash == assume_valid {
+            return Ok(true);
+        }
+        Ok(self
+            .ancestor_at_height(&assume_valid, block.height())?
+            .is_some_and(|ancestor| ancestor.hash == block_hash))
+    }
+
+    /// Check an announced block or header against the invalid-block cache
+    /// before downloading or validating it. A block whose parent is known
+    /// invalid is recorded as invalid itself, so its own children are
+    /// refused in turn.
+    pub fn known_invalid(&self, header: &BlockHeader) -> Result<Option<InvalidBlock>, StorageError> {
+        let hash = header.hash();
+        if let Some(record) = self
+            .invalid_block_tracker
+            .get_invalid_block(&hash)
+            .filter(|r| r.permanent)
+        {
+            return Ok(Some(record));
+        }
+        let parent = *header.prev_block_hash();
+        if !self.invalid_block_tracker.is_permanently_invalid(&parent) {
+            return Ok(None);
+        }
+        let height = (header.height() > 0).then_some(header.height());
+        self.record_invalid(hash, parent, height, InvalidationReason::ParentInvalid)
+            .map(Some)
+    }
+
+    /// Record a block that failed validation, in memory and on disk
+    fn record_invalid(
+        &self,
+        hash: [u8; 32],
+        parent: [u8; 32],
+        height: Option<u64>,
+        reason: InvalidationReason,
+    ) -> Result<InvalidBlock, StorageError> {
+        let record = self
+            .invalid_block_tracker
+            .mark_permanently_invalid(hash, reason, Some(parent), height)
+            .map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+        self.db
+            .open_tree(INVALID_BLOCKS_TREE)?
+            .insert(hash, bincode::serialize(&record)?)?;
+        Ok(record)
+    }
+
+    /// Record `block` as having failed validation
+    fn reject_block(&self, block: &Block, reason: InvalidationReason) -> Result<(), StorageError> {
+        self.record_invalid(block.hash(), *block.prev_block_hash(), Some(block.height()), reason)
+            .map(|_| ())
+    }
+
     /// Initialize the chain state with a genesis block
     pub fn initialize_with_genesis(&mut self, genesis_block: Block) -> Result<(), StorageError> {
         // Check if already initialized
@@ -216,35 +690,27 @@ impl ChainState {
             ));
         }
 
-        // Store genesis block with bloom filter and cache updates
         let genesis_hash = genesis_block.hash();
         tracing::info!("Initializing genesis block {}", hex::encode(&genesis_hash[..8]));
         
         let genesis_data = bincode::serialize(&genesis_block)
             .map_err(|e| StorageError::DatabaseError(format!("Genesis serialization failed: {}", e)))?;
-        self.db.store_block(&genesis_hash, &genesis_data)?;
-        self.db.flush()?;
+
+        // Like any other connected block, genesis, its outputs, its height
+        // index entry (#5) and the tip metadata are committed together.
+        let mut changes = ReorgChangeSet::new();
+        changes.put_block(genesis_hash, genesis_data);
+        self.plan_connect_block(&genesis_block, &mut changes)?;
+        changes.put_meta(b"genesis_hash".to_vec(), genesis_hash.to_vec());
+        self.db.apply_tip_change(changes, 0, genesis_hash)?;
+        self.index_header(&genesis_block);
         
         tracing::info!("Genesis block stored successfully");
         
-        // Set genesis hash in metadata
-        self.db.store_metadata(b"genesis_hash", &genesis_hash)?;
-        
         // Initialize chain state for genesis
         self.best_block_hash = genesis_hash;
+        self.header_index.lock().set_best(&genesis_hash);
         self.current_height = 0; // Genesis is height 0
-        
-        // Store height as big-endian bytes (to match get_height() which reads as big-endian)
-        self.db.set_metadata(b"height", &self.current_height.to_be_bytes())?;
-        self.db.set_metadata(b"best_hash", &genesis_hash)?;
-        // Record genesis in the height->hash index (#5): every best-chain block
-        // must be queryable by height, and genesis is height 0.
-        self.db.store_block_height_index(self.current_height, &genesis_hash)?;
-
-        // Process genesis transactions to create initial UTXO set
-        self.process_block_transactions(&genesis_block)?;
-        
-        self.db.flush()?;
 
         Ok(())
     }
@@ -257,6 +723,164 @@ impl ChainState {
         Ok(())
     }
 
+    /// Disconnect blocks down to `height` through the reorg path and
+    /// invalidate every disconnected block so peers cannot re-offer them.
+    /// Refuses targets below the last embedded checkpoint.
+    pub async fn rollback_to(&mut self, height: u64) -> Result<TipChange, StorageError> {
+        if height >= self.current_height {
+            return Err(StorageError::DatabaseError(format!(
+                "Rollback target {} is not below the current height {}",
+                height, self.current_height
+            )));
+        }
+        let floor = min_rollback_height().map_err(|e| StorageError::CheckpointError(e.to_string()))?;
+        if height < floor {
+            return Err(StorageError::CheckpointError(format!(
+                "Refusing to roll back to height {} below the checkpoint at height {}",
+                height, floor
+            )));
+        }
+
+        let mut disconnected = Vec::new();
+        let mut current = self.get_block_at_height(self.current_height)?;
+        while current.height() > height {
+            let prev_hash = *current.prev_block_hash();
+            disconnected.push(current);
+            current = self
+                .db
+                .get_block(&prev_hash)?
+                .ok_or_else(|| StorageError::DatabaseError("Block not found".to_string()))?;
+        }
+        let new_tip = current;
+        let (old_height, old_tip) = (self.current_height, self.best_block_hash);
+
+        warn!(
+            "Operator rollback from height {} to {}: disconnecting {} blocks",
+            old_height,
+            height,
+            disconnected.len()
+        );
+        self.handle_chain_reorganization(
+            &new_tip,
+            new_tip.clone(),
+            Vec::new(),
+            disconnected.clone(),
+            ForkChoiceReason::ManualSelection,
+        )
+        .await?;
+
+        let mut affected_blocks = Vec::with_capacity(disconnected.len());
+        for block in disconnected.iter().rev() {
+            self.mark_manually_invalid(block)?;
+            affected_blocks.push(block.hash());
+        }
+
+        Ok(TipChange {
+            old_height,
+            old_tip,
+            new_height: self.current_height,
+            new_tip: self.best_block_hash,
+            affected_blocks,
+        })
+    }
+
+    /// Mark a block invalid. A block on the active chain is rolled back
+    /// together with its descendants; a side-chain block is only marked.
+    pub async fn invalidate_block(&mut self, hash: &[u8; 32]) -> Result<TipChange, StorageError> {
+        let block = self
+            .db
+            .get_block(hash)?
+            .ok_or_else(|| StorageError::KeyNotFound(hex::encode(hash)))?;
+
+        let on_active_chain = block.height() <= self.current_height
+            && self.db.get_block_hash_by_height(block.height())? == Some(*hash);
+        if on_active_chain {
+            let target = block.height().checked_sub(1).ok_or_else(|| {
+                StorageError::CheckpointError("Cannot invalidate the genesis block".to_string())
+            })?;
+            return self.rollback_to(target).await;
+        }
+
+        self.mark_manually_invalid(&block)?;
+        Ok(TipChange {
+            old_height: self.current_height,
+            old_tip: self.best_block_hash,
+            new_height: self.current_height,
+            new_tip: self.best_block_hash,
+            affected_blocks: vec![*hash],
+        })
+    }
+
+    /// Clear an invalidation of `hash` and its descendants, then switch to
+    /// the best cleared chain if it has more work than the tip.
+    ///
+    /// Works for operator invalidations and for cached validation failures;
+    /// the latter lets an operator recover from a validation bug after an
+    /// upgrade. Blocks that failed validation were never stored, so they are
+    /// validated again when next received.
+    pub async fn reconsider_block(&mut self, hash: &[u8; 32]) -> Result<TipChange, StorageError> {
+        let cleared = self
+            .invalid_block_tracker
+            .reconsider(hash)
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if cleared.is_empty() {
+            return Err(StorageError::KeyNotFound(format!(
+                "block {} is not invalidated",
+                hex::encode(hash)
+            )));
+        }
+
+        let tree = self.db.open_tree(MANUAL_INVALIDATIONS_TREE)?;
+        let failures = self.db.open_tree(INVALID_BLOCKS_TREE)?;
+        let mut candidate: Option<Block> = None;
+        for cleared_hash in &cleared {
+            tree.remove(cleared_hash)?;
+            failures.remove(cleared_hash)?;
+            if let Some(block) = self.db.get_block(cleared_hash)? {
+                if candidate.as_ref().map_or(true, |c| block.height() > c.height()) {
+                    candidate = Some(block);
+                }
+            }
+        }
+
+        let (old_height, old_tip) = (self.current_height, self.best_block_hash);
+        if let Some(candidate) = candidate {
+            let tip = self.get_block_at_height(self.current_height)?;
+            if self.calculate_chain_work(&candidate)? > self.calculate_chain_work(&tip)? {
+                let (fork_point, blocks_to_apply, blocks_to_disconnect) =
+                    self.find_fork_point(&candidate)?;
+                self.handle_chain_reorganization(
+                    &candidate,
+                    fork_point,
+                    blocks_to_apply,
+                    blocks_to_disconnect,
+                    ForkChoiceReason::ManualSelection,
+                )
+                .await?;
+            }
+        }
+
+        Ok(TipChange {
+            old_height,
+            old_tip,
+            new_height: self.current_height,
+            new_tip: self.best_block_hash,
+            affected_blocks: cleared,
+        })
+    }
+
+    fn mark_manually_invalid(&self, block: &Block) -> Result<(), StorageError> {
+        let hash = block.hash();
+        let parent = *block.prev_block_hash();
+        self.invalid_block_tracker
+            .mark_manually_invalid(hash, Some(parent), Some(block.height()))
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        self.db
+            .open_tree(MANUAL_INVALIDATIONS_TREE)?
+            .insert(hash, bincode::serialize(&(parent, block.height()))?)?;
+        Ok(())
+    }
+
     /// Get a block by hash
     pub fn get_block(&self, hash: &[u8; 32]) -> Option<Block> {
         self.db.get_block(hash).ok().flatten()
@@ -339,6 +963,31 @@ impl ChainState {
             .unwrap_or(self.retarget_params.pow_limit_bits)
     }
 
+    /// The difficulty target for a next block stamped `timestamp`: the network
+    /// floor when the chain has stalled long enough for a min-difficulty block
+    /// (testnet only), otherwise `get_difficulty_target`.
+    pub fn difficulty_target_at(&self, timestamp: u64) -> u32 {
+        let params = self.retarget_params;
+        let stalled = self
+            .db
+            .get_stored_header(&self.best_block_hash)
+            .ok()
+            .flatten()
+            .is_some_and(|tip| {
+                difficulty_retarget::min_difficulty_allowed(
+                    self.current_height + 1,
+                    tip.timestamp(),
+                    timestamp,
+                    &params,
+                )
+            });
+        if stalled {
+            params.pow_limit_bits
+        } else {
+            self.get_difficulty_target()
+        }
+    }
+
     /// The required `bits` for the block at `current_height + 1`, derived from the
     /// tip (its parent) — off a retarget boundary this is the tip's bits; at a
     /// boundary it is the retargeted value. Shared with `validate_block` via
@@ -361,12 +1010,27 @@ impl ChainState {
             };
         Ok(difficulty_retarget::required_bits(
             next_height,
-            tip.header().bits(),
+            self.regular_bits(tip.header())?,
             boundary_timestamps,
             &params,
         ))
     }
 
+    /// The chain's real difficulty as of `parent`: its `bits`, or on networks
+    /// with min-difficulty blocks those of the newest ancestor that is not a
+    /// stall block, so the floor they were mined at never carries forward.
+    fn regular_bits(&self, parent: &BlockHeader) -> Result<u32, StorageError> {
+        let params = &self.retarget_params;
+        let mut header = parent.clone();
+        while difficulty_retarget::is_min_difficulty_block(header.height(), header.bits(), params) {
+            match self.db.get_stored_header(header.prev_block_hash())? {
+                Some(prev) => header = prev,
+                None => break,
+            }
+        }
+        Ok(header.bits())
+    }
+
     /// Get the current network difficulty as a float
     pub fn get_current_difficulty(&self) -> f64 {
         // Convert target bits to difficulty
@@ -567,26 +1231,31 @@ impl ChainState {
             tracing::debug!("Storing block {} at height {}", hex::encode(&block_hash[..8]), block.height());
             let block_data = bincode::serialize(&block)
                 .map_err(|e| StorageError::DatabaseError(format!("Block serialization failed: {}", e)))?;
-            self.db.store_block(&block_hash, &block_data)?;
-            self.db.flush()?;
+
+            // Record chart stats while the spent prevouts are still in the
+            // UTXO set. Stats are informational, so failures only warn.
+            if let Err(e) = self.db.record_block_stats(&block) {
+                tracing::warn!("Failed to record stats for block {}: {}", block.height(), e);
+            }
+
+            // The block, its UTXO changes and undo record, its height->hash
+            // index entry (#5) and the new tip metadata are committed together.
+            // The index is keyed on the stamped/derived `block.height()`, not an
+            // attacker-supplied wire value.
+            let mut changes = ReorgChangeSet::new();
+            changes.put_block(block_hash, block_data);
+            self.plan_connect_block(&block, &mut changes)?;
+            self.db.apply_tip_change(changes, block.height(), block_hash)?;
+            self.index_header(&block);
             
             self.chain_work.insert(block_hash, new_chain_work);
 
             // Update chain state
             self.current_height = block.height();
             self.best_block_hash = block_hash;
-            
-            // Store updated height and best hash as big-endian
-            self.db.set_metadata(b"height", &self.current_height.to_be_bytes())?;
-            self.db.set_metadata(b"best_hash", &block_hash)?;
-            // Record this block in the height->hash index (#5). `current_height`
-            // was just set to the stamped/derived `block.height()`, so the key is
-            // the trustworthy height, not an attacker-supplied wire value.
-            self.db.store_block_height_index(self.current_height, &block_hash)?;
-            self.db.flush()?;
-            
-            // Process transactions to update UTXO set
-            self.process_block_transactions(&block)?;
+            self.header_index.lock().set_best(&block_hash);
+            self.prune_undo();
+            self.prune_blocks();
             
             tracing::info!("Block added to chain: height={}, hash={}", self.current_height, hex::encode(&block_hash[..8]));
 
@@ -660,10 +1329,29 @@ impl ChainState {
             ))
         })?;
 
-        // `block.height()` is the trustworthy derived/stamped height. At a
-        // retarget boundary, sample the period's first/last timestamps along the
-        // block's own ancestry; otherwise difficulty is unchanged from the parent.
+        // `block.height()` is the trustworthy derived/stamped height. On testnet a
+        // block more than four block times after its parent may claim the floor.
         let height = block.height();
+        let timestamp = block.header().timestamp();
+        let parent_timestamp = parent.header().timestamp();
+        let stalled = difficulty_retarget::min_difficulty_allowed(
+            height,
+            parent_timestamp,
+            timestamp,
+            &params,
+        );
+        if stalled && block.header().bits() == params.pow_limit_bits {
+            tracing::info!(
+                "Block {} mined at minimum difficulty after a {}s stall",
+                hex::encode(&block.hash()[..8]),
+                timestamp.saturating_sub(parent_timestamp)
+            );
+            return Ok(true);
+        }
+
+        // At a retarget boundary, sample the period's first/last timestamps along
+        // the block's own ancestry; otherwise difficulty is unchanged from the
+        // parent's real (non-stall) difficulty.
         let boundary_timestamps =
             if height > 0 && params.interval > 0 && height % params.interval == 0 {
                 Some(self.period_boundary_timestamps(&parent, params.interval)?)
@@ -673,7 +1361,7 @@ impl ChainState {
 
         let required = difficulty_retarget::required_bits(
             height,
-            parent.header().bits(),
+            self.regular_bits(parent.header())?,
             boundary_timestamps,
             &params,
         );
@@ -702,69 +1390,147 @@ impl ChainState {
         interval: u64,
     ) -> Result<(u64, u64), StorageError> {
         let last_ts = parent.header().timestamp();
-        let mut cursor = parent.clone();
-        for _ in 0..interval.saturating_sub(1) {
-            let prev_hash = *cursor.prev_block_hash();
-            cursor = self.db.get_block(&prev_hash)?.ok_or_else(|| {
+        self.index_header(parent);
+        let first = parent
+            .height()
+            .checked_sub(interval.saturating_sub(1))
+            .map(|height| self.ancestor_at_height(&parent.hash(), height))
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| {
                 StorageError::DatabaseError(format!(
-                    "difficulty retarget: period ancestor {} is missing",
-                    hex::encode(&prev_hash[..8])
+                    "difficulty retarget: period ancestor of {} is missing",
+                    hex::encode(&parent.hash()[..8])
                 ))
             })?;
-        }
-        Ok((cursor.header().timestamp(), last_ts))
+        Ok((first.timestamp, last_ts))
     }
 
     /// Median-time-past: the median timestamp of up to the last 11 blocks ending
-    /// at `parent` (inclusive), gathered by walking `prev_block_hash` along the
-    /// block's OWN chain — never the canonical height index. A block's timestamp
-    /// must be at least this value (see `validate_block`). This is Bitcoin's MTP;
-    /// we reject timestamps strictly BELOW it, allowing equality so that blocks
-    /// sharing a one-second tick are not rejected — the future-time ceiling and
-    /// the retarget's 4x timespan clamp are what bound time-warp attacks.
-    fn median_time_past(&self, parent: &Block) -> Result<u64, StorageError> {
-        let mut timestamps = Vec::with_capacity(11);
-        let mut cursor = parent.clone();
-        loop {
-            timestamps.push(cursor.header().timestamp());
-            if timestamps.len() >= 11 {
-                break;
-            }
-            let prev = *cursor.prev_block_hash();
-            if prev == [0u8; 32] {
-                break; // reached genesis
-            }
-            match self.db.get_block(&prev)? {
-                Some(b) => cursor = b,
-                None => break, // ancestor missing; median over what we have
-            }
+    /// at `parent` (inclusive), gathered along the block's OWN chain through the
+    /// header index — never the canonical height index. `None` if `parent` is
+    /// unknown; a missing deeper ancestor medians over what exists. A block's
+    /// timestamp must be at least this value (see `validate_block`). This is
+    /// Bitcoin's MTP; we reject timestamps strictly BELOW it, allowing equality
+    /// so that blocks sharing a one-second tick are not rejected — the
+    /// future-time ceiling and the retarget's 4x timespan clamp are what bound
+    /// time-warp attacks.
+    fn median_time_past(&self, parent: &[u8; 32]) -> Result<Option<u64>, StorageError> {
+        self.header_index
+            .lock()
+            .median_time_past(parent, |hash| stored_header(&self.db, hash))
+    }
+
+    /// The ancestor of `hash` at `height` along `hash`'s own chain, or `None`
+    /// if `height` is above it or an ancestor is missing from storage.
+    pub fn ancestor_at_height(
+        &self,
+        hash: &[u8; 32],
+        height: u64,
+    ) -> Result<Option<IndexedHeader>, StorageError> {
+        self.header_index
+            .lock()
+            .ancestor_at_height(hash, height, |h| stored_header(&self.db, h))
+    }
+
+    /// Next block height and tip median time past, for timelock checks
+    pub fn finality_context(&self) -> FinalityContext {
+        let median_time_past = match self.median_time_past(&self.best_block_hash) {
+            Ok(Some(mtp)) => mtp,
+            _ => 0,
+        };
+        FinalityContext {
+            next_height: self.current_height + 1,
+            median_time_past,
         }
-        timestamps.sort_unstable();
-        Ok(timestamps[timestamps.len() / 2])
+    }
+
+    /// Where `txid` was confirmed on the active chain, for relative timelock
+    /// checks. A transaction found only in a block that has since left the
+    /// active chain is `Unconfirmed`; one the transaction index doesn't know
+    /// is `Unknown`.
+    pub fn input_age(&self, txid: &[u8; 32]) -> InputAge {
+        let Ok(Some(block_hash)) = self.db.get_transaction_block(txid) else {
+            return InputAge::Unknown;
+        };
+        let header = self
+            .header_index
+            .lock()
+            .header(&block_hash, |h| stored_header(&self.db, h));
+        let Ok(Some(header)) = header else {
+            return InputAge::Unknown;
+        };
+        match self.ancestor_at_height(&self.best_block_hash, header.height) {
+            Ok(Some(ancestor)) if ancestor.hash == block_hash => {}
+            Ok(_) => return InputAge::Unconfirmed,
+            Err(_) => return InputAge::Unknown,
+        }
+        match self.median_time_past(&header.prev_hash) {
+            Ok(Some(median_time_past)) => InputAge::Confirmed {
+                height: header.height,
+                median_time_past,
+            },
+            // Genesis has no parent
+            Ok(None) => InputAge::Confirmed {
+                height: header.height,
+                median_time_past: 0,
+            },
+            Err(_) => InputAge::Unknown,
+        }
+    }
+
+    /// Add a stored block's header to the header index.
+    fn index_header(&self, block: &Block) {
+        self.header_index
+            .lock()
+            .insert(IndexedHeader::from_header(block.header()));
     }
 
     async fn validate_block(&self, block: &Block) -> Result<bool, StorageError> {
         let block_hash = block.hash();
 
-        // Check if block is already marked as invalid
-        if self.invalid_block_tracker.is_permanently_invalid(&block_hash) {
-            tracing::warn!("Block {} is permanently invalid, rejecting", hex::encode(&block_hash[..8]));
+        // Known-invalid blocks and descendants of one are refused without
+        // running validation again
+        if let Some(record) = self.known_invalid(block.header())? {
+            tracing::warn!(
+                "Block {} is known invalid ({:?}), rejecting",
+                hex::encode(&block_hash[..8]),
+                record.reason
+            );
             return Ok(false);
         }
+        self.validation_runs.fetch_add(1, AtomicOrdering::Relaxed);
 
-        // Check if parent is invalid
-        let parent_hash = block.prev_block_hash();
-        if self.invalid_block_tracker.is_permanently_invalid(parent_hash) {
-            tracing::warn!("Parent block {} is invalid, marking block {} as invalid", 
-                hex::encode(&parent_hash[..8]), hex::encode(&block_hash[..8]));
-            self.invalid_block_tracker.mark_invalid(
-                block_hash,
-                InvalidationReason::ParentInvalid,
-                Some(*parent_hash),
-                Some(block.height()),
-            ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
-            return Ok(false);
+        if !self.validation_tracing {
+            return self.check_block(block, &mut Tracer::disabled()).await;
+        }
+        let mut trace = ValidationTrace::new();
+        let valid = self.check_block(block, &mut Tracer::new(&mut trace)).await?;
+        if !valid {
+            if let Some(step) = trace.failure() {
+                tracing::warn!(
+                    "Block {} rejected by check {} ({:?})",
+                    hex::encode(&block_hash[..8]),
+                    step.check,
+                    step.outcome
+                );
+            }
+            // A trace is diagnostic only: failing to keep it must not change
+            // the verdict
+            if let Err(e) = self.store_rejection_trace(block, trace) {
+                tracing::warn!("Failed to store rejection trace: {}", e);
+            }
         }
+        Ok(valid)
+    }
+
+    /// The checks behind `validate_block`, reported to `tracer`
+    async fn check_block(
+        &self,
+        block: &Block,
+        tracer: &mut Tracer<'_>,
+    ) -> Result<bool, StorageError> {
+        let block_hash = block.hash();
 
         tracing::debug!(
             "Validating block: height={}, prev_hash={}",
@@ -772,16 +1538,25 @@ impl ChainState {
             hex::encode(block.prev_block_hash())
         );
         
+        let basic_inputs = || {
+            format!(
+                "height={} transactions={} merkle_root={}",
+                block.height(),
+                block.transactions().len(),
+                hex::encode(block.merkle_root())
+            )
+        };
+        let started = tracer.start();
         if !block.validate() {
             tracing::warn!("Block failed basic validation: height={}", block.height());
-            self.invalid_block_tracker.mark_invalid(
-                block_hash,
+            tracer.fail(started, "basic", basic_inputs, "Basic validation failed");
+            self.reject_block(
+                block,
                 InvalidationReason::InvalidStructure("Basic validation failed".to_string()),
-                Some(*block.prev_block_hash()),
-                Some(block.height()),
-            ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+            )?;
             return Ok(false);
         }
+        tracer.pass(started, "basic", basic_inputs);
 
         // Difficulty (#2.2): the block's `bits` must equal the difficulty the
         // chain requires at its (derived, trustworthy) height and must not be
@@ -790,16 +1565,24 @@ impl ChainState {
         // index, which would use the wrong chain for a fork. Checked early so an
         // easy-bits block never reaches transaction or value validation. A
         // missing parent surfaces as Err (retryable orphan), not a permanent mark.
+        let difficulty_inputs =
+            || format!("bits={:#010x} height={}", block.header().bits(), block.height());
+        let started = tracer.start();
         if !self.check_block_difficulty(block)? {
             tracing::warn!("Block failed difficulty validation: height={}", block.height());
-            self.invalid_block_tracker.mark_invalid(
-                block_hash,
+            tracer.fail(
+                started,
+                "difficulty",
+                difficulty_inputs,
+                "Difficulty bits not as required",
+            );
+            self.reject_block(
+                block,
                 InvalidationReason::InvalidStructure("Difficulty bits not as required".to_string()),
-                Some(*block.prev_block_hash()),
-                Some(block.height()),
-            ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+            )?;
             return Ok(false);
         }
+        tracer.pass(started, "difficulty", difficulty_inputs);
 
         // Timestamp (#2.2). Median-time-past is a PERMANENT consensus rule: a
         // block's timestamp must not predate the median of its recent ancestors
@@ -808,9 +1591,15 @@ impl ChainState {
         // slightly ahead becomes valid once local clocks catch up, so it is
         // rejected WITHOUT being blacklisted. Genesis has no ancestors to median.
         let ts_prev_hash = *block.prev_block_hash();
-        if ts_prev_hash != [0u8; 32] {
-            if let Some(ts_parent) = self.db.get_block(&ts_prev_hash)? {
-                let mtp = self.median_time_past(&ts_parent)?;
+        let started = tracer.start();
+        let mtp = if ts_prev_hash != [0u8; 32] {
+            self.median_time_past(&ts_prev_hash)?
+        } else {
+            None
+        };
+        match mtp {
+            Some(mtp) => {
+                let mtp_inputs = || format!("timestamp={} mtp={}", block.timestamp(), mtp);
                 if block.timestamp() < mtp {
                     tracing::warn!(
                         "Block {} timestamp {} is below median-time-past {}",
@@ -818,22 +1607,30 @@ impl ChainState {
                         block.timestamp(),
                         mtp
                     );
-                    self.invalid_block_tracker.mark_invalid(
-                        block_hash,
+                    tracer.fail(
+                        started,
+                        "median-time-past",
+                        mtp_inputs,
+                        "Timestamp below median-time-past",
+                    );
+                    self.reject_block(
+                        block,
                         InvalidationReason::InvalidStructure(
                             "Timestamp below median-time-past".to_string(),
                         ),
-                        Some(ts_prev_hash),
-                        Some(block.height()),
-                    ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+                    )?;
                     return Ok(false);
                 }
+                tracer.pass(started, "median-time-past", mtp_inputs);
             }
+            None => tracer.skip("median-time-past", "no ancestors"),
         }
         let now_secs = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .map(|d| d.as_secs())
             .unwrap_or(0);
+        let future_inputs = || format!("timestamp={} now={}", block.timestamp(), now_secs);
+        let started = tracer.start();
         if now_secs > 0 && block.timestamp() > now_secs.saturating_add(MAX_FUTURE_BLOCK_TIME) {
             // Relay rule: reject but do NOT mark permanently invalid — the block
             // may become acceptable once this node's clock advances.
@@ -843,70 +1640,104 @@ impl ChainState {
                 block.timestamp(),
                 now_secs
             );
+            tracer.fail(
+                started,
+                "future-time",
+                future_inputs,
+                "Timestamp more than 2h ahead (retryable)",
+            );
             return Ok(false);
         }
+        tracer.pass(started, "future-time", future_inputs);
 
         // Validate against checkpoints
+        let checkpoint_inputs = || format!("height={}", block.height());
+        let started = tracer.start();
         if let Err(e) = validate_checkpoint(block) {
             tracing::warn!("Checkpoint validation failed: {}", e);
-            self.invalid_block_tracker.mark_invalid(
-                block_hash,
-                InvalidationReason::CheckpointViolation,
-                Some(*block.prev_block_hash()),
-                Some(block.height()),
-            ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+            tracer.fail(started, "checkpoint", checkpoint_inputs, &e);
+            self.reject_block(block, InvalidationReason::CheckpointViolation)?;
             return Ok(false);
         }
+        tracer.pass(started, "checkpoint", checkpoint_inputs);
 
         if block.height() != self.current_height + 1
             && *block.prev_block_hash() != self.best_block_hash
         {
+            let started = tracer.start();
             let fork_distance = self.calculate_fork_distance(block)?;
             tracing::debug!("Fork block at height {}, distance={}", block.height(), fork_distance);
+            let fork_inputs = || format!("distance={} max={}", fork_distance, MAX_FORK_DISTANCE);
             
+            // Depends on where our tip is, not on the block: not cached
             if fork_distance > MAX_FORK_DISTANCE {
                 tracing::warn!("Fork distance {} exceeds maximum {}", fork_distance, MAX_FORK_DISTANCE);
-                self.invalid_block_tracker.mark_invalid(
-                    block_hash,
-                    InvalidationReason::ForkTooDeep,
-                    Some(*block.prev_block_hash()),
-                    Some(block.height()),
-                ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+                tracer.fail(started, "fork-distance", fork_inputs, "Fork too far from the tip");
                 return Ok(false);
             }
+            tracer.pass(started, "fork-distance", fork_inputs);
         }
 
+        // Transactions are checked against the active chain's UTXO set, which
+        // is the block's own context only when it extends the tip. A fork
+        // block spending outputs of its own branch fails here without being
+        // invalid, so such failures are not cached.
+        let utxo_context = *block.prev_block_hash() == self.best_block_hash;
+
+        // Below the assumevalid block only signatures are skipped: inputs and
+        // value are still checked so the UTXO set stays exact
+        let assumed_valid = self.is_assumed_valid(block)?;
+
         for (i, tx) in block.transactions().iter().enumerate() {
-            if !self.validate_transaction(tx).await? {
+            if !self
+                .validate_transaction_traced(
+                    tx,
+                    !assumed_valid,
+                    &mut tracer.scoped(|| format!("tx[{}]", i)),
+                )
+                .await?
+            {
                 tracing::warn!("Transaction {} failed validation in block {}", i, hex::encode(&block.hash()[..8]));
-                self.invalid_block_tracker.mark_invalid(
-                    block_hash,
-                    InvalidationReason::TransactionValidation(format!("Transaction {} invalid", i)),
-                    Some(*block.prev_block_hash()),
-                    Some(block.height()),
-                ).map_err(|e| StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e)))?;
+                if utxo_context {
+                    self.reject_block(
+                        block,
+                        InvalidationReason::TransactionValidation(format!("Transaction {} invalid", i)),
+                    )?;
+                }
                 return Ok(false);
             }
         }
 
         // Coinbase subsidy cap (audit Critical #3): a block may not create value
         // beyond its block subsidy plus the fees of the transactions it confirms.
+        let value_inputs =
+            || format!("height={} subsidy={}", block.height(), block_subsidy(block.height()));
+        let started = tracer.start();
         if !self.check_block_value(block)? {
-            self.invalid_block_tracker
-                .mark_invalid(
-                    block_hash,
+            tracer.fail(
+                started,
+                "block-value",
+                value_inputs,
+                "Coinbase exceeds block subsidy plus fees",
+            );
+            if utxo_context {
+                self.reject_block(
+                    block,
                     InvalidationReason::InvalidStructure(
                         "Coinbase exceeds block subsidy plus fees".to_string(),
                     ),
-                    Some(*block.prev_block_hash()),
-                    Some(block.height()),
-                )
-                .map_err(|e| {
-                    StorageError::DatabaseError(format!("Failed to mark block invalid: {}", e))
-                })?;
+                )?;
+            }
             return Ok(false);
         }
+        tracer.pass(started, "block-value", value_inputs);
 
+        if assumed_valid {
+            self.assumed_valid_blocks.fetch_add(1, AtomicOrdering::Relaxed);
+        } else {
+            self.fully_verified_blocks.fetch_add(1, AtomicOrdering::Relaxed);
+            ConsensusMetrics::new().update_signature_cache(&self.signature_cache.stats());
+        }
         Ok(true)
     }
 
@@ -988,48 +1819,36 @@ impl ChainState {
         Ok(true)
     }
 
-    /// Process transactions in a block to update UTXO set
-    fn process_block_transactions(&mut self, block: &Block) -> Result<(), StorageError> {
-        for tx in block.transactions() {
-            let tx_hash = tx.hash();
-            
-            // Remove spent UTXOs (skip for coinbase as it has no real inputs)
-            if !tx.is_coinbase() {
-                for input in tx.inputs() {
-                    let prev_tx = input.prev_tx_hash();
-                    let prev_vout = input.prev_output_index();
-                    
-                    // Remove the spent UTXO
-                    if let Err(e) = self.db.remove_utxo(&prev_tx, prev_vout) {
-                        tracing::warn!("Failed to remove spent UTXO: {}", e);
-                    }
-                }
-            }
-            
-            // Add new UTXOs from outputs
-            for (vout, output) in tx.outputs().iter().enumerate() {
-                let output_data = bincode::serialize(output)
-                    .map_err(|e| StorageError::DatabaseError(format!("Output serialization failed: {}", e)))?;
-                
-                self.db.store_utxo(&tx_hash, vout as u32, &output_data)?;
-            }
-        }
-        
-        tracing::debug!("Processed {} transactions, updated UTXO set", block.transactions().len());
-        Ok(())
+    async fn validate_transaction(&self, tx: &Transaction) -> Result<bool, StorageError> {
+        self.validate_transaction_traced(tx, true, &mut Tracer::disabled()).await
     }
 
-    async fn validate_transaction(&self, tx: &Transaction) -> Result<bool, StorageError> {
+    async fn validate_transaction_traced(
+        &self,
+        tx: &Transaction,
+        verify_signatures: bool,
+        tracer: &mut Tracer<'_>,
+    ) -> Result<bool, StorageError> {
         // Skip UTXO validation for coinbase transactions
         if tx.is_coinbase() {
+            tracer.skip("inputs", "coinbase");
             return Ok(true);
         }
         
+        let inputs_summary =
+            || format!("txid={} inputs={}", hex::encode(tx.hash()), tx.inputs().len());
+        let started = tracer.start();
         let mut spent_outputs = HashSet::new();
         for (idx, input) in tx.inputs().iter().enumerate() {
             let outpoint = (input.prev_tx_hash(), input.prev_output_index());
             if !spent_outputs.insert(outpoint) {
                 tracing::warn!("Double-spend detected in transaction: input {}", idx);
+                tracer.fail(
+                    started,
+                    "inputs",
+                    inputs_summary,
+                    format_args!("input {} spends an outpoint this transaction already spent", idx),
+                );
                 return Ok(false);
             }
 
@@ -1044,9 +1863,21 @@ impl ChainState {
                     hex::encode(input.prev_tx_hash()),
                     input.prev_output_index()
                 );
+                tracer.fail(
+                    started,
+                    "inputs",
+                    inputs_summary,
+                    format_args!(
+                        "input {} spends missing UTXO {}:{}",
+                        idx,
+                        hex::encode(input.prev_tx_hash()),
+                        input.prev_output_index()
+                    ),
+                );
                 return Ok(false);
             }
         }
+        tracer.pass(started, "inputs", inputs_summary);
 
         // Value conservation (audit Critical #3): a non-coinbase transaction may
         // not create value — the sum of the amounts it spends must be at least
@@ -1054,6 +1885,7 @@ impl ChainState {
         // (`total_input`/`total_output` return `None` on overflow or a missing
         // prevout); a `None` rejects the transaction (fail-closed), since release
         // builds disable overflow-checks and a silent wrap could mint coins.
+        let started = tracer.start();
         let db_err: RefCell<Option<StorageError>> = RefCell::new(None);
         let get_prevout = |txid: &[u8; 32], vout: u32| -> Option<TransactionOutput> {
             match self.db.get_utxo(txid, vout) {
@@ -1079,6 +1911,12 @@ impl ChainState {
                     "Value conservation: input sum overflow or missing prevout for tx {}",
                     hex::encode(tx.hash())
                 );
+                tracer.fail(
+                    started,
+                    "value-conservation",
+                    String::new,
+                    "input sum overflow or missing prevout",
+                );
                 return Ok(false);
             }
         };
@@ -1089,9 +1927,16 @@ impl ChainState {
                     "Value conservation: output sum overflow for tx {}",
                     hex::encode(tx.hash())
                 );
+                tracer.fail(
+                    started,
+                    "value-conservation",
+                    || format!("in={}", total_in),
+                    "output sum overflow",
+                );
                 return Ok(false);
             }
         };
+        let value_inputs = || format!("in={} out={}", total_in, total_out);
         if total_in < total_out {
             tracing::warn!(
                 "Value conservation violated for tx {}: inputs {} < outputs {}",
@@ -1099,14 +1944,31 @@ impl ChainState {
                 total_in,
                 total_out
             );
+            tracer.fail(started, "value-conservation", value_inputs, "outputs exceed inputs");
             return Ok(false);
         }
+        tracer.pass(started, "value-conservation", value_inputs);
+
+        if !verify_signatures {
+            tracer.skip("authorization", "assumed valid");
+            return Ok(true);
+        }
 
         // Cryptographically verify every input is authorized to spend its UTXO
         // (audit Critical #1). Fail-closed: a missing, invalid, or unbound
         // signature rejects the transaction and therefore the block.
+        let authorization_inputs = || match tx.signature_data() {
+            Some(sig) => format!(
+                "scheme={:?} public_key={} signature={}",
+                sig.scheme,
+                redact_bytes(&sig.public_key),
+                redact_bytes(&sig.data)
+            ),
+            None => "signature=none".to_string(),
+        };
+        let started = tracer.start();
         match self.verify_transaction_authorization(tx) {
-            Ok(()) => {}
+            Ok(()) => tracer.pass(started, "authorization", authorization_inputs),
             // A genuine authorization failure (missing/invalid/unbound signature)
             // is a validity verdict -> reject. A transient DB read error is NOT a
             // validity verdict and must propagate so the block is retried rather
@@ -1117,6 +1979,7 @@ impl ChainState {
                     hex::encode(tx.hash()),
                     msg
                 );
+                tracer.fail(started, "authorization", authorization_inputs, &msg);
                 return Ok(false);
             }
             Err(e) => return Err(e),
@@ -1150,7 +2013,9 @@ impl ChainState {
                 }
             }
         };
-        let result = tx.verify_authorization(&get_prevout);
+        let result = self
+            .signature_cache
+            .verify_authorization(tx, self.verify_flags, &get_prevout);
         if let Some(e) = db_err.borrow_mut().take() {
             return Err(e);
         }
@@ -1200,34 +2065,41 @@ impl ChainState {
         &self,
         new_tip: &Block,
     ) -> Result<(Block, Vec<Block>, Vec<Block>), StorageError> {
+        // The candidate may not be stored yet, so seed its header directly
+        self.index_header(new_tip);
+        let fork = self
+            .header_index
+            .lock()
+            .fork_point(&new_tip.hash(), &self.best_block_hash, |hash| {
+                stored_header(&self.db, hash)
+            })?
+            .filter(|fork| fork.height > 0)
+            .ok_or(StorageError::InvalidChainReorganization)?;
+
+        // Only the blocks that actually change are read in full
         let mut blocks_to_apply = Vec::new();
-        let mut blocks_to_disconnect = Vec::new();
         let mut current = new_tip.clone();
-        let mut main_chain = self.get_block_at_height(self.current_height)?;
-
-        while current.height() > 0 && main_chain.height() > 0 {
-            if current.hash() == main_chain.hash() {
-                return Ok((current, blocks_to_apply, blocks_to_disconnect));
-            }
+        while current.height() > fork.height {
+            let prev_hash = *current.prev_block_hash();
+            blocks_to_apply.push(current);
+            current = self.stored_block(&prev_hash)?;
+        }
 
-            if current.height() > main_chain.height() {
-                blocks_to_apply.push(current.clone());
-                let prev_hash = *current.prev_block_hash();
-                current = self
-                    .db
-                    .get_block(&prev_hash)?
-                    .ok_or(StorageError::DatabaseError("Block not found".to_string()))?;
-            } else {
-                blocks_to_disconnect.push(main_chain.clone());
-                let prev_hash = *main_chain.prev_block_hash();
-                main_chain = self
-                    .db
-                    .get_block(&prev_hash)?
-                    .ok_or(StorageError::DatabaseError("Block not found".to_string()))?;
-            }
+        let mut blocks_to_disconnect = Vec::new();
+        let mut main_chain = self.stored_block(&self.best_block_hash)?;
+        while main_chain.height() > fork.height {
+            let prev_hash = *main_chain.prev_block_hash();
+            blocks_to_disconnect.push(main_chain);
+            main_chain = self.stored_block(&prev_hash)?;
         }
 
-        Err(StorageError::InvalidChainReorganization)
+        Ok((current, blocks_to_apply, blocks_to_disconnect))
+    }
+
+    fn stored_block(&self, hash: &[u8; 32]) -> Result<Block, StorageError> {
+        self.db
+            .get_block(hash)?
+            .ok_or(StorageError::DatabaseError("Block not found".to_string()))
     }
 
     async fn handle_chain_reorganization(
@@ -1300,28 +2172,26 @@ impl ChainState {
             b"total_difficulty".to_vec(),
             bincode::serialize(&new_total_difficulty)?,
         );
-        // Tip metadata: height big-endian to match every other writer/reader.
-        changes.put_meta(b"height".to_vec(), new_tip.height().to_be_bytes().to_vec());
-        changes.put_meta(b"best_hash".to_vec(), new_tip.hash().to_vec());
-
-        // Persist the new tip's block bytes — the only applied block not already
-        // stored (fork ancestors were stored when first received). store_block
-        // also refreshes the bloom filter / cache so the tip is readable. Block
-        // bytes are content-addressed, so a lingering orphan if the commit below
-        // fails is harmless — the authoritative chain state IS the atomic commit.
+
+        // The new tip's block bytes — the only applied block not already
+        // stored (fork ancestors were stored when first received) — join the
+        // same commit, so the tip metadata can never name a missing block.
         let tip_bytes = bincode::serialize(new_tip).map_err(|e| {
             StorageError::DatabaseError(format!("tip block serialize failed: {}", e))
         })?;
-        self.db.store_block(&new_tip.hash(), &tip_bytes)?;
+        changes.put_block(new_tip.hash(), tip_bytes);
 
-        // ---- PHASE B: COMMIT atomically. If this fails, NO in-memory state has
-        // been touched, so ChainState and the DB both still reflect old_tip. ----
-        self.db.apply_reorg_atomically(&changes)?;
+        // ---- PHASE B: COMMIT atomically, together with the tip metadata. If
+        // this fails, NO in-memory state has been touched, so ChainState and
+        // the DB both still reflect old_tip. ----
+        self.db.apply_tip_change(changes, new_tip.height(), new_tip.hash())?;
+        self.index_header(new_tip);
 
         // ---- PHASE C: post-commit side effects (reached only on success).
         // These are non-transactional and must run after the durable commit. ----
         self.best_block_hash = new_tip.hash();
         self.current_height = new_tip.height();
+        self.header_index.lock().set_best(&self.best_block_hash);
         self.last_reorg_time = SystemTime::now();
         self.reorg_count += 1;
 
@@ -1333,10 +2203,21 @@ impl ChainState {
                 self.chain_work.insert(block.hash(), work);
             }
         }
+
+        // Replace the stats of the disconnected blocks, oldest-first so each
+        // interval sees its parent. Spent prevouts resolve via the tx store.
+        for block in blocks_to_apply.iter().rev() {
+            if let Err(e) = self.db.record_block_stats(block) {
+                tracing::warn!("Failed to record stats for block {}: {}", block.height(), e);
+            }
+        }
         for block in blocks_to_disconnect.iter() {
             self.chain_work.remove(&block.hash());
         }
 
+        self.prune_undo();
+        self.prune_blocks();
+
         // Update fork points
         self.prune_fork_points()?;
 
@@ -1386,16 +2267,36 @@ impl ChainState {
     ///
     /// Emits ops only — performs NO database writes and mutates no in-memory
     /// state — so the whole reorg can be staged and then committed atomically.
-    /// Reverses the block's transactions: restores every spent prevout (resolved
-    /// against the pre-reorg chain, which still holds the disconnected blocks)
-    /// and removes every output the block created, then drops the block's
-    /// height->hash index entry. Transactions are reversed newest-first so an
-    /// output created and then spent within the same block nets out correctly.
+    /// Replays the block's undo record when one is kept; a corrupt record
+    /// fails the reorg with `StorageError::CorruptUndo` before anything is
+    /// written. Blocks without undo data (connected before it was recorded,
+    /// or pruned) reverse their transactions instead: restore every spent
+    /// prevout (resolved against the pre-reorg chain, which still holds the
+    /// disconnected blocks) and remove every output the block created.
+    /// Transactions are reversed newest-first so an output created and then
+    /// spent within the same block nets out correctly. Either way the block's
+    /// height->hash index entry and transaction and address index entries
+    /// are dropped.
     fn plan_disconnect_block(
         &self,
         block: &Block,
         changes: &mut ReorgChangeSet,
     ) -> Result<(), StorageError> {
+        if self.db.tx_index_enabled() {
+            txindex::plan_unindex_block(block, changes);
+        }
+        if let Some(block_undo) = self.db.get_undo(&block.hash())? {
+            undo::plan_disconnect(block.hash(), &block_undo, changes)?;
+            if self.db.address_index_enabled() {
+                let prevouts = address_index::prevouts_from_undo(&block_undo);
+                address_index::plan_unindex_block(block, &prevouts, changes);
+            }
+            changes.del_height_index(block.height());
+            return Ok(());
+        }
+
+        let mut prevouts = Prevouts::new();
+
         for tx in block.transactions().iter().rev() {
             let tx_hash = tx.hash();
 
@@ -1417,6 +2318,7 @@ impl ChainState {
                         ))
                     })?;
                     changes.put_utxo(create_utxo_key(&prev_tx, prev_vout), output_data);
+                    prevouts.insert((prev_tx, prev_vout), prev_output);
                 }
             }
 
@@ -1426,6 +2328,9 @@ impl ChainState {
             }
         }
 
+        if self.db.address_index_enabled() {
+            address_index::plan_unindex_block(block, &prevouts, changes);
+        }
         changes.del_height_index(block.height());
         Ok(())
     }
@@ -1434,35 +2339,24 @@ impl ChainState {
     ///
     /// Emits ops only — no database writes, no in-memory mutation. Applies the
     /// block's transactions to the UTXO set (spend each non-coinbase input,
-    /// create each output) and records its height->hash index entry, keyed on
-    /// the now-trustworthy stamped `block.height()`. The new tip's block bytes
-    /// are persisted separately by the caller (content-addressed and idempotent,
-    /// so they need not be inside the atomic UTXO/metadata commit).
+    /// create each output), stages the block's undo record and transaction
+    /// and address index entries, and records its height->hash index entry, keyed on the now-trustworthy stamped
+    /// `block.height()`. Spent outputs resolve against the UTXO set with the
+    /// ops already staged in `changes` applied, so a branch connects on top of
+    /// the disconnects before it. Block bytes are persisted by the caller.
     fn plan_connect_block(
         &self,
         block: &Block,
         changes: &mut ReorgChangeSet,
     ) -> Result<(), StorageError> {
-        for tx in block.transactions() {
-            let tx_hash = tx.hash();
-
-            if !tx.is_coinbase() {
-                for input in tx.inputs() {
-                    changes.del_utxo(create_utxo_key(
-                        &input.prev_tx_hash(),
-                        input.prev_output_index(),
-                    ));
-                }
-            }
-
-            for (vout, output) in tx.outputs().iter().enumerate() {
-                let output_data = bincode::serialize(output).map_err(|e| {
-                    StorageError::DatabaseError(format!("output serialize failed: {}", e))
-                })?;
-                changes.put_utxo(create_utxo_key(&tx_hash, vout as u32), output_data);
-            }
+        let block_undo = undo::plan_connect(&self.db, block, changes)?;
+        if self.db.tx_index_enabled() {
+            txindex::plan_index_block(block, changes);
+        }
+        if self.db.address_index_enabled() {
+            let prevouts = address_index::prevouts_from_undo(&block_undo);
+            address_index::plan_index_block(block, &prevouts, changes);
         }
-
         changes.put_height_index(block.height(), block.hash());
         Ok(())
     }
@@ -1476,6 +2370,7 @@ impl ChainState {
             .map_err(|e| StorageError::DatabaseError(format!("Block serialization failed: {}", e)))?;
         self.db.store_block(&block_hash, &block_data)?;
         self.db.flush()?;
+        self.index_header(&block);
 
         // Calculate block difficulty
         let block_difficulty = block_work_u64(&block);
@@ -1487,33 +2382,32 @@ impl ChainState {
         // Update total difficulty
         self.update_total_difficulty(block_difficulty)?;
 
-        // Update best block hash and height if higher than current
-        if self.current_height < block.height() {
-            self.best_block_hash = block_hash;
-            self.current_height = block.height();
-            // Store height as big-endian bytes (to match get_height() which reads as big-endian)
-            self.db.set_metadata(b"height", &self.current_height.to_be_bytes())?;
-            self.db.set_metadata(b"best_hash", &block_hash)?;
-            self.db.flush()?; // Flush metadata too
-        }
-
+        // The tip only moves through an atomic connect or reorg: moving it
+        // here would leave it on a block whose outputs were never applied.
         Ok(())
     }
 
     fn calculate_chain_work(&self, block: &Block) -> Result<Work, StorageError> {
         let mut total_work = Work::zero();
-        let mut current = block.clone();
+        let mut current = block.header().clone();
 
         tracing::debug!("Calculating chain work from height {}", current.height());
 
+        // Walks headers so pruned ancestors still count
         while current.height() > 0 {
-            total_work = total_work.saturating_add(block_work(&current)?);
+            total_work = total_work.saturating_add(header_work(&current)?);
 
-            // Get previous block
+            // Get previous header
             let prev_hash = current.prev_block_hash();
+
+            // Cumulative work never changes for a given block
+            if let Some(prev_work) = self.chain_work.get(prev_hash) {
+                total_work = total_work.saturating_add(*prev_work);
+                break;
+            }
             
-            if let Ok(Some(prev_block)) = self.db.get_block(prev_hash) {
-                current = prev_block;
+            if let Ok(Some(prev_header)) = self.db.get_stored_header(prev_hash) {
+                current = prev_header;
             } else {
                 tracing::error!(
                     "Chain work calculation failed: block at height {} not found (hash: {})",
@@ -1746,31 +2640,14 @@ impl ChainState {
             return Ok(true);
         }
 
-        let mut current_hash = *descendant_hash;
-
-        // Walk back the chain until we find the ancestor or reach genesis
-        loop {
-            let current_block = match self.db.get_block(&current_hash)? {
-                Some(block) => block,
-                None => return Ok(false),
-            };
-
-            // Check if we've reached height 0 (genesis)
-            if current_block.height() == 0 {
-                return Ok(false);
-            }
-
-            // Get the previous hash
-            let prev_hash = current_block.prev_block_hash();
-
-            // Check if we found our ancestor
-            if prev_hash == potential_ancestor_hash {
-                return Ok(true);
-            }
-
-            // Move to previous block
-            current_hash = *prev_hash;
-        }
+        let mut index = self.header_index.lock();
+        let mut load = |hash: &[u8; 32]| stored_header(&self.db, hash);
+        let Some(ancestor) = index.header(potential_ancestor_hash, &mut load)? else {
+            return Ok(false);
+        };
+        Ok(index
+            .ancestor_at_height(descendant_hash, ancestor.height, &mut load)?
+            .is_some_and(|found| found.hash == ancestor.hash))
     }
 
     /// Calculate metrics about the longest chain and active forks
@@ -1819,7 +2696,11 @@ impl ChainState {
 /// decodes to a zero target): such a block is unmineable and must never be
 /// awarded work.
 fn block_work(block: &Block) -> Result<Work, StorageError> {
-    chainwork::work_from_target(&block.header().target()).ok_or(StorageError::InvalidBlock)
+    header_work(block.header())
+}
+
+fn header_work(header: &BlockHeader) -> Result<Work, StorageError> {
+    chainwork::work_from_target(&header.target()).ok_or(StorageError::InvalidBlock)
 }
 
 /// Saturating u64 projection of a block's real work, for the legacy
@@ -1832,6 +2713,33 @@ fn block_work_u64(block: &Block) -> u64 {
         .unwrap_or(0)
 }
 
+/// Header-index loader: the header of a stored or pruned block, if any.
+fn stored_header(db: &BlockchainDB, hash: &[u8; 32]) -> Result<Option<IndexedHeader>, StorageError> {
+    Ok(db
+        .get_stored_header(hash)?
+        .map(|header| IndexedHeader::from_header(&header)))
+}
+
+impl VersionBitsChain for ChainState {
+    fn entry(&self, hash: &[u8; 32]) -> Option<ChainEntry> {
+        let mut index = self.header_index.lock();
+        let header = index
+            .header(hash, |h| stored_header(&self.db, h))
+            .ok()
+            .flatten()?;
+        Some(ChainEntry {
+            hash: *hash,
+            prev_hash: header.prev_hash,
+            height: header.height,
+            version: header.version,
+        })
+    }
+
+    fn median_time_past(&self, hash: &[u8; 32]) -> Option<u64> {
+        ChainState::median_time_past(self, hash).ok().flatten()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2405,6 +3313,183 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn rollback_invalidates_until_reconsidered() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path())?);
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, bits, 300);
+        let mut cs = regtest_chain_state(db.clone())?;
+
+        let mut chain = Vec::new();
+        let mut prev = a1h;
+        for tag in 2..=5u64 {
+            let block = mine(unique_coinbase_block(prev, bits, 300 + tag));
+            prev = block.hash();
+            assert!(cs.process_block(block.clone()).await?);
+            chain.push(block);
+        }
+        let (a2, a3, a5) = (&chain[0], &chain[1], &chain[3]);
+        assert_eq!(cs.get_height(), 5);
+
+        // Roll back 3 blocks: a3..a5 are disconnected and invalidated.
+        let change = cs.rollback_to(2).await?;
+        assert_eq!(change.old_height, 5);
+        assert_eq!((change.new_height, change.new_tip), (2, a2.hash()));
+        assert_eq!(change.affected_blocks, chain[1..].iter().map(|b| b.hash()).collect::<Vec<_>>());
+        assert_eq!(cs.get_best_block_hash(), a2.hash());
+        assert_eq!(db.get_block_hash_by_height(3)?, None);
+        assert!(db.get_utxo(&a3.transactions()[0].hash(), 0)?.is_none());
+
+        // Peers re-offering the discarded blocks are refused, also after a restart.
+        assert!(matches!(cs.process_block(a3.clone()).await, Err(StorageError::InvalidBlock)));
+        let mut reloaded = regtest_chain_state(db.clone())?;
+        assert!(matches!(reloaded.process_block(a3.clone()).await, Err(StorageError::InvalidBlock)));
+
+        // The target must be below the tip.
+        assert!(matches!(cs.rollback_to(2).await, Err(StorageError::DatabaseError(_))));
+
+        // Reconsidering a3 clears it and its descendants and reactivates them.
+        let change = cs.reconsider_block(&a3.hash()).await?;
+        assert_eq!(change.affected_blocks.len(), 3);
+        assert_eq!((cs.get_height(), cs.get_best_block_hash()), (5, a5.hash()));
+        assert!(db.get_utxo(&a3.transactions()[0].hash(), 0)?.is_some());
+        assert!(regtest_chain_state(db.clone())?
+            .invalid_block_tracker()
+            .get_invalid_block(&a5.hash())
+            .is_none());
+
+        // invalidate-block on an active-chain block rolls back to its parent.
+        let change = cs.invalidate_block(&a5.hash()).await?;
+        assert_eq!(change.new_height, 4);
+        assert_eq!(change.affected_blocks, vec![a5.hash()]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn utxo_stats_track_connects_disconnects_and_reorgs() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path())?);
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, bits, 500);
+        let mut cs = regtest_chain_state(db.clone())?;
+        let assert_in_step = |db: &BlockchainDB| -> Result<(), StorageError> {
+            assert_eq!(db.utxo_stats()?, db.scan_utxo_stats()?.1);
+            assert!(!db.supply_audit()?.discrepancy);
+            Ok(())
+        };
+
+        let mut prev = a1h;
+        for tag in 2..=4u64 {
+            let block = mine(unique_coinbase_block(prev, bits, 500 + tag));
+            prev = block.hash();
+            assert!(cs.process_block(block).await?);
+            assert_in_step(&db)?;
+        }
+        assert_eq!(db.utxo_stats()?.count, 3);
+
+        // Disconnect two blocks
+        cs.rollback_to(2).await?;
+        assert_in_step(&db)?;
+        assert_eq!(db.utxo_stats()?.count, 1);
+
+        // A heavier fork off a1 replaces a2 in one atomic reorg
+        let mut prev = a1h;
+        for tag in 12..=14u64 {
+            let block = mine(unique_coinbase_block(prev, bits, 500 + tag));
+            prev = block.hash();
+            cs.process_block(block).await?;
+        }
+        assert_eq!(cs.get_best_block_hash(), prev);
+        assert_in_step(&db)?;
+        assert_eq!(db.utxo_stats()?.count, 3);
+        assert_eq!(db.utxo_stats()?.total_amount, 3 * 5_000_000_000);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disconnects_replay_undo_and_refuse_corrupt_records() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path())?);
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, bits, 600);
+        let mut cs = regtest_chain_state(db.clone())?;
+
+        let mut prev = a1h;
+        let mut hashes = Vec::new();
+        for tag in 2..=4u64 {
+            let block = mine(unique_coinbase_block(prev, bits, 600 + tag));
+            prev = block.hash();
+            hashes.push(prev);
+            assert!(cs.process_block(block).await?);
+        }
+        let before = db.utxo_set_digest()?;
+        for hash in &hashes {
+            assert!(db.get_undo(hash)?.is_some());
+        }
+
+        // A damaged record for the tip fails the rollback before anything moves
+        let mut bytes = db.get_raw_data("block_undo", &prev)?.unwrap().to_vec();
+        bytes[2] ^= 0x01;
+        db.store_raw_data("block_undo", &prev, &bytes)?;
+        assert!(matches!(
+            cs.rollback_to(2).await,
+            Err(StorageError::CorruptUndo { .. })
+        ));
+        assert_eq!(cs.get_height(), 4);
+        assert_eq!(db.utxo_set_digest()?, before);
+
+        // Blocks with intact records are disconnected from them
+        db.remove_from_tree("block_undo", &prev)?;
+        cs.rollback_to(2).await?;
+        assert!(db.get_undo(&hashes[1])?.is_none());
+        assert!(db.get_undo(&hashes[0])?.is_some());
+        assert_eq!(db.utxo_stats()?.count, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_blocks_are_rejected_from_cache()-> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path())?);
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, bits, 400);
+        let mut cs = regtest_chain_state(db.clone())?;
+
+        let mut bad = mine(unique_coinbase_block(a1h, bits, 402));
+        while bad.verify_proof_of_work() {
+            bad.increment_nonce();
+        }
+        assert!(matches!(cs.process_block(bad.clone()).await, Err(StorageError::InvalidBlock)));
+        assert_eq!(cs.validation_runs(), 1);
+
+        // Re-announced: refused without a second validation run, also after
+        // a restart.
+        assert!(matches!(cs.process_block(bad.clone()).await, Err(StorageError::InvalidBlock)));
+        assert_eq!(cs.validation_runs(), 1);
+        let mut reloaded = regtest_chain_state(db.clone())?;
+        assert!(matches!(reloaded.process_block(bad.clone()).await, Err(StorageError::InvalidBlock)));
+        assert_eq!(reloaded.validation_runs(), 0);
+
+        // Descendants are refused by marker, however deep.
+        let child = mine(unique_coinbase_block(bad.hash(), bits, 403));
+        let grandchild = mine(unique_coinbase_block(child.hash(), bits, 404));
+        let marker = cs.known_invalid(child.header())?.expect("child of an invalid block");
+        assert_eq!(marker.reason, InvalidationReason::ParentInvalid);
+        assert!(matches!(cs.process_block(grandchild.clone()).await, Err(StorageError::InvalidBlock)));
+        assert_eq!(cs.validation_runs(), 1);
+        assert_eq!(cs.invalid_blocks().len(), 3);
+
+        // Clearing the entry drops its markers and allows revalidation.
+        let change = cs.reconsider_block(&bad.hash()).await?;
+        assert_eq!(change.affected_blocks, vec![bad.hash(), child.hash(), grandchild.hash()]);
+        assert!(regtest_chain_state(db.clone())?.invalid_blocks().is_empty());
+        assert!(cs.known_invalid(grandchild.header())?.is_none());
+        assert!(matches!(cs.process_block(bad).await, Err(StorageError::InvalidBlock)));
+        assert_eq!(cs.validation_runs(), 2);
+        Ok(())
+    }
+
     #[test]
     fn expected_height_is_derived_from_parent() {
         let temp_dir = tempdir().unwrap();
@@ -2600,7 +3685,12 @@ mod tests {
         let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
         let bits = 0x207f_ffff;
         let (_g, a1h) = seed_base_chain(&db, bits, 310);
-        let params = RetargetParams { target_block_time: 30, interval: 4, pow_limit_bits: bits };
+        let params = RetargetParams {
+            target_block_time: 30,
+            interval: 4,
+            pow_limit_bits: bits,
+            allow_min_difficulty_blocks: false,
+        };
         let mut cs = ChainState::with_params(db.clone(), params).unwrap();
 
         // Heights 2 and 3 are off-boundary (interval 4): difficulty is unchanged,
@@ -2619,6 +3709,207 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn stalled_testnet_takes_one_min_difficulty_block_then_recovers() {
+        // The real difficulty is 256x the floor, still cheap to mine. After four
+        // block times without a block one block may claim the floor, and the
+        // block after it is back at the real difficulty.
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let floor = 0x207f_ffff;
+        let real = 0x2000_ffff;
+        let (_g, a1h) = seed_base_chain(&db, real, 340);
+        let params = RetargetParams {
+            target_block_time: 30,
+            interval: 100,
+            pow_limit_bits: floor,
+            allow_min_difficulty_blocks: true,
+        };
+        let mut cs = ChainState::with_params(db.clone(), params).unwrap();
+        let a1_ts = db.get_stored_header(&a1h).unwrap().unwrap().timestamp();
+        let stall = 4 * params.target_block_time;
+        assert_eq!(cs.difficulty_target_at(a1_ts + stall), real);
+        assert_eq!(cs.difficulty_target_at(a1_ts + stall + 1), floor);
+
+        // Four block times is not yet a stall
+        let mut early = unique_coinbase_block(a1h, floor, 342);
+        early.header.set_timestamp(a1_ts + stall);
+        assert!(
+            cs.process_block(mine(early)).await.is_err(),
+            "a floor block without a stall must be rejected"
+        );
+
+        let mut a2 = unique_coinbase_block(a1h, floor, 343);
+        a2.header.set_timestamp(a1_ts + stall + 1);
+        let a2 = mine(a2);
+        assert!(cs.process_block(a2.clone()).await.unwrap());
+
+        // The stall block does not lower the chain's difficulty
+        let a2_ts = a2.header().timestamp();
+        assert_eq!(cs.difficulty_target_at(a2_ts + 1), real);
+        let mut cheap = unique_coinbase_block(a2.hash(), floor, 344);
+        cheap.header.set_timestamp(a2_ts + 1);
+        assert!(
+            cs.process_block(mine(cheap)).await.is_err(),
+            "the block after a stall block must return to the real difficulty"
+        );
+        let mut a3 = unique_coinbase_block(a2.hash(), real, 345);
+        a3.header.set_timestamp(a2_ts + 1);
+        assert!(cs.process_block(mine(a3)).await.unwrap());
+        assert_eq!(cs.get_difficulty_target(), real);
+    }
+
+    #[tokio::test]
+    async fn min_difficulty_blocks_are_rejected_where_not_allowed() {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let floor = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, 0x2000_ffff, 350);
+        let params = RetargetParams {
+            target_block_time: 30,
+            interval: 100,
+            pow_limit_bits: floor,
+            allow_min_difficulty_blocks: false,
+        };
+        let mut cs = ChainState::with_params(db.clone(), params).unwrap();
+        let a1_ts = db.get_stored_header(&a1h).unwrap().unwrap().timestamp();
+        assert_eq!(cs.difficulty_target_at(a1_ts + 3_600), 0x2000_ffff);
+
+        let mut a2 = unique_coinbase_block(a1h, floor, 352);
+        a2.header.set_timestamp(a1_ts + 3_600);
+        assert!(
+            cs.process_block(mine(a2)).await.is_err(),
+            "without the testnet rule a stall never lowers the difficulty"
+        );
+    }
+
+    /// Seed a UTXO owned by `owner` and build `a2 <- a3` on a fresh base
+    /// chain, where a2 confirms `spend`. Both headers are stored, as after
+    /// a headers-first download.
+    fn assume_valid_chain(
+        db: &Arc<BlockchainDB>,
+        owner: &supernova_core::crypto::quantum::QuantumKeyPair,
+        spend: Transaction,
+        tag: u64,
+    ) -> (Block, Block) {
+        use sha3::{Digest as _, Sha3_512};
+
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(db, bits, tag);
+        let owner_script = Sha3_512::digest(&owner.public_key)[..32].to_vec();
+        let prevout = TransactionOutput::new(1_000_000, owner_script);
+        db.store_utxo(&[9u8; 32], 0, &bincode::serialize(&prevout).unwrap())
+            .unwrap();
+
+        let coinbase = unique_coinbase_block(a1h, bits, tag + 2).transactions()[0].clone();
+        let mut a2 = Block::new_with_params(1, a1h, vec![coinbase, spend], bits);
+        a2.set_height(2);
+        let a2 = mine(a2);
+        let mut a3 = unique_coinbase_block(a2.hash(), bits, tag + 3);
+        a3.set_height(3);
+        let a3 = mine(a3);
+        for block in [&a2, &a3] {
+            db.store_block_header(&block.hash(), &bincode::serialize(block.header()).unwrap())
+                .unwrap();
+        }
+        (a2, a3)
+    }
+
+    fn signed_spend(
+        prev_txid: [u8; 32],
+        signer: &supernova_core::crypto::quantum::QuantumKeyPair,
+    ) -> Transaction {
+        use supernova_core::types::transaction::{
+            SignatureSchemeType, TransactionInput, TransactionSignatureData,
+        };
+
+        let inputs = vec![TransactionInput::new(prev_txid, 0, vec![], 0xffff_ffff)];
+        let outputs = vec![TransactionOutput::new(900_000, vec![0xab; 32])];
+        let mut tx = Transaction::new(2, inputs, outputs, 0);
+        let sig = signer.sign(&tx.signature_hash()).expect("sign");
+        tx.set_signature_data(TransactionSignatureData {
+            scheme: SignatureSchemeType::Dilithium,
+            security_level: signer.parameters.security_level,
+            data: sig,
+            public_key: signer.public_key.clone(),
+        });
+        tx
+    }
+
+    #[tokio::test]
+    async fn assumed_valid_blocks_skip_signatures_but_not_utxo_checks() {
+        use supernova_core::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+
+        let keypair =
+            || QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let owner = keypair();
+        let attacker = keypair();
+
+        // A spend signed by the wrong key below the assumevalid block syncs
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let (a2, a3) = assume_valid_chain(&db, &owner, signed_spend([9u8; 32], &attacker), 360);
+        let mut cs = regtest_chain_state(db.clone()).unwrap();
+        cs.set_assume_valid(Some(a3.hash()));
+        assert!(cs.process_block(a2.clone()).await.unwrap());
+        assert!(cs.process_block(a3.clone()).await.unwrap());
+        assert_eq!(cs.assumed_valid_blocks(), 2);
+        assert_eq!(cs.fully_verified_blocks(), 0);
+        assert!(db.get_utxo(&[9u8; 32], 0).unwrap().is_none());
+
+        // Without assumevalid the same chain is rejected at the bad signature
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let (a2, _a3) = assume_valid_chain(&db, &owner, signed_spend([9u8; 32], &attacker), 360);
+        let mut cs = regtest_chain_state(db.clone()).unwrap();
+        cs.set_assume_valid(None);
+        assert!(cs.process_block(a2).await.is_err());
+        assert_eq!(cs.assumed_valid_blocks(), 0);
+
+        // A spend of a missing UTXO is rejected even below the assumevalid block
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let (a2, a3) = assume_valid_chain(&db, &owner, signed_spend([8u8; 32], &owner), 370);
+        let mut cs = regtest_chain_state(db.clone()).unwrap();
+        cs.set_assume_valid(Some(a3.hash()));
+        assert!(
+            cs.process_block(a2).await.is_err(),
+            "assumevalid must not skip UTXO accounting"
+        );
+        assert_eq!(cs.assumed_valid_blocks(), 0);
+    }
+
+    #[tokio::test]
+    async fn pool_signatures_are_not_verified_again_when_their_block_connects() {
+        use crate::mempool::{MempoolConfig, TransactionPool};
+        use supernova_core::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+
+        let owner =
+            QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let spend = signed_spend([9u8; 32], &owner);
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let (a2, _a3) = assume_valid_chain(&db, &owner, spend.clone(), 380);
+        let mut cs = regtest_chain_state(db.clone()).unwrap();
+        cs.set_assume_valid(None);
+
+        // Mempool admission warms the cache the chain state shares
+        let pool = TransactionPool::new(MempoolConfig::default())
+            .with_signature_cache(Arc::clone(cs.signature_cache()));
+        pool.add_transaction(spend, 10).unwrap();
+        let warm = cs.signature_cache().stats();
+        assert_eq!((warm.misses, warm.entries), (1, 1));
+
+        assert!(cs.process_block(a2).await.unwrap());
+        assert_eq!(cs.fully_verified_blocks(), 1);
+        let connected = cs.signature_cache().stats();
+        assert_eq!(
+            connected.misses, warm.misses,
+            "connecting a block of pool transactions verifies no signatures"
+        );
+        assert!(connected.hits > warm.hits);
+    }
+
     #[tokio::test]
     async fn validate_block_rejects_out_of_range_timestamps() {
         // A block dated before median-time-past, or more than 2h in the future,
@@ -2663,6 +3954,70 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn rejected_block_trace_ends_at_failing_check() {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, bits, 330);
+        let mut cs = regtest_chain_state(db.clone()).unwrap();
+        let a2 = mine(unique_coinbase_block(a1h, bits, 332));
+        assert!(cs.process_block(a2.clone()).await.unwrap());
+
+        // Disabled by default: rejections leave no trace
+        let mut untraced = unique_coinbase_block(a2.hash(), bits, 333);
+        untraced.header.set_timestamp(1);
+        let untraced = mine(untraced);
+        assert!(cs.process_block(untraced.clone()).await.is_err());
+        assert!(cs.rejection_trace(&untraced.hash()).unwrap().is_none());
+
+        cs.set_validation_tracing(true);
+        let mut backdated = unique_coinbase_block(a2.hash(), bits, 334);
+        backdated.header.set_timestamp(1);
+        let backdated = mine(backdated);
+        assert!(cs.process_block(backdated.clone()).await.is_err());
+
+        let rejection = cs.rejection_trace(&backdated.hash()).unwrap().expect("trace recorded");
+        assert_eq!(rejection.height, 3);
+        let steps = rejection.trace.steps();
+        let (last, earlier) = steps.split_last().unwrap();
+        assert_eq!(last.check, "median-time-past");
+        assert!(last.failed());
+        assert!(last.inputs.starts_with("timestamp=1 "), "{}", last.inputs);
+        let checks: Vec<_> = earlier.iter().map(|s| s.check.as_str()).collect();
+        assert_eq!(checks, ["basic", "difficulty"]);
+        assert!(earlier.iter().all(|s| s.passed()));
+
+        // Accepted blocks are not recorded
+        let good = mine(unique_coinbase_block(a2.hash(), bits, 335));
+        assert!(cs.process_block(good.clone()).await.unwrap());
+        assert!(cs.rejection_trace(&good.hash()).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejection_traces_are_bounded() {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let cs = regtest_chain_state(db.clone()).unwrap();
+
+        let blocks: Vec<Block> = (0..MAX_REJECTION_TRACES as u64 + 3)
+            .map(|tag| unique_coinbase_block([0u8; 32], 0x207f_ffff, 500 + tag))
+            .collect();
+        for block in &blocks {
+            cs.store_rejection_trace(block, ValidationTrace::new()).unwrap();
+        }
+        // Storing a block again replaces its trace rather than adding one
+        let last = blocks.last().unwrap();
+        cs.store_rejection_trace(last, ValidationTrace::new()).unwrap();
+
+        assert_eq!(db.open_tree(REJECTION_TRACES_TREE).unwrap().len(), MAX_REJECTION_TRACES);
+        for evicted in &blocks[..3] {
+            assert!(cs.rejection_trace(&evicted.hash()).unwrap().is_none());
+        }
+        assert!(cs.rejection_trace(&blocks[3].hash()).unwrap().is_some());
+        assert!(cs.rejection_trace(&last.hash()).unwrap().is_some());
+    }
+
     #[test]
     fn prune_fork_points_ages_out_stale_entries() {
         // header_timestamp must reflect each block's real header timestamp so
@@ -2710,4 +4065,156 @@ mod tests {
             "a recent fork point must be retained"
         );
     }
+
+    #[tokio::test]
+    async fn header_index_tracks_reorgs_and_restarts() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path())?);
+        let bits = 0x207f_ffff;
+        let (g0h, a1h) = seed_base_chain(&db, bits, 400);
+        let mut cs = regtest_chain_state(db.clone())?;
+
+        let a2 = mine(unique_coinbase_block(a1h, bits, 401));
+        let a3 = mine(unique_coinbase_block(a2.hash(), bits, 402));
+        assert!(cs.process_block(a2.clone()).await?);
+        assert!(cs.process_block(a3.clone()).await?);
+
+        let b2 = mine(unique_coinbase_block(a1h, bits, 412));
+        let b3 = mine(unique_coinbase_block(b2.hash(), bits, 413));
+        let b4 = mine(unique_coinbase_block(b3.hash(), bits, 414));
+        assert!(!cs.process_block(b2.clone()).await?);
+        assert!(!cs.process_block(b3.clone()).await?);
+        assert!(cs.process_block(b4.clone()).await?);
+        assert_eq!(cs.get_best_block_hash(), b4.hash());
+
+        // MTP over the new branch matches a plain walk of the stored blocks
+        let mut timestamps: Vec<u64> = [g0h, a1h, b2.hash(), b3.hash(), b4.hash()]
+            .iter()
+            .map(|h| db.get_block(h).unwrap().unwrap().timestamp())
+            .collect();
+        timestamps.sort_unstable();
+        let expected_mtp = timestamps[timestamps.len() / 2];
+
+        for state in [cs.clone(), regtest_chain_state(db.clone())?] {
+            assert_eq!(
+                state.header_index.lock().best(),
+                Some(b4.hash()),
+                "index follows the reorged (and reloaded) tip"
+            );
+            let at = |tip: &[u8; 32], height| {
+                state.ancestor_at_height(tip, height).unwrap().map(|h| h.hash)
+            };
+            assert_eq!(at(&b4.hash(), 2), Some(b2.hash()));
+            assert_eq!(at(&b4.hash(), 0), Some(g0h));
+            // The abandoned branch is still navigable
+            assert_eq!(at(&a3.hash(), 2), Some(a2.hash()));
+            assert_eq!(at(&a3.hash(), 4), None);
+
+            assert!(state.is_ancestor_of(&a1h, &b4.hash())?);
+            assert!(!state.is_ancestor_of(&a2.hash(), &b4.hash())?);
+            assert_eq!(
+                VersionBitsChain::median_time_past(&state, &b4.hash()),
+                Some(expected_mtp)
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_the_reorg_window_and_still_validates() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path())?);
+        let bits = 0x207f_ffff;
+        let (g0h, a1h) = seed_base_chain(&db, bits, 700);
+        let pruned_chain_state = || -> Result<ChainState, StorageError> {
+            let mut cs = regtest_chain_state(db.clone())?;
+            cs.set_undo_depth(MAX_REORG_DEPTH);
+            // Nothing fits the target, so only the reorg window survives
+            cs.set_prune_target(Some(0));
+            Ok(cs)
+        };
+        let mut cs = pruned_chain_state()?;
+
+        let mut hashes = vec![g0h, a1h];
+        for tag in 2..=130u64 {
+            let tip = *hashes.last().unwrap();
+            let block = mine(unique_coinbase_block(tip, bits, 700 + tag));
+            hashes.push(block.hash());
+            assert!(cs.process_block(block).await?);
+            assert!(db.pruned_height()? <= (tag + 1).saturating_sub(MAX_REORG_DEPTH));
+        }
+
+        let pruned_height = db.pruned_height()?;
+        assert_eq!(pruned_height, 131 - MAX_REORG_DEPTH);
+        for (height, hash) in hashes.iter().enumerate() {
+            let height = height as u64;
+            let kept = height == 0 || height >= pruned_height;
+            assert_eq!(db.get_block(hash)?.is_some(), kept, "block {}", height);
+            // Headers and the height index outlive the blocks
+            let header = db.get_header_by_height(height)?.unwrap();
+            assert_eq!(header.hash(), *hash);
+        }
+        assert!(db.get_undo(&hashes[10])?.is_none());
+        assert!(db.get_undo(&hashes[130])?.is_some());
+
+        // New blocks are still validated on top of the pruned chain
+        let easy = mine(unique_coinbase_block(hashes[130], 0x20ff_ffff, 900));
+        assert!(cs.process_block(easy).await.is_err());
+        let next = mine(unique_coinbase_block(hashes[130], bits, 901));
+        assert!(cs.process_block(next).await?);
+        assert_eq!(cs.get_height(), 131);
+
+        // A reorg within the window finds every block it disconnects
+        let mut prev = hashes[128];
+        for tag in 910..914u64 {
+            let block = mine(unique_coinbase_block(prev, bits, tag));
+            prev = block.hash();
+            cs.process_block(block).await?;
+        }
+        assert_eq!(cs.get_best_block_hash(), prev);
+        assert_eq!(cs.get_height(), 132);
+
+        // A restarted node rebuilds its state from the remaining headers
+        let mut reloaded = pruned_chain_state()?;
+        let after = mine(unique_coinbase_block(prev, bits, 920));
+        assert!(reloaded.process_block(after).await?);
+        assert_eq!(reloaded.get_height(), 133);
+        assert_eq!(db.pruned_height()?, 134 - MAX_REORG_DEPTH);
+        Ok(())
+    }
+
+    #[test]
+    fn deployments_follow_the_stored_chain() {
+        let temp_dir = tempdir().unwrap();
+        let db = Arc::new(BlockchainDB::new(temp_dir.path()).unwrap());
+        let bits = 0x207f_ffff;
+        let (_g, a1h) = seed_base_chain(&db, bits, 240);
+        let mut cs = regtest_chain_state(db.clone()).unwrap();
+        assert_eq!(cs.get_best_block_hash(), a1h);
+
+        let mut params = VersionBitsParams::regtest();
+        params.window = 2;
+        cs.set_version_bits_params(params);
+
+        // Genesis + a1 close the first window; signaling opens for the next
+        let deployments = cs.deployments().unwrap();
+        assert_eq!(deployments.len(), 3);
+        for status in &deployments {
+            assert_eq!(status.state, ThresholdState::Started, "{}", status.deployment.name);
+            let stats = status.stats.expect("stats while started");
+            assert_eq!((stats.period, stats.elapsed), (2, 0));
+        }
+
+        let signal = vec!["quantum_policy".to_string()];
+        let version = cs.block_version(&signal).unwrap();
+        assert_eq!(version, version_bits::VERSIONBITS_TOP_BITS | (1 << 1));
+
+        // Legacy version-1 headers never signal, so nothing locks in
+        let next = mine(unique_coinbase_block(a1h, bits, 241));
+        assert_eq!(
+            cs.deployment_state_for_block("quantum_policy", &next).unwrap(),
+            ThresholdState::Started
+        );
+        assert!(!cs.is_deployment_active("quantum_policy", &next).unwrap());
+    }
 }
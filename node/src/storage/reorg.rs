@@ -20,6 +20,11 @@
 //! * UTXO key  = `tx_hash || index.to_be_bytes()` (see `create_utxo_key`)
 //! * height-index key = `height.to_be_bytes()` (big-endian, 8 bytes)
 
+use std::collections::HashMap;
+
+use super::address_index::AddressIndexBatch;
+use super::txindex::TxLocation;
+
 /// One tree mutation in a reorg change-set.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ReorgOp {
@@ -35,10 +40,25 @@ pub enum ReorgOp {
     PutHeightIndex([u8; 8], [u8; 32]),
     /// Remove a height->hash mapping (`block_height_index`).
     DelHeightIndex([u8; 8]),
+    /// Persist a block's encoded undo record under its hash (`block_undo`).
+    PutUndo([u8; 32], Vec<u8>),
+    /// Remove a block's undo record (`block_undo`).
+    DelUndo([u8; 32]),
+    /// Map a txid to its encoded `TxLocation` (`tx_index`).
+    PutTxIndex([u8; 32], [u8; TxLocation::ENCODED_LEN]),
+    /// Remove a txid from the transaction index (`tx_index`).
+    DelTxIndex([u8; 32]),
+    /// One block's address index writes (`address_index`), skipped while
+    /// the index rebuild has not reached the block's height.
+    AddressIndex(AddressIndexBatch),
     /// Test-only: force the committing transaction to abort AFTER earlier ops
     /// have been staged, so the all-or-nothing discard path can be exercised.
     #[cfg(test)]
     AbortForTest,
+    /// Test-only: panic inside the committing transaction, simulating a
+    /// crash part way through a commit.
+    #[cfg(test)]
+    PanicForTest,
 }
 
 /// An ordered set of tree mutations applied atomically by
@@ -87,6 +107,49 @@ impl ReorgChangeSet {
         self.ops.push(ReorgOp::DelHeightIndex(height.to_be_bytes()));
     }
 
+    /// Stage a block's undo record (encoded via `BlockUndo::encode`).
+    pub fn put_undo(&mut self, hash: [u8; 32], bytes: Vec<u8>) {
+        self.ops.push(ReorgOp::PutUndo(hash, bytes));
+    }
+
+    /// Stage the removal of a block's undo record.
+    pub fn del_undo(&mut self, hash: [u8; 32]) {
+        self.ops.push(ReorgOp::DelUndo(hash));
+    }
+
+    /// Stage a transaction index entry.
+    pub fn put_tx_index(&mut self, txid: [u8; 32], location: TxLocation) {
+        self.ops.push(ReorgOp::PutTxIndex(txid, location.encode()));
+    }
+
+    /// Stage the removal of a transaction index entry.
+    pub fn del_tx_index(&mut self, txid: [u8; 32]) {
+        self.ops.push(ReorgOp::DelTxIndex(txid));
+    }
+
+    /// Stage a block's address index writes.
+    pub fn address_index(&mut self, batch: AddressIndexBatch) {
+        self.ops.push(ReorgOp::AddressIndex(batch));
+    }
+
+    /// The value each UTXO key will hold once the staged ops are applied:
+    /// `Some(bytes)` if put, `None` if removed. Unstaged keys are absent.
+    pub fn staged_utxos(&self) -> HashMap<&[u8], Option<&[u8]>> {
+        let mut staged = HashMap::new();
+        for op in &self.ops {
+            match op {
+                ReorgOp::PutUtxo(key, value) => {
+                    staged.insert(key.as_slice(), Some(value.as_slice()));
+                }
+                ReorgOp::DelUtxo(key) => {
+                    staged.insert(key.as_slice(), None);
+                }
+                _ => {}
+            }
+        }
+        staged
+    }
+
     /// Number of staged ops.
     pub fn len(&self) -> usize {
         self.ops.len()
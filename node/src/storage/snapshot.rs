@@ -0,0 +1,577 @@
+//! Chain state snapshots for cloning a node's datadir.
+//!
+//! A snapshot is a single zstd-compressed stream holding a JSON manifest
+//! (format version, network, tip, UTXO commitment) followed by every entry of
+//! every sled tree, and a trailing SHA-256 over the uncompressed stream.
+//!
+//! Export opens the database directly, so the node must be stopped; sled's
+//! exclusive file lock enforces this and guarantees a consistent view. Import
+//! restores into a staging directory, verifies the checksum, tip and UTXO
+//! commitment, and only then moves it into place.
+
+use super::database::{BlockchainDB, StorageError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+/// Snapshot format version written by this build
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+const SNAPSHOT_MAGIC: &[u8; 8] = b"SNVSNAP\0";
+const RECORD_ENTRY: u8 = 1;
+const RECORD_END: u8 = 0;
+/// Upper bound on a single key or value, to reject corrupt length fields
+/// before allocating.
+const MAX_FIELD_LEN: u32 = 64 * 1024 * 1024;
+const PROGRESS_INTERVAL: u64 = 10_000;
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sled::Error),
+
+    #[error("Invalid manifest: {0}")]
+    Manifest(String),
+
+    #[error("Unsupported snapshot format version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Snapshot is for {found}, this node is configured for {expected}")]
+    NetworkMismatch { expected: String, found: String },
+
+    #[error("Snapshot archive is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Restored state does not match the manifest: {0}")]
+    VerificationFailed(String),
+
+    #[error("Target datadir {0:?} is not empty (use --force to replace it)")]
+    TargetNotEmpty(PathBuf),
+}
+
+/// Network a snapshot belongs to. Import refuses snapshots from a different
+/// chain or network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotNetwork {
+    pub chain_id: String,
+    pub network_id: String,
+}
+
+impl std::fmt::Display for SnapshotNetwork {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "chain '{}' / network '{}'", self.chain_id, self.network_id)
+    }
+}
+
+/// Describes the state captured in a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub format_version: u32,
+    pub network: SnapshotNetwork,
+    pub height: u64,
+    /// Hex-encoded hash of the best block
+    pub tip_hash: String,
+    /// Hex-encoded digest of the UTXO set, see `BlockchainDB::utxo_set_digest`
+    pub utxo_commitment: String,
+    pub utxo_count: u64,
+    /// Trees in the archive; records refer to them by index
+    pub trees: Vec<String>,
+    pub total_entries: u64,
+    /// Unix timestamp of the export
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotPhase {
+    Exporting,
+    Importing,
+    Verifying,
+}
+
+/// Progress reported during export and import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotProgress {
+    pub phase: SnapshotPhase,
+    pub entries_done: u64,
+    pub total_entries: u64,
+}
+
+/// Write a snapshot of `db` to `archive`. The archive is written to a
+/// temporary file and renamed into place once complete.
+pub fn export_snapshot(
+    db: &BlockchainDB,
+    network: &SnapshotNetwork,
+    archive: &Path,
+    progress: &mut dyn FnMut(&SnapshotProgress),
+) -> Result<SnapshotManifest, SnapshotError> {
+    db.flush()?;
+
+    let sled_db = db.db();
+    let mut trees = Vec::new();
+    let mut total_entries = 0u64;
+    for name in sled_db.tree_names() {
+        let name = String::from_utf8(name.to_vec())
+            .map_err(|_| SnapshotError::Manifest("non UTF-8 tree name".to_string()))?;
+        total_entries += sled_db.open_tree(&name)?.len() as u64;
+        trees.push(name);
+    }
+
+    let (utxo_digest, utxo_count) = db.utxo_set_digest()?;
+    let manifest = SnapshotManifest {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        network: network.clone(),
+        height: db.get_height()?,
+        tip_hash: hex::encode(db.get_best_block_hash()?),
+        utxo_commitment: hex::encode(utxo_digest),
+        utxo_count,
+        trees,
+        total_entries,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    };
+
+    let tmp_path = archive.with_extension("partial");
+    let file = BufWriter::new(File::create(&tmp_path)?);
+    let mut writer = HashingWriter::new(zstd::Encoder::new(file, 3)?);
+
+    writer.write_all(SNAPSHOT_MAGIC)?;
+    let manifest_json = serde_json::to_vec(&manifest)
+        .map_err(|e| SnapshotError::Manifest(e.to_string()))?;
+    writer.write_all(&(manifest_json.len() as u32).to_le_bytes())?;
+    writer.write_all(&manifest_json)?;
+
+    let mut report = SnapshotProgress {
+        phase: SnapshotPhase::Exporting,
+        entries_done: 0,
+        total_entries,
+    };
+    for (index, name) in manifest.trees.iter().enumerate() {
+        for entry in sled_db.open_tree(name)?.iter() {
+            let (key, value) = entry?;
+            writer.write_all(&[RECORD_ENTRY])?;
+            writer.write_all(&(index as u16).to_le_bytes())?;
+            write_field(&mut writer, &key)?;
+            write_field(&mut writer, &value)?;
+            report.entries_done += 1;
+            if report.entries_done % PROGRESS_INTERVAL == 0 {
+                progress(&report);
+            }
+        }
+    }
+    writer.write_all(&[RECORD_END])?;
+    let (mut encoder, digest) = writer.finish();
+    encoder.write_all(&digest)?;
+    encoder.finish()?.flush()?;
+    progress(&report);
+
+    fs::rename(&tmp_path, archive)?;
+    Ok(manifest)
+}
+
+/// Read only the manifest of a snapshot archive.
+pub fn read_manifest(archive: &Path) -> Result<SnapshotManifest, SnapshotError> {
+    let mut reader = HashingReader::new(zstd::Decoder::new(BufReader::new(File::open(archive)?))?);
+    read_header(&mut reader)
+}
+
+/// Restore `archive` into `datadir`. Refuses a non-empty `datadir` unless
+/// `force` is set, in which case the existing contents are replaced once the
+/// restored state has been verified.
+pub fn import_snapshot(
+    archive: &Path,
+    datadir: &Path,
+    network: &SnapshotNetwork,
+    force: bool,
+    progress: &mut dyn FnMut(&SnapshotProgress),
+) -> Result<SnapshotManifest, SnapshotError> {
+    if !force && dir_has_entries(datadir)? {
+        return Err(SnapshotError::TargetNotEmpty(datadir.to_path_buf()));
+    }
+
+    let staging = staging_path(datadir);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    let result = restore_into(archive, &staging, network, progress);
+    let manifest = match result {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+
+    if datadir.exists() {
+        fs::remove_dir_all(datadir)?;
+    }
+    fs::rename(&staging, datadir)?;
+    Ok(manifest)
+}
+
+fn restore_into(
+    archive: &Path,
+    staging: &Path,
+    network: &SnapshotNetwork,
+    progress: &mut dyn FnMut(&SnapshotProgress),
+) -> Result<SnapshotManifest, SnapshotError> {
+    let mut reader = HashingReader::new(zstd::Decoder::new(BufReader::new(File::open(archive)?))?);
+    let manifest = read_header(&mut reader)?;
+    if manifest.network != *network {
+        return Err(SnapshotError::NetworkMismatch {
+            expected: network.to_string(),
+            found: manifest.network.to_string(),
+        });
+    }
+
+    let mut report = SnapshotProgress {
+        phase: SnapshotPhase::Importing,
+        entries_done: 0,
+        total_entries: manifest.total_entries,
+    };
+    {
+        let sled_db = sled::open(staging)?;
+        let trees = manifest
+            .trees
+            .iter()
+            .map(|name| sled_db.open_tree(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut batches: Vec<(sled::Batch, usize)> =
+            trees.iter().map(|_| (sled::Batch::default(), 0)).collect();
+
+        loop {
+            match read_u8(&mut reader)? {
+                RECORD_ENTRY => {
+                    let index = read_u16(&mut reader)? as usize;
+                    let key = read_field(&mut reader)?;
+                    let value = read_field(&mut reader)?;
+                    let (batch, pending) = batches.get_mut(index).ok_or_else(|| {
+                        SnapshotError::Corrupt(format!("record refers to unknown tree {}", index))
+                    })?;
+                    batch.insert(key, value);
+                    *pending += 1;
+                    if *pending >= IMPORT_BATCH_SIZE {
+                        trees[index].apply_batch(std::mem::take(batch))?;
+                        *pending = 0;
+                    }
+                    report.entries_done += 1;
+                    if report.entries_done % PROGRESS_INTERVAL == 0 {
+                        progress(&report);
+                    }
+                }
+                RECORD_END => break,
+                other => {
+                    return Err(SnapshotError::Corrupt(format!("unknown record type {}", other)))
+                }
+            }
+        }
+
+        let computed = reader.digest();
+        let mut expected = [0u8; 32];
+        reader.read_exact(&mut expected)?;
+        if computed != expected {
+            return Err(SnapshotError::Corrupt("checksum mismatch".to_string()));
+        }
+        if report.entries_done != manifest.total_entries {
+            return Err(SnapshotError::Corrupt(format!(
+                "expected {} entries, found {}",
+                manifest.total_entries, report.entries_done
+            )));
+        }
+
+        for ((batch, _), tree) in batches.into_iter().zip(&trees) {
+            tree.apply_batch(batch)?;
+        }
+        sled_db.flush()?;
+        progress(&report);
+    }
+
+    report.phase = SnapshotPhase::Verifying;
+    progress(&report);
+    verify_restored(staging, &manifest)?;
+    Ok(manifest)
+}
+
+/// Reopen the restored database and check it against the manifest.
+fn verify_restored(path: &Path, manifest: &SnapshotManifest) -> Result<(), SnapshotError> {
+    let db = BlockchainDB::new(path)?;
+    let (digest, count) = db.utxo_set_digest()?;
+    if hex::encode(digest) != manifest.utxo_commitment || count != manifest.utxo_count {
+        return Err(SnapshotError::VerificationFailed(
+            "UTXO commitment mismatch".to_string(),
+        ));
+    }
+    let height = db.get_height()?;
+    if height != manifest.height {
+        return Err(SnapshotError::VerificationFailed(format!(
+            "height {} does not match manifest height {}",
+            height, manifest.height
+        )));
+    }
+    if hex::encode(db.get_best_block_hash()?) != manifest.tip_hash {
+        return Err(SnapshotError::VerificationFailed(
+            "tip hash does not match manifest".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn read_header<R: Read>(reader: &mut R) -> Result<SnapshotManifest, SnapshotError> {
+    let mut magic = [0u8; 8];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|_| SnapshotError::Corrupt("not a snapshot archive".to_string()))?;
+    if &magic != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::Corrupt("not a snapshot archive".to_string()));
+    }
+    let manifest_json = read_field(reader)?;
+    let manifest: SnapshotManifest = serde_json::from_slice(&manifest_json)
+        .map_err(|e| SnapshotError::Manifest(e.to_string()))?;
+    if manifest.format_version != SNAPSHOT_FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(manifest.format_version));
+    }
+    if manifest.trees.len() > u16::MAX as usize {
+        return Err(SnapshotError::Manifest("too many trees".to_string()));
+    }
+    Ok(manifest)
+}
+
+fn staging_path(datadir: &Path) -> PathBuf {
+    let mut name = datadir
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "datadir".into());
+    name.push(".importing");
+    datadir.with_file_name(name)
+}
+
+fn dir_has_entries(path: &Path) -> io::Result<bool> {
+    match fs::read_dir(path) {
+        Ok(mut entries) => Ok(entries.next().is_some()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_field<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_le_bytes())?;
+    writer.write_all(data)
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> Result<u8, SnapshotError> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf).map_err(truncated)?;
+    Ok(buf[0])
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, SnapshotError> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf).map_err(truncated)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_field<R: Read>(reader: &mut R) -> Result<Vec<u8>, SnapshotError> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).map_err(truncated)?;
+    let len = u32::from_le_bytes(len);
+    if len > MAX_FIELD_LEN {
+        return Err(SnapshotError::Corrupt(format!("field length {} too large", len)));
+    }
+    let mut data = vec![0u8; len as usize];
+    reader.read_exact(&mut data).map_err(truncated)?;
+    Ok(data)
+}
+
+fn truncated(e: io::Error) -> SnapshotError {
+    SnapshotError::Corrupt(format!("truncated or undecodable archive: {}", e))
+}
+
+/// Hashes every byte written through it.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    fn finish(self) -> (W, [u8; 32]) {
+        (self.inner, self.hasher.finalize().into())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Hashes every byte read through it.
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Digest of everything read so far
+    fn digest(&self) -> [u8; 32] {
+        self.hasher.clone().finalize().into()
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn network() -> SnapshotNetwork {
+        SnapshotNetwork {
+            chain_id: "supernova-dev".to_string(),
+            network_id: "supernova-testnet".to_string(),
+        }
+    }
+
+    /// A small chain: a height index, raw block payloads and a UTXO set.
+    fn synthetic_chain(path: &Path) -> BlockchainDB {
+        let db = BlockchainDB::new(path).unwrap();
+        for height in 0..=50u64 {
+            let hash = [height as u8; 32];
+            db.store_block_height_index(height, &hash).unwrap();
+            db.store_raw_data("headers", &hash, &height.to_le_bytes()).unwrap();
+            db.store_utxo(&hash, 0, &(height * 1_000).to_le_bytes()).unwrap();
+        }
+        db.set_height(50).unwrap();
+        db.flush().unwrap();
+        db
+    }
+
+    #[test]
+    fn export_import_round_trip_preserves_tip_and_commitment() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("chain.snapshot");
+        let source = synthetic_chain(&dir.path().join("source"));
+        let (digest, _) = source.utxo_set_digest().unwrap();
+        let tip = source.get_best_block_hash().unwrap();
+
+        let mut export_reports = Vec::new();
+        let manifest =
+            export_snapshot(&source, &network(), &archive, &mut |p| export_reports.push(*p))
+                .unwrap();
+        drop(source);
+        assert_eq!(manifest.height, 50);
+        assert_eq!(manifest.utxo_count, 51);
+        assert_eq!(read_manifest(&archive).unwrap(), manifest);
+        assert_eq!(export_reports.last().unwrap().entries_done, manifest.total_entries);
+
+        let target = dir.path().join("target");
+        let mut phases = Vec::new();
+        import_snapshot(&archive, &target, &network(), false, &mut |p| phases.push(p.phase))
+            .unwrap();
+        assert_eq!(phases.last(), Some(&SnapshotPhase::Verifying));
+        assert!(!staging_path(&target).exists());
+
+        let restored = BlockchainDB::new(&target).unwrap();
+        assert_eq!(restored.get_height().unwrap(), 50);
+        assert_eq!(restored.get_best_block_hash().unwrap(), tip);
+        assert_eq!(restored.utxo_set_digest().unwrap().0, digest);
+    }
+
+    #[test]
+    fn tampered_archive_is_rejected() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("chain.snapshot");
+        let source = synthetic_chain(&dir.path().join("source"));
+        export_snapshot(&source, &network(), &archive, &mut |_| {}).unwrap();
+        drop(source);
+
+        let mut bytes = fs::read(&archive).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xff;
+        fs::write(&archive, bytes).unwrap();
+
+        let target = dir.path().join("target");
+        let err = import_snapshot(&archive, &target, &network(), false, &mut |_| {}).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                SnapshotError::Corrupt(_)
+                    | SnapshotError::Manifest(_)
+                    | SnapshotError::VerificationFailed(_)
+            ),
+            "unexpected error: {err}"
+        );
+        assert!(!target.exists());
+        assert!(!staging_path(&target).exists());
+    }
+
+    #[test]
+    fn import_refuses_non_empty_datadir_and_foreign_network() {
+        let dir = tempdir().unwrap();
+        let archive = dir.path().join("chain.snapshot");
+        let source = synthetic_chain(&dir.path().join("source"));
+        export_snapshot(&source, &network(), &archive, &mut |_| {}).unwrap();
+        drop(source);
+
+        let target = dir.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("existing"), b"data").unwrap();
+        assert!(matches!(
+            import_snapshot(&archive, &target, &network(), false, &mut |_| {}),
+            Err(SnapshotError::TargetNotEmpty(_))
+        ));
+        assert!(target.join("existing").exists());
+
+        let mainnet = SnapshotNetwork {
+            network_id: "supernova-mainnet".to_string(),
+            ..network()
+        };
+        assert!(matches!(
+            import_snapshot(&archive, &target, &mainnet, true, &mut |_| {}),
+            Err(SnapshotError::NetworkMismatch { .. })
+        ));
+        assert!(target.join("existing").exists());
+
+        import_snapshot(&archive, &target, &network(), true, &mut |_| {}).unwrap();
+        assert!(!target.join("existing").exists());
+        assert_eq!(BlockchainDB::new(&target).unwrap().get_height().unwrap(), 50);
+    }
+}
@@ -0,0 +1,825 @@
+//! UTXO state snapshots for bootstrapping a node without replaying history.
+//!
+//! A snapshot at height `h` holds the best-chain headers up to `h`, the UTXO
+//! set as of `h` and the raw block at `h`, split into chunks of about
+//! [`CHUNK_TARGET_BYTES`]. Its manifest lists the SHA-256 of every chunk, so
+//! a chunk fetched from a peer is checked before it is applied. A manifest is
+//! only accepted for the height, tip hash and UTXO commitment the operator
+//! trusts: the restored headers must link that tip back to genesis and the
+//! restored UTXO set must reproduce that commitment, so a peer cannot
+//! substitute another history or set either.
+//!
+//! Snapshots are taken at exact heights. When the tip has moved past the
+//! height, the UTXO set is rewound with the undo data of the blocks above
+//! it. The records are written in key order, so every node produces the same
+//! chunks for the same height and a download can switch peers midway. A
+//! restored node keeps no blocks below the snapshot and looks like a node
+//! pruned at that height.
+
+use super::database::{BlockchainDB, StorageError};
+use super::reorg::ReorgChangeSet;
+use super::undo;
+use super::utxo_commitment::UtxoAccumulator;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use supernova_core::consensus::chainwork;
+use supernova_core::types::block::{Block, BlockHeader};
+use thiserror::Error;
+
+/// Snapshot format version written by this build
+pub const STATE_SNAPSHOT_VERSION: u32 = 1;
+
+/// A chunk is closed once the next record would take it past this size
+pub const CHUNK_TARGET_BYTES: usize = 1024 * 1024;
+
+/// Most chunks in one snapshot
+pub const MAX_SNAPSHOT_CHUNKS: usize = 65_536;
+
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Tree the UTXO set is restored into before it replaces the live set
+const RESTORE_TREE: &str = "snapshot_restore_utxos";
+
+#[derive(Debug, Error)]
+pub enum StateSnapshotError {
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sled::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] bincode::Error),
+
+    #[error("Invalid manifest: {0}")]
+    Manifest(String),
+
+    #[error("No snapshot at height {0}")]
+    NotFound(u64),
+
+    #[error("Cannot snapshot height {height}: {reason}")]
+    Unavailable { height: u64, reason: String },
+
+    #[error("Chunk {0} does not match its manifest hash")]
+    ChunkMismatch(u32),
+
+    #[error("Chunk {0} was not expected")]
+    UnexpectedChunk(u32),
+
+    #[error("Snapshot does not match the trusted {0}")]
+    Untrusted(&'static str),
+
+    #[error("Snapshot is corrupt: {0}")]
+    Corrupt(String),
+}
+
+/// The snapshot an operator trusts, published out of band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedSnapshot {
+    pub height: u64,
+    pub tip_hash: [u8; 32],
+    /// See `BlockchainDB::utxo_commitment`
+    pub utxo_commitment: [u8; 32],
+}
+
+/// Describes a snapshot and its chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateSnapshotManifest {
+    pub format_version: u32,
+    pub height: u64,
+    pub tip_hash: [u8; 32],
+    /// See `BlockchainDB::utxo_commitment`
+    pub utxo_commitment: [u8; 32],
+    pub utxo_count: u64,
+    /// SHA-256 of each chunk, in order
+    pub chunk_hashes: Vec<[u8; 32]>,
+    /// Unix timestamp of creation
+    pub created_at: u64,
+}
+
+impl StateSnapshotManifest {
+    /// The values an operator publishes to let others trust this snapshot
+    pub fn trusted(&self) -> TrustedSnapshot {
+        TrustedSnapshot {
+            height: self.height,
+            tip_hash: self.tip_hash,
+            utxo_commitment: self.utxo_commitment,
+        }
+    }
+
+    /// Check that the manifest describes `trusted` and is well formed
+    pub fn verify(&self, trusted: &TrustedSnapshot) -> Result<(), StateSnapshotError> {
+        if self.format_version != STATE_SNAPSHOT_VERSION {
+            return Err(StateSnapshotError::Manifest(format!(
+                "unsupported format version {}",
+                self.format_version
+            )));
+        }
+        if self.height != trusted.height {
+            return Err(StateSnapshotError::Untrusted("height"));
+        }
+        if self.tip_hash != trusted.tip_hash {
+            return Err(StateSnapshotError::Untrusted("tip hash"));
+        }
+        if self.utxo_commitment != trusted.utxo_commitment {
+            return Err(StateSnapshotError::Untrusted("UTXO commitment"));
+        }
+        if self.chunk_hashes.is_empty() || self.chunk_hashes.len() > MAX_SNAPSHOT_CHUNKS {
+            return Err(StateSnapshotError::Manifest(format!(
+                "{} chunks",
+                self.chunk_hashes.len()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// One entry of a chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum SnapshotRecord {
+    /// A best-chain header, bincode encoded
+    Header(Vec<u8>),
+    /// A UTXO entry as stored
+    Utxo(Vec<u8>, Vec<u8>),
+    /// The block at the snapshot height, bincode encoded
+    TipBlock(Vec<u8>),
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+fn chunk_file(index: u32) -> String {
+    format!("chunk_{}.bin", index)
+}
+
+/// Collects records into numbered chunk files
+struct ChunkWriter<'a> {
+    dir: &'a Path,
+    target_bytes: usize,
+    records: Vec<SnapshotRecord>,
+    bytes: usize,
+    hashes: Vec<[u8; 32]>,
+}
+
+impl<'a> ChunkWriter<'a> {
+    fn new(dir: &'a Path, target_bytes: usize) -> Self {
+        Self {
+            dir,
+            target_bytes,
+            records: Vec::new(),
+            bytes: 0,
+            hashes: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, record: SnapshotRecord) -> Result<(), StateSnapshotError> {
+        let size = bincode::serialized_size(&record)? as usize;
+        if !self.records.is_empty() && self.bytes + size > self.target_bytes {
+            self.flush()?;
+        }
+        self.bytes += size;
+        self.records.push(record);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StateSnapshotError> {
+        if self.records.is_empty() {
+            return Ok(());
+        }
+        if self.hashes.len() == MAX_SNAPSHOT_CHUNKS {
+            return Err(StateSnapshotError::Manifest(format!(
+                "more than {} chunks",
+                MAX_SNAPSHOT_CHUNKS
+            )));
+        }
+        let data = bincode::serialize(&self.records)?;
+        fs::write(self.dir.join(chunk_file(self.hashes.len() as u32)), &data)?;
+        self.hashes.push(sha256(&data));
+        self.records.clear();
+        self.bytes = 0;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<[u8; 32]>, StateSnapshotError> {
+        self.flush()?;
+        Ok(self.hashes)
+    }
+}
+
+/// UTXO changes that take the set at `tip` back to `height`
+fn rewind_changes(
+    db: &BlockchainDB,
+    tip: u64,
+    height: u64,
+) -> Result<ReorgChangeSet, StateSnapshotError> {
+    let unavailable = |reason: String| StateSnapshotError::Unavailable { height, reason };
+    if height > tip {
+        return Err(unavailable(format!("the tip is at {}", tip)));
+    }
+    let mut changes = ReorgChangeSet::new();
+    for above in (height + 1..=tip).rev() {
+        let hash = db
+            .get_block_hash_by_height(above)?
+            .ok_or_else(|| unavailable(format!("no block at height {}", above)))?;
+        let undo = db
+            .get_undo(&hash)?
+            .ok_or_else(|| unavailable(format!("undo data for height {} was pruned", above)))?;
+        undo::plan_disconnect(hash, &undo, &mut changes)?;
+    }
+    Ok(changes)
+}
+
+/// Write the chunks and manifest of a snapshot of the best chain at
+/// `height` into `dir`
+fn write_snapshot(
+    db: &BlockchainDB,
+    height: u64,
+    dir: &Path,
+    target_bytes: usize,
+) -> Result<StateSnapshotManifest, StateSnapshotError> {
+    let mut chunks = ChunkWriter::new(dir, target_bytes);
+    let mut commitment = UtxoAccumulator::default();
+    let mut utxo_count = 0u64;
+
+    let tip_hash = db.read_utxo_set(|tip, utxos| -> Result<[u8; 32], StateSnapshotError> {
+        let rewind = rewind_changes(db, tip, height)?;
+        let tip_hash = db
+            .get_block_hash_by_height(height)?
+            .ok_or(StateSnapshotError::NotFound(height))?;
+        let staged: BTreeMap<&[u8], Option<&[u8]>> = rewind.staged_utxos().into_iter().collect();
+        let mut staged = staged.into_iter().peekable();
+
+        let mut emit = |key: &[u8], value: &[u8]| {
+            commitment.insert(key, value);
+            utxo_count += 1;
+            chunks.push(SnapshotRecord::Utxo(key.to_vec(), value.to_vec()))
+        };
+        // Merge the rewound entries into the live set, in key order
+        for entry in utxos {
+            let (key, value) = entry?;
+            while let Some((staged_key, staged_value)) =
+                staged.next_if(|(staged_key, _)| *staged_key < key.as_ref())
+            {
+                if let Some(staged_value) = staged_value {
+                    emit(staged_key, staged_value)?;
+                }
+            }
+            match staged.next_if(|(staged_key, _)| *staged_key == key.as_ref()) {
+                Some((_, Some(staged_value))) => emit(key.as_ref(), staged_value)?,
+                Some((_, None)) => {}
+                None => emit(key.as_ref(), value.as_ref())?,
+            }
+        }
+        for (staged_key, staged_value) in staged {
+            if let Some(staged_value) = staged_value {
+                emit(staged_key, staged_value)?;
+            }
+        }
+        Ok(tip_hash)
+    })??;
+
+    // Walking back from the tip hash is unaffected by later reorgs
+    let mut headers = Vec::with_capacity(height as usize + 1);
+    let mut hash = tip_hash;
+    for expected in (0..=height).rev() {
+        let header =
+            db.get_stored_header(&hash)?
+                .ok_or_else(|| StateSnapshotError::Unavailable {
+                    height,
+                    reason: format!("no header at height {}", expected),
+                })?;
+        hash = *header.prev_block_hash();
+        headers.push(header);
+    }
+    for header in headers.iter().rev() {
+        chunks.push(SnapshotRecord::Header(bincode::serialize(header)?))?;
+    }
+    let tip_block = db
+        .get_block(&tip_hash)?
+        .ok_or_else(|| StateSnapshotError::Unavailable {
+            height,
+            reason: "the block was pruned".to_string(),
+        })?;
+    chunks.push(SnapshotRecord::TipBlock(bincode::serialize(&tip_block)?))?;
+
+    let manifest = StateSnapshotManifest {
+        format_version: STATE_SNAPSHOT_VERSION,
+        height,
+        tip_hash,
+        utxo_commitment: commitment.commitment(),
+        utxo_count,
+        chunk_hashes: chunks.finish()?,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| StateSnapshotError::Manifest(e.to_string()))?;
+    fs::write(dir.join(MANIFEST_FILE), json)?;
+    Ok(manifest)
+}
+
+/// Snapshots kept on disk, one `snapshot_<height>` directory each.
+#[derive(Debug, Clone)]
+pub struct StateSnapshotStore {
+    dir: PathBuf,
+    max_snapshots: usize,
+    chunk_bytes: usize,
+}
+
+impl StateSnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>, max_snapshots: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_snapshots: max_snapshots.max(1),
+            chunk_bytes: CHUNK_TARGET_BYTES,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Height of the latest snapshot due at `tip` when one is taken every
+    /// `interval` blocks
+    pub fn due_height(tip: u64, interval: u64) -> Option<u64> {
+        (interval > 0 && tip >= interval).then(|| tip / interval * interval)
+    }
+
+    /// Snapshot the best chain at `height`, dropping the oldest snapshots
+    /// beyond the limit. An existing snapshot at `height` is kept.
+    pub fn create(
+        &self,
+        db: &BlockchainDB,
+        height: u64,
+    ) -> Result<StateSnapshotManifest, StateSnapshotError> {
+        match self.manifest(height) {
+            Err(StateSnapshotError::NotFound(_)) => {}
+            existing => return existing,
+        }
+        let staging = self.dir.join(format!("snapshot_{}.tmp", height));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        let manifest = match write_snapshot(db, height, &staging, self.chunk_bytes) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e);
+            }
+        };
+        fs::rename(&staging, self.snapshot_dir(height))?;
+        self.prune()?;
+        Ok(manifest)
+    }
+
+    /// Heights of the complete snapshots, lowest first
+    pub fn heights(&self) -> Result<Vec<u64>, StateSnapshotError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut heights = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name();
+            if let Some(height) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("snapshot_"))
+                .and_then(|height| height.parse().ok())
+            {
+                heights.push(height);
+            }
+        }
+        heights.sort_unstable();
+        Ok(heights)
+    }
+
+    pub fn manifest(&self, height: u64) -> Result<StateSnapshotManifest, StateSnapshotError> {
+        let data = self.read(height, MANIFEST_FILE)?;
+        serde_json::from_slice(&data).map_err(|e| StateSnapshotError::Manifest(e.to_string()))
+    }
+
+    /// Raw bytes of chunk `index`, as hashed in the manifest
+    pub fn chunk(&self, height: u64, index: u32) -> Result<Vec<u8>, StateSnapshotError> {
+        self.read(height, &chunk_file(index))
+    }
+
+    fn read(&self, height: u64, file: &str) -> Result<Vec<u8>, StateSnapshotError> {
+        match fs::read(self.snapshot_dir(height).join(file)) {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Err(StateSnapshotError::NotFound(height))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn snapshot_dir(&self, height: u64) -> PathBuf {
+        self.dir.join(format!("snapshot_{}", height))
+    }
+
+    fn prune(&self) -> Result<(), StateSnapshotError> {
+        let heights = self.heights()?;
+        let excess = heights.len().saturating_sub(self.max_snapshots);
+        for height in &heights[..excess] {
+            tracing::info!("Removing state snapshot at height {}", height);
+            fs::remove_dir_all(self.snapshot_dir(*height))?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies a snapshot's chunks to a database as they arrive, then makes the
+/// restored state the node's chain.
+pub struct SnapshotRestore {
+    db: Arc<BlockchainDB>,
+    manifest: StateSnapshotManifest,
+    /// The restored UTXO set, kept apart from the live one until verified
+    staged: sled::Tree,
+    next_chunk: u32,
+    tip_block: Option<Block>,
+}
+
+impl SnapshotRestore {
+    /// Start restoring the snapshot `manifest` describes, which must match
+    /// `trusted`
+    pub fn begin(
+        db: Arc<BlockchainDB>,
+        manifest: StateSnapshotManifest,
+        trusted: &TrustedSnapshot,
+    ) -> Result<Self, StateSnapshotError> {
+        manifest.verify(trusted)?;
+        let staged = db.open_tree(RESTORE_TREE)?;
+        staged.clear()?;
+        Ok(Self {
+            db,
+            manifest,
+            staged,
+            next_chunk: 0,
+            tip_block: None,
+        })
+    }
+
+    pub fn manifest(&self) -> &StateSnapshotManifest {
+        &self.manifest
+    }
+
+    /// The next chunk to apply, or `None` once every chunk is applied
+    pub fn next_chunk(&self) -> Option<u32> {
+        ((self.next_chunk as usize) < self.manifest.chunk_hashes.len()).then_some(self.next_chunk)
+    }
+
+    /// Check chunk `index` against its manifest hash and apply it. Chunks
+    /// are applied in order.
+    pub fn apply_chunk(&mut self, index: u32, data: &[u8]) -> Result<(), StateSnapshotError> {
+        if self.next_chunk() != Some(index) {
+            return Err(StateSnapshotError::UnexpectedChunk(index));
+        }
+        if sha256(data) != self.manifest.chunk_hashes[index as usize] {
+            return Err(StateSnapshotError::ChunkMismatch(index));
+        }
+        let records: Vec<SnapshotRecord> = bincode::deserialize(data)?;
+        let mut utxos = sled::Batch::default();
+        for record in records {
+            match record {
+                SnapshotRecord::Header(bytes) => {
+                    let header: BlockHeader = bincode::deserialize(&bytes)?;
+                    self.db.store_block_header(&header.hash(), &bytes)?;
+                }
+                SnapshotRecord::Utxo(key, value) => utxos.insert(key, value),
+                SnapshotRecord::TipBlock(bytes) => {
+                    let block: Block = bincode::deserialize(&bytes)?;
+                    if block.hash() != self.manifest.tip_hash || !block.verify_merkle_root() {
+                        return Err(StateSnapshotError::Corrupt(
+                            "tip block does not match the manifest".to_string(),
+                        ));
+                    }
+                    self.tip_block = Some(block);
+                }
+            }
+        }
+        self.staged.apply_batch(utxos)?;
+        self.next_chunk += 1;
+        Ok(())
+    }
+
+    /// Verify the restored state and make it the node's chain. Nothing of
+    /// the live chain changes unless the UTXO set reproduces the trusted
+    /// commitment and the headers link the trusted tip to genesis.
+    pub fn finish(self) -> Result<StateSnapshotManifest, StateSnapshotError> {
+        let committed = self.commit();
+        if let Err(e) = self.db.db().drop_tree(RESTORE_TREE) {
+            tracing::warn!("Failed to drop the snapshot restore tree: {}", e);
+        }
+        committed?;
+        Ok(self.manifest)
+    }
+
+    fn commit(&self) -> Result<(), StateSnapshotError> {
+        let manifest = &self.manifest;
+        if self.next_chunk().is_some() {
+            return Err(StateSnapshotError::Corrupt(
+                "not every chunk was applied".to_string(),
+            ));
+        }
+        let mut commitment = UtxoAccumulator::default();
+        let mut utxo_count = 0u64;
+        for entry in self.staged.iter() {
+            let (key, value) = entry?;
+            commitment.insert(&key, &value);
+            utxo_count += 1;
+        }
+        if commitment.commitment() != manifest.utxo_commitment {
+            return Err(StateSnapshotError::Untrusted("UTXO commitment"));
+        }
+        if utxo_count != manifest.utxo_count {
+            return Err(StateSnapshotError::Corrupt(format!(
+                "{} UTXOs restored, manifest lists {}",
+                utxo_count, manifest.utxo_count
+            )));
+        }
+
+        let tip_block = self
+            .tip_block
+            .as_ref()
+            .ok_or_else(|| StateSnapshotError::Corrupt("no tip block".to_string()))?;
+
+        // The headers must link the trusted tip back to our genesis
+        let mut chain = Vec::with_capacity(manifest.height as usize + 1);
+        let mut total_difficulty = 0u64;
+        let mut hash = manifest.tip_hash;
+        for height in (0..=manifest.height).rev() {
+            let header = self
+                .db
+                .get_block_header(&hash)?
+                .filter(|header| header.height() == height)
+                .ok_or_else(|| {
+                    StateSnapshotError::Corrupt(format!("no header at height {}", height))
+                })?;
+            if height > 0 {
+                let work = chainwork::work_from_target(&header.target())
+                    .map(chainwork::work_to_u64_saturating)
+                    .unwrap_or(0);
+                total_difficulty = total_difficulty.saturating_add(work);
+            }
+            chain.push(hash);
+            hash = *header.prev_block_hash();
+        }
+        chain.reverse();
+        let genesis = self.db.get_metadata(b"genesis_hash")?;
+        if genesis
+            .as_ref()
+            .is_some_and(|genesis| genesis.as_ref() != chain[0])
+        {
+            return Err(StateSnapshotError::Untrusted("genesis block"));
+        }
+
+        for (height, hash) in chain.iter().enumerate() {
+            self.db.store_block_height_index(height as u64, hash)?;
+        }
+        self.db
+            .store_block(&manifest.tip_hash, &bincode::serialize(tip_block)?)?;
+        self.db.replace_utxo_set(&self.staged)?;
+        if self.db.utxo_commitment()? != manifest.utxo_commitment {
+            return Err(StateSnapshotError::Corrupt(
+                "UTXO set changed while it was restored".to_string(),
+            ));
+        }
+        if genesis.is_none() {
+            self.db.store_metadata(b"genesis_hash", &chain[0])?;
+        }
+        self.db
+            .store_metadata(b"total_difficulty", &bincode::serialize(&total_difficulty)?)?;
+        // Nothing below the snapshot is stored
+        self.db
+            .store_metadata(b"pruned_height", &manifest.height.to_be_bytes())?;
+        self.db.set_height(manifest.height)?;
+        self.db.store_metadata(b"best_hash", &manifest.tip_hash)?;
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+    use tempfile::tempdir;
+
+    /// A chain whose every block has a coinbase and, from height 2, a
+    /// transaction splitting the previous coinbase in two
+    fn chain(tag: u8, length: u64) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for height in 0..=length {
+            let coinbase = Transaction::new(
+                1,
+                vec![TransactionInput::new_coinbase(
+                    [&[tag][..], &height.to_le_bytes()].concat(),
+                )],
+                vec![TransactionOutput::new(50, vec![tag])],
+                0,
+            );
+            let mut transactions = vec![coinbase];
+            let prev = blocks.last().map(|block| block.hash()).unwrap_or([0u8; 32]);
+            if let Some(parent) = blocks.last().filter(|_| height >= 2) {
+                let spent = parent.transactions()[0].hash();
+                transactions.push(Transaction::new(
+                    1,
+                    vec![TransactionInput::new(spent, 0, Vec::new(), u32::MAX)],
+                    vec![
+                        TransactionOutput::new(20, vec![tag, 1]),
+                        TransactionOutput::new(30, vec![tag, 2]),
+                    ],
+                    0,
+                ));
+            }
+            let mut block = Block::new_with_params(1, prev, transactions, 0x207f_ffff);
+            block.set_height(height);
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn connect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let mut changes = ReorgChangeSet::new();
+        changes.put_block(block.hash(), bincode::serialize(block)?);
+        undo::plan_connect(db, block, &mut changes)?;
+        changes.put_height_index(block.height(), block.hash());
+        changes.put_meta(b"height".to_vec(), block.height().to_be_bytes().to_vec());
+        changes.put_meta(b"best_hash".to_vec(), block.hash().to_vec());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    fn node(path: &Path, blocks: &[Block]) -> Result<Arc<BlockchainDB>, StorageError> {
+        let db = Arc::new(BlockchainDB::new(path)?);
+        for block in blocks {
+            connect(&db, block)?;
+        }
+        Ok(db)
+    }
+
+    fn restore(
+        store: &StateSnapshotStore,
+        db: &Arc<BlockchainDB>,
+        manifest: &StateSnapshotManifest,
+        trusted: &TrustedSnapshot,
+    ) -> Result<StateSnapshotManifest, StateSnapshotError> {
+        let mut restore = SnapshotRestore::begin(Arc::clone(db), manifest.clone(), trusted)?;
+        while let Some(index) = restore.next_chunk() {
+            restore.apply_chunk(index, &store.chunk(manifest.height, index)?)?;
+        }
+        restore.finish()
+    }
+
+    #[test]
+    fn snapshots_rewind_to_their_height_and_restore_exactly() -> Result<(), StateSnapshotError> {
+        let dir = tempdir().unwrap();
+        let blocks = chain(1, 30);
+        let source = node(&dir.path().join("source"), &blocks)?;
+        let reference = node(&dir.path().join("reference"), &blocks[..=20])?;
+        let mut store = StateSnapshotStore::new(dir.path().join("snapshots"), 2);
+        store.chunk_bytes = 512;
+
+        // Taken ten blocks after its height, the snapshot still describes
+        // the UTXO set at that height
+        let manifest = store.create(&source, 20)?;
+        assert_eq!(manifest.tip_hash, blocks[20].hash());
+        assert_eq!(manifest.utxo_commitment, reference.utxo_commitment()?);
+        assert_eq!(manifest.utxo_count, reference.utxo_stats()?.count);
+        assert!(manifest.chunk_hashes.len() > 1);
+        assert_eq!(source.get_height()?, 30);
+
+        // Every node writes the same chunks for the same height
+        let mut other = StateSnapshotStore::new(dir.path().join("other"), 2);
+        other.chunk_bytes = 512;
+        assert_eq!(
+            other.create(&reference, 20)?.chunk_hashes,
+            manifest.chunk_hashes
+        );
+
+        let target = node(&dir.path().join("target"), &blocks[..1])?;
+        target.store_metadata(b"genesis_hash", &blocks[0].hash())?;
+        restore(&store, &target, &manifest, &manifest.trusted())?;
+        assert_eq!(target.get_height()?, 20);
+        assert_eq!(target.get_best_block_hash()?, blocks[20].hash());
+        assert_eq!(target.utxo_commitment()?, reference.utxo_commitment()?);
+        assert_eq!(target.pruned_height()?, 20);
+        for block in &blocks[..=20] {
+            let header = target.get_header_by_height(block.height())?.unwrap();
+            assert_eq!(header.hash(), block.hash());
+        }
+        assert!(target.get_block(&blocks[20].hash())?.is_some());
+        assert!(target.get_block(&blocks[10].hash())?.is_none());
+
+        // The restored node extends the chain like the reference does
+        for block in &blocks[21..] {
+            connect(&target, block)?;
+        }
+        assert_eq!(target.utxo_commitment()?, source.utxo_commitment()?);
+
+        // Older snapshots beyond the limit are dropped
+        store.create(&source, 25)?;
+        store.create(&source, 30)?;
+        assert_eq!(store.heights()?, vec![25, 30]);
+        assert!(matches!(
+            store.create(&source, 31),
+            Err(StateSnapshotError::Unavailable { height: 31, .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_and_untrusted_snapshots_are_rejected() -> Result<(), StateSnapshotError> {
+        let dir = tempdir().unwrap();
+        let blocks = chain(2, 12);
+        let source = node(&dir.path().join("source"), &blocks)?;
+        let mut store = StateSnapshotStore::new(dir.path().join("snapshots"), 4);
+        store.chunk_bytes = 512;
+        let manifest = store.create(&source, 12)?;
+        let trusted = manifest.trusted();
+        let target = node(&dir.path().join("target"), &blocks[..1])?;
+
+        // A flipped byte is caught before anything is applied
+        let mut restore = SnapshotRestore::begin(Arc::clone(&target), manifest.clone(), &trusted)?;
+        let mut chunk = store.chunk(12, 0)?;
+        chunk[0] ^= 0x01;
+        assert!(matches!(
+            restore.apply_chunk(0, &chunk),
+            Err(StateSnapshotError::ChunkMismatch(0))
+        ));
+        assert!(matches!(
+            restore.apply_chunk(1, &store.chunk(12, 1)?),
+            Err(StateSnapshotError::UnexpectedChunk(1))
+        ));
+        restore.apply_chunk(0, &store.chunk(12, 0)?)?;
+        drop(restore);
+
+        // A manifest for another state is refused outright
+        let mut other = trusted;
+        other.utxo_commitment[0] ^= 0x01;
+        assert!(matches!(
+            SnapshotRestore::begin(Arc::clone(&target), manifest.clone(), &other),
+            Err(StateSnapshotError::Untrusted("UTXO commitment"))
+        ));
+
+        // A peer relabelling the chunks of another chain gets through the
+        // chunk checks but not the commitment, and the live chain is untouched
+        let forged_blocks = chain(3, 12);
+        let forged_source = node(&dir.path().join("forged"), &forged_blocks)?;
+        let mut forged_store = StateSnapshotStore::new(dir.path().join("forged_snapshots"), 4);
+        forged_store.chunk_bytes = 512;
+        let mut forged = forged_store.create(&forged_source, 12)?;
+        forged.tip_hash = trusted.tip_hash;
+        forged.utxo_commitment = trusted.utxo_commitment;
+        assert!(matches!(
+            restore_forged(&forged_store, &target, &forged, &trusted),
+            Err(StateSnapshotError::Untrusted("UTXO commitment"))
+        ));
+        assert_eq!(target.get_height()?, 0);
+        assert_eq!(
+            target.get_utxo(&blocks[0].transactions()[0].hash(), 0)?,
+            Some(blocks[0].transactions()[0].outputs()[0].clone())
+        );
+        assert!(target
+            .get_utxo(&forged_blocks[1].transactions()[0].hash(), 0)?
+            .is_none());
+
+        restore(&store, &target, &manifest, &trusted)?;
+        assert_eq!(target.utxo_commitment()?, source.utxo_commitment()?);
+        Ok(())
+    }
+
+    /// Apply chunks that hash correctly under a relabelled manifest
+    fn restore_forged(
+        store: &StateSnapshotStore,
+        db: &Arc<BlockchainDB>,
+        forged: &StateSnapshotManifest,
+        trusted: &TrustedSnapshot,
+    ) -> Result<StateSnapshotManifest, StateSnapshotError> {
+        let mut restore = SnapshotRestore::begin(Arc::clone(db), forged.clone(), trusted)?;
+        while let Some(index) = restore.next_chunk() {
+            let chunk = store.chunk(forged.height, index)?;
+            // The forged tip block does not hash to the trusted tip
+            match restore.apply_chunk(index, &chunk) {
+                Err(StateSnapshotError::Corrupt(_)) => {
+                    restore.next_chunk += 1;
+                }
+                other => other?,
+            }
+        }
+        restore.finish()
+    }
+}
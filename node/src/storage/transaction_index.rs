@@ -7,6 +7,7 @@
 //! - Environmental score → green transactions
 //! - Lightning channel ID → channel transactions
 
+use supernova_core::script::classify;
 use supernova_core::types::transaction::{Transaction, TransactionOutput};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
@@ -516,18 +517,14 @@ impl TransactionIndexer {
         })
     }
 
-    /// Extract address from transaction output
-    fn extract_address_from_output(output: &TransactionOutput) -> Option<String> {
-        // Extract address from script_pubkey
-        // This is a simplified version - in production, you'd parse the script properly
-        let script = output.script_pubkey();
-        if script.is_empty() {
+    /// Key an output is indexed under: its address, or the hex script for
+    /// outputs without one (see `supernova_core::script::classify`). Data
+    /// carriers pay nobody and are not indexed.
+    pub(crate) fn extract_address_from_output(output: &TransactionOutput) -> Option<String> {
+        if output.is_unspendable() {
             return None;
         }
-
-        // For now, use hex encoding of script as address identifier
-        // In production, decode script to get actual address
-        Some(hex::encode(script))
+        classify::index_key(output.script_pubkey())
     }
 }
 
@@ -595,6 +592,52 @@ mod tests {
         assert!(txs.contains(&tx_hash));
     }
 
+    #[test]
+    fn test_quantum_outputs_indexed_by_address() {
+        let indexer = TransactionIndexer::new(TransactionIndexConfig::default());
+        let address = wallet::quantum_wallet::Address::from_public_key(&[9u8; 64]).unwrap();
+        let tx = Transaction::new(
+            1,
+            vec![TransactionInput::new([0u8; 32], 0, vec![], 0xffffffff)],
+            vec![TransactionOutput::new(1000, address.pubkey_hash().to_vec())],
+            0,
+        );
+
+        indexer
+            .index_transaction(&tx, [1u8; 32], 100, 0, None, None)
+            .unwrap();
+
+        // The wallet's address string finds the output directly
+        let txs = indexer
+            .get_transactions_by_address(&address.to_string(), None, 0)
+            .unwrap();
+        assert_eq!(txs, vec![tx.hash()]);
+    }
+
+    #[test]
+    fn test_data_outputs_not_indexed() {
+        let indexer = TransactionIndexer::new(TransactionIndexConfig::default());
+        let data = TransactionOutput::data_carrier(b"anchor");
+        let tx = Transaction::new(
+            1,
+            vec![TransactionInput::new([0u8; 32], 0, vec![], 0xffffffff)],
+            vec![TransactionOutput::new(1000, vec![1, 2, 3]), data.clone()],
+            0,
+        );
+
+        indexer
+            .index_transaction(&tx, [1u8; 32], 100, 0, None, None)
+            .unwrap();
+
+        assert_eq!(TransactionIndexer::extract_address_from_output(&data), None);
+        let data_key = hex::encode(data.script_pubkey());
+        assert!(indexer
+            .get_transactions_by_address(&data_key, None, 0)
+            .unwrap()
+            .is_empty());
+        assert_eq!(indexer.get_statistics().unwrap().indexed_addresses, 1);
+    }
+
     #[test]
     fn test_height_indexing() {
         let indexer = TransactionIndexer::new(TransactionIndexConfig::default());
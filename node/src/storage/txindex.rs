@@ -0,0 +1,340 @@
+//! Transaction index (`storage.tx_index`)
+//!
+//! Maps every best-chain txid to the block holding it and its offset in the
+//! block's transaction list. Entries are staged in the same change-set that
+//! connects or disconnects the block, so the index always moves with the
+//! chain. A node that turns the index on after it already has blocks runs a
+//! one-time [`TxIndexBuilder`] pass over the stored chain; the pass records
+//! its progress in the metadata tree together with each block's entries, so
+//! it resumes where it stopped after a restart. The same pass builds the
+//! address index when that is enabled.
+
+use super::address_index;
+use super::database::{BlockchainDB, StorageError};
+use super::reorg::ReorgChangeSet;
+use metrics::gauge;
+use std::sync::Arc;
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::Transaction;
+
+/// Metadata key of the next height the rebuild has to index
+pub(crate) const TX_INDEX_PROGRESS_KEY: &[u8] = b"tx_index_next";
+
+/// Rebuild progress once every stored block is indexed
+pub(crate) const TX_INDEX_SYNCED: u64 = u64::MAX;
+
+/// Blocks indexed between progress reports
+const REPORT_INTERVAL: u64 = 1_000;
+
+/// Blocks indexed per trip to the blocking pool
+const BATCH_BLOCKS: u64 = 500;
+
+/// Where a transaction sits in the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxLocation {
+    pub block_hash: [u8; 32],
+    /// Position in the block's transaction list
+    pub offset: u32,
+}
+
+impl TxLocation {
+    pub const ENCODED_LEN: usize = 36;
+
+    /// `block_hash || offset` (big-endian)
+    pub fn encode(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0u8; Self::ENCODED_LEN];
+        bytes[..32].copy_from_slice(&self.block_hash);
+        bytes[32..].copy_from_slice(&self.offset.to_be_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return None;
+        }
+        let mut block_hash = [0u8; 32];
+        block_hash.copy_from_slice(&bytes[..32]);
+        Some(Self {
+            block_hash,
+            offset: u32::from_be_bytes(bytes[32..].try_into().ok()?),
+        })
+    }
+}
+
+/// A best-chain transaction found through the index
+#[derive(Debug, Clone)]
+pub struct ConfirmedTransaction {
+    pub transaction: Transaction,
+    pub block_hash: [u8; 32],
+    pub block_height: u64,
+    pub offset: u32,
+    pub block_time: u64,
+    pub confirmations: u64,
+}
+
+/// Stage index entries for every transaction of a block being connected
+pub fn plan_index_block(block: &Block, changes: &mut ReorgChangeSet) {
+    let block_hash = block.hash();
+    for (offset, tx) in block.transactions().iter().enumerate() {
+        changes.put_tx_index(
+            tx.hash(),
+            TxLocation {
+                block_hash,
+                offset: offset as u32,
+            },
+        );
+    }
+}
+
+/// Stage the removal of a disconnected block's index entries
+pub fn plan_unindex_block(block: &Block, changes: &mut ReorgChangeSet) {
+    for tx in block.transactions() {
+        changes.del_tx_index(tx.hash());
+    }
+}
+
+/// One-time pass indexing the blocks stored before the index, or the
+/// address index, was enabled
+pub struct TxIndexBuilder {
+    db: Arc<BlockchainDB>,
+}
+
+impl TxIndexBuilder {
+    pub fn new(db: Arc<BlockchainDB>) -> Self {
+        Self { db }
+    }
+
+    /// Build the index in batches on the blocking pool until it covers the
+    /// chain. Stopping between batches loses nothing.
+    pub async fn run(self: Arc<Self>) {
+        loop {
+            let builder = Arc::clone(&self);
+            match tokio::task::spawn_blocking(move || builder.index_batch(BATCH_BLOCKS)).await {
+                Ok(Ok(true)) => {}
+                Ok(Ok(false)) => return,
+                Ok(Err(e)) => {
+                    tracing::error!("Transaction index build stopped: {}", e);
+                    return;
+                }
+                Err(e) => {
+                    tracing::error!("Transaction index build task failed: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Index up to `max_blocks` blocks; `false` once the index covers the
+    /// chain
+    pub fn index_batch(&self, max_blocks: u64) -> Result<bool, StorageError> {
+        for _ in 0..max_blocks {
+            if !self.step()? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Index the next block; `false` once the index covers the chain.
+    /// Blocks connected meanwhile are indexed as they connect, their address
+    /// entries once the pass has reached their height, so it only has to
+    /// reach the tip once. A block replaced by a reorg while being indexed
+    /// is retried.
+    pub fn step(&self) -> Result<bool, StorageError> {
+        let Some(height) = self.db.tx_index_next_height()? else {
+            return Ok(false);
+        };
+        let tip = self.db.get_height()?;
+        if height > tip {
+            if !self.db.mark_tx_index_synced(height)? {
+                return Ok(true);
+            }
+            publish(tip, tip, true);
+            tracing::info!("Transaction index built up to height {}", tip);
+            return Ok(false);
+        }
+
+        match self.db.get_block_by_height(height)? {
+            Some(block) => {
+                let mut changes = ReorgChangeSet::new();
+                plan_index_block(&block, &mut changes);
+                if self.db.address_index_enabled() {
+                    let prevouts = address_index::indexed_prevouts(&self.db, &block)?;
+                    address_index::plan_index_block(&block, &prevouts, &mut changes);
+                }
+                let block_hash = block.hash();
+                if !self.db.apply_index_changes(&changes, height, &block_hash)? {
+                    return Ok(true);
+                }
+            }
+            // Pruned blocks, or those below a restored snapshot, are skipped
+            None if height < self.db.pruned_height()? => {
+                self.db.set_tx_index_next_height(self.db.pruned_height()?)?;
+            }
+            None => {
+                return Err(StorageError::DatabaseError(format!(
+                    "Cannot index transactions: block {} of the best chain is missing",
+                    height
+                )))
+            }
+        }
+        if height % REPORT_INTERVAL == 0 {
+            publish(height, tip, false);
+            tracing::info!("Indexing transactions: {}/{} blocks", height, tip);
+        }
+        Ok(true)
+    }
+}
+
+fn publish(height: u64, tip: u64, synced: bool) {
+    gauge!("supernova_txindex_height", height as f64);
+    gauge!(
+        "supernova_txindex_progress_percent",
+        if tip == 0 {
+            100.0
+        } else {
+            height as f64 * 100.0 / tip as f64
+        }
+    );
+    gauge!("supernova_txindex_synced", if synced { 1.0 } else { 0.0 });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::undo;
+    use crate::storage::ChainState;
+    use supernova_core::consensus::difficulty_retarget::RetargetParams;
+    use supernova_core::types::transaction::{TransactionInput, TransactionOutput};
+    use tempfile::tempdir;
+
+    /// Blocks with a coinbase and, from height 2, a transaction spending the
+    /// previous coinbase
+    fn chain(length: u64) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for height in 0..=length {
+            let mut transactions = vec![Transaction::new(
+                1,
+                vec![TransactionInput::new_coinbase(
+                    height.to_le_bytes().to_vec(),
+                )],
+                vec![TransactionOutput::new(50, vec![1])],
+                0,
+            )];
+            let prev = blocks.last().map(|block| block.hash()).unwrap_or([0u8; 32]);
+            if let Some(parent) = blocks.last().filter(|_| height >= 2) {
+                transactions.push(Transaction::new(
+                    1,
+                    vec![TransactionInput::new(
+                        parent.transactions()[0].hash(),
+                        0,
+                        Vec::new(),
+                        u32::MAX,
+                    )],
+                    vec![TransactionOutput::new(40, vec![2])],
+                    0,
+                ));
+            }
+            let mut block = Block::new_with_params(1, prev, transactions, 0x207f_ffff);
+            block.set_height(height);
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn connect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let mut changes = ReorgChangeSet::new();
+        changes.put_block(block.hash(), bincode::serialize(block)?);
+        undo::plan_connect(db, block, &mut changes)?;
+        if db.tx_index_enabled() {
+            plan_index_block(block, &mut changes);
+        }
+        changes.put_height_index(block.height(), block.hash());
+        changes.put_meta(b"height".to_vec(), block.height().to_be_bytes().to_vec());
+        changes.put_meta(b"best_hash".to_vec(), block.hash().to_vec());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    fn disconnect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let undo = db.get_undo(&block.hash())?.expect("undo record");
+        let mut changes = ReorgChangeSet::new();
+        undo::plan_disconnect(block.hash(), &undo, &mut changes)?;
+        plan_unindex_block(block, &mut changes);
+        changes.del_height_index(block.height());
+        changes.put_meta(
+            b"height".to_vec(),
+            (block.height() - 1).to_be_bytes().to_vec(),
+        );
+        changes.put_meta(b"best_hash".to_vec(), block.prev_block_hash().to_vec());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    #[test]
+    fn rebuild_resumes_after_restart_and_follows_the_chain() -> Result<(), StorageError> {
+        let dir = tempdir().unwrap();
+        let blocks = chain(12);
+        let spend = blocks[7].transactions()[1].hash();
+        {
+            let db = Arc::new(BlockchainDB::new(dir.path())?);
+            for block in &blocks[..=10] {
+                connect(&db, block)?;
+            }
+
+            // Enabled after the fact, the index starts empty
+            db.set_tx_index(true)?;
+            assert_eq!(db.tx_index_next_height()?, Some(0));
+            assert_eq!(db.get_tx_location(&spend)?, None);
+            let builder = TxIndexBuilder::new(Arc::clone(&db));
+            for _ in 0..5 {
+                assert!(builder.step()?);
+            }
+            db.flush()?;
+        }
+
+        // The rebuild picks up where it stopped
+        let db = Arc::new(BlockchainDB::new(dir.path())?);
+        db.set_tx_index(true)?;
+        assert_eq!(db.tx_index_next_height()?, Some(5));
+        connect(&db, &blocks[11])?;
+        let builder = TxIndexBuilder::new(Arc::clone(&db));
+        assert!(builder.index_batch(6)?);
+        assert!(!builder.index_batch(6)?);
+        assert_eq!(db.tx_index_next_height()?, None);
+        for block in &blocks[..=11] {
+            for (offset, tx) in block.transactions().iter().enumerate() {
+                let location = db.get_tx_location(&tx.hash())?.unwrap();
+                assert_eq!(location.block_hash, block.hash());
+                assert_eq!(location.offset, offset as u32);
+            }
+        }
+
+        // Confirmations follow the tip, disconnected transactions vanish
+        connect(&db, &blocks[12])?;
+        let state = ChainState::with_params(
+            Arc::clone(&db),
+            RetargetParams {
+                target_block_time: 30,
+                interval: 100_000,
+                pow_limit_bits: 0x207f_ffff,
+                allow_min_difficulty_blocks: false,
+            },
+        )?;
+        let found = state.get_transaction_by_id(&spend)?.unwrap();
+        assert_eq!(found.block_height, 7);
+        assert_eq!(found.offset, 1);
+        assert_eq!(found.confirmations, 6);
+        assert_eq!(found.transaction.hash(), spend);
+
+        let last = blocks[12].transactions()[1].hash();
+        assert!(state.get_transaction_by_id(&last)?.is_some());
+        disconnect(&db, &blocks[12])?;
+        assert_eq!(db.get_tx_location(&last)?, None);
+
+        // Turning the index off drops it
+        db.set_tx_index(false)?;
+        db.set_tx_index(true)?;
+        assert_eq!(db.get_tx_location(&spend)?, None);
+        assert_eq!(db.tx_index_next_height()?, Some(0));
+        Ok(())
+    }
+}
@@ -0,0 +1,350 @@
+//! Per-block undo data.
+//!
+//! Connecting a block records the outputs it spent, with their original
+//! contents, and the outpoints it created. Disconnecting the block replays
+//! that record instead of searching earlier blocks for the spent outputs.
+//! Records are stored as `[version][bincode payload][crc32]`, the checksum
+//! covering the version byte, so a damaged record surfaces as
+//! `StorageError::CorruptUndo` rather than as a wrong UTXO set.
+
+use crate::storage::checksum::{ChecksumError, ChecksummedData};
+use crate::storage::database::{create_utxo_key, BlockchainDB, StorageError};
+use crate::storage::reorg::ReorgChangeSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::TransactionOutput;
+use thiserror::Error;
+
+/// Current encoding of undo records
+pub const UNDO_FORMAT_VERSION: u8 = 1;
+
+/// Blocks below the tip whose undo data is kept unless configured otherwise
+pub const DEFAULT_UNDO_DEPTH: u64 = 1_000;
+
+#[derive(Debug, Error)]
+pub enum UndoFormatError {
+    #[error("record is empty")]
+    Empty,
+    #[error("unsupported format version {0}")]
+    UnsupportedVersion(u8),
+    #[error("{0}")]
+    Checksum(#[from] ChecksumError),
+    #[error("payload could not be decoded: {0}")]
+    Codec(#[from] bincode::Error),
+}
+
+/// An output spent by the block, as it was in the UTXO set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpentOutput {
+    pub tx_hash: [u8; 32],
+    pub index: u32,
+    pub output: TransactionOutput,
+}
+
+/// What connecting a block changed in the UTXO set
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockUndo {
+    /// Outputs the block spent that existed before it
+    pub spent: Vec<SpentOutput>,
+    /// Outpoints the block created and left unspent
+    pub created: Vec<([u8; 32], u32)>,
+}
+
+impl BlockUndo {
+    pub fn encode(&self) -> Result<Vec<u8>, StorageError> {
+        let mut data = vec![UNDO_FORMAT_VERSION];
+        data.extend(bincode::serialize(self)?);
+        Ok(ChecksummedData::new(data).to_bytes())
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, UndoFormatError> {
+        let data = ChecksummedData::from_bytes(bytes)?.into_data();
+        match data.split_first() {
+            None => Err(UndoFormatError::Empty),
+            Some((&UNDO_FORMAT_VERSION, payload)) => Ok(bincode::deserialize(payload)?),
+            Some((version, _)) => Err(UndoFormatError::UnsupportedVersion(*version)),
+        }
+    }
+}
+
+/// Stage the UTXO changes of connecting `block`, together with its undo
+/// record. Spent outputs are looked up in the UTXO set as it will be once the
+/// ops already in `changes` are applied, so a whole branch can be staged
+/// block by block. Outputs created and spent within the block never reach
+/// the UTXO set and appear in neither list.
+pub fn plan_connect(
+    db: &BlockchainDB,
+    block: &Block,
+    changes: &mut ReorgChangeSet,
+) -> Result<BlockUndo, StorageError> {
+    let staged = changes.staged_utxos();
+    let mut undo = BlockUndo::default();
+    let mut created = BTreeMap::new();
+    for tx in block.transactions() {
+        if !tx.is_coinbase() {
+            for input in tx.inputs() {
+                let (tx_hash, index) = (input.prev_tx_hash(), input.prev_output_index());
+                if created.remove(&(tx_hash, index)).is_some() {
+                    continue;
+                }
+                undo.spent.push(SpentOutput {
+                    tx_hash,
+                    index,
+                    output: spent_output(db, &staged, &tx_hash, index)?,
+                });
+            }
+        }
+        let tx_hash = tx.txid();
+        for (index, output) in tx.outputs().iter().enumerate() {
+            created.insert((tx_hash, index as u32), output);
+        }
+    }
+
+    for spent in &undo.spent {
+        changes.del_utxo(create_utxo_key(&spent.tx_hash, spent.index));
+    }
+    for ((tx_hash, index), output) in created {
+        changes.put_utxo(
+            create_utxo_key(&tx_hash, index),
+            bincode::serialize(output)?,
+        );
+        undo.created.push((tx_hash, index));
+    }
+    changes.put_undo(block.hash(), undo.encode()?);
+    Ok(undo)
+}
+
+fn spent_output(
+    db: &BlockchainDB,
+    staged: &HashMap<&[u8], Option<&[u8]>>,
+    tx_hash: &[u8; 32],
+    index: u32,
+) -> Result<TransactionOutput, StorageError> {
+    let output = match staged.get(create_utxo_key(tx_hash, index).as_slice()) {
+        Some(Some(bytes)) => Some(bincode::deserialize(bytes)?),
+        Some(None) => None,
+        None => db.get_utxo(tx_hash, index)?,
+    };
+    output.ok_or_else(|| {
+        StorageError::KeyNotFound(format!("spent output {}:{}", hex::encode(tx_hash), index))
+    })
+}
+
+/// Stage the UTXO changes of disconnecting the block `undo` was recorded
+/// for, and the removal of the record itself
+pub fn plan_disconnect(
+    block_hash: [u8; 32],
+    undo: &BlockUndo,
+    changes: &mut ReorgChangeSet,
+) -> Result<(), StorageError> {
+    for (tx_hash, index) in &undo.created {
+        changes.del_utxo(create_utxo_key(tx_hash, *index));
+    }
+    for spent in &undo.spent {
+        changes.put_utxo(
+            create_utxo_key(&spent.tx_hash, spent.index),
+            bincode::serialize(&spent.output)?,
+        );
+    }
+    changes.del_undo(block_hash);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use supernova_core::types::transaction::{Transaction, TransactionInput};
+    use tempfile::tempdir;
+
+    fn connect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let mut changes = ReorgChangeSet::new();
+        changes.put_block(block.hash(), bincode::serialize(block)?);
+        plan_connect(db, block, &mut changes)?;
+        changes.put_height_index(block.height(), block.hash());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    fn disconnect(db: &BlockchainDB, block: &Block) -> Result<(), StorageError> {
+        let undo = db.get_undo(&block.hash())?.expect("undo record");
+        let mut changes = ReorgChangeSet::new();
+        plan_disconnect(block.hash(), &undo, &mut changes)?;
+        changes.del_height_index(block.height());
+        db.apply_reorg_atomically(&changes)
+    }
+
+    /// A coinbase plus up to three transactions spending random entries of
+    /// `unspent`, which may include outputs created earlier in the block
+    fn random_block(
+        rng: &mut StdRng,
+        prev: [u8; 32],
+        height: u64,
+        unspent: &mut Vec<([u8; 32], u32)>,
+    ) -> Block {
+        let coinbase = Transaction::new(
+            1,
+            vec![TransactionInput::new_coinbase(
+                height.to_le_bytes().to_vec(),
+            )],
+            vec![TransactionOutput::new(50, vec![rng.gen::<u8>(); 4])],
+            0,
+        );
+        unspent.push((coinbase.txid(), 0));
+        let mut transactions = vec![coinbase];
+
+        for _ in 0..rng.gen_range(0..4) {
+            if unspent.is_empty() {
+                break;
+            }
+            let inputs = (0..rng.gen_range(1..=unspent.len().min(3)))
+                .map(|_| {
+                    let (hash, index) = unspent.swap_remove(rng.gen_range(0..unspent.len()));
+                    TransactionInput::new(hash, index, Vec::new(), u32::MAX)
+                })
+                .collect();
+            let outputs = (0..rng.gen_range(1..4))
+                .map(|_| {
+                    let script = vec![rng.gen::<u8>(); rng.gen_range(1..8)];
+                    TransactionOutput::new(rng.gen_range(1..1_000), script)
+                })
+                .collect();
+            let tx = Transaction::new(1, inputs, outputs, 0);
+            unspent.extend((0..tx.outputs().len() as u32).map(|index| (tx.txid(), index)));
+            transactions.push(tx);
+        }
+
+        let mut block = Block::new_with_params(1, prev, transactions, 0x207f_ffff);
+        block.set_height(height);
+        block
+    }
+
+    #[test]
+    fn disconnecting_random_blocks_restores_the_utxo_set() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = BlockchainDB::new(temp_dir.path())?;
+        let mut rng = StdRng::seed_from_u64(1773);
+
+        let mut unspent = Vec::new();
+        for n in 1..=20u8 {
+            let output = TransactionOutput::new(1_000, vec![n]);
+            db.store_utxo(&[n; 32], 0, &bincode::serialize(&output)?)?;
+            unspent.push(([n; 32], 0));
+        }
+
+        let mut blocks = Vec::new();
+        let mut digests = vec![db.utxo_set_digest()?.0];
+        let mut prev = [0u8; 32];
+        for height in 1..=100 {
+            let block = random_block(&mut rng, prev, height, &mut unspent);
+            connect(&db, &block)?;
+            prev = block.hash();
+            blocks.push(block);
+            digests.push(db.utxo_set_digest()?.0);
+        }
+
+        // Every disconnect returns the set to exactly its state before the
+        // block, all the way back to the original
+        for block in blocks.iter().rev() {
+            disconnect(&db, block)?;
+            digests.pop();
+            assert_eq!(db.utxo_set_digest()?.0, *digests.last().unwrap());
+            assert!(db.get_undo(&block.hash())?.is_none());
+        }
+        assert_eq!(db.utxo_set_digest()?.1, 20);
+        assert_eq!(db.utxo_stats()?, db.scan_utxo_stats()?.1);
+        Ok(())
+    }
+
+    #[test]
+    fn a_branch_staged_in_one_change_set_matches_block_by_block() -> Result<(), StorageError> {
+        let (dir_a, dir_b) = (tempdir().unwrap(), tempdir().unwrap());
+        let (one_by_one, staged) = (
+            BlockchainDB::new(dir_a.path())?,
+            BlockchainDB::new(dir_b.path())?,
+        );
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut unspent = Vec::new();
+        let mut prev = [0u8; 32];
+        let mut branch = ReorgChangeSet::new();
+        let mut blocks = Vec::new();
+        for height in 1..=20 {
+            let block = random_block(&mut rng, prev, height, &mut unspent);
+            connect(&one_by_one, &block)?;
+            // Later blocks spend outputs only staged by earlier ones
+            plan_connect(&staged, &block, &mut branch)?;
+            prev = block.hash();
+            blocks.push(block);
+        }
+        staged.apply_reorg_atomically(&branch)?;
+        assert_eq!(staged.utxo_set_digest()?, one_by_one.utxo_set_digest()?);
+
+        let mut unwind = ReorgChangeSet::new();
+        for block in blocks.iter().rev() {
+            let undo = staged.get_undo(&block.hash())?.expect("undo record");
+            plan_disconnect(block.hash(), &undo, &mut unwind)?;
+        }
+        staged.apply_reorg_atomically(&unwind)?;
+        assert_eq!(staged.utxo_set_digest()?.1, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn damaged_undo_records_are_reported() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = BlockchainDB::new(temp_dir.path())?;
+        let mut rng = StdRng::seed_from_u64(7);
+        let block = random_block(&mut rng, [0u8; 32], 1, &mut Vec::new());
+        connect(&db, &block)?;
+        let hash = block.hash();
+        assert!(db.get_undo(&hash)?.is_some());
+
+        let mut bytes = db.get_raw_data("block_undo", &hash)?.unwrap().to_vec();
+        bytes[1] ^= 0xff;
+        db.store_raw_data("block_undo", &hash, &bytes)?;
+        assert!(matches!(
+            db.get_undo(&hash),
+            Err(StorageError::CorruptUndo {
+                source: UndoFormatError::Checksum(_),
+                ..
+            })
+        ));
+
+        // A record in a format this node does not know is refused, not misread
+        let newer = ChecksummedData::new(vec![UNDO_FORMAT_VERSION + 1]).to_bytes();
+        db.store_raw_data("block_undo", &hash, &newer)?;
+        assert!(matches!(
+            db.get_undo(&hash),
+            Err(StorageError::CorruptUndo {
+                source: UndoFormatError::UnsupportedVersion(2),
+                ..
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn pruning_keeps_recent_undo_only() -> Result<(), StorageError> {
+        let temp_dir = tempdir().unwrap();
+        let db = BlockchainDB::new(temp_dir.path())?;
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut unspent = Vec::new();
+        let mut blocks = Vec::new();
+        let mut prev = [0u8; 32];
+        for height in 1..=10 {
+            let block = random_block(&mut rng, prev, height, &mut unspent);
+            connect(&db, &block)?;
+            prev = block.hash();
+            blocks.push(block);
+        }
+
+        assert_eq!(db.prune_undo(7)?, 6);
+        assert_eq!(db.prune_undo(7)?, 0);
+        for block in &blocks {
+            let kept = db.get_undo(&block.hash())?.is_some();
+            assert_eq!(kept, block.height() >= 7, "height {}", block.height());
+        }
+        Ok(())
+    }
+}
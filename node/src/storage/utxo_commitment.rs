@@ -0,0 +1,417 @@
+//! Incremental UTXO set commitment
+//!
+//! The commitment is a lattice hash (LtHash16) of every UTXO entry: each
+//! entry is expanded with BLAKE3 into 1024 16-bit lanes, added lane-wise
+//! modulo 2^16 when the entry is written and subtracted when it is removed.
+//! Since addition commutes, the state depends only on the set and not on the
+//! order of writes, and keeping it current costs one hash per UTXO write. The
+//! published commitment is the BLAKE3 hash of the state.
+//!
+//! The state is kept in 256 buckets by first key byte (the first byte of the
+//! transaction hash); the buckets sum to the state of the whole set. A bucket
+//! can be recomputed from a prefix scan of the UTXO tree, so the fast
+//! integrity check recomputes a random sample of buckets instead of the set.
+
+use super::database::{BlockchainDB, StorageError};
+use rand::seq::index::sample;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Lanes in an [`LtHash`]
+pub const LTHASH_LANES: usize = 1024;
+
+/// Buckets the commitment is split into, one per first key byte
+pub const COMMITMENT_BUCKETS: usize = 256;
+
+/// Buckets recomputed by a fast integrity check unless told otherwise
+pub const DEFAULT_SAMPLE_BUCKETS: usize = 8;
+
+/// BLAKE3 key derivation context for entry hashes
+const ENTRY_CONTEXT: &str = "supernova 2024-06 utxo commitment entry";
+
+/// Metadata key prefix of the persisted buckets, followed by the bucket index
+pub(crate) const UTXO_COMMITMENT_PREFIX: &[u8] = b"utxo_commitment/";
+
+pub(crate) fn bucket_key(bucket: u8) -> Vec<u8> {
+    let mut key = UTXO_COMMITMENT_PREFIX.to_vec();
+    key.push(bucket);
+    key
+}
+
+/// Homomorphic multiset hash of 1024 16-bit lanes
+#[derive(Clone, PartialEq, Eq)]
+pub struct LtHash {
+    lanes: Box<[u16]>,
+}
+
+impl Default for LtHash {
+    fn default() -> Self {
+        Self {
+            lanes: vec![0; LTHASH_LANES].into_boxed_slice(),
+        }
+    }
+}
+
+impl std::fmt::Debug for LtHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LtHash({})", hex::encode(self.digest()))
+    }
+}
+
+impl LtHash {
+    fn entry(key: &[u8], value: &[u8]) -> Self {
+        let mut hasher = blake3::Hasher::new_derive_key(ENTRY_CONTEXT);
+        hasher.update(&(key.len() as u32).to_le_bytes());
+        hasher.update(key);
+        hasher.update(value);
+        let mut bytes = [0u8; LTHASH_LANES * 2];
+        hasher.finalize_xof().fill(&mut bytes);
+        Self {
+            lanes: bytes
+                .chunks_exact(2)
+                .map(|lane| u16::from_le_bytes([lane[0], lane[1]]))
+                .collect(),
+        }
+    }
+
+    /// Add an entry
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.combine(&Self::entry(key, value));
+    }
+
+    /// Take out an entry added earlier
+    pub fn remove(&mut self, key: &[u8], value: &[u8]) {
+        let entry = Self::entry(key, value);
+        for (lane, other) in self.lanes.iter_mut().zip(entry.lanes.iter()) {
+            *lane = lane.wrapping_sub(*other);
+        }
+    }
+
+    /// Add every entry of `other`
+    pub fn combine(&mut self, other: &LtHash) {
+        for (lane, other) in self.lanes.iter_mut().zip(other.lanes.iter()) {
+            *lane = lane.wrapping_add(*other);
+        }
+    }
+
+    /// BLAKE3 of the lanes
+    pub fn digest(&self) -> [u8; 32] {
+        blake3::hash(&self.to_bytes()).into()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.lanes
+            .iter()
+            .flat_map(|lane| lane.to_le_bytes())
+            .collect()
+    }
+
+    /// `None` unless `bytes` holds exactly [`LTHASH_LANES`] lanes
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != LTHASH_LANES * 2 {
+            return None;
+        }
+        Some(Self {
+            lanes: bytes
+                .chunks_exact(2)
+                .map(|lane| u16::from_le_bytes([lane[0], lane[1]]))
+                .collect(),
+        })
+    }
+}
+
+/// Running commitment to a set of key/value entries, split into buckets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoAccumulator {
+    buckets: Vec<LtHash>,
+}
+
+impl Default for UtxoAccumulator {
+    fn default() -> Self {
+        Self {
+            buckets: vec![LtHash::default(); COMMITMENT_BUCKETS],
+        }
+    }
+}
+
+impl UtxoAccumulator {
+    /// Accumulator of `entries`, computed from scratch
+    pub fn from_entries<K, V>(entries: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<[u8]>,
+        V: AsRef<[u8]>,
+    {
+        let mut accumulator = Self::default();
+        for (key, value) in entries {
+            accumulator.insert(key.as_ref(), value.as_ref());
+        }
+        accumulator
+    }
+
+    /// Bucket an entry falls in
+    pub fn bucket_of(key: &[u8]) -> u8 {
+        key.first().copied().unwrap_or(0)
+    }
+
+    pub fn insert(&mut self, key: &[u8], value: &[u8]) {
+        self.buckets[Self::bucket_of(key) as usize].insert(key, value);
+    }
+
+    pub fn remove(&mut self, key: &[u8], value: &[u8]) {
+        self.buckets[Self::bucket_of(key) as usize].remove(key, value);
+    }
+
+    /// Account for the entry at `key` being replaced (`old`) and/or written
+    /// (`new`). Returns the bucket that changed.
+    pub fn update(&mut self, key: &[u8], old: Option<&[u8]>, new: Option<&[u8]>) -> u8 {
+        if let Some(old) = old {
+            self.remove(key, old);
+        }
+        if let Some(new) = new {
+            self.insert(key, new);
+        }
+        Self::bucket_of(key)
+    }
+
+    pub fn bucket(&self, bucket: u8) -> &LtHash {
+        &self.buckets[bucket as usize]
+    }
+
+    pub(crate) fn set_bucket(&mut self, bucket: u8, hash: LtHash) {
+        self.buckets[bucket as usize] = hash;
+    }
+
+    /// Hash of the whole set: the sum of all buckets
+    pub fn state(&self) -> LtHash {
+        let mut state = LtHash::default();
+        for bucket in &self.buckets {
+            state.combine(bucket);
+        }
+        state
+    }
+
+    /// The published commitment
+    pub fn commitment(&self) -> [u8; 32] {
+        self.state().digest()
+    }
+}
+
+/// One bucket recomputed from the UTXO tree
+#[derive(Debug, Clone)]
+pub struct BucketCheck {
+    pub bucket: u8,
+    /// Entries in the bucket
+    pub entries: u64,
+    pub recomputed: LtHash,
+    /// Whether the recomputed bucket equals the running one
+    pub matches: bool,
+}
+
+/// Outcome of an integrity check of the UTXO set against its commitment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UtxoIntegrityReport {
+    /// Whether every bucket was recomputed
+    pub full: bool,
+    /// Running commitment (hex)
+    pub commitment: String,
+    /// Commitment recomputed from scratch (hex); full checks only
+    pub recomputed_commitment: Option<String>,
+    /// Buckets recomputed, out of 256
+    pub buckets_checked: usize,
+    /// UTXO entries read
+    pub entries_checked: u64,
+    /// Buckets whose recomputed hash differs from the running one
+    pub mismatched_buckets: Vec<u8>,
+    /// Whether every checked bucket matched
+    pub consistent: bool,
+}
+
+impl UtxoIntegrityReport {
+    /// Report over `checks`. `full` checks must cover every bucket.
+    pub fn new(commitment: [u8; 32], checks: &[BucketCheck], full: bool) -> Self {
+        let mismatched_buckets: Vec<u8> = checks
+            .iter()
+            .filter(|check| !check.matches)
+            .map(|check| check.bucket)
+            .collect();
+        let recomputed_commitment = full.then(|| {
+            let mut state = LtHash::default();
+            for check in checks {
+                state.combine(&check.recomputed);
+            }
+            hex::encode(state.digest())
+        });
+        Self {
+            full,
+            commitment: hex::encode(commitment),
+            recomputed_commitment,
+            buckets_checked: checks.len(),
+            entries_checked: checks.iter().map(|check| check.entries).sum(),
+            consistent: mismatched_buckets.is_empty(),
+            mismatched_buckets,
+        }
+    }
+}
+
+impl BlockchainDB {
+    /// Fast integrity check: recompute `buckets` randomly chosen buckets
+    /// (clamped to 1..=256) and compare them with the running commitment.
+    pub fn verify_utxo_sample(&self, buckets: usize) -> Result<UtxoIntegrityReport, StorageError> {
+        let count = buckets.clamp(1, COMMITMENT_BUCKETS);
+        let mut chosen: Vec<u8> = sample(&mut rand::thread_rng(), COMMITMENT_BUCKETS, count)
+            .into_iter()
+            .map(|bucket| bucket as u8)
+            .collect();
+        chosen.sort_unstable();
+
+        let checks = chosen
+            .into_iter()
+            .map(|bucket| self.check_utxo_bucket(bucket))
+            .collect::<Result<Vec<_>, _>>()?;
+        let report = UtxoIntegrityReport::new(self.utxo_commitment()?, &checks, false);
+        if !report.consistent {
+            tracing::error!(
+                target: "audit",
+                buckets = ?report.mismatched_buckets,
+                "UTXO set disagrees with its running commitment"
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::reorg::ReorgChangeSet;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    fn key(rng: &mut StdRng) -> Vec<u8> {
+        let mut key = vec![0u8; 36];
+        rng.fill(&mut key[..]);
+        key
+    }
+
+    #[test]
+    fn incremental_commitment_matches_recompute() {
+        let mut rng = StdRng::seed_from_u64(1774);
+        let mut accumulator = UtxoAccumulator::default();
+        let mut model: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+        let empty = accumulator.commitment();
+
+        for round in 0..2_000 {
+            let existing = model
+                .keys()
+                .nth(rng.gen_range(0..model.len().max(1)))
+                .cloned();
+            match (rng.gen_range(0..3), existing) {
+                (0, Some(key)) => {
+                    let value = model.remove(&key).unwrap();
+                    accumulator.update(&key, Some(&value), None);
+                }
+                (1, Some(key)) => {
+                    let value = vec![round as u8; rng.gen_range(1..40)];
+                    let old = model.insert(key.clone(), value.clone());
+                    accumulator.update(&key, old.as_deref(), Some(&value));
+                }
+                _ => {
+                    let key = key(&mut rng);
+                    let value = vec![round as u8; rng.gen_range(1..40)];
+                    model.insert(key.clone(), value.clone());
+                    accumulator.insert(&key, &value);
+                }
+            }
+        }
+
+        let recomputed = UtxoAccumulator::from_entries(&model);
+        assert_eq!(accumulator, recomputed);
+        assert_eq!(accumulator.commitment(), recomputed.commitment());
+
+        // Order of writes does not matter, content does
+        let reversed = UtxoAccumulator::from_entries(model.iter().rev());
+        assert_eq!(reversed.commitment(), accumulator.commitment());
+        let (first, _) = model.iter().next().unwrap();
+        let mut changed = model.clone();
+        changed.insert(first.clone(), b"other".to_vec());
+        assert_ne!(
+            UtxoAccumulator::from_entries(&changed).commitment(),
+            accumulator.commitment()
+        );
+
+        // Removing everything returns to the empty commitment
+        for (key, value) in &model {
+            accumulator.remove(key, value);
+        }
+        assert_eq!(accumulator.commitment(), empty);
+    }
+
+    #[test]
+    fn database_commitment_tracks_writes_and_catches_tampering() {
+        let dir = tempdir().unwrap();
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        let mut rng = StdRng::seed_from_u64(17740);
+        let mut hashes = Vec::new();
+        for n in 0..300u32 {
+            let mut hash = [0u8; 32];
+            rng.fill(&mut hash);
+            db.store_utxo(&hash, n % 3, &n.to_le_bytes()).unwrap();
+            hashes.push((hash, n % 3));
+        }
+        for (hash, index) in hashes.iter().step_by(4) {
+            db.remove_utxo(hash, *index).unwrap();
+        }
+        let mut changes = ReorgChangeSet::new();
+        let (hash, index) = hashes[1];
+        changes.del_utxo(crate::storage::database::create_utxo_key(&hash, index));
+        changes.put_utxo(vec![7u8; 36], b"staged".to_vec());
+        db.apply_reorg_atomically(&changes).unwrap();
+
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = db
+            .open_tree("utxos")
+            .unwrap()
+            .iter()
+            .map(|entry| entry.map(|(k, v)| (k.to_vec(), v.to_vec())).unwrap())
+            .collect();
+        let commitment = db.utxo_commitment().unwrap();
+        let recomputed = UtxoAccumulator::from_entries(entries.iter().map(|(k, v)| (k, v)));
+        assert_eq!(commitment, recomputed.commitment());
+        assert!(
+            db.verify_utxo_sample(COMMITMENT_BUCKETS)
+                .unwrap()
+                .consistent
+        );
+
+        // Persisted across a reopen
+        drop(db);
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        assert_eq!(db.utxo_commitment().unwrap(), commitment);
+
+        // A value changed behind the database's back
+        let (key, _) = &entries[10];
+        db.open_tree("utxos")
+            .unwrap()
+            .insert(key, b"flipped".to_vec())
+            .unwrap();
+        let bucket = UtxoAccumulator::bucket_of(key);
+        assert!(!db.check_utxo_bucket(bucket).unwrap().matches);
+        let report = db.verify_utxo_sample(COMMITMENT_BUCKETS).unwrap();
+        assert!(!report.consistent);
+        assert_eq!(report.mismatched_buckets, vec![bucket]);
+        assert_eq!(report.entries_checked, entries.len() as u64);
+
+        db.rebuild_utxo_stats().unwrap();
+        assert!(
+            db.verify_utxo_sample(COMMITMENT_BUCKETS)
+                .unwrap()
+                .consistent
+        );
+        db.clear_utxos().unwrap();
+        assert_eq!(
+            db.utxo_commitment().unwrap(),
+            UtxoAccumulator::default().commitment()
+        );
+    }
+}
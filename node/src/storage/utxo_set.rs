@@ -9,6 +9,7 @@ use memmap2::{MmapMut, MmapOptions};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
+use crate::storage::utxo_commitment::UtxoAccumulator;
 use crate::storage::StorageError;
 
 /// Represents an unspent transaction output
@@ -52,6 +53,18 @@ impl OutPoint {
     }
 }
 
+impl UnspentOutput {
+    /// Bytes committed to by the UTXO set commitment
+    fn commitment_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(17 + self.script_pubkey.len());
+        bytes.extend_from_slice(&self.value.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.push(self.is_coinbase as u8);
+        bytes.extend_from_slice(&self.script_pubkey);
+        bytes
+    }
+}
+
 /// UTXO commitment for verification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UtxoCommitment {
@@ -79,6 +92,8 @@ pub struct UtxoSet {
     size: usize,
     /// Latest commitment
     commitment: Option<UtxoCommitment>,
+    /// Incremental commitment, updated on every add and remove
+    accumulator: UtxoAccumulator,
     /// Whether the UTXO set has been modified since last save
     dirty: bool,
 }
@@ -130,6 +145,7 @@ impl UtxoSet {
             mmap: Some(mmap),
             size: 0,
             commitment: None,
+            accumulator: UtxoAccumulator::default(),
             dirty: false,
         };
 
@@ -209,6 +225,8 @@ impl UtxoSet {
             let (outpoint, output): (OutPoint, UnspentOutput) = bincode::deserialize(entry_data)?;
 
             // Add to in-memory map
+            self.accumulator
+                .insert(&outpoint.as_key(), &output.commitment_bytes());
             self.utxos.insert(outpoint, output);
 
             offset = entry_end;
@@ -358,13 +376,19 @@ impl UtxoSet {
 
     /// Add a new UTXO
     pub fn add(&mut self, outpoint: OutPoint, output: UnspentOutput) {
-        self.utxos.insert(outpoint, output);
+        let key = outpoint.as_key();
+        self.accumulator.insert(&key, &output.commitment_bytes());
+        if let Some(old) = self.utxos.insert(outpoint, output) {
+            self.accumulator.remove(&key, &old.commitment_bytes());
+        }
         self.dirty = true;
     }
 
     /// Remove a UTXO (spend it)
     pub fn remove(&mut self, outpoint: &OutPoint) -> Option<UnspentOutput> {
         if let Some((_, output)) = self.utxos.remove(outpoint) {
+            self.accumulator
+                .remove(&outpoint.as_key(), &output.commitment_bytes());
             self.dirty = true;
             Some(output)
         } else {
@@ -388,7 +412,7 @@ impl UtxoSet {
         }
 
         // Add new outputs
-        let txid = tx.hash();
+        let txid = tx.txid();
         for (vout, output) in tx.outputs().iter().enumerate() {
             let outpoint = OutPoint::new(txid, vout as u32);
             let unspent_output = UnspentOutput {
@@ -405,6 +429,12 @@ impl UtxoSet {
         Ok(())
     }
 
+    /// Incremental commitment to the current set. Unlike
+    /// [`Self::create_commitment`] this reads no entries.
+    pub fn utxo_commitment(&self) -> [u8; 32] {
+        self.accumulator.commitment()
+    }
+
     /// Create a UTXO commitment for verification
     pub fn create_commitment(&mut self, height: u64) -> Result<UtxoCommitment, StorageError> {
         let utxo_count = self.utxos.len();
@@ -543,6 +573,7 @@ impl UtxoSet {
     /// Clear the UTXO set (for reinitialization)
     pub fn clear(&mut self) {
         self.utxos.clear();
+        self.accumulator = UtxoAccumulator::default();
         self.dirty = true;
     }
 
@@ -615,7 +646,7 @@ mod tests {
 
         // Create and add a transaction
         let tx = create_test_transaction(1000);
-        let tx_hash = tx.hash();
+        let tx_hash = tx.txid();
 
         // Process the transaction (should add its outputs)
         utxo_set.process_transaction(&tx, 1, true).unwrap();
@@ -667,6 +698,37 @@ mod tests {
         assert!(!utxo_set.verify_commitment(&commitment).unwrap());
     }
 
+    #[test]
+    fn test_incremental_commitment_matches_recompute() {
+        let temp_dir = tempdir().unwrap();
+        let mut utxo_set = UtxoSet::new(temp_dir.path().join("utxo.db")).unwrap();
+        let empty = utxo_set.utxo_commitment();
+
+        for i in 0..20 {
+            let tx = create_test_transaction(1000 * (i + 1));
+            utxo_set.process_transaction(&tx, i, false).unwrap();
+        }
+        // Spend some, overwrite one in place
+        let entries = utxo_set.get_all();
+        for (outpoint, _) in entries.iter().step_by(3) {
+            utxo_set.remove(outpoint);
+        }
+        let (outpoint, mut output) = entries[1].clone();
+        output.value += 1;
+        utxo_set.add(outpoint, output);
+
+        let fresh_dir = tempdir().unwrap();
+        let mut fresh = UtxoSet::new(fresh_dir.path().join("utxo.db")).unwrap();
+        for (outpoint, output) in utxo_set.get_all() {
+            fresh.add(outpoint, output);
+        }
+        assert_eq!(utxo_set.utxo_commitment(), fresh.utxo_commitment());
+        assert_ne!(utxo_set.utxo_commitment(), empty);
+
+        utxo_set.clear();
+        assert_eq!(utxo_set.utxo_commitment(), empty);
+    }
+
     #[test]
     fn test_utxo_statistics() {
         let temp_dir = tempdir().unwrap();
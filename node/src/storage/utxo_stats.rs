@@ -0,0 +1,286 @@
+//! UTXO set statistics and supply audit
+//!
+//! `BlockchainDB` keeps running totals for the UTXO set (entries, amount and a
+//! per-script-class breakdown), adjusted on every UTXO write and mirrored to
+//! the metadata tree, so reading them never scans the set. A full scan
+//! (`BlockchainDB::scan_utxo_stats`, the `gettxoutsetinfo` equivalent)
+//! recomputes them to cross-check the running totals.
+//!
+//! The supply audit compares the UTXO total with what the subsidy schedule
+//! allows to exist at the current height. Coins can be destroyed (fees left
+//! unclaimed, unspendable outputs) but never created, so any excess means an
+//! inflation bug or a corrupt UTXO set.
+
+use super::database::{BlockchainDB, StorageError};
+use metrics::gauge;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use supernova_core::script::classify::classify;
+use supernova_core::types::transaction::TransactionOutput;
+use supernova_core::types::units::total_block_subsidy;
+use utoipa::ToSchema;
+
+/// Metadata key of the persisted [`UtxoStats`]
+pub(crate) const UTXO_STATS_KEY: &[u8] = b"utxo_stats";
+
+/// Blocks before a coinbase output can be spent
+pub const COINBASE_MATURITY: u64 = 100;
+
+/// Attempts at reading the stats and height without a block landing between
+const AUDIT_READ_ATTEMPTS: usize = 3;
+
+/// Count and amount of a group of UTXOs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ClassTotals {
+    pub count: u64,
+    pub amount: u64,
+}
+
+/// Aggregate statistics for the UTXO set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UtxoStats {
+    /// Number of entries
+    pub count: u64,
+    /// Sum of all decodable outputs, in novas
+    pub total_amount: u64,
+    /// Totals per script class (`p2pkh`, `quantum_pkh`, `null_data`, ...)
+    pub by_class: BTreeMap<String, ClassTotals>,
+    /// Entries whose value is not a serialized output; counted without an amount
+    pub undecodable: u64,
+}
+
+impl UtxoStats {
+    /// Account for a UTXO entry being replaced (`old`) and/or written (`new`)
+    pub(crate) fn update(&mut self, old: Option<&[u8]>, new: Option<&[u8]>) {
+        if let Some(old) = old {
+            self.remove(old);
+        }
+        if let Some(new) = new {
+            self.add(new);
+        }
+    }
+
+    pub(crate) fn add(&mut self, value: &[u8]) {
+        self.count += 1;
+        match decode(value) {
+            Some((class, amount)) => {
+                self.total_amount = self.total_amount.saturating_add(amount);
+                let totals = self.by_class.entry(class.to_string()).or_default();
+                totals.count += 1;
+                totals.amount = totals.amount.saturating_add(amount);
+            }
+            None => self.undecodable += 1,
+        }
+    }
+
+    fn remove(&mut self, value: &[u8]) {
+        self.count = self.count.saturating_sub(1);
+        match decode(value) {
+            Some((class, amount)) => {
+                self.total_amount = self.total_amount.saturating_sub(amount);
+                if let Some(totals) = self.by_class.get_mut(class) {
+                    totals.count = totals.count.saturating_sub(1);
+                    totals.amount = totals.amount.saturating_sub(amount);
+                    if totals.count == 0 {
+                        self.by_class.remove(class);
+                    }
+                }
+            }
+            None => self.undecodable = self.undecodable.saturating_sub(1),
+        }
+    }
+}
+
+fn decode(value: &[u8]) -> Option<(&'static str, u64)> {
+    let output: TransactionOutput = bincode::deserialize(value).ok()?;
+    Some((
+        classify(output.script_pubkey()).kind().as_str(),
+        output.amount(),
+    ))
+}
+
+/// Expected versus actual coin supply at a height
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SupplyAudit {
+    pub height: u64,
+    /// Genesis outputs plus every block subsidy up to `height`, in novas
+    pub expected_supply: u64,
+    /// Sum of the UTXO set, in novas
+    pub utxo_total: u64,
+    /// Part of `utxo_total` in coinbases younger than `COINBASE_MATURITY`
+    pub immature_coinbase: u64,
+    /// Coins in the UTXO set beyond `expected_supply`; must be zero
+    pub excess: u64,
+    /// Coins the schedule allowed that are not in the UTXO set (unclaimed
+    /// subsidy and fees, unspendable outputs)
+    pub unaccounted: u64,
+    /// Whether the UTXO set holds more than the schedule allows
+    pub discrepancy: bool,
+    pub stats: UtxoStats,
+}
+
+impl SupplyAudit {
+    /// Export the audit as metrics and log a discrepancy. Alerting rules fire
+    /// on `supernova_supply_discrepancy`.
+    pub fn publish(&self) {
+        gauge!("supernova_utxo_set_size", self.stats.count as f64);
+        gauge!("supernova_utxo_total_amount", self.utxo_total as f64);
+        gauge!("supernova_supply_expected", self.expected_supply as f64);
+        gauge!("supernova_supply_excess", self.excess as f64);
+        gauge!(
+            "supernova_supply_discrepancy",
+            if self.discrepancy { 1.0 } else { 0.0 }
+        );
+        if self.discrepancy {
+            tracing::error!(
+                target: "audit",
+                height = self.height,
+                expected = self.expected_supply,
+                actual = self.utxo_total,
+                "Supply discrepancy: the UTXO set holds {} novas more than the subsidy schedule allows",
+                self.excess
+            );
+        }
+    }
+}
+
+impl BlockchainDB {
+    /// Audit the running UTXO statistics against the subsidy schedule
+    pub fn supply_audit(&self) -> Result<SupplyAudit, StorageError> {
+        // A block connected between the two reads would skew the comparison
+        let mut attempt = 0;
+        loop {
+            let height = self.get_height()?;
+            let stats = self.utxo_stats()?;
+            attempt += 1;
+            if self.get_height()? == height || attempt == AUDIT_READ_ATTEMPTS {
+                return self.supply_audit_at(height, stats);
+            }
+        }
+    }
+
+    /// Audit `stats`, e.g. from a full scan, against the schedule at `height`
+    pub fn supply_audit_at(
+        &self,
+        height: u64,
+        stats: UtxoStats,
+    ) -> Result<SupplyAudit, StorageError> {
+        let genesis_outputs = match self.get_block_by_height(0)? {
+            Some(genesis) => genesis
+                .transactions()
+                .iter()
+                .flat_map(|tx| tx.outputs())
+                .fold(0u64, |acc, output| acc.saturating_add(output.amount())),
+            None => 0,
+        };
+        let expected_supply = genesis_outputs.saturating_add(total_block_subsidy(1, height));
+        let utxo_total = stats.total_amount;
+        let excess = utxo_total.saturating_sub(expected_supply);
+        Ok(SupplyAudit {
+            height,
+            expected_supply,
+            utxo_total,
+            immature_coinbase: self.immature_coinbase_amount(height)?,
+            excess,
+            unaccounted: expected_supply.saturating_sub(utxo_total),
+            discrepancy: excess > 0,
+            stats,
+        })
+    }
+
+    /// Unspent coinbase outputs of the blocks still maturing at `height`
+    pub fn immature_coinbase_amount(&self, height: u64) -> Result<u64, StorageError> {
+        let mut total = 0u64;
+        for h in height.saturating_sub(COINBASE_MATURITY - 1)..=height {
+            let Some(block) = self.get_block_by_height(h)? else {
+                continue;
+            };
+            let Some(coinbase) = block.transactions().iter().find(|tx| tx.is_coinbase()) else {
+                continue;
+            };
+            let hash = coinbase.hash();
+            for index in 0..coinbase.outputs().len() as u32 {
+                // Undecodable entries are already reported in the stats
+                if let Ok(Some(output)) = self.get_utxo(&hash, index) {
+                    total = total.saturating_add(output.amount());
+                }
+            }
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn output(amount: u64, script: Vec<u8>) -> Vec<u8> {
+        bincode::serialize(&TransactionOutput::new(amount, script)).unwrap()
+    }
+
+    #[test]
+    fn test_running_stats_match_scan() {
+        let dir = tempdir().unwrap();
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        let p2pkh = {
+            let mut script = vec![0x76, 0xa9, 0x14];
+            script.extend_from_slice(&[7u8; 20]);
+            script.extend_from_slice(&[0x88, 0xac]);
+            script
+        };
+
+        db.store_utxo(&[1; 32], 0, &output(1_000, p2pkh.clone()))
+            .unwrap();
+        db.store_utxo(&[1; 32], 1, &output(2_000, vec![0x6a]))
+            .unwrap();
+        db.store_utxo(&[2; 32], 0, &output(500, p2pkh)).unwrap();
+        db.store_utxo(&[3; 32], 0, &[0xAB, 0xCD]).unwrap();
+        // Overwriting replaces the old amount rather than adding to it
+        db.store_utxo(&[1; 32], 1, &output(3_000, vec![0x6a]))
+            .unwrap();
+        db.remove_utxo(&[2; 32], 0).unwrap();
+        db.remove_utxo(&[9; 32], 0).unwrap();
+
+        let stats = db.utxo_stats().unwrap();
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.total_amount, 4_000);
+        assert_eq!(stats.undecodable, 1);
+        assert_eq!(stats.by_class.values().map(|c| c.count).sum::<u64>(), 2);
+        assert_eq!(db.scan_utxo_stats().unwrap().1, stats);
+
+        // Persisted across a reopen
+        drop(db);
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        assert_eq!(db.utxo_stats().unwrap(), stats);
+
+        db.clear_utxos().unwrap();
+        assert_eq!(db.utxo_stats().unwrap(), UtxoStats::default());
+    }
+
+    #[test]
+    fn test_injected_utxo_is_a_discrepancy() {
+        let dir = tempdir().unwrap();
+        let db = BlockchainDB::new(dir.path()).unwrap();
+        db.set_metadata(b"height", &10u64.to_be_bytes()).unwrap();
+        db.store_utxo(&[1; 32], 0, &output(total_block_subsidy(1, 10), vec![]))
+            .unwrap();
+
+        let audit = db.supply_audit().unwrap();
+        assert!(!audit.discrepancy);
+        assert_eq!(audit.unaccounted, 0);
+
+        // Written behind the database's back, so only a scan sees it
+        db.open_tree("utxos")
+            .unwrap()
+            .insert([9u8; 36], output(1, vec![]))
+            .unwrap();
+        assert!(!db.supply_audit().unwrap().discrepancy);
+
+        let (_, scanned) = db.scan_utxo_stats().unwrap();
+        assert_ne!(scanned, db.utxo_stats().unwrap());
+        let audit = db.supply_audit_at(10, scanned).unwrap();
+        assert!(audit.discrepancy);
+        assert_eq!(audit.excess, 1);
+    }
+}
@@ -1,19 +1,25 @@
 // Wallet Manager for Node
 // Integrates quantum wallet with blockchain state
 
+use chrono::Utc;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use thiserror::Error;
 use wallet::quantum_wallet::{
     Keystore, UtxoIndex, WalletStorage,
-    Utxo,
+    Utxo, BuilderConfig, BumpCandidate, TransactionBuilder,
 };
+use wallet::{TransactionDirection, TransactionHistory, TransactionRecord, TransactionStatus};
 
 use crate::config::{NetworkEnvironment, NodeConfig};
 use crate::storage::BlockchainDB;
 use crate::storage::ChainState;
-use crate::mempool::TransactionPool;
+use crate::mempool::{MempoolError, TransactionPool};
 use crate::network::NetworkProxy;
+use crate::pending_final::{ParkedTransaction, PendingFinal, Submission};
+use supernova_core::script::classify;
+use supernova_core::types::block::Block;
 use supernova_core::types::transaction::Transaction;
 
 /// Hardcoded fallback passphrase used when the operator hasn't supplied
@@ -24,6 +30,10 @@ use supernova_core::types::transaction::Transaction;
 /// to use it on Production environments.
 const TESTNET_DEFAULT_PASSPHRASE: &str = "testnet_default_passphrase";
 
+/// Fee rate for wallet submissions, in attonovas per byte (matches the
+/// builder config)
+const WALLET_FEE_RATE: u64 = 1000;
+
 /// Environment variable used to supply the wallet keystore passphrase.
 /// Required when `[node].environment = "Production"`; optional (and
 /// recommended) on testnet / development. Set this in the node operator's
@@ -101,6 +111,9 @@ pub enum WalletManagerError {
     
     #[error("Blockchain error: {0}")]
     BlockchainError(String),
+    
+    #[error("Transaction is not final until height {min_height} and median time {min_time}; park it to broadcast automatically")]
+    NonFinal { min_height: u64, min_time: u64 },
 }
 
 /// Wallet manager integrating quantum wallet with blockchain
@@ -125,6 +138,17 @@ pub struct WalletManager {
     
     /// Network proxy for broadcasting
     network: Arc<NetworkProxy>,
+    
+    /// Status history of wallet-originated transactions
+    history: Arc<RwLock<TransactionHistory>>,
+    
+    /// Wallet UTXOs spent by our own unconfirmed transactions, kept so a
+    /// transaction can be rebuilt for a fee bump after its inputs have left
+    /// the UTXO index
+    unconfirmed_spends: Arc<RwLock<HashMap<[u8; 32], Vec<Utxo>>>>,
+    
+    /// Our timelocked transactions waiting to become final
+    pending_final: Arc<PendingFinal>,
 }
 
 impl WalletManager {
@@ -143,6 +167,11 @@ impl WalletManager {
         mempool: Arc<TransactionPool>,
         network: Arc<NetworkProxy>,
     ) -> Result<Self, WalletManagerError> {
+        let history = TransactionHistory::new(wallet_path.with_extension("history.json"))
+            .map_err(|e| WalletManagerError::StorageError(e.to_string()))?;
+        let pending_final = PendingFinal::open(wallet_path.with_extension("pending-final.json"))
+            .map_err(|e| WalletManagerError::StorageError(e.to_string()))?;
+        
         // Open wallet storage
         let mut storage = WalletStorage::open(wallet_path)
             .map_err(|e| WalletManagerError::StorageError(e.to_string()))?;
@@ -195,6 +224,9 @@ impl WalletManager {
             chain_state,
             mempool,
             network,
+            history: Arc::new(RwLock::new(history)),
+            unconfirmed_spends: Arc::new(RwLock::new(HashMap::new())),
+            pending_final: Arc::new(pending_final),
         })
     }
     
@@ -299,50 +331,60 @@ impl WalletManager {
     ) -> Result<(), WalletManagerError> {
         let tx_hash = tx.hash();
         
+        // One of ours was mined: its inputs are final and it can no longer be bumped
+        let was_ours = self.unconfirmed_spends.write()
+            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+            .remove(&tx_hash)
+            .is_some();
+        if was_ours {
+            self.history.write()
+                .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+                .update_transaction_status(&hex::encode(tx_hash), TransactionStatus::Confirmed(1))
+                .ok();
+        }
+        
         // Check outputs for any to our addresses
         for (vout, output) in tx.outputs().iter().enumerate() {
-            // Try to match output script to wallet addresses
-            // For now, simplified matching by script_pubkey
             let script_pubkey = output.script_pubkey();
+
+            // Use the shared classifier so the wallet agrees with the address
+            // index and the API on which address an output pays
+            let Some(address) = classify(script_pubkey).address() else {
+                continue;
+            };
+            let Some(wallet_addr) = wallet_addresses.iter().find(|a| **a == address) else {
+                continue;
+            };
+
+            // This output is ours!
+            let utxo = Utxo {
+                txid: tx_hash,
+                vout: vout as u32,
+                address: wallet_addr.clone(),
+                value: output.value(),
+                script_pubkey: script_pubkey.to_vec(),
+                block_height,
+                confirmations: 1, // Will be updated
+                spendable: true,
+                solvable: true,
+                label: None,
+            };
             
-            // Check if this output belongs to any of our addresses
-            for wallet_addr in wallet_addresses {
-                // Parse wallet address to get pubkey hash
-                if let Ok(addr) = wallet::quantum_wallet::Address::from_str(wallet_addr) {
-                    // Check if output script matches address
-                    if script_pubkey == addr.pubkey_hash() {
-                        // This output is ours!
-                        let utxo = Utxo {
-                            txid: tx_hash,
-                            vout: vout as u32,
-                            address: wallet_addr.clone(),
-                            value: output.value(),
-                            script_pubkey: script_pubkey.to_vec(),
-                            block_height,
-                            confirmations: 1, // Will be updated
-                            spendable: true,
-                            solvable: true,
-                            label: None,
-                        };
-                        
-                        // Add UTXO to index
-                        self.utxo_index.add_utxo(utxo.clone())
-                            .map_err(|e| WalletManagerError::UtxoError(e.to_string()))?;
-                        
-                        // Save UTXO to storage
-                        self.storage.read()
-                            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
-                            .store_utxo(&utxo)
-                            .map_err(|e| WalletManagerError::StorageError(e.to_string()))?;
-                        
-                        tracing::info!("Found wallet UTXO: {} NOVA at {}:{}",
-                            utxo.value as f64 / 100_000_000.0,
-                            hex::encode(&tx_hash[..8]),
-                            vout
-                        );
-                    }
-                }
-            }
+            // Add UTXO to index
+            self.utxo_index.add_utxo(utxo.clone())
+                .map_err(|e| WalletManagerError::UtxoError(e.to_string()))?;
+            
+            // Save UTXO to storage
+            self.storage.read()
+                .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+                .store_utxo(&utxo)
+                .map_err(|e| WalletManagerError::StorageError(e.to_string()))?;
+            
+            tracing::info!("Found wallet UTXO: {} NOVA at {}:{}",
+                utxo.value as f64 / 100_000_000.0,
+                hex::encode(&tx_hash[..8]),
+                vout
+            );
         }
         
         // Check inputs to mark spent UTXOs
@@ -388,6 +430,14 @@ impl WalletManager {
         Ok(())
     }
     
+    /// Flush pending wallet storage writes to disk
+    pub fn flush(&self) -> Result<(), WalletManagerError> {
+        let storage = self.storage.read()
+            .map_err(|_| WalletManagerError::StorageError("Storage lock poisoned".to_string()))?;
+        storage.flush()
+            .map_err(|e| WalletManagerError::StorageError(e.to_string()))
+    }
+
     /// Get keystore reference
     pub fn keystore(&self) -> Arc<Keystore> {
         Arc::clone(&self.keystore)
@@ -399,6 +449,10 @@ impl WalletManager {
     }
     
     /// Submit transaction to mempool
+    ///
+    /// A transaction whose lock time or relative locks aren't met yet fails
+    /// with `NonFinal`, carrying the earliest height and median time at which
+    /// it can be mined; [`Self::park_transaction`] broadcasts it then instead.
     pub fn submit_transaction_to_mempool(
         &self,
         transaction: Transaction,
@@ -410,42 +464,317 @@ impl WalletManager {
             .map_err(|e| WalletManagerError::TransactionError(format!("Serialization error: {}", e)))?
             .len();
         
-        // Estimate fee rate (simplified - in production would be more sophisticated)
-        let fee_rate = 1000; // 1000 attonovas per byte (matches builder config)
-        
         tracing::debug!("Submitting transaction {} ({} bytes) to mempool", 
             hex::encode(&txid[..8]), tx_size);
         
         // Submit to mempool
-        self.mempool.add_transaction(transaction.clone(), fee_rate)
-            .map_err(|e| WalletManagerError::TransactionError(format!("Mempool rejected: {}", e)))?;
+        self.mempool.add_transaction(transaction.clone(), WALLET_FEE_RATE)
+            .map_err(|e| match e {
+                MempoolError::NonFinal { min_height, min_time } => {
+                    WalletManagerError::NonFinal { min_height, min_time }
+                }
+                e => WalletManagerError::TransactionError(format!("Mempool rejected: {}", e)),
+            })?;
         
         tracing::info!("Transaction {} accepted to mempool", hex::encode(&txid[..8]));
         
-        // Broadcast transaction to P2P network
+        self.broadcast(&transaction);
+        self.record_send(&transaction)?;
+        Ok(txid)
+    }
+    
+    /// Submit a transaction, or hold it in the pending-final area if its
+    /// locks aren't met yet. Parked transactions are broadcast automatically
+    /// once the chain reaches them (see [`Self::release_pending_final`]);
+    /// either way the transaction is recorded in history and its inputs are
+    /// reserved.
+    pub fn park_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<Submission, WalletManagerError> {
+        let submission = self.pending_final
+            .submit(transaction.clone(), WALLET_FEE_RATE, &self.mempool)
+            .map_err(|e| WalletManagerError::TransactionError(format!("Mempool rejected: {}", e)))?;
+        if submission == Submission::Accepted {
+            self.broadcast(&transaction);
+        }
+        self.record_send(&transaction)?;
+        Ok(submission)
+    }
+    
+    /// Transactions waiting in the pending-final area
+    pub fn pending_final(&self) -> Vec<ParkedTransaction> {
+        self.pending_final.list()
+    }
+    
+    /// Submit and broadcast parked transactions that became final. Run on
+    /// every connected block.
+    pub fn release_pending_final(&self) {
+        for transaction in self.pending_final.release(&self.mempool) {
+            self.broadcast(&transaction);
+        }
+    }
+    
+    /// After `block` left the active chain: resubmit our sends it contained
+    /// and our sends the mempool evicted as non-final (`evicted`), parking
+    /// the ones that are no longer final
+    pub fn block_disconnected(&self, block: &Block, evicted: &[Transaction]) {
+        let candidates = block.transactions().iter()
+            .filter(|tx| !tx.is_coinbase())
+            .chain(evicted.iter())
+            .filter(|tx| self.is_own_send(&tx.hash()));
+        for transaction in candidates {
+            let txid = transaction.hash();
+            match self.pending_final.submit(transaction.clone(), WALLET_FEE_RATE, &self.mempool) {
+                Ok(Submission::Accepted) => self.broadcast(transaction),
+                Ok(Submission::Parked(lock)) => tracing::info!(
+                    "Reorg made transaction {} non-final; parked until height {}",
+                    hex::encode(&txid[..8]),
+                    lock.min_height
+                ),
+                Err(MempoolError::TransactionExists(_)) => {}
+                Err(e) => tracing::warn!(
+                    "Could not resubmit transaction {} after reorg: {}",
+                    hex::encode(&txid[..8]),
+                    e
+                ),
+            }
+        }
+    }
+    
+    fn is_own_send(&self, txid: &[u8; 32]) -> bool {
+        self.history.read().ok()
+            .and_then(|history| history.get_transaction(&hex::encode(txid))
+                .map(|record| matches!(record.direction, TransactionDirection::Sent)))
+            .unwrap_or(false)
+    }
+    
+    fn broadcast(&self, transaction: &Transaction) {
+        let txid = transaction.hash();
         tracing::debug!("Broadcasting transaction {} to network", hex::encode(&txid[..8]));
-        self.network.broadcast_transaction(&transaction);
+        self.network.broadcast_transaction(transaction);
         tracing::info!("Transaction {} broadcast to network", hex::encode(&txid[..8]));
+    }
+    
+    /// Store one of our sends in history and reserve its inputs
+    fn record_send(&self, transaction: &Transaction) -> Result<(), WalletManagerError> {
+        let txid = transaction.hash();
         
         // Store transaction in wallet history
         self.storage.read()
             .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
-            .store_transaction(&txid, &transaction)
+            .store_transaction(&txid, transaction)
+            .ok(); // Don't fail if history storage fails
+        
+        let spent = self.mark_inputs_spent(transaction);
+        let record = self.history_record(transaction, &spent);
+        self.history.write()
+            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+            .add_transaction(record)
+            .ok(); // Don't fail if history storage fails
+        self.unconfirmed_spends.write()
+            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+            .insert(txid, spent);
+        
+        Ok(())
+    }
+    
+    /// Replace an unconfirmed wallet transaction with one paying
+    /// `new_fee_rate` attonovas per byte (replace-by-fee).
+    ///
+    /// The replacement keeps the original recipients byte-for-byte and pays
+    /// the extra fee from the change output, adding wallet inputs when the
+    /// change is too small. Refused when the original didn't signal
+    /// replaceability, is already confirmed, or has been spent from. Once the
+    /// mempool accepts the replacement the original is marked `Replaced` in
+    /// history. Returns the txid of the replacement.
+    pub fn bump_fee(
+        &self,
+        txid: &[u8; 32],
+        new_fee_rate: u64,
+    ) -> Result<[u8; 32], WalletManagerError> {
+        if self.keystore.is_locked() {
+            return Err(WalletManagerError::WalletLocked);
+        }
+        
+        let original = self.get_transaction(txid)?
+            .ok_or_else(|| WalletManagerError::TransactionError(
+                format!("Unknown transaction {}", hex::encode(txid))
+            ))?;
+        
+        let confirmed = matches!(self.db.get_transaction(txid), Ok(Some(_)));
+        let inputs = self.unconfirmed_spends.read()
+            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+            .get(txid)
+            .cloned();
+        let inputs = match inputs {
+            Some(inputs) => inputs,
+            None if confirmed => Vec::new(),
+            None => return Err(WalletManagerError::TransactionError(
+                format!("Transaction {} was not sent by this wallet", hex::encode(txid))
+            )),
+        };
+        
+        let candidate = BumpCandidate {
+            change_index: self.change_index(&original),
+            child: self.find_child(txid, &original),
+            transaction: original,
+            inputs,
+            confirmed,
+        };
+        
+        let config = BuilderConfig {
+            fee_rate: new_fee_rate,
+            ..Default::default()
+        };
+        let mut builder = TransactionBuilder::new(Arc::clone(&self.keystore), config);
+        if candidate.change_index.is_none() {
+            let change = self.generate_new_address(Some("change".to_string()))?;
+            let change = wallet::quantum_wallet::Address::from_str(&change)
+                .map_err(|e| WalletManagerError::KeystoreError(e.to_string()))?;
+            builder.set_change_address(change);
+        }
+        
+        let available = self.list_unspent(1, u64::MAX, None)?;
+        let bump = builder.bump_fee(&candidate, &available)
+            .map_err(|e| WalletManagerError::TransactionError(e.to_string()))?;
+        let replacement = bump.transaction;
+        let replacement_txid = replacement.hash();
+        
+        self.mempool.replace_transaction(replacement.clone(), new_fee_rate)
+            .map_err(|e| WalletManagerError::TransactionError(format!("Mempool rejected: {}", e)))?;
+        
+        tracing::info!(
+            "Transaction {} replaced by {} (fee {} -> {})",
+            hex::encode(&txid[..8]),
+            hex::encode(&replacement_txid[..8]),
+            bump.original_fee,
+            bump.fee
+        );
+        
+        self.network.broadcast_transaction(&replacement);
+        
+        self.storage.read()
+            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?
+            .store_transaction(&replacement_txid, &replacement)
             .ok(); // Don't fail if history storage fails
         
-        // Mark input UTXOs as pending spent
+        // The original inputs are already marked spent; only the added ones are new
+        let mut spent = candidate.inputs;
+        for utxo in &bump.added_inputs {
+            if let Err(e) = self.utxo_index.mark_spent(&utxo.txid, utxo.vout) {
+                tracing::warn!("Failed to mark UTXO as spent: {}", e);
+            }
+        }
+        spent.extend(bump.added_inputs);
+        
+        let original_record = self.history_record(&candidate.transaction, &spent);
+        let replacement_record = self.history_record(&replacement, &spent);
+        {
+            let mut history = self.history.write()
+                .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?;
+            if history.get_transaction(&original_record.hash).is_none() {
+                history.add_transaction(original_record).ok();
+            }
+            history.record_replacement(&hex::encode(txid), replacement_record)
+                .map_err(|e| WalletManagerError::StorageError(e.to_string()))?;
+        }
+        
+        let mut unconfirmed = self.unconfirmed_spends.write()
+            .map_err(|_| WalletManagerError::StorageError("Lock poisoned".to_string()))?;
+        unconfirmed.remove(txid);
+        unconfirmed.insert(replacement_txid, spent);
+        
+        Ok(replacement_txid)
+    }
+    
+    /// History status of a wallet-originated transaction
+    pub fn transaction_status(&self, txid: &[u8; 32]) -> Option<TransactionStatus> {
+        self.history.read().ok()?
+            .get_transaction(&hex::encode(txid))
+            .map(|record| record.status.clone())
+    }
+    
+    /// Mark the wallet UTXOs spent by `transaction` and return them
+    fn mark_inputs_spent(&self, transaction: &Transaction) -> Vec<Utxo> {
+        let mut spent = Vec::new();
         for input in transaction.inputs() {
             let prev_txid = input.prev_tx_hash();
             let prev_vout = input.prev_output_index();
             
+            if let Ok(utxo) = self.utxo_index.get_utxo(&prev_txid, prev_vout) {
+                spent.push(utxo);
+            }
+            
             // Mark as spent in UTXO index (will be removed when block confirms)
             if let Err(e) = self.utxo_index.mark_spent(&prev_txid, prev_vout) {
                 tracing::warn!("Failed to mark UTXO as spent: {}", e);
                 // Don't fail the whole transaction for this
             }
         }
+        spent
+    }
+    
+    /// The builder appends change last, so a trailing output paying one of
+    /// our own addresses is treated as change
+    fn change_index(&self, transaction: &Transaction) -> Option<usize> {
+        let outputs = transaction.outputs();
+        if outputs.len() < 2 {
+            return None;
+        }
+        let last = outputs.len() - 1;
+        let address = classify(outputs[last].script_pubkey()).address()?;
+        self.keystore.has_address(&address).then_some(last)
+    }
+    
+    /// A transaction spending one of `transaction`'s outputs, in the mempool
+    /// or already recorded by the wallet
+    fn find_child(&self, txid: &[u8; 32], transaction: &Transaction) -> Option<[u8; 32]> {
+        if let Some(child) = self.mempool.get_all_transactions().into_iter()
+            .find(|tx| tx.inputs().iter().any(|input| input.prev_tx_hash() == *txid))
+        {
+            return Some(child.hash());
+        }
         
-        Ok(txid)
+        let spent_vout = (0..transaction.outputs().len() as u32)
+            .any(|vout| self.utxo_index.is_spent(txid, vout));
+        if !spent_vout {
+            return None;
+        }
+        self.unconfirmed_spends.read().ok()?
+            .iter()
+            .find(|(_, inputs)| inputs.iter().any(|utxo| utxo.txid == *txid))
+            .map(|(child, _)| *child)
+    }
+    
+    /// History record for one of our own sends
+    fn history_record(&self, transaction: &Transaction, inputs: &[Utxo]) -> TransactionRecord {
+        let change = self.change_index(transaction);
+        let output_total: u64 = transaction.outputs().iter().map(|o| o.value()).sum();
+        let amount = transaction.outputs().iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != change)
+            .map(|(_, o)| o.value())
+            .sum();
+        let input_total: u64 = inputs.iter()
+            .filter(|utxo| transaction.inputs().iter()
+                .any(|i| i.prev_tx_hash() == utxo.txid && i.prev_output_index() == utxo.vout))
+            .map(|utxo| utxo.value)
+            .sum();
+        
+        TransactionRecord {
+            hash: hex::encode(transaction.hash()),
+            timestamp: Utc::now(),
+            direction: TransactionDirection::Sent,
+            amount,
+            fee: input_total.saturating_sub(output_total),
+            status: TransactionStatus::Pending,
+            label: None,
+            category: None,
+            tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
+        }
     }
     
     /// Get transaction by txid (from wallet history or blockchain)
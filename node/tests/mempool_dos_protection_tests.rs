@@ -278,6 +278,7 @@ fn test_eviction_policy() {
         max_fee_rate: 10000, // High enough for test fee rates
         enable_rbf: true,
         min_rbf_fee_increase: 10.0,
+        max_data_carrier_bytes: 80,
     };
     
     let pool = TransactionPool::new(config);
@@ -0,0 +1,92 @@
+//! Batch ML-DSA signature verification.
+//!
+//! `QuantumBatchVerifier` parses each distinct public key once and verifies
+//! the batch across the rayon pool. This harness checks 1,000 Dilithium
+//! signatures from 50 signers, as a block's inputs would carry them, once
+//! through the batch verifier and once one `verify_quantum_signature` call
+//! at a time.
+//!
+//! Run with:
+//!
+//! ```
+//! cargo bench -p supernova-core --bench batch_verification
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use supernova_core::crypto::quantum::{
+    verify_quantum_signature, QuantumBatchVerifier, QuantumKeyPair, QuantumParameters,
+    QuantumScheme,
+};
+
+const SIGNATURES: usize = 1_000;
+const SIGNERS: usize = 50;
+
+struct Signed {
+    message: [u8; 32],
+    signature: Vec<u8>,
+    public_key: Vec<u8>,
+    parameters: QuantumParameters,
+}
+
+fn signatures() -> Vec<Signed> {
+    let keypairs: Vec<QuantumKeyPair> = (0..SIGNERS)
+        .map(|_| {
+            QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium))
+                .expect("keypair")
+        })
+        .collect();
+    (0..SIGNATURES)
+        .map(|i| {
+            let keypair = &keypairs[i % SIGNERS];
+            let mut message = [0u8; 32];
+            message[..8].copy_from_slice(&(i as u64).to_le_bytes());
+            Signed {
+                message,
+                signature: keypair.sign(&message).expect("sign"),
+                public_key: keypair.public_key.clone(),
+                parameters: keypair.parameters,
+            }
+        })
+        .collect()
+}
+
+fn bench_batch_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ml_dsa_verification");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(SIGNATURES as u64));
+    let signatures = signatures();
+
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            signatures.iter().all(|s| {
+                verify_quantum_signature(
+                    black_box(&s.public_key),
+                    &s.message,
+                    &s.signature,
+                    s.parameters,
+                )
+                .unwrap_or(false)
+            })
+        })
+    });
+
+    group.bench_function("batch", |b| {
+        b.iter(|| {
+            let mut batch = QuantumBatchVerifier::with_capacity(SIGNATURES);
+            for s in &signatures {
+                batch.push(
+                    &s.message,
+                    &s.signature,
+                    black_box(&s.public_key),
+                    s.parameters,
+                );
+            }
+            batch.verify().all_valid()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_batch_verification);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+//! Parallel input signature verification.
+//!
+//! `BlockValidator::verify_signatures` spreads a block's input checks across
+//! the rayon pool. This harness verifies a synthetic block of 4,000 inputs,
+//! each carrying an ML-DSA (Dilithium) signature, in pools of 1, 2, 4 and 8
+//! threads. Time per block should fall close to linearly with the thread
+//! count, up to the number of cores.
+//!
+//! Run with:
+//!
+//! ```
+//! cargo bench -p supernova-core --bench parallel_signatures
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::collections::HashMap;
+
+use supernova_core::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+use supernova_core::types::block::{Block, BlockHeader};
+use supernova_core::types::transaction::{
+    SignatureSchemeType, Transaction, TransactionInput, TransactionOutput, TransactionSignatureData,
+};
+use supernova_core::validation::BlockValidator;
+
+const INPUTS: usize = 4_000;
+
+/// The signer's key commitment, which its outputs are locked to
+fn owner_script(keypair: &QuantumKeyPair) -> Vec<u8> {
+    use sha3::{Digest, Sha3_512};
+    Sha3_512::digest(&keypair.public_key)[..32].to_vec()
+}
+
+fn prev_txid(i: usize) -> [u8; 32] {
+    let mut txid = [0u8; 32];
+    txid[..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+    txid
+}
+
+fn signed_spend(i: usize, keypair: &QuantumKeyPair) -> Transaction {
+    let mut tx = Transaction::new(
+        2,
+        vec![TransactionInput::new(prev_txid(i), 0, vec![], 0xffff_ffff)],
+        vec![TransactionOutput::new(900_000, vec![0x76; 25])],
+        0,
+    );
+    let signature = keypair.sign(&tx.signature_hash()).expect("sign");
+    tx.set_signature_data(TransactionSignatureData {
+        scheme: SignatureSchemeType::Dilithium,
+        security_level: keypair.parameters.security_level,
+        data: signature,
+        public_key: keypair.public_key.clone(),
+    });
+    tx
+}
+
+/// A block of `INPUTS` single-input spends and the outputs they spend
+fn block() -> (Block, HashMap<[u8; 32], TransactionOutput>) {
+    let keypair = QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium))
+        .expect("keypair");
+    let mut transactions = vec![Transaction::new_coinbase()];
+    transactions.extend((0..INPUTS).map(|i| signed_spend(i, &keypair)));
+    let block = Block::new(
+        BlockHeader::new(1, [1; 32], [0; 32], 0, 0x1d00ffff, 0),
+        transactions,
+    );
+
+    let prevout = TransactionOutput::new(1_000_000, owner_script(&keypair));
+    let utxos = (0..INPUTS)
+        .map(|i| (prev_txid(i), prevout.clone()))
+        .collect();
+    (block, utxos)
+}
+
+fn bench_verify_signatures(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_signatures");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(INPUTS as u64));
+    let validator = BlockValidator::new();
+    let (block, utxos) = block();
+    let get_output = |txid: &[u8; 32], vout: u32| utxos.get(txid).filter(|_| vout == 0).cloned();
+    assert!(validator.verify_signatures(&block, get_output).is_ok());
+
+    for threads in [1usize, 2, 4, 8] {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("thread pool");
+        group.bench_with_input(BenchmarkId::new("threads", threads), &block, |b, block| {
+            b.iter(|| pool.install(|| validator.verify_signatures(black_box(block), get_output)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify_signatures);
+criterion_main!(benches);
@@ -0,0 +1,217 @@
+//! Serialization benchmarks — transaction and block wire encoding.
+//!
+//! Quantum witnesses make encoding cost matter: a block is deserialized,
+//! validated and re-serialized several times on its way through the node,
+//! and a single ML-DSA or SPHINCS+ input carries kilobytes of signature
+//! material. This harness measures, per representative transaction shape:
+//!
+//!   1. bincode serialize / deserialize
+//!   2. txid computation (`Transaction::hash`)
+//!
+//! and per block: serialize / deserialize and merkle root construction.
+//!
+//! Shapes:
+//!
+//!   - `small_classical` — 1 input, 2 outputs, 72-byte signature script.
+//!   - `consolidation_400` — 400 classical inputs into 1 output.
+//!   - `mldsa_heavy` — 10 inputs, each an ML-DSA-65 signature + public key.
+//!   - `sphincs_heavy` — 4 inputs, each a SPHINCS+-256s signature + key.
+//!
+//! Before/after: `Transaction::hash` used to serialize into a temporary
+//! buffer, and for v2+ transactions with extended signature data it cloned
+//! the whole transaction first to strip that data. It now streams the
+//! encoding into the hasher without the clone. The `tx_hash` group runs the
+//! old algorithm as `buffered_clone` next to the current `streamed` one, so
+//! each report carries the before and after numbers for the machine it ran
+//! on.
+//!
+//! Run with:
+//!
+//! ```
+//! cargo bench -p supernova-core --bench serialization
+//! ```
+//!
+//! `tests/serialization_throughput.rs` asserts generous throughput floors
+//! over the same shapes so order-of-magnitude regressions fail the normal
+//! test suite.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sha2::{Digest, Sha256};
+
+use supernova_core::types::block::{Block, BlockHeader};
+use supernova_core::types::transaction::{
+    SignatureSchemeType, Transaction, TransactionInput, TransactionOutput,
+    TransactionSignatureData,
+};
+use supernova_core::util::merkle::MerkleTree;
+
+// --------------------------------------------------------------------------
+// Fixtures
+// --------------------------------------------------------------------------
+
+/// ML-DSA-65 signature and public key sizes (FIPS 204)
+const MLDSA65_SIG: usize = 3_309;
+const MLDSA65_PK: usize = 1_952;
+/// SPHINCS+-SHA2-256s signature and public key sizes
+const SPHINCS256S_SIG: usize = 29_792;
+const SPHINCS256S_PK: usize = 64;
+
+fn input(i: usize, script_len: usize) -> TransactionInput {
+    let mut prev = [0u8; 32];
+    prev[..8].copy_from_slice(&(i as u64).to_le_bytes());
+    TransactionInput::new(prev, (i % 4) as u32, vec![0x5a; script_len], 0xffff_fffe)
+}
+
+fn shape(inputs: usize, script_len: usize, outputs: usize) -> Transaction {
+    Transaction::new(
+        2,
+        (0..inputs).map(|i| input(i, script_len)).collect(),
+        (0..outputs)
+            .map(|i| TransactionOutput::new(1_000 + i as u64, vec![0x76; 34]))
+            .collect(),
+        0,
+    )
+}
+
+fn shapes() -> Vec<(&'static str, Transaction)> {
+    let mut mldsa = shape(10, MLDSA65_SIG + MLDSA65_PK, 2);
+    mldsa.set_signature_data(TransactionSignatureData {
+        scheme: SignatureSchemeType::Dilithium,
+        security_level: 3,
+        data: vec![0x11; MLDSA65_SIG],
+        public_key: vec![0x22; MLDSA65_PK],
+    });
+    let mut sphincs = shape(4, SPHINCS256S_SIG + SPHINCS256S_PK, 2);
+    sphincs.set_signature_data(TransactionSignatureData {
+        scheme: SignatureSchemeType::SphincsPlus,
+        security_level: 5,
+        data: vec![0x33; SPHINCS256S_SIG],
+        public_key: vec![0x44; SPHINCS256S_PK],
+    });
+
+    vec![
+        ("small_classical", shape(1, 72, 2)),
+        ("consolidation_400", shape(400, 72, 1)),
+        ("mldsa_heavy", mldsa),
+        ("sphincs_heavy", sphincs),
+    ]
+}
+
+/// A block of `count` transactions cycling through the shapes
+fn block(count: usize) -> Block {
+    let shapes = shapes();
+    let transactions: Vec<Transaction> =
+        (0..count).map(|i| shapes[i % shapes.len()].1.clone()).collect();
+    Block::new(BlockHeader::new(1, [0; 32], [0; 32], 1_700_000_000, 0x1d00ffff, 0), transactions)
+}
+
+/// `Transaction::hash` as it was before streaming: clone to strip the
+/// signature data, encode into a buffer, then hash the buffer.
+fn buffered_clone_hash(tx: &Transaction) -> [u8; 32] {
+    let mut stripped = tx.clone();
+    stripped.clear_signature_data();
+    let encoded = bincode::serialize(&stripped).expect("encode");
+    Sha256::digest(&encoded).into()
+}
+
+// --------------------------------------------------------------------------
+// Transactions
+// --------------------------------------------------------------------------
+
+fn bench_tx_serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tx_serialize");
+    for (name, tx) in shapes() {
+        let size = bincode::serialized_size(&tx).expect("size");
+        group.throughput(Throughput::Bytes(size));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &tx, |b, tx| {
+            b.iter(|| black_box(bincode::serialize(black_box(tx)).expect("encode")))
+        });
+    }
+    group.finish();
+}
+
+fn bench_tx_deserialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tx_deserialize");
+    for (name, tx) in shapes() {
+        let encoded = bincode::serialize(&tx).expect("encode");
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &encoded, |b, bytes| {
+            b.iter(|| {
+                let tx: Transaction = bincode::deserialize(black_box(bytes)).expect("decode");
+                black_box(tx)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_tx_hash(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tx_hash");
+    group.throughput(Throughput::Elements(1));
+    for (name, tx) in shapes() {
+        assert_eq!(tx.hash(), buffered_clone_hash(&tx), "hash algorithms disagree");
+        group.bench_with_input(BenchmarkId::new("streamed", name), &tx, |b, tx| {
+            b.iter(|| black_box(black_box(tx).hash()))
+        });
+        group.bench_with_input(BenchmarkId::new("buffered_clone", name), &tx, |b, tx| {
+            b.iter(|| black_box(buffered_clone_hash(black_box(tx))))
+        });
+    }
+    group.finish();
+}
+
+// --------------------------------------------------------------------------
+// Blocks
+// --------------------------------------------------------------------------
+
+fn bench_block_roundtrip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block");
+    group.sample_size(20);
+    for count in [100usize, 1_000] {
+        let block = block(count);
+        let encoded = bincode::serialize(&block).expect("encode");
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("serialize", count), &block, |b, block| {
+            b.iter(|| black_box(bincode::serialize(black_box(block)).expect("encode")))
+        });
+        group.bench_with_input(BenchmarkId::new("deserialize", count), &encoded, |b, bytes| {
+            b.iter(|| {
+                let block: Block = bincode::deserialize(black_box(bytes)).expect("decode");
+                black_box(block)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_merkle_root(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merkle_root");
+    for count in [100usize, 1_000, 10_000] {
+        let hashes: Vec<[u8; 32]> = (0..count as u64)
+            .map(|i| Sha256::digest(i.to_le_bytes()).into())
+            .collect();
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("tree", count), &hashes, |b, hashes| {
+            b.iter(|| black_box(MerkleTree::new(black_box(hashes)).root_hash()))
+        });
+    }
+
+    // Full path as validation runs it: hash every transaction, then build
+    let block = block(1_000);
+    group.throughput(Throughput::Elements(1_000));
+    group.bench_function("block_1000_from_transactions", |b| {
+        b.iter(|| black_box(black_box(&block).calculate_merkle_root()))
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_tx_serialize,
+    bench_tx_deserialize,
+    bench_tx_hash,
+    bench_block_roundtrip,
+    bench_merkle_root,
+);
+criterion_main!(benches);
@@ -0,0 +1,141 @@
+//! Validation tracing overhead.
+//!
+//! Validation tracing (`validation::trace`) must cost nothing measurable when
+//! it is off, since every block runs through the same instrumented
+//! validators. This harness measures:
+//!
+//!   1. `check_overhead` — one check called directly (`plain`) and through a
+//!      disabled `Tracer` (`disabled`). The two should be indistinguishable.
+//!   2. `block_validation` — `BlockValidator::validate_block_with_context`
+//!      (tracing off) against `validate_block_with_context_traced` (tracing
+//!      on) for blocks of 1, 100 and 1,000 transactions.
+//!
+//! Run with:
+//!
+//! ```
+//! cargo bench -p supernova-core --bench validation_trace
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use supernova_core::config::NetworkType;
+use supernova_core::governance::{treasury_script_pubkey, TREASURY_ALLOCATION_PERCENT};
+use supernova_core::types::block::{Block, BlockHeader};
+use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+use supernova_core::validation::{
+    BlockValidationConfig, BlockValidator, Tracer, ValidationContext,
+};
+
+const PREV_HASH: [u8; 32] = [1; 32];
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Coinbase paying the miner and the regtest treasury
+fn coinbase() -> Transaction {
+    const TOTAL: u64 = 5_000_000_000;
+    let treasury = TOTAL * TREASURY_ALLOCATION_PERCENT / 100;
+    Transaction::new(
+        1,
+        vec![TransactionInput::new_coinbase(vec![1, 2, 3])],
+        vec![
+            TransactionOutput::new(TOTAL - treasury, vec![0x76; 25]),
+            TransactionOutput::new(treasury, treasury_script_pubkey(NetworkType::Regtest)),
+        ],
+        0,
+    )
+}
+
+fn spend(i: usize) -> Transaction {
+    let mut prev = [0u8; 32];
+    prev[..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+    Transaction::new(
+        1,
+        vec![TransactionInput::new(prev, 0, vec![0x5a; 72], 0xffff_fffe)],
+        vec![
+            TransactionOutput::new(10_000, vec![0x76; 25]),
+            TransactionOutput::new(20_000, vec![0x76; 25]),
+        ],
+        0,
+    )
+}
+
+/// A valid block at height 1 with `count` transactions including the coinbase
+fn block(count: usize) -> Block {
+    let mut transactions = vec![coinbase()];
+    transactions.extend((1..count).map(spend));
+    let mut header = BlockHeader::new(1, PREV_HASH, [0; 32], now(), 0x1d00ffff, 0);
+    header.set_height(1);
+    let mut block = Block::new(header, transactions);
+    block.header.merkle_root = block.calculate_merkle_root();
+    block
+}
+
+fn context() -> ValidationContext {
+    let prev_timestamp = now() - 600;
+    ValidationContext {
+        prev_block_hash: PREV_HASH,
+        prev_block_height: 0,
+        prev_block_timestamp: prev_timestamp,
+        median_time_past: prev_timestamp - 3600,
+        current_difficulty: 0x1d00ffff,
+        utxo_provider: None,
+        network: NetworkType::Regtest,
+    }
+}
+
+fn validator() -> BlockValidator {
+    // The fixture blocks are not mined
+    BlockValidator::with_config(BlockValidationConfig {
+        validate_pow: false,
+        ..BlockValidationConfig::default()
+    })
+}
+
+fn bench_check_overhead(c: &mut Criterion) {
+    let mut group = c.benchmark_group("check_overhead");
+    let value = 42u64;
+    let check = |v: u64| if v > 100 { Err("too large") } else { Ok(v) };
+
+    group.bench_function("plain", |b| b.iter(|| check(black_box(value))));
+    group.bench_function("disabled", |b| {
+        b.iter(|| {
+            Tracer::disabled().check(
+                "value",
+                || format!("value={}", value),
+                || check(black_box(value)),
+            )
+        })
+    });
+    group.finish();
+}
+
+fn bench_block_validation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("block_validation");
+    group.sample_size(30);
+    let validator = validator();
+    let context = context();
+
+    for count in [1usize, 100, 1_000] {
+        let block = block(count);
+        assert!(validator
+            .validate_block_with_context(&block, &context)
+            .is_ok());
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("untraced", count), &block, |b, block| {
+            b.iter(|| validator.validate_block_with_context(black_box(block), &context))
+        });
+        group.bench_with_input(BenchmarkId::new("traced", count), &block, |b, block| {
+            b.iter(|| validator.validate_block_with_context_traced(black_box(block), &context))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_check_overhead, bench_block_validation);
+criterion_main!(benches);
@@ -68,6 +68,9 @@ pub enum HTLCError {
 
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
+
+    #[error("Hash function not supported by HTLC scripts: {0}")]
+    UnsupportedHashFunction(String),
 }
 
 /// Swap protocol errors
@@ -3,10 +3,13 @@
 //! This module implements HTLCs with quantum-resistant signatures for use in
 //! atomic swaps between Bitcoin and Supernova blockchains.
 
-use crate::atomic_swap::crypto::{compute_hash, HashLock};
+use crate::atomic_swap::crypto::{compute_hash, HashFunction, HashLock};
 use crate::atomic_swap::error::{HTLCError, SecurityError};
 use crate::crypto::{MLDSAPublicKey, MLDSASignature};
+use crate::script::{Opcode, ScriptBuilder};
+use crate::types::script::Script;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Minimum HTLC amount to avoid dust-like outputs.
@@ -312,19 +315,45 @@ impl SupernovaHTLC {
     }
 
     /// Create a funding transaction output for this HTLC
-    pub fn create_funding_output(&self) -> crate::types::TransactionOutput {
-        crate::types::TransactionOutput::new(self.amount, self.create_script_pubkey())
+    pub fn create_funding_output(&self) -> Result<crate::types::TransactionOutput, HTLCError> {
+        Ok(crate::types::TransactionOutput::new(
+            self.amount,
+            self.create_script_pubkey()?,
+        ))
     }
 
-    /// Create the script pubkey for this HTLC
-    /// This is a simplified version - in production, this would be more complex
-    fn create_script_pubkey(&self) -> Vec<u8> {
-        // In a real implementation, this would create a proper script
-        // For now, we'll create a placeholder that encodes the HTLC ID
-        let mut script = Vec::new();
-        script.extend_from_slice(b"HTLC:");
-        script.extend_from_slice(&self.htlc_id);
-        script
+    /// Lock time of the refund: the absolute timeout, which must fit a
+    /// transaction lock time
+    pub fn refund_lock_time(&self) -> Result<u32, HTLCError> {
+        u32::try_from(self.time_lock.absolute_timeout).map_err(|_| HTLCError::InvalidTimeout)
+    }
+
+    /// Witness script of the funding output: spendable with the SHA-256
+    /// preimage of the hash lock, or from the absolute timeout on.
+    ///
+    /// ML-DSA keys are larger than a script element, so which participant
+    /// may take either branch is left to the transaction signature checked
+    /// by [`Self::verify_claim`] and [`Self::verify_refund`].
+    pub fn funding_witness_script(&self) -> Result<Vec<u8>, HTLCError> {
+        if !matches!(self.hash_lock.hash_type, HashFunction::SHA256) {
+            return Err(HTLCError::UnsupportedHashFunction(format!(
+                "{:?}",
+                self.hash_lock.hash_type
+            )));
+        }
+        Ok(ScriptBuilder::hash_time_locked(
+            &self.hash_lock.hash_value,
+            &[Opcode::OP_1 as u8],
+            self.refund_lock_time()?,
+            &[Opcode::OP_1 as u8],
+        ))
+    }
+
+    /// Create the script pubkey for this HTLC: P2WSH of
+    /// [`Self::funding_witness_script`]
+    fn create_script_pubkey(&self) -> Result<Vec<u8>, HTLCError> {
+        let script_hash = Sha256::digest(self.funding_witness_script()?);
+        Ok(Script::new_p2wsh(&script_hash).as_bytes().to_vec())
     }
 
     /// Construct an **unsigned** refund transaction that spends the funding
@@ -334,10 +363,14 @@ impl SupernovaHTLC {
     /// input pointing at `(funding_outpoint_txid, funding_outpoint_vout)`,
     /// single output paying `amount - refund_fee` to the initiator's
     /// `refund_address` (falling back to `address`), `version = 2`,
-    /// `lock_time = 0` — but carries an empty `signature_script` and no
+    /// `lock_time` set to the absolute timeout — but carries an empty `signature_script` and no
     /// witness data. The signing layer is responsible for filling those
     /// in before broadcast.
     ///
+    /// The lock time satisfies the `OP_CHECKLOCKTIMEVERIFY` refund branch
+    /// of [`Self::funding_witness_script`], and the transaction is not final
+    /// before the timeout, measured against median time past.
+    ///
     /// The input's `sequence` is set to `time_lock.relative_timeout` as
     /// defense-in-depth: consensus sequence-based locktime rejects the
     /// refund if the relative timeout hasn't elapsed, even if the absolute
@@ -348,6 +381,8 @@ impl SupernovaHTLC {
     ///   the HTLC has already left the refundable state.
     /// - [`HTLCError::InvalidAmount`] if `refund_fee >= amount` or the
     ///   resulting refund is below [`MIN_HTLC_AMOUNT`].
+    /// - [`HTLCError::InvalidTimeout`] if the absolute timeout does not fit
+    ///   a lock time.
     pub fn build_refund_transaction(
         &self,
         funding_outpoint_txid: [u8; 32],
@@ -380,6 +415,8 @@ impl SupernovaHTLC {
             )));
         }
 
+        let lock_time = self.refund_lock_time()?;
+
         let refund_destination = self
             .initiator
             .refund_address
@@ -397,7 +434,12 @@ impl SupernovaHTLC {
             refund_destination_script(refund_destination),
         );
 
-        Ok(crate::types::Transaction::new(2, vec![input], vec![output], 0))
+        Ok(crate::types::Transaction::new(
+            2,
+            vec![input],
+            vec![output],
+            lock_time,
+        ))
     }
 
     /// Calculate the total amount needed including fees
@@ -551,6 +593,34 @@ mod tests {
         assert_eq!(total, 100_000_000 + 1000 + 100); // amount + claim_fee + service_fee
     }
 
+    #[test]
+    fn test_refund_is_locked_until_the_timeout() {
+        let htlc = create_test_htlc();
+        let lock_time = htlc.refund_lock_time().unwrap();
+        let refund = htlc.build_refund_transaction([7; 32], 0).unwrap();
+        assert_eq!(refund.lock_time(), lock_time);
+
+        let witness_script = htlc.funding_witness_script().unwrap();
+        let cltv = ScriptBuilder::new()
+            .check_lock_time_verify(lock_time)
+            .build();
+        assert!(witness_script
+            .windows(cltv.len())
+            .any(|w| w == cltv.as_slice()));
+        let script_hash = Sha256::digest(&witness_script);
+        assert_eq!(
+            htlc.create_funding_output().unwrap().pub_key_script,
+            Script::new_p2wsh(&script_hash).as_bytes().to_vec()
+        );
+
+        let mut late = htlc.clone();
+        late.time_lock.absolute_timeout = u64::from(u32::MAX) + 1;
+        assert!(matches!(
+            late.build_refund_transaction([7; 32], 0),
+            Err(HTLCError::InvalidTimeout)
+        ));
+    }
+
     // Tests for `build_refund_transaction` live in
     // `supernova-core/tests/atomic_swap_refund_construction.rs` because
     // adjacent in-module test fixtures here depend on pre-broken APIs
@@ -527,6 +527,15 @@ pub fn mainnet_checkpoints() -> Vec<Checkpoint> {
     ]
 }
 
+/// Hash of the latest hardcoded mainnet checkpoint, the default assumevalid
+/// block; `None` until the network has one
+pub fn default_assume_valid() -> Option<[u8; 32]> {
+    mainnet_checkpoints()
+        .into_iter()
+        .max_by_key(|checkpoint| checkpoint.height)
+        .map(|checkpoint| checkpoint.block_hash)
+}
+
 /// Hardcoded checkpoints for the Supernova testnet
 pub fn testnet_checkpoints() -> Vec<Checkpoint> {
     vec![
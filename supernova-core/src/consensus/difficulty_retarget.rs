@@ -20,6 +20,10 @@
 
 use uint::construct_uint;
 
+/// Block times without a block after which a testnet block may be mined at the
+/// `pow_limit` floor (see [`min_difficulty_allowed`]).
+pub const STALL_BLOCK_TIMES: u64 = 4;
+
 construct_uint! {
     /// 256-bit difficulty target.
     pub struct Target256(4);
@@ -39,21 +43,40 @@ pub struct RetargetParams {
     pub interval: u64,
     /// Easiest permitted compact target (the maximum target / minimum difficulty).
     pub pow_limit_bits: u32,
+    /// Testnet stall recovery: after `STALL_BLOCK_TIMES` block times without a
+    /// block, the next one may be mined at `pow_limit_bits`. Never on mainnet.
+    pub allow_min_difficulty_blocks: bool,
 }
 
 impl RetargetParams {
     /// Mainnet: 150 s blocks, 2016-block periods, `0x1e0fffff` floor.
     pub const fn mainnet() -> Self {
-        Self { target_block_time: 150, interval: 2016, pow_limit_bits: 0x1e0f_ffff }
+        Self {
+            target_block_time: 150,
+            interval: 2016,
+            pow_limit_bits: 0x1e0f_ffff,
+            allow_min_difficulty_blocks: false,
+        }
     }
-    /// Testnet: identical cadence/floor to mainnet today.
+    /// Testnet: identical cadence/floor to mainnet today, plus min-difficulty
+    /// blocks after a stall so a hashrate drop cannot freeze the chain.
     pub const fn testnet() -> Self {
-        Self { target_block_time: 150, interval: 2016, pow_limit_bits: 0x1e0f_ffff }
+        Self {
+            target_block_time: 150,
+            interval: 2016,
+            pow_limit_bits: 0x1e0f_ffff,
+            allow_min_difficulty_blocks: true,
+        }
     }
     /// Regtest: fast 30 s blocks, short 144-block periods, easy `0x207fffff`
     /// floor (matches the storage tests / current genesis bits).
     pub const fn regtest() -> Self {
-        Self { target_block_time: 30, interval: 144, pow_limit_bits: 0x207f_ffff }
+        Self {
+            target_block_time: 30,
+            interval: 144,
+            pow_limit_bits: 0x207f_ffff,
+            allow_min_difficulty_blocks: false,
+        }
     }
 
     /// Total target time for one period (`block_time * interval`).
@@ -144,8 +167,65 @@ fn retarget(prev_bits: u32, actual_timespan: u64, params: &RetargetParams) -> u3
     target_to_compact(narrow(&scaled))
 }
 
+/// True iff the block at `height` closes a period and is retargeted.
+pub fn is_retarget_height(height: u64, params: &RetargetParams) -> bool {
+    height > 0 && params.interval > 0 && height % params.interval == 0
+}
+
+/// True iff the block at `height`, stamped `timestamp` on a parent stamped
+/// `parent_timestamp`, may be mined at `pow_limit_bits` instead of the required
+/// difficulty: the network allows min-difficulty blocks and no block has been
+/// found for more than `STALL_BLOCK_TIMES` block times.
+///
+/// Retarget heights never qualify — the period's new difficulty must land on a
+/// real block, or the chain would carry the floor forward as its difficulty.
+pub fn min_difficulty_allowed(
+    height: u64,
+    parent_timestamp: u64,
+    timestamp: u64,
+    params: &RetargetParams,
+) -> bool {
+    params.allow_min_difficulty_blocks
+        && height > 0
+        && !is_retarget_height(height, params)
+        && timestamp
+            > parent_timestamp
+                .saturating_add(params.target_block_time.saturating_mul(STALL_BLOCK_TIMES))
+}
+
+/// True iff a block at `height` claiming `bits` may be a min-difficulty stall
+/// block, whose `bits` say nothing about the chain's real difficulty.
+pub fn is_min_difficulty_block(height: u64, bits: u32, params: &RetargetParams) -> bool {
+    params.allow_min_difficulty_blocks
+        && height > 0
+        && !is_retarget_height(height, params)
+        && bits == params.pow_limit_bits
+}
+
+/// The chain's real difficulty as of a parent: the `bits` of the newest block
+/// in `ancestry` — `(height, bits)` pairs from the parent backwards — that is
+/// not a min-difficulty stall block. Pass this as `prev_bits` to
+/// [`required_bits`] so stall blocks neither carry over to the next block nor
+/// seed the next retarget. `None` only for an empty `ancestry`; an ancestry
+/// made up only of stall blocks yields its oldest entry.
+pub fn last_regular_bits<I>(ancestry: I, params: &RetargetParams) -> Option<u32>
+where
+    I: IntoIterator<Item = (u64, u32)>,
+{
+    let mut last = None;
+    for (height, bits) in ancestry {
+        last = Some(bits);
+        if !is_min_difficulty_block(height, bits, params) {
+            break;
+        }
+    }
+    last
+}
+
 /// The compact `bits` the chain REQUIRES for the block at `height`, whose parent
-/// has `prev_bits`.
+/// has `prev_bits`. Where min-difficulty blocks are allowed, `prev_bits` is the
+/// parent's [`last_regular_bits`], and a block [`min_difficulty_allowed`] may
+/// claim `pow_limit_bits` instead of this value.
 ///
 /// Off a retarget boundary the difficulty is unchanged (`prev_bits`). At a
 /// boundary (`height % interval == 0`, `height > 0`), `boundary_timestamps` must
@@ -162,7 +242,7 @@ pub fn required_bits(
     boundary_timestamps: Option<(u64, u64)>,
     params: &RetargetParams,
 ) -> u32 {
-    if height > 0 && params.interval > 0 && height % params.interval == 0 {
+    if is_retarget_height(height, params) {
         if let Some((first_ts, last_ts)) = boundary_timestamps {
             return retarget(prev_bits, last_ts.saturating_sub(first_ts), params);
         }
@@ -266,6 +346,51 @@ mod tests {
         assert_eq!(required_bits(0, MID_BITS, Some((0, fast)), &p), MID_BITS);
     }
 
+    #[test]
+    fn stalled_testnet_allows_one_min_difficulty_block() {
+        let p = RetargetParams::testnet();
+        let stall = p.target_block_time * STALL_BLOCK_TIMES;
+        assert!(!min_difficulty_allowed(5, 1_000, 1_000 + stall, &p), "exactly 4x is no stall");
+        assert!(min_difficulty_allowed(5, 1_000, 1_001 + stall, &p));
+        // The period's retarget block is always mined at the real difficulty.
+        assert!(!min_difficulty_allowed(p.interval, 1_000, 1_000 + 100 * stall, &p));
+        assert!(!min_difficulty_allowed(0, 1_000, 1_000 + 100 * stall, &p));
+    }
+
+    #[test]
+    fn mainnet_and_regtest_never_allow_min_difficulty_blocks() {
+        for p in [RetargetParams::mainnet(), RetargetParams::regtest()] {
+            assert!(!p.allow_min_difficulty_blocks);
+            assert!(!min_difficulty_allowed(5, 0, u64::MAX, &p));
+            assert!(!is_min_difficulty_block(5, p.pow_limit_bits, &p));
+            let ancestry = [(5, p.pow_limit_bits), (4, MID_BITS)];
+            assert_eq!(last_regular_bits(ancestry, &p), Some(p.pow_limit_bits));
+        }
+    }
+
+    #[test]
+    fn difficulty_recovers_past_min_difficulty_blocks() {
+        let p = RetargetParams::testnet();
+        // Parent and grandparent were stall blocks mined at the floor; the
+        // chain's real difficulty is still MID_BITS.
+        let ancestry = [(7, p.pow_limit_bits), (6, p.pow_limit_bits), (5, MID_BITS), (4, MID_BITS)];
+        let regular = last_regular_bits(ancestry, &p).unwrap();
+        assert_eq!(regular, MID_BITS);
+        assert_eq!(required_bits(8, regular, None, &p), MID_BITS, "the next block returns to it");
+
+        // A retarget seeded from the regular bits moves from MID_BITS, not from
+        // the floor the stall blocks claimed.
+        let boundary = p.interval;
+        let ancestry = [(boundary - 1, p.pow_limit_bits), (boundary - 2, MID_BITS)];
+        let regular = last_regular_bits(ancestry, &p).unwrap();
+        let span = p.target_timespan();
+        assert_eq!(required_bits(boundary, regular, Some((0, span)), &p), MID_BITS);
+
+        // A retarget block at the floor is the real difficulty, not a stall block.
+        let ancestry = [(boundary, p.pow_limit_bits), (boundary - 1, MID_BITS)];
+        assert_eq!(last_regular_bits(ancestry, &p), Some(p.pow_limit_bits));
+    }
+
     #[test]
     fn floor_predicate_rejects_easier_and_zero_targets() {
         let p = RetargetParams::testnet(); // floor 0x1e0fffff
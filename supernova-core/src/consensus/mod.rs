@@ -10,7 +10,9 @@ pub mod fork_resolution_v2;
 pub mod secure_fork_resolution;
 pub mod time_warp_prevention;
 pub mod timestamp_validation;
+pub mod version_bits;
 pub mod weak_subjectivity;
+pub mod witness;
 
 #[cfg(test)]
 pub mod time_warp_tests;
@@ -47,6 +49,11 @@ pub use timestamp_validation::{
     MEDIAN_TIME_BLOCKS,
 };
 
+pub use version_bits::{
+    compute_block_version, signaling_stats, ChainEntry, Deployment, SignalingStats,
+    ThresholdState, VersionBitsCache, VersionBitsChain, VersionBitsError, VersionBitsParams,
+};
+
 pub use weak_subjectivity::{
     PeerChainInfo, WeakSubjectivityConfig, WeakSubjectivityError, WeakSubjectivityManager,
     WeakSubjectivityResult, WeakSubjectivityState, DEFAULT_WS_PERIOD_BLOCKS,
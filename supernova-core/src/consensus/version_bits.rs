@@ -0,0 +1,695 @@
+//! Version-bits deployment signaling
+//!
+//! Consensus changes are activated by miner signaling rather than hardcoded
+//! heights. Each deployment owns one bit of the block header version; once a
+//! threshold of blocks in a retarget window sets the bit, the deployment locks
+//! in and becomes active one window later.
+//!
+//! A deployment's state is the same for every block of a window and only
+//! changes at window boundaries:
+//!
+//! ```text
+//! Defined --(MTP >= start)--> Started --(threshold met)--> LockedIn --> Active
+//!    |                           |
+//!    +------(MTP >= timeout)-----+------------------------------------> Failed
+//! ```
+//!
+//! States are computed along a block's own ancestry, so a reorg onto a branch
+//! with different signaling simply yields a different state. Results are
+//! cached per window keyed by the hash of the window's last block, which keeps
+//! branches from overwriting each other.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Top three version bits that mark a header as using version-bits signaling
+pub const VERSIONBITS_TOP_BITS: u32 = 0x2000_0000;
+
+/// Mask selecting the top three version bits
+pub const VERSIONBITS_TOP_MASK: u32 = 0xE000_0000;
+
+/// Number of bits available for deployments
+pub const VERSIONBITS_NUM_BITS: u8 = 29;
+
+/// `start_time` of a deployment that is not scheduled yet
+pub const NEVER_STARTS: u64 = u64::MAX;
+
+/// `timeout` of a deployment that signals until it locks in
+pub const NO_TIMEOUT: u64 = u64::MAX;
+
+/// Deployment state for the blocks of one window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdState {
+    /// Before the start time; blocks don't signal
+    Defined,
+    /// Signaling window is open
+    Started,
+    /// Threshold reached; activates at the next window
+    LockedIn,
+    /// The new rules are enforced
+    Active,
+    /// Timed out before reaching the threshold
+    Failed,
+}
+
+impl ThresholdState {
+    /// Whether miners should set the deployment's bit
+    pub fn is_signaling(&self) -> bool {
+        matches!(self, ThresholdState::Started | ThresholdState::LockedIn)
+    }
+}
+
+impl std::fmt::Display for ThresholdState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ThresholdState::Defined => "defined",
+            ThresholdState::Started => "started",
+            ThresholdState::LockedIn => "locked_in",
+            ThresholdState::Active => "active",
+            ThresholdState::Failed => "failed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A named consensus change activated by version-bits signaling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    /// Stable name used by validation rules and the API
+    pub name: String,
+    /// Header version bit, `0..VERSIONBITS_NUM_BITS`
+    pub bit: u8,
+    /// Median-time-past at which signaling opens
+    pub start_time: u64,
+    /// Median-time-past after which a deployment that hasn't locked in fails
+    pub timeout: u64,
+    /// Percentage of a window's blocks that must signal to lock in
+    pub threshold_percent: u8,
+    /// Earliest height at which a locked-in deployment may become active
+    pub min_activation_height: u64,
+}
+
+impl Deployment {
+    /// Header version mask for this deployment's bit
+    pub fn mask(&self) -> u32 {
+        1u32 << self.bit
+    }
+
+    /// Number of signaling blocks needed in a window of `window` blocks
+    pub fn threshold(&self, window: u64) -> u64 {
+        (window * self.threshold_percent as u64).div_ceil(100)
+    }
+
+    /// Whether a header with `version` signals for this deployment
+    pub fn is_signaled_by(&self, version: u32) -> bool {
+        (version & VERSIONBITS_TOP_MASK) == VERSIONBITS_TOP_BITS && version & self.mask() != 0
+    }
+}
+
+/// Per-network deployment schedule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionBitsParams {
+    /// Blocks per signaling window; matches the retarget interval
+    pub window: u64,
+    /// Known deployments
+    pub deployments: Vec<Deployment>,
+}
+
+impl VersionBitsParams {
+    /// Mainnet: deployments are defined but not yet scheduled.
+    pub fn mainnet() -> Self {
+        Self {
+            window: 2016,
+            deployments: standard_deployments(NEVER_STARTS, NO_TIMEOUT, 95),
+        }
+    }
+
+    /// Testnet: signaling opens 2026-11-01 and times out a year later.
+    pub fn testnet() -> Self {
+        const START: u64 = 1_793_491_200;
+        const YEAR: u64 = 365 * 24 * 3600;
+        Self {
+            window: 2016,
+            deployments: standard_deployments(START, START + YEAR, 75),
+        }
+    }
+
+    /// Regtest: short windows and signaling open from genesis.
+    pub fn regtest() -> Self {
+        Self {
+            window: 144,
+            deployments: standard_deployments(0, NO_TIMEOUT, 75),
+        }
+    }
+
+    /// Look up a deployment by name
+    pub fn deployment(&self, name: &str) -> Option<&Deployment> {
+        self.deployments.iter().find(|d| d.name == name)
+    }
+}
+
+impl Default for VersionBitsParams {
+    fn default() -> Self {
+        Self::testnet()
+    }
+}
+
+/// Deployments proposed so far, sharing one schedule per network
+fn standard_deployments(start_time: u64, timeout: u64, threshold_percent: u8) -> Vec<Deployment> {
+    ["witness_commitment", "quantum_policy", "asert"]
+        .iter()
+        .enumerate()
+        .map(|(bit, name)| Deployment {
+            name: name.to_string(),
+            bit: bit as u8,
+            start_time,
+            timeout,
+            threshold_percent,
+            min_activation_height: 0,
+        })
+        .collect()
+}
+
+/// The header fields version-bits needs from a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainEntry {
+    pub hash: [u8; 32],
+    pub prev_hash: [u8; 32],
+    pub height: u64,
+    pub version: u32,
+}
+
+/// Read access to the block tree states are computed on
+pub trait VersionBitsChain {
+    /// Header fields of a known block
+    fn entry(&self, hash: &[u8; 32]) -> Option<ChainEntry>;
+
+    /// Median-time-past of the chain ending at `hash` (inclusive)
+    fn median_time_past(&self, hash: &[u8; 32]) -> Option<u64>;
+}
+
+/// Signaling progress in the window containing the next block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignalingStats {
+    /// Blocks per window
+    pub period: u64,
+    /// Signaling blocks required to lock in
+    pub threshold: u64,
+    /// Blocks of the current window already mined
+    pub elapsed: u64,
+    /// How many of those signaled
+    pub count: u64,
+    /// Whether the threshold can still be reached this window
+    pub possible: bool,
+}
+
+/// Errors computing a deployment state
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VersionBitsError {
+    #[error("Unknown deployment: {0}")]
+    UnknownDeployment(String),
+    #[error("Block {0} is missing from the chain")]
+    MissingBlock(String),
+}
+
+/// Deployment states cached per window
+#[derive(Debug, Clone, Default)]
+pub struct VersionBitsCache {
+    /// (deployment, last block of the previous window) -> state of the window
+    states: HashMap<(String, [u8; 32]), ThresholdState>,
+}
+
+impl VersionBitsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of cached window states
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+
+    /// Drop every cached state, e.g. after the schedule changes
+    pub fn clear(&mut self) {
+        self.states.clear();
+    }
+
+    /// State of `deployment` for the block built on `parent` (`None` for the
+    /// genesis block)
+    pub fn state<C: VersionBitsChain>(
+        &mut self,
+        params: &VersionBitsParams,
+        deployment: &Deployment,
+        chain: &C,
+        parent: Option<&[u8; 32]>,
+    ) -> Result<ThresholdState, VersionBitsError> {
+        let window = params.window.max(1);
+
+        // The state only changes at window boundaries: find the last block of
+        // the window before the one `parent`'s child belongs to
+        let Some(mut cursor) = self.window_end(window, chain, parent)? else {
+            return Ok(ThresholdState::Defined);
+        };
+
+        // Walk back one window at a time until a cached or trivially known state
+        let mut pending = Vec::new();
+        let mut state = loop {
+            let key = (deployment.name.clone(), cursor.hash);
+            if let Some(state) = self.states.get(&key) {
+                break *state;
+            }
+            let mtp = median_time_past(chain, &cursor.hash)?;
+            if mtp < deployment.start_time {
+                self.states.insert(key, ThresholdState::Defined);
+                break ThresholdState::Defined;
+            }
+            pending.push(cursor);
+            if cursor.height < window {
+                // The first window is always Defined
+                break ThresholdState::Defined;
+            }
+            cursor = ancestor(chain, cursor, cursor.height - window)?;
+        };
+
+        // Replay the transitions forward from the oldest uncached window
+        while let Some(end) = pending.pop() {
+            state = match state {
+                ThresholdState::Defined => {
+                    let mtp = median_time_past(chain, &end.hash)?;
+                    if mtp >= deployment.timeout {
+                        ThresholdState::Failed
+                    } else if mtp >= deployment.start_time {
+                        ThresholdState::Started
+                    } else {
+                        ThresholdState::Defined
+                    }
+                }
+                ThresholdState::Started => {
+                    let count = count_signals(deployment, window, chain, &end)?;
+                    if count >= deployment.threshold(window) {
+                        ThresholdState::LockedIn
+                    } else if median_time_past(chain, &end.hash)? >= deployment.timeout {
+                        ThresholdState::Failed
+                    } else {
+                        ThresholdState::Started
+                    }
+                }
+                ThresholdState::LockedIn => {
+                    if end.height + 1 >= deployment.min_activation_height {
+                        ThresholdState::Active
+                    } else {
+                        ThresholdState::LockedIn
+                    }
+                }
+                terminal @ (ThresholdState::Active | ThresholdState::Failed) => terminal,
+            };
+            self.states
+                .insert((deployment.name.clone(), end.hash), state);
+        }
+
+        Ok(state)
+    }
+
+    /// State of the deployment called `name` for the block built on `parent`
+    pub fn state_by_name<C: VersionBitsChain>(
+        &mut self,
+        params: &VersionBitsParams,
+        name: &str,
+        chain: &C,
+        parent: Option<&[u8; 32]>,
+    ) -> Result<ThresholdState, VersionBitsError> {
+        let deployment = params
+            .deployment(name)
+            .ok_or_else(|| VersionBitsError::UnknownDeployment(name.to_string()))?;
+        self.state(params, deployment, chain, parent)
+    }
+
+    /// Last block of the window before the one containing `parent`'s child,
+    /// or `None` while that child is still in the first window
+    fn window_end<C: VersionBitsChain>(
+        &self,
+        window: u64,
+        chain: &C,
+        parent: Option<&[u8; 32]>,
+    ) -> Result<Option<ChainEntry>, VersionBitsError> {
+        let Some(parent) = parent else {
+            return Ok(None);
+        };
+        let parent = lookup(chain, parent)?;
+        let next_height = parent.height + 1;
+        if next_height < window {
+            return Ok(None);
+        }
+        let end_height = next_height - (next_height % window) - 1;
+        ancestor(chain, parent, end_height).map(Some)
+    }
+}
+
+/// Signaling statistics for `deployment` in the window containing the block
+/// built on `parent`
+pub fn signaling_stats<C: VersionBitsChain>(
+    params: &VersionBitsParams,
+    deployment: &Deployment,
+    chain: &C,
+    parent: Option<&[u8; 32]>,
+) -> Result<SignalingStats, VersionBitsError> {
+    let window = params.window.max(1);
+    let threshold = deployment.threshold(window);
+
+    let (elapsed, count) = match parent {
+        None => (0, 0),
+        Some(parent) => {
+            let mut cursor = lookup(chain, parent)?;
+            let elapsed = (cursor.height + 1) % window;
+            let mut count = 0;
+            for _ in 0..elapsed {
+                if deployment.is_signaled_by(cursor.version) {
+                    count += 1;
+                }
+                if cursor.height == 0 {
+                    break;
+                }
+                cursor = lookup(chain, &cursor.prev_hash)?;
+            }
+            (elapsed, count)
+        }
+    };
+
+    Ok(SignalingStats {
+        period: window,
+        threshold,
+        elapsed,
+        count,
+        possible: count + (window - elapsed) >= threshold,
+    })
+}
+
+/// Header version for a block built on `parent`, setting the bit of every
+/// deployment in `signal` that is currently open for signaling
+pub fn compute_block_version<C: VersionBitsChain>(
+    cache: &mut VersionBitsCache,
+    params: &VersionBitsParams,
+    chain: &C,
+    parent: Option<&[u8; 32]>,
+    signal: &[String],
+) -> Result<u32, VersionBitsError> {
+    let mut version = VERSIONBITS_TOP_BITS;
+    for deployment in &params.deployments {
+        if !signal.iter().any(|name| *name == deployment.name) {
+            continue;
+        }
+        if cache
+            .state(params, deployment, chain, parent)?
+            .is_signaling()
+        {
+            version |= deployment.mask();
+        }
+    }
+    Ok(version)
+}
+
+fn lookup<C: VersionBitsChain>(chain: &C, hash: &[u8; 32]) -> Result<ChainEntry, VersionBitsError> {
+    chain
+        .entry(hash)
+        .ok_or_else(|| VersionBitsError::MissingBlock(hex::encode(hash)))
+}
+
+fn median_time_past<C: VersionBitsChain>(
+    chain: &C,
+    hash: &[u8; 32],
+) -> Result<u64, VersionBitsError> {
+    chain
+        .median_time_past(hash)
+        .ok_or_else(|| VersionBitsError::MissingBlock(hex::encode(hash)))
+}
+
+/// Walk `from`'s own ancestry back to `height`
+fn ancestor<C: VersionBitsChain>(
+    chain: &C,
+    from: ChainEntry,
+    height: u64,
+) -> Result<ChainEntry, VersionBitsError> {
+    let mut cursor = from;
+    while cursor.height > height {
+        cursor = lookup(chain, &cursor.prev_hash)?;
+    }
+    Ok(cursor)
+}
+
+/// Signaling blocks in the window ending at `end`
+fn count_signals<C: VersionBitsChain>(
+    deployment: &Deployment,
+    window: u64,
+    chain: &C,
+    end: &ChainEntry,
+) -> Result<u64, VersionBitsError> {
+    let mut cursor = *end;
+    let mut count = 0;
+    for _ in 0..window {
+        if deployment.is_signaled_by(cursor.version) {
+            count += 1;
+        }
+        if cursor.height == 0 {
+            break;
+        }
+        cursor = lookup(chain, &cursor.prev_hash)?;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: u64 = 10;
+    const START: u64 = 1_000;
+    const TIMEOUT: u64 = 10_000;
+
+    /// In-memory block tree; block `n` of a branch has timestamp `n * 10`
+    #[derive(Default)]
+    struct TestChain {
+        entries: HashMap<[u8; 32], (ChainEntry, u64)>,
+    }
+
+    impl TestChain {
+        /// Extend `parent` with `versions.len()` blocks on branch `branch`
+        fn extend(&mut self, parent: Option<[u8; 32]>, branch: u8, versions: &[u32]) -> [u8; 32] {
+            let mut prev = parent;
+            for version in versions {
+                let height = prev.map(|p| self.entries[&p].0.height + 1).unwrap_or(0);
+                let mut hash = [branch; 32];
+                hash[..8].copy_from_slice(&height.to_le_bytes());
+                let entry = ChainEntry {
+                    hash,
+                    prev_hash: prev.unwrap_or([0; 32]),
+                    height,
+                    version: *version,
+                };
+                self.entries.insert(hash, (entry, height * 10 + START));
+                prev = Some(hash);
+            }
+            prev.unwrap()
+        }
+    }
+
+    impl VersionBitsChain for TestChain {
+        fn entry(&self, hash: &[u8; 32]) -> Option<ChainEntry> {
+            self.entries.get(hash).map(|(e, _)| *e)
+        }
+
+        fn median_time_past(&self, hash: &[u8; 32]) -> Option<u64> {
+            // Timestamps increase monotonically, so the median of the last
+            // eleven is the sixth newest
+            let (entry, _) = self.entries.get(hash)?;
+            let mut cursor = *entry;
+            for _ in 0..5 {
+                if cursor.height == 0 {
+                    break;
+                }
+                cursor = self.entries.get(&cursor.prev_hash)?.0;
+            }
+            self.entries.get(&cursor.hash).map(|(_, t)| *t)
+        }
+    }
+
+    fn params(timeout: u64) -> VersionBitsParams {
+        VersionBitsParams {
+            window: WINDOW,
+            deployments: vec![Deployment {
+                name: "test".to_string(),
+                bit: 3,
+                start_time: START,
+                timeout,
+                threshold_percent: 75,
+                min_activation_height: 0,
+            }],
+        }
+    }
+
+    const SIGNAL: u32 = VERSIONBITS_TOP_BITS | (1 << 3);
+    const QUIET: u32 = VERSIONBITS_TOP_BITS;
+
+    fn state(
+        cache: &mut VersionBitsCache,
+        params: &VersionBitsParams,
+        chain: &TestChain,
+        tip: [u8; 32],
+    ) -> ThresholdState {
+        cache
+            .state_by_name(params, "test", chain, Some(&tip))
+            .unwrap()
+    }
+
+    #[test]
+    fn signaling_above_threshold_locks_in_then_activates() {
+        let params = params(TIMEOUT);
+        let mut chain = TestChain::default();
+        let mut cache = VersionBitsCache::new();
+
+        // Window 0 (heights 0-9): before the first boundary everything is Defined
+        let tip = chain.extend(None, 1, &[QUIET; 9]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::Defined
+        );
+
+        // Window 1 opens signaling
+        let tip = chain.extend(Some(tip), 1, &[QUIET]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::Started
+        );
+
+        // 8 of 10 blocks signal (threshold is 8)
+        let mut versions = vec![SIGNAL; 8];
+        versions.extend([QUIET; 2]);
+        let tip = chain.extend(Some(tip), 1, &versions);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::LockedIn
+        );
+
+        // Mid-window the state doesn't change
+        let mid = chain.extend(Some(tip), 1, &[QUIET; 5]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, mid),
+            ThresholdState::LockedIn
+        );
+
+        let tip = chain.extend(Some(mid), 1, &[QUIET; 5]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::Active
+        );
+
+        // Active is final
+        let tip = chain.extend(Some(tip), 1, &[QUIET; 30]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::Active
+        );
+
+        // A fresh cache agrees with the incrementally built one
+        let mut fresh = VersionBitsCache::new();
+        assert_eq!(
+            state(&mut fresh, &params, &chain, tip),
+            ThresholdState::Active
+        );
+    }
+
+    #[test]
+    fn signaling_below_threshold_times_out_as_failed() {
+        // Times out during window 3
+        let params = params(START + 35 * 10);
+        let mut chain = TestChain::default();
+        let mut cache = VersionBitsCache::new();
+
+        let mut versions = vec![QUIET; 10];
+        for _ in 0..5 {
+            // 7 of 10: one short of the threshold
+            versions.extend([SIGNAL; 7]);
+            versions.extend([QUIET; 3]);
+        }
+        let tip = chain.extend(None, 1, &versions[..20]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::Started
+        );
+
+        let tip = chain.extend(Some(tip), 1, &versions[20..]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip),
+            ThresholdState::Failed
+        );
+
+        let stats = signaling_stats(&params, &params.deployments[0], &chain, Some(&tip)).unwrap();
+        assert_eq!(stats.period, WINDOW);
+        assert_eq!(stats.threshold, 8);
+        assert_eq!(stats.elapsed, 0);
+    }
+
+    #[test]
+    fn reorg_across_window_boundary_recomputes_state() {
+        let params = params(TIMEOUT);
+        let mut chain = TestChain::default();
+        let mut cache = VersionBitsCache::new();
+
+        // Common history through window 1 (Started), then window 2 splits:
+        // branch 1 signals enough, branch 2 doesn't
+        let fork = chain.extend(None, 1, &[QUIET; 15]);
+        let mut strong = vec![SIGNAL; 8];
+        strong.extend([QUIET; 2]);
+        let tip_a = chain.extend(Some(fork), 1, &[SIGNAL; 5]);
+        let tip_a = chain.extend(Some(tip_a), 1, &strong);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip_a),
+            ThresholdState::LockedIn
+        );
+
+        let tip_b = chain.extend(Some(fork), 2, &[QUIET; 15]);
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip_b),
+            ThresholdState::Started
+        );
+
+        // Both branches stay cached independently
+        assert_eq!(
+            state(&mut cache, &params, &chain, tip_a),
+            ThresholdState::LockedIn
+        );
+
+        // Signaling stats follow the branch too
+        let mid_a = chain.extend(Some(tip_a), 1, &[SIGNAL; 4]);
+        let stats = signaling_stats(&params, &params.deployments[0], &chain, Some(&mid_a)).unwrap();
+        assert_eq!((stats.elapsed, stats.count), (4, 4));
+    }
+
+    #[test]
+    fn miners_signal_only_while_started_or_locked_in() {
+        let params = params(TIMEOUT);
+        let mut chain = TestChain::default();
+        let mut cache = VersionBitsCache::new();
+        let wanted = vec!["test".to_string()];
+
+        let tip = chain.extend(None, 1, &[QUIET; 5]);
+        let version =
+            compute_block_version(&mut cache, &params, &chain, Some(&tip), &wanted).unwrap();
+        assert_eq!(version, VERSIONBITS_TOP_BITS);
+
+        let tip = chain.extend(Some(tip), 1, &[QUIET; 5]);
+        let version =
+            compute_block_version(&mut cache, &params, &chain, Some(&tip), &wanted).unwrap();
+        assert!(params.deployments[0].is_signaled_by(version));
+
+        let version = compute_block_version(&mut cache, &params, &chain, Some(&tip), &[]).unwrap();
+        assert_eq!(version, VERSIONBITS_TOP_BITS);
+
+        // Legacy version-1 headers never count as signaling
+        assert!(!params.deployments[0].is_signaled_by(1 | (1 << 3)));
+    }
+}
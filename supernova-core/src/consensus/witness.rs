@@ -0,0 +1,299 @@
+//! Witness-separated transaction ids
+//!
+//! A transaction's txid ([`Transaction::txid`]) leaves out the data that
+//! authorizes it: the extended signature data and every input's witness
+//! stack. Re-signing a transaction or re-encoding its signature keeps the
+//! txid, so outpoints spending the transaction stay valid. The wtxid
+//! ([`Transaction::wtxid`]) covers the whole transaction.
+//!
+//! From the network's activation height ([`witness_txid_height`]) block
+//! merkle roots commit to txids, and a block carrying witness data commits to
+//! its wtxids in a zero-value coinbase output:
+//!
+//! ```text
+//! OP_RETURN <0xaa21a9ed || witness root>
+//! ```
+//!
+//! The witness root is the merkle root of the block's wtxids, with the
+//! coinbase's replaced by zeros since the coinbase cannot commit to itself.
+//! Below the activation height merkle roots commit to
+//! [`Transaction::legacy_txid`] and no commitment is checked.
+
+use crate::types::transaction::{Transaction, TransactionOutput};
+use crate::util::merkle::MerkleTree;
+use thiserror::Error;
+
+/// Tag opening a witness commitment, the one BIP141 uses
+pub const WITNESS_COMMITMENT_TAG: [u8; 4] = [0xaa, 0x21, 0xa9, 0xed];
+
+/// Activation height of witness-separated txids on the built-in networks
+pub const DEFAULT_WITNESS_TXID_HEIGHT: u64 = 0;
+
+const OP_RETURN: u8 = 0x6a;
+
+/// Push of the tag and the 32-byte witness root
+const COMMITMENT_PUSH: u8 = 36;
+
+/// Reasons a block's witness commitment is rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum WitnessCommitmentError {
+    #[error("Block carries witness data but its coinbase has no witness commitment")]
+    Missing,
+
+    #[error("Coinbase commits to witness root {found}, the block's is {expected}")]
+    Mismatch { expected: String, found: String },
+
+    #[error("Malformed witness commitment: {0}")]
+    Malformed(String),
+}
+
+/// Height from which the active network commits to witness-separated txids;
+/// `None` when it never does
+pub fn witness_txid_height() -> Option<u64> {
+    match crate::netparams::active() {
+        Some(params) => params.witness_txid_height,
+        None => Some(DEFAULT_WITNESS_TXID_HEIGHT),
+    }
+}
+
+/// Whether a block at `height` commits to txids and wtxids
+pub fn witness_txids_active(height: u64) -> bool {
+    witness_txid_height().is_some_and(|activation| height >= activation)
+}
+
+/// Merkle leaf of `transaction` in a block at `height`
+pub fn merkle_leaf(transaction: &Transaction, height: u64) -> [u8; 32] {
+    if witness_txids_active(height) {
+        transaction.txid()
+    } else {
+        transaction.legacy_txid()
+    }
+}
+
+/// Merkle leaves of `transactions` in a block at `height`
+pub fn merkle_leaves(transactions: &[Transaction], height: u64) -> Vec<[u8; 32]> {
+    transactions
+        .iter()
+        .map(|tx| merkle_leaf(tx, height))
+        .collect()
+}
+
+/// Header merkle root of `transactions` in a block at `height`
+pub fn merkle_root(transactions: &[Transaction], height: u64) -> [u8; 32] {
+    if transactions.is_empty() {
+        return [0; 32];
+    }
+    MerkleTree::new(&merkle_leaves(transactions, height)).root_hash()
+}
+
+/// Merkle root of the wtxids of `transactions`, the coinbase's as zeros
+pub fn witness_root(transactions: &[Transaction]) -> [u8; 32] {
+    if transactions.is_empty() {
+        return [0; 32];
+    }
+    let wtxids: Vec<[u8; 32]> = std::iter::once([0; 32])
+        .chain(transactions[1..].iter().map(Transaction::wtxid))
+        .collect();
+    MerkleTree::new(&wtxids).root_hash()
+}
+
+/// Commitment output script for `witness_root`
+pub fn commitment_script(witness_root: &[u8; 32]) -> Vec<u8> {
+    let mut script = vec![OP_RETURN, COMMITMENT_PUSH];
+    script.extend_from_slice(&WITNESS_COMMITMENT_TAG);
+    script.extend_from_slice(witness_root);
+    script
+}
+
+/// Whether any transaction but the coinbase carries data left out of its
+/// txid, which obliges the block to commit to its wtxids
+pub fn needs_commitment(transactions: &[Transaction]) -> bool {
+    transactions
+        .iter()
+        .skip(1)
+        .any(Transaction::has_witness_data)
+}
+
+/// Append the witness commitment to the coinbase of `transactions` when a
+/// block at `height` needs one. The coinbase must not carry one already.
+pub fn commit(transactions: &mut [Transaction], height: u64) {
+    if !witness_txids_active(height) || !needs_commitment(transactions) {
+        return;
+    }
+    let script = commitment_script(&witness_root(transactions));
+    transactions[0].add_output(TransactionOutput::new(0, script));
+}
+
+/// The witness root `coinbase` commits to, if any
+pub fn find_commitment(
+    coinbase: &Transaction,
+) -> Result<Option<[u8; 32]>, WitnessCommitmentError> {
+    let mut commitments = coinbase.outputs().iter().filter(|output| {
+        let script = &output.pub_key_script;
+        script.first() == Some(&OP_RETURN)
+            && script.get(2..2 + WITNESS_COMMITMENT_TAG.len()) == Some(&WITNESS_COMMITMENT_TAG[..])
+    });
+    let Some(commitment) = commitments.next() else {
+        return Ok(None);
+    };
+    if commitments.next().is_some() {
+        return Err(WitnessCommitmentError::Malformed(
+            "more than one commitment".to_string(),
+        ));
+    }
+
+    let script = &commitment.pub_key_script;
+    if script.len() != 2 + usize::from(COMMITMENT_PUSH) || script[1] != COMMITMENT_PUSH {
+        return Err(WitnessCommitmentError::Malformed(format!(
+            "commitment script is {} bytes",
+            script.len()
+        )));
+    }
+    if commitment.value() != 0 {
+        return Err(WitnessCommitmentError::Malformed(format!(
+            "commitment carries {} units",
+            commitment.value()
+        )));
+    }
+
+    let mut root = [0u8; 32];
+    root.copy_from_slice(&script[2 + WITNESS_COMMITMENT_TAG.len()..]);
+    Ok(Some(root))
+}
+
+/// Check the witness commitment of a block at `height` holding
+/// `transactions`. A commitment, when present, must match; it may only be
+/// left out when no transaction carries witness data.
+pub fn check_commitment(
+    transactions: &[Transaction],
+    height: u64,
+) -> Result<(), WitnessCommitmentError> {
+    if !witness_txids_active(height) {
+        return Ok(());
+    }
+    let Some(coinbase) = transactions.first() else {
+        return Ok(());
+    };
+    match find_commitment(coinbase)? {
+        Some(found) => {
+            let expected = witness_root(transactions);
+            if found != expected {
+                return Err(WitnessCommitmentError::Mismatch {
+                    expected: hex::encode(expected),
+                    found: hex::encode(found),
+                });
+            }
+            Ok(())
+        }
+        None if needs_commitment(transactions) => Err(WitnessCommitmentError::Missing),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use crate::types::transaction::{
+        SignatureSchemeType, TransactionInput, TransactionSignatureData,
+    };
+
+    fn spend(keypair: &QuantumKeyPair) -> Transaction {
+        let mut tx = Transaction::new(
+            2,
+            vec![TransactionInput::new([1; 32], 0, vec![], 0xffff_ffff)],
+            vec![TransactionOutput::new(1_000, vec![0xab; 32])],
+            0,
+        );
+        tx.set_signature_data(TransactionSignatureData {
+            scheme: SignatureSchemeType::Dilithium,
+            security_level: keypair.parameters.security_level,
+            data: keypair.sign(&tx.signature_hash()).unwrap(),
+            public_key: keypair.public_key.clone(),
+        });
+        tx
+    }
+
+    fn coinbase() -> Transaction {
+        Transaction::new(
+            2,
+            vec![TransactionInput::new_coinbase(vec![0x03, 1, 0, 0])],
+            vec![TransactionOutput::new(50, vec![0x51])],
+            0,
+        )
+    }
+
+    fn keypair() -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap()
+    }
+
+    #[test]
+    fn resigning_keeps_the_txid_but_not_the_wtxid() {
+        let signed = spend(&keypair());
+        let resigned = spend(&keypair());
+        assert_eq!(signed.txid(), resigned.txid());
+        assert_ne!(signed.wtxid(), resigned.wtxid());
+        assert_eq!(signed.hash(), signed.txid());
+
+        // A witness stack is malleable too
+        let mut witnessed = signed.clone();
+        witnessed.inputs_mut()[0].set_witness(vec![vec![0x01; 64]]);
+        assert_eq!(signed.txid(), witnessed.txid());
+        assert_ne!(signed.wtxid(), witnessed.wtxid());
+
+        // Anything a signature commits to still changes the txid
+        let other = Transaction::new(
+            2,
+            vec![TransactionInput::new([2; 32], 0, vec![], 0xffff_ffff)],
+            signed.outputs().to_vec(),
+            0,
+        );
+        assert_ne!(signed.txid(), other.txid());
+
+        // v1 transactions used to commit to their signature data
+        let mut v1 = Transaction::new(1, other.inputs().to_vec(), other.outputs().to_vec(), 0);
+        let unsigned = v1.legacy_txid();
+        v1.set_signature_data(signed.signature_data().unwrap().clone());
+        assert_ne!(v1.legacy_txid(), unsigned);
+        assert_eq!(v1.txid(), unsigned);
+    }
+
+    #[test]
+    fn blocks_with_witness_data_commit_to_their_wtxids() {
+        let mut transactions = vec![coinbase(), spend(&keypair())];
+        assert_eq!(
+            check_commitment(&transactions, 1),
+            Err(WitnessCommitmentError::Missing)
+        );
+
+        commit(&mut transactions, 1);
+        assert_eq!(
+            find_commitment(&transactions[0]).unwrap(),
+            Some(witness_root(&transactions))
+        );
+        check_commitment(&transactions, 1).unwrap();
+
+        // Swapping in a re-signed transaction keeps the merkle root but not
+        // the witness root
+        let root = merkle_root(&transactions, 1);
+        transactions[1] = spend(&keypair());
+        assert_eq!(merkle_root(&transactions, 1), root);
+        assert!(matches!(
+            check_commitment(&transactions, 1),
+            Err(WitnessCommitmentError::Mismatch { .. })
+        ));
+
+        // Blocks without witness data need none, but a wrong one still fails
+        let mut plain = vec![coinbase()];
+        check_commitment(&plain, 1).unwrap();
+        plain[0].add_output(TransactionOutput::new(0, commitment_script(&[7; 32])));
+        assert!(check_commitment(&plain, 1).is_err());
+
+        let mut paid = coinbase();
+        paid.add_output(TransactionOutput::new(1, commitment_script(&[0; 32])));
+        assert!(matches!(
+            find_commitment(&paid),
+            Err(WitnessCommitmentError::Malformed(_))
+        ));
+    }
+}
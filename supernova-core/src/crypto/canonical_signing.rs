@@ -0,0 +1,288 @@
+//! Signatures over canonical JSON payloads
+//!
+//! Signs the canonical encoding of a payload (see
+//! [`crate::util::canonical_json`]) rather than whatever bytes a particular
+//! serializer produced, so a signature survives being parsed and re-emitted
+//! by any JSON implementation. The signed message is
+//!
+//! ```text
+//! SHA-256("supernova/canonical-json" || version || canonical_json(payload))
+//! ```
+//!
+//! where `version` is the envelope version carried in the signature. Changing
+//! the canonicalization or digest rules means a new version; verifiers reject
+//! versions they do not know.
+
+use ed25519_dalek::Signer;
+use secp256k1::{Message, Secp256k1};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::crypto::quantum::{verify_quantum_signature, QuantumKeyPair, QuantumParameters};
+use crate::crypto::signature::{Ed25519Scheme, Secp256k1Scheme, SignatureError, SignatureScheme};
+use crate::util::canonical_json::{to_canonical_vec, CanonicalJsonError};
+
+/// Current signed-envelope version
+pub const CANONICAL_SIGNATURE_VERSION: u8 = 1;
+
+/// Domain separator so these signatures cannot be replayed as any other
+/// kind of signed message
+const DOMAIN_TAG: &[u8] = b"supernova/canonical-json";
+
+#[derive(Debug, Error)]
+pub enum CanonicalSignatureError {
+    #[error("Canonicalization failed: {0}")]
+    Canonical(#[from] CanonicalJsonError),
+    #[error("Signature error: {0}")]
+    Signature(#[from] SignatureError),
+    #[error("Unsupported signature version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Signature was made with a {signed:?} key, not {expected:?}")]
+    KeyTypeMismatch {
+        signed: CanonicalKeyType,
+        expected: CanonicalKeyType,
+    },
+    #[error("Invalid hex encoding: {0}")]
+    Hex(#[from] hex::FromHexError),
+}
+
+/// Key algorithm of a canonical signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanonicalKeyType {
+    Secp256k1,
+    Ed25519,
+    Quantum(QuantumParameters),
+}
+
+/// Private key able to sign canonical payloads
+pub enum CanonicalSigningKey {
+    Secp256k1(secp256k1::SecretKey),
+    Ed25519(ed25519_dalek::SigningKey),
+    Quantum(QuantumKeyPair),
+}
+
+impl CanonicalSigningKey {
+    pub fn key_type(&self) -> CanonicalKeyType {
+        match self {
+            CanonicalSigningKey::Secp256k1(_) => CanonicalKeyType::Secp256k1,
+            CanonicalSigningKey::Ed25519(_) => CanonicalKeyType::Ed25519,
+            CanonicalSigningKey::Quantum(kp) => CanonicalKeyType::Quantum(kp.parameters),
+        }
+    }
+
+    pub fn public_key(&self) -> CanonicalPublicKey {
+        let bytes = match self {
+            CanonicalSigningKey::Secp256k1(sk) => {
+                sk.public_key(&Secp256k1::signing_only()).serialize().to_vec()
+            }
+            CanonicalSigningKey::Ed25519(sk) => sk.verifying_key().to_bytes().to_vec(),
+            CanonicalSigningKey::Quantum(kp) => kp.public_key.clone(),
+        };
+        CanonicalPublicKey {
+            key_type: self.key_type(),
+            key: hex::encode(bytes),
+        }
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> Result<Vec<u8>, SignatureError> {
+        match self {
+            CanonicalSigningKey::Secp256k1(sk) => {
+                let message = Message::from_slice(digest)
+                    .map_err(|e| SignatureError::InvalidSignature(e.to_string()))?;
+                Ok(Secp256k1::signing_only()
+                    .sign_ecdsa(&message, sk)
+                    .serialize_compact()
+                    .to_vec())
+            }
+            CanonicalSigningKey::Ed25519(sk) => Ok(sk.sign(digest).to_bytes().to_vec()),
+            CanonicalSigningKey::Quantum(kp) => Ok(kp.sign(digest)?),
+        }
+    }
+}
+
+/// Public key, hex encoded, as published to verifiers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonicalPublicKey {
+    pub key_type: CanonicalKeyType,
+    pub key: String,
+}
+
+/// Detached signature over a canonical payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanonicalSignature {
+    pub version: u8,
+    pub key_type: CanonicalKeyType,
+    pub signature: String,
+}
+
+/// A payload bundled with its signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedEnvelope<T> {
+    pub payload: T,
+    pub signature: CanonicalSignature,
+}
+
+impl<T: Serialize> SignedEnvelope<T> {
+    pub fn seal(payload: T, key: &CanonicalSigningKey) -> Result<Self, CanonicalSignatureError> {
+        let signature = sign_canonical(&payload, key)?;
+        Ok(Self { payload, signature })
+    }
+
+    /// The payload, if the signature verifies under `public_key`
+    pub fn open(&self, public_key: &CanonicalPublicKey) -> Result<Option<&T>, CanonicalSignatureError> {
+        Ok(verify_canonical(&self.payload, &self.signature, public_key)?.then_some(&self.payload))
+    }
+}
+
+/// Digest signed for `payload` under envelope `version`
+pub fn canonical_digest<T: Serialize + ?Sized>(
+    payload: &T,
+    version: u8,
+) -> Result<[u8; 32], CanonicalSignatureError> {
+    if version != CANONICAL_SIGNATURE_VERSION {
+        return Err(CanonicalSignatureError::UnsupportedVersion(version));
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN_TAG);
+    hasher.update([version]);
+    hasher.update(to_canonical_vec(payload)?);
+    Ok(hasher.finalize().into())
+}
+
+/// Sign the canonical encoding of `payload`
+pub fn sign_canonical<T: Serialize + ?Sized>(
+    payload: &T,
+    key: &CanonicalSigningKey,
+) -> Result<CanonicalSignature, CanonicalSignatureError> {
+    let digest = canonical_digest(payload, CANONICAL_SIGNATURE_VERSION)?;
+    Ok(CanonicalSignature {
+        version: CANONICAL_SIGNATURE_VERSION,
+        key_type: key.key_type(),
+        signature: hex::encode(key.sign_digest(&digest)?),
+    })
+}
+
+/// Verify `signature` over the canonical encoding of `payload`. Returns
+/// `Ok(false)` for a well-formed signature that does not match.
+pub fn verify_canonical<T: Serialize + ?Sized>(
+    payload: &T,
+    signature: &CanonicalSignature,
+    public_key: &CanonicalPublicKey,
+) -> Result<bool, CanonicalSignatureError> {
+    if signature.key_type != public_key.key_type {
+        return Err(CanonicalSignatureError::KeyTypeMismatch {
+            signed: signature.key_type,
+            expected: public_key.key_type,
+        });
+    }
+    let digest = canonical_digest(payload, signature.version)?;
+    let key = hex::decode(&public_key.key)?;
+    let sig = hex::decode(&signature.signature)?;
+
+    let verified = match public_key.key_type {
+        CanonicalKeyType::Secp256k1 => Secp256k1Scheme.verify(&key, &digest, &sig),
+        CanonicalKeyType::Ed25519 => Ed25519Scheme.verify(&key, &digest, &sig),
+        CanonicalKeyType::Quantum(parameters) => {
+            verify_quantum_signature(&key, &digest, &sig, parameters).map_err(SignatureError::from)
+        }
+    };
+    match verified {
+        Ok(valid) => Ok(valid),
+        // A mismatching but well-formed signature is a failed check, not an error
+        Err(SignatureError::VerificationFailed(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::quantum::QuantumScheme;
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+    use serde_json::{json, Value};
+
+    fn keys() -> Vec<CanonicalSigningKey> {
+        let mut ed25519_seed = [0u8; 32];
+        OsRng.fill_bytes(&mut ed25519_seed);
+        vec![
+            CanonicalSigningKey::Secp256k1(secp256k1::SecretKey::new(&mut OsRng)),
+            CanonicalSigningKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&ed25519_seed)),
+            CanonicalSigningKey::Quantum(
+                QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap(),
+            ),
+        ]
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Report {
+        node_id: String,
+        height: u64,
+        hashrate: f64,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn signature_survives_non_canonical_round_trip() {
+        let report = Report {
+            node_id: "nodé-1 \u{1f680}".to_string(),
+            height: 812_345,
+            hashrate: 1.5e-7,
+            tags: vec!["mainnet".into(), "quantum".into()],
+        };
+
+        for key in keys() {
+            let public_key = key.public_key();
+            let envelope = SignedEnvelope::seal(report.clone(), &key).unwrap();
+            assert_eq!(envelope.signature.version, CANONICAL_SIGNATURE_VERSION);
+
+            // Re-emit through a serializer with different layout and field
+            // order, then parse as an untyped value
+            let mut value = serde_json::to_value(&envelope).unwrap();
+            let payload = value["payload"].take();
+            let reordered = format!(
+                "{{\n  \"tags\" : {},\n  \"hashrate\" : 0.00000015, \"height\":812345.0,\"node_id\":{}\n}}",
+                payload["tags"], payload["node_id"]
+            );
+            let reparsed: Value = serde_json::from_str(&reordered).unwrap();
+            let signature: CanonicalSignature =
+                serde_json::from_value(value["signature"].take()).unwrap();
+            assert!(verify_canonical(&reparsed, &signature, &public_key).unwrap());
+
+            // The typed envelope opens too
+            let wire = serde_json::to_string_pretty(&envelope).unwrap();
+            let decoded: SignedEnvelope<Report> = serde_json::from_str(&wire).unwrap();
+            assert_eq!(decoded.open(&public_key).unwrap(), Some(&report));
+
+            // Any change to the payload breaks the signature
+            let mut tampered = reparsed.clone();
+            tampered["height"] = json!(812_346);
+            assert!(!verify_canonical(&tampered, &signature, &public_key).unwrap());
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_key_type_and_unknown_version() {
+        let keys = keys();
+        let payload = json!({"event": "block", "height": 1});
+        let mut signature = sign_canonical(&payload, &keys[0]).unwrap();
+
+        assert!(matches!(
+            verify_canonical(&payload, &signature, &keys[1].public_key()),
+            Err(CanonicalSignatureError::KeyTypeMismatch { .. })
+        ));
+
+        signature.version = CANONICAL_SIGNATURE_VERSION + 1;
+        assert!(matches!(
+            verify_canonical(&payload, &signature, &keys[0].public_key()),
+            Err(CanonicalSignatureError::UnsupportedVersion(_))
+        ));
+
+        // Another key of the same type does not verify
+        let other = CanonicalSigningKey::Secp256k1(secp256k1::SecretKey::new(&mut OsRng));
+        let signature = sign_canonical(&payload, &keys[0]).unwrap();
+        assert!(!verify_canonical(&payload, &signature, &other.public_key()).unwrap());
+    }
+}
@@ -0,0 +1,326 @@
+//! Hybrid classical + ML-DSA signatures
+//!
+//! A hybrid key pairs a secp256k1 or Ed25519 key with an ML-DSA (Dilithium)
+//! key, and a hybrid signature carries one signature of each over the same
+//! message. Keys and signatures share the layout [`QuantumKeyPair`] uses for
+//! its hybrid scheme:
+//!
+//! ```text
+//! [classical length: u16 BE][classical part][ML-DSA part]
+//! ```
+//!
+//! The classical scheme is read from the classical key's length: 33 bytes for
+//! a compressed secp256k1 key, 32 for Ed25519. secp256k1 signatures are DER
+//! encoded over SHA-256 of the message; Ed25519 signs the message itself.
+//!
+//! Transactions carry hybrid witnesses as
+//! [`SignatureSchemeType::Hybrid`](crate::types::transaction::SignatureSchemeType)
+//! signature data. Consensus requires both halves to verify; during a
+//! migration period a network may accept either, see [`HybridPolicy`].
+
+use crate::crypto::quantum::{
+    verify_quantum_signature, ClassicalScheme, QuantumError, QuantumKeyPair, QuantumParameters,
+    QuantumScheme,
+};
+use crate::validation::SecurityLevel;
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey};
+use pqcrypto_dilithium::{dilithium2, dilithium3, dilithium5};
+use secp256k1::{ecdsa, Message, PublicKey, Secp256k1};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Length prefix of the classical part
+const LEN_PREFIX: usize = 2;
+
+/// Longest classical key or signature accepted in a hybrid encoding
+const MAX_CLASSICAL_LEN: usize = 128;
+
+const SECP256K1_KEY_LEN: usize = 33;
+const ED25519_KEY_LEN: usize = 32;
+
+/// Which halves of a hybrid signature must verify
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HybridPolicy {
+    /// Both the classical and the ML-DSA signature
+    #[default]
+    RequireBoth,
+    /// Either one; for the migration period only
+    EitherPasses,
+}
+
+/// A hybrid public key split into its halves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridPublicKey {
+    pub classical_scheme: ClassicalScheme,
+    pub classical: Vec<u8>,
+    pub quantum: Vec<u8>,
+}
+
+impl HybridPublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, QuantumError> {
+        let (classical, quantum) =
+            split(bytes).map_err(|e| QuantumError::InvalidKey(format!("hybrid key: {}", e)))?;
+        let classical_scheme = match classical.len() {
+            SECP256K1_KEY_LEN => ClassicalScheme::Secp256k1,
+            ED25519_KEY_LEN => ClassicalScheme::Ed25519,
+            len => {
+                return Err(QuantumError::InvalidKey(format!(
+                    "hybrid key: no classical scheme has {}-byte keys",
+                    len
+                )))
+            }
+        };
+        Ok(Self {
+            classical_scheme,
+            classical: classical.to_vec(),
+            quantum: quantum.to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        join(&self.classical, &self.quantum)
+    }
+}
+
+/// A hybrid signature split into its halves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HybridSignature {
+    pub classical: Vec<u8>,
+    pub quantum: Vec<u8>,
+}
+
+impl HybridSignature {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, QuantumError> {
+        let (classical, quantum) = split(bytes)
+            .map_err(|e| QuantumError::InvalidSignature(format!("hybrid signature: {}", e)))?;
+        Ok(Self {
+            classical: classical.to_vec(),
+            quantum: quantum.to_vec(),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        join(&self.classical, &self.quantum)
+    }
+
+    /// Length of [`Self::to_bytes`]
+    pub fn encoded_len(&self) -> usize {
+        LEN_PREFIX + self.classical.len() + self.quantum.len()
+    }
+}
+
+/// Sign `message` with both halves of a hybrid key pair
+pub fn sign_hybrid(
+    keypair: &QuantumKeyPair,
+    message: &[u8],
+) -> Result<HybridSignature, QuantumError> {
+    if !matches!(keypair.parameters.scheme, QuantumScheme::Hybrid(_)) {
+        return Err(QuantumError::UnsupportedScheme(format!(
+            "{:?} is not a hybrid scheme",
+            keypair.parameters.scheme
+        )));
+    }
+    HybridSignature::from_bytes(&keypair.sign(message)?)
+}
+
+/// Verify a hybrid `signature` over `message` under `policy`. Malformed
+/// encodings are errors; a half that does not verify is a failed half.
+pub fn verify_hybrid(
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+    security_level: u8,
+    policy: HybridPolicy,
+) -> Result<bool, QuantumError> {
+    let key = HybridPublicKey::from_bytes(public_key)?;
+    let signature = HybridSignature::from_bytes(signature)?;
+
+    let classical_ok = verify_classical(
+        key.classical_scheme,
+        &key.classical,
+        message,
+        &signature.classical,
+    );
+    let quantum_ok = verify_quantum_signature(
+        &key.quantum,
+        message,
+        &signature.quantum,
+        QuantumParameters::with_security_level(QuantumScheme::Dilithium, security_level),
+    )
+    .unwrap_or(false);
+
+    Ok(match policy {
+        HybridPolicy::RequireBoth => classical_ok && quantum_ok,
+        HybridPolicy::EitherPasses => classical_ok || quantum_ok,
+    })
+}
+
+/// Upper bound on the encoded length of a hybrid signature; secp256k1 DER
+/// signatures vary by a few bytes
+pub fn hybrid_signature_len(
+    classical: ClassicalScheme,
+    security_level: u8,
+) -> Result<usize, QuantumError> {
+    QuantumParameters::with_security_level(QuantumScheme::Hybrid(classical), security_level)
+        .expected_signature_length()
+}
+
+/// Encoded length of a hybrid public key
+pub fn hybrid_public_key_len(
+    classical: ClassicalScheme,
+    security_level: u8,
+) -> Result<usize, QuantumError> {
+    let classical_len = match classical {
+        ClassicalScheme::Secp256k1 => SECP256K1_KEY_LEN,
+        ClassicalScheme::Ed25519 => ED25519_KEY_LEN,
+    };
+    let quantum_len = match SecurityLevel::from(security_level) {
+        SecurityLevel::Low => dilithium2::public_key_bytes(),
+        SecurityLevel::Medium => dilithium3::public_key_bytes(),
+        SecurityLevel::High => dilithium5::public_key_bytes(),
+        _ => return Err(QuantumError::UnsupportedSecurityLevel(security_level)),
+    };
+    Ok(LEN_PREFIX + classical_len + quantum_len)
+}
+
+fn verify_classical(
+    scheme: ClassicalScheme,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    match scheme {
+        ClassicalScheme::Secp256k1 => {
+            let digest: [u8; 32] = Sha256::digest(message).into();
+            let (Ok(message), Ok(key), Ok(signature)) = (
+                Message::from_slice(&digest),
+                PublicKey::from_slice(public_key),
+                ecdsa::Signature::from_der(signature),
+            ) else {
+                return false;
+            };
+            Secp256k1::verification_only()
+                .verify_ecdsa(&message, &signature, &key)
+                .is_ok()
+        }
+        ClassicalScheme::Ed25519 => {
+            let (Ok(key), Ok(signature)) = (
+                <[u8; 32]>::try_from(public_key),
+                <[u8; 64]>::try_from(signature),
+            ) else {
+                return false;
+            };
+            VerifyingKey::from_bytes(&key)
+                .map(|key| {
+                    key.verify_strict(message, &Ed25519Signature::from_bytes(&signature))
+                        .is_ok()
+                })
+                .unwrap_or(false)
+        }
+    }
+}
+
+fn split(bytes: &[u8]) -> Result<(&[u8], &[u8]), String> {
+    if bytes.len() < LEN_PREFIX {
+        return Err("missing length prefix".to_string());
+    }
+    let classical_len = usize::from(u16::from_be_bytes([bytes[0], bytes[1]]));
+    if classical_len == 0 || classical_len > MAX_CLASSICAL_LEN {
+        return Err(format!("classical part of {} bytes", classical_len));
+    }
+    let rest = &bytes[LEN_PREFIX..];
+    if rest.len() <= classical_len {
+        return Err(format!(
+            "{} bytes leave no ML-DSA part after a {}-byte classical part",
+            rest.len(),
+            classical_len
+        ));
+    }
+    Ok(rest.split_at(classical_len))
+}
+
+fn join(classical: &[u8], quantum: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(LEN_PREFIX + classical.len() + quantum.len());
+    bytes.extend_from_slice(&(classical.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(classical);
+    bytes.extend_from_slice(quantum);
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair(classical: ClassicalScheme) -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::with_security_level(
+            QuantumScheme::Hybrid(classical),
+            3,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn both_halves_must_verify_unless_either_is_allowed() {
+        for classical in [ClassicalScheme::Secp256k1, ClassicalScheme::Ed25519] {
+            let keypair = keypair(classical);
+            let message = b"hybrid witness";
+            let signature = sign_hybrid(&keypair, message).unwrap();
+            let verify = |signature: &HybridSignature, policy| {
+                verify_hybrid(
+                    &keypair.public_key,
+                    message,
+                    &signature.to_bytes(),
+                    3,
+                    policy,
+                )
+                .unwrap()
+            };
+            assert!(verify(&signature, HybridPolicy::RequireBoth));
+
+            let mut broken_classical = signature.clone();
+            let last = broken_classical.classical.len() - 1;
+            broken_classical.classical[last] ^= 1;
+            let mut broken_quantum = signature.clone();
+            broken_quantum.quantum[0] ^= 1;
+            for broken in [&broken_classical, &broken_quantum] {
+                assert!(!verify(broken, HybridPolicy::RequireBoth));
+                assert!(verify(broken, HybridPolicy::EitherPasses));
+            }
+        }
+    }
+
+    #[test]
+    fn a_classical_signature_alone_is_malformed() {
+        let keypair = keypair(ClassicalScheme::Secp256k1);
+        let signature = sign_hybrid(&keypair, b"msg").unwrap();
+        let classical_only = join(&signature.classical, &[]);
+        for policy in [HybridPolicy::RequireBoth, HybridPolicy::EitherPasses] {
+            assert!(
+                verify_hybrid(&keypair.public_key, b"msg", &classical_only, 3, policy).is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn lengths_match_the_encodings() {
+        for classical in [ClassicalScheme::Secp256k1, ClassicalScheme::Ed25519] {
+            let keypair = keypair(classical);
+            assert_eq!(
+                keypair.public_key.len(),
+                hybrid_public_key_len(classical, 3).unwrap()
+            );
+            let key = HybridPublicKey::from_bytes(&keypair.public_key).unwrap();
+            assert_eq!(key.classical_scheme, classical);
+            assert_eq!(key.to_bytes(), keypair.public_key);
+
+            let signature = sign_hybrid(&keypair, b"msg").unwrap();
+            let bound = hybrid_signature_len(classical, 3).unwrap();
+            assert_eq!(signature.to_bytes().len(), signature.encoded_len());
+            assert!(signature.encoded_len() <= bound);
+            if classical == ClassicalScheme::Ed25519 {
+                // Ed25519 signatures have a fixed length, so the bound is exact
+                assert_eq!(signature.encoded_len(), bound);
+            }
+        }
+    }
+}
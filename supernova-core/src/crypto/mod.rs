@@ -2,8 +2,10 @@
 // Contains cryptographic primitives and functions
 
 // Public modules
+pub mod canonical_signing;
 pub mod falcon_real; // Using Falcon implementation
 pub mod hash;
+pub mod hybrid;
 pub mod kem;
 pub mod key_rotation;
 pub mod quantum;
@@ -19,14 +21,18 @@ mod quantum_security_test;
 // Re-export public types
 // Note: Not exporting Hash to avoid conflicts with other Hash types
 pub use quantum::{
-    sign_quantum, verify_quantum_signature, QuantumError, QuantumKeyPair, QuantumParameters,
-    QuantumScheme,
+    sign_quantum, verify_quantum_signature, BatchVerification, QuantumBatchVerifier, QuantumError,
+    QuantumKeyPair, QuantumParameters, QuantumScheme,
 };
 pub use quantum::{ECDSASignature, FalconSignature, SPHINCSSignature};
 pub use quantum::{MLDSAPrivateKey, MLDSAPublicKey, MLDSASecurityLevel, MLDSASignature};
 pub use signature::{
     SignatureError, SignatureParams, SignatureScheme, SignatureType, SignatureVerifier,
 };
+pub use canonical_signing::{
+    sign_canonical, verify_canonical, CanonicalKeyType, CanonicalPublicKey, CanonicalSignature,
+    CanonicalSigningKey, SignedEnvelope, CANONICAL_SIGNATURE_VERSION,
+};
 pub use zkp::{Commitment, ZeroKnowledgeProof, ZkpParams, ZkpType};
 
 // Export REAL Falcon implementation
@@ -37,6 +43,11 @@ pub use falcon_real::{
 
 // Legacy falcon exports removed - use RealFalcon* types instead
 
+pub use hybrid::{
+    hybrid_public_key_len, hybrid_signature_len, sign_hybrid, verify_hybrid, HybridPolicy,
+    HybridPublicKey, HybridSignature,
+};
+
 pub use kem::{decapsulate, encapsulate, KemError, KemKeyPair};
 
 // Export key rotation types
@@ -9,7 +9,8 @@ use pqcrypto_traits::sign::{
 use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashSet;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use thiserror::Error;
 use zeroize::{Zeroize, ZeroizeOnDrop};
@@ -1772,6 +1773,187 @@ pub fn verify_quantum_signature(
     keypair.verify(message, signature)
 }
 
+/// Verifies many post-quantum signatures in one pass.
+///
+/// pqcrypto offers no true ML-DSA batch verification, so the batch amortizes
+/// what it can: each distinct ML-DSA public key is parsed once however many
+/// signatures it made, and the verifications are spread across the rayon
+/// pool. Other schemes are verified one by one with
+/// [`verify_quantum_signature`]. The result for each entry matches verifying
+/// it alone.
+#[derive(Debug, Default)]
+pub struct QuantumBatchVerifier {
+    entries: Vec<BatchEntry>,
+}
+
+#[derive(Debug)]
+struct BatchEntry {
+    message: Vec<u8>,
+    signature: Vec<u8>,
+    public_key: Vec<u8>,
+    parameters: QuantumParameters,
+}
+
+/// One bit per entry of a [`QuantumBatchVerifier`], set when it verified
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchVerification {
+    bitmap: Vec<u64>,
+    len: usize,
+}
+
+impl BatchVerification {
+    fn from_results(results: &[bool]) -> Self {
+        let mut bitmap = vec![0u64; results.len().div_ceil(64)];
+        for (index, _) in results.iter().enumerate().filter(|(_, valid)| **valid) {
+            bitmap[index / 64] |= 1u64 << (index % 64);
+        }
+        Self {
+            bitmap,
+            len: results.len(),
+        }
+    }
+
+    /// Whether entry `index` verified; false past the end
+    pub fn is_valid(&self, index: usize) -> bool {
+        index < self.len && self.bitmap[index / 64] & (1u64 << (index % 64)) != 0
+    }
+
+    pub fn all_valid(&self) -> bool {
+        self.invalid().next().is_none()
+    }
+
+    /// Indices of the entries that failed, in order
+    pub fn invalid(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len).filter(|&index| !self.is_valid(index))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// An ML-DSA public key parsed for reuse across a batch
+enum MlDsaPublicKey {
+    Level2(dilithium2::PublicKey),
+    Level3(dilithium3::PublicKey),
+    Level5(dilithium5::PublicKey),
+}
+
+impl MlDsaPublicKey {
+    fn parse(security_level: u8, bytes: &[u8]) -> Option<Self> {
+        match SecurityLevel::from(security_level) {
+            SecurityLevel::Low => dilithium2::PublicKey::from_bytes(bytes)
+                .ok()
+                .map(Self::Level2),
+            SecurityLevel::Medium => dilithium3::PublicKey::from_bytes(bytes)
+                .ok()
+                .map(Self::Level3),
+            SecurityLevel::High => dilithium5::PublicKey::from_bytes(bytes)
+                .ok()
+                .map(Self::Level5),
+            _ => None,
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            Self::Level2(pk) => dilithium2::DetachedSignature::from_bytes(signature)
+                .is_ok_and(|sig| dilithium2::verify_detached_signature(&sig, message, pk).is_ok()),
+            Self::Level3(pk) => dilithium3::DetachedSignature::from_bytes(signature)
+                .is_ok_and(|sig| dilithium3::verify_detached_signature(&sig, message, pk).is_ok()),
+            Self::Level5(pk) => dilithium5::DetachedSignature::from_bytes(signature)
+                .is_ok_and(|sig| dilithium5::verify_detached_signature(&sig, message, pk).is_ok()),
+        }
+    }
+}
+
+impl QuantumBatchVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Queue `signature` by `public_key` over `message`, returning its index
+    /// in the [`BatchVerification`]
+    pub fn push(
+        &mut self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+        parameters: QuantumParameters,
+    ) -> usize {
+        self.entries.push(BatchEntry {
+            message: message.to_vec(),
+            signature: signature.to_vec(),
+            public_key: public_key.to_vec(),
+            parameters,
+        });
+        self.entries.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verify every queued signature. A malformed key or signature is an
+    /// entry that failed, not an error.
+    pub fn verify(&self) -> BatchVerification {
+        // Parse each distinct ML-DSA key once
+        let mut key_slots: HashMap<(u8, &[u8]), usize> = HashMap::new();
+        let mut distinct_keys = Vec::new();
+        let slots: Vec<Option<usize>> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                if entry.parameters.scheme != QuantumScheme::Dilithium {
+                    return None;
+                }
+                let key = (entry.parameters.security_level, entry.public_key.as_slice());
+                Some(*key_slots.entry(key).or_insert_with(|| {
+                    distinct_keys.push(key);
+                    distinct_keys.len() - 1
+                }))
+            })
+            .collect();
+        let parsed: Vec<Option<MlDsaPublicKey>> = distinct_keys
+            .par_iter()
+            .map(|&(security_level, bytes)| MlDsaPublicKey::parse(security_level, bytes))
+            .collect();
+
+        let results: Vec<bool> = self
+            .entries
+            .par_iter()
+            .zip(slots.par_iter())
+            .map(|(entry, slot)| match slot {
+                Some(slot) => parsed[*slot]
+                    .as_ref()
+                    .is_some_and(|key| key.verify(&entry.message, &entry.signature)),
+                None => verify_quantum_signature(
+                    &entry.public_key,
+                    &entry.message,
+                    &entry.signature,
+                    entry.parameters,
+                )
+                .unwrap_or(false),
+            })
+            .collect();
+        BatchVerification::from_results(&results)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2743,4 +2925,76 @@ mod tests {
             "public key carries `#[zeroize(skip)]` and must be unchanged"
         );
     }
+
+    /// `count` signatures, made by `keypairs` in turn
+    fn batch_of(keypairs: &[QuantumKeyPair], count: usize) -> QuantumBatchVerifier {
+        let mut batch = QuantumBatchVerifier::with_capacity(count);
+        for i in 0..count {
+            let keypair = &keypairs[i % keypairs.len()];
+            let message = format!("message {}", i).into_bytes();
+            let signature = keypair.sign(&message).unwrap();
+            batch.push(
+                &message,
+                &signature,
+                &keypair.public_key,
+                keypair.parameters,
+            );
+        }
+        batch
+    }
+
+    #[test]
+    fn batch_verification_flags_exactly_the_corrupted_signature() {
+        let keypairs: Vec<QuantumKeyPair> = [1, 3, 5]
+            .into_iter()
+            .map(|level| {
+                QuantumKeyPair::generate(QuantumParameters::with_security_level(
+                    QuantumScheme::Dilithium,
+                    level,
+                ))
+                .unwrap()
+            })
+            .collect();
+        let batch = batch_of(&keypairs, 70);
+        let result = batch.verify();
+        assert_eq!(result.len(), 70);
+        assert!(result.all_valid());
+
+        for corrupt in [0, 41, 69] {
+            let mut batch = batch_of(&keypairs, 70);
+            batch.entries[corrupt].signature[7] ^= 0x01;
+            let result = batch.verify();
+            assert!(!result.all_valid());
+            assert_eq!(result.invalid().collect::<Vec<_>>(), vec![corrupt]);
+        }
+    }
+
+    #[test]
+    fn batch_verification_matches_individual_verification() {
+        let dilithium =
+            QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let hybrid = QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Hybrid(
+            ClassicalScheme::Ed25519,
+        )))
+        .unwrap();
+        let mut batch = batch_of(&[dilithium, hybrid], 6);
+        // A truncated key and a signature under the wrong parameters
+        batch.entries[2].public_key.truncate(10);
+        batch.entries[3].parameters.scheme = QuantumScheme::Dilithium;
+
+        let result = batch.verify();
+        for (index, entry) in batch.entries.iter().enumerate() {
+            let alone = verify_quantum_signature(
+                &entry.public_key,
+                &entry.message,
+                &entry.signature,
+                entry.parameters,
+            )
+            .unwrap_or(false);
+            assert_eq!(result.is_valid(index), alone, "entry {}", index);
+        }
+        assert_eq!(result.invalid().collect::<Vec<_>>(), vec![2, 3]);
+        assert!(!result.is_valid(6));
+        assert!(QuantumBatchVerifier::new().verify().all_valid());
+    }
 }
@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use crate::crypto::quantum::QuantumParameters;
 use crate::environmental::{
+    attestation::{GreenAttestation, GreenBonusClaim, GreenBonusError},
     dashboard::EnvironmentalDashboard,
     emissions::{EmissionsError, EmissionsTracker},
     miner_reporting::{MinerEnvironmentalInfo, MinerReportingManager, MinerVerificationStatus},
@@ -20,6 +23,9 @@ pub enum EnvironmentalApiError {
     #[error("Treasury error: {0}")]
     TreasuryError(#[from] TreasuryError),
 
+    #[error("Green bonus error: {0}")]
+    GreenBonusError(#[from] GreenBonusError),
+
     #[error("Miner not found: {0}")]
     MinerNotFound(String),
 
@@ -174,8 +180,8 @@ pub struct EnvironmentalApi {
     emissions_tracker: EmissionsTracker,
     /// Miner reporting manager
     miner_reporting: Option<MinerReportingManager>,
-    /// Treasury
-    treasury: EnvironmentalTreasury,
+    /// Treasury, shared with the block validator checking green bonuses
+    treasury: Arc<EnvironmentalTreasury>,
     /// Configuration
     config: EnvironmentalConfig,
     /// Transparency dashboard
@@ -199,7 +205,7 @@ impl EnvironmentalApi {
         Self {
             emissions_tracker: EmissionsTracker::default(),
             miner_reporting: None,
-            treasury: EnvironmentalTreasury::default(),
+            treasury: Arc::new(EnvironmentalTreasury::default()),
             config: EnvironmentalConfig::default(),
             transparency: None,
             miner_info: HashMap::new(),
@@ -438,6 +444,14 @@ impl EnvironmentalApi {
         // Call the treasury method with the total fees
         let allocation = self.treasury.process_block_allocation(total_fees);
 
+        // Pay out the green bonus the block claims; block validation has
+        // checked it against the attestation and the balance
+        if let Some(coinbase) = block.transactions().first() {
+            if let Some(claim) = GreenBonusClaim::from_coinbase(coinbase)? {
+                self.treasury.pay_green_bonus(&claim)?;
+            }
+        }
+
         // Return the allocation amount
         Ok(allocation)
     }
@@ -527,6 +541,32 @@ impl EnvironmentalApi {
         self.treasury.get_balance(Some(TreasuryAccountType::Main))
     }
 
+    /// The treasury, for a block validator to check green bonuses against
+    pub fn treasury(&self) -> Arc<EnvironmentalTreasury> {
+        Arc::clone(&self.treasury)
+    }
+
+    /// Register the key an attestor signs green attestations with
+    pub fn register_green_attestor(
+        &self,
+        attestor: &str,
+        public_key: Vec<u8>,
+        parameters: QuantumParameters,
+    ) -> EnvironmentalResult<()> {
+        Ok(self
+            .treasury
+            .register_attestor(attestor, public_key, parameters)?)
+    }
+
+    /// Register a signed green attestation; bonus blocks commit to the
+    /// returned id
+    pub fn register_green_attestation(
+        &self,
+        attestation: GreenAttestation,
+    ) -> EnvironmentalResult<[u8; 32]> {
+        Ok(self.treasury.register_attestation(attestation)?)
+    }
+
     /// Get regional emissions data
     pub fn get_regional_emissions(&self) -> EnvironmentalResult<HashMap<String, f64>> {
         if self.miner_info.is_empty() {
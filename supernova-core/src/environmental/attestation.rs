@@ -0,0 +1,317 @@
+//! Green mining attestations and the coinbase bonus they pay
+//!
+//! An attestor whose key is registered with the [`EnvironmentalTreasury`]
+//! signs a claim that a miner runs on renewable energy. Once the claim is
+//! registered, blocks mined under it may pay a bonus of up to
+//! `bonus_bps / 10_000` of the block subsidy. The bonus is drawn from the
+//! treasury balance, so it never raises the subsidy itself.
+//!
+//! A bonus block carries two extra coinbase outputs after the miner and
+//! treasury outputs:
+//!
+//! - the bonus, paying the attestation's `payout_script`
+//! - directly after it, a zero-value commitment
+//!   `OP_RETURN <"GRNB" || attestation id>`
+//!
+//! The bonus is left out of the coinbase total the subsidy and treasury
+//! rules are checked against.
+//!
+//! [`EnvironmentalTreasury`]: super::treasury::EnvironmentalTreasury
+
+use crate::crypto::quantum::{QuantumError, QuantumKeyPair};
+use crate::types::transaction::{Transaction, TransactionOutput};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Tag opening the commitment of a bonus claim
+pub const GREEN_BONUS_TAG: [u8; 4] = *b"GRNB";
+
+/// Largest bonus an attestation may grant, in basis points of the subsidy
+pub const MAX_GREEN_BONUS_BPS: u16 = 2_000;
+
+const OP_RETURN: u8 = 0x6a;
+
+/// Push of the tag and the 32-byte attestation id
+const COMMITMENT_PUSH: u8 = 36;
+
+const SIGNING_DOMAIN: &[u8] = b"supernova-green-attestation-v1";
+
+/// Reasons a green bonus claim or attestation is rejected
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GreenBonusError {
+    #[error("Malformed bonus claim: {0}")]
+    MalformedClaim(String),
+
+    #[error("No attestation registry to check the bonus against")]
+    NoRegistry,
+
+    #[error("Unknown attestor: {0}")]
+    UnknownAttestor(String),
+
+    #[error("Attestation {0} is not registered")]
+    UnknownAttestation(String),
+
+    #[error("Attestation {0} is already registered")]
+    AlreadyRegistered(String),
+
+    #[error("Attestation signature does not verify for attestor {0}")]
+    InvalidSignature(String),
+
+    #[error("Invalid attestation: {0}")]
+    InvalidAttestation(String),
+
+    #[error("Attestation not valid until {valid_from}, block time {timestamp}")]
+    NotYetValid { valid_from: u64, timestamp: u64 },
+
+    #[error("Attestation expired at {expires_at}, block time {timestamp}")]
+    Expired { expires_at: u64, timestamp: u64 },
+
+    #[error("Bonus over-claimed: {claimed} > {allowed} allowed")]
+    OverClaimed { claimed: u64, allowed: u64 },
+
+    #[error("Bonus pays a script other than the attestation's payout script")]
+    PayoutMismatch,
+
+    #[error("Treasury exhausted: bonus {required} > balance {available}")]
+    TreasuryExhausted { required: u64, available: u64 },
+
+    #[error("Lock poisoned: {0}")]
+    LockPoisoned(String),
+}
+
+/// A signed claim that a miner mines on renewable energy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GreenAttestation {
+    /// Attestor registered with the treasury that signed the claim
+    pub attestor: String,
+    /// Script the bonus is paid to
+    pub payout_script: Vec<u8>,
+    /// Bonus per block, in basis points of the subsidy
+    pub bonus_bps: u16,
+    /// First block time (unix seconds) the claim covers
+    pub valid_from: u64,
+    /// Block time (unix seconds) from which the claim no longer applies
+    pub expires_at: u64,
+    /// Most the claim may draw from the treasury over its lifetime
+    pub max_total_bonus: u64,
+    /// Attestor's signature over [`GreenAttestation::signing_message`]
+    pub signature: Vec<u8>,
+}
+
+impl GreenAttestation {
+    /// Message the attestor signs; every field but the signature
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut message = SIGNING_DOMAIN.to_vec();
+        for field in [self.attestor.as_bytes(), &self.payout_script] {
+            message.extend_from_slice(&(field.len() as u64).to_le_bytes());
+            message.extend_from_slice(field);
+        }
+        message.extend_from_slice(&self.bonus_bps.to_le_bytes());
+        message.extend_from_slice(&self.valid_from.to_le_bytes());
+        message.extend_from_slice(&self.expires_at.to_le_bytes());
+        message.extend_from_slice(&self.max_total_bonus.to_le_bytes());
+        message
+    }
+
+    /// Id the coinbase commits to: SHA-256 of the signing message
+    pub fn id(&self) -> [u8; 32] {
+        Sha256::digest(self.signing_message()).into()
+    }
+
+    /// Sign the claim as its attestor
+    pub fn sign(&mut self, keypair: &QuantumKeyPair) -> Result<(), QuantumError> {
+        self.signature = keypair.sign(&self.signing_message())?;
+        Ok(())
+    }
+
+    /// Bonus a block with `subsidy` may pay under this claim
+    pub fn bonus_for(&self, subsidy: u64) -> u64 {
+        let bps = u128::from(self.bonus_bps.min(MAX_GREEN_BONUS_BPS));
+        (u128::from(subsidy) * bps / 10_000) as u64
+    }
+
+    /// Check that the claim covers a block mined at `timestamp`
+    pub fn check_window(&self, timestamp: u64) -> Result<(), GreenBonusError> {
+        if timestamp < self.valid_from {
+            return Err(GreenBonusError::NotYetValid {
+                valid_from: self.valid_from,
+                timestamp,
+            });
+        }
+        if timestamp >= self.expires_at {
+            return Err(GreenBonusError::Expired {
+                expires_at: self.expires_at,
+                timestamp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Coinbase outputs paying `amount` under this claim, in the order they
+    /// follow the miner and treasury outputs
+    pub fn bonus_outputs(&self, amount: u64) -> [TransactionOutput; 2] {
+        [
+            TransactionOutput::new(amount, self.payout_script.clone()),
+            TransactionOutput::new(0, commitment_script(&self.id())),
+        ]
+    }
+}
+
+/// An attestation in the treasury registry and what it has drawn so far
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredAttestation {
+    pub attestation: GreenAttestation,
+    /// Bonus paid out under the claim
+    pub paid: u64,
+}
+
+/// Commitment output script naming the attestation a bonus is paid under
+pub fn commitment_script(attestation_id: &[u8; 32]) -> Vec<u8> {
+    let mut script = vec![OP_RETURN, COMMITMENT_PUSH];
+    script.extend_from_slice(&GREEN_BONUS_TAG);
+    script.extend_from_slice(attestation_id);
+    script
+}
+
+/// A coinbase's claim to a green bonus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GreenBonusClaim {
+    pub attestation_id: [u8; 32],
+    /// Index of the bonus output in the coinbase
+    pub bonus_index: usize,
+    pub amount: u64,
+}
+
+impl GreenBonusClaim {
+    /// The claim `coinbase` makes, if any
+    pub fn from_coinbase(coinbase: &Transaction) -> Result<Option<Self>, GreenBonusError> {
+        let outputs = coinbase.outputs();
+        let mut commitments = outputs.iter().enumerate().filter(|(_, output)| {
+            output.pub_key_script.len() >= 2 + GREEN_BONUS_TAG.len()
+                && output.pub_key_script[0] == OP_RETURN
+                && output.pub_key_script[2..2 + GREEN_BONUS_TAG.len()] == GREEN_BONUS_TAG
+        });
+        let Some((index, commitment)) = commitments.next() else {
+            return Ok(None);
+        };
+        if commitments.next().is_some() {
+            return Err(GreenBonusError::MalformedClaim(
+                "more than one bonus commitment".to_string(),
+            ));
+        }
+
+        let script = &commitment.pub_key_script;
+        if script.len() != 2 + usize::from(COMMITMENT_PUSH) || script[1] != COMMITMENT_PUSH {
+            return Err(GreenBonusError::MalformedClaim(format!(
+                "commitment script is {} bytes",
+                script.len()
+            )));
+        }
+        if commitment.value() != 0 {
+            return Err(GreenBonusError::MalformedClaim(format!(
+                "commitment carries {} units",
+                commitment.value()
+            )));
+        }
+        // Outputs 0 and 1 are the miner and treasury outputs
+        if index < 3 {
+            return Err(GreenBonusError::MalformedClaim(format!(
+                "commitment at output {} leaves no room for the bonus",
+                index
+            )));
+        }
+
+        let mut attestation_id = [0u8; 32];
+        attestation_id.copy_from_slice(&script[2 + GREEN_BONUS_TAG.len()..]);
+        Ok(Some(Self {
+            attestation_id,
+            bonus_index: index - 1,
+            amount: outputs[index - 1].value(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::TransactionInput;
+
+    fn attestation() -> GreenAttestation {
+        GreenAttestation {
+            attestor: "rec-auditor".to_string(),
+            payout_script: vec![0x51; 25],
+            bonus_bps: 1_000,
+            valid_from: 100,
+            expires_at: 200,
+            max_total_bonus: 1_000_000,
+            signature: Vec::new(),
+        }
+    }
+
+    fn coinbase(outputs: Vec<TransactionOutput>) -> Transaction {
+        Transaction::new(1, vec![TransactionInput::new_coinbase(vec![])], outputs, 0)
+    }
+
+    #[test]
+    fn claims_are_read_from_the_commitment() {
+        let attestation = attestation();
+        let mut outputs = vec![
+            TransactionOutput::new(95, vec![1]),
+            TransactionOutput::new(5, vec![2]),
+        ];
+        assert_eq!(
+            GreenBonusClaim::from_coinbase(&coinbase(outputs.clone())),
+            Ok(None)
+        );
+
+        outputs.extend(attestation.bonus_outputs(10));
+        let claim = GreenBonusClaim::from_coinbase(&coinbase(outputs.clone()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(claim.attestation_id, attestation.id());
+        assert_eq!((claim.bonus_index, claim.amount), (2, 10));
+
+        // The bonus cannot take the place of the miner or treasury output
+        let early = coinbase(outputs[1..].to_vec());
+        assert!(matches!(
+            GreenBonusClaim::from_coinbase(&early),
+            Err(GreenBonusError::MalformedClaim(_))
+        ));
+        outputs.push(outputs[3].clone());
+        assert!(matches!(
+            GreenBonusClaim::from_coinbase(&coinbase(outputs)),
+            Err(GreenBonusError::MalformedClaim(_))
+        ));
+    }
+
+    #[test]
+    fn bonus_and_window_follow_the_claim() {
+        let mut attestation = attestation();
+        assert_eq!(attestation.bonus_for(50_000), 5_000);
+        attestation.bonus_bps = u16::MAX;
+        assert_eq!(attestation.bonus_for(50_000), 10_000);
+
+        assert!(attestation.check_window(100).is_ok());
+        assert!(matches!(
+            attestation.check_window(99),
+            Err(GreenBonusError::NotYetValid { .. })
+        ));
+        assert!(matches!(
+            attestation.check_window(200),
+            Err(GreenBonusError::Expired { .. })
+        ));
+    }
+
+    #[test]
+    fn the_id_covers_every_signed_field() {
+        let original = attestation();
+        let mut changed = original.clone();
+        changed.max_total_bonus += 1;
+        assert_ne!(original.id(), changed.id());
+
+        changed = original.clone();
+        changed.signature = vec![1, 2, 3];
+        assert_eq!(original.id(), changed.id());
+    }
+}
@@ -3,6 +3,7 @@
 
 // Re-export all modules
 pub mod api;
+pub mod attestation;
 // pub mod alerting;  // Temporarily disabled for compilation
 pub mod bond;
 pub mod carbon_tracking;
@@ -21,6 +22,7 @@ pub mod types;
 pub mod verification;
 
 // Re-export commonly used types with module-specific prefixes to avoid conflicts
+pub use attestation::{GreenAttestation, GreenBonusClaim, GreenBonusError, RegisteredAttestation};
 pub use dashboard::{EmissionsTimePeriod, EnvironmentalDashboard, EnvironmentalMetrics};
 pub use emissions::{
     EmissionCalculator, Emissions, EmissionsTracker, Region as EmissionsRegion, VerificationStatus,
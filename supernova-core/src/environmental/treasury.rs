@@ -1,3 +1,7 @@
+use crate::crypto::quantum::{verify_quantum_signature, QuantumParameters};
+use crate::environmental::attestation::{
+    GreenAttestation, GreenBonusClaim, GreenBonusError, RegisteredAttestation, MAX_GREEN_BONUS_BPS,
+};
 pub use crate::environmental::emissions::VerificationStatus;
 use crate::environmental::types::Region;
 use chrono::{DateTime, Utc};
@@ -193,6 +197,10 @@ pub struct EnvironmentalTreasury {
     total_recs_kwh: Arc<RwLock<f64>>,
     /// Total carbon offsets purchased (tonnes CO2e)
     total_offsets_tonnes: Arc<RwLock<f64>>,
+    /// Keys attestors sign green attestations with
+    attestors: Arc<RwLock<HashMap<String, (Vec<u8>, QuantumParameters)>>>,
+    /// Green attestations by id
+    attestations: Arc<RwLock<HashMap<[u8; 32], RegisteredAttestation>>>,
 }
 
 impl EnvironmentalTreasury {
@@ -228,6 +236,8 @@ impl EnvironmentalTreasury {
             distribution_history: Arc::new(RwLock::new(Vec::new())),
             total_recs_kwh: Arc::new(RwLock::new(0.0)),
             total_offsets_tonnes: Arc::new(RwLock::new(0.0)),
+            attestors: Arc::new(RwLock::new(HashMap::new())),
+            attestations: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -992,6 +1002,158 @@ impl EnvironmentalTreasury {
         Ok(purchases)
     }
 
+    /// Register the key `attestor` signs green attestations with
+    pub fn register_attestor(
+        &self,
+        attestor: &str,
+        public_key: Vec<u8>,
+        parameters: QuantumParameters,
+    ) -> Result<(), GreenBonusError> {
+        self.attestors
+            .write()
+            .map_err(|e| {
+                GreenBonusError::LockPoisoned(format!("Failed to write attestors: {}", e))
+            })?
+            .insert(attestor.to_string(), (public_key, parameters));
+        Ok(())
+    }
+
+    /// Register a signed green attestation, returning the id blocks commit to
+    pub fn register_attestation(
+        &self,
+        attestation: GreenAttestation,
+    ) -> Result<[u8; 32], GreenBonusError> {
+        if attestation.bonus_bps == 0 || attestation.bonus_bps > MAX_GREEN_BONUS_BPS {
+            return Err(GreenBonusError::InvalidAttestation(format!(
+                "bonus of {} bps outside 1..={}",
+                attestation.bonus_bps, MAX_GREEN_BONUS_BPS
+            )));
+        }
+        if attestation.expires_at <= attestation.valid_from {
+            return Err(GreenBonusError::InvalidAttestation(format!(
+                "expires at {} before it is valid from {}",
+                attestation.expires_at, attestation.valid_from
+            )));
+        }
+        self.verify_attestation(&attestation)?;
+
+        let id = attestation.id();
+        let mut attestations = self.attestations.write().map_err(|e| {
+            GreenBonusError::LockPoisoned(format!("Failed to write attestations: {}", e))
+        })?;
+        if attestations.contains_key(&id) {
+            return Err(GreenBonusError::AlreadyRegistered(hex::encode(id)));
+        }
+        attestations.insert(
+            id,
+            RegisteredAttestation {
+                attestation,
+                paid: 0,
+            },
+        );
+        Ok(id)
+    }
+
+    /// A registered green attestation and the bonus it has drawn
+    pub fn get_attestation(&self, id: &[u8; 32]) -> Option<RegisteredAttestation> {
+        match self.attestations.read() {
+            Ok(attestations) => attestations.get(id).cloned(),
+            Err(e) => {
+                log::error!("Failed to read attestations: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Check a coinbase's green bonus against its attestation and the
+    /// treasury balance, for a block at `timestamp` with `subsidy`
+    pub fn check_green_bonus(
+        &self,
+        claim: &GreenBonusClaim,
+        payout_script: &[u8],
+        timestamp: u64,
+        subsidy: u64,
+    ) -> Result<(), GreenBonusError> {
+        let registered = self.get_attestation(&claim.attestation_id).ok_or_else(|| {
+            GreenBonusError::UnknownAttestation(hex::encode(claim.attestation_id))
+        })?;
+        let attestation = &registered.attestation;
+        self.verify_attestation(attestation)?;
+
+        if payout_script != attestation.payout_script.as_slice() {
+            return Err(GreenBonusError::PayoutMismatch);
+        }
+        attestation.check_window(timestamp)?;
+
+        let allowed = attestation.bonus_for(subsidy);
+        if claim.amount > allowed {
+            return Err(GreenBonusError::OverClaimed {
+                claimed: claim.amount,
+                allowed,
+            });
+        }
+        let remaining = attestation.max_total_bonus.saturating_sub(registered.paid);
+        if claim.amount > remaining {
+            return Err(GreenBonusError::OverClaimed {
+                claimed: claim.amount,
+                allowed: remaining,
+            });
+        }
+
+        let available = self.get_balance(None);
+        if claim.amount > available {
+            return Err(GreenBonusError::TreasuryExhausted {
+                required: claim.amount,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Pay a connected block's green bonus out of the treasury balance
+    pub fn pay_green_bonus(&self, claim: &GreenBonusClaim) -> Result<u64, GreenBonusError> {
+        let mut attestations = self.attestations.write().map_err(|e| {
+            GreenBonusError::LockPoisoned(format!("Failed to write attestations: {}", e))
+        })?;
+        let registered = attestations.get_mut(&claim.attestation_id).ok_or_else(|| {
+            GreenBonusError::UnknownAttestation(hex::encode(claim.attestation_id))
+        })?;
+        let mut balance = self.balance.write().map_err(|e| {
+            GreenBonusError::LockPoisoned(format!("Failed to write balance: {}", e))
+        })?;
+
+        *balance = balance
+            .checked_sub(claim.amount)
+            .ok_or(GreenBonusError::TreasuryExhausted {
+                required: claim.amount,
+                available: *balance,
+            })?;
+        registered.paid = registered.paid.saturating_add(claim.amount);
+        Ok(claim.amount)
+    }
+
+    /// Check an attestation's signature against its attestor's key
+    fn verify_attestation(&self, attestation: &GreenAttestation) -> Result<(), GreenBonusError> {
+        let attestors = self.attestors.read().map_err(|e| {
+            GreenBonusError::LockPoisoned(format!("Failed to read attestors: {}", e))
+        })?;
+        let (public_key, parameters) = attestors
+            .get(&attestation.attestor)
+            .ok_or_else(|| GreenBonusError::UnknownAttestor(attestation.attestor.clone()))?;
+
+        match verify_quantum_signature(
+            public_key,
+            &attestation.signing_message(),
+            &attestation.signature,
+            *parameters,
+        ) {
+            Ok(true) => Ok(()),
+            _ => Err(GreenBonusError::InvalidSignature(
+                attestation.attestor.clone(),
+            )),
+        }
+    }
+
     /// Get recent asset purchases
     pub fn get_asset_purchases(&self, limit: usize) -> Vec<EnvironmentalAssetPurchase> {
         // SECURITY FIX (P0-002): Handle lock poisoning gracefully
@@ -42,6 +42,7 @@ pub mod lightning;
 pub mod mempool;
 pub mod mining;
 pub mod monitoring;
+pub mod netparams;
 pub mod network;
 pub mod p2p;
 pub mod rpc;
@@ -91,7 +92,7 @@ pub use crate::environmental::{
 };
 pub use crate::errors::{SupernovaError, SupernovaResult};
 pub use crate::mempool::{MempoolError, TransactionPool, TransactionPoolConfig};
-pub use crate::util::merkle::{MerkleError, MerkleProof, MerkleTree};
+pub use crate::util::merkle::{MerkleError, MerkleMultiProof, MerkleProof, MerkleTree};
 pub use crate::validation::{BlockValidationConfig, BlockValidator, TransactionValidator};
 pub use crate::verification::{VerificationService, VerificationStatus};
 
@@ -121,8 +122,9 @@ pub use freeze::*;
 // Add this export near other testnet-related exports
 pub use testnet::network_simulator::SimulationConfig;
 
-// Test utilities module - only available in test builds
-#[cfg(test)]
+// Test utilities module - only available in test builds, or to the tests of
+// dependent crates through the `test-utils` feature
+#[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
 
 #[cfg(test)]
@@ -178,6 +178,8 @@ impl AtomicChannelState {
             (Active, ClosingNegotiation) => true,
             (Active, ForceClosed) => true,
             (ClosingNegotiation, Closed) => true,
+            // Funding aborted before activation
+            (FundingCreated, Closed) | (FundingSigned, Closed) => true,
             // Allow same state (no-op)
             (a, b) if a == b => true,
             // All other transitions are invalid
@@ -0,0 +1,822 @@
+// supernova Lightning Network - BOLT11 Invoice Encoding
+//
+// This file encodes invoices as BOLT11 bech32 strings and decodes them:
+//
+//   ln<network><amount><multiplier> 1 <timestamp><tagged fields><signature><checksum>
+//
+// The network part is the address HRP, so mainnet invoices start `lnnova`.
+// The amount is in NOVA scaled by an optional `m`, `u`, `n` or `p`
+// multiplier. Tagged fields are a 5-bit type, a 10-bit length in 5-bit groups
+// and the data; readers skip types they do not know. The trailing 65-byte
+// recoverable secp256k1 signature by the node key covers SHA-256 of the HRP
+// and the data before it, and the payee is the key it recovers to.
+//
+// A node with a post-quantum key signs with it as well. Its public key
+// travels in `TAG_QUANTUM_KEY` fields and its signature in
+// `TAG_QUANTUM_SIGNATURE` fields, which come last: the quantum signature
+// covers everything before it, and the node key's signature covers the
+// quantum one. Both are longer than one field holds, so they are split over
+// consecutive fields of the same type. Wallets that know nothing of them
+// skip the fields and check the node key's signature alone.
+
+use crate::crypto::quantum::{
+    verify_quantum_signature, ClassicalScheme, QuantumKeyPair, QuantumParameters, QuantumScheme,
+};
+use crate::lightning::invoice::{InvoiceError, RouteHint};
+use crate::script::classify::address_hrp;
+use bech32::{u5, Variant};
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+
+const TAG_PAYMENT_HASH: u8 = 1; // 'p'
+const TAG_ROUTE_HINT: u8 = 3; // 'r'
+const TAG_FEATURES: u8 = 5; // '9'
+const TAG_EXPIRY: u8 = 6; // 'x'
+const TAG_DESCRIPTION: u8 = 13; // 'd'
+const TAG_PAYEE: u8 = 19; // 'n'
+const TAG_DESCRIPTION_HASH: u8 = 23; // 'h'
+const TAG_MIN_FINAL_CLTV: u8 = 24; // 'c'
+
+/// Post-quantum public key of the payee: scheme, security level and key
+pub const TAG_QUANTUM_KEY: u8 = 22; // 'k'
+
+/// Post-quantum signature by [`TAG_QUANTUM_KEY`]
+pub const TAG_QUANTUM_SIGNATURE: u8 = 18; // 'j'
+
+/// Expiry of an invoice without an `x` field, in seconds
+pub const DEFAULT_EXPIRY: u32 = 3600;
+
+/// Final CLTV delta of an invoice without a `c` field
+pub const DEFAULT_MIN_FINAL_CLTV: u32 = 18;
+
+const TIMESTAMP_GROUPS: usize = 7;
+const SIGNATURE_GROUPS: usize = 104;
+const MAX_FIELD_GROUPS: usize = 1023;
+
+/// Bytes per field of a split value; a multiple of 5, so only the last field
+/// is padded
+const SPLIT_FIELD_BYTES: usize = 635;
+
+/// Encoded length of one route hint hop
+const ROUTE_HOP_BYTES: usize = 51;
+
+/// Amount multipliers and the picoNOVA (0.1 millinova) each unit is worth
+const MULTIPLIERS: [(&str, u128); 5] = [
+    ("", 1_000_000_000_000),
+    ("m", 1_000_000_000),
+    ("u", 1_000_000),
+    ("n", 1_000),
+    ("p", 1),
+];
+
+/// What an invoice string carries besides its signatures
+#[derive(Debug, Clone)]
+pub struct InvoiceFields {
+    pub amount_mnova: u64,
+    pub timestamp: u64,
+    pub payment_hash: [u8; 32],
+    pub description: String,
+    pub expiry: u32,
+    pub min_final_cltv_expiry: u32,
+    pub features: u64,
+    pub route_hints: Vec<RouteHint>,
+}
+
+/// Post-quantum key an invoice was signed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantumInvoiceKey {
+    pub parameters: QuantumParameters,
+    pub public_key: Vec<u8>,
+}
+
+/// A decoded invoice whose signatures verified
+#[derive(Debug, Clone)]
+pub struct SignedInvoice {
+    pub fields: InvoiceFields,
+    /// Node key of the payee
+    pub payee: PublicKey,
+    /// The 64-byte compact signature by the payee and its recovery id
+    pub signature: [u8; 65],
+    pub quantum_key: Option<QuantumInvoiceKey>,
+}
+
+/// Encode and sign `fields` with `node_key` and, when given, `quantum_key`
+pub fn encode(
+    fields: &InvoiceFields,
+    node_key: &SecretKey,
+    quantum_key: Option<&QuantumKeyPair>,
+) -> Result<String, InvoiceError> {
+    let hrp = format!("ln{}{}", address_hrp(), encode_amount(fields.amount_mnova)?);
+    if fields.timestamp >> (5 * TIMESTAMP_GROUPS) != 0 {
+        return Err(InvoiceError::InvalidFormat(format!(
+            "Timestamp {} does not fit 35 bits",
+            fields.timestamp
+        )));
+    }
+
+    let mut data: Vec<u8> = (0..TIMESTAMP_GROUPS)
+        .rev()
+        .map(|i| ((fields.timestamp >> (5 * i)) & 0x1f) as u8)
+        .collect();
+    push_field(
+        &mut data,
+        TAG_PAYMENT_HASH,
+        &to_groups(&fields.payment_hash),
+    )?;
+    push_field(
+        &mut data,
+        TAG_DESCRIPTION,
+        &to_groups(fields.description.as_bytes()),
+    )?;
+    push_field(&mut data, TAG_EXPIRY, &int_groups(fields.expiry.into()))?;
+    push_field(
+        &mut data,
+        TAG_MIN_FINAL_CLTV,
+        &int_groups(fields.min_final_cltv_expiry.into()),
+    )?;
+    if fields.features != 0 {
+        push_field(&mut data, TAG_FEATURES, &int_groups(fields.features))?;
+    }
+    for hint in &fields.route_hints {
+        push_field(
+            &mut data,
+            TAG_ROUTE_HINT,
+            &to_groups(&encode_route_hop(hint)?),
+        )?;
+    }
+
+    if let Some(keypair) = quantum_key {
+        let mut key = vec![
+            scheme_id(keypair.parameters.scheme),
+            keypair.parameters.security_level,
+        ];
+        key.extend_from_slice(&keypair.public_key);
+        push_split(&mut data, TAG_QUANTUM_KEY, &key)?;
+        let signature = keypair
+            .sign(&signing_digest(&hrp, &data))
+            .map_err(|e| InvoiceError::InvalidSignature(e.to_string()))?;
+        push_split(&mut data, TAG_QUANTUM_SIGNATURE, &signature)?;
+    }
+
+    let message = Message::from_slice(&signing_digest(&hrp, &data))
+        .map_err(|e| InvoiceError::InvalidSignature(e.to_string()))?;
+    let (recovery_id, compact) = Secp256k1::signing_only()
+        .sign_ecdsa_recoverable(&message, node_key)
+        .serialize_compact();
+    let mut signature = compact.to_vec();
+    signature.push(recovery_id.to_i32() as u8);
+    data.extend(to_groups(&signature));
+
+    let data = data
+        .into_iter()
+        .map(u5::try_from_u8)
+        .collect::<Result<Vec<u5>, _>>()
+        .map_err(|e| InvoiceError::InvalidFormat(format!("bech32: {}", e)))?;
+    bech32::encode(&hrp, data, Variant::Bech32)
+        .map_err(|e| InvoiceError::InvalidFormat(format!("bech32: {}", e)))
+}
+
+/// Decode `invoice` and check its checksum and signatures. Expiry is left to
+/// the caller.
+pub fn decode(invoice: &str) -> Result<SignedInvoice, InvoiceError> {
+    let (hrp, data, variant) = bech32::decode(invoice)
+        .map_err(|e| InvoiceError::InvalidFormat(format!("bech32: {}", e)))?;
+    if variant != Variant::Bech32 {
+        return Err(InvoiceError::InvalidFormat(
+            "Invoices are bech32, not bech32m".to_string(),
+        ));
+    }
+    let hrp = hrp.to_lowercase();
+    let amount_mnova = parse_amount(&hrp)?;
+
+    let data: Vec<u8> = data.iter().map(|group| group.to_u8()).collect();
+    if data.len() < TIMESTAMP_GROUPS + SIGNATURE_GROUPS {
+        return Err(InvoiceError::InvalidFormat(format!(
+            "{} data characters leave no room for a timestamp and signature",
+            data.len()
+        )));
+    }
+    let (body, signature) = data.split_at(data.len() - SIGNATURE_GROUPS);
+
+    let mut fields = InvoiceFields {
+        amount_mnova,
+        timestamp: parse_int(&body[..TIMESTAMP_GROUPS]).unwrap_or_default(),
+        payment_hash: [0; 32],
+        description: String::new(),
+        expiry: DEFAULT_EXPIRY,
+        min_final_cltv_expiry: DEFAULT_MIN_FINAL_CLTV,
+        features: 0,
+        route_hints: Vec::new(),
+    };
+    let mut payment_hash = None;
+    let mut has_description = false;
+    let mut payee = None;
+    let mut quantum_key = Vec::new();
+    let mut quantum_signature = Vec::new();
+    let mut quantum_signed_len = None;
+
+    let mut pos = TIMESTAMP_GROUPS;
+    while pos < body.len() {
+        if body.len() - pos < 3 {
+            return Err(InvoiceError::InvalidFormat(
+                "Truncated tagged field".to_string(),
+            ));
+        }
+        let tag = body[pos];
+        let len = (usize::from(body[pos + 1]) << 5) | usize::from(body[pos + 2]);
+        let value = body.get(pos + 3..pos + 3 + len).ok_or_else(|| {
+            InvoiceError::InvalidFormat(format!("Tagged field of {} characters is truncated", len))
+        })?;
+        if quantum_signed_len.is_some() && tag != TAG_QUANTUM_SIGNATURE {
+            return Err(InvoiceError::InvalidFormat(
+                "Fields follow the quantum signature".to_string(),
+            ));
+        }
+
+        // Fields of a known type but the wrong length are skipped, as BOLT11
+        // requires
+        match tag {
+            TAG_PAYMENT_HASH if len == 52 => {
+                if payment_hash.is_some() {
+                    return Err(InvoiceError::InvalidHash(
+                        "Invoice has more than one payment hash".to_string(),
+                    ));
+                }
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&from_groups(value)[..32]);
+                payment_hash = Some(hash);
+            }
+            TAG_DESCRIPTION => {
+                fields.description = String::from_utf8(from_groups(value))
+                    .map_err(|e| InvoiceError::ParseError(format!("Invalid description: {}", e)))?;
+                has_description = true;
+            }
+            TAG_DESCRIPTION_HASH if len == 52 => has_description = true,
+            TAG_EXPIRY => {
+                fields.expiry = parse_int(value)
+                    .and_then(|expiry| u32::try_from(expiry).ok())
+                    .ok_or_else(|| InvoiceError::ParseError("Expiry out of range".to_string()))?;
+            }
+            TAG_MIN_FINAL_CLTV => {
+                fields.min_final_cltv_expiry = parse_int(value)
+                    .and_then(|cltv| u32::try_from(cltv).ok())
+                    .ok_or_else(|| {
+                        InvoiceError::ParseError("Final CLTV delta out of range".to_string())
+                    })?;
+            }
+            TAG_FEATURES => fields.features = parse_features(value)?,
+            TAG_PAYEE if len == 53 => {
+                payee =
+                    Some(PublicKey::from_slice(&from_groups(value)).map_err(|e| {
+                        InvoiceError::ParseError(format!("Invalid payee key: {}", e))
+                    })?);
+            }
+            TAG_ROUTE_HINT => fields.route_hints.extend(parse_route(&from_groups(value))?),
+            TAG_QUANTUM_KEY => quantum_key.extend(from_groups(value)),
+            TAG_QUANTUM_SIGNATURE => {
+                quantum_signed_len.get_or_insert(pos);
+                quantum_signature.extend(from_groups(value));
+            }
+            _ => {}
+        }
+        pos += 3 + len;
+    }
+
+    fields.payment_hash =
+        payment_hash.ok_or_else(|| InvoiceError::MissingField("payment hash".to_string()))?;
+    if !has_description {
+        return Err(InvoiceError::MissingField("description".to_string()));
+    }
+
+    let (payee, signature) = verify_node_signature(&hrp, body, signature, payee)?;
+
+    let quantum_key = match (quantum_key.is_empty(), quantum_signed_len) {
+        (true, None) => None,
+        (true, Some(_)) => {
+            return Err(InvoiceError::MissingField("quantum public key".to_string()))
+        }
+        (false, None) => return Err(InvoiceError::MissingField("quantum signature".to_string())),
+        (false, Some(signed_len)) => {
+            let key = parse_quantum_key(&quantum_key)?;
+            let verified = verify_quantum_signature(
+                &key.public_key,
+                &signing_digest(&hrp, &body[..signed_len]),
+                &quantum_signature,
+                key.parameters,
+            )
+            .map_err(|e| InvoiceError::InvalidSignature(e.to_string()))?;
+            if !verified {
+                return Err(InvoiceError::InvalidSignature(
+                    "Quantum signature does not verify".to_string(),
+                ));
+            }
+            Some(key)
+        }
+    };
+
+    Ok(SignedInvoice {
+        fields,
+        payee,
+        signature,
+        quantum_key,
+    })
+}
+
+/// Check the trailing signature over `body`, returning the payee and the
+/// signature bytes. An `n` field names the payee; otherwise it is recovered.
+fn verify_node_signature(
+    hrp: &str,
+    body: &[u8],
+    signature: &[u8],
+    payee: Option<PublicKey>,
+) -> Result<(PublicKey, [u8; 65]), InvoiceError> {
+    let invalid = |e: secp256k1::Error| InvoiceError::InvalidSignature(e.to_string());
+    let mut bytes = [0u8; 65];
+    bytes.copy_from_slice(&from_groups(signature)[..65]);
+    let recovery_id = RecoveryId::from_i32(i32::from(bytes[64])).map_err(invalid)?;
+    let recoverable =
+        RecoverableSignature::from_compact(&bytes[..64], recovery_id).map_err(invalid)?;
+    let message = Message::from_slice(&signing_digest(hrp, body)).map_err(invalid)?;
+
+    let secp = Secp256k1::verification_only();
+    let payee = match payee {
+        Some(payee) => payee,
+        None => secp
+            .recover_ecdsa(&message, &recoverable)
+            .map_err(invalid)?,
+    };
+    // Recovery accepts high-S signatures; verification does not
+    secp.verify_ecdsa(&message, &recoverable.to_standard(), &payee)
+        .map_err(|_| InvoiceError::InvalidSignature("Signature is not by the payee".to_string()))?;
+    Ok((payee, bytes))
+}
+
+/// SHA-256 of `hrp` and `groups` packed into bytes, zero-padded
+fn signing_digest(hrp: &str, groups: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(hrp.as_bytes());
+    hasher.update(regroup(groups, 5, 8, true));
+    hasher.finalize().into()
+}
+
+fn push_field(data: &mut Vec<u8>, tag: u8, value: &[u8]) -> Result<(), InvoiceError> {
+    if value.len() > MAX_FIELD_GROUPS {
+        return Err(InvoiceError::InvalidFormat(format!(
+            "Field {} of {} characters is longer than {}",
+            tag,
+            value.len(),
+            MAX_FIELD_GROUPS
+        )));
+    }
+    data.extend_from_slice(&[tag, (value.len() >> 5) as u8, (value.len() & 0x1f) as u8]);
+    data.extend_from_slice(value);
+    Ok(())
+}
+
+/// Push `value` as consecutive `tag` fields
+fn push_split(data: &mut Vec<u8>, tag: u8, value: &[u8]) -> Result<(), InvoiceError> {
+    for chunk in value.chunks(SPLIT_FIELD_BYTES) {
+        push_field(data, tag, &to_groups(chunk))?;
+    }
+    Ok(())
+}
+
+fn to_groups(bytes: &[u8]) -> Vec<u8> {
+    regroup(bytes, 8, 5, true)
+}
+
+/// Bytes of `groups`, dropping trailing bits that do not fill a byte
+fn from_groups(groups: &[u8]) -> Vec<u8> {
+    regroup(groups, 5, 8, false)
+}
+
+/// Regroup `from`-bit values into `to`-bit values, zero-padding the last
+/// one when `pad` is set
+fn regroup(values: &[u8], from: u32, to: u32, pad: bool) -> Vec<u8> {
+    let mask = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(values.len() * from as usize / to as usize + 1);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &value in values {
+        acc = (acc << from) | u32::from(value);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & mask) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+    if pad && bits > 0 {
+        out.push(((acc << (to - bits)) & mask) as u8);
+    }
+    out
+}
+
+/// Big-endian 5-bit groups of `value`, without leading zero groups
+fn int_groups(value: u64) -> Vec<u8> {
+    let len = (64 - value.leading_zeros() as usize).div_ceil(5);
+    (0..len)
+        .rev()
+        .map(|i| ((value >> (5 * i)) & 0x1f) as u8)
+        .collect()
+}
+
+fn parse_int(groups: &[u8]) -> Option<u64> {
+    groups.iter().try_fold(0u64, |value, &group| {
+        value.checked_mul(32).map(|value| value | u64::from(group))
+    })
+}
+
+/// Feature bits of an `9` field; bits past the 64 we track are unsupported
+fn parse_features(groups: &[u8]) -> Result<u64, InvoiceError> {
+    let mut features = 0u64;
+    for (index, &group) in groups.iter().rev().enumerate() {
+        for bit in 0..5 {
+            if (group >> bit) & 1 == 0 {
+                continue;
+            }
+            let position = index * 5 + bit;
+            if position >= 64 {
+                return Err(InvoiceError::UnsupportedFeature(position as u32));
+            }
+            features |= 1 << position;
+        }
+    }
+    Ok(features)
+}
+
+fn encode_amount(amount_mnova: u64) -> Result<String, InvoiceError> {
+    if amount_mnova == 0 {
+        return Err(InvoiceError::InvalidAmount(
+            "Amount must be greater than zero".to_string(),
+        ));
+    }
+    let pico = u128::from(amount_mnova) * 10;
+    // The largest unit that expresses the amount exactly
+    let (multiplier, per_unit) = MULTIPLIERS
+        .iter()
+        .copied()
+        .find(|&(_, per_unit)| pico % per_unit == 0)
+        .unwrap_or(("p", 1));
+    Ok(format!("{}{}", pico / per_unit, multiplier))
+}
+
+/// Amount in millinova from the HRP `ln<network><amount><multiplier>`
+fn parse_amount(hrp: &str) -> Result<u64, InvoiceError> {
+    let prefix = format!("ln{}", address_hrp());
+    let amount = hrp.strip_prefix(&prefix).ok_or_else(|| {
+        InvoiceError::InvalidFormat(format!(
+            "Invoice prefix {} is not {} followed by an amount",
+            hrp, prefix
+        ))
+    })?;
+    let invalid = || InvoiceError::InvalidAmount(format!("Invalid amount: {}", amount));
+
+    let (digits, multiplier) = match amount.char_indices().last() {
+        Some((index, c)) if c.is_ascii_alphabetic() => amount.split_at(index),
+        Some(_) => (amount, ""),
+        None => {
+            return Err(InvoiceError::InvalidAmount(
+                "Invoice has no amount".to_string(),
+            ))
+        }
+    };
+    let per_unit = MULTIPLIERS
+        .iter()
+        .find(|&&(name, _)| name == multiplier)
+        .map(|&(_, per_unit)| per_unit)
+        .ok_or_else(invalid)?;
+    if digits.is_empty() || digits.starts_with('0') || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let pico = digits
+        .parse::<u128>()
+        .ok()
+        .and_then(|value| value.checked_mul(per_unit))
+        .ok_or_else(invalid)?;
+    if pico % 10 != 0 {
+        return Err(InvoiceError::InvalidAmount(format!(
+            "{} is not a whole number of millinova",
+            amount
+        )));
+    }
+    u64::try_from(pico / 10).map_err(|_| invalid())
+}
+
+/// Route hint as one BOLT11 hop: node key, channel, fees and CLTV delta
+fn encode_route_hop(hint: &RouteHint) -> Result<Vec<u8>, InvoiceError> {
+    let node_id = hex::decode(&hint.node_id)
+        .ok()
+        .filter(|key| key.len() == 33)
+        .ok_or_else(|| {
+            InvoiceError::InvalidFormat(format!(
+                "Route hint node {} is not a 33-byte public key",
+                hint.node_id
+            ))
+        })?;
+    let mut hop = node_id;
+    hop.extend_from_slice(&hint.channel_id.to_be_bytes());
+    hop.extend_from_slice(&hint.base_fee_mnova.to_be_bytes());
+    hop.extend_from_slice(&hint.fee_rate_millionths.to_be_bytes());
+    hop.extend_from_slice(&hint.cltv_expiry_delta.to_be_bytes());
+    Ok(hop)
+}
+
+fn parse_route(bytes: &[u8]) -> Result<Vec<RouteHint>, InvoiceError> {
+    if bytes.len() % ROUTE_HOP_BYTES != 0 {
+        return Err(InvoiceError::ParseError(format!(
+            "Route hint of {} bytes is not a whole number of hops",
+            bytes.len()
+        )));
+    }
+    Ok(bytes
+        .chunks(ROUTE_HOP_BYTES)
+        .map(|hop| {
+            let mut channel_id = [0u8; 8];
+            channel_id.copy_from_slice(&hop[33..41]);
+            let mut fees = [0u8; 4];
+            fees.copy_from_slice(&hop[41..45]);
+            let base_fee_mnova = u32::from_be_bytes(fees);
+            fees.copy_from_slice(&hop[45..49]);
+            RouteHint {
+                node_id: hex::encode(&hop[..33]),
+                channel_id: u64::from_be_bytes(channel_id),
+                base_fee_mnova,
+                fee_rate_millionths: u32::from_be_bytes(fees),
+                cltv_expiry_delta: u16::from_be_bytes([hop[49], hop[50]]),
+            }
+        })
+        .collect())
+}
+
+fn scheme_id(scheme: QuantumScheme) -> u8 {
+    match scheme {
+        QuantumScheme::Dilithium => 0,
+        QuantumScheme::Falcon => 1,
+        QuantumScheme::SphincsPlus => 2,
+        QuantumScheme::Hybrid(ClassicalScheme::Secp256k1) => 3,
+        QuantumScheme::Hybrid(ClassicalScheme::Ed25519) => 4,
+    }
+}
+
+fn parse_quantum_key(bytes: &[u8]) -> Result<QuantumInvoiceKey, InvoiceError> {
+    let [scheme, security_level, public_key @ ..] = bytes else {
+        return Err(InvoiceError::ParseError(
+            "Quantum key field is too short".to_string(),
+        ));
+    };
+    let scheme = match scheme {
+        0 => QuantumScheme::Dilithium,
+        1 => QuantumScheme::Falcon,
+        2 => QuantumScheme::SphincsPlus,
+        3 => QuantumScheme::Hybrid(ClassicalScheme::Secp256k1),
+        4 => QuantumScheme::Hybrid(ClassicalScheme::Ed25519),
+        other => {
+            return Err(InvoiceError::ParseError(format!(
+                "Unknown quantum scheme {}",
+                other
+            )))
+        }
+    };
+    Ok(QuantumInvoiceKey {
+        parameters: QuantumParameters::with_security_level(scheme, *security_level),
+        public_key: public_key.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_key() -> SecretKey {
+        SecretKey::from_slice(&[0x11; 32]).unwrap()
+    }
+
+    fn fields() -> InvoiceFields {
+        InvoiceFields {
+            amount_mnova: 250_000_000,
+            timestamp: 1_700_000_000,
+            payment_hash: [0x42; 32],
+            description: "1 cup coffee".to_string(),
+            expiry: 600,
+            min_final_cltv_expiry: 40,
+            features: (1 << 8) | (1 << 14),
+            route_hints: vec![RouteHint {
+                node_id: format!("02{}", "ab".repeat(32)),
+                channel_id: 0x0102_0304_0506_0708,
+                base_fee_mnova: 1_000,
+                fee_rate_millionths: 20,
+                cltv_expiry_delta: 144,
+            }],
+        }
+    }
+
+    /// Re-sign `invoice` after `edit` changes its data, keeping the checksum
+    /// valid
+    fn resigned(invoice: &str, edit: impl FnOnce(&mut String, &mut Vec<u8>)) -> String {
+        let (hrp, data, _) = bech32::decode(invoice).unwrap();
+        let mut hrp = hrp;
+        let mut body: Vec<u8> = data[..data.len() - SIGNATURE_GROUPS]
+            .iter()
+            .map(|group| group.to_u8())
+            .collect();
+        edit(&mut hrp, &mut body);
+        let message = Message::from_slice(&signing_digest(&hrp, &body)).unwrap();
+        let (recovery_id, compact) = Secp256k1::new()
+            .sign_ecdsa_recoverable(&message, &node_key())
+            .serialize_compact();
+        let mut signature = compact.to_vec();
+        signature.push(recovery_id.to_i32() as u8);
+        body.extend(to_groups(&signature));
+        let data: Vec<u5> = body
+            .into_iter()
+            .map(|g| u5::try_from_u8(g).unwrap())
+            .collect();
+        bech32::encode(&hrp, data, Variant::Bech32).unwrap()
+    }
+
+    #[test]
+    fn invoices_round_trip_and_recover_the_payee() {
+        let encoded = encode(&fields(), &node_key(), None).unwrap();
+        assert!(encoded.starts_with(&format!("ln{}2500u1", address_hrp())));
+
+        for invoice in [encoded.clone(), encoded.to_uppercase()] {
+            let decoded = decode(&invoice).unwrap();
+            let expected = fields();
+            assert_eq!(decoded.fields.amount_mnova, expected.amount_mnova);
+            assert_eq!(decoded.fields.timestamp, expected.timestamp);
+            assert_eq!(decoded.fields.payment_hash, expected.payment_hash);
+            assert_eq!(decoded.fields.description, expected.description);
+            assert_eq!(decoded.fields.expiry, expected.expiry);
+            assert_eq!(decoded.fields.min_final_cltv_expiry, 40);
+            assert_eq!(decoded.fields.features, expected.features);
+            assert_eq!(decoded.fields.route_hints.len(), 1);
+            let hint = &decoded.fields.route_hints[0];
+            assert_eq!(hint.node_id, expected.route_hints[0].node_id);
+            assert_eq!(hint.channel_id, expected.route_hints[0].channel_id);
+            assert_eq!(hint.cltv_expiry_delta, 144);
+            assert_eq!(
+                decoded.payee,
+                PublicKey::from_secret_key(&Secp256k1::new(), &node_key())
+            );
+            assert!(decoded.quantum_key.is_none());
+        }
+    }
+
+    #[test]
+    fn amounts_use_the_largest_exact_multiplier() {
+        for (mnova, amount) in [
+            (100_000_000_000, "1"),
+            (300_000_000, "3m"),
+            (2_500_000, "25u"),
+            (1_200, "12n"),
+            (1, "10p"),
+        ] {
+            assert_eq!(encode_amount(mnova).unwrap(), amount);
+            let hrp = format!("ln{}{}", address_hrp(), amount);
+            assert_eq!(parse_amount(&hrp).unwrap(), mnova);
+        }
+        let network = address_hrp();
+        for amount in ["", "1x", "01m", "m", "15p", "99999999999999999999999"] {
+            let hrp = format!("ln{}{}", network, amount);
+            assert!(
+                matches!(parse_amount(&hrp), Err(InvoiceError::InvalidAmount(_))),
+                "{}",
+                amount
+            );
+        }
+        assert!(parse_amount("lnbc25u").is_err());
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped() {
+        let encoded = encode(&fields(), &node_key(), None).unwrap();
+        let with_unknown = resigned(&encoded, |_, body| {
+            // A 'z' field and a 'p' field of the wrong length
+            body.extend_from_slice(&[2, 0, 3, 1, 2, 3]);
+            body.extend_from_slice(&[TAG_PAYMENT_HASH, 0, 2, 7, 7]);
+        });
+        let decoded = decode(&with_unknown).unwrap();
+        assert_eq!(decoded.fields.payment_hash, [0x42; 32]);
+        assert_eq!(decoded.fields.description, "1 cup coffee");
+    }
+
+    #[test]
+    fn corrupted_invoices_are_rejected() {
+        let encoded = encode(&fields(), &node_key(), None).unwrap();
+
+        // A flipped data character breaks the checksum
+        let mut flipped = encoded.clone().into_bytes();
+        let at = flipped.len() - 20;
+        flipped[at] = if flipped[at] == b'q' { b'p' } else { b'q' };
+        let flipped = String::from_utf8(flipped).unwrap();
+        assert!(matches!(
+            decode(&flipped),
+            Err(InvoiceError::InvalidFormat(_))
+        ));
+
+        // Mixed case is not bech32
+        let mut mixed = encoded.clone();
+        mixed.replace_range(..1, "L");
+        assert!(matches!(
+            decode(&mixed),
+            Err(InvoiceError::InvalidFormat(_))
+        ));
+
+        // A raised amount with a valid checksum no longer recovers to the
+        // payee
+        let (hrp, data, _) = bech32::decode(&encoded).unwrap();
+        let raised = bech32::encode(
+            &hrp.replace("2500u", "2600u"),
+            data.clone(),
+            Variant::Bech32,
+        )
+        .unwrap();
+        let payee = PublicKey::from_secret_key(&Secp256k1::new(), &node_key());
+        match decode(&raised) {
+            Err(InvoiceError::InvalidSignature(_)) => {}
+            Ok(decoded) => assert_ne!(decoded.payee, payee),
+            Err(e) => panic!("unexpected error {}", e),
+        }
+
+        // Bech32m checksums are not invoices
+        let bech32m = bech32::encode(&hrp, data, Variant::Bech32m).unwrap();
+        assert!(matches!(
+            decode(&bech32m),
+            Err(InvoiceError::InvalidFormat(_))
+        ));
+
+        // A named payee must be the signer
+        let other = PublicKey::from_secret_key(
+            &Secp256k1::new(),
+            &SecretKey::from_slice(&[0x22; 32]).unwrap(),
+        );
+        let wrong_payee = resigned(&encoded, |_, body| {
+            push_field(body, TAG_PAYEE, &to_groups(&other.serialize())).unwrap();
+        });
+        assert!(matches!(
+            decode(&wrong_payee),
+            Err(InvoiceError::InvalidSignature(_))
+        ));
+
+        // Missing payment hash, description and truncated fields
+        let no_hash = resigned(&encoded, |_, body| {
+            assert_eq!(body[TIMESTAMP_GROUPS], TAG_PAYMENT_HASH);
+            body.drain(TIMESTAMP_GROUPS..TIMESTAMP_GROUPS + 3 + 52);
+        });
+        assert!(matches!(
+            decode(&no_hash),
+            Err(InvoiceError::MissingField(_))
+        ));
+        let truncated = resigned(&encoded, |_, body| body.extend_from_slice(&[3, 1, 0, 0]));
+        assert!(matches!(
+            decode(&truncated),
+            Err(InvoiceError::InvalidFormat(_))
+        ));
+        let unsupported = resigned(&encoded, |_, body| {
+            let mut features = vec![0u8; 13];
+            features[0] = 1 << 4;
+            push_field(body, TAG_FEATURES, &features).unwrap();
+        });
+        assert!(matches!(
+            decode(&unsupported),
+            Err(InvoiceError::UnsupportedFeature(64))
+        ));
+
+        assert!(decode("lnnova1qqqqqq").is_err());
+        assert!(decode("").is_err());
+    }
+
+    #[test]
+    fn quantum_signatures_are_split_over_fields_and_verified() {
+        let keypair =
+            QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let encoded = encode(&fields(), &node_key(), Some(&keypair)).unwrap();
+        let decoded = decode(&encoded).unwrap();
+        let key = decoded.quantum_key.unwrap();
+        assert_eq!(key.public_key, keypair.public_key);
+        assert_eq!(key.parameters, keypair.parameters);
+
+        // Find the quantum signature fields and corrupt one byte, keeping
+        // the node key's signature valid over the result
+        let forged = resigned(&encoded, |_, body| {
+            let mut pos = TIMESTAMP_GROUPS;
+            while body[pos] != TAG_QUANTUM_SIGNATURE {
+                pos += 3 + ((usize::from(body[pos + 1]) << 5) | usize::from(body[pos + 2]));
+            }
+            body[pos + 10] ^= 1;
+        });
+        assert!(matches!(
+            decode(&forged),
+            Err(InvoiceError::InvalidSignature(_))
+        ));
+
+        // Fields may not follow the quantum signature
+        let appended = resigned(&encoded, |_, body| body.extend_from_slice(&[2, 0, 0]));
+        assert!(matches!(
+            decode(&appended),
+            Err(InvoiceError::InvalidFormat(_))
+        ));
+    }
+}
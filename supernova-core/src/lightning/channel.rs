@@ -16,6 +16,7 @@ use thiserror::Error;
 use tracing::{error, info, warn};
 
 // Import proper types from existing modules
+use crate::script::{Opcode, ScriptBuilder};
 use crate::types::script::Script;
 use secp256k1::{PublicKey, SecretKey as PrivateKey};
 
@@ -462,6 +463,9 @@ impl Channel {
             },
             (Active, ForceClosed) => true,
             (ClosingNegotiation, Closed) => true,
+
+            // Funding aborted (timeout or double-spent inputs) before activation
+            (FundingCreated, Closed) | (FundingSigned, Closed) => true,
             
             // Invalid transitions
             _ => false,
@@ -648,6 +652,101 @@ impl Channel {
         self.transition_state(ChannelState::Active)
     }
 
+    /// Witness script locking the funding output: a 2-of-2 multisig over
+    /// both node keys, sorted so both peers derive the same script
+    pub fn funding_witness_script(&self) -> ChannelResult<Vec<u8>> {
+        let mut keys = vec![
+            self.local_node_id.serialize().to_vec(),
+            self.remote_node_id.serialize().to_vec(),
+        ];
+        keys.sort();
+        crate::script::ScriptBuilder::multisig(2, &keys)
+            .map_err(|e| ChannelError::FundingError(format!("Invalid funding script: {}", e)))
+    }
+
+    /// Witness script of the local balance on our commitment: spendable by
+    /// the local key once the commitment is `to_self_delay` blocks deep
+    pub fn to_local_witness_script(&self) -> Vec<u8> {
+        let spend = checksig_script(&self.local_node_id);
+        ScriptBuilder::relative_timelock(u32::from(self.to_self_delay), &spend)
+    }
+
+    /// Witness script of a pending HTLC: the receiving side claims with the
+    /// payment preimage, the offering side reclaims from `expiry_height`
+    pub fn htlc_witness_script(&self, htlc: &Htlc) -> Vec<u8> {
+        let (claimer, refunder) = if htlc.is_outgoing {
+            (&self.remote_node_id, &self.local_node_id)
+        } else {
+            (&self.local_node_id, &self.remote_node_id)
+        };
+        ScriptBuilder::hash_time_locked(
+            &htlc.payment_hash,
+            &checksig_script(claimer),
+            htlc.expiry_height,
+            &checksig_script(refunder),
+        )
+    }
+
+    /// Script pubkey of the funding output.
+    ///
+    /// Classic channels pay to P2WSH of the witness script; quantum channels
+    /// use the 32-byte SHA3-512 commitment form of wallet outputs instead.
+    pub fn funding_script_pubkey(&self) -> ChannelResult<Vec<u8>> {
+        let witness_script = self.funding_witness_script()?;
+        if self.config.use_quantum_signatures {
+            return Ok(crate::types::transaction::pubkey_commitment(&witness_script));
+        }
+        let script_hash = Sha256::digest(&witness_script);
+        Ok(Script::new_p2wsh(&script_hash).as_bytes().to_vec())
+    }
+
+    /// Record a built funding transaction and move to `FundingCreated`.
+    ///
+    /// `vout` must pay exactly the channel capacity to the funding script.
+    pub fn record_funding(&mut self, funding_tx: &Transaction, vout: u32) -> ChannelResult<()> {
+        if self.state != ChannelState::Initializing {
+            return Err(ChannelError::InvalidState(
+                "Channel must be in initializing state to record funding".to_string(),
+            ));
+        }
+
+        let output = funding_tx.outputs().get(vout as usize).ok_or_else(|| {
+            ChannelError::FundingError(format!("Funding transaction has no output {}", vout))
+        })?;
+        if output.value() != self.capacity_novas {
+            return Err(ChannelError::FundingError(format!(
+                "Funding output pays {} but channel capacity is {}",
+                output.value(),
+                self.capacity_novas
+            )));
+        }
+        if output.script_pubkey() != self.funding_script_pubkey()?.as_slice() {
+            return Err(ChannelError::FundingError(
+                "Funding output does not pay to the channel funding script".to_string(),
+            ));
+        }
+
+        // SECURITY FIX [P1-005]: Update channel state with validation
+        self.transition_state(ChannelState::FundingCreated)?;
+        self.funding_outpoint = Some(OutPoint {
+            txid: funding_tx.txid(),
+            vout,
+        });
+
+        Ok(())
+    }
+
+    /// Abandon a channel whose funding never completed
+    pub fn abort_funding(&mut self) -> ChannelResult<()> {
+        if self.state != ChannelState::FundingCreated && self.state != ChannelState::FundingSigned {
+            return Err(ChannelError::InvalidState(
+                "Only a channel awaiting funding can be aborted".to_string(),
+            ));
+        }
+
+        self.transition_state(ChannelState::Closed)
+    }
+
     /// Create funding transaction
     pub fn create_funding_transaction(
         &mut self,
@@ -661,27 +760,12 @@ impl Channel {
             ));
         }
 
-        // In a real implementation, this would create a 2-of-2 multisig output
-        // and properly calculate the change and fees
-
-        // Create a simple funding transaction structure
+        // The funding output is always output 0. Change and fee handling
+        // against real wallet UTXOs lives in `lightning::funding`.
         let funding_tx = Transaction::new(
             2, // version
             funding_inputs,
-            vec![TxOut::new(
-                self.capacity_novas,
-                Script::new_p2wsh(&[
-                    0x52, // OP_2
-                    0x21, // Push 33 bytes (compressed pubkey length)
-                    // Local pubkey would go here
-                    0x21, // Push 33 bytes
-                    // Remote pubkey would go here
-                    0x52, // OP_2
-                    0xae,
-                ])
-                .as_bytes()
-                .to_vec(),
-            )],
+            vec![TxOut::new(self.capacity_novas, self.funding_script_pubkey()?)],
             0, // lock_time
         );
 
@@ -699,14 +783,7 @@ impl Channel {
             }
         }
 
-        // SECURITY FIX [P1-005]: Update channel state with validation
-        self.transition_state(ChannelState::FundingCreated)?;
-
-        // Set funding outpoint (normally would use the txid of the funding transaction)
-        self.funding_outpoint = Some(OutPoint {
-            txid: [0u8; 32], // Would be funding_tx.txid() in a real implementation
-            vout: 0,
-        });
+        self.record_funding(&funding_tx, 0)?;
 
         Ok(funding_tx)
     }
@@ -723,11 +800,23 @@ impl Channel {
             ChannelError::FundingError("No funding outpoint".to_string())
         })?;
 
-        // In a real implementation, this would:
-        // 1. Create a transaction spending from the funding transaction's 2-of-2 output
-        // 2. Create outputs for each side based on the current balance
-        // 3. Add outputs for any pending HTLCs
-        // 4. Set proper sequence numbers for timelocks
+        // The local balance is delayed so the remote side can still react
+        // to a revoked commitment; each HTLC gets its own output
+        let mut outputs = vec![
+            TxOut::new(
+                self.local_balance_novas,
+                p2wsh(&self.to_local_witness_script()),
+            ),
+            TxOut::new(
+                self.remote_balance_novas,
+                Script::new_p2wpkh(&self.remote_node_id.serialize())
+                    .as_bytes()
+                    .to_vec(),
+            ),
+        ];
+        outputs.extend(self.pending_htlcs.iter().map(|htlc| {
+            TxOut::new(htlc.amount_novas, p2wsh(&self.htlc_witness_script(htlc)))
+        }));
 
         let commitment_tx = Transaction::new(
             2, // version
@@ -737,27 +826,10 @@ impl Channel {
                 Vec::new(), // Script sig (will be filled with signatures)
                 0xffffffff, // Sequence
             )],
-            vec![
-                // Output to local with their balance
-                TxOut::new(
-                    self.local_balance_novas,
-                    Script::new_p2wpkh(&self.local_node_id.serialize())
-                        .as_bytes()
-                        .to_vec(),
-                ),
-                // Output to remote with their balance
-                TxOut::new(
-                    self.remote_balance_novas,
-                    Script::new_p2wpkh(&self.remote_node_id.serialize())
-                        .as_bytes()
-                        .to_vec(),
-                ),
-            ],
+            outputs,
             0, // lock_time
         );
 
-        // In a real implementation, additional outputs would be added for each HTLC
-
         // Store the commitment transaction
         self.commitment_tx = Some(commitment_tx.clone());
 
@@ -1366,6 +1438,21 @@ impl ChannelManager {
     }
 }
 
+/// `<key> OP_CHECKSIG`
+fn checksig_script(key: &PublicKey) -> Vec<u8> {
+    ScriptBuilder::new()
+        .push_data(&key.serialize())
+        .push_opcode(Opcode::OP_CHECKSIG)
+        .build()
+}
+
+/// P2WSH script pubkey of `witness_script`
+fn p2wsh(witness_script: &[u8]) -> Vec<u8> {
+    Script::new_p2wsh(&Sha256::digest(witness_script))
+        .as_bytes()
+        .to_vec()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1540,6 +1627,32 @@ mod tests {
         assert_eq!(channel.state, ChannelState::ForceClosed);
     }
 
+    #[test]
+    fn test_commitment_outputs_are_timelocked() {
+        let mut channel = create_test_channel();
+        channel.create_funding_transaction(vec![], None, 1000).unwrap();
+        channel.sign_funding().unwrap();
+        channel.activate().unwrap();
+        channel.add_htlc([7u8; 32], 1_000_000, 500_000, true).unwrap();
+
+        let commitment = channel.create_commitment_transaction().unwrap();
+        let outputs = commitment.outputs();
+        assert_eq!(outputs.len(), 3);
+
+        let to_local = channel.to_local_witness_script();
+        assert!(to_local.starts_with(&ScriptBuilder::new().check_sequence_verify(144).build()));
+        assert_eq!(outputs[0].pub_key_script, p2wsh(&to_local));
+
+        let htlc = channel.pending_htlcs[0].clone();
+        let htlc_script = channel.htlc_witness_script(&htlc);
+        let refund = ScriptBuilder::new().check_lock_time_verify(500_000).build();
+        assert!(htlc_script
+            .windows(refund.len())
+            .any(|w| w == refund.as_slice()));
+        assert_eq!(outputs[2].value(), 1_000_000);
+        assert_eq!(outputs[2].pub_key_script, p2wsh(&htlc_script));
+    }
+
     /// SECURITY FIX [P1-005]: Test cooperative close transition
     #[test]
     fn test_cooperative_close_transition() {
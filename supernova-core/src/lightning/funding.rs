@@ -0,0 +1,654 @@
+// supernova Lightning Network - Channel Funding Flow
+//
+// This file drives a channel from `Initializing` to `Active`: it selects and
+// locks wallet UTXOs, builds the funding transaction, and follows the chain
+// until the funding output is buried deep enough. Funding that times out or
+// whose inputs are double-spent is aborted and the locked UTXOs released.
+
+use crate::lightning::channel::{Channel, ChannelError, ChannelId};
+use crate::lightning::wallet::{LightningWallet, WalletError, WalletUtxo};
+use crate::types::block::Block;
+use crate::types::transaction::{
+    OutPoint, Transaction, TransactionInput as TxIn, TransactionOutput as TxOut,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Confirmations required before a funded channel becomes active
+pub const DEFAULT_FUNDING_MIN_DEPTH: u32 = 3;
+
+/// Blocks to wait for the funding transaction to confirm (two weeks)
+pub const DEFAULT_FUNDING_TIMEOUT_BLOCKS: u64 = 2016;
+
+/// Default funding fee rate in novas per byte
+pub const DEFAULT_FUNDING_FEE_RATE: u64 = 1;
+
+// Size estimates used for fee calculation
+const TX_OVERHEAD_BYTES: u64 = 10;
+const INPUT_BYTES: u64 = 148;
+const OUTPUT_BYTES: u64 = 43;
+
+/// Error types for channel funding
+#[derive(Debug, Error)]
+pub enum FundingError {
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] WalletError),
+
+    #[error("Channel error: {0}")]
+    Channel(#[from] ChannelError),
+
+    #[error("Channel {0} is already being funded")]
+    AlreadyFunding(ChannelId),
+
+    #[error("Funding of channel {channel_id} timed out after {blocks} blocks")]
+    Timeout { channel_id: ChannelId, blocks: u64 },
+
+    #[error("Funding input {outpoint} of channel {channel_id} was spent by {conflicting_txid}")]
+    DoubleSpent {
+        channel_id: ChannelId,
+        outpoint: OutPoint,
+        conflicting_txid: String,
+    },
+}
+
+/// Funding flow configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingConfig {
+    /// Confirmations before the channel is activated
+    pub min_depth: u32,
+
+    /// Blocks after broadcast before an unconfirmed funding is abandoned
+    pub timeout_blocks: u64,
+
+    /// Fee rate for funding transactions (novas per byte)
+    pub fee_rate: u64,
+}
+
+impl Default for FundingConfig {
+    fn default() -> Self {
+        Self {
+            min_depth: DEFAULT_FUNDING_MIN_DEPTH,
+            timeout_blocks: DEFAULT_FUNDING_TIMEOUT_BLOCKS,
+            fee_rate: DEFAULT_FUNDING_FEE_RATE,
+        }
+    }
+}
+
+/// Progress reported while following the chain
+#[derive(Debug)]
+pub enum FundingUpdate {
+    /// The funding transaction has this many confirmations
+    Confirmed { channel_id: ChannelId, depth: u32 },
+
+    /// The funding reached the configured depth; activate the channel
+    Ready(ChannelId),
+
+    /// Funding was abandoned; the channel must be aborted
+    Failed(ChannelId, FundingError),
+}
+
+impl FundingUpdate {
+    /// Channel this update refers to
+    pub fn channel_id(&self) -> &ChannelId {
+        match self {
+            FundingUpdate::Confirmed { channel_id, .. } => channel_id,
+            FundingUpdate::Ready(channel_id) => channel_id,
+            FundingUpdate::Failed(channel_id, _) => channel_id,
+        }
+    }
+
+    /// Apply the update to the channel's state machine
+    pub fn apply(&self, channel: &mut Channel) -> Result<(), ChannelError> {
+        match self {
+            FundingUpdate::Confirmed { .. } => Ok(()),
+            FundingUpdate::Ready(_) => channel.activate(),
+            FundingUpdate::Failed(..) => channel.abort_funding(),
+        }
+    }
+}
+
+/// A funding transaction awaiting confirmation
+#[derive(Debug, Clone)]
+pub struct PendingFunding {
+    /// Funding transaction as broadcast
+    pub funding_tx: Transaction,
+
+    /// Channel funding output
+    pub funding_outpoint: OutPoint,
+
+    /// Wallet outputs locked as inputs
+    pub inputs: Vec<OutPoint>,
+
+    /// Change returned to the wallet, if any
+    pub change: Option<WalletUtxo>,
+
+    /// Our balance once the channel is active
+    pub local_balance: u64,
+
+    /// Chain height when the funding was broadcast
+    pub broadcast_height: u64,
+
+    /// Height of the block that confirmed the funding
+    pub confirmed_height: Option<u64>,
+}
+
+impl PendingFunding {
+    /// Confirmations at the given tip height
+    pub fn depth(&self, tip_height: u64) -> u32 {
+        match self.confirmed_height {
+            Some(confirmed) if tip_height >= confirmed => {
+                u32::try_from(tip_height - confirmed + 1).unwrap_or(u32::MAX)
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// Tracks channel fundings from broadcast to activation
+pub struct FundingTracker {
+    config: FundingConfig,
+    pending: HashMap<ChannelId, PendingFunding>,
+}
+
+impl FundingTracker {
+    /// Create a new tracker
+    pub fn new(config: FundingConfig) -> Self {
+        Self {
+            config,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Funding configuration
+    pub fn config(&self) -> &FundingConfig {
+        &self.config
+    }
+
+    /// Pending funding for a channel
+    pub fn get(&self, channel_id: &ChannelId) -> Option<&PendingFunding> {
+        self.pending.get(channel_id)
+    }
+
+    /// Number of fundings awaiting activation
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Select and lock wallet UTXOs and build the channel's funding transaction.
+    ///
+    /// On success the channel is in `FundingSigned` and the returned
+    /// transaction is ready to broadcast. On failure no UTXO stays locked.
+    pub fn fund_channel(
+        &mut self,
+        wallet: &mut LightningWallet,
+        channel: &mut Channel,
+        change_script: Vec<u8>,
+        height: u64,
+    ) -> Result<Transaction, FundingError> {
+        let channel_id = channel.id();
+        if self.pending.contains_key(&channel_id) {
+            return Err(FundingError::AlreadyFunding(channel_id));
+        }
+
+        let capacity = channel.capacity_novas;
+        let selected = self.select_inputs(wallet, capacity)?;
+        for (index, utxo) in selected.iter().enumerate() {
+            if let Err(e) = wallet.lock_utxo(&utxo.outpoint) {
+                for locked in &selected[..index] {
+                    wallet.unlock_utxo(&locked.outpoint);
+                }
+                return Err(e.into());
+            }
+        }
+
+        let inputs: Vec<OutPoint> = selected.iter().map(|u| u.outpoint.clone()).collect();
+        match self.build(channel, &selected, change_script) {
+            Ok((funding_tx, change)) => {
+                let funding_outpoint = OutPoint {
+                    txid: funding_tx.txid(),
+                    vout: 0,
+                };
+                info!(
+                    "Funding channel {} with {} novas in {}",
+                    channel_id,
+                    capacity,
+                    hex::encode(funding_outpoint.txid)
+                );
+                self.pending.insert(
+                    channel_id,
+                    PendingFunding {
+                        funding_tx: funding_tx.clone(),
+                        funding_outpoint,
+                        inputs,
+                        change,
+                        local_balance: channel.local_balance_novas,
+                        broadcast_height: height,
+                        confirmed_height: None,
+                    },
+                );
+                Ok(funding_tx)
+            }
+            Err(e) => {
+                for outpoint in &inputs {
+                    wallet.unlock_utxo(outpoint);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Process a block connected to the active chain.
+    ///
+    /// Releases locks of timed-out or conflicted fundings and reports which
+    /// channels should be activated or aborted.
+    pub fn block_connected(
+        &mut self,
+        wallet: &mut LightningWallet,
+        block: &Block,
+    ) -> Vec<FundingUpdate> {
+        let height = block.height();
+        let mut updates = Vec::new();
+
+        for tx in block.transactions() {
+            let txid = tx.hash();
+            let mut conflicted = Vec::new();
+            for (channel_id, pending) in self.pending.iter_mut() {
+                if txid == pending.funding_outpoint.txid {
+                    pending.confirmed_height = Some(height);
+                    continue;
+                }
+                if pending.confirmed_height.is_some() {
+                    continue;
+                }
+                let spent = tx.inputs().iter().find_map(|input| {
+                    let outpoint = OutPoint {
+                        txid: input.prev_tx_hash(),
+                        vout: input.prev_output_index(),
+                    };
+                    pending.inputs.contains(&outpoint).then_some(outpoint)
+                });
+                if let Some(outpoint) = spent {
+                    conflicted.push((channel_id.clone(), outpoint));
+                }
+            }
+
+            for (channel_id, outpoint) in conflicted {
+                if let Some(pending) = self.pending.remove(&channel_id) {
+                    release_inputs(wallet, &pending);
+                    wallet.remove_utxo(&outpoint);
+                    warn!(
+                        "Funding input {} of channel {} double-spent by {}",
+                        outpoint,
+                        channel_id,
+                        hex::encode(txid)
+                    );
+                    updates.push(FundingUpdate::Failed(
+                        channel_id.clone(),
+                        FundingError::DoubleSpent {
+                            channel_id,
+                            outpoint,
+                            conflicting_txid: hex::encode(txid),
+                        },
+                    ));
+                }
+            }
+        }
+
+        let mut finished = Vec::new();
+        for (channel_id, pending) in &self.pending {
+            let depth = pending.depth(height);
+            if depth >= self.config.min_depth {
+                finished.push((channel_id.clone(), None));
+            } else if depth > 0 {
+                updates.push(FundingUpdate::Confirmed {
+                    channel_id: channel_id.clone(),
+                    depth,
+                });
+            } else if height.saturating_sub(pending.broadcast_height) >= self.config.timeout_blocks
+            {
+                finished.push((channel_id.clone(), Some(self.config.timeout_blocks)));
+            }
+        }
+
+        for (channel_id, timed_out) in finished {
+            let Some(pending) = self.pending.remove(&channel_id) else {
+                continue;
+            };
+            match timed_out {
+                None => {
+                    for outpoint in &pending.inputs {
+                        wallet.remove_utxo(outpoint);
+                    }
+                    if let Some(change) = pending.change {
+                        wallet.add_utxo(change);
+                    }
+                    wallet.update_channel_balance(channel_id.clone(), pending.local_balance);
+                    info!(
+                        "Funding of channel {} reached depth {}",
+                        channel_id, self.config.min_depth
+                    );
+                    updates.push(FundingUpdate::Ready(channel_id));
+                }
+                Some(blocks) => {
+                    release_inputs(wallet, &pending);
+                    warn!(
+                        "Funding of channel {} timed out after {} blocks",
+                        channel_id, blocks
+                    );
+                    updates.push(FundingUpdate::Failed(
+                        channel_id.clone(),
+                        FundingError::Timeout { channel_id, blocks },
+                    ));
+                }
+            }
+        }
+
+        updates
+    }
+
+    /// Process a block disconnected from the active chain (reorg)
+    pub fn block_disconnected(&mut self, block: &Block) {
+        let height = block.height();
+        for pending in self.pending.values_mut() {
+            if pending
+                .confirmed_height
+                .is_some_and(|confirmed| confirmed >= height)
+            {
+                pending.confirmed_height = None;
+            }
+        }
+    }
+
+    /// Largest-first selection covering the capacity plus the fee
+    fn select_inputs(
+        &self,
+        wallet: &LightningWallet,
+        capacity: u64,
+    ) -> Result<Vec<WalletUtxo>, FundingError> {
+        let mut selected = Vec::new();
+        let mut total = 0u64;
+        for utxo in wallet.list_unspent() {
+            selected.push(utxo.clone());
+            total = total.saturating_add(utxo.value);
+            let needed = capacity.saturating_add(self.fee(selected.len(), 2));
+            if total >= needed {
+                return Ok(selected);
+            }
+        }
+
+        Err(WalletError::InsufficientFunds(format!(
+            "Funding needs {} novas plus fees, {} available",
+            capacity, total
+        ))
+        .into())
+    }
+
+    /// Build the funding transaction, recording it on the channel
+    fn build(
+        &self,
+        channel: &mut Channel,
+        selected: &[WalletUtxo],
+        change_script: Vec<u8>,
+    ) -> Result<(Transaction, Option<WalletUtxo>), FundingError> {
+        let capacity = channel.capacity_novas;
+        let total: u64 = selected.iter().map(|u| u.value).sum();
+        let inputs: Vec<TxIn> = selected
+            .iter()
+            .map(|u| TxIn::new(u.outpoint.txid, u.outpoint.vout, Vec::new(), 0xffffffff))
+            .collect();
+
+        let mut outputs = vec![TxOut::new(capacity, channel.funding_script_pubkey()?)];
+        let change_value = total
+            .saturating_sub(capacity)
+            .saturating_sub(self.fee(selected.len(), 2));
+        // Dust change is left to the miner rather than creating an output
+        let has_change = change_value > channel.config.dust_limit_novas;
+        if has_change {
+            outputs.push(TxOut::new(change_value, change_script.clone()));
+        }
+
+        let funding_tx = Transaction::new(2, inputs, outputs, 0);
+        channel.record_funding(&funding_tx, 0)?;
+        // Commitment signatures are exchanged before the funding is broadcast
+        channel.sign_funding()?;
+
+        let change = has_change.then(|| WalletUtxo {
+            outpoint: OutPoint {
+                txid: funding_tx.txid(),
+                vout: 1,
+            },
+            value: change_value,
+            script_pubkey: change_script,
+        });
+        Ok((funding_tx, change))
+    }
+
+    fn fee(&self, inputs: usize, outputs: usize) -> u64 {
+        let size = TX_OVERHEAD_BYTES + INPUT_BYTES * inputs as u64 + OUTPUT_BYTES * outputs as u64;
+        size.saturating_mul(self.config.fee_rate)
+    }
+}
+
+/// Unlock every input of an abandoned funding
+fn release_inputs(wallet: &mut LightningWallet, pending: &PendingFunding) {
+    for outpoint in &pending.inputs {
+        wallet.unlock_utxo(outpoint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::channel::ChannelState;
+    use crate::types::block::BlockHeader;
+    use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    const CAPACITY: u64 = 1_000_000;
+
+    fn test_channel() -> Channel {
+        let secp = Secp256k1::new();
+        let local = PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[1u8; 32]).unwrap());
+        let remote = PublicKey::from_secret_key(&secp, &SecretKey::from_slice(&[2u8; 32]).unwrap());
+        Channel::new(local, remote, CAPACITY, true, false)
+    }
+
+    fn utxo(seed: u8, value: u64) -> WalletUtxo {
+        WalletUtxo {
+            outpoint: OutPoint {
+                txid: [seed; 32],
+                vout: 0,
+            },
+            value,
+            script_pubkey: vec![seed; 32],
+        }
+    }
+
+    fn funded_wallet() -> LightningWallet {
+        let mut wallet = LightningWallet::new_test_wallet(0).unwrap();
+        wallet.add_utxo(utxo(1, 700_000));
+        wallet.add_utxo(utxo(2, 600_000));
+        wallet
+    }
+
+    /// Regtest chain: blocks are only their height and transactions
+    fn block_at(height: u64, txs: Vec<Transaction>) -> Block {
+        Block::new(
+            BlockHeader::new_with_height(1, [0; 32], [0; 32], 0, 0x207fffff, 0, height),
+            txs,
+        )
+    }
+
+    fn apply(updates: &[FundingUpdate], channel: &mut Channel) {
+        for update in updates {
+            if update.channel_id() == &channel.id() {
+                update.apply(channel).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn funding_activates_channel_after_three_confirmations() {
+        let mut wallet = funded_wallet();
+        let mut channel = test_channel();
+        let mut tracker = FundingTracker::new(FundingConfig::default());
+
+        let funding_tx = tracker
+            .fund_channel(&mut wallet, &mut channel, vec![9u8; 32], 100)
+            .unwrap();
+        assert_eq!(channel.state, ChannelState::FundingSigned);
+        assert_eq!(funding_tx.inputs().len(), 2);
+        assert_eq!(funding_tx.outputs()[0].value(), CAPACITY);
+        assert_eq!(
+            funding_tx.outputs()[0].script_pubkey(),
+            channel.funding_script_pubkey().unwrap()
+        );
+        assert!(wallet.is_utxo_locked(&utxo(1, 0).outpoint));
+        assert!(wallet.is_utxo_locked(&utxo(2, 0).outpoint));
+        assert!(wallet.list_unspent().is_empty());
+
+        let updates =
+            tracker.block_connected(&mut wallet, &block_at(101, vec![funding_tx.clone()]));
+        assert!(matches!(
+            updates[..],
+            [FundingUpdate::Confirmed { depth: 1, .. }]
+        ));
+        tracker.block_connected(&mut wallet, &block_at(102, vec![]));
+        assert_eq!(channel.state, ChannelState::FundingSigned);
+
+        let updates = tracker.block_connected(&mut wallet, &block_at(103, vec![]));
+        assert!(matches!(updates[..], [FundingUpdate::Ready(_)]));
+        apply(&updates, &mut channel);
+        assert_eq!(channel.state, ChannelState::Active);
+        assert_eq!(tracker.pending_count(), 0);
+
+        // Inputs are spent; the change output now belongs to the wallet
+        let change_value = 1_300_000 - CAPACITY - tracker.fee(2, 2);
+        assert!(wallet.get_utxo(&utxo(1, 0).outpoint).is_none());
+        assert_eq!(wallet.get_on_chain_balance(), change_value);
+        assert_eq!(
+            wallet.list_unspent()[0].outpoint,
+            OutPoint {
+                txid: funding_tx.txid(),
+                vout: 1
+            }
+        );
+    }
+
+    #[test]
+    fn reorg_of_confirming_block_resets_depth() {
+        let mut wallet = funded_wallet();
+        let mut channel = test_channel();
+        let mut tracker = FundingTracker::new(FundingConfig::default());
+        let funding_tx = tracker
+            .fund_channel(&mut wallet, &mut channel, vec![9u8; 32], 100)
+            .unwrap();
+
+        let confirming = block_at(101, vec![funding_tx.clone()]);
+        tracker.block_connected(&mut wallet, &confirming);
+        tracker.block_disconnected(&confirming);
+        assert_eq!(tracker.get(&channel.id()).unwrap().depth(101), 0);
+
+        tracker.block_connected(&mut wallet, &block_at(101, vec![]));
+        tracker.block_connected(&mut wallet, &block_at(102, vec![funding_tx]));
+        let updates = tracker.block_connected(&mut wallet, &block_at(103, vec![]));
+        assert!(matches!(
+            updates[..],
+            [FundingUpdate::Confirmed { depth: 2, .. }]
+        ));
+    }
+
+    #[test]
+    fn timeout_releases_locked_utxos() {
+        let mut wallet = funded_wallet();
+        let mut channel = test_channel();
+        let config = FundingConfig {
+            timeout_blocks: 10,
+            ..FundingConfig::default()
+        };
+        let mut tracker = FundingTracker::new(config);
+        tracker
+            .fund_channel(&mut wallet, &mut channel, vec![9u8; 32], 100)
+            .unwrap();
+
+        assert!(tracker
+            .block_connected(&mut wallet, &block_at(109, vec![]))
+            .is_empty());
+        let updates = tracker.block_connected(&mut wallet, &block_at(110, vec![]));
+        assert!(matches!(
+            updates[..],
+            [FundingUpdate::Failed(
+                _,
+                FundingError::Timeout { blocks: 10, .. }
+            )]
+        ));
+        apply(&updates, &mut channel);
+
+        assert_eq!(channel.state, ChannelState::Closed);
+        assert!(!wallet.is_utxo_locked(&utxo(1, 0).outpoint));
+        assert!(!wallet.is_utxo_locked(&utxo(2, 0).outpoint));
+        assert_eq!(wallet.list_unspent().len(), 2);
+        assert_eq!(wallet.get_on_chain_balance(), 1_300_000);
+    }
+
+    #[test]
+    fn conflicting_spend_aborts_funding() {
+        let mut wallet = funded_wallet();
+        let mut channel = test_channel();
+        let mut tracker = FundingTracker::new(FundingConfig::default());
+        tracker
+            .fund_channel(&mut wallet, &mut channel, vec![9u8; 32], 100)
+            .unwrap();
+
+        let conflict = Transaction::new(
+            2,
+            vec![TxIn::new([2u8; 32], 0, Vec::new(), 0xffffffff)],
+            vec![TxOut::new(590_000, vec![7u8; 32])],
+            0,
+        );
+        let updates = tracker.block_connected(&mut wallet, &block_at(101, vec![conflict]));
+        assert!(matches!(
+            &updates[..],
+            [FundingUpdate::Failed(_, FundingError::DoubleSpent { outpoint, .. })]
+                if *outpoint == utxo(2, 0).outpoint
+        ));
+        apply(&updates, &mut channel);
+
+        assert_eq!(channel.state, ChannelState::Closed);
+        assert_eq!(tracker.pending_count(), 0);
+        // The untouched input is spendable again; the conflicted one is gone
+        let unspent: Vec<_> = wallet.list_unspent().into_iter().cloned().collect();
+        assert_eq!(unspent, vec![utxo(1, 700_000)]);
+        assert_eq!(wallet.get_on_chain_balance(), 700_000);
+    }
+
+    #[test]
+    fn insufficient_funds_leaves_nothing_locked() {
+        let mut wallet = LightningWallet::new_test_wallet(0).unwrap();
+        wallet.add_utxo(utxo(1, CAPACITY));
+        let mut channel = test_channel();
+        let mut tracker = FundingTracker::new(FundingConfig::default());
+
+        let result = tracker.fund_channel(&mut wallet, &mut channel, vec![9u8; 32], 100);
+        assert!(matches!(
+            result,
+            Err(FundingError::Wallet(WalletError::InsufficientFunds(_)))
+        ));
+        assert!(!wallet.is_utxo_locked(&utxo(1, 0).outpoint));
+        assert_eq!(channel.state, ChannelState::Initializing);
+    }
+
+    #[test]
+    fn quantum_channels_fund_a_commitment_output() {
+        let mut wallet = funded_wallet();
+        let mut channel = test_channel();
+        channel.config.use_quantum_signatures = true;
+        let mut tracker = FundingTracker::new(FundingConfig::default());
+
+        let funding_tx = tracker
+            .fund_channel(&mut wallet, &mut channel, vec![9u8; 32], 100)
+            .unwrap();
+        assert_eq!(funding_tx.outputs()[0].script_pubkey().len(), 32);
+    }
+}
@@ -0,0 +1,563 @@
+// supernova Lightning Network - Graph and Mission Control Export
+//
+// This file exports our view of the Lightning Network for external
+// pathfinding and analysis tools: the known network graph as JSON or
+// Graphviz DOT, and mission control (our local payment success/failure
+// history per channel), which can be imported again after a reinstall.
+//
+// Both graph formats are produced as a sequence of chunks so that large
+// graphs can be streamed to a client instead of being rendered into a
+// single buffer.
+
+use crate::lightning::channel::ChannelId;
+use crate::lightning::router::{
+    ChannelHistory, ChannelInfo, NetworkGraph, NodeEnvironmentalData, NodeId, Router, RoutingError,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::iter;
+
+/// Version of the [`GraphExport`] JSON schema
+pub const GRAPH_EXPORT_VERSION: u32 = 1;
+
+/// Version of the [`MissionControlExport`] JSON schema
+pub const MISSION_CONTROL_VERSION: u32 = 1;
+
+/// The known network graph.
+///
+/// Serialized as JSON this is the documented export schema:
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "last_update": <unix seconds>,
+///   "nodes": [{ "node_id", "alias", "features": [u32], "environmental": null | {
+///       "renewable_percentage", "carbon_footprint_per_tx", "green_certified" } }],
+///   "channels": [{ "channel_id": <hex>, "node1", "node2", "capacity": <novas>,
+///       "policy": { "base_fee_mnova", "fee_rate_millionths", "cltv_expiry_delta",
+///                   "is_active", "last_update" } }]
+/// }
+/// ```
+///
+/// Channels are directed: `policy` applies to payments forwarded from
+/// `node1` to `node2`. Only announced channels are exported.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphExport {
+    /// Schema version, [`GRAPH_EXPORT_VERSION`]
+    pub version: u32,
+
+    /// Time of the last change to the graph
+    pub last_update: u64,
+
+    /// Known nodes, ordered by node ID
+    pub nodes: Vec<GraphNode>,
+
+    /// Announced channels, ordered by channel ID
+    pub channels: Vec<GraphChannel>,
+}
+
+/// A node in a [`GraphExport`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Node ID
+    pub node_id: String,
+
+    /// Announced alias, empty if the node has not announced one
+    pub alias: String,
+
+    /// Advertised feature bits
+    pub features: Vec<u32>,
+
+    /// Announced environmental data
+    pub environmental: Option<NodeEnvironmentalData>,
+}
+
+/// A directed channel in a [`GraphExport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphChannel {
+    /// Channel ID (hex)
+    pub channel_id: String,
+
+    /// Forwarding node
+    pub node1: String,
+
+    /// Receiving node
+    pub node2: String,
+
+    /// Channel capacity in nova units
+    pub capacity: u64,
+
+    /// Forwarding policy from `node1` to `node2`
+    pub policy: ChannelPolicy,
+}
+
+/// Forwarding policy of a [`GraphChannel`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelPolicy {
+    /// Base fee in millinova
+    pub base_fee_mnova: u32,
+
+    /// Fee rate in parts per million
+    pub fee_rate_millionths: u32,
+
+    /// CLTV expiry delta
+    pub cltv_expiry_delta: u16,
+
+    /// Whether the channel is active
+    pub is_active: bool,
+
+    /// Last update timestamp
+    pub last_update: u64,
+}
+
+impl From<&ChannelInfo> for GraphChannel {
+    fn from(channel: &ChannelInfo) -> Self {
+        Self {
+            channel_id: channel.channel_id.to_hex(),
+            node1: channel.source.to_string(),
+            node2: channel.destination.to_string(),
+            capacity: channel.capacity,
+            policy: ChannelPolicy {
+                base_fee_mnova: channel.base_fee_mnova,
+                fee_rate_millionths: channel.fee_rate_millionths,
+                cltv_expiry_delta: channel.cltv_expiry_delta,
+                is_active: channel.is_active,
+                last_update: channel.last_update,
+            },
+        }
+    }
+}
+
+impl GraphExport {
+    /// Snapshot of the public part of `graph`
+    pub fn from_graph(graph: &NetworkGraph) -> Self {
+        let mut nodes: Vec<GraphNode> = graph
+            .nodes()
+            .map(|node_id| {
+                let announcement = graph
+                    .node_announcement(node_id)
+                    .cloned()
+                    .unwrap_or_default();
+                GraphNode {
+                    node_id: node_id.to_string(),
+                    alias: announcement.alias,
+                    features: announcement.features,
+                    environmental: announcement.environmental,
+                }
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+
+        let mut channels: Vec<GraphChannel> =
+            graph.public_channels().map(GraphChannel::from).collect();
+        channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+        Self {
+            version: GRAPH_EXPORT_VERSION,
+            last_update: graph.last_update(),
+            nodes,
+            channels,
+        }
+    }
+
+    /// The export as JSON, one node or channel per chunk. Concatenated, the
+    /// chunks are exactly `serde_json::to_string(&self)`.
+    pub fn into_json_chunks(self) -> impl Iterator<Item = String> {
+        let header = format!(
+            "{{\"version\":{},\"last_update\":{},\"nodes\":[",
+            self.version, self.last_update
+        );
+        let nodes = self
+            .nodes
+            .into_iter()
+            .enumerate()
+            .map(|(index, node)| json_element(index, &node));
+        let channels = self
+            .channels
+            .into_iter()
+            .enumerate()
+            .map(|(index, channel)| json_element(index, &channel));
+
+        iter::once(header)
+            .chain(nodes)
+            .chain(iter::once("],\"channels\":[".to_string()))
+            .chain(channels)
+            .chain(iter::once("]}".to_string()))
+    }
+
+    /// The export as a Graphviz DOT digraph, one statement per chunk. Nodes
+    /// are labelled with their alias; edges carry capacity and fees, and
+    /// inactive channels are drawn dashed.
+    pub fn into_dot_chunks(self) -> impl Iterator<Item = String> {
+        let nodes = self.nodes.into_iter().map(|node| {
+            let label = if node.alias.is_empty() {
+                &node.node_id
+            } else {
+                &node.alias
+            };
+            format!(
+                "  {} [label={}];\n",
+                dot_string(&node.node_id),
+                dot_string(label)
+            )
+        });
+        let channels = self.channels.into_iter().map(|channel| {
+            format!(
+                "  {} -> {} [label={}, capacity={}, base_fee_mnova={}, fee_rate_millionths={}, cltv_expiry_delta={}{}];\n",
+                dot_string(&channel.node1),
+                dot_string(&channel.node2),
+                dot_string(&channel.channel_id),
+                channel.capacity,
+                channel.policy.base_fee_mnova,
+                channel.policy.fee_rate_millionths,
+                channel.policy.cltv_expiry_delta,
+                if channel.policy.is_active {
+                    ""
+                } else {
+                    ", style=dashed"
+                },
+            )
+        });
+
+        iter::once("digraph lightning {\n".to_string())
+            .chain(nodes)
+            .chain(channels)
+            .chain(iter::once("}\n".to_string()))
+    }
+}
+
+/// `item` as a JSON array element, preceded by a comma unless it is first
+fn json_element<T: Serialize>(index: usize, item: &T) -> String {
+    // Plain structs with string keys always serialize
+    let json = serde_json::to_string(item).unwrap_or_else(|_| "null".to_string());
+    if index == 0 {
+        json
+    } else {
+        format!(",{}", json)
+    }
+}
+
+/// `value` as a quoted DOT ID
+fn dot_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+/// Local payment history per channel, for export and import.
+///
+/// With `anonymized` set, `from` and `to` are salted SHA-256 hashes of the
+/// node IDs. The salt is random per export, so a node hashes identically
+/// within one export but cannot be linked across exports. Anonymized exports
+/// are for analysis only and cannot be imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissionControlExport {
+    /// Schema version, [`MISSION_CONTROL_VERSION`]
+    pub version: u32,
+
+    /// Whether node IDs are hashed
+    pub anonymized: bool,
+
+    /// History per channel, ordered by channel ID
+    pub entries: Vec<MissionControlEntry>,
+}
+
+/// Payment history of one channel in a [`MissionControlExport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissionControlEntry {
+    /// Channel ID (hex)
+    pub channel_id: String,
+
+    /// Forwarding node, if the channel is still in the graph
+    pub from: Option<String>,
+
+    /// Receiving node, if the channel is still in the graph
+    pub to: Option<String>,
+
+    /// Number of successful payments
+    pub successes: u64,
+
+    /// Number of failed payments
+    pub failures: u64,
+
+    /// Last success time
+    pub last_success: Option<u64>,
+
+    /// Last failure time
+    pub last_failure: Option<u64>,
+}
+
+impl MissionControlExport {
+    /// Export the payment history recorded by `router`
+    pub fn from_router(router: &Router, anonymize: bool) -> Self {
+        let mut salt = [0u8; 32];
+        if anonymize {
+            rand::thread_rng().fill_bytes(&mut salt);
+        }
+        let node_label = |node_id: &NodeId| {
+            if anonymize {
+                let mut hasher = Sha256::new();
+                hasher.update(salt);
+                hasher.update(node_id.as_str().as_bytes());
+                hex::encode(hasher.finalize())
+            } else {
+                node_id.to_string()
+            }
+        };
+
+        let mut entries: Vec<MissionControlEntry> = router
+            .channel_history()
+            .into_iter()
+            .map(|history| {
+                let channel = router.graph().get_channel(&history.channel_id, true);
+                MissionControlEntry {
+                    channel_id: history.channel_id.to_hex(),
+                    from: channel.map(|channel| node_label(&channel.source)),
+                    to: channel.map(|channel| node_label(&channel.destination)),
+                    successes: history.successes,
+                    failures: history.failures,
+                    last_success: history.last_success,
+                    last_failure: history.last_failure,
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+
+        Self {
+            version: MISSION_CONTROL_VERSION,
+            anonymized: anonymize,
+            entries,
+        }
+    }
+
+    /// Seed `router`'s history from this export. Channels the router already
+    /// has observations for keep them. Returns the number of entries taken.
+    pub fn import_into(&self, router: &mut Router) -> Result<usize, RoutingError> {
+        if self.version != MISSION_CONTROL_VERSION {
+            return Err(RoutingError::ImportError(format!(
+                "unsupported mission control version {}",
+                self.version
+            )));
+        }
+        if self.anonymized {
+            return Err(RoutingError::ImportError(
+                "anonymized mission control cannot be imported".to_string(),
+            ));
+        }
+
+        let histories = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let channel_id = ChannelId::from_hex(&entry.channel_id).map_err(|e| {
+                    RoutingError::ImportError(format!("channel {}: {}", entry.channel_id, e))
+                })?;
+                Ok(ChannelHistory {
+                    channel_id,
+                    successes: entry.successes,
+                    failures: entry.failures,
+                    last_success: entry.last_success,
+                    last_failure: entry.last_failure,
+                })
+            })
+            .collect::<Result<Vec<_>, RoutingError>>()?;
+
+        Ok(histories
+            .iter()
+            .filter(|history| router.seed_channel_history(history))
+            .count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lightning::router::NodeAnnouncement;
+    use std::collections::HashMap;
+
+    fn node(index: usize) -> NodeId {
+        NodeId::new(format!("node{:02}", index))
+    }
+
+    fn channel(id: u8, source: NodeId, destination: NodeId, base_fee_mnova: u32) -> ChannelInfo {
+        ChannelInfo {
+            channel_id: ChannelId::from_bytes([id; 32]),
+            source,
+            destination,
+            capacity: 1_000_000,
+            base_fee_mnova,
+            fee_rate_millionths: 100,
+            cltv_expiry_delta: 40,
+            is_active: id % 7 != 0,
+            last_update: 1_700_000_000 + id as u64,
+        }
+    }
+
+    /// 50 nodes in a ring with chords, every third one announcing metadata
+    fn synthetic_router() -> Router {
+        let mut router = Router::new();
+        for i in 0..50 {
+            if i % 3 == 0 {
+                router.update_node(
+                    node(i),
+                    NodeAnnouncement {
+                        alias: format!("node \"{}\"", i),
+                        features: vec![1, 9, 15],
+                        environmental: Some(NodeEnvironmentalData {
+                            renewable_percentage: i as f64 * 2.0,
+                            carbon_footprint_per_tx: 0.25,
+                            green_certified: i % 2 == 0,
+                        }),
+                    },
+                );
+            }
+        }
+        let mut id = 1u8;
+        for i in 0..50 {
+            router.update_channel(channel(id, node(i), node((i + 1) % 50), 1_000), false);
+            id += 1;
+            if i % 5 == 0 {
+                router.update_channel(channel(id, node(i), node((i + 17) % 50), 2_000), false);
+                id += 1;
+            }
+        }
+        // Private channels stay out of the export
+        router.update_channel(channel(255, node(0), node(25), 0), true);
+        router
+    }
+
+    #[test]
+    fn graph_json_round_trips_through_the_stream() {
+        let router = synthetic_router();
+        let export = GraphExport::from_graph(router.graph());
+        assert_eq!(export.nodes.len(), 50);
+        assert_eq!(export.channels.len(), 60);
+        assert!(export.nodes.iter().any(|node| node.environmental.is_some()));
+
+        let streamed: String = export.clone().into_json_chunks().collect();
+        assert_eq!(streamed, serde_json::to_string(&export).unwrap());
+        let parsed: GraphExport = serde_json::from_str(&streamed).unwrap();
+        assert_eq!(parsed, export);
+
+        // The schema fields are stable
+        let value: serde_json::Value = serde_json::from_str(&streamed).unwrap();
+        assert_eq!(value["version"], GRAPH_EXPORT_VERSION);
+        assert_eq!(value["nodes"][0]["node_id"], "node00");
+        assert_eq!(value["nodes"][0]["features"], serde_json::json!([1, 9, 15]));
+        assert!(value["channels"][0]["policy"]["fee_rate_millionths"].is_u64());
+    }
+
+    #[test]
+    fn graph_dot_output_is_well_formed() {
+        let export = GraphExport::from_graph(synthetic_router().graph());
+        let chunks: Vec<String> = export.clone().into_dot_chunks().collect();
+        let dot = chunks.concat();
+
+        assert!(dot.starts_with("digraph lightning {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches('{').count(), 1);
+        assert_eq!(dot.matches(" -> ").count(), export.channels.len());
+        // One statement per node and channel between the braces
+        assert_eq!(chunks.len(), export.nodes.len() + export.channels.len() + 2);
+        for statement in &chunks[1..chunks.len() - 1] {
+            assert!(statement.starts_with("  \""));
+            assert!(statement.ends_with("];\n"));
+            // Every quote outside an escape sequence is balanced
+            let unescaped = statement.replace("\\\\", "").replace("\\\"", "");
+            assert_eq!(unescaped.matches('"').count() % 2, 0);
+        }
+        // Aliases with quotes are escaped
+        assert!(dot.contains("\"node00\" [label=\"node \\\"0\\\"\"];"));
+        assert!(dot.contains("\"node01\" [label=\"node01\"];"));
+        assert!(dot.contains("style=dashed"));
+    }
+
+    #[test]
+    fn anonymized_mission_control_hashes_nodes_consistently() {
+        let mut router = synthetic_router();
+        // node01 forwards on the ring and receives from node00
+        for id in [1u8, 2, 3] {
+            let channel_id = ChannelId::from_bytes([id; 32]);
+            router.record_payment_result(&channel_id, true);
+            router.record_payment_result(&channel_id, false);
+        }
+
+        let plain = MissionControlExport::from_router(&router, false);
+        let anonymized = MissionControlExport::from_router(&router, true);
+        assert!(anonymized.anonymized);
+        assert_eq!(anonymized.entries.len(), 3);
+
+        let mut hashes: HashMap<String, String> = HashMap::new();
+        for (clear, hidden) in plain.entries.iter().zip(&anonymized.entries) {
+            assert_eq!(clear.channel_id, hidden.channel_id);
+            assert_eq!(
+                (clear.successes, clear.failures),
+                (hidden.successes, hidden.failures)
+            );
+            for (id, hash) in [(&clear.from, &hidden.from), (&clear.to, &hidden.to)] {
+                let (id, hash) = (id.clone().unwrap(), hash.clone().unwrap());
+                assert_ne!(id, hash);
+                assert_eq!(hash.len(), 64);
+                assert_eq!(hashes.entry(id).or_insert_with(|| hash.clone()), &hash);
+            }
+        }
+        // node00 and node01 appear in two entries each
+        assert!(hashes.len() < 2 * anonymized.entries.len());
+
+        let json = serde_json::to_string(&anonymized).unwrap();
+        assert!(!json.contains("node0"));
+
+        // A fresh salt per export keeps exports unlinkable
+        let again = MissionControlExport::from_router(&router, true);
+        assert_ne!(again.entries[0].from, anonymized.entries[0].from);
+    }
+
+    #[test]
+    fn imported_mission_control_steers_route_choice() {
+        let local = NodeId::new("local".to_string());
+        let build = || {
+            let mut router = Router::new();
+            router.set_local_node(local.clone());
+            // Via A is slightly cheaper than via B
+            router.update_channel(channel(1, local.clone(), node(1), 1_000), false);
+            router.update_channel(channel(2, node(1), node(9), 1_000), false);
+            router.update_channel(channel(3, local.clone(), node(2), 1_000), false);
+            router.update_channel(channel(4, node(2), node(9), 1_500), false);
+            router
+        };
+        let first_hop = |router: &Router| {
+            router
+                .find_route(node(9).as_str(), 50_000, &[])
+                .unwrap()
+                .hops[0]
+                .channel_id
+                .clone()
+        };
+
+        let mut before_reinstall = build();
+        assert_eq!(first_hop(&before_reinstall), ChannelId::from_bytes([1; 32]));
+        for _ in 0..3 {
+            before_reinstall.record_payment_result(&ChannelId::from_bytes([2; 32]), false);
+        }
+        assert_eq!(first_hop(&before_reinstall), ChannelId::from_bytes([3; 32]));
+        let export = MissionControlExport::from_router(&before_reinstall, false);
+
+        let mut reinstalled = build();
+        assert_eq!(first_hop(&reinstalled), ChannelId::from_bytes([1; 32]));
+        let json = serde_json::to_string(&export).unwrap();
+        let imported: MissionControlExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(imported.import_into(&mut reinstalled).unwrap(), 1);
+        assert_eq!(first_hop(&reinstalled), ChannelId::from_bytes([3; 32]));
+        // Importing again leaves the seeded history alone
+        assert_eq!(imported.import_into(&mut reinstalled).unwrap(), 0);
+
+        let anonymized = MissionControlExport::from_router(&before_reinstall, true);
+        assert!(matches!(
+            anonymized.import_into(&mut build()),
+            Err(RoutingError::ImportError(_))
+        ));
+    }
+}
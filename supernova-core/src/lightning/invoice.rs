@@ -3,13 +3,25 @@
 // This file contains the implementation of Lightning Network payment invoices,
 // including invoice generation, parsing, and verification.
 
+use crate::crypto::quantum::QuantumKeyPair;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Nonce,
+};
+use hkdf::Hkdf;
 use rand::{thread_rng, RngCore};
+use secp256k1::SecretKey;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::path::Path;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
+use zeroize::Zeroizing;
 
 // Import shared payment types
+use super::bolt11;
 use super::payment::{PaymentHash, PaymentPreimage};
 
 /// Error types for invoice operations
@@ -41,10 +53,50 @@ pub enum InvoiceError {
 
     #[error("Unsupported feature bit: {0}")]
     UnsupportedFeature(u32),
+
+    #[error("Invoice store error: {0}")]
+    Storage(String),
+
+    #[error("Invoice store could not be decrypted (wrong wallet seed or corrupt file)")]
+    Decryption,
+}
+
+/// HKDF salt and info for the invoice store key
+const INVOICE_STORE_SALT: &[u8] = b"supernova-lightning-invoice-store";
+const INVOICE_STORE_INFO: &[u8] = b"invoice-secrets-v1";
+
+/// On-disk format version of the invoice store; version 2 added signed
+/// invoice encodings
+const INVOICE_STORE_VERSION: u32 = 2;
+
+/// Key sealing invoice preimages and payment secrets at rest, derived from
+/// the wallet seed so the store is only readable by the owning wallet.
+pub struct InvoiceStoreKey(Zeroizing<[u8; 32]>);
+
+impl InvoiceStoreKey {
+    /// Derive the store key from the wallet seed.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, InvoiceError> {
+        let hkdf = Hkdf::<Sha256>::new(Some(INVOICE_STORE_SALT), seed);
+        let mut key = Zeroizing::new([0u8; 32]);
+        hkdf.expand(INVOICE_STORE_INFO, key.as_mut())
+            .map_err(|e| InvoiceError::Storage(e.to_string()))?;
+        Ok(Self(key))
+    }
+
+    fn cipher(&self) -> Result<ChaCha20Poly1305, InvoiceError> {
+        ChaCha20Poly1305::new_from_slice(self.0.as_ref())
+            .map_err(|e| InvoiceError::Storage(e.to_string()))
+    }
+}
+
+impl fmt::Debug for InvoiceStoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InvoiceStoreKey(<redacted>)")
+    }
 }
 
 /// Route hint for private channels
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RouteHint {
     /// Node ID
     pub node_id: String,
@@ -106,6 +158,9 @@ pub struct Invoice {
 
     /// Timestamp at which the invoice was settled, if any
     settled_time: Option<u64>,
+
+    /// Signed BOLT11 encoding, dropped when a signed field changes
+    encoded: Option<String>,
 }
 
 impl Invoice {
@@ -139,6 +194,7 @@ impl Invoice {
             is_private,
             settled: false,
             settled_time: None,
+            encoded: None,
         }
     }
 
@@ -226,6 +282,7 @@ impl Invoice {
             is_private: false,
             settled: false,
             settled_time: None,
+            encoded: None,
         })
     }
 
@@ -277,118 +334,53 @@ impl Invoice {
             is_private: false,
             settled: false,
             settled_time: None,
+            encoded: None,
         })
     }
 
-    /// Invoice string encoding prefix/version tag.
-    const ENCODING_PREFIX: &'static str = "snova1";
-
-    /// Parse an invoice from its encoded string form (see [`Invoice::to_string`]
-    /// for the corresponding encoder).
+    /// Parse a BOLT11 invoice string (see [`Invoice::to_string`] for the
+    /// encoder).
     ///
-    /// Performs real field validation rather than a placeholder: the payment
-    /// hash must be a well-formed 32-byte hex string, the amount must be
-    /// non-zero, and the invoice must not already be expired.
+    /// The checksum and signatures must verify and the invoice must not have
+    /// expired; tagged fields of unknown types are skipped. The destination
+    /// is the node key that signed the invoice, and the invoice counts as
+    /// private when it carries route hints.
     ///
     /// Note: like a real BOLT11 invoice handed to a payer, the encoded form
     /// never carries the payment preimage (only the invoice creator knows
     /// it), so a parsed [`Invoice`] holds a zeroed placeholder preimage that
     /// must not be relied upon for payment settlement.
     pub fn from_str(invoice_str: &str) -> Result<Self, InvoiceError> {
-        let mut parts = invoice_str.split(':');
-
-        let prefix = parts
-            .next()
-            .ok_or_else(|| InvoiceError::InvalidFormat("Empty invoice string".to_string()))?;
-        if prefix != Self::ENCODING_PREFIX {
-            return Err(InvoiceError::InvalidFormat(format!(
-                "Unrecognized invoice prefix: {}",
-                prefix
-            )));
-        }
-
-        let fields: Vec<&str> = parts.collect();
-        if fields.len() != 9 {
-            return Err(InvoiceError::InvalidFormat(format!(
-                "Expected 9 invoice fields, found {}",
-                fields.len()
-            )));
-        }
-
-        let amount_mnova: u64 = fields[0]
-            .parse()
-            .map_err(|_| InvoiceError::InvalidAmount(format!("Invalid amount: {}", fields[0])))?;
-        if amount_mnova == 0 {
-            return Err(InvoiceError::InvalidAmount(
-                "Amount must be greater than zero".to_string(),
-            ));
-        }
-
-        let timestamp: u64 = fields[1]
-            .parse()
-            .map_err(|_| InvoiceError::ParseError(format!("Invalid timestamp: {}", fields[1])))?;
-        let expiry: u32 = fields[2]
-            .parse()
-            .map_err(|_| InvoiceError::ParseError(format!("Invalid expiry: {}", fields[2])))?;
-        let min_final_cltv_expiry: u32 = fields[3].parse().map_err(|_| {
-            InvoiceError::ParseError(format!("Invalid CLTV delta: {}", fields[3]))
-        })?;
-        let features: u64 = fields[4]
-            .parse()
-            .map_err(|_| InvoiceError::ParseError(format!("Invalid features: {}", fields[4])))?;
-        let is_private = match fields[5] {
-            "0" => false,
-            "1" => true,
-            other => {
-                return Err(InvoiceError::ParseError(format!(
-                    "Invalid private flag: {}",
-                    other
-                )))
-            }
-        };
-
-        // Validates length (64 hex chars) and hex encoding of the payment hash.
-        let payment_hash = PaymentHash::from_hex(fields[6])
-            .map_err(|e| InvoiceError::InvalidHash(format!("{:?}", e)))?;
-
-        let destination_bytes = hex::decode(fields[7])
-            .map_err(|e| InvoiceError::ParseError(format!("Invalid destination: {}", e)))?;
-        let destination = String::from_utf8(destination_bytes)
-            .map_err(|e| InvoiceError::ParseError(format!("Invalid destination: {}", e)))?;
-
-        // Description is hex-encoded (like destination) so that arbitrary
-        // human-readable text, including ':' characters, round-trips safely
-        // through the ':'-delimited field format.
-        let description_bytes = hex::decode(fields[8])
-            .map_err(|e| InvoiceError::ParseError(format!("Invalid description: {}", e)))?;
-        let description = String::from_utf8(description_bytes)
-            .map_err(|e| InvoiceError::ParseError(format!("Invalid description: {}", e)))?;
+        let invoice_str = invoice_str.trim();
+        let signed = bolt11::decode(invoice_str)?;
+        let fields = signed.fields;
 
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or(Duration::from_secs(0))
             .as_secs();
-        if now > timestamp + expiry as u64 {
+        if now > fields.timestamp + fields.expiry as u64 {
             return Err(InvoiceError::Expired);
         }
 
         Ok(Self {
-            payment_hash,
+            payment_hash: PaymentHash::new(fields.payment_hash),
             // Payer-side invoices never carry the preimage; only the
             // creator/payee retains it (see doc comment above).
             payment_preimage: PaymentPreimage::new([0u8; 32]),
-            description,
-            destination,
-            amount_mnova,
-            timestamp,
-            expiry,
-            route_hints: Vec::new(),
-            min_final_cltv_expiry,
-            features,
-            signature: None,
-            is_private,
+            description: fields.description,
+            destination: hex::encode(signed.payee.serialize()),
+            amount_mnova: fields.amount_mnova,
+            timestamp: fields.timestamp,
+            expiry: fields.expiry,
+            is_private: !fields.route_hints.is_empty(),
+            route_hints: fields.route_hints,
+            min_final_cltv_expiry: fields.min_final_cltv_expiry,
+            features: fields.features,
+            signature: Some(signed.signature.to_vec()),
             settled: false,
             settled_time: None,
+            encoded: Some(invoice_str.to_lowercase()),
         })
     }
 
@@ -427,9 +419,11 @@ impl Invoice {
         &self.route_hints
     }
 
-    /// Add a route hint
+    /// Add a route hint. Hints are signed, so this drops the signature.
     pub fn add_route_hint(&mut self, hint: RouteHint) {
         self.route_hints.push(hint);
+        self.signature = None;
+        self.encoded = None;
     }
 
     /// Get expiry time in seconds
@@ -459,12 +453,14 @@ impl Invoice {
     /// 5. When payment arrives, preimage is revealed to complete the payment
     pub fn payment_preimage(&self) -> PaymentPreimage {
         // Return the actual preimage that was stored when the invoice was created
-        self.payment_preimage
+        self.payment_preimage.clone()
     }
 
-    /// Set signature
+    /// Set signature bytes. They replace the signed encoding, so the
+    /// invoice neither encodes nor verifies until it is signed again.
     pub fn set_signature(&mut self, signature: Vec<u8>) {
         self.signature = Some(signature);
+        self.encoded = None;
     }
 
     /// Check if the invoice has a signature
@@ -472,56 +468,68 @@ impl Invoice {
         self.signature.is_some()
     }
 
+    /// Sign the invoice with the node key and, when the node has one, its
+    /// post-quantum key, returning the BOLT11 encoding. The destination
+    /// becomes the node key.
+    pub fn sign(
+        &mut self,
+        node_key: &SecretKey,
+        quantum_key: Option<&QuantumKeyPair>,
+    ) -> Result<String, InvoiceError> {
+        let fields = bolt11::InvoiceFields {
+            amount_mnova: self.amount_mnova,
+            timestamp: self.timestamp,
+            payment_hash: self.payment_hash.into_inner(),
+            description: self.description.clone(),
+            expiry: self.expiry,
+            min_final_cltv_expiry: self.min_final_cltv_expiry,
+            features: self.features,
+            route_hints: self.route_hints.clone(),
+        };
+        let encoded = bolt11::encode(&fields, node_key, quantum_key)?;
+        let signed = bolt11::decode(&encoded)?;
+
+        self.destination = hex::encode(signed.payee.serialize());
+        self.signature = Some(signed.signature.to_vec());
+        self.encoded = Some(encoded.clone());
+        Ok(encoded)
+    }
+
     /// Verify the invoice signature.
     ///
-    /// **Security note (fail-closed):** cryptographic verification of the
-    /// invoice signature is not yet implemented. A signed invoice would need
-    /// to carry the destination's post-quantum public key (the `destination`
-    /// field is currently only an opaque node-id string) and a matching
-    /// invoice-signing counterpart. Until that exists, this method MUST NOT
-    /// report an unverified byte string as authentic: the mere presence of
-    /// bytes in the `signature` field proves nothing. It therefore always
-    /// fails closed rather than returning `Ok(true)`, so no caller can mistake
-    /// presence-of-bytes for a valid signature.
+    /// The signed encoding must decode with valid signatures, and the node
+    /// key that signed it must be the destination. Signature bytes without a
+    /// signed encoding (see [`Invoice::set_signature`]) prove nothing, so
+    /// they fail closed.
     pub fn verify_signature(&self) -> Result<bool, InvoiceError> {
-        if self.signature.is_none() {
+        let Some(signature) = &self.signature else {
             return Err(InvoiceError::InvalidSignature(
                 "Invoice has no signature".to_string(),
             ));
-        }
+        };
+        let encoded = self.encoded.as_ref().ok_or_else(|| {
+            InvoiceError::InvalidSignature(
+                "Invoice signature has no signed encoding to verify against".to_string(),
+            )
+        })?;
 
-        // Fail closed: real PQC verification against the destination's public
-        // key is not implemented, so a present signature cannot be trusted.
-        Err(InvoiceError::InvalidSignature(
-            "Invoice signature verification is not implemented; \
-             refusing to treat unverified signature bytes as authentic"
-                .to_string(),
-        ))
+        let signed = bolt11::decode(encoded)?;
+        if hex::encode(signed.payee.serialize()) != self.destination
+            || signed.signature[..] != signature[..]
+        {
+            return Err(InvoiceError::InvalidSignature(
+                "Invoice was not signed by its destination".to_string(),
+            ));
+        }
+        Ok(true)
     }
 
-    /// Encode the invoice as a string.
-    ///
-    /// This is Supernova's own reversible invoice encoding (tagged
-    /// `snova1`), not raw BOLT11 bech32: it deterministically serializes
-    /// every field needed to reconstruct an equivalent [`Invoice`] via
-    /// [`Invoice::from_str`], with destination and description hex-encoded
-    /// so they safely round-trip through the ':'-delimited format.
+    /// Encode the invoice as a BOLT11 string. Only signed invoices have an
+    /// encoding; see [`Invoice::sign`].
     pub fn to_string(&self) -> Result<String, InvoiceError> {
-        let invoice_str = format!(
-            "{}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
-            Self::ENCODING_PREFIX,
-            self.amount_mnova,
-            self.timestamp,
-            self.expiry,
-            self.min_final_cltv_expiry,
-            self.features,
-            if self.is_private { "1" } else { "0" },
-            self.payment_hash.to_hex(),
-            hex::encode(self.destination.as_bytes()),
-            hex::encode(self.description.as_bytes()),
-        );
-
-        Ok(invoice_str)
+        self.encoded
+            .clone()
+            .ok_or_else(|| InvoiceError::InvalidSignature("Invoice is not signed".to_string()))
     }
 }
 
@@ -535,7 +543,7 @@ impl fmt::Display for Invoice {
 }
 
 /// Invoice with enhanced features
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct EnhancedInvoice {
     /// Base invoice
     invoice: Invoice,
@@ -544,7 +552,7 @@ pub struct EnhancedInvoice {
     features: u64,
 
     /// Payment secrets for secure multi-hop payments
-    payment_secret: [u8; 32],
+    payment_secret: Zeroizing<[u8; 32]>,
 
     /// Payment metadata
     metadata: Vec<u8>,
@@ -559,8 +567,22 @@ pub struct EnhancedInvoice {
     attempts: u32,
 }
 
+impl fmt::Debug for EnhancedInvoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnhancedInvoice")
+            .field("invoice", &self.invoice)
+            .field("features", &self.features)
+            .field("payment_secret", &"<redacted>")
+            .field("metadata", &self.metadata)
+            .field("fallback_address", &self.fallback_address)
+            .field("state", &self.state)
+            .field("attempts", &self.attempts)
+            .finish()
+    }
+}
+
 /// State of an invoice
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InvoiceState {
     /// Invoice is open and can be paid
     Open,
@@ -622,8 +644,8 @@ impl EnhancedInvoice {
 
         // Generate a random payment secret
         let mut rng = thread_rng();
-        let mut payment_secret = [0u8; 32];
-        rng.fill_bytes(&mut payment_secret);
+        let mut payment_secret = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(payment_secret.as_mut());
 
         Ok(Self {
             invoice,
@@ -641,7 +663,7 @@ impl EnhancedInvoice {
         Self {
             invoice,
             features,
-            payment_secret,
+            payment_secret: Zeroizing::new(payment_secret),
             metadata: Vec::new(),
             fallback_address: None,
             state: InvoiceState::Open,
@@ -774,24 +796,162 @@ impl EnhancedInvoice {
         self.features
     }
 
-    /// Encode the invoice as a BOLT-11 string
+    /// Sign the base invoice, carrying this invoice's features; see
+    /// [`Invoice::sign`]
+    pub fn sign(
+        &mut self,
+        node_key: &SecretKey,
+        quantum_key: Option<&QuantumKeyPair>,
+    ) -> Result<String, InvoiceError> {
+        self.invoice.features = self.features;
+        self.invoice.sign(node_key, quantum_key)
+    }
+
+    /// Encode the invoice as a BOLT-11 string; it must have been signed
     pub fn to_bolt11(&self) -> Result<String, InvoiceError> {
-        // In a real implementation, this would encode a BOLT-11 invoice
-        // For simplicity, we'll just create a placeholder string
-        let invoice_str = format!(
-            "lnbc{}m{}{}{}{}",
-            self.amount_mnova() / 1_000_000,
-            hex::encode(&self.payment_hash().into_inner()[0..4]),
-            self.features,
-            self.invoice.timestamp(),
-            if self.supports_feature(feature_bits::FALLBACK_ADDRESS) {
-                "1"
-            } else {
-                "0"
-            }
+        self.invoice.to_string()
+    }
+}
+
+/// On-disk form of an [`EnhancedInvoice`]. Public fields are stored as-is;
+/// the preimage and payment secret only ever appear sealed.
+#[derive(Serialize, Deserialize)]
+struct StoredInvoice {
+    payment_hash: PaymentHash,
+    description: String,
+    destination: String,
+    amount_mnova: u64,
+    timestamp: u64,
+    expiry: u32,
+    route_hints: Vec<RouteHint>,
+    min_final_cltv_expiry: u32,
+    invoice_features: u64,
+    signature: Option<Vec<u8>>,
+    encoded: Option<String>,
+    is_private: bool,
+    settled: bool,
+    settled_time: Option<u64>,
+    features: u64,
+    metadata: Vec<u8>,
+    fallback_address: Option<String>,
+    state: InvoiceState,
+    attempts: u32,
+    /// Nonce followed by ChaCha20-Poly1305(preimage || payment secret), with
+    /// the payment hash as associated data
+    sealed_secrets: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredInvoiceDatabase {
+    version: u32,
+    invoices: Vec<StoredInvoice>,
+}
+
+impl StoredInvoice {
+    fn seal(invoice: &EnhancedInvoice, key: &InvoiceStoreKey) -> Result<Self, InvoiceError> {
+        let base = &invoice.invoice;
+        let mut secrets = Zeroizing::new([0u8; 64]);
+        secrets[..32].copy_from_slice(base.payment_preimage.as_bytes());
+        secrets[32..].copy_from_slice(invoice.payment_secret.as_ref());
+
+        let mut nonce = [0u8; 12];
+        thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = key
+            .cipher()?
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: secrets.as_ref(),
+                    aad: base.payment_hash.as_bytes(),
+                },
+            )
+            .map_err(|e| InvoiceError::Storage(e.to_string()))?;
+
+        let mut sealed_secrets = nonce.to_vec();
+        sealed_secrets.extend_from_slice(&ciphertext);
+
+        Ok(Self {
+            payment_hash: base.payment_hash,
+            description: base.description.clone(),
+            destination: base.destination.clone(),
+            amount_mnova: base.amount_mnova,
+            timestamp: base.timestamp,
+            expiry: base.expiry,
+            route_hints: base.route_hints.clone(),
+            min_final_cltv_expiry: base.min_final_cltv_expiry,
+            invoice_features: base.features,
+            signature: base.signature.clone(),
+            encoded: base.encoded.clone(),
+            is_private: base.is_private,
+            settled: base.settled,
+            settled_time: base.settled_time,
+            features: invoice.features,
+            metadata: invoice.metadata.clone(),
+            fallback_address: invoice.fallback_address.clone(),
+            state: invoice.state.clone(),
+            attempts: invoice.attempts,
+            sealed_secrets,
+        })
+    }
+
+    fn open(self, key: &InvoiceStoreKey) -> Result<EnhancedInvoice, InvoiceError> {
+        if self.sealed_secrets.len() < 12 {
+            return Err(InvoiceError::Decryption);
+        }
+        let (nonce, ciphertext) = self.sealed_secrets.split_at(12);
+        let secrets = Zeroizing::new(
+            key.cipher()?
+                .decrypt(
+                    Nonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: self.payment_hash.as_bytes(),
+                    },
+                )
+                .map_err(|_| InvoiceError::Decryption)?,
         );
+        if secrets.len() != 64 {
+            return Err(InvoiceError::Decryption);
+        }
 
-        Ok(invoice_str)
+        let mut preimage = [0u8; 32];
+        preimage.copy_from_slice(&secrets[..32]);
+        let payment_preimage = PaymentPreimage::new(preimage);
+        preimage.fill(0);
+        if payment_preimage.payment_hash() != self.payment_hash {
+            return Err(InvoiceError::InvalidHash(format!(
+                "Stored preimage does not match payment hash {}",
+                self.payment_hash
+            )));
+        }
+        let mut payment_secret = Zeroizing::new([0u8; 32]);
+        payment_secret.copy_from_slice(&secrets[32..]);
+
+        Ok(EnhancedInvoice {
+            invoice: Invoice {
+                payment_hash: self.payment_hash,
+                payment_preimage,
+                description: self.description,
+                destination: self.destination,
+                amount_mnova: self.amount_mnova,
+                timestamp: self.timestamp,
+                expiry: self.expiry,
+                route_hints: self.route_hints,
+                min_final_cltv_expiry: self.min_final_cltv_expiry,
+                features: self.invoice_features,
+                signature: self.signature,
+                is_private: self.is_private,
+                settled: self.settled,
+                settled_time: self.settled_time,
+                encoded: self.encoded,
+            },
+            features: self.features,
+            payment_secret,
+            metadata: self.metadata,
+            fallback_address: self.fallback_address,
+            state: self.state,
+            attempts: self.attempts,
+        })
     }
 }
 
@@ -997,6 +1157,60 @@ impl InvoiceDatabase {
 
         to_remove.len()
     }
+
+    /// Serialize the database with every preimage and payment secret sealed
+    /// under `key`.
+    pub fn to_sealed_bytes(&self, key: &InvoiceStoreKey) -> Result<Vec<u8>, InvoiceError> {
+        let invoices = self
+            .invoices
+            .values()
+            .map(|invoice| StoredInvoice::seal(invoice, key))
+            .collect::<Result<Vec<_>, _>>()?;
+        bincode::serialize(&StoredInvoiceDatabase {
+            version: INVOICE_STORE_VERSION,
+            invoices,
+        })
+        .map_err(|e| InvoiceError::Storage(e.to_string()))
+    }
+
+    /// Rebuild a database from [`InvoiceDatabase::to_sealed_bytes`] output.
+    pub fn from_sealed_bytes(bytes: &[u8], key: &InvoiceStoreKey) -> Result<Self, InvoiceError> {
+        let stored: StoredInvoiceDatabase =
+            bincode::deserialize(bytes).map_err(|e| InvoiceError::Storage(e.to_string()))?;
+        if stored.version != INVOICE_STORE_VERSION {
+            return Err(InvoiceError::Storage(format!(
+                "Unsupported invoice store version {}",
+                stored.version
+            )));
+        }
+
+        let mut db = Self::new();
+        for record in stored.invoices {
+            let invoice = record.open(key)?;
+            let payment_hash = invoice.payment_hash();
+            if matches!(invoice.state(), InvoiceState::Paid) {
+                db.paid_invoices.insert(payment_hash);
+            }
+            db.invoices_by_description
+                .insert(invoice.description().to_string(), payment_hash);
+            db.invoices.insert(payment_hash, invoice);
+        }
+        Ok(db)
+    }
+
+    /// Write the sealed database to `path`, replacing any previous file.
+    pub fn save(&self, path: &Path, key: &InvoiceStoreKey) -> Result<(), InvoiceError> {
+        let bytes = self.to_sealed_bytes(key)?;
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes).map_err(|e| InvoiceError::Storage(e.to_string()))?;
+        std::fs::rename(&tmp, path).map_err(|e| InvoiceError::Storage(e.to_string()))
+    }
+
+    /// Load a database written by [`InvoiceDatabase::save`].
+    pub fn load(path: &Path, key: &InvoiceStoreKey) -> Result<Self, InvoiceError> {
+        let bytes = std::fs::read(path).map_err(|e| InvoiceError::Storage(e.to_string()))?;
+        Self::from_sealed_bytes(&bytes, key)
+    }
 }
 
 #[cfg(test)]
@@ -1006,7 +1220,7 @@ mod invoice_lifecycle_tests {
     fn sample_invoice() -> Invoice {
         let preimage = PaymentPreimage::new_random();
         let payment_hash = preimage.payment_hash();
-        Invoice::new(
+        let mut invoice = Invoice::new(
             payment_hash,
             50_000,
             "coffee".to_string(),
@@ -1014,13 +1228,31 @@ mod invoice_lifecycle_tests {
             true,
             "029a059f014307e795a31e1ddfdd19c7df6c7b1e2d09d6788c31ca4c38bac0f9ab".to_string(),
             preimage,
-        )
+        );
+        invoice.add_route_hint(RouteHint {
+            node_id: "029a059f014307e795a31e1ddfdd19c7df6c7b1e2d09d6788c31ca4c38bac0f9ab"
+                .to_string(),
+            channel_id: 42,
+            base_fee_mnova: 1_000,
+            fee_rate_millionths: 10,
+            cltv_expiry_delta: 40,
+        });
+        invoice
+    }
+
+    fn node_key() -> SecretKey {
+        SecretKey::from_slice(&[0x33; 32]).unwrap()
     }
 
     #[test]
     fn to_string_from_str_round_trips_public_fields() {
-        let invoice = sample_invoice();
-        let encoded = invoice.to_string().expect("encode invoice");
+        let mut invoice = sample_invoice();
+        assert!(
+            invoice.to_string().is_err(),
+            "unsigned invoices have no encoding"
+        );
+        let encoded = invoice.sign(&node_key(), None).expect("sign invoice");
+        assert_eq!(invoice.to_string().unwrap(), encoded);
 
         let parsed = Invoice::from_str(&encoded).expect("parse invoice");
 
@@ -1030,7 +1262,14 @@ mod invoice_lifecycle_tests {
         assert_eq!(parsed.destination(), invoice.destination());
         assert_eq!(parsed.timestamp(), invoice.timestamp());
         assert_eq!(parsed.expiry(), invoice.expiry());
+        assert_eq!(
+            parsed.min_final_cltv_expiry(),
+            invoice.min_final_cltv_expiry()
+        );
+        assert_eq!(parsed.route_hints()[0].channel_id, 42);
         assert_eq!(parsed.is_private(), invoice.is_private());
+        assert!(parsed.verify_signature().unwrap());
+        assert!(invoice.verify_signature().unwrap());
     }
 
     #[test]
@@ -1040,16 +1279,27 @@ mod invoice_lifecycle_tests {
     }
 
     #[test]
-    fn from_str_rejects_malformed_payment_hash() {
-        let invoice = sample_invoice();
-        let encoded = invoice.to_string().expect("encode invoice");
-        // Corrupt the payment hash field (7th field after the prefix).
-        let mut fields: Vec<&str> = encoded.split(':').collect();
-        fields[7] = "not-hex";
-        let corrupted = fields.join(":");
+    fn from_str_rejects_corrupted_invoice() {
+        let encoded = sample_invoice().sign(&node_key(), None).unwrap();
+        // Flip a data character; the checksum no longer matches
+        let mut corrupted = encoded.into_bytes();
+        corrupted[20] = if corrupted[20] == b'q' { b'p' } else { b'q' };
+        let corrupted = String::from_utf8(corrupted).unwrap();
 
         let result = Invoice::from_str(&corrupted);
-        assert!(matches!(result, Err(InvoiceError::InvalidHash(_))));
+        assert!(matches!(result, Err(InvoiceError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn changing_a_signed_invoice_drops_its_signature() {
+        let mut invoice = sample_invoice();
+        invoice.sign(&node_key(), None).unwrap();
+        assert!(invoice.verify_signature().unwrap());
+
+        invoice.add_route_hint(invoice.route_hints()[0].clone());
+        assert!(!invoice.has_signature());
+        assert!(invoice.to_string().is_err());
+        assert!(invoice.verify_signature().is_err());
     }
 
     #[test]
@@ -1057,7 +1307,7 @@ mod invoice_lifecycle_tests {
         let preimage = PaymentPreimage::new_random();
         let payment_hash = preimage.payment_hash();
         // Expiry of 0 seconds from creation means it is immediately expired.
-        let invoice = Invoice::new(
+        let mut invoice = Invoice::new(
             payment_hash,
             1_000,
             "expired".to_string(),
@@ -1066,7 +1316,7 @@ mod invoice_lifecycle_tests {
             "dest".to_string(),
             preimage,
         );
-        let encoded = invoice.to_string().expect("encode invoice");
+        let encoded = invoice.sign(&node_key(), None).expect("encode invoice");
 
         // Ensure we're past the expiry boundary.
         std::thread::sleep(std::time::Duration::from_millis(1100));
@@ -1139,4 +1389,42 @@ mod invoice_lifecycle_tests {
             Err(InvoiceError::InvalidSignature(_))
         ));
     }
+
+    #[test]
+    fn sealed_store_hides_secrets_and_round_trips() {
+        let mut invoice = sample_invoice();
+        let encoded = invoice.sign(&node_key(), None).unwrap();
+        let payment_hash = invoice.payment_hash();
+        let preimage = invoice.payment_preimage();
+        let secret = [0x5a; 32];
+
+        let mut db = InvoiceDatabase::new();
+        db.add_invoice(EnhancedInvoice::from_parts(invoice, 0, secret))
+            .unwrap();
+
+        let key = InvoiceStoreKey::from_seed(&[9u8; 32]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invoices.db");
+        db.save(&path, &key).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        for needle in [preimage.as_bytes().to_vec(), secret.to_vec()] {
+            assert!(!bytes.windows(32).any(|w| w == needle.as_slice()));
+            let hex = hex::encode(&needle).into_bytes();
+            assert!(!bytes.windows(hex.len()).any(|w| w == hex.as_slice()));
+        }
+
+        let restored = InvoiceDatabase::load(&path, &key).unwrap();
+        let restored_invoice = restored.get_invoice(&payment_hash).unwrap();
+        assert_eq!(restored_invoice.payment_secret(), &secret);
+        assert_eq!(restored_invoice.base_invoice().payment_preimage(), preimage);
+        assert_eq!(restored_invoice.to_bolt11().unwrap(), encoded);
+        assert!(!format!("{:?}", restored_invoice).contains(&preimage.to_hex()));
+
+        let wrong_key = InvoiceStoreKey::from_seed(&[8u8; 32]).unwrap();
+        assert!(matches!(
+            InvoiceDatabase::load(&path, &wrong_key),
+            Err(InvoiceError::Decryption)
+        ));
+    }
 }
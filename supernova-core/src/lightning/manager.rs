@@ -1,3 +1,6 @@
+// Keep lock handling panic-free here even if the crate-wide policy is relaxed
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
@@ -9,9 +12,11 @@ use tracing::{error, info};
 use super::{LightningConfig, LightningNetworkError};
 use crate::lightning::payment::RouteHop;
 use crate::lightning::{
-    AtomicChannel, Channel, ChannelConfig, ChannelId, ChannelState, Invoice, LightningWallet,
-    OnionRouter, Payment, PaymentHash, PaymentStatus, QuantumChannelSecurity, Router, Watchtower,
+    AtomicChannel, Channel, ChannelConfig, ChannelId, ChannelState, FundingTracker, FundingUpdate,
+    GraphExport, Invoice, LightningWallet, MissionControlExport, OnionRouter, Payment, PaymentHash,
+    PaymentStatus, QuantumChannelSecurity, Router, Watchtower,
 };
+use crate::types::block::Block;
 use crate::types::transaction::Transaction;
 
 /// Lightning Network Manager - Central coordinator for Lightning Network operations
@@ -29,7 +34,7 @@ pub struct LightningManager {
     wallet: Arc<Mutex<LightningWallet>>,
 
     /// Payment router for finding paths
-    router: Arc<Router>,
+    router: Arc<RwLock<Router>>,
 
     /// Onion router for payment privacy
     onion_router: Arc<OnionRouter>,
@@ -60,6 +65,12 @@ pub struct LightningManager {
 
     /// Invoice index counter
     invoice_index: Arc<std::sync::atomic::AtomicU64>,
+
+    /// Funding transactions awaiting confirmation
+    funding: Arc<Mutex<FundingTracker>>,
+
+    /// Height of the last connected block
+    best_height: Arc<std::sync::atomic::AtomicU64>,
 }
 
 #[derive(Debug, Clone)]
@@ -74,7 +85,13 @@ pub struct PeerInfo {
 #[derive(Debug, Clone)]
 pub enum LightningEvent {
     ChannelOpened(ChannelId),
+    /// Funding reached the required depth and the channel is usable
+    ChannelActive(ChannelId),
+    /// Funding timed out or its inputs were double-spent
+    ChannelFundingFailed(ChannelId, String),
     ChannelClosed(ChannelId),
+    /// A transaction the node should relay (funding or closing)
+    BroadcastTransaction(Transaction),
     PaymentReceived(PaymentHash, u64),
     PaymentSent(PaymentHash, u64),
     InvoiceCreated(PaymentHash),
@@ -417,7 +434,7 @@ impl LightningManager {
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
 
         // Initialize router
-        let router = Arc::new(Router::new());
+        let router = Arc::new(RwLock::new(Router::new()));
 
         // Initialize onion router with a default private key
         let private_key = [1u8; 32]; // In production, this would be derived from the wallet
@@ -446,6 +463,8 @@ impl LightningManager {
             None
         };
 
+        let funding = Arc::new(Mutex::new(FundingTracker::new(config.funding.clone())));
+
         let manager = Self {
             config,
             channels: Arc::new(RwLock::new(HashMap::new())),
@@ -462,6 +481,8 @@ impl LightningManager {
             is_running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             payment_index: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             invoice_index: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            funding,
+            best_height: Arc::new(std::sync::atomic::AtomicU64::new(0)),
         };
 
         Ok((manager, event_receiver))
@@ -482,8 +503,9 @@ impl LightningManager {
                 .map_err(|e| ManagerError::WatchtowerError(e.to_string()))?;
         }
 
-        // Start router
-        self.router
+        // Start router (on a copy, so no lock is held across the await)
+        let router = self.read_router()?.clone();
+        router
             .start()
             .await
             .map_err(|e| ManagerError::RouterError(e.to_string()))?;
@@ -508,7 +530,8 @@ impl LightningManager {
         }
 
         // Stop router
-        self.router
+        let router = self.read_router()?.clone();
+        router
             .stop()
             .await
             .map_err(|e| ManagerError::RouterError(e.to_string()))?;
@@ -575,7 +598,7 @@ impl LightningManager {
         let synced_to_chain = current_height > 0; // Simple check
 
         // Check graph sync status by verifying we have network topology
-        let synced_to_graph = self.router.node_count() > 0;
+        let synced_to_graph = self.read_router()?.node_count() > 0;
 
         Ok(LightningInfo {
             node_id: self.get_node_id(),
@@ -686,37 +709,16 @@ impl LightningManager {
             ));
         }
 
-        // Check wallet balance
-        let wallet = self
-            .wallet
-            .lock()
-            .map_err(|e| ManagerError::LockPoisoned(format!("wallet: {}", e)))?;
-        let available_balance = wallet.get_balance();
-        if available_balance < local_funding_amount {
-            return Err(ManagerError::InsufficientBalance {
-                required: local_funding_amount,
-                available: available_balance,
-            });
-        }
-        drop(wallet);
-
-        // Generate channel ID
-        let channel_id = ChannelId::new_random();
-
-        // Create funding transaction
-        let funding_tx = self
-            .create_funding_transaction(local_funding_amount, &channel_id)
-            .await?;
-
         // Create channel configuration
         let mut config = ChannelConfig::default();
         config.announce_channel = !private;
+        config.use_quantum_signatures = self.config.use_quantum_signatures;
         if let Some(min_htlc) = min_htlc_mnova {
             config.min_htlc_value_mnova = min_htlc;
         }
 
         // Create channel
-        let channel = Channel::open(
+        let mut channel = Channel::open(
             node_id.to_string(),
             local_funding_amount,
             push_amount,
@@ -724,11 +726,43 @@ impl LightningManager {
             self.config.quantum_scheme,
         )
         .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+        let channel_id = channel.id();
+
+        // Select and lock wallet UTXOs and build the funding transaction
+        let funding_tx = {
+            let mut wallet = self
+                .wallet
+                .lock()
+                .map_err(|e| ManagerError::LockPoisoned(format!("wallet: {}", e)))?;
+            let available_balance = wallet.get_on_chain_balance();
+            if available_balance < local_funding_amount {
+                return Err(ManagerError::InsufficientBalance {
+                    required: local_funding_amount,
+                    available: available_balance,
+                });
+            }
+            let change_script = wallet
+                .change_script()
+                .map_err(|e| ManagerError::WalletError(e.to_string()))?;
+            let mut funding = self
+                .funding
+                .lock()
+                .map_err(|e| ManagerError::LockPoisoned(format!("funding: {}", e)))?;
+            let height = self.get_current_height();
+            funding
+                .fund_channel(&mut wallet, &mut channel, change_script, height)
+                .map_err(|e| ManagerError::WalletError(e.to_string()))?
+        };
+        let output_index = channel
+            .funding_outpoint
+            .as_ref()
+            .map(|outpoint| outpoint.vout)
+            .unwrap_or(0);
 
         // Wrap in AtomicChannel for thread safety
         let atomic_channel = Arc::new(AtomicChannel::new(channel));
 
-        // Add to pending channels
+        // Add to pending channels until the funding reaches the required depth
         {
             let mut pending_channels = self
                 .pending_channels
@@ -737,6 +771,8 @@ impl LightningManager {
             pending_channels.insert(channel_id.clone(), atomic_channel);
         }
 
+        self.broadcast_transaction(&funding_tx).await?;
+
         // Send event
         let _ = self
             .event_sender
@@ -744,8 +780,8 @@ impl LightningManager {
 
         Ok(OpenChannelResponse {
             channel_id: channel_id.to_hex(),
-            funding_txid: hex::encode(funding_tx.hash()),
-            output_index: 0,
+            funding_txid: hex::encode(funding_tx.txid()),
+            output_index,
         })
     }
 
@@ -849,7 +885,7 @@ impl LightningManager {
     ) -> Result<PaymentResponse, ManagerError> {
         info!("Sending payment: {}", payment_request);
 
-        // Parse payment request (simplified - in production would parse BOLT11)
+        // Parse the BOLT11 payment request, checking its signatures and expiry
         let invoice = self.parse_payment_request(payment_request)?;
 
         // Use provided amount or invoice amount
@@ -857,7 +893,7 @@ impl LightningManager {
 
         // Find route
         let route = self
-            .router
+            .read_router()?
             .find_route(
                 &invoice.destination,
                 amount,
@@ -933,7 +969,7 @@ impl LightningManager {
                 .map_err(|e| ManagerError::LockPoisoned(format!("payments: {}", e)))?;
             if let Some(payment) = payments.get_mut(&payment_hash) {
                 payment.status = PaymentStatus::Succeeded;
-                payment.payment_preimage = Some(preimage);
+                payment.payment_preimage = Some(preimage.clone());
                 payment.completed_at = Some(
                     SystemTime::now()
                         .duration_since(UNIX_EPOCH)
@@ -1005,17 +1041,9 @@ impl LightningManager {
             .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
 
         // Create invoice using payment module types directly
-        let invoice = Invoice::new_with_preimage(preimage, value_mnova, memo.to_string(), expiry)
-            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
-
-        // Store invoice using payment module types
-        {
-            let mut invoices = self
-                .invoices
-                .write()
-                .map_err(|e| ManagerError::LockPoisoned(format!("invoices: {}", e)))?;
-            invoices.insert(payment_hash, invoice.clone());
-        }
+        let mut invoice =
+            Invoice::new_with_preimage(preimage, value_mnova, memo.to_string(), expiry)
+                .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
 
         // Convert HTLCs from invoice (if any pending)
         let _htlcs = {
@@ -1050,11 +1078,28 @@ impl LightningManager {
             invoice_htlcs
         };
 
+        let payment_request = self
+            .wallet
+            .lock()
+            .map_err(|e| ManagerError::LockPoisoned(format!("wallet: {}", e)))?
+            .sign_invoice(&mut invoice)
+            .map_err(|e| ManagerError::WalletError(e.to_string()))?;
+
+        // Store the invoice only once nothing else can fail, so a failed
+        // call leaves no invoice behind
+        {
+            let mut invoices = self
+                .invoices
+                .write()
+                .map_err(|e| ManagerError::LockPoisoned(format!("invoices: {}", e)))?;
+            invoices.insert(payment_hash, invoice.clone());
+        }
+
         // Get invoice index
         let add_index = self.invoice_index.load(std::sync::atomic::Ordering::SeqCst);
 
         Ok(InvoiceResponse {
-            payment_request: self.encode_payment_request(&invoice)?,
+            payment_request,
             payment_hash: invoice.payment_hash().to_hex(),
             add_index,
         })
@@ -1167,6 +1212,10 @@ impl LightningManager {
         })
     }
 
+    /// Preimages are only returned by the calls that need them: the
+    /// `send_payment` result (proof of payment) and `lookup_payment`. List
+    /// conversions like this one and `invoice_to_lightning_invoice` leave
+    /// them empty.
     fn payment_to_lightning_payment(&self, payment: &Payment) -> LightningPayment {
         // Convert HTLCs from payment
         let htlcs = if let Some(route) = &payment.route {
@@ -1214,11 +1263,7 @@ impl LightningManager {
                     failure_source_index: 0,
                     height: 0,
                 }),
-                preimage: payment
-                    .payment_preimage
-                    .as_ref()
-                    .map(|p| p.to_hex())
-                    .unwrap_or_default(),
+                preimage: String::new(),
             }]
         } else {
             vec![]
@@ -1234,11 +1279,7 @@ impl LightningManager {
             value: payment.amount_mnova / 1000,
             creation_date: payment.created_at,
             fee: payment.fee_mnova / 1000,
-            payment_preimage: payment
-                .payment_preimage
-                .as_ref()
-                .map(|p| p.to_hex())
-                .unwrap_or_default(),
+            payment_preimage: String::new(),
             value_nova_units: payment.amount_mnova / 1000,
             value_mnova: payment.amount_mnova,
             payment_request: "".to_string(),
@@ -1262,7 +1303,10 @@ impl LightningManager {
         let htlcs = {
             let channels = match self.channels.read() {
                 Ok(c) => c,
-                Err(_) => return self.default_lightning_invoice(invoice),
+                Err(_) => {
+                    error!("Channels lock poisoned, listing invoice without HTLCs");
+                    return self.default_lightning_invoice(invoice);
+                }
             };
             let mut invoice_htlcs = vec![];
 
@@ -1306,7 +1350,8 @@ impl LightningManager {
 
         LightningInvoice {
             memo: invoice.description().to_string(),
-            r_preimage: invoice.payment_preimage().as_bytes().to_vec(),
+            // Listings never expose the preimage; see `lookup_payment`.
+            r_preimage: Vec::new(),
             r_hash: invoice.payment_hash().as_bytes().to_vec(),
             value: invoice.amount_mnova() / 1000,
             value_mnova: invoice.amount_mnova(),
@@ -1360,7 +1405,8 @@ impl LightningManager {
 
         LightningInvoice {
             memo: invoice.description().to_string(),
-            r_preimage: invoice.payment_preimage().as_bytes().to_vec(),
+            // Listings never expose the preimage; see `lookup_payment`.
+            r_preimage: Vec::new(),
             r_hash: invoice.payment_hash().as_bytes().to_vec(),
             value: invoice.amount_mnova() / 1000,
             value_mnova: invoice.amount_mnova(),
@@ -1405,72 +1451,112 @@ impl LightningManager {
         }
     }
 
-    async fn create_funding_transaction(
-        &self,
-        amount: u64,
-        channel_id: &ChannelId,
-    ) -> Result<Transaction, ManagerError> {
-        let wallet = self
-            .wallet
-            .lock()
-            .map_err(|e| ManagerError::LockPoisoned(format!("wallet: {}", e)))?;
-        wallet
-            .create_funding_transaction(amount, channel_id)
-            .map_err(|e| ManagerError::WalletError(e.to_string()))
-    }
-
     async fn broadcast_transaction(&self, tx: &Transaction) -> Result<(), ManagerError> {
-        // In a real implementation, this would broadcast to the network
+        // The node relays it to the mempool and peers
         info!("Broadcasting transaction: {}", hex::encode(tx.hash()));
+        self.event_sender
+            .send(LightningEvent::BroadcastTransaction(tx.clone()))
+            .map_err(|e| ManagerError::NetworkError(format!("event channel closed: {}", e)))
+    }
+
+    /// Follow a newly connected block: activate channels whose funding is
+    /// deep enough and abort those that timed out or were double-spent
+    pub fn block_connected(&self, block: &Block) -> Result<(), ManagerError> {
+        self.best_height
+            .store(block.height(), std::sync::atomic::Ordering::Relaxed);
+
+        let updates = {
+            let mut wallet = self
+                .wallet
+                .lock()
+                .map_err(|e| ManagerError::LockPoisoned(format!("wallet: {}", e)))?;
+            let mut funding = self
+                .funding
+                .lock()
+                .map_err(|e| ManagerError::LockPoisoned(format!("funding: {}", e)))?;
+            funding.block_connected(&mut wallet, block)
+        };
+
+        for update in updates {
+            let channel_id = update.channel_id().clone();
+            if let FundingUpdate::Confirmed { depth, .. } = update {
+                info!("Funding of channel {} has {} confirmations", channel_id, depth);
+                continue;
+            }
+
+            let Some(atomic_channel) = self
+                .pending_channels
+                .write()
+                .map_err(|e| ManagerError::LockPoisoned(format!("pending_channels: {}", e)))?
+                .remove(&channel_id)
+            else {
+                continue;
+            };
+            {
+                let mut channel = atomic_channel.channel.lock().map_err(|e| {
+                    ManagerError::ChannelError(format!("Failed to lock channel: {}", e))
+                })?;
+                update
+                    .apply(&mut channel)
+                    .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+                atomic_channel
+                    .set_state(channel.state)
+                    .map_err(|e| ManagerError::ChannelError(e.to_string()))?;
+            }
+
+            match update {
+                FundingUpdate::Ready(_) => {
+                    self.channels
+                        .write()
+                        .map_err(|e| ManagerError::LockPoisoned(format!("channels: {}", e)))?
+                        .insert(channel_id.clone(), atomic_channel);
+                    let _ = self
+                        .event_sender
+                        .send(LightningEvent::ChannelActive(channel_id));
+                }
+                FundingUpdate::Failed(_, error) => {
+                    error!("Funding of channel {} failed: {}", channel_id, error);
+                    let _ = self.event_sender.send(LightningEvent::ChannelFundingFailed(
+                        channel_id,
+                        error.to_string(),
+                    ));
+                }
+                FundingUpdate::Confirmed { .. } => {}
+            }
+        }
+
         Ok(())
     }
 
-    fn parse_payment_request(&self, _payment_request: &str) -> Result<ParsedInvoice, ManagerError> {
-        // Simplified BOLT11 parsing - in production would use proper parser
+    /// Follow a disconnected block so reorged funding confirmations recount
+    pub fn block_disconnected(&self, block: &Block) -> Result<(), ManagerError> {
+        self.best_height.store(
+            block.height().saturating_sub(1),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        self.funding
+            .lock()
+            .map_err(|e| ManagerError::LockPoisoned(format!("funding: {}", e)))?
+            .block_disconnected(block);
+        Ok(())
+    }
+
+    fn parse_payment_request(&self, payment_request: &str) -> Result<ParsedInvoice, ManagerError> {
+        let invoice = Invoice::from_str(payment_request)
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))?;
         Ok(ParsedInvoice {
-            payment_hash: crate::lightning::payment::PaymentHash::new([0u8; 32]), // Placeholder
-            amount_mnova: 1000000,                                                 // Placeholder
-            destination: "destination_node".to_string(),
-            expiry: 3600,
-            description: "Test payment".to_string(),
+            payment_hash: invoice.payment_hash(),
+            amount_mnova: invoice.amount_mnova(),
+            destination: invoice.destination().to_string(),
+            expiry: invoice.expiry(),
+            description: invoice.description().to_string(),
         })
     }
 
     fn encode_payment_request(&self, invoice: &Invoice) -> Result<String, ManagerError> {
-        // BOLT11 payment request encoding
-        // Format: ln[prefix][amount][separator][data][checksum]
-
-        let _network_prefix = "bc"; // mainnet
-        let amount_part = if invoice.amount_mnova() > 0 {
-            // Convert millinovas to the appropriate unit
-            let amount_novas = invoice.amount_mnova() / 1000;
-            if amount_novas >= 1000000 {
-                format!("{}m", amount_novas / 1000000) // mega-novas
-            } else if amount_novas >= 1000 {
-                format!("{}k", amount_novas / 1000) // kilo-novas
-            } else {
-                format!("{}", amount_novas) // novas
-            }
-        } else {
-            "".to_string()
-        };
-
-        // Simplified BOLT11 - in production would use proper bech32 encoding
-        let payment_hash_hex = invoice.payment_hash().to_hex();
-        let timestamp = invoice.created_at();
-        let expiry = invoice.expiry_seconds();
-
-        // Create a simplified but recognizable payment request
-        let payment_request = format!(
-            "lnbc{}1{}{}{}{}",
-            amount_part,
-            timestamp % 1000000,      // Simplified timestamp
-            &payment_hash_hex[0..10], // First 10 chars of payment hash
-            expiry,
-            "00" // Simplified checksum
-        );
-
-        Ok(payment_request)
+        invoice
+            .to_string()
+            .map_err(|e| ManagerError::InvalidPaymentRequest(e.to_string()))
     }
 
     async fn send_payment_through_route(
@@ -1513,7 +1599,8 @@ impl LightningManager {
         amt_mnova: u64,
         fee_limit_mnova: u64,
     ) -> Result<Option<Route>, ManagerError> {
-        match self.router.find_route(pub_key, amt_mnova, &[]) {
+        let found = self.read_router()?.find_route(pub_key, amt_mnova, &[]);
+        match found {
             Ok(route) => {
                 if route.total_fee_mnova <= fee_limit_mnova {
                     // Get channel capacities for each hop
@@ -1576,11 +1663,48 @@ impl LightningManager {
         }
     }
 
+    /// Read access to the payment router
+    fn read_router(&self) -> Result<std::sync::RwLockReadGuard<'_, Router>, ManagerError> {
+        self.router
+            .read()
+            .map_err(|e| ManagerError::LockPoisoned(format!("router: {}", e)))
+    }
+
+    /// Export the known network graph, see [`GraphExport`]
+    pub fn export_graph(&self) -> Result<GraphExport, ManagerError> {
+        Ok(GraphExport::from_graph(self.read_router()?.graph()))
+    }
+
+    /// Export local payment history per channel, with node IDs hashed when
+    /// `anonymize` is set
+    pub fn export_mission_control(
+        &self,
+        anonymize: bool,
+    ) -> Result<MissionControlExport, ManagerError> {
+        Ok(MissionControlExport::from_router(
+            &*self.read_router()?,
+            anonymize,
+        ))
+    }
+
+    /// Seed payment history from an earlier (non-anonymized) export, e.g.
+    /// after a reinstall. Returns the number of channels seeded.
+    pub fn import_mission_control(
+        &self,
+        export: &MissionControlExport,
+    ) -> Result<usize, ManagerError> {
+        let mut router = self
+            .router
+            .write()
+            .map_err(|e| ManagerError::LockPoisoned(format!("router: {}", e)))?;
+        export
+            .import_into(&mut router)
+            .map_err(|e| ManagerError::RouterError(e.to_string()))
+    }
+
     /// Get the current blockchain height
     fn get_current_height(&self) -> u64 {
-        // In a real implementation, this would query the blockchain state
-        // For now, return a placeholder value
-        700000 // Approximate current height
+        self.best_height.load(std::sync::atomic::Ordering::Relaxed)
     }
 
     /// Lookup a payment by hash
@@ -1736,4 +1860,93 @@ mod tests {
             result
         );
     }
+
+    #[test]
+    fn invoice_listing_does_not_expose_preimages() {
+        let wallet =
+            LightningWallet::new_test_wallet(1_000_000).expect("failed to create test wallet");
+        let (manager, _events) = LightningManager::new(LightningConfig::default(), wallet)
+            .expect("failed to create manager");
+
+        manager
+            .create_invoice(10_000, "coffee", 3600, false)
+            .expect("failed to create invoice");
+        let listed = manager.get_invoices(false, 0, 10).expect("failed to list");
+
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].r_preimage.is_empty());
+    }
+
+    #[test]
+    fn poisoned_locks_return_errors_instead_of_panicking() {
+        let wallet =
+            LightningWallet::new_test_wallet(1_000_000).expect("failed to create test wallet");
+        let (manager, _events) = LightningManager::new(LightningConfig::default(), wallet)
+            .expect("failed to create manager");
+        manager
+            .create_invoice(10_000, "coffee", 3600, false)
+            .expect("failed to create invoice");
+
+        let channels = Arc::clone(&manager.channels);
+        let router = Arc::clone(&manager.router);
+        let poisoner = std::thread::spawn(move || {
+            let _channels = channels.write().expect("channels lock");
+            let _router = router.write().expect("router lock");
+            panic!("panic while holding the channel and router locks");
+        });
+        assert!(poisoner.join().is_err());
+
+        assert!(matches!(
+            manager.get_info(),
+            Err(ManagerError::LockPoisoned(_))
+        ));
+        assert!(matches!(
+            manager.export_graph(),
+            Err(ManagerError::LockPoisoned(_))
+        ));
+        assert!(matches!(
+            manager.create_invoice(5_000, "tea", 3600, false),
+            Err(ManagerError::LockPoisoned(_))
+        ));
+        // Listing only loses the HTLC detail the channels would add
+        let listed = manager.get_invoices(false, 0, 10).expect("failed to list");
+        assert_eq!(listed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn opened_channel_activates_once_funding_is_buried() {
+        use crate::lightning::WalletUtxo;
+        use crate::types::block::BlockHeader;
+        use crate::types::transaction::OutPoint;
+
+        let mut wallet = LightningWallet::new_test_wallet(0).expect("failed to create test wallet");
+        wallet.add_utxo(WalletUtxo {
+            outpoint: OutPoint { txid: [5u8; 32], vout: 0 },
+            value: 2_000_000,
+            script_pubkey: vec![5u8; 32],
+        });
+        let (manager, mut events) = LightningManager::new(LightningConfig::default(), wallet)
+            .expect("failed to create manager");
+
+        let response = manager
+            .open_channel("peer", 1_000_000, 0, false, None)
+            .await
+            .expect("failed to open channel");
+        let funding_tx = match events.try_recv() {
+            Ok(LightningEvent::BroadcastTransaction(tx)) => tx,
+            other => panic!("expected funding broadcast, got {:?}", other),
+        };
+        assert_eq!(hex::encode(funding_tx.hash()), response.funding_txid);
+        assert_eq!(manager.get_info().unwrap().num_pending_channels, 1);
+
+        for (height, txs) in [(1, vec![funding_tx]), (2, vec![]), (3, vec![])] {
+            let header =
+                BlockHeader::new_with_height(1, [0; 32], [0; 32], 0, 0x207fffff, 0, height);
+            manager.block_connected(&Block::new(header, txs)).unwrap();
+        }
+
+        let info = manager.get_info().unwrap();
+        assert_eq!(info.num_pending_channels, 0);
+        assert_eq!(info.num_channels, 1);
+    }
 }
@@ -5,7 +5,10 @@
 
 pub mod atomic_operations;
 pub mod backup;
+pub mod bolt11;
 pub mod channel;
+pub mod funding;
+pub mod graph_export;
 pub mod green_routing;
 pub mod invoice;
 pub mod manager;
@@ -24,7 +27,14 @@ pub mod race_condition_tests;
 
 pub use atomic_operations::{AtomicChannel, AtomicChannelState, AtomicOperationError};
 pub use channel::{Channel, ChannelConfig, ChannelError, ChannelId, ChannelManager, ChannelState};
-pub use invoice::{EnhancedInvoice, Invoice, InvoiceDatabase, InvoiceError, RouteHint};
+pub use funding::{FundingConfig, FundingError, FundingTracker, FundingUpdate, PendingFunding};
+pub use graph_export::{
+    ChannelPolicy, GraphChannel, GraphExport, GraphNode, MissionControlEntry,
+    MissionControlExport, GRAPH_EXPORT_VERSION, MISSION_CONTROL_VERSION,
+};
+pub use invoice::{
+    EnhancedInvoice, Invoice, InvoiceDatabase, InvoiceError, InvoiceStoreKey, RouteHint,
+};
 pub use manager::{
     LightningChannel, LightningInfo, LightningInvoice, LightningManager, LightningPayment,
     ManagerError,
@@ -43,9 +53,10 @@ pub use quantum_lightning::{
 };
 pub use quantum_security::{QuantumChannelConfig, QuantumChannelSecurity, QuantumSecurityError};
 pub use router::{
-    ChannelInfo as RouterChannelInfo, NodeId, PathHop, PaymentPath, Router, RoutingError,
+    ChannelHistory, ChannelInfo as RouterChannelInfo, NodeAnnouncement, NodeEnvironmentalData,
+    NodeId, PathHop, PaymentPath, Router, RoutingError,
 };
-pub use wallet::{LightningWallet, WalletError};
+pub use wallet::{LightningWallet, WalletError, WalletUtxo};
 pub use watchtower::{
     BreachRemedy, ChannelMonitor, EncryptedChannelState, WatchError, Watchtower, WatchtowerClient,
     WatchtowerConfig,
@@ -170,6 +181,9 @@ pub struct LightningConfig {
 
     /// Quantum security level
     pub quantum_security_level: u8,
+
+    /// Channel funding confirmation policy
+    pub funding: FundingConfig,
 }
 
 impl LightningNetwork {
@@ -722,6 +736,7 @@ impl Default for LightningConfig {
             use_quantum_signatures: false,
             quantum_scheme: None,
             quantum_security_level: 1,
+            funding: FundingConfig::default(),
         }
     }
 }
@@ -13,6 +13,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use subtle::ConstantTimeEq;
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Payment hash - SHA256 hash of payment preimage
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -61,9 +62,19 @@ impl PaymentHash {
 }
 
 /// Payment preimage - 32 bytes of random data
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// The preimage is the proof of payment, so it is wiped on drop and never
+/// printed by `Debug`. Use [`PaymentPreimage::to_hex`] where the value is
+/// actually required.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
 pub struct PaymentPreimage([u8; 32]);
 
+impl fmt::Debug for PaymentPreimage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PaymentPreimage(<redacted>)")
+    }
+}
+
 impl PaymentPreimage {
     pub fn new(preimage: [u8; 32]) -> Self {
         Self(preimage)
@@ -196,7 +207,7 @@ pub enum HtlcState {
     /// HTLC is pending
     Pending,
     /// HTLC has been fulfilled with preimage
-    Fulfilled([u8; 32]),
+    Fulfilled(PaymentPreimage),
     /// HTLC has failed
     Failed(String),
     /// HTLC has timed out
@@ -365,7 +376,7 @@ impl PaymentProcessor {
             return Err(PaymentError::InvalidPreimage);
         }
 
-        htlc.state = HtlcState::Fulfilled(preimage);
+        htlc.state = HtlcState::Fulfilled(preimage_obj.clone());
 
         // Update payment status if this was the final HTLC
         let payment_hash = PaymentHash::new(htlc.payment_hash);
@@ -380,11 +391,7 @@ impl PaymentProcessor {
             payment.payment_preimage = Some(preimage_obj);
         }
 
-        info!(
-            "Fulfilled HTLC {}: preimage={:x?}",
-            htlc_id,
-            &preimage[0..4]
-        );
+        info!("Fulfilled HTLC {}", htlc_id);
 
         Ok(())
     }
@@ -636,4 +643,19 @@ mod tests {
         let computed_hash = preimage.payment_hash();
         assert_eq!(payment_hash, computed_hash);
     }
+
+    #[test]
+    fn debug_output_never_contains_preimage() {
+        let mut processor = PaymentProcessor::new(None);
+        let payment_hash = processor.create_payment(5_000, "dest").unwrap();
+        let payment = processor.get_payment(&payment_hash).unwrap();
+        let preimage_hex = payment.payment_preimage.as_ref().unwrap().to_hex();
+
+        let rendered = format!("{:?}", payment);
+        assert!(!rendered.contains(&preimage_hex));
+        assert!(rendered.contains("<redacted>"));
+
+        let state = HtlcState::Fulfilled(PaymentPreimage::from_hex(&preimage_hex).unwrap());
+        assert!(!format!("{:?}", state).contains(&preimage_hex));
+    }
 }
@@ -14,6 +14,7 @@ use crate::crypto::quantum::{
     sign_quantum, verify_quantum_signature, QuantumKeyPair, QuantumParameters, QuantumScheme,
 };
 use crate::crypto::zkp::ZeroKnowledgeProof;
+use crate::script::ScriptBuilder;
 use crate::types::{Transaction, TransactionOutput};
 use hex;
 use serde::{Deserialize, Serialize};
@@ -224,9 +225,10 @@ impl QuantumChannel {
 
         // Else (timeout path)
         script.push(0x67); // OP_ELSE
-        script.extend_from_slice(&htlc.timeout.to_le_bytes());
-        script.push(0xB1); // OP_CHECKLOCKTIMEVERIFY
-        script.push(0x75); // OP_DROP
+        let timeout =
+            u32::try_from(htlc.timeout).map_err(|_| ChannelError::InvalidTimeout(htlc.timeout))?;
+        // <timeout> OP_CHECKLOCKTIMEVERIFY OP_DROP
+        script.extend(ScriptBuilder::new().check_lock_time_verify(timeout).build());
 
         // Verify quantum signature from sender
         script.push(0xB0); // OP_QUANTUM_CHECKSIG
@@ -252,6 +254,10 @@ impl QuantumChannel {
         if !offered && amount > self.remote_balance {
             return Err(ChannelError::InsufficientBalance);
         }
+        // The refund path locks until `timeout` as a transaction lock time
+        if timeout > u64::from(u32::MAX) {
+            return Err(ChannelError::InvalidTimeout(timeout));
+        }
 
         // Create HTLC with quantum signature
         let htlc_data = format!(
@@ -472,6 +478,9 @@ pub enum ChannelError {
     #[error("Invalid preimage")]
     InvalidPreimage,
 
+    #[error("HTLC timeout {0} does not fit a lock time")]
+    InvalidTimeout(u64),
+
     #[error("Quantum signature error: {0}")]
     QuantumSignature(String),
 
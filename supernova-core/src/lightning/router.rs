@@ -32,6 +32,9 @@ pub enum RoutingError {
 
     #[error("Routing constraint error: {0}")]
     ConstraintError(String),
+
+    #[error("Import error: {0}")]
+    ImportError(String),
 }
 
 /// Node identifier in the Lightning Network
@@ -217,6 +220,10 @@ pub struct RouterPreferences {
 
     /// Channels to avoid
     pub avoid_channels: HashSet<ChannelId>,
+
+    /// Extra cost in millinova charged against a channel that has only
+    /// ever failed, scaled down by its observed success rate
+    pub failure_penalty_mnova: u64,
 }
 
 impl Default for RouterPreferences {
@@ -230,6 +237,7 @@ impl Default for RouterPreferences {
             preferred_nodes: HashSet::new(),
             avoid_nodes: HashSet::new(),
             avoid_channels: HashSet::new(),
+            failure_penalty_mnova: 100_000, // 100 nova for a channel that always fails
         }
     }
 }
@@ -297,6 +305,9 @@ struct ChannelHistoricalData {
 
     /// Last success time
     last_success: Option<u64>,
+
+    /// Last failure time
+    last_failure: Option<u64>,
 }
 
 impl ChannelScorer {
@@ -346,18 +357,19 @@ impl ChannelScorer {
                 failed_payments: 0,
                 average_time_ms: 0,
                 last_success: None,
+                last_failure: None,
             });
 
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or(Duration::from_secs(0))
+            .as_secs();
         if success {
             data.successful_payments += 1;
-            data.last_success = Some(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or(Duration::from_secs(0))
-                    .as_secs(),
-            );
+            data.last_success = Some(now);
         } else {
             data.failed_payments += 1;
+            data.last_failure = Some(now);
         }
 
         // Update success probability
@@ -367,6 +379,108 @@ impl ChannelScorer {
             self.success_probability.insert(channel_id.clone(), prob);
         }
     }
+
+    /// Share of `penalty_mnova` to add to a channel's cost: the fraction of
+    /// recorded attempts through it that failed, or nothing without history
+    pub fn failure_penalty(&self, channel_id: &ChannelId, penalty_mnova: u64) -> u64 {
+        match self.historical_data.get(channel_id) {
+            Some(data) => {
+                let total = data.successful_payments + data.failed_payments;
+                if total == 0 {
+                    0
+                } else {
+                    ((penalty_mnova as u128 * data.failed_payments as u128) / total as u128) as u64
+                }
+            }
+            None => 0,
+        }
+    }
+
+    /// Recorded payment outcomes for every channel with history
+    pub fn history(&self) -> Vec<ChannelHistory> {
+        self.historical_data
+            .iter()
+            .map(|(channel_id, data)| ChannelHistory {
+                channel_id: channel_id.clone(),
+                successes: data.successful_payments,
+                failures: data.failed_payments,
+                last_success: data.last_success,
+                last_failure: data.last_failure,
+            })
+            .collect()
+    }
+
+    /// Seed a channel's history, unless it already has local observations.
+    /// Returns whether the entry was taken.
+    pub fn seed_history(&mut self, history: &ChannelHistory) -> bool {
+        if self.historical_data.contains_key(&history.channel_id) {
+            return false;
+        }
+        let total = history.successes + history.failures;
+        if total == 0 {
+            return false;
+        }
+        self.historical_data.insert(
+            history.channel_id.clone(),
+            ChannelHistoricalData {
+                successful_payments: history.successes,
+                failed_payments: history.failures,
+                average_time_ms: 0,
+                last_success: history.last_success,
+                last_failure: history.last_failure,
+            },
+        );
+        self.success_probability.insert(
+            history.channel_id.clone(),
+            history.successes as f64 / total as f64,
+        );
+        true
+    }
+}
+
+/// Payment outcomes observed through one channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelHistory {
+    /// Channel ID
+    pub channel_id: ChannelId,
+
+    /// Number of successful payments
+    pub successes: u64,
+
+    /// Number of failed payments
+    pub failures: u64,
+
+    /// Last success time
+    pub last_success: Option<u64>,
+
+    /// Last failure time
+    pub last_failure: Option<u64>,
+}
+
+/// Announced metadata of a node in the network graph
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeAnnouncement {
+    /// Human-readable alias
+    pub alias: String,
+
+    /// Feature bits the node advertises
+    pub features: Vec<u32>,
+
+    /// Environmental data, if the node publishes any
+    pub environmental: Option<NodeEnvironmentalData>,
+}
+
+/// Environmental attributes announced by a node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeEnvironmentalData {
+    /// Share of the node's energy from renewable sources (0-100)
+    pub renewable_percentage: f64,
+
+    /// Carbon footprint per forwarded payment in grams of CO2e
+    pub carbon_footprint_per_tx: f64,
+
+    /// Whether the node holds a green certification
+    pub green_certified: bool,
 }
 
 /// Network graph representing the Lightning Network
@@ -381,6 +495,9 @@ pub struct NetworkGraph {
     /// Private channels not announced to the network
     private_channels: HashMap<ChannelId, ChannelInfo>,
 
+    /// Announced node metadata
+    announcements: HashMap<NodeId, NodeAnnouncement>,
+
     /// Last update time
     last_update: u64,
 }
@@ -398,6 +515,7 @@ impl NetworkGraph {
             nodes: HashMap::new(),
             channels: HashMap::new(),
             private_channels: HashMap::new(),
+            announcements: HashMap::new(),
             last_update: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or(Duration::from_secs(0))
@@ -410,6 +528,32 @@ impl NetworkGraph {
         self.nodes.entry(node_id).or_default();
     }
 
+    /// Record a node's announced metadata, adding the node if needed
+    pub fn set_node_announcement(&mut self, node_id: NodeId, announcement: NodeAnnouncement) {
+        self.add_node(node_id.clone());
+        self.announcements.insert(node_id, announcement);
+    }
+
+    /// Announced metadata of a node
+    pub fn node_announcement(&self, node_id: &NodeId) -> Option<&NodeAnnouncement> {
+        self.announcements.get(node_id)
+    }
+
+    /// All known nodes
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeId> {
+        self.nodes.keys()
+    }
+
+    /// All announced (public) channels
+    pub fn public_channels(&self) -> impl Iterator<Item = &ChannelInfo> {
+        self.channels.values()
+    }
+
+    /// Time of the last change to the graph
+    pub fn last_update(&self) -> u64 {
+        self.last_update
+    }
+
     /// Add a channel to the graph
     pub fn add_channel(&mut self, channel: ChannelInfo, is_private: bool) {
         // Make sure nodes exist
@@ -660,6 +804,8 @@ impl Router {
         #[derive(Debug, Clone, PartialEq, Eq)]
         struct RouteState {
             cost: u64,
+            /// Fees plus failure penalties, the quantity minimized
+            score: u64,
             node: NodeId,
             path: Vec<PathHop>,
             total_cltv: u32,
@@ -669,8 +815,8 @@ impl Router {
             fn cmp(&self, other: &Self) -> Ordering {
                 // Reverse for min-heap behavior
                 other
-                    .cost
-                    .cmp(&self.cost)
+                    .score
+                    .cmp(&self.score)
                     .then_with(|| other.total_cltv.cmp(&self.total_cltv))
             }
         }
@@ -688,6 +834,7 @@ impl Router {
         // Initialize with source node
         heap.push(RouteState {
             cost: 0,
+            score: 0,
             node: source.clone(),
             path: Vec::new(),
             total_cltv: 0,
@@ -766,6 +913,12 @@ impl Router {
                 }
 
                 let new_cost = current.cost + fee;
+                let new_score = current.score
+                    + fee
+                    + self.scorer.failure_penalty(
+                        &channel.channel_id,
+                        self.preferences.failure_penalty_mnova,
+                    );
                 let new_cltv = current.total_cltv + channel.cltv_expiry_delta as u32;
 
                 // Check CLTV limits
@@ -778,8 +931,8 @@ impl Router {
                     .get(&channel.destination)
                     .cloned()
                     .unwrap_or(u64::MAX);
-                if new_cost < current_distance {
-                    distances.insert(channel.destination.clone(), new_cost);
+                if new_score < current_distance {
+                    distances.insert(channel.destination.clone(), new_score);
 
                     // Create new hop
                     let hop = PathHop {
@@ -799,6 +952,7 @@ impl Router {
                     // Add to heap for exploration
                     heap.push(RouteState {
                         cost: new_cost,
+                        score: new_score,
                         node: channel.destination.clone(),
                         path: new_path,
                         total_cltv: new_cltv,
@@ -912,6 +1066,32 @@ impl Router {
         result
     }
 
+    /// Record the outcome of a payment attempt through a channel
+    pub fn record_payment_result(&mut self, channel_id: &ChannelId, success: bool) {
+        self.scorer.update_success_probability(channel_id, success);
+    }
+
+    /// Payment outcomes observed per channel
+    pub fn channel_history(&self) -> Vec<ChannelHistory> {
+        self.scorer.history()
+    }
+
+    /// Seed a channel's history from an earlier export; local observations
+    /// win over imported ones. Returns whether the entry was taken.
+    pub fn seed_channel_history(&mut self, history: &ChannelHistory) -> bool {
+        self.scorer.seed_history(history)
+    }
+
+    /// The known network graph
+    pub fn graph(&self) -> &NetworkGraph {
+        &self.graph
+    }
+
+    /// Record a node announcement in the network graph
+    pub fn update_node(&mut self, node_id: NodeId, announcement: NodeAnnouncement) {
+        self.graph.set_node_announcement(node_id, announcement);
+    }
+
     /// Update the network graph with a new channel
     pub fn update_channel(&mut self, channel: ChannelInfo, is_private: bool) {
         self.graph.add_channel(channel, is_private);
@@ -5,12 +5,13 @@
 
 use crate::crypto::quantum::{QuantumKeyPair, QuantumScheme};
 use crate::lightning::channel::ChannelId;
-use crate::lightning::invoice::{Invoice, InvoiceError};
+use crate::lightning::invoice::{Invoice, InvoiceError, InvoiceStoreKey};
 use crate::lightning::payment::{PaymentHash, PaymentPreimage};
+use crate::types::transaction::OutPoint;
 
 use rand::{thread_rng, Rng, RngCore};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::SystemTime;
 use subtle::ConstantTimeEq;
 use thiserror::Error;
@@ -38,6 +39,9 @@ pub enum WalletError {
 
     #[error("Cryptographic error: {0}")]
     CryptoError(String),
+
+    #[error("UTXO error: {0}")]
+    UtxoError(String),
 }
 
 /// Key derivation scheme for Lightning wallet
@@ -229,6 +233,19 @@ pub struct Payment {
     channel_id: Option<ChannelId>,
 }
 
+/// An on-chain output owned by the Lightning wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletUtxo {
+    /// Output being referenced
+    pub outpoint: OutPoint,
+
+    /// Value in novas
+    pub value: u64,
+
+    /// Locking script of the output
+    pub script_pubkey: Vec<u8>,
+}
+
 /// Main Lightning wallet implementation
 pub struct LightningWallet {
     /// Key manager
@@ -248,6 +265,12 @@ pub struct LightningWallet {
 
     /// Payment preimages
     preimages: HashMap<PaymentHash, PaymentPreimage>,
+
+    /// On-chain outputs available for funding channels
+    utxos: HashMap<OutPoint, WalletUtxo>,
+
+    /// Outputs reserved by an in-flight funding transaction
+    locked_utxos: HashSet<OutPoint>,
 }
 
 impl LightningWallet {
@@ -266,6 +289,8 @@ impl LightningWallet {
             invoices: HashMap::new(),
             payments: HashMap::new(),
             preimages: HashMap::new(),
+            utxos: HashMap::new(),
+            locked_utxos: HashSet::new(),
         })
     }
 
@@ -291,6 +316,8 @@ impl LightningWallet {
             invoices: HashMap::new(),
             payments: HashMap::new(),
             preimages: HashMap::new(),
+            utxos: HashMap::new(),
+            locked_utxos: HashSet::new(),
         })
     }
 
@@ -320,6 +347,77 @@ impl LightningWallet {
         self.on_chain_balance = balance;
     }
 
+    /// Track an on-chain output owned by this wallet
+    pub fn add_utxo(&mut self, utxo: WalletUtxo) {
+        let value = utxo.value;
+        if self.utxos.insert(utxo.outpoint.clone(), utxo).is_none() {
+            self.on_chain_balance = self.on_chain_balance.saturating_add(value);
+        }
+    }
+
+    /// Forget an output once it has been spent on-chain
+    pub fn remove_utxo(&mut self, outpoint: &OutPoint) -> Option<WalletUtxo> {
+        self.locked_utxos.remove(outpoint);
+        let utxo = self.utxos.remove(outpoint)?;
+        self.on_chain_balance = self.on_chain_balance.saturating_sub(utxo.value);
+        Some(utxo)
+    }
+
+    /// Look up a tracked output
+    pub fn get_utxo(&self, outpoint: &OutPoint) -> Option<&WalletUtxo> {
+        self.utxos.get(outpoint)
+    }
+
+    /// Reserve an output so no other transaction selects it
+    pub fn lock_utxo(&mut self, outpoint: &OutPoint) -> Result<(), WalletError> {
+        if !self.utxos.contains_key(outpoint) {
+            return Err(WalletError::UtxoError(format!("Unknown output {}", outpoint)));
+        }
+        if !self.locked_utxos.insert(outpoint.clone()) {
+            return Err(WalletError::UtxoError(format!(
+                "Output {} is already locked",
+                outpoint
+            )));
+        }
+        Ok(())
+    }
+
+    /// Release a reserved output; returns whether it was locked
+    pub fn unlock_utxo(&mut self, outpoint: &OutPoint) -> bool {
+        self.locked_utxos.remove(outpoint)
+    }
+
+    /// Check whether an output is reserved
+    pub fn is_utxo_locked(&self, outpoint: &OutPoint) -> bool {
+        self.locked_utxos.contains(outpoint)
+    }
+
+    /// Script that change from funding transactions is paid to: the
+    /// commitment of the node public key, like any wallet output
+    pub fn change_script(&self) -> Result<Vec<u8>, WalletError> {
+        let secp = secp256k1::Secp256k1::new();
+        let secret = secp256k1::SecretKey::from_slice(self.key_manager.node_private_key())
+            .map_err(|e| WalletError::KeyError(format!("Invalid node key: {}", e)))?;
+        let public = secp256k1::PublicKey::from_secret_key(&secp, &secret);
+        Ok(crate::types::transaction::pubkey_commitment(&public.serialize()))
+    }
+
+    /// Outputs that are neither locked nor spent, largest first
+    pub fn list_unspent(&self) -> Vec<&WalletUtxo> {
+        let mut unspent: Vec<&WalletUtxo> = self
+            .utxos
+            .values()
+            .filter(|utxo| !self.locked_utxos.contains(&utxo.outpoint))
+            .collect();
+        unspent.sort_by(|a, b| {
+            b.value
+                .cmp(&a.value)
+                .then_with(|| a.outpoint.txid.cmp(&b.outpoint.txid))
+                .then_with(|| a.outpoint.vout.cmp(&b.outpoint.vout))
+        });
+        unspent
+    }
+
     /// Update a channel balance
     pub fn update_channel_balance(&mut self, channel_id: ChannelId, balance: u64) {
         self.channel_balances.insert(channel_id, balance);
@@ -340,13 +438,14 @@ impl LightningWallet {
         let preimage = PaymentPreimage::new(preimage_bytes);
 
         // Create invoice with preimage - payment hash will be derived automatically
-        let invoice = Invoice::new_with_preimage(
-            preimage,
+        let mut invoice = Invoice::new_with_preimage(
+            preimage.clone(),
             amount_mnova,
             description.to_string(),
             expiry_seconds,
         )?;
 
+        self.sign_invoice(&mut invoice)?;
         let payment_hash = invoice.payment_hash();
 
         // Store the invoice and preimage
@@ -356,6 +455,19 @@ impl LightningWallet {
         Ok(invoice)
     }
 
+    /// Sign `invoice` with the node key, and the node's quantum key when
+    /// quantum keys are enabled; returns its BOLT11 encoding
+    pub fn sign_invoice(&mut self, invoice: &mut Invoice) -> Result<String, WalletError> {
+        let node_key = secp256k1::SecretKey::from_slice(self.key_manager.node_private_key())
+            .map_err(|e| WalletError::KeyError(format!("Invalid node key: {}", e)))?;
+        if self.key_manager.quantum_keys.is_some()
+            && self.key_manager.get_quantum_keypair("node").is_none()
+        {
+            self.key_manager.derive_key("node")?;
+        }
+        Ok(invoice.sign(&node_key, self.key_manager.get_quantum_keypair("node"))?)
+    }
+
     /// Pay an invoice
     pub fn pay_invoice(&mut self, invoice: &Invoice) -> Result<PaymentPreimage, WalletError> {
         let payment_hash = invoice.payment_hash();
@@ -379,7 +491,7 @@ impl LightningWallet {
 
         // Check if we already have the preimage for this payment hash
         if let Some(existing_preimage) = self.preimages.get(&payment_hash) {
-            return Ok(*existing_preimage);
+            return Ok(existing_preimage.clone());
         }
 
         // In a production Lightning Network implementation, this would:
@@ -408,7 +520,7 @@ impl LightningWallet {
             description: invoice.description().to_string(),
             creation_time: SystemTime::now(),
             status: PaymentStatus::Succeeded,
-            preimage: Some(preimage),
+            preimage: Some(preimage.clone()),
             channel_id: None,
         };
 
@@ -419,7 +531,7 @@ impl LightningWallet {
         self.payments.insert(payment_hash, payment);
 
         // Store the preimage for future reference
-        self.preimages.insert(payment_hash, preimage);
+        self.preimages.insert(payment_hash, preimage.clone());
 
         Ok(preimage)
     }
@@ -453,6 +565,11 @@ impl LightningWallet {
         }
     }
 
+    /// Key for sealing this wallet's invoice store at rest
+    pub fn invoice_store_key(&self) -> Result<InvoiceStoreKey, WalletError> {
+        Ok(InvoiceStoreKey::from_seed(&self.key_manager.master_seed)?)
+    }
+
     /// Get the preimage for a payment hash (if we have it)
     pub fn get_preimage(&self, payment_hash: &PaymentHash) -> Option<&PaymentPreimage> {
         self.preimages.get(payment_hash)
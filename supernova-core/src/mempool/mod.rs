@@ -1,3 +1,5 @@
+pub mod package;
 pub mod transaction_pool;
 
+pub use package::{select_by_package_fee_rate, PackageEntry};
 pub use transaction_pool::{MempoolEntry, MempoolError, TransactionPool, TransactionPoolConfig};
@@ -0,0 +1,235 @@
+//! Ancestor package selection for block assembly
+//!
+//! Ranks unconfirmed transactions by the fee rate of their ancestor package
+//! (the transaction plus every unconfirmed parent it needs) rather than by
+//! their own fee rate, so a high-fee child pulls a low-fee parent into a block
+//! (child-pays-for-parent). Parents are always emitted before their children.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::types::transaction::Transaction;
+
+/// A transaction offered for selection
+#[derive(Debug, Clone)]
+pub struct PackageEntry {
+    /// Transaction hash
+    pub hash: [u8; 32],
+    /// Absolute fee
+    pub fee: u64,
+    /// Size in bytes
+    pub size: usize,
+    /// Hashes of the transactions this one spends from. Parents that are not
+    /// among the candidates are treated as confirmed.
+    pub parents: Vec<[u8; 32]>,
+}
+
+impl PackageEntry {
+    /// Describe `tx`, taking its parents from its inputs
+    pub fn new(tx: &Transaction, fee: u64, size: usize) -> Self {
+        let mut parents: Vec<[u8; 32]> = tx.inputs().iter().map(|i| i.prev_tx_hash()).collect();
+        parents.sort_unstable();
+        parents.dedup();
+        Self {
+            hash: tx.hash(),
+            fee,
+            size,
+            parents,
+        }
+    }
+}
+
+/// Heap key: a package's fee and size, ordered by fee rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Candidate {
+    fee: u64,
+    size: usize,
+    index: usize,
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // fee / size compared without division; a zero size ranks as size 1
+        let lhs = self.fee as u128 * other.size.max(1) as u128;
+        let rhs = other.fee as u128 * self.size.max(1) as u128;
+        lhs.cmp(&rhs)
+            // Prefer the earlier entry so the order is deterministic
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Select entries by ancestor package fee rate until `max_size` bytes are
+/// used, returning their indices in block order. A package that does not fit
+/// is skipped whole; pass `usize::MAX` to order every entry.
+pub fn select_by_package_fee_rate(entries: &[PackageEntry], max_size: usize) -> Vec<usize> {
+    let mut index_of: HashMap<[u8; 32], usize> = HashMap::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        index_of.entry(entry.hash).or_insert(i);
+    }
+    let parents: Vec<Vec<usize>> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            entry
+                .parents
+                .iter()
+                .filter_map(|p| index_of.get(p).copied())
+                .filter(|&p| p != i)
+                .collect()
+        })
+        .collect();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); entries.len()];
+    for (child, ps) in parents.iter().enumerate() {
+        for &p in ps {
+            children[p].push(child);
+        }
+    }
+
+    let mut selected = vec![false; entries.len()];
+    let mut skipped = vec![false; entries.len()];
+
+    // The entry plus its unselected ancestors
+    let package = |index: usize, selected: &[bool]| -> HashSet<usize> {
+        let mut members = HashSet::from([index]);
+        let mut stack = vec![index];
+        while let Some(current) = stack.pop() {
+            for &p in &parents[current] {
+                if !selected[p] && members.insert(p) {
+                    stack.push(p);
+                }
+            }
+        }
+        members
+    };
+    let score = |index: usize, selected: &[bool]| -> Candidate {
+        let members = package(index, selected);
+        Candidate {
+            fee: members
+                .iter()
+                .fold(0u64, |acc, &m| acc.saturating_add(entries[m].fee)),
+            size: members
+                .iter()
+                .fold(0usize, |acc, &m| acc.saturating_add(entries[m].size)),
+            index,
+        }
+    };
+
+    let mut heap: BinaryHeap<Candidate> = (0..entries.len()).map(|i| score(i, &selected)).collect();
+    let mut order = Vec::with_capacity(entries.len());
+    let mut used = 0usize;
+
+    while let Some(top) = heap.pop() {
+        if selected[top.index] || skipped[top.index] {
+            continue;
+        }
+        // Ancestors selected since this key was pushed change the package
+        let current = score(top.index, &selected);
+        if current != top {
+            heap.push(current);
+            continue;
+        }
+        if used.saturating_add(current.size) > max_size {
+            skipped[top.index] = true;
+            continue;
+        }
+        used += current.size;
+
+        // Emit the package parents-first
+        let members = package(top.index, &selected);
+        let mut stack = vec![(top.index, false)];
+        while let Some((current, expanded)) = stack.pop() {
+            if selected[current] {
+                continue;
+            }
+            if expanded {
+                selected[current] = true;
+                order.push(current);
+                continue;
+            }
+            stack.push((current, true));
+            for &p in &parents[current] {
+                if members.contains(&p) && !selected[p] {
+                    stack.push((p, false));
+                }
+            }
+        }
+
+        // Descendants now carry fewer ancestors; rescore them
+        let mut stack: Vec<usize> = members
+            .iter()
+            .flat_map(|&m| children[m].iter().copied())
+            .collect();
+        let mut seen = HashSet::new();
+        while let Some(descendant) = stack.pop() {
+            if selected[descendant] || !seen.insert(descendant) {
+                continue;
+            }
+            heap.push(score(descendant, &selected));
+            stack.extend(children[descendant].iter().copied());
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u8, fee: u64, size: usize, parents: &[u8]) -> PackageEntry {
+        PackageEntry {
+            hash: [id; 32],
+            fee,
+            size,
+            parents: parents.iter().map(|p| [*p; 32]).collect(),
+        }
+    }
+
+    #[test]
+    fn test_child_pays_for_parent() {
+        let entries = vec![
+            entry(1, 100, 100, &[]),    // parent, 1 nova/byte
+            entry(2, 500, 100, &[]),    // independent, 5 nova/byte
+            entry(3, 2_000, 100, &[1]), // child, package 10.5 nova/byte
+        ];
+
+        let order = select_by_package_fee_rate(&entries, usize::MAX);
+        assert_eq!(order, vec![0, 2, 1]);
+
+        // With room for one package only the parent and child are chosen
+        let order = select_by_package_fee_rate(&entries, 200);
+        assert_eq!(order, vec![0, 2]);
+    }
+
+    #[test]
+    fn test_parents_precede_children() {
+        // A chain where fees rise toward the tip
+        let entries = vec![
+            entry(3, 3_000, 100, &[2]),
+            entry(2, 10, 100, &[1]),
+            entry(1, 10, 100, &[]),
+        ];
+
+        let order = select_by_package_fee_rate(&entries, usize::MAX);
+        assert_eq!(order, vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn test_oversized_package_is_skipped() {
+        let entries = vec![
+            entry(1, 1_000, 900, &[]),
+            entry(2, 50_000, 200, &[1]),
+            entry(3, 100, 100, &[]),
+        ];
+
+        // The child's package needs 1100 bytes, which does not fit
+        let order = select_by_package_fee_rate(&entries, 1_000);
+        assert_eq!(order, vec![0, 2]);
+    }
+}
@@ -1,11 +1,12 @@
 use dashmap::DashMap;
 use hex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 use thiserror::Error;
 
+use super::package::{select_by_package_fee_rate, PackageEntry};
 use crate::types::transaction::{Transaction, TransactionOutput};
 use crate::types::transaction_dependency::TransactionDependencyGraph;
 
@@ -46,6 +47,10 @@ pub enum MempoolError {
     #[error("Transaction conflicts with existing mempool transaction")]
     Conflict,
 
+    /// Transaction would make an unconfirmed chain exceed the configured limits
+    #[error("Transaction exceeds mempool chain limits: {0}")]
+    TooLongChain(String),
+
     /// General error
     #[error("Mempool error: {0}")]
     Other(String),
@@ -64,12 +69,41 @@ pub struct MempoolEntry {
     pub fee_rate: u64,
     /// Size in bytes
     pub size: usize,
+    /// Number of transactions in the ancestor package, including this one
+    pub ancestor_count: usize,
     /// Ancestor package size in bytes
     pub ancestor_size: usize,
     /// Ancestor package fee in satoshis
     pub ancestor_fee: u64,
     /// Ancestor package fee rate in satoshis per byte
     pub ancestor_fee_rate: u64,
+    /// Number of in-pool descendants, including this transaction
+    pub descendant_count: usize,
+    /// Descendant size in bytes, including this transaction
+    pub descendant_size: usize,
+    /// Descendant fee in satoshis, including this transaction
+    pub descendant_fee: u64,
+}
+
+impl MempoolEntry {
+    /// Entry for a transaction with no relatives in the pool yet
+    pub fn new(transaction: Transaction, fee: u64, size: usize) -> Self {
+        let fee_rate = fee / size.max(1) as u64;
+        Self {
+            transaction,
+            time_added: Instant::now(),
+            fee,
+            fee_rate,
+            size,
+            ancestor_count: 1,
+            ancestor_size: size,
+            ancestor_fee: fee,
+            ancestor_fee_rate: fee_rate,
+            descendant_count: 1,
+            descendant_size: size,
+            descendant_fee: fee,
+        }
+    }
 }
 
 /// Configuration for the transaction pool
@@ -85,6 +119,14 @@ pub struct TransactionPoolConfig {
     pub enable_replace_by_fee: bool,
     /// Minimum fee rate increment for replace-by-fee (percentage)
     pub rbf_min_fee_increment: u8,
+    /// Maximum transactions in an ancestor package, including the new one
+    pub max_ancestors: usize,
+    /// Maximum ancestor package size in bytes
+    pub max_ancestor_size: usize,
+    /// Maximum in-pool descendants of any transaction, including itself
+    pub max_descendants: usize,
+    /// Maximum descendant size in bytes of any transaction, including itself
+    pub max_descendant_size: usize,
 }
 
 impl Default for TransactionPoolConfig {
@@ -95,6 +137,10 @@ impl Default for TransactionPoolConfig {
             max_orphan_transactions: 100,
             enable_replace_by_fee: true,
             rbf_min_fee_increment: 10, // 10% higher fee required for RBF
+            max_ancestors: 25,
+            max_ancestor_size: 101 * 1024, // 101 KB
+            max_descendants: 25,
+            max_descendant_size: 101 * 1024, // 101 KB
         }
     }
 }
@@ -183,21 +229,14 @@ impl TransactionPool {
             });
         }
 
+        // Refuse to extend unconfirmed chains past the configured limits
+        self.check_chain_limits(&tx, size)?;
+
         // Check for conflicts and handle replace-by-fee if enabled
         self.check_conflicts(&tx, fee_rate)?;
 
         // Create mempool entry
-        let entry = MempoolEntry {
-            transaction: tx.clone(),
-            time_added: Instant::now(),
-            fee,
-            fee_rate,
-            size,
-            // These will be updated later
-            ancestor_size: size,
-            ancestor_fee: fee,
-            ancestor_fee_rate: fee_rate,
-        };
+        let entry = MempoolEntry::new(tx.clone(), fee, size);
 
         // Update dependency graph
         {
@@ -214,12 +253,12 @@ impl TransactionPool {
             graph.add_transaction(&tx, &mempool_txs);
         }
 
-        // Update ancestor metrics for this transaction and its descendants
-        self.update_ancestor_metrics(&tx_hash);
-
         // Add to mempool
         self.transactions.insert(tx_hash, entry);
 
+        // Update package metrics for this transaction and everything related to it
+        self.update_chain_metrics(&tx_hash);
+
         // Update mempool size
         {
             let mut size_bytes = self
@@ -253,13 +292,19 @@ impl TransactionPool {
         }
 
         // Update dependency graph
-        {
+        let related = {
             let mut graph = self
                 .dependency_graph
                 .lock()
                 .map_err(|e| MempoolError::Other(format!("Lock poisoned: {}", e)))?;
+            let mut related = graph.get_all_ancestors(tx_hash);
+            related.extend(graph.get_all_descendants(tx_hash));
             graph.remove_transaction(tx_hash);
-        }
+            related
+        };
+
+        // Ancestors lose a descendant and descendants lose an ancestor
+        self.refresh_chain_metrics(related);
 
         Ok(entry.transaction)
     }
@@ -279,19 +324,29 @@ impl TransactionPool {
             .collect()
     }
 
-    /// Get transactions sorted by fee rate (highest first)
+    /// Get transactions by ancestor package fee rate (highest first), with
+    /// parents ahead of their children
     pub fn get_sorted_transactions(&self) -> Vec<Transaction> {
-        let mut entries: Vec<_> = self
+        self.select_packages(usize::MAX)
+    }
+
+    /// Select up to `max_size` bytes of transactions by ancestor package fee
+    /// rate, so a high-fee child can pull in its low-fee parent
+    pub fn select_packages(&self, max_size: usize) -> Vec<Transaction> {
+        let entries: Vec<MempoolEntry> = self
             .transactions
             .iter()
             .map(|r| r.value().clone())
             .collect();
+        let packages: Vec<PackageEntry> = entries
+            .iter()
+            .map(|entry| PackageEntry::new(&entry.transaction, entry.fee, entry.size))
+            .collect();
 
-        // Sort by fee rate (highest first)
-        entries.sort_by(|a, b| b.fee_rate.cmp(&a.fee_rate));
-
-        // Return just the transactions
-        entries.into_iter().map(|entry| entry.transaction).collect()
+        select_by_package_fee_rate(&packages, max_size)
+            .into_iter()
+            .map(|i| entries[i].transaction.clone())
+            .collect()
     }
 
     /// Get transactions in order of priority (highest fee rate first)
@@ -619,9 +674,86 @@ impl TransactionPool {
         std::cmp::max(base_fee_rate + increment, self.config.min_fee_rate)
     }
 
-    /// Update ancestor metrics for a transaction and its descendants
-    fn update_ancestor_metrics(&self, tx_hash: &[u8; 32]) {
-        // Get the dependency graph
+    /// Reject `tx` if it would give itself too many or too large ancestors,
+    /// or give any of its ancestors too many or too large descendants
+    fn check_chain_limits(&self, tx: &Transaction, size: usize) -> Result<(), MempoolError> {
+        let ancestors = {
+            let graph = self
+                .dependency_graph
+                .lock()
+                .map_err(|e| MempoolError::Other(format!("Lock poisoned: {}", e)))?;
+            let mut ancestors = HashSet::new();
+            for input in tx.inputs() {
+                let parent = input.prev_tx_hash();
+                if self.transactions.contains_key(&parent) && ancestors.insert(parent) {
+                    ancestors.extend(graph.get_all_ancestors(&parent));
+                }
+            }
+            ancestors
+        };
+
+        let ancestor_count = ancestors.len() + 1;
+        if ancestor_count > self.config.max_ancestors {
+            return Err(MempoolError::TooLongChain(format!(
+                "{} ancestors exceeds limit of {}",
+                ancestor_count, self.config.max_ancestors
+            )));
+        }
+
+        let mut ancestor_size = size;
+        for hash in &ancestors {
+            let Some(entry) = self.transactions.get(hash) else {
+                continue;
+            };
+            ancestor_size += entry.size;
+            if entry.descendant_count + 1 > self.config.max_descendants {
+                return Err(MempoolError::TooLongChain(format!(
+                    "ancestor {} would have {} descendants, limit is {}",
+                    hex::encode(hash),
+                    entry.descendant_count + 1,
+                    self.config.max_descendants
+                )));
+            }
+            if entry.descendant_size + size > self.config.max_descendant_size {
+                return Err(MempoolError::TooLongChain(format!(
+                    "ancestor {} would have {} bytes of descendants, limit is {}",
+                    hex::encode(hash),
+                    entry.descendant_size + size,
+                    self.config.max_descendant_size
+                )));
+            }
+        }
+        if ancestor_size > self.config.max_ancestor_size {
+            return Err(MempoolError::TooLongChain(format!(
+                "{} bytes of ancestors exceeds limit of {}",
+                ancestor_size, self.config.max_ancestor_size
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Recompute package metrics for a newly added transaction and every
+    /// transaction sharing a chain with it
+    fn update_chain_metrics(&self, tx_hash: &[u8; 32]) {
+        let related = match self.dependency_graph.lock() {
+            Ok(graph) => {
+                let mut related = graph.get_all_ancestors(tx_hash);
+                related.extend(graph.get_all_descendants(tx_hash));
+                related.insert(*tx_hash);
+                related
+            }
+            Err(e) => {
+                log::error!("Failed to acquire dependency graph lock: {}", e);
+                return;
+            }
+        };
+        self.refresh_chain_metrics(related);
+    }
+
+    /// Recompute ancestor and descendant totals for `hashes` from the
+    /// dependency graph
+    fn refresh_chain_metrics(&self, hashes: HashSet<[u8; 32]>) {
         let graph = match self.dependency_graph.lock() {
             Ok(g) => g,
             Err(e) => {
@@ -630,50 +762,31 @@ impl TransactionPool {
             }
         };
 
-        // Get all descendants of this transaction
-        let descendants = graph.get_all_descendants(tx_hash);
-
-        // Add the transaction itself
-        let mut to_update = descendants;
-        to_update.insert(*tx_hash);
-
-        // Update each transaction's ancestor metrics
-        for desc_hash in to_update {
-            // Calculate package fee rate including all ancestors
-            if let Some(package_fee_rate) = graph.calculate_package_fee_rate(
-                &desc_hash,
-                &self
-                    .transactions
-                    .iter()
-                    .map(|r| (*r.key(), r.transaction.clone()))
-                    .collect(),
-                self.get_utxo_or_mempool(),
-            ) {
-                // Get ancestors
-                let ancestors = graph.get_all_ancestors(&desc_hash);
-
-                // Calculate total ancestor size and fee
-                let mut ancestor_size = 0;
-                let mut ancestor_fee = 0;
-
-                if let Some(entry) = self.transactions.get(&desc_hash) {
-                    ancestor_size += entry.size;
-                    ancestor_fee += entry.fee;
-                }
-
-                for anc_hash in ancestors {
-                    if let Some(entry) = self.transactions.get(&anc_hash) {
-                        ancestor_size += entry.size;
-                        ancestor_fee += entry.fee;
-                    }
-                }
+        // Sum `set` plus `hash` itself as (count, size, fee)
+        let totals = |hash: &[u8; 32], set: HashSet<[u8; 32]>| -> (usize, usize, u64) {
+            set.iter()
+                .chain(std::iter::once(hash))
+                .filter_map(|h| self.transactions.get(h).map(|e| (e.size, e.fee)))
+                .fold((0, 0, 0), |(count, size, fee), (s, f)| {
+                    (count + 1, size + s, fee.saturating_add(f))
+                })
+        };
 
-                // Update the entry
-                if let Some(mut entry) = self.transactions.get_mut(&desc_hash) {
-                    entry.ancestor_size = ancestor_size;
-                    entry.ancestor_fee = ancestor_fee;
-                    entry.ancestor_fee_rate = package_fee_rate;
-                }
+        for hash in hashes {
+            if !self.transactions.contains_key(&hash) {
+                continue;
+            }
+            let ancestors = totals(&hash, graph.get_all_ancestors(&hash));
+            let descendants = totals(&hash, graph.get_all_descendants(&hash));
+
+            if let Some(mut entry) = self.transactions.get_mut(&hash) {
+                entry.ancestor_count = ancestors.0;
+                entry.ancestor_size = ancestors.1;
+                entry.ancestor_fee = ancestors.2;
+                entry.ancestor_fee_rate = ancestors.2 / ancestors.1.max(1) as u64;
+                entry.descendant_count = descendants.0;
+                entry.descendant_size = descendants.1;
+                entry.descendant_fee = descendants.2;
             }
         }
     }
@@ -957,21 +1070,118 @@ mod tests {
     // through add_transaction's validation, so we can exercise check_conflicts
     // directly.
     fn insert_raw_entry(mempool: &TransactionPool, tx: &Transaction, fee: u64, size: usize) {
-        let fee_rate = fee / size as u64;
-        let entry = MempoolEntry {
-            transaction: tx.clone(),
-            time_added: Instant::now(),
-            fee,
-            fee_rate,
-            size,
-            ancestor_size: size,
-            ancestor_fee: fee,
-            ancestor_fee_rate: fee_rate,
-        };
+        let entry = MempoolEntry::new(tx.clone(), fee, size);
         mempool.transactions.insert(tx.hash(), entry);
         *mempool.size_bytes.write().unwrap() += size;
     }
 
+    // Helper: like `insert_raw_entry`, but also links the entry into the
+    // dependency graph so chain metrics and limits apply to it.
+    fn insert_chained_entry(mempool: &TransactionPool, tx: &Transaction, fee: u64, size: usize) {
+        let mempool_txs: HashMap<[u8; 32], Transaction> = mempool
+            .transactions
+            .iter()
+            .map(|entry| (*entry.key(), entry.transaction.clone()))
+            .collect();
+        mempool
+            .dependency_graph
+            .lock()
+            .unwrap()
+            .add_transaction(tx, &mempool_txs);
+        insert_raw_entry(mempool, tx, fee, size);
+        mempool.update_chain_metrics(&tx.hash());
+    }
+
+    #[test]
+    fn test_child_bumps_parent_into_template() {
+        let get_utxo = |_tx_hash: &[u8; 32], _index: u32| -> Option<TransactionOutput> { None };
+        let mempool = TransactionPool::new(TransactionPoolConfig::default(), get_utxo);
+
+        // A low-fee parent, an unrelated mid-fee transaction and a high-fee
+        // child spending the parent
+        let parent = create_test_tx(vec![(vec![1], 0)], 90_000);
+        let other = create_test_tx(vec![(vec![2], 0)], 90_000);
+        let child = create_test_tx(vec![(parent.hash().to_vec(), 0)], 80_000);
+        insert_chained_entry(&mempool, &parent, 100, 100);
+        insert_chained_entry(&mempool, &other, 500, 100);
+        insert_chained_entry(&mempool, &child, 2_000, 100);
+
+        let parent_entry = mempool.transactions.get(&parent.hash()).unwrap().clone();
+        assert_eq!(parent_entry.descendant_count, 2);
+        assert_eq!(parent_entry.descendant_fee, 2_100);
+        let child_entry = mempool.transactions.get(&child.hash()).unwrap().clone();
+        assert_eq!(child_entry.ancestor_count, 2);
+        assert_eq!(child_entry.ancestor_fee_rate, 10);
+
+        // Room for two transactions: the parent rides in with its child ahead
+        // of the higher-paying unrelated transaction
+        let hashes: Vec<[u8; 32]> = mempool
+            .select_packages(200)
+            .iter()
+            .map(|tx| tx.hash())
+            .collect();
+        assert_eq!(hashes, vec![parent.hash(), child.hash()]);
+
+        let hashes: Vec<[u8; 32]> = mempool
+            .get_sorted_transactions()
+            .iter()
+            .map(|tx| tx.hash())
+            .collect();
+        assert_eq!(hashes, vec![parent.hash(), child.hash(), other.hash()]);
+
+        // Removing the child drops the parent's descendant totals again
+        mempool.remove_transaction(&child.hash()).unwrap();
+        let parent_entry = mempool.transactions.get(&parent.hash()).unwrap().clone();
+        assert_eq!(parent_entry.descendant_count, 1);
+        assert_eq!(parent_entry.descendant_size, 100);
+    }
+
+    #[test]
+    fn test_chain_limits_reject_long_chains() {
+        let get_utxo = |_tx_hash: &[u8; 32], _index: u32| -> Option<TransactionOutput> { None };
+        let mut config = TransactionPoolConfig::default();
+        config.max_ancestors = 3;
+        config.max_descendants = 3;
+        config.max_ancestor_size = 1_000;
+        let mempool = TransactionPool::new(config, get_utxo);
+
+        // root <- a <- b
+        let root = create_test_tx(vec![(vec![1], 0)], 90_000);
+        let a = create_test_tx(vec![(root.hash().to_vec(), 0)], 80_000);
+        let b = create_test_tx(vec![(a.hash().to_vec(), 0)], 70_000);
+        for tx in [&root, &a, &b] {
+            mempool.check_chain_limits(tx, 100).unwrap();
+            insert_chained_entry(&mempool, tx, 1_000, 100);
+        }
+
+        // A fourth link would give it four ancestors
+        let c = create_test_tx(vec![(b.hash().to_vec(), 0)], 60_000);
+        assert!(matches!(
+            mempool.check_chain_limits(&c, 100),
+            Err(MempoolError::TooLongChain(_))
+        ));
+
+        // A second child of `a` would give `root` four descendants
+        let sibling = create_test_tx(vec![(a.hash().to_vec(), 1)], 60_000);
+        assert!(matches!(
+            mempool.check_chain_limits(&sibling, 100),
+            Err(MempoolError::TooLongChain(_))
+        ));
+
+        // A child of a fresh root fits by count but not by ancestor size
+        let other_root = create_test_tx(vec![(vec![2], 0)], 90_000);
+        insert_chained_entry(&mempool, &other_root, 1_000, 100);
+        let heavy = create_test_tx(vec![(other_root.hash().to_vec(), 0)], 60_000);
+        assert!(mempool.check_chain_limits(&heavy, 900).is_ok());
+        assert!(matches!(
+            mempool.check_chain_limits(&heavy, 950),
+            Err(MempoolError::TooLongChain(_))
+        ));
+        // Unrelated transactions are unaffected
+        let unrelated = create_test_tx(vec![(vec![9], 0)], 60_000);
+        assert!(mempool.check_chain_limits(&unrelated, 100).is_ok());
+    }
+
     #[test]
     fn test_check_conflicts_evicts_and_returns_ok() {
         let get_utxo = |_tx_hash: &[u8; 32], _index: u32| -> Option<TransactionOutput> { None };
@@ -1057,16 +1267,7 @@ mod tests {
         tx2_hash[0] = 1; // Match the input of tx1
 
         // Manually insert tx2 to simulate it being mined
-        let entry = MempoolEntry {
-            transaction: tx2.clone(),
-            time_added: Instant::now(),
-            fee: 5_000,
-            fee_rate: 5,
-            size: 1000,
-            ancestor_size: 1000,
-            ancestor_fee: 5_000,
-            ancestor_fee_rate: 5,
-        };
+        let entry = MempoolEntry::new(tx2.clone(), 5_000, 1000);
 
         mempool.transactions.insert(tx2_hash, entry);
 
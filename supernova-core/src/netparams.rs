@@ -0,0 +1,689 @@
+//! Network parameter files for custom networks
+//!
+//! A netparams file defines a network outside the built-in ones: its name,
+//! P2P magic, address prefix, genesis block, subsidy schedule, difficulty
+//! rules and version-bits deployments. The file is a [`SignedEnvelope`]
+//! signed by the network's authority; loaders verify the signature against an
+//! authority key the operator configured and accept an unverified file only
+//! when explicitly told to ([`NetParamsTrust::Insecure`]).
+//!
+//! Files are JSON, or TOML when the path ends in `.toml`. The signature covers
+//! the canonical JSON of the parameters, so both encodings of one network
+//! carry the same signature.
+//!
+//! A process runs one network. [`activate`] makes a file's parameters the
+//! ones consensus code reads: [`crate::types::units::block_subsidy`] follows its
+//! subsidy schedule, addresses use its prefix
+//! ([`crate::script::classify::address_hrp`]) and block merkle roots its
+//! witness-txid activation height ([`crate::consensus::witness`]).
+
+use crate::consensus::difficulty_retarget::{decode_target, RetargetParams};
+use crate::consensus::version_bits::{
+    Deployment, VersionBitsParams, NEVER_STARTS, NO_TIMEOUT, VERSIONBITS_NUM_BITS,
+};
+use crate::crypto::canonical_signing::{
+    CanonicalKeyType, CanonicalPublicKey, CanonicalSignatureError, CanonicalSigningKey,
+    SignedEnvelope,
+};
+use crate::types::block::{Block, BlockHeader};
+use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+use crate::types::units::NOVAS_PER_NOVA;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Subsidy of the first block of the built-in networks
+pub const DEFAULT_INITIAL_REWARD: u64 = 50 * NOVAS_PER_NOVA;
+
+/// Blocks between subsidy halvings on the built-in networks
+pub const DEFAULT_HALVING_INTERVAL: u64 = 420_000;
+
+/// Longest coinbase message a genesis block may carry
+pub const MAX_GENESIS_MESSAGE_LEN: usize = 100;
+
+static ACTIVE: OnceLock<NetParams> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum NetParamsError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed netparams file: {0}")]
+    Parse(String),
+
+    #[error("Signature error: {0}")]
+    Signature(#[from] CanonicalSignatureError),
+
+    #[error("Netparams signature does not verify under the authority key")]
+    BadSignature,
+
+    #[error("Invalid authority key '{0}': expected ed25519:<hex> or secp256k1:<hex>")]
+    InvalidAuthority(String),
+
+    #[error("Invalid network parameters: {0}")]
+    Invalid(String),
+
+    #[error("Genesis block hash is {computed}, but the file declares {declared}")]
+    GenesisMismatch { declared: String, computed: String },
+
+    #[error("No genesis nonce satisfies bits {0:#010x} at this timestamp")]
+    GenesisUnmineable(u32),
+
+    #[error("Network '{0}' is already active in this process")]
+    AlreadyActive(String),
+}
+
+/// Parameters of a custom network
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetParams {
+    /// Network name; used as the chain id and network id
+    pub name: String,
+    /// P2P message magic, 4 bytes hex encoded
+    pub magic: String,
+    /// Human-readable prefix of bech32m addresses
+    pub address_hrp: String,
+    /// Default P2P port
+    pub default_port: u16,
+    /// Blocks per version-bits signaling window
+    pub version_bits_window: u64,
+    pub genesis: GenesisParams,
+    pub subsidy: SubsidySchedule,
+    pub difficulty: DifficultyParams,
+    /// Version-bits deployments
+    #[serde(default)]
+    pub deployments: Vec<DeploymentSchedule>,
+    /// Height from which merkle roots commit to witness-separated txids and
+    /// blocks commit to their wtxids, see [`crate::consensus::witness`].
+    /// Omitted, the network keeps legacy txid merkle roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness_txid_height: Option<u64>,
+}
+
+/// Genesis block contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisParams {
+    /// Header timestamp
+    pub timestamp: u64,
+    /// Compact difficulty target of the genesis header
+    pub bits: u32,
+    pub nonce: u32,
+    /// Message carried in the coinbase input
+    pub message: String,
+    /// Expected block hash, hex encoded
+    pub hash: String,
+    /// Coinbase outputs
+    pub outputs: Vec<PremineOutput>,
+}
+
+/// A genesis coinbase output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PremineOutput {
+    /// Amount in base units (1 NOVA = `NOVAS_PER_NOVA`)
+    pub amount: u64,
+    /// Locking script, hex encoded
+    pub script_pubkey: String,
+}
+
+/// Block subsidy: `initial_reward` halved every `halving_interval` blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubsidySchedule {
+    pub initial_reward: u64,
+    pub halving_interval: u64,
+}
+
+impl Default for SubsidySchedule {
+    fn default() -> Self {
+        Self {
+            initial_reward: DEFAULT_INITIAL_REWARD,
+            halving_interval: DEFAULT_HALVING_INTERVAL,
+        }
+    }
+}
+
+impl SubsidySchedule {
+    /// Subsidy of the block at `height`
+    pub fn subsidy(&self, height: u64) -> u64 {
+        const MAX_HALVINGS: u64 = 64;
+        let halvings = height / self.halving_interval.max(1);
+        if halvings >= MAX_HALVINGS {
+            return 0;
+        }
+        self.initial_reward >> halvings
+    }
+
+    /// Sum of the subsidies of blocks `from..=to`
+    pub fn total_subsidy(&self, from: u64, to: u64) -> u64 {
+        let interval = self.halving_interval.max(1);
+        let mut total = 0u64;
+        let mut height = from;
+        while height <= to {
+            let reward = self.subsidy(height);
+            if reward == 0 {
+                break;
+            }
+            let era_end = (height / interval + 1)
+                .saturating_mul(interval)
+                .saturating_sub(1)
+                .min(to);
+            total = total.saturating_add(reward.saturating_mul(era_end - height + 1));
+            if era_end == u64::MAX {
+                break;
+            }
+            height = era_end + 1;
+        }
+        total
+    }
+}
+
+/// Difficulty retargeting rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DifficultyParams {
+    /// Target seconds per block
+    pub target_block_time: u64,
+    /// Blocks per retarget period
+    pub retarget_interval: u64,
+    /// Easiest permitted compact target
+    pub pow_limit_bits: u32,
+    /// Let a block be mined at `pow_limit_bits` after four block times
+    /// without one; for test networks only. Left out of the file when off so
+    /// existing signatures still verify.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_min_difficulty_blocks: bool,
+}
+
+/// A version-bits deployment. Times are median-time-past; an absent start
+/// never starts and an absent timeout never expires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeploymentSchedule {
+    pub name: String,
+    pub bit: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    pub threshold_percent: u8,
+    #[serde(default)]
+    pub min_activation_height: u64,
+}
+
+/// Signed netparams file
+pub type NetParamsFile = SignedEnvelope<NetParams>;
+
+/// How a loaded file's signature is checked
+#[derive(Debug, Clone)]
+pub enum NetParamsTrust {
+    /// The file must be signed by this key
+    Authority(CanonicalPublicKey),
+    /// The signature is not checked
+    Insecure,
+}
+
+impl NetParamsTrust {
+    /// Trust from an `--netparams-authority` value and an
+    /// `--insecure-netparams` flag. One of the two is required.
+    pub fn from_options(authority: Option<&str>, insecure: bool) -> Result<Self, NetParamsError> {
+        match (authority, insecure) {
+            (Some(key), _) => Ok(NetParamsTrust::Authority(parse_authority(key)?)),
+            (None, true) => Ok(NetParamsTrust::Insecure),
+            (None, false) => Err(NetParamsError::Invalid(
+                "a netparams authority key is required to verify the file; pass \
+                 --insecure-netparams to load it unverified"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+/// Parse an authority key written as `ed25519:<hex>` or `secp256k1:<hex>`
+pub fn parse_authority(value: &str) -> Result<CanonicalPublicKey, NetParamsError> {
+    let invalid = || NetParamsError::InvalidAuthority(value.to_string());
+    let (kind, key) = value.split_once(':').ok_or_else(invalid)?;
+    let key_type = match kind {
+        "ed25519" => CanonicalKeyType::Ed25519,
+        "secp256k1" => CanonicalKeyType::Secp256k1,
+        _ => return Err(invalid()),
+    };
+    let bytes = hex::decode(key).map_err(|_| invalid())?;
+    Ok(CanonicalPublicKey {
+        key_type,
+        key: hex::encode(bytes),
+    })
+}
+
+/// Format a public key the way [`parse_authority`] reads it
+pub fn format_authority(key: &CanonicalPublicKey) -> Option<String> {
+    match key.key_type {
+        CanonicalKeyType::Ed25519 => Some(format!("ed25519:{}", key.key)),
+        CanonicalKeyType::Secp256k1 => Some(format!("secp256k1:{}", key.key)),
+        CanonicalKeyType::Quantum(_) => None,
+    }
+}
+
+impl NetParams {
+    /// P2P magic bytes
+    pub fn magic_bytes(&self) -> Result<[u8; 4], NetParamsError> {
+        let bytes = hex::decode(&self.magic)
+            .map_err(|_| NetParamsError::Invalid(format!("magic '{}' is not hex", self.magic)))?;
+        bytes
+            .try_into()
+            .map_err(|_| NetParamsError::Invalid(format!("magic '{}' must be 4 bytes", self.magic)))
+    }
+
+    /// Declared genesis block hash
+    pub fn genesis_hash(&self) -> Result<[u8; 32], NetParamsError> {
+        let bytes = hex::decode(&self.genesis.hash).map_err(|_| {
+            NetParamsError::Invalid(format!("genesis hash '{}' is not hex", self.genesis.hash))
+        })?;
+        bytes.try_into().map_err(|_| {
+            NetParamsError::Invalid(format!(
+                "genesis hash '{}' must be 32 bytes",
+                self.genesis.hash
+            ))
+        })
+    }
+
+    /// Genesis block built from the parameters, checked against the declared
+    /// hash and the genesis difficulty
+    pub fn genesis_block(&self) -> Result<Block, NetParamsError> {
+        let block = self.unchecked_genesis_block()?;
+        let computed = hex::encode(block.header.hash());
+        if computed != self.genesis.hash.to_ascii_lowercase() {
+            return Err(NetParamsError::GenesisMismatch {
+                declared: self.genesis.hash.clone(),
+                computed,
+            });
+        }
+        if !block.header.meets_target() {
+            return Err(NetParamsError::Invalid(
+                "genesis block does not meet its difficulty target".to_string(),
+            ));
+        }
+        Ok(block)
+    }
+
+    fn unchecked_genesis_block(&self) -> Result<Block, NetParamsError> {
+        let outputs = self
+            .genesis
+            .outputs
+            .iter()
+            .map(|output| {
+                let script = hex::decode(&output.script_pubkey).map_err(|_| {
+                    NetParamsError::Invalid(format!(
+                        "premine script '{}' is not hex",
+                        output.script_pubkey
+                    ))
+                })?;
+                Ok(TransactionOutput::new(output.amount, script))
+            })
+            .collect::<Result<Vec<_>, NetParamsError>>()?;
+        let coinbase = Transaction::new(
+            2,
+            vec![TransactionInput::new_coinbase(
+                self.genesis.message.as_bytes().to_vec(),
+            )],
+            outputs,
+            0,
+        );
+        let header = BlockHeader::new(
+            1,
+            [0u8; 32],
+            [0u8; 32],
+            self.genesis.timestamp,
+            self.genesis.bits,
+            self.genesis.nonce,
+        );
+        let mut block = Block::new(header, vec![coinbase]);
+        block.header.merkle_root = block.calculate_merkle_root();
+        Ok(block)
+    }
+
+    /// Search for a genesis nonce meeting the genesis bits and record it and
+    /// the resulting hash
+    pub fn mine_genesis(&mut self) -> Result<Block, NetParamsError> {
+        let mut block = self.unchecked_genesis_block()?;
+        let mut nonce = 0u32;
+        loop {
+            block.header.nonce = nonce;
+            if block.header.meets_target() {
+                break;
+            }
+            nonce = nonce
+                .checked_add(1)
+                .ok_or(NetParamsError::GenesisUnmineable(self.genesis.bits))?;
+        }
+        self.genesis.nonce = nonce;
+        self.genesis.hash = hex::encode(block.header.hash());
+        Ok(block)
+    }
+
+    /// Check the parameters are usable, including the genesis block
+    pub fn validate(&self) -> Result<(), NetParamsError> {
+        let invalid = |msg: String| Err(NetParamsError::Invalid(msg));
+
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            return invalid(format!(
+                "name '{}' must be non-empty lowercase letters, digits, '-' or '_'",
+                self.name
+            ));
+        }
+        self.magic_bytes()?;
+        // bech32 allows 1 to 83 characters; addresses are compared lowercase
+        if self.address_hrp.is_empty()
+            || self.address_hrp.len() > 83
+            || !self
+                .address_hrp
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        {
+            return invalid(format!(
+                "address_hrp '{}' must be 1 to 83 lowercase letters or digits",
+                self.address_hrp
+            ));
+        }
+        if self.default_port == 0 {
+            return invalid("default_port must be non-zero".to_string());
+        }
+        if self.genesis.message.len() > MAX_GENESIS_MESSAGE_LEN {
+            return invalid(format!(
+                "genesis message exceeds {} bytes",
+                MAX_GENESIS_MESSAGE_LEN
+            ));
+        }
+        if self.genesis.outputs.is_empty() {
+            return invalid("genesis needs at least one output".to_string());
+        }
+        let mut premine = 0u64;
+        for output in &self.genesis.outputs {
+            premine = match premine.checked_add(output.amount) {
+                Some(total) => total,
+                None => return invalid("genesis outputs overflow".to_string()),
+            };
+        }
+        if self.subsidy.halving_interval == 0 {
+            return invalid("subsidy halving_interval must be positive".to_string());
+        }
+        if self.difficulty.target_block_time == 0 || self.difficulty.retarget_interval == 0 {
+            return invalid(
+                "difficulty target_block_time and retarget_interval must be positive".to_string(),
+            );
+        }
+        if decode_target(self.genesis.bits) > decode_target(self.difficulty.pow_limit_bits) {
+            return invalid(format!(
+                "genesis bits {:#010x} are easier than pow_limit_bits {:#010x}",
+                self.genesis.bits, self.difficulty.pow_limit_bits
+            ));
+        }
+        if self.version_bits_window == 0 {
+            return invalid("version_bits_window must be positive".to_string());
+        }
+        let mut names = HashSet::new();
+        let mut bits = HashSet::new();
+        for deployment in &self.deployments {
+            if deployment.bit >= VERSIONBITS_NUM_BITS {
+                return invalid(format!(
+                    "deployment '{}' uses bit {}, above the {} available",
+                    deployment.name, deployment.bit, VERSIONBITS_NUM_BITS
+                ));
+            }
+            if deployment.threshold_percent == 0 || deployment.threshold_percent > 100 {
+                return invalid(format!(
+                    "deployment '{}' threshold must be 1 to 100 percent",
+                    deployment.name
+                ));
+            }
+            if !names.insert(deployment.name.as_str()) || !bits.insert(deployment.bit) {
+                return invalid(format!(
+                    "deployment '{}' reuses a name or bit",
+                    deployment.name
+                ));
+            }
+        }
+        self.genesis_block().map(|_| ())
+    }
+
+    /// Retarget rules for chain validation
+    pub fn retarget_params(&self) -> RetargetParams {
+        RetargetParams {
+            target_block_time: self.difficulty.target_block_time,
+            interval: self.difficulty.retarget_interval,
+            pow_limit_bits: self.difficulty.pow_limit_bits,
+            allow_min_difficulty_blocks: self.difficulty.allow_min_difficulty_blocks,
+        }
+    }
+
+    /// Deployment schedule for version-bits tracking
+    pub fn version_bits_params(&self) -> VersionBitsParams {
+        VersionBitsParams {
+            window: self.version_bits_window,
+            deployments: self
+                .deployments
+                .iter()
+                .map(|d| Deployment {
+                    name: d.name.clone(),
+                    bit: d.bit,
+                    start_time: d.start_time.unwrap_or(NEVER_STARTS),
+                    timeout: d.timeout.unwrap_or(NO_TIMEOUT),
+                    threshold_percent: d.threshold_percent,
+                    min_activation_height: d.min_activation_height,
+                })
+                .collect(),
+        }
+    }
+
+    /// Sign the parameters with the network authority's key
+    pub fn sign(self, key: &CanonicalSigningKey) -> Result<NetParamsFile, NetParamsError> {
+        Ok(SignedEnvelope::seal(self, key)?)
+    }
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+/// Read a netparams file, check its signature under `trust` and validate it
+pub fn load(path: impl AsRef<Path>, trust: &NetParamsTrust) -> Result<NetParams, NetParamsError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    let file: NetParamsFile = if is_toml(path) {
+        toml::from_str(&text).map_err(|e| NetParamsError::Parse(e.to_string()))?
+    } else {
+        serde_json::from_str(&text).map_err(|e| NetParamsError::Parse(e.to_string()))?
+    };
+    match trust {
+        NetParamsTrust::Authority(key) => {
+            if file.open(key)?.is_none() {
+                return Err(NetParamsError::BadSignature);
+            }
+        }
+        NetParamsTrust::Insecure => {
+            tracing::warn!(
+                "Loading network parameters from {:?} without verifying their signature",
+                path
+            );
+        }
+    }
+    file.payload.validate()?;
+    Ok(file.payload)
+}
+
+/// Write a signed netparams file, as TOML when the path ends in `.toml`
+pub fn save(path: impl AsRef<Path>, file: &NetParamsFile) -> Result<(), NetParamsError> {
+    let path = path.as_ref();
+    let text = if is_toml(path) {
+        toml::to_string_pretty(file).map_err(|e| NetParamsError::Parse(e.to_string()))?
+    } else {
+        serde_json::to_string_pretty(file).map_err(|e| NetParamsError::Parse(e.to_string()))?
+    };
+    std::fs::write(path, text)?;
+    Ok(())
+}
+
+/// Make `params` the network this process runs. Activating the same
+/// parameters again is a no-op; switching networks is refused.
+pub fn activate(params: NetParams) -> Result<&'static NetParams, NetParamsError> {
+    let name = params.name.clone();
+    let active = ACTIVE.get_or_init(|| params.clone());
+    if *active != params {
+        return Err(NetParamsError::AlreadyActive(active.name.clone()));
+    }
+    tracing::info!("Network parameters '{}' active", name);
+    Ok(active)
+}
+
+/// Parameters of the custom network this process runs, if any
+pub fn active() -> Option<&'static NetParams> {
+    ACTIVE.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authority() -> CanonicalSigningKey {
+        CanonicalSigningKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]))
+    }
+
+    fn params() -> NetParams {
+        let mut params = NetParams {
+            name: "teamnet".to_string(),
+            magic: "0badcafe".to_string(),
+            address_hrp: "team".to_string(),
+            default_port: 18_555,
+            version_bits_window: 144,
+            genesis: GenesisParams {
+                timestamp: 1_767_225_600,
+                bits: 0x207f_ffff,
+                nonce: 0,
+                message: "teamnet genesis".to_string(),
+                hash: String::new(),
+                outputs: vec![PremineOutput {
+                    amount: 1_000 * NOVAS_PER_NOVA,
+                    script_pubkey: "51".to_string(),
+                }],
+            },
+            subsidy: SubsidySchedule {
+                initial_reward: 25 * NOVAS_PER_NOVA,
+                halving_interval: 1_000,
+            },
+            difficulty: DifficultyParams {
+                target_block_time: 30,
+                retarget_interval: 144,
+                pow_limit_bits: 0x207f_ffff,
+                allow_min_difficulty_blocks: false,
+            },
+            deployments: vec![DeploymentSchedule {
+                name: "asert".to_string(),
+                bit: 2,
+                start_time: Some(0),
+                timeout: None,
+                threshold_percent: 75,
+                min_activation_height: 0,
+            }],
+            witness_txid_height: Some(0),
+        };
+        params.mine_genesis().unwrap();
+        params
+    }
+
+    #[test]
+    fn test_signed_file_round_trips_as_json_and_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = authority();
+        let trust = NetParamsTrust::Authority(key.public_key());
+        let file = params().sign(&key).unwrap();
+
+        for name in ["teamnet.json", "teamnet.toml"] {
+            let path = dir.path().join(name);
+            save(&path, &file).unwrap();
+            let loaded = load(&path, &trust).unwrap();
+            assert_eq!(loaded, file.payload);
+            assert_eq!(
+                loaded.genesis_block().unwrap().header.hash(),
+                loaded.genesis_hash().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_signature_is_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("teamnet.json");
+        let mut file = params().sign(&authority()).unwrap();
+        file.payload.subsidy.initial_reward *= 2;
+        save(&path, &file).unwrap();
+
+        let trust = NetParamsTrust::Authority(authority().public_key());
+        assert!(matches!(
+            load(&path, &trust),
+            Err(NetParamsError::BadSignature)
+        ));
+
+        let other = CanonicalSigningKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&[9u8; 32]));
+        let trust = NetParamsTrust::Authority(other.public_key());
+        save(&path, &params().sign(&authority()).unwrap()).unwrap();
+        assert!(matches!(
+            load(&path, &trust),
+            Err(NetParamsError::BadSignature)
+        ));
+
+        assert!(load(&path, &NetParamsTrust::Insecure).is_ok());
+        assert!(NetParamsTrust::from_options(None, false).is_err());
+    }
+
+    #[test]
+    fn test_genesis_must_match_declared_hash() {
+        let mut params = params();
+        params.genesis.message = "a different genesis".to_string();
+        assert!(matches!(
+            params.validate(),
+            Err(NetParamsError::GenesisMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_authority_key_format() {
+        let key = authority().public_key();
+        let formatted = format_authority(&key).unwrap();
+        assert!(formatted.starts_with("ed25519:"));
+        assert_eq!(parse_authority(&formatted).unwrap(), key);
+        assert!(parse_authority("rsa:00").is_err());
+        assert!(parse_authority("ed25519:not-hex").is_err());
+    }
+
+    #[test]
+    fn test_schedules_convert() {
+        let params = params();
+        assert_eq!(params.subsidy.subsidy(999), 25 * NOVAS_PER_NOVA);
+        assert_eq!(params.subsidy.subsidy(1_000), 25 * NOVAS_PER_NOVA / 2);
+        let summed: u64 = (1..=2_500).map(|h| params.subsidy.subsidy(h)).sum();
+        assert_eq!(params.subsidy.total_subsidy(1, 2_500), summed);
+        assert_eq!(params.subsidy.total_subsidy(5, 4), 0);
+        // Just under 42M NOVA once the shifts round down
+        assert_eq!(
+            SubsidySchedule::default().total_subsidy(0, u64::MAX),
+            4_199_999_995_380_000
+        );
+        assert_eq!(params.retarget_params().pow_limit_bits, 0x207f_ffff);
+        assert!(!params.retarget_params().allow_min_difficulty_blocks);
+        // Off, the flag stays out of the signed JSON
+        let json = serde_json::to_string(&params).unwrap();
+        assert!(!json.contains("allow_min_difficulty_blocks"));
+        let deployment = &params.version_bits_params().deployments[0];
+        assert_eq!(deployment.timeout, NO_TIMEOUT);
+        assert_eq!(deployment.start_time, 0);
+    }
+}
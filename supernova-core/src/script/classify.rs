@@ -0,0 +1,515 @@
+//! Output script classification and address extraction
+//!
+//! The single place that decides what kind of output a script pubkey is and
+//! which address, if any, it pays. The wallet scanner, the address index and
+//! the API all go through [`classify`] so they agree on every script,
+//! including nonstandard ones.
+//!
+//! Classification is versioned. Each class records the
+//! [`CLASSIFIER_VERSION`] that introduced it, and [`classify_as_of`]
+//! reproduces the result of an older version: data indexed under version
+//! `n` stays readable after new classes are added, because anything newer
+//! than `n` still classifies as nonstandard there.
+//!
+//! Only pay-to-quantum-pubkey-hash outputs have an address on this network
+//! (bech32m, HRP [`address_hrp`]). Everything else is identified by its
+//! script; see [`index_key`].
+
+use crate::crypto::quantum::QuantumScheme;
+use bech32::{FromBase32, ToBase32, Variant};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Current classifier version; bump when adding a class
+pub const CLASSIFIER_VERSION: u16 = 1;
+
+/// Bech32 human-readable part of addresses on the built-in networks
+pub const ADDRESS_HRP: &str = "nova";
+
+/// Bech32 human-readable part of addresses on the network this process runs:
+/// the active netparams file's prefix, else [`ADDRESS_HRP`]
+pub fn address_hrp() -> &'static str {
+    crate::netparams::active()
+        .map(|params| params.address_hrp.as_str())
+        .unwrap_or(ADDRESS_HRP)
+}
+
+/// Length of a quantum pubkey commitment (SHA3-512 truncated)
+pub const QUANTUM_COMMITMENT_LEN: usize = 32;
+
+/// Most keys in a bare multisig script (`OP_16`)
+pub const MAX_MULTISIG_KEYS: usize = 16;
+
+/// Default relay limit on the payload of a data carrier output. Policy only:
+/// consensus accepts `OP_RETURN` outputs of any size.
+pub const DEFAULT_MAX_DATA_CARRIER_BYTES: usize = 80;
+
+const OP_0: u8 = 0x00;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_PUSHDATA4: u8 = 0x4e;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_RETURN: u8 = 0x6a;
+const OP_DUP: u8 = 0x76;
+const OP_EQUAL: u8 = 0x87;
+const OP_EQUALVERIFY: u8 = 0x88;
+const OP_HASH160: u8 = 0xa9;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKMULTISIG: u8 = 0xae;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Bech32 decoding failed: {0}")]
+    Bech32(String),
+    #[error("Address prefix '{0}' is not '{expected}'", expected = address_hrp())]
+    WrongNetwork(String),
+    #[error("Addresses must use bech32m")]
+    WrongVariant,
+    #[error("Address payload must be {QUANTUM_COMMITMENT_LEN} bytes, got {0}")]
+    InvalidLength(usize),
+}
+
+/// What an output script pays to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptClass {
+    /// `OP_DUP OP_HASH160 <20> OP_EQUALVERIFY OP_CHECKSIG`
+    P2pkh { pubkey_hash: [u8; 20] },
+    /// `OP_HASH160 <20> OP_EQUAL`
+    P2sh { script_hash: [u8; 20] },
+    /// `OP_0 <20>`
+    P2wpkh { pubkey_hash: [u8; 20] },
+    /// `OP_0 <32>`
+    P2wsh { script_hash: [u8; 32] },
+    /// Bare 32-byte commitment to a quantum public key, as produced by the
+    /// wallets. The commitment does not record the signature scheme, so
+    /// `scheme` is only known when the script form carries it.
+    QuantumPkh {
+        commitment: [u8; 32],
+        scheme: Option<QuantumScheme>,
+    },
+    /// `OP_m <key>... OP_n OP_CHECKMULTISIG`
+    Multisig { required: u8, keys: Vec<Vec<u8>> },
+    /// `OP_RETURN` followed only by data pushes
+    NullData { data: Vec<Vec<u8>> },
+    /// Anything else, including empty and truncated scripts
+    Nonstandard,
+}
+
+/// Fieldless [`ScriptClass`], for display and filtering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptKind {
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    QuantumPkh,
+    Multisig,
+    NullData,
+    Nonstandard,
+}
+
+impl ScriptKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptKind::P2pkh => "p2pkh",
+            ScriptKind::P2sh => "p2sh",
+            ScriptKind::P2wpkh => "p2wpkh",
+            ScriptKind::P2wsh => "p2wsh",
+            ScriptKind::QuantumPkh => "quantum_pkh",
+            ScriptKind::Multisig => "multisig",
+            ScriptKind::NullData => "null_data",
+            ScriptKind::Nonstandard => "nonstandard",
+        }
+    }
+
+    /// Classifier version that first recognised this kind
+    pub fn introduced_in(&self) -> u16 {
+        match self {
+            ScriptKind::P2pkh
+            | ScriptKind::P2sh
+            | ScriptKind::P2wpkh
+            | ScriptKind::P2wsh
+            | ScriptKind::QuantumPkh
+            | ScriptKind::Multisig
+            | ScriptKind::NullData
+            | ScriptKind::Nonstandard => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for ScriptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ScriptClass {
+    pub fn kind(&self) -> ScriptKind {
+        match self {
+            ScriptClass::P2pkh { .. } => ScriptKind::P2pkh,
+            ScriptClass::P2sh { .. } => ScriptKind::P2sh,
+            ScriptClass::P2wpkh { .. } => ScriptKind::P2wpkh,
+            ScriptClass::P2wsh { .. } => ScriptKind::P2wsh,
+            ScriptClass::QuantumPkh { .. } => ScriptKind::QuantumPkh,
+            ScriptClass::Multisig { .. } => ScriptKind::Multisig,
+            ScriptClass::NullData { .. } => ScriptKind::NullData,
+            ScriptClass::Nonstandard => ScriptKind::Nonstandard,
+        }
+    }
+
+    /// Canonical address, for classes that have one
+    pub fn address(&self) -> Option<String> {
+        match self {
+            ScriptClass::QuantumPkh { commitment, .. } => {
+                bech32::encode(address_hrp(), commitment.to_base32(), Variant::Bech32m).ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether outputs with this script can never be spent. Only data
+    /// carriers are provably unspendable; a nonstandard script may still be
+    /// satisfiable.
+    pub fn is_unspendable(&self) -> bool {
+        matches!(self, ScriptClass::NullData { .. })
+    }
+
+    /// Payload of a data carrier: its pushes concatenated
+    pub fn data_payload(&self) -> Option<Vec<u8>> {
+        match self {
+            ScriptClass::NullData { data } => Some(data.concat()),
+            _ => None,
+        }
+    }
+}
+
+/// A script's class under a given classifier version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptClassification {
+    pub version: u16,
+    pub class: ScriptClass,
+}
+
+impl ScriptClassification {
+    pub fn kind(&self) -> ScriptKind {
+        self.class.kind()
+    }
+
+    pub fn address(&self) -> Option<String> {
+        self.class.address()
+    }
+}
+
+/// Classify `script` with the current classifier. Never panics.
+pub fn classify(script: &[u8]) -> ScriptClassification {
+    classify_as_of(script, CLASSIFIER_VERSION)
+}
+
+/// Classify `script` as classifier `version` did: classes introduced later
+/// come back as nonstandard
+pub fn classify_as_of(script: &[u8], version: u16) -> ScriptClassification {
+    let class = detect(script);
+    let class = if class.kind().introduced_in() > version {
+        ScriptClass::Nonstandard
+    } else {
+        class
+    };
+    ScriptClassification { version, class }
+}
+
+/// Key an output is indexed and looked up under: its address when it has
+/// one, otherwise the hex-encoded script. `None` for an empty script.
+pub fn index_key(script: &[u8]) -> Option<String> {
+    index_key_as_of(script, CLASSIFIER_VERSION)
+}
+
+/// [`index_key`] under classifier `version`
+pub fn index_key_as_of(script: &[u8], version: u16) -> Option<String> {
+    if script.is_empty() {
+        return None;
+    }
+    Some(
+        classify_as_of(script, version)
+            .address()
+            .unwrap_or_else(|| hex::encode(script)),
+    )
+}
+
+/// Script pubkey paid by `address`; the inverse of
+/// [`ScriptClass::address`]
+pub fn address_to_script(address: &str) -> Result<Vec<u8>, AddressError> {
+    let (hrp, data, variant) =
+        bech32::decode(address).map_err(|e| AddressError::Bech32(e.to_string()))?;
+    if hrp != address_hrp() {
+        return Err(AddressError::WrongNetwork(hrp));
+    }
+    if variant != Variant::Bech32m {
+        return Err(AddressError::WrongVariant);
+    }
+    let payload = Vec::<u8>::from_base32(&data).map_err(|e| AddressError::Bech32(e.to_string()))?;
+    if payload.len() != QUANTUM_COMMITMENT_LEN {
+        return Err(AddressError::InvalidLength(payload.len()));
+    }
+    Ok(payload)
+}
+
+fn detect(script: &[u8]) -> ScriptClass {
+    // Checked before the opcode templates: wallets pay bare commitments, and
+    // a hash can begin with any byte, so every 32-byte script is one
+    if let Ok(commitment) = <[u8; 32]>::try_from(script) {
+        return ScriptClass::QuantumPkh {
+            commitment,
+            scheme: None,
+        };
+    }
+
+    match script {
+        [OP_DUP, OP_HASH160, 0x14, hash @ .., OP_EQUALVERIFY, OP_CHECKSIG] => {
+            if let Ok(pubkey_hash) = <[u8; 20]>::try_from(hash) {
+                return ScriptClass::P2pkh { pubkey_hash };
+            }
+        }
+        [OP_HASH160, 0x14, hash @ .., OP_EQUAL] => {
+            if let Ok(script_hash) = <[u8; 20]>::try_from(hash) {
+                return ScriptClass::P2sh { script_hash };
+            }
+        }
+        [OP_0, 0x14, hash @ ..] => {
+            if let Ok(pubkey_hash) = <[u8; 20]>::try_from(hash) {
+                return ScriptClass::P2wpkh { pubkey_hash };
+            }
+        }
+        [OP_0, 0x20, hash @ ..] => {
+            if let Ok(script_hash) = <[u8; 32]>::try_from(hash) {
+                return ScriptClass::P2wsh { script_hash };
+            }
+        }
+        [OP_RETURN, rest @ ..] => {
+            if let Some(data) = parse_pushes(rest) {
+                return ScriptClass::NullData { data };
+            }
+        }
+        [first @ OP_1..=OP_16, middle @ .., last @ OP_1..=OP_16, OP_CHECKMULTISIG] => {
+            let required = first - OP_1 + 1;
+            let total = (last - OP_1 + 1) as usize;
+            if let Some(keys) = parse_pushes(middle) {
+                if keys.len() == total
+                    && required as usize <= total
+                    && keys.iter().all(|key| !key.is_empty())
+                {
+                    return ScriptClass::Multisig { required, keys };
+                }
+            }
+        }
+        _ => {}
+    }
+    ScriptClass::Nonstandard
+}
+
+/// Split a push-only script into its pushed items; `None` if it contains
+/// any other opcode or a push runs past the end
+fn parse_pushes(mut script: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut items = Vec::new();
+    while let Some((&opcode, rest)) = script.split_first() {
+        let (len, rest) = match opcode {
+            OP_0 => (0, rest),
+            0x01..=0x4b => (opcode as usize, rest),
+            OP_PUSHDATA1 => {
+                let (len, rest) = rest.split_first()?;
+                (*len as usize, rest)
+            }
+            OP_PUSHDATA2 => {
+                let bytes = rest.get(..2)?;
+                (u16::from_le_bytes([bytes[0], bytes[1]]) as usize, &rest[2..])
+            }
+            OP_PUSHDATA4 => {
+                let bytes = rest.get(..4)?;
+                let len = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                (usize::try_from(len).ok()?, &rest[4..])
+            }
+            _ => return None,
+        };
+        items.push(rest.get(..len)?.to_vec());
+        script = &rest[len..];
+    }
+    Some(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::script::{identify_script_type, ScriptBuilder, ScriptType};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    fn commitment_address(commitment: &[u8; 32]) -> String {
+        bech32::encode("nova", commitment.to_base32(), Variant::Bech32m).unwrap()
+    }
+
+    #[test]
+    fn fixtures_for_every_class() {
+        let hash20 = [0x11u8; 20];
+        let hash32 = [0x22u8; 32];
+        let key = vec![0x02; 33];
+        let quantum_key = vec![0x05; 1312];
+
+        let fixtures: Vec<(Vec<u8>, ScriptClass)> = vec![
+            (
+                ScriptBuilder::pay_to_pubkey_hash(&hash20).unwrap(),
+                ScriptClass::P2pkh { pubkey_hash: hash20 },
+            ),
+            (
+                ScriptBuilder::pay_to_script_hash(&hash20).unwrap(),
+                ScriptClass::P2sh { script_hash: hash20 },
+            ),
+            (
+                ScriptBuilder::pay_to_witness_pubkey_hash(&hash20).unwrap(),
+                ScriptClass::P2wpkh { pubkey_hash: hash20 },
+            ),
+            (
+                ScriptBuilder::pay_to_witness_script_hash(&hash32).unwrap(),
+                ScriptClass::P2wsh { script_hash: hash32 },
+            ),
+            (
+                hash32.to_vec(),
+                ScriptClass::QuantumPkh { commitment: hash32, scheme: None },
+            ),
+            (
+                ScriptBuilder::multisig(2, &[key.clone(), quantum_key.clone(), key.clone()]).unwrap(),
+                ScriptClass::Multisig {
+                    required: 2,
+                    keys: vec![key.clone(), quantum_key, key.clone()],
+                },
+            ),
+            (
+                vec![OP_RETURN, 0x03, b'a', b'b', b'c', OP_0],
+                ScriptClass::NullData { data: vec![b"abc".to_vec(), vec![]] },
+            ),
+            (vec![OP_RETURN], ScriptClass::NullData { data: vec![] }),
+            (vec![], ScriptClass::Nonstandard),
+            (vec![OP_CHECKSIG], ScriptClass::Nonstandard),
+        ];
+
+        for (script, expected) in fixtures {
+            let classification = classify(&script);
+            assert_eq!(classification.version, CLASSIFIER_VERSION);
+            assert_eq!(classification.class, expected, "script {}", hex::encode(&script));
+        }
+
+        // Only quantum-pkh outputs have an address, and it round-trips
+        let address = classify(&hash32).address().unwrap();
+        assert_eq!(address_to_script(&address).unwrap(), hash32.to_vec());
+        assert!(classify(&ScriptBuilder::pay_to_pubkey_hash(&hash20).unwrap())
+            .address()
+            .is_none());
+    }
+
+    #[test]
+    fn malformed_templates_are_nonstandard() {
+        let cases: Vec<Vec<u8>> = vec![
+            // P2PKH with a 19-byte push
+            [&[OP_DUP, OP_HASH160, 0x13][..], &[0u8; 19], &[OP_EQUALVERIFY, OP_CHECKSIG, 0x00]].concat(),
+            // OP_RETURN followed by a non-push opcode
+            vec![OP_RETURN, OP_DUP],
+            // OP_RETURN with a push running past the end
+            vec![OP_RETURN, 0x05, 0x01],
+            vec![OP_RETURN, OP_PUSHDATA2, 0xff],
+            // 2-of-1 multisig
+            [&[0x52, 0x21][..], &[0x02; 33], &[0x51, OP_CHECKMULTISIG]].concat(),
+            // key count does not match OP_n
+            [&[0x51, 0x21][..], &[0x02; 33], &[0x52, OP_CHECKMULTISIG]].concat(),
+            // empty key
+            vec![0x51, OP_0, 0x51, OP_CHECKMULTISIG],
+        ];
+        for script in cases {
+            assert_eq!(classify(&script).class, ScriptClass::Nonstandard, "{}", hex::encode(&script));
+        }
+    }
+
+    /// Cases where the wallet scanner, the address index and the explorer
+    /// used to disagree
+    #[test]
+    fn former_disagreements_are_settled() {
+        // The wallet matched bare commitments to bech32 addresses, the index
+        // keyed them by hex script, and explorer search returned the
+        // address, which then found nothing in the index
+        let commitment = [0xabu8; 32];
+        let address = commitment_address(&commitment);
+        assert_eq!(index_key(&commitment).as_deref(), Some(address.as_str()));
+        assert_eq!(classify(&commitment).address().as_deref(), Some(address.as_str()));
+
+        // A commitment that happens to start with OP_RETURN, or to parse as
+        // a 1-of-1 multisig, is still a payment to that commitment
+        let mut op_return_like = [0x01u8; 32];
+        op_return_like[0] = OP_RETURN;
+        assert_eq!(classify(&op_return_like).kind(), ScriptKind::QuantumPkh);
+        let multisig_like: Vec<u8> = [&[0x51, 0x1c][..], &[0x02; 28], &[0x51, OP_CHECKMULTISIG]].concat();
+        assert_eq!(multisig_like.len(), 32);
+        assert_eq!(classify(&multisig_like).kind(), ScriptKind::QuantumPkh);
+
+        // Empty scripts were skipped by the index but are nonstandard, not
+        // an error, everywhere else
+        assert_eq!(index_key(&[]), None);
+        assert_eq!(classify(&[]).kind(), ScriptKind::Nonstandard);
+
+        // Nonstandard scripts keep the hex key the index always used
+        let odd = vec![0x01, 0x02, 0x03];
+        assert_eq!(index_key(&odd), Some("010203".to_string()));
+
+        // The legacy identify_script_type agrees on the classes it knows
+        let p2wsh = ScriptBuilder::pay_to_witness_script_hash(&[0u8; 32]).unwrap();
+        assert_eq!(identify_script_type(&p2wsh), ScriptType::P2WSH);
+        assert_eq!(identify_script_type(&commitment), ScriptType::Unknown);
+    }
+
+    #[test]
+    fn older_versions_ignore_newer_classes() {
+        let script = ScriptBuilder::pay_to_pubkey_hash(&[0x11; 20]).unwrap();
+        assert_eq!(classify_as_of(&script, 0).class, ScriptClass::Nonstandard);
+        assert_eq!(index_key_as_of(&script, 0), Some(hex::encode(&script)));
+        assert_eq!(classify_as_of(&script, CLASSIFIER_VERSION), classify(&script));
+    }
+
+    #[test]
+    fn addresses_for_other_networks_are_rejected() {
+        let commitment = [0x33u8; 32];
+        let testnet = bech32::encode("tnova", commitment.to_base32(), Variant::Bech32m).unwrap();
+        assert_eq!(address_to_script(&testnet), Err(AddressError::WrongNetwork("tnova".to_string())));
+        let bech32 = bech32::encode("nova", commitment.to_base32(), Variant::Bech32).unwrap();
+        assert_eq!(address_to_script(&bech32), Err(AddressError::WrongVariant));
+        let short = bech32::encode("nova", [0u8; 20].to_base32(), Variant::Bech32m).unwrap();
+        assert_eq!(address_to_script(&short), Err(AddressError::InvalidLength(20)));
+        assert!(matches!(address_to_script("not an address"), Err(AddressError::Bech32(_))));
+    }
+
+    #[test]
+    fn random_bytes_never_panic() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0x5c1a55);
+        let prefixes: [&[u8]; 6] = [
+            &[],
+            &[OP_RETURN],
+            &[OP_DUP, OP_HASH160, 0x14],
+            &[OP_0],
+            &[0x52],
+            &[OP_RETURN, OP_PUSHDATA4],
+        ];
+        for i in 0..20_000 {
+            let len = rng.gen_range(0..96);
+            let mut script = prefixes[i % prefixes.len()].to_vec();
+            script.extend((0..len).map(|_| rng.gen::<u8>()));
+            if rng.gen_bool(0.2) {
+                script.push(OP_CHECKMULTISIG);
+            }
+
+            let classification = classify(&script);
+            let key = index_key(&script);
+            assert_eq!(key.is_none(), script.is_empty());
+            if let Some(address) = classification.address() {
+                assert_eq!(address_to_script(&address).unwrap(), script);
+            }
+        }
+    }
+}
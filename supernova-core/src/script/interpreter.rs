@@ -4,6 +4,8 @@
 //! transaction scripts by executing opcodes and maintaining the stack.
 
 use crate::script::opcodes::Opcode;
+use crate::script::script_validator::ScriptFlags;
+use crate::types::timelock::SEQUENCE_LOCKTIME_DISABLE_FLAG;
 use ripemd::{Digest as RipemdDigest, Ripemd160};
 use sha2::{Digest, Sha256};
 
@@ -31,6 +33,10 @@ pub const HASH_GAS_COST: u64 = 50;
 /// Maximum script element size
 pub const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
 
+/// Largest number operand of the lock time opcodes, in bytes. Five bytes
+/// hold any `u32` lock time or sequence.
+pub const MAX_LOCKTIME_NUM_SIZE: usize = 5;
+
 /// Script execution errors
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ScriptError {
@@ -70,6 +76,10 @@ pub enum ScriptError {
     ElementTooLarge,
     /// Gas limit exceeded (DoS prevention)
     GasExhausted { used: u64, limit: u64 },
+    /// Negative lock time or sequence operand
+    NegativeLockTime,
+    /// The transaction does not meet the script's lock time or sequence
+    UnsatisfiedLockTime,
 }
 
 /// Stack for script execution
@@ -171,6 +181,8 @@ pub struct ScriptInterpreter {
     gas_used: u64,
     /// Maximum gas allowed
     gas_limit: u64,
+    /// Rules the script runs under
+    flags: ScriptFlags,
 }
 
 impl Default for ScriptInterpreter {
@@ -189,6 +201,7 @@ impl ScriptInterpreter {
             op_count: 0,
             gas_used: 0,
             gas_limit: MAX_SCRIPT_GAS,
+            flags: ScriptFlags::default(),
         }
     }
 
@@ -201,9 +214,17 @@ impl ScriptInterpreter {
             op_count: 0,
             gas_used: 0,
             gas_limit,
+            flags: ScriptFlags::default(),
         }
     }
 
+    /// Run scripts under `flags`. Lock time opcodes whose flag is off are
+    /// no-ops.
+    pub fn with_flags(mut self, flags: ScriptFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
     /// Push an item onto the main stack (for witness script execution)
     pub fn push_stack(&mut self, item: Vec<u8>) -> Result<(), ScriptError> {
         self.stack.push(item)
@@ -636,6 +657,32 @@ impl ScriptInterpreter {
                 Ok(())
             }
 
+            // Lock times. Both leave their operand on the stack.
+            Opcode::OP_CHECKLOCKTIMEVERIFY => {
+                if !self.flags.verify_checklocktimeverify {
+                    return Ok(());
+                }
+                let lock_time = self.lock_time_operand()?;
+                if !checker.check_lock_time(lock_time) {
+                    return Err(ScriptError::UnsatisfiedLockTime);
+                }
+                Ok(())
+            }
+            Opcode::OP_CHECKSEQUENCEVERIFY => {
+                if !self.flags.verify_checksequenceverify {
+                    return Ok(());
+                }
+                let sequence = self.lock_time_operand()?;
+                // An operand with the disable flag set imposes no lock
+                if sequence & i64::from(SEQUENCE_LOCKTIME_DISABLE_FLAG) != 0 {
+                    return Ok(());
+                }
+                if !checker.check_sequence(sequence) {
+                    return Err(ScriptError::UnsatisfiedLockTime);
+                }
+                Ok(())
+            }
+
             _ => {
                 // Unimplemented opcode
                 Err(ScriptError::InvalidOpcode(opcode as u8))
@@ -643,6 +690,19 @@ impl ScriptInterpreter {
         }
     }
 
+    /// The top stack item as the operand of a lock time opcode
+    fn lock_time_operand(&self) -> Result<i64, ScriptError> {
+        let value = decode_script_number(
+            self.stack.peek()?,
+            self.flags.verify_minimaldata,
+            MAX_LOCKTIME_NUM_SIZE,
+        )?;
+        if value < 0 {
+            return Err(ScriptError::NegativeLockTime);
+        }
+        Ok(value)
+    }
+
     /// Check if a stack value is true (non-zero)
     fn is_true(&self, value: &[u8]) -> bool {
         // Empty array is false
@@ -665,10 +725,57 @@ impl ScriptInterpreter {
     }
 }
 
+/// Decode a little-endian sign-magnitude script number of at most
+/// `max_len` bytes. With `require_minimal`, encodings with a redundant
+/// trailing byte are rejected.
+pub fn decode_script_number(
+    bytes: &[u8],
+    require_minimal: bool,
+    max_len: usize,
+) -> Result<i64, ScriptError> {
+    if bytes.len() > max_len || bytes.len() > 8 {
+        return Err(ScriptError::InvalidNumber);
+    }
+    let Some(&last) = bytes.last() else {
+        return Ok(0);
+    };
+    // The last byte may only lack magnitude bits when the one before it
+    // needs its high bit for magnitude
+    if require_minimal
+        && last & 0x7f == 0
+        && (bytes.len() == 1 || bytes[bytes.len() - 2] & 0x80 == 0)
+    {
+        return Err(ScriptError::InvalidNumber);
+    }
+
+    let mut value = 0i64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        value |= i64::from(byte) << (8 * i);
+    }
+    if last & 0x80 != 0 {
+        let sign_bit = 0x80i64 << (8 * (bytes.len() - 1));
+        return Ok(-(value & !sign_bit));
+    }
+    Ok(value)
+}
+
 /// Trait for signature verification
 pub trait SignatureChecker {
     /// Check if a signature is valid for a public key
     fn check_signature(&self, signature: &[u8], pubkey: &[u8]) -> Result<bool, ScriptError>;
+
+    /// Whether the spending transaction's lock time meets `lock_time`, the
+    /// operand of `OP_CHECKLOCKTIMEVERIFY`. Checkers without a transaction
+    /// fail it.
+    fn check_lock_time(&self, _lock_time: i64) -> bool {
+        false
+    }
+
+    /// Whether the spending input's sequence meets `sequence`, the operand
+    /// of `OP_CHECKSEQUENCEVERIFY`
+    fn check_sequence(&self, _sequence: i64) -> bool {
+        false
+    }
 }
 
 #[cfg(test)]
@@ -5,15 +5,19 @@
 
 use thiserror::Error;
 
+pub mod classify;
 pub mod interpreter;
+pub mod multisig;
 pub mod opcodes;
 pub mod script_builder;
 pub mod script_validator;
 
+pub use classify::{classify, ScriptClass, ScriptClassification, ScriptKind};
 pub use interpreter::{ExecutionStack, ScriptError, ScriptInterpreter};
+pub use multisig::{MultisigError, MultisigKeyKind, MultisigScript};
 pub use opcodes::{Opcode, ALL_OPCODES};
 pub use script_builder::{ScriptBuilder, ScriptBuilderError};
-pub use script_validator::{ScriptFlags, ScriptValidator};
+pub use script_validator::{ScriptFlags, ScriptValidator, TransactionChecker};
 
 /// Standard script types supported by Supernova
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -69,47 +73,13 @@ pub enum ScriptVerificationError {
 
 /// Determine the script type from a public key script
 pub fn identify_script_type(script: &[u8]) -> ScriptType {
-    // P2PKH: OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG
-    if script.len() == 25
-        && script[0] == 0x76  // OP_DUP
-        && script[1] == 0xa9  // OP_HASH160
-        && script[2] == 0x14  // Push 20 bytes
-        && script[23] == 0x88 // OP_EQUALVERIFY
-        && script[24] == 0xac
-    // OP_CHECKSIG
-    {
-        return ScriptType::P2PKH;
+    match classify(script).kind() {
+        ScriptKind::P2pkh => ScriptType::P2PKH,
+        ScriptKind::P2sh => ScriptType::P2SH,
+        ScriptKind::P2wpkh => ScriptType::P2WPKH,
+        ScriptKind::P2wsh => ScriptType::P2WSH,
+        _ => ScriptType::Unknown,
     }
-
-    // P2SH: OP_HASH160 <20 bytes> OP_EQUAL
-    if script.len() == 23
-        && script[0] == 0xa9  // OP_HASH160
-        && script[1] == 0x14  // Push 20 bytes
-        && script[22] == 0x87
-    // OP_EQUAL
-    {
-        return ScriptType::P2SH;
-    }
-
-    // P2WPKH: OP_0 <20 bytes>
-    if script.len() == 22
-        && script[0] == 0x00  // OP_0
-        && script[1] == 0x14
-    // Push 20 bytes
-    {
-        return ScriptType::P2WPKH;
-    }
-
-    // P2WSH: OP_0 <32 bytes>
-    if script.len() == 34
-        && script[0] == 0x00  // OP_0
-        && script[1] == 0x20
-    // Push 32 bytes
-    {
-        return ScriptType::P2WSH;
-    }
-
-    ScriptType::Unknown
 }
 
 /// Extract the hash from a standard script
@@ -192,3 +162,6 @@ mod tests {
 
 #[cfg(test)]
 mod script_validation_tests;
+
+#[cfg(test)]
+mod timelock_tests;
@@ -0,0 +1,386 @@
+//! M-of-N multisig over classical and quantum keys
+//!
+//! A [`MultisigScript`] is the bare `OP_m <key>... OP_n OP_CHECKMULTISIG`
+//! template of [`ScriptBuilder::multisig`], used as a witness script. The
+//! kind of each key follows from its length: 33-byte compressed secp256k1
+//! keys, and ML-DSA (Dilithium) or Falcon public keys at any supported
+//! security level, so one script may mix classical and quantum cosigners.
+//!
+//! Outputs pay the script the way Lightning funding outputs do: P2WSH of
+//! the script when every key is classical, the 32-byte SHA3-512 commitment
+//! of wallet outputs as soon as one key is quantum. A spend carries the
+//! signatures followed by the script in its witness. Signatures are over
+//! [`Transaction::signature_hash`] and may come in any order; each key
+//! counts once however many of its signatures are present.
+//!
+//! [`Transaction::signature_hash`]: crate::types::transaction::Transaction::signature_hash
+
+use crate::crypto::falcon_real::FalconSecurityLevel;
+use crate::crypto::quantum::{verify_quantum_signature, QuantumParameters, QuantumScheme};
+use crate::crypto::signature::{SignatureType, SignatureVerifier};
+use crate::script::classify::{classify, ScriptClass, MAX_MULTISIG_KEYS};
+use crate::script::script_builder::{ScriptBuilder, ScriptBuilderError};
+use crate::types::transaction::pubkey_commitment;
+use pqcrypto_dilithium::{dilithium2, dilithium3, dilithium5};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Length of a compact secp256k1 signature
+const SECP256K1_SIGNATURE_LEN: usize = 64;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MultisigError {
+    #[error("Invalid multisig script: {0}")]
+    InvalidScript(#[from] ScriptBuilderError),
+    #[error("Key {0} is neither a compressed secp256k1 key nor an ML-DSA or Falcon key")]
+    UnsupportedKey(usize),
+    #[error("Key {0} appears more than once")]
+    DuplicateKey(usize),
+    #[error("Script is not a multisig script")]
+    NotMultisig,
+    #[error("Witness script does not match the spent output")]
+    ScriptMismatch,
+    #[error("Witness carries {signatures} signatures for {keys} keys")]
+    TooManySignatures { signatures: usize, keys: usize },
+    #[error("Key is not part of the multisig script")]
+    UnknownKey,
+    #[error("{valid} of {required} required signatures are valid")]
+    NotEnoughSignatures { required: u8, valid: usize },
+}
+
+/// How a multisig key signs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultisigKeyKind {
+    Secp256k1,
+    Quantum(QuantumParameters),
+}
+
+impl MultisigKeyKind {
+    /// Kind of `key`, from its length; `None` for unsupported keys
+    pub fn of(key: &[u8]) -> Option<Self> {
+        let quantum = |scheme, security_level| {
+            Some(MultisigKeyKind::Quantum(QuantumParameters {
+                scheme,
+                security_level,
+            }))
+        };
+        match key.len() {
+            33 if matches!(key[0], 0x02 | 0x03) => Some(MultisigKeyKind::Secp256k1),
+            len if len == dilithium2::public_key_bytes() => quantum(QuantumScheme::Dilithium, 1),
+            len if len == dilithium3::public_key_bytes() => quantum(QuantumScheme::Dilithium, 3),
+            len if len == dilithium5::public_key_bytes() => quantum(QuantumScheme::Dilithium, 5),
+            len if len == FalconSecurityLevel::Falcon512.public_key_length() => {
+                quantum(QuantumScheme::Falcon, 1)
+            }
+            len if len == FalconSecurityLevel::Falcon1024.public_key_length() => {
+                quantum(QuantumScheme::Falcon, 5)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_quantum(&self) -> bool {
+        matches!(self, MultisigKeyKind::Quantum(_))
+    }
+
+    /// Largest signature this kind of key makes
+    pub fn max_signature_len(&self) -> usize {
+        match self {
+            MultisigKeyKind::Secp256k1 => SECP256K1_SIGNATURE_LEN,
+            MultisigKeyKind::Quantum(params) => params.expected_signature_length().unwrap_or(0),
+        }
+    }
+
+    /// Whether `signature` of `message` verifies under `key`. Signatures
+    /// of the wrong length are not tried.
+    fn verify(&self, key: &[u8], message: &[u8; 32], signature: &[u8]) -> bool {
+        match self {
+            MultisigKeyKind::Secp256k1 => {
+                signature.len() == SECP256K1_SIGNATURE_LEN
+                    && SignatureVerifier::new()
+                        .verify(SignatureType::Secp256k1, key, message, signature)
+                        .unwrap_or(false)
+            }
+            MultisigKeyKind::Quantum(params) => {
+                let fits = match params.scheme {
+                    // Falcon signatures vary in length up to the maximum
+                    QuantumScheme::Falcon => signature.len() <= self.max_signature_len(),
+                    _ => signature.len() == self.max_signature_len(),
+                };
+                fits && verify_quantum_signature(key, message, signature, *params).unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// An M-of-N multisig witness script
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigScript {
+    required: u8,
+    keys: Vec<Vec<u8>>,
+}
+
+impl MultisigScript {
+    /// `required` of `keys`, in the given order. Every key must be a
+    /// supported kind and distinct.
+    pub fn new(required: u8, keys: Vec<Vec<u8>>) -> Result<Self, MultisigError> {
+        if keys.len() > MAX_MULTISIG_KEYS {
+            return Err(ScriptBuilderError::TooManyPubkeys(keys.len()).into());
+        }
+        if required == 0 || required as usize > keys.len() {
+            return Err(ScriptBuilderError::InvalidMultisigThreshold {
+                threshold: required,
+                pubkey_count: keys.len(),
+            }
+            .into());
+        }
+        for (index, key) in keys.iter().enumerate() {
+            if MultisigKeyKind::of(key).is_none() {
+                return Err(MultisigError::UnsupportedKey(index));
+            }
+            if keys[..index].contains(key) {
+                return Err(MultisigError::DuplicateKey(index));
+            }
+        }
+        Ok(Self { required, keys })
+    }
+
+    /// Parse a witness script made by [`witness_script`](Self::witness_script)
+    pub fn parse(witness_script: &[u8]) -> Result<Self, MultisigError> {
+        match classify(witness_script).class {
+            ScriptClass::Multisig { required, keys } => Self::new(required, keys),
+            _ => Err(MultisigError::NotMultisig),
+        }
+    }
+
+    pub fn required(&self) -> u8 {
+        self.required
+    }
+
+    pub fn keys(&self) -> &[Vec<u8>] {
+        &self.keys
+    }
+
+    /// Whether any key is quantum, which decides the output form
+    pub fn is_quantum(&self) -> bool {
+        self.keys
+            .iter()
+            .any(|key| MultisigKeyKind::of(key).is_some_and(|kind| kind.is_quantum()))
+    }
+
+    pub fn witness_script(&self) -> Vec<u8> {
+        // Validated in `new`, so the builder cannot fail
+        ScriptBuilder::multisig(self.required, &self.keys).unwrap_or_default()
+    }
+
+    /// Locking script of outputs paying this script
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        let witness_script = self.witness_script();
+        if self.is_quantum() {
+            return pubkey_commitment(&witness_script);
+        }
+        let script_hash = Sha256::digest(&witness_script);
+        let mut script = vec![0x00, 0x20];
+        script.extend_from_slice(&script_hash);
+        script
+    }
+
+    /// Largest witness a spend needs: `required` of the largest signatures
+    /// and the script
+    pub fn max_witness_size(&self) -> usize {
+        let mut signature_lens: Vec<usize> = self
+            .keys
+            .iter()
+            .filter_map(|key| MultisigKeyKind::of(key))
+            .map(|kind| kind.max_signature_len())
+            .collect();
+        signature_lens.sort_unstable_by(|a, b| b.cmp(a));
+        let signatures: usize = signature_lens.iter().take(self.required as usize).sum();
+        signatures + self.witness_script().len()
+    }
+
+    /// Position of `key` in the script
+    pub fn key_index(&self, key: &[u8]) -> Option<usize> {
+        self.keys.iter().position(|candidate| candidate == key)
+    }
+
+    /// Indices of the keys with a valid signature of `message` among
+    /// `signatures`, in key order. A key is listed once however many of
+    /// its signatures are present.
+    pub fn signers(&self, message: &[u8; 32], signatures: &[Vec<u8>]) -> Vec<usize> {
+        let mut signed = vec![false; self.keys.len()];
+        for signature in signatures {
+            let matched = self.keys.iter().enumerate().position(|(index, key)| {
+                !signed[index]
+                    && MultisigKeyKind::of(key)
+                        .is_some_and(|kind| kind.verify(key, message, signature))
+            });
+            if let Some(index) = matched {
+                signed[index] = true;
+            }
+        }
+        (0..self.keys.len())
+            .filter(|&index| signed[index])
+            .collect()
+    }
+
+    /// Check that `signatures` satisfy the threshold for `message`
+    pub fn verify(&self, message: &[u8; 32], signatures: &[Vec<u8>]) -> Result<(), MultisigError> {
+        if signatures.len() > self.keys.len() {
+            return Err(MultisigError::TooManySignatures {
+                signatures: signatures.len(),
+                keys: self.keys.len(),
+            });
+        }
+        let valid = self.signers(message, signatures).len();
+        if valid < self.required as usize {
+            return Err(MultisigError::NotEnoughSignatures {
+                required: self.required,
+                valid,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether `witness` spends a multisig output: its last item is a multisig
+/// script that `script_pubkey` pays
+pub fn is_multisig_spend(witness: &[Vec<u8>], script_pubkey: &[u8]) -> bool {
+    witness.last().is_some_and(|witness_script| {
+        MultisigScript::parse(witness_script)
+            .is_ok_and(|script| script.script_pubkey() == script_pubkey)
+    })
+}
+
+/// Verify a multisig spend of an output locked by `script_pubkey`:
+/// `witness` is the signatures followed by the witness script
+pub fn verify_multisig_witness(
+    witness: &[Vec<u8>],
+    script_pubkey: &[u8],
+    message: &[u8; 32],
+) -> Result<(), MultisigError> {
+    let (witness_script, signatures) = witness.split_last().ok_or(MultisigError::NotMultisig)?;
+    let script = MultisigScript::parse(witness_script)?;
+    if script.script_pubkey() != script_pubkey {
+        return Err(MultisigError::ScriptMismatch);
+    }
+    script.verify(message, signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::quantum::QuantumKeyPair;
+    use secp256k1::{Message, Secp256k1, SecretKey};
+
+    fn classical_key(seed: u8) -> (SecretKey, Vec<u8>) {
+        let secp = Secp256k1::new();
+        let secret = SecretKey::from_slice(&[seed; 32]).unwrap();
+        let public = secret.public_key(&secp).serialize().to_vec();
+        (secret, public)
+    }
+
+    fn sign(secret: &SecretKey, message: &[u8; 32]) -> Vec<u8> {
+        let secp = Secp256k1::new();
+        let message = Message::from_slice(message).unwrap();
+        secp.sign_ecdsa(&message, secret)
+            .serialize_compact()
+            .to_vec()
+    }
+
+    #[test]
+    fn signatures_count_once_per_key_in_any_order() {
+        let keys: Vec<_> = (1..=3).map(classical_key).collect();
+        let script =
+            MultisigScript::new(2, keys.iter().map(|(_, public)| public.clone()).collect())
+                .unwrap();
+        assert!(!script.is_quantum());
+        assert_eq!(script.script_pubkey()[..2], [0x00, 0x20]);
+        assert_eq!(
+            MultisigScript::parse(&script.witness_script()).unwrap(),
+            script
+        );
+
+        let message = [9u8; 32];
+        let first = sign(&keys[0].0, &message);
+        let third = sign(&keys[2].0, &message);
+        script
+            .verify(&message, &[third.clone(), first.clone()])
+            .unwrap();
+        assert_eq!(
+            script.signers(&message, &[third.clone(), first.clone()]),
+            [0, 2]
+        );
+
+        // The same signature twice is one signer
+        assert_eq!(
+            script.verify(&message, &[first.clone(), first.clone()]),
+            Err(MultisigError::NotEnoughSignatures {
+                required: 2,
+                valid: 1
+            })
+        );
+        // A signature of another message counts for nothing
+        assert_eq!(
+            script.verify(&message, &[first.clone(), sign(&keys[1].0, &[8u8; 32])]),
+            Err(MultisigError::NotEnoughSignatures {
+                required: 2,
+                valid: 1
+            })
+        );
+        assert!(matches!(
+            script.verify(&message, &vec![first; 4]),
+            Err(MultisigError::TooManySignatures { .. })
+        ));
+    }
+
+    #[test]
+    fn scripts_with_quantum_keys_pay_a_commitment() {
+        let keypair = QuantumKeyPair::generate(QuantumParameters {
+            scheme: QuantumScheme::Dilithium,
+            security_level: 3,
+        })
+        .unwrap();
+        let (secret, public) = classical_key(1);
+        let script = MultisigScript::new(2, vec![public, keypair.public_key.clone()]).unwrap();
+        assert!(script.is_quantum());
+        let script_pubkey = script.script_pubkey();
+        assert_eq!(script_pubkey, pubkey_commitment(&script.witness_script()));
+
+        let message = [3u8; 32];
+        let witness = vec![
+            keypair.sign(&message).unwrap(),
+            sign(&secret, &message),
+            script.witness_script(),
+        ];
+        assert!(is_multisig_spend(&witness, &script_pubkey));
+        verify_multisig_witness(&witness, &script_pubkey, &message).unwrap();
+        assert!(script.max_witness_size() >= witness.iter().map(Vec::len).sum::<usize>());
+        assert_eq!(
+            verify_multisig_witness(&witness, &[0u8; 32], &message),
+            Err(MultisigError::ScriptMismatch)
+        );
+    }
+
+    #[test]
+    fn invalid_scripts_are_refused() {
+        let (_, key) = classical_key(1);
+        assert_eq!(
+            MultisigScript::new(1, vec![key.clone(), key.clone()]),
+            Err(MultisigError::DuplicateKey(1))
+        );
+        assert_eq!(
+            MultisigScript::new(1, vec![key.clone(), vec![0x04; 65]]),
+            Err(MultisigError::UnsupportedKey(1))
+        );
+        assert!(matches!(
+            MultisigScript::new(2, vec![key]),
+            Err(MultisigError::InvalidScript(_))
+        ));
+        assert_eq!(
+            MultisigScript::parse(&[0x51]),
+            Err(MultisigError::NotMultisig)
+        );
+    }
+}
@@ -108,6 +108,29 @@ impl ScriptBuilder {
         }
     }
 
+    /// Append the opcodes of `script`
+    pub fn push_script(mut self, script: &[u8]) -> Self {
+        self.script.extend_from_slice(script);
+        self
+    }
+
+    /// Push `<lock_time> OP_CHECKLOCKTIMEVERIFY OP_DROP`: what follows
+    /// only runs in a transaction with a lock time of at least `lock_time`,
+    /// a height or a Unix time like the lock time itself
+    pub fn check_lock_time_verify(self, lock_time: u32) -> Self {
+        self.push_number(i64::from(lock_time))
+            .push_opcode(Opcode::OP_CHECKLOCKTIMEVERIFY)
+            .push_opcode(Opcode::OP_DROP)
+    }
+
+    /// Push `<sequence> OP_CHECKSEQUENCEVERIFY OP_DROP`: what follows only
+    /// runs in an input whose relative lock is at least `sequence`
+    pub fn check_sequence_verify(self, sequence: u32) -> Self {
+        self.push_number(i64::from(sequence))
+            .push_opcode(Opcode::OP_CHECKSEQUENCEVERIFY)
+            .push_opcode(Opcode::OP_DROP)
+    }
+
     /// Build the final script
     pub fn build(self) -> Vec<u8> {
         self.script
@@ -217,6 +240,68 @@ impl ScriptBuilder {
         Ok(builder.push_opcode(Opcode::OP_CHECKMULTISIG).build())
     }
 
+    /// Create a data carrier script: `OP_RETURN` and a single push of
+    /// `data`, or a bare `OP_RETURN` for no data.
+    ///
+    /// A 30-byte payload is pushed with `OP_PUSHDATA1`: the minimal push
+    /// would make a 32-byte script, which classifies as a quantum pubkey
+    /// commitment.
+    pub fn data_carrier(data: &[u8]) -> Vec<u8> {
+        let builder = Self::new().push_opcode(Opcode::OP_RETURN);
+        if data.is_empty() {
+            return builder.build();
+        }
+        if data.len() == 30 {
+            let mut script = builder.push_opcode(Opcode::OP_PUSHDATA1).build();
+            script.push(data.len() as u8);
+            script.extend_from_slice(data);
+            return script;
+        }
+        builder.push_data(data).build()
+    }
+
+    /// Create `script` spendable only from absolute `lock_time`
+    pub fn absolute_timelock(lock_time: u32, script: &[u8]) -> Vec<u8> {
+        Self::new()
+            .check_lock_time_verify(lock_time)
+            .push_script(script)
+            .build()
+    }
+
+    /// Create `script` spendable only once the output is `sequence` old,
+    /// in the encoding of an input's relative lock
+    pub fn relative_timelock(sequence: u32, script: &[u8]) -> Vec<u8> {
+        Self::new()
+            .check_sequence_verify(sequence)
+            .push_script(script)
+            .build()
+    }
+
+    /// Create a hash time-locked script: `claim` runs for a spender that
+    /// reveals the SHA-256 preimage of `payment_hash`, `refund` from
+    /// absolute `lock_time` on.
+    ///
+    /// `OP_IF OP_SHA256 <hash> OP_EQUALVERIFY <claim> OP_ELSE
+    /// <lock_time> OP_CHECKLOCKTIMEVERIFY OP_DROP <refund> OP_ENDIF`
+    pub fn hash_time_locked(
+        payment_hash: &[u8; 32],
+        claim: &[u8],
+        lock_time: u32,
+        refund: &[u8],
+    ) -> Vec<u8> {
+        Self::new()
+            .push_opcode(Opcode::OP_IF)
+            .push_opcode(Opcode::OP_SHA256)
+            .push_data(payment_hash)
+            .push_opcode(Opcode::OP_EQUALVERIFY)
+            .push_script(claim)
+            .push_opcode(Opcode::OP_ELSE)
+            .check_lock_time_verify(lock_time)
+            .push_script(refund)
+            .push_opcode(Opcode::OP_ENDIF)
+            .build()
+    }
+
     /// Hash a public key to get pubkey hash
     pub fn hash_pubkey(pubkey: &[u8]) -> Vec<u8> {
         let mut sha = Sha256::new();
@@ -328,6 +413,19 @@ mod tests {
         assert_eq!(encode_script_number(256), vec![0x00, 0x01]);
     }
 
+    #[test]
+    fn test_timelock_templates() {
+        let script = ScriptBuilder::absolute_timelock(500_000_000, &[0x51]);
+        assert_eq!(script, vec![0x04, 0x00, 0x65, 0xcd, 0x1d, 0xb1, 0x75, 0x51]);
+
+        // Sequences with the high bit set need a fifth byte to stay positive
+        let script = ScriptBuilder::relative_timelock(0x8000_0000, &[]);
+        assert_eq!(script, vec![0x05, 0x00, 0x00, 0x00, 0x80, 0x00, 0xb2, 0x75]);
+
+        let script = ScriptBuilder::relative_timelock(144, &[]);
+        assert_eq!(script, vec![0x02, 0x90, 0x00, 0xb2, 0x75]);
+    }
+
     #[test]
     fn test_multisig_script() {
         let pubkey1 = vec![0x02; 33];
@@ -343,6 +441,26 @@ mod tests {
         assert_eq!(script[70], 0xae); // OP_CHECKMULTISIG
     }
 
+    #[test]
+    fn test_data_carrier_round_trips_through_classify() {
+        use crate::script::classify::{classify, ScriptKind};
+
+        for len in [0usize, 1, 29, 30, 31, 75, 76, 80, 255, 256] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let script = ScriptBuilder::data_carrier(&data);
+            assert_eq!(script[0], 0x6a);
+            let class = classify(&script).class;
+            assert_eq!(
+                class.kind(),
+                ScriptKind::NullData,
+                "payload of {} bytes",
+                len
+            );
+            assert!(class.is_unspendable());
+            assert_eq!(class.data_payload(), Some(data));
+        }
+    }
+
     #[test]
     fn test_multisig_invalid_threshold_zero() {
         let pubkey1 = vec![0x02; 33];
@@ -6,6 +6,10 @@ use crate::crypto::signature::{verify_signature, SignatureType};
 use crate::script::interpreter::{ScriptError, ScriptInterpreter, SignatureChecker};
 use crate::script::ScriptVerificationError;
 use crate::script::{extract_script_hash, identify_script_type, ScriptType};
+use crate::types::timelock::{
+    LOCKTIME_THRESHOLD, RELATIVE_LOCKTIME_VERSION, SEQUENCE_FINAL, SEQUENCE_LOCKTIME_DISABLE_FLAG,
+    SEQUENCE_LOCKTIME_MASK, SEQUENCE_LOCKTIME_TYPE_FLAG,
+};
 use crate::types::transaction::Transaction;
 use ripemd::{Digest as RipemdDigest, Ripemd160};
 use sha2::{Digest, Sha256};
@@ -124,7 +128,7 @@ impl<'a> ScriptValidator<'a> {
 
         // Create checker and run the scripts
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_flags(self.flags);
 
         // First run script_sig
         interpreter
@@ -167,7 +171,7 @@ impl<'a> ScriptValidator<'a> {
 
         // Create checker and interpreter
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_flags(self.flags);
 
         // Run script_sig (without redeem script) + redeem script
         let script_sig_without_redeem = &script_sig[..script_sig.len() - redeem_script.len() - 1];
@@ -295,7 +299,7 @@ impl<'a> ScriptValidator<'a> {
 
         // Execute the witness script with the stack
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_flags(self.flags);
 
         // Push stack elements onto interpreter stack
         for item in stack {
@@ -328,7 +332,7 @@ impl<'a> ScriptValidator<'a> {
         script_pubkey: &[u8],
     ) -> Result<(), ScriptVerificationError> {
         let checker = TransactionChecker::new(self.transaction, self.input_index);
-        let mut interpreter = ScriptInterpreter::new();
+        let mut interpreter = ScriptInterpreter::new().with_flags(self.flags);
 
         // Execute script_sig first
         interpreter
@@ -421,14 +425,15 @@ impl<'a> ScriptValidator<'a> {
     }
 }
 
-/// Transaction signature checker
-struct TransactionChecker<'a> {
+/// Checks signatures and lock times against input `input_index` of a
+/// transaction
+pub struct TransactionChecker<'a> {
     transaction: &'a Transaction,
     input_index: usize,
 }
 
 impl<'a> TransactionChecker<'a> {
-    fn new(transaction: &'a Transaction, input_index: usize) -> Self {
+    pub fn new(transaction: &'a Transaction, input_index: usize) -> Self {
         Self {
             transaction,
             input_index,
@@ -447,6 +452,48 @@ impl<'a> SignatureChecker for TransactionChecker<'a> {
             Err(_) => Err(ScriptError::SignatureFailed),
         }
     }
+
+    fn check_lock_time(&self, lock_time: i64) -> bool {
+        let tx_lock_time = i64::from(self.transaction.lock_time());
+        let threshold = i64::from(LOCKTIME_THRESHOLD);
+
+        // Heights and times do not compare
+        if (tx_lock_time < threshold) != (lock_time < threshold) {
+            return false;
+        }
+        if lock_time > tx_lock_time {
+            return false;
+        }
+        // A final input would let the transaction ignore its lock time
+        self.transaction
+            .inputs()
+            .get(self.input_index)
+            .is_some_and(|input| input.sequence() != SEQUENCE_FINAL)
+    }
+
+    fn check_sequence(&self, sequence: i64) -> bool {
+        let Some(input) = self.transaction.inputs().get(self.input_index) else {
+            return false;
+        };
+        let tx_sequence = i64::from(input.sequence());
+
+        // Relative locks need a transaction version that enforces them, on
+        // an input that does not disable its own
+        if self.transaction.version() < RELATIVE_LOCKTIME_VERSION {
+            return false;
+        }
+        if tx_sequence & i64::from(SEQUENCE_LOCKTIME_DISABLE_FLAG) != 0 {
+            return false;
+        }
+
+        let mask = i64::from(SEQUENCE_LOCKTIME_TYPE_FLAG | SEQUENCE_LOCKTIME_MASK);
+        let (tx_sequence, sequence) = (tx_sequence & mask, sequence & mask);
+        let type_flag = i64::from(SEQUENCE_LOCKTIME_TYPE_FLAG);
+        if (tx_sequence < type_flag) != (sequence < type_flag) {
+            return false;
+        }
+        sequence <= tx_sequence
+    }
 }
 
 #[cfg(test)]
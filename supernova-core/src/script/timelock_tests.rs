@@ -0,0 +1,229 @@
+//! Lock Time Opcode Tests
+//!
+//! `OP_CHECKLOCKTIMEVERIFY` and `OP_CHECKSEQUENCEVERIFY` against the
+//! CLTV/CSV cases of Bitcoin Core's `script_tests.json`, `tx_valid.json`
+//! and `tx_invalid.json`, run against a single-input spend.
+
+#[cfg(test)]
+mod tests {
+    use crate::script::interpreter::{ScriptError, ScriptInterpreter};
+    use crate::script::{Opcode, ScriptBuilder, ScriptFlags, TransactionChecker};
+    use crate::types::timelock::{SEQUENCE_FINAL, SEQUENCE_LOCKTIME_DISABLE_FLAG};
+    use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+
+    fn spend(version: u32, lock_time: u32, sequence: u32) -> Transaction {
+        Transaction::new(
+            version,
+            vec![TransactionInput::new([1; 32], 0, vec![], sequence)],
+            vec![TransactionOutput::new(1_000, vec![])],
+            lock_time,
+        )
+    }
+
+    fn run(script: &[u8], tx: &Transaction) -> Result<bool, ScriptError> {
+        ScriptInterpreter::new().execute(script, &TransactionChecker::new(tx, 0))
+    }
+
+    /// `<operand> <opcode> 1`
+    fn lock_script(operand: i64, opcode: Opcode) -> Vec<u8> {
+        ScriptBuilder::new()
+            .push_number(operand)
+            .push_opcode(opcode)
+            .push_opcode(Opcode::OP_1)
+            .build()
+    }
+
+    fn cltv(operand: i64, lock_time: u32) -> Result<bool, ScriptError> {
+        let tx = spend(1, lock_time, 0);
+        run(&lock_script(operand, Opcode::OP_CHECKLOCKTIMEVERIFY), &tx)
+    }
+
+    fn csv(operand: i64, version: u32, sequence: u32) -> Result<bool, ScriptError> {
+        let tx = spend(version, 0, sequence);
+        run(&lock_script(operand, Opcode::OP_CHECKSEQUENCEVERIFY), &tx)
+    }
+
+    #[test]
+    fn cltv_accepts_lock_times_of_the_same_kind_up_to_the_transactions() {
+        for (operand, lock_time) in [
+            (0, 0),
+            (0, 499_999_999),
+            (499_999_999, 499_999_999),
+            (500_000_000, 500_000_000),
+            (500_000_000, u32::MAX),
+            (i64::from(u32::MAX), u32::MAX),
+        ] {
+            assert_eq!(
+                cltv(operand, lock_time),
+                Ok(true),
+                "{} against {}",
+                operand,
+                lock_time
+            );
+        }
+    }
+
+    #[test]
+    fn cltv_rejects_later_or_mismatched_lock_times() {
+        for (operand, lock_time) in [
+            (1, 0),
+            (499_999_999, 500_000_000),
+            (500_000_000, 499_999_999),
+            (500_000_001, 500_000_000),
+            (1i64 << 32, u32::MAX),
+        ] {
+            assert_eq!(
+                cltv(operand, lock_time),
+                Err(ScriptError::UnsatisfiedLockTime),
+                "{} against {}",
+                operand,
+                lock_time
+            );
+        }
+
+        // A final input opts out of the lock time, so it cannot satisfy one
+        let tx = spend(1, 0, SEQUENCE_FINAL);
+        assert_eq!(
+            run(&lock_script(0, Opcode::OP_CHECKLOCKTIMEVERIFY), &tx),
+            Err(ScriptError::UnsatisfiedLockTime)
+        );
+    }
+
+    #[test]
+    fn csv_accepts_relative_locks_of_the_same_kind_up_to_the_inputs() {
+        for (operand, sequence) in [
+            (0, 0),
+            (0, 0xffff),
+            (0xffff, 0xffff),
+            (1 << 22, 1 << 22),
+            (0x40_ffff, 0x40_ffff),
+            // Bits outside the type flag and value are not compared
+            (1 << 16, 0),
+            (1i64 << 32, 0),
+        ] {
+            assert_eq!(
+                csv(operand, 2, sequence),
+                Ok(true),
+                "{} against {}",
+                operand,
+                sequence
+            );
+        }
+    }
+
+    #[test]
+    fn csv_rejects_longer_mismatched_or_unenforced_locks() {
+        for (operand, version, sequence) in [
+            (0xffff, 2, 0xfffe),
+            (1 << 22, 2, 0),
+            (0, 2, 1 << 22),
+            (0x40_ffff, 2, 0x40_fffe),
+            // Version 1 transactions carry no relative locks
+            (0, 1, 0),
+            // Nor do inputs that disable theirs
+            (0, 2, SEQUENCE_LOCKTIME_DISABLE_FLAG),
+            (0, 2, SEQUENCE_FINAL),
+        ] {
+            assert_eq!(
+                csv(operand, version, sequence),
+                Err(ScriptError::UnsatisfiedLockTime),
+                "{} against version {} sequence {}",
+                operand,
+                version,
+                sequence
+            );
+        }
+    }
+
+    #[test]
+    fn csv_with_the_disable_flag_is_a_no_op() {
+        let disabled = i64::from(SEQUENCE_LOCKTIME_DISABLE_FLAG);
+        assert_eq!(csv(disabled, 1, 0), Ok(true));
+        assert_eq!(csv(disabled | 0xffff, 2, SEQUENCE_FINAL), Ok(true));
+        assert_eq!(csv(i64::from(u32::MAX), 1, 0), Ok(true));
+    }
+
+    #[test]
+    fn operands_must_be_present_non_negative_and_minimal() {
+        let tx = spend(2, 0, 0);
+        for opcode in [0xb1, 0xb2] {
+            assert_eq!(run(&[opcode], &tx), Err(ScriptError::StackUnderflow));
+            // -1
+            assert_eq!(
+                run(&[0x01, 0x81, opcode, 0x51], &tx),
+                Err(ScriptError::NegativeLockTime)
+            );
+            // Zero with a redundant byte
+            assert_eq!(
+                run(&[0x01, 0x00, opcode, 0x51], &tx),
+                Err(ScriptError::InvalidNumber)
+            );
+            // Five-byte zero
+            assert_eq!(
+                run(&[0x05, 0, 0, 0, 0, 0, opcode, 0x51], &tx),
+                Err(ScriptError::InvalidNumber)
+            );
+            // Six bytes are too many even when minimal
+            assert_eq!(
+                run(&[0x06, 0, 0, 0, 0, 0, 0x01, opcode, 0x51], &tx),
+                Err(ScriptError::InvalidNumber)
+            );
+        }
+
+        // Without MINIMALDATA the five-byte zero is plain zero
+        let flags = ScriptFlags {
+            verify_minimaldata: false,
+            ..ScriptFlags::default()
+        };
+        let checker = TransactionChecker::new(&tx, 0);
+        let result = ScriptInterpreter::new()
+            .with_flags(flags)
+            .execute(&[0x05, 0, 0, 0, 0, 0, 0xb1, 0x51], &checker);
+        assert_eq!(result, Ok(true));
+    }
+
+    #[test]
+    fn the_operand_stays_on_the_stack() {
+        let tx = spend(1, 100, 0);
+        // 100 CLTV 100 EQUAL
+        let script = [0x01, 100, 0xb1, 0x01, 100, 0x87];
+        assert_eq!(run(&script, &tx), Ok(true));
+    }
+
+    #[test]
+    fn disabled_flags_turn_the_opcodes_into_no_ops() {
+        let flags = ScriptFlags {
+            verify_checklocktimeverify: false,
+            verify_checksequenceverify: false,
+            ..ScriptFlags::default()
+        };
+        let tx = spend(1, 0, SEQUENCE_FINAL);
+        let checker = TransactionChecker::new(&tx, 0);
+        for opcode in [
+            Opcode::OP_CHECKLOCKTIMEVERIFY,
+            Opcode::OP_CHECKSEQUENCEVERIFY,
+        ] {
+            let result = ScriptInterpreter::new()
+                .with_flags(flags)
+                .execute(&lock_script(1_000, opcode), &checker);
+            assert_eq!(result, Ok(true));
+        }
+    }
+
+    #[test]
+    fn timelock_templates_enforce_their_lock() {
+        let refund = ScriptBuilder::absolute_timelock(1_000, &[0x51]);
+        assert_eq!(
+            run(&refund, &spend(1, 999, 0)),
+            Err(ScriptError::UnsatisfiedLockTime)
+        );
+        assert_eq!(run(&refund, &spend(1, 1_000, 0)), Ok(true));
+
+        let delayed = ScriptBuilder::relative_timelock(144, &[0x51]);
+        assert_eq!(
+            run(&delayed, &spend(2, 0, 143)),
+            Err(ScriptError::UnsatisfiedLockTime)
+        );
+        assert_eq!(run(&delayed, &spend(2, 0, 144)), Ok(true));
+    }
+}
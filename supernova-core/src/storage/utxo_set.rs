@@ -590,6 +590,17 @@ impl UtxoSet {
         utxos
     }
 
+    /// Every UTXO held in memory
+    pub fn entries(&self) -> Vec<UtxoEntry> {
+        match self.cache.read() {
+            Ok(cache) => cache.iter().map(|(_, entry)| entry.clone()).collect(),
+            Err(e) => {
+                error!("Failed to read UTXO cache for entries: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
     /// Get the count of UTXOs in the set
     pub fn get_count(&self) -> usize {
         match self.cache.read() {
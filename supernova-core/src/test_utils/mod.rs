@@ -4,7 +4,8 @@
 //!
 //! This module contains mock implementations and test helpers that bypass critical
 //! security checks. It MUST NEVER be used in production code. All items in this
-//! module are gated behind #[cfg(test)] to prevent accidental production use.
+//! module are gated behind #[cfg(test)], or the `test-utils` feature for the
+//! tests of dependent crates, to prevent accidental production use.
 //!
 //! # Security Considerations
 //!
@@ -22,7 +23,7 @@
 //! 3. Provide builders that follow Rust's ownership model
 //! 4. Document security implications for each utility
 
-#![cfg(test)]
+#![cfg(any(test, feature = "test-utils"))]
 
 use crate::crypto::signature::{Signature, SignatureType, SignatureVerifier};
 use crate::error::SupernovaError;
@@ -142,6 +143,12 @@ impl TestTransactionBuilder {
     }
 }
 
+impl Default for TestTransactionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Builder for creating test blocks with proper structure
 ///
 /// This builder ensures blocks are created with valid structure
@@ -265,6 +272,12 @@ impl TestBlockBuilder {
     }
 }
 
+impl Default for TestBlockBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Helper function to create a mock UTXO lookup function for testing
 ///
 /// This creates a closure that returns predetermined outputs for specific
@@ -1,3 +1,4 @@
+use crate::consensus::difficulty_retarget::RetargetParams;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -14,6 +15,10 @@ pub struct TestNetConfig {
     pub difficulty_adjustment_window: u64,
     /// Maximum difficulty adjustment factor per window
     pub max_difficulty_adjustment_factor: f64,
+    /// Allow a block at minimum difficulty after four target block times
+    /// without one, so a hashrate drop cannot stall the network
+    #[serde(default = "default_allow_min_difficulty_blocks")]
+    pub allow_min_difficulty_blocks: bool,
     /// Genesis block configuration
     pub genesis_config: GenesisConfig,
     /// Whether to enable test faucet
@@ -42,6 +47,23 @@ pub struct TestNetConfig {
     pub logging: LoggingConfig,
 }
 
+fn default_allow_min_difficulty_blocks() -> bool {
+    true
+}
+
+impl TestNetConfig {
+    /// Consensus retarget rules for this network: the testnet floor with this
+    /// config's block time, window and stall rule
+    pub fn retarget_params(&self) -> RetargetParams {
+        RetargetParams {
+            target_block_time: self.target_block_time_secs,
+            interval: self.difficulty_adjustment_window,
+            allow_min_difficulty_blocks: self.allow_min_difficulty_blocks,
+            ..RetargetParams::testnet()
+        }
+    }
+}
+
 /// Genesis block configuration for test networks
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenesisConfig {
@@ -265,6 +287,7 @@ impl Default for TestNetConfig {
             initial_difficulty: 100_000, // Low initial difficulty for easier mining
             difficulty_adjustment_window: 2016, // Adjust every 2016 blocks (~3.5 days)
             max_difficulty_adjustment_factor: 4.0, // Allow up to 4x difficulty change
+            allow_min_difficulty_blocks: true,
             genesis_config: GenesisConfig {
                 timestamp: 1672531200, // June 1, 2025
                 initial_distribution: vec![CoinDistribution {
@@ -228,6 +228,7 @@ fn convert_to_blockchain_config(testnet_config: &TestNetConfig) -> TestNetConfig
         initial_difficulty: testnet_config.initial_difficulty,
         difficulty_adjustment_window: testnet_config.difficulty_adjustment_window,
         max_difficulty_adjustment_factor: testnet_config.max_difficulty_adjustment_factor,
+        allow_min_difficulty_blocks: testnet_config.allow_min_difficulty_blocks,
         p2p_port: testnet_config.p2p_port,
         rpc_port: testnet_config.rpc_port,
         // Set reasonable defaults for other fields
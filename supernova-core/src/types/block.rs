@@ -1,6 +1,6 @@
+use crate::consensus::witness::{self, WitnessCommitmentError};
 use crate::hash::{hash256, Hash256};
 use crate::types::transaction::Transaction;
-use crate::util::merkle::MerkleTree;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -300,14 +300,8 @@ impl Block {
         transactions: Vec<Transaction>,
         bits: u32,
     ) -> Self {
-        // Calculate Merkle root
-        let merkle_root = if transactions.is_empty() {
-            [0; 32]
-        } else {
-            let tx_hashes: Vec<Hash256> = transactions.iter().map(|tx| tx.hash()).collect();
-            let merkle_tree = MerkleTree::new(&tx_hashes);
-            merkle_tree.root_hash()
-        };
+        // Calculate Merkle root; the header starts at height 0
+        let merkle_root = witness::merkle_root(&transactions, 0);
 
         // Create timestamp. `duration_since(UNIX_EPOCH)` only fails if the
         // system clock predates 1970; fall back to 0 rather than panicking
@@ -343,18 +337,21 @@ impl Block {
         self.header.hash()
     }
 
-    /// Calculate the Merkle root of the transactions
+    /// Calculate the Merkle root of the transactions: of their txids from
+    /// the witness-txid activation height, of their legacy txids below it
     pub fn calculate_merkle_root(&self) -> Hash256 {
-        if self.transactions.is_empty() {
-            return [0; 32];
-        }
+        witness::merkle_root(&self.transactions, self.height())
+    }
 
-        // Get transaction hashes
-        let tx_hashes: Vec<Hash256> = self.transactions.iter().map(|tx| tx.hash()).collect();
+    /// Calculate the Merkle root of the wtxids the coinbase commits to
+    pub fn calculate_witness_root(&self) -> Hash256 {
+        witness::witness_root(&self.transactions)
+    }
 
-        // Create Merkle tree
-        let merkle_tree = MerkleTree::new(&tx_hashes);
-        merkle_tree.root_hash()
+    /// Check the coinbase's witness commitment, see
+    /// [`crate::consensus::witness`]
+    pub fn check_witness_commitment(&self) -> Result<(), WitnessCommitmentError> {
+        witness::check_commitment(&self.transactions, self.height())
     }
 
     /// Verify that the block meets the target difficulty
@@ -380,6 +377,11 @@ impl Block {
             return false;
         }
 
+        // Verify the witness commitment
+        if self.check_witness_commitment().is_err() {
+            return false;
+        }
+
         // Validate transactions
         if !self.validate_transactions() {
             return false;
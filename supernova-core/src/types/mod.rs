@@ -2,6 +2,7 @@ pub mod block;
 pub mod coinbase;
 pub mod extended_transaction;
 pub mod safe_arithmetic;
+pub mod timelock;
 pub mod transaction;
 pub mod transaction_dependency;
 pub mod transaction_safe;
@@ -33,6 +34,7 @@ pub use extended_transaction::ExtendedTransactionInput;
 pub use safe_arithmetic::{
     calculate_fee_safe, safe_add, safe_div, safe_mul, safe_sub, sum_safe, ArithmeticError,
 };
+pub use timelock::{FinalityContext, InputAge, TimeLock};
 pub use transaction::{Transaction, TransactionError, TransactionInput, TransactionOutput};
 pub use transaction_dependency::TransactionDependencyGraph;
 pub use transaction_safe::TransactionSafe;
@@ -0,0 +1,224 @@
+//! Transaction finality: absolute lock times and BIP68-style relative locks
+//!
+//! A transaction is final at a chain position when it could be included in the
+//! next block. Two rules apply:
+//!
+//! - Absolute: a non-zero `lock_time` below [`LOCKTIME_THRESHOLD`] is a block
+//!   height the next block must exceed; at or above it, a Unix time the tip's
+//!   median time past must exceed. Ignored when every input's sequence is
+//!   [`SEQUENCE_FINAL`].
+//! - Relative (version 2 and up): an input whose sequence lacks
+//!   [`SEQUENCE_LOCKTIME_DISABLE_FLAG`] must have been confirmed for at least
+//!   the encoded number of blocks, or of 512-second units when
+//!   [`SEQUENCE_LOCKTIME_TYPE_FLAG`] is set.
+
+use super::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// `lock_time` values below this are block heights, values at or above it are
+/// Unix timestamps
+pub const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+/// Input sequence that opts out of the absolute lock time
+pub const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+
+/// Set on an input's sequence to disable its relative lock
+pub const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+
+/// Set on an input's sequence to count the relative lock in time units
+/// instead of blocks
+pub const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+
+/// Bits of the sequence holding the relative lock value
+pub const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000_ffff;
+
+/// Relative time locks count in units of `1 << SEQUENCE_LOCKTIME_GRANULARITY`
+/// seconds (512)
+pub const SEQUENCE_LOCKTIME_GRANULARITY: u32 = 9;
+
+/// First transaction version whose input sequences carry relative locks
+pub const RELATIVE_LOCKTIME_VERSION: u32 = 2;
+
+/// Chain position a transaction's finality is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalityContext {
+    /// Height of the next block
+    pub next_height: u64,
+    /// Median time past of the current tip
+    pub median_time_past: u64,
+}
+
+/// Where the output an input spends was confirmed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputAge {
+    /// In the block at `height`; `median_time_past` is that block's parent's
+    Confirmed { height: u64, median_time_past: u64 },
+    /// Not confirmed yet; counts as confirming in the next block
+    Unconfirmed,
+    /// Not known to the caller; the input's relative lock is not evaluated
+    Unknown,
+}
+
+/// Earliest chain position at which a transaction is final. Both bounds must
+/// hold; zero means unconstrained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeLock {
+    /// Lowest height of a block that may include the transaction
+    pub min_height: u64,
+    /// Lowest tip median time past at which it may be included
+    pub min_time: u64,
+}
+
+impl TimeLock {
+    /// The locks `tx` is subject to when checked at `ctx`. `age` reports the
+    /// confirmation of each previous transaction an input with a relative
+    /// lock spends.
+    pub fn of(
+        tx: &Transaction,
+        ctx: &FinalityContext,
+        mut age: impl FnMut(&[u8; 32]) -> InputAge,
+    ) -> Self {
+        let mut lock = TimeLock::default();
+
+        let lock_time = tx.lock_time();
+        let opted_out = tx.inputs().iter().all(|i| i.sequence() == SEQUENCE_FINAL);
+        if lock_time != 0 && !opted_out {
+            if lock_time < LOCKTIME_THRESHOLD {
+                lock.min_height = u64::from(lock_time) + 1;
+            } else {
+                lock.min_time = u64::from(lock_time) + 1;
+            }
+        }
+
+        if tx.version() < RELATIVE_LOCKTIME_VERSION {
+            return lock;
+        }
+        for input in tx.inputs() {
+            let sequence = input.sequence();
+            if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+                continue;
+            }
+            let (height, median_time_past) = match age(&input.prev_tx_hash()) {
+                InputAge::Confirmed {
+                    height,
+                    median_time_past,
+                } => (height, median_time_past),
+                InputAge::Unconfirmed => (ctx.next_height, ctx.median_time_past),
+                InputAge::Unknown => continue,
+            };
+            let value = u64::from(sequence & SEQUENCE_LOCKTIME_MASK);
+            if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+                let min_time =
+                    median_time_past.saturating_add(value << SEQUENCE_LOCKTIME_GRANULARITY);
+                lock.min_time = lock.min_time.max(min_time);
+            } else {
+                lock.min_height = lock.min_height.max(height.saturating_add(value));
+            }
+        }
+        lock
+    }
+
+    /// Whether a transaction with this lock may go in the next block at `ctx`
+    pub fn is_satisfied(&self, ctx: &FinalityContext) -> bool {
+        ctx.next_height >= self.min_height && ctx.median_time_past >= self.min_time
+    }
+}
+
+impl Transaction {
+    /// Whether this transaction may be included in the next block at `ctx`
+    pub fn is_final(&self, ctx: &FinalityContext, age: impl FnMut(&[u8; 32]) -> InputAge) -> bool {
+        TimeLock::of(self, ctx, age).is_satisfied(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::transaction::{TransactionInput, TransactionOutput};
+
+    const PARENT: [u8; 32] = [7u8; 32];
+
+    fn tx(version: u32, sequence: u32, lock_time: u32) -> Transaction {
+        Transaction::new(
+            version,
+            vec![TransactionInput::new(PARENT, 0, vec![], sequence)],
+            vec![TransactionOutput::new(1_000, vec![])],
+            lock_time,
+        )
+    }
+
+    fn at(next_height: u64, median_time_past: u64) -> FinalityContext {
+        FinalityContext {
+            next_height,
+            median_time_past,
+        }
+    }
+
+    fn unknown(_: &[u8; 32]) -> InputAge {
+        InputAge::Unknown
+    }
+
+    #[test]
+    fn test_height_lock_time() {
+        let locked = tx(1, 0, 100);
+        let lock = TimeLock::of(&locked, &at(50, 0), unknown);
+        assert_eq!(lock.min_height, 101);
+        assert!(!locked.is_final(&at(100, 0), unknown));
+        assert!(locked.is_final(&at(101, 0), unknown));
+    }
+
+    #[test]
+    fn test_time_lock_time_uses_median_time_past() {
+        let locked = tx(1, 0, 1_700_000_000);
+        assert!(!locked.is_final(&at(10, 1_700_000_000), unknown));
+        assert!(locked.is_final(&at(10, 1_700_000_001), unknown));
+    }
+
+    #[test]
+    fn test_final_sequences_disable_lock_time() {
+        let opted_out = tx(1, SEQUENCE_FINAL, 100);
+        assert_eq!(
+            TimeLock::of(&opted_out, &at(1, 0), unknown),
+            TimeLock::default()
+        );
+    }
+
+    #[test]
+    fn test_relative_height_lock() {
+        let spend = tx(2, 10, 0);
+        let confirmed = |_: &[u8; 32]| InputAge::Confirmed {
+            height: 50,
+            median_time_past: 0,
+        };
+        assert!(!spend.is_final(&at(59, 0), confirmed));
+        assert!(spend.is_final(&at(60, 0), confirmed));
+
+        // Version 1 transactions and disabled sequences carry no relative lock
+        assert!(tx(1, 10, 0).is_final(&at(51, 0), confirmed));
+        assert!(tx(2, 10 | SEQUENCE_LOCKTIME_DISABLE_FLAG, 0).is_final(&at(51, 0), confirmed));
+    }
+
+    #[test]
+    fn test_relative_time_lock() {
+        let spend = tx(2, SEQUENCE_LOCKTIME_TYPE_FLAG | 2, 0);
+        let confirmed = |_: &[u8; 32]| InputAge::Confirmed {
+            height: 50,
+            median_time_past: 10_000,
+        };
+        let lock = TimeLock::of(&spend, &at(60, 10_000), confirmed);
+        assert_eq!(lock.min_time, 10_000 + 1_024);
+        assert!(!lock.is_satisfied(&at(60, 11_023)));
+        assert!(lock.is_satisfied(&at(60, 11_024)));
+    }
+
+    #[test]
+    fn test_unconfirmed_parent_counts_from_next_block() {
+        let spend = tx(2, 3, 0);
+        let lock = TimeLock::of(&spend, &at(20, 0), |_| InputAge::Unconfirmed);
+        assert_eq!(lock.min_height, 23);
+        assert_eq!(
+            TimeLock::of(&spend, &at(20, 0), unknown),
+            TimeLock::default()
+        );
+    }
+}
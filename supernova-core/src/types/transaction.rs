@@ -1,3 +1,4 @@
+use crate::crypto::hybrid::{sign_hybrid, verify_hybrid, HybridPolicy, HybridPublicKey};
 use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
 use crate::crypto::signature::{SignatureError, SignatureType, SignatureVerifier};
 use crate::environmental::emissions::{Emissions, EmissionsError, EmissionsTracker};
@@ -267,6 +268,26 @@ impl TransactionOutput {
     pub fn script_pubkey(&self) -> &[u8] {
         &self.pub_key_script
     }
+
+    /// Zero-value `OP_RETURN` output carrying `data`
+    pub fn data_carrier(data: &[u8]) -> Self {
+        Self::new(0, crate::script::ScriptBuilder::data_carrier(data))
+    }
+
+    /// Payload of a data carrier output, `None` for any other script
+    pub fn data_payload(&self) -> Option<Vec<u8>> {
+        crate::script::classify(&self.pub_key_script)
+            .class
+            .data_payload()
+    }
+
+    /// Whether this output can never be spent and so never counts towards
+    /// a balance
+    pub fn is_unspendable(&self) -> bool {
+        crate::script::classify(&self.pub_key_script)
+            .class
+            .is_unspendable()
+    }
 }
 
 /// Key commitment used by output scripts: the first 32 bytes of
@@ -331,41 +352,89 @@ impl Transaction {
         self.signature_data = None;
     }
 
-    /// Calculate the transaction hash.
-    ///
-    /// `bincode::serialize` on a `Transaction` with the standard derives in
-    /// scope cannot fail at runtime — the only failure modes (unknown type,
-    /// custom-serializer error, size-limit overflow) don't apply. The
-    /// `unwrap_or_else` arm logs and falls back to an empty buffer to
-    /// satisfy the panic-free lint policy without `unsafe`; the resulting
-    /// hash would be a known SHA-256-of-empty constant
-    /// (`e3b0c4429…b7852b855`), which is recognisable on inspection.
-    /// Cascading a `Result<[u8; 32], _>` return is not viable: this method
-    /// is on the hot consensus path and called pervasively.
+    /// Calculate the transaction hash: the txid, see [`Transaction::txid`]
     pub fn hash(&self) -> [u8; 32] {
-        let serialized = if self.version >= 2 && self.signature_data.is_some() {
-            // For v2+ transactions with extended signatures, hash with the
-            // signature data stripped so the same hash can be used for
-            // signing and identity.
-            let mut tx_copy = self.clone();
-            tx_copy.signature_data = None;
-            bincode::serialize(&tx_copy).unwrap_or_else(|e| {
-                error!("Transaction (v2+) bincode::serialize failed (unreachable): {}", e);
-                Vec::new()
-            })
+        self.txid()
+    }
+
+    /// The transaction id. Covers everything a signature commits to plus the
+    /// input scripts, and leaves out the extended signature data and every
+    /// input's witness, so signing or re-signing never changes it. Outpoints,
+    /// the mempool and the UTXO set are keyed by it; see
+    /// [`crate::consensus::witness`].
+    ///
+    /// The fields are encoded with `signature_data` as `None` instead of
+    /// cloning the transaction to strip it: bincode writes a struct as its
+    /// fields in order, so the bytes are identical. Inputs are only copied
+    /// when one of them carries a witness.
+    pub fn txid(&self) -> [u8; 32] {
+        let stripped: Vec<TransactionInput>;
+        let inputs = if self.has_witness() {
+            stripped = self
+                .inputs
+                .iter()
+                .map(|input| TransactionInput {
+                    witness: Vec::new(),
+                    ..input.clone()
+                })
+                .collect();
+            &stripped
         } else {
-            bincode::serialize(self).unwrap_or_else(|e| {
-                error!("Transaction bincode::serialize failed (unreachable): {}", e);
-                Vec::new()
-            })
+            &self.inputs
         };
+        Self::digest(&(
+            self.version,
+            inputs,
+            &self.outputs,
+            self.lock_time,
+            None::<&TransactionSignatureData>,
+        ))
+    }
+
+    /// Hash of the whole transaction, signature data and witnesses included
+    pub fn wtxid(&self) -> [u8; 32] {
+        Self::digest(self)
+    }
+
+    /// The id blocks below the witness-txid activation height commit to:
+    /// v2+ transactions leave out their signature data, but witnesses and
+    /// v1 signature data are covered
+    pub fn legacy_txid(&self) -> [u8; 32] {
+        if self.version >= 2 && self.signature_data.is_some() {
+            Self::digest(&(
+                self.version,
+                &self.inputs,
+                &self.outputs,
+                self.lock_time,
+                None::<&TransactionSignatureData>,
+            ))
+        } else {
+            Self::digest(self)
+        }
+    }
 
+    /// Whether the transaction carries data its txid leaves out
+    pub fn has_witness_data(&self) -> bool {
+        self.signature_data.is_some() || self.has_witness()
+    }
+
+    /// SHA-256 of the bincode encoding of `value`, streamed straight into
+    /// the hasher rather than collected into a buffer first.
+    ///
+    /// `bincode` encoding of these types cannot fail at runtime — the only
+    /// failure modes (unknown type, custom-serializer error, size-limit
+    /// overflow) don't apply. The error arm logs and falls back to the
+    /// SHA-256-of-empty constant (`e3b0c4429…b7852b855`), which is
+    /// recognisable on inspection, to satisfy the panic-free lint policy.
+    /// Cascading a `Result<[u8; 32], _>` return is not viable: ids are on
+    /// the hot consensus path and computed pervasively.
+    fn digest<T: Serialize + ?Sized>(value: &T) -> [u8; 32] {
         let mut hasher = Sha256::new();
-        hasher.update(&serialized);
-        let result = hasher.finalize();
-        let mut hash = [0u8; 32];
-        hash.copy_from_slice(&result);
-        hash
+        if let Err(e) = bincode::serialize_into(&mut hasher, value) {
+            error!("Transaction bincode::serialize failed (unreachable): {}", e);
+            hasher = Sha256::new();
+        }
+        hasher.finalize().into()
     }
 
     /// Canonical message digest that a transaction's signature commits to
@@ -429,6 +498,18 @@ impl Transaction {
     pub fn verify_authorization(
         &self,
         get_prevout: impl Fn(&[u8; 32], u32) -> Option<TransactionOutput>,
+    ) -> Result<(), TransactionError> {
+        self.verify_signature_only()?;
+        self.verify_key_binding(get_prevout)
+    }
+
+    /// Step (2) of [`Transaction::verify_authorization`] alone: check that
+    /// the signing key owns every output the transaction spends, without
+    /// verifying the signature. Callers that verified the signature earlier
+    /// (see [`crate::validation::sig_cache`]) need only this.
+    pub fn verify_key_binding(
+        &self,
+        get_prevout: impl Fn(&[u8; 32], u32) -> Option<TransactionOutput>,
     ) -> Result<(), TransactionError> {
         if self.is_coinbase() {
             return Ok(());
@@ -438,50 +519,6 @@ impl Transaction {
             TransactionError::InvalidSignature("transaction carries no signature".to_string())
         })?;
 
-        // (1) Cryptographic verification over the canonical sighash.
-        let message = self.signature_hash();
-        let crypto_ok = match sig.scheme {
-            SignatureSchemeType::Dilithium
-            | SignatureSchemeType::Falcon
-            | SignatureSchemeType::SphincsPlus => {
-                let scheme = match sig.scheme {
-                    SignatureSchemeType::Dilithium => QuantumScheme::Dilithium,
-                    SignatureSchemeType::Falcon => QuantumScheme::Falcon,
-                    _ => QuantumScheme::SphincsPlus,
-                };
-                let params = QuantumParameters {
-                    scheme,
-                    security_level: sig.security_level,
-                };
-                crate::crypto::quantum::verify_quantum_signature(
-                    &sig.public_key,
-                    &message,
-                    &sig.data,
-                    params,
-                )
-                .map_err(|e| TransactionError::QuantumSignatureError(e.to_string()))?
-            }
-            SignatureSchemeType::Legacy => SignatureVerifier::new()
-                .verify(
-                    SignatureType::Secp256k1,
-                    &sig.public_key,
-                    &message,
-                    &sig.data,
-                )
-                .map_err(TransactionError::from)?,
-            SignatureSchemeType::Ed25519 => SignatureVerifier::new()
-                .verify(SignatureType::Ed25519, &sig.public_key, &message, &sig.data)
-                .map_err(TransactionError::from)?,
-            SignatureSchemeType::Hybrid => {
-                return Err(TransactionError::InvalidSignature(
-                    "hybrid signature scheme is not yet supported".to_string(),
-                ));
-            }
-        };
-        if !crypto_ok {
-            return Err(TransactionError::SignatureVerificationFailed);
-        }
-
         // (2) Bind the signing key to every spent output (fail-closed).
         let commitment = pubkey_commitment(&sig.public_key);
         for (i, input) in self.inputs.iter().enumerate() {
@@ -522,7 +559,18 @@ impl Transaction {
     /// [`Transaction::verify_authorization`]; it does **not** replace it. Full
     /// authorization (the key-to-output binding in step (2)) is still enforced
     /// by consensus at block-inclusion time.
+    ///
+    /// Hybrid signatures must verify in both halves.
     pub fn verify_signature_only(&self) -> Result<(), TransactionError> {
+        self.verify_signature_with_policy(HybridPolicy::RequireBoth)
+    }
+
+    /// [`Transaction::verify_signature_only`] with hybrid signatures checked
+    /// under `policy`
+    pub fn verify_signature_with_policy(
+        &self,
+        policy: HybridPolicy,
+    ) -> Result<(), TransactionError> {
         if self.is_coinbase() {
             return Ok(());
         }
@@ -564,11 +612,14 @@ impl Transaction {
             SignatureSchemeType::Ed25519 => SignatureVerifier::new()
                 .verify(SignatureType::Ed25519, &sig.public_key, &message, &sig.data)
                 .map_err(TransactionError::from)?,
-            SignatureSchemeType::Hybrid => {
-                return Err(TransactionError::InvalidSignature(
-                    "hybrid signature scheme is not yet supported".to_string(),
-                ));
-            }
+            SignatureSchemeType::Hybrid => verify_hybrid(
+                &sig.public_key,
+                &message,
+                &sig.data,
+                sig.security_level,
+                policy,
+            )
+            .map_err(|e| TransactionError::QuantumSignatureError(e.to_string()))?,
         };
         if !crypto_ok {
             return Err(TransactionError::SignatureVerificationFailed);
@@ -602,6 +653,11 @@ impl Transaction {
         &self.outputs
     }
 
+    /// Append an output
+    pub fn add_output(&mut self, output: TransactionOutput) {
+        self.outputs.push(output);
+    }
+
     /// Get witness data for a specific input
     pub fn input_witness(&self, index: usize) -> Option<&[Vec<u8>]> {
         self.inputs.get(index).map(|input| input.witness())
@@ -836,12 +892,14 @@ impl Transaction {
                 )
                 .unwrap_or_default()
             }
-            SignatureSchemeType::Hybrid => {
-                // Implementation for hybrid verification is more complex and would require
-                // parsing the data to separate classical and quantum signatures
-                // For now, return false to indicate it's not implemented
-                false
-            }
+            SignatureSchemeType::Hybrid => verify_hybrid(
+                &signature_data.public_key,
+                &message_hash,
+                &signature_data.data,
+                signature_data.security_level,
+                HybridPolicy::RequireBoth,
+            )
+            .unwrap_or_default(),
         }
     }
 
@@ -1080,10 +1138,26 @@ impl Transaction {
                 ));
             }
             SignatureSchemeType::Hybrid => {
-                // This is a placeholder - hybrid schemes are not yet fully implemented
-                return Err(SignatureError::UnsupportedScheme(
-                    "Hybrid signatures not yet implemented".to_string(),
-                ));
+                // The classical scheme is implied by the hybrid public key
+                let hybrid_key = HybridPublicKey::from_bytes(public_key)
+                    .map_err(|e| SignatureError::InvalidKey(e.to_string()))?;
+                let quantum_keypair = QuantumKeyPair {
+                    public_key: public_key.to_vec(),
+                    secret_key: private_key.to_vec(),
+                    parameters: QuantumParameters {
+                        scheme: QuantumScheme::Hybrid(hybrid_key.classical_scheme),
+                        security_level,
+                    },
+                };
+
+                sign_hybrid(&quantum_keypair, &tx_hash)
+                    .map_err(|e| {
+                        SignatureError::CryptoOperationFailed(format!(
+                            "Hybrid signing failed: {}",
+                            e
+                        ))
+                    })?
+                    .to_bytes()
             }
         };
 
@@ -1606,6 +1680,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hybrid_signatures_need_both_halves_unless_migrating() {
+        use crate::crypto::hybrid::HybridSignature;
+        use crate::crypto::quantum::ClassicalScheme;
+
+        let keypair = QuantumKeyPair::generate(QuantumParameters::with_security_level(
+            QuantumScheme::Hybrid(ClassicalScheme::Ed25519),
+            3,
+        ))
+        .expect("keypair generation");
+        let script = pubkey_commitment(&keypair.public_key);
+        let inputs = vec![TransactionInput::new([7u8; 32], 0, vec![], 0xffffffff)];
+        let outputs = vec![TransactionOutput::new(40_000_000, vec![0xab; 32])];
+        let mut tx = Transaction::new(2, inputs, outputs, 0);
+        let signature = sign_hybrid(&keypair, &tx.signature_hash()).expect("sign");
+        let with_signature = |tx: &Transaction, signature: &HybridSignature| {
+            let mut tx = tx.clone();
+            tx.set_signature_data(TransactionSignatureData {
+                scheme: SignatureSchemeType::Hybrid,
+                security_level: 3,
+                data: signature.to_bytes(),
+                public_key: keypair.public_key.clone(),
+            });
+            tx
+        };
+
+        tx = with_signature(&tx, &signature);
+        let get_prevout =
+            move |_h: &[u8; 32], _i: u32| Some(TransactionOutput::new(50_000_000, script.clone()));
+        assert!(tx.verify_authorization(&get_prevout).is_ok());
+
+        // Only the classical signature: the quantum half is absent or invalid
+        let mut classical_only = tx.signature_data().unwrap().clone();
+        classical_only.data.truncate(2 + signature.classical.len());
+        let mut missing = tx.clone();
+        missing.set_signature_data(classical_only);
+        assert!(missing.verify_signature_only().is_err());
+        assert!(missing
+            .verify_signature_with_policy(HybridPolicy::EitherPasses)
+            .is_err());
+
+        let mut forged = signature.clone();
+        forged.quantum[0] ^= 1;
+        let forged = with_signature(&tx, &forged);
+        assert!(forged.verify_signature_only().is_err());
+        assert!(forged
+            .verify_signature_with_policy(HybridPolicy::EitherPasses)
+            .is_ok());
+    }
+
     #[test]
     fn missing_signature_is_rejected() {
         let inputs = vec![TransactionInput::new([7u8; 32], 0, vec![], 0xffffffff)];
@@ -1640,6 +1764,28 @@ mod tests {
         let tx = Transaction::new(1, inputs, outputs, 0);
         assert!(!tx.verify_ecdsa_signature(&[0u8; 64], &[2u8; 33], &[9u8; 32]));
     }
+
+    #[test]
+    fn streamed_hash_matches_buffered_encoding() {
+        let inputs = vec![TransactionInput::new([7u8; 32], 1, vec![0xab; 80], 0xffffffff)];
+        let outputs = vec![TransactionOutput::new(42, vec![0xcd; 32])];
+        let buffered = |tx: &Transaction| -> [u8; 32] {
+            Sha256::digest(bincode::serialize(tx).unwrap()).into()
+        };
+
+        let v1 = Transaction::new(1, inputs.clone(), outputs.clone(), 9);
+        assert_eq!(v1.hash(), buffered(&v1));
+
+        let mut v2 = Transaction::new(2, inputs, outputs, 9);
+        let unsigned = buffered(&v2);
+        v2.set_signature_data(TransactionSignatureData {
+            scheme: SignatureSchemeType::Dilithium,
+            security_level: 3,
+            data: vec![1; 64],
+            public_key: vec![2; 32],
+        });
+        assert_eq!(v2.hash(), unsigned);
+    }
 }
 
 /// Enum representing different types of transaction scripts
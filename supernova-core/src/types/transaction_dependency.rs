@@ -66,8 +66,14 @@ impl TransactionDependencyGraph {
             }
         }
 
-        // Remove its dependencies
-        self.dependencies.remove(tx_hash);
+        // Remove its dependencies, and itself from their dependents
+        if let Some(deps) = self.dependencies.remove(tx_hash) {
+            for parent in deps {
+                if let Some(dependents) = self.dependents.get_mut(&parent) {
+                    dependents.remove(tx_hash);
+                }
+            }
+        }
     }
 
     /// Get transactions that can be processed immediately (have no dependencies)
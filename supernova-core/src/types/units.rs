@@ -45,7 +45,12 @@ pub const NOVAS_PER_NOVA: u64 = 100_000_000; // 10^8
 ///
 /// This is the authoritative consensus value; every other subsidy implementation
 /// in the codebase is reconciled onto this same 50-NOVA / 420,000-block curve.
+/// A custom network loaded from a netparams file substitutes its own schedule
+/// (`crate::netparams::SubsidySchedule`).
 pub fn block_subsidy(height: u64) -> u64 {
+    if let Some(params) = crate::netparams::active() {
+        return params.subsidy.subsidy(height);
+    }
     const HALVING_INTERVAL: u64 = 420_000;
     const MAX_HALVINGS: u64 = 64;
     let halvings = height / HALVING_INTERVAL;
@@ -56,6 +61,15 @@ pub fn block_subsidy(height: u64) -> u64 {
     (50u64 * NOVAS_PER_NOVA) >> halvings
 }
 
+/// Sum of [`block_subsidy`] over heights `from..=to`: the most that blocks in
+/// that range can mint.
+pub fn total_block_subsidy(from: u64, to: u64) -> u64 {
+    crate::netparams::active()
+        .map(|params| params.subsidy)
+        .unwrap_or_default()
+        .total_subsidy(from, to)
+}
+
 /// Currency units for Supernova
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NovaUnit {
@@ -0,0 +1,273 @@
+//! Canonical JSON (RFC 8785 style)
+//!
+//! `serde_json` output depends on struct field order, map implementation and
+//! float formatting, so bytes signed by one build may not match what another
+//! build re-serializes. Canonical form fixes all of these:
+//!
+//! - object members sorted by the UTF-16 code units of their keys;
+//! - no insignificant whitespace;
+//! - strings escaped minimally: `"`, `\` and control characters only, using
+//!   the short escapes where JSON has them and lowercase `\u00xx` otherwise;
+//!   everything else, including non-ASCII, is emitted as UTF-8;
+//! - numbers formatted as ECMAScript `Number.prototype.toString` does.
+//!
+//! Unlike RFC 8785, integers outside the IEEE-754 safe range (±2^53 − 1) are
+//! rejected rather than silently rounded; encode such values as strings.
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Largest integer magnitude a double represents exactly
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum CanonicalJsonError {
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+    #[error("Number {0} is outside the safe integer range; encode it as a string")]
+    UnsafeInteger(String),
+    #[error("Non-finite numbers have no JSON representation")]
+    NonFiniteNumber,
+}
+
+/// Canonical encoding of any serializable value
+pub fn to_canonical_string<T: Serialize + ?Sized>(value: &T) -> Result<String, CanonicalJsonError> {
+    let value =
+        serde_json::to_value(value).map_err(|e| CanonicalJsonError::Serialization(e.to_string()))?;
+    canonicalize(&value)
+}
+
+/// Canonical encoding as UTF-8 bytes, ready to hash or sign
+pub fn to_canonical_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CanonicalJsonError> {
+    to_canonical_string(value).map(String::into_bytes)
+}
+
+/// Canonical encoding of a JSON value
+pub fn canonicalize(value: &Value) -> Result<String, CanonicalJsonError> {
+    let mut out = String::new();
+    write_value(&mut out, value)?;
+    Ok(out)
+}
+
+fn write_value(out: &mut String, value: &Value) -> Result<(), CanonicalJsonError> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n)?,
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item)?;
+            }
+            out.push(']');
+        }
+        Value::Object(members) => write_object(out, members)?,
+    }
+    Ok(())
+}
+
+fn write_object(out: &mut String, members: &Map<String, Value>) -> Result<(), CanonicalJsonError> {
+    let mut sorted: Vec<(&String, &Value)> = members.iter().collect();
+    sorted.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+    out.push('{');
+    for (i, (key, value)) in sorted.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_string(out, key);
+        out.push(':');
+        write_value(out, value)?;
+    }
+    out.push('}');
+    Ok(())
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_number(out: &mut String, n: &Number) -> Result<(), CanonicalJsonError> {
+    if let Some(u) = n.as_u64() {
+        if u > MAX_SAFE_INTEGER {
+            return Err(CanonicalJsonError::UnsafeInteger(u.to_string()));
+        }
+        let _ = write!(out, "{}", u);
+    } else if let Some(i) = n.as_i64() {
+        if i.unsigned_abs() > MAX_SAFE_INTEGER {
+            return Err(CanonicalJsonError::UnsafeInteger(i.to_string()));
+        }
+        let _ = write!(out, "{}", i);
+    } else {
+        let f = n.as_f64().ok_or(CanonicalJsonError::NonFiniteNumber)?;
+        out.push_str(&format_f64(f)?);
+    }
+    Ok(())
+}
+
+/// ECMAScript `Number.prototype.toString` for a finite double
+pub fn format_f64(value: f64) -> Result<String, CanonicalJsonError> {
+    if !value.is_finite() {
+        return Err(CanonicalJsonError::NonFiniteNumber);
+    }
+    if value == 0.0 {
+        // Covers -0 too
+        return Ok("0".to_string());
+    }
+
+    // `{:e}` yields the shortest round-tripping digits, e.g. "-1.2345e-7"
+    let sci = format!("{:e}", value.abs());
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let k = digits.len() as i32;
+    // Decimal point position relative to the digit string
+    let n = exponent + 1;
+
+    let mut out = String::new();
+    if value < 0.0 {
+        out.push('-');
+    }
+    if k <= n && n <= 21 {
+        out.push_str(&digits);
+        out.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        out.push_str(&digits[..n as usize]);
+        out.push('.');
+        out.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        out.push_str("0.");
+        out.push_str(&"0".repeat((-n) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let _ = write!(out, "e{}{}", if n - 1 < 0 { '-' } else { '+' }, (n - 1).abs());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn canon(s: &str) -> String {
+        canonicalize(&serde_json::from_str(s).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn number_formatting_matches_ecmascript() {
+        let cases: &[(f64, &str)] = &[
+            (0.0, "0"),
+            (-0.0, "0"),
+            (1.0, "1"),
+            (-1.5, "-1.5"),
+            (0.1, "0.1"),
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (123456789012345680000.0, "123456789012345680000"),
+            (1e-6, "0.000001"),
+            (1e-7, "1e-7"),
+            (1.5e-7, "1.5e-7"),
+            (4.5, "4.5"),
+            (2e-3, "0.002"),
+            (1e9 / 3.0, "333333333.3333333"),
+            (f64::from_bits(1), "5e-324"),
+            (f64::MAX, "1.7976931348623157e+308"),
+            (9007199254740994.0, "9007199254740994"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(format_f64(*value).unwrap(), *expected, "formatting {value:e}");
+        }
+        assert_eq!(format_f64(f64::NAN), Err(CanonicalJsonError::NonFiniteNumber));
+        assert_eq!(format_f64(f64::INFINITY), Err(CanonicalJsonError::NonFiniteNumber));
+
+        assert_eq!(canon("[1.0, 1E2, -0.0, 10.50, 9007199254740991]"), "[1,100,0,10.5,9007199254740991]");
+        assert!(matches!(
+            canonicalize(&json!(9007199254740992u64)),
+            Err(CanonicalJsonError::UnsafeInteger(_))
+        ));
+        assert!(matches!(
+            canonicalize(&json!(-9007199254740992i64)),
+            Err(CanonicalJsonError::UnsafeInteger(_))
+        ));
+    }
+
+    #[test]
+    fn strings_use_minimal_escaping() {
+        assert_eq!(
+            canon(r#""Aé€😀 \/ \" \\ \b\f\n\r\t \u0001\u001f\u007f""#),
+            "\"Aé€😀 / \\\" \\\\ \\b\\f\\n\\r\\t \\u0001\\u001f\u{7f}\""
+        );
+    }
+
+    #[test]
+    fn keys_sort_by_utf16_code_units() {
+        // RFC 8785 §3.2.3 example: U+1F600 (surrogate D83D) sorts before
+        // U+FB33 even though its UTF-8 encoding is larger.
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{80}": "Control",
+            "\u{f6}": "Latin Small Letter O With Diaeresis"
+        });
+        assert_eq!(
+            canonicalize(&value).unwrap(),
+            "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",\
+             \"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",\
+             \"\u{1f600}\":\"Emoji: Grinning Face\",\
+             \"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+        );
+    }
+
+    #[test]
+    fn field_order_does_not_change_encoding() {
+        #[derive(Serialize)]
+        struct Forward {
+            amount: u64,
+            memo: &'static str,
+            nested: Value,
+        }
+        #[derive(Serialize)]
+        struct Reversed {
+            nested: Value,
+            memo: &'static str,
+            amount: u64,
+        }
+        let a = Forward { amount: 5, memo: "hi", nested: json!({"z": 1, "a": [true, null]}) };
+        let b = Reversed { nested: json!({"a": [true, null], "z": 1}), memo: "hi", amount: 5 };
+        let expected = r#"{"amount":5,"memo":"hi","nested":{"a":[true,null],"z":1}}"#;
+        assert_eq!(to_canonical_string(&a).unwrap(), expected);
+        assert_eq!(to_canonical_string(&b).unwrap(), expected);
+        assert_eq!(canon("{ \"nested\" : {\"z\":1.0,\"a\":[ true , null ]}, \"memo\":\"hi\",\"amount\":5 }"), expected);
+    }
+}
@@ -0,0 +1,146 @@
+//! Local clock sanity checks
+//!
+//! Compares the local clock against an outside reference (peer-reported
+//! network time for a node, the node's reported time for a wallet) and
+//! against the latest block timestamp. Offsets are `reference - local`, so a
+//! positive offset means the local clock is behind.
+
+use serde::{Deserialize, Serialize};
+
+/// Skew beyond which a warning is raised, in seconds
+pub const DEFAULT_WARN_SKEW_SECS: u64 = 60;
+
+/// Skew beyond which block production and signing are refused, in seconds
+pub const DEFAULT_CRITICAL_SKEW_SECS: u64 = 600;
+
+/// How far off the local clock looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkewSeverity {
+    Ok,
+    Warning,
+    Critical,
+}
+
+/// Skew limits used to classify an offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkewThresholds {
+    pub warn_secs: u64,
+    pub critical_secs: u64,
+}
+
+impl Default for SkewThresholds {
+    fn default() -> Self {
+        Self {
+            warn_secs: DEFAULT_WARN_SKEW_SECS,
+            critical_secs: DEFAULT_CRITICAL_SKEW_SECS,
+        }
+    }
+}
+
+impl SkewThresholds {
+    /// Classify an offset in either direction
+    pub fn classify(&self, offset_secs: i64) -> SkewSeverity {
+        let skew = offset_secs.unsigned_abs();
+        if skew > self.critical_secs {
+            SkewSeverity::Critical
+        } else if skew > self.warn_secs {
+            SkewSeverity::Warning
+        } else {
+            SkewSeverity::Ok
+        }
+    }
+}
+
+/// Result of a clock check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockCheck {
+    /// Offset to the outside reference, if one was available
+    pub offset_secs: Option<i64>,
+    /// How far the latest block timestamp is ahead of the local clock
+    pub tip_lag_secs: i64,
+    pub severity: SkewSeverity,
+}
+
+impl ClockCheck {
+    /// The larger of the two measured skews, signed
+    pub fn skew_secs(&self) -> i64 {
+        match self.offset_secs {
+            Some(offset) if offset.unsigned_abs() >= self.tip_lag_secs.unsigned_abs() => offset,
+            _ => self.tip_lag_secs,
+        }
+    }
+}
+
+/// Median of the sampled offsets, `None` without samples
+pub fn median_offset(offsets: &[i64]) -> Option<i64> {
+    if offsets.is_empty() {
+        return None;
+    }
+    let mut sorted = offsets.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        Some(sorted[mid - 1] + (sorted[mid] - sorted[mid - 1]) / 2)
+    } else {
+        Some(sorted[mid])
+    }
+}
+
+/// Check the local clock against a reference offset and the tip timestamp.
+///
+/// The tip only counts when it is ahead of the local clock: an old tip just
+/// means the chain is quiet or still syncing.
+pub fn check_clock(
+    now: u64,
+    offset_secs: Option<i64>,
+    tip_timestamp: Option<u64>,
+    thresholds: &SkewThresholds,
+) -> ClockCheck {
+    let tip_lag_secs = tip_timestamp
+        .map(|tip| tip.saturating_sub(now).min(i64::MAX as u64) as i64)
+        .unwrap_or(0);
+    let severity = offset_secs
+        .map(|offset| thresholds.classify(offset))
+        .unwrap_or(SkewSeverity::Ok)
+        .max(thresholds.classify(tip_lag_secs));
+    ClockCheck {
+        offset_secs,
+        tip_lag_secs,
+        severity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skew_severity() {
+        let thresholds = SkewThresholds::default();
+        let now = 1_700_000_000;
+
+        let check = check_clock(now, Some(5), Some(now - 3_600), &thresholds);
+        assert_eq!(check.severity, SkewSeverity::Ok);
+
+        let check = check_clock(now, Some(-120), None, &thresholds);
+        assert_eq!(check.severity, SkewSeverity::Warning);
+        assert_eq!(check.skew_secs(), -120);
+
+        let check = check_clock(now, Some(3_600), None, &thresholds);
+        assert_eq!(check.severity, SkewSeverity::Critical);
+
+        // A tip far ahead of the local clock is enough on its own
+        let check = check_clock(now, None, Some(now + 900), &thresholds);
+        assert_eq!(check.severity, SkewSeverity::Critical);
+        assert_eq!(check.skew_secs(), 900);
+    }
+
+    #[test]
+    fn test_median_offset() {
+        assert_eq!(median_offset(&[]), None);
+        assert_eq!(median_offset(&[7]), Some(7));
+        assert_eq!(median_offset(&[-10, 4_000, 2, 3]), Some(2));
+        assert_eq!(median_offset(&[1, 900, -900]), Some(1));
+    }
+}
@@ -1,6 +1,7 @@
 //! Merkle Tree implementation for the Supernova blockchain
 //!
-//! This module provides a Merkle Tree implementation with proof generation and verification.
+//! This module provides a Merkle Tree implementation with proof generation and verification,
+//! for single leaves ([`MerkleProof`]) and for arbitrary leaf sets ([`MerkleMultiProof`]).
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -25,6 +26,18 @@ pub enum MerkleError {
     /// Serialization error
     #[error("Serialization error: {0}")]
     SerializationError(#[from] bincode::Error),
+
+    /// The same leaf index was given more than once
+    #[error("Duplicate leaf index: {0}")]
+    DuplicateLeaf(usize),
+
+    /// A multi-proof was requested or checked for no leaves
+    #[error("No leaves selected")]
+    NoLeaves,
+
+    /// Encoded multi-proof is malformed
+    #[error("Malformed multi-proof: {0}")]
+    MalformedProof(String),
 }
 
 /// Result type for Merkle tree operations
@@ -54,7 +67,29 @@ pub struct MerkleProof {
     pub root_hash: [u8; 32],
 }
 
+/// A proof that several leaves are included in one Merkle tree.
+///
+/// Carries only the internal hashes that cannot be recomputed from the
+/// proven leaves themselves, ordered bottom level first and left to right
+/// within a level. Proving every leaf needs no hashes at all.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleMultiProof {
+    /// Number of leaves in the tree the proof was made for
+    pub leaf_count: usize,
+    /// Sibling hashes not derivable from the proven leaves
+    pub hashes: Vec<[u8; 32]>,
+}
+
 impl MerkleTree {
+    /// Hash a data item into a leaf
+    pub fn hash_leaf<T: AsRef<[u8]>>(item: T) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(item.as_ref());
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize());
+        hash
+    }
+
     /// Create a new Merkle tree from the given data
     pub fn new<T: AsRef<[u8]>>(data: &[T]) -> Self {
         if data.is_empty() {
@@ -67,23 +102,17 @@ impl MerkleTree {
         }
 
         // Hash all leaves
-        let mut leaves = Vec::with_capacity(data.len());
-        for item in data {
-            let mut hasher = Sha256::new();
-            hasher.update(item.as_ref());
-            let mut hash = [0u8; 32];
-            hash.copy_from_slice(&hasher.finalize());
-            leaves.push(hash);
-        }
+        let leaves: Vec<[u8; 32]> = data.iter().map(Self::hash_leaf).collect();
 
-        // Build the tree
-        let mut nodes = Vec::new();
+        // Build the tree; a tree of n leaves has about log2(n) + 1 levels
+        let depth = (usize::BITS - leaves.len().leading_zeros()) as usize + 1;
+        let mut nodes = Vec::with_capacity(depth);
         nodes.push(leaves.clone());
 
         let mut level = 0;
         while nodes[level].len() > 1 {
             let current_level = &nodes[level];
-            let mut next_level = Vec::new();
+            let mut next_level = Vec::with_capacity(current_level.len().div_ceil(2));
 
             // Combine pairs of nodes to create the next level
             for i in (0..current_level.len()).step_by(2) {
@@ -168,6 +197,38 @@ impl MerkleTree {
         })
     }
 
+    /// Create a single proof covering the leaves at `indices`
+    pub fn prove_subset(&self, indices: &[usize]) -> MerkleResult<MerkleMultiProof> {
+        if self.leaves.is_empty() {
+            return Err(MerkleError::EmptyTree);
+        }
+        let mut known = sorted_unique_indices(indices.iter().copied(), self.leaves.len())?;
+
+        let mut hashes = Vec::new();
+        for level in &self.nodes[..self.nodes.len() - 1] {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let index = known[i];
+                if index % 2 == 0 && known.get(i + 1) == Some(&(index + 1)) {
+                    // Both children known: nothing to add
+                    i += 1;
+                } else if let Some(sibling) = level.get(index ^ 1) {
+                    hashes.push(*sibling);
+                }
+                // A missing sibling means the node is promoted unchanged
+                parents.push(index / 2);
+                i += 1;
+            }
+            known = parents;
+        }
+
+        Ok(MerkleMultiProof {
+            leaf_count: self.leaves.len(),
+            hashes,
+        })
+    }
+
     /// Verify a proof
     pub fn verify_proof(proof: &MerkleProof) -> bool {
         if proof.lemma.is_empty() {
@@ -248,6 +309,121 @@ impl MerkleProof {
     }
 }
 
+/// Sort `indices`, rejecting duplicates, an empty set and anything not
+/// below `leaf_count`
+fn sorted_unique_indices(
+    indices: impl Iterator<Item = usize>,
+    leaf_count: usize,
+) -> MerkleResult<Vec<usize>> {
+    let mut sorted: Vec<usize> = indices.collect();
+    if sorted.is_empty() {
+        return Err(MerkleError::NoLeaves);
+    }
+    sorted.sort_unstable();
+    for pair in sorted.windows(2) {
+        if pair[0] == pair[1] {
+            return Err(MerkleError::DuplicateLeaf(pair[0]));
+        }
+    }
+    match sorted.last() {
+        Some(&last) if last >= leaf_count => Err(MerkleError::IndexOutOfBounds(last)),
+        _ => Ok(sorted),
+    }
+}
+
+impl MerkleMultiProof {
+    /// Check that `leaves` — `(index, leaf hash)` pairs in any order — are
+    /// all included in the tree with root `root`.
+    ///
+    /// Misuse (no leaves, duplicate or out-of-range indices) is an error;
+    /// a proof that simply does not match yields `Ok(false)`.
+    pub fn verify(&self, root: &[u8; 32], leaves: &[(usize, [u8; 32])]) -> MerkleResult<bool> {
+        sorted_unique_indices(leaves.iter().map(|(index, _)| *index), self.leaf_count)?;
+        let mut known = leaves.to_vec();
+        known.sort_unstable_by_key(|(index, _)| *index);
+
+        let mut proof_hashes = self.hashes.iter();
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let (index, hash) = known[i];
+                let parent = match known.get(i + 1) {
+                    Some(&(next, right)) if index % 2 == 0 && next == index + 1 => {
+                        i += 1;
+                        MerkleTree::hash_pair(&hash, &right)
+                    }
+                    _ if index ^ 1 >= level_len => hash,
+                    _ => {
+                        let Some(sibling) = proof_hashes.next() else {
+                            return Ok(false);
+                        };
+                        if index % 2 == 0 {
+                            MerkleTree::hash_pair(&hash, sibling)
+                        } else {
+                            MerkleTree::hash_pair(sibling, &hash)
+                        }
+                    }
+                };
+                parents.push((index / 2, parent));
+                i += 1;
+            }
+            known = parents;
+            level_len = level_len.div_ceil(2);
+        }
+
+        Ok(proof_hashes.next().is_none() && known.first().map(|(_, hash)| hash) == Some(root))
+    }
+
+    /// Compact encoding: leaf count and hash count as little-endian `u32`s,
+    /// followed by the hashes
+    pub fn to_bytes(&self) -> MerkleResult<Vec<u8>> {
+        let leaf_count = u32::try_from(self.leaf_count)
+            .map_err(|_| MerkleError::MalformedProof("leaf count exceeds u32".to_string()))?;
+        let hash_count = u32::try_from(self.hashes.len())
+            .map_err(|_| MerkleError::MalformedProof("hash count exceeds u32".to_string()))?;
+        let mut bytes = Vec::with_capacity(8 + self.hashes.len() * 32);
+        bytes.extend_from_slice(&leaf_count.to_le_bytes());
+        bytes.extend_from_slice(&hash_count.to_le_bytes());
+        for hash in &self.hashes {
+            bytes.extend_from_slice(hash);
+        }
+        Ok(bytes)
+    }
+
+    /// Decode the [`to_bytes`](Self::to_bytes) encoding
+    pub fn from_bytes(bytes: &[u8]) -> MerkleResult<Self> {
+        let read_u32 = |offset: usize| -> MerkleResult<usize> {
+            bytes
+                .get(offset..offset + 4)
+                .and_then(|b| b.try_into().ok())
+                .map(|b| u32::from_le_bytes(b) as usize)
+                .ok_or_else(|| MerkleError::MalformedProof("truncated header".to_string()))
+        };
+        let leaf_count = read_u32(0)?;
+        let hash_count = read_u32(4)?;
+        let body = &bytes[8..];
+        if leaf_count == 0 || hash_count >= leaf_count || body.len() != hash_count * 32 {
+            return Err(MerkleError::MalformedProof(format!(
+                "{} hashes for {} leaves in {} bytes",
+                hash_count,
+                leaf_count,
+                body.len()
+            )));
+        }
+        let hashes = body
+            .chunks_exact(32)
+            .map(|chunk| {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(chunk);
+                hash
+            })
+            .collect();
+        Ok(Self { leaf_count, hashes })
+    }
+}
+
 impl fmt::Display for MerkleProof {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "MerkleProof {{")?;
@@ -344,6 +520,127 @@ mod tests {
         assert!(!MerkleTree::verify_proof(&proof));
     }
 
+    fn data(n: usize) -> Vec<Vec<u8>> {
+        (0..n).map(|i| format!("tx{}", i).into_bytes()).collect()
+    }
+
+    fn check_subset(tree: &MerkleTree, indices: &[usize]) -> MerkleMultiProof {
+        let proof = tree.prove_subset(indices).unwrap();
+        let leaves: Vec<_> = indices.iter().map(|&i| (i, tree.leaves[i])).collect();
+        assert!(proof.verify(&tree.root, &leaves).unwrap(), "indices {:?}", indices);
+
+        let decoded = MerkleMultiProof::from_bytes(&proof.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+        proof
+    }
+
+    #[test]
+    fn test_multi_proof_adjacent_and_scattered_leaves() {
+        let tree = MerkleTree::new(&data(16));
+
+        // Leaves 4 and 5 share a parent: one hash per level above it
+        let adjacent = check_subset(&tree, &[4, 5]);
+        assert_eq!(adjacent.hashes.len(), 3);
+
+        let scattered = check_subset(&tree, &[1, 6, 11]);
+        assert!(scattered.hashes.len() < 3 * 4);
+
+        // Leaf order in the verifier's input does not matter
+        let leaves = vec![(11, tree.leaves[11]), (1, tree.leaves[1]), (6, tree.leaves[6])];
+        assert!(scattered.verify(&tree.root, &leaves).unwrap());
+
+        // A wrong leaf or a wrong root fails
+        let mut tampered = leaves.clone();
+        tampered[0].1[0] ^= 1;
+        assert!(!scattered.verify(&tree.root, &tampered).unwrap());
+        assert!(!scattered.verify(&[0u8; 32], &leaves).unwrap());
+        // Proving a different subset with the same proof fails
+        let other = vec![(2, tree.leaves[2]), (6, tree.leaves[6]), (11, tree.leaves[11])];
+        assert!(!scattered.verify(&tree.root, &other).unwrap());
+    }
+
+    #[test]
+    fn test_multi_proof_all_leaves_needs_no_hashes() {
+        for n in [1, 2, 5, 8, 13] {
+            let tree = MerkleTree::new(&data(n));
+            let all: Vec<usize> = (0..n).collect();
+            assert!(check_subset(&tree, &all).hashes.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_multi_proof_single_leaf_matches_single_proof() {
+        let tree = MerkleTree::new(&data(8));
+        for i in 0..8 {
+            let single = tree.create_proof(i).unwrap();
+            let multi = check_subset(&tree, &[i]);
+            assert_eq!(multi.hashes, single.lemma);
+            assert!(MerkleTree::verify_proof(&single));
+        }
+    }
+
+    #[test]
+    fn test_multi_proof_rejects_misuse() {
+        let tree = MerkleTree::new(&data(6));
+        assert!(matches!(tree.prove_subset(&[2, 2]), Err(MerkleError::DuplicateLeaf(2))));
+        assert!(matches!(tree.prove_subset(&[0, 6]), Err(MerkleError::IndexOutOfBounds(6))));
+        assert!(matches!(tree.prove_subset(&[]), Err(MerkleError::NoLeaves)));
+        assert!(matches!(
+            MerkleTree::new::<&[u8]>(&[]).prove_subset(&[0]),
+            Err(MerkleError::EmptyTree)
+        ));
+
+        let proof = tree.prove_subset(&[1, 3]).unwrap();
+        let dup = vec![(1, tree.leaves[1]), (1, tree.leaves[1])];
+        assert!(matches!(proof.verify(&tree.root, &dup), Err(MerkleError::DuplicateLeaf(1))));
+        let out = vec![(1, tree.leaves[1]), (7, tree.leaves[3])];
+        assert!(matches!(proof.verify(&tree.root, &out), Err(MerkleError::IndexOutOfBounds(7))));
+
+        // Extra or missing hashes do not verify
+        let mut padded = proof.clone();
+        padded.hashes.push([0u8; 32]);
+        let leaves = vec![(1, tree.leaves[1]), (3, tree.leaves[3])];
+        assert!(!padded.verify(&tree.root, &leaves).unwrap());
+        let mut short = proof.clone();
+        short.hashes.pop();
+        assert!(!short.verify(&tree.root, &leaves).unwrap());
+
+        // Truncated or inconsistent encodings are rejected
+        let bytes = proof.to_bytes().unwrap();
+        assert!(MerkleMultiProof::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(MerkleMultiProof::from_bytes(&bytes[..5]).is_err());
+    }
+
+    #[test]
+    fn test_multi_proof_randomized_against_full_tree() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(0x5eed);
+
+        for _ in 0..200 {
+            let n = rng.gen_range(1..=40);
+            let tree = MerkleTree::new(&data(n));
+            let indices: Vec<usize> = (0..n).filter(|_| rng.gen_bool(0.3)).collect();
+            if indices.is_empty() {
+                continue;
+            }
+            let proof = check_subset(&tree, &indices);
+
+            // Replacing any proven leaf changes the recomputed root, which
+            // must match rebuilding the full tree with that leaf changed
+            let victim = indices[rng.gen_range(0..indices.len())];
+            let mut changed = data(n);
+            changed[victim] = b"forged".to_vec();
+            let forged_tree = MerkleTree::new(&changed);
+            let forged: Vec<_> = indices.iter().map(|&i| (i, forged_tree.leaves[i])).collect();
+            assert!(!proof.verify(&tree.root, &forged).unwrap());
+            if forged_tree.root != tree.root {
+                let forged_proof = forged_tree.prove_subset(&indices).unwrap();
+                assert!(forged_proof.verify(&forged_tree.root, &forged).unwrap());
+                assert!(!forged_proof.verify(&tree.root, &forged).unwrap());
+            }
+        }
+    }
+
     #[test]
     fn test_proof_out_of_bounds() {
         let data = vec![b"test".to_vec()];
@@ -1,4 +1,6 @@
 pub mod ascii_art;
+pub mod canonical_json;
+pub mod clock;
 pub mod hex;
 pub mod logging;
 /// Utility functions and data structures for supernova blockchain
@@ -13,7 +15,7 @@ pub mod metrics;
 
 // Re-export commonly used utilities
 pub use hex::{bytes_to_hex, hex_to_bytes};
-pub use merkle::{MerkleError, MerkleProof, MerkleTree};
+pub use merkle::{MerkleError, MerkleMultiProof, MerkleProof, MerkleTree};
 
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
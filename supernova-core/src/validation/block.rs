@@ -1,12 +1,24 @@
 // Block validation - comprehensive security implementation for Supernova
 
 use crate::config::NetworkType;
+use crate::consensus::checkpoint::default_assume_valid;
 use crate::consensus::difficulty::calculate_required_work;
+use crate::consensus::difficulty_retarget::{self, RetargetParams};
+use crate::consensus::witness::WitnessCommitmentError;
+use crate::crypto::quantum::{QuantumBatchVerifier, QuantumParameters, QuantumScheme};
+use crate::environmental::attestation::{GreenBonusClaim, GreenBonusError};
+use crate::environmental::treasury::EnvironmentalTreasury;
 use crate::governance::{TreasuryError, TREASURY_ALLOCATION_PERCENT, TREASURY_SCRIPT_LEN};
 use crate::types::block::Block;
-use crate::types::transaction::Transaction;
-use crate::validation::transaction::TransactionValidator;
-use std::collections::HashSet;
+use crate::types::transaction::{
+    SignatureSchemeType, Transaction, TransactionError, TransactionOutput,
+};
+use crate::validation::sig_cache::{hybrid_policy, SignatureCache, CONSENSUS_VERIFY_FLAGS};
+use crate::validation::trace::{redact_bytes, Traced, Tracer, ValidationTrace};
+use crate::validation::transaction::{signed_by_transaction, verify_input, TransactionValidator};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::debug;
 
@@ -85,9 +97,22 @@ pub enum BlockValidationError {
     #[error("Script validation failed: {0}")]
     ScriptValidationFailed(String),
 
-    /// Witness commitment mismatch
-    #[error("Witness commitment mismatch")]
-    WitnessCommitmentMismatch,
+    /// An input spends an output that is neither in the UTXO set nor
+    /// created earlier in the block
+    #[error("Missing previous output for tx {tx_index} input {input_index}")]
+    MissingPrevout { tx_index: usize, input_index: usize },
+
+    /// An input's signature did not verify
+    #[error("Signature verification failed for tx {tx_index} input {input_index}: {reason}")]
+    InputSignature {
+        tx_index: usize,
+        input_index: usize,
+        reason: String,
+    },
+
+    /// Witness commitment missing, malformed or not matching the wtxids
+    #[error("Invalid witness commitment: {0}")]
+    InvalidWitnessCommitment(#[from] WitnessCommitmentError),
 
     /// Invalid block header
     #[error("Invalid block header: {0}")]
@@ -96,6 +121,10 @@ pub enum BlockValidationError {
     /// Coinbase treasury allocation is missing or malformed
     #[error("Invalid treasury output: {0}")]
     InvalidTreasuryOutput(String),
+
+    /// Coinbase green bonus is not backed by a valid attestation
+    #[error("Invalid green bonus: {0}")]
+    InvalidGreenBonus(#[from] GreenBonusError),
 }
 
 impl From<TreasuryError> for BlockValidationError {
@@ -170,6 +199,13 @@ pub struct BlockValidationConfig {
 
     /// Maximum validation complexity (SECURITY FIX P1-005)
     pub max_validation_complexity: u64,
+
+    /// Assumevalid block: it and its ancestors skip script and signature
+    /// verification but keep every other check. `None` verifies all blocks.
+    pub assume_valid: Option<[u8; 32]>,
+
+    /// Signature rule flags, see [`crate::validation::sig_cache`]
+    pub verify_flags: u32,
 }
 
 impl Default for BlockValidationConfig {
@@ -184,6 +220,8 @@ impl Default for BlockValidationConfig {
             validate_witness: true,
             validate_pow: true,
             max_validation_complexity: ValidationComplexityLimits::MAX_VALIDATION_OPS,
+            assume_valid: default_assume_valid(),
+            verify_flags: CONSENSUS_VERIFY_FLAGS,
         }
     }
 }
@@ -206,6 +244,13 @@ pub struct ValidationContext {
     pub network: NetworkType,
 }
 
+/// An input to verify and the output it spends
+struct InputCheck {
+    tx_index: usize,
+    input_index: usize,
+    prevout: TransactionOutput,
+}
+
 /// Block validator
 pub struct BlockValidator {
     /// Configuration
@@ -213,6 +258,15 @@ pub struct BlockValidator {
 
     /// Transaction validator
     transaction_validator: TransactionValidator,
+
+    /// Treasury holding green attestations and paying their bonuses
+    green_treasury: Option<Arc<EnvironmentalTreasury>>,
+
+    /// Network retarget rules, for the testnet min-difficulty exception
+    retarget_params: Option<RetargetParams>,
+
+    /// Signatures already verified, e.g. on mempool acceptance
+    signature_cache: Option<Arc<SignatureCache>>,
 }
 
 impl Default for BlockValidator {
@@ -227,6 +281,9 @@ impl BlockValidator {
         Self {
             config: BlockValidationConfig::default(),
             transaction_validator: TransactionValidator::new(),
+            green_treasury: None,
+            retarget_params: None,
+            signature_cache: None,
         }
     }
 
@@ -235,9 +292,33 @@ impl BlockValidator {
         Self {
             config,
             transaction_validator: TransactionValidator::new(),
+            green_treasury: None,
+            retarget_params: None,
+            signature_cache: None,
         }
     }
 
+    /// Check green bonus claims against `treasury`. Without one, blocks
+    /// claiming a bonus are rejected.
+    pub fn with_green_treasury(mut self, treasury: Arc<EnvironmentalTreasury>) -> Self {
+        self.green_treasury = Some(treasury);
+        self
+    }
+
+    /// Apply `params`' retarget rules; where they allow min-difficulty blocks,
+    /// a block more than four block times after its parent may be mined at
+    /// the `pow_limit` floor instead of the context's difficulty
+    pub fn with_retarget_params(mut self, params: RetargetParams) -> Self {
+        self.retarget_params = Some(params);
+        self
+    }
+
+    /// Skip verifying signatures `cache` holds and add the ones verified
+    pub fn with_signature_cache(mut self, cache: Arc<SignatureCache>) -> Self {
+        self.signature_cache = Some(cache);
+        self
+    }
+
     /// Calculate validation complexity for a block
     /// 
     /// SECURITY FIX (P1-005): Pre-calculates validation complexity to detect
@@ -283,32 +364,65 @@ impl BlockValidator {
         &self,
         block: &Block,
         context: &ValidationContext,
+    ) -> BlockValidationResult {
+        self.validate_with_context(block, context, &mut Tracer::disabled())
+    }
+
+    /// Validate a block with full context, recording every check performed.
+    /// On rejection the trace ends at the failing check.
+    pub fn validate_block_with_context_traced(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+    ) -> Result<ValidationTrace, Traced<BlockValidationError>> {
+        let mut trace = ValidationTrace::new();
+        match self.validate_with_context(block, context, &mut Tracer::new(&mut trace)) {
+            Ok(()) => Ok(trace),
+            Err(error) => Err(Traced { error, trace }),
+        }
+    }
+
+    fn validate_with_context(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+        tracer: &mut Tracer<'_>,
     ) -> BlockValidationResult {
         debug!("Validating block at height {}", block.height());
 
         // SECURITY CHECK: Pre-validate complexity BEFORE expensive operations
-        let complexity = self.calculate_validation_complexity(block);
-        if complexity > self.config.max_validation_complexity {
-            return Err(BlockValidationError::InvalidHeader(format!(
-                "Block validation complexity too high: {} > {} (max). Possible DoS attack!",
-                complexity,
-                self.config.max_validation_complexity
-            )));
-        }
-        
+        let complexity = tracer.check(
+            "complexity",
+            || format!("max={}", self.config.max_validation_complexity),
+            || {
+                let complexity = self.calculate_validation_complexity(block);
+                if complexity > self.config.max_validation_complexity {
+                    return Err(BlockValidationError::InvalidHeader(format!(
+                        "Block validation complexity too high: {} > {} (max). Possible DoS attack!",
+                        complexity,
+                        self.config.max_validation_complexity
+                    )));
+                }
+                Ok(complexity)
+            },
+        )?;
+
         debug!("Block complexity: {} operations", complexity);
 
         // Phase 1: Structure validation
-        self.validate_structure(block)?;
+        self.validate_structure(block, tracer)?;
 
         // Phase 2: Header validation
-        self.validate_header(block, context)?;
+        self.validate_header(block, context, tracer)?;
 
         // Phase 3: Transaction validation
-        self.validate_transactions(block, context)?;
+        self.validate_transactions(block, context, tracer)?;
+        self.validate_input_signatures(block, context, tracer)?;
 
         // Phase 4: Consensus rules
-        self.validate_consensus_rules(block, context)?;
+        tracer.check("consensus-rules", String::new, || {
+            self.validate_consensus_rules(block, context)
+        })?;
 
         debug!("Block validation successful (complexity: {})", complexity);
         Ok(())
@@ -316,139 +430,250 @@ impl BlockValidator {
 
     /// Validate a block (simplified, without full context)
     pub fn validate_block(&self, block: &Block) -> BlockValidationResult {
+        self.validate_basic(block, &mut Tracer::disabled())
+    }
+
+    /// Validate a block without chain context, recording every check
+    /// performed. On rejection the trace ends at the failing check.
+    pub fn validate_block_traced(
+        &self,
+        block: &Block,
+    ) -> Result<ValidationTrace, Traced<BlockValidationError>> {
+        let mut trace = ValidationTrace::new();
+        match self.validate_basic(block, &mut Tracer::new(&mut trace)) {
+            Ok(()) => Ok(trace),
+            Err(error) => Err(Traced { error, trace }),
+        }
+    }
+
+    fn validate_basic(&self, block: &Block, tracer: &mut Tracer<'_>) -> BlockValidationResult {
         // Basic validation without chain context
         debug!("Performing basic block validation");
 
         // SECURITY CHECK: Pre-validate complexity before expensive operations
-        let complexity = self.calculate_validation_complexity(block);
-        if complexity > self.config.max_validation_complexity {
-            return Err(BlockValidationError::InvalidHeader(format!(
-                "Block validation complexity too high: {} > {} (max). Rejecting potentially malicious block.",
-                complexity,
-                self.config.max_validation_complexity
-            )));
-        }
+        let complexity = tracer.check(
+            "complexity",
+            || format!("max={}", self.config.max_validation_complexity),
+            || {
+                let complexity = self.calculate_validation_complexity(block);
+                if complexity > self.config.max_validation_complexity {
+                    return Err(BlockValidationError::InvalidHeader(format!(
+                        "Block validation complexity too high: {} > {} (max). Rejecting potentially malicious block.",
+                        complexity,
+                        self.config.max_validation_complexity
+                    )));
+                }
+                Ok(complexity)
+            },
+        )?;
 
         // Structure validation
-        self.validate_structure(block)?;
+        self.validate_structure(block, tracer)?;
 
         // Basic header checks
-        self.validate_basic_header(block)?;
+        tracer.check(
+            "header.version",
+            || format!("version={} min={}", block.version(), self.config.min_block_version),
+            || self.validate_version(block),
+        )?;
+        tracer.check(
+            "header.future-time",
+            || format!("timestamp={}", block.timestamp()),
+            || self.validate_future_time(block),
+        )?;
 
         // Transaction structure validation
-        self.validate_transaction_structure(block)?;
+        tracer.check(
+            "transactions.coinbase-position",
+            || format!("transactions={}", block.transactions().len()),
+            || self.validate_transaction_structure(block),
+        )?;
 
         // Validate merkle root - CRITICAL: Must verify transaction integrity
-        self.validate_merkle_root(block)?;
+        tracer.check(
+            "header.merkle-root",
+            || format!("merkle_root={}", hex::encode(block.merkle_root())),
+            || self.validate_merkle_root(block),
+        )?;
+        self.validate_witness_commitment(block, tracer)?;
 
         debug!("Basic block validation successful (complexity: {})", complexity);
         Ok(())
     }
 
     /// Phase 1: Validate block structure
-    fn validate_structure(&self, block: &Block) -> BlockValidationResult {
+    fn validate_structure(&self, block: &Block, tracer: &mut Tracer<'_>) -> BlockValidationResult {
         // Check block size
-        let block_size = block.size();
-        if block_size > self.config.max_block_size {
-            return Err(BlockValidationError::BlockTooLarge(
-                block_size,
-                self.config.max_block_size,
-            ));
-        }
+        tracer.check(
+            "structure.size",
+            || format!("size={} max={}", block.size(), self.config.max_block_size),
+            || {
+                let block_size = block.size();
+                if block_size > self.config.max_block_size {
+                    return Err(BlockValidationError::BlockTooLarge(
+                        block_size,
+                        self.config.max_block_size,
+                    ));
+                }
+                Ok(())
+            },
+        )?;
 
         // Check block weight
-        let block_weight = self.calculate_block_weight(block);
-        if block_weight > self.config.max_block_weight {
-            return Err(BlockValidationError::WeightTooHigh(
-                block_weight,
-                self.config.max_block_weight,
-            ));
-        }
+        tracer.check(
+            "structure.weight",
+            || {
+                format!(
+                    "weight={} max={}",
+                    self.calculate_block_weight(block),
+                    self.config.max_block_weight
+                )
+            },
+            || {
+                let block_weight = self.calculate_block_weight(block);
+                if block_weight > self.config.max_block_weight {
+                    return Err(BlockValidationError::WeightTooHigh(
+                        block_weight,
+                        self.config.max_block_weight,
+                    ));
+                }
+                Ok(())
+            },
+        )?;
 
         // Must have at least one transaction (coinbase)
-        if block.transactions().is_empty() {
-            return Err(BlockValidationError::MissingCoinbase);
-        }
+        tracer.check(
+            "structure.non-empty",
+            || format!("transactions={}", block.transactions().len()),
+            || {
+                if block.transactions().is_empty() {
+                    return Err(BlockValidationError::MissingCoinbase);
+                }
+                Ok(())
+            },
+        )?;
 
         // Check for duplicate transactions
-        let mut tx_hashes = HashSet::new();
-        for tx in block.transactions() {
-            let tx_hash = tx.hash();
-            if !tx_hashes.insert(tx_hash) {
-                return Err(BlockValidationError::DuplicateTransaction(tx_hash));
+        tracer.check("structure.unique-txids", String::new, || {
+            let mut tx_hashes = HashSet::new();
+            for tx in block.transactions() {
+                let tx_hash = tx.hash();
+                if !tx_hashes.insert(tx_hash) {
+                    return Err(BlockValidationError::DuplicateTransaction(tx_hash));
+                }
             }
-        }
+            Ok(())
+        })?;
 
         Ok(())
     }
 
     /// Phase 2: Validate block header
-    fn validate_header(&self, block: &Block, context: &ValidationContext) -> BlockValidationResult {
+    fn validate_header(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+        tracer: &mut Tracer<'_>,
+    ) -> BlockValidationResult {
         // Check version
-        if block.version() < self.config.min_block_version {
-            return Err(BlockValidationError::InvalidVersion(block.version()));
-        }
+        tracer.check(
+            "header.version",
+            || format!("version={} min={}", block.version(), self.config.min_block_version),
+            || self.validate_version(block),
+        )?;
 
         // Check previous block hash
-        if block.prev_block_hash() != &context.prev_block_hash {
-            return Err(BlockValidationError::PrevBlockMismatch);
-        }
+        tracer.check(
+            "header.prev-hash",
+            || {
+                format!(
+                    "prev_hash={} expected={}",
+                    hex::encode(block.prev_block_hash()),
+                    hex::encode(context.prev_block_hash)
+                )
+            },
+            || {
+                if block.prev_block_hash() != &context.prev_block_hash {
+                    return Err(BlockValidationError::PrevBlockMismatch);
+                }
+                Ok(())
+            },
+        )?;
 
         // Check height
-        if block.height() != context.prev_block_height + 1 {
-            return Err(BlockValidationError::InvalidHeader(format!(
-                "Invalid height: expected {}, got {}",
-                context.prev_block_height + 1,
-                block.height()
-            )));
-        }
+        tracer.check(
+            "header.height",
+            || format!("height={} parent_height={}", block.height(), context.prev_block_height),
+            || {
+                if block.height() != context.prev_block_height + 1 {
+                    return Err(BlockValidationError::InvalidHeader(format!(
+                        "Invalid height: expected {}, got {}",
+                        context.prev_block_height + 1,
+                        block.height()
+                    )));
+                }
+                Ok(())
+            },
+        )?;
 
         // Validate timestamp
-        self.validate_timestamp(block, context)?;
+        tracer.check(
+            "header.median-time-past",
+            || format!("timestamp={} mtp={}", block.timestamp(), context.median_time_past),
+            || self.validate_median_time_past(block, context),
+        )?;
+        tracer.check(
+            "header.future-time",
+            || format!("timestamp={}", block.timestamp()),
+            || self.validate_future_time(block),
+        )?;
 
         // Validate proof-of-work if enabled
         if self.config.validate_pow {
-            self.validate_pow(block, context)?;
+            tracer.check(
+                "header.pow",
+                || {
+                    format!(
+                        "difficulty={:#010x} bits={:#010x} parent_timestamp={}",
+                        context.current_difficulty,
+                        block.header.bits(),
+                        context.prev_block_timestamp
+                    )
+                },
+                || self.validate_pow(block, context),
+            )?;
+        } else {
+            tracer.skip("header.pow", "proof-of-work validation disabled");
         }
 
         // Validate merkle root
-        self.validate_merkle_root(block)?;
+        tracer.check(
+            "header.merkle-root",
+            || format!("merkle_root={}", hex::encode(block.merkle_root())),
+            || self.validate_merkle_root(block),
+        )?;
+        self.validate_witness_commitment(block, tracer)?;
 
         Ok(())
     }
 
-    /// Basic header validation (without context)
-    fn validate_basic_header(&self, block: &Block) -> BlockValidationResult {
-        // Check version
+    /// Check the block version against the configured minimum
+    fn validate_version(&self, block: &Block) -> BlockValidationResult {
         if block.version() < self.config.min_block_version {
             return Err(BlockValidationError::InvalidVersion(block.version()));
         }
 
-        // Check timestamp is not too far in future
-        let current_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-
-        if block.timestamp() > current_time + self.config.max_future_time_offset {
-            return Err(BlockValidationError::TimestampTooFar(
-                block.timestamp(),
-                current_time + self.config.max_future_time_offset,
-            ));
-        }
-
         Ok(())
     }
 
-    /// Validate block timestamp
-    fn validate_timestamp(
+    /// Check that the block timestamp is after median time past
+    fn validate_median_time_past(
         &self,
         block: &Block,
         context: &ValidationContext,
     ) -> BlockValidationResult {
         let block_time = block.timestamp();
 
-        // Check median time past
         if block_time <= context.median_time_past {
             return Err(BlockValidationError::TimestampTooEarly(
                 block_time,
@@ -456,15 +681,19 @@ impl BlockValidator {
             ));
         }
 
-        // Check not too far in future
+        Ok(())
+    }
+
+    /// Check that the block timestamp is not too far in the future
+    fn validate_future_time(&self, block: &Block) -> BlockValidationResult {
         let current_time = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
 
-        if block_time > current_time + self.config.max_future_time_offset {
+        if block.timestamp() > current_time + self.config.max_future_time_offset {
             return Err(BlockValidationError::TimestampTooFar(
-                block_time,
+                block.timestamp(),
                 current_time + self.config.max_future_time_offset,
             ));
         }
@@ -487,7 +716,7 @@ impl BlockValidator {
         let block_hash = block.hash();
 
         // Calculate target from difficulty
-        let target = calculate_required_work(context.current_difficulty);
+        let target = calculate_required_work(self.pow_bits(block, context));
 
         // Hash must be less than target (using the standalone function from hash module)
         if !crate::hash::meets_difficulty(&block_hash, &target) {
@@ -497,6 +726,29 @@ impl BlockValidator {
         Ok(())
     }
 
+    /// Difficulty a block's proof-of-work is checked against: the context's,
+    /// unless the block claims the floor under the testnet stall exception
+    fn pow_bits(&self, block: &Block, context: &ValidationContext) -> u32 {
+        let Some(params) = &self.retarget_params else {
+            return context.current_difficulty;
+        };
+        let stalled = difficulty_retarget::min_difficulty_allowed(
+            block.height(),
+            context.prev_block_timestamp,
+            block.timestamp(),
+            params,
+        );
+        if stalled && block.header.bits() == params.pow_limit_bits {
+            debug!(
+                "Block at height {} mined at minimum difficulty {} seconds after its parent",
+                block.height(),
+                block.timestamp().saturating_sub(context.prev_block_timestamp)
+            );
+            return params.pow_limit_bits;
+        }
+        context.current_difficulty
+    }
+
     /// Validate merkle root
     fn validate_merkle_root(&self, block: &Block) -> BlockValidationResult {
         let calculated_root = block.calculate_merkle_root();
@@ -508,39 +760,94 @@ impl BlockValidator {
         Ok(())
     }
 
+    /// Check the coinbase's commitment to the block's wtxids
+    fn validate_witness_commitment(
+        &self,
+        block: &Block,
+        tracer: &mut Tracer<'_>,
+    ) -> BlockValidationResult {
+        if !self.config.validate_witness {
+            tracer.skip("header.witness-commitment", "witness validation disabled");
+            return Ok(());
+        }
+        tracer.check(
+            "header.witness-commitment",
+            || {
+                format!(
+                    "witness_root={}",
+                    hex::encode(block.calculate_witness_root())
+                )
+            },
+            || {
+                block
+                    .check_witness_commitment()
+                    .map_err(BlockValidationError::from)
+            },
+        )
+    }
+
     /// Phase 3: Validate all transactions
     fn validate_transactions(
         &self,
         block: &Block,
         context: &ValidationContext,
+        tracer: &mut Tracer<'_>,
     ) -> BlockValidationResult {
         let mut has_coinbase = false;
 
         for (index, tx) in block.transactions().iter().enumerate() {
+            let mut tracer = tracer.scoped(|| format!("tx[{}]", index));
             if index == 0 {
                 // First transaction must be coinbase
-                if !tx.is_coinbase() {
-                    return Err(BlockValidationError::MissingCoinbase);
-                }
+                tracer.check(
+                    "is-coinbase",
+                    || format!("txid={}", hex::encode(tx.hash())),
+                    || {
+                        if !tx.is_coinbase() {
+                            return Err(BlockValidationError::MissingCoinbase);
+                        }
+                        Ok(())
+                    },
+                )?;
                 has_coinbase = true;
 
                 // Validate coinbase specifics
-                self.validate_coinbase(tx, block, context)?;
+                self.validate_coinbase(tx, block, context, &mut tracer)?;
             } else {
                 // Non-coinbase transactions
-                if tx.is_coinbase() {
-                    return Err(BlockValidationError::MultipleCoinbase);
-                }
+                tracer.check(
+                    "not-coinbase",
+                    || format!("txid={}", hex::encode(tx.hash())),
+                    || {
+                        if tx.is_coinbase() {
+                            return Err(BlockValidationError::MultipleCoinbase);
+                        }
+                        Ok(())
+                    },
+                )?;
 
                 // Validate transaction
-                if let Err(e) = self.transaction_validator.validate(tx) {
+                if let Err(e) = self.transaction_validator.validate_with_tracer(tx, &mut tracer) {
                     return Err(BlockValidationError::InvalidTransaction(e.to_string()));
                 }
 
                 // Check coinbase maturity for inputs
-                if self.spends_immature_coinbase(tx, block.height(), context) {
-                    return Err(BlockValidationError::ImmatureCoinbaseSpend);
-                }
+                tracer.check(
+                    "coinbase-maturity",
+                    || {
+                        format!(
+                            "height={} maturity={}",
+                            block.height(),
+                            self.config.coinbase_maturity
+                        )
+                    },
+                    || {
+                        if self.spends_immature_coinbase(tx, block.height(), context) {
+                            return Err(BlockValidationError::ImmatureCoinbaseSpend);
+                        }
+                        Ok(())
+                    },
+                )?;
             }
         }
 
@@ -551,6 +858,196 @@ impl BlockValidator {
         Ok(())
     }
 
+    /// Phase 3b: Verify input signatures against the context's UTXO set
+    fn validate_input_signatures(
+        &self,
+        block: &Block,
+        context: &ValidationContext,
+        tracer: &mut Tracer<'_>,
+    ) -> BlockValidationResult {
+        if !self.config.validate_scripts {
+            tracer.skip("signatures", "script validation disabled");
+            return Ok(());
+        }
+        let Some(utxo_provider) = &context.utxo_provider else {
+            tracer.skip("signatures", "no UTXO provider");
+            return Ok(());
+        };
+
+        tracer.check(
+            "signatures",
+            || {
+                let inputs: usize = block.transactions()[1..]
+                    .iter()
+                    .map(|tx| tx.inputs().len())
+                    .sum();
+                format!("inputs={}", inputs)
+            },
+            || {
+                self.verify_signatures(block, |txid, vout| {
+                    bincode::deserialize(&utxo_provider(txid, vout)?).ok()
+                })
+            },
+        )
+    }
+
+    /// Verify every input signature in `block` against the output it spends.
+    ///
+    /// Spent outputs are resolved up front, from `get_output` or from earlier
+    /// transactions in the block, so no lookup runs while the checks are
+    /// spread across the rayon pool. Post-quantum transaction signatures are
+    /// verified together in one [`QuantumBatchVerifier`] pass first. The
+    /// failure reported is the first in block order, whichever check
+    /// finishes first.
+    pub fn verify_signatures(
+        &self,
+        block: &Block,
+        get_output: impl Fn(&[u8; 32], u32) -> Option<TransactionOutput>,
+    ) -> BlockValidationResult {
+        let transactions = block.transactions();
+        let mut created: HashMap<([u8; 32], u32), &TransactionOutput> = HashMap::new();
+        let mut checks = Vec::new();
+        for (tx_index, tx) in transactions.iter().enumerate() {
+            if !tx.is_coinbase() {
+                for (input_index, input) in tx.inputs().iter().enumerate() {
+                    let (txid, vout) = (input.prev_tx_hash(), input.prev_output_index());
+                    let prevout = match created.get(&(txid, vout)) {
+                        Some(output) => (*output).clone(),
+                        None => {
+                            get_output(&txid, vout).ok_or(BlockValidationError::MissingPrevout {
+                                tx_index,
+                                input_index,
+                            })?
+                        }
+                    };
+                    checks.push(InputCheck {
+                        tx_index,
+                        input_index,
+                        prevout,
+                    });
+                }
+            }
+            let txid = tx.hash();
+            for (vout, output) in tx.outputs().iter().enumerate() {
+                created.insert((txid, vout as u32), output);
+            }
+        }
+
+        let sighashes: Vec<[u8; 32]> = transactions
+            .par_iter()
+            .map(Transaction::signature_hash)
+            .collect();
+        let mut signed = vec![false; transactions.len()];
+        for check in &checks {
+            let tx = &transactions[check.tx_index];
+            signed[check.tx_index] |= signed_by_transaction(tx, check.input_index, &check.prevout);
+        }
+        // Inputs sharing a transaction-level signature verify it once
+        let tx_signatures: Vec<OnceLock<Result<(), String>>> = self
+            .verify_signature_batch(transactions, &sighashes, &signed)
+            .into_iter()
+            .map(|batched| batched.map_or_else(OnceLock::new, OnceLock::from))
+            .collect();
+
+        let failure = checks.par_iter().find_map_first(|check| {
+            let tx = &transactions[check.tx_index];
+            let signature = if signed_by_transaction(tx, check.input_index, &check.prevout) {
+                tx_signatures[check.tx_index]
+                    .get_or_init(|| {
+                        let flags = self.config.verify_flags;
+                        match &self.signature_cache {
+                            Some(cache) => cache.verify_signature(tx, flags),
+                            None => tx.verify_signature_with_policy(hybrid_policy(flags)),
+                        }
+                        .map_err(|e| e.to_string())
+                    })
+                    .clone()
+            } else {
+                Ok(())
+            };
+            let result = signature.and_then(|()| {
+                verify_input(
+                    tx,
+                    check.input_index,
+                    &check.prevout,
+                    &sighashes[check.tx_index],
+                )
+                .map_err(|e| e.to_string())
+            });
+            result
+                .err()
+                .map(|reason| BlockValidationError::InputSignature {
+                    tx_index: check.tx_index,
+                    input_index: check.input_index,
+                    reason,
+                })
+        });
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Verify the post-quantum signatures of the transactions marked
+    /// `signed` in one batch, skipping those already in the signature cache.
+    /// Returns each transaction's result, or `None` where it is left to be
+    /// verified alone. A signature failing the batch is verified again alone
+    /// only to report why.
+    fn verify_signature_batch(
+        &self,
+        transactions: &[Transaction],
+        sighashes: &[[u8; 32]],
+        signed: &[bool],
+    ) -> Vec<Option<Result<(), String>>> {
+        let flags = self.config.verify_flags;
+        let mut results = vec![None; transactions.len()];
+        let mut batch = QuantumBatchVerifier::new();
+        let mut queued = Vec::new();
+        for (tx_index, tx) in transactions.iter().enumerate() {
+            let Some(signature) = tx.signature_data().filter(|_| signed[tx_index]) else {
+                continue;
+            };
+            let scheme = match signature.scheme {
+                SignatureSchemeType::Dilithium => QuantumScheme::Dilithium,
+                SignatureSchemeType::Falcon => QuantumScheme::Falcon,
+                SignatureSchemeType::SphincsPlus => QuantumScheme::SphincsPlus,
+                _ => continue,
+            };
+            let key = SignatureCache::key(&sighashes[tx_index], signature, flags);
+            if let Some(cache) = &self.signature_cache {
+                if cache.contains(&key) {
+                    results[tx_index] = Some(Ok(()));
+                    continue;
+                }
+            }
+            batch.push(
+                &sighashes[tx_index],
+                &signature.data,
+                &signature.public_key,
+                QuantumParameters {
+                    scheme,
+                    security_level: signature.security_level,
+                },
+            );
+            queued.push((tx_index, key));
+        }
+
+        let verified = batch.verify();
+        for (entry, (tx_index, key)) in queued.into_iter().enumerate() {
+            let result = if verified.is_valid(entry) {
+                if let Some(cache) = &self.signature_cache {
+                    cache.insert(key);
+                }
+                Ok(())
+            } else {
+                let reason = transactions[tx_index]
+                    .verify_signature_with_policy(hybrid_policy(flags))
+                    .err()
+                    .unwrap_or(TransactionError::SignatureVerificationFailed);
+                Err(reason.to_string())
+            };
+            results[tx_index] = Some(result);
+        }
+        results
+    }
+
     /// Basic transaction structure validation
     fn validate_transaction_structure(&self, block: &Block) -> BlockValidationResult {
         for (index, tx) in block.transactions().iter().enumerate() {
@@ -572,28 +1069,96 @@ impl BlockValidator {
         coinbase: &Transaction,
         block: &Block,
         context: &ValidationContext,
+        tracer: &mut Tracer<'_>,
     ) -> BlockValidationResult {
         // Calculate expected subsidy
         let expected_subsidy = self.calculate_block_subsidy(block.height());
 
-        // Calculate actual subsidy (outputs - inputs, but coinbase has no real inputs)
+        let claim = tracer.check(
+            "coinbase.green-bonus-claim",
+            || format!("outputs={}", coinbase.outputs().len()),
+            || GreenBonusClaim::from_coinbase(coinbase).map_err(BlockValidationError::from),
+        )?;
+        let bonus_index = claim.as_ref().map(|claim| claim.bonus_index);
+
+        // Calculate actual subsidy (outputs - inputs, but coinbase has no real inputs).
+        // A green bonus is paid from the treasury, not the subsidy.
         let actual_subsidy = coinbase
             .outputs()
             .iter()
-            .map(|out| out.value())
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != bonus_index)
+            .map(|(_, out)| out.value())
             .sum::<u64>();
 
         // For now, just check it doesn't exceed maximum
         // In full implementation, would need to account for fees
-        if actual_subsidy > expected_subsidy {
-            return Err(BlockValidationError::InvalidSubsidy(
-                expected_subsidy,
-                actual_subsidy,
-            ));
+        tracer.check(
+            "coinbase.subsidy",
+            || format!("outputs={} subsidy={}", actual_subsidy, expected_subsidy),
+            || {
+                if actual_subsidy > expected_subsidy {
+                    return Err(BlockValidationError::InvalidSubsidy(
+                        expected_subsidy,
+                        actual_subsidy,
+                    ));
+                }
+                Ok(())
+            },
+        )?;
+
+        tracer.check(
+            "coinbase.treasury",
+            || {
+                let script = coinbase
+                    .outputs()
+                    .get(1)
+                    .map(|out| redact_bytes(&out.pub_key_script))
+                    .unwrap_or_default();
+                format!("network={:?} treasury_script={}", context.network, script)
+            },
+            || self.validate_coinbase_treasury(coinbase, context.network, bonus_index),
+        )?;
+
+        match claim {
+            Some(claim) => tracer.check(
+                "coinbase.green-bonus",
+                || {
+                    format!(
+                        "attestation={} bonus={}",
+                        hex::encode(claim.attestation_id),
+                        claim.amount
+                    )
+                },
+                || self.validate_green_bonus(coinbase, block, &claim),
+            )?,
+            None => tracer.skip("coinbase.green-bonus", "no bonus claimed"),
         }
 
-        self.validate_coinbase_treasury(coinbase, context.network)?;
+        Ok(())
+    }
 
+    /// Check a green bonus claim against its attestation and the treasury
+    /// balance: the signature, the validity window, the per-block and
+    /// lifetime caps and the funds available
+    fn validate_green_bonus(
+        &self,
+        coinbase: &Transaction,
+        block: &Block,
+        claim: &GreenBonusClaim,
+    ) -> BlockValidationResult {
+        let treasury = self
+            .green_treasury
+            .as_ref()
+            .ok_or(GreenBonusError::NoRegistry)?;
+        let payout = &coinbase.outputs()[claim.bonus_index];
+        // The bonus is a share of the subsidy the miner computes it from
+        treasury.check_green_bonus(
+            claim,
+            &payout.pub_key_script,
+            block.timestamp(),
+            crate::types::block_subsidy(block.height()),
+        )?;
         Ok(())
     }
 
@@ -603,11 +1168,12 @@ impl BlockValidator {
     ///
     /// The total-output denominator is taken from the coinbase itself so
     /// the rule is self-consistent regardless of how the miner computes
-    /// fees or environmental bonuses.
+    /// fees. A green bonus output, at `bonus_index`, is left out of it.
     fn validate_coinbase_treasury(
         &self,
         coinbase: &Transaction,
         network: NetworkType,
+        bonus_index: Option<usize>,
     ) -> BlockValidationResult {
         let outputs = coinbase.outputs();
         if outputs.is_empty() {
@@ -616,7 +1182,12 @@ impl BlockValidator {
             ));
         }
 
-        let total: u64 = outputs.iter().map(|o| o.value()).sum();
+        let total: u64 = outputs
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| Some(*index) != bonus_index)
+            .map(|(_, o)| o.value())
+            .sum();
 
         // Rounding note: construction uses floor(total * pct / 100); the
         // validator must use the same rounding mode so `miner == validator`.
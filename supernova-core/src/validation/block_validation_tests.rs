@@ -6,12 +6,23 @@
 #[cfg(test)]
 mod tests {
     use crate::config::NetworkType;
+    use crate::consensus::difficulty::calculate_required_work;
+    use crate::consensus::difficulty_retarget::RetargetParams;
+    use crate::consensus::witness::{self, WitnessCommitmentError};
+    use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use crate::environmental::attestation::{GreenAttestation, GreenBonusError};
+    use crate::environmental::treasury::EnvironmentalTreasury;
     use crate::governance::{treasury_script_pubkey, TREASURY_ALLOCATION_PERCENT};
     use crate::types::block::{Block, BlockHeader};
-    use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+    use crate::types::transaction::{
+        pubkey_commitment, SignatureSchemeType, Transaction, TransactionInput, TransactionOutput,
+        TransactionSignatureData,
+    };
     use crate::validation::block::{
         BlockValidationConfig, BlockValidationError, BlockValidator, ValidationContext,
     };
+    use std::collections::HashMap;
+    use std::sync::Arc;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     /// Reward outputs that satisfy the consensus treasury rule:
@@ -502,4 +513,480 @@ mod tests {
             e => panic!("Unexpected error type: {:?}", e),
         }
     }
+
+    #[test]
+    fn test_trace_ends_at_failing_check() {
+        let validator = BlockValidator::new();
+        let prev_hash = [1; 32];
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let context = create_test_context(0, prev_hash, current_time - 600);
+        let mut block = create_test_block(1, prev_hash, current_time, 1);
+
+        // A valid block yields a complete trace with no failure
+        let trace = validator
+            .validate_block_with_context_traced(&block, &context)
+            .expect("valid block");
+        assert!(trace.failure().is_none());
+        assert!(trace.steps().iter().any(|s| s.check == "tx[0].coinbase.treasury"));
+        assert_eq!(trace.steps().last().unwrap().check, "consensus-rules");
+
+        // Corrupt the merkle root: every header check before it passes
+        block.header.merkle_root = [0xAA; 32];
+        let traced = validator
+            .validate_block_with_context_traced(&block, &context)
+            .unwrap_err();
+        assert!(matches!(traced.error, BlockValidationError::InvalidMerkleRoot));
+
+        let steps = traced.trace.steps();
+        let (last, earlier) = steps.split_last().unwrap();
+        assert_eq!(last.check, "header.merkle-root");
+        assert!(last.failed());
+        assert_eq!(traced.trace.failure(), Some(last));
+        assert!(earlier.iter().all(|s| s.passed()), "{:?}", earlier);
+        let checks: Vec<_> = earlier.iter().map(|s| s.check.as_str()).collect();
+        assert_eq!(
+            checks,
+            [
+                "complexity",
+                "structure.size",
+                "structure.weight",
+                "structure.non-empty",
+                "structure.unique-txids",
+                "header.version",
+                "header.prev-hash",
+                "header.height",
+                "header.median-time-past",
+                "header.future-time",
+                "header.pow",
+            ]
+        );
+
+        // Tracing does not change the verdict
+        assert!(matches!(
+            validator.validate_block_with_context(&block, &context),
+            Err(BlockValidationError::InvalidMerkleRoot)
+        ));
+    }
+
+    /// A treasury funded with 2% of `fees` and holding one signed
+    /// attestation for a 10% bonus that expires at `expires_at`
+    fn green_treasury(
+        fees: u64,
+        expires_at: u64,
+    ) -> (Arc<EnvironmentalTreasury>, GreenAttestation) {
+        let keypair =
+            QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let treasury = Arc::new(EnvironmentalTreasury::default());
+        treasury.process_transaction_fees(fees).unwrap();
+        treasury
+            .register_attestor(
+                "rec-auditor",
+                keypair.public_key.clone(),
+                keypair.parameters,
+            )
+            .unwrap();
+
+        let mut attestation = GreenAttestation {
+            attestor: "rec-auditor".to_string(),
+            payout_script: vec![0x51; 25],
+            bonus_bps: 1_000,
+            valid_from: 0,
+            expires_at,
+            max_total_bonus: u64::MAX,
+            signature: Vec::new(),
+        };
+        attestation.sign(&keypair).unwrap();
+        treasury.register_attestation(attestation.clone()).unwrap();
+        (treasury, attestation)
+    }
+
+    /// A block at height 1 whose coinbase claims `bonus` under `attestation`
+    fn create_bonus_block(
+        prev_hash: [u8; 32],
+        timestamp: u64,
+        attestation: &GreenAttestation,
+        bonus: u64,
+    ) -> Block {
+        let mut block = create_test_block(1, prev_hash, timestamp, 1);
+        let coinbase = &block.transactions[0];
+        let mut outputs = coinbase.outputs().to_vec();
+        outputs.extend(attestation.bonus_outputs(bonus));
+        block.transactions[0] =
+            Transaction::new(coinbase.version(), coinbase.inputs().to_vec(), outputs, 0);
+        block.header.merkle_root = block.calculate_merkle_root();
+        block
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    #[test]
+    fn test_green_bonus_is_paid_from_the_treasury() {
+        let bonus = crate::types::block_subsidy(1) / 10;
+        let (treasury, attestation) = green_treasury(bonus * 50, u64::MAX);
+        assert_eq!(treasury.get_balance(None), bonus);
+        let validator = BlockValidator::new().with_green_treasury(Arc::clone(&treasury));
+        let prev_hash = [1; 32];
+        let context = create_test_context(0, prev_hash, now() - 600);
+
+        let block = create_bonus_block(prev_hash, now(), &attestation, bonus);
+        let trace = validator
+            .validate_block_with_context_traced(&block, &context)
+            .expect("bonus block");
+        assert!(trace
+            .steps()
+            .iter()
+            .any(|s| s.check == "tx[0].coinbase.green-bonus"));
+
+        // Without the registry the claim cannot be checked
+        assert!(matches!(
+            BlockValidator::new().validate_block_with_context(&block, &context),
+            Err(BlockValidationError::InvalidGreenBonus(
+                GreenBonusError::NoRegistry
+            ))
+        ));
+
+        // More than the attested share of the subsidy
+        let greedy = create_bonus_block(prev_hash, now(), &attestation, bonus + 1);
+        assert!(matches!(
+            validator.validate_block_with_context(&greedy, &context),
+            Err(BlockValidationError::InvalidGreenBonus(
+                GreenBonusError::OverClaimed { .. }
+            ))
+        ));
+
+        // Paying the bonus draws the treasury down
+        let claim = crate::environmental::GreenBonusClaim::from_coinbase(&block.transactions[0])
+            .unwrap()
+            .unwrap();
+        treasury.pay_green_bonus(&claim).unwrap();
+        assert_eq!(treasury.get_balance(None), 0);
+        assert_eq!(
+            treasury.get_attestation(&attestation.id()).unwrap().paid,
+            bonus
+        );
+    }
+
+    #[test]
+    fn test_green_bonus_with_expired_attestation_is_rejected() {
+        let bonus = crate::types::block_subsidy(1) / 10;
+        let expires_at = now() - 60;
+        let (treasury, attestation) = green_treasury(bonus * 50, expires_at);
+        let validator = BlockValidator::new().with_green_treasury(treasury);
+        let prev_hash = [1; 32];
+        let context = create_test_context(0, prev_hash, now() - 600);
+
+        let block = create_bonus_block(prev_hash, now(), &attestation, bonus);
+        match validator.validate_block_with_context(&block, &context) {
+            Err(BlockValidationError::InvalidGreenBonus(GreenBonusError::Expired {
+                expires_at: at,
+                timestamp,
+            })) => {
+                assert_eq!(at, expires_at);
+                assert_eq!(timestamp, block.timestamp());
+            }
+            other => panic!("Expected an expired attestation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_green_bonus_rejected_when_treasury_exhausted() {
+        let bonus = crate::types::block_subsidy(1) / 10;
+        // Enough for 40% of the bonus
+        let (treasury, attestation) = green_treasury(bonus * 20, u64::MAX);
+        let validator = BlockValidator::new().with_green_treasury(treasury);
+        let prev_hash = [1; 32];
+        let context = create_test_context(0, prev_hash, now() - 600);
+
+        let block = create_bonus_block(prev_hash, now(), &attestation, bonus);
+        match validator.validate_block_with_context(&block, &context) {
+            Err(BlockValidationError::InvalidGreenBonus(GreenBonusError::TreasuryExhausted {
+                required,
+                available,
+            })) => assert_eq!((required, available), (bonus, bonus * 2 / 5)),
+            other => panic!("Expected an exhausted treasury, got {:?}", other),
+        }
+    }
+
+    /// Testnet rules with the regtest floor, so a floor block mines instantly
+    fn stall_params() -> RetargetParams {
+        RetargetParams {
+            pow_limit_bits: 0x207f_ffff,
+            ..RetargetParams::testnet()
+        }
+    }
+
+    /// A block at the floor difficulty, mined `gap` seconds after its parent
+    fn create_floor_block(prev_hash: [u8; 32], parent_timestamp: u64, gap: u64) -> Block {
+        let mut block = create_test_block(1, prev_hash, parent_timestamp + gap, 1);
+        block.header.bits = 0x207f_ffff;
+        // Nonce 0 skips the proof-of-work check in tests
+        block.header.set_nonce(1);
+        let target = calculate_required_work(0x207f_ffff);
+        while !crate::hash::meets_difficulty(&block.hash(), &target) {
+            block.header.increment_nonce();
+        }
+        block
+    }
+
+    #[test]
+    fn test_min_difficulty_block_accepted_after_stall() {
+        let params = stall_params();
+        let validator = BlockValidator::new().with_retarget_params(params);
+        let prev_hash = [1; 32];
+        let parent_timestamp = now() - 5 * params.target_block_time;
+        let context = create_test_context(0, prev_hash, parent_timestamp);
+
+        let block = create_floor_block(
+            prev_hash,
+            parent_timestamp,
+            4 * params.target_block_time + 1,
+        );
+        assert!(validator
+            .validate_block_with_context(&block, &context)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_min_difficulty_block_rejected_without_stall() {
+        let params = stall_params();
+        let validator = BlockValidator::new().with_retarget_params(params);
+        let prev_hash = [1; 32];
+        let parent_timestamp = now() - 5 * params.target_block_time;
+        let context = create_test_context(0, prev_hash, parent_timestamp);
+
+        // Exactly four block times is not yet a stall: the block must meet
+        // the context's difficulty
+        let block = create_floor_block(prev_hash, parent_timestamp, 4 * params.target_block_time);
+        assert!(matches!(
+            validator.validate_block_with_context(&block, &context),
+            Err(BlockValidationError::InvalidPoW)
+        ));
+    }
+
+    #[test]
+    fn test_mainnet_params_never_accept_min_difficulty_blocks() {
+        let params = RetargetParams {
+            pow_limit_bits: 0x207f_ffff,
+            ..RetargetParams::mainnet()
+        };
+        let prev_hash = [1; 32];
+        let parent_timestamp = now() - 100 * params.target_block_time;
+        let context = create_test_context(0, prev_hash, parent_timestamp);
+        let block = create_floor_block(prev_hash, parent_timestamp, 99 * params.target_block_time);
+
+        for validator in [
+            BlockValidator::new(),
+            BlockValidator::new().with_retarget_params(params),
+        ] {
+            assert!(matches!(
+                validator.validate_block_with_context(&block, &context),
+                Err(BlockValidationError::InvalidPoW)
+            ));
+        }
+    }
+
+    fn keypair() -> QuantumKeyPair {
+        QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap()
+    }
+
+    /// A transaction spending `(prev_txid, 0)`, signed by `signer`
+    fn signed_spend(prev_txid: [u8; 32], signer: &QuantumKeyPair) -> Transaction {
+        let mut tx = Transaction::new(
+            2,
+            vec![TransactionInput::new(prev_txid, 0, vec![], 0xffff_ffff)],
+            vec![TransactionOutput::new(
+                900_000,
+                pubkey_commitment(&signer.public_key),
+            )],
+            0,
+        );
+        let signature = signer.sign(&tx.signature_hash()).unwrap();
+        tx.set_signature_data(TransactionSignatureData {
+            scheme: SignatureSchemeType::Dilithium,
+            security_level: signer.parameters.security_level,
+            data: signature,
+            public_key: signer.public_key.clone(),
+        });
+        tx
+    }
+
+    /// UTXOs `([n; 32], 0)` for `n` in `1..=count`, all owned by `owner`
+    fn owned_outputs(
+        owner: &QuantumKeyPair,
+        count: u8,
+    ) -> HashMap<([u8; 32], u32), TransactionOutput> {
+        let output = TransactionOutput::new(1_000_000, pubkey_commitment(&owner.public_key));
+        (1..=count)
+            .map(|n| (([n; 32], 0), output.clone()))
+            .collect()
+    }
+
+    fn create_spend_block(spends: Vec<Transaction>) -> Block {
+        let mut block = create_test_block(1, [1; 32], now(), 1);
+        block.transactions.extend(spends);
+        witness::commit(&mut block.transactions, block.height());
+        block.header.merkle_root = block.calculate_merkle_root();
+        block
+    }
+
+    fn context_with_utxos(utxos: HashMap<([u8; 32], u32), TransactionOutput>) -> ValidationContext {
+        let mut context = create_test_context(0, [1; 32], now() - 600);
+        context.utxo_provider = Some(Box::new(move |txid, vout| {
+            utxos
+                .get(&(*txid, vout))
+                .map(|output| bincode::serialize(output).unwrap())
+        }));
+        context
+    }
+
+    #[test]
+    fn test_input_signatures_are_verified_against_the_utxo_set() {
+        let owner = keypair();
+        let validator = BlockValidator::new();
+
+        // The last transaction spends an output created earlier in the block
+        let mut spends: Vec<Transaction> = (1..=4).map(|n| signed_spend([n; 32], &owner)).collect();
+        spends.push(signed_spend(spends[3].hash(), &owner));
+        let block = create_spend_block(spends.clone());
+        let context = context_with_utxos(owned_outputs(&owner, 4));
+        assert!(validator
+            .validate_block_with_context(&block, &context)
+            .is_ok());
+
+        spends[2] = signed_spend([3; 32], &keypair());
+        let block = create_spend_block(spends.clone());
+        assert!(matches!(
+            validator.validate_block_with_context(&block, &context),
+            Err(BlockValidationError::InputSignature {
+                tx_index: 3,
+                input_index: 0,
+                ..
+            })
+        ));
+
+        spends[2] = signed_spend([9; 32], &owner);
+        let block = create_spend_block(spends);
+        assert!(matches!(
+            validator.validate_block_with_context(&block, &context),
+            Err(BlockValidationError::MissingPrevout {
+                tx_index: 3,
+                input_index: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn test_first_failing_input_is_reported_in_block_order() {
+        let owner = keypair();
+        let attacker = keypair();
+        let spends = (1..=16)
+            .map(|n| {
+                let signer = if n == 5 || n == 12 { &attacker } else { &owner };
+                signed_spend([n; 32], signer)
+            })
+            .collect();
+        let block = create_spend_block(spends);
+        let utxos = owned_outputs(&owner, 16);
+
+        let validator = BlockValidator::new();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        for _ in 0..20 {
+            let result = pool.install(|| {
+                validator.verify_signatures(&block, |txid, vout| utxos.get(&(*txid, vout)).cloned())
+            });
+            assert!(matches!(
+                result,
+                Err(BlockValidationError::InputSignature { tx_index: 5, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_a_corrupted_signature_in_the_batch_is_pinned_to_its_input() {
+        let owner = keypair();
+        let mut spends: Vec<Transaction> =
+            (1..=12).map(|n| signed_spend([n; 32], &owner)).collect();
+        let mut signature = spends[6].signature_data().unwrap().clone();
+        signature.data[100] ^= 0x01;
+        spends[6].set_signature_data(signature);
+        let block = create_spend_block(spends);
+        let utxos = owned_outputs(&owner, 12);
+
+        let result = BlockValidator::new()
+            .verify_signatures(&block, |txid, vout| utxos.get(&(*txid, vout)).cloned());
+        match result {
+            Err(BlockValidationError::InputSignature {
+                tx_index: 7,
+                input_index: 0,
+                reason,
+            }) => assert!(reason.contains("verification failed"), "{}", reason),
+            other => panic!("expected input 7:0 to fail, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_batched_signatures_fill_the_signature_cache() {
+        use crate::validation::SignatureCache;
+
+        let owner = keypair();
+        let spends = (1..=8).map(|n| signed_spend([n; 32], &owner)).collect();
+        let block = create_spend_block(spends);
+        let utxos = owned_outputs(&owner, 8);
+        let cache = Arc::new(SignatureCache::default());
+        let validator = BlockValidator::new().with_signature_cache(Arc::clone(&cache));
+        let get_output = |txid: &[u8; 32], vout: u32| utxos.get(&(*txid, vout)).cloned();
+
+        assert!(validator.verify_signatures(&block, get_output).is_ok());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (0, 8, 8));
+
+        assert!(validator.verify_signatures(&block, get_output).is_ok());
+        assert_eq!(cache.stats().hits, 8);
+    }
+
+    #[test]
+    fn test_witness_commitment_is_validated() {
+        let owner = keypair();
+        let spends: Vec<Transaction> = (1..=2).map(|n| signed_spend([n; 32], &owner)).collect();
+        let context = context_with_utxos(owned_outputs(&owner, 2));
+        let validator = BlockValidator::new();
+        let block = create_spend_block(spends.clone());
+        assert!(validator
+            .validate_block_with_context(&block, &context)
+            .is_ok());
+
+        // A witness added in flight keeps the txid and so the merkle root,
+        // but no longer matches the committed wtxids
+        let mut malleated = block.clone();
+        malleated.transactions[2].inputs_mut()[0].set_witness(vec![vec![0x01; 8]]);
+        assert_eq!(malleated.calculate_merkle_root(), block.calculate_merkle_root());
+        assert!(matches!(
+            validator.validate_block_with_context(&malleated, &context),
+            Err(BlockValidationError::InvalidWitnessCommitment(
+                WitnessCommitmentError::Mismatch { .. }
+            ))
+        ));
+
+        // Signed transactions are witness data, so the coinbase must commit
+        let mut uncommitted = create_test_block(1, [1; 32], now(), 1);
+        uncommitted.transactions.extend(spends);
+        uncommitted.header.merkle_root = uncommitted.calculate_merkle_root();
+        assert!(matches!(
+            validator.validate_block(&uncommitted),
+            Err(BlockValidationError::InvalidWitnessCommitment(
+                WitnessCommitmentError::Missing
+            ))
+        ));
+    }
 }
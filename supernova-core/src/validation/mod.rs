@@ -8,6 +8,8 @@ use std::fmt;
 
 pub mod block;
 pub mod crypto;
+pub mod sig_cache;
+pub mod trace;
 pub mod transaction;
 pub mod unified_validation;
 
@@ -205,6 +207,13 @@ pub use block::{
     ValidationContext,
 };
 
+pub use sig_cache::{
+    hybrid_policy, SignatureCache, SignatureCacheStats, CONSENSUS_VERIFY_FLAGS,
+    VERIFY_HYBRID_EITHER,
+};
+
+pub use trace::{TraceOutcome, TraceStep, Traced, Tracer, ValidationTrace};
+
 // Convenience functions for validation
 use crate::types::transaction::Transaction;
 use crate::types::Block;
@@ -0,0 +1,244 @@
+//! Cache of successful signature verifications
+//!
+//! A transaction's signature is verified when it enters the mempool and again
+//! when a block confirming it is connected. Both paths share one
+//! [`SignatureCache`], so the second verification of an unchanged signature
+//! is a lookup. Only successes are stored, and nothing is ever invalidated: a
+//! signature that verified over a sighash under a set of flags always will.
+//!
+//! Keys are a SHA-256 digest of everything the verification depends on, so a
+//! multi-kilobyte ML-DSA signature costs the same [`ENTRY_BYTES`] as any
+//! other.
+
+use crate::crypto::hybrid::HybridPolicy;
+use crate::types::transaction::{
+    Transaction, TransactionError, TransactionOutput, TransactionSignatureData,
+};
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Default memory budget of a cache
+pub const DEFAULT_SIG_CACHE_BYTES: usize = 32 << 20;
+
+/// Flags for the current consensus signature rules. A rule change gets a new
+/// flag so results verified under the old rules are not reused.
+pub const CONSENSUS_VERIFY_FLAGS: u32 = 0;
+
+/// Accept a hybrid signature when either half verifies. For the migration
+/// period to hybrid keys only; by default both halves must verify.
+pub const VERIFY_HYBRID_EITHER: u32 = 1 << 0;
+
+/// How hybrid signatures are checked under `flags`
+pub fn hybrid_policy(flags: u32) -> HybridPolicy {
+    if flags & VERIFY_HYBRID_EITHER != 0 {
+        HybridPolicy::EitherPasses
+    } else {
+        HybridPolicy::RequireBoth
+    }
+}
+
+/// Memory one entry takes: the 32-byte key and two list pointers in its LRU
+/// node, plus the key pointer, node pointer and control byte of its hash
+/// table slot. Hash table slack is not counted.
+pub const ENTRY_BYTES: usize = 32 + 4 * size_of::<usize>() + 1;
+
+const KEY_DOMAIN: &[u8] = b"supernova-sigcache-v1";
+
+/// Counters and occupancy of a [`SignatureCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SignatureCacheStats {
+    /// Verifications answered from the cache
+    pub hits: u64,
+    /// Verifications that had to run
+    pub misses: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_bytes: usize,
+}
+
+/// Bounded LRU set of signatures known to verify
+pub struct SignatureCache {
+    entries: Mutex<LruCache<[u8; 32], ()>>,
+    max_bytes: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for SignatureCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIG_CACHE_BYTES)
+    }
+}
+
+impl SignatureCache {
+    /// A cache using at most `max_bytes`; 0 caches nothing
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::unbounded()),
+            max_bytes,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Key for `signature` over `sighash` verified under `flags`
+    pub fn key(sighash: &[u8; 32], signature: &TransactionSignatureData, flags: u32) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(KEY_DOMAIN);
+        hasher.update(sighash);
+        hasher.update(flags.to_le_bytes());
+        hasher.update([signature.scheme as u8, signature.security_level]);
+        for field in [&signature.public_key, &signature.data] {
+            hasher.update((field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Whether `key` verified before, counting a hit or a miss
+    pub fn contains(&self, key: &[u8; 32]) -> bool {
+        let found = self.lock().get(key).is_some();
+        let counter = if found { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// Record that `key` verified, evicting the least recently used entries
+    /// beyond the budget
+    pub fn insert(&self, key: [u8; 32]) {
+        let capacity = self.max_bytes / ENTRY_BYTES;
+        if capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        entries.put(key, ());
+        while entries.len() > capacity {
+            entries.pop_lru();
+        }
+    }
+
+    /// [`Transaction::verify_signature_with_policy`] under `flags`, skipped
+    /// when the signature already verified under them
+    pub fn verify_signature(&self, tx: &Transaction, flags: u32) -> Result<(), TransactionError> {
+        let policy = hybrid_policy(flags);
+        let signature = match tx.signature_data() {
+            Some(signature) if !tx.is_coinbase() => signature,
+            _ => return tx.verify_signature_with_policy(policy),
+        };
+        let key = Self::key(&tx.signature_hash(), signature, flags);
+        if self.contains(&key) {
+            return Ok(());
+        }
+        tx.verify_signature_with_policy(policy)?;
+        self.insert(key);
+        Ok(())
+    }
+
+    /// [`Transaction::verify_authorization`] with the signature check
+    /// answered from the cache where possible
+    pub fn verify_authorization(
+        &self,
+        tx: &Transaction,
+        flags: u32,
+        get_prevout: impl Fn(&[u8; 32], u32) -> Option<TransactionOutput>,
+    ) -> Result<(), TransactionError> {
+        self.verify_signature(tx, flags)?;
+        tx.verify_key_binding(get_prevout)
+    }
+
+    pub fn stats(&self) -> SignatureCacheStats {
+        let entries = self.lock().len();
+        SignatureCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries,
+            bytes: entries * ENTRY_BYTES,
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<[u8; 32], ()>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use crate::types::transaction::{SignatureSchemeType, TransactionInput};
+
+    fn signed(keypair: &QuantumKeyPair, tag: u8) -> Transaction {
+        let mut tx = Transaction::new(
+            2,
+            vec![TransactionInput::new([tag; 32], 0, vec![], 0xffff_ffff)],
+            vec![TransactionOutput::new(1_000, vec![0xab; 32])],
+            0,
+        );
+        let data = keypair.sign(&tx.signature_hash()).unwrap();
+        tx.set_signature_data(TransactionSignatureData {
+            scheme: SignatureSchemeType::Dilithium,
+            security_level: keypair.parameters.security_level,
+            data,
+            public_key: keypair.public_key.clone(),
+        });
+        tx
+    }
+
+    #[test]
+    fn only_successes_are_cached_under_their_flags() {
+        let keypair =
+            QuantumKeyPair::generate(QuantumParameters::new(QuantumScheme::Dilithium)).unwrap();
+        let cache = SignatureCache::default();
+        let tx = signed(&keypair, 1);
+
+        cache.verify_signature(&tx, CONSENSUS_VERIFY_FLAGS).unwrap();
+        cache.verify_signature(&tx, CONSENSUS_VERIFY_FLAGS).unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+        // Other flags are a different fact
+        cache.verify_signature(&tx, VERIFY_HYBRID_EITHER).unwrap();
+        assert_eq!(cache.stats().misses, 2);
+
+        // A forged signature over the same sighash misses and is not stored
+        let mut forged = tx.clone();
+        let mut signature = tx.signature_data().unwrap().clone();
+        signature.data[0] ^= 1;
+        forged.set_signature_data(signature);
+        assert!(cache
+            .verify_signature(&forged, CONSENSUS_VERIFY_FLAGS)
+            .is_err());
+        assert!(cache
+            .verify_signature(&forged, CONSENSUS_VERIFY_FLAGS)
+            .is_err());
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().hits, 1);
+    }
+
+    #[test]
+    fn the_byte_budget_evicts_least_recently_used_entries() {
+        let cache = SignatureCache::new(3 * ENTRY_BYTES + ENTRY_BYTES / 2);
+        for key in 0..3u8 {
+            cache.insert([key; 32]);
+        }
+        assert!(cache.contains(&[0; 32]));
+        cache.insert([3; 32]);
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 3);
+        assert!(stats.bytes <= stats.max_bytes);
+        assert!(
+            !cache.contains(&[1; 32]),
+            "least recently used entry is evicted"
+        );
+        assert!(cache.contains(&[0; 32]) && cache.contains(&[3; 32]));
+
+        let disabled = SignatureCache::new(0);
+        disabled.insert([0; 32]);
+        assert_eq!(disabled.stats().entries, 0);
+    }
+}
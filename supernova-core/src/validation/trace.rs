@@ -0,0 +1,367 @@
+//! Validation tracing for diagnosing consensus failures.
+//!
+//! A [`Tracer`] is threaded through the block and transaction validators.
+//! When it wraps a [`ValidationTrace`], every check appends a [`TraceStep`]
+//! recording the check name, a summary of its inputs, the outcome and how
+//! long it took. When disabled, each call is a single `Option` test: input
+//! summaries are built by closures that never run, and no clock is read.
+//!
+//! Traces are meant to leave the node (REST API, logs), so they are bounded:
+//! at most [`MAX_TRACE_STEPS`] steps are kept, summaries are cut at
+//! [`MAX_SUMMARY_LEN`] bytes, and script or witness bytes should go through
+//! [`redact_bytes`], which keeps only a short hex prefix.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+/// Maximum number of steps kept in one trace. Later passing steps are only
+/// counted; the failing step is always kept.
+pub const MAX_TRACE_STEPS: usize = 512;
+
+/// Maximum length in bytes of an input summary or failure reason.
+pub const MAX_SUMMARY_LEN: usize = 256;
+
+/// Number of leading bytes of a script or witness shown in a trace.
+pub const REDACTED_BYTES_PREFIX: usize = 16;
+
+/// Result of a single traced check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TraceOutcome {
+    /// The check ran and passed
+    Passed,
+    /// The check ran and rejected the object
+    Failed { reason: String },
+    /// The check was not run, e.g. disabled by configuration
+    Skipped { reason: String },
+}
+
+/// One check performed during validation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStep {
+    /// Dotted check name, e.g. `header.merkle-root` or `tx[3].dust`
+    pub check: String,
+    /// Summary of the values the check looked at
+    pub inputs: String,
+    /// What the check decided
+    pub outcome: TraceOutcome,
+    /// Time spent in the check, in microseconds
+    pub duration_us: u64,
+}
+
+impl TraceStep {
+    pub fn passed(&self) -> bool {
+        self.outcome == TraceOutcome::Passed
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, TraceOutcome::Failed { .. })
+    }
+}
+
+/// Ordered record of the checks run against one block or transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationTrace {
+    steps: Vec<TraceStep>,
+    /// Passing steps dropped once `MAX_TRACE_STEPS` was reached
+    omitted_steps: usize,
+}
+
+impl ValidationTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Steps in the order they ran
+    pub fn steps(&self) -> &[TraceStep] {
+        &self.steps
+    }
+
+    /// Number of passing steps dropped to keep the trace bounded
+    pub fn omitted_steps(&self) -> usize {
+        self.omitted_steps
+    }
+
+    /// The check that rejected the object, if any
+    pub fn failure(&self) -> Option<&TraceStep> {
+        self.steps.iter().rev().find(|step| step.failed())
+    }
+
+    /// Append a step. Past `MAX_TRACE_STEPS`, non-failing steps are counted
+    /// instead of stored, so the check that ended validation is never lost.
+    pub fn push(&mut self, mut step: TraceStep) {
+        if self.steps.len() >= MAX_TRACE_STEPS && !step.failed() {
+            self.omitted_steps += 1;
+            return;
+        }
+        step.inputs = truncate(step.inputs);
+        step.outcome = match step.outcome {
+            TraceOutcome::Failed { reason } => TraceOutcome::Failed {
+                reason: truncate(reason),
+            },
+            TraceOutcome::Skipped { reason } => TraceOutcome::Skipped {
+                reason: truncate(reason),
+            },
+            TraceOutcome::Passed => TraceOutcome::Passed,
+        };
+        self.steps.push(step);
+    }
+}
+
+/// A validation error together with the trace that led to it
+#[derive(Debug, thiserror::Error)]
+#[error("{error}")]
+pub struct Traced<E> {
+    pub error: E,
+    pub trace: ValidationTrace,
+}
+
+/// Records checks into a [`ValidationTrace`], or does nothing when disabled
+#[derive(Debug)]
+pub struct Tracer<'a> {
+    trace: Option<&'a mut ValidationTrace>,
+    scope: Option<String>,
+}
+
+impl<'a> Tracer<'a> {
+    /// A tracer that records into `trace`
+    pub fn new(trace: &'a mut ValidationTrace) -> Self {
+        Self {
+            trace: Some(trace),
+            scope: None,
+        }
+    }
+
+    /// A tracer that records nothing
+    #[inline]
+    pub fn disabled() -> Self {
+        Self {
+            trace: None,
+            scope: None,
+        }
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// A tracer that prefixes check names with `scope`, e.g. `tx[2]`
+    pub fn scoped(&mut self, scope: impl FnOnce() -> String) -> Tracer<'_> {
+        match self.trace.as_deref_mut() {
+            None => Tracer::disabled(),
+            Some(trace) => Tracer {
+                trace: Some(trace),
+                scope: Some(match &self.scope {
+                    Some(outer) => format!("{}.{}", outer, scope()),
+                    None => scope(),
+                }),
+            },
+        }
+    }
+
+    /// Start timing a check; `None` when disabled
+    #[inline]
+    pub fn start(&self) -> Option<Instant> {
+        self.trace.as_ref().map(|_| Instant::now())
+    }
+
+    /// Record a passed check started at `started`
+    #[inline]
+    pub fn pass(
+        &mut self,
+        started: Option<Instant>,
+        check: &'static str,
+        inputs: impl FnOnce() -> String,
+    ) {
+        if self.trace.is_some() {
+            self.record(started, check, inputs(), TraceOutcome::Passed);
+        }
+    }
+
+    /// Record a failed check started at `started`
+    #[inline]
+    pub fn fail(
+        &mut self,
+        started: Option<Instant>,
+        check: &'static str,
+        inputs: impl FnOnce() -> String,
+        reason: impl fmt::Display,
+    ) {
+        if self.trace.is_some() {
+            let reason = reason.to_string();
+            self.record(started, check, inputs(), TraceOutcome::Failed { reason });
+        }
+    }
+
+    /// Record a check that was not run
+    #[inline]
+    pub fn skip(&mut self, check: &'static str, reason: &str) {
+        if self.trace.is_some() {
+            let reason = reason.to_string();
+            self.record(None, check, String::new(), TraceOutcome::Skipped { reason });
+        }
+    }
+
+    /// Run `f` as the check `check`, recording its outcome
+    #[inline]
+    pub fn check<T, E: fmt::Display>(
+        &mut self,
+        check: &'static str,
+        inputs: impl FnOnce() -> String,
+        f: impl FnOnce() -> Result<T, E>,
+    ) -> Result<T, E> {
+        if self.trace.is_none() {
+            return f();
+        }
+        let started = self.start();
+        let result = f();
+        match &result {
+            Ok(_) => self.pass(started, check, inputs),
+            Err(e) => self.fail(started, check, inputs, e),
+        }
+        result
+    }
+
+    fn record(
+        &mut self,
+        started: Option<Instant>,
+        check: &'static str,
+        inputs: String,
+        outcome: TraceOutcome,
+    ) {
+        let duration_us = started
+            .map(|s| s.elapsed().as_micros().min(u64::MAX as u128) as u64)
+            .unwrap_or(0);
+        let check = match &self.scope {
+            Some(scope) => format!("{}.{}", scope, check),
+            None => check.to_string(),
+        };
+        if let Some(trace) = self.trace.as_deref_mut() {
+            trace.push(TraceStep {
+                check,
+                inputs,
+                outcome,
+                duration_us,
+            });
+        }
+    }
+}
+
+/// Hex-encode script or witness bytes for a trace, keeping only the first
+/// `REDACTED_BYTES_PREFIX` bytes of anything longer.
+pub fn redact_bytes(bytes: &[u8]) -> String {
+    if bytes.len() <= REDACTED_BYTES_PREFIX {
+        return hex::encode(bytes);
+    }
+    format!(
+        "{}..({} bytes)",
+        hex::encode(&bytes[..REDACTED_BYTES_PREFIX]),
+        bytes.len()
+    )
+}
+
+/// Cut `s` to at most `MAX_SUMMARY_LEN` bytes on a char boundary
+fn truncate(mut s: String) -> String {
+    if s.len() <= MAX_SUMMARY_LEN {
+        return s;
+    }
+    let mut end = MAX_SUMMARY_LEN - 3;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s.truncate(end);
+    s.push_str("...");
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failing_check(tracer: &mut Tracer<'_>) -> Result<(), String> {
+        tracer.check("first", || "a=1".to_string(), || Ok::<_, String>(()))?;
+        tracer.skip("second", "disabled by config");
+        tracer.check("third", || "b=2".to_string(), || Err("bad b".to_string()))
+    }
+
+    #[test]
+    fn records_checks_in_order() {
+        let mut trace = ValidationTrace::new();
+        let result = failing_check(&mut Tracer::new(&mut trace));
+
+        assert_eq!(result, Err("bad b".to_string()));
+        let checks: Vec<_> = trace.steps().iter().map(|s| s.check.as_str()).collect();
+        assert_eq!(checks, ["first", "second", "third"]);
+        assert!(trace.steps()[0].passed());
+        assert_eq!(
+            trace.steps()[1].outcome,
+            TraceOutcome::Skipped {
+                reason: "disabled by config".to_string()
+            }
+        );
+        let failure = trace.failure().unwrap();
+        assert_eq!(failure.check, "third");
+        assert_eq!(failure.inputs, "b=2");
+    }
+
+    #[test]
+    fn disabled_tracer_runs_checks_without_formatting_inputs() {
+        let mut tracer = Tracer::disabled();
+        let result = tracer.check(
+            "check",
+            || panic!("inputs must not be summarized when disabled"),
+            || Err::<(), _>("rejected"),
+        );
+        assert_eq!(result, Err("rejected"));
+        assert!(tracer.start().is_none());
+        assert!(!tracer
+            .scoped(|| panic!("no scope when disabled"))
+            .is_enabled());
+    }
+
+    #[test]
+    fn scopes_nest() {
+        let mut trace = ValidationTrace::new();
+        let mut tracer = Tracer::new(&mut trace);
+        {
+            let mut tx = tracer.scoped(|| "tx[1]".to_string());
+            let mut input = tx.scoped(|| "input[0]".to_string());
+            input.pass(None, "script", String::new);
+        }
+        tracer.pass(None, "merkle-root", String::new);
+
+        let checks: Vec<_> = trace.steps().iter().map(|s| s.check.as_str()).collect();
+        assert_eq!(checks, ["tx[1].input[0].script", "merkle-root"]);
+    }
+
+    #[test]
+    fn traces_are_bounded() {
+        let mut trace = ValidationTrace::new();
+        let mut tracer = Tracer::new(&mut trace);
+        for _ in 0..MAX_TRACE_STEPS + 10 {
+            tracer.pass(None, "check", || "x".repeat(4 * MAX_SUMMARY_LEN));
+        }
+        tracer.fail(None, "last", String::new, "é".repeat(MAX_SUMMARY_LEN));
+
+        assert_eq!(trace.steps().len(), MAX_TRACE_STEPS + 1);
+        assert_eq!(trace.omitted_steps(), 10);
+        assert!(trace.steps()[0].inputs.len() <= MAX_SUMMARY_LEN);
+        match &trace.failure().unwrap().outcome {
+            TraceOutcome::Failed { reason } => assert!(reason.len() <= MAX_SUMMARY_LEN),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[test]
+    fn redacts_long_byte_strings() {
+        assert_eq!(redact_bytes(&[0xab; 4]), "abababab");
+        let witness = vec![0x11; 3_309];
+        let redacted = redact_bytes(&witness);
+        assert_eq!(
+            redacted,
+            format!("{}..(3309 bytes)", "11".repeat(REDACTED_BYTES_PREFIX))
+        );
+    }
+}
@@ -2,8 +2,15 @@
 
 use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
 use crate::crypto::signature::{SignatureParams, SignatureType};
-use crate::types::transaction::{SignatureSchemeType, Transaction, TransactionOutput};
+use crate::script::classify::{classify, ScriptKind};
+use crate::script::multisig::{is_multisig_spend, verify_multisig_witness};
+use crate::script::{ScriptFlags, ScriptValidator};
+use crate::types::timelock::{FinalityContext, InputAge, TimeLock};
+use crate::types::transaction::{
+    pubkey_commitment, SignatureSchemeType, Transaction, TransactionOutput,
+};
 use crate::validation::crypto::{CryptoValidationConfig, CryptoValidator};
+use crate::validation::trace::{redact_bytes, Tracer, ValidationTrace};
 use crate::validation::{SecurityLevel, ValidationError, ValidationMetrics};
 use serde::{Deserialize, Serialize};
 
@@ -75,6 +82,64 @@ impl Default for ValidationConfig {
     }
 }
 
+/// Whether input `index` of `tx` is authorized by the transaction-level
+/// signature rather than its own witness or signature script
+pub fn signed_by_transaction(tx: &Transaction, index: usize, prevout: &TransactionOutput) -> bool {
+    tx.signature_data().is_some()
+        && !tx
+            .inputs()
+            .get(index)
+            .is_some_and(|input| is_multisig_spend(input.witness(), &prevout.pub_key_script))
+}
+
+/// Verify that input `index` of `tx` may spend `prevout`, given the
+/// transaction's `sighash`. For an input [`signed_by_transaction`] only the
+/// signing key's ownership of `prevout` is checked here; the signature
+/// itself is checked once per transaction with
+/// [`Transaction::verify_signature_only`].
+pub fn verify_input(
+    tx: &Transaction,
+    index: usize,
+    prevout: &TransactionOutput,
+    sighash: &[u8; 32],
+) -> Result<(), ValidationError> {
+    let input = tx.inputs().get(index).ok_or_else(|| {
+        ValidationError::InvalidStructure(format!("Transaction has no input {}", index))
+    })?;
+
+    // Multisig spends carry their signatures and script in the witness,
+    // signed over the signature hash
+    if is_multisig_spend(input.witness(), &prevout.pub_key_script) {
+        let script = &prevout.pub_key_script;
+        return verify_multisig_witness(input.witness(), script, sighash).map_err(|e| {
+            ValidationError::InvalidSignature(format!(
+                "Multisig verification failed for input {}: {}",
+                index, e
+            ))
+        });
+    }
+
+    if let Some(sig_data) = tx.signature_data() {
+        if prevout.pub_key_script != pubkey_commitment(&sig_data.public_key) {
+            return Err(ValidationError::InvalidSignature(format!(
+                "Input {} is not authorized by the transaction's signing key",
+                index
+            )));
+        }
+        return Ok(());
+    }
+
+    // Use the transaction's own verification logic
+    if tx.verify_signature(input.signature_script(), &prevout.pub_key_script, index) {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidSignature(format!(
+            "Signature verification failed for input {}",
+            index
+        )))
+    }
+}
+
 /// Transaction validator for standard and extended transactions
 pub struct TransactionValidator {
     /// Configuration
@@ -124,59 +189,83 @@ impl TransactionValidator {
 
     /// Validate a transaction
     pub fn validate(&self, tx: &Transaction) -> Result<ValidationResult, ValidationError> {
+        self.validate_with_tracer(tx, &mut Tracer::disabled())
+    }
+
+    /// Validate a transaction, recording every check performed in `trace`
+    pub fn validate_traced(
+        &self,
+        tx: &Transaction,
+        trace: &mut ValidationTrace,
+    ) -> Result<ValidationResult, ValidationError> {
+        self.validate_with_tracer(tx, &mut Tracer::new(trace))
+    }
+
+    /// Validate a transaction, reporting each check to `tracer`
+    pub fn validate_with_tracer(
+        &self,
+        tx: &Transaction,
+        tracer: &mut Tracer<'_>,
+    ) -> Result<ValidationResult, ValidationError> {
         let mut metrics = ValidationMetrics::default();
         let start_time = std::time::Instant::now();
 
         // Perform validation checks in order of complexity (cheaper checks first)
 
         // 1. Basic structure validation
-        if let Err(err) = self.validate_structure(tx) {
+        if let Err(err) = tracer.check(
+            "structure",
+            || {
+                format!(
+                    "txid={} inputs={} outputs={} coinbase={}",
+                    hex::encode(tx.hash()),
+                    tx.inputs().len(),
+                    tx.outputs().len(),
+                    tx.is_coinbase()
+                )
+            },
+            || self.validate_structure(tx),
+        ) {
             return Ok(ValidationResult::Invalid(err));
         }
 
         // 2. Size validation
-        if let Err(err) = self.validate_size(tx) {
+        if let Err(err) = tracer.check(
+            "size",
+            || format!("size={} max={}", tx.calculate_size(), self.config.max_size),
+            || self.validate_size(tx),
+        ) {
             return Ok(ValidationResult::Invalid(err));
         }
 
         // 3. Version check
-        if tx.version() < self.config.min_version {
-            return Ok(ValidationResult::Invalid(
-                ValidationError::InvalidStructure(format!(
-                    "Transaction version too low: {} (minimum: {})",
-                    tx.version(),
-                    self.config.min_version
-                )),
-            ));
+        if let Err(err) = tracer.check(
+            "version",
+            || format!("version={} min={}", tx.version(), self.config.min_version),
+            || self.validate_version(tx),
+        ) {
+            return Ok(ValidationResult::Invalid(err));
         }
 
         // 4. Check for dust outputs if configured
-        if !self.config.allow_zero_value {
-            for (i, output) in tx.outputs().iter().enumerate() {
-                if output.amount() == 0 {
-                    return Ok(ValidationResult::Invalid(
-                        ValidationError::InvalidStructure(format!(
-                            "Zero value output at index {}",
-                            i
-                        )),
-                    ));
-                }
-            }
+        if self.config.allow_zero_value {
+            tracer.skip("zero-value", "zero-value outputs allowed by config");
+        } else if let Err(err) = tracer.check(
+            "zero-value",
+            || format!("outputs={}", tx.outputs().len()),
+            || self.validate_no_zero_value(tx),
+        ) {
+            return Ok(ValidationResult::Invalid(err));
         }
 
-        if self.config.dust_threshold > 0 {
-            for (i, output) in tx.outputs().iter().enumerate() {
-                if output.amount() > 0 && output.amount() < self.config.dust_threshold {
-                    return Ok(ValidationResult::Invalid(
-                        ValidationError::InvalidStructure(format!(
-                            "Dust output at index {}: {} (minimum: {})",
-                            i,
-                            output.amount(),
-                            self.config.dust_threshold
-                        )),
-                    ));
-                }
-            }
+        if self.config.dust_threshold == 0 {
+            tracer.skip("dust", "dust threshold is 0");
+        } else if let Err(err) = tracer.check(
+            "dust",
+            || format!("threshold={}", self.config.dust_threshold),
+            || self.validate_no_dust(tx),
+        ) {
+            return Ok(ValidationResult::Invalid(err));
         }
 
         // 5. Validate transaction signatures (this requires access to UTXO set)
@@ -184,32 +273,21 @@ impl TransactionValidator {
         // and verify signatures against them. For now, we'll assume this validation happens elsewhere.
 
         // 6. For quantum resistance (if required), check signature scheme
-        if self.config.require_quantum_resistance {
-            if let Some(sig_data) = tx.signature_data() {
-                match sig_data.scheme {
-                    SignatureSchemeType::Legacy | SignatureSchemeType::Ed25519 => {
-                        return Ok(ValidationResult::Invalid(
-                            ValidationError::InvalidSignature(
-                                "Quantum-resistant signature required".to_string(),
-                            ),
-                        ));
-                    }
-                    // These schemes are quantum-resistant
-                    SignatureSchemeType::Dilithium
-                    | SignatureSchemeType::Falcon
-                    | SignatureSchemeType::SphincsPlus
-                    | SignatureSchemeType::Hybrid => {
-                        // Acceptable quantum-resistant schemes
-                    }
-                }
-            } else if tx.version() >= 2 {
-                // Version 2+ transactions without signature data when quantum resistance is required
-                return Ok(ValidationResult::Invalid(
-                    ValidationError::InvalidSignature(
-                        "Quantum-resistant signature required for v2+ transactions".to_string(),
-                    ),
-                ));
-            }
+        if !self.config.require_quantum_resistance {
+            tracer.skip("quantum-signature", "quantum resistance not required");
+        } else if let Err(err) = tracer.check(
+            "quantum-signature",
+            || match tx.signature_data() {
+                Some(sig_data) => format!(
+                    "scheme={:?} signature={}",
+                    sig_data.scheme,
+                    redact_bytes(&sig_data.data)
+                ),
+                None => format!("version={} signature=none", tx.version()),
+            },
+            || self.validate_quantum_signature_scheme(tx),
+        ) {
+            return Ok(ValidationResult::Invalid(err));
         }
 
         // Update metrics
@@ -226,6 +304,76 @@ impl TransactionValidator {
         Ok(ValidationResult::Valid)
     }
 
+    /// Check the transaction version against the configured minimum
+    fn validate_version(&self, tx: &Transaction) -> Result<(), ValidationError> {
+        if tx.version() < self.config.min_version {
+            return Err(ValidationError::InvalidStructure(format!(
+                "Transaction version too low: {} (minimum: {})",
+                tx.version(),
+                self.config.min_version
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Reject zero-value outputs
+    fn validate_no_zero_value(&self, tx: &Transaction) -> Result<(), ValidationError> {
+        for (i, output) in tx.outputs().iter().enumerate() {
+            if output.amount() == 0 {
+                return Err(ValidationError::InvalidStructure(format!(
+                    "Zero value output at index {}",
+                    i
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reject non-zero outputs below the dust threshold
+    fn validate_no_dust(&self, tx: &Transaction) -> Result<(), ValidationError> {
+        for (i, output) in tx.outputs().iter().enumerate() {
+            if output.amount() > 0 && output.amount() < self.config.dust_threshold {
+                return Err(ValidationError::InvalidStructure(format!(
+                    "Dust output at index {}: {} (minimum: {})",
+                    i,
+                    output.amount(),
+                    self.config.dust_threshold
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Require a quantum-resistant signature scheme
+    fn validate_quantum_signature_scheme(&self, tx: &Transaction) -> Result<(), ValidationError> {
+        if let Some(sig_data) = tx.signature_data() {
+            match sig_data.scheme {
+                SignatureSchemeType::Legacy | SignatureSchemeType::Ed25519 => {
+                    return Err(ValidationError::InvalidSignature(
+                        "Quantum-resistant signature required".to_string(),
+                    ));
+                }
+                // These schemes are quantum-resistant
+                SignatureSchemeType::Dilithium
+                | SignatureSchemeType::Falcon
+                | SignatureSchemeType::SphincsPlus
+                | SignatureSchemeType::Hybrid => {
+                    // Acceptable quantum-resistant schemes
+                }
+            }
+        } else if tx.version() >= 2 {
+            // Version 2+ transactions without signature data when quantum resistance is required
+            return Err(ValidationError::InvalidSignature(
+                "Quantum-resistant signature required for v2+ transactions".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Validate transaction structure
     fn validate_structure(&self, tx: &Transaction) -> Result<(), ValidationError> {
         // Check that transaction has at least one input and one output (except for special coinbase tx)
@@ -329,7 +477,9 @@ impl TransactionValidator {
         }
     }
 
-    /// Validate transaction signatures
+    /// Validate transaction signatures. An input spending a multisig output
+    /// must meet the threshold of the script in its witness (see
+    /// [`crate::script::multisig`]).
     pub fn validate_signatures(
         &self,
         tx: &Transaction,
@@ -406,17 +556,10 @@ impl TransactionValidator {
                     }
                 };
 
-                // Use the transaction's own verification logic
-                if tx.verify_signature(input.signature_script(), &prev_output.pub_key_script, i) {
-                    valid_inputs += 1;
-                } else {
-                    return Ok(ValidationResult::Invalid(
-                        ValidationError::InvalidSignature(format!(
-                            "Signature verification failed for input {}",
-                            i
-                        )),
-                    ));
+                if let Err(e) = verify_input(tx, i, &prev_output, &tx.signature_hash()) {
+                    return Ok(ValidationResult::Invalid(e));
                 }
+                valid_inputs += 1;
             }
 
             // Ensure all inputs were validated
@@ -434,6 +577,64 @@ impl TransactionValidator {
         }
     }
 
+    /// Check that `tx` may be included in the next block at `ctx`, and run
+    /// the witness scripts of inputs spending P2WSH outputs so their
+    /// `OP_CHECKLOCKTIMEVERIFY` and `OP_CHECKSEQUENCEVERIFY` locks hold.
+    /// A time-based lock time must be below the tip's median time past, so
+    /// a script's time lock cannot be met ahead of the chain's clock.
+    pub fn validate_time_locks(
+        &self,
+        tx: &Transaction,
+        ctx: &FinalityContext,
+        get_output: impl Fn(&[u8; 32], u32) -> Option<TransactionOutput>,
+        age: impl FnMut(&[u8; 32]) -> InputAge,
+    ) -> Result<ValidationResult, ValidationError> {
+        if tx.is_coinbase() {
+            return Ok(ValidationResult::Valid);
+        }
+
+        let lock = TimeLock::of(tx, ctx, age);
+        if !lock.is_satisfied(ctx) {
+            return Ok(ValidationResult::Invalid(
+                ValidationError::InvalidStructure(format!(
+                    "Transaction is not final: needs height {} and median time past {}, \
+                     next block is at height {} with median time past {}",
+                    lock.min_height, lock.min_time, ctx.next_height, ctx.median_time_past
+                )),
+            ));
+        }
+
+        for (i, input) in tx.inputs().iter().enumerate() {
+            let prevout = match get_output(&input.prev_tx_hash(), input.prev_output_index()) {
+                Some(output) => output,
+                None => {
+                    return Ok(ValidationResult::Invalid(
+                        ValidationError::InvalidStructure(format!(
+                            "Previous output not found for input {}",
+                            i
+                        )),
+                    ));
+                }
+            };
+            let script_path = classify(&prevout.pub_key_script).kind() == ScriptKind::P2wsh
+                && !is_multisig_spend(input.witness(), &prevout.pub_key_script);
+            if !script_path {
+                continue;
+            }
+            if let Err(e) = ScriptValidator::new(tx, i, ScriptFlags::default()).validate(
+                input.signature_script(),
+                &prevout.pub_key_script,
+                prevout.value(),
+            ) {
+                return Ok(ValidationResult::Invalid(ValidationError::InvalidScript(
+                    format!("Input {}: {}", i, e),
+                )));
+            }
+        }
+
+        Ok(ValidationResult::Valid)
+    }
+
     /// Check if transaction outputs exceed inputs
     pub fn validate_output_value(
         &self,
@@ -644,3 +845,194 @@ mod sign_quantum_transaction_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod multisig_tests {
+    use super::*;
+    use crate::crypto::quantum::{QuantumKeyPair, QuantumParameters, QuantumScheme};
+    use crate::script::MultisigScript;
+    use crate::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+    use secp256k1::{Message, Secp256k1, SecretKey};
+
+    /// A 2-of-3 output over two secp256k1 keys and an ML-DSA key, and a
+    /// spend of it with the witness built from `sign`
+    fn spend(
+        sign: impl Fn(&[SecretKey], &QuantumKeyPair, &[u8; 32]) -> Vec<Vec<u8>>,
+    ) -> (Transaction, TransactionOutput) {
+        let secp = Secp256k1::new();
+        let secrets = [
+            SecretKey::from_slice(&[1; 32]).unwrap(),
+            SecretKey::from_slice(&[2; 32]).unwrap(),
+        ];
+        let ml_dsa = QuantumKeyPair::generate(QuantumParameters {
+            scheme: QuantumScheme::Dilithium,
+            security_level: 3,
+        })
+        .unwrap();
+        let mut keys: Vec<Vec<u8>> = secrets
+            .iter()
+            .map(|secret| secret.public_key(&secp).serialize().to_vec())
+            .collect();
+        keys.push(ml_dsa.public_key.clone());
+        let script = MultisigScript::new(2, keys).unwrap();
+        let prevout = TransactionOutput::new(90_000, script.script_pubkey());
+
+        let outputs = vec![TransactionOutput::new(80_000, vec![0x42; 32])];
+        let skeleton = Transaction::new(
+            1,
+            vec![TransactionInput::new([7; 32], 0, vec![], 0xffff_ffff)],
+            outputs.clone(),
+            0,
+        );
+        let mut witness = sign(&secrets, &ml_dsa, &skeleton.signature_hash());
+        witness.push(script.witness_script());
+        let input = TransactionInput::new_with_witness([7; 32], 0, vec![], 0xffff_ffff, witness);
+        (Transaction::new(1, vec![input], outputs, 0), prevout)
+    }
+
+    fn validate(tx: &Transaction, prevout: &TransactionOutput) -> ValidationResult {
+        TransactionValidator::new()
+            .validate_signatures(tx, |_, _| Some(prevout.clone()))
+            .unwrap()
+    }
+
+    fn secp_sign(secret: &SecretKey, sighash: &[u8; 32]) -> Vec<u8> {
+        let message = Message::from_slice(sighash).unwrap();
+        Secp256k1::new()
+            .sign_ecdsa(&message, secret)
+            .serialize_compact()
+            .to_vec()
+    }
+
+    #[test]
+    fn mixed_quantum_and_classical_threshold_is_verified() {
+        // The ML-DSA cosigner and one classical cosigner, out of key order
+        let (tx, prevout) = spend(|secrets, ml_dsa, sighash| {
+            vec![
+                ml_dsa.sign(sighash).unwrap(),
+                secp_sign(&secrets[1], sighash),
+            ]
+        });
+        assert!(matches!(validate(&tx, &prevout), ValidationResult::Valid));
+
+        let (tx, prevout) = spend(|secrets, _, sighash| {
+            vec![
+                secp_sign(&secrets[0], sighash),
+                secp_sign(&secrets[1], sighash),
+            ]
+        });
+        assert!(matches!(validate(&tx, &prevout), ValidationResult::Valid));
+    }
+
+    #[test]
+    fn threshold_is_not_met_by_one_key_or_its_repeats() {
+        let (tx, prevout) = spend(|secrets, _, sighash| vec![secp_sign(&secrets[0], sighash)]);
+        assert!(matches!(
+            validate(&tx, &prevout),
+            ValidationResult::Invalid(ValidationError::InvalidSignature(_))
+        ));
+
+        let (tx, prevout) = spend(|_, ml_dsa, sighash| {
+            let signature = ml_dsa.sign(sighash).unwrap();
+            vec![signature.clone(), signature]
+        });
+        assert!(matches!(
+            validate(&tx, &prevout),
+            ValidationResult::Invalid(ValidationError::InvalidSignature(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod time_lock_tests {
+    use super::*;
+    use crate::script::ScriptBuilder;
+    use crate::types::transaction::TransactionInput;
+    use sha2::{Digest, Sha256};
+
+    const PARENT: [u8; 32] = [9; 32];
+
+    /// A P2WSH output of `script` and a spend of it through the script
+    fn spend(
+        script: Vec<u8>,
+        version: u32,
+        lock_time: u32,
+        sequence: u32,
+    ) -> (Transaction, TransactionOutput) {
+        let program = ScriptBuilder::pay_to_witness_script_hash(&Sha256::digest(&script)).unwrap();
+        let prevout = TransactionOutput::new(90_000, program);
+        let input = TransactionInput::new_with_witness(PARENT, 0, vec![], sequence, vec![script]);
+        let outputs = vec![TransactionOutput::new(80_000, vec![0x42; 32])];
+        (
+            Transaction::new(version, vec![input], outputs, lock_time),
+            prevout,
+        )
+    }
+
+    fn validate(
+        tx: &Transaction,
+        prevout: &TransactionOutput,
+        ctx: FinalityContext,
+        confirmed_at: u64,
+    ) -> ValidationResult {
+        let age = |_: &[u8; 32]| InputAge::Confirmed {
+            height: confirmed_at,
+            median_time_past: 0,
+        };
+        TransactionValidator::new()
+            .validate_time_locks(tx, &ctx, |_, _| Some(prevout.clone()), age)
+            .unwrap()
+    }
+
+    fn at(next_height: u64, median_time_past: u64) -> FinalityContext {
+        FinalityContext {
+            next_height,
+            median_time_past,
+        }
+    }
+
+    #[test]
+    fn time_locks_are_measured_against_median_time_past() {
+        let unlock = 1_700_000_000;
+        let script = ScriptBuilder::absolute_timelock(unlock, &[0x51]);
+
+        let (tx, prevout) = spend(script.clone(), 1, unlock, 0);
+        assert!(matches!(
+            validate(&tx, &prevout, at(10, u64::from(unlock)), 1),
+            ValidationResult::Invalid(ValidationError::InvalidStructure(_))
+        ));
+        assert!(matches!(
+            validate(&tx, &prevout, at(10, u64::from(unlock) + 1), 1),
+            ValidationResult::Valid
+        ));
+
+        // A final lock time that falls short of the script's fails the script
+        let (early, prevout) = spend(script, 1, unlock - 1, 0);
+        assert!(matches!(
+            validate(&early, &prevout, at(10, u64::from(unlock) + 1), 1),
+            ValidationResult::Invalid(ValidationError::InvalidScript(_))
+        ));
+    }
+
+    #[test]
+    fn relative_locks_need_the_output_to_be_old_enough() {
+        let script = ScriptBuilder::relative_timelock(10, &[0x51]);
+
+        let (tx, prevout) = spend(script.clone(), 2, 0, 10);
+        assert!(matches!(
+            validate(&tx, &prevout, at(59, 0), 50),
+            ValidationResult::Invalid(ValidationError::InvalidStructure(_))
+        ));
+        assert!(matches!(
+            validate(&tx, &prevout, at(60, 0), 50),
+            ValidationResult::Valid
+        ));
+
+        // An input sequence below the script's lock fails the script
+        let (short, prevout) = spend(script, 2, 0, 9);
+        assert!(matches!(
+            validate(&short, &prevout, at(100, 0), 50),
+            ValidationResult::Invalid(ValidationError::InvalidScript(_))
+        ));
+    }
+}
@@ -6,7 +6,7 @@
 use crate::consensus::difficulty::DifficultyAdjustment;
 use crate::consensus::time_warp_prevention::{TimeWarpConfig, TimeWarpPrevention};
 use crate::types::block::{Block, BlockHeader};
-use crate::types::transaction::{Transaction, TransactionOutput};
+use crate::types::transaction::TransactionOutput;
 use std::collections::HashMap;
 use thiserror::Error;
 
@@ -146,7 +146,7 @@ impl UnifiedBlockValidator {
 
     /// Validate merkle root matches transactions
     fn validate_merkle_root(&self, block: &Block) -> UnifiedValidationResult<()> {
-        let calculated_root = self.calculate_merkle_root(&block.transactions);
+        let calculated_root = block.calculate_merkle_root();
 
         if calculated_root != *block.header.merkle_root() {
             return Err(UnifiedValidationError::InvalidMerkleRoot);
@@ -155,22 +155,6 @@ impl UnifiedBlockValidator {
         Ok(())
     }
 
-    /// Calculate merkle root from transactions
-    fn calculate_merkle_root(&self, transactions: &[Transaction]) -> [u8; 32] {
-        use crate::util::merkle::MerkleTree;
-
-        if transactions.is_empty() {
-            return [0; 32];
-        }
-
-        // Get transaction hashes
-        let tx_hashes: Vec<[u8; 32]> = transactions.iter().map(|tx| tx.hash()).collect();
-
-        // Use the same MerkleTree implementation that Block uses
-        let merkle_tree = MerkleTree::new(&tx_hashes);
-        merkle_tree.root_hash()
-    }
-
     /// Validate all transactions in the block
     fn validate_transactions(
         &self,
@@ -710,7 +694,7 @@ impl UnifiedBlockValidator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::transaction::{TransactionInput, TransactionOutput};
+    use crate::types::transaction::{Transaction, TransactionInput};
 
     /// Test difficulty constant within valid bounds
     /// This value is within the range [min_target=0x1b00ffff, max_target=0x1e0fffff]
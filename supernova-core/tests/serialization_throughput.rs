@@ -0,0 +1,133 @@
+//! Serialization throughput floors.
+//!
+//! A cheap, self-checking companion to `benches/serialization.rs` that runs
+//! in the normal test suite. Each check times a fixed amount of work and
+//! fails only if throughput falls below a floor set one to two orders of
+//! magnitude under what a debug build on modest CI hardware achieves, so it
+//! catches pathological regressions (an accidental quadratic, a clone per
+//! byte) rather than noise.
+//!
+//! Floors can be overridden per check with environment variables, e.g.
+//! `SUPERNOVA_FLOOR_TX_HASH_MB_S=1`, or all checks disabled with
+//! `SUPERNOVA_SKIP_THROUGHPUT_FLOORS=1` on very slow or heavily shared
+//! machines.
+
+use std::time::{Duration, Instant};
+
+use supernova_core::types::block::{Block, BlockHeader};
+use supernova_core::types::transaction::{
+    SignatureSchemeType, Transaction, TransactionInput, TransactionOutput,
+    TransactionSignatureData,
+};
+use supernova_core::util::merkle::MerkleTree;
+
+/// Minimum wall time measured per check, to smooth out timer granularity
+const MIN_MEASUREMENT: Duration = Duration::from_millis(200);
+
+fn floor(var: &str, default: f64) -> Option<f64> {
+    if std::env::var_os("SUPERNOVA_SKIP_THROUGHPUT_FLOORS").is_some() {
+        return None;
+    }
+    Some(
+        std::env::var(var)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default),
+    )
+}
+
+/// Run `op` repeatedly for at least `MIN_MEASUREMENT`; returns runs per second
+fn runs_per_sec(mut op: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0u64;
+    while start.elapsed() < MIN_MEASUREMENT {
+        op();
+        runs += 1;
+    }
+    runs as f64 / start.elapsed().as_secs_f64()
+}
+
+fn assert_floor(name: &str, measured: f64, var: &str, default: f64, unit: &str) {
+    if let Some(min) = floor(var, default) {
+        assert!(
+            measured >= min,
+            "{name}: {measured:.1} {unit} is below the floor of {min} {unit} \
+             (override with {var})"
+        );
+    }
+}
+
+fn witness_heavy_tx() -> Transaction {
+    let inputs = (0..10u8)
+        .map(|i| TransactionInput::new([i; 32], 0, vec![0x5a; 3_309 + 1_952], 0xffff_fffe))
+        .collect();
+    let mut tx = Transaction::new(2, inputs, vec![TransactionOutput::new(1_000, vec![0x76; 34])], 0);
+    tx.set_signature_data(TransactionSignatureData {
+        scheme: SignatureSchemeType::Dilithium,
+        security_level: 3,
+        data: vec![0x11; 3_309],
+        public_key: vec![0x22; 1_952],
+    });
+    tx
+}
+
+fn consolidation_tx() -> Transaction {
+    let inputs = (0..400u32)
+        .map(|i| {
+            let mut prev = [0u8; 32];
+            prev[..4].copy_from_slice(&i.to_le_bytes());
+            TransactionInput::new(prev, 0, vec![0x5a; 72], 0xffff_fffe)
+        })
+        .collect();
+    Transaction::new(2, inputs, vec![TransactionOutput::new(1_000, vec![0x76; 34])], 0)
+}
+
+#[test]
+fn transaction_encoding_meets_floor() {
+    for (name, tx) in [("witness_heavy", witness_heavy_tx()), ("consolidation_400", consolidation_tx())] {
+        let encoded = bincode::serialize(&tx).expect("encode");
+        let mb = encoded.len() as f64 / 1e6;
+
+        let rate = runs_per_sec(|| {
+            std::hint::black_box(bincode::serialize(std::hint::black_box(&tx)).expect("encode"));
+        });
+        assert_floor(&format!("{name} serialize"), rate * mb, "SUPERNOVA_FLOOR_TX_SERIALIZE_MB_S", 20.0, "MB/s");
+
+        let rate = runs_per_sec(|| {
+            let tx: Transaction = bincode::deserialize(std::hint::black_box(&encoded)).expect("decode");
+            std::hint::black_box(tx);
+        });
+        assert_floor(&format!("{name} deserialize"), rate * mb, "SUPERNOVA_FLOOR_TX_DESERIALIZE_MB_S", 10.0, "MB/s");
+
+        let rate = runs_per_sec(|| {
+            std::hint::black_box(std::hint::black_box(&tx).hash());
+        });
+        assert_floor(&format!("{name} hash"), rate * mb, "SUPERNOVA_FLOOR_TX_HASH_MB_S", 10.0, "MB/s");
+    }
+}
+
+#[test]
+fn block_merkle_root_meets_floor() {
+    let transactions: Vec<Transaction> = (0..1_000u32)
+        .map(|i| {
+            Transaction::new(
+                1,
+                vec![TransactionInput::new([(i % 251) as u8; 32], i, vec![0x5a; 72], 0)],
+                vec![TransactionOutput::new(1_000, vec![0x76; 34])],
+                0,
+            )
+        })
+        .collect();
+    let block = Block::new(BlockHeader::new(1, [0; 32], [0; 32], 0, 0x1d00ffff, 0), transactions);
+
+    let rate = runs_per_sec(|| {
+        std::hint::black_box(std::hint::black_box(&block).calculate_merkle_root());
+    });
+    assert_floor("merkle root of 1000 txs", rate * 1_000.0, "SUPERNOVA_FLOOR_MERKLE_TX_S", 20_000.0, "tx/s");
+
+    let hashes: Vec<[u8; 32]> = block.transactions().iter().map(|tx| tx.hash()).collect();
+    let rate = runs_per_sec(|| {
+        std::hint::black_box(MerkleTree::new(std::hint::black_box(&hashes)).root_hash());
+    });
+    assert_floor("merkle tree of 1000 leaves", rate * 1_000.0, "SUPERNOVA_FLOOR_MERKLE_LEAVES_S", 100_000.0, "leaves/s");
+}
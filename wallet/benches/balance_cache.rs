@@ -0,0 +1,118 @@
+//! Balance and history-total queries on a wallet with 50,000 history
+//! records and 50,000 UTXOs.
+//!
+//! - `recompute`: the previous implementation, scanning the UTXO set per
+//!   address and summing every history record on each query.
+//! - `cached`: the same queries through `WalletHandle`, answered from the
+//!   balance cache and the incremental history totals.
+//!
+//! Run:
+//!
+//! ```
+//! cargo bench -p wallet --bench balance_cache
+//! ```
+
+use bitcoin::network::Network;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use supernova_core::storage::utxo_set::{UtxoEntry, UtxoSet};
+use supernova_core::types::transaction::{OutPoint, TransactionOutput};
+use tempfile::TempDir;
+use wallet::{
+    AccountType, HDWallet, TransactionDirection, TransactionHistory, TransactionRecord,
+    TransactionStatus, WalletHandle,
+};
+
+const RECORDS: u32 = 50_000;
+const ADDRESSES: u32 = 50;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn fixture(dir: &TempDir) -> (HDWallet, TransactionHistory, UtxoSet) {
+    let mut hd = HDWallet::from_mnemonic(
+        MNEMONIC,
+        None,
+        Network::Testnet,
+        dir.path().join("wallet.json"),
+    )
+    .unwrap();
+    hd.create_account("main".to_string(), AccountType::NativeSegWit)
+        .unwrap();
+    hd.set_gap_limit(ADDRESSES).unwrap();
+    for _ in 0..ADDRESSES {
+        hd.get_new_address("main").unwrap();
+    }
+    let scripts: Vec<Vec<u8>> = hd
+        .address_scripts()
+        .unwrap()
+        .into_iter()
+        .map(|(script, _)| script)
+        .collect();
+
+    let utxos = UtxoSet::new_in_memory(RECORDS as usize * 2);
+    for id in 0..RECORDS {
+        let mut txid = [0u8; 32];
+        txid[..4].copy_from_slice(&id.to_be_bytes());
+        let script = &scripts[id as usize % scripts.len()];
+        utxos
+            .add(UtxoEntry {
+                outpoint: OutPoint { txid, vout: 0 },
+                output: TransactionOutput::new(1_000 + u64::from(id), script.clone()),
+                height: id,
+                is_coinbase: false,
+                is_confirmed: true,
+            })
+            .unwrap();
+    }
+
+    let mut history = TransactionHistory::new(dir.path().join("history.json")).unwrap();
+    history
+        .add_transactions((0..RECORDS).map(|id| TransactionRecord {
+            hash: format!("{:064x}", id),
+            timestamp: chrono::Utc::now(),
+            direction: if id % 3 == 0 {
+                TransactionDirection::Sent
+            } else {
+                TransactionDirection::Received
+            },
+            amount: 1_000 + u64::from(id),
+            fee: 10,
+            status: TransactionStatus::Confirmed(id),
+            label: None,
+            category: None,
+            tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
+        }))
+        .unwrap();
+
+    (hd, history, utxos)
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    let (hd, history, utxos) = fixture(&dir);
+
+    let mut group = c.benchmark_group("wallet_50k");
+    group.sample_size(10);
+    group.bench_function("recompute/total_balance", |b| {
+        b.iter(|| black_box(hd.get_total_balance(&utxos).unwrap()))
+    });
+    group.bench_function("recompute/history_totals", |b| {
+        b.iter(|| black_box(history.recompute_stats()))
+    });
+
+    let handle = WalletHandle::new(hd, history, utxos);
+    // Warm the cache so the first sample doesn't pay for the rebuild
+    handle.get_total_balance().unwrap();
+    group.bench_function("cached/total_balance", |b| {
+        b.iter(|| black_box(handle.get_total_balance().unwrap()))
+    });
+    group.bench_function("cached/snapshot", |b| {
+        b.iter(|| black_box(handle.snapshot().unwrap()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_queries);
+criterion_main!(benches);
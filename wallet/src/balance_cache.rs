@@ -0,0 +1,362 @@
+//! Per-account balance cache.
+//!
+//! [`HDWallet::get_balance`] scans the UTXO set once per address, which is
+//! quadratic in practice and makes every balance query slow on a large
+//! wallet. A [`BalanceCache`] keeps a counter per account, adjusted as sync
+//! adds and removes UTXOs, so queries are a map lookup.
+//!
+//! The cache remembers which wallet generation ([`HDWallet::generation`])
+//! and UTXO count it was built against. When either differs at query time —
+//! an address was generated, or the UTXO set changed without going through
+//! the cache — it is rebuilt with a single pass over the UTXO set. The reorg
+//! path invalidates it explicitly with [`BalanceCache::invalidate`].
+//!
+//! The cache's lock is a leaf: it is taken after the wallet's own locks and
+//! nothing else is acquired while it is held.
+
+use crate::hdwallet::{AccountBalances, AccountState, HDWallet, HDWalletError};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use supernova_core::storage::utxo_set::{UtxoEntry, UtxoSet};
+use supernova_core::types::transaction::OutPoint;
+
+#[derive(Debug, Default)]
+struct CacheState {
+    /// False until built, and after an invalidation
+    valid: bool,
+    /// Wallet generation the ownership map was built from
+    wallet_generation: u64,
+    /// UTXO count after the last change the cache saw
+    utxo_count: usize,
+    /// Locking script to owning account
+    owners: HashMap<Vec<u8>, String>,
+    /// Wallet outputs counted in `balances`
+    tracked: HashMap<OutPoint, (String, u64)>,
+    balances: HashMap<String, u64>,
+}
+
+impl CacheState {
+    fn rebuild(&mut self, hd: &HDWallet, utxos: &UtxoSet) -> Result<(), HDWalletError> {
+        self.owners = hd
+            .address_scripts()?
+            .into_iter()
+            .map(|(script, account)| (script, account.to_string()))
+            .collect();
+        self.tracked.clear();
+        self.balances.clear();
+        let entries = utxos.entries();
+        self.utxo_count = entries.len();
+        for entry in entries {
+            self.track(entry);
+        }
+        self.wallet_generation = hd.generation();
+        self.valid = true;
+        Ok(())
+    }
+
+    fn track(&mut self, entry: UtxoEntry) {
+        let Some(account) = self.owners.get(&entry.output.pub_key_script) else {
+            return;
+        };
+        let amount = entry.amount();
+        let balance = self.balances.entry(account.clone()).or_default();
+        *balance = balance.wrapping_add(amount);
+        if let Some((previous_account, previous)) = self
+            .tracked
+            .insert(entry.outpoint, (account.clone(), amount))
+        {
+            self.untrack_amount(&previous_account, previous);
+        }
+    }
+
+    fn untrack(&mut self, outpoint: &OutPoint) {
+        if let Some((account, amount)) = self.tracked.remove(outpoint) {
+            self.untrack_amount(&account, amount);
+        }
+    }
+
+    fn untrack_amount(&mut self, account: &str, amount: u64) {
+        if let Some(balance) = self.balances.get_mut(account) {
+            *balance = balance.wrapping_sub(amount);
+        }
+    }
+}
+
+/// Cached per-account balances
+#[derive(Debug, Default)]
+pub struct BalanceCache {
+    state: Mutex<CacheState>,
+    /// Recompute every query from the UTXO set and check it against the
+    /// cache. Test-only: a mismatch panics.
+    verify: AtomicBool,
+    rebuilds: AtomicU64,
+}
+
+impl BalanceCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check every query against a full recompute. For tests; a mismatch
+    /// panics.
+    pub fn set_verify(&self, verify: bool) {
+        self.verify.store(verify, Ordering::Relaxed);
+    }
+
+    /// Number of full rebuilds so far
+    pub fn rebuilds(&self) -> u64 {
+        self.rebuilds.load(Ordering::Relaxed)
+    }
+
+    /// Drop the cached balances; the next query rebuilds them. Called when
+    /// the UTXO set changes in ways the cache wasn't told about, e.g. a reorg.
+    pub fn invalidate(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.valid = false;
+        }
+    }
+
+    /// Record UTXO changes already applied to `utxos`. A cache that is not
+    /// current is left for the next query to rebuild.
+    pub fn apply(&self, hd: &HDWallet, utxos: &UtxoSet, created: &[UtxoEntry], spent: &[OutPoint]) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if !state.valid || state.wallet_generation != hd.generation() {
+            state.valid = false;
+            return;
+        }
+        for outpoint in spent {
+            state.untrack(outpoint);
+        }
+        for entry in created {
+            state.track(entry.clone());
+        }
+        state.utxo_count = utxos.get_count();
+    }
+
+    /// Balance of one account
+    pub fn balance(
+        &self,
+        hd: &HDWallet,
+        utxos: &UtxoSet,
+        account_name: &str,
+    ) -> Result<u64, HDWalletError> {
+        hd.account_state(account_name)?;
+        let balance = self.with_current(hd, utxos, |state| {
+            state.balances.get(account_name).copied().unwrap_or(0)
+        })?;
+        if self.verify.load(Ordering::Relaxed) {
+            assert_eq!(
+                balance,
+                hd.get_balance(account_name, utxos)?,
+                "cached balance of '{}' diverged from the UTXO set",
+                account_name
+            );
+        }
+        Ok(balance)
+    }
+
+    /// Balances of the active and archived accounts
+    pub fn balances(
+        &self,
+        hd: &HDWallet,
+        utxos: &UtxoSet,
+    ) -> Result<AccountBalances, HDWalletError> {
+        let per_account = self.with_current(hd, utxos, |state| state.balances.clone())?;
+        let mut balances = AccountBalances::default();
+        for (account_name, balance) in per_account {
+            match hd.account_state(&account_name)? {
+                AccountState::Active => balances.active += balance,
+                AccountState::Archived => balances.archived += balance,
+            }
+        }
+        if self.verify.load(Ordering::Relaxed) {
+            assert_eq!(
+                balances,
+                hd.get_balances(utxos)?,
+                "cached balances diverged from the UTXO set"
+            );
+        }
+        Ok(balances)
+    }
+
+    fn with_current<T>(
+        &self,
+        hd: &HDWallet,
+        utxos: &UtxoSet,
+        read: impl FnOnce(&CacheState) -> T,
+    ) -> Result<T, HDWalletError> {
+        // A panic mid-update leaves the counters unreliable; rebuild them
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| {
+            let mut state = poisoned.into_inner();
+            state.valid = false;
+            state
+        });
+        let stale = !state.valid
+            || state.wallet_generation != hd.generation()
+            || state.utxo_count != utxos.get_count();
+        if stale {
+            state.rebuild(hd, utxos)?;
+            self.rebuilds.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(read(&state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdwallet::AccountType;
+    use bitcoin::network::Network;
+    use proptest::prelude::*;
+    use supernova_core::types::transaction::TransactionOutput;
+
+    const TEST_MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Scripts of every wallet address, then one no account owns
+    fn test_scripts(hd: &HDWallet) -> Vec<Vec<u8>> {
+        let mut scripts: Vec<Vec<u8>> = hd
+            .address_scripts()
+            .unwrap()
+            .into_iter()
+            .map(|(script, _)| script)
+            .collect();
+        scripts.sort();
+        scripts.push(vec![0x51]);
+        scripts
+    }
+
+    fn wallet(dir: &std::path::Path) -> (HDWallet, Vec<Vec<u8>>) {
+        let mut hd = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.join("wallet.json"),
+        )
+        .unwrap();
+        hd.create_account("spending".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        hd.create_account("savings".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        hd.set_gap_limit(100).unwrap();
+        for account in ["spending", "spending", "savings"] {
+            hd.get_new_address(account).unwrap();
+        }
+        let scripts = test_scripts(&hd);
+        (hd, scripts)
+    }
+
+    fn utxo(id: u32, amount: u64, script: &[u8]) -> UtxoEntry {
+        let mut txid = [0u8; 32];
+        txid[..4].copy_from_slice(&id.to_be_bytes());
+        UtxoEntry {
+            outpoint: OutPoint { txid, vout: 0 },
+            output: TransactionOutput::new(amount, script.to_vec()),
+            height: id,
+            is_coinbase: false,
+            is_confirmed: true,
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add { id: u32, amount: u64, script: usize },
+        Spend { id: u32 },
+        NewAddress,
+        Archive,
+        Reorg,
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            6 => (0u32..64, 1u64..1_000_000, 0usize..8)
+                .prop_map(|(id, amount, script)| Op::Add { id, amount, script }),
+            4 => (0u32..64).prop_map(|id| Op::Spend { id }),
+            1 => Just(Op::NewAddress),
+            1 => Just(Op::Archive),
+            1 => Just(Op::Reorg),
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn cache_matches_recompute(ops in proptest::collection::vec(op(), 1..60)) {
+            let dir = tempfile::tempdir().unwrap();
+            let (mut hd, mut scripts) = wallet(dir.path());
+            let utxos = UtxoSet::new_in_memory(1_000);
+            let cache = BalanceCache::new();
+            cache.set_verify(true);
+
+            for op in ops {
+                match op {
+                    Op::Add { id, amount, script } => {
+                        let entry = utxo(id, amount, &scripts[script % scripts.len()]);
+                        if utxos.contains(&entry.outpoint).unwrap() {
+                            continue;
+                        }
+                        utxos.add(entry.clone()).unwrap();
+                        cache.apply(&hd, &utxos, &[entry], &[]);
+                    }
+                    Op::Spend { id } => {
+                        let outpoint = utxo(id, 0, &[]).outpoint;
+                        if utxos.remove(&outpoint).unwrap().is_some() {
+                            cache.apply(&hd, &utxos, &[], &[outpoint]);
+                        }
+                    }
+                    Op::NewAddress => {
+                        hd.get_new_address("spending").unwrap();
+                        scripts = test_scripts(&hd);
+                    }
+                    Op::Archive => {
+                        let _ = hd.archive_account("savings", &utxos, true);
+                    }
+                    Op::Reorg => {
+                        // Outputs change behind the cache's back, then the
+                        // reorg path invalidates it
+                        for id in 0..8 {
+                            utxos.remove(&utxo(id, 0, &[]).outpoint).unwrap();
+                        }
+                        cache.invalidate();
+                    }
+                }
+                cache.balance(&hd, &utxos, "spending").unwrap();
+                cache.balance(&hd, &utxos, "savings").unwrap();
+                cache.balances(&hd, &utxos).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_updates_avoid_rebuilds() {
+        let dir = tempfile::tempdir().unwrap();
+        let (hd, scripts) = wallet(dir.path());
+        let utxos = UtxoSet::new_in_memory(1_000);
+        let cache = BalanceCache::new();
+        cache.set_verify(true);
+
+        assert_eq!(cache.balance(&hd, &utxos, "spending").unwrap(), 0);
+        assert_eq!(cache.rebuilds(), 1);
+        // The last script is unowned
+        let owned = &scripts[..scripts.len() - 1];
+        for id in 0..20 {
+            let entry = utxo(id, 100, &owned[id as usize % owned.len()]);
+            utxos.add(entry.clone()).unwrap();
+            cache.apply(&hd, &utxos, &[entry], &[]);
+        }
+        let balances = cache.balances(&hd, &utxos).unwrap();
+        assert_eq!(balances.active, 2_000);
+        assert_eq!(cache.rebuilds(), 1);
+
+        // A change the cache wasn't told about is detected by the count
+        utxos.add(utxo(99, 5, &scripts[0])).unwrap();
+        assert_eq!(cache.balances(&hd, &utxos).unwrap().active, 2_005);
+        assert_eq!(cache.rebuilds(), 2);
+
+        assert!(cache.balance(&hd, &utxos, "missing").is_err());
+    }
+}
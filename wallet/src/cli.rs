@@ -1,15 +1,23 @@
 use crate::{
     backup_warning::BackupWarning,
-    hdwallet::{AccountType, HDWallet},
+    clock::HttpNodeClock,
+    display::{format_amount, DisplayUnit, RateProviderSettings},
+    hdwallet::{AccountState, AccountType, HDWallet, HDWalletError},
     history::{TransactionDirection, TransactionHistory, TransactionRecord, TransactionStatus},
+    policy::{Approval, DestinationRule, SpendingPolicy},
+    seeds::{self, SeedStore},
+    settings::WalletSettings,
     ui::tui::WalletTui,
+    ui::wizard::{SetupWizard, WizardOutcome},
 };
 use bitcoin::network::Network; // Bitcoin-compatible
 use supernova_core::storage::utxo_set::UtxoSet;
 use chrono::Utc;
 use clap::{CommandFactory, Parser, Subcommand};
+use std::io::{self, IsTerminal, Write};
 use std::path::PathBuf;
 use std::str::FromStr;
+use zeroize::Zeroizing;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -20,6 +28,22 @@ pub struct Cli {
     #[arg(short, long, default_value = "testnet")]
     network: String,
 
+    /// Signed netparams file of a custom network; overrides `--network`
+    #[arg(long, value_name = "PATH")]
+    network_file: Option<PathBuf>,
+
+    /// Key the netparams file must be signed by (`ed25519:<hex>`)
+    #[arg(long, value_name = "KEY", requires = "network_file")]
+    netparams_authority: Option<String>,
+
+    /// Load the netparams file without verifying its signature
+    #[arg(long, requires = "network_file")]
+    insecure_netparams: bool,
+
+    /// Named seed to use instead of the active one
+    #[arg(long, value_name = "NAME")]
+    seed: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -36,17 +60,54 @@ enum Commands {
     FromMnemonic {
         /// Mnemonic phrase
         mnemonic: String,
+
+        /// Prompt for the BIP39 passphrase the seed was protected with; a
+        /// different passphrase restores a different wallet
+        #[arg(long)]
+        bip39_passphrase: bool,
+    },
+
+    /// List the seeds in the wallet directory
+    ListSeeds,
+
+    /// Make a seed the one commands use by default
+    UseSeed {
+        /// Seed name
+        name: String,
+    },
+
+    /// Encrypt the wallet's seed with a passphrase
+    Encrypt,
+
+    /// List accounts in the wallet
+    ListAccounts {
+        /// Include archived accounts
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Hide an account from listings and totals
+    ArchiveAccount {
+        /// Account name
+        name: String,
+
+        /// Archive even though the account still holds funds
+        #[arg(long)]
+        confirm: bool,
     },
 
-    /// List all accounts in the wallet
-    ListAccounts,
+    /// Restore an archived account
+    UnarchiveAccount {
+        /// Account name
+        name: String,
+    },
 
     /// Create a new account
     CreateAccount {
         /// Account name
         name: String,
 
-        /// Account type (legacy, segwit, native_segwit)
+        /// Account type (legacy, segwit, native_segwit, quantum, slh-dsa)
         #[arg(short, long, default_value = "native_segwit")]
         account_type: String,
     },
@@ -72,6 +133,90 @@ enum Commands {
         skip_check: bool,
     },
 
+    /// Set balance display preferences (unit and optional fiat currency)
+    SetDisplay {
+        /// Primary unit: nova or units
+        #[arg(long)]
+        unit: Option<String>,
+
+        /// Secondary fiat currency code (e.g. USD); pass "none" to disable
+        #[arg(long)]
+        fiat: Option<String>,
+
+        /// HTTP endpoint returning {"rate": <price per NOVA>, "timestamp": <unix>}
+        #[arg(long, conflicts_with = "fixed_rate")]
+        rate_endpoint: Option<String>,
+
+        /// Use a fixed, manually entered price per NOVA
+        #[arg(long)]
+        fixed_rate: Option<f64>,
+
+        /// Seconds to cache a fetched rate
+        #[arg(long, default_value = "300")]
+        rate_ttl: u64,
+    },
+
+    /// Set the spending policy for an account (replaces any existing policy)
+    SetPolicy {
+        /// Account name
+        #[arg(short, long)]
+        account: String,
+
+        /// Maximum amount per transaction, in nova units
+        #[arg(long)]
+        per_tx_limit: Option<u64>,
+
+        /// Maximum amount spent in any rolling 24h window, in nova units
+        #[arg(long)]
+        daily_limit: Option<u64>,
+
+        /// Spends above this amount require a second approval
+        #[arg(long)]
+        approval_threshold: Option<u64>,
+
+        /// Allowed destination (`addr:<address>` or `desc:<descriptor>`); repeatable
+        #[arg(long = "allow")]
+        allow: Vec<String>,
+
+        /// Denied destination (`addr:<address>` or `desc:<descriptor>`); repeatable
+        #[arg(long = "deny")]
+        deny: Vec<String>,
+
+        /// Hex secp256k1 public key allowed to approve parked spends; repeatable
+        #[arg(long = "cosigner")]
+        cosigner: Vec<String>,
+    },
+
+    /// Show the spending policy for an account
+    ShowPolicy {
+        /// Account name
+        #[arg(short, long)]
+        account: String,
+    },
+
+    /// List spends waiting for approval
+    ListPending,
+
+    /// Approve a spend parked by its account's approval threshold
+    ApproveSpend {
+        /// Pending spend id
+        id: String,
+
+        /// Cosigner public key (hex); requires --signature
+        #[arg(long, requires = "signature")]
+        cosigner: Option<String>,
+
+        /// Cosigner signature over the pending spend digest (hex, DER or compact)
+        #[arg(long, requires = "cosigner")]
+        signature: Option<String>,
+    },
+
+    /// Discard a spend waiting for approval
+    RejectSpend {
+        /// Pending spend id
+        id: String,
+    },
+
     /// Run the TUI
     Tui,
 
@@ -88,11 +233,176 @@ enum Commands {
     },
 }
 
+/// Render a wallet error, calling out spending-policy denials so they are not
+/// mistaken for ordinary failures.
+fn describe_wallet_error(context: &str, e: HDWalletError) -> String {
+    match e {
+        HDWalletError::Policy(violation) => {
+            format!("{}: denied by spending policy: {}", context, violation)
+        }
+        other => format!("{}: {}", context, other),
+    }
+}
+
+/// Read a passphrase without echoing it. When stdin is not a terminal the
+/// passphrase is read as a plain line, so scripts can pipe it in.
+fn read_passphrase(prompt: &str) -> Result<Zeroizing<String>, String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+    use crossterm::terminal;
+
+    print!("{}", prompt);
+    io::stdout()
+        .flush()
+        .map_err(|e| format!("IO error: {}", e))?;
+    // Reserved up front so pushing characters never reallocates and leaves
+    // a copy of the passphrase behind
+    let mut passphrase = Zeroizing::new(String::with_capacity(256));
+    if !io::stdin().is_terminal() {
+        io::stdin()
+            .read_line(&mut passphrase)
+            .map_err(|e| format!("IO error: {}", e))?;
+        let len = passphrase.trim_end_matches(['\r', '\n']).len();
+        passphrase.truncate(len);
+        return Ok(passphrase);
+    }
+
+    terminal::enable_raw_mode().map_err(|e| format!("Terminal error: {}", e))?;
+    let read = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err("Cancelled".to_string())
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(format!("Terminal error: {}", e)),
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    println!();
+    read.map(|()| passphrase)
+}
+
+/// Ask for the passphrase of an encrypted wallet. The CLI exits after one
+/// command, so the seed stays unlocked until then.
+fn unlock_wallet(wallet: &mut HDWallet) -> Result<(), String> {
+    if !wallet.is_encrypted() {
+        return Ok(());
+    }
+    let passphrase = read_passphrase("Wallet passphrase: ")?;
+    wallet
+        .unlock(&passphrase, None)
+        .map_err(|e| format!("Failed to unlock wallet: {}", e))
+}
+
+/// Ask for a new passphrase twice. An empty answer means none.
+fn choose_passphrase() -> Result<Option<Zeroizing<String>>, String> {
+    loop {
+        let passphrase = read_passphrase("New wallet passphrase (empty for none): ")?;
+        if passphrase.is_empty() {
+            return Ok(None);
+        }
+        let confirm = read_passphrase("Repeat the passphrase: ")?;
+        if *passphrase == *confirm {
+            return Ok(Some(passphrase));
+        }
+        println!("Passphrases do not match; try again.");
+    }
+}
+
+/// Ask for the BIP39 passphrase of a restored seed, twice, since a typo
+/// silently restores another wallet
+fn read_seed_passphrase() -> Result<Zeroizing<String>, String> {
+    loop {
+        let passphrase = read_passphrase("BIP39 passphrase: ")?;
+        let confirm = read_passphrase("Repeat the BIP39 passphrase: ")?;
+        if *passphrase == *confirm {
+            return Ok(passphrase);
+        }
+        println!("Passphrases do not match; try again.");
+    }
+}
+
+/// Seal the seed under a passphrase chosen at the prompt, asking again if
+/// it is too weak. Returns whether the wallet was encrypted.
+fn encrypt_interactively(wallet: &mut HDWallet) -> Result<bool, String> {
+    loop {
+        let Some(passphrase) = choose_passphrase()? else {
+            return Ok(false);
+        };
+        match wallet.encrypt(&passphrase) {
+            Ok(()) => return Ok(true),
+            Err(HDWalletError::PasswordTooWeak(suggestions)) => {
+                println!("Passphrase too weak: {}", suggestions);
+            }
+            Err(e) => return Err(format!("Failed to encrypt wallet: {}", e)),
+        }
+    }
+}
+
+/// Offer to upgrade a wallet that stores its seed in plaintext. Only asked
+/// on a terminal; otherwise the `encrypt` command is pointed out.
+fn offer_encryption(wallet: &mut HDWallet) -> Result<(), String> {
+    if !wallet.stores_plaintext_seed() {
+        return Ok(());
+    }
+    println!("⚠️  This wallet stores its seed phrase unencrypted.");
+    if !io::stdin().is_terminal() {
+        println!("Run the 'encrypt' command to protect it with a passphrase.");
+        return Ok(());
+    }
+    println!("Encrypt it now? (yes/no): ");
+    io::stdout()
+        .flush()
+        .map_err(|e| format!("IO error: {}", e))?;
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| format!("IO error: {}", e))?;
+    if input.trim().eq_ignore_ascii_case("yes") && encrypt_interactively(wallet)? {
+        println!("✓ Wallet encrypted in place.");
+    }
+    Ok(())
+}
+
+fn parse_rules(values: &[String]) -> Result<Vec<DestinationRule>, String> {
+    values
+        .iter()
+        .map(|v| {
+            DestinationRule::from_str(v)
+                .map_err(|e| format!("Invalid destination '{}': {}", v, e))
+        })
+        .collect()
+}
+
 pub fn run_cli() -> Result<(), String> {
     let cli = Cli::parse();
 
+    // A custom network uses its own address prefix; its keys derive like
+    // testnet keys
+    if let Some(path) = &cli.network_file {
+        use supernova_core::netparams::{self, NetParamsTrust};
+
+        let trust = NetParamsTrust::from_options(
+            cli.netparams_authority.as_deref(),
+            cli.insecure_netparams,
+        )
+        .map_err(|e| e.to_string())?;
+        let params = netparams::load(path, &trust)
+            .and_then(netparams::activate)
+            .map_err(|e| format!("Failed to load network file: {}", e))?;
+        println!("Using network '{}'", params.name);
+    }
+
     // Parse network string to Network enum
     let network = match cli.network.to_lowercase().as_str() {
+        _ if cli.network_file.is_some() => Network::Testnet,
         "mainnet" | "nova" => Network::Bitcoin, // Bitcoin-compatible
         "testnet" => Network::Testnet,
         "regtest" => Network::Regtest,
@@ -110,21 +420,45 @@ pub fn run_cli() -> Result<(), String> {
             .map_err(|e| format!("Failed to create wallet directory: {}", e))?;
     }
 
-    let wallet_path = wallet_dir.join("wallet.json");
-    let history_path = wallet_dir.join("history.json");
+    // Settings stay in the wallet directory; each seed has its own wallet
+    // and history files
+    let seed_store = SeedStore::new(&wallet_dir);
+    let seed = match cli.seed {
+        Some(name) => name,
+        None => seed_store.active().map_err(|e| e.to_string())?,
+    };
+    let seed_dir = seed_store.seed_dir(&seed).map_err(|e| e.to_string())?;
+    let wallet_path = seed_dir.join(seeds::WALLET_FILE);
+    let history_path = seed_dir.join(seeds::HISTORY_FILE);
+    let create_seed_dir = || {
+        if wallet_path.exists() {
+            return Err(format!(
+                "Seed '{}' already has a wallet; pick another name with --seed",
+                seed
+            ));
+        }
+        std::fs::create_dir_all(&seed_dir)
+            .map_err(|e| format!("Failed to create seed directory: {}", e))
+    };
 
     match cli.command {
         Some(Commands::New) => {
+            create_seed_dir()?;
             println!("Creating new wallet...");
             let mut wallet = HDWallet::new(network, wallet_path.clone())
                 .map_err(|e| format!("Failed to create wallet: {}", e))?;
 
             // Display backup warning and seed phrase
-            BackupWarning::display_seed_phrase(wallet.get_mnemonic());
+            let mnemonic = Zeroizing::new(
+                wallet
+                    .get_mnemonic()
+                    .map_err(|e| e.to_string())?
+                    .to_string(),
+            );
+            BackupWarning::display_seed_phrase(&mnemonic);
 
             // Prompt user to acknowledge backup
             println!("Have you written down your seed phrase? (yes/no): ");
-            use std::io::{self, Write};
             io::stdout().flush().map_err(|e| format!("IO error: {}", e))?;
             
             let mut input = String::new();
@@ -138,15 +472,18 @@ pub fn run_cli() -> Result<(), String> {
             } else {
                 println!("⚠️  WARNING: You have NOT acknowledged your backup!");
                 println!("Please write down your seed phrase before continuing.");
-                println!("Your seed phrase: {}", wallet.get_mnemonic());
+                println!("Your seed phrase: {}", mnemonic.as_str());
             }
 
-            // Create default account
-            wallet
-                .create_account("default".to_string(), AccountType::NativeSegWit)
-                .map_err(|e| format!("Failed to create default account: {}", e))?;
-
-            wallet.save().map_err(|e| format!("Failed to save wallet: {}", e))?;
+            // Create default account; nothing is written until the
+            // passphrase is chosen, so the seed never hits the disk unsealed
+            wallet.add_account("default".to_string(), AccountType::NativeSegWit);
+            if !encrypt_interactively(&mut wallet)? {
+                println!("⚠️  No passphrase set; the seed phrase is stored unencrypted.");
+                wallet
+                    .save()
+                    .map_err(|e| format!("Failed to save wallet: {}", e))?;
+            }
             println!("Default account created.");
             Ok(())
         }
@@ -156,34 +493,95 @@ pub fn run_cli() -> Result<(), String> {
                 return Err("No wallet found. Create one first with 'new' command.".to_string());
             }
 
-            let wallet =
+            let mut wallet =
                 HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
 
             // Check and display backup warnings
             wallet.check_and_display_backup_warning();
+            offer_encryption(&mut wallet)?;
 
             println!("Wallet loaded successfully.");
             Ok(())
         }
 
-        Some(Commands::FromMnemonic { mnemonic }) => {
+        Some(Commands::FromMnemonic {
+            mnemonic,
+            bip39_passphrase,
+        }) => {
+            create_seed_dir()?;
+            let seed_passphrase = if bip39_passphrase {
+                Some(read_seed_passphrase()?)
+            } else {
+                None
+            };
             println!("Creating wallet from mnemonic...");
-            let wallet = HDWallet::from_mnemonic(&mnemonic, network, wallet_path)
-                .map_err(|e| format!("Failed to create wallet from mnemonic: {}", e))?;
-
-            println!("Wallet created successfully.");
+            let wallet = HDWallet::from_mnemonic(
+                &mnemonic,
+                seed_passphrase.as_deref().map(String::as_str),
+                network,
+                wallet_path,
+            )
+            .map_err(|e| format!("Failed to create wallet from mnemonic: {}", e))?;
 
             // Create default account
             let mut wallet = wallet;
-            wallet
-                .create_account("default".to_string(), AccountType::NativeSegWit)
-                .map_err(|e| format!("Failed to create default account: {}", e))?;
+            wallet.add_account("default".to_string(), AccountType::NativeSegWit);
+            if !encrypt_interactively(&mut wallet)? {
+                println!("⚠️  No passphrase set; the seed phrase is stored unencrypted.");
+                wallet
+                    .save()
+                    .map_err(|e| format!("Failed to save wallet: {}", e))?;
+            }
 
+            println!("Wallet created successfully.");
             println!("Default account created.");
             Ok(())
         }
 
-        Some(Commands::ListAccounts) => {
+        Some(Commands::ListSeeds) => {
+            let names = seed_store.list().map_err(|e| e.to_string())?;
+            if names.is_empty() {
+                println!("No seeds found. Create one with the 'new' command.");
+            }
+            let active = seed_store.active().map_err(|e| e.to_string())?;
+            for name in names {
+                let marker = if name == active { "*" } else { " " };
+                println!("{} {}", marker, name);
+            }
+            Ok(())
+        }
+
+        Some(Commands::UseSeed { name }) => {
+            seed_store
+                .set_active(&name)
+                .map_err(|e| format!("Failed to select seed: {}", e))?;
+            println!("Seed '{}' is now active.", name);
+            Ok(())
+        }
+
+        Some(Commands::Encrypt) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+            if wallet.is_watch_only() {
+                return Err("A watch-only wallet has no seed to encrypt.".to_string());
+            }
+            if wallet.is_encrypted() {
+                return Err("The wallet is already encrypted.".to_string());
+            }
+
+            if encrypt_interactively(&mut wallet)? {
+                println!("✓ Seed encrypted. Signing and new addresses now ask for the passphrase.");
+            } else {
+                println!("No passphrase given; the wallet is unchanged.");
+            }
+            Ok(())
+        }
+
+        Some(Commands::ListAccounts { all }) => {
             if !wallet_path.exists() {
                 return Err("No wallet found. Create one first with 'new' command.".to_string());
             }
@@ -191,18 +589,19 @@ pub fn run_cli() -> Result<(), String> {
             let wallet =
                 HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
 
-            let accounts = wallet.list_accounts();
+            let accounts = wallet.list_accounts(all);
             if accounts.is_empty() {
                 println!("No accounts found.");
             } else {
                 println!("Accounts:");
                 for (idx, account) in accounts {
                     println!(
-                        "{}. {} (type: {:?}, addresses: {})",
+                        "{}. {} (type: {:?}, addresses: {}){}",
                         idx,
                         account.name,
                         account.account_type,
-                        account.addresses.len()
+                        account.addresses.len(),
+                        if account.state == AccountState::Archived { " [archived]" } else { "" }
                     );
                 }
             }
@@ -219,6 +618,7 @@ pub fn run_cli() -> Result<(), String> {
 
             let acc_type = AccountType::from_str(&account_type)
                 .map_err(|e| format!("Invalid account type: {}", e))?;
+            unlock_wallet(&mut wallet)?;
 
             wallet
                 .create_account(name.clone(), acc_type)
@@ -238,6 +638,7 @@ pub fn run_cli() -> Result<(), String> {
 
             let mut wallet =
                 HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+            unlock_wallet(&mut wallet)?;
 
             let address = wallet
                 .get_new_address(&account)
@@ -247,6 +648,43 @@ pub fn run_cli() -> Result<(), String> {
             Ok(())
         }
 
+        Some(Commands::ArchiveAccount { name, confirm }) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+            let utxo_set = UtxoSet::new_in_memory(1000);
+
+            match wallet.archive_account(&name, &utxo_set, confirm) {
+                Ok(()) => {
+                    println!("Account '{}' archived", name);
+                    Ok(())
+                }
+                Err(HDWalletError::ArchiveNeedsConfirmation { balance, .. }) => Err(format!(
+                    "Account '{}' still holds {}; rerun with --confirm to archive it",
+                    name,
+                    format_amount(balance, DisplayUnit::default())
+                )),
+                Err(e) => Err(format!("Failed to archive account: {}", e)),
+            }
+        }
+
+        Some(Commands::UnarchiveAccount { name }) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+            wallet
+                .unarchive_account(&name)
+                .map_err(|e| format!("Failed to unarchive account: {}", e))?;
+            println!("Account '{}' restored", name);
+            Ok(())
+        }
+
         Some(Commands::GetBalance { account }) => {
             if !wallet_path.exists() {
                 return Err("No wallet found. Create one first with 'new' command.".to_string());
@@ -262,7 +700,19 @@ pub fn run_cli() -> Result<(), String> {
                 .get_balance(&account, &utxo_set)
                 .map_err(|e| format!("Failed to get balance: {}", e))?;
 
-            println!("Balance for '{}': {} nova units", account, balance);
+            // Display only: a missing or unreachable rate provider never
+            // fails the command, it just renders the fiat value as "—".
+            let display = WalletSettings::load(&wallet_dir)
+                .map(|s| s.display)
+                .unwrap_or_default();
+            let rate_source = display.build_rate_source();
+            let rate = display.current_rate(rate_source.as_ref());
+
+            println!(
+                "Balance for '{}': {}",
+                account,
+                display.format_with_fiat(balance, rate.as_ref())
+            );
             Ok(())
         }
 
@@ -273,6 +723,7 @@ pub fn run_cli() -> Result<(), String> {
 
             let mut wallet =
                 HDWallet::load(wallet_path.clone()).map_err(|e| format!("Failed to load wallet: {}", e))?;
+            unlock_wallet(&mut wallet)?;
 
             match wallet.verify_backup(skip_check) {
                 Ok(()) => {
@@ -286,7 +737,125 @@ pub fn run_cli() -> Result<(), String> {
             Ok(())
         }
 
-        Some(Commands::Tui) => {
+        Some(Commands::SetDisplay {
+            unit,
+            fiat,
+            rate_endpoint,
+            fixed_rate,
+            rate_ttl,
+        }) => {
+            let mut settings = WalletSettings::load(&wallet_dir)
+                .map_err(|e| format!("Failed to load settings: {}", e))?;
+
+            if let Some(unit) = unit {
+                settings.display.primary_unit = DisplayUnit::from_str(&unit)?;
+            }
+            if let Some(fiat) = fiat {
+                settings.display.fiat_currency = if fiat.eq_ignore_ascii_case("none") {
+                    None
+                } else {
+                    Some(fiat.to_uppercase())
+                };
+            }
+            if let Some(endpoint) = rate_endpoint {
+                settings.display.rate_provider = RateProviderSettings::Http {
+                    endpoint,
+                    ttl_secs: rate_ttl,
+                };
+            } else if let Some(price_per_nova) = fixed_rate {
+                settings.display.rate_provider = RateProviderSettings::Fixed { price_per_nova };
+            }
+
+            settings
+                .save(&wallet_dir)
+                .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+            println!(
+                "Display: {} (example: {}), fiat: {}",
+                match settings.display.primary_unit {
+                    DisplayUnit::Nova => "NOVA",
+                    DisplayUnit::NovaUnits => "nova units",
+                },
+                format_amount(123_456_789, settings.display.primary_unit),
+                settings
+                    .display
+                    .fiat_currency
+                    .as_deref()
+                    .unwrap_or("disabled")
+            );
+            Ok(())
+        }
+
+        Some(Commands::SetPolicy {
+            account,
+            per_tx_limit,
+            daily_limit,
+            approval_threshold,
+            allow,
+            deny,
+            cosigner,
+        }) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+
+            let policy = SpendingPolicy {
+                per_transaction_limit: per_tx_limit,
+                daily_limit,
+                allow_list: parse_rules(&allow)?,
+                deny_list: parse_rules(&deny)?,
+                approval_threshold,
+                cosigner_keys: cosigner,
+            };
+            let summary = policy.summary();
+            unlock_wallet(&mut wallet)?;
+
+            wallet
+                .set_spending_policy(&account, policy)
+                .map_err(|e| describe_wallet_error("Failed to set policy", e))?;
+
+            println!("Spending policy for '{}': {}", account, summary);
+            Ok(())
+        }
+
+        Some(Commands::ShowPolicy { account }) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+            unlock_wallet(&mut wallet)?;
+
+            match wallet
+                .spending_policy(&account)
+                .map_err(|e| describe_wallet_error("Failed to read policy", e))?
+            {
+                None => println!("No spending policy set for '{}'.", account),
+                Some(policy) => {
+                    println!("Spending policy for '{}': {}", account, policy.summary());
+                    for rule in &policy.allow_list {
+                        println!("  allow {}", rule);
+                    }
+                    for rule in &policy.deny_list {
+                        println!("  deny  {}", rule);
+                    }
+                    for key in &policy.cosigner_keys {
+                        println!("  cosigner {}", key);
+                    }
+                    println!(
+                        "Spent in the last 24h: {} nova units",
+                        wallet.spent_last_24h(&account)
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        Some(Commands::ListPending) => {
             if !wallet_path.exists() {
                 return Err("No wallet found. Create one first with 'new' command.".to_string());
             }
@@ -294,11 +863,113 @@ pub fn run_cli() -> Result<(), String> {
             let wallet =
                 HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
 
+            let pending = wallet.pending_spends();
+            if pending.is_empty() {
+                println!("No spends awaiting approval.");
+            } else {
+                println!("Spends awaiting approval:");
+                for spend in pending {
+                    println!(
+                        "{}  {} -> {}  {} nova units  (digest {}, parked {})",
+                        spend.id,
+                        spend.account,
+                        spend.destination.address,
+                        spend.amount,
+                        hex::encode(spend.approval_digest()),
+                        spend.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        Some(Commands::ApproveSpend {
+            id,
+            cosigner,
+            signature,
+        }) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+
+            let approval = match (cosigner, signature) {
+                (Some(public_key), Some(signature)) => Approval::Cosigner {
+                    public_key,
+                    signature,
+                },
+                _ => Approval::Confirm,
+            };
+            unlock_wallet(&mut wallet)?;
+
+            let spend = wallet
+                .approve_pending_spend(&id, &approval)
+                .map_err(|e| describe_wallet_error("Approval failed", e))?;
+
+            println!(
+                "✓ Approved {} nova units from '{}' to {}",
+                spend.amount, spend.account, spend.destination.address
+            );
+            Ok(())
+        }
+
+        Some(Commands::RejectSpend { id }) => {
+            if !wallet_path.exists() {
+                return Err("No wallet found. Create one first with 'new' command.".to_string());
+            }
+
+            let mut wallet =
+                HDWallet::load(wallet_path).map_err(|e| format!("Failed to load wallet: {}", e))?;
+
+            let spend = wallet
+                .reject_pending_spend(&id)
+                .map_err(|e| describe_wallet_error("Reject failed", e))?;
+
+            println!("Discarded pending spend {} ({} nova units)", spend.id, spend.amount);
+            Ok(())
+        }
+
+        Some(Commands::Tui) => {
+            let wallet = if SetupWizard::needed(&wallet_path) {
+                // First run: walk the user through setup instead of failing
+                match WalletTui::run_setup_wizard(wallet_path.clone())
+                    .map_err(|e| format!("TUI error: {}", e))?
+                {
+                    Some(WizardOutcome::Wallet(wallet)) => wallet,
+                    Some(WizardOutcome::WatchOnly(watch_only)) => {
+                        println!(
+                            "Watch-only wallet for {} saved to {}",
+                            watch_only.network,
+                            wallet_path.display()
+                        );
+                        return Ok(());
+                    }
+                    None => {
+                        println!("Setup cancelled; no wallet was created.");
+                        return Ok(());
+                    }
+                }
+            } else {
+                let mut wallet = HDWallet::load(wallet_path)
+                    .map_err(|e| format!("Failed to load wallet: {}", e))?;
+                offer_encryption(&mut wallet)?;
+                wallet
+            };
+
             let history = TransactionHistory::new(history_path)
                 .map_err(|e| format!("Failed to load transaction history: {}", e))?;
 
+            let settings = WalletSettings::load(&wallet_dir).unwrap_or_default();
+
             let mut tui = WalletTui::new(wallet, history)
-                .map_err(|e| format!("Failed to create TUI: {}", e))?;
+                .map_err(|e| format!("Failed to create TUI: {}", e))?
+                .with_display_preferences(settings.display)
+                .with_ui_preferences(settings.ui)
+                .with_settings_dir(wallet_dir.clone())
+                .with_auto_lock(settings.auto_lock())
+                .with_node_clock(Box::new(HttpNodeClock::from_env()));
 
             tui.run().map_err(|e| format!("TUI error: {}", e))?;
             Ok(())
@@ -335,6 +1006,8 @@ pub fn run_cli() -> Result<(), String> {
                 label: Some(format!("Test transaction to {}", account)),
                 category: Some("Test".to_string()),
                 tags: vec!["test".to_string(), "demo".to_string()],
+                memo: None,
+                addresses: Vec::new(),
             };
 
             history
@@ -0,0 +1,262 @@
+//! Local clock check against the connected node.
+//!
+//! The wallet compares its clock with the time the node reports in its status
+//! and with the node's tip timestamp. A skewed clock produces misleading
+//! confirmation times and lock-time estimates, so the TUI footer warns when
+//! the two disagree.
+
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use supernova_core::util::clock::{check_clock, ClockCheck, SkewSeverity, SkewThresholds};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClockError {
+    #[error("HTTP error: {0}")]
+    Http(String),
+    #[error("Malformed node status: {0}")]
+    Malformed(String),
+}
+
+/// Time as reported by the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct NodeTime {
+    /// Node wall-clock time, Unix seconds
+    pub node_time: u64,
+    /// Timestamp of the node's tip, if it has one
+    #[serde(default)]
+    pub tip_timestamp: Option<u64>,
+}
+
+/// Source of the node's time.
+pub trait NodeClock: Send + Sync {
+    fn node_time(&self) -> Result<NodeTime, ClockError>;
+}
+
+impl<C: NodeClock + ?Sized> NodeClock for Box<C> {
+    fn node_time(&self) -> Result<NodeTime, ClockError> {
+        (**self).node_time()
+    }
+}
+
+/// Reads the `clock` section of the node's `/api/v1/node/status`.
+#[derive(Debug, Clone)]
+pub struct HttpNodeClock {
+    node_url: String,
+    timeout: Duration,
+}
+
+#[derive(Debug, Deserialize)]
+struct StatusResponse {
+    clock: NodeTime,
+}
+
+impl HttpNodeClock {
+    pub fn new(node_url: impl Into<String>) -> Self {
+        Self {
+            node_url: node_url.into(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Node named by `SUPERNOVA_NODE_URL`, as used for broadcasting.
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("SUPERNOVA_NODE_URL")
+                .unwrap_or_else(|_| "http://localhost:9332".to_string()),
+        )
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl NodeClock for HttpNodeClock {
+    fn node_time(&self) -> Result<NodeTime, ClockError> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| ClockError::Http(e.to_string()))?;
+        let response = client
+            .get(format!(
+                "{}/api/v1/node/status",
+                self.node_url.trim_end_matches('/')
+            ))
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| ClockError::Http(e.to_string()))?;
+        let body: StatusResponse = response
+            .json()
+            .map_err(|e| ClockError::Malformed(e.to_string()))?;
+        Ok(body.clock)
+    }
+}
+
+/// Compare local time with what the node reports.
+pub fn check_against_node(
+    local_now: u64,
+    node: &NodeTime,
+    thresholds: &SkewThresholds,
+) -> ClockCheck {
+    let offset = node.node_time as i64 - local_now as i64;
+    check_clock(local_now, Some(offset), node.tip_timestamp, thresholds)
+}
+
+/// Footer text for a check, `None` when the clock looks fine.
+pub fn footer_warning(check: &ClockCheck) -> Option<String> {
+    if check.severity == SkewSeverity::Ok {
+        return None;
+    }
+    let skew = check.skew_secs();
+    let direction = if skew > 0 { "behind" } else { "ahead of" };
+    let reference = match check.offset_secs {
+        Some(offset) if offset == skew => "the node",
+        _ => "the latest block",
+    };
+    Some(format!(
+        "Local clock is {} {} {}; check the system time",
+        format_skew(skew.unsigned_abs()),
+        direction,
+        reference
+    ))
+}
+
+fn format_skew(secs: u64) -> String {
+    match secs {
+        s if s >= 3_600 => format!("{}h {}m", s / 3_600, (s % 3_600) / 60),
+        s if s >= 60 => format!("{}m {}s", s / 60, s % 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Re-checks the clock at most once per interval. An unreachable node clears
+/// the last result rather than keeping a stale warning.
+pub struct ClockWatch<C> {
+    source: C,
+    thresholds: SkewThresholds,
+    interval: Duration,
+    last: Mutex<Option<(Instant, Option<ClockCheck>)>>,
+}
+
+impl<C: NodeClock> ClockWatch<C> {
+    pub fn new(source: C, interval: Duration) -> Self {
+        Self {
+            source,
+            thresholds: SkewThresholds::default(),
+            interval,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Latest check, refreshing it if the interval has passed.
+    pub fn check(&self) -> Option<ClockCheck> {
+        self.check_at(Instant::now(), chrono::Utc::now().timestamp().max(0) as u64)
+    }
+
+    pub fn check_at(&self, now: Instant, local_now: u64) -> Option<ClockCheck> {
+        let mut last = self.last.lock().ok()?;
+        if let Some((at, check)) = *last {
+            if now.saturating_duration_since(at) < self.interval {
+                return check;
+            }
+        }
+        let check = match self.source.node_time() {
+            Ok(node) => Some(check_against_node(local_now, &node, &self.thresholds)),
+            Err(e) => {
+                tracing::debug!("Node time unavailable: {}", e);
+                None
+            }
+        };
+        *last = Some((now, check));
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const LOCAL: u64 = 1_700_000_000;
+
+    struct FixedNodeClock {
+        node: NodeTime,
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl NodeClock for FixedNodeClock {
+        fn node_time(&self) -> Result<NodeTime, ClockError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.node)
+        }
+    }
+
+    fn watch(node_time: u64, tip_timestamp: Option<u64>) -> ClockWatch<FixedNodeClock> {
+        ClockWatch::new(
+            FixedNodeClock {
+                node: NodeTime {
+                    node_time,
+                    tip_timestamp,
+                },
+                calls: Arc::new(AtomicUsize::new(0)),
+            },
+            Duration::from_secs(60),
+        )
+    }
+
+    #[test]
+    fn warns_when_node_disagrees_with_local_time() {
+        let t0 = Instant::now();
+
+        let check = watch(LOCAL + 5, Some(LOCAL - 600))
+            .check_at(t0, LOCAL)
+            .unwrap();
+        assert_eq!(check.severity, SkewSeverity::Ok);
+        assert_eq!(footer_warning(&check), None);
+
+        let check = watch(LOCAL + 900, None).check_at(t0, LOCAL).unwrap();
+        assert_eq!(check.severity, SkewSeverity::Critical);
+        assert_eq!(
+            footer_warning(&check).unwrap(),
+            "Local clock is 15m 0s behind the node; check the system time"
+        );
+
+        let check = watch(LOCAL - 90, None).check_at(t0, LOCAL).unwrap();
+        assert_eq!(check.severity, SkewSeverity::Warning);
+        assert!(footer_warning(&check)
+            .unwrap()
+            .contains("1m 30s ahead of the node"));
+
+        // Node agrees with us but its tip is from our future
+        let check = watch(LOCAL, Some(LOCAL + 7_200))
+            .check_at(t0, LOCAL)
+            .unwrap();
+        assert!(footer_warning(&check)
+            .unwrap()
+            .contains("2h 0m behind the latest block"));
+    }
+
+    #[test]
+    fn rechecks_once_per_interval() {
+        let watch = watch(LOCAL + 900, None);
+        let t0 = Instant::now();
+        watch.check_at(t0, LOCAL);
+        watch.check_at(t0 + Duration::from_secs(59), LOCAL);
+        assert_eq!(watch.source.calls.load(Ordering::SeqCst), 1);
+        watch.check_at(t0 + Duration::from_secs(60), LOCAL);
+        assert_eq!(watch.source.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unreachable_node_is_not_a_warning() {
+        let watch = ClockWatch::new(
+            HttpNodeClock::new("http://127.0.0.1:9").with_timeout(Duration::from_millis(200)),
+            Duration::from_secs(60),
+        );
+        assert_eq!(watch.check(), None);
+    }
+}
@@ -0,0 +1,499 @@
+//! Coin selection for [`WalletManager::select_coins`](crate::WalletManager::select_coins).
+//!
+//! Outputs are compared by effective value: their amount minus the fee of
+//! spending them at the requested rate. An output that costs more to spend
+//! than it holds is never selected. Fee rates are in base units per virtual
+//! byte, sized for single-key segwit inputs and outputs.
+
+use crate::WalletError;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use supernova_core::storage::utxo_set::UtxoEntry;
+use supernova_core::types::transaction::OutPoint;
+
+/// Smallest change output worth creating; smaller excess goes to the fee
+pub const DEFAULT_DUST_THRESHOLD: u64 = 546;
+
+/// Version, locktime and input/output counts
+const TX_OVERHEAD_VBYTES: u64 = 11;
+const INPUT_VBYTES: u64 = 68;
+const OUTPUT_VBYTES: u64 = 31;
+
+/// Search steps branch and bound may take before giving up
+const BNB_MAX_TRIES: usize = 100_000;
+
+/// How [`select_coins`] picks outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoinSelectionStrategy {
+    /// Fewest inputs: spend the largest outputs first
+    #[default]
+    LargestFirst,
+    /// Consolidate: spend the smallest outputs first
+    SmallestFirst,
+    /// Search for a set that needs no change output, falling back to
+    /// largest-first when none exists
+    BranchAndBound,
+    /// Spend outputs in random order, so selections don't fingerprint the
+    /// wallet
+    Random,
+}
+
+/// Outputs chosen to fund a payment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinSelection {
+    pub outpoints: Vec<OutPoint>,
+    /// Sum of the selected outputs
+    pub input_total: u64,
+    pub fee: u64,
+    /// Change to send back to the wallet; zero when the excess was below the
+    /// dust threshold and was left to the fee
+    pub change: u64,
+}
+
+/// Virtual size of an output whose script is `script_len` bytes: the
+/// value, the script length prefix and the script
+pub fn output_vbytes(script_len: usize) -> u64 {
+    let prefix = match script_len {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        _ => 5,
+    };
+    8 + prefix + script_len as u64
+}
+
+/// Select outputs from `utxos` paying `target` at `fee_rate`.
+pub fn select_coins(
+    utxos: &[UtxoEntry],
+    target: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+    strategy: CoinSelectionStrategy,
+    rng: &mut impl Rng,
+) -> Result<CoinSelection, WalletError> {
+    select_coins_for_outputs(
+        utxos,
+        target,
+        OUTPUT_VBYTES,
+        fee_rate,
+        dust_threshold,
+        strategy,
+        rng,
+    )
+}
+
+/// [`select_coins`] for a transaction whose outputs other than change take
+/// `outputs_vbytes`, such as a payment plus a data carrier. A zero `target`
+/// pays for outputs that carry no value, so the fee is allowed to exceed it.
+pub fn select_coins_for_outputs(
+    utxos: &[UtxoEntry],
+    target: u64,
+    outputs_vbytes: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+    strategy: CoinSelectionStrategy,
+    rng: &mut impl Rng,
+) -> Result<CoinSelection, WalletError> {
+    select_coins_for_inputs(
+        utxos,
+        target,
+        INPUT_VBYTES,
+        outputs_vbytes,
+        fee_rate,
+        dust_threshold,
+        strategy,
+        rng,
+    )
+}
+
+/// [`select_coins_for_outputs`] spending inputs of `input_vbytes` each
+/// rather than single-key segwit ones, such as multisig spends
+pub fn select_coins_for_inputs(
+    utxos: &[UtxoEntry],
+    target: u64,
+    input_vbytes: u64,
+    outputs_vbytes: u64,
+    fee_rate: u64,
+    dust_threshold: u64,
+    strategy: CoinSelectionStrategy,
+    rng: &mut impl Rng,
+) -> Result<CoinSelection, WalletError> {
+    let input_fee = fee_rate.saturating_mul(input_vbytes);
+    let change_cost = fee_rate.saturating_mul(OUTPUT_VBYTES);
+    // The payment itself, before any input is added
+    let needed = fee_rate
+        .saturating_mul(TX_OVERHEAD_VBYTES.saturating_add(outputs_vbytes))
+        .saturating_add(target);
+
+    let mut candidates: Vec<(&UtxoEntry, u64)> = utxos
+        .iter()
+        .filter(|utxo| utxo.amount() > input_fee)
+        .map(|utxo| (utxo, utxo.amount() - input_fee))
+        .collect();
+    let available = candidates
+        .iter()
+        .fold(0u64, |total, (_, value)| total.saturating_add(*value));
+    if available < needed {
+        return Err(WalletError::InsufficientFunds {
+            available,
+            required: needed,
+        });
+    }
+
+    // Ties broken by outpoint so selections are reproducible
+    candidates.sort_by(|(a, a_value), (b, b_value)| {
+        b_value
+            .cmp(a_value)
+            .then_with(|| a.outpoint.txid.cmp(&b.outpoint.txid))
+            .then_with(|| a.outpoint.vout.cmp(&b.outpoint.vout))
+    });
+    let selected = match strategy {
+        CoinSelectionStrategy::LargestFirst => accumulate(&candidates, needed),
+        CoinSelectionStrategy::SmallestFirst => {
+            candidates.reverse();
+            accumulate(&candidates, needed)
+        }
+        CoinSelectionStrategy::BranchAndBound => {
+            let values: Vec<u64> = candidates.iter().map(|(_, value)| *value).collect();
+            let upper = needed
+                .saturating_add(change_cost)
+                .saturating_add(dust_threshold);
+            branch_and_bound(&values, needed, upper)
+                .unwrap_or_else(|| accumulate(&candidates, needed))
+        }
+        CoinSelectionStrategy::Random => {
+            candidates.shuffle(rng);
+            accumulate(&candidates, needed)
+        }
+    };
+
+    let effective: u64 = selected.iter().map(|&i| candidates[i].1).sum();
+    let input_total: u64 = selected.iter().map(|&i| candidates[i].0.amount()).sum();
+    let excess = effective - needed;
+    let change = if excess >= change_cost.saturating_add(dust_threshold) {
+        excess - change_cost
+    } else {
+        0
+    };
+    let fee = input_total - target - change;
+    if target > 0 && fee > target {
+        return Err(WalletError::FeeExceedsAmount {
+            fee,
+            amount: target,
+        });
+    }
+
+    Ok(CoinSelection {
+        outpoints: selected
+            .iter()
+            .map(|&i| candidates[i].0.outpoint.clone())
+            .collect(),
+        input_total,
+        fee,
+        change,
+    })
+}
+
+/// Take candidates in order until their effective value reaches `needed`.
+/// The caller has checked that all of them together do.
+fn accumulate(candidates: &[(&UtxoEntry, u64)], needed: u64) -> Vec<usize> {
+    let mut selected = Vec::new();
+    let mut total = 0u64;
+    for (i, (_, value)) in candidates.iter().enumerate() {
+        if total >= needed {
+            break;
+        }
+        selected.push(i);
+        total += value;
+    }
+    selected
+}
+
+/// Depth-first search over `values` (sorted descending) for the subset
+/// summing into `needed..=upper` with the least excess.
+fn branch_and_bound(values: &[u64], needed: u64, upper: u64) -> Option<Vec<usize>> {
+    let mut remaining: u64 = values.iter().sum();
+    let mut current: Vec<usize> = Vec::new();
+    let mut current_value = 0u64;
+    let mut best: Option<(u64, Vec<usize>)> = None;
+    let mut index = 0;
+
+    for _ in 0..BNB_MAX_TRIES {
+        let backtrack = if current_value + remaining < needed || current_value > upper {
+            true
+        } else if current_value >= needed {
+            let excess = current_value - needed;
+            if !matches!(&best, Some((best_excess, _)) if *best_excess <= excess) {
+                best = Some((excess, current.clone()));
+            }
+            if excess == 0 {
+                break;
+            }
+            true
+        } else {
+            false
+        };
+
+        if backtrack {
+            // Undo the most recent inclusion and continue with it excluded,
+            // returning the values skipped since then to the lookahead
+            let Some(&last) = current.last() else {
+                break;
+            };
+            while index > last + 1 {
+                index -= 1;
+                remaining += values[index];
+            }
+            current.pop();
+            current_value -= values[last];
+            index = last + 1;
+        } else {
+            remaining -= values[index];
+            current_value += values[index];
+            current.push(index);
+            index += 1;
+        }
+    }
+
+    best.map(|(_, selection)| selection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use supernova_core::types::transaction::TransactionOutput;
+
+    const FEE_RATE: u64 = 2;
+    const INPUT_FEE: u64 = FEE_RATE * INPUT_VBYTES;
+    const BASE_FEE: u64 = FEE_RATE * (TX_OVERHEAD_VBYTES + OUTPUT_VBYTES);
+
+    fn utxo(id: u32, amount: u64) -> UtxoEntry {
+        let mut txid = [0u8; 32];
+        txid[..4].copy_from_slice(&id.to_be_bytes());
+        UtxoEntry {
+            outpoint: OutPoint { txid, vout: 0 },
+            output: TransactionOutput::new(amount, vec![0x00, 0x14]),
+            height: 1,
+            is_coinbase: false,
+            is_confirmed: true,
+        }
+    }
+
+    /// Mixed sizes, from dust up to one large output
+    fn mixed_utxos() -> Vec<UtxoEntry> {
+        [100, 2_000, 5_000, 10_000, 25_000, 60_000, 150_000, 400_000]
+            .into_iter()
+            .enumerate()
+            .map(|(i, amount)| utxo(i as u32, amount))
+            .collect()
+    }
+
+    fn select(
+        utxos: &[UtxoEntry],
+        target: u64,
+        strategy: CoinSelectionStrategy,
+    ) -> Result<CoinSelection, WalletError> {
+        let mut rng = StdRng::seed_from_u64(7);
+        select_coins(
+            utxos,
+            target,
+            FEE_RATE,
+            DEFAULT_DUST_THRESHOLD,
+            strategy,
+            &mut rng,
+        )
+    }
+
+    fn amounts(utxos: &[UtxoEntry], selection: &CoinSelection) -> Vec<u64> {
+        selection
+            .outpoints
+            .iter()
+            .map(|outpoint| {
+                utxos
+                    .iter()
+                    .find(|utxo| &utxo.outpoint == outpoint)
+                    .unwrap()
+                    .amount()
+            })
+            .collect()
+    }
+
+    /// Inputs pay the target, the fee and the change exactly
+    fn assert_balanced(utxos: &[UtxoEntry], selection: &CoinSelection, target: u64) {
+        assert_eq!(
+            amounts(utxos, selection).iter().sum::<u64>(),
+            selection.input_total
+        );
+        assert_eq!(
+            selection.input_total,
+            target + selection.fee + selection.change
+        );
+        assert!(selection.change == 0 || selection.change >= DEFAULT_DUST_THRESHOLD);
+        let minimum_fee = BASE_FEE
+            + INPUT_FEE * selection.outpoints.len() as u64
+            + if selection.change > 0 {
+                FEE_RATE * OUTPUT_VBYTES
+            } else {
+                0
+            };
+        assert!(selection.fee >= minimum_fee);
+    }
+
+    #[test]
+    fn largest_first_uses_fewest_inputs() {
+        let utxos = mixed_utxos();
+        let selection = select(&utxos, 300_000, CoinSelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(amounts(&utxos, &selection), vec![400_000]);
+        assert_balanced(&utxos, &selection, 300_000);
+        assert!(selection.change > 0);
+    }
+
+    #[test]
+    fn smallest_first_consolidates_and_skips_uneconomical_outputs() {
+        let utxos = mixed_utxos();
+        let selection = select(&utxos, 30_000, CoinSelectionStrategy::SmallestFirst).unwrap();
+        // The 100 output is worth less than its input fee
+        assert_eq!(
+            amounts(&utxos, &selection),
+            vec![2_000, 5_000, 10_000, 25_000]
+        );
+        assert_balanced(&utxos, &selection, 30_000);
+    }
+
+    #[test]
+    fn branch_and_bound_finds_changeless_selection() {
+        let utxos = mixed_utxos();
+        // 60,000 + 25,000 after their input fees, less the base fee
+        let target = 85_000 - 2 * INPUT_FEE - BASE_FEE;
+        let selection = select(&utxos, target, CoinSelectionStrategy::BranchAndBound).unwrap();
+        assert_eq!(selection.change, 0);
+        let mut selected = amounts(&utxos, &selection);
+        selected.sort();
+        assert_eq!(selected, vec![25_000, 60_000]);
+        assert_balanced(&utxos, &selection, target);
+
+        // Largest-first pays the same target with a single input and change
+        let largest = select(&utxos, target, CoinSelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(amounts(&utxos, &largest), vec![400_000]);
+        assert!(largest.change > 0);
+    }
+
+    #[test]
+    fn branch_and_bound_falls_back_when_no_exact_set() {
+        let utxos = vec![utxo(0, 50_000), utxo(1, 50_000)];
+        let selection = select(&utxos, 20_000, CoinSelectionStrategy::BranchAndBound).unwrap();
+        assert_eq!(selection.outpoints.len(), 1);
+        assert!(selection.change > 0);
+        assert_balanced(&utxos, &selection, 20_000);
+    }
+
+    #[test]
+    fn random_selection_covers_target() {
+        let utxos = mixed_utxos();
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let selection = select_coins(
+                &utxos,
+                100_000,
+                FEE_RATE,
+                DEFAULT_DUST_THRESHOLD,
+                CoinSelectionStrategy::Random,
+                &mut rng,
+            )
+            .unwrap();
+            assert_balanced(&utxos, &selection, 100_000);
+            assert!(!amounts(&utxos, &selection).contains(&100));
+        }
+    }
+
+    #[test]
+    fn exact_match_needs_no_change() {
+        let utxos = mixed_utxos();
+        let target = 10_000 - INPUT_FEE - BASE_FEE;
+        for strategy in [
+            CoinSelectionStrategy::LargestFirst,
+            CoinSelectionStrategy::BranchAndBound,
+        ] {
+            let utxos = vec![utxos[3].clone()];
+            let selection = select(&utxos, target, strategy).unwrap();
+            assert_eq!(selection.change, 0);
+            assert_eq!(selection.fee, INPUT_FEE + BASE_FEE);
+        }
+    }
+
+    #[test]
+    fn excess_below_dust_goes_to_fee() {
+        let utxos = vec![utxo(0, 10_000)];
+        let target = 10_000 - INPUT_FEE - BASE_FEE - 300;
+        let selection = select(&utxos, target, CoinSelectionStrategy::LargestFirst).unwrap();
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.fee, INPUT_FEE + BASE_FEE + 300);
+    }
+
+    #[test]
+    fn insufficient_confirmed_funds() {
+        let utxos = mixed_utxos();
+        let err = select(&utxos, 1_000_000, CoinSelectionStrategy::LargestFirst).unwrap_err();
+        assert!(matches!(
+            err,
+            WalletError::InsufficientFunds { available, required }
+                if available < required && required == 1_000_000 + BASE_FEE
+        ));
+        assert!(matches!(
+            select(&[], 1_000, CoinSelectionStrategy::Random),
+            Err(WalletError::InsufficientFunds { available: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn zero_value_outputs_pay_for_their_own_size() {
+        let utxos = mixed_utxos();
+        // OP_RETURN OP_PUSHDATA1 <80> and the payload
+        let data_vbytes = output_vbytes(83);
+        assert_eq!(data_vbytes, 92);
+        assert_eq!(output_vbytes(22), OUTPUT_VBYTES);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let selection = select_coins_for_outputs(
+            &utxos,
+            0,
+            data_vbytes,
+            FEE_RATE,
+            DEFAULT_DUST_THRESHOLD,
+            CoinSelectionStrategy::SmallestFirst,
+            &mut rng,
+        )
+        .unwrap();
+        let inputs = selection.outpoints.len() as u64;
+        let change_vbytes = if selection.change > 0 {
+            OUTPUT_VBYTES
+        } else {
+            0
+        };
+        assert!(
+            selection.fee
+                >= FEE_RATE
+                    * (TX_OVERHEAD_VBYTES + data_vbytes + change_vbytes + inputs * INPUT_VBYTES)
+        );
+        assert_eq!(selection.input_total, selection.fee + selection.change);
+    }
+
+    #[test]
+    fn tiny_outputs_whose_fee_exceeds_the_target_are_refused() {
+        // Each output is worth 20 after its input fee
+        let utxos: Vec<UtxoEntry> = (0..100).map(|i| utxo(i, 20 + INPUT_FEE)).collect();
+        let err = select(&utxos, 1_000, CoinSelectionStrategy::SmallestFirst).unwrap_err();
+        assert!(matches!(
+            err,
+            WalletError::FeeExceedsAmount { fee, amount: 1_000 } if fee > 1_000
+        ));
+
+        // Outputs that each cost more to spend than they hold are not funds
+        let dust: Vec<UtxoEntry> = (0..100).map(|i| utxo(i, INPUT_FEE)).collect();
+        assert!(matches!(
+            select(&dust, 1_000, CoinSelectionStrategy::LargestFirst),
+            Err(WalletError::InsufficientFunds { available: 0, .. })
+        ));
+    }
+}
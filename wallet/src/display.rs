@@ -0,0 +1,208 @@
+//! Amount display preferences and formatting.
+
+use crate::rates::{CachedRateProvider, FiatRate, FixedRateProvider, HttpRateProvider, RateProvider};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Base units per NOVA.
+pub const NOVA_UNITS_PER_NOVA: u64 = 100_000_000;
+
+/// Shown in place of a fiat value that cannot be determined.
+pub const UNAVAILABLE: &str = "—";
+
+/// Unit balances are primarily displayed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayUnit {
+    /// Whole NOVA with eight decimal places.
+    #[default]
+    Nova,
+    /// Raw base units.
+    NovaUnits,
+}
+
+impl std::str::FromStr for DisplayUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nova" => Ok(DisplayUnit::Nova),
+            "units" | "nova_units" | "nova-units" => Ok(DisplayUnit::NovaUnits),
+            _ => Err(format!("Invalid display unit: {} (expected nova or units)", s)),
+        }
+    }
+}
+
+/// Where fiat rates come from.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateProviderSettings {
+    /// No fiat values are shown.
+    #[default]
+    None,
+    /// JSON HTTP endpoint, see [`HttpRateProvider`].
+    Http {
+        endpoint: String,
+        #[serde(default = "default_rate_ttl_secs")]
+        ttl_secs: u64,
+    },
+    /// A manually entered rate.
+    Fixed { price_per_nova: f64 },
+}
+
+fn default_rate_ttl_secs() -> u64 {
+    300
+}
+
+/// Display preferences stored in the wallet settings file.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct DisplayPreferences {
+    #[serde(default)]
+    pub primary_unit: DisplayUnit,
+    /// ISO 4217 code of the secondary fiat currency, if any.
+    #[serde(default)]
+    pub fiat_currency: Option<String>,
+    #[serde(default)]
+    pub rate_provider: RateProviderSettings,
+}
+
+impl DisplayPreferences {
+    /// Build the configured rate source, or `None` if fiat display is off.
+    pub fn build_rate_source(&self) -> Option<CachedRateProvider<Box<dyn RateProvider>>> {
+        self.fiat_currency.as_ref()?;
+        let (provider, ttl): (Box<dyn RateProvider>, u64) = match &self.rate_provider {
+            RateProviderSettings::None => return None,
+            RateProviderSettings::Http { endpoint, ttl_secs } => {
+                (Box::new(HttpRateProvider::new(endpoint.clone())), *ttl_secs)
+            }
+            RateProviderSettings::Fixed { price_per_nova } => (
+                Box::new(FixedRateProvider::new(*price_per_nova)),
+                u64::MAX,
+            ),
+        };
+        Some(CachedRateProvider::new(provider, Duration::from_secs(ttl)))
+    }
+
+    /// Look up the current rate for the preferred currency, if any.
+    pub fn current_rate<P: RateProvider>(
+        &self,
+        source: Option<&CachedRateProvider<P>>,
+    ) -> Option<FiatRate> {
+        let currency = self.fiat_currency.as_ref()?;
+        source?.rate(currency)
+    }
+
+    /// Primary amount followed by the approximate fiat value, if enabled.
+    pub fn format_with_fiat(&self, amount: u64, rate: Option<&FiatRate>) -> String {
+        let primary = format_amount(amount, self.primary_unit);
+        match &self.fiat_currency {
+            None => primary,
+            Some(currency) => format!("{} ({})", primary, format_fiat(amount, currency, rate)),
+        }
+    }
+}
+
+/// Format an amount in the given unit.
+pub fn format_amount(amount: u64, unit: DisplayUnit) -> String {
+    match unit {
+        DisplayUnit::Nova => format!(
+            "{}.{:08} NOVA",
+            amount / NOVA_UNITS_PER_NOVA,
+            amount % NOVA_UNITS_PER_NOVA
+        ),
+        DisplayUnit::NovaUnits => format!("{} nova units", amount),
+    }
+}
+
+/// Format a signed amount (e.g. net flow) in the given unit.
+pub fn format_signed_amount(amount: i64, unit: DisplayUnit) -> String {
+    let formatted = format_amount(amount.unsigned_abs(), unit);
+    if amount < 0 {
+        format!("-{}", formatted)
+    } else {
+        formatted
+    }
+}
+
+/// Approximate fiat value, marked with "≈" and the rate's timestamp, or
+/// "— XXX" when no rate is available.
+pub fn format_fiat(amount: u64, currency: &str, rate: Option<&FiatRate>) -> String {
+    let currency = currency.to_uppercase();
+    match rate.filter(|r| r.currency.eq_ignore_ascii_case(&currency)) {
+        Some(rate) => {
+            let value = amount as f64 / NOVA_UNITS_PER_NOVA as f64 * rate.price_per_nova;
+            format!(
+                "≈ {:.2} {} @ {}",
+                value,
+                currency,
+                rate.as_of.format("%Y-%m-%d %H:%M UTC")
+            )
+        }
+        None => format!("{} {}", UNAVAILABLE, currency),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn rate(price: f64) -> FiatRate {
+        FiatRate {
+            currency: "USD".to_string(),
+            price_per_nova: price,
+            as_of: Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn formats_both_units() {
+        assert_eq!(format_amount(150_000_000, DisplayUnit::Nova), "1.50000000 NOVA");
+        assert_eq!(format_amount(1, DisplayUnit::Nova), "0.00000001 NOVA");
+        assert_eq!(
+            format_amount(150_000_000, DisplayUnit::NovaUnits),
+            "150000000 nova units"
+        );
+        assert_eq!(
+            format_signed_amount(-50_000_000, DisplayUnit::Nova),
+            "-0.50000000 NOVA"
+        );
+    }
+
+    #[test]
+    fn fiat_is_marked_approximate_with_timestamp() {
+        let prefs = DisplayPreferences {
+            fiat_currency: Some("usd".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            prefs.format_with_fiat(250_000_000, Some(&rate(2.0))),
+            "2.50000000 NOVA (≈ 5.00 USD @ 2026-03-01 09:30 UTC)"
+        );
+        assert_eq!(
+            prefs.format_with_fiat(250_000_000, None),
+            "2.50000000 NOVA (— USD)"
+        );
+        // No fiat currency configured: primary unit only.
+        assert_eq!(
+            DisplayPreferences::default().format_with_fiat(1, Some(&rate(2.0))),
+            "0.00000001 NOVA"
+        );
+    }
+
+    #[test]
+    fn fiat_is_optional() {
+        let prefs = DisplayPreferences::default();
+        assert!(prefs.build_rate_source().is_none());
+
+        let fixed = DisplayPreferences {
+            fiat_currency: Some("EUR".to_string()),
+            rate_provider: RateProviderSettings::Fixed { price_per_nova: 3.0 },
+            ..Default::default()
+        };
+        let source = fixed.build_rate_source();
+        let current = fixed.current_rate(source.as_ref()).unwrap();
+        assert_eq!(current.currency, "EUR");
+        assert_eq!(current.price_per_nova, 3.0);
+    }
+}
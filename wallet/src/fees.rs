@@ -0,0 +1,104 @@
+//! Fee rate estimates from the connected node.
+//!
+//! The send screen offers the node's low, normal and high priority rates as
+//! presets. Estimates are advisory: when the node is unreachable the user
+//! enters a rate by hand.
+
+use serde::Deserialize;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Confirmation target asked of the node, in blocks
+pub const DEFAULT_TARGET_BLOCKS: u32 = 6;
+
+#[derive(Error, Debug)]
+pub enum FeeError {
+    #[error("HTTP error: {0}")]
+    Http(String),
+    #[error("Malformed fee estimate: {0}")]
+    Malformed(String),
+}
+
+/// Fee rates in nova units per byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct FeeRates {
+    pub low_priority: u64,
+    pub normal_priority: u64,
+    pub high_priority: u64,
+}
+
+/// Source of fee rate estimates.
+pub trait FeeEstimator: Send + Sync {
+    fn fee_rates(&self) -> Result<FeeRates, FeeError>;
+}
+
+impl<E: FeeEstimator + ?Sized> FeeEstimator for Box<E> {
+    fn fee_rates(&self) -> Result<FeeRates, FeeError> {
+        (**self).fee_rates()
+    }
+}
+
+/// Reads the node's `/api/v1/mempool/fees`.
+#[derive(Debug, Clone)]
+pub struct HttpFeeEstimator {
+    node_url: String,
+    target_blocks: u32,
+    timeout: Duration,
+}
+
+impl HttpFeeEstimator {
+    pub fn new(node_url: impl Into<String>) -> Self {
+        Self {
+            node_url: node_url.into(),
+            target_blocks: DEFAULT_TARGET_BLOCKS,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Node named by `SUPERNOVA_NODE_URL`, as used for broadcasting.
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("SUPERNOVA_NODE_URL")
+                .unwrap_or_else(|_| "http://localhost:9332".to_string()),
+        )
+    }
+
+    pub fn with_target_blocks(mut self, target_blocks: u32) -> Self {
+        self.target_blocks = target_blocks;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl FeeEstimator for HttpFeeEstimator {
+    fn fee_rates(&self) -> Result<FeeRates, FeeError> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| FeeError::Http(e.to_string()))?;
+        let response = client
+            .get(format!(
+                "{}/api/v1/mempool/fees?target_blocks={}",
+                self.node_url.trim_end_matches('/'),
+                self.target_blocks
+            ))
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| FeeError::Http(e.to_string()))?;
+        let rates: FeeRates = response
+            .json()
+            .map_err(|e| FeeError::Malformed(e.to_string()))?;
+        if rates.low_priority > rates.normal_priority || rates.normal_priority > rates.high_priority
+        {
+            return Err(FeeError::Malformed(format!(
+                "rates out of order: {} / {} / {}",
+                rates.low_priority, rates.normal_priority, rates.high_priority
+            )));
+        }
+        Ok(rates)
+    }
+}
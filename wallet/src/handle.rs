@@ -0,0 +1,615 @@
+//! Thread-safe, cloneable wallet handle.
+//!
+//! [`WalletManager`](crate::WalletManager) owns its parts and needs `&mut self`
+//! for every change, so one task cannot sync while another serves balance
+//! queries. A [`WalletHandle`] puts each part behind its own `RwLock` and can
+//! be cloned into the TUI, a background sync task and notification hooks.
+//! Read paths take shared locks and return owned data; write paths take
+//! exclusive locks only on the parts they change.
+//!
+//! # Lock order
+//!
+//! Locks are always acquired in this order, and any subset must respect it:
+//!
+//! 1. HD wallet (accounts, addresses, spending policies)
+//! 2. transaction history
+//! 3. UTXO cache
+//! 4. balance cache (internal to [`BalanceCache`], never held across calls)
+//!
+//! An operation that touches several parts holds every lock it needs for its
+//! whole duration, so readers never observe a history entry without its
+//! UTXO changes or vice versa. Persistence happens while the write lock is
+//! held, which serializes file writes; each file is replaced by atomic rename.
+//!
+//! Balance queries are answered from a [`BalanceCache`] kept current by the
+//! sync and send paths; [`WalletHandle::apply_reorg`] invalidates it.
+
+use crate::balance_cache::BalanceCache;
+use crate::hdwallet::{AccountType, AddressFilter, HDAccount, HDAddress, HDWallet};
+use crate::history::{
+    ExportedTransaction, HistoryExportOptions, TransactionHistory, TransactionRecord,
+    TransactionStatus,
+};
+use crate::policy::{Destination, PolicyDecision};
+use crate::WalletError;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use supernova_core::storage::utxo_set::{UtxoEntry, UtxoSet};
+use supernova_core::types::transaction::OutPoint;
+
+/// Changes discovered by one sync step, applied atomically.
+#[derive(Debug, Clone, Default)]
+pub struct SyncUpdate {
+    /// New outputs paying this wallet
+    pub created: Vec<UtxoEntry>,
+    /// Wallet outputs spent on chain
+    pub spent: Vec<OutPoint>,
+    /// History records for the transactions involved
+    pub transactions: Vec<TransactionRecord>,
+}
+
+/// Undo of blocks that left the best chain, applied atomically.
+#[derive(Debug, Clone, Default)]
+pub struct ReorgUpdate {
+    /// Wallet outputs created by the disconnected blocks
+    pub disconnected: Vec<OutPoint>,
+    /// Wallet outputs the disconnected blocks spent, unspent again
+    pub restored: Vec<UtxoEntry>,
+    /// Transactions that are no longer confirmed
+    pub unconfirmed: Vec<String>,
+}
+
+/// A consistent view of balances and history totals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletSnapshot {
+    /// Balance of active accounts
+    pub total_balance: u64,
+    /// Balance of archived accounts, not included in `total_balance`
+    pub archived_balance: u64,
+    pub total_received: u64,
+    pub total_sent: u64,
+    pub total_fees: u64,
+    pub transaction_count: usize,
+}
+
+/// Shareable handle to a wallet. Cloning is cheap and every clone refers to
+/// the same wallet.
+#[derive(Clone)]
+pub struct WalletHandle {
+    hd_wallet: Arc<RwLock<HDWallet>>,
+    history: Arc<RwLock<TransactionHistory>>,
+    utxos: Arc<RwLock<UtxoSet>>,
+    balances: Arc<BalanceCache>,
+}
+
+fn read<'a, T>(lock: &'a RwLock<T>, name: &'static str) -> Result<RwLockReadGuard<'a, T>, WalletError> {
+    lock.read().map_err(|_| WalletError::LockPoisoned(name))
+}
+
+fn write<'a, T>(lock: &'a RwLock<T>, name: &'static str) -> Result<RwLockWriteGuard<'a, T>, WalletError> {
+    lock.write().map_err(|_| WalletError::LockPoisoned(name))
+}
+
+fn apply_utxo_changes(
+    utxos: &UtxoSet,
+    created: Vec<UtxoEntry>,
+    spent: &[OutPoint],
+) -> Result<(), WalletError> {
+    for outpoint in spent {
+        utxos.remove(outpoint).map_err(WalletError::Utxo)?;
+    }
+    // Data carriers can never be spent, so they never enter the cache
+    for entry in created.into_iter().filter(|e| !e.output.is_unspendable()) {
+        utxos.add(entry).map_err(WalletError::Utxo)?;
+    }
+    Ok(())
+}
+
+impl WalletHandle {
+    pub fn new(hd_wallet: HDWallet, history: TransactionHistory, utxos: UtxoSet) -> Self {
+        Self {
+            hd_wallet: Arc::new(RwLock::new(hd_wallet)),
+            history: Arc::new(RwLock::new(history)),
+            utxos: Arc::new(RwLock::new(utxos)),
+            balances: Arc::new(BalanceCache::new()),
+        }
+    }
+
+    /// Check every balance and history query against a full recompute.
+    /// For tests; a mismatch panics.
+    pub fn set_verify_caches(&self, verify: bool) -> Result<(), WalletError> {
+        self.balances.set_verify(verify);
+        self.history_mut()?.set_verify_stats(verify);
+        Ok(())
+    }
+
+    fn hd(&self) -> Result<RwLockReadGuard<'_, HDWallet>, WalletError> {
+        read(&self.hd_wallet, "hd wallet")
+    }
+
+    fn hd_mut(&self) -> Result<RwLockWriteGuard<'_, HDWallet>, WalletError> {
+        write(&self.hd_wallet, "hd wallet")
+    }
+
+    fn history(&self) -> Result<RwLockReadGuard<'_, TransactionHistory>, WalletError> {
+        read(&self.history, "history")
+    }
+
+    fn history_mut(&self) -> Result<RwLockWriteGuard<'_, TransactionHistory>, WalletError> {
+        write(&self.history, "history")
+    }
+
+    fn utxos(&self) -> Result<RwLockReadGuard<'_, UtxoSet>, WalletError> {
+        read(&self.utxos, "utxo cache")
+    }
+
+    fn utxos_mut(&self) -> Result<RwLockWriteGuard<'_, UtxoSet>, WalletError> {
+        write(&self.utxos, "utxo cache")
+    }
+
+    // ---- Read paths ----
+
+    pub fn get_balance(&self, account_name: &str) -> Result<u64, WalletError> {
+        let hd = self.hd()?;
+        let utxos = self.utxos()?;
+        Ok(self.balances.balance(&hd, &utxos, account_name)?)
+    }
+
+    pub fn get_total_balance(&self) -> Result<u64, WalletError> {
+        let hd = self.hd()?;
+        let utxos = self.utxos()?;
+        Ok(self.balances.balances(&hd, &utxos)?.active)
+    }
+
+    pub fn list_accounts(&self, include_archived: bool) -> Result<Vec<(u32, HDAccount)>, WalletError> {
+        Ok(self
+            .hd()?
+            .list_accounts(include_archived)
+            .into_iter()
+            .map(|(index, account)| (index, account.clone()))
+            .collect())
+    }
+
+    pub fn list_addresses(&self, account_name: &str, filter: AddressFilter) -> Result<Vec<HDAddress>, WalletError> {
+        Ok(self
+            .hd()?
+            .list_addresses(account_name, filter)?
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    pub fn get_address_count(&self) -> Result<usize, WalletError> {
+        Ok(self.hd()?.get_address_count())
+    }
+
+    pub fn get_transaction(&self, hash: &str) -> Result<Option<TransactionRecord>, WalletError> {
+        Ok(self.history()?.get_transaction(hash).cloned())
+    }
+
+    pub fn get_recent_transactions(&self, count: usize) -> Result<Vec<TransactionRecord>, WalletError> {
+        Ok(self
+            .history()?
+            .get_recent_transactions(count)
+            .into_iter()
+            .cloned()
+            .collect())
+    }
+
+    pub fn get_transaction_memo(&self, hash: &str) -> Result<Option<String>, WalletError> {
+        Ok(self.history()?.get_transaction_memo(hash))
+    }
+
+    pub fn export_history(
+        &self,
+        options: HistoryExportOptions,
+    ) -> Result<Vec<ExportedTransaction>, WalletError> {
+        Ok(self.history()?.export_entries(options))
+    }
+
+    /// Balances and history totals taken under all three read locks, so the
+    /// two always describe the same state.
+    pub fn snapshot(&self) -> Result<WalletSnapshot, WalletError> {
+        let hd = self.hd()?;
+        let history = self.history()?;
+        let utxos = self.utxos()?;
+        let balances = self.balances.balances(&hd, &utxos)?;
+        let stats = history.stats();
+        Ok(WalletSnapshot {
+            total_balance: balances.active,
+            archived_balance: balances.archived,
+            total_received: stats.total_received,
+            total_sent: stats.total_sent,
+            total_fees: stats.total_fees,
+            transaction_count: stats.transaction_count,
+        })
+    }
+
+    // ---- Write paths ----
+
+    pub fn create_account(&self, name: String, account_type: AccountType) -> Result<(), WalletError> {
+        Ok(self.hd_mut()?.create_account(name, account_type)?)
+    }
+
+    pub fn get_new_address(&self, account_name: &str) -> Result<HDAddress, WalletError> {
+        Ok(self.hd_mut()?.get_new_address(account_name)?)
+    }
+
+    pub fn label_address(&self, address: &str, label: &str) -> Result<(), WalletError> {
+        Ok(self.hd_mut()?.label_address(address, label)?)
+    }
+
+    pub fn archive_account(&self, account_name: &str, confirm_nonzero: bool) -> Result<(), WalletError> {
+        let mut hd = self.hd_mut()?;
+        let utxos = self.utxos()?;
+        Ok(hd.archive_account(account_name, &utxos, confirm_nonzero)?)
+    }
+
+    pub fn unarchive_account(&self, account_name: &str) -> Result<(), WalletError> {
+        Ok(self.hd_mut()?.unarchive_account(account_name)?)
+    }
+
+    pub fn purge_watch(&self, account_name: &str, confirm: bool) -> Result<(), WalletError> {
+        Ok(self.hd_mut()?.purge_watch(account_name, confirm)?)
+    }
+
+    pub fn add_transaction(&self, record: TransactionRecord) -> Result<(), WalletError> {
+        let mut hd = self.hd_mut()?;
+        hd.mark_received(std::slice::from_ref(&record))?;
+        Ok(self.history_mut()?.add_transaction(record)?)
+    }
+
+    pub fn update_transaction_status(
+        &self,
+        hash: &str,
+        status: TransactionStatus,
+    ) -> Result<(), WalletError> {
+        Ok(self.history_mut()?.update_transaction_status(hash, status)?)
+    }
+
+    pub fn add_transaction_memo(&self, hash: &str, text: &str) -> Result<(), WalletError> {
+        Ok(self.history_mut()?.add_transaction_memo(hash, text)?)
+    }
+
+    /// Apply one sync step: history records and UTXO changes become visible
+    /// together. Addresses paid by received records are marked used.
+    pub fn apply_sync(&self, update: SyncUpdate) -> Result<(), WalletError> {
+        let mut hd = self.hd_mut()?;
+        let mut history = self.history_mut()?;
+        let utxos = self.utxos_mut()?;
+        hd.mark_received(&update.transactions)?;
+        if !update.transactions.is_empty() {
+            history.add_transactions(update.transactions)?;
+        }
+        self.balances
+            .apply(&hd, &utxos, &update.created, &update.spent);
+        apply_utxo_changes(&utxos, update.created, &update.spent)
+    }
+
+    /// Undo blocks that left the best chain. Their outputs are removed, the
+    /// outputs they spent come back and their transactions return to
+    /// pending. Cached balances are rebuilt on the next query.
+    pub fn apply_reorg(&self, update: ReorgUpdate) -> Result<(), WalletError> {
+        let _hd = self.hd()?;
+        let mut history = self.history_mut()?;
+        let utxos = self.utxos_mut()?;
+        for hash in &update.unconfirmed {
+            match history.update_transaction_status(hash, TransactionStatus::Pending) {
+                Ok(()) | Err(crate::history::HistoryError::TransactionNotFound) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let result = apply_utxo_changes(&utxos, update.restored, &update.disconnected);
+        self.balances.invalidate();
+        result
+    }
+
+    /// Record an outgoing payment after checking it against the account's
+    /// spending policy. Nothing is recorded unless the policy approves it.
+    pub fn record_send(
+        &self,
+        account_name: &str,
+        destination: &Destination,
+        record: TransactionRecord,
+        spent: Vec<OutPoint>,
+        change: Vec<UtxoEntry>,
+    ) -> Result<PolicyDecision, WalletError> {
+        let mut hd = self.hd_mut()?;
+        let mut history = self.history_mut()?;
+        let utxos = self.utxos_mut()?;
+
+        let decision = hd.authorize_spend(account_name, destination, record.amount)?;
+        if matches!(decision, PolicyDecision::Approved) {
+            history.add_transaction(record)?;
+            self.balances.apply(&hd, &utxos, &change, &spent);
+            apply_utxo_changes(&utxos, change, &spent)?;
+        }
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::TransactionDirection;
+    use bitcoin::network::Network;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+    use supernova_core::script::ScriptBuilder;
+    use supernova_core::types::transaction::TransactionOutput;
+    use tempfile::tempdir;
+
+    const TEST_MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn record(hash: String, direction: TransactionDirection, amount: u64, fee: u64) -> TransactionRecord {
+        TransactionRecord {
+            hash,
+            timestamp: chrono::Utc::now(),
+            direction,
+            amount,
+            fee,
+            status: TransactionStatus::Confirmed(1),
+            label: None,
+            category: None,
+            tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
+        }
+    }
+
+    fn utxo(id: u32, amount: u64, script: &[u8]) -> UtxoEntry {
+        let mut txid = [0u8; 32];
+        txid[..4].copy_from_slice(&id.to_be_bytes());
+        UtxoEntry {
+            outpoint: OutPoint { txid, vout: 0 },
+            output: TransactionOutput::new(amount, script.to_vec()),
+            height: id,
+            is_coinbase: false,
+            is_confirmed: true,
+        }
+    }
+
+    #[test]
+    fn concurrent_sync_reads_and_send_stay_consistent() {
+        let dir = tempdir().unwrap();
+        let hd = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("wallet.json"),
+        )
+        .unwrap();
+        let history = TransactionHistory::new(dir.path().join("history.json")).unwrap();
+        let handle = WalletHandle::new(hd, history, UtxoSet::new_in_memory(1000));
+
+        handle
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let address = handle.get_new_address("main").unwrap();
+        let script = bitcoin::Address::from_str(address.get_address())
+            .unwrap()
+            .assume_checked()
+            .script_pubkey()
+            .as_bytes()
+            .to_vec();
+
+        // Pre-fund the outputs the sender will spend.
+        const SENDS: u32 = 5;
+        let funding: Vec<UtxoEntry> = (0..SENDS).map(|i| utxo(i, 100, &script)).collect();
+        handle
+            .apply_sync(SyncUpdate {
+                transactions: (0..SENDS)
+                    .map(|i| record(format!("fund-{}", i), TransactionDirection::Received, 100, 0))
+                    .collect(),
+                created: funding.clone(),
+                spent: vec![],
+            })
+            .unwrap();
+
+        const SYNC_STEPS: u32 = 200;
+        let stop = Arc::new(AtomicBool::new(false));
+        let (done_tx, done_rx) = mpsc::channel::<&'static str>();
+        let mut workers = Vec::new();
+
+        // Sync loop: each step receives one new output.
+        {
+            let handle = handle.clone();
+            let script = script.clone();
+            let done = done_tx.clone();
+            workers.push(thread::spawn(move || {
+                for step in 0..SYNC_STEPS {
+                    let id = 1_000 + step;
+                    handle
+                        .apply_sync(SyncUpdate {
+                            created: vec![utxo(id, 100, &script)],
+                            spent: vec![],
+                            transactions: vec![record(
+                                format!("sync-{}", step),
+                                TransactionDirection::Received,
+                                100,
+                                0,
+                            )],
+                        })
+                        .unwrap();
+                }
+                done.send("sync").unwrap();
+            }));
+        }
+
+        // Sender: spends each funding output as 60 out, 10 fee, 30 change.
+        {
+            let handle = handle.clone();
+            let script = script.clone();
+            let done = done_tx.clone();
+            workers.push(thread::spawn(move || {
+                for (i, entry) in funding.into_iter().enumerate() {
+                    let decision = handle
+                        .record_send(
+                            "main",
+                            &Destination::address("tb1qexternal"),
+                            record(format!("send-{}", i), TransactionDirection::Sent, 60, 10),
+                            vec![entry.outpoint],
+                            vec![utxo(10_000 + i as u32, 30, &script)],
+                        )
+                        .unwrap();
+                    assert!(matches!(decision, PolicyDecision::Approved));
+                }
+                done.send("send").unwrap();
+            }));
+        }
+
+        // Readers: every snapshot must balance and history never shrinks.
+        for _ in 0..3 {
+            let handle = handle.clone();
+            let stop = stop.clone();
+            let done = done_tx.clone();
+            workers.push(thread::spawn(move || {
+                let mut last_count = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    let snap = handle.snapshot().unwrap();
+                    assert_eq!(
+                        snap.total_balance,
+                        snap.total_received - snap.total_sent - snap.total_fees,
+                        "inconsistent snapshot {:?}",
+                        snap
+                    );
+                    assert!(snap.transaction_count >= last_count);
+                    last_count = snap.transaction_count;
+                    handle.get_balance("main").unwrap();
+                    handle.get_recent_transactions(10).unwrap();
+                }
+                done.send("reader").unwrap();
+            }));
+        }
+        drop(done_tx);
+
+        // Writers must finish well within the timeout, or something deadlocked.
+        for _ in 0..2 {
+            done_rx
+                .recv_timeout(Duration::from_secs(60))
+                .expect("writer did not finish: possible deadlock");
+        }
+        stop.store(true, Ordering::Relaxed);
+        for _ in 0..3 {
+            done_rx
+                .recv_timeout(Duration::from_secs(10))
+                .expect("reader did not finish: possible deadlock");
+        }
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let snap = handle.snapshot().unwrap();
+        assert_eq!(snap.transaction_count, (2 * SENDS + SYNC_STEPS) as usize);
+        assert_eq!(
+            snap.total_balance,
+            u64::from(SYNC_STEPS) * 100 + u64::from(SENDS) * 30
+        );
+    }
+
+    #[test]
+    fn data_outputs_never_count_towards_balances() {
+        let dir = tempdir().unwrap();
+        let hd = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("wallet.json"),
+        )
+        .unwrap();
+        let history = TransactionHistory::new(dir.path().join("history.json")).unwrap();
+        let handle = WalletHandle::new(hd, history, UtxoSet::new_in_memory(1000));
+        handle.set_verify_caches(true).unwrap();
+        handle
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let address = handle.get_new_address("main").unwrap();
+        let script = bitcoin::Address::from_str(address.get_address())
+            .unwrap()
+            .assume_checked()
+            .script_pubkey()
+            .as_bytes()
+            .to_vec();
+
+        // A sync source that reports a data carrier (here one carrying value)
+        // alongside a real payment
+        let carrier = utxo(2, 700, &ScriptBuilder::data_carrier(b"anchor"));
+        assert!(carrier.output.is_unspendable());
+        handle
+            .apply_sync(SyncUpdate {
+                created: vec![utxo(1, 500, &script), carrier],
+                spent: vec![],
+                transactions: vec![],
+            })
+            .unwrap();
+
+        assert_eq!(handle.get_balance("main").unwrap(), 500);
+        assert_eq!(handle.snapshot().unwrap().total_balance, 500);
+        assert_eq!(handle.utxos.read().unwrap().get_count(), 1);
+    }
+
+    #[test]
+    fn reorg_restores_balances_and_history() {
+        let dir = tempdir().unwrap();
+        let hd = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("wallet.json"),
+        )
+        .unwrap();
+        let history = TransactionHistory::new(dir.path().join("history.json")).unwrap();
+        let handle = WalletHandle::new(hd, history, UtxoSet::new_in_memory(1000));
+        handle.set_verify_caches(true).unwrap();
+
+        handle
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let address = handle.get_new_address("main").unwrap();
+        let script = bitcoin::Address::from_str(address.get_address())
+            .unwrap()
+            .assume_checked()
+            .script_pubkey()
+            .as_bytes()
+            .to_vec();
+
+        let funding = utxo(1, 500, &script);
+        handle
+            .apply_sync(SyncUpdate {
+                created: vec![funding.clone()],
+                spent: vec![],
+                transactions: vec![record("fund".to_string(), TransactionDirection::Received, 500, 0)],
+            })
+            .unwrap();
+        assert_eq!(handle.get_balance("main").unwrap(), 500);
+
+        // A block spends the funding output and pays 200 back to us
+        let received = utxo(2, 200, &script);
+        handle
+            .apply_sync(SyncUpdate {
+                created: vec![received.clone()],
+                spent: vec![funding.outpoint],
+                transactions: vec![record("block".to_string(), TransactionDirection::Received, 200, 0)],
+            })
+            .unwrap();
+        assert_eq!(handle.get_total_balance().unwrap(), 200);
+
+        // That block is disconnected
+        handle
+            .apply_reorg(ReorgUpdate {
+                disconnected: vec![received.outpoint],
+                restored: vec![funding],
+                unconfirmed: vec!["block".to_string(), "unknown".to_string()],
+            })
+            .unwrap();
+        assert_eq!(handle.get_balance("main").unwrap(), 500);
+        assert_eq!(handle.snapshot().unwrap().total_balance, 500);
+        assert!(matches!(
+            handle.get_transaction("block").unwrap().unwrap().status,
+            TransactionStatus::Pending
+        ));
+    }
+}
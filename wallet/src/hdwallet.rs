@@ -1,19 +1,37 @@
 use super::backup_warning::{BackupMetadata, BackupStatus, BackupWarning, SeedPhraseVerifier};
+use super::history::{TransactionDirection, TransactionRecord};
 use super::password_strength::PasswordStrengthChecker;
+use super::policy::{
+    derive_policy_key, Approval, Destination, PendingSpend, PolicyDecision, PolicyStore,
+    PolicyViolation, SpendingPolicy,
+};
+use super::seed_vault::{join_seed, split_seed, KdfParams, SealedSeed};
 use bip39::{Language, Mnemonic};
 use bitcoin as btc_compat; // Bitcoin-compatible
 use btc_compat::{
-    bip32::{ChildNumber, DerivationPath, Xpriv},
+    bip32::{ChildNumber, DerivationPath, Xpriv, Xpub},
     network::Network,
     secp256k1::Secp256k1,
     Address, PrivateKey,
 };
 use chrono::Utc;
-use supernova_core::storage::utxo_set::UtxoSet;
+use hkdf::Hkdf;
+use ml_dsa::{KeyGen, MlDsa65, B32};
+use sha2::Sha512;
+use sha3::{Digest, Sha3_512};
+use slh_dsa::{Shake192f, SigningKey as SlhDsaSigningKey};
+use supernova_core::crypto::quantum::QuantumScheme;
+use supernova_core::script::classify::{address_to_script, ScriptClass};
+use supernova_core::storage::utxo_set::{UtxoEntry, UtxoSet};
 use rand::rngs::OsRng;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use aes_gcm::{
     aead::{Aead, KeyInit, OsRng as AesOsRng},
@@ -23,35 +41,108 @@ use argon2::{Argon2, Algorithm, Version, Params, PasswordHasher};
 use argon2::password_hash::{SaltString, PasswordHash};
 use zeroize::Zeroizing;
 
+/// BIP44 chain of receiving addresses
+const EXTERNAL_CHAIN: u32 = 0;
+/// BIP44 chain of change addresses
+const INTERNAL_CHAIN: u32 = 1;
+
+/// Account a watch-only wallet derives its addresses in
+pub const WATCH_ONLY_ACCOUNT: &str = "watch-only";
+
+/// Unused receive addresses an account may have past its last used one
+/// (BIP44); restores derive this far ahead
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// Longest address label, in bytes
+pub const MAX_ADDRESS_LABEL_LEN: usize = 100;
+
+/// Depth of an account-level key, m/44'/coin'/account'
+const ACCOUNT_KEY_DEPTH: u8 = 3;
+
+/// HKDF info prefix for quantum account keys; the scheme and derivation
+/// path are appended so every keypair has its own context
+const QUANTUM_KEY_INFO: &str = "supernova/wallet/quantum-key/v1";
+
+/// SLH-DSA-SHAKE-192f security parameter `n`, the length of each key seed
+const SLH_DSA_SEED_LEN: usize = 24;
+
+/// Locking script paid by `address`: a Bitcoin-style script for classical
+/// addresses, the bare public key commitment for quantum (bech32m) ones
+pub(crate) fn address_script(address: &str) -> Result<Vec<u8>, HDWalletError> {
+    match Address::from_str(address) {
+        Ok(address) => Ok(address.assume_checked().script_pubkey().as_bytes().to_vec()),
+        Err(classical) => address_to_script(address).map_err(|quantum| {
+            HDWalletError::AddressParsing(format!("{}; {}", classical, quantum))
+        }),
+    }
+}
+
+/// Name of the parameter set a quantum account derives its keys for.
+/// Only schemes with seeded key generation can back an HD account.
+fn quantum_parameter_set(scheme: QuantumScheme) -> Result<&'static str, HDWalletError> {
+    match scheme {
+        QuantumScheme::Dilithium => Ok("ml-dsa-65"),
+        QuantumScheme::SphincsPlus => Ok("slh-dsa-shake-192f"),
+        QuantumScheme::Falcon | QuantumScheme::Hybrid(_) => Err(
+            HDWalletError::UnsupportedScheme(format!("{:?}", scheme)),
+        ),
+    }
+}
+
+/// Address of a quantum public key: its SHA3-512 commitment, bech32m-encoded
+fn quantum_address(public_key: &[u8]) -> Result<String, HDWalletError> {
+    let mut commitment = [0u8; 32];
+    commitment.copy_from_slice(&Sha3_512::digest(public_key)[..32]);
+    ScriptClass::QuantumPkh {
+        commitment,
+        scheme: None,
+    }
+    .address()
+    .ok_or_else(|| HDWalletError::Compatibility("cannot encode quantum address".to_string()))
+}
+
 /// Write wallet material to `path` with owner-only (0o600) permissions.
 ///
 /// SECURITY (R5-97): The default `std::fs::write` honors the process umask,
 /// which typically yields world-readable (0o644) files. Wallet files can
 /// contain the BIP39 mnemonic or a cleartext WIF private key, so any local user
-/// could read them. On Unix we create/truncate the file with mode 0o600 and
-/// additionally reset the permissions in case the file pre-existed with looser
-/// bits. On non-Unix platforms this falls back to a plain write.
-fn write_wallet_file_secure(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
+/// could read them. On Unix the data is written with mode 0o600. On non-Unix
+/// platforms this falls back to default permissions.
+///
+/// The contents go to a sibling temporary file that is synced and then
+/// renamed over `path`, so a crash or a concurrent reader never observes a
+/// truncated wallet. Callers serialize writes to the same path (the
+/// [`WalletHandle`](crate::WalletHandle) does so by holding the write lock).
+pub(crate) fn write_wallet_file_secure(path: &std::path::Path, contents: &[u8]) -> std::io::Result<()> {
     use std::io::Write;
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(format!(".tmp-{}", std::process::id()));
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
     #[cfg(unix)]
     {
-        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
-        let mut file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .mode(0o600)
-            .open(path)?;
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let written = options.open(&tmp_path).and_then(|mut file| {
         // Reset perms explicitly: `.mode()` only applies when the file is newly
-        // created, so an existing 0o644 wallet would otherwise keep loose bits.
-        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        // created, so a leftover temp file would otherwise keep loose bits.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
         file.write_all(contents)?;
-        file.flush()?;
-        Ok(())
-    }
-    #[cfg(not(unix))]
-    {
-        std::fs::write(path, contents)
+        file.sync_all()
+    });
+    match written.and_then(|_| std::fs::rename(&tmp_path, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&tmp_path);
+            Err(e)
+        }
     }
 }
 
@@ -81,6 +172,34 @@ pub enum HDWalletError {
     BackupVerificationFailed(String),
     #[error("Key derivation error: {0}")]
     KeyDerivationError(String),
+    #[error("Spending policy: {0}")]
+    Policy(#[from] PolicyViolation),
+    #[error("Account is archived: {0}")]
+    AccountArchived(String),
+    #[error("Account {account} holds {balance} and needs confirmation to archive")]
+    ArchiveNeedsConfirmation { account: String, balance: u64 },
+    #[error("Account is watch-only: {0}")]
+    WatchOnly(String),
+    #[error("Account is not watch-only: {0}")]
+    NotWatchOnly(String),
+    #[error("Confirmation required to purge watch-only account: {0}")]
+    PurgeNeedsConfirmation(String),
+    #[error("Quantum scheme cannot back an HD account: {0}")]
+    UnsupportedScheme(String),
+    #[error("Wallet is watch-only: {0} needs private keys")]
+    WatchOnlyWallet(&'static str),
+    #[error("Unknown derivation path: {0}")]
+    UnknownDerivationPath(String),
+    #[error("Wallet is locked: {0} needs the passphrase")]
+    Locked(&'static str),
+    #[error("Wrong wallet passphrase")]
+    WrongPassphrase,
+    #[error("Account {account} has {gap_limit} unused addresses; one must be used first")]
+    GapLimitReached { account: String, gap_limit: u32 },
+    #[error("Gap limit must be at least 1")]
+    InvalidGapLimit,
+    #[error("Address label is {0} bytes, the maximum is {MAX_ADDRESS_LABEL_LEN}")]
+    LabelTooLong(usize),
 }
 // SECURITY FIX (P2-008): Encrypted Wallet Backup Structure
 // ============================================================================
@@ -107,6 +226,10 @@ struct EncryptedBackup {
 /// Default version for legacy backups without version field
 fn default_version() -> u32 { 1 }
 
+fn default_gap_limit() -> u32 {
+    DEFAULT_GAP_LIMIT
+}
+
 /// HD Wallet with secure mnemonic storage
 ///
 /// SECURITY FIX (P1-002): Mnemonic now uses Zeroizing<String> wrapper
@@ -116,14 +239,51 @@ fn default_version() -> u32 { 1 }
 /// Each clone gets its own zeroized copy that will be cleared on drop.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct HDWallet {
-    /// Master mnemonic - wrapped in Zeroizing for secure memory erasure
-    #[serde(serialize_with = "serialize_zeroizing", deserialize_with = "deserialize_zeroizing")]
+    /// Master mnemonic - wrapped in Zeroizing for secure memory erasure.
+    /// Empty in an encrypted wallet, which keeps it in `sealed_seed`.
+    #[serde(
+        default,
+        skip_serializing_if = "zeroizing_is_empty",
+        serialize_with = "serialize_zeroizing",
+        deserialize_with = "deserialize_zeroizing"
+    )]
     mnemonic: Zeroizing<String>,
+    /// BIP39 passphrase (the "25th word") mixed into the seed; empty for
+    /// none. Like the mnemonic, empty in an encrypted wallet.
+    #[serde(
+        default,
+        skip_serializing_if = "zeroizing_is_empty",
+        serialize_with = "serialize_zeroizing",
+        deserialize_with = "deserialize_zeroizing"
+    )]
+    seed_passphrase: Zeroizing<String>,
+    /// Mnemonic and BIP39 passphrase encrypted under the wallet passphrase
+    /// (see `seed_vault`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_seed: Option<SealedSeed>,
+    /// Decrypted seed of an unlocked encrypted wallet; never persisted
+    #[serde(skip)]
+    unlocked: Option<UnlockedSeed>,
     network: Network,
+    /// Persisted so a watch-only wallet stays watch-only across reloads
+    #[serde(default)]
+    mode: WalletMode,
     accounts: HashMap<String, HDAccount>,
     wallet_path: PathBuf,
     #[serde(default)]
     backup_metadata: BackupMetadata,
+    /// Per-account spending policies, rolling spend trackers and parked
+    /// spends. Policies are sealed with a seed-derived key (see `policy`).
+    #[serde(default)]
+    spending_policies: PolicyStore,
+    /// Unused receive addresses allowed past the last used one before
+    /// `get_new_address` refuses to derive more
+    #[serde(default = "default_gap_limit")]
+    gap_limit: u32,
+    /// Bumped whenever the set of accounts or addresses changes, so caches
+    /// keyed on address ownership can tell they are stale
+    #[serde(skip)]
+    generation: u64,
 }
 
 // SECURITY FIX (R3-61): Manual Debug impl that redacts the master mnemonic.
@@ -135,10 +295,15 @@ impl std::fmt::Debug for HDWallet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("HDWallet")
             .field("mnemonic", &"<redacted>")
+            .field("seed_passphrase", &"<redacted>")
+            .field("encrypted", &self.is_encrypted())
+            .field("locked", &self.is_locked())
             .field("network", &self.network)
+            .field("mode", &self.mode)
             .field("accounts", &self.accounts)
             .field("wallet_path", &self.wallet_path)
             .field("backup_metadata", &self.backup_metadata)
+            .field("spending_policies", &self.spending_policies)
             .finish()
     }
 }
@@ -160,6 +325,27 @@ where
     Ok(Zeroizing::new(s))
 }
 
+fn zeroizing_is_empty(value: &Zeroizing<String>) -> bool {
+    value.is_empty()
+}
+
+/// Mnemonic decrypted by [`HDWallet::unlock`], dropped (and wiped) on
+/// [`HDWallet::lock`]
+#[derive(Clone)]
+struct UnlockedSeed {
+    mnemonic: Zeroizing<String>,
+    seed_passphrase: Zeroizing<String>,
+    /// Auto-lock deadline; `None` stays unlocked until locked explicitly
+    expires: Option<Instant>,
+}
+
+impl UnlockedSeed {
+    fn expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| Instant::now() >= expires)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HDAccount {
     pub name: String,
@@ -174,6 +360,52 @@ pub struct HDAccount {
     /// Next unused BIP44 address index (the `index` level of the external chain).
     #[serde(default)]
     pub next_index: u32,
+    /// Next unused index on the internal (change) chain.
+    #[serde(default)]
+    pub next_change_index: u32,
+    /// Lifecycle state; archived accounts are hidden but kept in sync.
+    #[serde(default)]
+    pub state: AccountState,
+    /// Addresses imported for watching only; no keys derive from our seed.
+    #[serde(default)]
+    pub watch_only: bool,
+    /// Account-level extended public key (m/44'/coin'/account') that
+    /// addresses derive from in a watch-only wallet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xpub: Option<String>,
+}
+
+/// Whether a wallet holds its seed or only public derivation material.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum WalletMode {
+    #[default]
+    Full,
+    /// Built from an account xpub: derives addresses and tracks balances,
+    /// but cannot sign or reveal a seed.
+    WatchOnly,
+}
+
+/// Account lifecycle state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AccountState {
+    #[default]
+    Active,
+    /// Hidden from default listings and totals, excluded from new-address
+    /// generation, synced after active accounts. Restorable at any time.
+    Archived,
+}
+
+/// Balances split by account state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountBalances {
+    pub active: u64,
+    pub archived: u64,
+}
+
+impl AccountBalances {
+    pub fn total(&self) -> u64 {
+        self.active + self.archived
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -186,13 +418,59 @@ pub struct HDAddress {
     /// re-derived on demand; without it, funds sent here would be unspendable.
     #[serde(default)]
     pub index: u32,
+    /// Derived on the internal chain (`m/44'/coin'/account'/1/index`) to
+    /// receive change, rather than handed out for payments.
+    #[serde(default)]
+    pub change: bool,
+    /// Handed out by `get_new_address` and not paid yet
+    #[serde(default)]
+    pub reserved: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Usage of an address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressState {
+    /// Derived ahead (by a rescan) but never handed out or paid
+    Unused,
+    /// Paid on chain
+    Used,
+    /// Handed out and waiting for a payment
+    Reserved,
+}
+
+/// Which addresses `list_addresses` returns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressFilter {
+    #[default]
+    All,
+    /// Addresses in one state
+    State(AddressState),
+    /// Used or reserved: everything but the addresses derived ahead
+    Issued,
+    Labeled,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+impl AddressFilter {
+    fn matches(&self, hd_address: &HDAddress) -> bool {
+        match self {
+            AddressFilter::All => true,
+            AddressFilter::State(state) => hd_address.state() == *state,
+            AddressFilter::Issued => hd_address.state() != AddressState::Unused,
+            AddressFilter::Labeled => hd_address.label.is_some(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AccountType {
     Legacy,
     SegWit,
     NativeSegWit,
+    /// Post-quantum keys (ML-DSA-65 or SLH-DSA-SHAKE-192f) derived from the
+    /// wallet seed, paid to bech32m addresses committing to the public key
+    Quantum(QuantumScheme),
 }
 
 impl HDWallet {
@@ -208,15 +486,26 @@ impl HDWallet {
 
         Ok(Self {
             mnemonic: Zeroizing::new(mnemonic.to_string()),
+            seed_passphrase: Zeroizing::new(String::new()),
+            sealed_seed: None,
+            unlocked: None,
             network,
+            mode: WalletMode::Full,
             accounts: HashMap::new(),
             wallet_path,
             backup_metadata: BackupMetadata::new(),
+            spending_policies: PolicyStore::default(),
+            gap_limit: DEFAULT_GAP_LIMIT,
+            generation: 0,
         })
     }
 
+    /// Restore a wallet from its mnemonic. A BIP39 `seed_passphrase` is
+    /// mixed into the seed as the spec describes, so each passphrase opens
+    /// an entirely different wallet; `None` and `Some("")` are the same.
     pub fn from_mnemonic(
         mnemonic: &str,
+        seed_passphrase: Option<&str>,
         network: Network,
         wallet_path: PathBuf,
     ) -> Result<Self, HDWalletError> {
@@ -224,13 +513,208 @@ impl HDWallet {
             .map_err(|e| HDWalletError::InvalidMnemonic(e.to_string()))?;
         Ok(Self {
             mnemonic: Zeroizing::new(mnemonic.to_string()),
+            seed_passphrase: Zeroizing::new(seed_passphrase.unwrap_or_default().to_string()),
+            sealed_seed: None,
+            unlocked: None,
             network,
+            mode: WalletMode::Full,
             accounts: HashMap::new(),
             wallet_path,
             backup_metadata: BackupMetadata::new(),
+            spending_policies: PolicyStore::default(),
+            gap_limit: DEFAULT_GAP_LIMIT,
+            generation: 0,
         })
     }
 
+    /// Watch-only wallet deriving native segwit addresses from an
+    /// account-level `xpub`, as exported by [`account_xpub`](Self::account_xpub).
+    /// It has no mnemonic: anything needing a private key fails with
+    /// [`HDWalletError::WatchOnlyWallet`].
+    pub fn from_xpub(
+        xpub: &str,
+        network: Network,
+        wallet_path: PathBuf,
+    ) -> Result<Self, HDWalletError> {
+        let parsed =
+            Xpub::from_str(xpub).map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+        if (parsed.network == Network::Bitcoin) != (network == Network::Bitcoin) {
+            return Err(HDWalletError::Compatibility(format!(
+                "xpub is for {}, wallet is for {}",
+                parsed.network, network
+            )));
+        }
+        if parsed.depth != ACCOUNT_KEY_DEPTH {
+            return Err(HDWalletError::KeyDerivationError(format!(
+                "expected an account-level xpub (depth {}), got depth {}",
+                ACCOUNT_KEY_DEPTH, parsed.depth
+            )));
+        }
+        let account_index = match parsed.child_number {
+            ChildNumber::Hardened { index } | ChildNumber::Normal { index } => index,
+        };
+
+        let mut wallet = Self {
+            mnemonic: Zeroizing::new(String::new()),
+            seed_passphrase: Zeroizing::new(String::new()),
+            sealed_seed: None,
+            unlocked: None,
+            network,
+            mode: WalletMode::WatchOnly,
+            accounts: HashMap::new(),
+            wallet_path,
+            backup_metadata: BackupMetadata::new(),
+            spending_policies: PolicyStore::default(),
+            gap_limit: DEFAULT_GAP_LIMIT,
+            generation: 0,
+        };
+        wallet.accounts.insert(
+            WATCH_ONLY_ACCOUNT.to_string(),
+            HDAccount {
+                name: WATCH_ONLY_ACCOUNT.to_string(),
+                account_type: AccountType::NativeSegWit,
+                addresses: Vec::new(),
+                account_index,
+                next_index: 0,
+                next_change_index: 0,
+                state: AccountState::Active,
+                watch_only: false,
+                xpub: Some(parsed.to_string()),
+            },
+        );
+        Ok(wallet)
+    }
+
+    /// Whether the wallet holds its seed or only an account xpub
+    pub fn mode(&self) -> WalletMode {
+        self.mode
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        self.mode == WalletMode::WatchOnly
+    }
+
+    /// Whether the seed is sealed under a passphrase
+    pub fn is_encrypted(&self) -> bool {
+        self.sealed_seed.is_some()
+    }
+
+    /// Whether the seed is sealed and not currently unlocked. Balances,
+    /// accounts and addresses stay readable while locked.
+    pub fn is_locked(&self) -> bool {
+        self.is_encrypted()
+            && self
+                .unlocked
+                .as_ref()
+                .map_or(true, |unlocked| unlocked.expired())
+    }
+
+    /// Whether the wallet file holds the mnemonic in plaintext and should be
+    /// upgraded with [`encrypt`](Self::encrypt)
+    pub fn stores_plaintext_seed(&self) -> bool {
+        !self.is_watch_only() && !self.is_encrypted()
+    }
+
+    /// Seal the seed under `passphrase` and rewrite the wallet file in place.
+    /// Accounts, addresses and policies stay readable; deriving keys needs
+    /// [`unlock`](Self::unlock) from then on. This is also how a plaintext
+    /// wallet is upgraded. The wallet is left locked.
+    pub fn encrypt(&mut self, passphrase: &str) -> Result<(), HDWalletError> {
+        self.encrypt_with(passphrase, KdfParams::default())
+    }
+
+    pub(crate) fn encrypt_with(
+        &mut self,
+        passphrase: &str,
+        kdf: KdfParams,
+    ) -> Result<(), HDWalletError> {
+        if self.is_watch_only() {
+            return Err(HDWalletError::WatchOnlyWallet("encrypting the seed"));
+        }
+        if self.is_encrypted() {
+            return Err(HDWalletError::EncryptionError(
+                "wallet is already encrypted".to_string(),
+            ));
+        }
+        PasswordStrengthChecker::new()
+            .validate(passphrase)
+            .map_err(|suggestions| HDWalletError::PasswordTooWeak(suggestions.join("; ")))?;
+
+        let secret = join_seed(&self.mnemonic, &self.seed_passphrase);
+        let sealed = SealedSeed::seal(&secret, passphrase, kdf)?;
+        let mnemonic = std::mem::replace(&mut self.mnemonic, Zeroizing::new(String::new()));
+        let seed_passphrase =
+            std::mem::replace(&mut self.seed_passphrase, Zeroizing::new(String::new()));
+        self.sealed_seed = Some(sealed);
+        if let Err(e) = self.save() {
+            self.mnemonic = mnemonic;
+            self.seed_passphrase = seed_passphrase;
+            self.sealed_seed = None;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Decrypt the seed so keys can be derived, until [`lock`](Self::lock)
+    /// or until `timeout` elapses. A wallet without a passphrase has nothing
+    /// to unlock.
+    pub fn unlock(
+        &mut self,
+        passphrase: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), HDWalletError> {
+        let Some(sealed) = &self.sealed_seed else {
+            return Ok(());
+        };
+        let (mnemonic, seed_passphrase) = split_seed(&sealed.open(passphrase)?);
+        Mnemonic::parse_in_normalized(Language::English, mnemonic.as_str()).map_err(|e| {
+            HDWalletError::DecryptionError(format!("sealed seed is not a mnemonic: {}", e))
+        })?;
+        self.unlocked = Some(UnlockedSeed {
+            mnemonic,
+            seed_passphrase,
+            expires: timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
+        });
+        Ok(())
+    }
+
+    /// Wipe the decrypted seed
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+    }
+
+    /// Wipe the decrypted seed if its auto-lock deadline has passed, which
+    /// already makes it unusable. Returns whether the wallet was locked.
+    pub fn lock_if_expired(&mut self) -> bool {
+        if self.unlocked.as_ref().is_some_and(UnlockedSeed::expired) {
+            self.lock();
+            return true;
+        }
+        false
+    }
+
+    /// The mnemonic and BIP39 passphrase, if this wallet has them and they
+    /// are not locked away
+    fn seed_secret(&self, operation: &'static str) -> Result<(&str, &str), HDWalletError> {
+        if self.is_watch_only() {
+            return Err(HDWalletError::WatchOnlyWallet(operation));
+        }
+        if self.sealed_seed.is_none() {
+            return Ok((self.mnemonic.as_str(), self.seed_passphrase.as_str()));
+        }
+        match &self.unlocked {
+            Some(unlocked) if !unlocked.expired() => Ok((
+                unlocked.mnemonic.as_str(),
+                unlocked.seed_passphrase.as_str(),
+            )),
+            _ => Err(HDWalletError::Locked(operation)),
+        }
+    }
+
+    fn mnemonic_secret(&self, operation: &'static str) -> Result<&str, HDWalletError> {
+        self.seed_secret(operation).map(|(mnemonic, _)| mnemonic)
+    }
+
     /// Save wallet with encryption
     /// 
     /// SECURITY FIX (P2-008): Encrypts wallet backup with Argon2id + ChaCha20-Poly1305.
@@ -416,7 +900,8 @@ impl HDWallet {
     /// Legacy plaintext save (DEPRECATED - use save_encrypted)
     ///
     /// SECURITY WARNING: This method stores the wallet in plaintext.
-    /// Use save_encrypted() for production deployments.
+    /// Use save_encrypted() for production deployments. A wallet sealed with
+    /// [`encrypt`](Self::encrypt) only writes its sealed seed.
     ///
     /// SECURITY FIX (R5-90): Never silently downgrade an encrypted wallet to
     /// plaintext. `create_account`/`get_new_address`/`verify_backup` call this
@@ -459,6 +944,20 @@ impl HDWallet {
         name: String,
         account_type: AccountType,
     ) -> Result<(), HDWalletError> {
+        if self.is_watch_only() {
+            return Err(HDWalletError::WatchOnlyWallet("creating accounts"));
+        }
+        if let AccountType::Quantum(scheme) = account_type {
+            quantum_parameter_set(scheme)?;
+        }
+        self.add_account(name, account_type);
+        self.save()?;
+        Ok(())
+    }
+
+    /// Add an account in memory without persisting the wallet. Used while
+    /// a wallet is assembled before its first write.
+    pub fn add_account(&mut self, name: String, account_type: AccountType) {
         // Assign a stable, collision-free BIP44 account index so every address
         // in this account is deterministically re-derivable from the mnemonic.
         let account_index = self
@@ -475,11 +974,108 @@ impl HDWallet {
             addresses: Vec::new(),
             account_index,
             next_index: 0,
+            next_change_index: 0,
+            state: AccountState::Active,
+            watch_only: false,
+            xpub: None,
         };
 
         self.accounts.insert(name, account);
-        self.save()?;
-        Ok(())
+        self.generation += 1;
+    }
+
+    /// Add an account that only watches `addresses`. It has no keys, so no
+    /// new addresses can be generated for it.
+    pub fn import_watch_only_account(
+        &mut self,
+        name: String,
+        account_type: AccountType,
+        addresses: Vec<String>,
+    ) -> Result<(), HDWalletError> {
+        let addresses = addresses
+            .into_iter()
+            .map(|address| {
+                Address::from_str(&address)
+                    .map_err(|e| HDWalletError::AddressParsing(e.to_string()))?;
+                Ok(HDAddress {
+                    address,
+                    is_used: false,
+                    index: 0,
+                    change: false,
+                    reserved: false,
+                    label: None,
+                })
+            })
+            .collect::<Result<Vec<_>, HDWalletError>>()?;
+
+        let account = HDAccount {
+            name: name.clone(),
+            account_type,
+            addresses,
+            account_index: 0,
+            next_index: 0,
+            next_change_index: 0,
+            state: AccountState::Active,
+            watch_only: true,
+            xpub: None,
+        };
+        self.accounts.insert(name, account);
+        self.generation += 1;
+        self.save()
+    }
+
+    /// Hide an account from default listings and totals. An account that
+    /// still holds funds is only archived with `confirm_nonzero`.
+    pub fn archive_account(
+        &mut self,
+        account_name: &str,
+        utxo_set: &UtxoSet,
+        confirm_nonzero: bool,
+    ) -> Result<(), HDWalletError> {
+        let balance = self.get_balance(account_name, utxo_set)?;
+        if balance > 0 && !confirm_nonzero {
+            return Err(HDWalletError::ArchiveNeedsConfirmation {
+                account: account_name.to_string(),
+                balance,
+            });
+        }
+        self.set_account_state(account_name, AccountState::Archived)
+    }
+
+    /// Restore an archived account to the active set.
+    pub fn unarchive_account(&mut self, account_name: &str) -> Result<(), HDWalletError> {
+        self.set_account_state(account_name, AccountState::Active)
+    }
+
+    fn set_account_state(
+        &mut self,
+        account_name: &str,
+        state: AccountState,
+    ) -> Result<(), HDWalletError> {
+        let account = self
+            .accounts
+            .get_mut(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        account.state = state;
+        self.save()
+    }
+
+    /// Remove a watch-only account entirely. Nothing of ours can be lost
+    /// since it holds no keys, but the removal still takes `confirm`.
+    pub fn purge_watch(&mut self, account_name: &str, confirm: bool) -> Result<(), HDWalletError> {
+        let account = self
+            .accounts
+            .get(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        if !account.watch_only {
+            return Err(HDWalletError::NotWatchOnly(account_name.to_string()));
+        }
+        if !confirm {
+            return Err(HDWalletError::PurgeNeedsConfirmation(account_name.to_string()));
+        }
+        self.accounts.remove(account_name);
+        self.generation += 1;
+        self.save()
     }
 
     /// BIP44 coin type for the wallet's network (0' = mainnet, 1' = test networks).
@@ -502,12 +1098,17 @@ impl HDWallet {
         account_index: u32,
         address_index: u32,
     ) -> Result<PrivateKey, HDWalletError> {
-        let mnemonic = Mnemonic::parse_in_normalized(Language::English, self.mnemonic.as_str())
-            .map_err(|e| HDWalletError::InvalidMnemonic(e.to_string()))?;
-        // SECURITY FIX (R5-96): The 64-byte BIP39 master seed is a top-level
-        // secret; hold it in Zeroizing so it is wiped on drop instead of being
-        // left in freed stack/heap memory after derivation.
-        let seed = Zeroizing::new(mnemonic.to_seed(""));
+        self.derive_chain_private_key(account_index, EXTERNAL_CHAIN, address_index)
+    }
+
+    /// Derive the key at `m/44'/coin'/account'/chain/index`.
+    fn derive_chain_private_key(
+        &self,
+        account_index: u32,
+        chain: u32,
+        address_index: u32,
+    ) -> Result<PrivateKey, HDWalletError> {
+        let seed = self.bip39_seed()?;
 
         let secp = Secp256k1::new();
         let master = Xpriv::new_master(self.network, &seed[..])
@@ -520,8 +1121,7 @@ impl HDWallet {
                 .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
             ChildNumber::from_hardened_idx(account_index)
                 .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
-            // External chain (receiving addresses)
-            ChildNumber::from_normal_idx(0)
+            ChildNumber::from_normal_idx(chain)
                 .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
             ChildNumber::from_normal_idx(address_index)
                 .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
@@ -535,6 +1135,131 @@ impl HDWallet {
         Ok(PrivateKey::new(child.private_key, self.network))
     }
 
+    /// The 64-byte BIP39 master seed.
+    ///
+    /// SECURITY FIX (R5-96): The seed is a top-level secret; hold it in
+    /// Zeroizing so it is wiped on drop instead of being left in freed
+    /// stack/heap memory after derivation.
+    fn bip39_seed(&self) -> Result<Zeroizing<[u8; 64]>, HDWalletError> {
+        let (mnemonic, seed_passphrase) = self.seed_secret("deriving from the seed")?;
+        let mnemonic = Mnemonic::parse_in_normalized(Language::English, mnemonic)
+            .map_err(|e| HDWalletError::InvalidMnemonic(e.to_string()))?;
+        Ok(Zeroizing::new(mnemonic.to_seed(seed_passphrase)))
+    }
+
+    /// Derive the public key at `account'/chain/index`, from the account xpub
+    /// when there is one and from the seed otherwise.
+    fn derive_chain_public_key(
+        &self,
+        account: &HDAccount,
+        chain: u32,
+        address_index: u32,
+    ) -> Result<btc_compat::PublicKey, HDWalletError> {
+        let secp = Secp256k1::new();
+        let Some(xpub) = &account.xpub else {
+            let private_key =
+                self.derive_chain_private_key(account.account_index, chain, address_index)?;
+            return Ok(private_key.public_key(&secp));
+        };
+        let xpub =
+            Xpub::from_str(xpub).map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+        let path = [
+            ChildNumber::from_normal_idx(chain)
+                .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
+            ChildNumber::from_normal_idx(address_index)
+                .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
+        ];
+        let child = xpub
+            .derive_pub(&secp, &path)
+            .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+        Ok(btc_compat::PublicKey::new(child.public_key))
+    }
+
+    /// Extended public key of `account_name` at m/44'/coin'/account', from
+    /// which a watch-only wallet derives the same addresses (see
+    /// [`from_xpub`](Self::from_xpub)).
+    pub fn account_xpub(&self, account_name: &str) -> Result<String, HDWalletError> {
+        let account = self
+            .accounts
+            .get(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        if let Some(xpub) = &account.xpub {
+            return Ok(xpub.clone());
+        }
+        if account.watch_only {
+            return Err(HDWalletError::WatchOnly(account_name.to_string()));
+        }
+        if let AccountType::Quantum(scheme) = account.account_type {
+            return Err(HDWalletError::Compatibility(format!(
+                "account {} holds {:?} keys, not secp256k1",
+                account_name, scheme
+            )));
+        }
+
+        let seed = self.bip39_seed()?;
+        let secp = Secp256k1::new();
+        let master = Xpriv::new_master(self.network, &seed[..])
+            .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+        let path: DerivationPath = vec![
+            ChildNumber::from_hardened_idx(44)
+                .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
+            ChildNumber::from_hardened_idx(self.coin_type())
+                .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
+            ChildNumber::from_hardened_idx(account.account_index)
+                .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?,
+        ]
+        .into();
+        let account_key = master
+            .derive_priv(&secp, &path)
+            .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+        Ok(Xpub::from_priv(&secp, &account_key).to_string())
+    }
+
+    /// Derive the public key of the quantum keypair at
+    /// `account'/chain/index`.
+    ///
+    /// The key generation seed is expanded with HKDF-SHA512 from the BIP39
+    /// seed, with the parameter set and path in the info string, so restoring
+    /// the mnemonic re-derives the same keypair. ML-DSA takes it as the FIPS
+    /// 204 seed `xi`; SLH-DSA splits it into SK.seed, SK.prf and PK.seed.
+    fn derive_quantum_public_key(
+        &self,
+        scheme: QuantumScheme,
+        account_index: u32,
+        chain: u32,
+        address_index: u32,
+    ) -> Result<Vec<u8>, HDWalletError> {
+        let parameter_set = quantum_parameter_set(scheme)?;
+        let seed = self.bip39_seed()?;
+        let info = format!(
+            "{}/{}/{}/{}/{}",
+            QUANTUM_KEY_INFO, parameter_set, account_index, chain, address_index
+        );
+        let hkdf = Hkdf::<Sha512>::new(None, &seed[..]);
+        let mut key_seed = Zeroizing::new([0u8; 3 * SLH_DSA_SEED_LEN]);
+        hkdf.expand(info.as_bytes(), &mut key_seed[..])
+            .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+
+        match scheme {
+            QuantumScheme::Dilithium => {
+                let mut xi = Zeroizing::new([0u8; 32]);
+                xi.copy_from_slice(&key_seed[..32]);
+                let keypair = MlDsa65::key_gen_internal(&B32::from(*xi));
+                Ok(keypair.verifying_key().encode().to_vec())
+            }
+            QuantumScheme::SphincsPlus => {
+                let (sk_seed, rest) = key_seed.split_at(SLH_DSA_SEED_LEN);
+                let (sk_prf, pk_seed) = rest.split_at(SLH_DSA_SEED_LEN);
+                let signing_key =
+                    SlhDsaSigningKey::<Shake192f>::slh_keygen_internal(sk_seed, sk_prf, pk_seed);
+                Ok(signing_key.verifying_key().to_bytes().to_vec())
+            }
+            QuantumScheme::Falcon | QuantumScheme::Hybrid(_) => Err(
+                HDWalletError::UnsupportedScheme(format!("{:?}", scheme)),
+            ),
+        }
+    }
+
     /// Re-derive the signing key for a previously generated address.
     ///
     /// SECURITY (R3-60): Enables spending funds sent to addresses produced by
@@ -552,52 +1277,470 @@ impl HDWallet {
         self.derive_external_private_key(account.account_index, address_index)
     }
 
-    fn address_for_pubkey(
+    /// Re-derive the signing key for the address paying `script` in
+    /// `account_name`, for spending an output locked to it.
+    pub fn signing_key_for_script(
         &self,
-        account_type: AccountType,
-        public_key: &btc_compat::PublicKey,
-    ) -> Result<Address, HDWalletError> {
-        Ok(match account_type {
-            AccountType::Legacy => Address::p2pkh(public_key, self.network),
-            AccountType::SegWit => Address::p2shwpkh(public_key, self.network)
-                .map_err(|e| HDWalletError::Compatibility(e.to_string()))?,
-            AccountType::NativeSegWit => Address::p2wpkh(public_key, self.network)
-                .map_err(|e| HDWalletError::Compatibility(e.to_string()))?,
-        })
-    }
-
-    pub fn get_new_address(&mut self, account_name: &str) -> Result<HDAddress, HDWalletError> {
-        // Read the account's derivation metadata without holding a mutable
-        // borrow of `self` across the (immutable) derivation call below.
-        let (account_index, account_type, address_index) = {
-            let account = self
-                .accounts
-                .get(account_name)
-                .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
-            (account.account_index, account.account_type, account.next_index)
-        };
-
-        // SECURITY FIX (R3-60): Deterministic BIP44 derivation from the mnemonic
-        // seed instead of a random, discarded key.
-        let secp = Secp256k1::new();
-        let private_key = self.derive_external_private_key(account_index, address_index)?;
-        let public_key = private_key.public_key(&secp);
-        let address = self.address_for_pubkey(account_type, &public_key)?;
-
-        let hd_address = HDAddress {
-            address: address.to_string(),
-            is_used: false,
-            index: address_index,
-        };
-
+        account_name: &str,
+        script: &[u8],
+    ) -> Result<PrivateKey, HDWalletError> {
         let account = self
             .accounts
-            .get_mut(account_name)
+            .get(account_name)
             .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
-        account.addresses.push(hd_address.clone());
-        account.next_index = address_index + 1;
-        self.save()?;
-        Ok(hd_address)
+        if account.watch_only {
+            return Err(HDWalletError::WatchOnly(account_name.to_string()));
+        }
+        if let AccountType::Quantum(scheme) = account.account_type {
+            return Err(HDWalletError::Compatibility(format!(
+                "account {} holds {:?} keys, not secp256k1",
+                account_name, scheme
+            )));
+        }
+        for hd_address in &account.addresses {
+            if address_script(&hd_address.address)? == script {
+                let chain = if hd_address.change {
+                    INTERNAL_CHAIN
+                } else {
+                    EXTERNAL_CHAIN
+                };
+                return self.derive_chain_private_key(
+                    account.account_index,
+                    chain,
+                    hd_address.index,
+                );
+            }
+        }
+        Err(HDWalletError::AddressNotFound(hex::encode(script)))
+    }
+
+    /// BIP44 path (m/44'/coin'/account'/chain/index) of the address paying
+    /// `script` in `account_name`. Needs no keys, so a watch-only wallet can
+    /// tell a signer which keys to use.
+    pub fn derivation_path_for_script(
+        &self,
+        account_name: &str,
+        script: &[u8],
+    ) -> Result<String, HDWalletError> {
+        let account = self
+            .accounts
+            .get(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        if account.watch_only {
+            return Err(HDWalletError::WatchOnly(account_name.to_string()));
+        }
+        for hd_address in &account.addresses {
+            if address_script(&hd_address.address)? == script {
+                let chain = if hd_address.change {
+                    INTERNAL_CHAIN
+                } else {
+                    EXTERNAL_CHAIN
+                };
+                return Ok(format!(
+                    "m/44'/{}'/{}'/{}/{}",
+                    self.coin_type(),
+                    account.account_index,
+                    chain,
+                    hd_address.index
+                ));
+            }
+        }
+        Err(HDWalletError::AddressNotFound(hex::encode(script)))
+    }
+
+    /// Re-derive the signing key at BIP44 `path` for spending an output
+    /// locked to `script`, with the type of the account it belongs to.
+    ///
+    /// Only paths to addresses this wallet generated are accepted, and the
+    /// address there must pay `script`; anything else is an
+    /// [`HDWalletError::UnknownDerivationPath`].
+    pub fn signing_key_for_path(
+        &self,
+        path: &str,
+        script: &[u8],
+    ) -> Result<(PrivateKey, AccountType), HDWalletError> {
+        let (account, chain, address_index) = self.address_at_path(path, script)?;
+        if account.xpub.is_some() {
+            return Err(HDWalletError::UnknownDerivationPath(path.to_string()));
+        }
+        let key = self.derive_chain_private_key(account.account_index, chain, address_index)?;
+        Ok((key, account.account_type))
+    }
+
+    /// Public key at BIP44 `path` for an output locked to `script`, checked
+    /// like [`signing_key_for_path`](Self::signing_key_for_path). Needs no
+    /// seed on a watch-only wallet, so signatures from an external signer
+    /// can be verified and placed.
+    pub fn public_key_for_path(
+        &self,
+        path: &str,
+        script: &[u8],
+    ) -> Result<(btc_compat::PublicKey, AccountType), HDWalletError> {
+        let (account, chain, address_index) = self.address_at_path(path, script)?;
+        let public_key = self.derive_chain_public_key(account, chain, address_index)?;
+        Ok((public_key, account.account_type))
+    }
+
+    /// The secp256k1 account, chain and index of the generated address at
+    /// BIP44 `path`, which must pay `script`
+    fn address_at_path(
+        &self,
+        path: &str,
+        script: &[u8],
+    ) -> Result<(&HDAccount, u32, u32), HDWalletError> {
+        let unknown = || HDWalletError::UnknownDerivationPath(path.to_string());
+        let derivation_path = DerivationPath::from_str(path).map_err(|_| unknown())?;
+        let (account_index, chain, address_index) = {
+            use ChildNumber::{Hardened, Normal};
+            match derivation_path.as_ref() {
+                &[Hardened { index: 44 }, Hardened { index: coin }, Hardened { index: account }, Normal { index: chain }, Normal { index }]
+                    if coin == self.coin_type()
+                        && (chain == EXTERNAL_CHAIN || chain == INTERNAL_CHAIN) =>
+                {
+                    (account, chain, index)
+                }
+                _ => return Err(unknown()),
+            }
+        };
+
+        let account = self
+            .accounts
+            .values()
+            .find(|account| account.account_index == account_index && !account.watch_only)
+            .ok_or_else(unknown)?;
+        if let AccountType::Quantum(_) = account.account_type {
+            return Err(unknown());
+        }
+        let hd_address = account
+            .addresses
+            .iter()
+            .find(|hd_address| {
+                hd_address.index == address_index && hd_address.change == (chain == INTERNAL_CHAIN)
+            })
+            .ok_or_else(unknown)?;
+        if address_script(&hd_address.address)? != script {
+            return Err(HDWalletError::UnknownDerivationPath(format!(
+                "{} does not pay {}",
+                path,
+                hex::encode(script)
+            )));
+        }
+        Ok((account, chain, address_index))
+    }
+
+    /// Network the wallet's addresses are encoded for
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    fn address_for_pubkey(
+        &self,
+        account_type: AccountType,
+        public_key: &btc_compat::PublicKey,
+    ) -> Result<Address, HDWalletError> {
+        Ok(match account_type {
+            AccountType::Legacy => Address::p2pkh(public_key, self.network),
+            AccountType::SegWit => Address::p2shwpkh(public_key, self.network)
+                .map_err(|e| HDWalletError::Compatibility(e.to_string()))?,
+            AccountType::NativeSegWit => Address::p2wpkh(public_key, self.network)
+                .map_err(|e| HDWalletError::Compatibility(e.to_string()))?,
+            AccountType::Quantum(_) => {
+                return Err(HDWalletError::Compatibility(
+                    "quantum accounts have no secp256k1 addresses".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Reserve a receive address of the account: the first one derived
+    /// ahead and never handed out, or a new one. Fails with
+    /// [`HDWalletError::GapLimitReached`] once the account has
+    /// [`gap_limit`](Self::gap_limit) unused addresses past its last used
+    /// one, as a restore would not look further.
+    pub fn get_new_address(&mut self, account_name: &str) -> Result<HDAddress, HDWalletError> {
+        self.next_address(account_name, false)
+    }
+
+    /// Derive a fresh address on the account's internal chain to receive the
+    /// change of a payment.
+    pub fn get_new_change_address(
+        &mut self,
+        account_name: &str,
+    ) -> Result<HDAddress, HDWalletError> {
+        self.next_address(account_name, true)
+    }
+
+    /// The change address the account's next payment would use, without
+    /// recording it
+    pub fn peek_change_address(&self, account_name: &str) -> Result<HDAddress, HDWalletError> {
+        self.upcoming_address(account_name, true)
+    }
+
+    fn next_address(
+        &mut self,
+        account_name: &str,
+        change: bool,
+    ) -> Result<HDAddress, HDWalletError> {
+        let hd_address = if change {
+            self.derive_next_address(account_name, true)?
+        } else {
+            self.reserve_receive_address(account_name)?
+        };
+        self.save()?;
+        Ok(hd_address)
+    }
+
+    fn reserve_receive_address(&mut self, account_name: &str) -> Result<HDAddress, HDWalletError> {
+        let gap_limit = self.gap_limit;
+        let account = self
+            .accounts
+            .get_mut(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        // Archived and watch-only accounts are refused by the derivation
+        if account.state == AccountState::Active && !account.watch_only {
+            let ahead = account
+                .addresses
+                .iter_mut()
+                .filter(|hd_address| {
+                    !hd_address.change && hd_address.state() == AddressState::Unused
+                })
+                .min_by_key(|hd_address| hd_address.index);
+            if let Some(hd_address) = ahead {
+                hd_address.reserved = true;
+                return Ok(hd_address.clone());
+            }
+        }
+        if account.next_index.saturating_sub(account.used_end(false)) >= gap_limit {
+            return Err(HDWalletError::GapLimitReached {
+                account: account_name.to_string(),
+                gap_limit,
+            });
+        }
+
+        let mut hd_address = self.derive_next_address(account_name, false)?;
+        hd_address.reserved = true;
+        if let Some(stored) = self
+            .accounts
+            .get_mut(account_name)
+            .and_then(|account| account.addresses.last_mut())
+        {
+            stored.reserved = true;
+        }
+        Ok(hd_address)
+    }
+
+    /// Derive and record the account's next address without saving
+    fn derive_next_address(
+        &mut self,
+        account_name: &str,
+        change: bool,
+    ) -> Result<HDAddress, HDWalletError> {
+        let hd_address = self.upcoming_address(account_name, change)?;
+        let account = self
+            .accounts
+            .get_mut(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        account.addresses.push(hd_address.clone());
+        if change {
+            account.next_change_index = hd_address.index + 1;
+        } else {
+            account.next_index = hd_address.index + 1;
+        }
+        self.generation += 1;
+        Ok(hd_address)
+    }
+
+    /// Derive the account's next address on a chain
+    fn upcoming_address(
+        &self,
+        account_name: &str,
+        change: bool,
+    ) -> Result<HDAddress, HDWalletError> {
+        // Read the account's derivation metadata without holding a mutable
+        // borrow of `self` across the (immutable) derivation call below.
+        let (account, address_index) = {
+            let account = self
+                .accounts
+                .get(account_name)
+                .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+            if account.state == AccountState::Archived {
+                return Err(HDWalletError::AccountArchived(account_name.to_string()));
+            }
+            if account.watch_only {
+                return Err(HDWalletError::WatchOnly(account_name.to_string()));
+            }
+            let next_index = if change {
+                account.next_change_index
+            } else {
+                account.next_index
+            };
+            (account.clone(), next_index)
+        };
+
+        // SECURITY FIX (R3-60): Deterministic BIP44 derivation from the mnemonic
+        // seed instead of a random, discarded key.
+        let chain = if change { INTERNAL_CHAIN } else { EXTERNAL_CHAIN };
+        let address = match account.account_type {
+            AccountType::Quantum(scheme) => {
+                let public_key = self.derive_quantum_public_key(
+                    scheme,
+                    account.account_index,
+                    chain,
+                    address_index,
+                )?;
+                quantum_address(&public_key)?
+            }
+            account_type => {
+                let public_key = self.derive_chain_public_key(&account, chain, address_index)?;
+                self.address_for_pubkey(account_type, &public_key)?
+                    .to_string()
+            }
+        };
+
+        Ok(HDAddress {
+            address,
+            is_used: false,
+            index: address_index,
+            change,
+            reserved: false,
+            label: None,
+        })
+    }
+
+    /// Derive addresses until every active account has `gap_limit` unused
+    /// ones past its last used address, on both chains. Returns how many
+    /// were added.
+    pub fn extend_to_gap_limit(&mut self, gap_limit: u32) -> Result<usize, HDWalletError> {
+        let mut pending = Vec::new();
+        for (account_name, account) in &self.accounts {
+            if account.watch_only || account.state == AccountState::Archived {
+                continue;
+            }
+            for change in [false, true] {
+                let used_end = account.used_end(change);
+                let next = if change {
+                    account.next_change_index
+                } else {
+                    account.next_index
+                };
+                let missing = (used_end + gap_limit).saturating_sub(next);
+                if missing > 0 {
+                    pending.push((account_name.clone(), change, missing));
+                }
+            }
+        }
+
+        let mut added = 0;
+        for (account_name, change, missing) in pending {
+            for _ in 0..missing {
+                self.derive_next_address(&account_name, change)?;
+                added += 1;
+            }
+        }
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Unused receive addresses allowed past the last used one
+    pub fn gap_limit(&self) -> u32 {
+        self.gap_limit
+    }
+
+    pub fn set_gap_limit(&mut self, gap_limit: u32) -> Result<(), HDWalletError> {
+        if gap_limit == 0 {
+            return Err(HDWalletError::InvalidGapLimit);
+        }
+        self.gap_limit = gap_limit;
+        self.save()
+    }
+
+    /// Set the label of one of the wallet's addresses; an empty label
+    /// removes it
+    pub fn label_address(&mut self, address: &str, label: &str) -> Result<(), HDWalletError> {
+        let label = label.trim();
+        if label.len() > MAX_ADDRESS_LABEL_LEN {
+            return Err(HDWalletError::LabelTooLong(label.len()));
+        }
+        let hd_address = self
+            .accounts
+            .values_mut()
+            .flat_map(|account| account.addresses.iter_mut())
+            .find(|hd_address| hd_address.address == address)
+            .ok_or_else(|| HDWalletError::AddressNotFound(address.to_string()))?;
+        hd_address.label = (!label.is_empty()).then(|| label.to_string());
+        self.save()
+    }
+
+    /// Addresses of an account matching `filter`, receive addresses first,
+    /// each chain in derivation order
+    pub fn list_addresses(
+        &self,
+        account_name: &str,
+        filter: AddressFilter,
+    ) -> Result<Vec<&HDAddress>, HDWalletError> {
+        let account = self
+            .accounts
+            .get(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        let mut addresses: Vec<&HDAddress> = account
+            .addresses
+            .iter()
+            .filter(|hd_address| filter.matches(hd_address))
+            .collect();
+        addresses.sort_by_key(|hd_address| (hd_address.change, hd_address.index));
+        Ok(addresses)
+    }
+
+    /// Flag `address` as used. Returns whether it belongs to the wallet.
+    pub fn mark_address_used(&mut self, address: &str) -> bool {
+        match self
+            .accounts
+            .values_mut()
+            .flat_map(|account| account.addresses.iter_mut())
+            .find(|hd_address| hd_address.address == address)
+        {
+            Some(hd_address) => {
+                hd_address.is_used = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flag the addresses paid by the received `records` as used, saving
+    /// the wallet if any was. Returns how many were flagged.
+    pub fn mark_received(&mut self, records: &[TransactionRecord]) -> Result<usize, HDWalletError> {
+        let mut marked = 0;
+        for record in records {
+            if record.direction != TransactionDirection::Received {
+                continue;
+            }
+            for address in &record.addresses {
+                if self.mark_address_used(address) {
+                    marked += 1;
+                }
+            }
+        }
+        if marked > 0 {
+            self.save()?;
+        }
+        Ok(marked)
+    }
+
+    /// Flag the address paying `script` as used. Returns whether it belongs
+    /// to the wallet.
+    pub fn mark_used(&mut self, script: &[u8]) -> Result<bool, HDWalletError> {
+        for account in self.accounts.values_mut() {
+            for hd_address in &mut account.addresses {
+                if address_script(&hd_address.address)? == script {
+                    hd_address.is_used = true;
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
     }
 
     pub fn get_balance(
@@ -612,28 +1755,103 @@ impl HDWallet {
 
         let mut balance = 0;
         for hd_address in &account.addresses {
-            let address = Address::from_str(&hd_address.address)
-                .map_err(|e| HDWalletError::AddressParsing(e.to_string()))?;
-            let address = address.assume_checked();
-            balance += utxo_set.get_balance(address.script_pubkey().as_bytes());
+            balance += utxo_set.get_balance(&address_script(&hd_address.address)?);
         }
 
         Ok(balance)
     }
 
+    /// Confirmed outputs paying the account's addresses
+    pub fn spendable_utxos(
+        &self,
+        account_name: &str,
+        utxo_set: &UtxoSet,
+    ) -> Result<Vec<UtxoEntry>, HDWalletError> {
+        let account = self
+            .accounts
+            .get(account_name)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))?;
+        let scripts = account
+            .addresses
+            .iter()
+            .map(|hd_address| address_script(&hd_address.address))
+            .collect::<Result<HashSet<_>, _>>()?;
+        Ok(utxo_set
+            .entries()
+            .into_iter()
+            .filter(|entry| entry.is_confirmed && scripts.contains(&entry.output.pub_key_script))
+            .collect())
+    }
+
+    /// Changes whenever accounts or addresses are added or removed
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Locking script of every address, with the account it belongs to
+    pub fn address_scripts(&self) -> Result<Vec<(Vec<u8>, &str)>, HDWalletError> {
+        let mut scripts = Vec::with_capacity(self.get_address_count());
+        for (account_name, account) in &self.accounts {
+            for hd_address in &account.addresses {
+                scripts.push((address_script(&hd_address.address)?, account_name.as_str()));
+            }
+        }
+        Ok(scripts)
+    }
+
+    /// State of an account
+    pub fn account_state(&self, account_name: &str) -> Result<AccountState, HDWalletError> {
+        self.accounts
+            .get(account_name)
+            .map(|account| account.state)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))
+    }
+
+    /// Address type of an account
+    pub fn account_type(&self, account_name: &str) -> Result<AccountType, HDWalletError> {
+        self.accounts
+            .get(account_name)
+            .map(|account| account.account_type)
+            .ok_or_else(|| HDWalletError::AccountNotFound(account_name.to_string()))
+    }
+
+    /// Balance of the active accounts; see `get_balances` for archived ones.
     pub fn get_total_balance(&self, utxo_set: &UtxoSet) -> Result<u64, HDWalletError> {
-        let mut total = 0;
-        for account_name in self.accounts.keys() {
-            total += self.get_balance(account_name, utxo_set)?;
+        Ok(self.get_balances(utxo_set)?.active)
+    }
+
+    pub fn get_balances(&self, utxo_set: &UtxoSet) -> Result<AccountBalances, HDWalletError> {
+        let mut balances = AccountBalances::default();
+        for (account_name, account) in &self.accounts {
+            let balance = self.get_balance(account_name, utxo_set)?;
+            match account.state {
+                AccountState::Active => balances.active += balance,
+                AccountState::Archived => balances.archived += balance,
+            }
         }
-        Ok(total)
+        Ok(balances)
     }
 
-    pub fn list_accounts(&self) -> Vec<(u32, &HDAccount)> {
+    pub fn list_accounts(&self, include_archived: bool) -> Vec<(u32, &HDAccount)> {
         self.accounts
-            .iter()
+            .values()
+            .filter(|account| include_archived || account.state == AccountState::Active)
             .enumerate()
-            .map(|(i, (_, account))| (i as u32, account))
+            .map(|(i, account)| (i as u32, account))
+            .collect()
+    }
+
+    /// Every address to sync, active accounts first so archived ones are
+    /// only refreshed after them.
+    pub fn sync_addresses(&self) -> Vec<&str> {
+        let (active, archived): (Vec<&HDAccount>, Vec<&HDAccount>) = self
+            .accounts
+            .values()
+            .partition(|account| account.state == AccountState::Active);
+        active
+            .into_iter()
+            .chain(archived)
+            .flat_map(|account| account.addresses.iter().map(HDAddress::get_address))
             .collect()
     }
 
@@ -644,12 +1862,111 @@ impl HDWallet {
             .sum()
     }
 
+    /// Key used to seal spending policies, derived from the BIP39 seed so a
+    /// policy copied from (or edited outside) this wallet does not verify.
+    fn policy_key(&self) -> Result<Zeroizing<[u8; 32]>, HDWalletError> {
+        let seed = self.bip39_seed()?;
+        Ok(Zeroizing::new(derive_policy_key(&seed[..])))
+    }
+
+    /// Key for transaction memos in the history file, see
+    /// [`derive_memo_key`](crate::history::derive_memo_key).
+    pub fn memo_key(&self) -> Result<Zeroizing<[u8; 32]>, HDWalletError> {
+        let seed = self.bip39_seed()?;
+        Ok(crate::history::derive_memo_key(&seed[..]))
+    }
+
+    /// Install or replace the spending policy for an account. An empty
+    /// policy removes any existing one.
+    pub fn set_spending_policy(
+        &mut self,
+        account_name: &str,
+        policy: SpendingPolicy,
+    ) -> Result<(), HDWalletError> {
+        if !self.accounts.contains_key(account_name) {
+            return Err(HDWalletError::AccountNotFound(account_name.to_string()));
+        }
+        let key = self.policy_key()?;
+        self.spending_policies.set_policy(&key, account_name, policy)?;
+        self.save()?;
+        Ok(())
+    }
+
+    /// The account's verified spending policy, if one is set.
+    pub fn spending_policy(
+        &self,
+        account_name: &str,
+    ) -> Result<Option<SpendingPolicy>, HDWalletError> {
+        // Accounts without a policy need no key, so a locked wallet can
+        // still tell them apart
+        if !self.spending_policies.has_policy(account_name) {
+            return Ok(None);
+        }
+        let key = self.policy_key()?;
+        Ok(self.spending_policies.policy(&key, account_name)?.cloned())
+    }
+
+    /// Amount the account has spent in the rolling 24-hour window.
+    pub fn spent_last_24h(&self, account_name: &str) -> u64 {
+        self.spending_policies.spent_in_window(account_name, Utc::now())
+    }
+
+    /// Check a spend against the account's policy. Must be called on the
+    /// transaction build path before anything is signed; an approved spend is
+    /// counted against the daily limit and persisted immediately so the
+    /// window survives restarts.
+    pub fn authorize_spend(
+        &mut self,
+        account_name: &str,
+        destination: &Destination,
+        amount: u64,
+    ) -> Result<PolicyDecision, HDWalletError> {
+        if !self.accounts.contains_key(account_name) {
+            return Err(HDWalletError::AccountNotFound(account_name.to_string()));
+        }
+        let key = self.policy_key()?;
+        let decision = self.spending_policies.evaluate(
+            &key,
+            account_name,
+            destination,
+            amount,
+            Utc::now(),
+        )?;
+        self.save()?;
+        Ok(decision)
+    }
+
+    /// Spends parked above their account's approval threshold.
+    pub fn pending_spends(&self) -> &[PendingSpend] {
+        self.spending_policies.pending()
+    }
+
+    /// Approve a parked spend, returning it so the caller can build it.
+    pub fn approve_pending_spend(
+        &mut self,
+        id: &str,
+        approval: &Approval,
+    ) -> Result<PendingSpend, HDWalletError> {
+        let key = self.policy_key()?;
+        let pending = self.spending_policies.approve(&key, id, approval, Utc::now())?;
+        self.save()?;
+        Ok(pending)
+    }
+
+    /// Discard a parked spend.
+    pub fn reject_pending_spend(&mut self, id: &str) -> Result<PendingSpend, HDWalletError> {
+        let pending = self.spending_policies.reject(id)?;
+        self.save()?;
+        Ok(pending)
+    }
+
     /// Get mnemonic as string reference
     ///
     /// SECURITY NOTE: This returns a reference to the zeroizing mnemonic.
     /// The caller should not store this reference beyond its immediate use.
-    pub fn get_mnemonic(&self) -> &str {
-        self.mnemonic.as_str()
+    /// An encrypted wallet must be unlocked first.
+    pub fn get_mnemonic(&self) -> Result<&str, HDWalletError> {
+        self.mnemonic_secret("revealing the seed")
     }
 
     /// Get backup status
@@ -674,8 +1991,11 @@ impl HDWallet {
             return Ok(());
         }
 
-        SeedPhraseVerifier::verify_interactive(&self.mnemonic, false)
-            .map_err(|e| HDWalletError::BackupVerificationFailed(e))?;
+        SeedPhraseVerifier::verify_interactive(
+            self.mnemonic_secret("verifying the backup")?,
+            false,
+        )
+        .map_err(|e| HDWalletError::BackupVerificationFailed(e))?;
         
         self.backup_metadata.verify();
         self.save()?;
@@ -716,12 +2036,32 @@ impl HDAccount {
     pub fn add_address(&mut self, address: HDAddress) {
         self.addresses.push(address);
     }
+
+    /// One past the highest used index on a chain; 0 if none is used
+    fn used_end(&self, change: bool) -> u32 {
+        self.addresses
+            .iter()
+            .filter(|hd_address| hd_address.change == change && hd_address.is_used)
+            .map(|hd_address| hd_address.index + 1)
+            .max()
+            .unwrap_or(0)
+    }
 }
 
 impl HDAddress {
     pub fn get_address(&self) -> &str {
         &self.address
     }
+
+    pub fn state(&self) -> AddressState {
+        if self.is_used {
+            AddressState::Used
+        } else if self.reserved {
+            AddressState::Reserved
+        } else {
+            AddressState::Unused
+        }
+    }
 }
 
 impl std::str::FromStr for AccountType {
@@ -732,6 +2072,8 @@ impl std::str::FromStr for AccountType {
             "legacy" => Ok(AccountType::Legacy),
             "segwit" => Ok(AccountType::SegWit),
             "native_segwit" => Ok(AccountType::NativeSegWit),
+            "quantum" | "ml-dsa" => Ok(AccountType::Quantum(QuantumScheme::Dilithium)),
+            "sphincs+" | "slh-dsa" => Ok(AccountType::Quantum(QuantumScheme::SphincsPlus)),
             _ => Err(format!("Invalid account type: {}", s)),
         }
     }
@@ -752,12 +2094,20 @@ mod hd_derivation_tests {
     fn get_new_address_is_deterministic_from_mnemonic() {
         let dir1 = tempfile::tempdir().unwrap();
         let dir2 = tempfile::tempdir().unwrap();
-        let mut w1 =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, dir1.path().join("w.json"))
-                .unwrap();
-        let mut w2 =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, dir2.path().join("w.json"))
-                .unwrap();
+        let mut w1 = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir1.path().join("w.json"),
+        )
+        .unwrap();
+        let mut w2 = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir2.path().join("w.json"),
+        )
+        .unwrap();
         w1.create_account("acct".to_string(), AccountType::NativeSegWit)
             .unwrap();
         w2.create_account("acct".to_string(), AccountType::NativeSegWit)
@@ -783,9 +2133,13 @@ mod hd_derivation_tests {
     #[test]
     fn address_signing_key_is_recoverable_from_seed() {
         let dir = tempfile::tempdir().unwrap();
-        let mut w =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, dir.path().join("w.json"))
-                .unwrap();
+        let mut w = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("w.json"),
+        )
+        .unwrap();
         w.create_account("acct".to_string(), AccountType::NativeSegWit)
             .unwrap();
         let addr = w.get_new_address("acct").unwrap();
@@ -798,6 +2152,114 @@ mod hd_derivation_tests {
         assert_eq!(rederived.to_string(), addr.address);
     }
 
+    #[test]
+    fn change_addresses_use_the_internal_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("w.json"),
+        )
+        .unwrap();
+        w.create_account("acct".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let receive = w.get_new_address("acct").unwrap();
+        let change = w.get_new_change_address("acct").unwrap();
+
+        // Both chains start at index 0 but yield different addresses
+        assert_eq!((receive.index, change.index), (0, 0));
+        assert!(change.change && !receive.change);
+        assert_ne!(receive.address, change.address);
+        assert_eq!(w.get_new_change_address("acct").unwrap().index, 1);
+        assert_eq!(w.get_new_address("acct").unwrap().index, 1);
+
+        let secp = Secp256k1::new();
+        let key = w
+            .signing_key_for_script("acct", &address_script(&change.address).unwrap())
+            .unwrap();
+        let rederived = Address::p2wpkh(&key.public_key(&secp), Network::Testnet).unwrap();
+        assert_eq!(rederived.to_string(), change.address);
+
+        assert!(matches!(
+            w.signing_key_for_script("acct", &[0x51]),
+            Err(HDWalletError::AddressNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn quantum_addresses_are_restored_from_mnemonic() {
+        use supernova_core::script::classify::address_hrp;
+
+        for scheme in [QuantumScheme::Dilithium, QuantumScheme::SphincsPlus] {
+            let dir1 = tempfile::tempdir().unwrap();
+            let dir2 = tempfile::tempdir().unwrap();
+            let mut original = HDWallet::from_mnemonic(
+                TEST_MNEMONIC,
+                None,
+                Network::Testnet,
+                dir1.path().join("w.json"),
+            )
+            .unwrap();
+            original
+                .create_account("pq".to_string(), AccountType::Quantum(scheme))
+                .unwrap();
+            let first = original.get_new_address("pq").unwrap();
+            let second = original.get_new_address("pq").unwrap();
+            assert_ne!(first.address, second.address);
+            assert!(first.address.starts_with(address_hrp()));
+            // The script is the bare 32-byte public key commitment
+            assert_eq!(address_script(&first.address).unwrap().len(), 32);
+
+            let mut restored = HDWallet::from_mnemonic(
+                TEST_MNEMONIC,
+                None,
+                Network::Testnet,
+                dir2.path().join("w.json"),
+            )
+            .unwrap();
+            restored
+                .create_account("pq".to_string(), AccountType::Quantum(scheme))
+                .unwrap();
+            assert_eq!(restored.get_new_address("pq").unwrap().address, first.address);
+            assert_eq!(restored.get_new_address("pq").unwrap().address, second.address);
+        }
+    }
+
+    #[test]
+    fn quantum_outputs_count_toward_the_balance() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("w.json"),
+        )
+        .unwrap();
+        w.create_account(
+            "pq".to_string(),
+            AccountType::Quantum(QuantumScheme::Dilithium),
+        )
+        .unwrap();
+        let utxos = UtxoSet::new_in_memory(10);
+        fund(&mut w, &utxos, "pq", 1, 5_000);
+        fund(&mut w, &utxos, "pq", 2, 7_000);
+
+        assert_eq!(w.get_balance("pq", &utxos).unwrap(), 12_000);
+        assert_eq!(w.spendable_utxos("pq", &utxos).unwrap().len(), 2);
+        // No secp256k1 key backs a quantum address
+        let script = utxos.entries()[0].output.pub_key_script.clone();
+        assert!(w.signing_key_for_script("pq", &script).is_err());
+
+        assert!(matches!(
+            w.create_account(
+                "falcon".to_string(),
+                AccountType::Quantum(QuantumScheme::Falcon)
+            ),
+            Err(HDWalletError::UnsupportedScheme(_))
+        ));
+    }
+
     /// R5-90: internal persistence must never silently downgrade an encrypted
     /// wallet to plaintext. After `save_encrypted()`, an address-generating call
     /// (which persists via the deprecated plaintext `save()`) must NOT rewrite
@@ -808,7 +2270,7 @@ mod hd_derivation_tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("wallet.json");
         let mut w =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, path.clone()).unwrap();
+            HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, path.clone()).unwrap();
 
         // Persist as an encrypted backup (quantum-resistant password required).
         let password = "Xq9!vTp#Lm7$Rw4&ZkBnHjCdFgVs";
@@ -837,7 +2299,7 @@ mod hd_derivation_tests {
 
         // The encrypted wallet is still decryptable and intact.
         let reloaded = HDWallet::load_encrypted(path, password).unwrap();
-        assert_eq!(reloaded.get_mnemonic(), TEST_MNEMONIC);
+        assert_eq!(reloaded.get_mnemonic().unwrap(), TEST_MNEMONIC);
     }
 
     /// SECURITY (R5-97): Wallet files must be created owner-only (0o600) so a
@@ -851,7 +2313,7 @@ mod hd_derivation_tests {
         // Encrypted backup path.
         let dir = tempfile::tempdir().unwrap();
         let enc_path = dir.path().join("wallet.enc");
-        let w = HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, enc_path.clone())
+        let w = HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, enc_path.clone())
             .unwrap();
         w.save_encrypted("Xq9!vTp#Lm7$Rw4&ZkBnHjCdFgVs").unwrap();
         let mode = std::fs::metadata(&enc_path).unwrap().permissions().mode();
@@ -859,7 +2321,7 @@ mod hd_derivation_tests {
 
         // Deprecated plaintext save path (fresh plaintext wallet).
         let plain_path = dir.path().join("wallet.json");
-        let w2 = HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, plain_path.clone())
+        let w2 = HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, plain_path.clone())
             .unwrap();
         #[allow(deprecated)]
         w2.save().unwrap();
@@ -870,7 +2332,7 @@ mod hd_derivation_tests {
         let loose_path = dir.path().join("loose.json");
         std::fs::write(&loose_path, b"{}").unwrap();
         std::fs::set_permissions(&loose_path, std::fs::Permissions::from_mode(0o644)).unwrap();
-        let w3 = HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, loose_path.clone())
+        let w3 = HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, loose_path.clone())
             .unwrap();
         #[allow(deprecated)]
         w3.save().unwrap();
@@ -886,7 +2348,7 @@ mod hd_derivation_tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("wallet.json");
         let mut w =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, path.clone()).unwrap();
+            HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, path.clone()).unwrap();
 
         // Fresh file: create_account persists via save() without error.
         w.create_account("acct".to_string(), AccountType::NativeSegWit)
@@ -912,7 +2374,7 @@ mod hd_derivation_tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("wallet.json");
         let mut w =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, path.clone()).unwrap();
+            HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, path.clone()).unwrap();
         w.create_account("acct".to_string(), AccountType::NativeSegWit)
             .unwrap();
 
@@ -920,7 +2382,7 @@ mod hd_derivation_tests {
         w.save_encrypted(password).unwrap();
 
         let reloaded = HDWallet::load_encrypted(path, password).unwrap();
-        assert_eq!(reloaded.get_mnemonic(), TEST_MNEMONIC);
+        assert_eq!(reloaded.get_mnemonic().unwrap(), TEST_MNEMONIC);
         assert!(reloaded.accounts.contains_key("acct"));
 
         // The seed-derivation path (also now Zeroizing the 64-byte BIP39 seed)
@@ -936,9 +2398,13 @@ mod hd_derivation_tests {
     #[test]
     fn debug_output_redacts_mnemonic() {
         let dir = tempfile::tempdir().unwrap();
-        let w =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, dir.path().join("w.json"))
-                .unwrap();
+        let w = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.path().join("w.json"),
+        )
+        .unwrap();
 
         let debug = format!("{:?}", w);
         // No seed word must appear in the Debug output.
@@ -962,7 +2428,7 @@ mod hd_derivation_tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("wallet.json");
         let mut w =
-            HDWallet::from_mnemonic(TEST_MNEMONIC, Network::Testnet, path.clone()).unwrap();
+            HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, path.clone()).unwrap();
 
         let password = "Xq9!vTp#Lm7$Rw4&ZkBnHjCdFgVs";
         w.save_encrypted(password).unwrap();
@@ -989,4 +2455,523 @@ mod hd_derivation_tests {
             Err(HDWalletError::DecryptionError(_))
         ));
     }
+
+    /// Spending policies and the rolling spend window persist in the wallet
+    /// file, and a policy edited on disk stops verifying.
+    #[test]
+    #[allow(deprecated)]
+    fn spending_policy_persists_and_detects_tampering() {
+        use crate::policy::{Destination, PolicyViolation, SpendingPolicy};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.json");
+        let mut w =
+            HDWallet::from_mnemonic(TEST_MNEMONIC, None, Network::Testnet, path.clone()).unwrap();
+        w.create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        w.set_spending_policy(
+            "main",
+            SpendingPolicy {
+                daily_limit: Some(1_000),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        w.authorize_spend("main", &Destination::address("dest"), 600)
+            .unwrap();
+
+        let mut reloaded = HDWallet::load(path.clone()).unwrap();
+        assert_eq!(reloaded.spent_last_24h("main"), 600);
+        assert!(matches!(
+            reloaded.authorize_spend("main", &Destination::address("dest"), 500),
+            Err(HDWalletError::Policy(PolicyViolation::DailyLimitExceeded {
+                spent: 600,
+                ..
+            }))
+        ));
+
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        json["spending_policies"]["policies"]["main"]["policy"]["daily_limit"] =
+            serde_json::json!(u64::MAX);
+        std::fs::write(&path, json.to_string()).unwrap();
+        let mut tampered = HDWallet::load(path).unwrap();
+        assert!(matches!(
+            tampered.authorize_spend("main", &Destination::address("dest"), 1),
+            Err(HDWalletError::Policy(PolicyViolation::PolicyTampered { .. }))
+        ));
+    }
+
+    fn fund(w: &mut HDWallet, utxos: &UtxoSet, account: &str, id: u8, amount: u64) {
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::{OutPoint, TransactionOutput};
+
+        let address = w.get_new_address(account).unwrap();
+        let script = address_script(address.get_address()).unwrap();
+        utxos
+            .add(UtxoEntry {
+                outpoint: OutPoint {
+                    txid: [id; 32],
+                    vout: 0,
+                },
+                output: TransactionOutput::new(amount, script),
+                height: 1,
+                is_coinbase: false,
+                is_confirmed: true,
+            })
+            .unwrap();
+    }
+
+    fn wallet_with_accounts(dir: &std::path::Path) -> HDWallet {
+        let mut w = HDWallet::from_mnemonic(
+            TEST_MNEMONIC,
+            None,
+            Network::Testnet,
+            dir.join("wallet.json"),
+        )
+        .unwrap();
+        w.create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        w.create_account("old".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        w
+    }
+
+    #[test]
+    fn archive_and_unarchive_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        let utxos = UtxoSet::new_in_memory(100);
+        fund(&mut w, &utxos, "old", 1, 0);
+
+        w.archive_account("old", &utxos, false).unwrap();
+        assert!(matches!(
+            w.get_new_address("old"),
+            Err(HDWalletError::AccountArchived(_))
+        ));
+
+        // The state survives a reload and the account comes back intact
+        let mut reloaded = HDWallet::load(dir.path().join("wallet.json")).unwrap();
+        assert_eq!(reloaded.list_accounts(false).len(), 1);
+        reloaded.unarchive_account("old").unwrap();
+        let (_, old) = reloaded
+            .list_accounts(false)
+            .into_iter()
+            .find(|(_, a)| a.name == "old")
+            .unwrap();
+        assert_eq!(old.state, AccountState::Active);
+        assert_eq!(old.addresses.len(), 1);
+        assert_eq!(reloaded.get_new_address("old").unwrap().index, 1);
+    }
+
+    #[test]
+    fn default_listing_excludes_archived_accounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        let utxos = UtxoSet::new_in_memory(100);
+        fund(&mut w, &utxos, "main", 1, 0);
+        fund(&mut w, &utxos, "old", 2, 0);
+        w.archive_account("old", &utxos, false).unwrap();
+
+        let names: Vec<_> = w
+            .list_accounts(false)
+            .into_iter()
+            .map(|(_, a)| a.name.clone())
+            .collect();
+        assert_eq!(names, vec!["main".to_string()]);
+        assert_eq!(w.list_accounts(true).len(), 2);
+
+        // Archived accounts are still synced, after the active ones
+        let sync = w.sync_addresses();
+        assert_eq!(sync.len(), 2);
+        assert_eq!(sync[0], w.accounts["main"].addresses[0].address);
+    }
+
+    #[test]
+    fn totals_split_active_and_archived_balances() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        let utxos = UtxoSet::new_in_memory(100);
+        fund(&mut w, &utxos, "main", 1, 700);
+        fund(&mut w, &utxos, "old", 2, 300);
+        w.archive_account("old", &utxos, true).unwrap();
+
+        let balances = w.get_balances(&utxos).unwrap();
+        assert_eq!(balances.active, 700);
+        assert_eq!(balances.archived, 300);
+        assert_eq!(balances.total(), 1_000);
+        assert_eq!(w.get_total_balance(&utxos).unwrap(), 700);
+        assert_eq!(w.get_balance("old", &utxos).unwrap(), 300);
+    }
+
+    #[test]
+    fn archiving_funded_account_needs_confirmation() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        let utxos = UtxoSet::new_in_memory(100);
+        fund(&mut w, &utxos, "old", 1, 5_000);
+
+        assert!(matches!(
+            w.archive_account("old", &utxos, false),
+            Err(HDWalletError::ArchiveNeedsConfirmation { balance: 5_000, .. })
+        ));
+        assert_eq!(w.list_accounts(false).len(), 2);
+
+        w.archive_account("old", &utxos, true).unwrap();
+        assert_eq!(w.list_accounts(false).len(), 1);
+    }
+
+    #[test]
+    fn purge_watch_removes_only_confirmed_watch_only_accounts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        let watched = w.get_new_address("main").unwrap().address;
+        w.import_watch_only_account("watched".to_string(), AccountType::NativeSegWit, vec![watched])
+            .unwrap();
+        assert!(matches!(
+            w.get_new_address("watched"),
+            Err(HDWalletError::WatchOnly(_))
+        ));
+
+        assert!(matches!(
+            w.purge_watch("main", true),
+            Err(HDWalletError::NotWatchOnly(_))
+        ));
+        assert!(matches!(
+            w.purge_watch("watched", false),
+            Err(HDWalletError::PurgeNeedsConfirmation(_))
+        ));
+        w.purge_watch("watched", true).unwrap();
+        assert_eq!(w.list_accounts(true).len(), 2);
+    }
+
+    const PASSPHRASE: &str = "Xq9!vTp#Lm7$Rw4&ZkBnHjCdFgVs";
+
+    /// An old plaintext wallet.json is upgraded in place: the mnemonic leaves
+    /// the file, while accounts, addresses and balances stay readable
+    #[test]
+    #[allow(deprecated)]
+    fn plaintext_wallet_is_upgraded_in_place() {
+        use crate::seed_vault::TEST_KDF;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.json");
+        let mut w = wallet_with_accounts(dir.path());
+        let utxos = UtxoSet::new_in_memory(100);
+        fund(&mut w, &utxos, "main", 1, 2_500);
+        let key = w.derive_address_private_key("main", 0).unwrap();
+
+        let mut old = HDWallet::load(path.clone()).unwrap();
+        assert!(old.stores_plaintext_seed());
+        assert!(matches!(
+            old.encrypt_with("weak", TEST_KDF),
+            Err(HDWalletError::PasswordTooWeak(_))
+        ));
+        old.encrypt_with(PASSPHRASE, TEST_KDF).unwrap();
+        assert!(old.is_locked());
+
+        let json = std::fs::read_to_string(&path).unwrap();
+        for word in TEST_MNEMONIC.split_whitespace() {
+            assert!(
+                !json.contains(word),
+                "upgraded wallet leaked seed word: {word}"
+            );
+        }
+        assert!(json.contains("sealed_seed"));
+
+        // Public data works without the passphrase
+        let mut upgraded = HDWallet::load(path.clone()).unwrap();
+        assert!(upgraded.is_encrypted() && upgraded.is_locked());
+        assert!(!upgraded.stores_plaintext_seed());
+        assert_eq!(upgraded.get_balance("main", &utxos).unwrap(), 2_500);
+        assert_eq!(upgraded.list_accounts(false).len(), 2);
+        assert!(matches!(
+            upgraded.derive_address_private_key("main", 0),
+            Err(HDWalletError::Locked(_))
+        ));
+        assert!(matches!(
+            upgraded.get_new_address("main"),
+            Err(HDWalletError::Locked(_))
+        ));
+        assert!(matches!(
+            upgraded.get_mnemonic(),
+            Err(HDWalletError::Locked(_))
+        ));
+        assert!(matches!(
+            upgraded.encrypt_with(PASSPHRASE, TEST_KDF),
+            Err(HDWalletError::EncryptionError(_))
+        ));
+
+        // Unlocked, the same keys derive and changes persist sealed
+        upgraded.unlock(PASSPHRASE, None).unwrap();
+        assert_eq!(upgraded.get_mnemonic().unwrap(), TEST_MNEMONIC);
+        assert_eq!(
+            upgraded
+                .derive_address_private_key("main", 0)
+                .unwrap()
+                .to_bytes(),
+            key.to_bytes()
+        );
+        upgraded.get_new_address("main").unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        assert!(!json.contains(TEST_MNEMONIC));
+        assert_eq!(HDWallet::load(path).unwrap().get_address_count(), 3);
+
+        upgraded.lock();
+        assert!(upgraded.is_locked());
+        assert!(upgraded.derive_address_private_key("main", 0).is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn wrong_passphrase_and_corrupted_seed_are_rejected() {
+        use crate::seed_vault::TEST_KDF;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.json");
+        let mut w = wallet_with_accounts(dir.path());
+        w.encrypt_with(PASSPHRASE, TEST_KDF).unwrap();
+
+        let mut reloaded = HDWallet::load(path.clone()).unwrap();
+        assert!(matches!(
+            reloaded.unlock("Not-The-Right-Passphrase-42!", None),
+            Err(HDWalletError::WrongPassphrase)
+        ));
+        assert!(reloaded.is_locked());
+
+        // Flip one ciphertext bit: the passphrase still checks out, but the
+        // AEAD tag does not
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let mut ciphertext =
+            hex::decode(json["sealed_seed"]["ciphertext"].as_str().unwrap()).unwrap();
+        ciphertext[3] ^= 0x80;
+        json["sealed_seed"]["ciphertext"] = serde_json::json!(hex::encode(ciphertext));
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        let mut corrupted = HDWallet::load(path).unwrap();
+        assert!(matches!(
+            corrupted.unlock(PASSPHRASE, None),
+            Err(HDWalletError::DecryptionError(message)) if message.contains("authentication")
+        ));
+        assert!(corrupted.is_locked());
+        // Public data is unaffected
+        assert_eq!(corrupted.list_accounts(false).len(), 2);
+    }
+
+    /// Official BIP39 vectors: mnemonic, passphrase, seed and master xprv
+    const BIP39_VECTORS: [(&str, &str, &str, &str); 3] = [
+        (
+            TEST_MNEMONIC,
+            "",
+            "5eb00bbddcf069084889a8ab9155568165f5c453ccb85e70811aaed6f6da5fc19a5ac40b389cd370d086206dec8aa6c43daea6690f20ad3d8d48b2d2ce9e38e4",
+            "xprv9s21ZrQH143K3GJpoapnV8SFfukcVBSfeCficPSGfubmSFDxo1kuHnLisriDvSnRRuL2Qrg5ggqHKNVpxR86QEC8w35uxmGoggxtQTPvfUu",
+        ),
+        (
+            TEST_MNEMONIC,
+            "TREZOR",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            "xprv9s21ZrQH143K3h3fDYiay8mocZ3afhfULfb5GX8kCBdno77K4HiA15Tg23wpbeF1pLfs1c5SPmYHrEpTuuRhxMwvKDwqdKiGJS9XFKzUsAF",
+        ),
+        (
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "TREZOR",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            "xprv9s21ZrQH143K2gA81bYFHqU68xz1cX2APaSq5tt6MFSLeXnCKV1RVUJt9FWNTbrrryem4ZckN8k4Ls1H6nwdvDTvnV7zEXs2HgPezuVccsq",
+        ),
+    ];
+
+    #[test]
+    fn bip39_seed_matches_the_official_vectors() {
+        let dir = tempfile::tempdir().unwrap();
+        for (mnemonic, passphrase, seed, xprv) in BIP39_VECTORS {
+            let w = HDWallet::from_mnemonic(
+                mnemonic,
+                Some(passphrase),
+                Network::Bitcoin,
+                dir.path().join("w.json"),
+            )
+            .unwrap();
+            let derived = w.bip39_seed().unwrap();
+            assert_eq!(hex::encode(&derived[..]), seed);
+            let master = Xpriv::new_master(Network::Bitcoin, &derived[..]).unwrap();
+            assert_eq!(master.to_string(), xprv);
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn bip39_passphrase_opens_a_different_wallet() {
+        use crate::seed_vault::TEST_KDF;
+
+        let dir = tempfile::tempdir().unwrap();
+        let first_address = |passphrase: Option<&str>, file: &str| {
+            let mut w = HDWallet::from_mnemonic(
+                TEST_MNEMONIC,
+                passphrase,
+                Network::Testnet,
+                dir.path().join(file),
+            )
+            .unwrap();
+            w.create_account("main".to_string(), AccountType::NativeSegWit)
+                .unwrap();
+            (w.get_new_address("main").unwrap().address, w)
+        };
+        let (plain, _) = first_address(None, "plain.json");
+        let (empty, _) = first_address(Some(""), "empty.json");
+        let (hidden, mut w) = first_address(Some("TREZOR"), "hidden.json");
+        assert_eq!(plain, empty);
+        assert_ne!(plain, hidden);
+
+        // The passphrase survives a reload and is sealed with the mnemonic
+        let path = dir.path().join("hidden.json");
+        let reloaded = HDWallet::load(path.clone()).unwrap();
+        assert_eq!(reloaded.bip39_seed().unwrap(), w.bip39_seed().unwrap());
+        w.encrypt_with(PASSPHRASE, TEST_KDF).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("TREZOR"));
+        let mut reloaded = HDWallet::load(path).unwrap();
+        reloaded.unlock(PASSPHRASE, None).unwrap();
+        assert_eq!(reloaded.get_mnemonic().unwrap(), TEST_MNEMONIC);
+        assert_eq!(
+            hex::encode(&reloaded.bip39_seed().unwrap()[..]),
+            BIP39_VECTORS[1].2
+        );
+    }
+
+    #[test]
+    fn unlock_expires_after_the_timeout() {
+        use crate::seed_vault::TEST_KDF;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        w.encrypt_with(PASSPHRASE, TEST_KDF).unwrap();
+
+        w.unlock(PASSPHRASE, Some(Duration::from_millis(50)))
+            .unwrap();
+        assert!(!w.is_locked());
+        assert!(w.derive_address_private_key("main", 0).is_ok());
+        assert!(!w.lock_if_expired());
+
+        std::thread::sleep(Duration::from_millis(80));
+        assert!(w.is_locked());
+        assert!(matches!(
+            w.derive_address_private_key("main", 0),
+            Err(HDWalletError::Locked(_))
+        ));
+        assert!(w.lock_if_expired());
+        assert!(w.unlocked.is_none());
+    }
+
+    #[test]
+    fn gap_limit_stops_new_addresses_until_one_is_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        assert_eq!(w.gap_limit(), DEFAULT_GAP_LIMIT);
+
+        let results: Vec<_> = (0..25).map(|_| w.get_new_address("main")).collect();
+        let issued: Vec<HDAddress> = results.into_iter().map_while(Result::ok).collect();
+        assert_eq!(issued.len(), 20);
+        assert!(matches!(
+            w.get_new_address("main"),
+            Err(HDWalletError::GapLimitReached { gap_limit: 20, .. })
+        ));
+        // Other accounts keep their own gap
+        assert!(w.get_new_address("old").is_ok());
+
+        // A payment to index 4 moves the gap along by five addresses
+        let script = address_script(&issued[4].address).unwrap();
+        assert!(w.mark_used(&script).unwrap());
+        for index in 20..25 {
+            assert_eq!(w.get_new_address("main").unwrap().index, index);
+        }
+        assert!(w.get_new_address("main").is_err());
+        w.set_gap_limit(21).unwrap();
+        assert_eq!(w.get_new_address("main").unwrap().index, 25);
+
+        let used = w
+            .list_addresses("main", AddressFilter::State(AddressState::Used))
+            .unwrap();
+        assert_eq!(used.len(), 1);
+        assert_eq!(used[0].index, 4);
+        assert!(matches!(
+            w.set_gap_limit(0),
+            Err(HDWalletError::InvalidGapLimit)
+        ));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn addresses_carry_labels_and_usage_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut w = wallet_with_accounts(dir.path());
+        let first = w.get_new_address("main").unwrap();
+        assert_eq!(first.state(), AddressState::Reserved);
+        w.label_address(&first.address, "  rent  ").unwrap();
+
+        // Addresses derived ahead are handed out before new ones
+        assert_eq!(w.extend_to_gap_limit(3).unwrap(), 2 + 3);
+        let ahead = w
+            .list_addresses("main", AddressFilter::State(AddressState::Unused))
+            .unwrap();
+        assert_eq!(ahead.len(), 2 + 3);
+        assert!(!ahead[0].change && ahead[4].change);
+        let second = w.get_new_address("main").unwrap();
+        assert_eq!(second.index, 1);
+        assert_eq!(
+            w.list_addresses("main", AddressFilter::All).unwrap().len(),
+            6
+        );
+
+        let received = |addresses: Vec<String>, direction| TransactionRecord {
+            hash: "tx".to_string(),
+            timestamp: Utc::now(),
+            direction,
+            amount: 1_000,
+            fee: 0,
+            status: crate::history::TransactionStatus::Pending,
+            label: None,
+            category: None,
+            tags: vec![],
+            memo: None,
+            addresses,
+        };
+        let records = [
+            received(vec![second.address.clone()], TransactionDirection::Sent),
+            received(
+                vec![first.address.clone(), "tb1qstranger".to_string()],
+                TransactionDirection::Received,
+            ),
+        ];
+        assert_eq!(w.mark_received(&records).unwrap(), 1);
+
+        let reloaded = HDWallet::load(dir.path().join("wallet.json")).unwrap();
+        let issued = reloaded
+            .list_addresses("main", AddressFilter::Issued)
+            .unwrap();
+        assert_eq!(issued.len(), 2);
+        assert_eq!(issued[0].state(), AddressState::Used);
+        assert_eq!(issued[0].label.as_deref(), Some("rent"));
+        assert_eq!(issued[1].state(), AddressState::Reserved);
+        assert_eq!(
+            reloaded
+                .list_addresses("main", AddressFilter::Labeled)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        w.label_address(&first.address, "").unwrap();
+        assert!(w
+            .list_addresses("main", AddressFilter::Labeled)
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            w.label_address(&first.address, &"x".repeat(MAX_ADDRESS_LABEL_LEN + 1)),
+            Err(HDWalletError::LabelTooLong(_))
+        ));
+        assert!(matches!(
+            w.label_address("tb1qstranger", "x"),
+            Err(HDWalletError::AddressNotFound(_))
+        ));
+    }
 }
@@ -1,8 +1,22 @@
+use crate::policy::hmac_sha256;
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
 use chrono::{DateTime, Utc};
+use rand::rngs::OsRng;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Maximum memo length in bytes (UTF-8).
+pub const MAX_MEMO_LEN: usize = 512;
+
+/// Domain tag mixed into the memo encryption key.
+const MEMO_KEY_DOMAIN: &[u8] = b"supernova-wallet/transaction-memo/v1";
 
 #[derive(Error, Debug)]
 pub enum HistoryError {
@@ -14,21 +28,30 @@ pub enum HistoryError {
     TransactionNotFound,
     #[error("Invalid transaction data")]
     InvalidTransactionData,
+    #[error("Wallet is locked; memos are unavailable")]
+    Locked,
+    #[error("Memo is {0} bytes, the maximum is {MAX_MEMO_LEN}")]
+    MemoTooLong(usize),
+    #[error("Memo encryption failed")]
+    MemoEncryption,
+    #[error("Invalid import data on line {line}: {reason}")]
+    InvalidImport { line: usize, reason: String },
 }
 
 /// Transaction direction
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransactionDirection {
     Sent,
     Received,
 }
 
 /// Transaction status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TransactionStatus {
     Pending,
     Confirmed(u32), // Number of confirmations
     Failed,
+    Replaced(String), // Hash of the replacing transaction
 }
 
 /// Transaction record with metadata
@@ -43,6 +66,157 @@ pub struct TransactionRecord {
     pub label: Option<String>,
     pub category: Option<String>,
     pub tags: Vec<String>,
+    /// Private note, encrypted with a key derived from the wallet seed.
+    /// Absent in history files written before memos existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<EncryptedMemo>,
+    /// Wallet addresses the transaction pays; adding a received record
+    /// marks them used
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub addresses: Vec<String>,
+}
+
+/// AES-256-GCM ciphertext of a memo. The transaction hash is bound as
+/// associated data, so a memo copied onto another record fails to decrypt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedMemo {
+    /// Hex-encoded 96-bit nonce
+    pub nonce: String,
+    /// Hex-encoded ciphertext and tag
+    pub ciphertext: String,
+}
+
+/// Options for [`TransactionHistory::export`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryExportOptions {
+    /// Include decrypted memos. Ignored while the wallet is locked.
+    pub include_memos: bool,
+}
+
+/// File format for [`TransactionHistory::export`] and
+/// [`TransactionHistory::import`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    /// One row per record under [`CSV_HEADER`]. Tags are joined with `;`
+    /// and memos are left out.
+    Csv,
+    /// An array of full [`TransactionRecord`]s, memos still encrypted
+    Json,
+}
+
+/// Column order of CSV exports. Changing it breaks re-import of older files.
+pub const CSV_HEADER: &str = "hash,timestamp,direction,amount,fee,status,label,category,tags";
+
+/// Records selected for export. Unset fields match every record.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Earliest timestamp, inclusive
+    pub from: Option<DateTime<Utc>>,
+    /// Latest timestamp, exclusive
+    pub until: Option<DateTime<Utc>>,
+    pub direction: Option<TransactionDirection>,
+    pub category: Option<String>,
+    pub tag: Option<String>,
+    /// Smallest amount, inclusive
+    pub min_amount: Option<u64>,
+    /// Largest amount, inclusive
+    pub max_amount: Option<u64>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, record: &TransactionRecord) -> bool {
+        self.from.map_or(true, |from| record.timestamp >= from)
+            && self.until.map_or(true, |until| record.timestamp < until)
+            && self.direction.map_or(true, |d| record.direction == d)
+            && self
+                .category
+                .as_ref()
+                .map_or(true, |c| record.category.as_ref() == Some(c))
+            && self.tag.as_ref().map_or(true, |t| record.tags.contains(t))
+            && self.min_amount.map_or(true, |min| record.amount >= min)
+            && self.max_amount.map_or(true, |max| record.amount <= max)
+    }
+}
+
+/// What [`TransactionHistory::import`] changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Records not previously in the history
+    pub added: usize,
+    /// Existing records that gained a label, category, memo or tags
+    pub updated: usize,
+    /// Existing records the import had nothing to add to
+    pub unchanged: usize,
+}
+
+/// A history record as exported: memos are plaintext or omitted, never
+/// ciphertext.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedTransaction {
+    pub hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub direction: TransactionDirection,
+    pub amount: u64,
+    pub fee: u64,
+    pub status: TransactionStatus,
+    pub label: Option<String>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Derive the memo encryption key from the wallet's BIP39 seed. Memos are
+/// therefore readable from the mnemonic plus the history file alone.
+pub fn derive_memo_key(seed: &[u8]) -> Zeroizing<[u8; 32]> {
+    Zeroizing::new(hmac_sha256(MEMO_KEY_DOMAIN, seed))
+}
+
+/// Totals over every record in the history. Kept current as records are
+/// inserted or replaced, so reading them does not re-scan the history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryStats {
+    pub total_sent: u64,
+    pub total_received: u64,
+    pub total_fees: u64,
+    pub transaction_count: usize,
+}
+
+impl HistoryStats {
+    /// Totals computed from scratch
+    pub fn from_records<'a>(records: impl IntoIterator<Item = &'a TransactionRecord>) -> Self {
+        let mut stats = Self::default();
+        for record in records {
+            stats.add(record);
+        }
+        stats
+    }
+
+    fn add(&mut self, record: &TransactionRecord) {
+        match record.direction {
+            TransactionDirection::Sent => {
+                self.total_sent = self.total_sent.wrapping_add(record.amount)
+            }
+            TransactionDirection::Received => {
+                self.total_received = self.total_received.wrapping_add(record.amount)
+            }
+        }
+        self.total_fees = self.total_fees.wrapping_add(record.fee);
+        self.transaction_count += 1;
+    }
+
+    fn remove(&mut self, record: &TransactionRecord) {
+        match record.direction {
+            TransactionDirection::Sent => {
+                self.total_sent = self.total_sent.wrapping_sub(record.amount)
+            }
+            TransactionDirection::Received => {
+                self.total_received = self.total_received.wrapping_sub(record.amount)
+            }
+        }
+        self.total_fees = self.total_fees.wrapping_sub(record.fee);
+        self.transaction_count -= 1;
+    }
 }
 
 /// Transaction history manager
@@ -50,6 +224,13 @@ pub struct TransactionRecord {
 pub struct TransactionHistory {
     transactions: HashMap<String, TransactionRecord>,
     history_path: PathBuf,
+    /// Present while the wallet is unlocked
+    memo_key: Option<Zeroizing<[u8; 32]>>,
+    /// Running totals, updated by every insertion
+    stats: HistoryStats,
+    /// Recompute the totals on every read and check them against `stats`.
+    /// Test-only: a mismatch panics.
+    verify_stats: bool,
 }
 
 impl TransactionHistory {
@@ -58,6 +239,9 @@ impl TransactionHistory {
         let mut history = Self {
             transactions: HashMap::new(),
             history_path,
+            memo_key: None,
+            stats: HistoryStats::default(),
+            verify_stats: false,
         };
 
         history.load()?;
@@ -66,11 +250,29 @@ impl TransactionHistory {
 
     /// Add a transaction to history
     pub fn add_transaction(&mut self, record: TransactionRecord) -> Result<(), HistoryError> {
-        self.transactions.insert(record.hash.clone(), record);
+        self.insert_record(record);
         self.save()?;
         Ok(())
     }
 
+    /// Add several transactions, writing the history file once
+    pub fn add_transactions(
+        &mut self,
+        records: impl IntoIterator<Item = TransactionRecord>,
+    ) -> Result<(), HistoryError> {
+        for record in records {
+            self.insert_record(record);
+        }
+        self.save()
+    }
+
+    fn insert_record(&mut self, record: TransactionRecord) {
+        self.stats.add(&record);
+        if let Some(previous) = self.transactions.insert(record.hash.clone(), record) {
+            self.stats.remove(&previous);
+        }
+    }
+
     /// Update transaction status
     pub fn update_transaction_status(
         &mut self,
@@ -86,6 +288,24 @@ impl TransactionHistory {
         }
     }
 
+    /// Record that `replacement` was accepted in place of `original`: the
+    /// original is marked replaced and the replacement is added as pending
+    pub fn record_replacement(
+        &mut self,
+        original: &str,
+        mut replacement: TransactionRecord,
+    ) -> Result<(), HistoryError> {
+        let record = self
+            .transactions
+            .get_mut(original)
+            .ok_or(HistoryError::TransactionNotFound)?;
+        record.status = TransactionStatus::Replaced(replacement.hash.clone());
+        replacement.status = TransactionStatus::Pending;
+        self.insert_record(replacement);
+        self.save()?;
+        Ok(())
+    }
+
     /// Update transaction label
     pub fn add_transaction_label(&mut self, hash: &str, label: String) -> Result<(), HistoryError> {
         if let Some(record) = self.transactions.get_mut(hash) {
@@ -123,6 +343,165 @@ impl TransactionHistory {
         }
     }
 
+    /// Make memos readable and writable with `key` (see [`derive_memo_key`]).
+    pub fn unlock_memos(&mut self, key: Zeroizing<[u8; 32]>) {
+        self.memo_key = Some(key);
+    }
+
+    /// Forget the memo key; memos read as `None` until unlocked again.
+    pub fn lock_memos(&mut self) {
+        self.memo_key = None;
+    }
+
+    pub fn memos_unlocked(&self) -> bool {
+        self.memo_key.is_some()
+    }
+
+    /// Set, replace, or (with empty `text`) clear a transaction's memo.
+    pub fn add_transaction_memo(&mut self, hash: &str, text: &str) -> Result<(), HistoryError> {
+        if text.len() > MAX_MEMO_LEN {
+            return Err(HistoryError::MemoTooLong(text.len()));
+        }
+        let key = self.memo_key.as_ref().ok_or(HistoryError::Locked)?;
+        let record = self
+            .transactions
+            .get_mut(hash)
+            .ok_or(HistoryError::TransactionNotFound)?;
+        record.memo = if text.is_empty() {
+            None
+        } else {
+            Some(encrypt_memo(key, hash, text)?)
+        };
+        self.save()
+    }
+
+    /// Decrypted memo for a transaction. `None` when there is no memo, the
+    /// wallet is locked, or the memo does not decrypt under this wallet's key.
+    pub fn get_transaction_memo(&self, hash: &str) -> Option<String> {
+        let key = self.memo_key.as_ref()?;
+        let record = self.transactions.get(hash)?;
+        decrypt_memo(key, &record.hash, record.memo.as_ref()?)
+    }
+
+    /// All transactions, newest first, in export form. Memos are included
+    /// only when requested and the wallet is unlocked.
+    pub fn export_entries(&self, options: HistoryExportOptions) -> Vec<ExportedTransaction> {
+        self.get_all_transactions()
+            .into_iter()
+            .map(|tx| ExportedTransaction {
+                hash: tx.hash.clone(),
+                timestamp: tx.timestamp,
+                direction: tx.direction.clone(),
+                amount: tx.amount,
+                fee: tx.fee,
+                status: tx.status.clone(),
+                label: tx.label.clone(),
+                category: tx.category.clone(),
+                tags: tx.tags.clone(),
+                memo: if options.include_memos {
+                    self.get_transaction_memo(&tx.hash)
+                } else {
+                    None
+                },
+            })
+            .collect()
+    }
+
+    /// Records matching `filter`, newest first, as CSV or JSON for
+    /// accounting tools. Records with equal timestamps are ordered by hash
+    /// so repeated exports are identical.
+    pub fn export(
+        &self,
+        format: ExportFormat,
+        filter: HistoryFilter,
+    ) -> Result<String, HistoryError> {
+        let mut records: Vec<&TransactionRecord> = self
+            .transactions
+            .values()
+            .filter(|record| filter.matches(record))
+            .collect();
+        records.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(a.hash.cmp(&b.hash)));
+
+        match format {
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(&records)?),
+            ExportFormat::Csv => {
+                let mut out = String::from(CSV_HEADER);
+                out.push('\n');
+                for record in records {
+                    let row = [
+                        record.hash.clone(),
+                        record.timestamp.to_rfc3339(),
+                        format!("{:?}", record.direction),
+                        record.amount.to_string(),
+                        record.fee.to_string(),
+                        record.status.to_string(),
+                        record.label.clone().unwrap_or_default(),
+                        record.category.clone().unwrap_or_default(),
+                        record.tags.join(";"),
+                    ];
+                    let fields: Vec<String> = row.iter().map(|f| csv_escape(f)).collect();
+                    out.push_str(&fields.join(","));
+                    out.push('\n');
+                }
+                Ok(out)
+            }
+        }
+    }
+
+    /// Merge records previously produced by [`export`](Self::export). New
+    /// hashes are added. For hashes already present the existing record
+    /// wins: only a missing label, category or memo is filled in, and new
+    /// tags are appended. The history is written once, and only if the
+    /// whole input parses.
+    pub fn import(
+        &mut self,
+        format: ExportFormat,
+        data: &str,
+    ) -> Result<ImportSummary, HistoryError> {
+        let records = match format {
+            ExportFormat::Json => serde_json::from_str::<Vec<TransactionRecord>>(data)?,
+            ExportFormat::Csv => parse_csv_records(data)?,
+        };
+
+        let mut summary = ImportSummary::default();
+        for imported in records {
+            let Some(existing) = self.transactions.get_mut(&imported.hash) else {
+                summary.added += 1;
+                self.insert_record(imported);
+                continue;
+            };
+            let mut changed = false;
+            if existing.label.is_none() && imported.label.is_some() {
+                existing.label = imported.label;
+                changed = true;
+            }
+            if existing.category.is_none() && imported.category.is_some() {
+                existing.category = imported.category;
+                changed = true;
+            }
+            if existing.memo.is_none() && imported.memo.is_some() {
+                existing.memo = imported.memo;
+                changed = true;
+            }
+            for tag in imported.tags {
+                if !existing.tags.contains(&tag) {
+                    existing.tags.push(tag);
+                    changed = true;
+                }
+            }
+            if changed {
+                summary.updated += 1;
+            } else {
+                summary.unchanged += 1;
+            }
+        }
+
+        if summary.added + summary.updated > 0 {
+            self.save()?;
+        }
+        Ok(summary)
+    }
+
     /// Get transaction by hash
     pub fn get_transaction(&self, hash: &str) -> Option<&TransactionRecord> {
         self.transactions.get(hash)
@@ -158,51 +537,238 @@ impl TransactionHistory {
             .collect()
     }
 
+    /// Totals over the whole history
+    pub fn stats(&self) -> HistoryStats {
+        if self.verify_stats {
+            assert_eq!(
+                self.stats,
+                self.recompute_stats(),
+                "history totals diverged from the records"
+            );
+        }
+        self.stats
+    }
+
+    /// Totals computed by scanning every record, bypassing the running totals
+    pub fn recompute_stats(&self) -> HistoryStats {
+        HistoryStats::from_records(self.transactions.values())
+    }
+
+    /// Check the running totals against a full recompute on every read.
+    /// For tests; a mismatch panics.
+    pub fn set_verify_stats(&mut self, verify: bool) {
+        self.verify_stats = verify;
+    }
+
+    /// Number of transactions
+    pub fn transaction_count(&self) -> usize {
+        self.stats().transaction_count
+    }
+
     /// Get total sent amount
     pub fn get_total_sent(&self) -> u64 {
-        self.transactions
-            .values()
-            .filter(|tx| matches!(tx.direction, TransactionDirection::Sent))
-            .map(|tx| tx.amount)
-            .sum()
+        self.stats().total_sent
     }
 
     /// Get total received amount
     pub fn get_total_received(&self) -> u64 {
-        self.transactions
-            .values()
-            .filter(|tx| matches!(tx.direction, TransactionDirection::Received))
-            .map(|tx| tx.amount)
-            .sum()
+        self.stats().total_received
     }
 
     /// Get total fees paid
     pub fn get_total_fees(&self) -> u64 {
-        self.transactions.values().map(|tx| tx.fee).sum()
+        self.stats().total_fees
     }
 
     /// Calculate net flow (received - sent - fees)
     pub fn get_net_flow(&self) -> i64 {
-        let sent = self.get_total_sent() as i64;
-        let received = self.get_total_received() as i64;
-        received - sent
+        let stats = self.stats();
+        stats.total_received as i64 - stats.total_sent as i64
     }
 
     fn load(&mut self) -> Result<(), HistoryError> {
         if self.history_path.exists() {
             let data = std::fs::read_to_string(&self.history_path)?;
             self.transactions = serde_json::from_str(&data)?;
+            self.stats = self.recompute_stats();
         }
         Ok(())
     }
 
     fn save(&self) -> Result<(), HistoryError> {
         let data = serde_json::to_string_pretty(&self.transactions)?;
-        std::fs::write(&self.history_path, data)?;
+        crate::hdwallet::write_wallet_file_secure(&self.history_path, data.as_bytes())?;
         Ok(())
     }
 }
 
+fn encrypt_memo(key: &[u8; 32], hash: &str, text: &str) -> Result<EncryptedMemo, HistoryError> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| HistoryError::MemoEncryption)?;
+    let mut nonce = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: text.as_bytes(),
+                aad: hash.as_bytes(),
+            },
+        )
+        .map_err(|_| HistoryError::MemoEncryption)?;
+    Ok(EncryptedMemo {
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    })
+}
+
+fn decrypt_memo(key: &[u8; 32], hash: &str, memo: &EncryptedMemo) -> Option<String> {
+    let nonce = hex::decode(&memo.nonce).ok().filter(|n| n.len() == 12)?;
+    let ciphertext = hex::decode(&memo.ciphertext).ok()?;
+    let cipher = Aes256Gcm::new_from_slice(key).ok()?;
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: hash.as_bytes(),
+            },
+        )
+        .ok()?;
+    String::from_utf8(plaintext).ok()
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Split CSV text into rows of fields, honouring quoted fields. Returns
+/// each row with the line it starts on.
+fn parse_csv(data: &str) -> Result<Vec<(usize, Vec<String>)>, HistoryError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = data.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => row.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes && chars.peek() == Some(&'\n') => {}
+            '\n' if !in_quotes => {
+                row.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut row)));
+                line += 1;
+                row_line = line;
+            }
+            _ => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if in_quotes {
+        return Err(HistoryError::InvalidImport {
+            line: row_line,
+            reason: "unterminated quoted field".to_string(),
+        });
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+    Ok(rows)
+}
+
+fn parse_csv_records(data: &str) -> Result<Vec<TransactionRecord>, HistoryError> {
+    let mut rows = parse_csv(data)?.into_iter();
+    match rows.next() {
+        Some((_, header)) if header.join(",") == CSV_HEADER => {}
+        _ => {
+            return Err(HistoryError::InvalidImport {
+                line: 1,
+                reason: format!("expected header `{}`", CSV_HEADER),
+            })
+        }
+    }
+
+    let mut records = Vec::new();
+    for (line, fields) in rows {
+        let invalid = |reason: String| HistoryError::InvalidImport { line, reason };
+        let [hash, timestamp, direction, amount, fee, status, label, category, tags]: [String; 9] =
+            fields.try_into().map_err(|f: Vec<String>| {
+                invalid(format!("expected 9 fields, found {}", f.len()))
+            })?;
+        let optional = |s: String| if s.is_empty() { None } else { Some(s) };
+        records.push(TransactionRecord {
+            hash,
+            timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                .map_err(|e| invalid(format!("timestamp: {}", e)))?
+                .with_timezone(&Utc),
+            direction: match direction.as_str() {
+                "Sent" => TransactionDirection::Sent,
+                "Received" => TransactionDirection::Received,
+                other => return Err(invalid(format!("unknown direction `{}`", other))),
+            },
+            amount: amount
+                .parse()
+                .map_err(|e| invalid(format!("amount: {}", e)))?,
+            fee: fee.parse().map_err(|e| invalid(format!("fee: {}", e)))?,
+            status: status.parse().map_err(invalid)?,
+            label: optional(label),
+            category: optional(category),
+            tags: tags
+                .split(';')
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect(),
+            memo: None,
+        });
+    }
+    Ok(records)
+}
+
+impl std::str::FromStr for TransactionStatus {
+    type Err = String;
+
+    /// Parse the form written by `Display`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(by) = s.strip_prefix("Replaced by ") {
+            return Ok(TransactionStatus::Replaced(by.to_string()));
+        }
+        if let Some(count) = s
+            .strip_prefix("Confirmed (")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return count
+                .parse()
+                .map(TransactionStatus::Confirmed)
+                .map_err(|e| format!("confirmations: {}", e));
+        }
+        match s {
+            "Pending" => Ok(TransactionStatus::Pending),
+            "Failed" => Ok(TransactionStatus::Failed),
+            other => Err(format!("unknown status `{}`", other)),
+        }
+    }
+}
+
 impl std::fmt::Display for TransactionStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -211,6 +777,7 @@ impl std::fmt::Display for TransactionStatus {
                 write!(f, "Confirmed ({})", confirmations)
             }
             TransactionStatus::Failed => write!(f, "Failed"),
+            TransactionStatus::Replaced(by) => write!(f, "Replaced by {}", by),
         }
     }
 }
@@ -237,6 +804,8 @@ mod tests {
             label: None,
             category: None,
             tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
         };
 
         // Add transaction
@@ -296,4 +865,416 @@ mod tests {
         assert_eq!(history.get_total_fees(), 10);
         assert_eq!(history.get_net_flow(), -1000);
     }
+
+    fn record(hash: &str, timestamp: DateTime<Utc>) -> TransactionRecord {
+        TransactionRecord {
+            hash: hash.to_string(),
+            timestamp,
+            direction: TransactionDirection::Received,
+            amount: 5000,
+            fee: 0,
+            status: TransactionStatus::Confirmed(3),
+            label: None,
+            category: None,
+            tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stats_track_inserts_overwrites_and_reload() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let mut history = TransactionHistory::new(path.clone()).unwrap();
+        history.set_verify_stats(true);
+
+        history
+            .add_transactions((0..10).map(|i| record(&format!("tx{}", i), Utc::now())))
+            .unwrap();
+        let mut sent = record("sent", Utc::now());
+        sent.direction = TransactionDirection::Sent;
+        sent.amount = 700;
+        sent.fee = 30;
+        history.add_transaction(sent.clone()).unwrap();
+
+        // Re-adding a record replaces it rather than counting it twice
+        sent.fee = 50;
+        history.add_transaction(sent).unwrap();
+        let mut bump = record("bump", Utc::now());
+        bump.direction = TransactionDirection::Sent;
+        bump.amount = 700;
+        bump.fee = 80;
+        history.record_replacement("sent", bump).unwrap();
+
+        let stats = history.stats();
+        assert_eq!(stats.transaction_count, 12);
+        assert_eq!(stats.total_received, 50_000);
+        assert_eq!(stats.total_sent, 1_400);
+        assert_eq!(stats.total_fees, 130);
+        assert_eq!(history.get_net_flow(), 50_000 - 1_400);
+
+        let mut reloaded = TransactionHistory::new(path).unwrap();
+        reloaded.set_verify_stats(true);
+        assert_eq!(reloaded.stats(), stats);
+    }
+
+    #[test]
+    fn memo_round_trips_and_is_hidden_while_locked() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let mut history = TransactionHistory::new(path.clone()).unwrap();
+        history.add_transaction(record("tx1", Utc::now())).unwrap();
+
+        assert!(matches!(
+            history.add_transaction_memo("tx1", "rent"),
+            Err(HistoryError::Locked)
+        ));
+
+        history.unlock_memos(derive_memo_key(b"seed"));
+        history.add_transaction_memo("tx1", "rent for march").unwrap();
+        assert_eq!(history.get_transaction_memo("tx1").as_deref(), Some("rent for march"));
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("rent"));
+
+        // Reloaded from disk and unlocked with the same seed-derived key.
+        let mut reloaded = TransactionHistory::new(path).unwrap();
+        assert_eq!(reloaded.get_transaction_memo("tx1"), None);
+        reloaded.unlock_memos(derive_memo_key(b"other seed"));
+        assert_eq!(reloaded.get_transaction_memo("tx1"), None);
+        reloaded.unlock_memos(derive_memo_key(b"seed"));
+        assert_eq!(reloaded.get_transaction_memo("tx1").as_deref(), Some("rent for march"));
+        reloaded.lock_memos();
+        assert_eq!(reloaded.get_transaction_memo("tx1"), None);
+
+        reloaded.unlock_memos(derive_memo_key(b"seed"));
+        assert!(matches!(
+            reloaded.add_transaction_memo("tx1", &"x".repeat(MAX_MEMO_LEN + 1)),
+            Err(HistoryError::MemoTooLong(_))
+        ));
+        reloaded.add_transaction_memo("tx1", "").unwrap();
+        assert!(reloaded.get_transaction("tx1").unwrap().memo.is_none());
+    }
+
+    #[test]
+    fn export_excludes_memos_unless_requested() {
+        let dir = tempdir().unwrap();
+        let mut history = TransactionHistory::new(dir.path().join("history.json")).unwrap();
+        history.add_transaction(record("tx1", Utc::now())).unwrap();
+        history.unlock_memos(derive_memo_key(b"seed"));
+        history.add_transaction_memo("tx1", "private").unwrap();
+
+        let default = history.export_entries(HistoryExportOptions::default());
+        assert_eq!(default[0].memo, None);
+        assert!(!serde_json::to_string(&default).unwrap().contains("memo"));
+
+        let with_memos = history.export_entries(HistoryExportOptions { include_memos: true });
+        assert_eq!(with_memos[0].memo.as_deref(), Some("private"));
+
+        history.lock_memos();
+        assert_eq!(
+            history.export_entries(HistoryExportOptions { include_memos: true })[0].memo,
+            None
+        );
+    }
+
+    #[test]
+    fn history_with_memos_loads_in_code_without_memo_field() {
+        // The record shape before memos were added.
+        #[derive(Deserialize)]
+        struct LegacyRecord {
+            hash: String,
+            amount: u64,
+            tags: Vec<String>,
+            label: Option<String>,
+        }
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let mut history = TransactionHistory::new(path.clone()).unwrap();
+        history.add_transaction(record("tx1", Utc::now())).unwrap();
+        history.unlock_memos(derive_memo_key(b"seed"));
+        history.add_transaction_memo("tx1", "note").unwrap();
+
+        let data = std::fs::read_to_string(&path).unwrap();
+        assert!(data.contains("\"memo\""));
+        let legacy: HashMap<String, LegacyRecord> = serde_json::from_str(&data).unwrap();
+        assert_eq!(legacy["tx1"].hash, "tx1");
+        assert_eq!(legacy["tx1"].amount, 5000);
+        assert!(legacy["tx1"].tags.is_empty() && legacy["tx1"].label.is_none());
+
+        // And a file written before memos existed loads with no memo.
+        let old = r#"{"tx0":{"hash":"tx0","timestamp":"2026-01-01T00:00:00Z","direction":"Sent","amount":1,"fee":0,"status":"Pending","label":null,"category":null,"tags":[]}}"#;
+        std::fs::write(&path, old).unwrap();
+        let loaded = TransactionHistory::new(path).unwrap();
+        assert!(loaded.get_transaction("tx0").unwrap().memo.is_none());
+    }
+
+    #[test]
+    fn replacement_marks_original_and_tracks_new_transaction() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let mut history = TransactionHistory::new(path.clone()).unwrap();
+        history.add_transaction(record("orig", Utc::now())).unwrap();
+
+        let mut bumped = record("bumped", Utc::now());
+        bumped.fee = 20;
+        bumped.status = TransactionStatus::Failed;
+        history.record_replacement("orig", bumped).unwrap();
+
+        assert!(matches!(
+            &history.get_transaction("orig").unwrap().status,
+            TransactionStatus::Replaced(by) if by == "bumped"
+        ));
+        assert!(matches!(
+            history.get_transaction("bumped").unwrap().status,
+            TransactionStatus::Pending
+        ));
+
+        // The replacement confirms while the original stays replaced
+        history
+            .update_transaction_status("bumped", TransactionStatus::Confirmed(1))
+            .unwrap();
+        let reloaded = TransactionHistory::new(path).unwrap();
+        assert_eq!(
+            reloaded.get_transaction("orig").unwrap().status.to_string(),
+            "Replaced by bumped"
+        );
+        assert!(matches!(
+            reloaded.get_transaction("bumped").unwrap().status,
+            TransactionStatus::Confirmed(1)
+        ));
+
+        assert!(matches!(
+            history.record_replacement("missing", record("other", Utc::now())),
+            Err(HistoryError::TransactionNotFound)
+        ));
+    }
+
+    /// Fifty records a day apart: every third is sent, amounts step by 1000,
+    /// categories rotate through three values and tags overlap.
+    fn accounting_history(path: PathBuf) -> (TransactionHistory, DateTime<Utc>) {
+        let start = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let mut history = TransactionHistory::new(path).unwrap();
+        let records = (0..50u64).map(|i| {
+            let mut r = record(
+                &format!("tx{:02}", i),
+                start + chrono::Duration::days(i as i64),
+            );
+            if i % 3 == 0 {
+                r.direction = TransactionDirection::Sent;
+                r.fee = 10;
+            }
+            r.amount = (i + 1) * 1000;
+            r.category = Some(["payroll", "rent", "misc"][(i % 3) as usize].to_string());
+            if i % 2 == 0 {
+                r.tags.push("even".to_string());
+            }
+            if i % 5 == 0 {
+                r.tags.push("tax".to_string());
+            }
+            if i % 7 == 0 {
+                r.label = Some(format!("Invoice {}, \"final\"", i));
+            }
+            r
+        });
+        history.add_transactions(records).unwrap();
+        (history, start)
+    }
+
+    fn exported_hashes(history: &TransactionHistory, filter: HistoryFilter) -> Vec<String> {
+        let json = history.export(ExportFormat::Json, filter).unwrap();
+        let records: Vec<TransactionRecord> = serde_json::from_str(&json).unwrap();
+        records.into_iter().map(|r| r.hash).collect()
+    }
+
+    #[test]
+    fn export_filters_combine() {
+        let dir = tempdir().unwrap();
+        let (history, start) = accounting_history(dir.path().join("history.json"));
+
+        let all = exported_hashes(&history, HistoryFilter::default());
+        assert_eq!(all.len(), 50);
+        assert_eq!(all[0], "tx49", "newest first");
+
+        // Days 10..20, half-open
+        let january = HistoryFilter {
+            from: Some(start + chrono::Duration::days(10)),
+            until: Some(start + chrono::Duration::days(20)),
+            ..Default::default()
+        };
+        assert_eq!(exported_hashes(&history, january.clone()).len(), 10);
+
+        let sent_in_range = HistoryFilter {
+            direction: Some(TransactionDirection::Sent),
+            ..january
+        };
+        assert_eq!(
+            exported_hashes(&history, sent_in_range),
+            vec!["tx18", "tx15", "tx12"]
+        );
+
+        // tax and even overlap on multiples of ten
+        let tax = HistoryFilter {
+            tag: Some("tax".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(exported_hashes(&history, tax.clone()).len(), 10);
+        let even = HistoryFilter {
+            tag: Some("even".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(exported_hashes(&history, even).len(), 25);
+
+        let rent_amounts = HistoryFilter {
+            category: Some("rent".to_string()),
+            min_amount: Some(10_000),
+            max_amount: Some(30_000),
+            ..Default::default()
+        };
+        // i % 3 == 1 with amount (i + 1) * 1000 in 10k..=30k: i = 10, 13, ..., 28
+        assert_eq!(exported_hashes(&history, rent_amounts).len(), 7);
+
+        let tax_rent = HistoryFilter {
+            category: Some("rent".to_string()),
+            ..tax
+        };
+        assert_eq!(
+            exported_hashes(&history, tax_rent),
+            vec!["tx40", "tx25", "tx10"]
+        );
+
+        let nothing = HistoryFilter {
+            min_amount: Some(40_000),
+            max_amount: Some(30_000),
+            ..Default::default()
+        };
+        assert!(exported_hashes(&history, nothing).is_empty());
+    }
+
+    #[test]
+    fn csv_export_escapes_and_keeps_column_order() {
+        let dir = tempdir().unwrap();
+        let (history, _) = accounting_history(dir.path().join("history.json"));
+
+        let filter = HistoryFilter {
+            until: Some(
+                DateTime::parse_from_rfc3339("2025-01-02T00:00:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            ..Default::default()
+        };
+        let csv = history.export(ExportFormat::Csv, filter.clone()).unwrap();
+        assert_eq!(
+            csv,
+            format!(
+                "{}\ntx00,2025-01-01T00:00:00+00:00,Sent,1000,10,Confirmed (3),\
+                 \"Invoice 0, \"\"final\"\"\",payroll,even;tax\n",
+                CSV_HEADER
+            )
+        );
+        // Same input, same bytes
+        assert_eq!(history.export(ExportFormat::Csv, filter).unwrap(), csv);
+    }
+
+    #[test]
+    fn import_round_trips_both_formats() {
+        let dir = tempdir().unwrap();
+        let (history, _) = accounting_history(dir.path().join("history.json"));
+
+        for format in [ExportFormat::Csv, ExportFormat::Json] {
+            let data = history.export(format, HistoryFilter::default()).unwrap();
+            let mut fresh =
+                TransactionHistory::new(dir.path().join(format!("{:?}.json", format))).unwrap();
+            let summary = fresh.import(format, &data).unwrap();
+            assert_eq!(summary.added, 50);
+            assert_eq!(fresh.stats(), history.stats());
+            assert_eq!(
+                fresh
+                    .export(ExportFormat::Csv, HistoryFilter::default())
+                    .unwrap(),
+                history
+                    .export(ExportFormat::Csv, HistoryFilter::default())
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn import_merges_without_clobbering_labels() {
+        let dir = tempdir().unwrap();
+        let (source, _) = accounting_history(dir.path().join("source.json"));
+        let csv = source
+            .export(ExportFormat::Csv, HistoryFilter::default())
+            .unwrap();
+
+        let path = dir.path().join("history.json");
+        let mut history = TransactionHistory::new(path.clone()).unwrap();
+        let mut own = record("tx07", Utc::now());
+        own.label = Some("My label".to_string());
+        own.tags = vec!["mine".to_string()];
+        history
+            .add_transactions([own, record("tx01", Utc::now())])
+            .unwrap();
+        history
+            .add_transaction_tag("tx01", "odd".to_string())
+            .unwrap();
+
+        let summary = history.import(ExportFormat::Csv, &csv).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                added: 48,
+                updated: 2,
+                unchanged: 0
+            }
+        );
+
+        let tx07 = history.get_transaction("tx07").unwrap();
+        assert_eq!(tx07.label.as_deref(), Some("My label"));
+        assert_eq!(tx07.category.as_deref(), Some("rent"));
+        assert_eq!(tx07.tags, vec!["mine"]);
+        assert_eq!(
+            history.get_transaction("tx01").unwrap().category.as_deref(),
+            Some("rent")
+        );
+
+        // Importing again changes nothing, and the merge was persisted
+        let again = history.import(ExportFormat::Csv, &csv).unwrap();
+        assert_eq!(again.unchanged, 50);
+        let reloaded = TransactionHistory::new(path).unwrap();
+        assert_eq!(
+            reloaded.get_transaction("tx07").unwrap().label.as_deref(),
+            Some("My label")
+        );
+        assert_eq!(reloaded.stats().transaction_count, 50);
+    }
+
+    #[test]
+    fn import_rejects_malformed_csv() {
+        let dir = tempdir().unwrap();
+        let mut history = TransactionHistory::new(dir.path().join("history.json")).unwrap();
+
+        assert!(matches!(
+            history.import(ExportFormat::Csv, "hash,amount\n"),
+            Err(HistoryError::InvalidImport { line: 1, .. })
+        ));
+        let bad_status = format!(
+            "{}\ntx,2025-01-01T00:00:00+00:00,Sent,1,0,Confirmed (3),,,\n\
+             tx2,2025-01-01T00:00:00+00:00,Sent,1,0,Lost,,,\n",
+            CSV_HEADER
+        );
+        assert!(matches!(
+            history.import(ExportFormat::Csv, &bad_status),
+            Err(HistoryError::InvalidImport { line: 3, .. })
+        ));
+        let unterminated = format!("{}\ntx,\"open", CSV_HEADER);
+        assert!(matches!(
+            history.import(ExportFormat::Csv, &unterminated),
+            Err(HistoryError::InvalidImport { line: 2, .. })
+        ));
+        assert!(history.get_all_transactions().is_empty());
+    }
 }
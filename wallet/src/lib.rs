@@ -16,41 +16,158 @@
 
 pub mod cli;
 mod backup_warning;
+mod balance_cache;
+pub mod clock;
+pub mod coin_selection;
 mod core; // Legacy Bitcoin-based wallet (deprecated)
+pub mod display;
+pub mod fees;
+mod handle;
 mod hdwallet;
 mod history;
+pub mod multisig;
 pub mod password_strength;
+pub mod policy;
+pub mod rates;
+mod rescan;
+mod seed_vault;
+pub mod seeds;
+pub mod settings;
+pub mod signer;
 mod ui;
+pub mod unsigned;
 
 // NEW: Quantum-resistant wallet infrastructure
 pub mod quantum_wallet;
 
 use bitcoin::network::Network; // Bitcoin-compatible
-use supernova_core::storage::utxo_set::UtxoSet;
+use bitcoin::secp256k1::{ecdsa, Message, Secp256k1};
+use bitcoin::PrivateKey;
+use quantum_wallet::transaction_builder::FINAL_SEQUENCE;
+use supernova_core::script::classify::DEFAULT_MAX_DATA_CARRIER_BYTES;
+use supernova_core::script::MultisigError;
+use supernova_core::storage::utxo_set::{UtxoEntry, UtxoSet};
+use supernova_core::types::transaction::{
+    OutPoint, Transaction, TransactionInput, TransactionOutput,
+};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
+pub use balance_cache::BalanceCache;
+pub use coin_selection::{CoinSelection, CoinSelectionStrategy};
 pub use core::Wallet;
-pub use hdwallet::{AccountType, HDAddress, HDWallet};
-pub use history::{TransactionDirection, TransactionHistory, TransactionRecord, TransactionStatus};
+pub use handle::{ReorgUpdate, SyncUpdate, WalletHandle, WalletSnapshot};
+pub use hdwallet::{
+    AccountBalances, AccountState, AccountType, AddressFilter, AddressState, HDAccount, HDAddress,
+    HDWallet, WalletMode, DEFAULT_GAP_LIMIT, MAX_ADDRESS_LABEL_LEN, WATCH_ONLY_ACCOUNT,
+};
+pub use history::{
+    EncryptedMemo, ExportFormat, ExportedTransaction, HistoryExportOptions, HistoryFilter,
+    HistoryStats, ImportSummary, TransactionDirection, TransactionHistory, TransactionRecord,
+    TransactionStatus, CSV_HEADER, MAX_MEMO_LEN,
+};
+pub use multisig::{CosignerKey, MultisigAccount};
+pub use policy::{
+    Approval, Destination, DestinationRule, PendingSpend, PolicyDecision, PolicyViolation,
+    SpendingPolicy,
+};
+pub use rescan::{ChainQuery, RescanProgress, RescanSummary};
+pub use seed_vault::{KdfParams, SealedSeed, DEFAULT_AUTO_LOCK};
+pub use seeds::{SeedStore, SeedStoreError, DEFAULT_SEED};
+pub use signer::{ExternalSigner, ProcessSigner, SignerError, SignerKey};
+pub use ui::keymap::{Action, KeyBinding, KeyConflict, KeyMap, KeymapError};
+pub use ui::send::{
+    AmountUnit, FeePreset, SendBackend, SendError, SendFlow, SendPreview, SendRequest, SendStep,
+};
+pub use ui::theme::{
+    BuiltinTheme, ColorDepth, ColorRole, Palette, RoleStyle, ThemeColor, ThemeDefinition,
+    ThemeError,
+};
 pub use ui::tui::WalletTui;
+pub use ui::UiPreferences;
+pub use unsigned::{PartialSignature, UnsignedError, UnsignedInput, UnsignedTransaction};
 
 #[derive(Error, Debug)]
 pub enum WalletError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
     #[error("HD wallet error: {0}")]
-    HDWallet(#[from] hdwallet::HDWalletError),
+    HDWallet(hdwallet::HDWalletError),
     #[error("History error: {0}")]
     History(#[from] history::HistoryError),
     #[error("UI error: {0}")]
     UI(String),
+    #[error("Spending policy: {0}")]
+    Policy(#[from] PolicyViolation),
+    #[error("UTXO cache error: {0}")]
+    Utxo(String),
+    #[error("Wallet lock poisoned: {0}")]
+    LockPoisoned(&'static str),
+    #[error("Insufficient confirmed funds: {available} spendable after fees, {required} required")]
+    InsufficientFunds { available: u64, required: u64 },
+    #[error("Fee of {fee} would exceed the amount sent ({amount})")]
+    FeeExceedsAmount { fee: u64, amount: u64 },
+    #[error("Invalid recipient address {address}: {reason}")]
+    InvalidRecipient { address: String, reason: String },
+    #[error("Failed to sign input {input}: {reason}")]
+    Signing { input: usize, reason: String },
+    #[error("Data payload of {size} bytes exceeds the relay limit of {max}")]
+    DataTooLarge { size: usize, max: usize },
+    #[error("Wallet is watch-only: {0} needs private keys")]
+    WatchOnly(&'static str),
+    #[error("Wallet is locked: {0} needs the passphrase")]
+    Locked(&'static str),
+    #[error("Unsigned transaction: {0}")]
+    Unsigned(#[from] UnsignedError),
+    #[error("Input {input} does not belong to this wallet: {reason}")]
+    UnknownInput { input: usize, reason: String },
+    #[error("Seed store: {0}")]
+    Seeds(#[from] SeedStoreError),
+    #[error("Chain query failed: {0}")]
+    Chain(String),
+    #[error("External signer: {0}")]
+    Signer(#[from] SignerError),
+    #[error("Multisig: {0}")]
+    Multisig(#[from] MultisigError),
+}
+
+impl From<hdwallet::HDWalletError> for WalletError {
+    fn from(err: hdwallet::HDWalletError) -> Self {
+        match err {
+            hdwallet::HDWalletError::WatchOnlyWallet(operation) => {
+                WalletError::WatchOnly(operation)
+            }
+            hdwallet::HDWalletError::Locked(operation) => WalletError::Locked(operation),
+            err => WalletError::HDWallet(err),
+        }
+    }
+}
+
+impl WalletError {
+    /// The policy denial behind this error, if it is one. Used by the CLI and
+    /// TUI to render denials distinctly from ordinary failures.
+    pub fn policy_violation(&self) -> Option<&PolicyViolation> {
+        match self {
+            WalletError::Policy(v) | WalletError::HDWallet(hdwallet::HDWalletError::Policy(v)) => {
+                Some(v)
+            }
+            _ => None,
+        }
+    }
 }
 
 pub struct WalletManager {
     hd_wallet: HDWallet,
     transaction_history: TransactionHistory,
     utxo_set: UtxoSet,
+    dust_threshold: u64,
+    /// How long [`unlock`](WalletManager::unlock) keeps the seed available
+    auto_lock: Option<Duration>,
+    /// Signs instead of the seed when registered
+    external_signer: Option<Arc<dyn ExternalSigner>>,
 }
 
 impl WalletManager {
@@ -60,39 +177,96 @@ impl WalletManager {
 
         let utxo_set = UtxoSet::new_in_memory(1000);
         let hd_wallet = HDWallet::new(network, wallet_path)?;
-        let transaction_history = TransactionHistory::new(history_path)?;
+        let mut transaction_history = TransactionHistory::new(history_path)?;
+        transaction_history.unlock_memos(hd_wallet.memo_key()?);
 
         Ok(Self {
             hd_wallet,
             transaction_history,
             utxo_set,
+            dust_threshold: coin_selection::DEFAULT_DUST_THRESHOLD,
+            auto_lock: Some(DEFAULT_AUTO_LOCK),
+            external_signer: None,
         })
     }
 
+    /// Load the active seed of `wallet_dir` (see [`SeedStore`])
     pub fn load(wallet_dir: PathBuf) -> Result<Self, WalletError> {
-        let wallet_path = wallet_dir.join("wallet.json");
-        let history_path = wallet_dir.join("history.json");
+        let active = SeedStore::new(&wallet_dir).active()?;
+        Self::load_seed(wallet_dir, &active)
+    }
+
+    /// Load the seed called `name`, whether or not it is active
+    pub fn load_seed(wallet_dir: PathBuf, name: &str) -> Result<Self, WalletError> {
+        let seed_dir = SeedStore::new(wallet_dir).seed_dir(name)?;
+        let wallet_path = seed_dir.join(seeds::WALLET_FILE);
+        if !wallet_path.exists() {
+            return Err(SeedStoreError::NotFound(name.to_string()).into());
+        }
+        let history_path = seed_dir.join(seeds::HISTORY_FILE);
 
         let hd_wallet = HDWallet::load(wallet_path)?;
-        let transaction_history = TransactionHistory::new(history_path)?;
+        let mut transaction_history = TransactionHistory::new(history_path)?;
+        // Memos are sealed with a seed-derived key, so a watch-only or
+        // encrypted wallet leaves them locked until `unlock`
+        if !hd_wallet.is_watch_only() && !hd_wallet.is_locked() {
+            transaction_history.unlock_memos(hd_wallet.memo_key()?);
+        }
         let utxo_set = UtxoSet::new_in_memory(1000);
 
         Ok(Self {
             hd_wallet,
             transaction_history,
             utxo_set,
+            dust_threshold: coin_selection::DEFAULT_DUST_THRESHOLD,
+            auto_lock: Some(DEFAULT_AUTO_LOCK),
+            external_signer: None,
         })
     }
 
+    /// Restore a wallet from its mnemonic and optional BIP39 passphrase;
+    /// each passphrase restores a different wallet
     pub fn from_mnemonic(
         mnemonic: &str,
+        seed_passphrase: Option<&str>,
+        wallet_dir: PathBuf,
+        network: Network,
+    ) -> Result<Self, WalletError> {
+        let wallet_path = wallet_dir.join("wallet.json");
+        let history_path = wallet_dir.join("history.json");
+
+        let hd_wallet = HDWallet::from_mnemonic(mnemonic, seed_passphrase, network, wallet_path)?;
+        let mut transaction_history = TransactionHistory::new(history_path)?;
+        transaction_history.unlock_memos(hd_wallet.memo_key()?);
+        let utxo_set = UtxoSet::new_in_memory(1000);
+
+        Ok(Self {
+            hd_wallet,
+            transaction_history,
+            utxo_set,
+            dust_threshold: coin_selection::DEFAULT_DUST_THRESHOLD,
+            auto_lock: Some(DEFAULT_AUTO_LOCK),
+            external_signer: None,
+        })
+    }
+
+    /// Watch-only wallet for the account behind `xpub` (an account-level
+    /// extended public key, see [`export_xpub`](Self::export_xpub)). It
+    /// derives receive addresses in [`WATCH_ONLY_ACCOUNT`] and tracks their
+    /// balance, but signing, seed export and memo encryption fail with
+    /// [`WalletError::WatchOnly`]. The mode is saved in `wallet.json`, so
+    /// [`load`](Self::load) keeps the restriction.
+    pub fn new_watch_only(
         wallet_dir: PathBuf,
         network: Network,
+        xpub: &str,
     ) -> Result<Self, WalletError> {
         let wallet_path = wallet_dir.join("wallet.json");
         let history_path = wallet_dir.join("history.json");
 
-        let hd_wallet = HDWallet::from_mnemonic(mnemonic, network, wallet_path)?;
+        let hd_wallet = HDWallet::from_xpub(xpub, network, wallet_path)?;
+        #[allow(deprecated)]
+        hd_wallet.save()?;
         let transaction_history = TransactionHistory::new(history_path)?;
         let utxo_set = UtxoSet::new_in_memory(1000);
 
@@ -100,14 +274,124 @@ impl WalletManager {
             hd_wallet,
             transaction_history,
             utxo_set,
+            dust_threshold: coin_selection::DEFAULT_DUST_THRESHOLD,
+            auto_lock: Some(DEFAULT_AUTO_LOCK),
+            external_signer: None,
         })
     }
 
+    pub fn is_watch_only(&self) -> bool {
+        self.hd_wallet.is_watch_only()
+    }
+
+    /// Account-level extended public key of `account_name`, for
+    /// [`new_watch_only`](Self::new_watch_only)
+    pub fn export_xpub(&self, account_name: &str) -> Result<String, WalletError> {
+        Ok(self.hd_wallet.account_xpub(account_name)?)
+    }
+
+    /// The wallet's BIP39 mnemonic, for backups
+    pub fn export_mnemonic(&self) -> Result<&str, WalletError> {
+        if self.hd_wallet.is_watch_only() {
+            return Err(WalletError::WatchOnly("exporting the seed"));
+        }
+        Ok(self.hd_wallet.get_mnemonic()?)
+    }
+
+    /// Whether `wallet.json` still holds the seed in plaintext. Such a
+    /// wallet should be upgraded with [`encrypt`](Self::encrypt).
+    pub fn stores_plaintext_seed(&self) -> bool {
+        self.hd_wallet.stores_plaintext_seed()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.hd_wallet.is_encrypted()
+    }
+
+    /// Encrypt the seed under `passphrase`, rewriting `wallet.json` in
+    /// place. Balances, accounts and addresses stay available; signing,
+    /// new addresses and memos need [`unlock`](Self::unlock) afterwards.
+    pub fn encrypt(&mut self, passphrase: &str) -> Result<(), WalletError> {
+        self.hd_wallet.encrypt(passphrase)?;
+        self.transaction_history.lock_memos();
+        Ok(())
+    }
+
+    /// Decrypt the seed for signing until [`lock`](Self::lock) or the
+    /// auto-lock timeout. Does nothing for an unencrypted wallet.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), WalletError> {
+        self.hd_wallet.unlock(passphrase, self.auto_lock)?;
+        if !self.hd_wallet.is_watch_only() {
+            self.unlock_memos()?;
+        }
+        Ok(())
+    }
+
+    /// Wipe the decrypted seed and memo key. Does nothing for an
+    /// unencrypted wallet, whose seed is always available.
+    pub fn lock(&mut self) {
+        if self.hd_wallet.is_encrypted() {
+            self.hd_wallet.lock();
+            self.transaction_history.lock_memos();
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.hd_wallet.is_locked()
+    }
+
+    /// Time an unlock lasts, from the next [`unlock`](Self::unlock); `None`
+    /// keeps the wallet unlocked until [`lock`](Self::lock)
+    pub fn set_auto_lock(&mut self, timeout: Option<Duration>) {
+        self.auto_lock = timeout;
+    }
+
+    pub fn auto_lock(&self) -> Option<Duration> {
+        self.auto_lock
+    }
+
+    /// Lock the wallet once its auto-lock timeout has passed. An expired
+    /// unlock already refuses to sign; this also wipes the secrets, and is
+    /// meant to be polled by interactive front ends. Returns whether the
+    /// wallet was locked.
+    pub fn lock_if_expired(&mut self) -> bool {
+        if self.hd_wallet.lock_if_expired() {
+            self.transaction_history.lock_memos();
+            return true;
+        }
+        false
+    }
+
+    /// Sign every transaction with `signer` instead of the seed. This lets
+    /// a watch-only wallet built from the signer's xpub spend.
+    pub fn register_external_signer(&mut self, signer: Arc<dyn ExternalSigner>) {
+        self.external_signer = Some(signer);
+    }
+
+    /// Go back to signing with the seed
+    pub fn clear_external_signer(&mut self) {
+        self.external_signer = None;
+    }
+
+    pub fn has_external_signer(&self) -> bool {
+        self.external_signer.is_some()
+    }
+
+    /// Convert into a [`WalletHandle`] that can be shared across threads,
+    /// e.g. between the TUI, a sync task and notification hooks.
+    pub fn into_handle(self) -> WalletHandle {
+        WalletHandle::new(self.hd_wallet, self.transaction_history, self.utxo_set)
+    }
+
+    /// Run the TUI on a copy of the wallet, with payments from its send
+    /// screen built and recorded by this manager.
     pub fn run_tui(&mut self) -> Result<(), WalletError> {
         let mut tui = WalletTui::new(self.hd_wallet.clone(), self.transaction_history.clone())
-            .map_err(|e| WalletError::UI(e.to_string()))?;
+            .map_err(|e| WalletError::UI(e.to_string()))?
+            .with_fee_estimator(Box::new(fees::HttpFeeEstimator::from_env()));
 
-        tui.run().map_err(|e| WalletError::UI(e.to_string()))?;
+        tui.run_with_send_backend(self)
+            .map_err(|e| WalletError::UI(e.to_string()))?;
         Ok(())
     }
 
@@ -127,6 +411,26 @@ impl WalletManager {
             .map_err(WalletError::HDWallet)
     }
 
+    pub fn label_address(&mut self, address: &str, label: &str) -> Result<(), WalletError> {
+        Ok(self.hd_wallet.label_address(address, label)?)
+    }
+
+    pub fn list_addresses(
+        &self,
+        account_name: &str,
+        filter: AddressFilter,
+    ) -> Result<Vec<&HDAddress>, WalletError> {
+        Ok(self.hd_wallet.list_addresses(account_name, filter)?)
+    }
+
+    pub fn gap_limit(&self) -> u32 {
+        self.hd_wallet.gap_limit()
+    }
+
+    pub fn set_gap_limit(&mut self, gap_limit: u32) -> Result<(), WalletError> {
+        Ok(self.hd_wallet.set_gap_limit(gap_limit)?)
+    }
+
     pub fn get_balance(&self, account_name: &str) -> Result<u64, WalletError> {
         self.hd_wallet
             .get_balance(account_name, &self.utxo_set)
@@ -139,15 +443,482 @@ impl WalletManager {
             .map_err(WalletError::HDWallet)
     }
 
-    pub fn list_accounts(&self) -> Vec<(u32, &hdwallet::HDAccount)> {
-        self.hd_wallet.list_accounts()
+    /// Smallest change output [`select_coins`](Self::select_coins) will create
+    pub fn set_dust_threshold(&mut self, dust_threshold: u64) {
+        self.dust_threshold = dust_threshold;
+    }
+
+    pub fn dust_threshold(&self) -> u64 {
+        self.dust_threshold
+    }
+
+    /// Choose confirmed outputs of `account_name` to pay `target_amount` at
+    /// `fee_rate` (base units per virtual byte). Change below the dust
+    /// threshold is left to the fee instead of creating an output.
+    pub fn select_coins(
+        &self,
+        account_name: &str,
+        target_amount: u64,
+        fee_rate: u64,
+        strategy: CoinSelectionStrategy,
+    ) -> Result<CoinSelection, WalletError> {
+        let utxos = self
+            .hd_wallet
+            .spendable_utxos(account_name, &self.utxo_set)?;
+        coin_selection::select_coins(
+            &utxos,
+            target_amount,
+            fee_rate,
+            self.dust_threshold,
+            strategy,
+            &mut rand::rngs::OsRng,
+        )
+    }
+
+    /// Build and sign a payment of `amount` to `recipient` from the confirmed
+    /// outputs of `account_name`, paying `fee_rate` (base units per virtual
+    /// byte).
+    ///
+    /// Change above the dust threshold goes to a fresh address on the
+    /// account's change chain. Every input is signed over
+    /// [`Transaction::signature_hash`] with the key of the address it spends:
+    /// a compact secp256k1 signature and compressed public key, carried in the
+    /// witness for segwit accounts and in the script sig for legacy ones.
+    ///
+    /// With an [external signer](Self::register_external_signer) the
+    /// signatures come from it instead, once it has reviewed the
+    /// transaction; this also works on a watch-only wallet.
+    ///
+    /// The payment is recorded as pending and the spent outputs leave the
+    /// UTXO cache. Spending policy is not evaluated here; call
+    /// [`authorize_spend`](Self::authorize_spend) first.
+    pub fn create_transaction(
+        &mut self,
+        account_name: &str,
+        recipient: &str,
+        amount: u64,
+        fee_rate: u64,
+    ) -> Result<Transaction, WalletError> {
+        self.create_transaction_with_data(account_name, recipient, amount, None, fee_rate)
+    }
+
+    /// What [`create_transaction`](Self::create_transaction) would spend on
+    /// this payment at the default coin selection. Side-effect free: the
+    /// change address is the one the payment would use, but it is not
+    /// derived and nothing is recorded.
+    pub fn preview_payment(
+        &self,
+        account_name: &str,
+        recipient: &str,
+        amount: u64,
+        fee_rate: u64,
+    ) -> Result<SendPreview, WalletError> {
+        let recipient_script = self.recipient_script(recipient)?;
+        let utxos = self
+            .hd_wallet
+            .spendable_utxos(account_name, &self.utxo_set)?;
+        let selection = coin_selection::select_coins_for_outputs(
+            &utxos,
+            amount,
+            coin_selection::output_vbytes(recipient_script.len()),
+            fee_rate,
+            self.dust_threshold,
+            CoinSelectionStrategy::default(),
+            &mut rand::rngs::OsRng,
+        )?;
+        let change_address = if selection.change > 0 {
+            Some(self.hd_wallet.peek_change_address(account_name)?.address)
+        } else {
+            None
+        };
+        Ok(SendPreview {
+            amount,
+            fee: selection.fee,
+            change: selection.change,
+            change_address,
+            inputs: selection.outpoints.len(),
+        })
+    }
+
+    /// [`create_transaction`](Self::create_transaction) with an optional
+    /// zero-value `OP_RETURN` output carrying `data`, placed after the
+    /// payment. `data` may be at most
+    /// [`DEFAULT_MAX_DATA_CARRIER_BYTES`] so that nodes relay the result.
+    pub fn create_transaction_with_data(
+        &mut self,
+        account_name: &str,
+        recipient: &str,
+        amount: u64,
+        data: Option<&[u8]>,
+        fee_rate: u64,
+    ) -> Result<Transaction, WalletError> {
+        let recipient_script = self.recipient_script(recipient)?;
+        self.build_transaction(
+            account_name,
+            Some(TransactionOutput::new(amount, recipient_script)),
+            data,
+            fee_rate,
+        )
+    }
+
+    /// Build and sign a transaction from `account_name` that only embeds
+    /// `data` in a zero-value `OP_RETURN` output, for anchoring and
+    /// attestations. The fee is the whole cost; any change returns to the
+    /// account. Recorded as a pending send of zero.
+    pub fn create_data_transaction(
+        &mut self,
+        account_name: &str,
+        data: &[u8],
+        fee_rate: u64,
+    ) -> Result<Transaction, WalletError> {
+        self.build_transaction(account_name, None, Some(data), fee_rate)
+    }
+
+    /// Fund, sign and record a transaction paying `payment` and carrying
+    /// `data`, in that order, followed by change
+    fn build_transaction(
+        &mut self,
+        account_name: &str,
+        payment: Option<TransactionOutput>,
+        data: Option<&[u8]>,
+        fee_rate: u64,
+    ) -> Result<Transaction, WalletError> {
+        if let Some(signer) = self.external_signer.clone() {
+            let unsigned = self.fund_unsigned(account_name, payment, data, fee_rate)?;
+            return self.sign_unsigned_with(unsigned, Some(signer.as_ref()));
+        }
+        if self.hd_wallet.is_watch_only() {
+            return Err(WalletError::WatchOnly("signing"));
+        }
+        let account_type = self.hd_wallet.account_type(account_name)?;
+        let (funding, keys) = self.fund_transaction(
+            account_name,
+            payment,
+            data,
+            fee_rate,
+            |hd_wallet, index, entry| {
+                hd_wallet
+                    .signing_key_for_script(account_name, &entry.output.pub_key_script)
+                    .map_err(|e| WalletError::Signing {
+                        input: index,
+                        reason: e.to_string(),
+                    })
+            },
+        )?;
+
+        let secp = Secp256k1::new();
+        let inputs: Vec<SigningInput> = funding
+            .spent
+            .iter()
+            .zip(keys)
+            .map(|(entry, key)| SigningInput {
+                outpoint: entry.outpoint.clone(),
+                sequence: FINAL_SEQUENCE,
+                public_key: key.public_key(&secp),
+                key: InputKey::Seed(key),
+                account_type,
+            })
+            .collect();
+        let transaction =
+            sign_transaction(self.hd_wallet.network(), &inputs, funding.outputs, None)?;
+        self.record_send(&transaction, funding.amount, funding.fee)?;
+        Ok(transaction)
+    }
+
+    /// Fund a payment of `amount` to `recipient` from `account_name` like
+    /// [`create_transaction`](Self::create_transaction), but leave it
+    /// unsigned for a signer holding the seed (see [`unsigned`]). Works on a
+    /// watch-only wallet. Change goes to a fresh change address; nothing is
+    /// recorded until the signed transaction is.
+    pub fn build_unsigned(
+        &mut self,
+        account_name: &str,
+        recipient: &str,
+        amount: u64,
+        fee_rate: u64,
+    ) -> Result<UnsignedTransaction, WalletError> {
+        let payment = TransactionOutput::new(amount, self.recipient_script(recipient)?);
+        self.fund_unsigned(account_name, Some(payment), None, fee_rate)
+    }
+
+    /// Fund `payment` and `data` from `account_name` into a transaction
+    /// for a signer, with the path of every spent output and of the change
+    fn fund_unsigned(
+        &mut self,
+        account_name: &str,
+        payment: Option<TransactionOutput>,
+        data: Option<&[u8]>,
+        fee_rate: u64,
+    ) -> Result<UnsignedTransaction, WalletError> {
+        let (funding, paths) = self.fund_transaction(
+            account_name,
+            payment,
+            data,
+            fee_rate,
+            |hd_wallet, _, entry| {
+                Ok(hd_wallet
+                    .derivation_path_for_script(account_name, &entry.output.pub_key_script)?)
+            },
+        )?;
+        let output_paths = funding
+            .outputs
+            .iter()
+            .map(|output| {
+                self.hd_wallet
+                    .derivation_path_for_script(account_name, &output.pub_key_script)
+                    .ok()
+            })
+            .collect();
+
+        Ok(UnsignedTransaction {
+            format: unsigned::UNSIGNED_FORMAT_VERSION,
+            version: 1,
+            inputs: funding
+                .spent
+                .into_iter()
+                .zip(paths)
+                .map(|(entry, derivation_path)| UnsignedInput {
+                    outpoint: entry.outpoint,
+                    sequence: FINAL_SEQUENCE,
+                    amount: entry.output.amount(),
+                    script_pubkey: entry.output.pub_key_script,
+                    derivation_path,
+                    witness_script: None,
+                    partial_signatures: Vec::new(),
+                })
+                .collect(),
+            outputs: funding.outputs,
+            output_paths,
+            lock_time: 0,
+            amount: funding.amount,
+        })
+    }
+
+    /// Sign a transaction built by [`build_unsigned`](Self::build_unsigned),
+    /// possibly on another (watch-only) wallet, and record it like
+    /// [`create_transaction`](Self::create_transaction).
+    ///
+    /// Every input must spend an address this wallet generated at the given
+    /// derivation path; an input that doesn't, or that contradicts the
+    /// wallet's own UTXO cache, fails with [`WalletError::UnknownInput`]
+    /// before anything is signed. With an
+    /// [external signer](Self::register_external_signer) registered, it
+    /// signs instead of the seed.
+    pub fn sign_unsigned(&mut self, tx: UnsignedTransaction) -> Result<Transaction, WalletError> {
+        let signer = self.external_signer.clone();
+        self.sign_unsigned_with(tx, signer.as_deref())
+    }
+
+    fn sign_unsigned_with(
+        &mut self,
+        tx: UnsignedTransaction,
+        signer: Option<&dyn ExternalSigner>,
+    ) -> Result<Transaction, WalletError> {
+        if signer.is_none() && self.hd_wallet.is_watch_only() {
+            return Err(WalletError::WatchOnly("signing"));
+        }
+        if tx.format != unsigned::UNSIGNED_FORMAT_VERSION {
+            return Err(UnsignedError::UnsupportedFormat(tx.format).into());
+        }
+        if tx.version != 1 || tx.lock_time != 0 {
+            return Err(UnsignedError::Decoding(
+                "only version 1 transactions without a lock time can be signed".to_string(),
+            )
+            .into());
+        }
+        let fee = tx.fee()?;
+
+        let secp = Secp256k1::new();
+        let mut inputs = Vec::with_capacity(tx.inputs.len());
+        for (index, input) in tx.inputs.iter().enumerate() {
+            let unknown = |reason: String| WalletError::UnknownInput {
+                input: index,
+                reason,
+            };
+            if let Some(entry) = self
+                .utxo_set
+                .get(&input.outpoint)
+                .map_err(WalletError::Utxo)?
+            {
+                if entry.output.amount() != input.amount
+                    || entry.output.pub_key_script != input.script_pubkey
+                {
+                    return Err(unknown(format!(
+                        "{} differs from the wallet's record of it",
+                        input.outpoint
+                    )));
+                }
+            }
+            let (public_key, key, account_type) = if signer.is_some() {
+                let (public_key, account_type) = self
+                    .hd_wallet
+                    .public_key_for_path(&input.derivation_path, &input.script_pubkey)
+                    .map_err(|e| unknown(e.to_string()))?;
+                let key = InputKey::External(input.derivation_path.clone());
+                (public_key, key, account_type)
+            } else {
+                let (key, account_type) = self
+                    .hd_wallet
+                    .signing_key_for_path(&input.derivation_path, &input.script_pubkey)
+                    .map_err(|e| unknown(e.to_string()))?;
+                (key.public_key(&secp), InputKey::Seed(key), account_type)
+            };
+            inputs.push(SigningInput {
+                outpoint: input.outpoint.clone(),
+                sequence: input.sequence,
+                public_key,
+                key,
+                account_type,
+            });
+        }
+
+        if let Some(signer) = signer {
+            signer.review_transaction(&tx)?;
+        }
+        let transaction = sign_transaction(self.hd_wallet.network(), &inputs, tx.outputs, signer)?;
+        self.record_send(&transaction, tx.amount, fee)?;
+        Ok(transaction)
+    }
+
+    /// Locking script of `recipient`, which must be an address on the
+    /// wallet's network
+    fn recipient_script(&self, recipient: &str) -> Result<Vec<u8>, WalletError> {
+        let invalid_recipient = |reason: String| WalletError::InvalidRecipient {
+            address: recipient.to_string(),
+            reason,
+        };
+        Ok(bitcoin::Address::from_str(recipient)
+            .map_err(|e| invalid_recipient(e.to_string()))?
+            .require_network(self.hd_wallet.network())
+            .map_err(|e| invalid_recipient(e.to_string()))?
+            .script_pubkey()
+            .as_bytes()
+            .to_vec())
+    }
+
+    /// Select confirmed outputs of `account_name` to pay `payment` and carry
+    /// `data`, and add change. `resolve` is called for every selected output
+    /// before a change address is derived, so a failure doesn't use one up.
+    fn fund_transaction<T>(
+        &mut self,
+        account_name: &str,
+        payment: Option<TransactionOutput>,
+        data: Option<&[u8]>,
+        fee_rate: u64,
+        resolve: impl Fn(&HDWallet, usize, &UtxoEntry) -> Result<T, WalletError>,
+    ) -> Result<(Funding, Vec<T>), WalletError> {
+        if let Some(data) = data {
+            if data.len() > DEFAULT_MAX_DATA_CARRIER_BYTES {
+                return Err(WalletError::DataTooLarge {
+                    size: data.len(),
+                    max: DEFAULT_MAX_DATA_CARRIER_BYTES,
+                });
+            }
+        }
+        let amount = payment.as_ref().map_or(0, TransactionOutput::amount);
+        let mut outputs: Vec<TransactionOutput> = payment
+            .into_iter()
+            .chain(data.map(TransactionOutput::data_carrier))
+            .collect();
+        let outputs_vbytes = outputs
+            .iter()
+            .map(|output| coin_selection::output_vbytes(output.pub_key_script.len()))
+            .sum();
+
+        let utxos = self
+            .hd_wallet
+            .spendable_utxos(account_name, &self.utxo_set)?;
+        let selection = coin_selection::select_coins_for_outputs(
+            &utxos,
+            amount,
+            outputs_vbytes,
+            fee_rate,
+            self.dust_threshold,
+            CoinSelectionStrategy::default(),
+            &mut rand::rngs::OsRng,
+        )?;
+        let mut spent = Vec::with_capacity(selection.outpoints.len());
+        for outpoint in &selection.outpoints {
+            let entry = self
+                .utxo_set
+                .get(outpoint)
+                .map_err(WalletError::Utxo)?
+                .ok_or_else(|| {
+                    WalletError::Utxo(format!("selected output {} is already spent", outpoint))
+                })?;
+            spent.push(entry);
+        }
+        let resolved = spent
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| resolve(&self.hd_wallet, index, entry))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if selection.change > 0 {
+            let change_address = self.hd_wallet.get_new_change_address(account_name)?;
+            outputs.push(TransactionOutput::new(
+                selection.change,
+                hdwallet::address_script(&change_address.address)?,
+            ));
+        }
+
+        Ok((
+            Funding {
+                spent,
+                outputs,
+                amount,
+                fee: selection.fee,
+            },
+            resolved,
+        ))
+    }
+
+    /// Record a signed payment as a pending send and drop the outputs it
+    /// spends from the UTXO cache
+    fn record_send(
+        &mut self,
+        transaction: &Transaction,
+        amount: u64,
+        fee: u64,
+    ) -> Result<(), WalletError> {
+        self.transaction_history
+            .add_transaction(TransactionRecord {
+                hash: hex::encode(transaction.txid()),
+                timestamp: chrono::Utc::now(),
+                direction: TransactionDirection::Sent,
+                amount,
+                fee,
+                status: TransactionStatus::Pending,
+                label: None,
+                category: None,
+                tags: Vec::new(),
+                memo: None,
+                addresses: Vec::new(),
+            })
+            .map_err(WalletError::History)?;
+        for input in transaction.inputs() {
+            let outpoint = OutPoint {
+                txid: input.prev_tx_hash(),
+                vout: input.prev_output_index(),
+            };
+            self.utxo_set.remove(&outpoint).map_err(WalletError::Utxo)?;
+        }
+        Ok(())
+    }
+
+    pub fn list_accounts(&self, include_archived: bool) -> Vec<(u32, &hdwallet::HDAccount)> {
+        self.hd_wallet.list_accounts(include_archived)
     }
 
     pub fn get_address_count(&self) -> usize {
         self.hd_wallet.get_address_count()
     }
 
+    /// Record a transaction. The addresses a received one pays are marked
+    /// used.
     pub fn add_transaction(&mut self, record: TransactionRecord) -> Result<(), WalletError> {
+        self.hd_wallet
+            .mark_received(std::slice::from_ref(&record))?;
         self.transaction_history
             .add_transaction(record)
             .map_err(WalletError::History)
@@ -185,6 +956,59 @@ impl WalletManager {
             .map_err(WalletError::History)
     }
 
+    /// Attach an encrypted private note to a transaction. An empty memo
+    /// removes it.
+    pub fn add_transaction_memo(&mut self, hash: &str, text: &str) -> Result<(), WalletError> {
+        self.lock_if_expired();
+        self.transaction_history
+            .add_transaction_memo(hash, text)
+            .map_err(WalletError::History)
+    }
+
+    pub fn get_transaction_memo(&self, hash: &str) -> Option<String> {
+        if self.hd_wallet.is_locked() {
+            return None;
+        }
+        self.transaction_history.get_transaction_memo(hash)
+    }
+
+    /// Drop the memo key; memos read as `None` until [`unlock_memos`](Self::unlock_memos).
+    pub fn lock_memos(&mut self) {
+        self.transaction_history.lock_memos();
+    }
+
+    pub fn unlock_memos(&mut self) -> Result<(), WalletError> {
+        let key = self.hd_wallet.memo_key()?;
+        self.transaction_history.unlock_memos(key);
+        Ok(())
+    }
+
+    pub fn export_history(&self, options: HistoryExportOptions) -> Vec<ExportedTransaction> {
+        self.transaction_history.export_entries(options)
+    }
+
+    /// Filtered history as CSV or JSON; see [`TransactionHistory::export`].
+    pub fn export_transactions(
+        &self,
+        format: ExportFormat,
+        filter: HistoryFilter,
+    ) -> Result<String, WalletError> {
+        self.transaction_history
+            .export(format, filter)
+            .map_err(WalletError::History)
+    }
+
+    /// Merge a previous export into the history without overwriting labels.
+    pub fn import_transactions(
+        &mut self,
+        format: ExportFormat,
+        data: &str,
+    ) -> Result<ImportSummary, WalletError> {
+        self.transaction_history
+            .import(format, data)
+            .map_err(WalletError::History)
+    }
+
     pub fn get_transaction(&self, hash: &str) -> Option<&TransactionRecord> {
         self.transaction_history.get_transaction(hash)
     }
@@ -221,6 +1045,219 @@ impl WalletManager {
     pub fn get_net_flow(&self) -> i64 {
         self.transaction_history.get_net_flow()
     }
+
+    pub fn set_spending_policy(
+        &mut self,
+        account_name: &str,
+        policy: SpendingPolicy,
+    ) -> Result<(), WalletError> {
+        self.hd_wallet
+            .set_spending_policy(account_name, policy)
+            .map_err(WalletError::HDWallet)
+    }
+
+    pub fn get_spending_policy(
+        &self,
+        account_name: &str,
+    ) -> Result<Option<SpendingPolicy>, WalletError> {
+        self.hd_wallet
+            .spending_policy(account_name)
+            .map_err(WalletError::HDWallet)
+    }
+
+    /// Evaluate a spend against the account's policy. The transaction build
+    /// path calls this before selecting coins or signing; a
+    /// [`PolicyDecision::PendingApproval`] means the spend must not be built
+    /// until [`approve_pending_spend`](Self::approve_pending_spend) succeeds.
+    pub fn authorize_spend(
+        &mut self,
+        account_name: &str,
+        destination: &Destination,
+        amount: u64,
+    ) -> Result<PolicyDecision, WalletError> {
+        self.hd_wallet
+            .authorize_spend(account_name, destination, amount)
+            .map_err(WalletError::HDWallet)
+    }
+
+    pub fn pending_spends(&self) -> &[PendingSpend] {
+        self.hd_wallet.pending_spends()
+    }
+
+    pub fn approve_pending_spend(
+        &mut self,
+        id: &str,
+        approval: &Approval,
+    ) -> Result<PendingSpend, WalletError> {
+        self.hd_wallet
+            .approve_pending_spend(id, approval)
+            .map_err(WalletError::HDWallet)
+    }
+
+    pub fn reject_pending_spend(&mut self, id: &str) -> Result<PendingSpend, WalletError> {
+        self.hd_wallet
+            .reject_pending_spend(id)
+            .map_err(WalletError::HDWallet)
+    }
+}
+
+/// The TUI send screen: payments pass the account's spending policy, then
+/// are built, signed and recorded like
+/// [`create_transaction`](WalletManager::create_transaction).
+impl SendBackend for WalletManager {
+    fn preview(&self, request: &SendRequest) -> Result<SendPreview, String> {
+        self.preview_payment(
+            &request.account,
+            &request.recipient,
+            request.amount,
+            request.fee_rate,
+        )
+        .map_err(|e| e.to_string())
+    }
+
+    fn send(&mut self, request: &SendRequest) -> Result<String, String> {
+        let destination = Destination::address(request.recipient.clone());
+        match self
+            .authorize_spend(&request.account, &destination, request.amount)
+            .map_err(|e| e.to_string())?
+        {
+            PolicyDecision::Approved => {}
+            PolicyDecision::PendingApproval(pending) => {
+                return Err(format!(
+                    "Held for a second approval as pending spend {}",
+                    pending.id
+                ))
+            }
+        }
+        let transaction = self
+            .create_transaction(
+                &request.account,
+                &request.recipient,
+                request.amount,
+                request.fee_rate,
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(hex::encode(transaction.txid()))
+    }
+}
+
+/// Outputs selected to fund a payment, and the outputs they pay
+struct Funding {
+    spent: Vec<UtxoEntry>,
+    /// Payment, data carrier and change, in transaction order
+    outputs: Vec<TransactionOutput>,
+    amount: u64,
+    fee: u64,
+}
+
+/// An output to spend with the key of the address it pays
+struct SigningInput {
+    outpoint: OutPoint,
+    sequence: u32,
+    public_key: bitcoin::PublicKey,
+    key: InputKey,
+    account_type: AccountType,
+}
+
+/// Where an input's signature comes from
+enum InputKey {
+    Seed(PrivateKey),
+    /// The external signer's key at this BIP44 path
+    External(String),
+}
+
+/// Sign a version 1 transaction spending `inputs` to `outputs`.
+///
+/// Every input is signed over [`Transaction::signature_hash`] with its own
+/// key: a compact secp256k1 signature and compressed public key, carried in
+/// the witness for segwit accounts and in the script sig for legacy ones.
+/// Signatures from `signer` must verify against the input's public key.
+fn sign_transaction(
+    network: Network,
+    inputs: &[SigningInput],
+    outputs: Vec<TransactionOutput>,
+    signer: Option<&dyn ExternalSigner>,
+) -> Result<Transaction, WalletError> {
+    // The sighash covers the outpoints and outputs only, so every input
+    // signs the same message and scripts can be filled in afterwards
+    let unsigned_inputs = inputs
+        .iter()
+        .map(|input| {
+            TransactionInput::new(
+                input.outpoint.txid,
+                input.outpoint.vout,
+                Vec::new(),
+                input.sequence,
+            )
+        })
+        .collect();
+    let sighash = Transaction::new(1, unsigned_inputs, outputs.clone(), 0).signature_hash();
+    let message = Message::from_digest(sighash);
+
+    let secp = Secp256k1::new();
+    let mut signed_inputs = Vec::with_capacity(inputs.len());
+    for (index, input) in inputs.iter().enumerate() {
+        let signing_error = |reason: String| WalletError::Signing {
+            input: index,
+            reason,
+        };
+        let public_key = input.public_key;
+        let signature = match &input.key {
+            InputKey::Seed(key) => secp.sign_ecdsa(&message, &key.inner).serialize_compact(),
+            InputKey::External(path) => {
+                let signer = signer
+                    .ok_or_else(|| signing_error("no external signer registered".to_string()))?;
+                let signature = signer.sign_digest(path, &sighash)?;
+                let signature = ecdsa::Signature::from_compact(&signature)
+                    .map_err(|e| signing_error(format!("external signature: {}", e)))?;
+                secp.verify_ecdsa(&message, &signature, &public_key.inner)
+                    .map_err(|_| {
+                        signing_error(format!("external signature does not match {}", path))
+                    })?;
+                signature.serialize_compact()
+            }
+        }
+        .to_vec();
+        let pubkey = public_key.to_bytes();
+
+        let (script_sig, witness) = match input.account_type {
+            AccountType::Legacy => (push_script(&[&signature, &pubkey]), Vec::new()),
+            AccountType::SegWit => {
+                let redeem_script = bitcoin::Address::p2wpkh(&public_key, network)
+                    .map_err(|e| signing_error(e.to_string()))?
+                    .script_pubkey();
+                (
+                    push_script(&[redeem_script.as_bytes()]),
+                    vec![signature, pubkey],
+                )
+            }
+            AccountType::NativeSegWit => (Vec::new(), vec![signature, pubkey]),
+            AccountType::Quantum(scheme) => {
+                return Err(signing_error(format!(
+                    "{:?} keys are not secp256k1",
+                    scheme
+                )))
+            }
+        };
+        signed_inputs.push(TransactionInput::new_with_witness(
+            input.outpoint.txid,
+            input.outpoint.vout,
+            script_sig,
+            input.sequence,
+            witness,
+        ));
+    }
+    Ok(Transaction::new(1, signed_inputs, outputs, 0))
+}
+
+/// Script pushing each of `items`, all shorter than `OP_PUSHDATA1`
+fn push_script(items: &[&[u8]]) -> Vec<u8> {
+    let mut script = Vec::new();
+    for item in items {
+        script.push(item.len() as u8);
+        script.extend_from_slice(item);
+    }
+    script
 }
 
 #[cfg(test)]
@@ -253,6 +1290,8 @@ mod tests {
             label: None,
             category: None,
             tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
         };
 
         manager.add_transaction(tx).unwrap();
@@ -263,4 +1302,731 @@ mod tests {
         assert_eq!(manager.get_total_sent(), 0);
         assert_eq!(manager.get_net_flow(), 1000);
     }
+
+    #[test]
+    fn select_coins_spends_only_confirmed_account_outputs() {
+        use std::str::FromStr;
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::{OutPoint, TransactionOutput};
+
+        let dir = tempdir().unwrap();
+        let mut manager = WalletManager::new(dir.path().to_path_buf(), Network::Testnet).unwrap();
+        manager
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        manager
+            .create_account("other".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let script_of = |address: HDAddress| {
+            bitcoin::Address::from_str(address.get_address())
+                .unwrap()
+                .assume_checked()
+                .script_pubkey()
+                .as_bytes()
+                .to_vec()
+        };
+        let main = script_of(manager.get_new_address("main").unwrap());
+        let other = script_of(manager.get_new_address("other").unwrap());
+
+        let outputs = [
+            (50_000, &main, true),
+            (20_000, &main, true),
+            (900_000, &main, false),
+            (700_000, &other, true),
+        ];
+        for (id, (amount, script, is_confirmed)) in outputs.into_iter().enumerate() {
+            manager
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id as u8 + 1; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(amount, script.clone()),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed,
+                })
+                .unwrap();
+        }
+
+        let selection = manager
+            .select_coins("main", 60_000, 1, CoinSelectionStrategy::LargestFirst)
+            .unwrap();
+        assert_eq!(selection.input_total, 70_000);
+        assert_eq!(selection.input_total, 60_000 + selection.fee + selection.change);
+
+        // The unconfirmed output and the other account's output don't count
+        assert!(matches!(
+            manager.select_coins("main", 100_000, 1, CoinSelectionStrategy::BranchAndBound),
+            Err(WalletError::InsufficientFunds { .. })
+        ));
+        assert!(manager
+            .select_coins("missing", 1_000, 1, CoinSelectionStrategy::Random)
+            .is_err());
+
+        // Change smaller than the dust threshold goes to the fee
+        manager.set_dust_threshold(100_000);
+        let selection = manager
+            .select_coins("main", 60_000, 1, CoinSelectionStrategy::SmallestFirst)
+            .unwrap();
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.fee, 10_000);
+    }
+
+    #[test]
+    fn create_transaction_signs_inputs_and_records_the_send() {
+        use bitcoin::secp256k1::{ecdsa::Signature, PublicKey};
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::OutPoint;
+
+        let dir = tempdir().unwrap();
+        let mut manager = WalletManager::new(dir.path().to_path_buf(), Network::Testnet).unwrap();
+        manager
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let funded = [
+            manager.get_new_address("main").unwrap(),
+            manager.get_new_address("main").unwrap(),
+        ];
+        for (id, address) in funded.iter().enumerate() {
+            manager
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id as u8 + 1; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(
+                        40_000,
+                        hdwallet::address_script(&address.address).unwrap(),
+                    ),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+        }
+        let recipient = manager.get_new_address("main").unwrap().address;
+
+        let tx = manager
+            .create_transaction("main", &recipient, 50_000, 1)
+            .unwrap();
+
+        // Recipient first, then change to a fresh internal-chain address
+        let record = manager.get_all_transactions()[0].clone();
+        let outputs = tx.outputs();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].amount(), 50_000);
+        assert_eq!(
+            outputs[0].pub_key_script,
+            hdwallet::address_script(&recipient).unwrap()
+        );
+        assert_eq!(80_000, 50_000 + record.fee + outputs[1].amount());
+        assert!(manager
+            .hd_wallet
+            .signing_key_for_script("main", &outputs[1].pub_key_script)
+            .is_ok());
+        assert!(funded.iter().all(|a| {
+            hdwallet::address_script(&a.address).unwrap() != outputs[1].pub_key_script
+        }));
+
+        // Each input carries a signature over the sighash by its own key
+        let secp = Secp256k1::verification_only();
+        let message = Message::from_digest(tx.signature_hash());
+        assert_eq!(tx.inputs().len(), 2);
+        for input in tx.inputs() {
+            assert!(input.script_sig().is_empty());
+            let [signature, pubkey] = input.witness() else {
+                panic!("expected a [signature, pubkey] witness");
+            };
+            let pubkey = PublicKey::from_slice(pubkey).unwrap();
+            let signature = Signature::from_compact(signature).unwrap();
+            secp.verify_ecdsa(&message, &signature, &pubkey).unwrap();
+            let address =
+                bitcoin::Address::p2wpkh(&bitcoin::PublicKey::new(pubkey), Network::Testnet)
+                    .unwrap();
+            assert!(funded.iter().any(|a| a.address == address.to_string()));
+        }
+
+        assert_eq!(record.hash, hex::encode(tx.hash()));
+        assert!(matches!(record.direction, TransactionDirection::Sent));
+        assert!(matches!(record.status, TransactionStatus::Pending));
+        assert_eq!(record.amount, 50_000);
+        // The spent outputs can't be selected again
+        assert_eq!(manager.utxo_set.get_count(), 0);
+    }
+
+    #[test]
+    fn send_backend_previews_without_changing_the_wallet() {
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::OutPoint;
+
+        let dir = tempdir().unwrap();
+        let mut manager = WalletManager::new(dir.path().to_path_buf(), Network::Testnet).unwrap();
+        manager
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        for id in 0..2u8 {
+            let address = manager.get_new_address("main").unwrap();
+            manager
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id + 1; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(
+                        40_000,
+                        hdwallet::address_script(&address.address).unwrap(),
+                    ),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+        }
+        let request = SendRequest {
+            account: "main".to_string(),
+            recipient: manager.get_new_address("main").unwrap().address,
+            amount: 50_000,
+            fee_rate: 1,
+        };
+        let addresses = manager.get_address_count();
+
+        let preview = SendBackend::preview(&manager, &request).unwrap();
+        assert_eq!(preview.inputs, 2);
+        assert_eq!(80_000, preview.total_debit() + preview.change);
+        assert_eq!(SendBackend::preview(&manager, &request).unwrap(), preview);
+        assert_eq!(manager.get_address_count(), addresses);
+        assert!(manager.get_all_transactions().is_empty());
+        assert_eq!(manager.utxo_set.get_count(), 2);
+
+        // The payment uses the previewed change address
+        let txid = SendBackend::send(&mut manager, &request).unwrap();
+        let record = manager.get_transaction(&txid).unwrap();
+        assert_eq!(record.fee, preview.fee);
+        let change = manager
+            .list_addresses("main", AddressFilter::All)
+            .unwrap()
+            .into_iter()
+            .find(|address| address.change)
+            .unwrap();
+        assert_eq!(preview.change_address, Some(change.address.clone()));
+    }
+
+    #[test]
+    fn create_transaction_failures_are_distinct() {
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::OutPoint;
+
+        let dir = tempdir().unwrap();
+        let mut manager = WalletManager::new(dir.path().to_path_buf(), Network::Testnet).unwrap();
+        manager
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let address = manager.get_new_address("main").unwrap().address;
+        let fund = |manager: &mut WalletManager, id: u8, address: &str| {
+            manager
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(
+                        10_000,
+                        hdwallet::address_script(address).unwrap(),
+                    ),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+        };
+        fund(&mut manager, 1, &address);
+
+        assert!(matches!(
+            manager.create_transaction("main", "not-an-address", 1_000, 1),
+            Err(WalletError::InvalidRecipient { .. })
+        ));
+        // A mainnet address can't be paid from a testnet wallet
+        assert!(matches!(
+            manager.create_transaction(
+                "main",
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+                1_000,
+                1
+            ),
+            Err(WalletError::InvalidRecipient { .. })
+        ));
+        assert!(matches!(
+            manager.create_transaction("main", &address, 50_000, 1),
+            Err(WalletError::InsufficientFunds { .. })
+        ));
+
+        // Watch-only outputs are selectable but have no key to sign with
+        let watched = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        manager
+            .hd_wallet
+            .import_watch_only_account(
+                "watched".to_string(),
+                AccountType::NativeSegWit,
+                vec![watched.to_string()],
+            )
+            .unwrap();
+        fund(&mut manager, 2, watched);
+        assert!(matches!(
+            manager.create_transaction("watched", &address, 1_000, 1),
+            Err(WalletError::Signing { input: 0, .. })
+        ));
+        assert!(manager.get_all_transactions().is_empty());
+    }
+
+    #[test]
+    fn data_transactions_embed_and_decode_payloads() {
+        use supernova_core::script::{classify, ScriptKind};
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::OutPoint;
+
+        let dir = tempdir().unwrap();
+        let mut manager = WalletManager::new(dir.path().to_path_buf(), Network::Testnet).unwrap();
+        manager
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        for id in 1..=2u8 {
+            let address = manager.get_new_address("main").unwrap().address;
+            manager
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(
+                        100_000,
+                        hdwallet::address_script(&address).unwrap(),
+                    ),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+        }
+
+        // Data only: the carrier and change back to the account
+        let payload = b"attestation 7f3a: 1.2 tCO2e offset".to_vec();
+        let tx = manager
+            .create_data_transaction("main", &payload, 2)
+            .unwrap();
+        let tx: Transaction = bincode::deserialize(&bincode::serialize(&tx).unwrap()).unwrap();
+        let outputs = tx.outputs();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[0].amount(), 0);
+        assert_eq!(outputs[0].data_payload(), Some(payload.clone()));
+        assert_eq!(
+            classify(outputs[0].script_pubkey()).kind(),
+            ScriptKind::NullData
+        );
+        assert!(outputs[0].is_unspendable());
+        let record = manager.get_all_transactions()[0].clone();
+        assert_eq!(record.amount, 0);
+        assert_eq!(100_000, record.fee + outputs[1].amount());
+        // The fee covers the carrier as well as the input and change
+        let carrier_vbytes = coin_selection::output_vbytes(outputs[0].pub_key_script.len());
+        assert!(record.fee >= 2 * (11 + 68 + carrier_vbytes + 31));
+
+        // A payment can carry data after the recipient
+        let recipient = manager.get_new_address("main").unwrap().address;
+        let tx = manager
+            .create_transaction_with_data("main", &recipient, 30_000, Some(b"invoice 42"), 1)
+            .unwrap();
+        let outputs = tx.outputs();
+        assert_eq!(outputs[0].amount(), 30_000);
+        assert_eq!(outputs[1].data_payload(), Some(b"invoice 42".to_vec()));
+        assert_eq!(
+            tx.outputs().iter().filter(|o| o.is_unspendable()).count(),
+            1
+        );
+
+        // Nothing is spent for a payload nodes would not relay
+        let remaining = manager.utxo_set.get_count();
+        assert!(matches!(
+            manager.create_data_transaction("main", &[0; 81], 1),
+            Err(WalletError::DataTooLarge { size: 81, max: 80 })
+        ));
+        assert_eq!(manager.utxo_set.get_count(), remaining);
+    }
+
+    #[test]
+    fn watch_only_wallet_derives_the_same_addresses_from_an_xpub() {
+        use supernova_core::storage::utxo_set::UtxoEntry;
+        use supernova_core::types::transaction::OutPoint;
+
+        let full_dir = tempdir().unwrap();
+        let mut full = WalletManager::new(full_dir.path().to_path_buf(), Network::Testnet).unwrap();
+        full.create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let xpub = full.export_xpub("main").unwrap();
+
+        let watch_dir = tempdir().unwrap();
+        let mut watch =
+            WalletManager::new_watch_only(watch_dir.path().to_path_buf(), Network::Testnet, &xpub)
+                .unwrap();
+        assert!(watch.is_watch_only());
+        assert_eq!(watch.export_xpub(WATCH_ONLY_ACCOUNT).unwrap(), xpub);
+
+        for _ in 0..10 {
+            let expected = full.get_new_address("main").unwrap();
+            let derived = watch.get_new_address(WATCH_ONLY_ACCOUNT).unwrap();
+            assert_eq!(derived.address, expected.address);
+            assert_eq!(derived.index, expected.index);
+        }
+
+        // Balances are tracked against the UTXO set
+        let address = watch.get_new_address(WATCH_ONLY_ACCOUNT).unwrap();
+        watch
+            .utxo_set
+            .add(UtxoEntry {
+                outpoint: OutPoint {
+                    txid: [1; 32],
+                    vout: 0,
+                },
+                output: TransactionOutput::new(
+                    75_000,
+                    hdwallet::address_script(&address.address).unwrap(),
+                ),
+                height: 1,
+                is_coinbase: false,
+                is_confirmed: true,
+            })
+            .unwrap();
+        assert_eq!(watch.get_balance(WATCH_ONLY_ACCOUNT).unwrap(), 75_000);
+
+        // Nothing that needs private keys works
+        let recipient = full.get_new_address("main").unwrap().address;
+        assert!(matches!(
+            watch.create_transaction(WATCH_ONLY_ACCOUNT, &recipient, 10_000, 1),
+            Err(WalletError::WatchOnly(_))
+        ));
+        assert!(matches!(
+            watch.export_mnemonic(),
+            Err(WalletError::WatchOnly(_))
+        ));
+        assert!(matches!(
+            watch.unlock_memos(),
+            Err(WalletError::WatchOnly(_))
+        ));
+        assert!(matches!(
+            watch.create_account("other".to_string(), AccountType::NativeSegWit),
+            Err(WalletError::WatchOnly(_))
+        ));
+        assert_eq!(watch.get_balance(WATCH_ONLY_ACCOUNT).unwrap(), 75_000);
+
+        // The mode survives a reload
+        let mut reloaded = WalletManager::load(watch_dir.path().to_path_buf()).unwrap();
+        assert!(reloaded.is_watch_only());
+        assert!(matches!(
+            reloaded.export_mnemonic(),
+            Err(WalletError::WatchOnly(_))
+        ));
+        assert_eq!(
+            reloaded
+                .get_new_address(WATCH_ONLY_ACCOUNT)
+                .unwrap()
+                .address,
+            full.get_new_address("main").unwrap().address
+        );
+
+        // A full wallet's xpub is only accepted on its own network
+        assert!(WalletManager::new_watch_only(
+            tempdir().unwrap().path().to_path_buf(),
+            Network::Bitcoin,
+            &xpub
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unsigned_transactions_round_trip_from_watch_only_to_signer() {
+        use bitcoin::secp256k1::{ecdsa::Signature, PublicKey};
+
+        let full_dir = tempdir().unwrap();
+        let mut full = WalletManager::new(full_dir.path().to_path_buf(), Network::Testnet).unwrap();
+        full.create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let watch_dir = tempdir().unwrap();
+        let mut watch = WalletManager::new_watch_only(
+            watch_dir.path().to_path_buf(),
+            Network::Testnet,
+            &full.export_xpub("main").unwrap(),
+        )
+        .unwrap();
+
+        // Both wallets know the funded addresses; only the watcher has the
+        // outputs, as an offline signer would
+        let mut funded = Vec::new();
+        for id in 1..=2u8 {
+            let address = watch.get_new_address(WATCH_ONLY_ACCOUNT).unwrap().address;
+            assert_eq!(full.get_new_address("main").unwrap().address, address);
+            watch
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(
+                        40_000,
+                        hdwallet::address_script(&address).unwrap(),
+                    ),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+            funded.push(address);
+        }
+        let recipient = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+        let unsigned = watch
+            .build_unsigned(WATCH_ONLY_ACCOUNT, recipient, 50_000, 1)
+            .unwrap();
+        assert_eq!(unsigned.inputs.len(), 2);
+        assert!(unsigned
+            .inputs
+            .iter()
+            .all(|input| input.derivation_path.starts_with("m/44'/1'/0'/0/")));
+        assert_eq!(unsigned.input_total(), 80_000);
+        let fee = unsigned.fee().unwrap();
+        // Nothing is recorded or spent before the transaction is signed
+        assert!(watch.get_all_transactions().is_empty());
+        assert_eq!(watch.utxo_set.get_count(), 2);
+        assert!(matches!(
+            watch.sign_unsigned(unsigned.clone()),
+            Err(WalletError::WatchOnly(_))
+        ));
+
+        let blob = unsigned.to_base64().unwrap();
+        let decoded = UnsignedTransaction::from_base64(&format!("{}\n", blob)).unwrap();
+        let tx = full.sign_unsigned(decoded).unwrap();
+
+        let amounts_and_scripts = |outputs: &[TransactionOutput]| {
+            outputs
+                .iter()
+                .map(|o| (o.amount(), o.pub_key_script.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            amounts_and_scripts(tx.outputs()),
+            amounts_and_scripts(&unsigned.outputs)
+        );
+        assert_eq!(tx.outputs()[0].amount(), 50_000);
+        let secp = Secp256k1::verification_only();
+        let message = Message::from_digest(tx.signature_hash());
+        for input in tx.inputs() {
+            let [signature, pubkey] = input.witness() else {
+                panic!("expected a [signature, pubkey] witness");
+            };
+            let pubkey = PublicKey::from_slice(pubkey).unwrap();
+            let signature = Signature::from_compact(signature).unwrap();
+            secp.verify_ecdsa(&message, &signature, &pubkey).unwrap();
+            let address =
+                bitcoin::Address::p2wpkh(&bitcoin::PublicKey::new(pubkey), Network::Testnet)
+                    .unwrap();
+            assert!(funded.contains(&address.to_string()));
+        }
+        let record = full.get_all_transactions()[0].clone();
+        assert_eq!(record.hash, hex::encode(tx.hash()));
+        assert_eq!(record.amount, 50_000);
+        assert_eq!(record.fee, fee);
+    }
+
+    #[test]
+    fn sign_unsigned_refuses_inputs_from_other_wallets() {
+        let full_dir = tempdir().unwrap();
+        let mut full = WalletManager::new(full_dir.path().to_path_buf(), Network::Testnet).unwrap();
+        full.create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let address = full.get_new_address("main").unwrap().address;
+        full.utxo_set
+            .add(UtxoEntry {
+                outpoint: OutPoint {
+                    txid: [1; 32],
+                    vout: 0,
+                },
+                output: TransactionOutput::new(40_000, hdwallet::address_script(&address).unwrap()),
+                height: 1,
+                is_coinbase: false,
+                is_confirmed: true,
+            })
+            .unwrap();
+        let recipient = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let unsigned = full.build_unsigned("main", recipient, 10_000, 1).unwrap();
+
+        // Another seed has an address at the same path, paying another script
+        let other_dir = tempdir().unwrap();
+        let mut other =
+            WalletManager::new(other_dir.path().to_path_buf(), Network::Testnet).unwrap();
+        other
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        other.get_new_address("main").unwrap();
+        assert!(matches!(
+            other.sign_unsigned(unsigned.clone()),
+            Err(WalletError::UnknownInput { input: 0, .. })
+        ));
+
+        // Paths the wallet never generated, or that aren't BIP44, are refused
+        for path in [
+            "m/44'/1'/0'/0/999",
+            "m/44'/1'/7'/0/0",
+            "m/84'/1'/0'/0/0",
+            "garbage",
+        ] {
+            let mut tampered = unsigned.clone();
+            tampered.inputs[0].derivation_path = path.to_string();
+            assert!(matches!(
+                full.sign_unsigned(tampered),
+                Err(WalletError::UnknownInput { input: 0, .. })
+            ));
+        }
+
+        // The blob can't misstate an output the signer knows about
+        let mut inflated = unsigned.clone();
+        inflated.inputs[0].amount = 1_000_000;
+        assert!(matches!(
+            full.sign_unsigned(inflated),
+            Err(WalletError::UnknownInput { input: 0, .. })
+        ));
+        let mut overspent = unsigned.clone();
+        overspent.outputs[0] =
+            TransactionOutput::new(50_000, overspent.outputs[0].pub_key_script.clone());
+        assert!(matches!(
+            full.sign_unsigned(overspent),
+            Err(WalletError::Unsigned(
+                UnsignedError::OutputsExceedInputs { .. }
+            ))
+        ));
+
+        assert!(UnsignedTransaction::from_base64("not base64!").is_err());
+        let mut future = unsigned.clone();
+        future.format = unsigned::UNSIGNED_FORMAT_VERSION + 1;
+        assert!(matches!(
+            UnsignedTransaction::from_base64(&future.to_base64().unwrap()),
+            Err(UnsignedError::UnsupportedFormat(_))
+        ));
+        assert!(full.get_all_transactions().is_empty());
+        assert_eq!(full.utxo_set.get_count(), 1);
+    }
+
+    #[test]
+    fn named_seeds_keep_their_own_passphrase_wallets() {
+        const MNEMONIC: &str =
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let dir = tempdir().unwrap();
+        let store = SeedStore::new(dir.path());
+        let first_address = |name: &str, seed_passphrase: Option<&str>| {
+            let seed_dir = store.seed_dir(name).unwrap();
+            std::fs::create_dir_all(&seed_dir).unwrap();
+            let mut manager =
+                WalletManager::from_mnemonic(MNEMONIC, seed_passphrase, seed_dir, Network::Testnet)
+                    .unwrap();
+            manager
+                .create_account("main".to_string(), AccountType::NativeSegWit)
+                .unwrap();
+            manager.get_new_address("main").unwrap().address
+        };
+        let plain = first_address(DEFAULT_SEED, None);
+        let hidden = first_address("hidden", Some("TREZOR"));
+        assert_ne!(plain, hidden);
+
+        // The default seed stays active until another is selected
+        let manager = WalletManager::load(dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            manager.hd_wallet.list_accounts(false)[0].1.addresses[0].address,
+            plain
+        );
+        store.set_active("hidden").unwrap();
+        let manager = WalletManager::load(dir.path().to_path_buf()).unwrap();
+        assert_eq!(
+            manager.hd_wallet.list_accounts(false)[0].1.addresses[0].address,
+            hidden
+        );
+        assert!(matches!(
+            WalletManager::load_seed(dir.path().to_path_buf(), "missing"),
+            Err(WalletError::Seeds(SeedStoreError::NotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn encrypted_wallet_needs_unlock_for_secrets() {
+        const PASSPHRASE: &str = "Xq9!vTp#Lm7$Rw4&ZkBnHjCdFgVs";
+        let dir = tempdir().unwrap();
+        let mut manager = WalletManager::new(dir.path().to_path_buf(), Network::Testnet).unwrap();
+        manager
+            .create_account("main".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        let first = manager.get_new_address("main").unwrap().address;
+        manager
+            .add_transaction(TransactionRecord {
+                hash: "memo_tx".to_string(),
+                timestamp: chrono::Utc::now(),
+                direction: TransactionDirection::Received,
+                amount: 1000,
+                fee: 0,
+                status: TransactionStatus::Pending,
+                label: None,
+                category: None,
+                tags: vec![],
+                memo: None,
+                addresses: Vec::new(),
+            })
+            .unwrap();
+        manager.add_transaction_memo("memo_tx", "rent").unwrap();
+        assert!(manager.stores_plaintext_seed());
+        manager
+            .hd_wallet
+            .encrypt_with(PASSPHRASE, seed_vault::TEST_KDF)
+            .unwrap();
+        drop(manager);
+
+        // Public data loads without the passphrase
+        let mut manager = WalletManager::load(dir.path().to_path_buf()).unwrap();
+        assert!(manager.is_encrypted() && manager.is_locked());
+        assert!(!manager.stores_plaintext_seed());
+        assert_eq!(manager.get_balance("main").unwrap(), 0);
+        assert_eq!(manager.get_transaction_memo("memo_tx"), None);
+        assert!(matches!(
+            manager.export_mnemonic(),
+            Err(WalletError::Locked(_))
+        ));
+        assert!(matches!(
+            manager.get_new_address("main"),
+            Err(WalletError::Locked(_))
+        ));
+        assert!(matches!(
+            manager.unlock("not the passphrase"),
+            Err(WalletError::HDWallet(HDWalletError::WrongPassphrase))
+        ));
+
+        manager.set_auto_lock(None);
+        manager.unlock(PASSPHRASE).unwrap();
+        assert!(!manager.is_locked());
+        assert_eq!(
+            manager.get_transaction_memo("memo_tx").as_deref(),
+            Some("rent")
+        );
+        let second = manager.get_new_address("main").unwrap().address;
+        assert_ne!(first, second);
+        assert!(!manager.lock_if_expired());
+
+        manager.lock();
+        assert!(manager.is_locked());
+        assert_eq!(manager.get_transaction_memo("memo_tx"), None);
+        assert!(matches!(
+            manager.export_mnemonic(),
+            Err(WalletError::Locked(_))
+        ));
+    }
 }
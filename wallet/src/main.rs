@@ -1,9 +1,18 @@
 mod cli;
 mod backup_warning;
+mod balance_cache;
+mod clock;
 mod core;
+mod display;
+mod fees;
 mod hdwallet;
 mod history;
 mod password_strength;
+mod policy;
+mod rates;
+mod seed_vault;
+mod seeds;
+mod settings;
 mod ui;
 
 fn main() {
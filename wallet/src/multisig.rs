@@ -0,0 +1,473 @@
+//! Shared-custody accounts.
+//!
+//! A [`MultisigAccount`] locks funds to M of N cosigner keys, classical
+//! secp256k1 or quantum (ML-DSA, Falcon) in any mix; see
+//! [`supernova_core::script::multisig`] for the script and output forms.
+//! Spending goes through the unsigned-transaction blob: one participant
+//! builds it with [`WalletManager::build_multisig_unsigned`], each cosigner
+//! adds a signature with [`MultisigAccount::cosign`] and passes the blob on,
+//! and whoever completes the threshold calls
+//! [`WalletManager::finalize_multisig`].
+
+use crate::coin_selection::{self, CoinSelectionStrategy};
+use crate::quantum_wallet::transaction_builder::FINAL_SEQUENCE;
+use crate::unsigned::{self, PartialSignature, UnsignedError, UnsignedInput, UnsignedTransaction};
+use crate::{WalletError, WalletManager};
+use bitcoin::network::Network;
+use bitcoin::secp256k1::{Message, Secp256k1};
+use bitcoin::PrivateKey;
+use serde::{Deserialize, Serialize};
+use supernova_core::crypto::quantum::QuantumKeyPair;
+use supernova_core::script::classify::classify;
+use supernova_core::script::{MultisigError, MultisigScript};
+use supernova_core::storage::utxo_set::{UtxoEntry, UtxoSet};
+use supernova_core::types::transaction::{Transaction, TransactionInput, TransactionOutput};
+
+/// Outpoint, script length prefix and sequence of a segwit input
+const INPUT_BASE_VBYTES: u64 = 41;
+
+/// An M-of-N account over cosigner public keys
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigAccount {
+    pub name: String,
+    script: MultisigScript,
+}
+
+impl MultisigAccount {
+    /// `required` of `keys`. The keys are sorted, so every cosigner derives
+    /// the same address whatever order they were exchanged in.
+    pub fn new(name: &str, required: u8, mut keys: Vec<Vec<u8>>) -> Result<Self, WalletError> {
+        keys.sort();
+        Ok(Self {
+            name: name.to_string(),
+            script: MultisigScript::new(required, keys)?,
+        })
+    }
+
+    pub fn required(&self) -> u8 {
+        self.script.required()
+    }
+
+    pub fn keys(&self) -> &[Vec<u8>] {
+        self.script.keys()
+    }
+
+    pub fn witness_script(&self) -> Vec<u8> {
+        self.script.witness_script()
+    }
+
+    /// Locking script of the account's outputs
+    pub fn script_pubkey(&self) -> Vec<u8> {
+        self.script.script_pubkey()
+    }
+
+    /// Address paying the account: P2WSH on `network` when every key is
+    /// classical, the bech32m commitment address once any key is quantum
+    pub fn address(&self, network: Network) -> Result<String, WalletError> {
+        if !self.script.is_quantum() {
+            let witness_script = bitcoin::ScriptBuf::from_bytes(self.witness_script());
+            return Ok(bitcoin::Address::p2wsh(&witness_script, network).to_string());
+        }
+        classify(&self.script_pubkey())
+            .address()
+            .ok_or_else(|| WalletError::Multisig(MultisigError::NotMultisig))
+    }
+
+    /// Virtual size of spending one of the account's outputs, with the
+    /// largest signatures the keys can make
+    pub fn input_vbytes(&self) -> u64 {
+        let witness_items = self.required() as u64 + 1;
+        // Item count and a three-byte length prefix per item, as quantum
+        // keys and signatures need
+        let witness_size = 1 + 3 * witness_items + self.script.max_witness_size() as u64;
+        INPUT_BASE_VBYTES + (witness_size + 3) / 4
+    }
+
+    /// Confirmed outputs in `utxo_set` paying the account
+    pub fn spendable_utxos(&self, utxo_set: &UtxoSet) -> Vec<UtxoEntry> {
+        let script_pubkey = self.script_pubkey();
+        utxo_set
+            .entries()
+            .into_iter()
+            .filter(|entry| entry.is_confirmed && entry.output.pub_key_script == script_pubkey)
+            .collect()
+    }
+
+    /// Sign every input of `tx` with `key`, one of the account's keys, and
+    /// return how many cosigners have now signed. Signing again replaces
+    /// the key's earlier signature rather than adding one.
+    pub fn cosign(
+        &self,
+        tx: &mut UnsignedTransaction,
+        key: &CosignerKey,
+    ) -> Result<usize, WalletError> {
+        self.check_inputs(tx)?;
+        let public_key = key.public_key();
+        if self.script.key_index(&public_key).is_none() {
+            return Err(MultisigError::UnknownKey.into());
+        }
+        let sighash = tx.signature_hash();
+        for (index, input) in tx.inputs.iter_mut().enumerate() {
+            let signature = key.sign(&sighash).map_err(|reason| WalletError::Signing {
+                input: index,
+                reason,
+            })?;
+            input
+                .partial_signatures
+                .retain(|partial| partial.public_key != public_key);
+            input.partial_signatures.push(PartialSignature {
+                public_key: public_key.clone(),
+                signature,
+            });
+        }
+        Ok(self.cosigners(tx))
+    }
+
+    /// Cosigners with a valid signature on every input of `tx`
+    pub fn cosigners(&self, tx: &UnsignedTransaction) -> usize {
+        let sighash = tx.signature_hash();
+        tx.inputs
+            .iter()
+            .map(|input| self.valid_signatures(input, &sighash).len())
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Signatures of `input` that verify under the key they claim, by key
+    /// index: one per key, in key order
+    fn valid_signatures(&self, input: &UnsignedInput, sighash: &[u8; 32]) -> Vec<(usize, Vec<u8>)> {
+        let mut valid: Vec<(usize, Vec<u8>)> = Vec::new();
+        for partial in &input.partial_signatures {
+            let Some(index) = self.script.key_index(&partial.public_key) else {
+                continue;
+            };
+            let signers = self
+                .script
+                .signers(sighash, std::slice::from_ref(&partial.signature));
+            if signers == [index] && !valid.iter().any(|(signed, _)| *signed == index) {
+                valid.push((index, partial.signature.clone()));
+            }
+        }
+        valid.sort_by_key(|(index, _)| *index);
+        valid
+    }
+
+    /// Assemble the signed transaction once every input meets the
+    /// threshold. Fails with [`MultisigError::NotEnoughSignatures`] before
+    /// that.
+    pub fn finalize(&self, tx: &UnsignedTransaction) -> Result<Transaction, WalletError> {
+        self.check_inputs(tx)?;
+        let sighash = tx.signature_hash();
+        let witness_script = self.witness_script();
+        let mut inputs = Vec::with_capacity(tx.inputs.len());
+        for input in &tx.inputs {
+            let valid = self.valid_signatures(input, &sighash);
+            if valid.len() < self.required() as usize {
+                return Err(MultisigError::NotEnoughSignatures {
+                    required: self.required(),
+                    valid: valid.len(),
+                }
+                .into());
+            }
+            // Exactly `required` signatures
+            let mut witness: Vec<Vec<u8>> = valid
+                .into_iter()
+                .take(self.required() as usize)
+                .map(|(_, signature)| signature)
+                .collect();
+            witness.push(witness_script.clone());
+            inputs.push(TransactionInput::new_with_witness(
+                input.outpoint.txid,
+                input.outpoint.vout,
+                Vec::new(),
+                input.sequence,
+                witness,
+            ));
+        }
+        Ok(Transaction::new(
+            tx.version,
+            inputs,
+            tx.outputs.clone(),
+            tx.lock_time,
+        ))
+    }
+
+    /// Every input must spend one of the account's outputs
+    fn check_inputs(&self, tx: &UnsignedTransaction) -> Result<(), WalletError> {
+        if tx.format != unsigned::UNSIGNED_FORMAT_VERSION {
+            return Err(UnsignedError::UnsupportedFormat(tx.format).into());
+        }
+        let witness_script = self.witness_script();
+        let script_pubkey = self.script_pubkey();
+        for (index, input) in tx.inputs.iter().enumerate() {
+            if input.witness_script.as_ref() != Some(&witness_script)
+                || input.script_pubkey != script_pubkey
+            {
+                return Err(WalletError::UnknownInput {
+                    input: index,
+                    reason: format!("not an output of multisig account '{}'", self.name),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A cosigner's secret key
+pub enum CosignerKey {
+    Classical(PrivateKey),
+    Quantum(QuantumKeyPair),
+}
+
+impl CosignerKey {
+    /// Public key as it appears in the multisig script
+    pub fn public_key(&self) -> Vec<u8> {
+        match self {
+            CosignerKey::Classical(key) => key.public_key(&Secp256k1::new()).to_bytes(),
+            CosignerKey::Quantum(keypair) => keypair.public_key.clone(),
+        }
+    }
+
+    fn sign(&self, digest: &[u8; 32]) -> Result<Vec<u8>, String> {
+        match self {
+            CosignerKey::Classical(key) => Ok(Secp256k1::new()
+                .sign_ecdsa(&Message::from_digest(*digest), &key.inner)
+                .serialize_compact()
+                .to_vec()),
+            CosignerKey::Quantum(keypair) => keypair.sign(digest).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl WalletManager {
+    /// Fund a payment of `amount` to `recipient` from the outputs of
+    /// `account` in the UTXO cache, for its cosigners to sign. Change goes
+    /// back to the account.
+    pub fn build_multisig_unsigned(
+        &mut self,
+        account: &MultisigAccount,
+        recipient: &str,
+        amount: u64,
+        fee_rate: u64,
+    ) -> Result<UnsignedTransaction, WalletError> {
+        let mut outputs = vec![TransactionOutput::new(
+            amount,
+            self.recipient_script(recipient)?,
+        )];
+        let outputs_vbytes = coin_selection::output_vbytes(outputs[0].pub_key_script.len());
+        let utxos = account.spendable_utxos(&self.utxo_set);
+        let selection = coin_selection::select_coins_for_inputs(
+            &utxos,
+            amount,
+            account.input_vbytes(),
+            outputs_vbytes,
+            fee_rate,
+            self.dust_threshold,
+            CoinSelectionStrategy::default(),
+            &mut rand::rngs::OsRng,
+        )?;
+        if selection.change > 0 {
+            outputs.push(TransactionOutput::new(
+                selection.change,
+                account.script_pubkey(),
+            ));
+        }
+
+        let witness_script = account.witness_script();
+        let inputs = utxos
+            .into_iter()
+            .filter(|entry| selection.outpoints.contains(&entry.outpoint))
+            .map(|entry| UnsignedInput {
+                outpoint: entry.outpoint,
+                sequence: FINAL_SEQUENCE,
+                amount: entry.output.amount(),
+                script_pubkey: entry.output.pub_key_script,
+                derivation_path: String::new(),
+                witness_script: Some(witness_script.clone()),
+                partial_signatures: Vec::new(),
+            })
+            .collect();
+        Ok(UnsignedTransaction {
+            format: unsigned::UNSIGNED_FORMAT_VERSION,
+            version: 1,
+            inputs,
+            output_paths: vec![None; outputs.len()],
+            outputs,
+            lock_time: 0,
+            amount,
+        })
+    }
+
+    /// [`MultisigAccount::finalize`] `tx` and record it like
+    /// [`create_transaction`](Self::create_transaction)
+    pub fn finalize_multisig(
+        &mut self,
+        account: &MultisigAccount,
+        tx: &UnsignedTransaction,
+    ) -> Result<Transaction, WalletError> {
+        let fee = tx.fee()?;
+        let transaction = account.finalize(tx)?;
+        self.record_send(&transaction, tx.amount, fee)?;
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supernova_core::crypto::quantum::{QuantumParameters, QuantumScheme};
+    use supernova_core::types::transaction::OutPoint;
+    use supernova_core::{TransactionValidator, ValidationResult};
+    use tempfile::tempdir;
+
+    const RECIPIENT: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    fn classical(seed: u8) -> CosignerKey {
+        CosignerKey::Classical(PrivateKey::from_slice(&[seed; 32], Network::Testnet).unwrap())
+    }
+
+    fn ml_dsa() -> CosignerKey {
+        CosignerKey::Quantum(
+            QuantumKeyPair::generate(QuantumParameters {
+                scheme: QuantumScheme::Dilithium,
+                security_level: 3,
+            })
+            .unwrap(),
+        )
+    }
+
+    /// A wallet holding two outputs of 40,000 paying `account`
+    fn funded(account: &MultisigAccount, dir: &std::path::Path) -> WalletManager {
+        let mut wallet = WalletManager::new(dir.to_path_buf(), Network::Testnet).unwrap();
+        for id in 1..=2u8 {
+            wallet
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(40_000, account.script_pubkey()),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+        }
+        wallet
+    }
+
+    #[test]
+    fn two_of_three_with_an_ml_dsa_cosigner() {
+        let cosigners = [classical(1), classical(2), ml_dsa()];
+        let keys: Vec<Vec<u8>> = cosigners.iter().map(CosignerKey::public_key).collect();
+        let account = MultisigAccount::new("vault", 2, keys.clone()).unwrap();
+        // Key order does not change the account
+        let reversed = keys.into_iter().rev().collect();
+        assert_eq!(MultisigAccount::new("vault", 2, reversed).unwrap(), account);
+        assert!(account
+            .address(Network::Testnet)
+            .unwrap()
+            .starts_with("nova1"));
+
+        let dir = tempdir().unwrap();
+        let mut wallet = funded(&account, dir.path());
+        let unsigned = wallet
+            .build_multisig_unsigned(&account, RECIPIENT, 30_000, 1)
+            .unwrap();
+        assert_eq!(unsigned.inputs.len(), 1);
+        let fee = unsigned.fee().unwrap();
+        assert!(fee >= account.input_vbytes());
+
+        // The blob goes from cosigner to cosigner
+        let mut blob = unsigned.to_base64().unwrap();
+        let mut tx = UnsignedTransaction::from_base64(&blob).unwrap();
+        assert_eq!(account.cosign(&mut tx, &cosigners[2]).unwrap(), 1);
+        assert!(matches!(
+            account.finalize(&tx),
+            Err(WalletError::Multisig(MultisigError::NotEnoughSignatures {
+                required: 2,
+                valid: 1
+            }))
+        ));
+        blob = tx.to_base64().unwrap();
+
+        let mut tx = UnsignedTransaction::from_base64(&blob).unwrap();
+        assert_eq!(account.cosign(&mut tx, &cosigners[0]).unwrap(), 2);
+        let signed = wallet.finalize_multisig(&account, &tx).unwrap();
+        assert_eq!(signed.inputs()[0].witness().len(), 3);
+
+        let prevouts: Vec<UtxoEntry> = unsigned
+            .inputs
+            .iter()
+            .map(|input| wallet_entry(input))
+            .collect();
+        let result = TransactionValidator::new()
+            .validate_signatures(&signed, |txid, vout| {
+                prevouts
+                    .iter()
+                    .find(|entry| entry.outpoint.txid == *txid && entry.outpoint.vout == vout)
+                    .map(|entry| entry.output.clone())
+            })
+            .unwrap();
+        assert!(matches!(result, ValidationResult::Valid));
+        assert_eq!(wallet.get_all_transactions()[0].fee, fee);
+        assert_eq!(account.spendable_utxos(&wallet.utxo_set).len(), 1);
+    }
+
+    fn wallet_entry(input: &UnsignedInput) -> UtxoEntry {
+        UtxoEntry {
+            outpoint: input.outpoint.clone(),
+            output: TransactionOutput::new(input.amount, input.script_pubkey.clone()),
+            height: 1,
+            is_coinbase: false,
+            is_confirmed: true,
+        }
+    }
+
+    #[test]
+    fn repeated_or_foreign_signatures_do_not_reach_the_threshold() {
+        let cosigners = [classical(1), classical(2), classical(3)];
+        let account = MultisigAccount::new(
+            "shared",
+            2,
+            cosigners.iter().map(CosignerKey::public_key).collect(),
+        )
+        .unwrap();
+        let address = account.address(Network::Testnet).unwrap();
+        assert!(address.starts_with("tb1q") && address.len() == 62);
+
+        let dir = tempdir().unwrap();
+        let mut wallet = funded(&account, dir.path());
+        let mut tx = wallet
+            .build_multisig_unsigned(&account, RECIPIENT, 50_000, 1)
+            .unwrap();
+        assert_eq!(tx.inputs.len(), 2);
+        assert_eq!(account.cosign(&mut tx, &cosigners[1]).unwrap(), 1);
+        assert_eq!(account.cosign(&mut tx, &cosigners[1]).unwrap(), 1);
+
+        // A stranger can't sign, nor pass a signature off as a cosigner's
+        assert!(matches!(
+            account.cosign(&mut tx, &classical(4)),
+            Err(WalletError::Multisig(MultisigError::UnknownKey))
+        ));
+        let mut forged = tx.clone();
+        for input in &mut forged.inputs {
+            let mut copy = input.partial_signatures[0].clone();
+            copy.public_key = cosigners[0].public_key();
+            input.partial_signatures.push(copy);
+        }
+        assert_eq!(account.cosigners(&forged), 1);
+        assert!(matches!(
+            wallet.finalize_multisig(&account, &forged),
+            Err(WalletError::Multisig(
+                MultisigError::NotEnoughSignatures { .. }
+            ))
+        ));
+        assert!(wallet.get_all_transactions().is_empty());
+
+        assert_eq!(account.cosign(&mut tx, &cosigners[2]).unwrap(), 2);
+        let signed = wallet.finalize_multisig(&account, &tx).unwrap();
+        assert_eq!(signed.inputs().len(), 2);
+    }
+}
@@ -0,0 +1,781 @@
+//! Per-account spending policies.
+//!
+//! A [`SpendingPolicy`] constrains what an account may send: a per-transaction
+//! cap, a rolling 24-hour cap, destination allow/deny lists, and an approval
+//! threshold above which a spend is parked until a second approval (or a
+//! cosigner signature) arrives.
+//!
+//! Policies live in the wallet file next to the accounts they govern. Each
+//! one is sealed with an HMAC-SHA256 keyed from the wallet seed, so editing
+//! the JSON on disk to loosen a limit is detected on the next evaluation and
+//! every spend from that account is refused until the policy is re-set
+//! through the wallet.
+
+use bitcoin::secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Length of the rolling spend window.
+pub const SPEND_WINDOW_HOURS: i64 = 24;
+
+/// Domain tag mixed into the policy sealing key.
+const POLICY_KEY_DOMAIN: &[u8] = b"supernova-wallet/spending-policy/v1";
+
+/// A rule matching a spend destination.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum DestinationRule {
+    /// Exact address match.
+    Address(String),
+    /// Output descriptor match, e.g. `wpkh(02ab...)`. The optional `#checksum`
+    /// suffix is ignored on both sides.
+    Descriptor(String),
+}
+
+impl DestinationRule {
+    fn matches(&self, destination: &Destination) -> bool {
+        match self {
+            DestinationRule::Address(address) => *address == destination.address,
+            DestinationRule::Descriptor(descriptor) => destination
+                .descriptor
+                .as_deref()
+                .map(|d| strip_checksum(d) == strip_checksum(descriptor))
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl std::fmt::Display for DestinationRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DestinationRule::Address(address) => write!(f, "address {}", address),
+            DestinationRule::Descriptor(descriptor) => write!(f, "descriptor {}", descriptor),
+        }
+    }
+}
+
+impl FromStr for DestinationRule {
+    type Err = String;
+
+    /// Parses `addr:<address>` / `desc:<descriptor>`; a bare value containing
+    /// `(` is treated as a descriptor, anything else as an address.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty destination rule".to_string());
+        }
+        if let Some(rest) = s.strip_prefix("addr:") {
+            return Ok(DestinationRule::Address(rest.to_string()));
+        }
+        if let Some(rest) = s.strip_prefix("desc:") {
+            return Ok(DestinationRule::Descriptor(rest.to_string()));
+        }
+        if s.contains('(') {
+            Ok(DestinationRule::Descriptor(s.to_string()))
+        } else {
+            Ok(DestinationRule::Address(s.to_string()))
+        }
+    }
+}
+
+fn strip_checksum(descriptor: &str) -> &str {
+    descriptor.split('#').next().unwrap_or(descriptor).trim()
+}
+
+/// Where a spend is going.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Destination {
+    pub address: String,
+    /// Descriptor of the destination script, when the caller knows it.
+    #[serde(default)]
+    pub descriptor: Option<String>,
+}
+
+impl Destination {
+    pub fn address(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            descriptor: None,
+        }
+    }
+
+    pub fn with_descriptor(mut self, descriptor: impl Into<String>) -> Self {
+        self.descriptor = Some(descriptor.into());
+        self
+    }
+}
+
+/// Spending constraints for one account. Every field is optional; an empty
+/// policy permits everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingPolicy {
+    /// Maximum amount of a single spend, in nova units.
+    #[serde(default)]
+    pub per_transaction_limit: Option<u64>,
+    /// Maximum total spent within any rolling 24-hour window.
+    #[serde(default)]
+    pub daily_limit: Option<u64>,
+    /// If non-empty, only these destinations may be paid.
+    #[serde(default)]
+    pub allow_list: Vec<DestinationRule>,
+    /// Destinations that may never be paid. Checked before the allow-list.
+    #[serde(default)]
+    pub deny_list: Vec<DestinationRule>,
+    /// Spends strictly above this amount are parked for a second approval.
+    #[serde(default)]
+    pub approval_threshold: Option<u64>,
+    /// Hex-encoded secp256k1 public keys whose signature over a pending
+    /// spend's digest approves it in place of an interactive approval.
+    #[serde(default)]
+    pub cosigner_keys: Vec<String>,
+}
+
+impl SpendingPolicy {
+    pub fn is_empty(&self) -> bool {
+        *self == SpendingPolicy::default()
+    }
+
+    /// One-line human-readable summary for CLI/TUI display.
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(limit) = self.per_transaction_limit {
+            parts.push(format!("per-tx ≤ {}", limit));
+        }
+        if let Some(limit) = self.daily_limit {
+            parts.push(format!("24h ≤ {}", limit));
+        }
+        if let Some(threshold) = self.approval_threshold {
+            parts.push(format!("approval > {}", threshold));
+        }
+        if !self.allow_list.is_empty() {
+            parts.push(format!("allow-list: {}", self.allow_list.len()));
+        }
+        if !self.deny_list.is_empty() {
+            parts.push(format!("deny-list: {}", self.deny_list.len()));
+        }
+        if !self.cosigner_keys.is_empty() {
+            parts.push(format!("cosigners: {}", self.cosigner_keys.len()));
+        }
+        if parts.is_empty() {
+            "no restrictions".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
+
+/// Why a spend was refused.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PolicyViolation {
+    #[error("amount {amount} exceeds the per-transaction limit of {limit}")]
+    PerTransactionLimitExceeded { amount: u64, limit: u64 },
+    #[error(
+        "daily limit exceeded: {spent} already spent in the last 24h, \
+         {requested} requested, limit {limit}"
+    )]
+    DailyLimitExceeded { spent: u64, requested: u64, limit: u64 },
+    #[error("destination {address} is on the deny-list ({rule})")]
+    DestinationDenied { address: String, rule: DestinationRule },
+    #[error("destination {address} is not on the allow-list")]
+    DestinationNotAllowed { address: String },
+    #[error("spending policy for account '{account}' failed integrity check; re-set the policy")]
+    PolicyTampered { account: String },
+    #[error("pending spend {0} not found")]
+    PendingNotFound(String),
+    #[error("cosigner approval rejected: {0}")]
+    InvalidCosignerApproval(String),
+}
+
+/// Outcome of evaluating a spend that did not violate the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// The spend may proceed and has been counted against the daily limit.
+    Approved,
+    /// The spend is above the approval threshold and was parked.
+    PendingApproval(PendingSpend),
+}
+
+/// A spend parked until it receives a second approval.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingSpend {
+    pub id: String,
+    pub account: String,
+    pub destination: Destination,
+    pub amount: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PendingSpend {
+    /// Digest a cosigner signs to approve this spend.
+    pub fn approval_digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"supernova-wallet/pending-spend/v1");
+        hasher.update(self.id.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.account.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.destination.address.as_bytes());
+        hasher.update([0]);
+        hasher.update(self.amount.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// How a parked spend is approved.
+#[derive(Debug, Clone)]
+pub enum Approval {
+    /// An explicit second approval call by the wallet operator.
+    Confirm,
+    /// An ECDSA signature over [`PendingSpend::approval_digest`] from one of
+    /// the policy's cosigner keys.
+    Cosigner { public_key: String, signature: String },
+}
+
+/// A spend counted against the rolling daily window.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendEntry {
+    pub timestamp: DateTime<Utc>,
+    pub amount: u64,
+}
+
+/// Rolling 24-hour spend tracker for one account.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendTracker {
+    entries: Vec<SpendEntry>,
+}
+
+impl SpendTracker {
+    /// Total spent in the window ending at `now`. An entry exactly 24h old has
+    /// left the window.
+    pub fn spent_in_window(&self, now: DateTime<Utc>) -> u64 {
+        let cutoff = now - Duration::hours(SPEND_WINDOW_HOURS);
+        self.entries
+            .iter()
+            .filter(|e| e.timestamp > cutoff && e.timestamp <= now)
+            .map(|e| e.amount)
+            .fold(0u64, |acc, a| acc.saturating_add(a))
+    }
+
+    pub fn record(&mut self, amount: u64, now: DateTime<Utc>) {
+        self.prune(now);
+        self.entries.push(SpendEntry {
+            timestamp: now,
+            amount,
+        });
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::hours(SPEND_WINDOW_HOURS);
+        self.entries.retain(|e| e.timestamp > cutoff);
+    }
+}
+
+/// A policy together with its seal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedPolicy {
+    policy: SpendingPolicy,
+    /// Hex HMAC-SHA256 over the account name and canonical policy JSON.
+    mac: String,
+}
+
+/// All policy state persisted in the wallet file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyStore {
+    #[serde(default)]
+    policies: HashMap<String, SealedPolicy>,
+    #[serde(default)]
+    trackers: HashMap<String, SpendTracker>,
+    #[serde(default)]
+    pending: Vec<PendingSpend>,
+}
+
+impl PolicyStore {
+    /// Install (or replace) the policy for `account`. An empty policy removes it.
+    pub fn set_policy(
+        &mut self,
+        key: &[u8; 32],
+        account: &str,
+        policy: SpendingPolicy,
+    ) -> Result<(), PolicyViolation> {
+        for cosigner in &policy.cosigner_keys {
+            parse_public_key(cosigner)?;
+        }
+        if policy.is_empty() {
+            self.policies.remove(account);
+            return Ok(());
+        }
+        let mac = seal(key, account, &policy);
+        self.policies
+            .insert(account.to_string(), SealedPolicy { policy, mac });
+        Ok(())
+    }
+
+    /// Whether `account` has a policy, verified or not
+    pub fn has_policy(&self, account: &str) -> bool {
+        self.policies.contains_key(account)
+    }
+
+    /// The verified policy for `account`, if any.
+    pub fn policy(
+        &self,
+        key: &[u8; 32],
+        account: &str,
+    ) -> Result<Option<&SpendingPolicy>, PolicyViolation> {
+        match self.policies.get(account) {
+            None => Ok(None),
+            Some(sealed) => {
+                let expected = seal(key, account, &sealed.policy);
+                if constant_time_eq(expected.as_bytes(), sealed.mac.as_bytes()) {
+                    Ok(Some(&sealed.policy))
+                } else {
+                    Err(PolicyViolation::PolicyTampered {
+                        account: account.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    pub fn spent_in_window(&self, account: &str, now: DateTime<Utc>) -> u64 {
+        self.trackers
+            .get(account)
+            .map(|t| t.spent_in_window(now))
+            .unwrap_or(0)
+    }
+
+    pub fn pending(&self) -> &[PendingSpend] {
+        &self.pending
+    }
+
+    /// Evaluate a spend. Approved spends are recorded against the daily
+    /// window immediately; spends above the approval threshold are parked and
+    /// only counted once approved.
+    pub fn evaluate(
+        &mut self,
+        key: &[u8; 32],
+        account: &str,
+        destination: &Destination,
+        amount: u64,
+        now: DateTime<Utc>,
+    ) -> Result<PolicyDecision, PolicyViolation> {
+        let policy = match self.policy(key, account)? {
+            Some(policy) => policy.clone(),
+            None => return Ok(PolicyDecision::Approved),
+        };
+
+        self.check_limits(&policy, account, destination, amount, now)?;
+
+        if policy.approval_threshold.is_some_and(|t| amount > t) {
+            let pending = PendingSpend {
+                id: pending_id(account, destination, amount, now, self.pending.len()),
+                account: account.to_string(),
+                destination: destination.clone(),
+                amount,
+                created_at: now,
+            };
+            self.pending.push(pending.clone());
+            return Ok(PolicyDecision::PendingApproval(pending));
+        }
+
+        self.trackers
+            .entry(account.to_string())
+            .or_default()
+            .record(amount, now);
+        Ok(PolicyDecision::Approved)
+    }
+
+    /// Approve a parked spend. Limits are re-checked at approval time because
+    /// the daily window may have filled up while the spend was waiting.
+    pub fn approve(
+        &mut self,
+        key: &[u8; 32],
+        id: &str,
+        approval: &Approval,
+        now: DateTime<Utc>,
+    ) -> Result<PendingSpend, PolicyViolation> {
+        let index = self
+            .pending
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| PolicyViolation::PendingNotFound(id.to_string()))?;
+        let pending = self.pending[index].clone();
+
+        let policy = self
+            .policy(key, &pending.account)?
+            .cloned()
+            .unwrap_or_default();
+
+        if let Approval::Cosigner {
+            public_key,
+            signature,
+        } = approval
+        {
+            verify_cosigner(&policy, &pending, public_key, signature)?;
+        }
+
+        self.check_limits(&policy, &pending.account, &pending.destination, pending.amount, now)?;
+
+        self.pending.remove(index);
+        self.trackers
+            .entry(pending.account.clone())
+            .or_default()
+            .record(pending.amount, now);
+        Ok(pending)
+    }
+
+    /// Drop a parked spend without approving it.
+    pub fn reject(&mut self, id: &str) -> Result<PendingSpend, PolicyViolation> {
+        let index = self
+            .pending
+            .iter()
+            .position(|p| p.id == id)
+            .ok_or_else(|| PolicyViolation::PendingNotFound(id.to_string()))?;
+        Ok(self.pending.remove(index))
+    }
+
+    fn check_limits(
+        &self,
+        policy: &SpendingPolicy,
+        account: &str,
+        destination: &Destination,
+        amount: u64,
+        now: DateTime<Utc>,
+    ) -> Result<(), PolicyViolation> {
+        if let Some(rule) = policy.deny_list.iter().find(|r| r.matches(destination)) {
+            return Err(PolicyViolation::DestinationDenied {
+                address: destination.address.clone(),
+                rule: rule.clone(),
+            });
+        }
+        if !policy.allow_list.is_empty()
+            && !policy.allow_list.iter().any(|r| r.matches(destination))
+        {
+            return Err(PolicyViolation::DestinationNotAllowed {
+                address: destination.address.clone(),
+            });
+        }
+        if let Some(limit) = policy.per_transaction_limit {
+            if amount > limit {
+                return Err(PolicyViolation::PerTransactionLimitExceeded { amount, limit });
+            }
+        }
+        if let Some(limit) = policy.daily_limit {
+            let spent = self.spent_in_window(account, now);
+            if spent.saturating_add(amount) > limit {
+                return Err(PolicyViolation::DailyLimitExceeded {
+                    spent,
+                    requested: amount,
+                    limit,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Derive the policy sealing key from the wallet's BIP39 seed.
+pub fn derive_policy_key(seed: &[u8]) -> [u8; 32] {
+    hmac_sha256(POLICY_KEY_DOMAIN, seed)
+}
+
+fn seal(key: &[u8; 32], account: &str, policy: &SpendingPolicy) -> String {
+    // serde_json serializes struct fields in declaration order, so this is
+    // stable for a given policy.
+    let body = serde_json::to_vec(policy).unwrap_or_default();
+    let mut message = Vec::with_capacity(account.len() + 1 + body.len());
+    message.extend_from_slice(account.as_bytes());
+    message.push(0);
+    message.extend_from_slice(&body);
+    hex::encode(hmac_sha256(key, &message))
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+    let mut block_key = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block_key[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block_key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.update(message);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(inner_hash);
+    outer.finalize().into()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn pending_id(
+    account: &str,
+    destination: &Destination,
+    amount: u64,
+    now: DateTime<Utc>,
+    salt: usize,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(account.as_bytes());
+    hasher.update(destination.address.as_bytes());
+    hasher.update(amount.to_le_bytes());
+    hasher.update(now.timestamp_nanos_opt().unwrap_or_default().to_le_bytes());
+    hasher.update(salt.to_le_bytes());
+    hex::encode(&hasher.finalize()[..8])
+}
+
+fn parse_public_key(hex_key: &str) -> Result<PublicKey, PolicyViolation> {
+    let bytes = hex::decode(hex_key)
+        .map_err(|e| PolicyViolation::InvalidCosignerApproval(format!("bad key hex: {}", e)))?;
+    PublicKey::from_slice(&bytes)
+        .map_err(|e| PolicyViolation::InvalidCosignerApproval(format!("bad public key: {}", e)))
+}
+
+fn verify_cosigner(
+    policy: &SpendingPolicy,
+    pending: &PendingSpend,
+    public_key: &str,
+    signature: &str,
+) -> Result<(), PolicyViolation> {
+    if !policy
+        .cosigner_keys
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(public_key))
+    {
+        return Err(PolicyViolation::InvalidCosignerApproval(
+            "key is not a registered cosigner for this account".to_string(),
+        ));
+    }
+    let key = parse_public_key(public_key)?;
+    let sig_bytes = hex::decode(signature).map_err(|e| {
+        PolicyViolation::InvalidCosignerApproval(format!("bad signature hex: {}", e))
+    })?;
+    let sig = Signature::from_der(&sig_bytes)
+        .or_else(|_| Signature::from_compact(&sig_bytes))
+        .map_err(|e| PolicyViolation::InvalidCosignerApproval(format!("bad signature: {}", e)))?;
+    let message = Message::from_digest_slice(&pending.approval_digest())
+        .map_err(|e| PolicyViolation::InvalidCosignerApproval(e.to_string()))?;
+    Secp256k1::verification_only()
+        .verify_ecdsa(&message, &sig, &key)
+        .map_err(|_| {
+            PolicyViolation::InvalidCosignerApproval("signature does not verify".to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::SecretKey;
+
+    const KEY: [u8; 32] = [7u8; 32];
+
+    fn t0() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn store_with(policy: SpendingPolicy) -> PolicyStore {
+        let mut store = PolicyStore::default();
+        store.set_policy(&KEY, "main", policy).unwrap();
+        store
+    }
+
+    #[test]
+    fn per_transaction_limit_is_enforced() {
+        let mut store = store_with(SpendingPolicy {
+            per_transaction_limit: Some(1_000),
+            ..Default::default()
+        });
+        let dest = Destination::address("addr1");
+        assert_eq!(
+            store.evaluate(&KEY, "main", &dest, 1_000, t0()),
+            Ok(PolicyDecision::Approved)
+        );
+        assert_eq!(
+            store.evaluate(&KEY, "main", &dest, 1_001, t0()),
+            Err(PolicyViolation::PerTransactionLimitExceeded {
+                amount: 1_001,
+                limit: 1_000
+            })
+        );
+    }
+
+    #[test]
+    fn daily_limit_window_edge_is_exactly_24h() {
+        let mut store = store_with(SpendingPolicy {
+            daily_limit: Some(1_000),
+            ..Default::default()
+        });
+        let dest = Destination::address("addr1");
+        assert!(store.evaluate(&KEY, "main", &dest, 800, t0()).is_ok());
+
+        // One second before the window closes the earlier spend still counts.
+        let almost = t0() + Duration::hours(24) - Duration::seconds(1);
+        assert_eq!(
+            store.evaluate(&KEY, "main", &dest, 300, almost),
+            Err(PolicyViolation::DailyLimitExceeded {
+                spent: 800,
+                requested: 300,
+                limit: 1_000
+            })
+        );
+
+        // Exactly 24h later it has rolled out.
+        let edge = t0() + Duration::hours(24);
+        assert_eq!(store.spent_in_window("main", edge), 0);
+        assert!(store.evaluate(&KEY, "main", &dest, 300, edge).is_ok());
+    }
+
+    #[test]
+    fn spend_tracker_survives_serialization() {
+        let mut store = store_with(SpendingPolicy {
+            daily_limit: Some(1_000),
+            ..Default::default()
+        });
+        let dest = Destination::address("addr1");
+        store.evaluate(&KEY, "main", &dest, 900, t0()).unwrap();
+
+        let json = serde_json::to_string(&store).unwrap();
+        let mut restored: PolicyStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.spent_in_window("main", t0()), 900);
+        assert!(matches!(
+            restored.evaluate(&KEY, "main", &dest, 200, t0() + Duration::hours(1)),
+            Err(PolicyViolation::DailyLimitExceeded { spent: 900, .. })
+        ));
+    }
+
+    #[test]
+    fn deny_list_matches_addresses_and_descriptors() {
+        let mut store = store_with(SpendingPolicy {
+            deny_list: vec![
+                DestinationRule::Address("bad-addr".to_string()),
+                DestinationRule::Descriptor("wpkh(02aa)#abcd1234".to_string()),
+            ],
+            ..Default::default()
+        });
+        assert!(matches!(
+            store.evaluate(&KEY, "main", &Destination::address("bad-addr"), 1, t0()),
+            Err(PolicyViolation::DestinationDenied { .. })
+        ));
+        let by_descriptor = Destination::address("other").with_descriptor("wpkh(02aa)");
+        assert!(matches!(
+            store.evaluate(&KEY, "main", &by_descriptor, 1, t0()),
+            Err(PolicyViolation::DestinationDenied { .. })
+        ));
+        assert!(store
+            .evaluate(&KEY, "main", &Destination::address("fine"), 1, t0())
+            .is_ok());
+    }
+
+    #[test]
+    fn allow_list_rejects_unlisted_destinations() {
+        let mut store = store_with(SpendingPolicy {
+            allow_list: vec!["addr:exchange".parse().unwrap()],
+            ..Default::default()
+        });
+        assert!(store
+            .evaluate(&KEY, "main", &Destination::address("exchange"), 5, t0())
+            .is_ok());
+        assert_eq!(
+            store.evaluate(&KEY, "main", &Destination::address("stranger"), 5, t0()),
+            Err(PolicyViolation::DestinationNotAllowed {
+                address: "stranger".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn tampered_policy_is_refused() {
+        let store = store_with(SpendingPolicy {
+            per_transaction_limit: Some(10),
+            ..Default::default()
+        });
+        let mut json: serde_json::Value = serde_json::to_value(&store).unwrap();
+        json["policies"]["main"]["policy"]["per_transaction_limit"] = serde_json::json!(1_000_000);
+        let mut tampered: PolicyStore = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            tampered.evaluate(&KEY, "main", &Destination::address("a"), 5, t0()),
+            Err(PolicyViolation::PolicyTampered {
+                account: "main".to_string()
+            })
+        );
+        // A different wallet's key cannot vouch for the policy either.
+        assert!(store.policy(&[9u8; 32], "main").is_err());
+    }
+
+    #[test]
+    fn above_threshold_spend_is_parked_until_approved() {
+        let mut store = store_with(SpendingPolicy {
+            daily_limit: Some(10_000),
+            approval_threshold: Some(1_000),
+            ..Default::default()
+        });
+        let dest = Destination::address("addr1");
+        let pending = match store.evaluate(&KEY, "main", &dest, 5_000, t0()).unwrap() {
+            PolicyDecision::PendingApproval(p) => p,
+            other => panic!("expected pending, got {:?}", other),
+        };
+        assert_eq!(store.pending().len(), 1);
+        // Parked spends do not count against the window until approved.
+        assert_eq!(store.spent_in_window("main", t0()), 0);
+
+        let approved = store
+            .approve(&KEY, &pending.id, &Approval::Confirm, t0())
+            .unwrap();
+        assert_eq!(approved.amount, 5_000);
+        assert!(store.pending().is_empty());
+        assert_eq!(store.spent_in_window("main", t0()), 5_000);
+        assert_eq!(
+            store.approve(&KEY, &pending.id, &Approval::Confirm, t0()),
+            Err(PolicyViolation::PendingNotFound(pending.id))
+        );
+    }
+
+    #[test]
+    fn cosigner_signature_approves_pending_spend() {
+        let secp = Secp256k1::new();
+        let cosigner = SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let cosigner_pub = PublicKey::from_secret_key(&secp, &cosigner);
+        let mut store = store_with(SpendingPolicy {
+            approval_threshold: Some(0),
+            cosigner_keys: vec![hex::encode(cosigner_pub.serialize())],
+            ..Default::default()
+        });
+        let pending = match store
+            .evaluate(&KEY, "main", &Destination::address("addr1"), 10, t0())
+            .unwrap()
+        {
+            PolicyDecision::PendingApproval(p) => p,
+            other => panic!("expected pending, got {:?}", other),
+        };
+
+        let msg = Message::from_digest_slice(&pending.approval_digest()).unwrap();
+        let stranger = SecretKey::from_slice(&[4u8; 32]).unwrap();
+        let bad_sig = secp.sign_ecdsa(&msg, &stranger);
+        let wrong = Approval::Cosigner {
+            public_key: hex::encode(cosigner_pub.serialize()),
+            signature: hex::encode(bad_sig.serialize_der()),
+        };
+        assert!(matches!(
+            store.approve(&KEY, &pending.id, &wrong, t0()),
+            Err(PolicyViolation::InvalidCosignerApproval(_))
+        ));
+
+        let sig = secp.sign_ecdsa(&msg, &cosigner);
+        let good = Approval::Cosigner {
+            public_key: hex::encode(cosigner_pub.serialize()),
+            signature: hex::encode(sig.serialize_der()),
+        };
+        assert!(store.approve(&KEY, &pending.id, &good, t0()).is_ok());
+    }
+}
@@ -6,6 +6,7 @@ use serde::{Deserialize, Serialize};
 use sha3::{Digest, Sha3_512};
 use std::fmt;
 use thiserror::Error;
+use supernova_core::script::classify::address_hrp;
 
 #[derive(Error, Debug)]
 pub enum AddressError {
@@ -58,8 +59,8 @@ impl Address {
         let mut pubkey_hash = [0u8; 32];
         pubkey_hash.copy_from_slice(&full_hash[..32]);
         
-        // Encode as Bech32 with 'nova' prefix
-        let address = bech32::encode("nova", pubkey_hash.to_base32(), Variant::Bech32m)
+        // Encode as Bech32 with the network's prefix
+        let address = bech32::encode(address_hrp(), pubkey_hash.to_base32(), Variant::Bech32m)
             .map_err(|e| AddressError::Bech32Error(e.to_string()))?;
         
         Ok(Self {
@@ -75,10 +76,10 @@ impl Address {
         let (hrp, data, variant) = bech32::decode(address)
             .map_err(|e| AddressError::Bech32Error(e.to_string()))?;
         
-        // Verify HRP is 'nova'
-        if hrp.as_str() != "nova" {
+        // Verify HRP matches the network
+        if hrp.as_str() != address_hrp() {
             return Err(AddressError::InvalidFormat(
-                format!("Invalid prefix: expected 'nova', got '{}'", hrp)
+                format!("Invalid prefix: expected '{}', got '{}'", address_hrp(), hrp)
             ));
         }
         
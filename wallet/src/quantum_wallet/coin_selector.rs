@@ -0,0 +1,497 @@
+// Privacy-Aware Coin Selection for Quantum Wallet
+// Groups wallet UTXOs into clusters the chain can already link together and
+// avoids spending across clusters, which would reveal common ownership
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::transaction_builder::TransactionError;
+use super::utxo_index::Utxo;
+
+/// Identifier of a UTXO cluster
+pub type ClusterId = u64;
+
+/// Linkage graph over the wallet's addresses and funding transactions.
+///
+/// Outputs received in the same transaction, outputs paid to the same
+/// address, and inputs spent together in one of our transactions are already
+/// linkable by an observer, so they share a cluster. Clusters only ever merge.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClusterMap {
+    /// Union-find parent links; a root points to itself
+    parent: HashMap<ClusterId, ClusterId>,
+
+    /// Cluster node for each address
+    by_address: HashMap<String, ClusterId>,
+
+    /// Cluster node for each funding transaction (hex txid)
+    by_txid: HashMap<String, ClusterId>,
+
+    next_id: ClusterId,
+}
+
+impl ClusterMap {
+    /// Create an empty cluster map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a received output: links its address with its funding transaction
+    pub fn observe_utxo(&mut self, utxo: &Utxo) {
+        let address_node = self.address_node(&utxo.address);
+        let tx_node = self.tx_node(&hex::encode(utxo.txid));
+        self.union(address_node, tx_node);
+    }
+
+    /// Record one of our transactions spending `inputs` together
+    pub fn observe_spend(&mut self, inputs: &[Utxo]) {
+        let mut first = None;
+        for utxo in inputs {
+            self.observe_utxo(utxo);
+            let node = self.address_node(&utxo.address);
+            match first {
+                None => first = Some(node),
+                Some(root) => self.union(root, node),
+            }
+        }
+    }
+
+    /// Cluster of a UTXO, registering it if it was never observed
+    pub fn cluster_of(&mut self, utxo: &Utxo) -> ClusterId {
+        self.observe_utxo(utxo);
+        let node = self.address_node(&utxo.address);
+        self.find(node)
+    }
+
+    /// Cluster of an address, if the address has been observed
+    pub fn cluster_of_address(&self, address: &str) -> Option<ClusterId> {
+        self.by_address.get(address).map(|&node| self.root(node))
+    }
+
+    /// Number of distinct clusters
+    pub fn cluster_count(&self) -> usize {
+        self.parent.iter().filter(|(node, parent)| node == parent).count()
+    }
+
+    /// Group UTXOs by cluster. Clusters are returned in id order and their
+    /// UTXOs largest first.
+    pub fn group(&mut self, utxos: &[Utxo]) -> BTreeMap<ClusterId, Vec<Utxo>> {
+        let mut groups: BTreeMap<ClusterId, Vec<Utxo>> = BTreeMap::new();
+        for utxo in utxos {
+            let cluster = self.cluster_of(utxo);
+            groups.entry(cluster).or_default().push(utxo.clone());
+        }
+        for group in groups.values_mut() {
+            group.sort_by(|a, b| b.value.cmp(&a.value));
+        }
+        groups
+    }
+
+    fn address_node(&mut self, address: &str) -> ClusterId {
+        if let Some(&node) = self.by_address.get(address) {
+            return node;
+        }
+        let node = self.make_node();
+        self.by_address.insert(address.to_string(), node);
+        node
+    }
+
+    fn tx_node(&mut self, txid: &str) -> ClusterId {
+        if let Some(&node) = self.by_txid.get(txid) {
+            return node;
+        }
+        let node = self.make_node();
+        self.by_txid.insert(txid.to_string(), node);
+        node
+    }
+
+    fn make_node(&mut self) -> ClusterId {
+        let node = self.next_id;
+        self.next_id += 1;
+        self.parent.insert(node, node);
+        node
+    }
+
+    /// Root lookup without path compression, for shared references
+    fn root(&self, mut node: ClusterId) -> ClusterId {
+        while let Some(&parent) = self.parent.get(&node) {
+            if parent == node {
+                break;
+            }
+            node = parent;
+        }
+        node
+    }
+
+    fn find(&mut self, node: ClusterId) -> ClusterId {
+        let root = self.root(node);
+        // Path compression
+        let mut current = node;
+        while current != root {
+            let next = self.parent.get(&current).copied().unwrap_or(root);
+            self.parent.insert(current, root);
+            current = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: ClusterId, b: ClusterId) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            // Keep the older id so cluster ids stay stable as the graph grows
+            let (keep, merge) = if ra < rb { (ra, rb) } else { (rb, ra) };
+            self.parent.insert(merge, keep);
+        }
+    }
+}
+
+/// Privacy problem a selection could not avoid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrivacyWarning {
+    /// No single cluster could fund the payment, so spending it links these
+    /// clusters on-chain
+    ClustersMerged {
+        clusters: Vec<ClusterId>,
+        /// Largest amount any single cluster could have funded
+        largest_cluster_value: u64,
+    },
+}
+
+/// Result of a privacy-aware selection
+#[derive(Debug, Clone)]
+pub struct PrivacySelection {
+    /// Selected UTXOs
+    pub utxos: Vec<Utxo>,
+
+    /// Clusters the selected UTXOs belong to
+    pub clusters: Vec<ClusterId>,
+
+    /// Set when the selection merges clusters
+    pub warning: Option<PrivacyWarning>,
+}
+
+impl PrivacySelection {
+    /// Total value of the selected UTXOs
+    pub fn total(&self) -> u64 {
+        self.utxos.iter().map(|u| u.value).sum()
+    }
+}
+
+/// One transaction of a per-cluster split payment
+#[derive(Debug, Clone)]
+pub struct ClusterSpend {
+    pub cluster: ClusterId,
+
+    /// Inputs, all from `cluster`
+    pub utxos: Vec<Utxo>,
+
+    /// Share of the payment this transaction sends
+    pub amount: u64,
+
+    /// Fee this transaction pays
+    pub fee: u64,
+}
+
+/// Privacy-aware coin selector
+pub struct CoinSelector {
+    /// Outputs below this value are not worth sending on their own
+    pub dust_threshold: u64,
+}
+
+impl CoinSelector {
+    /// Create a selector with the given dust threshold
+    pub fn new(dust_threshold: u64) -> Self {
+        Self { dust_threshold }
+    }
+
+    /// Select UTXOs worth at least `target`, from a single cluster when possible.
+    ///
+    /// Among clusters that can fund the target alone, the one with the smallest
+    /// total is used, leaving larger clusters untouched. Otherwise whole
+    /// clusters are added largest first and the result carries a
+    /// [`PrivacyWarning::ClustersMerged`].
+    pub fn select(
+        &self,
+        utxos: &[Utxo],
+        target: u64,
+        clusters: &mut ClusterMap,
+    ) -> Result<PrivacySelection, TransactionError> {
+        let groups = spendable_groups(utxos, clusters);
+        if groups.is_empty() {
+            return Err(TransactionError::NoUtxos);
+        }
+
+        let single = groups
+            .iter()
+            .filter(|(_, group)| group_value(group) >= target)
+            .min_by_key(|(_, group)| group_value(group));
+        if let Some((&cluster, group)) = single {
+            return Ok(PrivacySelection {
+                utxos: take_until(group, target),
+                clusters: vec![cluster],
+                warning: None,
+            });
+        }
+
+        let mut by_value: Vec<(&ClusterId, &Vec<Utxo>)> = groups.iter().collect();
+        by_value.sort_by(|a, b| group_value(b.1).cmp(&group_value(a.1)).then(a.0.cmp(b.0)));
+        let largest_cluster_value = by_value.first().map(|(_, g)| group_value(g)).unwrap_or(0);
+
+        let mut selection = PrivacySelection {
+            utxos: Vec::new(),
+            clusters: Vec::new(),
+            warning: None,
+        };
+        let mut total = 0u64;
+        for (&cluster, group) in by_value {
+            let remaining = target - total;
+            let taken = take_until(group, remaining);
+            total = total.saturating_add(group_value(&taken));
+            selection.utxos.extend(taken);
+            selection.clusters.push(cluster);
+            if total >= target {
+                selection.warning = Some(PrivacyWarning::ClustersMerged {
+                    clusters: selection.clusters.clone(),
+                    largest_cluster_value,
+                });
+                return Ok(selection);
+            }
+        }
+
+        Err(TransactionError::InsufficientFunds {
+            needed: target,
+            available: total,
+        })
+    }
+
+    /// Split a payment of `amount` into one transaction per cluster so no
+    /// transaction merges clusters.
+    ///
+    /// `fee_for_inputs` gives the fee of a transaction with that many inputs.
+    /// Clusters are drained largest first; a cluster whose share would fall
+    /// below the dust threshold is skipped. Fails with `InsufficientFunds` if
+    /// the clusters cannot cover the amount plus every transaction's fee.
+    pub fn split_by_cluster(
+        &self,
+        utxos: &[Utxo],
+        amount: u64,
+        clusters: &mut ClusterMap,
+        fee_for_inputs: impl Fn(usize) -> u64,
+    ) -> Result<Vec<ClusterSpend>, TransactionError> {
+        if amount == 0 {
+            return Err(TransactionError::InvalidAmount("Amount must be positive".to_string()));
+        }
+        let groups = spendable_groups(utxos, clusters);
+        if groups.is_empty() {
+            return Err(TransactionError::NoUtxos);
+        }
+
+        let mut by_value: Vec<(ClusterId, Vec<Utxo>)> = groups.into_iter().collect();
+        by_value.sort_by(|a, b| group_value(&b.1).cmp(&group_value(&a.1)).then(a.0.cmp(&b.0)));
+
+        let mut spends = Vec::new();
+        let mut remaining = amount;
+        let mut available = 0u64;
+        for (cluster, group) in by_value {
+            if remaining == 0 {
+                break;
+            }
+            let all_fee = fee_for_inputs(group.len());
+            let capacity = group_value(&group).saturating_sub(all_fee);
+            available = available.saturating_add(capacity);
+            let share = remaining.min(capacity);
+            if share == 0 || (share < self.dust_threshold && share < remaining) {
+                continue;
+            }
+
+            // Use only as many of the cluster's inputs as the share needs
+            let mut inputs = Vec::new();
+            let mut total = 0u64;
+            for utxo in group {
+                total = total.saturating_add(utxo.value);
+                inputs.push(utxo);
+                if total >= share.saturating_add(fee_for_inputs(inputs.len())) {
+                    break;
+                }
+            }
+            let fee = fee_for_inputs(inputs.len());
+
+            spends.push(ClusterSpend {
+                cluster,
+                utxos: inputs,
+                amount: share,
+                fee,
+            });
+            remaining -= share;
+        }
+
+        if remaining > 0 {
+            return Err(TransactionError::InsufficientFunds {
+                needed: amount,
+                available,
+            });
+        }
+        Ok(spends)
+    }
+}
+
+fn spendable_groups(utxos: &[Utxo], clusters: &mut ClusterMap) -> BTreeMap<ClusterId, Vec<Utxo>> {
+    let spendable: Vec<Utxo> = utxos
+        .iter()
+        .filter(|u| u.spendable && u.solvable)
+        .cloned()
+        .collect();
+    clusters.group(&spendable)
+}
+
+fn group_value(group: &[Utxo]) -> u64 {
+    group.iter().fold(0u64, |acc, u| acc.saturating_add(u.value))
+}
+
+/// Largest-first prefix of `group` worth at least `target` (or all of it)
+fn take_until(group: &[Utxo], target: u64) -> Vec<Utxo> {
+    let mut taken = Vec::new();
+    let mut total = 0u64;
+    for utxo in group {
+        if total >= target {
+            break;
+        }
+        total = total.saturating_add(utxo.value);
+        taken.push(utxo.clone());
+    }
+    taken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utxo(txid_byte: u8, vout: u32, address: &str, value: u64) -> Utxo {
+        let mut txid = [0u8; 32];
+        txid[0] = txid_byte;
+
+        Utxo {
+            txid,
+            vout,
+            address: address.to_string(),
+            value,
+            script_pubkey: vec![],
+            block_height: 100,
+            confirmations: 10,
+            spendable: true,
+            solvable: true,
+            label: None,
+        }
+    }
+
+    /// Three clusters: {a1, a2} via a shared funding tx, {b} and {c1, c2}
+    /// via a shared address.
+    fn three_clusters() -> (Vec<Utxo>, ClusterMap) {
+        let utxos = vec![
+            utxo(1, 0, "nova1a1", 300_000),
+            utxo(1, 1, "nova1a2", 200_000),
+            utxo(2, 0, "nova1b", 400_000),
+            utxo(3, 0, "nova1c", 100_000),
+            utxo(4, 0, "nova1c", 150_000),
+        ];
+        let mut clusters = ClusterMap::new();
+        for u in &utxos {
+            clusters.observe_utxo(u);
+        }
+        (utxos, clusters)
+    }
+
+    #[test]
+    fn test_clusters_follow_address_and_tx_links() {
+        let (utxos, mut clusters) = three_clusters();
+        assert_eq!(clusters.cluster_count(), 3);
+        assert_eq!(clusters.cluster_of(&utxos[0]), clusters.cluster_of(&utxos[1]));
+        assert_eq!(clusters.cluster_of(&utxos[3]), clusters.cluster_of(&utxos[4]));
+        assert_ne!(clusters.cluster_of(&utxos[0]), clusters.cluster_of(&utxos[2]));
+
+        // Spending b and c together links them from then on
+        clusters.observe_spend(&[utxos[2].clone(), utxos[3].clone()]);
+        assert_eq!(clusters.cluster_count(), 2);
+        assert_eq!(
+            clusters.cluster_of_address("nova1b"),
+            clusters.cluster_of_address("nova1c")
+        );
+    }
+
+    #[test]
+    fn test_selection_avoids_merging_when_possible() {
+        let (utxos, mut clusters) = three_clusters();
+        let selector = CoinSelector::new(546);
+
+        // 350k fits in cluster b (400k) or a (500k); the smaller one is used
+        let selection = selector.select(&utxos, 350_000, &mut clusters).unwrap();
+        assert!(selection.warning.is_none());
+        assert_eq!(selection.clusters.len(), 1);
+        assert_eq!(selection.utxos.len(), 1);
+        assert_eq!(selection.utxos[0].address, "nova1b");
+
+        // 450k only fits in cluster a, which needs both of its outputs
+        let selection = selector.select(&utxos, 450_000, &mut clusters).unwrap();
+        assert!(selection.warning.is_none());
+        assert_eq!(selection.total(), 500_000);
+        let cluster = clusters.cluster_of(&utxos[0]);
+        assert_eq!(selection.clusters, vec![cluster]);
+    }
+
+    #[test]
+    fn test_merge_warning_when_amount_forces_it() {
+        let (utxos, mut clusters) = three_clusters();
+        let selector = CoinSelector::new(546);
+
+        let selection = selector.select(&utxos, 800_000, &mut clusters).unwrap();
+        assert!(selection.total() >= 800_000);
+        match selection.warning {
+            Some(PrivacyWarning::ClustersMerged { clusters: merged, largest_cluster_value }) => {
+                assert_eq!(merged.len(), 2);
+                assert_eq!(largest_cluster_value, 500_000);
+            }
+            None => panic!("expected a merge warning"),
+        }
+
+        assert!(matches!(
+            selector.select(&utxos, 2_000_000, &mut clusters),
+            Err(TransactionError::InsufficientFunds { needed: 2_000_000, available: 1_150_000 })
+        ));
+    }
+
+    #[test]
+    fn test_split_by_cluster_totals() {
+        let (utxos, mut clusters) = three_clusters();
+        let selector = CoinSelector::new(546);
+        let fee = |inputs: usize| 1_000 * inputs as u64;
+
+        let spends = selector.split_by_cluster(&utxos, 800_000, &mut clusters, fee).unwrap();
+        assert_eq!(spends.iter().map(|s| s.amount).sum::<u64>(), 800_000);
+
+        let mut seen = Vec::new();
+        for spend in &spends {
+            // Each transaction stays inside one cluster and pays its own fee
+            assert!(!seen.contains(&spend.cluster));
+            seen.push(spend.cluster);
+            for u in &spend.utxos {
+                assert_eq!(clusters.cluster_of(u), spend.cluster);
+            }
+            let inputs: u64 = spend.utxos.iter().map(|u| u.value).sum();
+            assert_eq!(spend.fee, fee(spend.utxos.len()));
+            assert!(inputs >= spend.amount + spend.fee);
+        }
+
+        // All three clusters together hold 1,150,000 minus 5 inputs of fees
+        assert!(matches!(
+            selector.split_by_cluster(&utxos, 1_146_000, &mut clusters, fee),
+            Err(TransactionError::InsufficientFunds { .. })
+        ));
+        assert_eq!(
+            selector
+                .split_by_cluster(&utxos, 1_145_000, &mut clusters, fee)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+}
@@ -13,6 +13,11 @@ use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
+use supernova_core::crypto::hybrid::{sign_hybrid, verify_hybrid, HybridPolicy, HybridPublicKey};
+use supernova_core::crypto::quantum::{
+    ClassicalScheme, QuantumKeyPair, QuantumParameters, QuantumScheme,
+};
+use supernova_core::types::transaction::SignatureSchemeType;
 use thiserror::Error;
 use zeroize::Zeroize;
 
@@ -52,6 +57,9 @@ pub enum KeystoreError {
     #[error("Keypair generation failed: {0}")]
     GenerationFailed(String),
     
+    #[error("Signing failed: {0}")]
+    SigningFailed(String),
+    
     #[error("Encryption error: {0}")]
     EncryptionError(String),
     
@@ -59,12 +67,20 @@ pub enum KeystoreError {
     SerializationError(String),
 }
 
+/// Security level of every keystore key: ML-DSA is Dilithium5
+pub const SIGNATURE_SECURITY_LEVEL: u8 = 5;
+
 /// Quantum-resistant keypair
+///
+/// A hybrid keypair holds the combined classical and ML-DSA keys in the
+/// layout of [`supernova_core::crypto::hybrid`] and signs with both. Plain
+/// ML-DSA public keys are exactly `dilithium5::public_key_bytes()` long, so
+/// the two are told apart by length and stored keypairs keep their format.
 #[derive(Clone, Serialize, Deserialize)]
 pub struct KeyPair {
-    /// ML-DSA public key (~1952 bytes for Dilithium5)
+    /// ML-DSA public key (2592 bytes for Dilithium5), or the hybrid public key
     pub public_key: Vec<u8>,
-    /// ML-DSA secret key (~4864 bytes for Dilithium5)
+    /// ML-DSA secret key (4864 bytes for Dilithium5), or the hybrid secret key
     pub secret_key: Vec<u8>,
     /// Derived address
     pub address: Address,
@@ -98,9 +114,28 @@ impl KeyPair {
         // Generate ML-DSA (Dilithium5) keypair for maximum security
         let (pk, sk) = dilithium5::keypair();
         
-        let public_key = pk.as_bytes().to_vec();
-        let secret_key = sk.as_bytes().to_vec();
-        
+        Self::from_keys(pk.as_bytes().to_vec(), sk.as_bytes().to_vec(), label)
+    }
+    
+    /// Generate a hybrid keypair pairing a `classical` key with an ML-DSA key
+    pub fn generate_hybrid(
+        label: Option<String>,
+        classical: ClassicalScheme,
+    ) -> Result<Self, KeystoreError> {
+        let hybrid = QuantumKeyPair::generate(QuantumParameters::with_security_level(
+            QuantumScheme::Hybrid(classical),
+            SIGNATURE_SECURITY_LEVEL,
+        ))
+        .map_err(|e| KeystoreError::GenerationFailed(e.to_string()))?;
+        
+        Self::from_keys(hybrid.public_key.clone(), hybrid.secret_key.clone(), label)
+    }
+    
+    fn from_keys(
+        public_key: Vec<u8>,
+        secret_key: Vec<u8>,
+        label: Option<String>,
+    ) -> Result<Self, KeystoreError> {
         // Derive address from public key using SHA3-512
         let address = Address::from_public_key(&public_key)
             .map_err(|e| KeystoreError::GenerationFailed(e.to_string()))?;
@@ -119,8 +154,40 @@ impl KeyPair {
         })
     }
     
-    /// Sign a message using ML-DSA
+    /// Classical scheme of a hybrid keypair, `None` for plain ML-DSA
+    pub fn hybrid_scheme(&self) -> Option<ClassicalScheme> {
+        if self.public_key.len() == dilithium5::public_key_bytes() {
+            return None;
+        }
+        HybridPublicKey::from_bytes(&self.public_key)
+            .ok()
+            .map(|key| key.classical_scheme)
+    }
+    
+    /// Scheme of the signatures [`sign`](Self::sign) produces
+    pub fn signature_scheme(&self) -> SignatureSchemeType {
+        match self.hybrid_scheme() {
+            Some(_) => SignatureSchemeType::Hybrid,
+            None => SignatureSchemeType::Dilithium,
+        }
+    }
+    
+    /// Sign a message using ML-DSA, plus the classical key of a hybrid keypair
     pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, KeystoreError> {
+        if let Some(classical) = self.hybrid_scheme() {
+            let hybrid = QuantumKeyPair {
+                public_key: self.public_key.clone(),
+                secret_key: self.secret_key.clone(),
+                parameters: QuantumParameters::with_security_level(
+                    QuantumScheme::Hybrid(classical),
+                    SIGNATURE_SECURITY_LEVEL,
+                ),
+            };
+            return sign_hybrid(&hybrid, message)
+                .map(|signature| signature.to_bytes())
+                .map_err(|e| KeystoreError::SigningFailed(e.to_string()));
+        }
+        
         // Reconstruct secret key from bytes
         let sk = dilithium5::SecretKey::from_bytes(&self.secret_key)
             .map_err(|_| KeystoreError::GenerationFailed("Invalid secret key".to_string()))?;
@@ -131,8 +198,19 @@ impl KeyPair {
         Ok(signature.as_bytes().to_vec())
     }
     
-    /// Verify a signature
+    /// Verify a signature; hybrid signatures need both halves to verify
     pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        if public_key.len() != dilithium5::public_key_bytes() {
+            return verify_hybrid(
+                public_key,
+                message,
+                signature,
+                SIGNATURE_SECURITY_LEVEL,
+                HybridPolicy::RequireBoth,
+            )
+            .unwrap_or(false);
+        }
+        
         // Reconstruct public key
         let pk = match dilithium5::PublicKey::from_bytes(public_key) {
             Ok(pk) => pk,
@@ -288,6 +366,25 @@ impl Keystore {
         
         // Generate new keypair
         let keypair = KeyPair::generate(label)?;
+        self.insert_keypair(keypair)
+    }
+    
+    /// Generate a new address whose spends carry hybrid `classical` and
+    /// ML-DSA signatures
+    pub fn generate_hybrid_address(
+        &self,
+        label: Option<String>,
+        classical: ClassicalScheme,
+    ) -> Result<Address, KeystoreError> {
+        if self.is_locked() {
+            return Err(KeystoreError::Locked);
+        }
+        
+        let keypair = KeyPair::generate_hybrid(label, classical)?;
+        self.insert_keypair(keypair)
+    }
+    
+    fn insert_keypair(&self, keypair: KeyPair) -> Result<Address, KeystoreError> {
         let address = keypair.address.clone();
         
         // Store keypair
@@ -425,6 +522,28 @@ mod tests {
         assert!(!KeyPair::verify(&other_keypair.public_key, message, &signature));
     }
     
+    #[test]
+    fn test_hybrid_keypair_signs_with_both_keys() {
+        for classical in [ClassicalScheme::Secp256k1, ClassicalScheme::Ed25519] {
+            let keypair = KeyPair::generate_hybrid(None, classical).unwrap();
+            assert_eq!(keypair.hybrid_scheme(), Some(classical));
+            assert_eq!(keypair.signature_scheme(), SignatureSchemeType::Hybrid);
+            assert_eq!(
+                keypair.address,
+                Address::from_public_key(&keypair.public_key).unwrap()
+            );
+            
+            let message = b"Test message for signing";
+            let signature = keypair.sign(message).unwrap();
+            assert!(KeyPair::verify(&keypair.public_key, message, &signature));
+            assert!(!KeyPair::verify(&keypair.public_key, b"wrong message", &signature));
+        }
+        
+        let plain = KeyPair::generate(None).unwrap();
+        assert_eq!(plain.hybrid_scheme(), None);
+        assert_eq!(plain.signature_scheme(), SignatureSchemeType::Dilithium);
+    }
+    
     #[test]
     fn test_keystore_locking() {
         let mut keystore = Keystore::new();
@@ -5,6 +5,7 @@ pub mod keystore;
 pub mod storage;
 pub mod utxo_index;
 pub mod transaction_builder;
+pub mod coin_selector;
 pub mod address;
 pub mod hd_derivation;  // Quantum HD key derivation
 
@@ -12,7 +13,11 @@ pub mod hd_derivation;  // Quantum HD key derivation
 pub use keystore::{Keystore, KeyPair, KeystoreError};
 pub use storage::{WalletStorage, StorageError};
 pub use utxo_index::{UtxoIndex, Utxo, UtxoError};
-pub use transaction_builder::{TransactionBuilder, TransactionError, BuilderConfig, CoinSelectionStrategy};
+pub use transaction_builder::{
+    signals_replaceability, BuilderConfig, BumpCandidate, CoinSelectionStrategy, FeeBump,
+    TransactionBuilder, TransactionError, MAX_REPLACEABLE_SEQUENCE,
+};
+pub use coin_selector::{ClusterId, ClusterMap, ClusterSpend, CoinSelector, PrivacySelection, PrivacyWarning};
 pub use address::{Address, AddressType, AddressError};
 pub use hd_derivation::{QuantumHDDerivation, QuantumHDConfig, HDDerivationError};
 
@@ -14,6 +14,7 @@ use std::sync::Arc;
 use thiserror::Error;
 use zeroize::Zeroize;
 
+use super::coin_selector::ClusterMap;
 use super::keystore::KeyPair;
 use super::utxo_index::Utxo;
 
@@ -252,6 +253,28 @@ impl WalletStorage {
         Ok(utxos)
     }
     
+    /// Store privacy cluster assignments
+    pub fn store_clusters(&self, clusters: &ClusterMap) -> Result<(), StorageError> {
+        self.db.insert(
+            b"__clusters__",
+            bincode::serialize(clusters)
+                .map_err(|e| StorageError::SerializationError(e.to_string()))?
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        
+        Ok(())
+    }
+    
+    /// Load privacy cluster assignments; empty if none were stored yet
+    pub fn load_clusters(&self) -> Result<ClusterMap, StorageError> {
+        match self.db.get(b"__clusters__")
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+        {
+            Some(bytes) => bincode::deserialize(&bytes)
+                .map_err(|e| StorageError::SerializationError(e.to_string())),
+            None => Ok(ClusterMap::new()),
+        }
+    }
+    
     /// Store wallet metadata
     pub fn store_metadata(&self, metadata: &WalletMetadata) -> Result<(), StorageError> {
         self.db.insert(
@@ -542,5 +565,39 @@ mod tests {
         let dec = storage.decrypt_data(&enc).unwrap();
         assert_eq!(dec, plaintext);
     }
+    
+    #[test]
+    fn test_cluster_persistence() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("wallet.db");
+        let utxo = Utxo {
+            txid: [7u8; 32],
+            vout: 0,
+            address: "nova1qcluster".to_string(),
+            value: 1000,
+            script_pubkey: vec![],
+            block_height: 1,
+            confirmations: 1,
+            spendable: true,
+            solvable: true,
+            label: None,
+        };
+        
+        {
+            let storage = WalletStorage::open(&path).unwrap();
+            assert_eq!(storage.load_clusters().unwrap().cluster_count(), 0);
+            
+            let mut clusters = ClusterMap::new();
+            clusters.observe_utxo(&utxo);
+            storage.store_clusters(&clusters).unwrap();
+            storage.flush().unwrap();
+        }
+        
+        let storage = WalletStorage::open(&path).unwrap();
+        let mut clusters = storage.load_clusters().unwrap();
+        let expected = clusters.cluster_of_address("nova1qcluster");
+        assert!(expected.is_some());
+        assert_eq!(Some(clusters.cluster_of(&utxo)), expected);
+        assert_eq!(clusters.cluster_count(), 1);
+    }
 }
-
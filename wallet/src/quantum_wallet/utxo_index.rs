@@ -6,6 +6,8 @@ use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use thiserror::Error;
 
+use super::coin_selector::ClusterMap;
+
 #[derive(Error, Debug)]
 pub enum UtxoError {
     #[error("UTXO not found: {txid}:{vout}")]
@@ -86,6 +88,9 @@ pub struct UtxoIndex {
     
     /// Current blockchain height
     current_height: Arc<RwLock<u64>>,
+    
+    /// Privacy clusters, updated as outputs arrive and are spent
+    clusters: Arc<RwLock<ClusterMap>>,
 }
 
 impl UtxoIndex {
@@ -97,6 +102,7 @@ impl UtxoIndex {
             spent: Arc::new(RwLock::new(HashSet::new())),
             pending: Arc::new(RwLock::new(HashMap::new())),
             current_height: Arc::new(RwLock::new(0)),
+            clusters: Arc::new(RwLock::new(ClusterMap::new())),
         }
     }
     
@@ -112,6 +118,10 @@ impl UtxoIndex {
             utxo.confirmations = current_height.saturating_sub(utxo.block_height) + 1;
         }
         
+        self.clusters.write()
+            .map_err(|e| UtxoError::LockPoisoned(e.to_string()))?
+            .observe_utxo(&utxo);
+        
         // Add to address index
         self.utxos_by_address.write()
             .map_err(|e| UtxoError::LockPoisoned(e.to_string()))?
@@ -302,6 +312,10 @@ impl UtxoIndex {
     pub fn add_pending(&self, utxo: Utxo) -> Result<(), UtxoError> {
         let outpoint = utxo.outpoint();
         
+        self.clusters.write()
+            .map_err(|e| UtxoError::LockPoisoned(e.to_string()))?
+            .observe_utxo(&utxo);
+        
         self.pending.write()
             .map_err(|e| UtxoError::LockPoisoned(e.to_string()))?
             .insert(outpoint, utxo);
@@ -362,6 +376,29 @@ impl UtxoIndex {
             .unwrap_or(0)
     }
     
+    /// Record that our own transaction spent `inputs` together, linking
+    /// their clusters
+    pub fn record_spend(&self, inputs: &[Utxo]) -> Result<(), UtxoError> {
+        self.clusters.write()
+            .map_err(|e| UtxoError::LockPoisoned(e.to_string()))?
+            .observe_spend(inputs);
+        Ok(())
+    }
+    
+    /// Snapshot of the privacy clusters, for selection or persistence
+    pub fn clusters(&self) -> Result<ClusterMap, UtxoError> {
+        Ok(self.clusters.read()
+            .map_err(|e| UtxoError::LockPoisoned(e.to_string()))?
+            .clone())
+    }
+    
+    /// Replace the privacy clusters with a persisted map
+    pub fn restore_clusters(&self, clusters: ClusterMap) -> Result<(), UtxoError> {
+        *self.clusters.write()
+            .map_err(|e| UtxoError::LockPoisoned(e.to_string()))? = clusters;
+        Ok(())
+    }
+    
     /// Get total number of addresses being tracked
     pub fn total_addresses(&self) -> usize {
         self.utxos_by_address.read()
@@ -514,5 +551,33 @@ mod tests {
         assert_eq!(utxo.block_height, 150);
         assert!(utxo.confirmations >= 1);
     }
+    
+    #[test]
+    fn test_clusters_update_as_utxos_arrive() {
+        let index = UtxoIndex::new();
+        let mut first = create_test_utxo("nova1qtest123", 1000000, 100);
+        first.txid[1] = 1;
+        let mut second = create_test_utxo("nova1qtest456", 2000000, 100);
+        second.txid[1] = 2;
+        
+        index.add_utxo(first.clone()).unwrap();
+        index.add_utxo(second.clone()).unwrap();
+        assert_eq!(index.clusters().unwrap().cluster_count(), 2);
+        
+        // A later output paying both addresses in one transaction links them
+        let mut joint = create_test_utxo("nova1qtest123", 500000, 101);
+        joint.txid[1] = 3;
+        let mut joint_other = joint.clone();
+        joint_other.vout = 1;
+        joint_other.address = "nova1qtest456".to_string();
+        index.add_utxo(joint).unwrap();
+        index.add_pending(joint_other).unwrap();
+        
+        let clusters = index.clusters().unwrap();
+        assert_eq!(clusters.cluster_count(), 1);
+        assert_eq!(
+            clusters.cluster_of_address("nova1qtest123"),
+            clusters.cluster_of_address("nova1qtest456")
+        );
+    }
 }
-
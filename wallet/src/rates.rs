@@ -0,0 +1,307 @@
+//! Fiat exchange rates for display.
+//!
+//! Rates are strictly a presentation aid: they are fetched on demand for the
+//! balance and history views and must never feed into fee estimation, coin
+//! selection or any other part of transaction construction. Every provider is
+//! optional; when none is configured, or it is unreachable, fiat values are
+//! rendered as "—".
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RateError {
+    #[error("HTTP error: {0}")]
+    Http(String),
+    #[error("Malformed rate response: {0}")]
+    Malformed(String),
+    #[error("No rate available for {0}")]
+    Unavailable(String),
+}
+
+/// Price of one NOVA in a fiat currency at a point in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FiatRate {
+    /// ISO 4217 code, upper case.
+    pub currency: String,
+    pub price_per_nova: f64,
+    /// When the rate was quoted by the source.
+    pub as_of: DateTime<Utc>,
+}
+
+/// Source of fiat rates.
+pub trait RateProvider: Send + Sync {
+    fn fetch_rate(&self, currency: &str) -> Result<FiatRate, RateError>;
+}
+
+impl<P: RateProvider + ?Sized> RateProvider for Box<P> {
+    fn fetch_rate(&self, currency: &str) -> Result<FiatRate, RateError> {
+        (**self).fetch_rate(currency)
+    }
+}
+
+/// A constant rate. Used in tests and for operators who want a manual rate.
+#[derive(Debug, Clone)]
+pub struct FixedRateProvider {
+    pub price_per_nova: f64,
+    pub as_of: DateTime<Utc>,
+}
+
+impl FixedRateProvider {
+    pub fn new(price_per_nova: f64) -> Self {
+        Self {
+            price_per_nova,
+            as_of: Utc::now(),
+        }
+    }
+}
+
+impl RateProvider for FixedRateProvider {
+    fn fetch_rate(&self, currency: &str) -> Result<FiatRate, RateError> {
+        Ok(FiatRate {
+            currency: currency.to_uppercase(),
+            price_per_nova: self.price_per_nova,
+            as_of: self.as_of,
+        })
+    }
+}
+
+/// Response body expected from the HTTP rate endpoint.
+#[derive(Debug, Deserialize)]
+struct HttpRateResponse {
+    rate: f64,
+    /// Unix seconds; defaults to the time of the fetch when omitted.
+    #[serde(default)]
+    timestamp: Option<i64>,
+}
+
+/// Fetches rates from a JSON HTTP endpoint. `{currency}` in the endpoint is
+/// replaced with the upper-case currency code; otherwise `?currency=XXX` is
+/// appended. The endpoint must return `{"rate": <f64>, "timestamp": <unix>}`.
+#[derive(Debug, Clone)]
+pub struct HttpRateProvider {
+    endpoint: String,
+    timeout: Duration,
+}
+
+impl HttpRateProvider {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn url_for(&self, currency: &str) -> String {
+        let currency = currency.to_uppercase();
+        if self.endpoint.contains("{currency}") {
+            self.endpoint.replace("{currency}", &currency)
+        } else if self.endpoint.contains('?') {
+            format!("{}&currency={}", self.endpoint, currency)
+        } else {
+            format!("{}?currency={}", self.endpoint, currency)
+        }
+    }
+}
+
+impl RateProvider for HttpRateProvider {
+    fn fetch_rate(&self, currency: &str) -> Result<FiatRate, RateError> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| RateError::Http(e.to_string()))?;
+        let response = client
+            .get(self.url_for(currency))
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| RateError::Http(e.to_string()))?;
+        let body: HttpRateResponse = response
+            .json()
+            .map_err(|e| RateError::Malformed(e.to_string()))?;
+        if !body.rate.is_finite() || body.rate < 0.0 {
+            return Err(RateError::Malformed(format!("invalid rate {}", body.rate)));
+        }
+        let as_of = body
+            .timestamp
+            .and_then(|t| DateTime::from_timestamp(t, 0))
+            .unwrap_or_else(Utc::now);
+        Ok(FiatRate {
+            currency: currency.to_uppercase(),
+            price_per_nova: body.rate,
+            as_of,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    rate: Option<FiatRate>,
+    fetched_at: Instant,
+}
+
+/// Caches a provider's rates for a TTL. A failed fetch is also remembered for
+/// the TTL, so an offline provider is not retried on every redraw; during that
+/// time the rate is reported as unavailable rather than served stale.
+pub struct CachedRateProvider<P> {
+    inner: P,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl<P: RateProvider> CachedRateProvider<P> {
+    pub fn new(inner: P, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Current rate for `currency`, or `None` if the provider is unavailable.
+    pub fn rate(&self, currency: &str) -> Option<FiatRate> {
+        self.rate_at(currency, Instant::now())
+    }
+
+    pub fn rate_at(&self, currency: &str, now: Instant) -> Option<FiatRate> {
+        let key = currency.to_uppercase();
+        let mut cache = self.cache.lock().ok()?;
+        if let Some(entry) = cache.get(&key) {
+            if now.saturating_duration_since(entry.fetched_at) < self.ttl {
+                return entry.rate.clone();
+            }
+        }
+        let rate = match self.inner.fetch_rate(&key) {
+            Ok(rate) => Some(rate),
+            Err(e) => {
+                tracing::debug!("Fiat rate for {} unavailable: {}", key, e);
+                None
+            }
+        };
+        cache.insert(
+            key,
+            CacheEntry {
+                rate: rate.clone(),
+                fetched_at: now,
+            },
+        );
+        rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+        fail: bool,
+    }
+
+    impl RateProvider for CountingProvider {
+        fn fetch_rate(&self, currency: &str) -> Result<FiatRate, RateError> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(RateError::Http("offline".to_string()));
+            }
+            Ok(FiatRate {
+                currency: currency.to_string(),
+                price_per_nova: 1.0 + n as f64,
+                as_of: Utc::now(),
+            })
+        }
+    }
+
+    #[test]
+    fn cache_serves_within_ttl_and_refreshes_after() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachedRateProvider::new(
+            CountingProvider {
+                calls: Arc::clone(&calls),
+                fail: false,
+            },
+            Duration::from_secs(60),
+        );
+        let t0 = Instant::now();
+
+        let first = provider.rate_at("usd", t0).unwrap();
+        assert_eq!(first.price_per_nova, 1.0);
+        assert_eq!(first.currency, "USD");
+        let cached = provider.rate_at("USD", t0 + Duration::from_secs(59)).unwrap();
+        assert_eq!(cached.price_per_nova, 1.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let refreshed = provider.rate_at("USD", t0 + Duration::from_secs(60)).unwrap();
+        assert_eq!(refreshed.price_per_nova, 2.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn failing_provider_degrades_to_none_without_hammering() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachedRateProvider::new(
+            CountingProvider {
+                calls: Arc::clone(&calls),
+                fail: true,
+            },
+            Duration::from_secs(60),
+        );
+        let t0 = Instant::now();
+        assert!(provider.rate_at("USD", t0).is_none());
+        assert!(provider.rate_at("USD", t0 + Duration::from_secs(1)).is_none());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn unreachable_http_endpoint_is_an_error_not_a_panic() {
+        let provider = HttpRateProvider::new("http://127.0.0.1:9/rate")
+            .with_timeout(Duration::from_millis(200));
+        assert!(provider.fetch_rate("USD").is_err());
+        assert_eq!(
+            provider.url_for("eur"),
+            "http://127.0.0.1:9/rate?currency=EUR"
+        );
+        assert_eq!(
+            HttpRateProvider::new("https://x/{currency}/nova").url_for("usd"),
+            "https://x/USD/nova"
+        );
+    }
+
+    /// Rates are display-only: no code on the transaction construction path
+    /// may reach for a rate provider.
+    #[test]
+    fn send_path_does_not_reference_rate_provider() {
+        let send_path = [
+            ("core.rs", include_str!("core.rs")),
+            ("policy.rs", include_str!("policy.rs")),
+            (
+                "quantum_wallet/transaction_builder.rs",
+                include_str!("quantum_wallet/transaction_builder.rs"),
+            ),
+            (
+                "quantum_wallet/utxo_index.rs",
+                include_str!("quantum_wallet/utxo_index.rs"),
+            ),
+        ];
+        for (file, source) in send_path {
+            for needle in ["RateProvider", "FiatRate", "rates::"] {
+                assert!(
+                    !source.contains(needle),
+                    "{} references `{}`; fiat rates must stay out of the send path",
+                    file,
+                    needle
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! Rebuilding balances and history from the chain.
+//!
+//! A wallet restored from its mnemonic, or one that lost `history.json`,
+//! knows its accounts but none of their funds. [`WalletManager::rescan`]
+//! walks the best chain through a [`ChainQuery`] and rebuilds the UTXO cache
+//! and the history from the transactions paying or spending the wallet's
+//! addresses. Addresses are derived ahead of the last used one up to the gap
+//! limit, and further whenever the scan finds activity near the end.
+
+use crate::history::{TransactionDirection, TransactionRecord, TransactionStatus};
+use crate::{WalletError, WalletManager};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use supernova_core::storage::utxo_set::UtxoEntry;
+use supernova_core::types::block::Block;
+use supernova_core::types::transaction::{OutPoint, Transaction};
+
+/// Read access to the best chain, from a node or a test fixture
+pub trait ChainQuery {
+    /// Height of the best block
+    fn tip_height(&self) -> Result<u64, String>;
+
+    /// Best-chain block at `height`; `None` if it is not available
+    fn block_at_height(&self, height: u64) -> Result<Option<Block>, String>;
+}
+
+/// Reported after every scanned block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RescanProgress {
+    pub height: u64,
+    pub tip: u64,
+    /// Wallet transactions found so far
+    pub transactions: usize,
+}
+
+/// Outcome of a rescan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RescanSummary {
+    pub blocks: u64,
+    pub transactions: usize,
+    /// Addresses derived to keep the gap limit
+    pub addresses_derived: usize,
+}
+
+impl WalletManager {
+    /// Rebuild balances and history from the blocks at `from_height` and
+    /// above; see [`rescan_with_progress`](Self::rescan_with_progress).
+    pub fn rescan(
+        &mut self,
+        chain: &dyn ChainQuery,
+        from_height: u64,
+        gap_limit: u32,
+    ) -> Result<RescanSummary, WalletError> {
+        self.rescan_with_progress(chain, from_height, gap_limit, |_| {})
+    }
+
+    /// Rebuild balances and history from the blocks at `from_height` and
+    /// above, calling `progress` after each block.
+    ///
+    /// Cached outputs created below `from_height` are kept; those above are
+    /// dropped and rediscovered. History records are rewritten with their
+    /// block's timestamp and confirmations, keeping labels, tags and memos.
+    /// Deriving addresses needs the seed, so an encrypted wallet must be
+    /// unlocked.
+    pub fn rescan_with_progress(
+        &mut self,
+        chain: &dyn ChainQuery,
+        from_height: u64,
+        gap_limit: u32,
+        mut progress: impl FnMut(&RescanProgress),
+    ) -> Result<RescanSummary, WalletError> {
+        let tip = chain.tip_height().map_err(WalletError::Chain)?;
+        let mut summary = RescanSummary {
+            addresses_derived: self.hd_wallet.extend_to_gap_limit(gap_limit)?,
+            ..RescanSummary::default()
+        };
+        let mut scripts = self.address_script_set()?;
+
+        for entry in self.utxo_set.entries() {
+            if u64::from(entry.height) >= from_height {
+                self.utxo_set
+                    .remove(&entry.outpoint)
+                    .map_err(WalletError::Utxo)?;
+            }
+        }
+
+        let mut records = Vec::new();
+        for height in from_height..=tip {
+            let block = chain
+                .block_at_height(height)
+                .map_err(WalletError::Chain)?
+                .ok_or_else(|| WalletError::Chain(format!("block {} is not available", height)))?;
+            let timestamp = i64::try_from(block.timestamp())
+                .ok()
+                .and_then(|secs| DateTime::from_timestamp(secs, 0))
+                .unwrap_or_else(Utc::now);
+            let confirmations = u32::try_from(tip - height + 1).unwrap_or(u32::MAX);
+            for tx in block.transactions() {
+                let Some((direction, amount, fee)) =
+                    self.scan_transaction(tx, height, gap_limit, &mut scripts, &mut summary)?
+                else {
+                    continue;
+                };
+                let hash = hex::encode(tx.txid());
+                let previous = self.transaction_history.get_transaction(&hash);
+                records.push(TransactionRecord {
+                    label: previous.and_then(|record| record.label.clone()),
+                    category: previous.and_then(|record| record.category.clone()),
+                    tags: previous
+                        .map(|record| record.tags.clone())
+                        .unwrap_or_default(),
+                    memo: previous.and_then(|record| record.memo.clone()),
+                    addresses: Vec::new(),
+                    hash,
+                    timestamp,
+                    direction,
+                    amount,
+                    fee,
+                    status: TransactionStatus::Confirmed(confirmations),
+                });
+            }
+            summary.blocks += 1;
+            progress(&RescanProgress {
+                height,
+                tip,
+                transactions: records.len(),
+            });
+        }
+
+        summary.transactions = records.len();
+        if !records.is_empty() {
+            self.transaction_history.add_transactions(records)?;
+        }
+        #[allow(deprecated)]
+        self.hd_wallet.save()?;
+        Ok(summary)
+    }
+
+    fn address_script_set(&self) -> Result<HashSet<Vec<u8>>, WalletError> {
+        Ok(self
+            .hd_wallet
+            .address_scripts()?
+            .into_iter()
+            .map(|(script, _)| script)
+            .collect())
+    }
+
+    /// Apply `tx` to the UTXO cache. Returns the direction, amount and fee
+    /// of its history record if it touches the wallet.
+    fn scan_transaction(
+        &mut self,
+        tx: &Transaction,
+        height: u64,
+        gap_limit: u32,
+        scripts: &mut HashSet<Vec<u8>>,
+        summary: &mut RescanSummary,
+    ) -> Result<Option<(TransactionDirection, u64, u64)>, WalletError> {
+        let txid = tx.txid();
+        let mut spent = None;
+        let mut foreign_inputs = false;
+        if !tx.is_coinbase() {
+            for input in tx.inputs() {
+                let outpoint = OutPoint {
+                    txid: input.prev_tx_hash(),
+                    vout: input.prev_output_index(),
+                };
+                match self.utxo_set.remove(&outpoint).map_err(WalletError::Utxo)? {
+                    Some(entry) => *spent.get_or_insert(0) += entry.amount(),
+                    None => foreign_inputs = true,
+                }
+            }
+        }
+
+        let mut received = None;
+        let mut matched = vec![false; tx.outputs().len()];
+        loop {
+            let mut found = false;
+            for (vout, output) in tx.outputs().iter().enumerate() {
+                if matched[vout] || !scripts.contains(&output.pub_key_script) {
+                    continue;
+                }
+                matched[vout] = true;
+                found = true;
+                *received.get_or_insert(0) += output.amount();
+                self.hd_wallet.mark_used(&output.pub_key_script)?;
+                // Data carriers can never be spent, so they never enter the cache
+                if !output.is_unspendable() {
+                    self.utxo_set
+                        .add(UtxoEntry {
+                            outpoint: OutPoint {
+                                txid,
+                                vout: vout as u32,
+                            },
+                            output: output.clone(),
+                            height: height as u32,
+                            is_coinbase: tx.is_coinbase(),
+                            is_confirmed: true,
+                        })
+                        .map_err(WalletError::Utxo)?;
+                }
+            }
+            // Activity near the end of the derived addresses may continue
+            // past them; derive further and look at this transaction again
+            if !found {
+                break;
+            }
+            let derived = self.hd_wallet.extend_to_gap_limit(gap_limit)?;
+            if derived == 0 {
+                break;
+            }
+            summary.addresses_derived += derived;
+            *scripts = self.address_script_set()?;
+        }
+
+        Ok(match (spent, received) {
+            (None, None) => None,
+            (None, Some(received)) => Some((TransactionDirection::Received, received, 0)),
+            (Some(spent), received) => {
+                let received = received.unwrap_or(0);
+                // The fee is only known when every input is the wallet's
+                let fee = if foreign_inputs {
+                    0
+                } else {
+                    let outputs: u64 = tx.outputs().iter().map(|output| output.amount()).sum();
+                    spent.saturating_sub(outputs)
+                };
+                let amount = spent.saturating_sub(received).saturating_sub(fee);
+                Some((TransactionDirection::Sent, amount, fee))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdwallet::{address_script, AccountType, DEFAULT_GAP_LIMIT};
+    use bitcoin::network::Network;
+    use supernova_core::test_utils::{TestBlockBuilder, TestTransactionBuilder};
+    use tempfile::tempdir;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const GENESIS_TIME: u64 = 1_700_000_000;
+
+    struct TestChain(Vec<Block>);
+
+    impl ChainQuery for TestChain {
+        fn tip_height(&self) -> Result<u64, String> {
+            Ok(self.0.len() as u64 - 1)
+        }
+
+        fn block_at_height(&self, height: u64) -> Result<Option<Block>, String> {
+            Ok(self.0.get(height as usize).cloned())
+        }
+    }
+
+    impl TestChain {
+        /// Append a block whose coinbase pays `reward` to `script`
+        fn mine(&mut self, reward: (u64, Vec<u8>), transactions: Vec<Transaction>) {
+            let height = self.0.len() as u64;
+            let coinbase = TestTransactionBuilder::new()
+                .add_coinbase_input(height.to_le_bytes().to_vec())
+                .add_output(reward.0, reward.1)
+                .build()
+                .unwrap();
+            let mut builder = TestBlockBuilder::new()
+                .prev_block_hash(self.0.last().map_or([0u8; 32], Block::hash))
+                .timestamp(GENESIS_TIME + height * 600)
+                .target(0x207f_ffff)
+                .add_transaction(coinbase);
+            for tx in transactions {
+                builder = builder.add_transaction(tx);
+            }
+            self.0.push(builder.build().unwrap());
+        }
+    }
+
+    #[test]
+    fn restored_wallet_recovers_funds_past_the_first_gap() {
+        let original_dir = tempdir().unwrap();
+        let mut original = WalletManager::from_mnemonic(
+            MNEMONIC,
+            None,
+            original_dir.path().to_path_buf(),
+            Network::Testnet,
+        )
+        .unwrap();
+        original
+            .create_account("default".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        // Hand out addresses past the restore's gap limit
+        original.set_gap_limit(38).unwrap();
+        let receive: Vec<Vec<u8>> = (0..38)
+            .map(|_| address_script(&original.get_new_address("default").unwrap().address).unwrap())
+            .collect();
+        let change = address_script(
+            &original
+                .hd_wallet
+                .get_new_change_address("default")
+                .unwrap()
+                .address,
+        )
+        .unwrap();
+        let elsewhere = vec![0x51];
+
+        // Index 18 is inside the first gap, and using it brings 37 into reach
+        let mut chain = TestChain(Vec::new());
+        chain.mine((50_000, elsewhere.clone()), Vec::new());
+        chain.mine((30_000, receive[18].clone()), Vec::new());
+        let to_37 = TestTransactionBuilder::new()
+            .add_input([7u8; 32], 0)
+            .add_output(70_000, receive[37].clone())
+            .add_output(5_000, elsewhere.clone())
+            .build()
+            .unwrap();
+        let to_37_hash = hex::encode(to_37.hash());
+        chain.mine((50_000, elsewhere.clone()), vec![to_37]);
+        let payment = TestTransactionBuilder::new()
+            .add_input(chain.0[1].transactions()[0].hash(), 0)
+            .add_output(10_000, elsewhere.clone())
+            .add_output(19_000, change)
+            .build()
+            .unwrap();
+        let payment_hash = hex::encode(payment.hash());
+        chain.mine((50_000, elsewhere), vec![payment]);
+
+        let restored_dir = tempdir().unwrap();
+        let mut restored = WalletManager::from_mnemonic(
+            MNEMONIC,
+            None,
+            restored_dir.path().to_path_buf(),
+            Network::Testnet,
+        )
+        .unwrap();
+        restored
+            .create_account("default".to_string(), AccountType::NativeSegWit)
+            .unwrap();
+        assert_eq!(restored.get_balance("default").unwrap(), 0);
+
+        let mut reported = Vec::new();
+        let summary = restored
+            .rescan_with_progress(&chain, 0, DEFAULT_GAP_LIMIT, |p| reported.push(p.height))
+            .unwrap();
+        assert_eq!(reported, [0, 1, 2, 3]);
+        assert_eq!(summary.blocks, 4);
+        assert_eq!(summary.transactions, 3);
+        assert_eq!(restored.get_balance("default").unwrap(), 70_000 + 19_000);
+
+        let account = &restored.list_accounts(false)[0].1;
+        let found = account
+            .addresses
+            .iter()
+            .find(|hd_address| hd_address.index == 37 && !hd_address.change)
+            .unwrap();
+        assert!(found.is_used);
+        assert_eq!(account.next_index, 38 + DEFAULT_GAP_LIMIT);
+
+        let received = restored.get_transaction(&to_37_hash).unwrap();
+        assert_eq!(received.direction, TransactionDirection::Received);
+        assert_eq!(received.amount, 70_000);
+        assert_eq!(received.status, TransactionStatus::Confirmed(2));
+        assert_eq!(
+            received.timestamp.timestamp() as u64,
+            GENESIS_TIME + 2 * 600
+        );
+        let sent = restored.get_transaction(&payment_hash).unwrap();
+        assert_eq!(sent.direction, TransactionDirection::Sent);
+        assert_eq!((sent.amount, sent.fee), (10_000, 1_000));
+
+        // Scanning again rebuilds the same state, keeping labels
+        restored
+            .add_transaction_label(&to_37_hash, "salary".to_string())
+            .unwrap();
+        let again = restored.rescan(&chain, 0, DEFAULT_GAP_LIMIT).unwrap();
+        assert_eq!(again.transactions, 3);
+        assert_eq!(again.addresses_derived, 0);
+        assert_eq!(restored.get_balance("default").unwrap(), 70_000 + 19_000);
+        assert_eq!(restored.get_all_transactions().len(), 3);
+        assert_eq!(
+            restored
+                .get_transaction(&to_37_hash)
+                .unwrap()
+                .label
+                .as_deref(),
+            Some("salary")
+        );
+    }
+}
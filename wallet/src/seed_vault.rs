@@ -0,0 +1,276 @@
+//! Passphrase encryption of the wallet seed.
+//!
+//! An encrypted `wallet.json` keeps its accounts, addresses, labels and
+//! policies readable so balances display without the passphrase; only the
+//! mnemonic and BIP39 passphrase are replaced by a [`SealedSeed`]. The sealing key is stretched
+//! from the passphrase with Argon2id and split with HKDF-SHA512 into an
+//! AES-256-GCM key and a key-check value, so a wrong passphrase is told
+//! apart from a ciphertext that fails its authentication tag.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+use crate::hdwallet::HDWalletError;
+
+/// How long an unlocked wallet stays unlocked unless configured otherwise
+pub const DEFAULT_AUTO_LOCK: Duration = Duration::from_secs(5 * 60);
+
+/// Current [`SealedSeed`] format
+pub const SEALED_SEED_VERSION: u32 = 1;
+
+/// Associated data binding the ciphertext to its purpose
+const SEED_AAD: &[u8] = b"supernova-wallet/sealed-seed/v1";
+
+/// HKDF info of the AES-256-GCM key
+const SEED_KEY_INFO: &[u8] = b"supernova-wallet/sealed-seed/v1/key";
+
+/// HKDF info of the key-check value
+const SEED_CHECK_INFO: &[u8] = b"supernova-wallet/sealed-seed/v1/check";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Cheap costs so tests stay fast; the format is the same
+#[cfg(test)]
+pub(crate) const TEST_KDF: KdfParams = KdfParams {
+    m_cost: 256,
+    t_cost: 1,
+    p_cost: 1,
+};
+
+/// Sealed plaintext: the mnemonic, then a newline and the BIP39 passphrase
+/// when there is one. Normalized mnemonics never contain a newline.
+pub(crate) fn join_seed(mnemonic: &str, seed_passphrase: &str) -> Zeroizing<String> {
+    let mut secret = Zeroizing::new(String::with_capacity(
+        mnemonic.len() + 1 + seed_passphrase.len(),
+    ));
+    secret.push_str(mnemonic);
+    if !seed_passphrase.is_empty() {
+        secret.push('\n');
+        secret.push_str(seed_passphrase);
+    }
+    secret
+}
+
+/// Inverse of [`join_seed`]: the mnemonic and BIP39 passphrase
+pub(crate) fn split_seed(secret: &str) -> (Zeroizing<String>, Zeroizing<String>) {
+    let (mnemonic, seed_passphrase) = secret.split_once('\n').unwrap_or((secret, ""));
+    (
+        Zeroizing::new(mnemonic.to_string()),
+        Zeroizing::new(seed_passphrase.to_string()),
+    )
+}
+
+/// Argon2id cost parameters, stored with the seed so they can be raised
+/// for new wallets without breaking old ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub m_cost: u32,
+    /// Iterations
+    pub t_cost: u32,
+    /// Lanes
+    pub p_cost: u32,
+}
+
+impl Default for KdfParams {
+    /// The costs `save_encrypted` uses for whole-file backups
+    fn default() -> Self {
+        Self {
+            m_cost: 65536,
+            t_cost: 3,
+            p_cost: 4,
+        }
+    }
+}
+
+/// The wallet seed (see [`join_seed`]) encrypted under a passphrase. Byte
+/// fields are hex.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedSeed {
+    pub version: u32,
+    pub kdf: KdfParams,
+    pub salt: String,
+    /// Derived from the passphrase alongside the key; a mismatch means the
+    /// passphrase is wrong rather than the ciphertext damaged
+    pub key_check: String,
+    pub nonce: String,
+    /// AES-256-GCM ciphertext and tag of the seed
+    pub ciphertext: String,
+}
+
+/// Encryption key and key-check value for `passphrase`
+fn derive_keys(
+    passphrase: &str,
+    salt: &[u8],
+    kdf: KdfParams,
+) -> Result<(Zeroizing<[u8; 32]>, [u8; 32]), HDWalletError> {
+    let params = Params::new(kdf.m_cost, kdf.t_cost, kdf.p_cost, None)
+        .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+    let mut stretched = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut stretched[..])
+        .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+
+    let hkdf = Hkdf::<Sha512>::new(Some(salt), &stretched[..]);
+    let mut key = Zeroizing::new([0u8; 32]);
+    let mut check = [0u8; 32];
+    hkdf.expand(SEED_KEY_INFO, &mut key[..])
+        .and_then(|_| hkdf.expand(SEED_CHECK_INFO, &mut check))
+        .map_err(|e| HDWalletError::KeyDerivationError(e.to_string()))?;
+    Ok((key, check))
+}
+
+fn decode(field: &str, value: &str) -> Result<Vec<u8>, HDWalletError> {
+    hex::decode(value)
+        .map_err(|e| HDWalletError::DecryptionError(format!("invalid {} encoding: {}", field, e)))
+}
+
+impl SealedSeed {
+    /// Encrypt `secret` under `passphrase` with a fresh salt and nonce
+    pub fn seal(secret: &str, passphrase: &str, kdf: KdfParams) -> Result<Self, HDWalletError> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let (key, check) = derive_keys(passphrase, &salt, kdf)?;
+        let cipher = Aes256Gcm::new_from_slice(&key[..])
+            .map_err(|e| HDWalletError::EncryptionError(e.to_string()))?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: secret.as_bytes(),
+                    aad: SEED_AAD,
+                },
+            )
+            .map_err(|e| HDWalletError::EncryptionError(e.to_string()))?;
+
+        Ok(Self {
+            version: SEALED_SEED_VERSION,
+            kdf,
+            salt: hex::encode(salt),
+            key_check: hex::encode(check),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypt the sealed secret. Fails with [`HDWalletError::WrongPassphrase`]
+    /// if the passphrase does not match, and with a decryption error if the
+    /// ciphertext fails authentication.
+    pub fn open(&self, passphrase: &str) -> Result<Zeroizing<String>, HDWalletError> {
+        if self.version != SEALED_SEED_VERSION {
+            return Err(HDWalletError::DecryptionError(format!(
+                "unsupported sealed seed version {}",
+                self.version
+            )));
+        }
+        let salt = decode("salt", &self.salt)?;
+        let nonce: [u8; NONCE_LEN] = decode("nonce", &self.nonce)?
+            .try_into()
+            .map_err(|_| HDWalletError::DecryptionError("invalid nonce length".to_string()))?;
+        let ciphertext = decode("ciphertext", &self.ciphertext)?;
+
+        let (key, check) = derive_keys(passphrase, &salt, self.kdf)?;
+        if hex::encode(check) != self.key_check {
+            return Err(HDWalletError::WrongPassphrase);
+        }
+        let cipher = Aes256Gcm::new_from_slice(&key[..])
+            .map_err(|e| HDWalletError::DecryptionError(e.to_string()))?;
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: SEED_AAD,
+                    },
+                )
+                .map_err(|_| {
+                    HDWalletError::DecryptionError(
+                        "sealed seed failed authentication; the wallet file is corrupted"
+                            .to_string(),
+                    )
+                })?,
+        );
+        let secret = std::str::from_utf8(&plaintext)
+            .map_err(|e| HDWalletError::DecryptionError(e.to_string()))?;
+        Ok(Zeroizing::new(secret.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn seal_open_round_trip_and_wrong_passphrase() {
+        let sealed = SealedSeed::seal(MNEMONIC, "correct horse", TEST_KDF).unwrap();
+        assert_eq!(sealed.open("correct horse").unwrap().as_str(), MNEMONIC);
+        assert!(matches!(
+            sealed.open("wrong horse"),
+            Err(HDWalletError::WrongPassphrase)
+        ));
+
+        // Fresh salt and nonce every time
+        let again = SealedSeed::seal(MNEMONIC, "correct horse", TEST_KDF).unwrap();
+        assert_ne!(sealed.salt, again.salt);
+        assert_ne!(sealed.ciphertext, again.ciphertext);
+    }
+
+    #[test]
+    fn tampered_ciphertext_fails_authentication() {
+        let sealed = SealedSeed::seal(MNEMONIC, "correct horse", TEST_KDF).unwrap();
+        let mut bytes = hex::decode(&sealed.ciphertext).unwrap();
+        for index in [0, bytes.len() - 1] {
+            bytes[index] ^= 0x01;
+            let tampered = SealedSeed {
+                ciphertext: hex::encode(&bytes),
+                ..sealed.clone()
+            };
+            bytes[index] ^= 0x01;
+            assert!(matches!(
+                tampered.open("correct horse"),
+                Err(HDWalletError::DecryptionError(message)) if message.contains("authentication")
+            ));
+        }
+
+        let truncated = SealedSeed {
+            nonce: "00".to_string(),
+            ..sealed
+        };
+        assert!(matches!(
+            truncated.open("correct horse"),
+            Err(HDWalletError::DecryptionError(_))
+        ));
+    }
+
+    #[test]
+    fn seed_passphrase_is_kept_apart_from_the_mnemonic() {
+        let joined = join_seed(MNEMONIC, "line one\nline two");
+        let (mnemonic, seed_passphrase) = split_seed(&joined);
+        assert_eq!(mnemonic.as_str(), MNEMONIC);
+        assert_eq!(seed_passphrase.as_str(), "line one\nline two");
+
+        // Seeds sealed without a passphrase are the bare mnemonic
+        assert_eq!(join_seed(MNEMONIC, "").as_str(), MNEMONIC);
+        let (mnemonic, seed_passphrase) = split_seed(MNEMONIC);
+        assert_eq!(mnemonic.as_str(), MNEMONIC);
+        assert!(seed_passphrase.is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Several named seeds in one wallet directory.
+//!
+//! The seed named [`DEFAULT_SEED`] lives directly in the wallet directory,
+//! so wallets created before named seeds keep working. Every other seed has
+//! its own `seeds/<name>/` directory with its own `wallet.json` and
+//! `history.json`. Settings are shared, and `settings.json` records which
+//! seed is active.
+
+use crate::settings::{SettingsError, WalletSettings};
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// Seed kept at the top of the wallet directory
+pub const DEFAULT_SEED: &str = "default";
+
+/// Directory of the other seeds, inside the wallet directory
+pub const SEEDS_DIR: &str = "seeds";
+
+pub const WALLET_FILE: &str = "wallet.json";
+pub const HISTORY_FILE: &str = "history.json";
+
+const MAX_NAME_LEN: usize = 32;
+
+#[derive(Error, Debug)]
+pub enum SeedStoreError {
+    #[error("Invalid seed name '{0}': use up to 32 letters, digits, '-' or '_'")]
+    InvalidName(String),
+    #[error("No seed named '{0}'")]
+    NotFound(String),
+    #[error("Settings error: {0}")]
+    Settings(#[from] SettingsError),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The seeds of one wallet directory and the active-seed selector
+#[derive(Debug, Clone)]
+pub struct SeedStore {
+    wallet_dir: PathBuf,
+}
+
+impl SeedStore {
+    pub fn new(wallet_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            wallet_dir: wallet_dir.into(),
+        }
+    }
+
+    pub fn validate_name(name: &str) -> Result<(), SeedStoreError> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NAME_LEN
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if valid {
+            Ok(())
+        } else {
+            Err(SeedStoreError::InvalidName(name.to_string()))
+        }
+    }
+
+    /// Directory holding the seed's wallet and history files
+    pub fn seed_dir(&self, name: &str) -> Result<PathBuf, SeedStoreError> {
+        Self::validate_name(name)?;
+        if name == DEFAULT_SEED {
+            return Ok(self.wallet_dir.clone());
+        }
+        Ok(self.wallet_dir.join(SEEDS_DIR).join(name))
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.seed_dir(name)
+            .is_ok_and(|dir| dir.join(WALLET_FILE).exists())
+    }
+
+    /// Seeds with a wallet file: the default first, the others by name
+    pub fn list(&self) -> Result<Vec<String>, SeedStoreError> {
+        let mut names = Vec::new();
+        let seeds_dir = self.wallet_dir.join(SEEDS_DIR);
+        if seeds_dir.is_dir() {
+            for entry in std::fs::read_dir(&seeds_dir)? {
+                let entry = entry?;
+                let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if name != DEFAULT_SEED && self.exists(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        names.sort();
+        if self.exists(DEFAULT_SEED) {
+            names.insert(0, DEFAULT_SEED.to_string());
+        }
+        Ok(names)
+    }
+
+    /// The selected seed; [`DEFAULT_SEED`] until another is chosen
+    pub fn active(&self) -> Result<String, SeedStoreError> {
+        let settings = WalletSettings::load(&self.wallet_dir)?;
+        Ok(settings
+            .active_seed
+            .unwrap_or_else(|| DEFAULT_SEED.to_string()))
+    }
+
+    /// Directory of the active seed
+    pub fn active_dir(&self) -> Result<PathBuf, SeedStoreError> {
+        self.seed_dir(&self.active()?)
+    }
+
+    /// Make `name` the seed commands and the TUI open by default. The seed
+    /// must already exist.
+    pub fn set_active(&self, name: &str) -> Result<(), SeedStoreError> {
+        if !self.exists(name) {
+            return Err(SeedStoreError::NotFound(name.to_string()));
+        }
+        let mut settings = WalletSettings::load(&self.wallet_dir)?;
+        settings.active_seed = (name != DEFAULT_SEED).then(|| name.to_string());
+        settings.save(&self.wallet_dir)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn create(store: &SeedStore, name: &str) {
+        let dir = store.seed_dir(name).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(WALLET_FILE), "{}").unwrap();
+    }
+
+    #[test]
+    fn seeds_are_listed_and_selected_by_name() {
+        let dir = tempdir().unwrap();
+        let store = SeedStore::new(dir.path());
+        assert!(store.list().unwrap().is_empty());
+        assert_eq!(store.active().unwrap(), DEFAULT_SEED);
+        assert_eq!(store.active_dir().unwrap(), dir.path());
+
+        create(&store, DEFAULT_SEED);
+        create(&store, "savings");
+        create(&store, "cold-2");
+        // A directory without a wallet file is not a seed
+        std::fs::create_dir_all(dir.path().join(SEEDS_DIR).join("empty")).unwrap();
+        assert_eq!(store.list().unwrap(), ["default", "cold-2", "savings"]);
+
+        store.set_active("savings").unwrap();
+        assert_eq!(store.active().unwrap(), "savings");
+        assert_eq!(
+            store.active_dir().unwrap(),
+            dir.path().join(SEEDS_DIR).join("savings")
+        );
+        assert!(matches!(
+            store.set_active("empty"),
+            Err(SeedStoreError::NotFound(_))
+        ));
+
+        store.set_active(DEFAULT_SEED).unwrap();
+        let settings = WalletSettings::load(dir.path()).unwrap();
+        assert_eq!(settings.active_seed, None);
+    }
+
+    #[test]
+    fn names_cannot_escape_the_wallet_directory() {
+        let store = SeedStore::new("/tmp/wallet");
+        for name in ["", "../other", "a/b", "with space", "x".repeat(33).as_str()] {
+            assert!(matches!(
+                store.seed_dir(name),
+                Err(SeedStoreError::InvalidName(_))
+            ));
+        }
+        assert!(store.seed_dir("Main_1").is_ok());
+    }
+}
@@ -0,0 +1,141 @@
+//! User-level wallet settings, stored as `settings.json` in the wallet
+//! directory. Settings hold no key material.
+
+use crate::display::DisplayPreferences;
+use crate::seed_vault::DEFAULT_AUTO_LOCK;
+use crate::ui::UiPreferences;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Error, Debug)]
+pub enum SettingsError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WalletSettings {
+    #[serde(default)]
+    pub display: DisplayPreferences,
+    #[serde(default)]
+    pub ui: UiPreferences,
+    /// Seconds an encrypted wallet stays unlocked; unset uses
+    /// [`DEFAULT_AUTO_LOCK`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_lock_secs: Option<u64>,
+    /// Seed opened by default (see `seeds`); unset means the default seed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_seed: Option<String>,
+}
+
+impl WalletSettings {
+    pub fn auto_lock(&self) -> Duration {
+        self.auto_lock_secs
+            .map_or(DEFAULT_AUTO_LOCK, Duration::from_secs)
+    }
+
+    pub fn path(wallet_dir: &Path) -> PathBuf {
+        wallet_dir.join(SETTINGS_FILE)
+    }
+
+    /// Load settings, falling back to defaults when the file does not exist.
+    pub fn load(wallet_dir: &Path) -> Result<Self, SettingsError> {
+        let path = Self::path(wallet_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    pub fn save(&self, wallet_dir: &Path) -> Result<(), SettingsError> {
+        let data = serde_json::to_string_pretty(self)?;
+        std::fs::write(Self::path(wallet_dir), data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::{DisplayUnit, RateProviderSettings};
+    use crate::ui::keymap::Action;
+    use crate::ui::theme::{ColorDepth, ColorRole, RoleStyle, ThemeColor, ThemeDefinition};
+    use std::collections::BTreeMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn settings_roundtrip_and_default_when_missing() {
+        let dir = tempdir().unwrap();
+        assert_eq!(WalletSettings::load(dir.path()).unwrap(), WalletSettings::default());
+
+        let settings = WalletSettings {
+            display: DisplayPreferences {
+                primary_unit: DisplayUnit::NovaUnits,
+                fiat_currency: Some("USD".to_string()),
+                rate_provider: RateProviderSettings::Http {
+                    endpoint: "https://rates.example/nova".to_string(),
+                    ttl_secs: 120,
+                },
+            },
+            ..Default::default()
+        };
+        settings.save(dir.path()).unwrap();
+        assert_eq!(WalletSettings::load(dir.path()).unwrap(), settings);
+    }
+
+    #[test]
+    fn ui_preferences_roundtrip() {
+        let dir = tempdir().unwrap();
+        let mut settings = WalletSettings::default();
+        settings.ui.theme = "sunrise".to_string();
+        settings.ui.color_depth = Some(ColorDepth::Ansi8);
+        settings.ui.themes.push(ThemeDefinition {
+            name: "sunrise".to_string(),
+            base: "light".to_string(),
+            roles: BTreeMap::from([(
+                ColorRole::Accent,
+                RoleStyle {
+                    fg: Some(ThemeColor::Rgb(215, 95, 0)),
+                    bold: true,
+                    ..Default::default()
+                },
+            )]),
+        });
+        settings
+            .ui
+            .keybindings
+            .insert(Action::Settings, "f2".to_string());
+        settings.save(dir.path()).unwrap();
+
+        let loaded = WalletSettings::load(dir.path()).unwrap();
+        assert_eq!(loaded, settings);
+        let palette = loaded.ui.palette(ColorDepth::TrueColor).unwrap();
+        assert_eq!(palette.name(), "sunrise");
+        assert_eq!(palette.depth(), ColorDepth::Ansi8);
+        assert_eq!(palette.role(ColorRole::Accent).fg, Some(ThemeColor::Rgb(215, 95, 0)));
+        assert_eq!(loaded.ui.keymap().unwrap().overrides(), settings.ui.keybindings);
+
+        // Files written before theming load with the default theme
+        std::fs::write(WalletSettings::path(dir.path()), r#"{"display":{}}"#).unwrap();
+        let legacy = WalletSettings::load(dir.path()).unwrap();
+        assert_eq!(legacy.ui, UiPreferences::default());
+
+        // Colors are stored by name
+        std::fs::write(
+            WalletSettings::path(dir.path()),
+            r#"{"ui":{"theme":"x","themes":[{"name":"x","roles":{"muted":{"fg":"chartreuse"}}}]}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            WalletSettings::load(dir.path()),
+            Err(SettingsError::Serialization(_))
+        ));
+    }
+}
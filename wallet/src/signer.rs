@@ -0,0 +1,497 @@
+//! External signers: keys kept on a separate device.
+//!
+//! The wallet holds only an account xpub (see
+//! [`WalletManager::new_watch_only`]) and asks an [`ExternalSigner`] for one
+//! signature per input once it has been shown the transaction. Register a
+//! signer with [`WalletManager::register_external_signer`] and the usual
+//! transaction and [`WalletManager::sign_unsigned`] paths sign through
+//! it. Every signature is checked against the wallet's own public key
+//! before it is used.
+//!
+//! [`ProcessSigner`] integrates any device through a bridge program that
+//! speaks newline-delimited JSON on its stdin and stdout:
+//!
+//! ```text
+//! -> {"id":1,"method":"list_keys","params":null}
+//! <- {"id":1,"result":[{"path":"m/44'/1'/0'","xpub":"tpub..."}]}
+//! -> {"id":2,"method":"get_xpub","params":{"path":"m/44'/1'/0'"}}
+//! <- {"id":2,"result":"tpub..."}
+//! -> {"id":3,"method":"review_transaction","params":{"inputs":[...],"outputs":[...],"amount":50000,"fee":210}}
+//! <- {"id":3,"result":null}
+//! -> {"id":4,"method":"sign_digest","params":{"path":"m/44'/1'/0'/0/3","digest":"<hex>"}}
+//! <- {"id":4,"result":"<64-byte compact signature, hex>"}
+//! ```
+//!
+//! A failed request answers `{"id":n,"error":{"code":"...","message":"..."}}`;
+//! the code `rejected` means the user declined on the device. Lines that are
+//! not JSON objects are ignored, so bridge log output does no harm.
+//!
+//! [`WalletManager::new_watch_only`]: crate::WalletManager::new_watch_only
+//! [`WalletManager::register_external_signer`]: crate::WalletManager::register_external_signer
+//! [`WalletManager::sign_unsigned`]: crate::WalletManager::sign_unsigned
+
+use crate::unsigned::UnsignedTransaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long a [`ProcessSigner`] waits for an answer, including the user
+/// confirming on the device
+pub const DEFAULT_SIGNER_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Error code of a request the user declined
+const REJECTED_CODE: &str = "rejected";
+
+#[derive(Error, Debug)]
+pub enum SignerError {
+    #[error("Rejected on the signer: {0}")]
+    Rejected(String),
+    #[error("Signer did not answer within {0:?}")]
+    Timeout(Duration),
+    #[error("Signer failed: {0}")]
+    Device(String),
+    #[error("Signer protocol error: {0}")]
+    Protocol(String),
+    #[error("Signer IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// An account key held by a signer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerKey {
+    /// BIP44 account path, e.g. `m/44'/1'/0'`
+    pub path: String,
+    pub xpub: String,
+}
+
+/// A device holding the seed that signs on the wallet's behalf
+pub trait ExternalSigner: Send + Sync {
+    /// Account keys the signer offers
+    fn list_keys(&self) -> Result<Vec<SignerKey>, SignerError>;
+
+    /// Extended public key at `path`
+    fn get_xpub(&self, path: &str) -> Result<String, SignerError>;
+
+    /// Show the transaction about to be signed. An error, such as the user
+    /// declining, aborts the signing.
+    fn review_transaction(&self, _tx: &UnsignedTransaction) -> Result<(), SignerError> {
+        Ok(())
+    }
+
+    /// Compact (64-byte) secp256k1 signature of `digest` with the key at
+    /// `path`
+    fn sign_digest(&self, path: &str, digest: &[u8; 32]) -> Result<Vec<u8>, SignerError>;
+}
+
+/// What a signer shows before signing: where the funds come from and go
+#[derive(Debug, Serialize)]
+struct TransactionReview {
+    inputs: Vec<ReviewInput>,
+    outputs: Vec<ReviewOutput>,
+    /// Amount paid to the recipient
+    amount: u64,
+    fee: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ReviewInput {
+    path: String,
+    amount: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ReviewOutput {
+    /// Locking script, hex
+    script: String,
+    amount: u64,
+    /// Path of a change output paying back to the signer's keys
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change_path: Option<String>,
+}
+
+impl TransactionReview {
+    fn new(tx: &UnsignedTransaction) -> Result<Self, SignerError> {
+        Ok(Self {
+            inputs: tx
+                .inputs
+                .iter()
+                .map(|input| ReviewInput {
+                    path: input.derivation_path.clone(),
+                    amount: input.amount,
+                })
+                .collect(),
+            outputs: tx
+                .outputs
+                .iter()
+                .enumerate()
+                .map(|(index, output)| ReviewOutput {
+                    script: hex::encode(&output.pub_key_script),
+                    amount: output.amount(),
+                    change_path: tx.output_paths.get(index).cloned().flatten(),
+                })
+                .collect(),
+            amount: tx.amount,
+            fee: tx.fee().map_err(|e| SignerError::Protocol(e.to_string()))?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(default)]
+    result: Value,
+    #[serde(default)]
+    error: Option<ResponseError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseError {
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+struct Connection {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines read from the signer's stdout by a reader thread
+    lines: Receiver<String>,
+    next_id: u64,
+}
+
+/// [`ExternalSigner`] bridged to a child process over newline-delimited
+/// JSON on stdio (see the [module docs](self)). Requests are answered one
+/// at a time; the process is killed when the signer is dropped.
+pub struct ProcessSigner {
+    connection: Mutex<Connection>,
+    timeout: Duration,
+}
+
+impl ProcessSigner {
+    /// Start `command` with piped stdin and stdout
+    pub fn spawn(mut command: Command) -> Result<Self, SignerError> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(SignerError::Protocol(
+                "signer stdio is not piped".to_string(),
+            ));
+        };
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            connection: Mutex::new(Connection {
+                child,
+                stdin,
+                lines,
+                next_id: 0,
+            }),
+            timeout: DEFAULT_SIGNER_TIMEOUT,
+        })
+    }
+
+    /// Time to wait for each answer
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, SignerError> {
+        let mut connection = self
+            .connection
+            .lock()
+            .map_err(|_| SignerError::Protocol("signer connection poisoned".to_string()))?;
+        connection.next_id += 1;
+        let id = connection.next_id;
+        let mut request = json!({ "id": id, "method": method, "params": params }).to_string();
+        request.push('\n');
+        connection.stdin.write_all(request.as_bytes())?;
+        connection.stdin.flush()?;
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let line = match connection
+                .lines
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => return Err(SignerError::Timeout(self.timeout)),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(SignerError::Protocol("signer process exited".to_string()))
+                }
+            };
+            // Log output, or a late answer to a request that timed out
+            let Ok(response) = serde_json::from_str::<Response>(&line) else {
+                continue;
+            };
+            if response.id != id {
+                continue;
+            }
+            return match response.error {
+                Some(error) if error.code == REJECTED_CODE => {
+                    Err(SignerError::Rejected(error.message))
+                }
+                Some(error) => Err(SignerError::Device(format!(
+                    "{}: {}",
+                    error.code, error.message
+                ))),
+                None => Ok(response.result),
+            };
+        }
+    }
+}
+
+impl Drop for ProcessSigner {
+    fn drop(&mut self) {
+        if let Ok(connection) = self.connection.get_mut() {
+            let _ = connection.child.kill();
+            let _ = connection.child.wait();
+        }
+    }
+}
+
+impl ExternalSigner for ProcessSigner {
+    fn list_keys(&self) -> Result<Vec<SignerKey>, SignerError> {
+        serde_json::from_value(self.call("list_keys", Value::Null)?)
+            .map_err(|e| SignerError::Protocol(format!("invalid key list: {}", e)))
+    }
+
+    fn get_xpub(&self, path: &str) -> Result<String, SignerError> {
+        match self.call("get_xpub", json!({ "path": path }))? {
+            Value::String(xpub) => Ok(xpub),
+            other => Err(SignerError::Protocol(format!(
+                "expected an xpub, got {}",
+                other
+            ))),
+        }
+    }
+
+    fn review_transaction(&self, tx: &UnsignedTransaction) -> Result<(), SignerError> {
+        let review = serde_json::to_value(TransactionReview::new(tx)?)
+            .map_err(|e| SignerError::Protocol(e.to_string()))?;
+        self.call("review_transaction", review)?;
+        Ok(())
+    }
+
+    fn sign_digest(&self, path: &str, digest: &[u8; 32]) -> Result<Vec<u8>, SignerError> {
+        let result = self.call(
+            "sign_digest",
+            json!({ "path": path, "digest": hex::encode(digest) }),
+        )?;
+        result
+            .as_str()
+            .and_then(|signature| hex::decode(signature).ok())
+            .ok_or_else(|| {
+                SignerError::Protocol(format!("expected a hex signature, got {}", result))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hdwallet::{address_script, WATCH_ONLY_ACCOUNT};
+    use crate::{WalletError, WalletManager};
+    use bip39::{Language, Mnemonic};
+    use bitcoin::bip32::{DerivationPath, Xpriv, Xpub};
+    use bitcoin::network::Network;
+    use bitcoin::secp256k1::{ecdsa::Signature, Message, PublicKey, Secp256k1};
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use supernova_core::storage::utxo_set::UtxoEntry;
+    use supernova_core::types::transaction::{OutPoint, TransactionOutput};
+    use tempfile::tempdir;
+
+    const MOCK_ENV: &str = "SUPERNOVA_MOCK_SIGNER";
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    const ACCOUNT_PATH: &str = "m/44'/1'/0'";
+    const RECIPIENT: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    /// The mock signer itself: a no-op in a normal test run, and a device
+    /// holding [`MNEMONIC`] when started by [`mock_signer`]. `approve` signs
+    /// whatever it is shown, `reject` declines every review and `hang`
+    /// never answers a signing request.
+    #[test]
+    fn mock_signer_process() {
+        let Ok(mode) = std::env::var(MOCK_ENV) else {
+            return;
+        };
+        let secp = Secp256k1::new();
+        let seed = Mnemonic::parse_in_normalized(Language::English, MNEMONIC)
+            .unwrap()
+            .to_seed("");
+        let master = Xpriv::new_master(Network::Testnet, &seed).unwrap();
+        let derive = |path: &str| {
+            master
+                .derive_priv(&secp, &DerivationPath::from_str(path).unwrap())
+                .unwrap()
+        };
+        let xpub = Xpub::from_priv(&secp, &derive(ACCOUNT_PATH)).to_string();
+
+        let mut stdout = std::io::stdout();
+        for line in std::io::stdin().lines() {
+            let request: Value = serde_json::from_str(&line.unwrap()).unwrap();
+            let params = &request["params"];
+            let mut answer = match request["method"].as_str().unwrap() {
+                "list_keys" => json!({ "result": [{ "path": ACCOUNT_PATH, "xpub": xpub }] }),
+                "get_xpub" => json!({ "result": xpub }),
+                "review_transaction" if mode == "reject" => {
+                    json!({ "error": { "code": "rejected", "message": "declined on device" } })
+                }
+                // A device must be able to tell the payment from the change
+                "review_transaction" => {
+                    let outputs = params["outputs"].as_array().unwrap();
+                    let has_change = outputs.iter().any(|o| o["change_path"].is_string());
+                    if has_change && params["fee"].is_u64() {
+                        json!({ "result": null })
+                    } else {
+                        json!({ "error": { "code": "invalid", "message": "missing details" } })
+                    }
+                }
+                "sign_digest" if mode == "hang" => continue,
+                "sign_digest" => {
+                    let key = derive(params["path"].as_str().unwrap());
+                    let digest: [u8; 32] = hex::decode(params["digest"].as_str().unwrap())
+                        .unwrap()
+                        .try_into()
+                        .unwrap();
+                    let signature = secp
+                        .sign_ecdsa(&Message::from_digest(digest), &key.private_key)
+                        .serialize_compact();
+                    json!({ "result": hex::encode(signature) })
+                }
+                other => panic!("unexpected method {}", other),
+            };
+            answer["id"] = request["id"].clone();
+            // Bridges may log to stdout too
+            writeln!(stdout, "log: answering {}", request["method"]).unwrap();
+            writeln!(stdout, "{}", answer).unwrap();
+            stdout.flush().unwrap();
+        }
+    }
+
+    /// [`mock_signer_process`] in a child process of this test binary
+    fn mock_signer(mode: &str) -> ProcessSigner {
+        let mut command = Command::new(std::env::current_exe().unwrap());
+        command
+            .args([
+                "signer::tests::mock_signer_process",
+                "--exact",
+                "--nocapture",
+                "--test-threads=1",
+            ])
+            .env(MOCK_ENV, mode);
+        ProcessSigner::spawn(command).unwrap()
+    }
+
+    /// A watch-only wallet over the signer's account with three outputs of
+    /// 40,000
+    fn funded_watch_only(signer: &dyn ExternalSigner, dir: &std::path::Path) -> WalletManager {
+        let keys = signer.list_keys().unwrap();
+        assert_eq!(keys[0].path, ACCOUNT_PATH);
+        assert_eq!(signer.get_xpub(ACCOUNT_PATH).unwrap(), keys[0].xpub);
+        let mut wallet =
+            WalletManager::new_watch_only(dir.to_path_buf(), Network::Testnet, &keys[0].xpub)
+                .unwrap();
+        for id in 1..=3u8 {
+            let address = wallet.get_new_address(WATCH_ONLY_ACCOUNT).unwrap().address;
+            wallet
+                .utxo_set
+                .add(UtxoEntry {
+                    outpoint: OutPoint {
+                        txid: [id; 32],
+                        vout: 0,
+                    },
+                    output: TransactionOutput::new(40_000, address_script(&address).unwrap()),
+                    height: 1,
+                    is_coinbase: false,
+                    is_confirmed: true,
+                })
+                .unwrap();
+        }
+        wallet
+    }
+
+    #[test]
+    fn watch_only_wallet_spends_through_a_signer_process() {
+        let signer = Arc::new(mock_signer("approve"));
+        let dir = tempdir().unwrap();
+        let mut wallet = funded_watch_only(signer.as_ref(), dir.path());
+
+        let unsigned = wallet
+            .build_unsigned(WATCH_ONLY_ACCOUNT, RECIPIENT, 50_000, 1)
+            .unwrap();
+        assert_eq!(unsigned.output_paths.len(), unsigned.outputs.len());
+        assert_eq!(unsigned.output_paths[0], None);
+        assert!(unsigned.output_paths[1]
+            .as_deref()
+            .is_some_and(|path| path.starts_with("m/44'/1'/0'/1/")));
+
+        wallet.register_external_signer(signer);
+        let tx = wallet.sign_unsigned(unsigned).unwrap();
+        assert_eq!(tx.outputs()[0].amount(), 50_000);
+        let secp = Secp256k1::verification_only();
+        let message = Message::from_digest(tx.signature_hash());
+        for input in tx.inputs() {
+            let [signature, pubkey] = input.witness() else {
+                panic!("expected a [signature, pubkey] witness");
+            };
+            let signature = Signature::from_compact(signature).unwrap();
+            let pubkey = PublicKey::from_slice(pubkey).unwrap();
+            secp.verify_ecdsa(&message, &signature, &pubkey).unwrap();
+        }
+        assert_eq!(wallet.get_all_transactions().len(), 1);
+
+        // The regular send path signs through it as well
+        let tx = wallet
+            .create_transaction(WATCH_ONLY_ACCOUNT, RECIPIENT, 20_000, 1)
+            .unwrap();
+        assert_eq!(tx.outputs()[0].amount(), 20_000);
+        assert_eq!(wallet.get_all_transactions().len(), 2);
+
+        wallet.clear_external_signer();
+        assert!(matches!(
+            wallet.create_transaction(WATCH_ONLY_ACCOUNT, RECIPIENT, 1_000, 1),
+            Err(WalletError::WatchOnly(_))
+        ));
+    }
+
+    #[test]
+    fn declined_or_silent_signers_leave_the_wallet_untouched() {
+        let dir = tempdir().unwrap();
+        let signer = Arc::new(mock_signer("reject"));
+        let mut wallet = funded_watch_only(signer.as_ref(), dir.path());
+        wallet.register_external_signer(signer);
+        assert!(matches!(
+            wallet.create_transaction(WATCH_ONLY_ACCOUNT, RECIPIENT, 50_000, 1),
+            Err(WalletError::Signer(SignerError::Rejected(_)))
+        ));
+        assert!(wallet.get_all_transactions().is_empty());
+        assert_eq!(wallet.utxo_set.get_count(), 3);
+
+        let signer = mock_signer("hang").with_timeout(Duration::from_millis(300));
+        let started = Instant::now();
+        assert!(matches!(
+            signer.sign_digest("m/44'/1'/0'/0/0", &[7; 32]),
+            Err(SignerError::Timeout(_))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
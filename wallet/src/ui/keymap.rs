@@ -0,0 +1,469 @@
+//! Configurable key bindings for the wallet TUI.
+//!
+//! Bindings are written in the settings file as strings such as `q`, `tab`,
+//! `ctrl+n` or `f2`. Screen-specific actions may reuse a global key only
+//! where the global action would do nothing, e.g. `a` both opens the
+//! Accounts tab and, once there, generates an address.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum KeymapError {
+    #[error("Invalid key '{0}'")]
+    InvalidKey(String),
+    #[error("Conflicting key bindings: {}", format_conflicts(.0))]
+    Conflicts(Vec<KeyConflict>),
+}
+
+fn format_conflicts(conflicts: &[KeyConflict]) -> String {
+    conflicts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// TUI screens, in tab order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tab {
+    Overview,
+    Accounts,
+    Transactions,
+    Settings,
+    Help,
+}
+
+impl Tab {
+    pub const ALL: [Tab; 5] = [
+        Tab::Overview,
+        Tab::Accounts,
+        Tab::Transactions,
+        Tab::Settings,
+        Tab::Help,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Tab::Overview => "Overview",
+            Tab::Accounts => "Accounts",
+            Tab::Transactions => "Transactions",
+            Tab::Settings => "Settings",
+            Tab::Help => "Help",
+        }
+    }
+
+    pub fn next(self) -> Tab {
+        let i = Self::ALL.iter().position(|t| *t == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Quit,
+    NextTab,
+    Help,
+    Overview,
+    Accounts,
+    Transactions,
+    Settings,
+    Lock,
+    Up,
+    Down,
+    NewAccount,
+    NewAddress,
+    Send,
+    LabelTransaction,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Action::Quit,
+        Action::NextTab,
+        Action::Help,
+        Action::Overview,
+        Action::Accounts,
+        Action::Transactions,
+        Action::Settings,
+        Action::Lock,
+        Action::Up,
+        Action::Down,
+        Action::NewAccount,
+        Action::NewAddress,
+        Action::Send,
+        Action::LabelTransaction,
+    ];
+
+    /// The screen the action is limited to, or `None` for global actions
+    pub fn scope(self) -> Option<Tab> {
+        match self {
+            Action::NewAccount | Action::NewAddress | Action::Send => Some(Tab::Accounts),
+            Action::LabelTransaction => Some(Tab::Transactions),
+            _ => None,
+        }
+    }
+
+    /// The screen a global navigation action switches to
+    pub fn target(self) -> Option<Tab> {
+        match self {
+            Action::Overview => Some(Tab::Overview),
+            Action::Accounts => Some(Tab::Accounts),
+            Action::Transactions => Some(Tab::Transactions),
+            Action::Settings => Some(Tab::Settings),
+            _ => None,
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit application",
+            Action::NextTab => "Cycle through tabs",
+            Action::Help => "Show/hide help",
+            Action::Overview => "Go to overview",
+            Action::Accounts => "Go to accounts",
+            Action::Transactions => "Go to transactions",
+            Action::Settings => "Go to settings",
+            Action::Lock => "Lock the wallet",
+            Action::Up => "Move selection up",
+            Action::Down => "Move selection down",
+            Action::NewAccount => "Create new account",
+            Action::NewAddress => "Generate new address for selected account",
+            Action::Send => "Send from selected account",
+            Action::LabelTransaction => "Add/edit label for selected transaction",
+        }
+    }
+
+    fn default_binding(self) -> KeyBinding {
+        let code = match self {
+            Action::Quit => KeyCode::Char('q'),
+            Action::NextTab => KeyCode::Tab,
+            Action::Help => KeyCode::Char('?'),
+            Action::Overview => KeyCode::Char('o'),
+            Action::Accounts => KeyCode::Char('a'),
+            Action::Transactions => KeyCode::Char('t'),
+            Action::Settings => KeyCode::Char('s'),
+            Action::Lock => KeyCode::Char('L'),
+            Action::Up => KeyCode::Up,
+            Action::Down => KeyCode::Down,
+            Action::NewAccount => KeyCode::Char('n'),
+            Action::NewAddress => KeyCode::Char('a'),
+            Action::Send => KeyCode::Char('p'),
+            Action::LabelTransaction => KeyCode::Char('l'),
+        };
+        KeyBinding::new(code, KeyModifiers::NONE)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Action::Quit => "quit",
+            Action::NextTab => "next-tab",
+            Action::Help => "help",
+            Action::Overview => "overview",
+            Action::Accounts => "accounts",
+            Action::Transactions => "transactions",
+            Action::Settings => "settings",
+            Action::Lock => "lock",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::NewAccount => "new-account",
+            Action::NewAddress => "new-address",
+            Action::Send => "send",
+            Action::LabelTransaction => "label-transaction",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A key plus the Ctrl/Alt modifiers that must be held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+    ("backspace", KeyCode::Backspace),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("delete", KeyCode::Delete),
+];
+
+impl KeyBinding {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Shift is ignored for characters, since it is already part of the
+    /// character (`?` arrives as shift + `?`).
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        let mut modifiers = key.modifiers;
+        if matches!(key.code, KeyCode::Char(_)) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        key.code == self.code && modifiers == self.modifiers
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            f.write_str("ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            f.write_str("alt+")?;
+        }
+        if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, code)| *code == self.code) {
+            return f.write_str(name);
+        }
+        match self.code {
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || KeymapError::InvalidKey(s.to_string());
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s.trim();
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if lower.starts_with("ctrl+") && rest.len() > 5 {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                let lower = rest.to_ascii_lowercase();
+                match NAMED_KEYS.iter().find(|(name, _)| *name == lower) {
+                    Some((_, code)) => *code,
+                    None => lower
+                        .strip_prefix('f')
+                        .and_then(|n| n.parse::<u8>().ok())
+                        .filter(|n| (1..=12).contains(n))
+                        .map(KeyCode::F)
+                        .ok_or_else(invalid)?,
+                }
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+/// Two actions that would fire on the same key press.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyConflict {
+    pub key: String,
+    pub first: Action,
+    pub second: Action,
+}
+
+impl fmt::Display for KeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' is bound to both {} and {}",
+            self.key, self.first, self.second
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    bindings: BTreeMap<Action, KeyBinding>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_binding()))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    /// Defaults with the settings-file overrides applied. Fails if a key
+    /// does not parse or the result has conflicts.
+    pub fn with_overrides(overrides: &BTreeMap<Action, String>) -> Result<Self, KeymapError> {
+        let mut keymap = Self::default();
+        for (action, key) in overrides {
+            keymap.bindings.insert(*action, key.parse()?);
+        }
+        let conflicts = keymap.conflicts();
+        if !conflicts.is_empty() {
+            return Err(KeymapError::Conflicts(conflicts));
+        }
+        Ok(keymap)
+    }
+
+    pub fn binding(&self, action: Action) -> KeyBinding {
+        self.bindings
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_binding())
+    }
+
+    /// Every pair of actions reachable from the same screen with the same
+    /// key. A screen action may share a key with the global action that
+    /// opens its own screen, which is a no-op there.
+    pub fn conflicts(&self) -> Vec<KeyConflict> {
+        let mut conflicts = Vec::new();
+        for (i, first) in Action::ALL.iter().enumerate() {
+            for second in &Action::ALL[i + 1..] {
+                let key = self.binding(*first);
+                if key != self.binding(*second) {
+                    continue;
+                }
+                let overlap = match (first.scope(), second.scope()) {
+                    (None, None) => true,
+                    (Some(a), Some(b)) => a == b,
+                    (None, Some(tab)) => first.target() != Some(tab),
+                    (Some(tab), None) => second.target() != Some(tab),
+                };
+                if overlap {
+                    conflicts.push(KeyConflict {
+                        key: key.to_string(),
+                        first: *first,
+                        second: *second,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// The action a key press triggers on `tab`. Screen actions take
+    /// precedence over global ones.
+    pub fn action_for(&self, key: &KeyEvent, tab: Tab) -> Option<Action> {
+        let find = |scope: Option<Tab>| {
+            Action::ALL
+                .into_iter()
+                .find(|action| action.scope() == scope && self.binding(*action).matches(key))
+        };
+        find(Some(tab)).or_else(|| find(None))
+    }
+
+    /// Bindings that differ from the defaults, as stored in settings
+    pub fn overrides(&self) -> BTreeMap<Action, String> {
+        self.bindings
+            .iter()
+            .filter(|(action, binding)| action.default_binding() != **binding)
+            .map(|(action, binding)| (*action, binding.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn default_keymap_is_conflict_free_and_resolves_per_screen() {
+        let keymap = KeyMap::default();
+        assert!(keymap.conflicts().is_empty());
+
+        let a = press(KeyCode::Char('a'));
+        assert_eq!(keymap.action_for(&a, Tab::Overview), Some(Action::Accounts));
+        assert_eq!(
+            keymap.action_for(&a, Tab::Accounts),
+            Some(Action::NewAddress)
+        );
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('n')), Tab::Overview),
+            None
+        );
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('p')), Tab::Accounts),
+            Some(Action::Send)
+        );
+
+        let question = KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT);
+        assert_eq!(
+            keymap.action_for(&question, Tab::Settings),
+            Some(Action::Help)
+        );
+        let ctrl_q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(&ctrl_q, Tab::Overview), None);
+    }
+
+    #[test]
+    fn conflicting_overrides_are_reported() {
+        // Two global actions on one key
+        let overrides = BTreeMap::from([(Action::Settings, "o".to_string())]);
+        let Err(KeymapError::Conflicts(conflicts)) = KeyMap::with_overrides(&overrides) else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(
+            conflicts,
+            vec![KeyConflict {
+                key: "o".to_string(),
+                first: Action::Overview,
+                second: Action::Settings,
+            }]
+        );
+
+        // A screen action shadowing a global one that still matters there
+        let overrides = BTreeMap::from([(Action::NewAccount, "t".to_string())]);
+        assert!(matches!(
+            KeyMap::with_overrides(&overrides),
+            Err(KeymapError::Conflicts(c)) if c.len() == 1
+        ));
+
+        // Actions on different screens may share a key
+        let overrides = BTreeMap::from([(Action::LabelTransaction, "n".to_string())]);
+        let keymap = KeyMap::with_overrides(&overrides).unwrap();
+        assert_eq!(keymap.overrides(), overrides);
+    }
+
+    #[test]
+    fn key_strings_round_trip() {
+        for key in ["q", "?", "tab", "ctrl+n", "alt+x", "f5", "space", "up"] {
+            let binding: KeyBinding = key.parse().unwrap();
+            assert_eq!(binding.to_string(), key);
+        }
+        assert_eq!(
+            "Ctrl+Enter".parse::<KeyBinding>(),
+            Ok(KeyBinding::new(KeyCode::Enter, KeyModifiers::CONTROL))
+        );
+        assert!("f13".parse::<KeyBinding>().is_err());
+        assert!("ctrl+".parse::<KeyBinding>().is_err());
+        assert!("".parse::<KeyBinding>().is_err());
+    }
+}
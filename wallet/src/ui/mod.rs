@@ -1 +1,53 @@
+pub mod keymap;
+pub mod send;
+pub mod theme;
 pub mod tui;
+pub mod wizard;
+
+use keymap::{Action, KeyMap, KeymapError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use theme::{default_theme_name, ColorDepth, Palette, ThemeDefinition, ThemeError};
+
+/// TUI appearance and key bindings stored in the wallet settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiPreferences {
+    #[serde(default = "default_theme_name")]
+    pub theme: String,
+    /// User-defined themes, selectable by name like the built-in ones
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub themes: Vec<ThemeDefinition>,
+    /// Overrides terminal detection, e.g. `ansi8` for a terminal that
+    /// misreports its capabilities
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color_depth: Option<ColorDepth>,
+    /// Key overrides for actions, e.g. `"settings": "f2"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keybindings: BTreeMap<Action, String>,
+}
+
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self {
+            theme: default_theme_name(),
+            themes: Vec::new(),
+            color_depth: None,
+            keybindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl UiPreferences {
+    /// Resolve the selected theme for a terminal of `detected` depth.
+    pub fn palette(&self, detected: ColorDepth) -> Result<Palette, ThemeError> {
+        Palette::resolve(
+            &self.theme,
+            &self.themes,
+            self.color_depth.unwrap_or(detected),
+        )
+    }
+
+    pub fn keymap(&self) -> Result<KeyMap, KeymapError> {
+        KeyMap::with_overrides(&self.keybindings)
+    }
+}
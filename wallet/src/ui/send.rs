@@ -0,0 +1,677 @@
+//! Send screen
+//!
+//! A terminal-free state machine for paying an address from one account:
+//! recipient, amount, fee rate, confirmation and the result. The TUI renders
+//! the current step and forwards user input; tests drive the same
+//! transitions directly.
+//!
+//! Nothing reaches the wallet before the user confirms. Leaving the fee step
+//! asks the [`SendBackend`] for a preview, which must not change the wallet;
+//! only confirming calls [`SendBackend::send`]. Backing out or cancelling at
+//! any earlier point leaves the wallet as it was, and every value entered so
+//! far is kept when stepping back.
+
+use bitcoin::network::Network;
+use bitcoin::Address;
+use std::str::FromStr;
+use thiserror::Error;
+
+use crate::display::{format_amount, DisplayUnit, NOVA_UNITS_PER_NOVA};
+use crate::fees::FeeRates;
+
+/// Base units per millinova
+pub const NOVA_UNITS_PER_MILLINOVA: u64 = NOVA_UNITS_PER_NOVA / 1_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SendError {
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("Invalid fee rate: {0}")]
+    InvalidFeeRate(String),
+    #[error("{0}")]
+    Wallet(String),
+    #[error("The payment has already been sent")]
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendStep {
+    Recipient,
+    Amount,
+    Fee,
+    /// Review the total debit, fee and change before sending
+    Confirm,
+    /// The payment was signed and recorded
+    Sent,
+}
+
+/// Unit the amount is entered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmountUnit {
+    Nova,
+    MilliNova,
+}
+
+impl AmountUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            AmountUnit::Nova => "NOVA",
+            AmountUnit::MilliNova => "mNOVA",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            AmountUnit::Nova => AmountUnit::MilliNova,
+            AmountUnit::MilliNova => AmountUnit::Nova,
+        }
+    }
+
+    fn base_units(self) -> u64 {
+        match self {
+            AmountUnit::Nova => NOVA_UNITS_PER_NOVA,
+            AmountUnit::MilliNova => NOVA_UNITS_PER_MILLINOVA,
+        }
+    }
+
+    fn decimals(self) -> usize {
+        match self {
+            AmountUnit::Nova => 8,
+            AmountUnit::MilliNova => 5,
+        }
+    }
+}
+
+/// Fee rate choices on the fee step, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeePreset {
+    Low,
+    Normal,
+    High,
+    /// A rate typed in by the user
+    Manual,
+}
+
+impl FeePreset {
+    pub const ALL: [FeePreset; 4] = [
+        FeePreset::Low,
+        FeePreset::Normal,
+        FeePreset::High,
+        FeePreset::Manual,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            FeePreset::Low => "Low priority",
+            FeePreset::Normal => "Normal priority",
+            FeePreset::High => "High priority",
+            FeePreset::Manual => "Manual rate",
+        }
+    }
+
+    /// The estimated rate behind the preset; `None` for a manual rate
+    pub fn rate(self, rates: &FeeRates) -> Option<u64> {
+        match self {
+            FeePreset::Low => Some(rates.low_priority),
+            FeePreset::Normal => Some(rates.normal_priority),
+            FeePreset::High => Some(rates.high_priority),
+            FeePreset::Manual => None,
+        }
+    }
+}
+
+/// A payment as entered on the send screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendRequest {
+    pub account: String,
+    pub recipient: String,
+    /// Base units paid to the recipient
+    pub amount: u64,
+    /// Base units per virtual byte
+    pub fee_rate: u64,
+}
+
+/// What a payment would spend, worked out without changing the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendPreview {
+    pub amount: u64,
+    pub fee: u64,
+    /// Zero when the excess is below the dust threshold and goes to the fee
+    pub change: u64,
+    pub change_address: Option<String>,
+    pub inputs: usize,
+}
+
+impl SendPreview {
+    /// What leaves the account: the amount plus the fee
+    pub fn total_debit(&self) -> u64 {
+        self.amount.saturating_add(self.fee)
+    }
+}
+
+/// Wallet operations behind the send screen
+pub trait SendBackend {
+    /// Select inputs and work out the fee and change of `request`. Must not
+    /// change the wallet: no change address is derived and nothing is
+    /// recorded.
+    fn preview(&self, request: &SendRequest) -> Result<SendPreview, String>;
+
+    /// Build, sign and record the payment. Returns the transaction id.
+    fn send(&mut self, request: &SendRequest) -> Result<String, String>;
+}
+
+/// Send screen state machine
+pub struct SendFlow {
+    account: String,
+    network: Network,
+    rates: Option<FeeRates>,
+    step: SendStep,
+    recipient: String,
+    amount_text: String,
+    unit: AmountUnit,
+    fee_preset: FeePreset,
+    manual_fee_rate: String,
+    request: Option<SendRequest>,
+    preview: Option<SendPreview>,
+    txid: Option<String>,
+}
+
+impl SendFlow {
+    /// Start a payment from `account`. Without fee estimates only a manual
+    /// rate is offered.
+    pub fn new(account: &str, network: Network, rates: Option<FeeRates>) -> Self {
+        Self {
+            account: account.to_string(),
+            network,
+            rates,
+            step: SendStep::Recipient,
+            recipient: String::new(),
+            amount_text: String::new(),
+            unit: AmountUnit::Nova,
+            fee_preset: if rates.is_some() {
+                FeePreset::Normal
+            } else {
+                FeePreset::Manual
+            },
+            manual_fee_rate: String::new(),
+            request: None,
+            preview: None,
+            txid: None,
+        }
+    }
+
+    pub fn step(&self) -> SendStep {
+        self.step
+    }
+
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    pub fn rates(&self) -> Option<&FeeRates> {
+        self.rates.as_ref()
+    }
+
+    pub fn recipient(&self) -> &str {
+        &self.recipient
+    }
+
+    pub fn set_recipient(&mut self, recipient: &str) {
+        self.recipient = recipient.trim().to_string();
+    }
+
+    /// Live validation of the recipient: `None` while it is empty
+    pub fn address_feedback(&self) -> Option<Result<(), SendError>> {
+        if self.recipient.is_empty() {
+            return None;
+        }
+        Some(self.check_recipient())
+    }
+
+    pub fn amount_text(&self) -> &str {
+        &self.amount_text
+    }
+
+    pub fn set_amount_text(&mut self, amount: &str) {
+        self.amount_text = amount.trim().to_string();
+    }
+
+    pub fn unit(&self) -> AmountUnit {
+        self.unit
+    }
+
+    /// Switch between NOVA and millinova, converting a valid amount so it
+    /// keeps its value
+    pub fn toggle_unit(&mut self) {
+        let next = self.unit.toggled();
+        if let Ok(amount) = parse_amount(&self.amount_text, self.unit) {
+            self.amount_text = format_in_unit(amount, next);
+        }
+        self.unit = next;
+    }
+
+    /// The entered amount in base units
+    pub fn amount(&self) -> Result<u64, SendError> {
+        parse_amount(&self.amount_text, self.unit)
+    }
+
+    pub fn fee_preset(&self) -> FeePreset {
+        self.fee_preset
+    }
+
+    /// Presets that can be chosen: all of them with estimates, otherwise
+    /// only a manual rate
+    pub fn fee_presets(&self) -> &'static [FeePreset] {
+        if self.rates.is_some() {
+            &FeePreset::ALL
+        } else {
+            &[FeePreset::Manual]
+        }
+    }
+
+    pub fn set_fee_preset(&mut self, preset: FeePreset) {
+        if self.fee_presets().contains(&preset) {
+            self.fee_preset = preset;
+        }
+    }
+
+    pub fn manual_fee_rate(&self) -> &str {
+        &self.manual_fee_rate
+    }
+
+    pub fn set_manual_fee_rate(&mut self, rate: &str) {
+        self.manual_fee_rate = rate.trim().to_string();
+    }
+
+    /// The chosen fee rate in base units per virtual byte
+    pub fn fee_rate(&self) -> Result<u64, SendError> {
+        let rate = match self.rates.as_ref().and_then(|r| self.fee_preset.rate(r)) {
+            Some(rate) => rate,
+            None => self.manual_fee_rate.parse::<u64>().map_err(|_| {
+                SendError::InvalidFeeRate("enter whole nova units per byte".to_string())
+            })?,
+        };
+        if rate == 0 {
+            return Err(SendError::InvalidFeeRate(
+                "must be at least 1 nova unit per byte".to_string(),
+            ));
+        }
+        Ok(rate)
+    }
+
+    /// The preview shown on the confirmation step
+    pub fn preview(&self) -> Option<&SendPreview> {
+        self.preview.as_ref()
+    }
+
+    /// Id of the sent transaction
+    pub fn txid(&self) -> Option<&str> {
+        self.txid.as_deref()
+    }
+
+    /// Validate the current step and advance. Leaving the fee step previews
+    /// the payment; leaving the confirmation step sends it. On error the
+    /// flow stays on the current step.
+    pub fn next<B: SendBackend + ?Sized>(
+        &mut self,
+        backend: &mut B,
+    ) -> Result<SendStep, SendError> {
+        self.step = match self.step {
+            SendStep::Recipient => {
+                self.check_recipient()?;
+                SendStep::Amount
+            }
+            SendStep::Amount => {
+                self.amount()?;
+                SendStep::Fee
+            }
+            SendStep::Fee => {
+                let request = SendRequest {
+                    account: self.account.clone(),
+                    recipient: self.recipient.clone(),
+                    amount: self.amount()?,
+                    fee_rate: self.fee_rate()?,
+                };
+                self.preview = Some(backend.preview(&request).map_err(SendError::Wallet)?);
+                self.request = Some(request);
+                SendStep::Confirm
+            }
+            SendStep::Confirm => {
+                let request = self
+                    .request
+                    .as_ref()
+                    .ok_or_else(|| SendError::Wallet("nothing to send".to_string()))?;
+                self.txid = Some(backend.send(request).map_err(SendError::Wallet)?);
+                SendStep::Sent
+            }
+            SendStep::Sent => return Err(SendError::Finished),
+        };
+        Ok(self.step)
+    }
+
+    /// Step back, keeping everything entered. Returns `None` on the first
+    /// step and after sending, where backing out means leaving the screen.
+    pub fn back(&mut self) -> Option<SendStep> {
+        self.step = match self.step {
+            SendStep::Recipient | SendStep::Sent => return None,
+            SendStep::Amount => SendStep::Recipient,
+            SendStep::Fee => SendStep::Amount,
+            SendStep::Confirm => {
+                self.request = None;
+                self.preview = None;
+                SendStep::Fee
+            }
+        };
+        Some(self.step)
+    }
+
+    /// Lines for the confirmation step, with addresses shortened to fit
+    /// `width` columns
+    pub fn confirmation_lines(&self, unit: DisplayUnit, width: usize) -> Vec<String> {
+        let (Some(request), Some(preview)) = (&self.request, &self.preview) else {
+            return Vec::new();
+        };
+        let fit = |text: &str| abbreviate(text, width.saturating_sub(LABEL_WIDTH));
+        let mut lines = vec![
+            format!("From:        {}", request.account),
+            format!("To:          {}", fit(&request.recipient)),
+            format!("Amount:      {}", format_amount(preview.amount, unit)),
+            format!(
+                "Fee:         {} ({} per byte)",
+                format_amount(preview.fee, unit),
+                request.fee_rate
+            ),
+            format!(
+                "Total debit: {}",
+                format_amount(preview.total_debit(), unit)
+            ),
+        ];
+        match &preview.change_address {
+            Some(address) if preview.change > 0 => {
+                lines.push(format!(
+                    "Change:      {}",
+                    format_amount(preview.change, unit)
+                ));
+                lines.push(format!("  to         {}", fit(address)));
+            }
+            _ => lines.push("Change:      none".to_string()),
+        }
+        lines.push(format!("Inputs:      {}", preview.inputs));
+        lines
+    }
+
+    fn check_recipient(&self) -> Result<(), SendError> {
+        Address::from_str(&self.recipient)
+            .map_err(|e| SendError::InvalidAddress(e.to_string()))?
+            .require_network(self.network)
+            .map_err(|e| SendError::InvalidAddress(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Width of the labels in [`SendFlow::confirmation_lines`]
+const LABEL_WIDTH: usize = 13;
+
+/// Shorten `text` to at most `max` characters by eliding its middle, so
+/// both ends of an address stay visible
+pub fn abbreviate(text: &str, max: usize) -> String {
+    let len = text.chars().count();
+    if len <= max {
+        return text.to_string();
+    }
+    if max < 3 {
+        return text.chars().take(max).collect();
+    }
+    let tail = (max - 1) / 2;
+    let head = max - 1 - tail;
+    let start: String = text.chars().take(head).collect();
+    let end: String = text.chars().skip(len - tail).collect();
+    format!("{}…{}", start, end)
+}
+
+/// Parse a decimal amount in `unit` into base units
+fn parse_amount(text: &str, unit: AmountUnit) -> Result<u64, SendError> {
+    let invalid = |reason: &str| SendError::InvalidAmount(reason.to_string());
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err(invalid("enter an amount"));
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid("use digits and one decimal point"));
+    }
+    if fraction.len() > unit.decimals() {
+        return Err(SendError::InvalidAmount(format!(
+            "at most {} decimal places in {}",
+            unit.decimals(),
+            unit.label()
+        )));
+    }
+    let whole = if whole.is_empty() {
+        0
+    } else {
+        whole.parse::<u64>().map_err(|_| invalid("too large"))?
+    };
+    let fraction = format!("{:0<width$}", fraction, width = unit.decimals())
+        .parse::<u64>()
+        .unwrap_or(0);
+    let amount = whole
+        .checked_mul(unit.base_units())
+        .and_then(|units| units.checked_add(fraction))
+        .ok_or_else(|| invalid("too large"))?;
+    if amount == 0 {
+        return Err(invalid("must be more than zero"));
+    }
+    Ok(amount)
+}
+
+/// `amount` base units as a decimal in `unit`, without trailing zeros
+fn format_in_unit(amount: u64, unit: AmountUnit) -> String {
+    let whole = amount / unit.base_units();
+    let fraction = amount % unit.base_units();
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let digits = format!("{:0width$}", fraction, width = unit.decimals());
+    format!("{}.{}", whole, digits.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    #[derive(Default)]
+    struct MockBackend {
+        previews: usize,
+        sent: Vec<SendRequest>,
+        fail_send: bool,
+    }
+
+    impl SendBackend for MockBackend {
+        fn preview(&self, request: &SendRequest) -> Result<SendPreview, String> {
+            let fee = request.fee_rate * 141;
+            Ok(SendPreview {
+                amount: request.amount,
+                fee,
+                change: 100_000_000 - request.amount - fee,
+                change_address: Some("tb1qchange".to_string()),
+                inputs: 1,
+            })
+        }
+
+        fn send(&mut self, request: &SendRequest) -> Result<String, String> {
+            if self.fail_send {
+                return Err("signer refused".to_string());
+            }
+            self.sent.push(request.clone());
+            Ok("ab".repeat(32))
+        }
+    }
+
+    fn rates() -> FeeRates {
+        FeeRates {
+            low_priority: 1,
+            normal_priority: 2,
+            high_priority: 5,
+        }
+    }
+
+    fn filled(backend: &mut MockBackend) -> SendFlow {
+        let mut flow = SendFlow::new("default", Network::Testnet, Some(rates()));
+        flow.set_recipient(RECIPIENT);
+        assert_eq!(flow.next(backend), Ok(SendStep::Amount));
+        flow.set_amount_text("0.25");
+        assert_eq!(flow.next(backend), Ok(SendStep::Fee));
+        flow
+    }
+
+    #[test]
+    fn happy_path_previews_then_sends_once() {
+        let mut backend = MockBackend::default();
+        let mut flow = filled(&mut backend);
+        flow.set_fee_preset(FeePreset::High);
+        assert_eq!(flow.next(&mut backend), Ok(SendStep::Confirm));
+        assert!(backend.sent.is_empty());
+
+        let preview = flow.preview().unwrap();
+        assert_eq!(preview.total_debit(), 25_000_000 + 5 * 141);
+        let lines = flow.confirmation_lines(DisplayUnit::Nova, 80);
+        assert!(lines.contains(&format!("To:          {}", RECIPIENT)));
+        assert!(lines.contains(&"Total debit: 0.25000705 NOVA".to_string()));
+        assert!(lines.contains(&"  to         tb1qchange".to_string()));
+
+        assert_eq!(flow.next(&mut backend), Ok(SendStep::Sent));
+        assert_eq!(flow.txid(), Some("ab".repeat(32).as_str()));
+        assert_eq!(
+            backend.sent,
+            [SendRequest {
+                account: "default".to_string(),
+                recipient: RECIPIENT.to_string(),
+                amount: 25_000_000,
+                fee_rate: 5,
+            }]
+        );
+        assert_eq!(flow.next(&mut backend), Err(SendError::Finished));
+        assert_eq!(flow.back(), None);
+        assert_eq!(backend.sent.len(), 1);
+    }
+
+    #[test]
+    fn invalid_entries_keep_the_current_step() {
+        let mut backend = MockBackend::default();
+        let mut flow = SendFlow::new("default", Network::Testnet, None);
+        assert!(flow.address_feedback().is_none());
+
+        // Last character changed: the checksum no longer matches
+        flow.set_recipient("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsy");
+        assert!(matches!(
+            flow.address_feedback(),
+            Some(Err(SendError::InvalidAddress(_)))
+        ));
+        assert!(flow.next(&mut backend).is_err());
+        // Right format, wrong network
+        flow.set_recipient("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        assert!(flow.next(&mut backend).is_err());
+        assert_eq!(flow.step(), SendStep::Recipient);
+
+        flow.set_recipient(RECIPIENT);
+        assert_eq!(flow.address_feedback(), Some(Ok(())));
+        flow.next(&mut backend).unwrap();
+        for amount in ["", "0", "1.000000001", "1,5", "-1", "184467440738"] {
+            flow.set_amount_text(amount);
+            assert!(
+                matches!(flow.next(&mut backend), Err(SendError::InvalidAmount(_))),
+                "{}",
+                amount
+            );
+        }
+        flow.set_amount_text(".5");
+        flow.next(&mut backend).unwrap();
+
+        // No estimates: only a manual rate, which must be entered
+        assert_eq!(flow.fee_presets(), [FeePreset::Manual]);
+        flow.set_fee_preset(FeePreset::Normal);
+        assert_eq!(flow.fee_preset(), FeePreset::Manual);
+        for rate in ["", "0", "1.5"] {
+            flow.set_manual_fee_rate(rate);
+            assert!(matches!(
+                flow.next(&mut backend),
+                Err(SendError::InvalidFeeRate(_))
+            ));
+        }
+        assert_eq!(flow.step(), SendStep::Fee);
+        flow.set_manual_fee_rate("3");
+        assert_eq!(flow.next(&mut backend), Ok(SendStep::Confirm));
+        assert_eq!(flow.preview().unwrap().amount, 50_000_000);
+    }
+
+    #[test]
+    fn back_keeps_entries_and_never_sends() {
+        let mut backend = MockBackend::default();
+        let mut flow = filled(&mut backend);
+        flow.next(&mut backend).unwrap();
+        assert_eq!(flow.back(), Some(SendStep::Fee));
+        assert!(flow.preview().is_none());
+        assert!(flow.confirmation_lines(DisplayUnit::Nova, 80).is_empty());
+        assert_eq!(flow.back(), Some(SendStep::Amount));
+        assert_eq!(flow.amount_text(), "0.25");
+        assert_eq!(flow.back(), Some(SendStep::Recipient));
+        assert_eq!(flow.recipient(), RECIPIENT);
+        assert_eq!(flow.back(), None);
+        assert!(backend.sent.is_empty());
+
+        // A failed send stays on the confirmation step
+        backend.fail_send = true;
+        let mut flow = filled(&mut backend);
+        flow.next(&mut backend).unwrap();
+        assert_eq!(
+            flow.next(&mut backend),
+            Err(SendError::Wallet("signer refused".to_string()))
+        );
+        assert_eq!(flow.step(), SendStep::Confirm);
+        assert!(flow.txid().is_none());
+    }
+
+    #[test]
+    fn unit_toggle_keeps_the_amount() {
+        let mut flow = SendFlow::new("default", Network::Testnet, Some(rates()));
+        flow.set_amount_text("1.5");
+        flow.toggle_unit();
+        assert_eq!(flow.unit(), AmountUnit::MilliNova);
+        assert_eq!(flow.amount_text(), "1500");
+        flow.set_amount_text("0.00001");
+        assert_eq!(flow.amount(), Ok(1));
+        flow.toggle_unit();
+        assert_eq!(flow.amount_text(), "0.00000001");
+        assert_eq!(flow.amount(), Ok(1));
+
+        // An unparsable amount is left for the user to fix
+        flow.set_amount_text("abc");
+        flow.toggle_unit();
+        assert_eq!(flow.amount_text(), "abc");
+        assert_eq!(flow.unit(), AmountUnit::MilliNova);
+    }
+
+    #[test]
+    fn narrow_terminals_elide_the_middle_of_addresses() {
+        assert_eq!(abbreviate(RECIPIENT, 100), RECIPIENT);
+        assert_eq!(abbreviate(RECIPIENT, 11), "tb1qw…pjzsx");
+        assert_eq!(abbreviate(RECIPIENT, 11).chars().count(), 11);
+        assert_eq!(abbreviate(RECIPIENT, 2), "tb");
+
+        let mut backend = MockBackend::default();
+        let mut flow = filled(&mut backend);
+        flow.next(&mut backend).unwrap();
+        let lines = flow.confirmation_lines(DisplayUnit::Nova, 30);
+        assert!(lines[1].starts_with("To:          tb1q"));
+        assert!(lines[1].ends_with("zsx"));
+        assert_eq!(lines[1].chars().count(), 30);
+    }
+}
@@ -0,0 +1,735 @@
+//! Color themes for the wallet TUI.
+//!
+//! Widgets never name a terminal color directly; they ask a [`Palette`] for
+//! the style of a [`ColorRole`]. A palette is resolved from a built-in theme
+//! or a theme definition in the wallet settings file, then degraded to what
+//! the terminal can display, so the whole pipeline runs without a terminal.
+
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ThemeError {
+    #[error("Unknown theme: {0}")]
+    UnknownTheme(String),
+    #[error("Invalid color '{0}': expected a color name, #rrggbb or a 0-255 palette index")]
+    InvalidColor(String),
+}
+
+/// What a piece of text means, independent of how it is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorRole {
+    /// Ordinary text and borders
+    Text,
+    /// Names, counts, hotkeys and text being edited
+    Accent,
+    /// The highlighted list entry or tab
+    Selection,
+    Heading,
+    /// Incoming funds, confirmations, success
+    Positive,
+    /// Outgoing funds, failures, errors
+    Negative,
+    /// Unconfirmed transactions
+    Pending,
+    Info,
+    /// De-emphasized detail such as hashes and archived balances
+    Muted,
+    /// Spending policy details and denials
+    Policy,
+}
+
+impl ColorRole {
+    pub const ALL: [ColorRole; 10] = [
+        ColorRole::Text,
+        ColorRole::Accent,
+        ColorRole::Selection,
+        ColorRole::Heading,
+        ColorRole::Positive,
+        ColorRole::Negative,
+        ColorRole::Pending,
+        ColorRole::Info,
+        ColorRole::Muted,
+        ColorRole::Policy,
+    ];
+}
+
+/// A terminal color as written in the settings file: a name such as
+/// `light-red`, `#rrggbb`, or a 256-color palette index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ThemeColor {
+    Reset,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    DarkGray,
+    LightRed,
+    LightGreen,
+    LightYellow,
+    LightBlue,
+    LightMagenta,
+    LightCyan,
+    White,
+    Rgb(u8, u8, u8),
+    Indexed(u8),
+}
+
+const NAMED: [(&str, ThemeColor); 17] = [
+    ("reset", ThemeColor::Reset),
+    ("black", ThemeColor::Black),
+    ("red", ThemeColor::Red),
+    ("green", ThemeColor::Green),
+    ("yellow", ThemeColor::Yellow),
+    ("blue", ThemeColor::Blue),
+    ("magenta", ThemeColor::Magenta),
+    ("cyan", ThemeColor::Cyan),
+    ("gray", ThemeColor::Gray),
+    ("dark-gray", ThemeColor::DarkGray),
+    ("light-red", ThemeColor::LightRed),
+    ("light-green", ThemeColor::LightGreen),
+    ("light-yellow", ThemeColor::LightYellow),
+    ("light-blue", ThemeColor::LightBlue),
+    ("light-magenta", ThemeColor::LightMagenta),
+    ("light-cyan", ThemeColor::LightCyan),
+    ("white", ThemeColor::White),
+];
+
+/// xterm's default RGB values for the 16 ANSI colors, in index order
+const ANSI_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const ANSI_COLORS: [ThemeColor; 16] = [
+    ThemeColor::Black,
+    ThemeColor::Red,
+    ThemeColor::Green,
+    ThemeColor::Yellow,
+    ThemeColor::Blue,
+    ThemeColor::Magenta,
+    ThemeColor::Cyan,
+    ThemeColor::Gray,
+    ThemeColor::DarkGray,
+    ThemeColor::LightRed,
+    ThemeColor::LightGreen,
+    ThemeColor::LightYellow,
+    ThemeColor::LightBlue,
+    ThemeColor::LightMagenta,
+    ThemeColor::LightCyan,
+    ThemeColor::White,
+];
+
+impl ThemeColor {
+    /// Closest color the terminal can show. On 8-color terminals bright
+    /// variants fall back to their base color and dark gray, which would
+    /// vanish on either background, becomes the terminal's default.
+    pub fn degrade(self, depth: ColorDepth) -> ThemeColor {
+        match depth {
+            ColorDepth::TrueColor => self,
+            ColorDepth::Indexed256 => match self {
+                ThemeColor::Rgb(r, g, b) => ThemeColor::Indexed(nearest_indexed(r, g, b)),
+                other => other,
+            },
+            ColorDepth::Ansi16 => match self.rgb() {
+                Some((r, g, b)) => nearest(&ANSI_COLORS, r, g, b),
+                None => self,
+            },
+            ColorDepth::Ansi8 => match self {
+                ThemeColor::Reset | ThemeColor::DarkGray => ThemeColor::Reset,
+                ThemeColor::LightRed => ThemeColor::Red,
+                ThemeColor::LightGreen => ThemeColor::Green,
+                ThemeColor::LightYellow => ThemeColor::Yellow,
+                ThemeColor::LightBlue => ThemeColor::Blue,
+                ThemeColor::LightMagenta => ThemeColor::Magenta,
+                ThemeColor::LightCyan => ThemeColor::Cyan,
+                ThemeColor::White => ThemeColor::Gray,
+                ThemeColor::Rgb(..) | ThemeColor::Indexed(_) => match self.rgb() {
+                    Some((r, g, b)) => nearest(&ANSI_COLORS[..8], r, g, b),
+                    None => ThemeColor::Reset,
+                },
+                base => base,
+            },
+        }
+    }
+
+    /// RGB value for palette-independent colors (RGB and 256-color indexes)
+    fn rgb(self) -> Option<(u8, u8, u8)> {
+        match self {
+            ThemeColor::Rgb(r, g, b) => Some((r, g, b)),
+            ThemeColor::Indexed(i) => Some(indexed_rgb(i)),
+            _ => None,
+        }
+    }
+
+    pub fn to_color(self) -> Color {
+        match self {
+            ThemeColor::Reset => Color::Reset,
+            ThemeColor::Black => Color::Black,
+            ThemeColor::Red => Color::Red,
+            ThemeColor::Green => Color::Green,
+            ThemeColor::Yellow => Color::Yellow,
+            ThemeColor::Blue => Color::Blue,
+            ThemeColor::Magenta => Color::Magenta,
+            ThemeColor::Cyan => Color::Cyan,
+            ThemeColor::Gray => Color::Gray,
+            ThemeColor::DarkGray => Color::DarkGray,
+            ThemeColor::LightRed => Color::LightRed,
+            ThemeColor::LightGreen => Color::LightGreen,
+            ThemeColor::LightYellow => Color::LightYellow,
+            ThemeColor::LightBlue => Color::LightBlue,
+            ThemeColor::LightMagenta => Color::LightMagenta,
+            ThemeColor::LightCyan => Color::LightCyan,
+            ThemeColor::White => Color::White,
+            ThemeColor::Rgb(r, g, b) => Color::Rgb(r, g, b),
+            ThemeColor::Indexed(i) => Color::Indexed(i),
+        }
+    }
+}
+
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match index {
+        0..=15 => ANSI_RGB[index as usize],
+        16..=231 => {
+            let i = index - 16;
+            (
+                LEVELS[(i / 36) as usize],
+                LEVELS[(i / 6 % 6) as usize],
+                LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).unsigned_abs().pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+fn nearest(candidates: &[ThemeColor], r: u8, g: u8, b: u8) -> ThemeColor {
+    candidates
+        .iter()
+        .zip(ANSI_RGB.iter())
+        .min_by_key(|(_, rgb)| distance(**rgb, (r, g, b)))
+        .map(|(color, _)| *color)
+        .unwrap_or(ThemeColor::Reset)
+}
+
+/// Closest entry in the 6x6x6 cube or gray ramp of the 256-color palette
+fn nearest_indexed(r: u8, g: u8, b: u8) -> u8 {
+    (16..=255u8)
+        .min_by_key(|i| distance(indexed_rgb(*i), (r, g, b)))
+        .unwrap_or(16)
+}
+
+impl fmt::Display for ThemeColor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeColor::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            ThemeColor::Indexed(i) => write!(f, "{}", i),
+            named => {
+                let name = NAMED
+                    .iter()
+                    .find(|(_, color)| color == named)
+                    .map(|(name, _)| *name)
+                    .unwrap_or("reset");
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+impl FromStr for ThemeColor {
+    type Err = ThemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().to_ascii_lowercase();
+        if let Some(hex) = value.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(ThemeColor::Rgb(r, g, b)),
+                _ => Err(ThemeError::InvalidColor(s.to_string())),
+            };
+        }
+        if let Ok(index) = value.parse::<u8>() {
+            return Ok(ThemeColor::Indexed(index));
+        }
+        let value = value.replace('_', "-");
+        NAMED
+            .iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, color)| *color)
+            .ok_or_else(|| ThemeError::InvalidColor(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for ThemeColor {
+    type Error = ThemeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        color.to_string()
+    }
+}
+
+/// How many colors the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorDepth {
+    Ansi8,
+    Ansi16,
+    Indexed256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// Read `COLORTERM` and `TERM` from the environment.
+    pub fn detect() -> Self {
+        Self::from_env(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if matches!(colorterm, Some("truecolor") | Some("24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match term.unwrap_or_default() {
+            t if t.contains("256color") => ColorDepth::Indexed256,
+            t if t.contains("16color") => ColorDepth::Ansi16,
+            "" | "dumb" | "ansi" | "linux" | "vt100" | "vt220" | "xterm" | "screen" => {
+                ColorDepth::Ansi8
+            }
+            _ => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// How one role is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoleStyle {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fg: Option<ThemeColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bg: Option<ThemeColor>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub underline: bool,
+    #[serde(default)]
+    pub reversed: bool,
+}
+
+impl RoleStyle {
+    const fn fg(color: ThemeColor) -> Self {
+        Self {
+            fg: Some(color),
+            bg: None,
+            bold: false,
+            underline: false,
+            reversed: false,
+        }
+    }
+
+    const fn plain() -> Self {
+        Self {
+            fg: None,
+            bg: None,
+            bold: false,
+            underline: false,
+            reversed: false,
+        }
+    }
+
+    const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    const fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    const fn reversed(mut self) -> Self {
+        self.reversed = true;
+        self
+    }
+
+    fn to_style(self, depth: ColorDepth) -> Style {
+        let mut style = Style::default();
+        if let Some(fg) = self.fg {
+            style = style.fg(fg.degrade(depth).to_color());
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg.degrade(depth).to_color());
+        }
+        let mut modifiers = Modifier::empty();
+        if self.bold {
+            modifiers |= Modifier::BOLD;
+        }
+        if self.underline {
+            modifiers |= Modifier::UNDERLINED;
+        }
+        if self.reversed {
+            modifiers |= Modifier::REVERSED;
+        }
+        style.add_modifier(modifiers)
+    }
+}
+
+/// A user theme from the settings file. Roles it leaves out come from
+/// `base`, a built-in theme name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThemeDefinition {
+    pub name: String,
+    #[serde(default = "default_theme_name")]
+    pub base: String,
+    #[serde(default)]
+    pub roles: BTreeMap<ColorRole, RoleStyle>,
+}
+
+pub fn default_theme_name() -> String {
+    BuiltinTheme::Dark.name().to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinTheme {
+    Dark,
+    Light,
+    HighContrast,
+    Monochrome,
+}
+
+impl BuiltinTheme {
+    pub const ALL: [BuiltinTheme; 4] = [
+        BuiltinTheme::Dark,
+        BuiltinTheme::Light,
+        BuiltinTheme::HighContrast,
+        BuiltinTheme::Monochrome,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinTheme::Dark => "dark",
+            BuiltinTheme::Light => "light",
+            BuiltinTheme::HighContrast => "high-contrast",
+            BuiltinTheme::Monochrome => "monochrome",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|theme| theme.name() == name)
+    }
+
+    pub fn role(self, role: ColorRole) -> RoleStyle {
+        use ColorRole as R;
+        use ThemeColor as C;
+        match self {
+            // The original wallet colors
+            BuiltinTheme::Dark => match role {
+                R::Text => RoleStyle::fg(C::White),
+                R::Accent | R::Pending => RoleStyle::fg(C::Yellow),
+                R::Selection | R::Heading => RoleStyle::fg(C::Yellow).bold(),
+                R::Positive => RoleStyle::fg(C::Green),
+                R::Negative => RoleStyle::fg(C::Red),
+                R::Info => RoleStyle::fg(C::Blue),
+                R::Muted => RoleStyle::fg(C::DarkGray),
+                R::Policy => RoleStyle::fg(C::Magenta),
+            },
+            // Nothing pale: yellow and light grays wash out on white
+            BuiltinTheme::Light => match role {
+                R::Text => RoleStyle::fg(C::Black),
+                R::Accent => RoleStyle::fg(C::Magenta),
+                R::Selection => RoleStyle::fg(C::Magenta).bold(),
+                R::Heading => RoleStyle::fg(C::Blue).bold(),
+                R::Positive => RoleStyle::fg(C::Green),
+                R::Negative => RoleStyle::fg(C::Red),
+                R::Pending => RoleStyle::fg(C::Rgb(175, 95, 0)),
+                R::Info => RoleStyle::fg(C::Blue),
+                R::Muted => RoleStyle::fg(C::DarkGray),
+                R::Policy => RoleStyle::fg(C::Rgb(95, 0, 135)),
+            },
+            // Bright colors plus a non-color cue for every status, so
+            // red/green is never the only difference
+            BuiltinTheme::HighContrast => match role {
+                R::Text | R::Muted => RoleStyle::fg(C::White),
+                R::Accent | R::Pending => RoleStyle::fg(C::LightYellow).bold(),
+                R::Selection => RoleStyle::fg(C::White).bold().reversed(),
+                R::Heading => RoleStyle::fg(C::White).bold().underline(),
+                R::Positive => RoleStyle::fg(C::LightCyan).bold(),
+                R::Negative => RoleStyle::fg(C::LightRed).bold().underline(),
+                R::Info => RoleStyle::fg(C::LightCyan),
+                R::Policy => RoleStyle::fg(C::LightMagenta).underline(),
+            },
+            BuiltinTheme::Monochrome => match role {
+                R::Text | R::Info | R::Muted => RoleStyle::plain(),
+                R::Accent | R::Positive => RoleStyle::plain().bold(),
+                R::Selection => RoleStyle::plain().reversed(),
+                R::Heading => RoleStyle::plain().bold().underline(),
+                R::Negative => RoleStyle::plain().bold().underline(),
+                R::Pending | R::Policy => RoleStyle::plain().underline(),
+            },
+        }
+    }
+}
+
+/// Role styles resolved for one theme and terminal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    name: String,
+    depth: ColorDepth,
+    roles: BTreeMap<ColorRole, RoleStyle>,
+}
+
+impl Palette {
+    /// Resolve `name` against the built-in themes and `custom` definitions.
+    /// A custom theme may shadow a built-in one of the same name.
+    pub fn resolve(
+        name: &str,
+        custom: &[ThemeDefinition],
+        depth: ColorDepth,
+    ) -> Result<Self, ThemeError> {
+        let (base, overrides) = match custom.iter().find(|theme| theme.name == name) {
+            Some(theme) => {
+                let base = BuiltinTheme::from_name(&theme.base)
+                    .ok_or_else(|| ThemeError::UnknownTheme(theme.base.clone()))?;
+                (base, Some(&theme.roles))
+            }
+            None => (
+                BuiltinTheme::from_name(name)
+                    .ok_or_else(|| ThemeError::UnknownTheme(name.to_string()))?,
+                None,
+            ),
+        };
+        let roles = ColorRole::ALL
+            .into_iter()
+            .map(|role| {
+                let style = overrides
+                    .and_then(|roles| roles.get(&role).copied())
+                    .unwrap_or_else(|| base.role(role));
+                (role, style)
+            })
+            .collect();
+        Ok(Self {
+            name: name.to_string(),
+            depth,
+            roles,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn depth(&self) -> ColorDepth {
+        self.depth
+    }
+
+    pub fn role(&self, role: ColorRole) -> RoleStyle {
+        self.roles.get(&role).copied().unwrap_or_default()
+    }
+
+    pub fn style(&self, role: ColorRole) -> Style {
+        self.role(role).to_style(self.depth)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        let roles = ColorRole::ALL
+            .into_iter()
+            .map(|role| (role, BuiltinTheme::Dark.role(role)))
+            .collect();
+        Self {
+            name: default_theme_name(),
+            depth: ColorDepth::TrueColor,
+            roles,
+        }
+    }
+}
+
+/// Names selectable in the settings screen: built-ins, then custom themes
+/// that do not shadow one.
+pub fn theme_names(custom: &[ThemeDefinition]) -> Vec<String> {
+    let mut names: Vec<String> = BuiltinTheme::ALL
+        .iter()
+        .map(|theme| theme.name().to_string())
+        .collect();
+    for theme in custom {
+        if !names.contains(&theme.name) {
+            names.push(theme.name.clone());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_builtin_theme_resolves_every_role() {
+        for theme in BuiltinTheme::ALL {
+            let palette = Palette::resolve(theme.name(), &[], ColorDepth::TrueColor).unwrap();
+            for role in ColorRole::ALL {
+                assert_eq!(palette.role(role), theme.role(role));
+            }
+        }
+
+        let dark = Palette::resolve("dark", &[], ColorDepth::TrueColor).unwrap();
+        assert_eq!(dark.style(ColorRole::Positive).fg, Some(Color::Green));
+        assert_eq!(dark.style(ColorRole::Negative).fg, Some(Color::Red));
+
+        let light = Palette::resolve("light", &[], ColorDepth::TrueColor).unwrap();
+        assert_eq!(light.style(ColorRole::Text).fg, Some(Color::Black));
+        assert_ne!(light.style(ColorRole::Accent).fg, Some(Color::Yellow));
+
+        // High contrast never relies on hue alone for good/bad
+        let hc = Palette::resolve("high-contrast", &[], ColorDepth::TrueColor).unwrap();
+        let (good, bad) = (hc.role(ColorRole::Positive), hc.role(ColorRole::Negative));
+        assert_ne!((good.bold, good.underline), (bad.bold, bad.underline));
+
+        let mono = Palette::resolve("monochrome", &[], ColorDepth::TrueColor).unwrap();
+        for role in ColorRole::ALL {
+            assert_eq!(mono.role(role).fg, None, "{:?}", role);
+        }
+        assert!(mono
+            .style(ColorRole::Selection)
+            .add_modifier
+            .contains(Modifier::REVERSED));
+
+        assert_eq!(
+            Palette::resolve("solarized", &[], ColorDepth::TrueColor),
+            Err(ThemeError::UnknownTheme("solarized".to_string()))
+        );
+    }
+
+    #[test]
+    fn custom_theme_overrides_roles_on_its_base() {
+        let custom = ThemeDefinition {
+            name: "mine".to_string(),
+            base: "light".to_string(),
+            roles: BTreeMap::from([(
+                ColorRole::Positive,
+                RoleStyle::fg(ThemeColor::Rgb(0, 135, 0)).bold(),
+            )]),
+        };
+        let palette = Palette::resolve("mine", &[custom.clone()], ColorDepth::TrueColor).unwrap();
+        assert_eq!(
+            palette.style(ColorRole::Positive),
+            Style::default()
+                .fg(Color::Rgb(0, 135, 0))
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            palette.role(ColorRole::Text),
+            BuiltinTheme::Light.role(ColorRole::Text)
+        );
+
+        let orphan = ThemeDefinition {
+            base: "nope".to_string(),
+            ..custom
+        };
+        assert_eq!(
+            Palette::resolve("mine", &[orphan], ColorDepth::TrueColor),
+            Err(ThemeError::UnknownTheme("nope".to_string()))
+        );
+    }
+
+    #[test]
+    fn colors_degrade_to_eight_color_terminals() {
+        let depth = ColorDepth::Ansi8;
+        assert_eq!(ThemeColor::LightRed.degrade(depth), ThemeColor::Red);
+        assert_eq!(ThemeColor::LightCyan.degrade(depth), ThemeColor::Cyan);
+        assert_eq!(ThemeColor::White.degrade(depth), ThemeColor::Gray);
+        assert_eq!(ThemeColor::DarkGray.degrade(depth), ThemeColor::Reset);
+        assert_eq!(ThemeColor::Green.degrade(depth), ThemeColor::Green);
+        assert_eq!(
+            ThemeColor::Rgb(255, 165, 0).degrade(depth),
+            ThemeColor::Yellow
+        );
+        assert_eq!(ThemeColor::Indexed(196).degrade(depth), ThemeColor::Red);
+        assert_eq!(ThemeColor::Indexed(12).degrade(depth), ThemeColor::Blue);
+
+        assert_eq!(
+            ThemeColor::Rgb(255, 0, 0).degrade(ColorDepth::Ansi16),
+            ThemeColor::LightRed
+        );
+        assert_eq!(
+            ThemeColor::Rgb(255, 0, 0).degrade(ColorDepth::Indexed256),
+            ThemeColor::Indexed(196)
+        );
+
+        // A whole palette degrades, keeping its modifiers
+        let hc = Palette::resolve("high-contrast", &[], depth).unwrap();
+        let negative = hc.style(ColorRole::Negative);
+        assert_eq!(negative.fg, Some(Color::Red));
+        assert!(negative.add_modifier.contains(Modifier::UNDERLINED));
+    }
+
+    #[test]
+    fn color_names_round_trip_and_terminals_are_detected() {
+        for color in [
+            ThemeColor::DarkGray,
+            ThemeColor::LightMagenta,
+            ThemeColor::Rgb(1, 2, 255),
+            ThemeColor::Indexed(42),
+        ] {
+            assert_eq!(color.to_string().parse::<ThemeColor>(), Ok(color));
+        }
+        assert_eq!("Light_Blue".parse(), Ok(ThemeColor::LightBlue));
+        assert!("#12345".parse::<ThemeColor>().is_err());
+        assert!("chartreuse".parse::<ThemeColor>().is_err());
+
+        assert_eq!(
+            ColorDepth::from_env(Some("truecolor"), Some("xterm")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            ColorDepth::from_env(None, Some("xterm-256color")),
+            ColorDepth::Indexed256
+        );
+        assert_eq!(ColorDepth::from_env(None, Some("linux")), ColorDepth::Ansi8);
+        assert_eq!(ColorDepth::from_env(None, None), ColorDepth::Ansi8);
+        assert_eq!(ColorDepth::from_env(None, Some("rxvt")), ColorDepth::Ansi16);
+    }
+}
@@ -8,18 +8,35 @@ use crossterm::{
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Frame, Terminal,
 };
 use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
 
+use super::keymap::{Action, KeyMap, Tab};
+use super::send::{FeePreset, SendBackend, SendError, SendFlow, SendStep};
+use super::theme::{theme_names, ColorDepth, ColorRole, Palette};
+use super::wizard::{
+    SetupWizard, WalletSource, WizardError, WizardOutcome, WizardStep, ACCOUNT_TYPES, NETWORKS,
+};
+use super::UiPreferences;
 use crate::{
-    hdwallet::{AccountType, HDAddress, HDWallet},
+    balance_cache::BalanceCache,
+    clock::{footer_warning, ClockWatch, NodeClock},
+    display::{format_amount, format_signed_amount, DisplayPreferences},
+    fees::FeeEstimator,
+    hdwallet::{AccountType, AddressFilter, AddressState, HDAddress, HDWallet, HDWalletError},
     history::{TransactionDirection, TransactionHistory, TransactionStatus},
+    rates::{CachedRateProvider, FiatRate, RateProvider},
+    seed_vault::DEFAULT_AUTO_LOCK,
+    settings::WalletSettings,
 };
 use supernova_core::storage::utxo_set::UtxoSet;
+use zeroize::Zeroize;
 
 #[derive(Debug)]
 pub enum InputMode {
@@ -27,6 +44,17 @@ pub enum InputMode {
     AccountCreation,
     TransactionLabeling,
     AddressDisplay,
+    /// Passphrase entry for an encrypted wallet; input is masked
+    Unlock,
+    /// The send screen, which takes over the main area
+    Send,
+}
+
+/// An operation interrupted by a locked wallet, retried after unlocking
+#[derive(Debug)]
+enum LockedAction {
+    CreateAccount(String),
+    NewAddress(String),
 }
 
 #[derive(Debug)]
@@ -34,6 +62,19 @@ pub enum Message {
     Info(String),
     Success(String),
     Error(String),
+    /// A spend refused by the account's spending policy.
+    PolicyDenied(String),
+}
+
+impl Message {
+    /// Map a wallet error to a status message, keeping policy denials
+    /// distinct from ordinary failures.
+    fn from_wallet_error(context: &str, e: &HDWalletError) -> Self {
+        match e {
+            HDWalletError::Policy(violation) => Message::PolicyDenied(violation.to_string()),
+            other => Message::Error(format!("{}: {}", context, other)),
+        }
+    }
 }
 
 pub struct WalletTui {
@@ -48,14 +89,28 @@ pub struct WalletTui {
     last_generated_address: Option<HDAddress>,
     selected_transaction: Option<String>, // Transaction hash
     utxo_set: UtxoSet,                    // Add UTXO set
-}
-
-#[derive(PartialEq, Clone, Copy)]
-enum Tab {
-    Overview,
-    Accounts,
-    Transactions,
-    Help,
+    balances: BalanceCache,
+    display: DisplayPreferences,
+    /// Optional fiat rate source; display only, never used to build transactions.
+    rate_source: Option<CachedRateProvider<Box<dyn RateProvider>>>,
+    ui: UiPreferences,
+    palette: Palette,
+    keymap: KeyMap,
+    /// Highlighted theme on the settings screen
+    themes_state: ListState,
+    /// Where theme changes are saved; `None` keeps them for this session
+    settings_dir: Option<PathBuf>,
+    /// Compares the local clock with the node's
+    clock: Option<ClockWatch<Box<dyn NodeClock>>>,
+    pending_unlock: Option<LockedAction>,
+    /// How long an unlock from the passphrase prompt lasts
+    auto_lock: Duration,
+    /// Fee rates offered as presets on the send screen
+    fee_estimator: Option<Box<dyn FeeEstimator>>,
+    /// The payment being entered on the send screen
+    send: Option<SendFlow>,
+    /// Whether this session was started with a send backend
+    can_send: bool,
 }
 
 impl WalletTui {
@@ -75,17 +130,105 @@ impl WalletTui {
             last_generated_address: None,
             selected_transaction: None,
             utxo_set: UtxoSet::new_in_memory(1000), // Create in-memory UTXO set
+            balances: BalanceCache::new(),
+            display: DisplayPreferences::default(),
+            rate_source: None,
+            ui: UiPreferences::default(),
+            palette: UiPreferences::default()
+                .palette(ColorDepth::detect())
+                .unwrap_or_default(),
+            keymap: KeyMap::default(),
+            themes_state: ListState::default(),
+            settings_dir: None,
+            clock: None,
+            pending_unlock: None,
+            auto_lock: DEFAULT_AUTO_LOCK,
+            fee_estimator: None,
+            send: None,
+            can_send: false,
         })
     }
 
+    /// Apply the user's theme and key bindings. Invalid preferences leave
+    /// the defaults in place and are reported in the status bar.
+    pub fn with_ui_preferences(mut self, ui: UiPreferences) -> Self {
+        match ui.palette(ColorDepth::detect()) {
+            Ok(palette) => self.palette = palette,
+            Err(e) => self.message = Some(Message::Error(format!("Theme: {}", e))),
+        }
+        match ui.keymap() {
+            Ok(keymap) => self.keymap = keymap,
+            Err(e) => self.message = Some(Message::Error(format!("Key bindings: {}", e))),
+        }
+        self.ui = ui;
+        self
+    }
+
+    /// Save theme changes made in the settings screen to this wallet directory.
+    pub fn with_settings_dir(mut self, wallet_dir: PathBuf) -> Self {
+        self.settings_dir = Some(wallet_dir);
+        self
+    }
+
+    /// Relock an encrypted wallet this long after each unlock.
+    pub fn with_auto_lock(mut self, timeout: Duration) -> Self {
+        self.auto_lock = timeout;
+        self
+    }
+
+    /// Warn in the footer when the local clock disagrees with the node's.
+    pub fn with_node_clock(mut self, source: Box<dyn NodeClock>) -> Self {
+        self.clock = Some(ClockWatch::new(source, Duration::from_secs(300)));
+        self
+    }
+
+    /// Offer the node's fee estimates as presets on the send screen.
+    pub fn with_fee_estimator(mut self, estimator: Box<dyn FeeEstimator>) -> Self {
+        self.fee_estimator = Some(estimator);
+        self
+    }
+
+    fn style(&self, role: ColorRole) -> Style {
+        self.palette.style(role)
+    }
+
+    fn key(&self, action: Action) -> String {
+        self.keymap.binding(action).to_string()
+    }
+
+    /// Apply the user's unit and fiat display preferences.
+    pub fn with_display_preferences(mut self, display: DisplayPreferences) -> Self {
+        self.rate_source = display.build_rate_source();
+        self.display = display;
+        self
+    }
+
+    fn current_rate(&self) -> Option<FiatRate> {
+        self.display.current_rate(self.rate_source.as_ref())
+    }
+
     pub fn run(&mut self) -> Result<(), io::Error> {
+        self.run_terminal(None)
+    }
+
+    /// [`run`](Self::run) with the send screen enabled: payments entered
+    /// there are previewed and sent through `backend`.
+    pub fn run_with_send_backend(
+        &mut self,
+        backend: &mut dyn SendBackend,
+    ) -> Result<(), io::Error> {
+        self.run_terminal(Some(backend))
+    }
+
+    fn run_terminal(&mut self, backend: Option<&mut dyn SendBackend>) -> Result<(), io::Error> {
+        self.can_send = backend.is_some();
         enable_raw_mode()?;
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
 
-        let res = self.run_app(&mut terminal);
+        let res = self.run_app(&mut terminal, backend);
 
         disable_raw_mode()?;
         execute!(
@@ -101,18 +244,31 @@ impl WalletTui {
     fn run_app<B: ratatui::backend::Backend>(
         &mut self,
         terminal: &mut Terminal<B>,
+        mut backend: Option<&mut dyn SendBackend>,
     ) -> Result<(), io::Error> {
         loop {
             terminal.draw(|f| self.render(f))?;
 
-            if let Event::Key(key) = event::read()? {
-                match self.input_mode {
-                    InputMode::Normal => self.handle_normal_mode(key)?,
-                    InputMode::AccountCreation => self.handle_account_creation_mode(key)?,
-                    InputMode::TransactionLabeling => self.handle_transaction_labeling_mode(key)?,
-                    InputMode::AddressDisplay => self.handle_address_display_mode(key)?,
+            // Poll rather than block so the auto-lock fires while idle
+            if event::poll(Duration::from_secs(1))? {
+                if let Event::Key(key) = event::read()? {
+                    match self.input_mode {
+                        InputMode::Normal => self.handle_normal_mode(key)?,
+                        InputMode::AccountCreation => self.handle_account_creation_mode(key)?,
+                        InputMode::TransactionLabeling => {
+                            self.handle_transaction_labeling_mode(key)?
+                        }
+                        InputMode::AddressDisplay => self.handle_address_display_mode(key)?,
+                        InputMode::Unlock => self.handle_unlock_mode(key)?,
+                        InputMode::Send => self.handle_send_mode(key, backend.as_deref_mut())?,
+                    }
                 }
             }
+            if self.wallet.lock_if_expired() {
+                self.message = Some(Message::Info(
+                    "Auto-lock timeout reached; wallet locked".to_string(),
+                ));
+            }
         }
     }
 
@@ -131,15 +287,14 @@ impl WalletTui {
             .split(f.size());
 
         // Render tabs
-        let titles = ["Overview", "Accounts", "Transactions", "Help"];
         let tabs = Tabs::new(
-            titles
+            Tab::ALL
                 .iter()
                 .map(|t| {
-                    let (first, rest) = t.split_at(1);
+                    let (first, rest) = t.title().split_at(1);
                     Line::from(vec![
-                        Span::styled(first, Style::default().fg(Color::Yellow)),
-                        Span::styled(rest, Style::default().fg(Color::White)),
+                        Span::styled(first, self.style(ColorRole::Accent)),
+                        Span::styled(rest, self.style(ColorRole::Text)),
                     ])
                 })
                 .collect::<Vec<_>>(),
@@ -149,27 +304,31 @@ impl WalletTui {
                 .borders(Borders::ALL)
                 .title("supernova Wallet"),
         )
-        .select(self.current_tab as usize)
-        .style(Style::default().fg(Color::White))
-        .highlight_style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        );
+        .select(
+            Tab::ALL
+                .iter()
+                .position(|t| *t == self.current_tab)
+                .unwrap_or(0),
+        )
+        .style(self.style(ColorRole::Text))
+        .highlight_style(self.style(ColorRole::Selection));
 
         f.render_widget(tabs, chunks[0]);
 
-        // Render main content based on current tab
+        // Render main content based on current tab; the send screen
+        // replaces it while open
         match self.current_tab {
+            _ if matches!(self.input_mode, InputMode::Send) => self.render_send(f, chunks[1]),
             Tab::Overview => self.render_overview(f, chunks[1]),
             Tab::Accounts => self.render_accounts(f, chunks[1]),
             Tab::Transactions => self.render_transactions(f, chunks[1]),
+            Tab::Settings => self.render_settings(f, chunks[1]),
             Tab::Help => self.render_help(f, chunks[1]),
         }
 
         // Render input prompt if in edit mode
         match self.input_mode {
-            InputMode::Normal => self.render_status_bar(f, chunks[2]),
+            InputMode::Normal | InputMode::Send => self.render_status_bar(f, chunks[2]),
             InputMode::AccountCreation => {
                 self.render_input_prompt(f, chunks[2], "Enter account name: ")
             }
@@ -177,6 +336,7 @@ impl WalletTui {
                 self.render_input_prompt(f, chunks[2], "Enter transaction label: ")
             }
             InputMode::AddressDisplay => self.render_address_display(f, chunks[2]),
+            InputMode::Unlock => self.render_unlock_prompt(f, chunks[2]),
         }
     }
 
@@ -185,50 +345,97 @@ impl WalletTui {
             Some(Message::Info(msg)) => Line::from(vec![
                 Span::styled(
                     "INFO: ",
-                    Style::default()
-                        .fg(Color::Blue)
-                        .add_modifier(Modifier::BOLD),
+                    self.style(ColorRole::Info).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(msg),
             ]),
             Some(Message::Success(msg)) => Line::from(vec![
                 Span::styled(
                     "SUCCESS: ",
-                    Style::default()
-                        .fg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
+                    self.style(ColorRole::Positive).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(msg),
             ]),
             Some(Message::Error(msg)) => Line::from(vec![
                 Span::styled(
                     "ERROR: ",
-                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    self.style(ColorRole::Negative).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(msg),
+            ]),
+            Some(Message::PolicyDenied(msg)) => Line::from(vec![
+                Span::styled(
+                    "DENIED BY POLICY: ",
+                    self.style(ColorRole::Policy).add_modifier(Modifier::BOLD),
                 ),
                 Span::raw(msg),
             ]),
+            None if self.send.is_some() => Line::from(self.send_hints()),
             None => {
+                let help = self.key(Action::Help);
                 let help_text = match self.current_tab {
-                    Tab::Overview => "Press ? for help",
-                    Tab::Accounts => {
-                        "Press n to create new account | a to generate address | ? for help"
-                    }
-                    Tab::Transactions => "Press l to label transaction | ? for help",
-                    Tab::Help => "Press q to quit help | arrows to navigate",
+                    Tab::Overview => format!("Press {} for help", help),
+                    Tab::Accounts => format!(
+                        "Press {} to create new account | {} to generate address | {} to send | {} for help",
+                        self.key(Action::NewAccount),
+                        self.key(Action::NewAddress),
+                        self.key(Action::Send),
+                        help
+                    ),
+                    Tab::Transactions => format!(
+                        "Press {} to label transaction | {} for help",
+                        self.key(Action::LabelTransaction),
+                        help
+                    ),
+                    Tab::Settings => format!(
+                        "{}/{} to choose a theme | Enter to apply | {} for help",
+                        self.key(Action::Up),
+                        self.key(Action::Down),
+                        help
+                    ),
+                    Tab::Help => format!(
+                        "Press {} to quit help | arrows to navigate",
+                        self.key(Action::Quit)
+                    ),
                 };
                 Line::from(help_text)
             }
         };
 
-        let status_bar = Paragraph::new(status_text)
-            .block(Block::default().borders(Borders::ALL).title("Status"));
+        let mut title = vec![Span::raw("Status")];
+        if self.wallet.is_encrypted() {
+            let (state, role) = if self.wallet.is_locked() {
+                (" LOCKED ", ColorRole::Muted)
+            } else {
+                (" UNLOCKED ", ColorRole::Positive)
+            };
+            title.push(Span::raw(" "));
+            title.push(Span::styled(state, self.style(role)));
+        }
+        if let Some(warning) = self
+            .clock
+            .as_ref()
+            .and_then(|clock| clock.check())
+            .and_then(|check| footer_warning(&check))
+        {
+            title.push(Span::raw(" "));
+            title.push(Span::styled(
+                format!(" CLOCK: {} ", warning),
+                self.style(ColorRole::Negative).add_modifier(Modifier::BOLD),
+            ));
+        }
+        let status_bar = Paragraph::new(status_text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(title)),
+        );
         f.render_widget(status_bar, area);
     }
 
     fn render_input_prompt(&self, f: &mut Frame, area: Rect, prompt: &str) {
         let input = Paragraph::new(Line::from(vec![
             Span::raw(prompt),
-            Span::styled(&self.input_text, Style::default().fg(Color::Yellow)),
+            Span::styled(&self.input_text, self.style(ColorRole::Accent)),
         ]))
         .block(Block::default().borders(Borders::ALL).title("Input"));
         f.render_widget(input, area);
@@ -240,15 +447,32 @@ impl WalletTui {
         );
     }
 
+    fn render_unlock_prompt(&self, f: &mut Frame, area: Rect) {
+        let prompt = "Wallet passphrase: ";
+        let masked = "*".repeat(self.input_text.chars().count());
+        let input = Paragraph::new(Line::from(vec![
+            Span::raw(prompt),
+            Span::styled(masked.as_str(), self.style(ColorRole::Accent)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Unlock (Enter to unlock, Esc to cancel)"),
+        );
+        f.render_widget(input, area);
+        f.set_cursor(
+            area.x + prompt.len() as u16 + masked.len() as u16 + 1,
+            area.y + 1,
+        );
+    }
+
     fn render_address_display(&self, f: &mut Frame, area: Rect) {
         let address_text = if let Some(address) = &self.last_generated_address {
             Line::from(vec![
-                Span::styled("New address: ", Style::default().fg(Color::Green)),
+                Span::styled("New address: ", self.style(ColorRole::Positive)),
                 Span::styled(
                     address.get_address(),
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD),
+                    self.style(ColorRole::Accent).add_modifier(Modifier::BOLD),
                 ),
             ])
         } else {
@@ -263,48 +487,172 @@ impl WalletTui {
         f.render_widget(address_display, area);
     }
 
+    fn render_send(&self, f: &mut Frame, area: Rect) {
+        let Some(flow) = self.send.as_ref() else {
+            return;
+        };
+        let unit = self.display.primary_unit;
+        let width = area.width.saturating_sub(2) as usize;
+        let field = |label: &str, value: String| {
+            Line::from(vec![
+                Span::raw(label.to_string()),
+                Span::styled(value, self.style(ColorRole::Accent)),
+            ])
+        };
+        let feedback = |result: Result<String, SendError>| match result {
+            Ok(text) => Line::from(Span::styled(text, self.style(ColorRole::Positive))),
+            Err(e) => Line::from(Span::styled(e.to_string(), self.style(ColorRole::Negative))),
+        };
+
+        let (title, lines) = match flow.step() {
+            SendStep::Recipient => {
+                let mut lines = vec![field("Pay to: ", flow.recipient().to_string())];
+                lines.push(match flow.address_feedback() {
+                    None => Line::from(Span::styled(
+                        format!("Enter a {} address", self.wallet.network()),
+                        self.style(ColorRole::Muted),
+                    )),
+                    Some(result) => feedback(result.map(|()| "Valid address".to_string())),
+                });
+                ("Recipient", lines)
+            }
+            SendStep::Amount => {
+                let mut lines = vec![Line::from(vec![
+                    Span::raw("Amount: "),
+                    Span::styled(flow.amount_text(), self.style(ColorRole::Accent)),
+                    Span::raw(" "),
+                    Span::styled(flow.unit().label(), self.style(ColorRole::Heading)),
+                ])];
+                if !flow.amount_text().is_empty() {
+                    lines.push(feedback(flow.amount().map(|a| format_amount(a, unit))));
+                }
+                ("Amount", lines)
+            }
+            SendStep::Fee => {
+                let mut lines = Vec::new();
+                if flow.rates().is_none() {
+                    lines.push(Line::from(Span::styled(
+                        "No fee estimates from the node; enter a rate",
+                        self.style(ColorRole::Muted),
+                    )));
+                }
+                for preset in flow.fee_presets() {
+                    let rate = match flow.rates().and_then(|r| preset.rate(r)) {
+                        Some(rate) => rate.to_string(),
+                        None => flow.manual_fee_rate().to_string(),
+                    };
+                    let text = format!("{:<16} {} per byte", preset.description(), rate);
+                    lines.push(if *preset == flow.fee_preset() {
+                        Line::from(Span::styled(
+                            format!("> {}", text),
+                            self.style(ColorRole::Selection),
+                        ))
+                    } else {
+                        Line::from(format!("  {}", text))
+                    });
+                }
+                ("Fee rate", lines)
+            }
+            SendStep::Confirm => {
+                let mut lines: Vec<Line> = flow
+                    .confirmation_lines(unit, width)
+                    .into_iter()
+                    .map(Line::from)
+                    .collect();
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "Enter signs the payment; Esc goes back",
+                    self.style(ColorRole::Pending),
+                )));
+                ("Confirm", lines)
+            }
+            SendStep::Sent => {
+                let txid = flow.txid().unwrap_or_default().to_string();
+                let lines = vec![
+                    Line::from(Span::styled(
+                        "Transaction signed and recorded as pending",
+                        self.style(ColorRole::Positive),
+                    )),
+                    Line::from(""),
+                    field("Txid: ", txid),
+                ];
+                ("Sent", lines)
+            }
+        };
+
+        let title = format!("Send from {} - {}", flow.account(), title);
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let screen = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        f.render_widget(screen, area);
+    }
+
+    fn send_hints(&self) -> &'static str {
+        match self.send.as_ref().map(SendFlow::step) {
+            Some(SendStep::Recipient) | None => "Enter: continue | Esc: cancel",
+            Some(SendStep::Amount) => "Enter: continue | Tab: NOVA/mNOVA | Esc: back",
+            Some(SendStep::Fee) => "Up/Down: choose a rate | Enter: review | Esc: back",
+            Some(SendStep::Confirm) => "Enter: sign and send | Esc: back",
+            Some(SendStep::Sent) => "Enter/Esc: close",
+        }
+    }
+
     fn render_overview(&self, f: &mut Frame, area: Rect) {
-        let total_balance = self.wallet.get_total_balance(&self.utxo_set).unwrap_or(0);
+        let balances = self
+            .balances
+            .balances(&self.wallet, &self.utxo_set)
+            .unwrap_or_default();
+        let total_balance = balances.active;
+        let rate = self.current_rate();
+        let unit = self.display.primary_unit;
         let total_sent = self.history.get_total_sent();
         let total_received = self.history.get_total_received();
         let net_flow = self.history.get_net_flow();
-        let account_count = self.wallet.list_accounts().len();
+        let account_count = self.wallet.list_accounts(false).len();
         let address_count = self.wallet.get_address_count();
-        let transaction_count = self.history.get_all_transactions().len();
+        let transaction_count = self.history.transaction_count();
 
-        let text = vec![
-            Line::from(vec![
-                Span::raw("Total Balance: "),
+        let mut text = vec![Line::from(vec![
+            Span::raw("Total Balance: "),
+            Span::styled(
+                self.display.format_with_fiat(total_balance, rate.as_ref()),
+                self.style(ColorRole::Positive).add_modifier(Modifier::BOLD),
+            ),
+        ])];
+        if balances.archived > 0 {
+            text.push(Line::from(vec![
+                Span::raw("Archived Accounts: "),
                 Span::styled(
-                    format!("{} nova", total_balance),
-                    Style::default()
-                        .fg(Color::Green)
-                        .add_modifier(Modifier::BOLD),
+                    self.display.format_with_fiat(balances.archived, rate.as_ref()),
+                    self.style(ColorRole::Muted),
                 ),
-            ]),
+            ]));
+        }
+        text.extend(vec![
             Line::from(Span::raw("")),
             Line::from(vec![
                 Span::raw("Total Sent: "),
                 Span::styled(
-                    format!("{} nova", total_sent),
-                    Style::default().fg(Color::Red),
+                    self.display.format_with_fiat(total_sent, rate.as_ref()),
+                    self.style(ColorRole::Negative),
                 ),
             ]),
             Line::from(vec![
                 Span::raw("Total Received: "),
                 Span::styled(
-                    format!("{} nova", total_received),
-                    Style::default().fg(Color::Green),
+                    self.display.format_with_fiat(total_received, rate.as_ref()),
+                    self.style(ColorRole::Positive),
                 ),
             ]),
             Line::from(vec![
                 Span::raw("Net Flow: "),
                 Span::styled(
-                    format!("{} nova", net_flow),
-                    Style::default().fg(if net_flow >= 0 {
-                        Color::Green
+                    format_signed_amount(net_flow, unit),
+                    self.style(if net_flow >= 0 {
+                        ColorRole::Positive
                     } else {
-                        Color::Red
+                        ColorRole::Negative
                     }),
                 ),
             ]),
@@ -313,29 +661,29 @@ impl WalletTui {
                 Span::raw("Accounts: "),
                 Span::styled(
                     format!("{}", account_count),
-                    Style::default().fg(Color::Yellow),
+                    self.style(ColorRole::Accent),
                 ),
             ]),
             Line::from(vec![
                 Span::raw("Addresses: "),
                 Span::styled(
                     format!("{}", address_count),
-                    Style::default().fg(Color::Yellow),
+                    self.style(ColorRole::Accent),
                 ),
             ]),
             Line::from(vec![
                 Span::raw("Transactions: "),
                 Span::styled(
                     format!("{}", transaction_count),
-                    Style::default().fg(Color::Yellow),
+                    self.style(ColorRole::Accent),
                 ),
             ]),
             Line::from(Span::raw("")),
             Line::from(Span::styled(
-                "Press Tab to navigate between tabs",
-                Style::default().fg(Color::Blue),
+                format!("Press {} to navigate between tabs", self.key(Action::NextTab)),
+                self.style(ColorRole::Info),
             )),
-        ];
+        ]);
 
         let overview =
             Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Overview"));
@@ -344,23 +692,70 @@ impl WalletTui {
     }
 
     fn render_accounts(&mut self, f: &mut Frame, area: Rect) {
+        let rate = self.current_rate();
+        let selected = self.accounts_state.selected();
         // Collect account data first to avoid borrowing conflicts
         let accounts_data: Vec<_> = {
-            let accounts = self.wallet.list_accounts();
+            let accounts = self.wallet.list_accounts(false);
             accounts
                 .iter()
                 .map(|(index, account)| {
                     let balance = self
-                        .wallet
-                        .get_balance(&account.name, &self.utxo_set)
+                        .balances
+                        .balance(&self.wallet, &self.utxo_set, &account.name)
                         .unwrap_or(0);
-                    let addr_count = account.addresses.len();
+                    let issued = self
+                        .wallet
+                        .list_addresses(&account.name, AddressFilter::Issued)
+                        .unwrap_or_default();
+                    let used = issued
+                        .iter()
+                        .filter(|hd_address| hd_address.state() == AddressState::Used)
+                        .count();
+                    let usage = format!(
+                        "Addresses: {} used, {} reserved, {} unused",
+                        used,
+                        issued.len() - used,
+                        account.addresses.len() - issued.len()
+                    );
+                    // Only the highlighted account lists its addresses; the
+                    // ones derived ahead stay hidden
+                    let addresses: Vec<(String, AddressState, Option<String>)> =
+                        if selected == Some(*index as usize) {
+                            issued
+                                .iter()
+                                .map(|hd_address| {
+                                    (
+                                        hd_address.address.clone(),
+                                        hd_address.state(),
+                                        hd_address.label.clone(),
+                                    )
+                                })
+                                .collect()
+                        } else {
+                            Vec::new()
+                        };
+                    let policy = match self.wallet.spending_policy(&account.name) {
+                        Ok(Some(policy)) => Some(format!(
+                            "Policy: {} | spent 24h: {}",
+                            policy.summary(),
+                            self.wallet.spent_last_24h(&account.name)
+                        )),
+                        Ok(None) => None,
+                        // Policies are verified with a seed-derived key
+                        Err(HDWalletError::Locked(_)) => {
+                            Some("Policy: unlock to verify".to_string())
+                        }
+                        Err(e) => Some(format!("Policy: {}", e)),
+                    };
                     (
                         *index,
                         account.name.clone(),
                         account.account_type,
                         balance,
-                        addr_count,
+                        usage,
+                        addresses,
+                        policy,
                     )
                 })
                 .collect()
@@ -368,45 +763,58 @@ impl WalletTui {
 
         let items: Vec<ListItem> = accounts_data
             .iter()
-            .map(|(index, name, account_type, balance, addr_count)| {
-                ListItem::new(vec![
+            .map(|(index, name, kind, balance, usage, issued, policy)| {
+                let mut lines = vec![
                     Line::from(vec![
-                        Span::styled(format!("{}. ", index), Style::default().fg(Color::DarkGray)),
+                        Span::styled(format!("{}. ", index), self.style(ColorRole::Muted)),
                         Span::styled(
                             name,
-                            Style::default()
-                                .fg(Color::Yellow)
-                                .add_modifier(Modifier::BOLD),
+                            self.style(ColorRole::Accent).add_modifier(Modifier::BOLD),
                         ),
                         Span::raw(" - "),
                         Span::styled(
-                            format!("{} nova", balance),
-                            Style::default().fg(Color::Green),
+                            self.display.format_with_fiat(*balance, rate.as_ref()),
+                            self.style(ColorRole::Positive),
                         ),
                     ]),
                     Line::from(vec![
                         Span::raw("   "),
-                        Span::styled(
-                            format!("Type: {:?}", account_type),
-                            Style::default().fg(Color::Blue),
-                        ),
+                        Span::styled(format!("Type: {:?}", kind), self.style(ColorRole::Info)),
                         Span::raw(" | "),
-                        Span::styled(
-                            format!("Addresses: {}", addr_count),
-                            Style::default().fg(Color::Blue),
-                        ),
+                        Span::styled(usage.clone(), self.style(ColorRole::Info)),
                     ]),
-                ])
+                ];
+                for (address, state, label) in issued {
+                    let (usage, role) = match state {
+                        AddressState::Used => ("used", ColorRole::Positive),
+                        AddressState::Reserved => ("reserved", ColorRole::Pending),
+                        AddressState::Unused => ("unused", ColorRole::Muted),
+                    };
+                    let mut spans = vec![
+                        Span::raw("     "),
+                        Span::styled(address.clone(), self.style(ColorRole::Muted)),
+                        Span::raw(" "),
+                        Span::styled(format!("[{}]", usage), self.style(role)),
+                    ];
+                    if let Some(label) = label {
+                        spans.push(Span::raw(" "));
+                        spans.push(Span::styled(label.clone(), self.style(ColorRole::Accent)));
+                    }
+                    lines.push(Line::from(spans));
+                }
+                if let Some(policy) = policy {
+                    lines.push(Line::from(vec![
+                        Span::raw("   "),
+                        Span::styled(policy.clone(), self.style(ColorRole::Policy)),
+                    ]));
+                }
+                ListItem::new(lines)
             })
             .collect();
 
         let accounts_list = List::new(items)
             .block(Block::default().borders(Borders::ALL).title("Accounts"))
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
+            .highlight_style(self.style(ColorRole::Selection))
             .highlight_symbol(">> ");
 
         let account_count = accounts_data.len();
@@ -418,25 +826,30 @@ impl WalletTui {
     }
 
     fn render_transactions(&mut self, f: &mut Frame, area: Rect) {
+        let rate = self.current_rate();
+        let display = self.display.clone();
+        let palette = &self.palette;
         let transactions = self.history.get_all_transactions();
         let items: Vec<ListItem> = transactions
             .iter()
             .map(|tx| {
-                let amount_color = match tx.direction {
-                    TransactionDirection::Sent => Color::Red,
-                    TransactionDirection::Received => Color::Green,
+                let amount_role = match tx.direction {
+                    TransactionDirection::Sent => ColorRole::Negative,
+                    TransactionDirection::Received => ColorRole::Positive,
                 };
 
-                let status_color = match &tx.status {
-                    TransactionStatus::Pending => Color::Yellow,
-                    TransactionStatus::Confirmed(_) => Color::Green,
-                    TransactionStatus::Failed => Color::Red,
+                let status_role = match &tx.status {
+                    TransactionStatus::Pending => ColorRole::Pending,
+                    TransactionStatus::Confirmed(_) => ColorRole::Positive,
+                    TransactionStatus::Failed => ColorRole::Negative,
+                    TransactionStatus::Replaced(_) => ColorRole::Muted,
                 };
 
                 let status_text = match &tx.status {
                     TransactionStatus::Pending => "Pending".to_string(),
                     TransactionStatus::Confirmed(n) => format!("Confirmed ({})", n),
                     TransactionStatus::Failed => "Failed".to_string(),
+                    TransactionStatus::Replaced(_) => "Replaced".to_string(),
                 };
 
                 let label_text = if let Some(label) = &tx.label {
@@ -449,24 +862,22 @@ impl WalletTui {
                     Line::from(vec![
                         Span::styled(
                             tx.timestamp.format("%Y-%m-%d %H:%M").to_string(),
-                            Style::default().fg(Color::Blue),
+                            palette.style(ColorRole::Info),
                         ),
                         Span::raw(" - "),
                         Span::styled(
-                            format!("{} nova", tx.amount),
-                            Style::default()
-                                .fg(amount_color)
-                                .add_modifier(Modifier::BOLD),
+                            display.format_with_fiat(tx.amount, rate.as_ref()),
+                            palette.style(amount_role).add_modifier(Modifier::BOLD),
                         ),
-                        Span::styled(label_text, Style::default().fg(Color::Yellow)),
+                        Span::styled(label_text, palette.style(ColorRole::Accent)),
                     ]),
                     Line::from(vec![
                         Span::raw("   "),
-                        Span::styled(status_text, Style::default().fg(status_color)),
+                        Span::styled(status_text, palette.style(status_role)),
                         Span::raw(" | "),
                         Span::styled(
                             format!("Tx: {}...", &tx.hash[0..8]),
-                            Style::default().fg(Color::DarkGray),
+                            palette.style(ColorRole::Muted),
                         ),
                     ]),
                 ])
@@ -475,11 +886,7 @@ impl WalletTui {
 
         let transactions_list = List::new(items)
             .block(Block::default().borders(Borders::ALL).title("Transactions"))
-            .highlight_style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
+            .highlight_style(self.style(ColorRole::Selection))
             .highlight_symbol(">> ");
 
         let tx_count = transactions.len();
@@ -491,46 +898,52 @@ impl WalletTui {
     }
 
     fn render_help(&self, f: &mut Frame, area: Rect) {
-        let text = vec![
+        let heading = self.style(ColorRole::Text).add_modifier(Modifier::BOLD);
+        let shortcut = |action: Action| {
+            Line::from(format!(
+                "  {:<9} - {}",
+                self.key(action),
+                action.description()
+            ))
+        };
+        let arrows = format!("{}/{}", self.key(Action::Up), self.key(Action::Down));
+        let mut text = vec![
             Line::from(vec![Span::styled(
                 "supernova Wallet Help",
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
+                self.style(ColorRole::Heading),
             )]),
             Line::from(""),
-            Line::from(vec![Span::styled(
-                "Global Shortcuts:",
-                Style::default().add_modifier(Modifier::BOLD),
-            )]),
-            Line::from("  Tab       - Cycle through tabs"),
-            Line::from("  q         - Quit application"),
-            Line::from("  ?         - Show/hide help"),
+            Line::from(vec![Span::styled("Global Shortcuts:", heading)]),
+        ];
+        text.extend(
+            Action::ALL
+                .into_iter()
+                .filter(|action| {
+                    action.scope().is_none() && !matches!(action, Action::Up | Action::Down)
+                })
+                .map(shortcut),
+        );
+        text.extend(vec![
             Line::from("  Esc       - Cancel current operation"),
             Line::from(""),
-            Line::from(vec![Span::styled(
-                "Accounts Tab:",
-                Style::default().add_modifier(Modifier::BOLD),
-            )]),
-            Line::from("  ↑/↓       - Navigate accounts"),
-            Line::from("  n         - Create new account"),
-            Line::from("  a         - Generate new address for selected account"),
+            Line::from(vec![Span::styled("Accounts Tab:", heading)]),
+            Line::from(format!("  {:<9} - Navigate accounts", arrows)),
+            shortcut(Action::NewAccount),
+            shortcut(Action::NewAddress),
+            shortcut(Action::Send),
             Line::from(""),
-            Line::from(vec![Span::styled(
-                "Transactions Tab:",
-                Style::default().add_modifier(Modifier::BOLD),
-            )]),
-            Line::from("  ↑/↓       - Navigate transactions"),
-            Line::from("  l         - Add/edit label for selected transaction"),
+            Line::from(vec![Span::styled("Transactions Tab:", heading)]),
+            Line::from(format!("  {:<9} - Navigate transactions", arrows)),
+            shortcut(Action::LabelTransaction),
             Line::from(""),
-            Line::from(vec![Span::styled(
-                "Account Types:",
-                Style::default().add_modifier(Modifier::BOLD),
-            )]),
+            Line::from(vec![Span::styled("Settings Tab:", heading)]),
+            Line::from("  Enter     - Apply the highlighted theme"),
+            Line::from(""),
+            Line::from(vec![Span::styled("Account Types:", heading)]),
             Line::from("  Legacy       - Compatibility addresses (1...)"), // Bitcoin-compatible
             Line::from("  SegWit       - Segregated Witness addresses (3...)"),
             Line::from("  NativeSegWit - Bech32 addresses (bc1...)"),
-        ];
+        ]);
 
         let help_text =
             Paragraph::new(text).block(Block::default().borders(Borders::ALL).title("Help"));
@@ -538,51 +951,152 @@ impl WalletTui {
         f.render_widget(help_text, area);
     }
 
+    fn render_settings(&mut self, f: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+            .split(area);
+
+        let names = theme_names(&self.ui.themes);
+        let items: Vec<ListItem> = names
+            .iter()
+            .map(|name| {
+                let marker = if name == self.palette.name() { " (current)" } else { "" };
+                ListItem::new(Line::from(vec![
+                    Span::styled(name.clone(), self.style(ColorRole::Text)),
+                    Span::styled(marker, self.style(ColorRole::Muted)),
+                ]))
+            })
+            .collect();
+        if self.themes_state.selected().is_none() {
+            let current = names.iter().position(|n| n == self.palette.name());
+            self.themes_state.select(current.or(Some(0)));
+        }
+        let themes = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title("Theme"))
+            .highlight_style(self.style(ColorRole::Selection))
+            .highlight_symbol(">> ");
+
+        // Preview every role in the current theme
+        let mut lines = vec![
+            Line::from(format!("Color depth: {:?}", self.palette.depth())),
+            Line::from(""),
+        ];
+        lines.extend(ColorRole::ALL.iter().map(|role| {
+            Line::from(vec![
+                Span::raw(format!("{:<10} ", format!("{:?}", role))),
+                Span::styled("Sample 1.00000000 NOVA", self.style(*role)),
+            ])
+        }));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Key bindings:",
+            self.style(ColorRole::Text).add_modifier(Modifier::BOLD),
+        )));
+        lines.extend(Action::ALL.iter().map(|action| {
+            Line::from(vec![
+                Span::styled(format!("  {:<9} ", self.key(*action)), self.style(ColorRole::Accent)),
+                Span::raw(action.description()),
+            ])
+        }));
+        let preview =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Preview"));
+
+        f.render_stateful_widget(themes, chunks[0], &mut self.themes_state);
+        f.render_widget(preview, chunks[1]);
+    }
+
+    /// Switch to the highlighted theme and remember it in the settings file.
+    fn apply_selected_theme(&mut self) {
+        let names = theme_names(&self.ui.themes);
+        let Some(name) = self.themes_state.selected().and_then(|i| names.get(i)) else {
+            return;
+        };
+        let mut ui = self.ui.clone();
+        ui.theme = name.clone();
+        let palette = match ui.palette(ColorDepth::detect()) {
+            Ok(palette) => palette,
+            Err(e) => {
+                self.message = Some(Message::Error(format!("Theme: {}", e)));
+                return;
+            }
+        };
+        self.palette = palette;
+        self.ui = ui;
+
+        let saved = match &self.settings_dir {
+            None => Ok(()),
+            Some(dir) => WalletSettings::load(dir).and_then(|mut settings| {
+                settings.ui.theme = self.ui.theme.clone();
+                settings.save(dir)
+            }),
+        };
+        self.message = Some(match saved {
+            Ok(()) => Message::Success(format!("Theme set to '{}'", self.ui.theme)),
+            Err(e) => Message::Error(format!("Theme applied but not saved: {}", e)),
+        });
+    }
+
     fn handle_normal_mode(&mut self, key: KeyEvent) -> Result<(), io::Error> {
-        match key.code {
-            KeyCode::Char('q') => {
-                if key.modifiers.contains(KeyModifiers::CONTROL) {
-                    return Err(io::Error::new(io::ErrorKind::Interrupted, "Quit"));
-                }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('q') {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Quit"));
+        }
+        if key.code == KeyCode::Enter && self.current_tab == Tab::Settings {
+            self.apply_selected_theme();
+            return Ok(());
+        }
+
+        let Some(action) = self.keymap.action_for(&key, self.current_tab) else {
+            return Ok(());
+        };
+        match action {
+            Action::Quit => {
                 if self.current_tab != Tab::Help {
                     return Err(io::Error::new(io::ErrorKind::Interrupted, "Quit"));
                 } else {
                     self.current_tab = Tab::Overview;
                 }
             }
-            KeyCode::Tab => {
+            Action::NextTab => {
                 self.cycle_tab();
             }
-            KeyCode::Char('?') => {
+            Action::Help => {
                 if self.current_tab != Tab::Help {
                     self.current_tab = Tab::Help;
                 } else {
                     self.current_tab = Tab::Overview;
                 }
             }
-            KeyCode::Char('o') => self.current_tab = Tab::Overview,
-            KeyCode::Char('a') => {
-                if self.current_tab == Tab::Accounts {
-                    self.handle_generate_address()?;
-                } else {
-                    self.current_tab = Tab::Accounts;
+            Action::Overview | Action::Accounts | Action::Transactions | Action::Settings => {
+                if let Some(tab) = action.target() {
+                    self.current_tab = tab;
                 }
             }
-            KeyCode::Char('t') => self.current_tab = Tab::Transactions,
-            KeyCode::Char('n') => {
-                if self.current_tab == Tab::Accounts {
-                    self.input_mode = InputMode::AccountCreation;
-                    self.input_text.clear();
-                }
+            Action::NewAddress => {
+                self.handle_generate_address()?;
             }
-            KeyCode::Char('l') => {
-                if self.current_tab == Tab::Transactions {
-                    self.handle_transaction_label_start()?;
-                }
+            Action::NewAccount => {
+                self.input_mode = InputMode::AccountCreation;
+                self.input_text.clear();
+            }
+            Action::Send => {
+                self.start_send();
             }
-            KeyCode::Down => match self.current_tab {
+            Action::Lock => {
+                let text = if self.wallet.is_encrypted() {
+                    self.wallet.lock();
+                    "Wallet locked"
+                } else {
+                    "The wallet is not encrypted; run `encrypt` from the CLI to set a passphrase"
+                };
+                self.message = Some(Message::Info(text.to_string()));
+            }
+            Action::LabelTransaction => {
+                self.handle_transaction_label_start()?;
+            }
+            Action::Down => match self.current_tab {
                 Tab::Accounts => {
-                    let accounts = self.wallet.list_accounts();
+                    let accounts = self.wallet.list_accounts(false);
                     if !accounts.is_empty() {
                         let i = match self.accounts_state.selected() {
                             Some(i) => {
@@ -614,11 +1128,19 @@ impl WalletTui {
                         self.selected_transaction = Some(transactions[i].hash.clone());
                     }
                 }
+                Tab::Settings => {
+                    let count = theme_names(&self.ui.themes).len();
+                    let i = match self.themes_state.selected() {
+                        Some(i) if i + 1 < count => i + 1,
+                        _ => 0,
+                    };
+                    self.themes_state.select(Some(i));
+                }
                 _ => {}
             },
-            KeyCode::Up => match self.current_tab {
+            Action::Up => match self.current_tab {
                 Tab::Accounts => {
-                    let accounts = self.wallet.list_accounts();
+                    let accounts = self.wallet.list_accounts(false);
                     if !accounts.is_empty() {
                         let i = match self.accounts_state.selected() {
                             Some(i) => {
@@ -650,9 +1172,16 @@ impl WalletTui {
                         self.selected_transaction = Some(transactions[i].hash.clone());
                     }
                 }
+                Tab::Settings => {
+                    let count = theme_names(&self.ui.themes).len();
+                    let i = match self.themes_state.selected() {
+                        Some(0) | None => count.saturating_sub(1),
+                        Some(i) => i - 1,
+                    };
+                    self.themes_state.select(Some(i));
+                }
                 _ => {}
             },
-            _ => {}
         }
         Ok(())
     }
@@ -660,10 +1189,12 @@ impl WalletTui {
     fn handle_account_creation_mode(&mut self, key: KeyEvent) -> Result<(), io::Error> {
         match key.code {
             KeyCode::Enter => {
-                if !self.input_text.is_empty() {
-                    self.create_account();
-                }
                 self.input_mode = InputMode::Normal;
+                let account_name = self.input_text.trim().to_string();
+                self.input_text.clear();
+                if !account_name.is_empty() {
+                    self.create_account(account_name);
+                }
             }
             KeyCode::Esc => {
                 self.input_mode = InputMode::Normal;
@@ -711,17 +1242,145 @@ impl WalletTui {
         Ok(())
     }
 
-    fn cycle_tab(&mut self) {
-        self.current_tab = match self.current_tab {
-            Tab::Overview => Tab::Accounts,
-            Tab::Accounts => Tab::Transactions,
-            Tab::Transactions => Tab::Help,
-            Tab::Help => Tab::Overview,
+    fn handle_unlock_mode(&mut self, key: KeyEvent) -> Result<(), io::Error> {
+        match key.code {
+            KeyCode::Enter => {
+                let unlocked = self.wallet.unlock(&self.input_text, Some(self.auto_lock));
+                self.input_text.zeroize();
+                self.input_mode = InputMode::Normal;
+                let pending = self.pending_unlock.take();
+                match (unlocked, pending) {
+                    (Ok(()), Some(LockedAction::CreateAccount(name))) => self.create_account(name),
+                    (Ok(()), Some(LockedAction::NewAddress(name))) => self.generate_address(name),
+                    (Ok(()), None) => {
+                        self.message = Some(Message::Success("Wallet unlocked".to_string()));
+                    }
+                    (Err(e), _) => {
+                        self.message = Some(Message::from_wallet_error("Failed to unlock", &e));
+                    }
+                }
+            }
+            KeyCode::Esc => {
+                self.input_text.zeroize();
+                self.pending_unlock = None;
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c) => {
+                self.input_text.push(c);
+            }
+            KeyCode::Backspace => {
+                self.input_text.pop();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_send_mode<S: SendBackend + ?Sized>(
+        &mut self,
+        key: KeyEvent,
+        backend: Option<&mut S>,
+    ) -> Result<(), io::Error> {
+        let Some(flow) = self.send.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.message = None;
+                if flow.back().is_none() {
+                    self.close_send();
+                }
+            }
+            KeyCode::Enter => {
+                let Some(backend) = backend else {
+                    self.close_send();
+                    return Ok(());
+                };
+                match flow.next(backend) {
+                    Ok(_) => self.message = None,
+                    Err(SendError::Finished) => self.close_send(),
+                    Err(e) => self.message = Some(Message::Error(e.to_string())),
+                }
+            }
+            KeyCode::Tab if flow.step() == SendStep::Amount => flow.toggle_unit(),
+            KeyCode::Up | KeyCode::Down if flow.step() == SendStep::Fee => {
+                let presets = flow.fee_presets();
+                let i = presets
+                    .iter()
+                    .position(|p| *p == flow.fee_preset())
+                    .unwrap_or(0);
+                let i = if key.code == KeyCode::Up {
+                    (i + presets.len() - 1) % presets.len()
+                } else {
+                    (i + 1) % presets.len()
+                };
+                flow.set_fee_preset(presets[i]);
+            }
+            KeyCode::Char(c) => edit_send_field(flow, |text| text.push(c)),
+            KeyCode::Backspace => edit_send_field(flow, |text| {
+                text.pop();
+            }),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Open the send screen for the highlighted account
+    fn start_send(&mut self) {
+        if !self.can_send {
+            self.message = Some(Message::Info(
+                "Sending is not available in this session".to_string(),
+            ));
+            return;
+        }
+        let account = self.accounts_state.selected().and_then(|i| {
+            let accounts = self.wallet.list_accounts(false);
+            accounts.get(i).map(|(_, account)| account.name.clone())
+        });
+        let Some(account) = account else {
+            self.message = Some(Message::Error("No account selected".to_string()));
+            return;
         };
+        self.message = None;
+        let rates = match self.fee_estimator.as_ref().map(|e| e.fee_rates()) {
+            Some(Ok(rates)) => Some(rates),
+            Some(Err(e)) => {
+                self.message = Some(Message::Info(format!("No fee estimates: {}", e)));
+                None
+            }
+            None => None,
+        };
+        self.send = Some(SendFlow::new(&account, self.wallet.network(), rates));
+        self.input_mode = InputMode::Send;
+    }
+
+    /// Leave the send screen, dropping everything entered
+    fn close_send(&mut self) {
+        let txid = self
+            .send
+            .take()
+            .and_then(|flow| flow.txid().map(str::to_string));
+        self.message = Some(match txid {
+            Some(txid) => Message::Success(format!("Sent {}", txid)),
+            None => Message::Info("Send cancelled".to_string()),
+        });
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Ask for the passphrase, then retry `action`
+    fn request_unlock(&mut self, action: LockedAction) {
+        self.pending_unlock = Some(action);
+        self.input_text.clear();
+        self.input_mode = InputMode::Unlock;
+        self.message = None;
+    }
+
+    fn cycle_tab(&mut self) {
+        self.current_tab = self.current_tab.next();
     }
 
-    fn create_account(&mut self) {
-        let account_name = self.input_text.trim().to_string();
+    fn create_account(&mut self, account_name: String) {
         match self
             .wallet
             .create_account(account_name.clone(), AccountType::NativeSegWit)
@@ -732,23 +1391,25 @@ impl WalletTui {
                     account_name
                 )));
                 // Select the newly created account - get account count after the mutable borrow is released
-                let account_count = self.wallet.list_accounts().len();
+                let account_count = self.wallet.list_accounts(false).len();
                 if account_count > 0 {
                     self.accounts_state.select(Some(account_count - 1));
                 }
             }
+            Err(HDWalletError::Locked(_)) => {
+                self.request_unlock(LockedAction::CreateAccount(account_name));
+            }
             Err(e) => {
-                self.message = Some(Message::Error(format!("Failed to create account: {}", e)));
+                self.message = Some(Message::from_wallet_error("Failed to create account", &e));
             }
         }
-        self.input_text.clear();
     }
 
     fn handle_generate_address(&mut self) -> Result<(), io::Error> {
         if let Some(idx) = self.accounts_state.selected() {
             // Collect account name first to avoid borrowing conflicts
             let account_name = {
-                let accounts = self.wallet.list_accounts();
+                let accounts = self.wallet.list_accounts(false);
                 if idx < accounts.len() {
                     Some(accounts[idx].1.name.clone())
                 } else {
@@ -757,16 +1418,7 @@ impl WalletTui {
             };
 
             if let Some(name) = account_name {
-                match self.wallet.get_new_address(&name) {
-                    Ok(address) => {
-                        self.last_generated_address = Some(address);
-                        self.input_mode = InputMode::AddressDisplay;
-                    }
-                    Err(e) => {
-                        self.message =
-                            Some(Message::Error(format!("Failed to generate address: {}", e)));
-                    }
-                }
+                self.generate_address(name);
             }
         } else {
             self.message = Some(Message::Error("No account selected".to_string()));
@@ -774,6 +1426,21 @@ impl WalletTui {
         Ok(())
     }
 
+    fn generate_address(&mut self, account_name: String) {
+        match self.wallet.get_new_address(&account_name) {
+            Ok(address) => {
+                self.last_generated_address = Some(address);
+                self.input_mode = InputMode::AddressDisplay;
+            }
+            Err(HDWalletError::Locked(_)) => {
+                self.request_unlock(LockedAction::NewAddress(account_name));
+            }
+            Err(e) => {
+                self.message = Some(Message::from_wallet_error("Failed to generate address", &e));
+            }
+        }
+    }
+
     fn handle_transaction_label_start(&mut self) -> Result<(), io::Error> {
         if let Some(idx) = self.transactions_state.selected() {
             let transactions = self.history.get_all_transactions();
@@ -834,6 +1501,8 @@ impl WalletTui {
             label: None,
             category: None,
             tags: vec![],
+            memo: None,
+            addresses: Vec::new(),
         };
 
         match self.history.add_transaction(tx) {
@@ -851,3 +1520,384 @@ impl WalletTui {
         Ok(())
     }
 }
+
+impl WalletTui {
+    /// Run the first-run setup wizard full screen. Returns `None` if the
+    /// user cancelled, in which case nothing was written.
+    pub fn run_setup_wizard(wallet_path: PathBuf) -> Result<Option<WizardOutcome>, io::Error> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+
+        let mut screen = WizardScreen::new(SetupWizard::new(wallet_path));
+        let res = screen.run(&mut terminal);
+
+        disable_raw_mode()?;
+        execute!(
+            terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+        terminal.show_cursor()?;
+
+        res
+    }
+}
+
+/// Terminal front end for [`SetupWizard`]. Text fields are edited in local
+/// buffers and handed to the wizard when the user moves to another step.
+struct WizardScreen {
+    wizard: SetupWizard,
+    /// Highlighted entry on list steps (network, source, account type)
+    selected: usize,
+    fields: Vec<String>,
+    focus: usize,
+    error: Option<String>,
+    palette: Palette,
+}
+
+enum WizardAction {
+    Continue,
+    Cancel,
+    Finished(WizardOutcome),
+}
+
+impl WizardScreen {
+    fn new(wizard: SetupWizard) -> Self {
+        let mut screen = Self {
+            wizard,
+            selected: 0,
+            fields: Vec::new(),
+            focus: 0,
+            error: None,
+            palette: UiPreferences::default()
+                .palette(ColorDepth::detect())
+                .unwrap_or_default(),
+        };
+        screen.load_step();
+        screen
+    }
+
+    fn run<B: ratatui::backend::Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+    ) -> Result<Option<WizardOutcome>, io::Error> {
+        loop {
+            terminal.draw(|f| self.render(f))?;
+
+            if let Event::Key(key) = event::read()? {
+                match self.handle_key(key) {
+                    WizardAction::Continue => {}
+                    WizardAction::Cancel => return Ok(None),
+                    WizardAction::Finished(outcome) => return Ok(Some(outcome)),
+                }
+            }
+        }
+    }
+
+    /// Populate the edit buffers and list selection from the wizard
+    fn load_step(&mut self) {
+        let w = &self.wizard;
+        self.focus = 0;
+        self.fields = match w.step() {
+            WizardStep::VerifyMnemonic => w.quiz_answers().to_vec(),
+            WizardStep::EnterMnemonic => vec![w.imported_mnemonic().to_string()],
+            WizardStep::EnterXpub => vec![w.xpub().to_string()],
+            WizardStep::Encryption => {
+                vec![w.password().to_string(), w.password_confirm().to_string()]
+            }
+            WizardStep::Account => vec![w.account_name().to_string()],
+            _ => Vec::new(),
+        };
+        self.selected = match w.step() {
+            WizardStep::Network => NETWORKS.iter().position(|n| *n == w.network()),
+            WizardStep::Source => WalletSource::ALL.iter().position(|s| *s == w.source()),
+            WizardStep::Account => ACCOUNT_TYPES.iter().position(|t| *t == w.account_type()),
+            _ => None,
+        }
+        .unwrap_or(0);
+    }
+
+    /// Hand the edit buffers and list selection to the wizard
+    fn commit_step(&mut self) {
+        let empty = String::new();
+        let field = |i: usize| self.fields.get(i).unwrap_or(&empty).clone();
+        match self.wizard.step() {
+            WizardStep::Network => self.wizard.set_network(NETWORKS[self.selected]),
+            WizardStep::Source => self.wizard.set_source(WalletSource::ALL[self.selected]),
+            WizardStep::VerifyMnemonic => {
+                for i in 0..self.fields.len() {
+                    self.wizard.set_quiz_answer(i, &field(i));
+                }
+            }
+            WizardStep::EnterMnemonic => self.wizard.set_imported_mnemonic(&field(0)),
+            WizardStep::EnterXpub => self.wizard.set_xpub(&field(0)),
+            WizardStep::Encryption => self.wizard.set_password(&field(0), &field(1)),
+            WizardStep::Account => {
+                self.wizard.set_account(&field(0), ACCOUNT_TYPES[self.selected])
+            }
+            WizardStep::ShowMnemonic | WizardStep::Summary => {}
+        }
+    }
+
+    fn list_len(&self) -> usize {
+        match self.wizard.step() {
+            WizardStep::Network => NETWORKS.len(),
+            WizardStep::Source => WalletSource::ALL.len(),
+            WizardStep::Account => ACCOUNT_TYPES.len(),
+            _ => 0,
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> WizardAction {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return WizardAction::Cancel;
+        }
+
+        match key.code {
+            KeyCode::Esc => {
+                self.commit_step();
+                self.error = None;
+                if self.wizard.back().is_none() {
+                    return WizardAction::Cancel;
+                }
+                self.load_step();
+            }
+            KeyCode::Enter => {
+                self.commit_step();
+                if self.wizard.step() == WizardStep::Summary {
+                    match self.wizard.finish() {
+                        Ok(outcome) => return WizardAction::Finished(outcome),
+                        Err(e) => self.error = Some(e.to_string()),
+                    }
+                    return WizardAction::Continue;
+                }
+                match self.wizard.next() {
+                    Ok(_) => {
+                        self.error = None;
+                        self.load_step();
+                    }
+                    Err(e) => {
+                        self.error = Some(e.to_string());
+                        // A failed quiz asks for different words
+                        if matches!(e, WizardError::QuizFailed) {
+                            self.load_step();
+                        }
+                    }
+                }
+            }
+            KeyCode::Up => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            KeyCode::Down => {
+                if self.selected + 1 < self.list_len() {
+                    self.selected += 1;
+                }
+            }
+            KeyCode::Tab => {
+                if !self.fields.is_empty() {
+                    self.focus = (self.focus + 1) % self.fields.len();
+                }
+            }
+            KeyCode::BackTab => {
+                if !self.fields.is_empty() {
+                    self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(field) = self.fields.get_mut(self.focus) {
+                    field.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(field) = self.fields.get_mut(self.focus) {
+                    field.pop();
+                }
+            }
+            _ => {}
+        }
+        WizardAction::Continue
+    }
+
+    fn render(&self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints(
+                [
+                    Constraint::Min(1),    // Step content
+                    Constraint::Length(3), // Errors and key hints
+                ]
+                .as_ref(),
+            )
+            .split(f.size());
+
+        let (title, mut lines) = match self.wizard.step() {
+            WizardStep::Network => (
+                "Choose a network",
+                self.list_lines(NETWORKS.iter().map(|n| n.to_string())),
+            ),
+            WizardStep::Source => (
+                "Create or import",
+                self.list_lines(WalletSource::ALL.iter().map(|s| s.description().to_string())),
+            ),
+            WizardStep::ShowMnemonic => ("Write down your seed phrase", self.mnemonic_lines()),
+            WizardStep::VerifyMnemonic => {
+                let labels = self
+                    .wizard
+                    .quiz_positions()
+                    .iter()
+                    .map(|p| format!("Word #{}: ", p + 1))
+                    .collect::<Vec<_>>();
+                ("Verify your seed phrase", self.field_lines(&labels, false))
+            }
+            WizardStep::EnterMnemonic => (
+                "Restore from seed phrase",
+                self.field_lines(&["Seed phrase: ".to_string()], false),
+            ),
+            WizardStep::EnterXpub => (
+                "Watch-only wallet",
+                self.field_lines(&["Extended public key: ".to_string()], false),
+            ),
+            WizardStep::Encryption => {
+                let mut lines = vec![
+                    Line::from("Protect the wallet file with a password (leave both empty to skip)."),
+                    Line::from(""),
+                ];
+                lines.extend(self.field_lines(
+                    &["Password: ".to_string(), "Confirm:  ".to_string()],
+                    true,
+                ));
+                ("Encryption", lines)
+            }
+            WizardStep::Account => {
+                let mut lines = self.field_lines(&["Account name: ".to_string()], false);
+                lines.push(Line::from(""));
+                lines.push(Line::from("Account type (Up/Down):"));
+                lines.extend(
+                    self.list_lines(ACCOUNT_TYPES.iter().map(|t| format!("{:?}", t))),
+                );
+                ("First account", lines)
+            }
+            WizardStep::Summary => {
+                let mut lines: Vec<Line> =
+                    self.wizard.summary().into_iter().map(Line::from).collect();
+                lines.push(Line::from(""));
+                lines.push(Line::from("Press Enter to create the wallet."));
+                ("Summary", lines)
+            }
+        };
+        if self.wizard.step() == WizardStep::VerifyMnemonic && self.wizard.quiz_failures() > 0 {
+            lines.push(Line::from(""));
+            lines.push(Line::from(
+                "Press Esc to view the seed phrase again if you need to check it.",
+            ));
+        }
+
+        let content = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("supernova Wallet Setup - {}", title)),
+        );
+        f.render_widget(content, chunks[0]);
+
+        let status = match &self.error {
+            Some(error) => Line::from(Span::styled(
+                error.as_str(),
+                self.palette.style(ColorRole::Negative),
+            )),
+            None => Line::from(
+                "Enter: continue | Esc: back | Tab: next field | Ctrl+C: cancel without saving",
+            ),
+        };
+        let status_bar =
+            Paragraph::new(status).block(Block::default().borders(Borders::ALL).title("Status"));
+        f.render_widget(status_bar, chunks[1]);
+    }
+
+    fn list_lines(&self, items: impl Iterator<Item = String>) -> Vec<Line<'static>> {
+        items
+            .enumerate()
+            .map(|(i, item)| {
+                if i == self.selected {
+                    Line::from(Span::styled(
+                        format!("> {}", item),
+                        self.palette.style(ColorRole::Selection),
+                    ))
+                } else {
+                    Line::from(format!("  {}", item))
+                }
+            })
+            .collect()
+    }
+
+    fn field_lines(&self, labels: &[String], masked: bool) -> Vec<Line<'static>> {
+        labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let value = self.fields.get(i).map(String::as_str).unwrap_or_default();
+                let shown = if masked {
+                    "*".repeat(value.chars().count())
+                } else {
+                    value.to_string()
+                };
+                let style = if i == self.focus {
+                    self.palette.style(ColorRole::Accent)
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![Span::raw(label.clone()), Span::styled(shown, style)])
+            })
+            .collect()
+    }
+
+    fn mnemonic_lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![
+            Line::from("Write these words down in order and keep them offline."),
+            Line::from("Anyone with these words can spend your funds."),
+            Line::from(""),
+        ];
+        let words: Vec<&str> = self
+            .wizard
+            .generated_mnemonic()
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect();
+        for (r, chunk) in words.chunks(4).enumerate() {
+            let cells: Vec<String> = chunk
+                .iter()
+                .enumerate()
+                .map(|(c, word)| format!("{:>2}. {:<10}", r * 4 + c + 1, word))
+                .collect();
+            lines.push(Line::from(cells.join("  ")));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from("Press Enter once you have written them down."));
+        lines
+    }
+}
+
+/// Apply `edit` to the text field of the send screen's current step
+fn edit_send_field(flow: &mut SendFlow, edit: impl FnOnce(&mut String)) {
+    match flow.step() {
+        SendStep::Recipient => {
+            let mut text = flow.recipient().to_string();
+            edit(&mut text);
+            flow.set_recipient(&text);
+        }
+        SendStep::Amount => {
+            let mut text = flow.amount_text().to_string();
+            edit(&mut text);
+            flow.set_amount_text(&text);
+        }
+        SendStep::Fee if flow.fee_preset() == FeePreset::Manual => {
+            let mut text = flow.manual_fee_rate().to_string();
+            edit(&mut text);
+            flow.set_manual_fee_rate(&text);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,639 @@
+//! First-run wallet setup wizard
+//!
+//! A terminal-free state machine that walks a new user through network
+//! selection, creating or importing a wallet, backing up and verifying the
+//! seed phrase, choosing an encryption password and creating the first
+//! account. The TUI renders the current step and forwards user input; tests
+//! drive the same transitions directly.
+//!
+//! Nothing touches the disk until [`SetupWizard::finish`] on the summary
+//! step, which writes the wallet file in a single atomic replace. With a
+//! password, the seed is sealed before that write, so it never reaches the
+//! disk in plaintext. Backing out
+//! or cancelling at any earlier point leaves no files behind, and every value
+//! entered so far is kept when stepping back.
+
+use bip39::{Language, Mnemonic};
+use bitcoin::bip32::Xpub;
+use bitcoin::network::Network;
+use rand::rngs::OsRng;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+use crate::backup_warning::SeedPhraseVerifier;
+use crate::hdwallet::{write_wallet_file_secure, AccountType, HDWallet, HDWalletError};
+use crate::password_strength::PasswordStrengthChecker;
+use crate::seed_vault::DEFAULT_AUTO_LOCK;
+
+/// Seed words the user must re-enter to prove the backup
+pub const QUIZ_WORDS: usize = 3;
+
+/// Networks offered on the first step, in display order
+pub const NETWORKS: [Network; 4] = [
+    Network::Bitcoin,
+    Network::Testnet,
+    Network::Signet,
+    Network::Regtest,
+];
+
+/// Account types offered on the account step, in display order
+pub const ACCOUNT_TYPES: [AccountType; 3] = [
+    AccountType::NativeSegWit,
+    AccountType::SegWit,
+    AccountType::Legacy,
+];
+
+#[derive(Debug, Error)]
+pub enum WizardError {
+    #[error("A wallet already exists at {0}")]
+    WalletExists(PathBuf),
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+    #[error("Invalid extended public key: {0}")]
+    InvalidXpub(String),
+    #[error("Seed phrase verification failed; check your backup and try again")]
+    QuizFailed,
+    #[error("Passwords do not match")]
+    PasswordMismatch,
+    #[error("Password too weak: {0}")]
+    PasswordTooWeak(String),
+    #[error("Account name must not be empty")]
+    EmptyAccountName,
+    #[error("Setup is not on the summary step")]
+    NotFinished,
+    #[error("Wallet error: {0}")]
+    Wallet(#[from] HDWalletError),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// How the wallet's keys are obtained
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletSource {
+    CreateNew,
+    ImportMnemonic,
+    /// Watch-only: balances and addresses without spending keys
+    ImportXpub,
+}
+
+impl WalletSource {
+    pub const ALL: [WalletSource; 3] = [
+        WalletSource::CreateNew,
+        WalletSource::ImportMnemonic,
+        WalletSource::ImportXpub,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            WalletSource::CreateNew => "Create a new wallet",
+            WalletSource::ImportMnemonic => "Restore from a seed phrase",
+            WalletSource::ImportXpub => "Watch-only from an extended public key",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WizardStep {
+    Network,
+    Source,
+    /// Restore: enter the seed phrase
+    EnterMnemonic,
+    /// Watch-only: enter the extended public key
+    EnterXpub,
+    /// Create: write down the generated seed phrase
+    ShowMnemonic,
+    /// Create: re-enter randomly chosen seed words
+    VerifyMnemonic,
+    /// Optional encryption password (not offered for watch-only wallets)
+    Encryption,
+    Account,
+    Summary,
+}
+
+/// Watch-only wallet record written for an imported extended public key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchOnlyWallet {
+    pub network: Network,
+    pub xpub: String,
+    pub account_name: String,
+    pub account_type: AccountType,
+}
+
+/// What the wizard wrote
+#[derive(Debug)]
+pub enum WizardOutcome {
+    Wallet(HDWallet),
+    WatchOnly(WatchOnlyWallet),
+}
+
+/// First-run setup state machine
+pub struct SetupWizard {
+    wallet_path: PathBuf,
+    step: WizardStep,
+    network: Network,
+    source: WalletSource,
+    generated_mnemonic: Option<Zeroizing<String>>,
+    imported_mnemonic: Zeroizing<String>,
+    xpub: String,
+    quiz_positions: Vec<usize>,
+    quiz_answers: Vec<String>,
+    quiz_failures: u32,
+    password: Zeroizing<String>,
+    password_confirm: Zeroizing<String>,
+    account_name: String,
+    account_type: AccountType,
+}
+
+impl SetupWizard {
+    pub fn new(wallet_path: PathBuf) -> Self {
+        Self {
+            wallet_path,
+            step: WizardStep::Network,
+            network: Network::Testnet,
+            source: WalletSource::CreateNew,
+            generated_mnemonic: None,
+            imported_mnemonic: Zeroizing::new(String::new()),
+            xpub: String::new(),
+            quiz_positions: Vec::new(),
+            quiz_answers: vec![String::new(); QUIZ_WORDS],
+            quiz_failures: 0,
+            password: Zeroizing::new(String::new()),
+            password_confirm: Zeroizing::new(String::new()),
+            account_name: "default".to_string(),
+            account_type: AccountType::NativeSegWit,
+        }
+    }
+
+    /// Whether the first-run wizard should be offered for this wallet path
+    pub fn needed(wallet_path: &Path) -> bool {
+        !wallet_path.exists()
+    }
+
+    pub fn step(&self) -> WizardStep {
+        self.step
+    }
+
+    pub fn network(&self) -> Network {
+        self.network
+    }
+
+    pub fn source(&self) -> WalletSource {
+        self.source
+    }
+
+    pub fn set_network(&mut self, network: Network) {
+        self.network = network;
+    }
+
+    pub fn set_source(&mut self, source: WalletSource) {
+        self.source = source;
+    }
+
+    pub fn imported_mnemonic(&self) -> &str {
+        &self.imported_mnemonic
+    }
+
+    pub fn set_imported_mnemonic(&mut self, mnemonic: &str) {
+        self.imported_mnemonic = Zeroizing::new(mnemonic.to_string());
+    }
+
+    pub fn xpub(&self) -> &str {
+        &self.xpub
+    }
+
+    pub fn set_xpub(&mut self, xpub: &str) {
+        self.xpub = xpub.trim().to_string();
+    }
+
+    /// The generated seed phrase, once the create path has produced one
+    pub fn generated_mnemonic(&self) -> Option<&str> {
+        self.generated_mnemonic.as_ref().map(|m| m.as_str())
+    }
+
+    /// Zero-based word positions asked in the verification quiz
+    pub fn quiz_positions(&self) -> &[usize] {
+        &self.quiz_positions
+    }
+
+    pub fn quiz_answers(&self) -> &[String] {
+        &self.quiz_answers
+    }
+
+    pub fn set_quiz_answer(&mut self, index: usize, word: &str) {
+        if let Some(answer) = self.quiz_answers.get_mut(index) {
+            *answer = word.trim().to_string();
+        }
+    }
+
+    pub fn quiz_failures(&self) -> u32 {
+        self.quiz_failures
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    pub fn password_confirm(&self) -> &str {
+        &self.password_confirm
+    }
+
+    pub fn set_password(&mut self, password: &str, confirm: &str) {
+        self.password = Zeroizing::new(password.to_string());
+        self.password_confirm = Zeroizing::new(confirm.to_string());
+    }
+
+    pub fn account_name(&self) -> &str {
+        &self.account_name
+    }
+
+    pub fn account_type(&self) -> AccountType {
+        self.account_type
+    }
+
+    pub fn set_account(&mut self, name: &str, account_type: AccountType) {
+        self.account_name = name.trim().to_string();
+        self.account_type = account_type;
+    }
+
+    /// Whether the finished wallet will be encrypted
+    pub fn encrypts(&self) -> bool {
+        self.source != WalletSource::ImportXpub && !self.password.is_empty()
+    }
+
+    /// Validate the current step and advance. On error the wizard stays on
+    /// the current step; a failed quiz draws new word positions.
+    pub fn next(&mut self) -> Result<WizardStep, WizardError> {
+        self.step = match self.step {
+            WizardStep::Network => WizardStep::Source,
+            WizardStep::Source => match self.source {
+                WalletSource::CreateNew => {
+                    if self.generated_mnemonic.is_none() {
+                        self.generated_mnemonic = Some(generate_mnemonic()?);
+                    }
+                    WizardStep::ShowMnemonic
+                }
+                WalletSource::ImportMnemonic => WizardStep::EnterMnemonic,
+                WalletSource::ImportXpub => WizardStep::EnterXpub,
+            },
+            WizardStep::ShowMnemonic => {
+                self.new_quiz();
+                WizardStep::VerifyMnemonic
+            }
+            WizardStep::VerifyMnemonic => {
+                self.check_quiz()?;
+                WizardStep::Encryption
+            }
+            WizardStep::EnterMnemonic => {
+                let normalized = normalize_mnemonic(&self.imported_mnemonic);
+                Mnemonic::parse_in_normalized(Language::English, &normalized)
+                    .map_err(|e| WizardError::InvalidMnemonic(e.to_string()))?;
+                self.imported_mnemonic = Zeroizing::new(normalized);
+                WizardStep::Encryption
+            }
+            WizardStep::EnterXpub => {
+                let xpub = Xpub::from_str(&self.xpub)
+                    .map_err(|e| WizardError::InvalidXpub(e.to_string()))?;
+                if !network_matches(xpub.network, self.network) {
+                    return Err(WizardError::InvalidXpub(format!(
+                        "key is for {}, wallet is for {}",
+                        xpub.network, self.network
+                    )));
+                }
+                WizardStep::Account
+            }
+            WizardStep::Encryption => {
+                self.check_password()?;
+                WizardStep::Account
+            }
+            WizardStep::Account => {
+                if self.account_name.is_empty() {
+                    return Err(WizardError::EmptyAccountName);
+                }
+                WizardStep::Summary
+            }
+            WizardStep::Summary => return Err(WizardError::NotFinished),
+        };
+        Ok(self.step)
+    }
+
+    /// Step back, keeping everything entered. Returns `None` on the first
+    /// step, where backing out means leaving the wizard.
+    pub fn back(&mut self) -> Option<WizardStep> {
+        self.step = match self.step {
+            WizardStep::Network => return None,
+            WizardStep::Source => WizardStep::Network,
+            WizardStep::EnterMnemonic | WizardStep::EnterXpub | WizardStep::ShowMnemonic => {
+                WizardStep::Source
+            }
+            WizardStep::VerifyMnemonic => WizardStep::ShowMnemonic,
+            WizardStep::Encryption => match self.source {
+                WalletSource::CreateNew => WizardStep::ShowMnemonic,
+                _ => WizardStep::EnterMnemonic,
+            },
+            WizardStep::Account => match self.source {
+                WalletSource::ImportXpub => WizardStep::EnterXpub,
+                _ => WizardStep::Encryption,
+            },
+            WizardStep::Summary => WizardStep::Account,
+        };
+        Some(self.step)
+    }
+
+    /// Lines describing what [`finish`](Self::finish) will write
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = vec![
+            format!("Network:    {}", self.network),
+            format!("Wallet:     {}", self.source.description()),
+            format!("Account:    {} ({:?})", self.account_name, self.account_type),
+        ];
+        match self.source {
+            WalletSource::ImportXpub => lines.push("Keys:       none (watch-only)".to_string()),
+            _ => lines.push(format!(
+                "Encryption: {}",
+                if self.encrypts() { "password protected" } else { "none (plaintext)" }
+            )),
+        }
+        lines.push(format!("File:       {}", self.wallet_path.display()));
+        lines
+    }
+
+    /// Write the wallet. Only valid on the summary step; the file is
+    /// written once, atomically, and never over an existing wallet.
+    pub fn finish(&self) -> Result<WizardOutcome, WizardError> {
+        if self.step != WizardStep::Summary {
+            return Err(WizardError::NotFinished);
+        }
+        if self.wallet_path.exists() {
+            return Err(WizardError::WalletExists(self.wallet_path.clone()));
+        }
+        if let Some(parent) = self.wallet_path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+
+        let mnemonic = match self.source {
+            WalletSource::ImportXpub => {
+                let watch_only = WatchOnlyWallet {
+                    network: self.network,
+                    xpub: self.xpub.clone(),
+                    account_name: self.account_name.clone(),
+                    account_type: self.account_type,
+                };
+                let json = serde_json::to_string_pretty(&watch_only)?;
+                write_wallet_file_secure(&self.wallet_path, json.as_bytes())?;
+                return Ok(WizardOutcome::WatchOnly(watch_only));
+            }
+            WalletSource::CreateNew => self
+                .generated_mnemonic
+                .clone()
+                .ok_or_else(|| WizardError::InvalidMnemonic("no seed phrase generated".to_string()))?,
+            WalletSource::ImportMnemonic => self.imported_mnemonic.clone(),
+        };
+
+        let mut wallet =
+            HDWallet::from_mnemonic(&mnemonic, None, self.network, self.wallet_path.clone())?;
+        wallet.add_account(self.account_name.clone(), self.account_type);
+        // The quiz proved the backup; an imported phrase is its own backup.
+        wallet.acknowledge_backup();
+        wallet.verify_backup(true)?;
+
+        if self.encrypts() {
+            // Accounts and addresses stay readable; the wallet comes back
+            // unlocked so the session can start without a second prompt
+            wallet.encrypt(&self.password)?;
+            wallet.unlock(&self.password, Some(DEFAULT_AUTO_LOCK))?;
+        } else {
+            #[allow(deprecated)]
+            wallet.save()?;
+        }
+        Ok(WizardOutcome::Wallet(wallet))
+    }
+
+    fn new_quiz(&mut self) {
+        let words = self.generated_mnemonic().map(|m| m.split_whitespace().count()).unwrap_or(0);
+        let mut rng = rand::thread_rng();
+        let mut positions = Vec::with_capacity(QUIZ_WORDS);
+        while positions.len() < QUIZ_WORDS.min(words) {
+            let p = rng.gen_range(0..words);
+            if !positions.contains(&p) {
+                positions.push(p);
+            }
+        }
+        positions.sort_unstable();
+        self.quiz_positions = positions;
+        self.quiz_answers = vec![String::new(); QUIZ_WORDS];
+    }
+
+    fn check_quiz(&mut self) -> Result<(), WizardError> {
+        let mnemonic = self.generated_mnemonic().unwrap_or_default();
+        let answers = &self.quiz_answers[..self.quiz_positions.len()];
+        if SeedPhraseVerifier::verify_programmatic(mnemonic, answers, &self.quiz_positions).is_ok() {
+            return Ok(());
+        }
+        self.quiz_failures += 1;
+        self.new_quiz();
+        Err(WizardError::QuizFailed)
+    }
+
+    fn check_password(&self) -> Result<(), WizardError> {
+        if self.password.is_empty() && self.password_confirm.is_empty() {
+            return Ok(());
+        }
+        if *self.password != *self.password_confirm {
+            return Err(WizardError::PasswordMismatch);
+        }
+        PasswordStrengthChecker::new()
+            .validate(&self.password)
+            .map_err(|suggestions| WizardError::PasswordTooWeak(suggestions.join("; ")))
+    }
+}
+
+fn generate_mnemonic() -> Result<Zeroizing<String>, WizardError> {
+    let mut entropy = Zeroizing::new([0u8; 16]);
+    OsRng.fill_bytes(&mut entropy[..]);
+    let mnemonic = Mnemonic::from_entropy(&entropy[..])
+        .map_err(|e| WizardError::InvalidMnemonic(e.to_string()))?;
+    Ok(Zeroizing::new(mnemonic.to_string()))
+}
+
+fn normalize_mnemonic(mnemonic: &str) -> String {
+    mnemonic
+        .split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Extended keys only distinguish mainnet from test networks
+fn network_matches(key: Network, wallet: Network) -> bool {
+    (key == Network::Bitcoin) == (wallet == Network::Bitcoin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRONG_PASSWORD: &str = "Quantum-Resistant#Wallet-Passphrase-2024!";
+    const TEST_MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn answer_quiz(wizard: &mut SetupWizard, correct: bool) {
+        let words: Vec<String> = wizard
+            .generated_mnemonic()
+            .unwrap()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let positions = wizard.quiz_positions().to_vec();
+        for (i, pos) in positions.into_iter().enumerate() {
+            let word = if correct { words[pos].clone() } else { "wrong".to_string() };
+            wizard.set_quiz_answer(i, &word);
+        }
+    }
+
+    fn files_in(dir: &Path) -> usize {
+        std::fs::read_dir(dir).unwrap().count()
+    }
+
+    #[test]
+    fn create_new_happy_path_writes_encrypted_wallet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.json");
+        let mut wizard = SetupWizard::new(path.clone());
+
+        wizard.set_network(Network::Testnet);
+        assert_eq!(wizard.next().unwrap(), WizardStep::Source);
+        wizard.set_source(WalletSource::CreateNew);
+        assert_eq!(wizard.next().unwrap(), WizardStep::ShowMnemonic);
+        let mnemonic = wizard.generated_mnemonic().unwrap().to_string();
+        assert_eq!(mnemonic.split_whitespace().count(), 12);
+        assert_eq!(wizard.next().unwrap(), WizardStep::VerifyMnemonic);
+        assert_eq!(wizard.quiz_positions().len(), QUIZ_WORDS);
+        answer_quiz(&mut wizard, true);
+        assert_eq!(wizard.next().unwrap(), WizardStep::Encryption);
+        wizard.set_password(STRONG_PASSWORD, STRONG_PASSWORD);
+        assert_eq!(wizard.next().unwrap(), WizardStep::Account);
+        wizard.set_account("savings", AccountType::SegWit);
+        assert_eq!(wizard.next().unwrap(), WizardStep::Summary);
+        assert!(wizard.summary().iter().any(|l| l.contains("password protected")));
+
+        // Nothing is written before the final confirmation
+        assert_eq!(files_in(dir.path()), 0);
+
+        let WizardOutcome::Wallet(wallet) = wizard.finish().unwrap() else {
+            panic!("expected a spending wallet");
+        };
+        assert!(wallet.is_encrypted());
+        assert_eq!(wallet.get_mnemonic().unwrap(), mnemonic);
+        assert_eq!(wallet.list_accounts(true).len(), 1);
+        assert_eq!(files_in(dir.path()), 1);
+        assert!(!std::fs::read_to_string(&path).unwrap().contains(&mnemonic));
+
+        #[allow(deprecated)]
+        let mut reopened = HDWallet::load(path).unwrap();
+        assert!(reopened.is_locked());
+        assert_eq!(reopened.list_accounts(true).len(), 1);
+        reopened.unlock(STRONG_PASSWORD, None).unwrap();
+        assert_eq!(reopened.get_mnemonic().unwrap(), mnemonic);
+    }
+
+    #[test]
+    fn failed_quiz_loops_with_new_words() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wizard = SetupWizard::new(dir.path().join("wallet.json"));
+        wizard.next().unwrap();
+        wizard.next().unwrap();
+        wizard.next().unwrap();
+
+        for attempt in 1..=3 {
+            answer_quiz(&mut wizard, false);
+            assert!(matches!(wizard.next(), Err(WizardError::QuizFailed)));
+            assert_eq!(wizard.step(), WizardStep::VerifyMnemonic);
+            assert_eq!(wizard.quiz_failures(), attempt);
+            assert!(wizard.quiz_answers().iter().all(String::is_empty));
+        }
+
+        // Going back to re-read the phrase keeps the same phrase
+        let mnemonic = wizard.generated_mnemonic().unwrap().to_string();
+        assert_eq!(wizard.back(), Some(WizardStep::ShowMnemonic));
+        wizard.next().unwrap();
+        assert_eq!(wizard.generated_mnemonic().unwrap(), mnemonic);
+        answer_quiz(&mut wizard, true);
+        assert_eq!(wizard.next().unwrap(), WizardStep::Encryption);
+    }
+
+    #[test]
+    fn back_navigation_preserves_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wizard = SetupWizard::new(dir.path().join("wallet.json"));
+        wizard.set_network(Network::Signet);
+        wizard.next().unwrap();
+        wizard.set_source(WalletSource::ImportMnemonic);
+        wizard.next().unwrap();
+        wizard.set_imported_mnemonic(&TEST_MNEMONIC.to_uppercase());
+        assert_eq!(wizard.next().unwrap(), WizardStep::Encryption);
+        wizard.set_password("mismatch-one", "mismatch-two");
+        assert!(matches!(wizard.next(), Err(WizardError::PasswordMismatch)));
+        wizard.set_password("", "");
+        wizard.next().unwrap();
+        wizard.set_account("spending", AccountType::Legacy);
+        wizard.next().unwrap();
+
+        assert_eq!(wizard.back(), Some(WizardStep::Account));
+        assert_eq!(wizard.back(), Some(WizardStep::Encryption));
+        assert_eq!(wizard.back(), Some(WizardStep::EnterMnemonic));
+        assert_eq!(wizard.back(), Some(WizardStep::Source));
+        assert_eq!(wizard.back(), Some(WizardStep::Network));
+        assert_eq!(wizard.back(), None);
+
+        assert_eq!(wizard.network(), Network::Signet);
+        assert_eq!(wizard.source(), WalletSource::ImportMnemonic);
+        assert_eq!(wizard.imported_mnemonic(), TEST_MNEMONIC);
+        assert_eq!(wizard.account_name(), "spending");
+        assert!(matches!(wizard.account_type(), AccountType::Legacy));
+
+        for _ in 0..5 {
+            wizard.next().unwrap();
+        }
+        assert_eq!(wizard.step(), WizardStep::Summary);
+        let WizardOutcome::Wallet(wallet) = wizard.finish().unwrap() else {
+            panic!("expected a spending wallet");
+        };
+        assert_eq!(wallet.get_mnemonic().unwrap(), TEST_MNEMONIC);
+    }
+
+    #[test]
+    fn cancelling_leaves_no_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("wallet.json");
+        let mut wizard = SetupWizard::new(path.clone());
+        wizard.next().unwrap();
+        wizard.next().unwrap();
+        wizard.next().unwrap();
+        answer_quiz(&mut wizard, true);
+        wizard.next().unwrap();
+        wizard.next().unwrap();
+        wizard.next().unwrap();
+        assert_eq!(wizard.step(), WizardStep::Summary);
+        drop(wizard);
+        assert_eq!(files_in(dir.path()), 0);
+        assert!(SetupWizard::needed(&path));
+
+        // Watch-only import rejects keys for the wrong network
+        let mut wizard = SetupWizard::new(path);
+        wizard.set_network(Network::Bitcoin);
+        wizard.next().unwrap();
+        wizard.set_source(WalletSource::ImportXpub);
+        wizard.next().unwrap();
+        wizard.set_xpub("tpubD6NzVbkrYhZ4XgiXtGrdW5XDAPFCL9h7we1vwNCpn8tGbBcgfVYjXyhWo4E1xkh56hjod1RhGjxbaTLV3X4FyWuejifB9jusQ46QzG87VKp");
+        assert!(matches!(wizard.next(), Err(WizardError::InvalidXpub(_))));
+        assert_eq!(files_in(dir.path()), 0);
+    }
+}
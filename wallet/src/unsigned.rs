@@ -0,0 +1,145 @@
+//! Unsigned transactions for offline signing.
+//!
+//! A wallet without keys (see [`WalletManager::new_watch_only`]) funds a
+//! payment with [`WalletManager::build_unsigned`] and hands the resulting
+//! [`UnsignedTransaction`] to a wallet holding the seed, which checks that
+//! every input is its own and signs with
+//! [`WalletManager::sign_unsigned`]. In between, the blob travels as base64
+//! so it fits a QR code or a file.
+//!
+//! Spends of a [`MultisigAccount`] travel the same way, collecting one
+//! [`PartialSignature`] per cosigner until the threshold is met.
+//!
+//! [`WalletManager::new_watch_only`]: crate::WalletManager::new_watch_only
+//! [`WalletManager::build_unsigned`]: crate::WalletManager::build_unsigned
+//! [`WalletManager::sign_unsigned`]: crate::WalletManager::sign_unsigned
+//! [`MultisigAccount`]: crate::multisig::MultisigAccount
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::{Deserialize, Serialize};
+use supernova_core::types::transaction::{
+    OutPoint, Transaction, TransactionInput, TransactionOutput,
+};
+use thiserror::Error;
+
+/// Encoding version of [`UnsignedTransaction`]
+pub const UNSIGNED_FORMAT_VERSION: u32 = 3;
+
+#[derive(Error, Debug)]
+pub enum UnsignedError {
+    #[error("Failed to encode: {0}")]
+    Encoding(String),
+    #[error("Failed to decode: {0}")]
+    Decoding(String),
+    #[error("Unsupported format version {0}")]
+    UnsupportedFormat(u32),
+    #[error("Outputs of {outputs} exceed inputs of {inputs}")]
+    OutputsExceedInputs { inputs: u64, outputs: u64 },
+}
+
+/// A funded transaction skeleton with what a signer needs to sign it: the
+/// amount and script of each spent output and the BIP44 path of its key,
+/// and the path of any change so a device can tell it from the payment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    /// Encoding version, [`UNSIGNED_FORMAT_VERSION`]
+    pub format: u32,
+    /// Transaction version
+    pub version: u32,
+    pub inputs: Vec<UnsignedInput>,
+    /// Outputs in transaction order, including change
+    pub outputs: Vec<TransactionOutput>,
+    /// BIP44 path of each output paying back to the wallet (change); `None`
+    /// for the others. Aligned with `outputs`.
+    pub output_paths: Vec<Option<String>>,
+    pub lock_time: u32,
+    /// Amount paid to the recipient, recorded in the signer's history
+    pub amount: u64,
+}
+
+/// An output to spend and the key that spends it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsignedInput {
+    pub outpoint: OutPoint,
+    pub sequence: u32,
+    /// Value of the spent output
+    pub amount: u64,
+    /// Locking script of the spent output
+    pub script_pubkey: Vec<u8>,
+    /// BIP44 path of the signing key, e.g. `m/44'/1'/0'/0/3`; empty for
+    /// multisig inputs
+    pub derivation_path: String,
+    /// Witness script of a multisig input
+    pub witness_script: Option<Vec<u8>>,
+    /// Cosigner signatures collected so far for a multisig input
+    pub partial_signatures: Vec<PartialSignature>,
+}
+
+/// One cosigner's signature of a multisig input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialSignature {
+    pub public_key: Vec<u8>,
+    /// Signature of [`UnsignedTransaction::signature_hash`]
+    pub signature: Vec<u8>,
+}
+
+impl UnsignedTransaction {
+    /// Total value of the spent outputs
+    pub fn input_total(&self) -> u64 {
+        self.inputs.iter().map(|input| input.amount).sum()
+    }
+
+    /// Total value of the outputs
+    pub fn output_total(&self) -> u64 {
+        self.outputs.iter().map(TransactionOutput::amount).sum()
+    }
+
+    /// Hash every input signs: [`Transaction::signature_hash`] of the
+    /// transaction this one becomes
+    pub fn signature_hash(&self) -> [u8; 32] {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| {
+                TransactionInput::new(
+                    input.outpoint.txid,
+                    input.outpoint.vout,
+                    Vec::new(),
+                    input.sequence,
+                )
+            })
+            .collect();
+        Transaction::new(self.version, inputs, self.outputs.clone(), self.lock_time)
+            .signature_hash()
+    }
+
+    /// Fee paid: inputs less outputs
+    pub fn fee(&self) -> Result<u64, UnsignedError> {
+        let (inputs, outputs) = (self.input_total(), self.output_total());
+        inputs
+            .checked_sub(outputs)
+            .ok_or(UnsignedError::OutputsExceedInputs { inputs, outputs })
+    }
+
+    /// Encode for transport
+    pub fn to_base64(&self) -> Result<String, UnsignedError> {
+        let bytes = bincode::serialize(self).map_err(|e| UnsignedError::Encoding(e.to_string()))?;
+        Ok(BASE64.encode(bytes))
+    }
+
+    /// Decode a blob from [`to_base64`](Self::to_base64). Surrounding
+    /// whitespace, as left by files and QR scanners, is ignored.
+    pub fn from_base64(encoded: &str) -> Result<Self, UnsignedError> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| UnsignedError::Decoding(e.to_string()))?;
+        // The version leads the encoding; check it before the layout it
+        // implies
+        let format: u32 =
+            bincode::deserialize(&bytes).map_err(|e| UnsignedError::Decoding(e.to_string()))?;
+        if format != UNSIGNED_FORMAT_VERSION {
+            return Err(UnsignedError::UnsupportedFormat(format));
+        }
+        bincode::deserialize(&bytes).map_err(|e| UnsignedError::Decoding(e.to_string()))
+    }
+}
@@ -45,8 +45,12 @@ fn test_encrypted_save_and_load() {
         .expect("Failed to load encrypted wallet");
     
     // Verify wallet data matches
-    assert_eq!(wallet.get_mnemonic(), loaded_wallet.get_mnemonic(), "Mnemonics should match");
-    assert_eq!(wallet.list_accounts().len(), loaded_wallet.list_accounts().len(), "Accounts should match");
+    assert_eq!(
+        wallet.get_mnemonic().unwrap(),
+        loaded_wallet.get_mnemonic().unwrap(),
+        "Mnemonics should match"
+    );
+    assert_eq!(wallet.list_accounts(true).len(), loaded_wallet.list_accounts(true).len(), "Accounts should match");
     
     println!("✓ Encrypted save/load round-trip successful");
 }
@@ -92,7 +96,7 @@ fn test_backup_not_plaintext() {
     let wallet = HDWallet::new(Network::Testnet, wallet_path.clone())
         .expect("Failed to create wallet");
     
-    let mnemonic = wallet.get_mnemonic().to_string();
+    let mnemonic = wallet.get_mnemonic().unwrap().to_string();
     
     wallet.save_encrypted("Correct-Horse-Battery-Staple-789!@#Plain$%^")
         .expect("Failed to save");